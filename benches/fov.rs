@@ -0,0 +1,51 @@
+use ascii_dungeon_explorer::map::{GeneratorKind, Map, TileType};
+use ascii_dungeon_explorer::resources::RandomNumberGenerator;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const RANGES: [i32; 3] = [8, 16, 32];
+
+/// An open floor strewn with pillars, one tile in `one_in` on average: the worst case for
+/// shadowcasting, which splits its scan at every edge of every shadow
+fn pillar_field(width: i32, height: i32, one_in: i32) -> Map {
+    let mut rng = RandomNumberGenerator::new(7);
+    let mut map = Map::new(width, height, 1);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let tile = if rng.roll_dice(1, one_in) == 1 { TileType::Wall } else { TileType::Floor };
+            map.set_tile(x, y, tile);
+        }
+    }
+    map
+}
+
+/// The floor tile nearest the middle of the map, to look out from
+fn centre(map: &Map) -> (i32, i32) {
+    let middle = (map.width / 2, map.height / 2);
+    (0..map.width * map.height)
+        .map(|idx| (idx % map.width, idx / map.width))
+        .filter(|&(x, y)| map.get_tile(x, y) == Some(TileType::Floor))
+        .min_by_key(|&(x, y)| (x - middle.0).abs() + (y - middle.1).abs())
+        .unwrap_or(middle)
+}
+
+fn shadowcasting(c: &mut Criterion) {
+    let maps = [
+        ("open", pillar_field(160, 100, 1000)),
+        ("pillars", pillar_field(160, 100, 6)),
+        ("dense pillars", pillar_field(160, 100, 3)),
+        ("caves", GeneratorKind::Caves.generate(160, 100, 1, 42)),
+    ];
+    let mut group = c.benchmark_group("fov");
+    for (name, map) in maps.iter() {
+        let origin = centre(map);
+        for range in RANGES {
+            group.bench_with_input(BenchmarkId::new(*name, range), &range, |b, &range| {
+                b.iter(|| black_box(map.field_of_view(origin, range)));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, shadowcasting);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+use ascii_dungeon_explorer::map::GeneratorKind;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Level sizes from the usual one up to well past anything the game makes today
+const SIZES: [(i32, i32); 3] = [(80, 50), (160, 100), (320, 200)];
+const SEED: u64 = 42;
+
+fn generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mapgen");
+    for kind in GeneratorKind::ALL {
+        for (width, height) in SIZES {
+            let id = BenchmarkId::new(format!("{:?}", kind), format!("{}x{}", width, height));
+            group.bench_with_input(id, &(width, height), |b, &(width, height)| {
+                b.iter(|| black_box(kind.generate(width, height, 1, SEED)));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generation);
+criterion_main!(benches);
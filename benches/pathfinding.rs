@@ -0,0 +1,77 @@
+use ascii_dungeon_explorer::map::{DijkstraMap, Map, TileType};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const WIDTH: i32 = 160;
+const HEIGHT: i32 = 100;
+
+fn open_floor() -> Map {
+    let mut map = Map::new(WIDTH, HEIGHT, 1);
+    for y in 1..HEIGHT - 1 {
+        for x in 1..WIDTH - 1 {
+            map.set_tile(x, y, TileType::Floor);
+        }
+    }
+    map
+}
+
+/// Walls across the map every other column, each leaving a gap at alternate ends, so the
+/// only way through runs the whole height of the map over and over
+fn serpentine() -> Map {
+    let mut map = open_floor();
+    for x in (2..WIDTH - 2).step_by(2) {
+        let gap = if x % 4 == 0 { HEIGHT - 2 } else { 1 };
+        for y in 1..HEIGHT - 1 {
+            if y != gap {
+                map.set_tile(x, y, TileType::Wall);
+            }
+        }
+    }
+    map
+}
+
+/// An open map with the goal walled in, so A* searches every tile before giving up
+fn sealed_goal() -> (Map, (i32, i32)) {
+    let mut map = open_floor();
+    let goal = (WIDTH - 10, HEIGHT - 10);
+    for dy in -2..=2 {
+        for dx in -2..=2 {
+            if dx.abs() == 2 || dy.abs() == 2 {
+                map.set_tile(goal.0 + dx, goal.1 + dy, TileType::Wall);
+            }
+        }
+    }
+    (map, goal)
+}
+
+fn astar(c: &mut Criterion) {
+    let mut group = c.benchmark_group("astar");
+    let open = open_floor();
+    group.bench_function("open corner to corner", |b| {
+        b.iter(|| black_box(open.find_path((1, 1), (WIDTH - 2, HEIGHT - 2))));
+    });
+    let maze = serpentine();
+    group.bench_function("serpentine", |b| {
+        b.iter(|| black_box(maze.find_path((1, 1), (WIDTH - 2, HEIGHT - 2))));
+    });
+    let (sealed, goal) = sealed_goal();
+    group.bench_function("unreachable goal", |b| {
+        b.iter(|| black_box(sealed.find_path((1, 1), goal)));
+    });
+    group.finish();
+}
+
+fn dijkstra(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dijkstra");
+    let open = open_floor();
+    group.bench_function("open single source", |b| {
+        b.iter(|| black_box(DijkstraMap::new(&open, &[(WIDTH / 2, HEIGHT / 2)])));
+    });
+    let maze = serpentine();
+    group.bench_function("serpentine single source", |b| {
+        b.iter(|| black_box(DijkstraMap::new(&maze, &[(1, 1)])));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, astar, dijkstra);
+criterion_main!(benches);
@@ -0,0 +1,214 @@
+//! Play without reading the screen: the screen-reader mode keeps a plain-text transcript of the
+//! game log, menu selections and descriptions asked for on demand, one line per event, in a file a
+//! screen reader or `tail -f` can follow while the terminal is busy with the map.
+
+use specs::{World, WorldExt, Entity, Join};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use crate::components::{Position, Name, Monster, CombatStats};
+use crate::game_state::HealthEstimate;
+use crate::localization::{self, tr, Message};
+use crate::map::Map;
+use crate::resources::GameLog;
+
+/// Where the transcript goes unless the options say otherwise
+pub const TRANSCRIPT_PATH: &str = "transcript.txt";
+
+/// Writes the transcript while screen-reader mode is on
+#[derive(Default)]
+pub struct Narrator {
+    transcript: Option<File>,
+    /// Log entries already written, counted with `GameLog::written`
+    log_seen: u64,
+    last_announcement: Option<String>,
+}
+
+impl Narrator {
+    pub fn is_enabled(&self) -> bool {
+        self.transcript.is_some()
+    }
+
+    /// Start appending to the transcript at `path`, or stop
+    pub fn set_enabled(&mut self, enabled: bool, path: &Path) -> io::Result<()> {
+        if !enabled {
+            self.transcript = None;
+            return Ok(());
+        }
+        if self.transcript.is_none() {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            self.transcript = Some(file);
+            self.last_announcement = None;
+            self.say(&tr("access.started"));
+        }
+        Ok(())
+    }
+
+    /// Write one line, as soon as it is said
+    pub fn say(&mut self, line: &str) {
+        if let Some(transcript) = self.transcript.as_mut() {
+            let _ = writeln!(transcript, "{}", line).and_then(|_| transcript.flush());
+        }
+    }
+
+    /// Repeat whatever the log gained since the last call
+    pub fn follow_log(&mut self, log: &GameLog) {
+        // A loaded or cleared log starts counting again
+        if log.written < self.log_seen {
+            self.log_seen = log.written;
+        }
+        let new = ((log.written - self.log_seen) as usize).min(log.entries.len());
+        self.log_seen = log.written;
+        if !self.is_enabled() {
+            return;
+        }
+        let lines: Vec<String> = log.entries.iter().skip(log.entries.len() - new).cloned().collect();
+        for line in lines {
+            self.say(&line);
+        }
+    }
+
+    /// Say what is on screen or selected, once each time it changes
+    pub fn announce(&mut self, announcement: Option<String>) {
+        if announcement.is_some() && announcement != self.last_announcement {
+            if let Some(text) = &announcement {
+                self.say(text);
+            }
+        }
+        self.last_announcement = announcement;
+    }
+}
+
+/// "3 north, 2 east" from the player to something `dx`, `dy` away
+pub fn direction_text(dx: i32, dy: i32) -> String {
+    let mut parts = Vec::new();
+    if dy != 0 {
+        let direction = tr(if dy < 0 { "direction.north" } else { "direction.south" });
+        parts.push(Message::new("access.steps").arg("count", dy.abs()).arg("direction", direction).text());
+    }
+    if dx != 0 {
+        let direction = tr(if dx < 0 { "direction.west" } else { "direction.east" });
+        parts.push(Message::new("access.steps").arg("count", dx.abs()).arg("direction", direction).text());
+    }
+    if parts.is_empty() {
+        return tr("access.here");
+    }
+    parts.join(", ")
+}
+
+fn compass(dx: i32, dy: i32) -> &'static str {
+    match (dx.signum(), dy.signum()) {
+        (0, -1) => "direction.north",
+        (1, -1) => "direction.northeast",
+        (1, 0) => "direction.east",
+        (1, 1) => "direction.southeast",
+        (0, 1) => "direction.south",
+        (-1, 1) => "direction.southwest",
+        (-1, 0) => "direction.west",
+        _ => "direction.northwest",
+    }
+}
+
+/// The tile the player stands on, what lies there with them and which ways are open
+pub fn describe_surroundings(world: &World, player: Entity) -> Vec<String> {
+    let positions = world.read_storage::<Position>();
+    let (x, y) = match positions.get(player) {
+        Some(pos) => (pos.x, pos.y),
+        None => return Vec::new(),
+    };
+    let map = world.fetch::<Map>();
+    let mut lines = Vec::new();
+
+    if let Some(tile) = map.get_tile(x, y) {
+        lines.push(Message::new("access.standing_on")
+            .arg("tile", tile.name())
+            .arg("description", tile.description())
+            .text());
+    }
+
+    let names = world.read_storage::<Name>();
+    let here: Vec<String> = (&world.entities(), &positions, &names).join()
+        .filter(|(entity, pos, _)| *entity != player && pos.x == x && pos.y == y)
+        .map(|(_, _, name)| localization::name(&name.name))
+        .collect();
+    if !here.is_empty() {
+        lines.push(Message::new("access.things_here").arg("things", here.join(", ")).text());
+    }
+
+    let exits: Vec<String> = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)].iter()
+        .filter(|(dx, dy)| map.get_tile(x + dx, y + dy).map_or(false, |tile| !tile.blocks_movement()))
+        .map(|(dx, dy)| tr(compass(*dx, *dy)))
+        .collect();
+    lines.push(if exits.is_empty() {
+        tr("access.no_exits")
+    } else {
+        Message::new("access.exits").arg("directions", exits.join(", ")).text()
+    });
+    lines
+}
+
+/// Each living enemy the player can see, nearest first, with where it is and how hurt
+pub fn describe_visible_enemies(world: &World, player: Entity) -> Vec<String> {
+    let positions = world.read_storage::<Position>();
+    let (px, py) = match positions.get(player) {
+        Some(pos) => (pos.x, pos.y),
+        None => return Vec::new(),
+    };
+    let map = world.fetch::<Map>();
+    let names = world.read_storage::<Name>();
+    let mut enemies: Vec<(i32, String)> = (&world.read_storage::<Monster>(), &positions, &world.read_storage::<CombatStats>(), names.maybe())
+        .join()
+        .filter(|(_, pos, stats, _)| stats.hp > 0 && map.is_visible(pos.x, pos.y))
+        .map(|(_, pos, stats, name)| {
+            let (dx, dy) = (pos.x - px, pos.y - py);
+            let text = Message::new("access.enemy")
+                .arg("name", name.map_or(tr("access.something"), |n| localization::name(&n.name)))
+                .arg("where", direction_text(dx, dy))
+                .arg("health", HealthEstimate::from_stats(stats).label())
+                .text();
+            (dx.abs().max(dy.abs()), text)
+        })
+        .collect();
+    if enemies.is_empty() {
+        return vec![tr("access.no_enemies")];
+    }
+    enemies.sort_by_key(|(distance, _)| *distance);
+    enemies.into_iter().map(|(_, text)| text).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_transcript_repeats_new_log_lines_and_each_announcement_once() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(TRANSCRIPT_PATH);
+        let mut log = GameLog::new(3);
+        log.add_entry("Before the transcript started.".to_string());
+
+        let mut narrator = Narrator::default();
+        narrator.follow_log(&log);
+        narrator.set_enabled(true, &path).unwrap();
+        for line in ["One.", "Two.", "Three.", "Four."] {
+            log.add_entry(line.to_string());
+        }
+        narrator.follow_log(&log);
+        narrator.announce(Some("Options. Colors: Full".to_string()));
+        narrator.announce(Some("Options. Colors: Full".to_string()));
+        narrator.announce(None);
+        narrator.announce(Some("Options. Colors: Full".to_string()));
+
+        let transcript = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = transcript.lines().skip(1).collect();
+        assert_eq!(lines, vec!["Two.", "Three.", "Four.", "Options. Colors: Full", "Options. Colors: Full"]);
+    }
+
+    #[test]
+    fn test_directions_read_north_south_then_east_west() {
+        assert_eq!(direction_text(2, -3), "3 north, 2 east");
+        assert_eq!(direction_text(-1, 0), "1 west");
+        assert_eq!(direction_text(0, 0), "here");
+    }
+}
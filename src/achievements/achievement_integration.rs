@@ -338,6 +338,16 @@ impl AchievementIntegration {
     pub fn on_perfect_level(&mut self) {
         self.process_game_event(&GameEvent::PerfectLevel);
     }
+
+    /// Player took the artifact from the final boss's lair
+    pub fn on_artifact_claimed(&mut self) {
+        self.process_game_event(&GameEvent::ArtifactClaimed);
+    }
+
+    /// Player escaped the dungeon with the artifact
+    pub fn on_victory(&mut self, turns: u32) {
+        self.process_game_event(&GameEvent::Victory { turns });
+    }
 }
 
 /// Achievement integration builder for easy setup
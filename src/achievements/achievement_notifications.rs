@@ -3,10 +3,10 @@ use std::time::{Duration, Instant};
 use crossterm::style::Color;
 use crate::{
     ui::{
-        ui_components::{UIComponent, UIRenderCommand, UIPanel, UIText, TextAlignment},
+        ui_components::{UIRenderCommand, UIPanel},
     },
     achievements::achievement_system::{
-        AchievementNotification, AchievementRarity, AchievementType,
+        AchievementNotification, AchievementRarity,
     },
 };
 
@@ -279,8 +279,9 @@ impl AchievementNotificationSystem {
     }
 }
 
-impl UIComponent for AchievementNotificationSystem {
-    fn render(&self) -> Vec<UIRenderCommand> {
+impl AchievementNotificationSystem {
+    /// Draw every notification on screen
+    pub fn render(&self) -> Vec<UIRenderCommand> {
         let mut commands = Vec::new();
 
         for (index, active_notification) in self.active_notifications.iter().enumerate() {
@@ -290,9 +291,7 @@ impl UIComponent for AchievementNotificationSystem {
 
         commands
     }
-}
 
-impl AchievementNotificationSystem {
     /// Render a single notification
     fn render_notification(&self, active_notification: &ActiveNotification, index: usize) -> Vec<UIRenderCommand> {
         let mut commands = Vec::new();
@@ -309,13 +308,13 @@ impl AchievementNotificationSystem {
         let base_width = 35;
         let base_height = match self.config.style {
             NotificationStyle::Popup => 6,
-            NotificationStyle::Toast => 4,
+            NotificationStyle::Toast => 3,
             NotificationStyle::Banner => 3,
-            NotificationStyle::Minimal => 2,
+            NotificationStyle::Minimal => 1,
         };
 
         let width = (base_width as f32 * scale) as i32;
-        let height = (base_height as f32 * scale) as i32;
+        let height = base_height;
 
         // Calculate position with animation offset
         let (base_x, base_y) = self.calculate_position(index, height);
@@ -345,16 +344,21 @@ impl AchievementNotificationSystem {
                 self.render_toast_notification(&mut commands, notification, x, y, width, height, rarity_color, &rarity_effect);
             },
             NotificationStyle::Banner => {
-                self.render_banner_notification(&mut commands, notification, x, y, width, height, rarity_color, &rarity_effect);
+                self.render_banner_notification(&mut commands, notification, y, height, rarity_color, &rarity_effect);
             },
             NotificationStyle::Minimal => {
-                self.render_minimal_notification(&mut commands, notification, x, y, width, height, rarity_color);
+                self.render_minimal_notification(&mut commands, notification, x, y, rarity_color);
             },
         }
 
         commands
     }
 
+    /// A line of text on the notification's black background
+    fn text(commands: &mut Vec<UIRenderCommand>, x: i32, y: i32, text: String, fg: Color) {
+        commands.push(UIRenderCommand::DrawText { x, y, text, fg, bg: Color::Black });
+    }
+
     /// Render popup style notification
     fn render_popup_notification(
         &self,
@@ -369,56 +373,21 @@ impl AchievementNotificationSystem {
         active_notification: &ActiveNotification,
     ) {
         // Main panel
-        commands.push(UIRenderCommand::Panel(UIPanel {
-            x,
-            y,
-            width,
-            height,
-            title: Some("Achievement Unlocked!".to_string()),
-            border_color: rarity_color,
-            background_color: Color::Black,
-        }));
-
-        // Achievement icon and effect
-        commands.push(UIRenderCommand::Text(UIText {
-            x: x + 2,
-            y: y + 2,
-            text: format!("{} {}", notification.achievement_icon, rarity_effect),
-            color: Color::Yellow,
-            alignment: TextAlignment::Left,
-        }));
-
-        // Achievement name
-        commands.push(UIRenderCommand::Text(UIText {
-            x: x + 2,
-            y: y + 3,
-            text: notification.achievement_name.clone(),
-            color: rarity_color,
-            alignment: TextAlignment::Left,
-        }));
-
-        // Points
-        commands.push(UIRenderCommand::Text(UIText {
-            x: x + 2,
-            y: y + 4,
-            text: format!("+{} points", notification.points),
-            color: Color::Green,
-            alignment: TextAlignment::Left,
-        }));
+        commands.extend(UIPanel::new("Achievement Unlocked!".to_string(), x, y, width, height)
+            .with_colors(rarity_color, Color::Black, Color::Yellow)
+            .render());
+
+        // Achievement icon and effect, then its name and points
+        Self::text(commands, x + 2, y + 1, format!("{} {}", notification.achievement_icon, rarity_effect), Color::Yellow);
+        Self::text(commands, x + 2, y + 2, notification.achievement_name.clone(), rarity_color);
+        Self::text(commands, x + 2, y + 3, format!("+{} points", notification.points), Color::Green);
 
         // Progress bar if enabled
         if self.config.show_progress {
             let progress_width = width - 4;
-            let filled = (active_notification.display_progress * progress_width as f32) as i32;
+            let filled = ((active_notification.display_progress * progress_width as f32) as i32).clamp(0, progress_width);
             let empty = progress_width - filled;
-
-            commands.push(UIRenderCommand::Text(UIText {
-                x: x + 2,
-                y: y + height - 1,
-                text: format!("{}{}", "█".repeat(filled as usize), "░".repeat(empty as usize)),
-                color: Color::DarkGrey,
-                alignment: TextAlignment::Left,
-            }));
+            Self::text(commands, x + 2, y + height - 2, format!("{}{}", "█".repeat(filled as usize), "░".repeat(empty as usize)), Color::DarkGrey);
         }
     }
 
@@ -435,29 +404,13 @@ impl AchievementNotificationSystem {
         rarity_effect: &str,
     ) {
         // Simplified panel
-        commands.push(UIRenderCommand::Panel(UIPanel {
-            x,
-            y,
-            width,
-            height,
-            title: None,
-            border_color: rarity_color,
-            background_color: Color::Black,
-        }));
+        commands.extend(UIPanel::new(String::new(), x, y, width, height)
+            .with_colors(rarity_color, Color::Black, rarity_color)
+            .render());
 
         // Single line with icon, name, and points
-        commands.push(UIRenderCommand::Text(UIText {
-            x: x + 1,
-            y: y + 1,
-            text: format!("{} {} {} (+{})", 
-                notification.achievement_icon,
-                rarity_effect,
-                notification.achievement_name,
-                notification.points
-            ),
-            color: rarity_color,
-            alignment: TextAlignment::Left,
-        }));
+        let text = format!("{} {} {} (+{})", notification.achievement_icon, rarity_effect, notification.achievement_name, notification.points);
+        Self::text(commands, x + 1, y + 1, text, rarity_color);
     }
 
     /// Render banner style notification
@@ -465,37 +418,25 @@ impl AchievementNotificationSystem {
         &self,
         commands: &mut Vec<UIRenderCommand>,
         notification: &AchievementNotification,
-        x: i32,
         y: i32,
-        width: i32,
         height: i32,
         rarity_color: Color,
         rarity_effect: &str,
     ) {
         // Full width banner
-        commands.push(UIRenderCommand::Panel(UIPanel {
-            x: 0,
-            y,
-            width: 80, // Full screen width
-            height,
-            title: None,
-            border_color: rarity_color,
-            background_color: Color::Black,
-        }));
+        commands.extend(UIPanel::new(String::new(), 0, y, 80, height)
+            .with_colors(rarity_color, Color::Black, rarity_color)
+            .render());
 
         // Centered text
-        commands.push(UIRenderCommand::Text(UIText {
-            x: 40 - (notification.achievement_name.len() as i32 / 2),
-            y: y + 1,
-            text: format!("{} {} {} - Achievement Unlocked! (+{} points)", 
-                notification.achievement_icon,
-                rarity_effect,
-                notification.achievement_name,
-                notification.points
-            ),
-            color: rarity_color,
-            alignment: TextAlignment::Center,
-        }));
+        let text = format!("{} {} {} - Achievement Unlocked! (+{} points)",
+            notification.achievement_icon,
+            rarity_effect,
+            notification.achievement_name,
+            notification.points
+        );
+        let x = (40 - text.chars().count() as i32 / 2).max(1);
+        Self::text(commands, x, y + 1, text, rarity_color);
     }
 
     /// Render minimal style notification
@@ -505,22 +446,11 @@ impl AchievementNotificationSystem {
         notification: &AchievementNotification,
         x: i32,
         y: i32,
-        width: i32,
-        height: i32,
         rarity_color: Color,
     ) {
         // Just text, no panel
-        commands.push(UIRenderCommand::Text(UIText {
-            x,
-            y,
-            text: format!("{} {} (+{})", 
-                notification.achievement_icon,
-                notification.achievement_name,
-                notification.points
-            ),
-            color: rarity_color,
-            alignment: TextAlignment::Left,
-        }));
+        let text = format!("{} {} (+{})", notification.achievement_icon, notification.achievement_name, notification.points);
+        Self::text(commands, x, y, text, rarity_color);
     }
 }
 
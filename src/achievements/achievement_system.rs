@@ -5,6 +5,11 @@ use specs::{World, System, ReadStorage, WriteStorage, Entities, Join};
 use crate::components::{Player, Name, Position, Health, Experience};
 use crate::resources::GameLog;
 
+/// Turn limit for the fastest-win achievement
+pub const SWIFT_VICTORY_TURNS: u32 = 20000;
+/// Captives to see safely on their way for the rescuer achievement
+pub const RESCUER_CAPTIVES: u32 = 5;
+
 /// Achievement types
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AchievementType {
@@ -161,7 +166,7 @@ impl Achievement {
     }
 }
 
-/// Achievement rewards
+/// Achievement rewards. Item, Cosmetic and Unlock name unlockable content by id
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AchievementReward {
     Experience(u32),
@@ -266,7 +271,8 @@ impl AchievementSystem {
                 AchievementDifficulty::Medium,
                 50,
             ).with_progress_target(100)
-            .with_icon("💀".to_string()),
+            .with_icon("💀".to_string())
+            .with_rewards(vec![AchievementReward::Cosmetic("glyph_crimson".to_string())]),
 
             Achievement::new(
                 "boss_slayer".to_string(),
@@ -276,7 +282,8 @@ impl AchievementSystem {
                 AchievementRarity::Rare,
                 AchievementDifficulty::Hard,
                 100,
-            ).with_icon("👑".to_string()),
+            ).with_icon("👑".to_string())
+            .with_rewards(vec![AchievementReward::Cosmetic("glyph_gold".to_string())]),
 
             // Exploration achievements
             Achievement::new(
@@ -298,7 +305,8 @@ impl AchievementSystem {
                 AchievementDifficulty::Medium,
                 30,
             ).with_progress_target(50)
-            .with_icon("🗺️".to_string()),
+            .with_icon("🗺️".to_string())
+            .with_rewards(vec![AchievementReward::Unlock("class_ranger".to_string())]),
 
             Achievement::new(
                 "deep_delver".to_string(),
@@ -321,7 +329,8 @@ impl AchievementSystem {
                 AchievementDifficulty::Easy,
                 15,
             ).with_progress_target(2)
-            .with_icon("📈".to_string()),
+            .with_icon("📈".to_string())
+            .with_rewards(vec![AchievementReward::Item("starting_health_potion".to_string())]),
 
             Achievement::new(
                 "veteran".to_string(),
@@ -344,7 +353,8 @@ impl AchievementSystem {
                 AchievementDifficulty::Easy,
                 20,
             ).with_progress_target(100)
-            .with_icon("💰".to_string()),
+            .with_icon("💰".to_string())
+            .with_rewards(vec![AchievementReward::Unlock("background_merchant".to_string())]),
 
             Achievement::new(
                 "hoarder".to_string(),
@@ -379,6 +389,50 @@ impl AchievementSystem {
                 150,
             ).with_icon("✨".to_string()),
 
+            Achievement::new(
+                "rescuer".to_string(),
+                "Safe Passage".to_string(),
+                format!("Rescue {} captives from the dungeon", RESCUER_CAPTIVES),
+                AchievementType::Exploration,
+                AchievementRarity::Rare,
+                AchievementDifficulty::Medium,
+                75,
+            ).with_progress_target(RESCUER_CAPTIVES)
+            .with_icon("🤝".to_string()),
+
+            // Victory achievements
+            Achievement::new(
+                "artifact_claimed".to_string(),
+                "Heart of the Dungeon".to_string(),
+                "Take the Amulet of the Depths from its guardian".to_string(),
+                AchievementType::Exploration,
+                AchievementRarity::Epic,
+                AchievementDifficulty::Hard,
+                150,
+            ).with_icon("📿".to_string()),
+
+            Achievement::new(
+                "victory".to_string(),
+                "Champion of the Depths".to_string(),
+                "Escape the dungeon with the Amulet of the Depths".to_string(),
+                AchievementType::Special,
+                AchievementRarity::Legendary,
+                AchievementDifficulty::Extreme,
+                300,
+            ).with_icon("🏆".to_string())
+            .with_rewards(vec![AchievementReward::Item("starting_phoenix_feather".to_string())]),
+
+            Achievement::new(
+                "swift_victory".to_string(),
+                "In and Out".to_string(),
+                format!("Win a run in {} turns or fewer", SWIFT_VICTORY_TURNS),
+                AchievementType::Special,
+                AchievementRarity::Legendary,
+                AchievementDifficulty::Extreme,
+                400,
+            ).with_hidden(true)
+            .with_icon("⚡".to_string()),
+
             // Hidden achievements
             Achievement::new(
                 "secret_room".to_string(),
@@ -650,10 +704,12 @@ impl AchievementSystem {
                 self.increment_progress("explorer", 1);
             },
             GameEvent::LevelChanged(level) => {
-                self.update_progress("deep_delver", *level as u32);
                 self.update_progress("level_up", *level as u32);
                 self.update_progress("veteran", *level as u32);
             },
+            GameEvent::DepthReached(depth) => {
+                self.update_progress("deep_delver", *depth as u32);
+            },
             GameEvent::GoldCollected(amount) => {
                 self.increment_progress("treasure_hunter", *amount);
             },
@@ -672,6 +728,18 @@ impl AchievementSystem {
             GameEvent::PerfectLevel => {
                 self.increment_progress("perfectionist", 1);
             },
+            GameEvent::ArtifactClaimed => {
+                self.increment_progress("artifact_claimed", 1);
+            },
+            GameEvent::CaptiveRescued => {
+                self.increment_progress("rescuer", 1);
+            },
+            GameEvent::Victory { turns } => {
+                self.increment_progress("victory", 1);
+                if *turns <= SWIFT_VICTORY_TURNS {
+                    self.increment_progress("swift_victory", 1);
+                }
+            },
         }
     }
 
@@ -723,13 +791,20 @@ pub enum GameEvent {
     BossDefeated,
     PlayerMoved,
     RoomVisited,
+    /// The character reached this level
     LevelChanged(i32),
+    /// The character went deeper than ever before, to this depth
+    DepthReached(i32),
     GoldCollected(u32),
     ItemCollected,
     PlaytimeUpdate(u32),
     SecretRoomFound,
     EasterEggFound,
     PerfectLevel,
+    ArtifactClaimed,
+    /// A captive was freed and saw their reward paid
+    CaptiveRescued,
+    Victory { turns: u32 },
 }
 
 /// Achievement save data for persistence
@@ -831,6 +906,12 @@ mod tests {
         // Process level change event
         system.process_game_event(&GameEvent::LevelChanged(2));
         assert!(system.is_unlocked("level_up"));
+        
+        // Character levels and dungeon depth count towards different achievements
+        system.process_game_event(&GameEvent::LevelChanged(10));
+        assert!(!system.is_unlocked("deep_delver"));
+        system.process_game_event(&GameEvent::DepthReached(10));
+        assert!(system.is_unlocked("deep_delver"));
     }
 
     #[test]
@@ -0,0 +1,148 @@
+use serde::{Serialize, Deserialize};
+use std::fmt::Debug;
+
+/// How a node came out of a tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeStatus {
+    Success,
+    Failure,
+}
+
+/// A behavior tree over some set of conditions `C` and actions `A`, as written in the data files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BehaviorNode<C, A> {
+    /// Tries each child in turn and succeeds with the first that does
+    Selector(Vec<BehaviorNode<C, A>>),
+    /// Runs each child in turn and fails with the first that does
+    Sequence(Vec<BehaviorNode<C, A>>),
+    /// Succeeds when the condition holds
+    Condition(C),
+    /// Does something; fails if it could not
+    Action(A),
+}
+
+/// Whatever a tree is running against: answers its conditions and carries out its actions
+pub trait Blackboard<C, A> {
+    fn check(&mut self, condition: &C) -> bool;
+    fn act(&mut self, action: &A) -> bool;
+}
+
+impl<C: Debug, A: Debug> BehaviorNode<C, A> {
+    /// Run the tree once, returning how it went and the action that carried it, if any
+    pub fn tick(&self, blackboard: &mut impl Blackboard<C, A>) -> (NodeStatus, Option<String>) {
+        let mut active = None;
+        let status = self.tick_node(blackboard, &mut active);
+        (status, active)
+    }
+
+    fn tick_node(&self, blackboard: &mut impl Blackboard<C, A>, active: &mut Option<String>) -> NodeStatus {
+        match self {
+            BehaviorNode::Selector(children) => {
+                for child in children {
+                    if child.tick_node(blackboard, active) == NodeStatus::Success {
+                        return NodeStatus::Success;
+                    }
+                }
+                NodeStatus::Failure
+            },
+            BehaviorNode::Sequence(children) => {
+                for child in children {
+                    if child.tick_node(blackboard, active) == NodeStatus::Failure {
+                        return NodeStatus::Failure;
+                    }
+                }
+                NodeStatus::Success
+            },
+            BehaviorNode::Condition(condition) => {
+                if blackboard.check(condition) { NodeStatus::Success } else { NodeStatus::Failure }
+            },
+            BehaviorNode::Action(action) => {
+                if blackboard.act(action) {
+                    *active = Some(format!("{:?}", action));
+                    NodeStatus::Success
+                } else {
+                    NodeStatus::Failure
+                }
+            },
+        }
+    }
+
+    /// Nodes in the whole tree, for sanity checks on loaded data
+    pub fn size(&self) -> usize {
+        match self {
+            BehaviorNode::Selector(children) | BehaviorNode::Sequence(children) => {
+                1 + children.iter().map(|child| child.size()).sum::<usize>()
+            },
+            BehaviorNode::Condition(_) | BehaviorNode::Action(_) => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    enum Check { Hungry, Tired }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    enum Act { Eat, Sleep, Wander }
+
+    #[derive(Default)]
+    struct Creature {
+        hungry: bool,
+        tired: bool,
+        done: Vec<String>,
+    }
+
+    impl Blackboard<Check, Act> for Creature {
+        fn check(&mut self, condition: &Check) -> bool {
+            match condition {
+                Check::Hungry => self.hungry,
+                Check::Tired => self.tired,
+            }
+        }
+
+        fn act(&mut self, action: &Act) -> bool {
+            self.done.push(format!("{:?}", action));
+            true
+        }
+    }
+
+    fn tree() -> BehaviorNode<Check, Act> {
+        serde_json::from_str(r#"
+            { "Selector": [
+                { "Sequence": [ { "Condition": "Hungry" }, { "Action": "Eat" } ] },
+                { "Sequence": [ { "Condition": "Tired" }, { "Action": "Sleep" } ] },
+                { "Action": "Wander" }
+            ] }
+        "#).expect("test tree parses")
+    }
+
+    #[test]
+    fn test_selector_takes_the_first_branch_that_succeeds() {
+        let tree = tree();
+        assert_eq!(tree.size(), 8);
+
+        let mut creature = Creature { tired: true, ..Creature::default() };
+        assert_eq!(tree.tick(&mut creature), (NodeStatus::Success, Some("Sleep".to_string())));
+        assert_eq!(creature.done, vec!["Sleep".to_string()]);
+
+        let mut creature = Creature { hungry: true, tired: true, ..Creature::default() };
+        assert_eq!(tree.tick(&mut creature).1, Some("Eat".to_string()));
+
+        let mut creature = Creature::default();
+        assert_eq!(tree.tick(&mut creature).1, Some("Wander".to_string()));
+    }
+
+    #[test]
+    fn test_sequence_stops_at_the_first_failure() {
+        let tree: BehaviorNode<Check, Act> = BehaviorNode::Sequence(vec![
+            BehaviorNode::Condition(Check::Hungry),
+            BehaviorNode::Action(Act::Eat),
+        ]);
+        let mut creature = Creature::default();
+        assert_eq!(tree.tick(&mut creature), (NodeStatus::Failure, None));
+        assert!(creature.done.is_empty());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod ai_component;
+pub mod behavior_tree;
+pub mod monster_behavior;
 pub mod behavior_system;
 pub mod pathfinding;
 pub mod ai_movement_system;
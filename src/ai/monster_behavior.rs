@@ -0,0 +1,96 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::components::AIMode;
+use super::behavior_tree::BehaviorNode;
+
+/// Behavior trees shipped with the game, one per kind of monster
+const BUILTIN_BEHAVIORS: &str = include_str!("../../data/behavior_trees.json");
+
+/// The tree used for any monster without one of its own
+pub const DEFAULT_BEHAVIOR: &str = "default";
+
+/// What a monster's behavior tree can ask about
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MonsterCondition {
+    /// The monster is in this mode
+    InMode(AIMode),
+    /// Its pack has picked something to go after
+    HasPackQuarry,
+    /// Its pack has given it a place to stand
+    HasPackPost,
+    /// The player is in sight
+    SeesPlayer,
+    /// It heard a noise this turn
+    HeardNoise,
+    /// One in this many turns
+    Chance(i32),
+}
+
+/// What a monster's behavior tree can have it do
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MonsterAction {
+    /// Stand still
+    Wait,
+    /// Go after the pack's quarry from the side it was given
+    HuntPackQuarry,
+    /// Keep to its place around the pack leader
+    KeepFormation,
+    /// Chase down the player in sight, attacking when close enough
+    Hunt,
+    /// Head for the last noise heard
+    Listen,
+    /// Lost sight of its quarry; go and look where it was last seen
+    LoseTrail,
+    /// Keep heading for what it was investigating
+    Investigate,
+    /// Check the rooms nearby
+    Search,
+    /// Stop searching and head home
+    GiveUp,
+    /// Walk back to its post
+    ReturnHome,
+    /// Amble about near its post
+    Patrol,
+    /// Flit about at random, whatever else is going on
+    Flutter,
+}
+
+pub type MonsterTree = BehaviorNode<MonsterCondition, MonsterAction>;
+
+/// Every monster behavior tree, keyed by the lower-case monster name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonsterBehaviors {
+    pub trees: HashMap<String, MonsterTree>,
+}
+
+impl MonsterBehaviors {
+    /// The trees bundled with the game
+    pub fn builtin() -> Self {
+        serde_json::from_str(BUILTIN_BEHAVIORS).expect("Bundled behavior tree data is invalid")
+    }
+
+    /// Load trees from a data file, e.g. for mods or testing
+    pub fn load_from_file(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(filename)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// The tree for a kind of monster, falling back to the default one
+    pub fn tree(&self, kind: &str) -> Option<&MonsterTree> {
+        self.trees.get(kind).or_else(|| self.trees.get(DEFAULT_BEHAVIOR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_trees_load_with_a_default() {
+        let behaviors = MonsterBehaviors::builtin();
+        let default = behaviors.tree(DEFAULT_BEHAVIOR).expect("default tree");
+        assert!(default.size() > 1);
+        assert_eq!(behaviors.tree("no such monster"), Some(default));
+        assert_ne!(behaviors.tree("bat"), Some(default));
+    }
+}
@@ -1,8 +1,9 @@
 use ascii_dungeon_explorer::components::*;
 use ascii_dungeon_explorer::rendering::terminal::with_terminal;
+use ascii_dungeon_explorer::rendering::MotionSettings;
 use ascii_dungeon_explorer::resources::{GameLog, RandomNumberGenerator};
 use ascii_dungeon_explorer::systems::{
-    CombatFeedbackSystem, ParticleEffectSystem, ScreenShakeState, ScreenShakeSystem,
+    CombatFeedbackSystem, ParticleEffectSystem, ParticleEmitter, ScreenShakeState, ScreenShakeSystem,
     SoundEffectSystem, VisualEffectsSystem,
 };
 use crossterm::event::{read, Event, KeyCode};
@@ -20,6 +21,8 @@ fn main() {
     world.insert(GameLog::new());
     world.insert(RandomNumberGenerator::new_with_random_seed());
     world.insert(ScreenShakeState::new());
+    world.insert(ParticleEmitter::default());
+    world.insert(MotionSettings::default());
 
     // Create a player
     let player = world
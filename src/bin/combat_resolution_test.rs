@@ -46,6 +46,7 @@ fn main() {
             critical_multiplier: 2.5,
             attack_speed: 100,
             last_attack_turn: 0,
+            armor_penetration: 0,
         })
         .with(Defender {
             armor_class: 14,
@@ -96,6 +97,7 @@ fn main() {
             critical_multiplier: 2.0,
             attack_speed: 90,
             last_attack_turn: 0,
+            armor_penetration: 0,
         })
         .with(Defender {
             armor_class: 12,
@@ -73,6 +73,7 @@ fn main() {
         // Update and render effects
         context.update_effects();
         context.render_effects(&map, player_pos);
+        context.present();
 
         // Sleep for a bit
         thread::sleep(Duration::from_millis(50));
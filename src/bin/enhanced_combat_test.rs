@@ -1,4 +1,5 @@
 use ascii_dungeon_explorer::components::*;
+use ascii_dungeon_explorer::map::Map;
 use ascii_dungeon_explorer::rendering::terminal::with_terminal;
 use ascii_dungeon_explorer::resources::{GameLog, RandomNumberGenerator};
 use ascii_dungeon_explorer::systems::{
@@ -18,6 +19,7 @@ fn main() {
     // Add resources
     world.insert(GameLog::new());
     world.insert(RandomNumberGenerator::new_with_random_seed());
+    world.insert(Map::new(80, 50, 1));
 
     // Create a player with enhanced combat components
     let player = world
@@ -45,6 +47,7 @@ fn main() {
             critical_multiplier: 2.5,
             attack_speed: 100,
             last_attack_turn: 0,
+            armor_penetration: 0,
         })
         .with(Defender {
             armor_class: 12,
@@ -83,6 +86,7 @@ fn main() {
             critical_multiplier: 2.0,
             attack_speed: 120,
             last_attack_turn: 0,
+            armor_penetration: 0,
         })
         .with(Defender {
             armor_class: 11,
@@ -239,6 +239,8 @@ fn entity_glyph_and_color(entity_type: &SpawnType) -> (char, Color) {
             EnemyType::Snake => ('S', Color::Green),
             EnemyType::Slime => ('j', Color::Green),
         },
+        SpawnType::Boss(_) => ('B', Color::Red),
+        SpawnType::Guardian(_) => ('V', Color::Magenta),
         SpawnType::Item(item_type) => match item_type {
             ItemType::HealthPotion => ('!', Color::Red),
             ItemType::ManaPotion => ('!', Color::Blue),
@@ -102,6 +102,7 @@ fn main() {
             if let Some(spawn) = entity_here {
                 match spawn.entity_type {
                     SpawnType::Enemy(_) => print!("E"),
+                    SpawnType::Boss(_) => print!("B"),
                     SpawnType::Item(_) => print!("I"),
                     SpawnType::Special(_) => print!("S"),
                 }
@@ -1,8 +1,8 @@
 use ascii_dungeon_explorer::components::*;
 use ascii_dungeon_explorer::rendering::terminal::with_terminal;
-use ascii_dungeon_explorer::resources::{GameLog, RandomNumberGenerator};
+use ascii_dungeon_explorer::resources::{GameLog, RandomNumberGenerator, GameStateResource};
 use ascii_dungeon_explorer::systems::{
-    AbilityUsageSystem, ResourceRegenerationSystem, StatusEffectSystem,
+    AbilityResolutionSystem, ResourceRegenerationSystem, StatusEffectSystem,
 };
 use ascii_dungeon_explorer::ui::{
     draw_resource_bars, show_resource_management_screen, ResourceAction,
@@ -21,6 +21,7 @@ fn main() {
     // Add resources
     world.insert(GameLog::new());
     world.insert(RandomNumberGenerator::new_with_random_seed());
+    world.insert(GameStateResource::default());
 
     // Create a player with resource management
     let player = world
@@ -48,9 +49,9 @@ fn main() {
         .build();
 
     // Create systems
-    let mut resource_regen_system = ResourceRegenerationSystem {};
-    let mut status_effect_system = StatusEffectSystem {};
-    let mut ability_usage_system = AbilityUsageSystem {};
+    let mut resource_regen_system = ResourceRegenerationSystem::new();
+    let mut status_effect_system = StatusEffectSystem::new();
+    let mut ability_resolution_system = AbilityResolutionSystem {};
 
     // Main loop
     let mut running = true;
@@ -224,9 +225,10 @@ fn main() {
                     }
                     KeyCode::Char('n') => {
                         // Next turn - run systems
+                        world.write_resource::<GameStateResource>().turn_count += 1;
                         resource_regen_system.run_now(&world);
                         status_effect_system.run_now(&world);
-                        ability_usage_system.run_now(&world);
+                        ability_resolution_system.run_now(&world);
                         world.maintain();
 
                         turn_counter += 1;
@@ -246,7 +248,7 @@ fn main() {
                             )
                             .expect("Failed to insert ability use");
 
-                        ability_usage_system.run_now(&world);
+                        ability_resolution_system.run_now(&world);
                         world.maintain();
 
                         turn_counter += 1;
@@ -2,7 +2,7 @@ use ascii_dungeon_explorer::components::*;
 use ascii_dungeon_explorer::rendering::terminal::with_terminal;
 use ascii_dungeon_explorer::resources::{GameLog, RandomNumberGenerator};
 use ascii_dungeon_explorer::systems::{
-    AbilityCooldownSystem, AbilityTargetingSystem, SpecialAbilitiesSystem,
+    AbilityCooldownSystem, AbilityTargetingSystem, AbilityResolutionSystem,
 };
 use crossterm::event::{read, Event, KeyCode};
 use crossterm::style::Color;
@@ -141,9 +141,9 @@ fn main() {
     }
 
     // Create systems
-    let mut special_abilities_system = SpecialAbilitiesSystem {};
+    let mut ability_resolution_system = AbilityResolutionSystem {};
     let mut ability_targeting_system = AbilityTargetingSystem {};
-    let mut ability_cooldown_system = AbilityCooldownSystem {};
+    let mut ability_cooldown_system = AbilityCooldownSystem::new();
 
     // Main loop
     let mut running = true;
@@ -381,7 +381,7 @@ fn main() {
 
                         // Run ability systems
                         ability_targeting_system.run_now(&world);
-                        special_abilities_system.run_now(&world);
+                        ability_resolution_system.run_now(&world);
                         world.maintain();
                     }
                     KeyCode::Char('h') => {
@@ -399,7 +399,7 @@ fn main() {
                             )
                             .expect("Failed to insert heal ability");
 
-                        special_abilities_system.run_now(&world);
+                        ability_resolution_system.run_now(&world);
                         world.maintain();
                     }
                     KeyCode::Char('r') => {
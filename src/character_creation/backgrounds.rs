@@ -0,0 +1,105 @@
+use specs::{World, WorldExt, Entity};
+use crate::components::{
+    BackgroundPerk, BackgroundType, CombatStats, Contact, Position, Skills, StatusEffect, StatusEffectType, StatusEffects,
+    FAMILY_PURSE_GOLD, SHRINE_BLESSING_TURNS, VETERAN_BONUS_HP
+};
+use crate::items::{AdvancedInventory, ItemFactory, ItemProperties, ToolType};
+
+/// Hand `owner` their background's perk and the contact who knew them in town. Starting skills
+/// come with `Skills::for_background`, so the owner should already have them
+pub fn grant_background(world: &mut World, owner: Entity, background: BackgroundType) {
+    match background.perk() {
+        BackgroundPerk::Veteran => {
+            if let Some(stats) = world.write_storage::<CombatStats>().get_mut(owner) {
+                stats.max_hp += VETERAN_BONUS_HP;
+                stats.hp += VETERAN_BONUS_HP;
+            }
+        },
+        BackgroundPerk::Studious => {
+            if let Some(skills) = world.write_storage::<Skills>().get_mut(owner) {
+                skills.add_skill_points(1);
+            }
+        },
+        BackgroundPerk::FamilyPurse => {
+            if let Some(inventory) = world.write_storage::<AdvancedInventory>().get_mut(owner) {
+                inventory.gold += FAMILY_PURSE_GOLD;
+            }
+        },
+        BackgroundPerk::Underworld => {
+            // The stealth half comes with the background's skills
+            let lockpicks = ItemFactory::new().create_tool(world, ToolType::Lockpick, Position { x: 0, y: 0 });
+            world.write_storage::<Position>().remove(lockpicks);
+            let weight = world.read_storage::<ItemProperties>().get(lockpicks).map_or(0.0, |props| props.weight);
+            if let Some(inventory) = world.write_storage::<AdvancedInventory>().get_mut(owner) {
+                inventory.add_item(lockpicks, 1, weight);
+            }
+        },
+        BackgroundPerk::ShrineBlessing => {
+            let mut effects = world.write_storage::<StatusEffects>();
+            if !effects.contains(owner) {
+                effects.insert(owner, StatusEffects::new()).expect("Unable to insert status effects");
+            }
+            if let Some(effects) = effects.get_mut(owner) {
+                effects.add_effect(StatusEffect::new(StatusEffectType::Blessed, SHRINE_BLESSING_TURNS, 1));
+            }
+        },
+        // Paid out at the counter, through `BackgroundType::price_multiplier`
+        BackgroundPerk::Haggler => {},
+    }
+
+    world.write_storage::<Contact>()
+        .insert(owner, background.contact())
+        .expect("Unable to insert contact");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use crate::components::SkillType;
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<ItemProperties>();
+        world.register::<AdvancedInventory>();
+        world
+    }
+
+    fn character(world: &mut World, background: BackgroundType) -> Entity {
+        let owner = world.create_entity()
+            .with(CombatStats { max_hp: 20, hp: 20, defense: 0, power: 1 })
+            .with(Skills::for_background(background))
+            .with(AdvancedInventory::new(26, 150.0))
+            .build();
+        grant_background(world, owner, background);
+        owner
+    }
+
+    #[test]
+    fn test_an_outlaw_sets_out_sneaky_with_lockpicks() {
+        let mut world = world();
+        let outlaw = character(&mut world, BackgroundType::Outlaw);
+
+        assert_eq!(world.read_storage::<Skills>().get(outlaw).unwrap().get_skill_level(SkillType::Stealth), 2);
+        let inventory = world.read_storage::<AdvancedInventory>();
+        let props = world.read_storage::<ItemProperties>();
+        let carried: Vec<&str> = inventory.get(outlaw).unwrap().items.iter()
+            .filter_map(|slot| props.get(slot.entity))
+            .map(|props| props.name.as_str())
+            .collect();
+        assert_eq!(carried, vec!["Lockpick Set"]);
+        assert_eq!(world.read_storage::<Contact>().get(outlaw), Some(&BackgroundType::Outlaw.contact()));
+    }
+
+    #[test]
+    fn test_each_background_has_its_own_perk() {
+        let mut world = world();
+        let acolyte = character(&mut world, BackgroundType::Acolyte);
+        let soldier = character(&mut world, BackgroundType::Soldier);
+
+        assert!(world.read_storage::<StatusEffects>().get(acolyte).unwrap().has_effect(StatusEffectType::Blessed));
+        assert_eq!(world.read_storage::<CombatStats>().get(soldier).unwrap().max_hp, 20 + VETERAN_BONUS_HP);
+        assert!(BackgroundType::Merchant.price_multiplier() < BackgroundType::Noble.price_multiplier());
+    }
+}
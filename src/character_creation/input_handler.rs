@@ -1,6 +1,8 @@
 use crossterm::event::{KeyEvent, KeyCode};
+use specs::WorldExt;
 use crate::game_state::{RunState, GameState};
-use crate::components::{AttributeType, ClassType, BackgroundType};
+use crate::components::{AttributeType, ClassType, BackgroundType, Attributes, GameSettings};
+use crate::resources::RandomNumberGenerator;
 use super::CharacterCreationState;
 
 pub fn handle_character_creation_input(key_event: KeyEvent, game_state: &mut GameState, creation_state: &mut CharacterCreationState) -> bool {
@@ -27,6 +29,12 @@ fn handle_name_input(key_event: KeyEvent, game_state: &mut GameState, creation_s
             creation_state.player_name.pop();
             true
         },
+        KeyCode::Tab => {
+            // Suggest a generated name
+            let mut rng = game_state.world.write_resource::<RandomNumberGenerator>();
+            creation_state.suggest_name(&mut rng);
+            true
+        },
         KeyCode::Enter => {
             if !creation_state.player_name.is_empty() {
                 game_state.run_state = RunState::CharacterClass;
@@ -44,23 +52,23 @@ fn handle_name_input(key_event: KeyEvent, game_state: &mut GameState, creation_s
 fn handle_class_input(key_event: KeyEvent, game_state: &mut GameState, creation_state: &mut CharacterCreationState) -> bool {
     match key_event.code {
         KeyCode::Char('1') | KeyCode::Char('f') => {
-            creation_state.selected_class = ClassType::Fighter;
+            creation_state.select_class(ClassType::Fighter);
             true
         },
         KeyCode::Char('2') | KeyCode::Char('r') => {
-            creation_state.selected_class = ClassType::Rogue;
+            creation_state.select_class(ClassType::Rogue);
             true
         },
         KeyCode::Char('3') | KeyCode::Char('m') => {
-            creation_state.selected_class = ClassType::Mage;
+            creation_state.select_class(ClassType::Mage);
             true
         },
         KeyCode::Char('4') | KeyCode::Char('c') => {
-            creation_state.selected_class = ClassType::Cleric;
+            creation_state.select_class(ClassType::Cleric);
             true
         },
         KeyCode::Char('5') | KeyCode::Char('a') => {
-            creation_state.selected_class = ClassType::Ranger;
+            creation_state.select_class(ClassType::Ranger);
             true
         },
         KeyCode::Enter => {
@@ -78,33 +86,35 @@ fn handle_class_input(key_event: KeyEvent, game_state: &mut GameState, creation_
 fn handle_background_input(key_event: KeyEvent, game_state: &mut GameState, creation_state: &mut CharacterCreationState) -> bool {
     match key_event.code {
         KeyCode::Char('1') | KeyCode::Char('s') => {
-            creation_state.selected_background = BackgroundType::Soldier;
+            creation_state.select_background(BackgroundType::Soldier);
             true
         },
         KeyCode::Char('2') | KeyCode::Char('c') => {
-            creation_state.selected_background = BackgroundType::Scholar;
+            creation_state.select_background(BackgroundType::Scholar);
             true
         },
         KeyCode::Char('3') | KeyCode::Char('n') => {
-            creation_state.selected_background = BackgroundType::Noble;
+            creation_state.select_background(BackgroundType::Noble);
             true
         },
         KeyCode::Char('4') | KeyCode::Char('o') => {
-            creation_state.selected_background = BackgroundType::Outlaw;
+            creation_state.select_background(BackgroundType::Outlaw);
             true
         },
         KeyCode::Char('5') | KeyCode::Char('a') => {
-            creation_state.selected_background = BackgroundType::Acolyte;
+            creation_state.select_background(BackgroundType::Acolyte);
             true
         },
         KeyCode::Char('6') | KeyCode::Char('m') => {
-            creation_state.selected_background = BackgroundType::Merchant;
+            creation_state.select_background(BackgroundType::Merchant);
             true
         },
         KeyCode::Enter => {
-            // Apply class and background bonuses before moving to attributes
-            creation_state.apply_class_bonuses();
-            creation_state.apply_background_bonuses();
+            // Generate attributes and apply class and background bonuses before moving on
+            {
+                let mut rng = game_state.world.write_resource::<RandomNumberGenerator>();
+                creation_state.reset_attributes(&mut rng);
+            }
             game_state.run_state = RunState::CharacterAttributes;
             true
         },
@@ -143,13 +153,29 @@ fn handle_attributes_input(key_event: KeyEvent, game_state: &mut GameState, crea
             true
         },
         KeyCode::Left | KeyCode::Char('h') => {
-            // Decrease selected attribute
-            creation_state.attributes.decrease_attribute(creation_state.selected_attribute);
+            // Decrease selected attribute, or trade scores upwards with the standard array
+            if !creation_state.swap_attribute(false) {
+                creation_state.attributes.decrease_attribute(creation_state.selected_attribute);
+            }
             true
         },
         KeyCode::Right | KeyCode::Char('l') => {
-            // Increase selected attribute
-            creation_state.attributes.increase_attribute(creation_state.selected_attribute);
+            // Increase selected attribute, or trade scores downwards with the standard array
+            if !creation_state.swap_attribute(true) {
+                creation_state.attributes.increase_attribute(creation_state.selected_attribute);
+            }
+            true
+        },
+        KeyCode::Char('m') => {
+            // Switch between point buy, standard array and rolling
+            let mut rng = game_state.world.write_resource::<RandomNumberGenerator>();
+            creation_state.cycle_attribute_method(&mut rng);
+            true
+        },
+        KeyCode::Char('r') => {
+            // Reroll, while rerolls last
+            let mut rng = game_state.world.write_resource::<RandomNumberGenerator>();
+            creation_state.reroll(&mut rng);
             true
         },
         KeyCode::Enter => {
@@ -171,27 +197,17 @@ fn handle_attributes_input(key_event: KeyEvent, game_state: &mut GameState, crea
 fn handle_equipment_input(key_event: KeyEvent, game_state: &mut GameState, creation_state: &mut CharacterCreationState) -> bool {
     match key_event.code {
         KeyCode::Up | KeyCode::Char('k') => {
-            // Move selection up
-            if creation_state.selected_equipment > 0 {
-                creation_state.selected_equipment -= 1;
-            } else {
-                creation_state.selected_equipment = creation_state.available_equipment.len() - 1;
-            }
+            creation_state.cycle_kit(false);
             true
         },
         KeyCode::Down | KeyCode::Char('j') => {
-            // Move selection down
-            creation_state.selected_equipment = (creation_state.selected_equipment + 1) % creation_state.available_equipment.len();
+            creation_state.cycle_kit(true);
             true
         },
-        KeyCode::Char(' ') => {
-            // Toggle selection
-            let idx = creation_state.selected_equipment;
-            if creation_state.selected_equipment_indices.contains(&idx) {
-                creation_state.selected_equipment_indices.retain(|&i| i != idx);
-            } else if creation_state.selected_equipment_indices.len() < 3 {
-                // Limit to 3 starting equipment items
-                creation_state.selected_equipment_indices.push(idx);
+        KeyCode::Char(c @ '1'..='9') => {
+            let idx = c as usize - '1' as usize;
+            if idx < creation_state.kits.len() {
+                creation_state.selected_kit = idx;
             }
             true
         },
@@ -214,10 +230,24 @@ fn handle_confirm_input(key_event: KeyEvent, game_state: &mut GameState, creatio
             let player_x = 40;
             let player_y = 25;
             let player = creation_state.create_player(&mut game_state.world, player_x, player_y);
+            game_state.new_game_settings = game_state.new_game_settings.clone().with_game_mode(creation_state.game_mode.clone());
+            game_state.world.write_storage::<GameSettings>()
+                .insert(player, game_state.new_game_settings.clone())
+                .expect("Unable to insert game settings");
             game_state.player = Some(player);
             game_state.run_state = RunState::PreRun;
             true
         },
+        KeyCode::Char('g') => {
+            // Cycle how forgiving death is
+            creation_state.game_mode = creation_state.game_mode.next();
+            true
+        },
+        KeyCode::Char('c') => {
+            // Cycle through the unlocked glyph colors
+            creation_state.cycle_glyph_color();
+            true
+        },
         KeyCode::Char('n') | KeyCode::Esc => {
             game_state.run_state = RunState::CharacterEquipment;
             true
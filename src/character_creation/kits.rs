@@ -0,0 +1,222 @@
+use specs::{World, WorldExt, Entity};
+use crate::components::{ClassType, Position};
+use crate::items::{
+    AdvancedInventory, AmmoKind, AmmoMaterial, ArmorType, ConsumableFactory, Equipment, EquipmentSlot,
+    ItemFactory, ItemProperties, ItemRarity, ItemTag, PotionPotency, WeaponType
+};
+use crate::resources::RandomNumberGenerator;
+
+/// Arrows or bolts that come with a starting bow or crossbow
+pub const STARTING_AMMO: i32 = 20;
+
+/// One piece of a starting kit
+#[derive(Debug, Clone, PartialEq)]
+pub enum KitItem {
+    /// Wielded in the main hand
+    Weapon(WeaponType),
+    /// Wielded in the main hand, needing both hands whatever its kind
+    TwoHanded(WeaponType),
+    /// Carried in the off hand
+    OffHand(WeaponType),
+    Armor(ArmorType),
+    /// A bundle for the quiver
+    Ammo(AmmoKind),
+    HealthPotion,
+}
+
+impl KitItem {
+    pub fn name(&self) -> String {
+        match self {
+            KitItem::Weapon(weapon) | KitItem::OffHand(weapon) => weapon.item_name().to_string(),
+            KitItem::TwoHanded(weapon) => format!("{} (two hands)", weapon.item_name()),
+            KitItem::Armor(armor) => armor.item_name().to_string(),
+            KitItem::Ammo(kind) => format!("{} {}s", STARTING_AMMO, kind.name()),
+            KitItem::HealthPotion => "Health Potion".to_string(),
+        }
+    }
+}
+
+/// What a kit adds up to once it is all worn, for comparing kits side by side
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct KitPreview {
+    pub attack: i32,
+    pub damage: i32,
+    pub defense: i32,
+    pub weight: f32,
+}
+
+/// A set of starting equipment a class may set out with
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartingKit {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub items: Vec<KitItem>,
+}
+
+impl StartingKit {
+    fn new(name: &'static str, description: &'static str, items: Vec<KitItem>) -> Self {
+        StartingKit { name, description, items }
+    }
+
+    /// The kits a new character of `class` chooses between, the usual one first
+    pub fn for_class(class: ClassType) -> Vec<StartingKit> {
+        use KitItem::*;
+        match class {
+            ClassType::Fighter => vec![
+                StartingKit::new("Sword and Shield", "Steady blows behind a wall of iron",
+                    vec![Weapon(WeaponType::Sword), Armor(ArmorType::Shield), Armor(ArmorType::Chest)]),
+                StartingKit::new("Two-Hander", "A great axe swung with both hands; hits hard, guards nothing",
+                    vec![TwoHanded(WeaponType::Axe), Armor(ArmorType::Chest), Armor(ArmorType::Helmet)]),
+                StartingKit::new("Skirmisher", "A spear and light boots, to strike and be gone",
+                    vec![Weapon(WeaponType::Spear), Armor(ArmorType::Boots), HealthPotion]),
+            ],
+            ClassType::Rogue => vec![
+                StartingKit::new("Twin Daggers", "A blade in each hand and a cloak to hide them",
+                    vec![Weapon(WeaponType::Dagger), OffHand(WeaponType::Dagger), Armor(ArmorType::Cloak)]),
+                StartingKit::new("Crossbow", "Strike from the dark before they know you are there",
+                    vec![Weapon(WeaponType::Crossbow), Ammo(AmmoKind::Bolt), Armor(ArmorType::Boots)]),
+            ],
+            ClassType::Mage => vec![
+                StartingKit::new("Staff", "A sturdy staff to lean on and to hit with",
+                    vec![Weapon(WeaponType::Staff), Armor(ArmorType::Cloak), HealthPotion]),
+                StartingKit::new("Wand and Dagger", "A wand for focus and a dagger for emergencies",
+                    vec![Weapon(WeaponType::Wand), OffHand(WeaponType::Dagger), Armor(ArmorType::Amulet)]),
+            ],
+            ClassType::Cleric => vec![
+                StartingKit::new("Mace and Shield", "The faith's hammer and its bulwark",
+                    vec![Weapon(WeaponType::Mace), Armor(ArmorType::Shield), Armor(ArmorType::Helmet)]),
+                StartingKit::new("Pilgrim", "A walking staff, mail and a potion for the road",
+                    vec![Weapon(WeaponType::Staff), Armor(ArmorType::Chest), HealthPotion]),
+            ],
+            ClassType::Ranger => vec![
+                StartingKit::new("Longbow", "A bow, a full quiver and gloves for the string",
+                    vec![Weapon(WeaponType::Bow), Ammo(AmmoKind::Arrow), Armor(ArmorType::Gloves)]),
+                StartingKit::new("Spear and Cloak", "For rangers who would rather meet the beast up close",
+                    vec![Weapon(WeaponType::Spear), Armor(ArmorType::Cloak), Armor(ArmorType::Boots)]),
+            ],
+        }
+    }
+
+    /// What the kit adds to attack, damage and defense, and how much it weighs
+    pub fn preview(&self) -> KitPreview {
+        let mut preview = KitPreview::default();
+        for item in &self.items {
+            match item {
+                KitItem::Weapon(weapon) | KitItem::TwoHanded(weapon) | KitItem::OffHand(weapon) => {
+                    let (attack, damage) = weapon.base_bonuses();
+                    preview.attack += attack;
+                    preview.damage += damage;
+                    preview.weight += weapon.base_weight();
+                },
+                KitItem::Armor(armor) => {
+                    preview.defense += armor.base_defense();
+                    preview.weight += armor.base_weight();
+                },
+                KitItem::Ammo(_) | KitItem::HealthPotion => {},
+            }
+        }
+        preview
+    }
+
+    /// Make the kit through the item factories and hand it to `owner`, wearing what can be worn
+    pub fn give_to(&self, world: &mut World, owner: Entity, rng: &mut RandomNumberGenerator) -> Vec<Entity> {
+        let factory = ItemFactory::new();
+        let mut given = Vec::new();
+        for piece in &self.items {
+            let position = Position { x: 0, y: 0 };
+            let (item, slot, quantity) = match piece {
+                KitItem::Weapon(weapon) | KitItem::TwoHanded(weapon) => {
+                    (factory.create_weapon(world, weapon.clone(), position, rng), Some(EquipmentSlot::MainHand), 1)
+                },
+                KitItem::OffHand(weapon) => {
+                    (factory.create_weapon(world, weapon.clone(), position, rng), Some(EquipmentSlot::OffHand), 1)
+                },
+                KitItem::Armor(armor) => {
+                    let item = factory.create_armor(world, armor.clone(), position, rng);
+                    let props = world.read_storage::<ItemProperties>();
+                    let slot = props.get(item).and_then(|props| EquipmentSlot::for_item_type(&props.item_type));
+                    (item, slot, 1)
+                },
+                KitItem::Ammo(kind) => {
+                    let item = ConsumableFactory::new().create_ammunition(world, position, *kind, AmmoMaterial::Wooden, STARTING_AMMO);
+                    (item, Some(EquipmentSlot::Quiver), STARTING_AMMO)
+                },
+                KitItem::HealthPotion => {
+                    (ConsumableFactory::new().create_health_potion(world, position, PotionPotency::Lesser), None, 1)
+                },
+            };
+
+            // Plain gear, whatever the factory rolled, carried rather than lying on the floor
+            world.write_storage::<Position>().remove(item);
+            let weight = {
+                let mut props = world.write_storage::<ItemProperties>();
+                match props.get_mut(item) {
+                    Some(props) => {
+                        props.rarity = ItemRarity::Common;
+                        if matches!(piece, KitItem::TwoHanded(_)) && !props.has_tag(&ItemTag::TwoHanded) {
+                            props.tags.push(ItemTag::TwoHanded);
+                        }
+                        props.weight
+                    },
+                    None => 0.0,
+                }
+            };
+            if let Some(inventory) = world.write_storage::<AdvancedInventory>().get_mut(owner) {
+                inventory.add_item(item, quantity, weight);
+            }
+            if let (Some(slot), Some(equipment)) = (slot, world.write_storage::<Equipment>().get_mut(owner)) {
+                equipment.equip_item(slot, item);
+            }
+            given.push(item);
+        }
+        given
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use crate::items::is_two_handed;
+
+    #[test]
+    fn test_every_class_has_a_choice_of_kits() {
+        for class in [ClassType::Fighter, ClassType::Rogue, ClassType::Mage, ClassType::Cleric, ClassType::Ranger] {
+            let kits = StartingKit::for_class(class);
+            assert!((2..=3).contains(&kits.len()));
+            assert!(kits.iter().all(|kit| kit.preview().attack > 0));
+        }
+
+        // The two-hander trades the shield's defense for harder hits
+        let fighter = StartingKit::for_class(ClassType::Fighter);
+        let (board, great_axe) = (fighter[0].preview(), fighter[1].preview());
+        assert!(great_axe.damage > board.damage);
+        assert!(great_axe.defense < board.defense);
+    }
+
+    #[test]
+    fn test_a_kit_is_carried_and_worn() {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<ItemProperties>();
+        world.register::<crate::items::ItemBonuses>();
+        world.register::<crate::items::ItemStack>();
+        world.register::<AdvancedInventory>();
+        world.register::<Equipment>();
+        let owner = world.create_entity()
+            .with(AdvancedInventory::new(26, 150.0))
+            .with(Equipment::new())
+            .build();
+
+        let kit = &StartingKit::for_class(ClassType::Fighter)[1];
+        let given = kit.give_to(&mut world, owner, &mut RandomNumberGenerator::new(9));
+        assert_eq!(given.len(), kit.items.len());
+
+        let equipment = world.read_storage::<Equipment>();
+        let axe = equipment.get(owner).and_then(|gear| gear.get_equipped(&EquipmentSlot::MainHand)).unwrap();
+        let props = world.read_storage::<ItemProperties>();
+        assert!(is_two_handed(props.get(axe).unwrap()));
+        assert!(world.read_storage::<Position>().get(axe).is_none());
+        assert!(world.read_storage::<AdvancedInventory>().get(owner).unwrap().find_item(axe).is_some());
+    }
+}
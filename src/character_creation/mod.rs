@@ -10,7 +10,11 @@ use crate::resources::GameLog;
 mod state;
 mod input_handler;
 mod renderer;
+mod kits;
+mod backgrounds;
 
-pub use state::CharacterCreationState;
+pub use state::{CharacterCreationState, AttributeMethod, STANDARD_ARRAY, MAX_REROLLS, glyph_color_named, starting_item_name};
 pub use input_handler::handle_character_creation_input;
-pub use renderer::render_character_creation;
\ No newline at end of file
+pub use renderer::render_character_creation;
+pub use kits::{StartingKit, KitItem, KitPreview, STARTING_AMMO};
+pub use backgrounds::grant_background;
\ No newline at end of file
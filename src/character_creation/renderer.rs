@@ -1,8 +1,8 @@
 use crossterm::style::Color;
 use crate::game_state::{RunState, GameState};
 use crate::rendering::terminal::with_terminal;
-use crate::components::AttributeType;
-use super::CharacterCreationState;
+use crate::components::{AttributeType, GameMode};
+use super::{CharacterCreationState, AttributeMethod, starting_item_name};
 
 pub fn render_character_creation(game_state: &GameState, creation_state: &CharacterCreationState) {
     match game_state.run_state {
@@ -48,7 +48,7 @@ fn render_name_screen(creation_state: &CharacterCreationState) {
         )?;
         
         // Draw instructions
-        terminal.draw_text_centered(center_y + 5, "Press Enter to continue, Esc to return to main menu", Color::Grey, Color::Black)?;
+        terminal.draw_text_centered(center_y + 5, "Press Enter to continue, Tab to suggest a name, Esc to return to main menu", Color::Grey, Color::Black)?;
         
         terminal.flush()
     });
@@ -79,8 +79,13 @@ fn render_class_screen(creation_state: &CharacterCreationState) {
             (crate::components::ClassType::Ranger, "5", "Ranger - A skilled hunter with high dexterity and wisdom"),
         ];
         
+        // Classes not yet earned are greyed out, with how to earn them
         for (i, (class_type, key, desc)) in classes.iter().enumerate() {
             let y_pos = center_y - 10 + i as u16 * 2;
+            if let Some(hint) = creation_state.class_lock(*class_type) {
+                terminal.draw_text(center_x - 30, y_pos, &format!("{} - {} - Locked: {}", key, class_type.name(), hint), Color::DarkGrey, Color::Black)?;
+                continue;
+            }
             let color = if *class_type == creation_state.selected_class { Color::Yellow } else { Color::White };
             terminal.draw_text(center_x - 30, y_pos, &format!("{} - {}", key, desc), color, Color::Black)?;
         }
@@ -132,6 +137,10 @@ fn render_background_screen(creation_state: &CharacterCreationState) {
         
         for (i, (bg_type, key, desc)) in backgrounds.iter().enumerate() {
             let y_pos = center_y - 10 + i as u16 * 2;
+            if let Some(hint) = creation_state.background_lock(*bg_type) {
+                terminal.draw_text(center_x - 30, y_pos, &format!("{} - {} - Locked: {}", key, bg_type.name(), hint), Color::DarkGrey, Color::Black)?;
+                continue;
+            }
             let color = if *bg_type == creation_state.selected_background { Color::Yellow } else { Color::White };
             terminal.draw_text(center_x - 30, y_pos, &format!("{} - {}", key, desc), color, Color::Black)?;
         }
@@ -145,6 +154,17 @@ fn render_background_screen(creation_state: &CharacterCreationState) {
         let bonus_attr = creation_state.selected_background.attribute_bonus();
         terminal.draw_text_centered(desc_y + 4, &format!("Attribute Bonus: {:?} (+1)", bonus_attr), Color::Green, Color::Black)?;
         
+        // Draw starting skills, perk and town contact
+        let background = creation_state.selected_background;
+        let skills: Vec<String> = background.starting_skills().iter()
+            .map(|(skill, level)| format!("{} {}", skill.name(), level))
+            .collect();
+        terminal.draw_text_centered(desc_y + 5, &format!("Starting Skills: {}", skills.join(", ")), Color::Green, Color::Black)?;
+        let perk = background.perk();
+        terminal.draw_text_centered(desc_y + 6, &format!("Perk: {} - {}", perk.name(), perk.description()), Color::Cyan, Color::Black)?;
+        let contact = background.contact();
+        terminal.draw_text_centered(desc_y + 7, &format!("Contact in town: {}, {}", contact.name, contact.role), Color::Grey, Color::Black)?;
+        
         // Draw instructions
         terminal.draw_text_centered(height - 3, "Press Enter to continue, Esc to go back", Color::Grey, Color::Black)?;
         
@@ -168,8 +188,15 @@ fn render_attributes_screen(creation_state: &CharacterCreationState) {
         terminal.draw_text_centered(center_y - 15, "CHARACTER CREATION", Color::Yellow, Color::Black)?;
         terminal.draw_text_centered(center_y - 13, "Allocate your attributes", Color::White, Color::Black)?;
         
-        // Draw attribute allocation instructions
-        terminal.draw_text_centered(center_y - 11, &format!("Unspent Points: {}", creation_state.attributes.unspent_points), Color::Green, Color::Black)?;
+        // Draw the generation method and what it lets the player do
+        let method = creation_state.attribute_method;
+        terminal.draw_text_centered(center_y - 12, &format!("Method: {} (m to change) - {}", method.name(), method.description()), Color::Cyan, Color::Black)?;
+        let method_status = match method {
+            AttributeMethod::PointBuy => format!("Unspent Points: {}", creation_state.attributes.unspent_points),
+            AttributeMethod::StandardArray => "Left/right trades the selected score with its neighbour".to_string(),
+            AttributeMethod::Roll => format!("Rerolls left: {} (r to reroll)", creation_state.rerolls_remaining),
+        };
+        terminal.draw_text_centered(center_y - 11, &method_status, Color::Green, Color::Black)?;
         terminal.draw_text_centered(center_y - 10, "Use arrow keys or hjkl to navigate, left/right to adjust", Color::Grey, Color::Black)?;
         
         // Draw attributes
@@ -254,53 +281,41 @@ fn render_equipment_screen(creation_state: &CharacterCreationState) {
         
         // Draw title
         terminal.draw_text_centered(center_y - 15, "CHARACTER CREATION", Color::Yellow, Color::Black)?;
-        terminal.draw_text_centered(center_y - 13, "Choose your starting equipment", Color::White, Color::Black)?;
-        
-        // Draw equipment selection instructions
-        terminal.draw_text_centered(center_y - 11, "Select up to 3 items (Space to select, Enter to continue)", Color::Grey, Color::Black)?;
-        terminal.draw_text_centered(center_y - 10, &format!("Selected: {}/3", creation_state.selected_equipment_indices.len()), Color::Green, Color::Black)?;
+        terminal.draw_text_centered(center_y - 13, &format!("Choose your starting kit as a {}", creation_state.selected_class.name()), Color::White, Color::Black)?;
         
-        // Draw equipment options
-        for (i, (name, slot)) in creation_state.available_equipment.iter().enumerate() {
-            let y_pos = center_y - 7 + i as u16;
-            let is_selected = creation_state.selected_equipment_indices.contains(&i);
-            let is_current = i == creation_state.selected_equipment;
-            
+        // Draw each kit with what it adds once worn
+        for (i, kit) in creation_state.kits.iter().enumerate() {
+            let y_pos = center_y - 10 + i as u16 * 3;
+            let is_current = i == creation_state.selected_kit;
             let color = if is_current { Color::Yellow } else { Color::White };
-            let prefix = if is_selected { "[X] " } else { "[ ] " };
+            let preview = kit.preview();
             
-            terminal.draw_text(center_x - 20, y_pos, &format!("{}{} ({})", prefix, name, format!("{:?}", slot)), color, Color::Black)?;
+            terminal.draw_text(center_x - 30, y_pos, &format!("{} - {}", i + 1, kit.name), color, Color::Black)?;
+            terminal.draw_text(
+                center_x + 2,
+                y_pos,
+                &format!("ATK +{}  DMG +{}  DEF +{}  {:.1} lb", preview.attack, preview.damage, preview.defense, preview.weight),
+                Color::Green,
+                Color::Black,
+            )?;
+            let items: Vec<String> = kit.items.iter().map(|item| item.name()).collect();
+            terminal.draw_text(center_x - 26, y_pos + 1, &items.join(", "), Color::Grey, Color::Black)?;
             
             // Draw selection indicator
             if is_current {
-                terminal.draw_text(center_x - 22, y_pos, "→", Color::Yellow, Color::Black)?;
+                terminal.draw_text(center_x - 32, y_pos, "→", Color::Yellow, Color::Black)?;
             }
         }
         
-        // Draw equipment description
-        if creation_state.selected_equipment < creation_state.available_equipment.len() {
-            let (name, slot) = &creation_state.available_equipment[creation_state.selected_equipment];
-            let desc_y = center_y + 5;
-            
-            terminal.draw_text_centered(desc_y, name, Color::Yellow, Color::Black)?;
-            
-            let desc = match slot {
-                crate::components::EquipmentSlot::Melee => "A melee weapon for close combat. Increases attack power.",
-                crate::components::EquipmentSlot::Ranged => "A ranged weapon for attacking from a distance.",
-                crate::components::EquipmentSlot::Shield => "A shield for protection. Increases defense.",
-                crate::components::EquipmentSlot::Armor => "Body armor for protection. Significantly increases defense.",
-                crate::components::EquipmentSlot::Helmet => "Head protection. Increases defense.",
-                crate::components::EquipmentSlot::Boots => "Footwear for protection and mobility. Increases defense.",
-                crate::components::EquipmentSlot::Gloves => "Hand protection. Increases attack power slightly.",
-                crate::components::EquipmentSlot::Ring => "A magical ring. Provides minor bonuses to defense.",
-                crate::components::EquipmentSlot::Amulet => "A magical amulet. Provides minor bonuses to attack and defense.",
-            };
-            
-            terminal.draw_text_centered(desc_y + 1, desc, Color::White, Color::Black)?;
+        // Draw the selected kit's description
+        if let Some(kit) = creation_state.selected_kit() {
+            let desc_y = center_y + 2;
+            terminal.draw_text_centered(desc_y, kit.name, Color::Yellow, Color::Black)?;
+            terminal.draw_text_centered(desc_y + 1, kit.description, Color::White, Color::Black)?;
         }
         
         // Draw instructions
-        terminal.draw_text_centered(height - 3, "Press Enter to continue, Esc to go back", Color::Grey, Color::Black)?;
+        terminal.draw_text_centered(height - 3, "Up/Down or a number to choose, Enter to continue, Esc to go back", Color::Grey, Color::Black)?;
         
         terminal.flush()
     });
@@ -325,7 +340,7 @@ fn render_confirm_screen(creation_state: &CharacterCreationState) {
         // Draw character summary
         terminal.draw_text(center_x - 30, center_y - 10, &format!("Name: {}", creation_state.player_name), Color::White, Color::Black)?;
         terminal.draw_text(center_x - 30, center_y - 8, &format!("Class: {}", creation_state.selected_class.name()), Color::White, Color::Black)?;
-        terminal.draw_text(center_x - 30, center_y - 6, &format!("Background: {}", creation_state.selected_background.name()), Color::White, Color::Black)?;
+        terminal.draw_text(center_x - 30, center_y - 6, &format!("Background: {} ({})", creation_state.selected_background.name(), creation_state.selected_background.perk().name()), Color::White, Color::Black)?;
         
         // Draw attributes
         terminal.draw_text(center_x - 30, center_y - 4, "Attributes:", Color::White, Color::Black)?;
@@ -336,16 +351,41 @@ fn render_confirm_screen(creation_state: &CharacterCreationState) {
         terminal.draw_text(center_x - 10, center_y, &format!("WIS: {}", creation_state.attributes.wisdom), Color::White, Color::Black)?;
         terminal.draw_text(center_x - 10, center_y + 2, &format!("CHA: {}", creation_state.attributes.charisma), Color::White, Color::Black)?;
         
-        // Draw equipment
-        terminal.draw_text(center_x + 5, center_y - 4, "Equipment:", Color::White, Color::Black)?;
-        for (i, &idx) in creation_state.selected_equipment_indices.iter().enumerate() {
-            let (name, _) = &creation_state.available_equipment[idx];
-            terminal.draw_text(center_x + 10, center_y - 2 + i as u16 * 2, name, Color::White, Color::Black)?;
+        // Draw the starting kit
+        if let Some(kit) = creation_state.selected_kit() {
+            terminal.draw_text(center_x + 5, center_y - 4, &format!("Kit: {}", kit.name), Color::White, Color::Black)?;
+            for (i, item) in kit.items.iter().enumerate() {
+                terminal.draw_text(center_x + 10, center_y - 2 + i as u16 * 2, &item.name(), Color::White, Color::Black)?;
+            }
+        }
+        
+        // Draw what achievements have unlocked: the glyph's color and any extra starting items
+        terminal.draw_text(center_x + 5, center_y - 10, "Glyph:", Color::White, Color::Black)?;
+        terminal.draw_text(center_x + 12, center_y - 10, "@", creation_state.glyph_color(), Color::Black)?;
+        if creation_state.glyph_colors.len() > 1 {
+            terminal.draw_text(center_x + 14, center_y - 10, "(c to change)", Color::Grey, Color::Black)?;
+        }
+        if !creation_state.starting_items.is_empty() {
+            let items: Vec<&str> = creation_state.starting_items.iter().filter_map(|key| starting_item_name(key)).collect();
+            terminal.draw_text(center_x + 5, center_y - 8, &format!("Also carrying: {}", items.join(", ")), Color::Green, Color::Black)?;
+        }
+        
+        // Draw the game mode, warning plainly about what ironman modes take away
+        let mode = &creation_state.game_mode;
+        terminal.draw_text_centered(center_y + 5, &format!("Game Mode: {} (g to change) - {}", mode.name(), mode.description()), Color::Cyan, Color::Black)?;
+        if mode.is_ironman() {
+            terminal.draw_text_centered(center_y + 6, "IRONMAN: one save, written automatically; you can only save by quitting", Color::Red, Color::Black)?;
+            let loss = if *mode == GameMode::Permadeath {
+                "Death is final and deletes this character's save"
+            } else {
+                "When your last revival is spent, death deletes this character's save"
+            };
+            terminal.draw_text_centered(center_y + 7, loss, Color::Red, Color::Black)?;
         }
         
         // Draw confirmation prompt
-        terminal.draw_text_centered(center_y + 8, "Are you ready to begin your adventure?", Color::Yellow, Color::Black)?;
-        terminal.draw_text_centered(center_y + 10, "Press Y or Enter to confirm, N or Esc to go back", Color::Grey, Color::Black)?;
+        terminal.draw_text_centered(center_y + 9, "Are you ready to begin your adventure?", Color::Yellow, Color::Black)?;
+        terminal.draw_text_centered(center_y + 11, "Press Y or Enter to confirm, G to change game mode, C to change glyph color, N or Esc to go back", Color::Grey, Color::Black)?;
         
         terminal.flush()
     });
@@ -1,7 +1,78 @@
 use specs::{World, WorldExt, Entity, Builder};
 use crate::components::*;
-use crate::resources::GameLog;
+use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::items::{AdvancedInventory, ConsumableFactory, Equipment, EquipmentFactory, EquipmentQuality, ItemProperties, PotionPotency};
+use crate::progression::{
+    Bank, UnlockableContentSystem, UnlockableContent, UNLOCKS_CLASS, UNLOCKS_BACKGROUND,
+    UNLOCKS_GLYPH_COLOR, UNLOCKS_STARTING_ITEM
+};
+use crate::utils::{NameGenerator, NameStyle};
 use crossterm::style::Color;
+use super::{StartingKit, grant_background};
+
+/// Scores handed out by the standard array, best first
+pub const STANDARD_ARRAY: [i32; 6] = [15, 14, 13, 12, 10, 8];
+/// How many times a rolled character may be rerolled
+pub const MAX_REROLLS: i32 = 3;
+
+/// The color a glyph color unlock names, if it is one the game knows
+pub fn glyph_color_named(name: &str) -> Option<Color> {
+    match name {
+        "white" => Some(Color::White),
+        "red" => Some(Color::Red),
+        "yellow" => Some(Color::Yellow),
+        _ => None,
+    }
+}
+
+/// What a starting item unlock names, as the player sees it
+pub fn starting_item_name(key: &str) -> Option<&'static str> {
+    match key {
+        "health_potion" => Some("Health Potion"),
+        "phoenix_feather" => Some("Phoenix Feather"),
+        "armory_piece" => Some("Gear from your armory"),
+        _ => None,
+    }
+}
+
+/// How to unlock a piece of content, for showing beside a locked option
+fn unlock_hint(content: &UnlockableContent) -> String {
+    content.hint.clone().unwrap_or_else(|| content.description.clone())
+}
+
+/// How starting attribute scores are generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeMethod {
+    PointBuy,
+    StandardArray,
+    Roll,
+}
+
+impl AttributeMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AttributeMethod::PointBuy => "Point Buy",
+            AttributeMethod::StandardArray => "Standard Array",
+            AttributeMethod::Roll => "Roll 3d6",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            AttributeMethod::PointBuy => "Spend points to raise attributes from 8",
+            AttributeMethod::StandardArray => "Arrange 15, 14, 13, 12, 10 and 8 between attributes",
+            AttributeMethod::Roll => "Roll 3d6 for each attribute in order",
+        }
+    }
+
+    pub fn next(&self) -> AttributeMethod {
+        match self {
+            AttributeMethod::PointBuy => AttributeMethod::StandardArray,
+            AttributeMethod::StandardArray => AttributeMethod::Roll,
+            AttributeMethod::Roll => AttributeMethod::PointBuy,
+        }
+    }
+}
 
 pub struct CharacterCreationState {
     pub player_name: String,
@@ -9,9 +80,22 @@ pub struct CharacterCreationState {
     pub selected_background: BackgroundType,
     pub attributes: Attributes,
     pub selected_attribute: AttributeType,
-    pub selected_equipment: usize,
-    pub available_equipment: Vec<(String, EquipmentSlot)>,
-    pub selected_equipment_indices: Vec<usize>,
+    pub attribute_method: AttributeMethod,
+    pub rerolls_remaining: i32,
+    /// The starting kits the selected class chooses between
+    pub kits: Vec<StartingKit>,
+    pub selected_kit: usize,
+    /// How forgiving death is, picked on the confirmation screen
+    pub game_mode: GameMode,
+    /// Classes still locked behind an achievement, with how to unlock them
+    pub locked_classes: Vec<(ClassType, String)>,
+    /// Backgrounds still locked behind an achievement, with how to unlock them
+    pub locked_backgrounds: Vec<(BackgroundType, String)>,
+    /// Colors the player's glyph may be drawn in, white first
+    pub glyph_colors: Vec<(String, Color)>,
+    pub selected_glyph_color: usize,
+    /// Unlocked items every new character carries from the start
+    pub starting_items: Vec<String>,
 }
 
 impl CharacterCreationState {
@@ -22,22 +106,101 @@ impl CharacterCreationState {
             selected_background: BackgroundType::Soldier,
             attributes: Attributes::new(),
             selected_attribute: AttributeType::Strength,
-            selected_equipment: 0,
-            available_equipment: vec![
-                ("Sword".to_string(), EquipmentSlot::Melee),
-                ("Bow".to_string(), EquipmentSlot::Ranged),
-                ("Shield".to_string(), EquipmentSlot::Shield),
-                ("Leather Armor".to_string(), EquipmentSlot::Armor),
-                ("Helmet".to_string(), EquipmentSlot::Helmet),
-                ("Boots".to_string(), EquipmentSlot::Boots),
-                ("Gloves".to_string(), EquipmentSlot::Gloves),
-                ("Ring of Protection".to_string(), EquipmentSlot::Ring),
-                ("Amulet of Health".to_string(), EquipmentSlot::Amulet),
-            ],
-            selected_equipment_indices: Vec::new(),
+            attribute_method: AttributeMethod::PointBuy,
+            rerolls_remaining: MAX_REROLLS,
+            kits: StartingKit::for_class(ClassType::Fighter),
+            selected_kit: 0,
+            game_mode: GameMode::Normal,
+            locked_classes: Vec::new(),
+            locked_backgrounds: Vec::new(),
+            glyph_colors: vec![("white".to_string(), Color::White)],
+            selected_glyph_color: 0,
+            starting_items: Vec::new(),
         }
     }
     
+    /// Lock the classes and backgrounds not yet earned, and offer the colors and items that are
+    pub fn apply_unlocks(&mut self, content: &UnlockableContentSystem) {
+        let classes = [ClassType::Fighter, ClassType::Rogue, ClassType::Mage, ClassType::Cleric, ClassType::Ranger];
+        self.locked_classes = classes.iter()
+            .filter_map(|class| content.locked_by(UNLOCKS_CLASS, class.name()).map(|c| (*class, unlock_hint(c))))
+            .collect();
+        
+        let backgrounds = [
+            BackgroundType::Soldier, BackgroundType::Scholar, BackgroundType::Noble,
+            BackgroundType::Outlaw, BackgroundType::Acolyte, BackgroundType::Merchant,
+        ];
+        self.locked_backgrounds = backgrounds.iter()
+            .filter_map(|background| content.locked_by(UNLOCKS_BACKGROUND, background.name()).map(|c| (*background, unlock_hint(c))))
+            .collect();
+        
+        self.glyph_colors = vec![("white".to_string(), Color::White)];
+        for name in content.unlocked_values(UNLOCKS_GLYPH_COLOR) {
+            if let Some(color) = glyph_color_named(&name) {
+                self.glyph_colors.push((name, color));
+            }
+        }
+        self.selected_glyph_color = 0;
+        
+        self.starting_items = content.unlocked_values(UNLOCKS_STARTING_ITEM).into_iter()
+            .filter(|key| starting_item_name(key).is_some())
+            .collect();
+    }
+    
+    /// Add what the property bought through the bank puts in every new character's pack
+    pub fn apply_estate(&mut self, bank: &Bank) {
+        self.starting_items.extend(bank.starting_items().into_iter().map(str::to_string));
+    }
+    
+    /// How to unlock a class, if it is still locked
+    pub fn class_lock(&self, class: ClassType) -> Option<&str> {
+        self.locked_classes.iter().find(|(locked, _)| *locked == class).map(|(_, hint)| hint.as_str())
+    }
+    
+    /// How to unlock a background, if it is still locked
+    pub fn background_lock(&self, background: BackgroundType) -> Option<&str> {
+        self.locked_backgrounds.iter().find(|(locked, _)| *locked == background).map(|(_, hint)| hint.as_str())
+    }
+    
+    /// Choose a class, unless it is still locked
+    pub fn select_class(&mut self, class: ClassType) -> bool {
+        if self.class_lock(class).is_some() {
+            return false;
+        }
+        self.selected_class = class;
+        self.kits = StartingKit::for_class(class);
+        self.selected_kit = 0;
+        true
+    }
+    
+    pub fn selected_kit(&self) -> Option<&StartingKit> {
+        self.kits.get(self.selected_kit)
+    }
+    
+    /// Move the kit selection up or down the list, wrapping round
+    pub fn cycle_kit(&mut self, forward: bool) {
+        let count = self.kits.len().max(1);
+        self.selected_kit = if forward { (self.selected_kit + 1) % count } else { (self.selected_kit + count - 1) % count };
+    }
+    
+    /// Choose a background, unless it is still locked
+    pub fn select_background(&mut self, background: BackgroundType) -> bool {
+        if self.background_lock(background).is_some() {
+            return false;
+        }
+        self.selected_background = background;
+        true
+    }
+    
+    /// Switch to the next unlocked glyph color
+    pub fn cycle_glyph_color(&mut self) {
+        self.selected_glyph_color = (self.selected_glyph_color + 1) % self.glyph_colors.len();
+    }
+    
+    pub fn glyph_color(&self) -> Color {
+        self.glyph_colors.get(self.selected_glyph_color).map_or(Color::White, |(_, color)| *color)
+    }
+    
     pub fn apply_class_bonuses(&mut self) {
         // Apply +2 to primary attribute and +1 to secondary attribute
         match self.selected_class.primary_attribute() {
@@ -71,6 +234,85 @@ impl CharacterCreationState {
         }
     }
     
+    /// Regenerate the attributes with the current method, then apply class and background bonuses
+    pub fn reset_attributes(&mut self, rng: &mut RandomNumberGenerator) {
+        self.attributes = match self.attribute_method {
+            AttributeMethod::PointBuy => Attributes::new(),
+            AttributeMethod::StandardArray => Attributes::from_scores(STANDARD_ARRAY),
+            AttributeMethod::Roll => Self::roll_attributes(rng),
+        };
+        self.apply_class_bonuses();
+        self.apply_background_bonuses();
+    }
+    
+    /// Switch to the next generation method, starting from fresh attributes
+    pub fn cycle_attribute_method(&mut self, rng: &mut RandomNumberGenerator) {
+        self.attribute_method = self.attribute_method.next();
+        self.rerolls_remaining = MAX_REROLLS;
+        self.reset_attributes(rng);
+    }
+    
+    /// Roll a new set of scores, if any rerolls are left
+    pub fn reroll(&mut self, rng: &mut RandomNumberGenerator) -> bool {
+        if self.attribute_method != AttributeMethod::Roll || self.rerolls_remaining <= 0 {
+            return false;
+        }
+        self.rerolls_remaining -= 1;
+        self.reset_attributes(rng);
+        true
+    }
+    
+    /// With the standard array, trade the selected attribute's score with a neighbour's
+    pub fn swap_attribute(&mut self, forward: bool) -> bool {
+        if self.attribute_method != AttributeMethod::StandardArray {
+            return false;
+        }
+        let order = AttributeType::all();
+        let idx = order.iter().position(|attr| *attr == self.selected_attribute).unwrap_or(0);
+        let other = if forward { order[(idx + 1) % order.len()] } else { order[(idx + order.len() - 1) % order.len()] };
+        
+        let current = self.attributes.get_attribute(self.selected_attribute);
+        let neighbour = self.attributes.get_attribute(other);
+        self.attributes.set_attribute(self.selected_attribute, neighbour);
+        self.attributes.set_attribute(other, current);
+        self.selected_attribute = other;
+        true
+    }
+    
+    fn roll_attributes(rng: &mut RandomNumberGenerator) -> Attributes {
+        let mut scores = [0; 6];
+        for score in scores.iter_mut() {
+            *score = rng.roll_dice(3, 6);
+        }
+        Attributes::from_scores(scores)
+    }
+    
+    /// Replace the current name with a generated one
+    pub fn suggest_name(&mut self, rng: &mut RandomNumberGenerator) {
+        self.player_name = NameGenerator::personal_name(NameStyle::Human, rng);
+    }
+    
+    /// Pick a name, class, background, rolled attributes and starting kit in one go
+    pub fn randomize(&mut self, rng: &mut RandomNumberGenerator) {
+        let classes = [ClassType::Fighter, ClassType::Rogue, ClassType::Mage, ClassType::Cleric, ClassType::Ranger];
+        let backgrounds = [
+            BackgroundType::Soldier, BackgroundType::Scholar, BackgroundType::Noble,
+            BackgroundType::Outlaw, BackgroundType::Acolyte, BackgroundType::Merchant,
+        ];
+        
+        let classes: Vec<ClassType> = classes.iter().copied().filter(|class| self.class_lock(*class).is_none()).collect();
+        let backgrounds: Vec<BackgroundType> = backgrounds.iter().copied().filter(|background| self.background_lock(*background).is_none()).collect();
+        
+        self.suggest_name(rng);
+        self.select_class(classes[rng.range(0, classes.len() as i32 - 1) as usize]);
+        self.selected_background = backgrounds[rng.range(0, backgrounds.len() as i32 - 1) as usize];
+        self.attribute_method = AttributeMethod::Roll;
+        self.rerolls_remaining = 0;
+        self.reset_attributes(rng);
+        
+        self.selected_kit = rng.range(0, self.kits.len() as i32 - 1) as usize;
+    }
+    
     pub fn create_player(&self, world: &mut World, x: i32, y: i32) -> Entity {
         // Calculate HP based on class and constitution modifier
         let con_modifier = self.attributes.get_modifier(AttributeType::Constitution);
@@ -78,11 +320,13 @@ impl CharacterCreationState {
         let max_hp = base_hp + con_modifier;
         
         // Create the player entity with all components
+        let abilities = Abilities::starting(self.selected_class);
+        let hotbar = Hotbar::for_class(self.selected_class, &abilities);
         let player = world.create_entity()
             .with(Position { x, y })
             .with(Renderable {
                 glyph: '@',
-                fg: Color::White,
+                fg: self.glyph_color(),
                 bg: Color::Black,
                 render_order: 0,
             })
@@ -103,18 +347,29 @@ impl CharacterCreationState {
             })
             .with(PlayerInput::new())
             .with(Inventory::new(26))
+            .with(AdvancedInventory::new(26, 150.0))
+            .with(Equipment::new())
             .with(Experience::new())
             .with(self.attributes.clone())
             .with(CharacterClass { class_type: self.selected_class })
             .with(Background { background_type: self.selected_background })
-            .with(Skills::new())
-            .with(Abilities::new())
+            .with(Skills::for_background(self.selected_background))
+            .with(abilities)
+            .with(hotbar)
             .build();
         
-        // Add selected equipment to inventory
-        for &equipment_idx in &self.selected_equipment_indices {
-            let (name, slot) = &self.available_equipment[equipment_idx];
-            self.create_equipment(world, player, name, *slot);
+        // What the background brings: its perk and someone in town who remembers them
+        grant_background(world, player, self.selected_background);
+        
+        // Wear the chosen kit
+        if let Some(kit) = self.selected_kit() {
+            let mut rng = world.read_resource::<RandomNumberGenerator>().fork("starting_kit");
+            kit.give_to(world, player, &mut rng);
+        }
+        
+        // And whatever achievements have added to every starting pack
+        for key in &self.starting_items {
+            self.create_starting_item(world, player, key);
         }
         
         // Add a welcome message
@@ -124,66 +379,94 @@ impl CharacterCreationState {
         player
     }
     
-    fn create_equipment(&self, world: &mut World, owner: Entity, name: &str, slot: EquipmentSlot) -> Entity {
-        // Create equipment with appropriate bonuses based on type
-        let (power_bonus, defense_bonus) = match slot {
-            EquipmentSlot::Melee => (2, 0),
-            EquipmentSlot::Ranged => (1, 0),
-            EquipmentSlot::Shield => (0, 2),
-            EquipmentSlot::Armor => (0, 3),
-            EquipmentSlot::Helmet => (0, 1),
-            EquipmentSlot::Boots => (0, 1),
-            EquipmentSlot::Gloves => (1, 0),
-            EquipmentSlot::Ring => (0, 1),
-            EquipmentSlot::Amulet => (1, 1),
+    fn create_starting_item(&self, world: &mut World, owner: Entity, key: &str) {
+        let factory = ConsumableFactory::new();
+        let position = Position { x: 0, y: 0 };
+        let item = match key {
+            "health_potion" => factory.create_health_potion(world, position, PotionPotency::Lesser),
+            "phoenix_feather" => factory.create_phoenix_feather(world, position),
+            "armory_piece" => {
+                let mut rng = world.read_resource::<RandomNumberGenerator>().fork("armory");
+                EquipmentFactory::new().create_random_equipment(world, position, EquipmentQuality::Common, &mut rng)
+            },
+            _ => return,
         };
         
-        let glyph = match slot {
-            EquipmentSlot::Melee => '/',
-            EquipmentSlot::Ranged => '}',
-            EquipmentSlot::Shield => '(',
-            EquipmentSlot::Armor => '[',
-            EquipmentSlot::Helmet => '^',
-            EquipmentSlot::Boots => 'b',
-            EquipmentSlot::Gloves => 'g',
-            EquipmentSlot::Ring => '=',
-            EquipmentSlot::Amulet => '"',
-        };
-        
-        let mut item_builder = world.create_entity()
-            .with(Item {})
-            .with(Name { name: name.to_string() })
-            .with(Renderable {
-                glyph,
-                fg: Color::Cyan,
-                bg: Color::Black,
-                render_order: 2,
-            })
-            .with(Equippable { slot });
-        
-        if power_bonus > 0 {
-            item_builder = item_builder.with(MeleePowerBonus { power: power_bonus });
+        // Carried, not lying on the floor
+        world.write_storage::<Position>().remove(item);
+        let weight = world.read_storage::<ItemProperties>().get(item).map_or(0.0, |props| props.weight);
+        if let Some(inventory) = world.write_storage::<AdvancedInventory>().get_mut(owner) {
+            inventory.add_item(item, 1, weight);
         }
-        
-        if defense_bonus > 0 {
-            item_builder = item_builder.with(DefenseBonus { defense: defense_bonus });
-        }
-        
-        // Add to inventory and equip it
-        let item = item_builder.build();
-        
-        {
-            let mut inventory = world.write_storage::<Inventory>();
-            if let Some(inv) = inventory.get_mut(owner) {
-                inv.items.push(item);
-            }
-        }
-        
-        {
-            let mut equipped = world.write_storage::<Equipped>();
-            equipped.insert(item, Equipped { owner, slot }).expect("Failed to equip item");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(attributes: &Attributes) -> i32 {
+        AttributeType::all().iter().map(|attr| attributes.get_attribute(*attr)).sum()
+    }
+
+    #[test]
+    fn test_standard_array_swaps_keep_the_same_scores() {
+        let mut rng = RandomNumberGenerator::new(1);
+        let mut state = CharacterCreationState::new();
+        state.attribute_method = AttributeMethod::StandardArray;
+        state.reset_attributes(&mut rng);
+        let before = total(&state.attributes);
+
+        assert!(state.swap_attribute(true));
+        assert_eq!(state.selected_attribute, AttributeType::Dexterity);
+        assert_eq!(total(&state.attributes), before);
+        assert_eq!(state.attributes.unspent_points, 0);
+    }
+
+    #[test]
+    fn test_rerolls_are_limited() {
+        let mut rng = RandomNumberGenerator::new(2);
+        let mut state = CharacterCreationState::new();
+        state.cycle_attribute_method(&mut rng);
+        assert!(!state.reroll(&mut rng));
+        state.cycle_attribute_method(&mut rng);
+        assert_eq!(state.attribute_method, AttributeMethod::Roll);
+
+        for _ in 0..MAX_REROLLS {
+            assert!(state.reroll(&mut rng));
         }
-        
-        item
+        assert!(!state.reroll(&mut rng));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_locked_classes_and_backgrounds_cannot_be_chosen() {
+        let mut content = UnlockableContentSystem::new();
+        let mut state = CharacterCreationState::new();
+        state.apply_unlocks(&content);
+
+        assert!(state.class_lock(ClassType::Ranger).is_some());
+        assert!(!state.select_class(ClassType::Ranger));
+        assert!(!state.select_background(BackgroundType::Merchant));
+        assert!(state.select_class(ClassType::Mage));
+        assert_eq!(state.kits, StartingKit::for_class(ClassType::Mage));
+        assert_eq!(state.glyph_colors.len(), 1);
+
+        content.unlock_content("class_ranger", "test");
+        content.unlock_content("glyph_gold", "test");
+        state.apply_unlocks(&content);
+        assert!(state.select_class(ClassType::Ranger));
+        state.cycle_glyph_color();
+        assert_eq!(state.glyph_color(), Color::Yellow);
+    }
+
+    #[test]
+    fn test_random_character_is_complete() {
+        let mut rng = RandomNumberGenerator::new(3);
+        let mut state = CharacterCreationState::new();
+        state.randomize(&mut rng);
+
+        assert!(!state.player_name.is_empty());
+        assert_eq!(state.selected_kit().map(|kit| kit.items.is_empty()), Some(false));
+        assert_eq!(state.attributes.unspent_points, 0);
+    }
+}
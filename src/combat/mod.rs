@@ -1,2 +1,6 @@
 // Combat system will be implemented here
-// This will include damage calculation, combat resolution, and combat effects
\ No newline at end of file
+// This will include damage calculation, combat resolution, and combat effects
+
+mod preview;
+
+pub use preview::{CombatPreview, hit_chance, preview_shot, preview_area_ability};
@@ -0,0 +1,119 @@
+use specs::{World, WorldExt, Entity};
+use crate::components::{
+    AbilityType, Attacker, AttributeType, Attributes, CombatStats, DamageResistances, Defender, Skills, SkillType, Undead
+};
+use crate::items::Ammunition;
+use crate::systems::{attack_hits, ability_damage};
+
+/// What the player can expect from an attack before committing to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CombatPreview {
+    /// Percent chance the attack lands
+    pub hit_chance: i32,
+    pub min_damage: i32,
+    pub max_damage: i32,
+    /// Damage of a confirmed critical, when the attack can crit
+    pub critical_damage: Option<i32>,
+}
+
+impl CombatPreview {
+    /// Short line for the targeting status bar, e.g. "Hit 65%  Dmg 4-6 (crit 12)"
+    pub fn summary(&self) -> String {
+        let damage = if self.min_damage == self.max_damage {
+            self.min_damage.to_string()
+        } else {
+            format!("{}-{}", self.min_damage, self.max_damage)
+        };
+        match self.critical_damage {
+            Some(critical) => format!("Hit {}%  Dmg {} (crit {})", self.hit_chance, damage, critical),
+            None => format!("Hit {}%  Dmg {}", self.hit_chance, damage),
+        }
+    }
+}
+
+/// Percent chance a d20 attack roll with this bonus beats the defense, natural 1s and 20s included
+pub fn hit_chance(attack_bonus: i32, defense: i32) -> i32 {
+    let hits = (1..=20).filter(|roll| attack_hits(*roll, roll + attack_bonus, defense)).count() as i32;
+    hits * 5
+}
+
+/// Expected outcome of shooting `ammo` at `target`, mirroring the ranged combat system
+pub fn preview_shot(world: &World, shooter: Entity, target: Entity, ammo: Entity) -> Option<CombatPreview> {
+    let combat_stats = world.read_storage::<CombatStats>();
+    let attributes = world.read_storage::<Attributes>();
+    let skills = world.read_storage::<Skills>();
+    let attackers = world.read_storage::<Attacker>();
+    let defenders = world.read_storage::<Defender>();
+    let ammunition = world.read_storage::<Ammunition>();
+
+    let shooter_stats = combat_stats.get(shooter)?;
+    let ammo = ammunition.get(ammo)?;
+
+    let dex_modifier = attributes.get(shooter).map_or(0, |attrs| attrs.get_modifier(AttributeType::Dexterity));
+    let skill_level = skills.get(shooter).map_or(0, |skills| skills.get_skill_level(SkillType::RangedWeapons));
+    let attack_bonus = dex_modifier
+        + skill_level
+        + shooter_stats.power / 2
+        + attackers.get(shooter).map_or(0, |a| a.attack_bonus);
+    let defense = defenders.get(target).map_or(10, |d| d.armor_class)
+        + attributes.get(target).map_or(0, |attrs| attrs.get_modifier(AttributeType::Dexterity))
+        + combat_stats.get(target).map_or(0, |stats| stats.defense);
+
+    let base = shooter_stats.power + dex_modifier + skill_level / 2;
+    let damage = ammo.hit_damage(base, world.read_storage::<Undead>().contains(target));
+    let multiplier = attackers.get(shooter).map_or(2.0, |a| a.critical_multiplier);
+    let reduction = defenders.get(target)
+        .map_or(0, |d| d.effective_damage_reduction(attackers.get(shooter).map_or(0, |a| a.armor_penetration)));
+    let resistances = world.read_storage::<DamageResistances>();
+    let damage_type = ammo.material.damage_type();
+    let land = |raw: i32| {
+        let reduced = i32::max(1, raw - reduction);
+        resistances.get(target).map_or(reduced, |resist| resist.calculate_damage(reduced, damage_type))
+    };
+
+    let normal = land(damage);
+    let critical = attackers.get(shooter).map(|_| land((damage as f32 * multiplier) as i32));
+    Some(CombatPreview {
+        hit_chance: hit_chance(attack_bonus, defense),
+        min_damage: normal,
+        max_damage: normal,
+        critical_damage: critical,
+    })
+}
+
+/// Expected damage of an area ability on one creature it catches; areas never miss
+pub fn preview_area_ability(world: &World, target: Entity, ability: AbilityType) -> Option<CombatPreview> {
+    let damage = ability_damage(ability)?;
+    let resistances = world.read_storage::<DamageResistances>();
+    let land = |raw: i32| resistances.get(target).map_or(raw, |resist| resist.calculate_damage(raw, damage.damage_type));
+
+    Some(CombatPreview {
+        hit_chance: 100,
+        min_damage: land(damage.min()),
+        max_damage: land(damage.max()),
+        critical_damage: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_chance_respects_natural_rolls() {
+        // Even hopeless attacks land on a natural 20, and sure things still fumble on a 1
+        assert_eq!(hit_chance(0, 40), 5);
+        assert_eq!(hit_chance(40, 10), 95);
+        // Needs 10 or better on the die
+        assert_eq!(hit_chance(5, 15), 55);
+    }
+
+    #[test]
+    fn test_preview_summary() {
+        let preview = CombatPreview { hit_chance: 65, min_damage: 4, max_damage: 6, critical_damage: Some(12) };
+        assert_eq!(preview.summary(), "Hit 65%  Dmg 4-6 (crit 12)");
+
+        let sure = CombatPreview { hit_chance: 100, min_damage: 7, max_damage: 7, critical_damage: None };
+        assert_eq!(sure.summary(), "Hit 100%  Dmg 7");
+    }
+}
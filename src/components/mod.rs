@@ -1,4 +1,5 @@
 use specs::{Component, VecStorage, NullStorage, World, WorldExt};
+use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
 use specs_derive::Component;
 use serde::{Serialize, Deserialize};
 
@@ -10,6 +11,16 @@ pub struct Position {
     pub y: i32,
 }
 
+// Where an entity waits on a level the player has left; it takes the place of Position until
+// the player comes back
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct OtherLevelPosition {
+    pub x: i32,
+    pub y: i32,
+    pub location: crate::map::Location,
+}
+
 // Renderable component
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
@@ -61,6 +72,130 @@ pub struct CombatStats {
 #[storage(NullStorage)]
 pub struct Monster;
 
+/// What a monster is busy with when it is not simply standing guard
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AIMode {
+    /// Keeping to its post, shuffling about now and then
+    Patrol,
+    /// Chasing a target it can see
+    Hunting,
+    /// Heading for where it last heard or saw something
+    Investigating,
+    /// Looking around the rooms near that spot
+    Searching,
+    /// Giving up and going back to its post
+    Returning,
+    /// Running for the level's exit
+    Fleeing,
+}
+
+impl AIMode {
+    /// How the monster looks to someone watching it
+    pub fn description(&self) -> &'static str {
+        match self {
+            AIMode::Patrol => "It has not noticed you.",
+            AIMode::Hunting => "It is hunting you!",
+            AIMode::Investigating => "It is moving to check on something it heard.",
+            AIMode::Searching => "It is searching the area.",
+            AIMode::Returning => "It is heading back to its post.",
+            AIMode::Fleeing => "It is running for its life!",
+        }
+    }
+}
+
+// Monster AI state: how alert it is and what it is going after
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct MonsterAI {
+    pub mode: AIMode,
+    /// Where the monster patrols from and returns to
+    pub home: (i32, i32),
+    /// Extra tiles it can hear from; sharp-eared creatures notice more
+    pub alertness: i32,
+    /// Where it is heading while hunting, investigating or searching
+    pub goal: Option<(i32, i32)>,
+    /// Turns of searching left before it gives up
+    pub search_turns: i32,
+    /// Spots still to check while searching
+    pub search_spots: Vec<(i32, i32)>,
+    /// Which behavior tree drives it
+    pub behavior: String,
+    /// The action its behavior tree settled on last turn, for the wizard-mode overlay
+    #[serde(default)]
+    pub active_node: Option<String>,
+}
+
+impl MonsterAI {
+    pub fn new(home: (i32, i32), alertness: i32) -> Self {
+        MonsterAI {
+            mode: AIMode::Patrol,
+            home,
+            alertness,
+            goal: None,
+            search_turns: 0,
+            search_spots: Vec::new(),
+            behavior: crate::ai::monster_behavior::DEFAULT_BEHAVIOR.to_string(),
+            active_node: None,
+        }
+    }
+    
+    pub fn with_behavior(mut self, behavior: &str) -> Self {
+        self.behavior = behavior.to_string();
+        self
+    }
+    
+    /// Whether the monster is off its post after something
+    pub fn is_alerted(&self) -> bool {
+        !matches!(self.mode, AIMode::Patrol | AIMode::Returning)
+    }
+}
+
+// How readily a monster breaks and runs
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Morale {
+    /// Flees once its health drops below this percent; None for the fearless
+    pub flee_below: Option<i32>,
+    /// Percent added to that threshold by watching allies fall
+    pub shaken: i32,
+    /// Whether it may throw down its pack and beg for mercy when caught
+    pub can_surrender: bool,
+    pub surrendered: bool,
+}
+
+impl Morale {
+    pub fn new(flee_below: Option<i32>, can_surrender: bool) -> Self {
+        Morale { flee_below, shaken: 0, can_surrender, surrendered: false }
+    }
+    
+    /// Whether a creature this hurt breaks and runs
+    pub fn breaks(&self, stats: &CombatStats) -> bool {
+        match self.flee_below {
+            Some(threshold) if stats.max_hp > 0 => stats.hp * 100 / stats.max_hp < threshold + self.shaken,
+            _ => false,
+        }
+    }
+}
+
+/// How a pack of monsters fights together
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PackTactics {
+    /// Rushes in from every side at once, like a wolf pack
+    Swarm,
+    /// Keeps its spacing around a leader and cuts down one foe at a time
+    Warband,
+}
+
+// Belonging to a pack that hunts and retreats together
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct PackMember {
+    /// The pack's leader; the leader points at itself
+    #[serde(with = "crate::persistence::entity_refs")]
+    pub leader: specs::Entity,
+    pub tactics: PackTactics,
+}
+
 // Item marker component
 #[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
 #[storage(NullStorage)]
@@ -139,6 +274,42 @@ impl Abilities {
         self.abilities.insert(ability_type);
     }
     
+    /// The class abilities a new character of `class_type` knows from the start
+    pub fn starting(class_type: ClassType) -> Self {
+        let mut abilities = Abilities::new();
+        for ability in AbilityType::get_class_abilities(class_type) {
+            if ability.required_level() <= 1 {
+                abilities.add_ability(ability);
+            }
+        }
+        abilities
+    }
+    
+    /// Class abilities the character is high enough level for but has not learned
+    pub fn learnable(&self, class_type: ClassType, level: i32) -> Vec<AbilityType> {
+        AbilityType::get_class_abilities(class_type).into_iter()
+            .filter(|ability| ability.required_level() <= level && !self.has_ability(*ability))
+            .collect()
+    }
+    
+    /// Abilities known from classes other than the character's own
+    pub fn cross_class_count(&self, class_type: ClassType) -> i32 {
+        let own = AbilityType::get_class_abilities(class_type);
+        self.abilities.iter().filter(|ability| !own.contains(ability)).count() as i32
+    }
+    
+    /// Other classes' abilities a trainer could teach the character: only those their level
+    /// allows, and none once they know one for every `LEVELS_PER_CROSS_CLASS_ABILITY` levels
+    pub fn cross_class_learnable(&self, class_type: ClassType, level: i32) -> Vec<AbilityType> {
+        if self.cross_class_count(class_type) >= level / LEVELS_PER_CROSS_CLASS_ABILITY {
+            return Vec::new();
+        }
+        ClassType::all().into_iter()
+            .filter(|other| *other != class_type)
+            .flat_map(|other| self.learnable(other, level))
+            .collect()
+    }
+    
     pub fn is_on_cooldown(&self, ability_type: AbilityType) -> bool {
         self.ability_cooldowns.get(&ability_type).map_or(false, |&cd| cd > 0)
     }
@@ -160,6 +331,78 @@ impl Abilities {
     }
 }
 
+/// Levels a character needs for every ability they learn from outside their class
+pub const LEVELS_PER_CROSS_CLASS_ABILITY: i32 = 3;
+
+/// Slots on the hotbar, fired with the number keys 1-9
+pub const HOTBAR_SLOTS: usize = 9;
+
+/// What a hotbar slot fires
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum HotbarEntry {
+    Ability(AbilityType),
+    /// A consumable by name, so any stack of it in the pack will do
+    Item(String),
+}
+
+// Hotbar component: abilities and consumables on the number keys
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Hotbar {
+    pub slots: Vec<Option<HotbarEntry>>,
+}
+
+impl Default for Hotbar {
+    fn default() -> Self {
+        Hotbar::new()
+    }
+}
+
+impl Hotbar {
+    pub fn new() -> Self {
+        Hotbar {
+            slots: vec![None; HOTBAR_SLOTS],
+        }
+    }
+    
+    /// A hotbar with the class abilities a character knows in the first slots
+    pub fn for_class(class_type: ClassType, abilities: &Abilities) -> Self {
+        let mut hotbar = Hotbar::new();
+        let known = AbilityType::get_class_abilities(class_type).into_iter()
+            .filter(|ability| abilities.has_ability(*ability));
+        for (slot, ability) in known.take(HOTBAR_SLOTS).enumerate() {
+            hotbar.slots[slot] = Some(HotbarEntry::Ability(ability));
+        }
+        hotbar
+    }
+    
+    pub fn get(&self, slot: usize) -> Option<&HotbarEntry> {
+        self.slots.get(slot).and_then(|entry| entry.as_ref())
+    }
+    
+    /// The slot `entry` sits in, if it is on the bar
+    pub fn slot_of(&self, entry: &HotbarEntry) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.as_ref() == Some(entry))
+    }
+    
+    /// Put `entry` in `slot`, moving it there if it was already elsewhere on the bar
+    pub fn assign(&mut self, slot: usize, entry: HotbarEntry) {
+        if slot >= self.slots.len() {
+            return;
+        }
+        if let Some(previous) = self.slot_of(&entry) {
+            self.slots[previous] = None;
+        }
+        self.slots[slot] = Some(entry);
+    }
+    
+    pub fn clear(&mut self, slot: usize) {
+        if let Some(entry) = self.slots.get_mut(slot) {
+            *entry = None;
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AbilityType {
     // Fighter abilities
@@ -167,6 +410,7 @@ pub enum AbilityType {
     Cleave,
     ShieldBash,
     SecondWind,
+    Charge,
     
     // Rogue abilities
     Backstab,
@@ -201,6 +445,7 @@ impl AbilityType {
             AbilityType::Cleave => "Cleave",
             AbilityType::ShieldBash => "Shield Bash",
             AbilityType::SecondWind => "Second Wind",
+            AbilityType::Charge => "Charge",
             
             // Rogue abilities
             AbilityType::Backstab => "Backstab",
@@ -233,8 +478,9 @@ impl AbilityType {
             // Fighter abilities
             AbilityType::PowerAttack => "A powerful attack that sacrifices accuracy for increased damage.",
             AbilityType::Cleave => "A sweeping attack that can hit multiple adjacent enemies.",
-            AbilityType::ShieldBash => "Bash an enemy with your shield, stunning them briefly.",
+            AbilityType::ShieldBash => "Bash an enemy with your shield, stunning them and knocking them back.",
             AbilityType::SecondWind => "Recover a portion of your health through sheer determination.",
+            AbilityType::Charge => "Rush at an enemy in a straight line, striking harder the further you run.",
             
             // Rogue abilities
             AbilityType::Backstab => "A deadly attack from stealth that deals massive damage.",
@@ -269,6 +515,7 @@ impl AbilityType {
             AbilityType::Cleave => 5,
             AbilityType::ShieldBash => 4,
             AbilityType::SecondWind => 10,
+            AbilityType::Charge => 6,
             
             // Rogue abilities
             AbilityType::Backstab => 5,
@@ -303,6 +550,7 @@ impl AbilityType {
             AbilityType::Cleave => 3,
             AbilityType::ShieldBash => 5,
             AbilityType::SecondWind => 7,
+            AbilityType::Charge => 4,
             
             // Rogue abilities
             AbilityType::Backstab => 1,
@@ -337,6 +585,7 @@ impl AbilityType {
                 AbilityType::Cleave,
                 AbilityType::ShieldBash,
                 AbilityType::SecondWind,
+                AbilityType::Charge,
             ],
             ClassType::Rogue => vec![
                 AbilityType::Backstab,
@@ -414,13 +663,56 @@ pub struct WantsToMove {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct WantsToAttack {
+    #[serde(with = "crate::persistence::entity_refs")]
     pub target: specs::Entity,
 }
 
+/// What is driving a forced movement
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ForcedMovementKind {
+    /// Shoved away from the source; hitting a wall or a creature hurts
+    Knockback,
+    /// The mover rushes forward and strikes whatever stops it
+    Charge,
+}
+
+// Movement imposed on an entity, resolved tile by tile against the map
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct ForcedMovement {
+    pub dx: i32,
+    pub dy: i32,
+    pub distance: i32,
+    pub source: specs::Entity,
+    pub kind: ForcedMovementKind,
+}
+
+impl ForcedMovement {
+    /// Knock whatever stands at `to` further away from `from`
+    pub fn knockback(source: specs::Entity, from: (i32, i32), to: (i32, i32), distance: i32) -> Self {
+        ForcedMovement {
+            dx: (to.0 - from.0).signum(),
+            dy: (to.1 - from.1).signum(),
+            distance,
+            source,
+            kind: ForcedMovementKind::Knockback,
+        }
+    }
+
+    /// Rush from `from` towards `to`
+    pub fn charge(source: specs::Entity, from: (i32, i32), to: (i32, i32), distance: i32) -> Self {
+        ForcedMovement {
+            kind: ForcedMovementKind::Charge,
+            ..ForcedMovement::knockback(source, from, to, distance)
+        }
+    }
+}
+
 // WantsToPickupItem component for item pickup intent
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct WantsToPickupItem {
+    #[serde(with = "crate::persistence::entity_refs")]
     pub item: specs::Entity,
 }
 
@@ -428,7 +720,9 @@ pub struct WantsToPickupItem {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct WantsToUseItem {
+    #[serde(with = "crate::persistence::entity_refs")]
     pub item: specs::Entity,
+    #[serde(with = "crate::persistence::entity_refs::option")]
     pub target: Option<specs::Entity>,
 }
 
@@ -436,7 +730,10 @@ pub struct WantsToUseItem {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct WantsToDropItem {
+    #[serde(with = "crate::persistence::entity_refs")]
     pub item: specs::Entity,
+    /// How many of a stack to drop
+    pub quantity: i32,
 }
 
 // Death-related components
@@ -459,9 +756,60 @@ pub enum DeathCause {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct Corpse {
+    #[serde(with = "crate::persistence::entity_refs::option")]
     pub original_entity: Option<specs::Entity>,
     pub decay_timer: i32,
     pub loot_generated: bool,
+    /// Name of the creature that died here
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub max_hp: i32,
+    /// Undead remains are too far gone to eat
+    #[serde(default)]
+    pub undead: bool,
+    #[serde(default)]
+    pub butchered: bool,
+}
+
+/// Turns a fresh corpse lasts before only bones remain
+pub const CORPSE_DECAY_TURNS: i32 = 100;
+
+impl Corpse {
+    pub fn new(original_entity: Option<specs::Entity>, name: &str, max_hp: i32, undead: bool) -> Self {
+        Corpse {
+            original_entity,
+            decay_timer: CORPSE_DECAY_TURNS,
+            loot_generated: false,
+            name: name.to_string(),
+            max_hp,
+            undead,
+            butchered: false,
+        }
+    }
+
+    /// Portions of meat butchering yields; bigger creatures give more
+    pub fn meat_yield(&self) -> i32 {
+        if self.undead || self.butchered {
+            0
+        } else {
+            (self.max_hp / 10).clamp(1, 5)
+        }
+    }
+
+    /// Whether a necromancer could still raise this corpse
+    pub fn can_be_raised(&self) -> bool {
+        !self.butchered && self.decay_timer > 0
+    }
+
+    /// Fresh corpses rise as zombies, rotted ones only as skeletons
+    pub fn raised_kind(&self) -> CompanionKind {
+        if self.decay_timer > CORPSE_DECAY_TURNS / 2 {
+            CompanionKind::Zombie
+        } else {
+            CompanionKind::Skeleton
+        }
+    }
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -474,7 +822,7 @@ pub struct DeathAnimation {
     pub original_color: crossterm::style::Color,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub enum DeathAnimationType {
     Fade,
     Dissolve,
@@ -486,6 +834,7 @@ pub enum DeathAnimationType {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct Inventory {
+    #[serde(with = "crate::persistence::entity_refs::vec")]
     pub items: Vec<specs::Entity>,
     pub capacity: usize,
 }
@@ -507,6 +856,7 @@ impl Inventory {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct Equipped {
+    #[serde(with = "crate::persistence::entity_refs")]
     pub owner: specs::Entity,
     pub slot: EquipmentSlot,
 }
@@ -520,6 +870,9 @@ pub struct Experience {
     pub level_up_target: i32,
     pub unspent_points: i32,
     pub total_exp_earned: i32,
+    /// Levels gained whose hit points and skill points the level-up system has yet to hand out
+    #[serde(default)]
+    pub levels_unclaimed: i32,
 }
 
 impl Experience {
@@ -530,6 +883,7 @@ impl Experience {
             level_up_target: 100,
             unspent_points: 0,
             total_exp_earned: 0,
+            levels_unclaimed: 0,
         }
     }
     
@@ -549,6 +903,7 @@ impl Experience {
         self.current -= self.level_up_target;
         self.level_up_target = (self.level_up_target as f32 * 1.5) as i32;
         self.unspent_points += 3; // Grant 3 points per level
+        self.levels_unclaimed += 1;
     }
     
     pub fn exp_to_next_level(&self) -> i32 {
@@ -560,6 +915,13 @@ impl Experience {
     }
 }
 
+/// Levels the player has gained whose points and ability are still to be chosen on the level-up screen
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct PendingLevelUp {
+    pub levels: i32,
+}
+
 // Character attributes component
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
@@ -586,15 +948,40 @@ impl Attributes {
         }
     }
     
-    pub fn get_modifier(&self, attribute: AttributeType) -> i32 {
-        let value = match attribute {
+    /// Fixed scores in `AttributeType::all()` order, with no points left to spend
+    pub fn from_scores(scores: [i32; 6]) -> Self {
+        let mut attributes = Attributes::new();
+        attributes.unspent_points = 0;
+        for (attribute, score) in AttributeType::all().iter().zip(scores.iter()) {
+            attributes.set_attribute(*attribute, *score);
+        }
+        attributes
+    }
+    
+    pub fn get_attribute(&self, attribute: AttributeType) -> i32 {
+        match attribute {
             AttributeType::Strength => self.strength,
             AttributeType::Dexterity => self.dexterity,
             AttributeType::Constitution => self.constitution,
             AttributeType::Intelligence => self.intelligence,
             AttributeType::Wisdom => self.wisdom,
             AttributeType::Charisma => self.charisma,
-        };
+        }
+    }
+    
+    pub fn set_attribute(&mut self, attribute: AttributeType, value: i32) {
+        match attribute {
+            AttributeType::Strength => self.strength = value,
+            AttributeType::Dexterity => self.dexterity = value,
+            AttributeType::Constitution => self.constitution = value,
+            AttributeType::Intelligence => self.intelligence = value,
+            AttributeType::Wisdom => self.wisdom = value,
+            AttributeType::Charisma => self.charisma = value,
+        }
+    }
+    
+    pub fn get_modifier(&self, attribute: AttributeType) -> i32 {
+        let value = self.get_attribute(attribute);
         
         // Calculate modifier: (value - 10) / 2, rounded down
         (value - 10) / 2
@@ -703,7 +1090,7 @@ impl Attributes {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AttributeType {
     Strength,
     Dexterity,
@@ -713,6 +1100,19 @@ pub enum AttributeType {
     Charisma,
 }
 
+impl AttributeType {
+    pub fn all() -> [AttributeType; 6] {
+        [
+            AttributeType::Strength,
+            AttributeType::Dexterity,
+            AttributeType::Constitution,
+            AttributeType::Intelligence,
+            AttributeType::Wisdom,
+            AttributeType::Charisma,
+        ]
+    }
+}
+
 // Character class component
 #[derive(Component, Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[storage(VecStorage)]
@@ -730,6 +1130,10 @@ pub enum ClassType {
 }
 
 impl ClassType {
+    pub fn all() -> [ClassType; 5] {
+        [ClassType::Fighter, ClassType::Rogue, ClassType::Mage, ClassType::Cleric, ClassType::Ranger]
+    }
+    
     pub fn name(&self) -> &'static str {
         match self {
             ClassType::Fighter => "Fighter",
@@ -798,12 +1202,20 @@ pub struct Background {
     pub background_type: BackgroundType,
 }
 
+/// The highest level any skill can be trained to
+pub const MAX_SKILL_LEVEL: i32 = 5;
+/// Skill experience for each level a skill already has, to reach the next
+pub const SKILL_XP_PER_LEVEL: i32 = 50;
+
 // Skills component for tracking character skills
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct Skills {
     pub skills: std::collections::HashMap<SkillType, i32>,
     pub unspent_skill_points: i32,
+    /// Experience toward each skill's next level, from study rather than skill points
+    #[serde(default)]
+    pub experience: std::collections::HashMap<SkillType, i32>,
 }
 
 impl Skills {
@@ -815,12 +1227,20 @@ impl Skills {
             skills.insert(skill_type, 0);
         }
         
-        // Add starting skills based on class and background later
-        
         Skills {
             skills,
             unspent_skill_points: 0,
+            experience: std::collections::HashMap::new(),
+        }
+    }
+    
+    /// Untrained but for what the background taught
+    pub fn for_background(background: BackgroundType) -> Self {
+        let mut skills = Skills::new();
+        for (skill_type, level) in background.starting_skills() {
+            skills.skills.insert(*skill_type, (*level).min(MAX_SKILL_LEVEL));
         }
+        skills
     }
     
     pub fn get_skill_level(&self, skill_type: SkillType) -> i32 {
@@ -833,7 +1253,7 @@ impl Skills {
         }
         
         let current_level = self.get_skill_level(skill_type);
-        if current_level < 5 { // Maximum skill level is 5
+        if current_level < MAX_SKILL_LEVEL {
             self.skills.insert(skill_type, current_level + 1);
             self.unspent_skill_points -= 1;
             return true;
@@ -845,6 +1265,28 @@ impl Skills {
     pub fn add_skill_points(&mut self, points: i32) {
         self.unspent_skill_points += points;
     }
+    
+    /// Experience needed to take `skill_type` to its next level
+    pub fn experience_to_next(&self, skill_type: SkillType) -> i32 {
+        SKILL_XP_PER_LEVEL * (self.get_skill_level(skill_type) + 1)
+    }
+    
+    /// Add experience to a skill, raising it a level when there is enough. Returns whether it rose
+    pub fn gain_experience(&mut self, skill_type: SkillType, amount: i32) -> bool {
+        let level = self.get_skill_level(skill_type);
+        if level >= MAX_SKILL_LEVEL {
+            return false;
+        }
+        let needed = self.experience_to_next(skill_type);
+        let gained = self.experience.entry(skill_type).or_insert(0);
+        *gained += amount;
+        if *gained < needed {
+            return false;
+        }
+        *gained -= needed;
+        self.skills.insert(skill_type, level + 1);
+        true
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
@@ -993,11 +1435,114 @@ impl BackgroundType {
             BackgroundType::Merchant => AttributeType::Charisma,
         }
     }
+
+    /// Skill levels the character already has when they set out
+    pub fn starting_skills(&self) -> &'static [(SkillType, i32)] {
+        match self {
+            BackgroundType::Soldier => &[(SkillType::MeleeWeapons, 1), (SkillType::Defense, 1)],
+            BackgroundType::Scholar => &[(SkillType::Arcane, 1), (SkillType::Enchanting, 1)],
+            BackgroundType::Noble => &[(SkillType::Persuasion, 1), (SkillType::MeleeWeapons, 1)],
+            BackgroundType::Outlaw => &[(SkillType::Stealth, 2), (SkillType::Lockpicking, 1)],
+            BackgroundType::Acolyte => &[(SkillType::Divine, 1), (SkillType::Alchemy, 1)],
+            BackgroundType::Merchant => &[(SkillType::Persuasion, 1), (SkillType::Perception, 1)],
+        }
+    }
+
+    pub fn perk(&self) -> BackgroundPerk {
+        match self {
+            BackgroundType::Soldier => BackgroundPerk::Veteran,
+            BackgroundType::Scholar => BackgroundPerk::Studious,
+            BackgroundType::Noble => BackgroundPerk::FamilyPurse,
+            BackgroundType::Outlaw => BackgroundPerk::Underworld,
+            BackgroundType::Acolyte => BackgroundPerk::ShrineBlessing,
+            BackgroundType::Merchant => BackgroundPerk::Haggler,
+        }
+    }
+
+    /// Multiplier for what town services charge someone of this background
+    pub fn price_multiplier(&self) -> f32 {
+        match self.perk() {
+            BackgroundPerk::Haggler => HAGGLER_PRICE_MULTIPLIER,
+            _ => 1.0,
+        }
+    }
+
+    /// The person in Ravenhollow who knew the character before the dungeon
+    pub fn contact(&self) -> Contact {
+        let (name, role, favor) = match self {
+            BackgroundType::Soldier => ("Sergeant Holt", "Captain of the town watch", "Keeps an ear out for trouble on the road"),
+            BackgroundType::Scholar => ("Mistress Verra", "Keeper of the Ravenhollow archive", "Reads old inscriptions and scrolls"),
+            BackgroundType::Noble => ("Lord Aldric", "Steward of the manor house", "Vouches for you when the townsfolk are wary"),
+            BackgroundType::Outlaw => ("Sly Maren", "Fence behind the tannery", "Buys what others won't ask about"),
+            BackgroundType::Acolyte => ("Brother Tomas", "Priest of the village shrine", "Blesses those who set out for the depths"),
+            BackgroundType::Merchant => ("Old Bessa", "Trader at the market square", "Knows who is selling what, and for how much"),
+        };
+        Contact { name: name.to_string(), role: role.to_string(), favor: favor.to_string() }
+    }
+}
+
+/// Extra maximum health for a Veteran
+pub const VETERAN_BONUS_HP: i32 = 3;
+/// Gold a noble sets out with from the family purse
+pub const FAMILY_PURSE_GOLD: i32 = 50;
+/// What a Haggler pays for town services, as a share of the usual price
+pub const HAGGLER_PRICE_MULTIPLIER: f32 = 0.85;
+/// How long the shrine's blessing lasts into the first descent
+pub const SHRINE_BLESSING_TURNS: i32 = 200;
+
+/// The one thing a background gives that no other does
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum BackgroundPerk {
+    Veteran,
+    Studious,
+    FamilyPurse,
+    Underworld,
+    ShrineBlessing,
+    Haggler,
+}
+
+impl BackgroundPerk {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackgroundPerk::Veteran => "Veteran",
+            BackgroundPerk::Studious => "Studious",
+            BackgroundPerk::FamilyPurse => "Family Purse",
+            BackgroundPerk::Underworld => "Underworld Ties",
+            BackgroundPerk::ShrineBlessing => "Shrine Blessing",
+            BackgroundPerk::Haggler => "Haggler",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            BackgroundPerk::Veteran => "Hardened by campaigns: starts with extra maximum health.",
+            BackgroundPerk::Studious => "Quick to learn: starts with a skill point to spend.",
+            BackgroundPerk::FamilyPurse => "Starts with gold from the family coffers.",
+            BackgroundPerk::Underworld => "Starts with a set of lockpicks and a knack for going unseen.",
+            BackgroundPerk::ShrineBlessing => "Sets out blessed by the village shrine.",
+            BackgroundPerk::Haggler => "Pays less for the blacksmith's and the temple's services.",
+        }
+    }
+}
+
+/// Someone in town the character can call on
+#[derive(Component, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[storage(VecStorage)]
+pub struct Contact {
+    pub name: String,
+    pub role: String,
+    /// What they will do for an old friend
+    pub favor: String,
 }
 
+// Marks entities that go into save files; references between them are saved as marker ids,
+// since `Entity` ids are handed out afresh on load
+pub struct SerializeMe;
+
 // Register all components with the world
 pub fn register_components(world: &mut World) {
     world.register::<Position>();
+    world.register::<OtherLevelPosition>();
     world.register::<Renderable>();
     world.register::<Player>();
     world.register::<Viewshed>();
@@ -1022,15 +1567,18 @@ pub fn register_components(world: &mut World) {
     world.register::<Inventory>();
     world.register::<Equipped>();
     world.register::<Experience>();
+    world.register::<PendingLevelUp>();
     
     // Character creation components
     world.register::<Attributes>();
     world.register::<CharacterClass>();
     world.register::<Background>();
+    world.register::<Contact>();
     
     // Character progression components
     world.register::<Skills>();
     world.register::<Abilities>();
+    world.register::<Hotbar>();
     
     // Combat components
     world.register::<SufferDamage>();
@@ -1044,6 +1592,7 @@ pub fn register_components(world: &mut World) {
     // Death and revival components
     world.register::<DeathState>();
     world.register::<RevivalItem>();
+    world.register::<WantsToRevive>();
     world.register::<DeathPenalty>();
     world.register::<GameSettings>();
     
@@ -1052,6 +1601,7 @@ pub fn register_components(world: &mut World) {
     world.register::<Defender>();
     world.register::<DamageInfo>();
     world.register::<DamageResistances>();
+    world.register::<ForcedMovement>();
     world.register::<CombatAction>();
     world.register::<Initiative>();
     world.register::<CombatFeedback>();
@@ -1060,10 +1610,83 @@ pub fn register_components(world: &mut World) {
     // Combat rewards components
     world.register::<LootTable>();
     world.register::<UniqueEnemy>();
+    world.register::<Elite>();
+    world.register::<Undead>();
+    world.register::<BreathWeapon>();
     world.register::<CombatReward>();
     world.register::<BossEnemy>();
+    world.register::<VictoryArtifact>();
     world.register::<Treasure>();
     world.register::<WantsToInteract>();
+    
+    // Corpse components
+    world.register::<Corpse>();
+    world.register::<WantsToButcher>();
+    world.register::<WantsToRest>();
+    world.register::<Campfire>();
+    world.register::<Piety>();
+    world.register::<Altar>();
+    world.register::<Furniture>();
+    world.register::<WantsToUseFurniture>();
+    world.register::<ReleasesGas>();
+    world.register::<GasFlask>();
+    world.register::<WantsToThrow>();
+    world.register::<Exposure>();
+    world.register::<VaultGuardian>();
+    world.register::<Captive>();
+    world.register::<Escort>();
+    world.register::<WantsToRescue>();
+    world.register::<Rival>();
+    world.register::<WantsToParley>();
+    world.register::<Townsperson>();
+    world.register::<ForSale>();
+    world.register::<WantsToDeal>();
+    world.register::<WantsToExplore>();
+    world.register::<WantsToTravel>();
+    world.register::<WantsToOperateDoor>();
+    world.register::<OpensDoors>();
+    world.register::<Swimming>();
+    world.register::<MovementMode>();
+    world.register::<Sprinting>();
+    world.register::<MonsterAI>();
+    world.register::<Morale>();
+    world.register::<PackMember>();
+    world.register::<WantsToRaiseCorpse>();
+    
+    // Durability components
+    world.register::<crate::items::EquipmentWear>();
+    world.register::<crate::items::RepairKit>();
+    world.register::<crate::items::WantsToRepair>();
+    
+    // Enchanting components
+    world.register::<crate::items::Essences>();
+    world.register::<crate::items::EnchantingStation>();
+    world.register::<crate::items::WantsToDisenchant>();
+    world.register::<crate::items::WantsToEnchant>();
+    
+    // Artifact components
+    world.register::<crate::items::Artifact>();
+    
+    // Curse components
+    world.register::<crate::items::WantsToRemoveCurse>();
+    
+    // Alchemy components
+    world.register::<crate::items::AlchemyStation>();
+    world.register::<crate::items::WantsToBrew>();
+    
+    // Ranged combat components
+    world.register::<crate::items::Ammunition>();
+    world.register::<crate::items::WantsToShoot>();
+    
+    // Companion components
+    world.register::<Companion>();
+    world.register::<WantsToSummon>();
+    world.register::<WantsToDismissCompanion>();
+    world.register::<LastAttacker>();
+    
+    // Save markers, handed out as entities are built
+    world.register::<SimpleMarker<SerializeMe>>();
+    world.insert(SimpleMarkerAllocator::<SerializeMe>::new());
 }
 
 // Combat-related components
@@ -1160,7 +1783,7 @@ impl PlayerResources {
     }
 }
 
-// Status effects that can affect resources
+// Status effects applied by combat, consumables and the environment
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct StatusEffects {
@@ -1174,16 +1797,21 @@ impl StatusEffects {
         }
     }
     
+    /// Add an effect, merging it with an active one according to the type's stacking rule
     pub fn add_effect(&mut self, effect: StatusEffect) {
-        // Check if effect already exists and update duration
         for existing_effect in &mut self.effects {
             if existing_effect.effect_type == effect.effect_type {
                 existing_effect.duration = i32::max(existing_effect.duration, effect.duration);
+                existing_effect.magnitude = match effect.effect_type.stacking_rule() {
+                    StackingRule::Refresh => i32::max(existing_effect.magnitude, effect.magnitude),
+                    StackingRule::Intensify { max_magnitude } => {
+                        i32::min(existing_effect.magnitude + effect.magnitude, max_magnitude)
+                    },
+                };
                 return;
             }
         }
         
-        // Add new effect
         self.effects.push(effect);
     }
     
@@ -1199,12 +1827,33 @@ impl StatusEffects {
         self.effects.iter().find(|effect| effect.effect_type == effect_type)
     }
     
-    pub fn update_effects(&mut self) {
-        // Decrease duration and remove expired effects
+    /// Whether any active effect stops the entity from acting this turn
+    pub fn is_incapacitated(&self) -> bool {
+        self.effects.iter().any(|effect| effect.effect_type.prevents_action())
+    }
+    
+    /// Sum the stat changes of every active effect
+    pub fn stat_modifiers(&self) -> StatusModifiers {
+        let mut modifiers = StatusModifiers::default();
+        for effect in &self.effects {
+            effect.effect_type.contribute_modifiers(effect.magnitude, &mut modifiers);
+        }
+        modifiers
+    }
+    
+    /// Decrease durations by one turn, returning the effects that expired
+    pub fn update_effects(&mut self) -> Vec<StatusEffectType> {
+        let mut expired = Vec::new();
         self.effects.retain_mut(|effect| {
             effect.duration -= 1;
-            effect.duration > 0
+            if effect.duration <= 0 {
+                expired.push(effect.effect_type);
+                false
+            } else {
+                true
+            }
         });
+        expired
     }
 }
 
@@ -1215,7 +1864,57 @@ pub struct StatusEffect {
     pub magnitude: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+impl StatusEffect {
+    pub fn new(effect_type: StatusEffectType, duration: i32, magnitude: i32) -> Self {
+        StatusEffect {
+            effect_type,
+            duration,
+            magnitude,
+        }
+    }
+}
+
+/// How reapplying an already active effect behaves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StackingRule {
+    /// Keep the longer duration and the stronger magnitude
+    Refresh,
+    /// Magnitudes add together up to a cap
+    Intensify { max_magnitude: i32 },
+}
+
+/// Aggregated stat changes from all active status effects
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusModifiers {
+    pub power: i32,
+    pub defense: i32,
+    pub speed: i32,
+    pub attributes: std::collections::HashMap<AttributeType, i32>,
+    pub mana_regen: f32,
+    pub stamina_regen: f32,
+}
+
+impl StatusModifiers {
+    pub fn attribute(&self, attribute: AttributeType) -> i32 {
+        *self.attributes.get(&attribute).unwrap_or(&0)
+    }
+    
+    fn add_attribute(&mut self, attribute: AttributeType, amount: i32) {
+        *self.attributes.entry(attribute).or_insert(0) += amount;
+    }
+    
+    /// Combat stats with power and defense adjusted by the modifiers
+    pub fn apply_to(&self, stats: &CombatStats) -> CombatStats {
+        CombatStats {
+            max_hp: stats.max_hp,
+            hp: stats.hp,
+            defense: stats.defense + self.defense,
+            power: i32::max(0, stats.power + self.power),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StatusEffectType {
     // Resource effects
     ManaRegenBoost,
@@ -1223,18 +1922,61 @@ pub enum StatusEffectType {
     StaminaRegenBoost,
     StaminaRegenPenalty,
     
-    // Combat effects
+    // Damage and healing over time
     Poisoned,
+    Burning,
+    Bleeding,
+    Diseased,
+    Regeneration,
+    
+    // Combat effects
     Blessed,
     Cursed,
     Haste,
     Slow,
+    Stunned,
+    Paralyzed,
+    Asleep,
+    Confused,
+    Feared,
+    Blinded,
+    Silenced,
     
     // Stat effects
     StrengthBoost,
     StrengthPenalty,
+    DexterityBoost,
+    ConstitutionBoost,
+    IntelligenceBoost,
+    WisdomBoost,
+    CharismaBoost,
     DefenseBoost,
     DefensePenalty,
+    
+    // Utility effects
+    WellFed,
+    Invisible,
+    Levitating,
+    WaterWalking,
+    Detecting,
+    
+    // Environmental effects
+    Wet,
+    
+    // Lingering after a revival
+    Weakened,
+    Frail,
+    
+    // Out of stamina
+    Exhausted,
+    
+    // The cold and heat of the deep branches
+    Chilled,
+    Overheated,
+    /// Kept warm against the cold
+    Warmed,
+    /// Kept from wilting in the heat
+    Hydrated,
 }
 
 impl StatusEffectType {
@@ -1245,14 +1987,43 @@ impl StatusEffectType {
             StatusEffectType::StaminaRegenBoost => "Stamina Regeneration Boost",
             StatusEffectType::StaminaRegenPenalty => "Stamina Regeneration Penalty",
             StatusEffectType::Poisoned => "Poisoned",
+            StatusEffectType::Burning => "Burning",
+            StatusEffectType::Bleeding => "Bleeding",
+            StatusEffectType::Diseased => "Diseased",
+            StatusEffectType::Regeneration => "Regeneration",
             StatusEffectType::Blessed => "Blessed",
             StatusEffectType::Cursed => "Cursed",
             StatusEffectType::Haste => "Haste",
             StatusEffectType::Slow => "Slow",
+            StatusEffectType::Stunned => "Stunned",
+            StatusEffectType::Paralyzed => "Paralyzed",
+            StatusEffectType::Asleep => "Asleep",
+            StatusEffectType::Confused => "Confused",
+            StatusEffectType::Feared => "Feared",
+            StatusEffectType::Blinded => "Blinded",
+            StatusEffectType::Silenced => "Silenced",
             StatusEffectType::StrengthBoost => "Strength Boost",
             StatusEffectType::StrengthPenalty => "Strength Penalty",
+            StatusEffectType::DexterityBoost => "Dexterity Boost",
+            StatusEffectType::ConstitutionBoost => "Constitution Boost",
+            StatusEffectType::IntelligenceBoost => "Intelligence Boost",
+            StatusEffectType::WisdomBoost => "Wisdom Boost",
+            StatusEffectType::CharismaBoost => "Charisma Boost",
             StatusEffectType::DefenseBoost => "Defense Boost",
             StatusEffectType::DefensePenalty => "Defense Penalty",
+            StatusEffectType::WellFed => "Well Fed",
+            StatusEffectType::Invisible => "Invisible",
+            StatusEffectType::Levitating => "Levitating",
+            StatusEffectType::WaterWalking => "Water Walking",
+            StatusEffectType::Detecting => "Detecting",
+            StatusEffectType::Wet => "Wet",
+            StatusEffectType::Weakened => "Weakened",
+            StatusEffectType::Frail => "Frail",
+            StatusEffectType::Exhausted => "Exhausted",
+            StatusEffectType::Chilled => "Chilled",
+            StatusEffectType::Overheated => "Overheated",
+            StatusEffectType::Warmed => "Warmed",
+            StatusEffectType::Hydrated => "Hydrated",
         }
     }
     
@@ -1260,13 +2031,191 @@ impl StatusEffectType {
         match self {
             StatusEffectType::ManaRegenBoost |
             StatusEffectType::StaminaRegenBoost |
+            StatusEffectType::Regeneration |
             StatusEffectType::Blessed |
             StatusEffectType::Haste |
             StatusEffectType::StrengthBoost |
-            StatusEffectType::DefenseBoost => true,
+            StatusEffectType::DexterityBoost |
+            StatusEffectType::ConstitutionBoost |
+            StatusEffectType::IntelligenceBoost |
+            StatusEffectType::WisdomBoost |
+            StatusEffectType::CharismaBoost |
+            StatusEffectType::DefenseBoost |
+            StatusEffectType::WellFed |
+            StatusEffectType::Invisible |
+            StatusEffectType::Levitating |
+            StatusEffectType::WaterWalking |
+            StatusEffectType::Detecting |
+            StatusEffectType::Warmed |
+            StatusEffectType::Hydrated => true,
             _ => false,
         }
     }
+    
+    pub fn stacking_rule(&self) -> StackingRule {
+        match self {
+            StatusEffectType::Poisoned => StackingRule::Intensify { max_magnitude: 5 },
+            StatusEffectType::Bleeding => StackingRule::Intensify { max_magnitude: 4 },
+            StatusEffectType::Regeneration => StackingRule::Intensify { max_magnitude: 10 },
+            _ => StackingRule::Refresh,
+        }
+    }
+    
+    /// Damage type whose resistance also protects against this effect
+    pub fn resisted_by(&self) -> Option<DamageType> {
+        match self {
+            StatusEffectType::Poisoned | StatusEffectType::Diseased => Some(DamageType::Poison),
+            StatusEffectType::Burning => Some(DamageType::Fire),
+            StatusEffectType::Slow => Some(DamageType::Ice),
+            StatusEffectType::Stunned | StatusEffectType::Paralyzed => Some(DamageType::Lightning),
+            StatusEffectType::Cursed => Some(DamageType::Dark),
+            StatusEffectType::Confused | StatusEffectType::Feared | StatusEffectType::Asleep => Some(DamageType::Psychic),
+            StatusEffectType::Bleeding => Some(DamageType::Physical),
+            _ => None,
+        }
+    }
+    
+    pub fn prevents_action(&self) -> bool {
+        matches!(self, StatusEffectType::Stunned | StatusEffectType::Paralyzed | StatusEffectType::Asleep)
+    }
+    
+    fn contribute_modifiers(&self, magnitude: i32, modifiers: &mut StatusModifiers) {
+        match self {
+            StatusEffectType::StrengthBoost => modifiers.add_attribute(AttributeType::Strength, magnitude),
+            StatusEffectType::StrengthPenalty => modifiers.add_attribute(AttributeType::Strength, -magnitude),
+            StatusEffectType::DexterityBoost => modifiers.add_attribute(AttributeType::Dexterity, magnitude),
+            StatusEffectType::ConstitutionBoost => modifiers.add_attribute(AttributeType::Constitution, magnitude),
+            StatusEffectType::IntelligenceBoost => modifiers.add_attribute(AttributeType::Intelligence, magnitude),
+            StatusEffectType::WisdomBoost => modifiers.add_attribute(AttributeType::Wisdom, magnitude),
+            StatusEffectType::CharismaBoost => modifiers.add_attribute(AttributeType::Charisma, magnitude),
+            StatusEffectType::Blessed => {
+                modifiers.power += 1;
+                modifiers.defense += 1;
+            },
+            StatusEffectType::Cursed => {
+                modifiers.power -= magnitude;
+                modifiers.defense -= magnitude;
+            },
+            StatusEffectType::Blinded => modifiers.power -= 2,
+            StatusEffectType::Feared => modifiers.power -= magnitude,
+            StatusEffectType::DefenseBoost => modifiers.defense += magnitude,
+            StatusEffectType::DefensePenalty => modifiers.defense -= magnitude,
+            StatusEffectType::Haste => modifiers.speed += magnitude,
+            StatusEffectType::Slow => modifiers.speed -= magnitude,
+            StatusEffectType::ManaRegenBoost => modifiers.mana_regen += magnitude as f32 * 0.1,
+            StatusEffectType::ManaRegenPenalty => modifiers.mana_regen -= magnitude as f32 * 0.1,
+            StatusEffectType::StaminaRegenBoost => modifiers.stamina_regen += magnitude as f32 * 0.1,
+            StatusEffectType::StaminaRegenPenalty => modifiers.stamina_regen -= magnitude as f32 * 0.1,
+            StatusEffectType::WellFed => modifiers.stamina_regen += 0.2,
+            StatusEffectType::Wet => modifiers.stamina_regen -= 0.1,
+            StatusEffectType::Chilled => {
+                modifiers.stamina_regen -= magnitude as f32 * 0.1;
+                modifiers.speed -= magnitude / 2;
+            },
+            StatusEffectType::Overheated => {
+                modifiers.stamina_regen -= magnitude as f32 * 0.1;
+                modifiers.power -= magnitude / 2;
+            },
+            StatusEffectType::Weakened => {
+                modifiers.power -= magnitude;
+                modifiers.defense -= magnitude;
+            },
+            StatusEffectType::Frail => {
+                for attribute in AttributeType::all() {
+                    modifiers.add_attribute(attribute, -magnitude);
+                }
+            },
+            StatusEffectType::Exhausted => {
+                modifiers.power -= magnitude;
+                modifiers.defense -= magnitude;
+                modifiers.speed -= 1;
+            },
+            _ => {}
+        }
+    }
+    
+    /// What the effect does, for the look panel
+    pub fn description(&self) -> &'static str {
+        match self {
+            StatusEffectType::ManaRegenBoost => "Mana comes back faster.",
+            StatusEffectType::ManaRegenPenalty => "Mana comes back slower.",
+            StatusEffectType::StaminaRegenBoost => "Stamina comes back faster.",
+            StatusEffectType::StaminaRegenPenalty => "Stamina comes back slower.",
+            StatusEffectType::Poisoned => "Loses health every turn, worse with each dose.",
+            StatusEffectType::Burning => "Loses health every turn until the flames go out.",
+            StatusEffectType::Bleeding => "Loses health every turn, worse with each wound.",
+            StatusEffectType::Diseased => "Sickness saps health every turn.",
+            StatusEffectType::Regeneration => "Wounds close a little every turn.",
+            StatusEffectType::Blessed => "Hits and defends a little better.",
+            StatusEffectType::Cursed => "Hits and defends worse.",
+            StatusEffectType::Haste => "Moves and acts faster.",
+            StatusEffectType::Slow => "Moves and acts slower.",
+            StatusEffectType::Stunned => "Cannot act.",
+            StatusEffectType::Paralyzed => "Cannot move a muscle.",
+            StatusEffectType::Asleep => "Cannot act until it wakes.",
+            StatusEffectType::Confused => "Stumbles about at random.",
+            StatusEffectType::Feared => "Too frightened to fight well.",
+            StatusEffectType::Blinded => "Sees nothing and hits badly.",
+            StatusEffectType::Silenced => "Cannot cast spells.",
+            StatusEffectType::StrengthBoost => "Stronger than usual.",
+            StatusEffectType::StrengthPenalty => "Weaker than usual.",
+            StatusEffectType::DexterityBoost => "Quicker than usual.",
+            StatusEffectType::ConstitutionBoost => "Tougher than usual.",
+            StatusEffectType::IntelligenceBoost => "Sharper than usual.",
+            StatusEffectType::WisdomBoost => "Wiser than usual.",
+            StatusEffectType::CharismaBoost => "More persuasive than usual.",
+            StatusEffectType::DefenseBoost => "Harder to hurt.",
+            StatusEffectType::DefensePenalty => "Easier to hurt.",
+            StatusEffectType::WellFed => "Stamina comes back faster on a full stomach.",
+            StatusEffectType::Invisible => "Cannot be seen.",
+            StatusEffectType::Levitating => "Floats over water, lava and chasms.",
+            StatusEffectType::WaterWalking => "Walks on water as if it were ground.",
+            StatusEffectType::Detecting => "Senses what lies hidden nearby.",
+            StatusEffectType::Wet => "Soaked through; stamina comes back slower.",
+            StatusEffectType::Chilled => "Freezing; stamina drains away, and with none left the cold bites into health.",
+            StatusEffectType::Overheated => "Wilting in the heat; stamina drains away, and with none left health goes too.",
+            StatusEffectType::Warmed => "Warm inside; the cold can't take hold.",
+            StatusEffectType::Hydrated => "Well watered; the heat can't take hold.",
+            StatusEffectType::Weakened => "Still weak from dying; hits and defends worse.",
+            StatusEffectType::Frail => "Every attribute is lowered after coming back.",
+            StatusEffectType::Exhausted => "Out of stamina; hits weaker, guards worse and moves slower.",
+        }
+    }
+    
+    /// Message shown to the player when the effect wears off
+    pub fn expiry_message(&self) -> &'static str {
+        match self {
+            StatusEffectType::Poisoned => "The poison has run its course.",
+            StatusEffectType::Burning => "The flames die out.",
+            StatusEffectType::Bleeding => "Your wounds stop bleeding.",
+            StatusEffectType::Diseased => "You feel healthy again.",
+            StatusEffectType::Regeneration => "Your regeneration fades.",
+            StatusEffectType::Blessed => "You no longer feel blessed.",
+            StatusEffectType::Cursed => "The curse lifts.",
+            StatusEffectType::Haste => "You slow down to normal speed.",
+            StatusEffectType::Slow => "You can move freely again.",
+            StatusEffectType::Stunned | StatusEffectType::Paralyzed => "You can move again.",
+            StatusEffectType::Asleep => "You wake up.",
+            StatusEffectType::Confused => "Your head clears.",
+            StatusEffectType::Feared => "You regain your courage.",
+            StatusEffectType::Blinded => "Your vision returns.",
+            StatusEffectType::Silenced => "You can speak again.",
+            StatusEffectType::WellFed => "You are no longer well fed.",
+            StatusEffectType::Invisible => "You become visible again.",
+            StatusEffectType::Levitating => "You drift back to the ground.",
+            StatusEffectType::WaterWalking => "You can no longer walk on water.",
+            StatusEffectType::Detecting => "Your heightened senses fade.",
+            StatusEffectType::Wet => "You dry off.",
+            StatusEffectType::Chilled => "Feeling creeps back into your fingers.",
+            StatusEffectType::Overheated => "You cool down.",
+            StatusEffectType::Warmed => "The warmth inside you fades.",
+            StatusEffectType::Hydrated => "Your mouth is getting dry.",
+            StatusEffectType::Weakened => "The chill of death leaves your limbs.",
+            StatusEffectType::Frail => "You feel like yourself again.",
+            StatusEffectType::Exhausted => "You get your breath back.",
+            _ => "An effect wears off.",
+        }
+    }
 }
 
 // Component for actions that consume resources
@@ -1274,6 +2223,7 @@ impl StatusEffectType {
 #[storage(VecStorage)]
 pub struct WantsToUseAbility {
     pub ability: AbilityType,
+    #[serde(with = "crate::persistence::entity_refs::option")]
     pub target: Option<Entity>,
     pub mana_cost: i32,
     pub stamina_cost: i32,
@@ -1289,6 +2239,7 @@ pub struct DeathState {
     pub revival_attempts: i32,
     pub max_revival_attempts: i32,
     pub death_penalty_applied: bool,
+    pub durability_penalty_applied: bool,
 }
 
 impl DeathState {
@@ -1300,14 +2251,25 @@ impl DeathState {
             revival_attempts: 0,
             max_revival_attempts: 3,
             death_penalty_applied: false,
+            durability_penalty_applied: false,
         }
     }
     
+    /// As many revivals as `mode` allows
+    pub fn for_mode(mode: &GameMode) -> Self {
+        let max_revival_attempts = match mode.max_revivals() {
+            unlimited if unlimited < 0 => i32::MAX,
+            limit => limit,
+        };
+        DeathState { max_revival_attempts, ..DeathState::new() }
+    }
+    
     pub fn kill(&mut self, cause: String, turn: i32) {
         self.is_dead = true;
         self.death_cause = cause;
         self.death_turn = turn;
         self.death_penalty_applied = false;
+        self.durability_penalty_applied = false;
     }
     
     pub fn revive(&mut self) -> bool {
@@ -1325,6 +2287,15 @@ impl DeathState {
     }
 }
 
+/// The player's choice on the revival screen: come back using `item`, or by spending one of the
+/// run's revivals and taking the death penalty when `item` is None
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToRevive {
+    #[serde(with = "crate::persistence::entity_refs::option")]
+    pub item: Option<Entity>,
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct RevivalItem {
@@ -1375,6 +2346,17 @@ pub enum GameMode {
 }
 
 impl GameMode {
+    pub fn all() -> [GameMode; 4] {
+        [GameMode::Casual, GameMode::Normal, GameMode::Hardcore, GameMode::Permadeath]
+    }
+
+    /// The mode after this one, wrapping back to the first
+    pub fn next(&self) -> GameMode {
+        let modes = GameMode::all();
+        let index = modes.iter().position(|mode| mode == self).unwrap_or(0);
+        modes[(index + 1) % modes.len()].clone()
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             GameMode::Normal => "Normal",
@@ -1393,7 +2375,34 @@ impl GameMode {
         }
     }
     
-    pub fn max_revivals(&self) -> i32 {
+    /// Whether the run keeps a single rolling save that is written for the player, and lost on death
+    pub fn is_ironman(&self) -> bool {
+        matches!(self, GameMode::Hardcore | GameMode::Permadeath)
+    }
+    
+    /// Percent a day the bank in Ravenhollow adds to an account, or below zero takes for keeping
+    /// it; the less forgiving the mode, the dearer it is to put gold beyond death's reach
+    pub fn bank_rate(&self) -> i32 {
+        match self {
+            GameMode::Casual => 2,
+            GameMode::Normal => 1,
+            GameMode::Hardcore => -1,
+            GameMode::Permadeath => -2,
+        }
+    }
+    
+    /// Whether a guild agent dealt a mortal wound on a contract dies of it, rather than being
+    /// carried back to the infirmary
+    pub fn agent_permadeath(&self) -> bool {
+        matches!(self, GameMode::Hardcore | GameMode::Permadeath)
+    }
+
+    /// Whether a revival item can bring the character back; a permadeath run has one life
+    pub fn allows_revival_items(&self) -> bool {
+        *self != GameMode::Permadeath
+    }
+    
+    pub fn max_revivals(&self) -> i32 {
         match self {
             GameMode::Normal => 3,
             GameMode::Hardcore => 1,
@@ -1424,6 +2433,108 @@ impl GameMode {
     }
 }
 
+// Difficulty presets, chosen alongside the game mode when starting a run
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+impl Difficulty {
+    pub fn all() -> [Difficulty; 4] {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Nightmare]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Nightmare => "Nightmare",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Weaker, sparser monsters, better loot and cheaper services",
+            Difficulty::Normal => "The dungeon as intended",
+            Difficulty::Hard => "Tougher monsters in greater numbers, scarcer treasure",
+            Difficulty::Nightmare => "Crowded with brutal monsters; good loot is rare and costly",
+        }
+    }
+
+    /// The next preset, wrapping around; used to cycle the selection in menus
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Nightmare,
+            Difficulty::Nightmare => Difficulty::Easy,
+        }
+    }
+
+    /// Multiplier for monster hit points and attack power
+    pub fn monster_stat_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Nightmare => 1.6,
+        }
+    }
+
+    /// Multiplier for each level's encounter budget
+    pub fn spawn_density(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Nightmare => 1.5,
+        }
+    }
+
+    /// Multiplier for the generation weight of anything better than common loot
+    pub fn rare_loot_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.3,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.85,
+            Difficulty::Nightmare => 0.7,
+        }
+    }
+
+    /// Multiplier for shop and service prices
+    pub fn price_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.2,
+            Difficulty::Nightmare => 1.5,
+        }
+    }
+
+    /// Scale a monster stat, never dropping it below 1
+    pub fn scale_monster_stat(&self, value: i32) -> i32 {
+        i32::max(1, (value as f32 * self.monster_stat_multiplier()).round() as i32)
+    }
+
+    pub fn scale_budget(&self, budget: i32) -> i32 {
+        (budget as f32 * self.spawn_density()).round() as i32
+    }
+
+    pub fn scale_price(&self, price: i32) -> i32 {
+        (price as f32 * self.price_multiplier()).round() as i32
+    }
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct GameSettings {
@@ -1431,6 +2542,37 @@ pub struct GameSettings {
     pub permadeath_enabled: bool,
     pub auto_save_on_death: bool,
     pub death_screen_enabled: bool,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Show the dice behind every attack in the combat log
+    #[serde(default)]
+    pub verbose_combat: bool,
+    /// Leave no blood or bones on the floor
+    #[serde(default)]
+    pub disable_gore: bool,
+    /// Skip rain, snow and ash overlays and lightning flashes on slow terminals
+    #[serde(default)]
+    pub disable_weather_effects: bool,
+    /// Playback speed of attack and death animations; 2.0 plays twice as fast
+    #[serde(default = "default_animation_speed")]
+    pub animation_speed: f32,
+    /// Resolve every turn instantly instead of animating it
+    #[serde(default)]
+    pub skip_animations: bool,
+    /// Play a particular run again; a new seed is drawn for each game when unset
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Walking into a closed door opens it instead of stopping short
+    #[serde(default = "default_bump_to_open")]
+    pub bump_to_open: bool,
+}
+
+fn default_animation_speed() -> f32 {
+    1.0
+}
+
+fn default_bump_to_open() -> bool {
+    true
 }
 
 impl GameSettings {
@@ -1440,8 +2582,37 @@ impl GameSettings {
             game_mode: mode,
             auto_save_on_death: true,
             death_screen_enabled: true,
+            difficulty: Difficulty::Normal,
+            verbose_combat: false,
+            disable_gore: false,
+            disable_weather_effects: false,
+            animation_speed: default_animation_speed(),
+            skip_animations: false,
+            seed: None,
+            bump_to_open: default_bump_to_open(),
         }
     }
+
+    pub fn with_game_mode(mut self, mode: GameMode) -> Self {
+        self.permadeath_enabled = mode == GameMode::Permadeath;
+        self.game_mode = mode;
+        self
+    }
+
+    /// Ironman runs save only by themselves and at save-and-quit, and their save goes when they die
+    pub fn is_ironman(&self) -> bool {
+        self.permadeath_enabled || self.game_mode.is_ironman()
+    }
+
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 }
 
 // Enhanced combat components
@@ -1453,6 +2624,8 @@ pub struct Attacker {
     pub critical_multiplier: f32,
     pub attack_speed: i32,
     pub last_attack_turn: i32,
+    #[serde(default)]
+    pub armor_penetration: i32,
 }
 
 impl Attacker {
@@ -1463,6 +2636,7 @@ impl Attacker {
             critical_multiplier: 2.0, // 2x damage on crit
             attack_speed: 100, // Base attack speed (lower is faster)
             last_attack_turn: 0,
+            armor_penetration: 0, // Damage reduction ignored on a hit
         }
     }
     
@@ -1495,6 +2669,11 @@ impl Defender {
             parry_chance: 0.0, // Requires weapon
         }
     }
+
+    /// Damage reduction left after armor penetration, never below zero
+    pub fn effective_damage_reduction(&self, penetration: i32) -> i32 {
+        i32::max(0, self.damage_reduction - penetration)
+    }
     
     pub fn calculate_defense(&self, rng: &mut crate::resources::RandomNumberGenerator) -> DefenseResult {
         let roll = rng.roll_dice(1, 100) as f32;
@@ -1525,6 +2704,7 @@ pub enum DefenseResult {
 pub struct DamageInfo {
     pub base_damage: i32,
     pub damage_type: DamageType,
+    #[serde(with = "crate::persistence::entity_refs")]
     pub source: Entity,
     pub is_critical: bool,
     pub penetration: i32, // Armor penetration
@@ -1590,7 +2770,9 @@ impl DamageResistances {
 #[storage(VecStorage)]
 pub struct CombatAction {
     pub action_type: CombatActionType,
+    #[serde(with = "crate::persistence::entity_refs")]
     pub actor: Entity,
+    #[serde(with = "crate::persistence::entity_refs::option")]
     pub target: Option<Entity>,
     pub priority: i32,
     pub delay: i32,
@@ -1738,11 +2920,17 @@ impl SoundEffectType {
 #[storage(VecStorage)]
 pub struct ParticleEffect {
     pub position: FloatingPosition,
+    /// The tile it was thrown from, where it is held when motion is reduced
+    pub origin: (i32, i32),
     pub velocity: ParticleVelocity,
     pub color: crossterm::style::Color,
     pub character: char,
     pub lifetime: f32,
     pub max_lifetime: f32,
+    /// Downward acceleration, in tiles per second squared
+    pub gravity: f32,
+    /// Draw order; below zero goes under creatures, otherwise over them
+    pub depth: i32,
 }
 
 #[derive(Debug, Clone)]
@@ -1800,9 +2988,129 @@ pub enum LootDrop {
 #[storage(NullStorage)]
 pub struct UniqueEnemy;
 
+/// Modifiers an elite monster can roll at spawn
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EliteAffix {
+    /// Acts earlier and hits a little harder
+    Fast,
+    /// Thick hide or plating
+    Armored,
+    /// Heals for part of the damage it deals
+    Vampiric,
+    /// Splits into two lesser copies when it dies
+    Splitting,
+}
+
+impl EliteAffix {
+    pub const ALL: [EliteAffix; 4] = [EliteAffix::Fast, EliteAffix::Armored, EliteAffix::Vampiric, EliteAffix::Splitting];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EliteAffix::Fast => "Fast",
+            EliteAffix::Armored => "Armored",
+            EliteAffix::Vampiric => "Vampiric",
+            EliteAffix::Splitting => "Splitting",
+        }
+    }
+
+    /// What the affix does, as shown when looking at the monster
+    pub fn description(&self) -> &'static str {
+        match self {
+            EliteAffix::Fast => "It moves with unnatural speed.",
+            EliteAffix::Armored => "Thick plates cover its hide.",
+            EliteAffix::Vampiric => "Its wounds close as it draws blood.",
+            EliteAffix::Splitting => "Its body seems ready to tear in two.",
+        }
+    }
+
+    /// Glyph color marking the monster as elite
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            EliteAffix::Fast => crossterm::style::Color::Yellow,
+            EliteAffix::Armored => crossterm::style::Color::Blue,
+            EliteAffix::Vampiric => crossterm::style::Color::DarkRed,
+            EliteAffix::Splitting => crossterm::style::Color::Green,
+        }
+    }
+
+    /// Percent chance a monster spawned at this depth is elite
+    pub fn elite_chance(depth: i32) -> i32 {
+        (4 + depth * 3).min(40)
+    }
+
+    /// Roll the affixes for a freshly spawned monster; deeper levels can stack two
+    pub fn roll(depth: i32, rng: &mut crate::resources::RandomNumberGenerator) -> Vec<EliteAffix> {
+        if rng.roll_dice(1, 100) > Self::elite_chance(depth) {
+            return Vec::new();
+        }
+        let count = if depth >= 5 && rng.roll_dice(1, 4) == 1 { 2 } else { 1 };
+        Self::pick(count, rng)
+    }
+
+    /// Draw `count` different affixes at random
+    pub fn pick(count: usize, rng: &mut crate::resources::RandomNumberGenerator) -> Vec<EliteAffix> {
+        let mut pool = Self::ALL.to_vec();
+        let mut affixes = Vec::new();
+        while affixes.len() < count && !pool.is_empty() {
+            let pick = rng.roll_dice(1, pool.len() as i32) as usize - 1;
+            affixes.push(pool.remove(pick));
+        }
+        affixes
+    }
+}
+
+/// An elite or champion monster and the affixes it rolled
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Elite {
+    pub affixes: Vec<EliteAffix>,
+}
+
+impl Elite {
+    pub fn has(&self, affix: EliteAffix) -> bool {
+        self.affixes.contains(&affix)
+    }
+
+    /// The monster's name with its affixes, e.g. "Orc (Fast, Vampiric)"
+    pub fn decorate(&self, name: &str) -> String {
+        let affixes: Vec<&str> = self.affixes.iter().map(|affix| affix.name()).collect();
+        format!("{} ({})", name, affixes.join(", "))
+    }
+}
+
+/// Marker for skeletons, zombies and other undead; silver hurts them more
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(NullStorage)]
+pub struct Undead;
+
+/// A cone of elemental breath that recharges over several turns
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct BreathWeapon {
+    pub range: i32,
+    /// Damage rolled as (dice, sides)
+    pub damage_dice: (i32, i32),
+    pub damage_type: DamageType,
+    pub cooldown: i32,
+    pub ready_in: i32,
+}
+
+impl BreathWeapon {
+    pub fn fire() -> Self {
+        BreathWeapon {
+            range: 5,
+            damage_dice: (4, 6),
+            damage_type: DamageType::Fire,
+            cooldown: 6,
+            ready_in: 2,
+        }
+    }
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct CombatReward {
+    #[serde(with = "crate::persistence::entity_refs")]
     pub source_entity: Entity,
     pub source_name: String,
     pub experience_gained: i32,
@@ -1819,7 +3127,7 @@ pub struct BossEnemy {
     pub guaranteed_drops: Vec<LootDrop>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum BossType {
     MiniBoss,
     AreaBoss,
@@ -1844,6 +3152,11 @@ impl BossType {
     }
 }
 
+// The artifact guarded by the final boss; carrying it out of the dungeon wins the game
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(NullStorage)]
+pub struct VictoryArtifact;
+
 // Treasure component for special loot containers
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
@@ -1861,6 +3174,8 @@ pub enum TreasureType {
     Urn,
     Corpse,
     SecretCache,
+    /// Thrown down by a monster that surrendered
+    Pack,
 }
 
 impl TreasureType {
@@ -1871,6 +3186,7 @@ impl TreasureType {
             TreasureType::Urn => 'U',
             TreasureType::Corpse => '%',
             TreasureType::SecretCache => '*',
+            TreasureType::Pack => '(',
         }
     }
     
@@ -1881,6 +3197,7 @@ impl TreasureType {
             TreasureType::Urn => crossterm::style::Color::Grey,
             TreasureType::Corpse => crossterm::style::Color::DarkRed,
             TreasureType::SecretCache => crossterm::style::Color::Magenta,
+            TreasureType::Pack => crossterm::style::Color::DarkYellow,
         }
     }
 }
@@ -1890,4 +3207,725 @@ impl TreasureType {
 #[storage(VecStorage)]
 pub struct WantsToInteract {
     pub target: Entity,
-}
\ No newline at end of file
+}
+// Intent to carve a corpse into food
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToButcher {
+    pub corpse: Entity,
+}
+
+// Intent to raise a corpse as an undead ally; the hook for necromancy abilities
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToRaiseCorpse {
+    pub corpse: Entity,
+}
+
+// Intent to rest in place, one turn at a time until done or interrupted
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToRest {
+    /// Turns still to rest; ignored when resting until healed
+    pub turns_left: u32,
+    /// Keep resting until health, mana and stamina are full
+    pub until_healed: bool,
+    /// Turns rested so far
+    pub turns_rested: u32,
+    /// Health at the end of the last rest turn, to notice being hurt
+    pub last_hp: Option<i32>,
+}
+
+impl WantsToRest {
+    pub fn turns(turns: u32) -> Self {
+        WantsToRest { turns_left: turns, until_healed: false, turns_rested: 0, last_hp: None }
+    }
+    
+    pub fn until_healed() -> Self {
+        WantsToRest { turns_left: 0, until_healed: true, turns_rested: 0, last_hp: None }
+    }
+}
+
+// Intent to keep exploring a step at a time until there is nothing left or something turns up
+#[derive(Component, Debug, Clone, Default)]
+#[storage(VecStorage)]
+pub struct WantsToExplore {
+    /// Steps taken so far
+    pub steps: u32,
+    /// Health after the last step, to notice being hurt
+    pub last_hp: Option<i32>,
+}
+
+// Intent to walk to a chosen tile by way of the known map, a step at a time, until there or interrupted
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToTravel {
+    pub destination: (i32, i32),
+    /// Steps taken so far
+    pub steps: u32,
+    /// Health after the last step, to notice being hurt
+    pub last_hp: Option<i32>,
+}
+
+impl WantsToTravel {
+    pub fn to(destination: (i32, i32)) -> Self {
+        WantsToTravel { destination, steps: 0, last_hp: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorAction {
+    Open,
+    Close,
+    /// Throw a shoulder against a door that won't open, loudly
+    Bash,
+    /// Wedge a closed door shut against whatever is on the other side
+    Jam,
+}
+
+// Intent to do something to the door on an adjacent tile
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToOperateDoor {
+    pub target: (i32, i32),
+    pub action: DoorAction,
+}
+
+/// Marker for monsters clever enough to work a door handle
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(NullStorage)]
+pub struct OpensDoors;
+
+/// How a creature gets about; anything without one walks
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[storage(VecStorage)]
+pub enum MovementMode {
+    Walking,
+    /// Wings carry it over water, lava and chasms
+    Flying,
+    /// Drifting just above the ground, over water, lava and chasms alike
+    Levitating,
+    /// Slips through solid rock, though a closed door still stops it
+    Phasing,
+}
+
+impl Default for MovementMode {
+    fn default() -> Self {
+        MovementMode::Walking
+    }
+}
+
+impl MovementMode {
+    /// How a creature moves right now: its own way, unless levitation lifts a walker off its feet
+    pub fn current(innate: Option<&MovementMode>, effects: Option<&StatusEffects>) -> MovementMode {
+        let innate = innate.copied().unwrap_or_default();
+        let levitating = effects.map_or(false, |effects| effects.has_effect(StatusEffectType::Levitating));
+        if innate == MovementMode::Walking && levitating {
+            MovementMode::Levitating
+        } else {
+            innate
+        }
+    }
+
+    /// Off the ground, so liquids and chasms below are no danger
+    pub fn is_airborne(&self) -> bool {
+        matches!(self, MovementMode::Flying | MovementMode::Levitating)
+    }
+}
+
+// Out of its depth in water, and how many turns in a row it has gone under
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct Swimming {
+    pub turns_under: i32,
+}
+
+/// Marker for a player running flat out: two tiles a move, paid for in stamina
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(NullStorage)]
+pub struct Sprinting;
+
+// A fire to rest by; resting within its warmth restores more
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Campfire {
+    /// Tiles from the fire that still count as beside it
+    pub radius: i32,
+}
+
+/// The most piety a god will keep track of
+pub const MAX_PIETY: i32 = 100;
+/// Piety for each step up in the strength of Cleric abilities
+pub const PIETY_PER_BONUS: i32 = 20;
+
+/// The gods who hear prayers in the depths
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Deity {
+    Aurelion,
+    Khorga,
+    Sylvara,
+}
+
+impl Deity {
+    pub fn all() -> [Deity; 3] {
+        [Deity::Aurelion, Deity::Khorga, Deity::Sylvara]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Deity::Aurelion => "Aurelion",
+            Deity::Khorga => "Khorga",
+            Deity::Sylvara => "Sylvara",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Deity::Aurelion => "the Dawnfather",
+            Deity::Khorga => "the Red Hand",
+            Deity::Sylvara => "the Green Mother",
+        }
+    }
+
+    /// What pleases the god and what angers it
+    pub fn description(&self) -> &'static str {
+        match self {
+            Deity::Aurelion => "Loves the destruction of the undead. Raising the dead is an abomination.",
+            Deity::Khorga => "Loves every kill, and a great foe most of all. Scorns those who cast spells.",
+            Deity::Sylvara => "Loves the hunt that wastes nothing and the end of unnatural things. Hates fire and necromancy.",
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            Deity::Aurelion => crossterm::style::Color::Yellow,
+            Deity::Khorga => crossterm::style::Color::Red,
+            Deity::Sylvara => crossterm::style::Color::Green,
+        }
+    }
+}
+
+/// Whom a character worships, and how far they stand in the god's favour
+#[derive(Component, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[storage(VecStorage)]
+pub struct Piety {
+    pub deity: Deity,
+    pub piety: i32,
+}
+
+impl Piety {
+    pub fn new(deity: Deity) -> Self {
+        Piety { deity, piety: 0 }
+    }
+
+    /// How much stronger the god makes Cleric abilities
+    pub fn bonus(&self) -> i32 {
+        self.piety.clamp(0, MAX_PIETY) / PIETY_PER_BONUS
+    }
+
+    pub fn standing(&self) -> &'static str {
+        match self.piety {
+            i32::MIN..=9 => "Unnoticed",
+            10..=29 => "Noticed",
+            30..=59 => "Favoured",
+            60..=89 => "Champion",
+            _ => "Exalted",
+        }
+    }
+}
+
+/// A place to pray to one god, and to take it as your own
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Altar {
+    pub deity: Deity,
+}
+
+/// Dungeon furniture that does something when the player stops to use it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FurnitureKind {
+    /// Drink for a random effect, good or bad
+    Fountain,
+    /// Sacrifice an item for favour or a blessing
+    Shrine,
+    /// Opens a hidden door somewhere nearby
+    Lever,
+    /// Study for experience in a skill
+    Bookshelf,
+}
+
+impl FurnitureKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FurnitureKind::Fountain => "Fountain",
+            FurnitureKind::Shrine => "Shrine",
+            FurnitureKind::Lever => "Lever",
+            FurnitureKind::Bookshelf => "Bookshelf",
+        }
+    }
+
+    pub fn glyph(&self) -> char {
+        match self {
+            FurnitureKind::Fountain => '{',
+            FurnitureKind::Shrine => '^',
+            FurnitureKind::Lever => '/',
+            FurnitureKind::Bookshelf => '#',
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            FurnitureKind::Fountain => crossterm::style::Color::Cyan,
+            FurnitureKind::Shrine => crossterm::style::Color::White,
+            FurnitureKind::Lever => crossterm::style::Color::Grey,
+            FurnitureKind::Bookshelf => crossterm::style::Color::DarkYellow,
+        }
+    }
+
+    /// Turns before it can be used again, or None when it can only be used once
+    pub fn cooldown(&self) -> Option<u32> {
+        match self {
+            FurnitureKind::Fountain => Some(FOUNTAIN_REFILL_TURNS),
+            FurnitureKind::Shrine => Some(SHRINE_COOLDOWN_TURNS),
+            FurnitureKind::Lever | FurnitureKind::Bookshelf => None,
+        }
+    }
+}
+
+/// Turns a fountain takes to fill again after a drink
+pub const FOUNTAIN_REFILL_TURNS: u32 = 100;
+/// Turns before a shrine will take another offering
+pub const SHRINE_COOLDOWN_TURNS: u32 = 300;
+
+// A piece of furniture standing in the dungeon; bumping into it offers its uses
+#[derive(Component, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[storage(VecStorage)]
+pub struct Furniture {
+    pub kind: FurnitureKind,
+    /// Used up for good, for furniture that works only once
+    pub spent: bool,
+    /// Turn from which it can be used again
+    pub ready_turn: u32,
+    /// The hidden door a lever opens
+    pub opens: Option<(i32, i32)>,
+}
+
+impl Furniture {
+    pub fn new(kind: FurnitureKind) -> Self {
+        Furniture { kind, spent: false, ready_turn: 0, opens: None }
+    }
+
+    pub fn lever(opens: (i32, i32)) -> Self {
+        Furniture { opens: Some(opens), ..Furniture::new(FurnitureKind::Lever) }
+    }
+
+    pub fn is_ready(&self, turn: u32) -> bool {
+        !self.spent && turn >= self.ready_turn
+    }
+
+    /// Turns left before it can be used again; None once it is spent
+    pub fn turns_until_ready(&self, turn: u32) -> Option<u32> {
+        if self.spent {
+            return None;
+        }
+        Some(self.ready_turn.saturating_sub(turn))
+    }
+
+    /// Start the cooldown, or use it up if it only works once
+    pub fn mark_used(&mut self, turn: u32) {
+        match self.kind.cooldown() {
+            Some(cooldown) => self.ready_turn = turn + cooldown,
+            None => self.spent = true,
+        }
+    }
+}
+
+// Intent to use a piece of furniture, offering `sacrifice` if it is a shrine
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToUseFurniture {
+    pub furniture: specs::Entity,
+    pub sacrifice: Option<specs::Entity>,
+}
+
+/// Gas that hangs in the air, drifts into open neighbouring tiles and thins out over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GasKind {
+    PoisonGas,
+    /// The rot that rises off the walking dead
+    Miasma,
+    /// Scalding vapour
+    Steam,
+    /// Fungal spores that addle the mind
+    Spores,
+}
+
+impl GasKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GasKind::PoisonGas => "poison gas",
+            GasKind::Miasma => "miasma",
+            GasKind::Steam => "steam",
+            GasKind::Spores => "spores",
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            GasKind::PoisonGas => crossterm::style::Color::Green,
+            GasKind::Miasma => crossterm::style::Color::DarkMagenta,
+            GasKind::Steam => crossterm::style::Color::White,
+            GasKind::Spores => crossterm::style::Color::DarkYellow,
+        }
+    }
+
+    /// What breathing it in does to you
+    pub fn status(&self) -> StatusEffectType {
+        match self {
+            GasKind::PoisonGas => StatusEffectType::Poisoned,
+            GasKind::Miasma => StatusEffectType::Diseased,
+            GasKind::Steam => StatusEffectType::Burning,
+            GasKind::Spores => StatusEffectType::Confused,
+        }
+    }
+}
+
+// Lets loose a cloud of gas where it dies
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct ReleasesGas {
+    pub kind: GasKind,
+    pub volume: i32,
+}
+
+// A flask that bursts into a cloud of gas where it is thrown
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct GasFlask {
+    pub kind: GasKind,
+    pub volume: i32,
+}
+
+/// Marker for the out-of-depth elite shut in a greed vault; it costs the level none of its budget
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(NullStorage)]
+pub struct VaultGuardian;
+
+/// Someone held or stranded in the dungeon, waiting for the player to free them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptiveKind {
+    /// A trader taken by the dungeon's denizens; once freed, they need leading to the stairs
+    Merchant,
+    /// A lost delver who can find their own way out once found, and pays for the help up front
+    Adventurer,
+}
+
+impl CaptiveKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CaptiveKind::Merchant => "Captured Merchant",
+            CaptiveKind::Adventurer => "Lost Adventurer",
+        }
+    }
+
+    /// What they do for a living, for the log
+    pub fn role(&self) -> &'static str {
+        match self {
+            CaptiveKind::Merchant => "merchant",
+            CaptiveKind::Adventurer => "adventurer",
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            CaptiveKind::Merchant => crossterm::style::Color::Yellow,
+            CaptiveKind::Adventurer => crossterm::style::Color::Green,
+        }
+    }
+
+    /// Whether they have to be walked to the stairs before the reward is paid
+    pub fn needs_escort(&self) -> bool {
+        matches!(self, CaptiveKind::Merchant)
+    }
+}
+
+// Someone waiting to be freed; bumping into them sets them loose
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Captive {
+    pub kind: CaptiveKind,
+}
+
+// A freed captive following their rescuer to the stairs
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Escort {
+    #[serde(with = "crate::persistence::entity_refs")]
+    pub leader: specs::Entity,
+    pub kind: CaptiveKind,
+}
+
+// Intent to free a captive standing next to the rescuer
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToRescue {
+    pub captive: specs::Entity,
+}
+
+/// How a rival party stands with the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RivalStance {
+    /// Minding their own business, and open to trade
+    Neutral,
+    /// Fighting at the player's side
+    Allied,
+    /// Out for the player's blood
+    Hostile,
+}
+
+// A member of a rival adventuring party, exploring the dungeon on its own account
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Rival {
+    /// Shared by everyone in the same party
+    pub party: u32,
+    pub stance: RivalStance,
+    /// Rooms the party has been through on this level
+    pub rooms_explored: u32,
+}
+
+/// What the player puts to a rival party
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parley {
+    /// Buy something from their pack
+    Buy(specs::Entity),
+    /// Pay them to fight at the player's side
+    Ally,
+    /// Turn on them
+    Attack,
+}
+
+// Intent to deal with a rival party through one of its members
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToParley {
+    pub rival: specs::Entity,
+    pub offer: Parley,
+}
+
+/// What someone in Ravenhollow does for a living
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TownRole {
+    /// Minds the general store and takes payment for its wares
+    Shopkeeper,
+    /// Keeps the peace, and comes for whoever breaks it
+    Guard,
+    /// Goes about their day on the commons
+    Villager,
+    /// Keeps the books at the bank
+    Banker,
+    /// Tends the temple and takes offerings for the gods
+    Priest,
+    /// Throws dice on the square with anyone who has gold to lose
+    Gambler,
+    /// Retrains skills and attributes at the guild hall, and teaches other classes' abilities
+    Trainer,
+    /// Mends worn gear at the smithy, for gold
+    Blacksmith,
+}
+
+impl TownRole {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TownRole::Shopkeeper => "Shopkeeper",
+            TownRole::Guard => "Guard",
+            TownRole::Villager => "Villager",
+            TownRole::Banker => "Banker",
+            TownRole::Priest => "Priest",
+            TownRole::Gambler => "Gambler",
+            TownRole::Trainer => "Trainer",
+            TownRole::Blacksmith => "Blacksmith",
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            TownRole::Shopkeeper => crossterm::style::Color::Yellow,
+            TownRole::Guard => crossterm::style::Color::Blue,
+            TownRole::Villager => crossterm::style::Color::White,
+            TownRole::Banker => crossterm::style::Color::Green,
+            TownRole::Priest => crossterm::style::Color::Magenta,
+            TownRole::Gambler => crossterm::style::Color::DarkYellow,
+            TownRole::Trainer => crossterm::style::Color::Cyan,
+            TownRole::Blacksmith => crossterm::style::Color::Red,
+        }
+    }
+}
+
+// Someone who lives in Ravenhollow
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Townsperson {
+    pub role: TownRole,
+    /// Where they stand, or wander about, when nothing calls them away
+    pub post: (i32, i32),
+    /// Hurt by the player, and already reported to the watch
+    pub wronged: bool,
+}
+
+// A ware from the general store not yet paid for, whether still on the shelf or in the player's pack
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct ForSale {
+    /// Carried out of the shop, and reported to the watch
+    pub stolen: bool,
+}
+
+/// What the player does when dealing with someone in town
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TownDeal {
+    /// Pay the shopkeeper for the wares in the pack
+    PayForWares,
+    /// Pay the watch what is owed for crimes
+    PayFine,
+    /// Go quietly and serve time in the guardhouse cells instead
+    ServeSentence,
+    /// Leave this much gold with the bank
+    Deposit(i32),
+    /// Take this much gold out of the bank
+    Withdraw(i32),
+    /// Buy property in town out of the bank account
+    BuyProperty(crate::progression::Property),
+    /// Give this much gold to the temple in the name of the player's god
+    Donate(i32),
+    /// Stake this much gold on a throw of the dice
+    Gamble(i32),
+    /// Pay a trainer to teach an ability from another class
+    LearnAbility(AbilityType),
+    Attack,
+}
+
+// Intent to deal with someone in town
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToDeal {
+    pub townsperson: specs::Entity,
+    pub deal: TownDeal,
+}
+
+// How many turns in a row the wearer has spent unprotected against a level's cold or heat
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct Exposure {
+    pub turns: i32,
+}
+
+// Intent to throw an item from the pack at a tile
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToThrow {
+    pub item: specs::Entity,
+    pub target: (i32, i32),
+}
+
+// Companion components
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Companion {
+    #[serde(with = "crate::persistence::entity_refs")]
+    pub owner: specs::Entity,
+    pub kind: CompanionKind,
+    pub turns_remaining: Option<i32>, // None for companions that stay until killed or dismissed
+    pub summoned_turn: u32,
+    pub follow_distance: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum CompanionKind {
+    Wolf,
+    Hawk,
+    Bear,
+    Skeleton,
+    Zombie,
+}
+
+impl CompanionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompanionKind::Wolf => "Wolf",
+            CompanionKind::Hawk => "Hawk",
+            CompanionKind::Bear => "Bear",
+            CompanionKind::Skeleton => "Skeleton",
+            CompanionKind::Zombie => "Zombie",
+        }
+    }
+    
+    pub fn glyph(&self) -> char {
+        match self {
+            CompanionKind::Wolf => 'w',
+            CompanionKind::Hawk => 'h',
+            CompanionKind::Bear => 'B',
+            CompanionKind::Skeleton => 's',
+            CompanionKind::Zombie => 'z',
+        }
+    }
+    
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            CompanionKind::Wolf => crossterm::style::Color::Grey,
+            CompanionKind::Hawk => crossterm::style::Color::DarkYellow,
+            CompanionKind::Bear => crossterm::style::Color::DarkYellow,
+            CompanionKind::Skeleton => crossterm::style::Color::White,
+            CompanionKind::Zombie => crossterm::style::Color::DarkGreen,
+        }
+    }
+    
+    /// Base combat stats, scaled by the summoner's level
+    pub fn stats(&self, owner_level: i32) -> CombatStats {
+        let (hp, power, defense) = match self {
+            CompanionKind::Wolf => (12, 4, 1),
+            CompanionKind::Hawk => (6, 3, 0),
+            CompanionKind::Bear => (20, 5, 2),
+            CompanionKind::Skeleton => (10, 4, 2),
+            CompanionKind::Zombie => (16, 3, 1),
+        };
+        let bonus = (owner_level - 1).max(0);
+        CombatStats {
+            max_hp: hp + bonus * 2,
+            hp: hp + bonus * 2,
+            power: power + bonus / 2,
+            defense: defense + bonus / 3,
+        }
+    }
+    
+    /// Undead minions crumble after a while; animals stay until they fall
+    pub fn default_duration(&self) -> Option<i32> {
+        match self {
+            CompanionKind::Skeleton | CompanionKind::Zombie => Some(60),
+            _ => None,
+        }
+    }
+}
+
+// Intent to summon a companion next to the summoner
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToSummon {
+    pub kind: CompanionKind,
+}
+
+// Intent to send a companion away
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToDismissCompanion {
+    #[serde(with = "crate::persistence::entity_refs")]
+    pub companion: specs::Entity,
+}
+
+// Last entity to deal damage, used for kill credit
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct LastAttacker {
+    #[serde(with = "crate::persistence::entity_refs")]
+    pub entity: specs::Entity,
+}
@@ -0,0 +1,617 @@
+//! Game options in one place, kept in `config.toml` beside the game.
+//!
+//! The file is read at startup and watched while the game runs, so an edit made in a text editor
+//! takes effect within a second. Changes made on the Options screen are written straight back.
+//! Anything missing from the file keeps its default, and unknown keys are ignored.
+
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::components::GameSettings;
+use crate::rendering::{ColorMode, GlyphMode, MotionSettings, ANIMATION_SPEED_RANGE};
+use crate::localization::{self, tr, FALLBACK_LANGUAGE};
+use crate::accessibility::TRANSCRIPT_PATH;
+use crate::language_model::ProviderKind;
+
+/// Where the options are kept
+pub const CONFIG_PATH: &str = "config.toml";
+
+/// How often the file is checked for edits made outside the game
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// Language tag such as `es` or `pt-BR`; see `localization`
+    pub language: String,
+    pub display: DisplayConfig,
+    pub audio: AudioConfig,
+    pub gameplay: GameplayConfig,
+    pub tutorial: TutorialConfig,
+    pub accessibility: AccessibilityConfig,
+    pub language_model: LlmConfig,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            language: FALLBACK_LANGUAGE.to_string(),
+            display: DisplayConfig::default(),
+            audio: AudioConfig::default(),
+            gameplay: GameplayConfig::default(),
+            tutorial: TutorialConfig::default(),
+            accessibility: AccessibilityConfig::default(),
+            language_model: LlmConfig::default(),
+        }
+    }
+}
+
+/// Which glyphs to draw with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlyphChoice {
+    /// Unicode when the locale says UTF-8, or as `ASCII_DUNGEON_GLYPHS` says
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+impl GlyphChoice {
+    pub fn mode(self) -> GlyphMode {
+        match self {
+            GlyphChoice::Auto => GlyphMode::detect(),
+            GlyphChoice::Unicode => GlyphMode::Unicode,
+            GlyphChoice::Ascii => GlyphMode::Ascii,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub colors: ColorMode,
+    pub glyphs: GlyphChoice,
+    /// Playback speed of attack and death animations; 2.0 plays twice as fast
+    pub animation_speed: f32,
+    pub skip_animations: bool,
+    pub weather_effects: bool,
+    /// Write an HTML page with each screenshot as well as text and ANSI
+    pub screenshot_html: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            colors: ColorMode::Full,
+            glyphs: GlyphChoice::Auto,
+            animation_speed: 1.0,
+            skip_animations: false,
+            weather_effects: true,
+            screenshot_html: false,
+        }
+    }
+}
+
+/// Volumes from 0.0 (silent) to 1.0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig { master_volume: 0.8, music_volume: 0.7, effects_volume: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameplayConfig {
+    /// Minutes between autosaves; 0 turns them off
+    pub autosave_interval_minutes: u32,
+    pub verbose_combat: bool,
+    /// Blood and bones where creatures fall
+    pub gore: bool,
+    /// Walking into a closed door opens it
+    pub bump_to_open: bool,
+}
+
+impl Default for GameplayConfig {
+    fn default() -> Self {
+        GameplayConfig { autosave_interval_minutes: 5, verbose_combat: false, gore: true, bump_to_open: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TutorialConfig {
+    /// Walk new players through their first run
+    pub enabled: bool,
+    /// Point out things the player hasn't met before, such as a first shrine or locked door
+    pub hints: bool,
+}
+
+impl Default for TutorialConfig {
+    fn default() -> Self {
+        TutorialConfig { enabled: true, hints: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Keep a plain-text transcript of events and menus for a screen reader to follow
+    pub screen_reader: bool,
+    /// Where the transcript is written
+    pub transcript: String,
+    /// No screen shake, and particles held where they start
+    pub reduced_motion: bool,
+    /// No flashing or blinking colors
+    pub no_flash: bool,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        AccessibilityConfig {
+            screen_reader: false,
+            transcript: TRANSCRIPT_PATH.to_string(),
+            reduced_motion: false,
+            no_flash: false,
+        }
+    }
+}
+
+/// Where generated text, such as the guild's contract reports, comes from. With no provider
+/// everything is told from templates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    pub provider: ProviderKind,
+    /// The model file for `local`, or the model's name for `openai`
+    pub model: String,
+    /// Base URL for `llama_server` and `openai`; left empty, each uses its usual one
+    pub endpoint: String,
+    /// The environment variable holding the API key, so the key itself stays out of this file
+    pub api_key_env: String,
+    /// Longest to wait on one answer
+    pub timeout_seconds: u64,
+    /// Times to ask again after a timeout or a server error
+    pub retries: u32,
+    /// Tokens a session may spend on every request together; 0 for no limit
+    pub token_budget: u32,
+    /// Requests that may wait on the model at once; any more are turned away
+    pub queue_size: usize,
+    /// Answers kept on disk to reuse when the same thing is asked again; 0 keeps none
+    pub cache_size: usize,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        LlmConfig {
+            provider: ProviderKind::None,
+            model: String::new(),
+            endpoint: String::new(),
+            api_key_env: "OPENAI_API_KEY".to_string(),
+            timeout_seconds: 20,
+            retries: 2,
+            token_budget: 50_000,
+            queue_size: 16,
+            cache_size: 256,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Time between autosaves, if they are on
+    pub fn autosave_interval(&self) -> Option<Duration> {
+        match self.gameplay.autosave_interval_minutes {
+            0 => None,
+            minutes => Some(Duration::from_secs(minutes as u64 * 60)),
+        }
+    }
+
+    /// What effects may shake, scatter or flash
+    pub fn motion(&self) -> MotionSettings {
+        MotionSettings {
+            reduced_motion: self.accessibility.reduced_motion,
+            no_flash: self.accessibility.no_flash,
+        }
+    }
+
+    /// Carry the options a run keeps with its character over to its settings
+    pub fn apply_to(&self, settings: &mut GameSettings) {
+        settings.animation_speed = self.display.animation_speed;
+        settings.skip_animations = self.display.skip_animations;
+        settings.disable_weather_effects = !self.display.weather_effects;
+        settings.verbose_combat = self.gameplay.verbose_combat;
+        settings.disable_gore = !self.gameplay.gore;
+        settings.bump_to_open = self.gameplay.bump_to_open;
+    }
+
+    /// Pull anything out of range back into it
+    fn clamped(mut self) -> Self {
+        let (slowest, fastest) = ANIMATION_SPEED_RANGE;
+        self.display.animation_speed = self.display.animation_speed.clamp(slowest, fastest);
+        for volume in [&mut self.audio.master_volume, &mut self.audio.music_volume, &mut self.audio.effects_volume] {
+            *volume = volume.clamp(0.0, 1.0);
+        }
+        self.gameplay.autosave_interval_minutes = self.gameplay.autosave_interval_minutes.min(60);
+        self.language_model.timeout_seconds = self.language_model.timeout_seconds.clamp(1, 300);
+        self.language_model.retries = self.language_model.retries.min(5);
+        self.language_model.queue_size = self.language_model.queue_size.clamp(1, 256);
+        self.language_model.cache_size = self.language_model.cache_size.min(4096);
+        self.language = localization::normalize_language(&self.language);
+        if self.language.is_empty() {
+            self.language = FALLBACK_LANGUAGE.to_string();
+        }
+        self
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str::<GameConfig>(text)
+            .map(GameConfig::clamped)
+            .map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        toml::to_string_pretty(self).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(msg) => write!(f, "{}", msg),
+            ConfigError::Parse(msg) => write!(f, "{} is not valid: {}", CONFIG_PATH, msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(error: std::io::Error) -> Self {
+        ConfigError::Io(error.to_string())
+    }
+}
+
+/// The options and the file they live in, noticing when the file is edited
+pub struct ConfigFile {
+    path: PathBuf,
+    pub config: GameConfig,
+    /// The file as last read or written, to tell edits apart from our own saves
+    last_text: Option<String>,
+    last_check: Instant,
+}
+
+impl ConfigFile {
+    /// Read the options at `path`, or start from the defaults when there is no file yet. A file
+    /// that does not parse is left alone and reported, and the defaults are used meanwhile.
+    pub fn load(path: impl AsRef<Path>) -> (Self, Option<ConfigError>) {
+        let mut file = ConfigFile {
+            path: path.as_ref().to_path_buf(),
+            config: GameConfig::default(),
+            last_text: None,
+            last_check: Instant::now(),
+        };
+        let error = file.reload().err();
+        (file, error)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the options out, creating the file if need be
+    pub fn save(&mut self) -> Result<(), ConfigError> {
+        let text = self.config.to_toml()?;
+        fs::write(&self.path, &text)?;
+        self.last_text = Some(text);
+        Ok(())
+    }
+
+    /// Read the file again if it changed since it was last read or written; `Ok(true)` when the
+    /// options did
+    pub fn reload(&mut self) -> Result<bool, ConfigError> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        if self.last_text.as_deref() == Some(text.as_str()) {
+            return Ok(false);
+        }
+        // Remember the text even when it fails to parse, so a broken file is reported once
+        self.last_text = Some(text.clone());
+        let config = GameConfig::from_toml(&text)?;
+        let changed = config != self.config;
+        self.config = config;
+        Ok(changed)
+    }
+
+    /// `reload`, at most once a second; call every update
+    pub fn poll(&mut self) -> Result<bool, ConfigError> {
+        if self.last_check.elapsed() < RELOAD_INTERVAL {
+            return Ok(false);
+        }
+        self.last_check = Instant::now();
+        self.reload()
+    }
+}
+
+/// One line of the Options screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigOption {
+    Language,
+    Colors,
+    Glyphs,
+    AnimationSpeed,
+    SkipAnimations,
+    WeatherEffects,
+    ScreenshotHtml,
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    AutosaveInterval,
+    VerboseCombat,
+    Gore,
+    BumpToOpen,
+    Tutorial,
+    TutorialHints,
+    ScreenReader,
+    ReducedMotion,
+    NoFlash,
+}
+
+impl ConfigOption {
+    pub const ALL: [ConfigOption; 19] = [
+        ConfigOption::Language,
+        ConfigOption::Colors,
+        ConfigOption::Glyphs,
+        ConfigOption::AnimationSpeed,
+        ConfigOption::SkipAnimations,
+        ConfigOption::WeatherEffects,
+        ConfigOption::ScreenshotHtml,
+        ConfigOption::MasterVolume,
+        ConfigOption::MusicVolume,
+        ConfigOption::EffectsVolume,
+        ConfigOption::AutosaveInterval,
+        ConfigOption::VerboseCombat,
+        ConfigOption::Gore,
+        ConfigOption::BumpToOpen,
+        ConfigOption::Tutorial,
+        ConfigOption::TutorialHints,
+        ConfigOption::ScreenReader,
+        ConfigOption::ReducedMotion,
+        ConfigOption::NoFlash,
+    ];
+
+    /// The message key of the option's name
+    pub fn key(self) -> &'static str {
+        match self {
+            ConfigOption::Language => "options.language",
+            ConfigOption::Colors => "options.colors",
+            ConfigOption::Glyphs => "options.glyphs",
+            ConfigOption::AnimationSpeed => "options.animation_speed",
+            ConfigOption::SkipAnimations => "options.skip_animations",
+            ConfigOption::WeatherEffects => "options.weather_effects",
+            ConfigOption::ScreenshotHtml => "options.screenshot_html",
+            ConfigOption::MasterVolume => "options.master_volume",
+            ConfigOption::MusicVolume => "options.music_volume",
+            ConfigOption::EffectsVolume => "options.effects_volume",
+            ConfigOption::AutosaveInterval => "options.autosave_interval",
+            ConfigOption::VerboseCombat => "options.verbose_combat",
+            ConfigOption::Gore => "options.gore",
+            ConfigOption::BumpToOpen => "options.bump_to_open",
+            ConfigOption::Tutorial => "options.tutorial",
+            ConfigOption::TutorialHints => "options.tutorial_hints",
+            ConfigOption::ScreenReader => "options.screen_reader",
+            ConfigOption::ReducedMotion => "options.reduced_motion",
+            ConfigOption::NoFlash => "options.no_flash",
+        }
+    }
+
+    pub fn label(self) -> String {
+        tr(self.key())
+    }
+
+    pub fn value(self, config: &GameConfig) -> String {
+        let on_off = |on: bool| tr(if on { "options.on" } else { "options.off" });
+        let percent = |volume: f32| format!("{:.0}%", volume * 100.0);
+        match self {
+            ConfigOption::Language => localization::language_name(&config.language),
+            ConfigOption::Colors => tr(match config.display.colors {
+                ColorMode::Full => "options.colors_full",
+                ColorMode::Basic => "options.colors_basic",
+                ColorMode::Monochrome => "options.colors_monochrome",
+            }),
+            ConfigOption::Glyphs => tr(match config.display.glyphs {
+                GlyphChoice::Auto => "options.glyphs_auto",
+                GlyphChoice::Unicode => "options.glyphs_unicode",
+                GlyphChoice::Ascii => "options.glyphs_ascii",
+            }),
+            ConfigOption::AnimationSpeed => format!("{:.2}x", config.display.animation_speed),
+            ConfigOption::SkipAnimations => on_off(config.display.skip_animations),
+            ConfigOption::WeatherEffects => on_off(config.display.weather_effects),
+            ConfigOption::ScreenshotHtml => on_off(config.display.screenshot_html),
+            ConfigOption::MasterVolume => percent(config.audio.master_volume),
+            ConfigOption::MusicVolume => percent(config.audio.music_volume),
+            ConfigOption::EffectsVolume => percent(config.audio.effects_volume),
+            ConfigOption::AutosaveInterval => match config.gameplay.autosave_interval_minutes {
+                0 => tr("options.off"),
+                minutes => localization::Message::new("options.minutes").arg("minutes", minutes).text(),
+            },
+            ConfigOption::VerboseCombat => on_off(config.gameplay.verbose_combat),
+            ConfigOption::Gore => on_off(config.gameplay.gore),
+            ConfigOption::BumpToOpen => on_off(config.gameplay.bump_to_open),
+            ConfigOption::Tutorial => on_off(config.tutorial.enabled),
+            ConfigOption::TutorialHints => on_off(config.tutorial.hints),
+            ConfigOption::ScreenReader => on_off(config.accessibility.screen_reader),
+            ConfigOption::ReducedMotion => on_off(config.accessibility.reduced_motion),
+            ConfigOption::NoFlash => on_off(config.accessibility.no_flash),
+        }
+    }
+
+    /// Step the option up (`step` > 0) or down; switches flip either way
+    pub fn adjust(self, config: &mut GameConfig, step: i32) {
+        let volume = |volume: &mut f32| *volume = (*volume + 0.1 * step.signum() as f32).clamp(0.0, 1.0);
+        match self {
+            ConfigOption::Language => {
+                let languages = localization::languages();
+                let index = languages.iter().position(|language| *language == config.language).unwrap_or(0) as i32;
+                let next = (index + step.signum()).rem_euclid(languages.len() as i32) as usize;
+                config.language = languages[next].clone();
+            },
+            ConfigOption::Colors => {
+                const MODES: [ColorMode; 3] = [ColorMode::Full, ColorMode::Basic, ColorMode::Monochrome];
+                config.display.colors = cycle(&MODES, config.display.colors, step);
+            },
+            ConfigOption::Glyphs => {
+                const CHOICES: [GlyphChoice; 3] = [GlyphChoice::Auto, GlyphChoice::Unicode, GlyphChoice::Ascii];
+                config.display.glyphs = cycle(&CHOICES, config.display.glyphs, step);
+            },
+            ConfigOption::AnimationSpeed => {
+                let (slowest, fastest) = ANIMATION_SPEED_RANGE;
+                let speed = config.display.animation_speed + 0.25 * step.signum() as f32;
+                config.display.animation_speed = speed.clamp(slowest, fastest);
+            },
+            ConfigOption::SkipAnimations => config.display.skip_animations = !config.display.skip_animations,
+            ConfigOption::WeatherEffects => config.display.weather_effects = !config.display.weather_effects,
+            ConfigOption::ScreenshotHtml => config.display.screenshot_html = !config.display.screenshot_html,
+            ConfigOption::MasterVolume => volume(&mut config.audio.master_volume),
+            ConfigOption::MusicVolume => volume(&mut config.audio.music_volume),
+            ConfigOption::EffectsVolume => volume(&mut config.audio.effects_volume),
+            ConfigOption::AutosaveInterval => {
+                let minutes = config.gameplay.autosave_interval_minutes as i32 + step.signum();
+                config.gameplay.autosave_interval_minutes = minutes.clamp(0, 60) as u32;
+            },
+            ConfigOption::VerboseCombat => config.gameplay.verbose_combat = !config.gameplay.verbose_combat,
+            ConfigOption::Gore => config.gameplay.gore = !config.gameplay.gore,
+            ConfigOption::BumpToOpen => config.gameplay.bump_to_open = !config.gameplay.bump_to_open,
+            ConfigOption::Tutorial => config.tutorial.enabled = !config.tutorial.enabled,
+            ConfigOption::TutorialHints => config.tutorial.hints = !config.tutorial.hints,
+            ConfigOption::ScreenReader => config.accessibility.screen_reader = !config.accessibility.screen_reader,
+            ConfigOption::ReducedMotion => config.accessibility.reduced_motion = !config.accessibility.reduced_motion,
+            ConfigOption::NoFlash => config.accessibility.no_flash = !config.accessibility.no_flash,
+        }
+    }
+
+    /// How far along its range a sliding option sits, from 0.0 to 1.0; None for switches and choices
+    pub fn slider(self, config: &GameConfig) -> Option<f32> {
+        match self {
+            ConfigOption::AnimationSpeed => {
+                let (slowest, fastest) = ANIMATION_SPEED_RANGE;
+                Some((config.display.animation_speed - slowest) / (fastest - slowest))
+            },
+            ConfigOption::MasterVolume => Some(config.audio.master_volume),
+            ConfigOption::MusicVolume => Some(config.audio.music_volume),
+            ConfigOption::EffectsVolume => Some(config.audio.effects_volume),
+            ConfigOption::AutosaveInterval => Some(config.gameplay.autosave_interval_minutes as f32 / 60.0),
+            _ => None,
+        }
+    }
+
+    /// Whether an on/off switch is on; None for sliders and choices
+    pub fn switch(self, config: &GameConfig) -> Option<bool> {
+        match self {
+            ConfigOption::SkipAnimations => Some(config.display.skip_animations),
+            ConfigOption::WeatherEffects => Some(config.display.weather_effects),
+            ConfigOption::ScreenshotHtml => Some(config.display.screenshot_html),
+            ConfigOption::VerboseCombat => Some(config.gameplay.verbose_combat),
+            ConfigOption::Gore => Some(config.gameplay.gore),
+            ConfigOption::BumpToOpen => Some(config.gameplay.bump_to_open),
+            ConfigOption::Tutorial => Some(config.tutorial.enabled),
+            ConfigOption::TutorialHints => Some(config.tutorial.hints),
+            ConfigOption::ScreenReader => Some(config.accessibility.screen_reader),
+            ConfigOption::ReducedMotion => Some(config.accessibility.reduced_motion),
+            ConfigOption::NoFlash => Some(config.accessibility.no_flash),
+            _ => None,
+        }
+    }
+
+    /// Put the option back to its default
+    pub fn reset(self, config: &mut GameConfig) {
+        let defaults = GameConfig::default();
+        match self {
+            ConfigOption::Language => config.language = defaults.language,
+            ConfigOption::Colors => config.display.colors = defaults.display.colors,
+            ConfigOption::Glyphs => config.display.glyphs = defaults.display.glyphs,
+            ConfigOption::AnimationSpeed => config.display.animation_speed = defaults.display.animation_speed,
+            ConfigOption::SkipAnimations => config.display.skip_animations = defaults.display.skip_animations,
+            ConfigOption::WeatherEffects => config.display.weather_effects = defaults.display.weather_effects,
+            ConfigOption::ScreenshotHtml => config.display.screenshot_html = defaults.display.screenshot_html,
+            ConfigOption::MasterVolume => config.audio.master_volume = defaults.audio.master_volume,
+            ConfigOption::MusicVolume => config.audio.music_volume = defaults.audio.music_volume,
+            ConfigOption::EffectsVolume => config.audio.effects_volume = defaults.audio.effects_volume,
+            ConfigOption::AutosaveInterval => config.gameplay.autosave_interval_minutes = defaults.gameplay.autosave_interval_minutes,
+            ConfigOption::VerboseCombat => config.gameplay.verbose_combat = defaults.gameplay.verbose_combat,
+            ConfigOption::Gore => config.gameplay.gore = defaults.gameplay.gore,
+            ConfigOption::BumpToOpen => config.gameplay.bump_to_open = defaults.gameplay.bump_to_open,
+            ConfigOption::Tutorial => config.tutorial.enabled = defaults.tutorial.enabled,
+            ConfigOption::TutorialHints => config.tutorial.hints = defaults.tutorial.hints,
+            ConfigOption::ScreenReader => config.accessibility.screen_reader = defaults.accessibility.screen_reader,
+            ConfigOption::ReducedMotion => config.accessibility.reduced_motion = defaults.accessibility.reduced_motion,
+            ConfigOption::NoFlash => config.accessibility.no_flash = defaults.accessibility.no_flash,
+        }
+    }
+}
+
+/// The choice after (or before) `current`, wrapping around
+fn cycle<T: Copy + PartialEq>(choices: &[T], current: T, step: i32) -> T {
+    let index = choices.iter().position(|&choice| choice == current).unwrap_or(0) as i32;
+    choices[(index + step.signum()).rem_euclid(choices.len() as i32) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_options_keep_their_defaults_and_bad_values_are_pulled_into_range() {
+        let config = GameConfig::from_toml("[display]\ncolors = \"monochrome\"\nanimation_speed = 50.0\n\n[audio]\nmusic_volume = 0.25\n").unwrap();
+        assert_eq!(config.display.colors, ColorMode::Monochrome);
+        assert_eq!(config.display.animation_speed, ANIMATION_SPEED_RANGE.1);
+        assert_eq!(config.audio.music_volume, 0.25);
+        assert_eq!(config.audio.master_volume, AudioConfig::default().master_volume);
+        assert_eq!(config.gameplay, GameplayConfig::default());
+
+        assert_eq!(GameConfig::from_toml(&config.to_toml().unwrap()).unwrap(), config);
+        assert!(GameConfig::from_toml("[display]\ncolors = 3").is_err());
+        assert_eq!(GameConfig::from_toml("language = \"pt_BR\"").unwrap().language, "pt-br");
+    }
+
+    #[test]
+    fn test_edits_to_the_file_are_picked_up_but_our_own_saves_are_not() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_PATH);
+        let (mut file, error) = ConfigFile::load(&path);
+        assert!(error.is_none());
+        assert_eq!(file.config, GameConfig::default());
+
+        ConfigOption::Gore.adjust(&mut file.config, 1);
+        file.save().unwrap();
+        assert_eq!(file.reload(), Ok(false));
+
+        fs::write(&path, "[gameplay]\nautosave_interval_minutes = 0\n").unwrap();
+        assert_eq!(file.reload(), Ok(true));
+        assert_eq!(file.config.autosave_interval(), None);
+        assert!(file.config.gameplay.gore);
+
+        fs::write(&path, "not = [toml").unwrap();
+        assert!(file.reload().is_err());
+        assert_eq!(file.reload(), Ok(false));
+        assert_eq!(file.config.gameplay.autosave_interval_minutes, 0);
+    }
+}
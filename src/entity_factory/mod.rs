@@ -1,7 +1,21 @@
 use specs::{World, WorldExt, Builder, Entity};
+use specs::saveload::{MarkedBuilder, SimpleMarker};
 use crate::components::*;
-use crate::map::TileType;
-use crate::resources::RandomNumberGenerator;
+use crate::map::{TileType, EnemyType, DungeonBranch, FurniturePlacement, CaptivePlacement, StationKind, StationPlacement};
+use crate::resources::{RandomNumberGenerator, MAPGEN_STREAM};
+use crate::utils::{NameGenerator, NameStyle};
+use crate::items::{AdvancedInventory, AlchemyStation, EnchantingStation, Equipment, ItemProperties, ItemType, ConsumableType, ConsumableEffect, Consumable as ItemsConsumable};
+
+/// Elite affixes every greed vault guardian carries
+const VAULT_GUARDIAN_AFFIXES: usize = 2;
+
+/// What a rival adventurer does for a living: their title, then health, defence and power before
+/// depth is taken into account
+const RIVAL_CALLINGS: [(&str, i32, i32, i32); 3] = [
+    ("Fighter", 22, 2, 5),
+    ("Rogue", 16, 1, 4),
+    ("Mage", 12, 0, 6),
+];
 
 pub struct EntityFactory;
 
@@ -9,6 +23,7 @@ impl EntityFactory {
     // Create a player entity
     pub fn create_player(world: &mut World, x: i32, y: i32) -> Entity {
         world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
             .with(Position { x, y })
             .with(Renderable {
                 glyph: '@',
@@ -33,6 +48,8 @@ impl EntityFactory {
             })
             .with(PlayerInput::new())
             .with(Inventory::new(26))
+            .with(AdvancedInventory::new(26, 150.0))
+            .with(Equipment::new())
             .with(Experience::new())
             .build()
     }
@@ -47,6 +64,7 @@ impl EntityFactory {
         };
         
         world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
             .with(Position { x, y })
             .with(Renderable {
                 glyph,
@@ -73,9 +91,252 @@ impl EntityFactory {
             .build()
     }
     
+    // Create an enemy chosen by the spawn director, scaled for the run's difficulty
+    pub fn create_enemy(world: &mut World, x: i32, y: i32, enemy: EnemyType, difficulty: Difficulty) -> Entity {
+        let (hp, defense, power) = enemy.base_stats();
+        let hp = difficulty.scale_monster_stat(hp);
+        let power = difficulty.scale_monster_stat(power);
+        
+        let entity = world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x, y })
+            .with(Renderable {
+                glyph: enemy.glyph(),
+                fg: (255, 0, 0),
+                bg: (0, 0, 0),
+                render_order: 1,
+            })
+            .with(Viewshed {
+                visible_tiles: Vec::new(),
+                range: 6,
+                dirty: true,
+            })
+            .with(Name {
+                name: enemy.name().to_string(),
+            })
+            .with(BlocksTile {})
+            .with(CombatStats {
+                max_hp: hp,
+                hp,
+                defense,
+                power,
+            })
+            .with(Monster {})
+            .with(MonsterAI::new((x, y), enemy.alertness()).with_behavior(&enemy.name().to_lowercase()))
+            .with(Morale::new(enemy.flee_below(), enemy.can_surrender()))
+            .build();
+        
+        if enemy.is_undead() {
+            world.write_storage::<Undead>()
+                .insert(entity, Undead)
+                .expect("Unable to insert undead marker");
+        }
+        if enemy.opens_doors() {
+            world.write_storage::<OpensDoors>()
+                .insert(entity, OpensDoors)
+                .expect("Unable to insert door opener marker");
+        }
+        if enemy.movement_mode() != MovementMode::Walking {
+            world.write_storage::<MovementMode>()
+                .insert(entity, enemy.movement_mode())
+                .expect("Unable to insert movement mode");
+        }
+        if enemy == EnemyType::Dragon {
+            world.write_storage::<BreathWeapon>()
+                .insert(entity, BreathWeapon::fire())
+                .expect("Unable to insert breath weapon");
+        }
+        if let Some((kind, volume)) = enemy.death_gas() {
+            world.write_storage::<ReleasesGas>()
+                .insert(entity, ReleasesGas { kind, volume })
+                .expect("Unable to insert death gas");
+        }
+        entity
+    }
+    
+    // Put a monster in its leader's pack, making the leader the head of it if it is not already
+    pub fn join_pack(world: &mut World, entity: Entity, leader: Entity, tactics: PackTactics) {
+        let mut packs = world.write_storage::<PackMember>();
+        if !packs.contains(leader) {
+            packs.insert(leader, PackMember { leader, tactics })
+                .expect("Unable to insert pack leader");
+        }
+        packs.insert(entity, PackMember { leader, tactics })
+            .expect("Unable to insert pack member");
+    }
+    
+    // Turn a freshly spawned enemy into an elite with the given affixes
+    pub fn make_elite(world: &mut World, entity: Entity, affixes: Vec<EliteAffix>) {
+        let first = match affixes.first() {
+            Some(affix) => *affix,
+            None => return,
+        };
+        
+        if let Some(stats) = world.write_storage::<CombatStats>().get_mut(entity) {
+            for affix in &affixes {
+                match affix {
+                    EliteAffix::Fast => stats.power += 1,
+                    EliteAffix::Armored => {
+                        stats.defense += 3;
+                        stats.max_hp += stats.max_hp / 4;
+                    },
+                    EliteAffix::Vampiric => stats.max_hp += stats.max_hp / 5,
+                    EliteAffix::Splitting => stats.max_hp += stats.max_hp / 2,
+                }
+            }
+            stats.hp = stats.max_hp;
+        }
+        if affixes.contains(&EliteAffix::Fast) {
+            world.write_storage::<Initiative>()
+                .insert(entity, Initiative::new(8))
+                .expect("Unable to insert initiative");
+        }
+        if let Some(renderable) = world.write_storage::<Renderable>().get_mut(entity) {
+            renderable.fg = first.color();
+        }
+        world.write_storage::<Elite>()
+            .insert(entity, Elite { affixes })
+            .expect("Unable to insert elite affixes");
+    }
+    
+    // Create a named champion of the given kind, e.g. "Grulk the Flayer", tougher than its kin
+    pub fn create_unique_enemy(world: &mut World, x: i32, y: i32, enemy: EnemyType, difficulty: Difficulty) -> Entity {
+        let style = match enemy {
+            EnemyType::Goblin | EnemyType::Orc | EnemyType::Troll => NameStyle::Orcish,
+            EnemyType::Skeleton | EnemyType::Ghost | EnemyType::Demon | EnemyType::Dragon => NameStyle::Elder,
+            _ => NameStyle::Human,
+        };
+        let name = {
+            let mut rng = world.write_resource::<RandomNumberGenerator>();
+            NameGenerator::unique_enemy_name(style, &mut rng)
+        };
+        
+        let entity = Self::create_enemy(world, x, y, enemy, difficulty);
+        world.write_storage::<Name>()
+            .insert(entity, Name { name })
+            .expect("Unable to rename unique enemy");
+        world.write_storage::<UniqueEnemy>()
+            .insert(entity, UniqueEnemy)
+            .expect("Unable to insert unique enemy");
+        if let Some(stats) = world.write_storage::<CombatStats>().get_mut(entity) {
+            stats.max_hp *= 2;
+            stats.hp = stats.max_hp;
+            stats.power += 2;
+        }
+        entity
+    }
+    
+    // Create the guardian of a greed vault: a monster from deeper down, always an elite, that
+    // keeps to its hoard and leaves the level's budget alone
+    pub fn create_vault_guardian(world: &mut World, x: i32, y: i32, enemy: EnemyType, difficulty: Difficulty) -> Entity {
+        let entity = Self::create_enemy(world, x, y, enemy, difficulty);
+        let affixes = {
+            let mut rng = world.write_resource::<RandomNumberGenerator>();
+            EliteAffix::pick(VAULT_GUARDIAN_AFFIXES, rng.stream(MAPGEN_STREAM))
+        };
+        Self::make_elite(world, entity, affixes);
+        world.write_storage::<VaultGuardian>()
+            .insert(entity, VaultGuardian)
+            .expect("Unable to insert vault guardian marker");
+        entity
+    }
+
+    // Create the guardian of a branch's deepest level; the main dungeon's guards the victory artifact
+    pub fn create_branch_boss(world: &mut World, x: i32, y: i32, branch: DungeonBranch, difficulty: Difficulty) -> Option<Entity> {
+        let (enemy, name) = branch.boss()?;
+        let (hp, defense, power) = enemy.base_stats();
+        let hp = difficulty.scale_monster_stat(hp);
+        let power = difficulty.scale_monster_stat(power);
+        let boss_type = if branch == DungeonBranch::Main { BossType::FinalBoss } else { BossType::AreaBoss };
+        
+        if boss_type == BossType::FinalBoss {
+            Self::create_victory_artifact(world, x, y);
+        }
+        
+        Some(world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x, y })
+            .with(Renderable {
+                glyph: enemy.glyph().to_ascii_uppercase(),
+                fg: (255, 0, 255),
+                bg: (0, 0, 0),
+                render_order: 1,
+            })
+            .with(Viewshed {
+                visible_tiles: Vec::new(),
+                range: 8,
+                dirty: true,
+            })
+            .with(Name {
+                name: name.to_string(),
+            })
+            .with(BlocksTile {})
+            .with(CombatStats {
+                max_hp: hp * 3,
+                hp: hp * 3,
+                defense: defense + 2,
+                power: power + 3,
+            })
+            .with(Monster {})
+            .with(BossEnemy {
+                boss_type,
+                difficulty_multiplier: 3.0,
+                guaranteed_drops: Vec::new(),
+            })
+            .build())
+    }
+    
+    // Create the artifact that must be carried back to the surface
+    pub fn create_victory_artifact(world: &mut World, x: i32, y: i32) -> Entity {
+        world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x, y })
+            .with(Renderable {
+                glyph: '"',
+                fg: (255, 215, 0),
+                bg: (0, 0, 0),
+                render_order: 2,
+            })
+            .with(Name {
+                name: "Amulet of the Depths".to_string(),
+            })
+            .with(Item {})
+            .with(VictoryArtifact)
+            .build()
+    }
+    
+    // Create a unique artifact from its data definition
+    pub fn create_artifact(world: &mut World, x: i32, y: i32, definition: &crate::items::ArtifactDefinition) -> Entity {
+        let properties = crate::items::ItemProperties::new(definition.name.clone(), definition.item_type.clone())
+            .with_description(definition.description.clone())
+            .with_rarity(crate::items::ItemRarity::Artifact)
+            .with_value(definition.value);
+        let mut bonuses = crate::items::ItemBonuses::new();
+        bonuses.combat_bonuses = definition.bonuses.clone();
+        
+        world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x, y })
+            .with(Renderable {
+                glyph: definition.glyph,
+                fg: (255, 140, 0),
+                bg: (0, 0, 0),
+                render_order: 2,
+            })
+            .with(Name {
+                name: definition.name.clone(),
+            })
+            .with(Item {})
+            .with(properties)
+            .with(bonuses)
+            .with(crate::items::Artifact::from_definition(definition))
+            .build()
+    }
+    
     // Create an item entity
     pub fn create_health_potion(world: &mut World, x: i32, y: i32) -> Entity {
         world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
             .with(Position { x, y })
             .with(Renderable {
                 glyph: '!',
@@ -88,12 +349,21 @@ impl EntityFactory {
             })
             .with(Item {})
             .with(ProvidesHealing { heal_amount: 8 })
+            .with(ItemProperties::new("Health Potion".to_string(), ItemType::Consumable(ConsumableType::Potion))
+                .with_description("A potion that restores 8 health instantly.".to_string())
+                .with_value(25)
+                .with_weight(0.5)
+                .with_stack_size(10))
+            .with(ItemsConsumable::new(ConsumableType::Potion)
+                .with_effects(vec![ConsumableEffect::Healing { amount: 8, over_time: false }])
+                .with_charges(1))
             .build()
     }
     
     // Create stairs down
     pub fn create_stairs_down(world: &mut World, x: i32, y: i32) -> Entity {
         world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
             .with(Position { x, y })
             .with(Renderable {
                 glyph: '>',
@@ -110,6 +380,7 @@ impl EntityFactory {
     // Create stairs up
     pub fn create_stairs_up(world: &mut World, x: i32, y: i32) -> Entity {
         world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
             .with(Position { x, y })
             .with(Renderable {
                 glyph: '<',
@@ -122,4 +393,192 @@ impl EntityFactory {
             })
             .build()
     }
+    
+    // Create a campfire to rest beside
+    pub fn create_campfire(world: &mut World, x: i32, y: i32) -> Entity {
+        world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x, y })
+            .with(Renderable {
+                glyph: '*',
+                fg: (255, 140, 0),
+                bg: (0, 0, 0),
+                render_order: 3,
+            })
+            .with(Name {
+                name: "Campfire".to_string(),
+            })
+            .with(Campfire { radius: 2 })
+            .build()
+    }
+
+    // Create an altar to one of the gods; it stands aside rather than blocking the way
+    pub fn create_altar(world: &mut World, x: i32, y: i32, deity: Deity) -> Entity {
+        world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x, y })
+            .with(Renderable {
+                glyph: '_',
+                fg: deity.color(),
+                bg: crossterm::style::Color::Black,
+                render_order: 3,
+            })
+            .with(Name {
+                name: format!("Altar of {}", deity.name()),
+            })
+            .with(Altar { deity })
+            .build()
+    }
+
+    // Create a fountain, shrine, lever or bookshelf where the feature generator placed it
+    pub fn create_furniture(world: &mut World, placement: &FurniturePlacement) -> Entity {
+        let kind = placement.kind;
+        let furniture = match placement.opens {
+            Some(door) => Furniture::lever(door),
+            None => Furniture::new(kind),
+        };
+        world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x: placement.x, y: placement.y })
+            .with(Renderable {
+                glyph: kind.glyph(),
+                fg: kind.color(),
+                bg: crossterm::style::Color::Black,
+                render_order: 3,
+            })
+            .with(Name {
+                name: kind.name().to_string(),
+            })
+            .with(BlocksTile {})
+            .with(furniture)
+            .build()
+    }
+
+    /// A crafting station where the feature generator placed it, worked from its own screen
+    pub fn create_station(world: &mut World, placement: &StationPlacement) -> Entity {
+        let kind = placement.kind;
+        let station = world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x: placement.x, y: placement.y })
+            .with(Renderable {
+                glyph: kind.glyph(),
+                fg: kind.color(),
+                bg: crossterm::style::Color::Black,
+                render_order: 3,
+            })
+            .with(Name {
+                name: kind.name().to_string(),
+            })
+            .with(BlocksTile {});
+        match kind {
+            StationKind::Enchanting => station.with(EnchantingStation).build(),
+            StationKind::Alchemy => station.with(AlchemyStation).build(),
+        }
+    }
+
+    /// A rival adventuring party standing on the given tiles, one member to each, who keep to
+    /// themselves until the player deals with them
+    pub fn create_rival_party(world: &mut World, tiles: &[(i32, i32)], depth: i32) -> Vec<Entity> {
+        let (party, rolls) = {
+            let mut rng = world.write_resource::<RandomNumberGenerator>();
+            let rng = rng.stream(MAPGEN_STREAM);
+            let party = rng.range(1, 1_000_000) as u32;
+            let rolls: Vec<(String, i32)> = tiles.iter()
+                .map(|_| (NameGenerator::personal_name(NameStyle::Human, rng), rng.range(5, 15) * depth.max(1)))
+                .collect();
+            (party, rolls)
+        };
+        let mut members = Vec::with_capacity(tiles.len());
+        for (i, (&(x, y), (personal, gold))) in tiles.iter().zip(rolls).enumerate() {
+            let (calling, hp, defense, power) = RIVAL_CALLINGS[i % RIVAL_CALLINGS.len()];
+            let hp = hp + depth * 3;
+            let name = format!("{} the {}", personal, calling);
+            let mut inventory = AdvancedInventory::new(10, 60.0);
+            inventory.gold = gold;
+            let member = world.create_entity()
+                .marked::<SimpleMarker<SerializeMe>>()
+                .with(Position { x, y })
+                .with(Renderable {
+                    glyph: '@',
+                    fg: crossterm::style::Color::Cyan,
+                    bg: crossterm::style::Color::Black,
+                    render_order: 1,
+                })
+                .with(Viewshed {
+                    visible_tiles: Vec::new(),
+                    range: 8,
+                    dirty: true,
+                })
+                .with(Name { name })
+                .with(BlocksTile {})
+                .with(CombatStats {
+                    max_hp: hp,
+                    hp,
+                    defense: defense + depth / 3,
+                    power: power + depth / 2,
+                })
+                .with(Rival { party, stance: RivalStance::Neutral, rooms_explored: 0 })
+                .with(OpensDoors)
+                .with(inventory)
+                .build();
+            members.push(member);
+        }
+        members
+    }
+
+    /// Someone who lives in Ravenhollow, keeping to `post` when nothing calls them away. Guards
+    /// are a match for anyone fresh out of the dungeon; everyone else folds at the first blow
+    pub fn create_townsperson(world: &mut World, x: i32, y: i32, role: TownRole) -> Entity {
+        let personal = {
+            let mut rng = world.write_resource::<RandomNumberGenerator>();
+            NameGenerator::personal_name(NameStyle::Human, rng.stream(MAPGEN_STREAM))
+        };
+        let (hp, defense, power) = match role {
+            TownRole::Guard => (40, 4, 8),
+            _ => (10, 0, 1),
+        };
+        world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x, y })
+            .with(Renderable {
+                glyph: '@',
+                fg: role.color(),
+                bg: crossterm::style::Color::Black,
+                render_order: 1,
+            })
+            .with(Viewshed {
+                visible_tiles: Vec::new(),
+                range: 10,
+                dirty: true,
+            })
+            .with(Name { name: format!("{} the {}", personal, role.name()) })
+            .with(BlocksTile {})
+            .with(CombatStats { max_hp: hp, hp, defense, power })
+            .with(Townsperson { role, post: (x, y), wronged: false })
+            .with(OpensDoors)
+            .build()
+    }
+
+    /// Someone waiting to be freed, who stands still and takes no part in the fighting until then
+    pub fn create_captive(world: &mut World, placement: &CaptivePlacement) -> Entity {
+        let kind = placement.kind;
+        let name = {
+            let mut rng = world.write_resource::<RandomNumberGenerator>();
+            NameGenerator::personal_name(NameStyle::Human, &mut rng)
+        };
+        world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Position { x: placement.x, y: placement.y })
+            .with(Renderable {
+                glyph: '@',
+                fg: kind.color(),
+                bg: crossterm::style::Color::Black,
+                render_order: 1,
+            })
+            .with(Name { name })
+            .with(CombatStats { max_hp: 12, hp: 12, defense: 0, power: 0 })
+            .with(BlocksTile {})
+            .with(Captive { kind })
+            .build()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,530 @@
+use rand::RngCore;
+use specs::{World, WorldExt, Entity, Join};
+use crate::components::{
+    Position, OtherLevelPosition, Companion, Monster, Name, Viewshed, Difficulty, EliteAffix, Deity, VaultGuardian,
+    WantsToMove, WantsToExplore, WantsToTravel, ForSale, TownRole,
+};
+use crate::entity_factory::EntityFactory;
+use crate::items::{ConsumableFactory, ConsumableContext, EquipmentFactory, EquipmentQuality, ItemFactory, RepairKitType, ToolType};
+use crate::map::{
+    Climate, TileType, Map, Location, DungeonBranch, DungeonLevels, BranchLayout, GeneratorKind, SpawnDirector, SpawnHistory, DungeonFeatureGenerator,
+    SpawnType, SpecialFeatureType, EntitySpawn, EnemyType, DijkstraMaps, TownLayout, BuildingKind, place_branch_entrances,
+    arrival_point,
+};
+use crate::localization::Message;
+use crate::progression::{WorldEvents, WorldEventKind, flood};
+use crate::resources::{GameStateResource, GameClock, GameLog, RandomNumberGenerator, MAPGEN_STREAM, WORLD_STREAM};
+use crate::systems::ParticleEmitter;
+
+pub const LEVEL_WIDTH: i32 = 80;
+pub const LEVEL_HEIGHT: i32 = 50;
+
+/// How far from the stairs companions may come out when the tiles beside them are taken
+const COMPANION_SPREAD: i32 = 3;
+
+/// Lay out a level never visited before, from the run's map generation stream
+pub fn generate_level(world: &World, location: Location) -> Map {
+    let layout = world.fetch::<BranchLayout>();
+    let mut rng = world.write_resource::<RandomNumberGenerator>();
+    let rng = rng.stream(MAPGEN_STREAM);
+    let seed = rng.next_u64();
+    if location.is_town() {
+        return TownLayout::ravenhollow().generate(LEVEL_WIDTH, LEVEL_HEIGHT, seed);
+    }
+    let kind = match location.branch {
+        DungeonBranch::Mines | DungeonBranch::Glacier | DungeonBranch::Caldera => GeneratorKind::Caves,
+        _ => GeneratorKind::Rooms,
+    };
+    let mut map = kind.generate(LEVEL_WIDTH, LEVEL_HEIGHT, location.difficulty_depth(&layout), seed);
+    map.location = location;
+    map.theme = location.branch.theme();
+    map.generation_seed = seed;
+    let entrance = map.entrance;
+    place_branch_entrances(&mut map, &layout, rng, &[entrance]);
+    map
+}
+
+/// Set the feature generator's fountains, shrines, levers, bookshelves and crafting stations
+/// about a fresh level, along with anyone it left waiting to be rescued
+pub fn furnish_level(world: &mut World, map: &mut Map) {
+    let rng = world.write_resource::<RandomNumberGenerator>().stream(MAPGEN_STREAM).fork("furniture");
+    let mut generator = DungeonFeatureGenerator::new(rng);
+    let placements = generator.furnish(map);
+    for placement in &placements {
+        EntityFactory::create_furniture(world, placement);
+    }
+    for station in &generator.stations {
+        EntityFactory::create_station(world, station);
+    }
+    for captive in &generator.captives {
+        EntityFactory::create_captive(world, captive);
+    }
+}
+
+/// Provisions left lying about a level that leads down into the cold or the heat
+const CLIMATE_PROVISIONS: i32 = 2;
+
+/// A main level with the way down into the Glacier or the Caldera on it has what it takes to
+/// face that climate lying somewhere about: a cloak made for it and a few provisions
+pub fn stock_climate_provisions(world: &mut World, map: &Map) {
+    let climates: Vec<Climate> = map.branch_entrances.iter()
+        .map(|entrance| entrance.branch.theme().climate())
+        .filter(|climate| *climate != Climate::Temperate)
+        .collect();
+    if climates.is_empty() {
+        return;
+    }
+    let mut floor = Vec::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            if map.get_tile(x, y) == Some(TileType::Floor) && (x, y) != map.entrance && (x, y) != map.exit && !map.in_vault(x, y) {
+                floor.push((x, y));
+            }
+        }
+    }
+    if floor.is_empty() {
+        return;
+    }
+
+    let mut rng = world.write_resource::<RandomNumberGenerator>().stream(MAPGEN_STREAM).fork("provisions");
+    for climate in climates {
+        for i in 0..=CLIMATE_PROVISIONS {
+            let (x, y) = floor[rng.range(0, floor.len() as i32 - 1) as usize];
+            if i == 0 {
+                EquipmentFactory::new().create_climate_cloak(world, Position { x, y }, climate);
+            } else {
+                ConsumableFactory::new().create_climate_provision(world, Position { x, y }, climate);
+            }
+        }
+    }
+}
+
+/// High-tier pieces heaped in every greed vault
+const VAULT_TREASURES: usize = 3;
+
+/// Heap each greed vault with high-tier gear, and where no lever was set beside one, leave the
+/// key to it lying out on the level
+pub fn stock_vaults(world: &mut World, map: &Map) {
+    if map.vaults.is_empty() {
+        return;
+    }
+    let mut rng = world.write_resource::<RandomNumberGenerator>().stream(MAPGEN_STREAM).fork("vaults");
+    for vault in &map.vaults {
+        let mut tiles = vault.treasure_tiles();
+        for _ in 0..VAULT_TREASURES.min(tiles.len()) {
+            let (x, y) = tiles.swap_remove(rng.range(0, tiles.len() as i32 - 1) as usize);
+            EquipmentFactory::new().create_random_equipment(world, Position { x, y }, EquipmentQuality::Rare, &mut rng);
+        }
+    }
+
+    let mut floor = Vec::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            if map.get_tile(x, y) == Some(TileType::Floor) && (x, y) != map.entrance && !map.in_vault(x, y) {
+                floor.push((x, y));
+            }
+        }
+    }
+    let keys = map.vaults.iter().filter(|vault| vault.keyed).count();
+    for _ in 0..keys.min(floor.len()) {
+        let (x, y) = floor.swap_remove(rng.range(0, floor.len() as i32 - 1) as usize);
+        ItemFactory::new().create_tool(world, ToolType::Key, Position { x, y });
+    }
+}
+
+/// Percent chance that a fresh level deep enough has a rival party exploring it
+const RIVAL_PARTY_CHANCE: i32 = 25;
+/// Shallowest level rival parties turn up on
+const RIVAL_MIN_DEPTH: i32 = 2;
+/// Most adventurers in a rival party
+const RIVAL_PARTY_SIZE: i32 = 3;
+
+/// Now and then, set a rival adventuring party down in one of a fresh level's rooms, away from
+/// both staircases and clear of anything already standing there
+pub fn place_rival_party(world: &mut World) {
+    let (depth, room) = {
+        let map = world.fetch::<Map>();
+        let mut rng = world.write_resource::<RandomNumberGenerator>();
+        let rng = rng.stream(MAPGEN_STREAM);
+        if map.depth < RIVAL_MIN_DEPTH || map.rooms.len() < 3 || rng.range(1, 100) > RIVAL_PARTY_CHANCE {
+            return;
+        }
+        (map.depth, map.rooms[rng.range(1, map.rooms.len() as i32 - 2) as usize])
+    };
+    let tiles: Vec<(i32, i32)> = {
+        let map = world.fetch::<Map>();
+        let positions = world.read_storage::<Position>();
+        let taken: Vec<(i32, i32)> = positions.join().map(|pos| (pos.x, pos.y)).collect();
+        let size = world.write_resource::<RandomNumberGenerator>().stream(MAPGEN_STREAM).range(2, RIVAL_PARTY_SIZE);
+        let (cx, cy) = room.center();
+        let mut tiles = Vec::new();
+        for y in cy - 1..=cy + 1 {
+            for x in cx - 1..=cx + 1 {
+                if room.contains(x, y) && !map.is_blocked(x, y) && !taken.contains(&(x, y)) && tiles.len() < size as usize {
+                    tiles.push((x, y));
+                }
+            }
+        }
+        tiles
+    };
+    if tiles.len() >= 2 {
+        EntityFactory::create_rival_party(world, &tiles, depth);
+    }
+}
+
+/// Wares laid out along the back wall of the general store
+const SHOP_WARES: usize = 8;
+/// Villagers about the square
+const TOWN_VILLAGERS: usize = 4;
+
+/// Fill Ravenhollow the first time it is reached: the shopkeeper behind the counter with the
+/// wares and a pair of repair kits laid out behind them, the banker at their desk, the priest
+/// before an altar to each god, the trainer in the guild hall, the blacksmith at the forge, a
+/// pair of guards just inside the guardhouse and another pair on the square, a gambler by the
+/// stairs, and villagers going about their day around them
+pub fn populate_town(world: &mut World) {
+    let layout = TownLayout::ravenhollow();
+    let mut rng = world.write_resource::<RandomNumberGenerator>().stream(MAPGEN_STREAM).fork("town");
+
+    if let Some(shop) = layout.building(BuildingKind::Shop) {
+        EntityFactory::create_townsperson(world, shop.door.0 + 1, shop.door.1 - 2, TownRole::Shopkeeper);
+        let shelf: Vec<(i32, i32)> = shop.inner_tiles().into_iter().filter(|&(_, y)| y == shop.room.y1 + 1).collect();
+        for (i, &(x, y)) in shelf.iter().take(SHOP_WARES).enumerate() {
+            let ware = if i % 2 == 0 {
+                ConsumableFactory::new().create_random_consumable(world, Position { x, y }, ConsumableContext::Exploration, &mut rng)
+            } else {
+                EquipmentFactory::new().create_random_equipment(world, Position { x, y }, EquipmentQuality::Common, &mut rng)
+            };
+            world.write_storage::<ForSale>().insert(ware, ForSale::default()).expect("Unable to price ware");
+        }
+        for (kit_type, &(x, y)) in [RepairKitType::Weaponsmith, RepairKitType::Armorsmith].into_iter().zip(shelf.iter().skip(SHOP_WARES)) {
+            let kit = ItemFactory::new().create_repair_kit(world, kit_type, Position { x, y });
+            world.write_storage::<ForSale>().insert(kit, ForSale::default()).expect("Unable to price ware");
+        }
+    }
+
+    if let Some(bank) = layout.building(BuildingKind::Bank) {
+        EntityFactory::create_townsperson(world, bank.door.0, bank.room.y1 + 2, TownRole::Banker);
+    }
+    if let Some(temple) = layout.building(BuildingKind::Temple) {
+        EntityFactory::create_townsperson(world, temple.door.0, temple.door.1 - 2, TownRole::Priest);
+        let (cx, top) = (temple.door.0, temple.room.y1 + 1);
+        for (deity, x) in Deity::all().into_iter().zip([cx - 3, cx, cx + 3]) {
+            EntityFactory::create_altar(world, x, top, deity);
+        }
+    }
+
+    if let Some(hall) = layout.building(BuildingKind::GuildHall) {
+        let (x, y) = hall.room.center();
+        EntityFactory::create_townsperson(world, x, y, TownRole::Trainer);
+    }
+    if let Some(smithy) = layout.building(BuildingKind::Smithy) {
+        let (x, y) = smithy.room.center();
+        EntityFactory::create_townsperson(world, x, y, TownRole::Blacksmith);
+    }
+
+    let square = layout.square;
+    EntityFactory::create_townsperson(world, layout.stairs.0 - 5, layout.stairs.1 + 3, TownRole::Gambler);
+    let mut posts = vec![(square.x1 + 1, square.y1 + 1), (square.x2 - 2, square.y2 - 2)];
+    if let Some(house) = layout.building(BuildingKind::Guardhouse) {
+        posts.push((house.door.0 - 1, house.door.1 - 1));
+        posts.push((house.door.0 + 1, house.door.1 - 1));
+    }
+    for (x, y) in posts {
+        EntityFactory::create_townsperson(world, x, y, TownRole::Guard);
+    }
+
+    let mut placed = 0;
+    while placed < TOWN_VILLAGERS {
+        let (x, y) = (rng.range(square.x1 + 2, square.x2 - 3), rng.range(square.y1 + 2, square.y2 - 3));
+        let taken = (x, y) == layout.stairs || world.read_storage::<Position>().join().any(|pos| (pos.x, pos.y) == (x, y));
+        if !taken {
+            EntityFactory::create_townsperson(world, x, y, TownRole::Villager);
+            placed += 1;
+        }
+    }
+}
+
+/// Bring a level's planned spawns to life: monsters, some of them elites, packs, vault guardians,
+/// bosses and altars
+pub fn populate_level(world: &mut World, spawns: Vec<EntitySpawn>, difficulty: Difficulty) {
+    let depth = world.fetch::<Map>().depth;
+    let mut spawned: Vec<Option<Entity>> = Vec::with_capacity(spawns.len());
+    for spawn in spawns {
+        let mut created = None;
+        match spawn.entity_type {
+            SpawnType::Enemy(enemy) => {
+                let entity = EntityFactory::create_enemy(world, spawn.x, spawn.y, enemy, difficulty);
+                let affixes = EliteAffix::roll(depth, world.write_resource::<RandomNumberGenerator>().stream(MAPGEN_STREAM));
+                EntityFactory::make_elite(world, entity, affixes);
+                // Followers fall in behind the leader spawned just before them
+                if let (Some(leader), Some((tactics, _))) = (spawn.leader.and_then(|idx| spawned[idx]), enemy.pack()) {
+                    EntityFactory::join_pack(world, entity, leader, tactics);
+                }
+                created = Some(entity);
+            }
+            SpawnType::Guardian(enemy) => {
+                created = Some(EntityFactory::create_vault_guardian(world, spawn.x, spawn.y, enemy, difficulty));
+            }
+            SpawnType::Boss(branch) => {
+                EntityFactory::create_branch_boss(world, spawn.x, spawn.y, branch, difficulty);
+            }
+            SpawnType::Special(SpecialFeatureType::Altar) => {
+                let gods = Deity::all();
+                let deity = gods[world.write_resource::<RandomNumberGenerator>().stream(MAPGEN_STREAM).range(0, gods.len() as i32 - 1) as usize];
+                EntityFactory::create_altar(world, spawn.x, spawn.y, deity);
+            }
+            _ => {}
+        }
+        spawned.push(created);
+    }
+}
+
+/// While the underground rivers are up, a fresh level comes with pools of water across its floor
+pub fn flood_level(world: &mut World, map: &mut Map) {
+    if !world.read_resource::<WorldEvents>().is_active(WorldEventKind::DungeonFlood) {
+        return;
+    }
+    let flooded = flood(map, world.write_resource::<RandomNumberGenerator>().stream(WORLD_STREAM));
+    if flooded > 0 {
+        world.write_resource::<GameLog>().add_message(Message::new("log.level_flooded"));
+    }
+}
+
+/// Take the player and their companions to `destination`, leaving everything else where it
+/// stands to be found again on the way back. Returns the companions that came along
+pub fn change_level(world: &mut World, player: Entity, destination: Location, difficulty: Difficulty) -> Vec<Entity> {
+    world.maintain();
+    let departing = world.fetch::<Map>().clone();
+    let from = departing.location;
+    let (turn, phase) = {
+        let game_state = world.read_resource::<GameStateResource>();
+        (game_state.turn_count, game_state.phase)
+    };
+
+    let companions: Vec<Entity> = {
+        let entities = world.entities();
+        let companions = world.read_storage::<Companion>();
+        let positions = world.read_storage::<Position>();
+        (&entities, &companions, &positions).join()
+            .filter(|(_, companion, _)| companion.owner == player)
+            .map(|(entity, _, _)| entity)
+            .collect()
+    };
+    let mut travellers = companions.clone();
+    travellers.push(player);
+
+    let remaining_threat = leave_level(world, &travellers, from);
+    world.write_resource::<SpawnHistory>().record_departure(&departing, turn, remaining_threat);
+    world.write_resource::<DungeonLevels>().store(departing);
+
+    // A level already seen comes back as it was left, with whatever has crept back since
+    let stored = world.write_resource::<DungeonLevels>().take(destination);
+    let returning = stored.is_some();
+    let map = match stored {
+        Some(map) => map,
+        None if destination.is_town() => generate_level(world, destination),
+        None => {
+            let mut map = generate_level(world, destination);
+            furnish_level(world, &mut map);
+            stock_climate_provisions(world, &map);
+            stock_vaults(world, &map);
+            flood_level(world, &mut map);
+            map
+        },
+    };
+    let arrival = arrival_point(&map, from);
+    let spawns = {
+        let daylight = !world.read_resource::<GameClock>().is_night();
+        let history = world.read_resource::<SpawnHistory>();
+        let mut rng = world.write_resource::<RandomNumberGenerator>();
+        let rng = rng.stream(MAPGEN_STREAM);
+        if destination.is_town() {
+            // Nothing comes up out of the dungeon into town
+            Vec::new()
+        } else if returning {
+            SpawnDirector::repopulate(&map, &history, turn, phase, difficulty, rng, &[arrival])
+        } else {
+            SpawnDirector::plan_level_at(&map, phase, difficulty, daylight, rng, &[arrival])
+        }
+    };
+    let depth = map.depth;
+    let vaults = if returning { 0 } else { map.vaults.len() };
+    let tiles = companion_tiles(&map, arrival, companions.len());
+    world.insert(map);
+    return_to_level(world, destination);
+    populate_level(world, spawns, difficulty);
+    if !returning && destination.is_town() {
+        populate_town(world);
+    } else if !returning {
+        place_rival_party(world);
+    }
+    if vaults > 0 {
+        world.write_resource::<GameLog>().add_message(Message::new("log.greed_vault_sensed"));
+    }
+
+    {
+        let mut positions = world.write_storage::<Position>();
+        let mut viewsheds = world.write_storage::<Viewshed>();
+        let places = std::iter::once(arrival).chain(tiles);
+        for (entity, (x, y)) in std::iter::once(player).chain(companions.iter().copied()).zip(places) {
+            positions.insert(entity, Position { x, y }).expect("Unable to place traveller");
+            if let Some(viewshed) = viewsheds.get_mut(entity) {
+                viewshed.dirty = true;
+            }
+        }
+    }
+    // Whatever the player was walking toward was on the level left behind
+    world.write_storage::<WantsToMove>().remove(player);
+    world.write_storage::<WantsToExplore>().remove(player);
+    world.write_storage::<WantsToTravel>().remove(player);
+
+    world.write_resource::<BranchLayout>().record_visit(destination);
+    {
+        let mut game_state = world.write_resource::<GameStateResource>();
+        game_state.depth = depth;
+        game_state.location = destination;
+    }
+    world.write_resource::<DijkstraMaps>().clear();
+    world.write_resource::<ParticleEmitter>().stair_transition(arrival);
+    companions
+}
+
+/// Set aside everything on the level but the travellers, returning the threat of the monsters left on it
+fn leave_level(world: &mut World, travellers: &[Entity], location: Location) -> i32 {
+    let entities = world.entities();
+    let mut positions = world.write_storage::<Position>();
+    let mut elsewhere = world.write_storage::<OtherLevelPosition>();
+    let monsters = world.read_storage::<Monster>();
+    let guardians = world.read_storage::<VaultGuardian>();
+    let names = world.read_storage::<Name>();
+
+    let staying: Vec<(Entity, i32, i32)> = (&entities, &positions).join()
+        .filter(|(entity, _)| !travellers.contains(entity))
+        .map(|(entity, pos)| (entity, pos.x, pos.y))
+        .collect();
+    let mut threat = 0;
+    for (entity, x, y) in staying {
+        positions.remove(entity);
+        elsewhere.insert(entity, OtherLevelPosition { x, y, location })
+            .expect("Unable to set aside entity");
+        if monsters.contains(entity) && !guardians.contains(entity) {
+            threat += names.get(entity)
+                .and_then(|name| EnemyType::from_name(&name.name))
+                .map_or(0, |enemy| enemy.threat());
+        }
+    }
+    threat
+}
+
+/// Put back everything set aside on a level being returned to
+fn return_to_level(world: &mut World, location: Location) {
+    let entities = world.entities();
+    let mut positions = world.write_storage::<Position>();
+    let mut elsewhere = world.write_storage::<OtherLevelPosition>();
+
+    let waiting: Vec<(Entity, i32, i32)> = (&entities, &elsewhere).join()
+        .filter(|(_, other)| other.location == location)
+        .map(|(entity, other)| (entity, other.x, other.y))
+        .collect();
+    for (entity, x, y) in waiting {
+        elsewhere.remove(entity);
+        positions.insert(entity, Position { x, y }).expect("Unable to bring back entity");
+    }
+}
+
+/// Open tiles for companions nearest the stairs first, falling back on the stairs themselves
+fn companion_tiles(map: &Map, arrival: (i32, i32), count: usize) -> Vec<(i32, i32)> {
+    let mut tiles = Vec::with_capacity(count);
+    for radius in 1..=COMPANION_SPREAD {
+        for y in arrival.1 - radius..=arrival.1 + radius {
+            for x in arrival.0 - radius..=arrival.0 + radius {
+                let on_ring = (x - arrival.0).abs().max((y - arrival.1).abs()) == radius;
+                if on_ring && !map.is_blocked(x, y) && tiles.len() < count {
+                    tiles.push((x, y));
+                }
+            }
+        }
+    }
+    tiles.resize(count, arrival);
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use crate::components::{CompanionKind, Player};
+    use crate::map::TileType;
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<crate::items::ItemProperties>();
+        world.register::<crate::items::ItemBonuses>();
+        world.register::<crate::items::Equippable>();
+        world.insert(RandomNumberGenerator::new(7));
+        world.insert(GameLog::new(10));
+        world.insert(GameStateResource::default());
+        world.insert(GameClock::default());
+        world.insert(SpawnHistory::new());
+        world.insert(BranchLayout::default());
+        world.insert(DungeonLevels::default());
+        world.insert(DijkstraMaps::default());
+        world.insert(ParticleEmitter::default());
+        world.insert(WorldEvents::default());
+
+        let mut map = Map::new(LEVEL_WIDTH, LEVEL_HEIGHT, 1);
+        for x in 1..20 {
+            map.set_tile(x, 5, TileType::Floor);
+        }
+        map.set_tile(18, 5, TileType::DownStairs);
+        map.exit = (18, 5);
+        world.insert(map);
+        world
+    }
+
+    #[test]
+    fn test_the_player_and_companions_arrive_on_the_stairs_back() {
+        let mut world = world();
+        let player = world.create_entity().with(Player).with(Position { x: 18, y: 5 }).build();
+        let wolf = world.create_entity()
+            .with(Position { x: 17, y: 5 })
+            .with(Companion { owner: player, kind: CompanionKind::Wolf, turns_remaining: None, summoned_turn: 0, follow_distance: 2 })
+            .build();
+        let potion = world.create_entity().with(Position { x: 3, y: 5 }).build();
+
+        let companions = change_level(&mut world, player, Location::main(2), Difficulty::Normal);
+        assert_eq!(companions, vec![wolf]);
+
+        let map = world.fetch::<Map>().clone();
+        assert_eq!(map.location, Location::main(2));
+        assert_eq!(world.read_resource::<GameStateResource>().depth, 2);
+        let positions = world.read_storage::<Position>();
+        let pos = positions.get(player).unwrap();
+        assert_eq!((pos.x, pos.y), map.entrance);
+        assert_eq!(map.get_tile(pos.x, pos.y), Some(TileType::UpStairs));
+        assert!(positions.get(wolf).is_some());
+        assert!(positions.get(potion).is_none());
+        assert_eq!(world.read_storage::<OtherLevelPosition>().get(potion).map(|other| other.location), Some(Location::main(1)));
+    }
+
+    #[test]
+    fn test_going_back_finds_the_level_as_it_was_left() {
+        let mut world = world();
+        let player = world.create_entity().with(Player).with(Position { x: 18, y: 5 }).build();
+        let potion = world.create_entity().with(Position { x: 3, y: 5 }).build();
+        world.write_resource::<Map>().revealed_tiles[3] = true;
+
+        change_level(&mut world, player, Location::main(2), Difficulty::Normal);
+        change_level(&mut world, player, Location::main(1), Difficulty::Normal);
+
+        let map = world.fetch::<Map>();
+        assert!(map.revealed_tiles[3]);
+        let positions = world.read_storage::<Position>();
+        let pos = positions.get(player).unwrap();
+        assert_eq!((pos.x, pos.y), (18, 5));
+        assert_eq!(positions.get(potion).map(|pos| (pos.x, pos.y)), Some((3, 5)));
+        assert!(world.read_resource::<DungeonLevels>().has_visited(Location::main(2)));
+    }
+}
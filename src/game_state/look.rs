@@ -0,0 +1,265 @@
+use specs::{World, WorldExt, Join};
+use crate::components::{
+    Name, Position, Renderable, CombatStats, StatusEffect, StatusEffects, Player, Companion, Monster, Elite, Corpse, Undead,
+    MonsterAI, Morale, Captive, Escort, Rival, RivalStance, Townsperson, TownRole, ForSale
+};
+use crate::items::ItemProperties;
+use crate::map::Map;
+use crate::progression::WorldEvents;
+use crate::systems::{Faction, Reputation, TownWatch, ware_price};
+
+/// A rough read on how hurt a creature is; the exact hit points stay hidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthEstimate {
+    Unhurt,
+    Scratched,
+    Wounded,
+    BadlyWounded,
+    NearDeath,
+}
+
+impl HealthEstimate {
+    pub fn from_stats(stats: &CombatStats) -> Self {
+        let percent = if stats.max_hp > 0 { stats.hp.max(0) * 100 / stats.max_hp } else { 0 };
+        match percent {
+            100.. => HealthEstimate::Unhurt,
+            75..=99 => HealthEstimate::Scratched,
+            45..=74 => HealthEstimate::Wounded,
+            20..=44 => HealthEstimate::BadlyWounded,
+            _ => HealthEstimate::NearDeath,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HealthEstimate::Unhurt => "Unhurt",
+            HealthEstimate::Scratched => "Scratched",
+            HealthEstimate::Wounded => "Wounded",
+            HealthEstimate::BadlyWounded => "Badly wounded",
+            HealthEstimate::NearDeath => "Near death",
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            HealthEstimate::Unhurt | HealthEstimate::Scratched => crossterm::style::Color::Green,
+            HealthEstimate::Wounded => crossterm::style::Color::Yellow,
+            HealthEstimate::BadlyWounded | HealthEstimate::NearDeath => crossterm::style::Color::Red,
+        }
+    }
+
+    /// Bar drawn in coarse steps, so it never gives away the exact hit points
+    pub fn bar(&self, width: usize) -> String {
+        let filled = match self {
+            HealthEstimate::Unhurt => width,
+            HealthEstimate::Scratched => width * 3 / 4,
+            HealthEstimate::Wounded => width / 2,
+            HealthEstimate::BadlyWounded => width / 4,
+            HealthEstimate::NearDeath => 1.min(width),
+        };
+        format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+    }
+}
+
+/// Which side whatever is under the cursor is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookFaction {
+    Player,
+    Ally,
+    Hostile,
+    Neutral,
+}
+
+impl LookFaction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LookFaction::Player => "You",
+            LookFaction::Ally => "Ally",
+            LookFaction::Hostile => "Hostile",
+            LookFaction::Neutral => "Neutral",
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            LookFaction::Player => crossterm::style::Color::Yellow,
+            LookFaction::Ally => crossterm::style::Color::Green,
+            LookFaction::Hostile => crossterm::style::Color::Red,
+            LookFaction::Neutral => crossterm::style::Color::Grey,
+        }
+    }
+}
+
+/// Everything the look panel shows about one tile
+#[derive(Debug, Clone)]
+pub struct LookInfo {
+    pub name: String,
+    pub health: Option<HealthEstimate>,
+    /// Effects on it, each with the turns it has left
+    pub statuses: Vec<StatusEffect>,
+    pub faction: LookFaction,
+    pub description: String,
+    /// The monster's active behavior tree node, shown in wizard mode
+    pub behavior: Option<String>,
+}
+
+/// Describe the topmost entity on a visible tile, or the tile itself when nothing stands there
+pub fn examine(world: &World, x: i32, y: i32) -> Option<LookInfo> {
+    let map = world.fetch::<Map>();
+    if !map.is_visible(x, y) {
+        return None;
+    }
+
+    let positions = world.read_storage::<Position>();
+    let renderables = world.read_storage::<Renderable>();
+    let entity = (&world.entities(), &positions, &renderables).join()
+        .filter(|(_, pos, _)| pos.x == x && pos.y == y)
+        .max_by_key(|(_, _, render)| render.render_order)
+        .map(|(entity, _, _)| entity);
+
+    let entity = match entity {
+        Some(entity) => entity,
+        None => {
+            let tile = map.get_tile(x, y)?;
+            let mut description = tile.description().to_string();
+            if let Some(decal) = map.get_decal(x, y) {
+                description = format!("{} {}", description, decal.description());
+            }
+            if let Some(state) = map.get_tile_state(x, y) {
+                description = format!("{} {}", description, state.effect.description());
+            }
+            if let Some(cloud) = map.gas_at(x, y) {
+                description = format!("{} A cloud of {} hangs here.", description, cloud.kind.name());
+            }
+            if map.is_vault_door(x, y) && map.is_closed_door(x, y) {
+                description = format!("{} It seals a greed vault; something stirs beyond it.", description);
+            }
+            return Some(LookInfo {
+                name: tile.name().to_string(),
+                health: None,
+                statuses: Vec::new(),
+                faction: LookFaction::Neutral,
+                description,
+                behavior: None,
+            });
+        },
+    };
+
+    let base_name = world.read_storage::<Name>().get(entity).map_or("Something".to_string(), |n| n.name.clone());
+    let elites = world.read_storage::<Elite>();
+    let name = match elites.get(entity) {
+        Some(elite) => elite.decorate(&base_name),
+        None => base_name,
+    };
+
+    let health = world.read_storage::<CombatStats>().get(entity).map(HealthEstimate::from_stats);
+    let statuses = world.read_storage::<StatusEffects>().get(entity)
+        .map(|status| status.effects.clone())
+        .unwrap_or_default();
+
+    let stance = world.read_storage::<Rival>().get(entity).map(|rival| rival.stance);
+    let role = world.read_storage::<Townsperson>().get(entity).map(|person| person.role);
+    let town_standing = world.read_resource::<Reputation>().standing(Faction::Townsfolk);
+    let watch_hostile = world.read_resource::<TownWatch>().guards_hostile(town_standing);
+    let faction = if world.read_storage::<Player>().contains(entity) {
+        LookFaction::Player
+    } else if let Some(stance) = stance {
+        match stance {
+            RivalStance::Allied => LookFaction::Ally,
+            RivalStance::Hostile => LookFaction::Hostile,
+            RivalStance::Neutral => LookFaction::Neutral,
+        }
+    } else if role == Some(TownRole::Guard) && watch_hostile {
+        LookFaction::Hostile
+    } else if world.read_storage::<Companion>().contains(entity) || world.read_storage::<Escort>().contains(entity) {
+        LookFaction::Ally
+    } else if world.read_storage::<Monster>().contains(entity) {
+        LookFaction::Hostile
+    } else {
+        LookFaction::Neutral
+    };
+
+    let description = if let Some(props) = world.read_storage::<ItemProperties>().get(entity) {
+        match world.read_storage::<ForSale>().get(entity) {
+            Some(ware) if ware.stolen => format!("{} Stolen from the general store.", props.description),
+            Some(_) => format!("{} For sale at {} gold.", props.description, ware_price(props.value, town_standing, world.read_resource::<WorldEvents>().price_percent())),
+            None => props.description.clone(),
+        }
+    } else if let Some(corpse) = world.read_storage::<Corpse>().get(entity) {
+        if corpse.butchered {
+            format!("What is left of a {} after the butcher's knife.", corpse.name)
+        } else {
+            format!("The remains of a {}. It might still carry something.", corpse.name)
+        }
+    } else if let Some(companion) = world.read_storage::<Companion>().get(entity) {
+        format!("A {} fighting at your side.", companion.kind.name().to_lowercase())
+    } else if let Some(captive) = world.read_storage::<Captive>().get(entity) {
+        format!("A {} waiting to be freed. Walk into them to set them loose.", captive.kind.name().to_lowercase())
+    } else if let Some(escort) = world.read_storage::<Escort>().get(entity) {
+        format!("A {} you freed, following you to the stairs.", escort.kind.role())
+    } else if let Some(role) = role {
+        match role {
+            TownRole::Guard if watch_hostile => "One of the town watch, sword drawn and coming for you.".to_string(),
+            TownRole::Guard => "One of the town watch, keeping the peace in Ravenhollow.".to_string(),
+            TownRole::Shopkeeper => "Keeps the general store. Walk into them to pay for what you have picked up.".to_string(),
+            TownRole::Villager => "One of the people of Ravenhollow, going about their day.".to_string(),
+            TownRole::Banker => "Keeps the bank's books. Walk into them to bank gold or buy property.".to_string(),
+            TownRole::Priest => "Tends the temple. Walk into them to make an offering to your god.".to_string(),
+            TownRole::Gambler => "Plays dice for gold with anyone who will. Walk into them to place a bet.".to_string(),
+            TownRole::Trainer => "Trains the guild's adventurers. Walk into them to rework your skills or attributes, or learn another class's abilities.".to_string(),
+            TownRole::Blacksmith => "Works the forge at the smithy. Walk into them to have worn gear mended.".to_string(),
+        }
+    } else if let Some(stance) = stance {
+        match stance {
+            RivalStance::Allied => "A rival adventurer you paid to fight alongside you.".to_string(),
+            RivalStance::Hostile => "A rival adventurer out for your blood.".to_string(),
+            RivalStance::Neutral => "A rival adventurer working the same dungeon. Walk into them to trade or talk.".to_string(),
+        }
+    } else if let Some(elite) = elites.get(entity) {
+        let affixes: Vec<&str> = elite.affixes.iter().map(|affix| affix.description()).collect();
+        affixes.join(" ")
+    } else if world.read_storage::<Undead>().contains(entity) {
+        "A restless dead thing that should have stayed down.".to_string()
+    } else if world.read_storage::<Morale>().get(entity).map_or(false, |morale| morale.surrendered) {
+        "It has given up the fight and cowers, waiting to see what you will do.".to_string()
+    } else if let Some(ai) = world.read_storage::<MonsterAI>().get(entity) {
+        ai.mode.description().to_string()
+    } else if faction == LookFaction::Hostile {
+        "It looks ready to fight.".to_string()
+    } else if faction == LookFaction::Player {
+        "That's you.".to_string()
+    } else {
+        String::new()
+    };
+
+    let behavior = world.read_storage::<MonsterAI>().get(entity)
+        .map(|ai| ai.active_node.clone().unwrap_or_else(|| "(idle)".to_string()));
+
+    Some(LookInfo { name, health, statuses, faction, description, behavior })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(hp: i32, max_hp: i32) -> CombatStats {
+        CombatStats { max_hp, hp, defense: 0, power: 0 }
+    }
+
+    #[test]
+    fn test_health_estimate_bands() {
+        assert_eq!(HealthEstimate::from_stats(&stats(20, 20)), HealthEstimate::Unhurt);
+        assert_eq!(HealthEstimate::from_stats(&stats(16, 20)), HealthEstimate::Scratched);
+        assert_eq!(HealthEstimate::from_stats(&stats(10, 20)), HealthEstimate::Wounded);
+        assert_eq!(HealthEstimate::from_stats(&stats(5, 20)), HealthEstimate::BadlyWounded);
+        assert_eq!(HealthEstimate::from_stats(&stats(1, 20)), HealthEstimate::NearDeath);
+        assert_eq!(HealthEstimate::from_stats(&stats(-4, 20)), HealthEstimate::NearDeath);
+    }
+
+    #[test]
+    fn test_health_bar_is_coarse() {
+        assert_eq!(HealthEstimate::Unhurt.bar(8), "[########]");
+        assert_eq!(HealthEstimate::Wounded.bar(8), "[####----]");
+        assert_eq!(HealthEstimate::NearDeath.bar(8), "[#-------]");
+    }
+}
@@ -1,31 +1,132 @@
 mod state_machine;
 mod state_stack;
 mod run_state;
+mod ranged_targeting;
+mod look;
+mod travel;
+mod level_transition;
 
 pub use run_state::RunState;
+pub use ranged_targeting::RangedTargeting;
+pub use look::{LookInfo, LookFaction, HealthEstimate, examine};
+pub use travel::{TravelCursor, points_of_interest};
+pub use level_transition::{change_level, generate_level, populate_level, LEVEL_WIDTH, LEVEL_HEIGHT};
 
 use crossterm::event::{KeyCode, KeyEvent};
-use specs::{World, WorldExt, Entity};
+use specs::{Component, World, WorldExt, Entity, Join, RunNow};
 use crate::components::*;
-use crate::resources::{GameLog, RandomNumberGenerator, GameStateResource};
-use crate::map::Map;
+use crate::resources::{GameLog, GameEvents, RandomNumberGenerator, MAPGEN_STREAM, LOOT_STREAM, WORLD_STREAM, GameStateResource, GameClock, RunPhase, RunStatistics};
+use crate::persistence::{RunHistory, RunRecord, RunOutcome, RUN_HISTORY_PATH, HeroFile, HeroRecord, HeroError, HEROES_DIR};
+use crate::persistence::{SaveSystem, SaveSlot, SaveFile, SaveError, SaveMetadata, SaveData, WorldSerializer, WorldState, SAVES_DIR};
+use crate::persistence::serialization::create_serialization_system;
+use crate::map::{Map, TileType, DijkstraMaps, SpawnDirector, SpawnHistory, SpawnType, Location, BranchLayout, DungeonLevels, Weather, LevelTension, place_branch_entrances, stairs_destination};
 use crate::entity_factory::EntityFactory;
-use crate::systems::SystemRunner;
+use crate::input::{handle_player_input, PlayerAction};
+use crate::ai::monster_behavior::MonsterBehaviors;
+use crate::items::{AdvancedInventory, ArtifactDatabase, ArtifactRegistry, AlchemyAction, AlchemyJournal, AlchemyStation, AlchemyUI, ConsumableFactory, CurseRemovalSource, ItemProperties, PotionPotency, RepairSource, EnchantingAction, EnchantingStation, EnchantingUI, WantsToBrew, WantsToDisenchant, WantsToEnchant, WantsToEquip, WantsToRemoveCurse, WantsToRepair, WantsToUnequip, WantsToUseConsumable};
+use crate::systems::{SystemRunner, DeathPenaltySystem, RevivalSystem, AreaEffectQueue, ParticleEmitter, ScreenShakeState, WanderingSpawns, NoiseEvents, Squads, DivineDeeds, Reputation, Faction, TownWatch, Training, GasReleases, SPRINT_STAMINA_COST, altar_near, devote, pray, report_world_news};
+use crate::rendering::{AnimationQueue, MotionSettings, CellGrid, ScreenshotFormat, DEFAULT_FORMATS, SCREENSHOTS_DIR, save_screenshot, screenshot_name, map_viewport_width, draw_commands};
+use crate::rendering::screenshot::to_text;
+use crate::presence::{Activity, Presence, PresenceStatus};
+use crate::config::{ConfigFile, CONFIG_PATH};
+use crate::language_model::{ModelQueue, MODEL_CACHE_PATH};
+use crate::localization::{self, tr, Message};
+use crate::accessibility::{Narrator, describe_surroundings, describe_visible_enemies};
+use crate::achievements::{AchievementSystem, AchievementNotificationSystem, NotificationConfig};
+use crate::ui::{HelpSystem, SaveLoadUI, SaveLoadResult, UiBackend, TerminalUiBackend, default_ui_backend, TutorialEvents, TutorialTrigger, TutorialMessage, InventoryUI, InventoryAction, CharacterSheet, LevelUpScreen, HotbarAction, OptionsScreen, OptionsInput, RevivalScreen, DeathAction, PrayerScreen, PrayerAction, FurnitureMenu, FurnitureChoice, RivalMenu, TownMenu, TownChoice, BestiaryScreen, AgentScreen, AgentAction, GuildOverview, HallAction, StatusIconRegistry, UIBar, is_expiring, status_detail};
+use crate::progression::{PlayerHistorySystem, UnlockableContentSystem, UnlockableContentSaveData, UNLOCKS_PATH, Bestiary, BESTIARY_PATH, Bank, BANK_PATH, WorldEvents, WORLD_PATH};
+use crate::guild::{CraftingService, GuildRoster, HallFacility, GUILD_PATH, MAX_AGENTS};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use crate::character_creation::{CharacterCreationState, handle_character_creation_input, render_character_creation};
 
 pub use state_machine::StateType;
 use state_stack::StateStack;
 
+/// Turns a plain rest lasts
+pub const DEFAULT_REST_TURNS: u32 = 20;
+
+/// Turns between the automatic saves of an ironman run; each new level saves too
+const IRONMAN_SAVE_TURNS: u32 = 100;
+
+/// How often the main menu looks again for the save Continue would load
+const CONTINUE_REFRESH: Duration = Duration::from_secs(2);
+
+/// Effects listed in the look panel before the description takes over
+const LOOK_STATUS_LINES: usize = 3;
+
+/// Most reused model answers listed when wizard mode inspects the cache
+const MODEL_CACHE_SHOWN: usize = 5;
+
+/// The main menu's entries below Continue, in the order they are drawn
+const MAIN_MENU_OPTIONS: [&str; 8] = ["menu.new_game", "menu.random_character", "menu.load_game", "menu.options", "menu.help", "menu.run_history", "menu.bestiary", "menu.quit"];
+
 pub struct GameState {
     pub running: bool,
     pub state_stack: StateStack,
     pub world: World,
     pub player: Option<Entity>,
-    pub current_depth: i32,
+    pub current_location: Location,
     pub turn_count: u32,
     pub system_runner: SystemRunner,
     pub run_state: RunState,
     pub character_creation: CharacterCreationState,
+    pub new_game_settings: GameSettings,
+    pub targeting: RangedTargeting,
+    /// Debug overlays for testing, such as what a looked-at monster's behavior tree is doing
+    pub wizard_mode: bool,
+    /// What a screenshot is written as
+    pub screenshot_formats: Vec<ScreenshotFormat>,
+    /// Rich presence and desktop notifications
+    pub presence: Presence,
+    /// Options, kept in `config.toml`
+    pub config: ConfigFile,
+    /// The tabbed Options screen
+    pub options: OptionsScreen,
+    /// The screen-reader transcript
+    pub narrator: Narrator,
+    /// Help topics and the tutorial steps already shown
+    pub help: HelpSystem,
+    /// Tutorial popups not yet dismissed, the oldest on screen
+    pub tutorial_popups: VecDeque<TutorialMessage>,
+    /// The inventory screen
+    pub inventory_ui: InventoryUI,
+    /// The save and load screens, and importing a hero
+    pub save_load: SaveLoadUI,
+    /// What the inventory, save and load, and help screens are drawn through
+    ui_backend: Box<dyn UiBackend>,
+    /// The tabbed character sheet
+    pub character_sheet: CharacterSheet,
+    /// Spending the points of a new level
+    pub level_up: LevelUpScreen,
+    /// What to do after falling
+    pub revival: RevivalScreen,
+    /// Praying to the gods, at an altar or anywhere
+    pub prayer: PrayerScreen,
+    pub furniture_menu: FurnitureMenu,
+    pub rival_menu: RivalMenu,
+    pub town_menu: TownMenu,
+    /// The enchanting station the player is working at, if any
+    pub enchanting: Option<EnchantingUI>,
+    /// The alchemy station the player is working at, if any
+    pub alchemy: Option<AlchemyUI>,
+    /// Browsing the monsters met so far
+    pub bestiary: BestiaryScreen,
+    /// Hiring the guild's agents and sending them out on contracts
+    pub agents: AgentScreen,
+    /// The guild's hall: its stores, its rooms and its alchemist
+    pub guild_hall: GuildOverview,
+    /// Picking a place to travel to on the known map
+    pub travel: TravelCursor,
+    /// The turn and level of an ironman run's last save
+    ironman_checkpoint: Option<(u32, Location)>,
+    /// The save the main menu's Continue entry picks up, if there is one
+    pub continue_save: Option<SaveSlot>,
+    /// When the saves were last looked over for `continue_save`
+    continue_checked: Option<Instant>,
+    /// Why the last attempt to continue failed, shown under the main menu
+    pub main_menu_notice: Option<String>,
 }
 
 impl GameState {
@@ -39,31 +140,162 @@ impl GameState {
         world.insert(GameLog::new(100));
         world.insert(RandomNumberGenerator::new_with_random_seed());
         world.insert(GameStateResource::default());
+        world.insert(SpawnHistory::new());
+        world.insert(BranchLayout::default());
+        world.insert(DungeonLevels::default());
+        world.insert(RunStatistics::default());
+        world.insert(ArtifactDatabase::builtin());
+        world.insert(MonsterBehaviors::builtin());
+        world.insert(ArtifactRegistry::default());
+        world.insert(AlchemyJournal::default());
+        world.insert(AreaEffectQueue::default());
+        world.insert(AnimationQueue::default());
+        world.insert(ParticleEmitter::default());
+        world.insert(Weather::default());
+        world.insert(GameClock::default());
+        world.insert(LevelTension::default());
+        world.insert(WanderingSpawns::default());
+        world.insert(NoiseEvents::default());
+        world.insert(GasReleases::default());
+        world.insert(DivineDeeds::default());
+        world.insert(Reputation::default());
+        world.insert(TownWatch::default());
+        world.insert(TutorialEvents::default());
+        world.insert(GameEvents::default());
+        world.insert(AchievementSystem::new());
+        world.insert(AchievementNotificationSystem::new(NotificationConfig::default()));
+        let mut unlocks = UnlockableContentSystem::new();
+        unlocks.import_data(UnlockableContentSaveData::load(UNLOCKS_PATH).unwrap_or_default());
+        world.insert(unlocks);
+        world.insert(Bestiary::load(BESTIARY_PATH).unwrap_or_default());
+        world.insert(Bank::load(BANK_PATH).unwrap_or_default());
+        world.insert(WorldEvents::load(WORLD_PATH).unwrap_or_default());
+        let mut roster = GuildRoster::load(GUILD_PATH).unwrap_or_default();
+        if roster.recruits.is_empty() {
+            roster.refresh_recruits(&mut RandomNumberGenerator::new_with_random_seed());
+        }
+        world.insert(roster);
+        world.insert(ModelQueue::default());
+        world.insert(PlayerHistorySystem::default());
+        world.insert(Squads::default());
+        world.insert(DijkstraMaps::default());
+        world.insert(StatusIconRegistry::default());
         
         // Create a default map (will be replaced when a game starts)
         let map = Map::new(80, 50, 1);
         world.insert(map);
         
-        GameState {
+        let mut system_runner = SystemRunner::new();
+        system_runner.setup(&mut world);
+        
+        let (config, config_error) = ConfigFile::load(CONFIG_PATH);
+        localization::set_language(&config.config.language);
+        {
+            let mut log = world.write_resource::<GameLog>();
+            if let Some(e) = config_error {
+                log.add_message(Message::new("log.default_options").arg("error", e));
+            }
+            for e in localization::init() {
+                log.add_message(Message::new("log.catalog_not_loaded").arg("error", e));
+            }
+        }
+        
+        let mut state = GameState {
             running: true,
             state_stack: StateStack::new(),
             world,
             player: None,
-            current_depth: 1,
+            current_location: Location::default(),
             turn_count: 0,
-            system_runner: SystemRunner::new(),
+            system_runner,
             run_state: RunState::MainMenu,
             character_creation: CharacterCreationState::new(),
+            new_game_settings: GameSettings::new(GameMode::Normal),
+            targeting: RangedTargeting::default(),
+            wizard_mode: false,
+            screenshot_formats: DEFAULT_FORMATS.to_vec(),
+            presence: Presence::from_features(),
+            config,
+            options: OptionsScreen::new(),
+            narrator: Narrator::default(),
+            help: HelpSystem::new(),
+            tutorial_popups: VecDeque::new(),
+            inventory_ui: InventoryUI::new(),
+            save_load: SaveLoadUI::new(),
+            ui_backend: default_ui_backend().unwrap_or_else(|_| Box::new(TerminalUiBackend::new())),
+            character_sheet: CharacterSheet::new(),
+            level_up: LevelUpScreen::new(),
+            revival: RevivalScreen::new(),
+            prayer: PrayerScreen::new(),
+            furniture_menu: FurnitureMenu::new(),
+            rival_menu: RivalMenu::new(),
+            town_menu: TownMenu::new(),
+            enchanting: None,
+            alchemy: None,
+            bestiary: BestiaryScreen::new(),
+            agents: AgentScreen::new(),
+            guild_hall: GuildOverview::new(),
+            travel: TravelCursor::default(),
+            ironman_checkpoint: None,
+            continue_save: None,
+            continue_checked: None,
+            main_menu_notice: None,
+        };
+        state.apply_config();
+        state.refresh_continue_save();
+        state
+    }
+    
+    /// Put the options into effect: on the screen, in the new game settings and on the current character
+    fn apply_config(&mut self) {
+        let config = self.config.config.clone();
+        config.apply_to(&mut self.new_game_settings);
+        if let Some(player) = self.player {
+            if let Some(settings) = self.world.write_storage::<GameSettings>().get_mut(player) {
+                config.apply_to(settings);
+            }
+        }
+        self.screenshot_formats = DEFAULT_FORMATS.to_vec();
+        if config.display.screenshot_html {
+            self.screenshot_formats.push(ScreenshotFormat::Html);
+        }
+        localization::set_language(&config.language);
+        self.help.tutorial_enabled = config.tutorial.enabled;
+        if !config.tutorial.enabled {
+            self.tutorial_popups.clear();
+        }
+        self.world.insert(config.motion());
+        let transcript = Path::new(&config.accessibility.transcript);
+        if let Err(e) = self.narrator.set_enabled(config.accessibility.screen_reader, transcript) {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.transcript_failed").arg("error", e));
         }
+        if self.world.read_resource::<ModelQueue>().config != config.language_model {
+            // Keep what the old queue learned before the new one reads it back
+            if let Err(e) = self.world.read_resource::<ModelQueue>().save_cache(MODEL_CACHE_PATH) {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.model_cache_not_saved").arg("error", e));
+            }
+            let (queue, error) = ModelQueue::from_config(&config.language_model, MODEL_CACHE_PATH);
+            if let Some(e) = error {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.narrator_unavailable").arg("error", e));
+            }
+            self.world.insert(queue);
+        }
+        let _ = crate::rendering::with_terminal(|terminal| {
+            terminal.set_glyph_mode(config.display.glyphs.mode());
+            terminal.set_color_mode(config.display.colors);
+            Ok(())
+        });
+        self.world.insert(config);
     }
     
     // Initialize a new game
     fn initialize_new_game(&mut self) {
-        // Clear existing entities
+        // Clear existing entities, and anything they were waiting on the model for
         self.world.delete_all();
+        self.world.read_resource::<ModelQueue>().cancel_owned();
         
         // Create a new map
-        let mut map = Map::new(80, 50, 1);
+        let mut map = Map::new(LEVEL_WIDTH, LEVEL_HEIGHT, 1);
         
         // For now, just create a simple room in the center of the map
         for y in 20..30 {
@@ -77,32 +309,61 @@ impl GameState {
         // Place the player in the center of the room
         let player_x = 40;
         let player_y = 25;
+        map.entrance = (player_x, player_y);
+        map.exit = (48, 28);
+        map.set_tile(48, 28, crate::map::TileType::DownStairs);
+        
+        // Seed the run, either as asked or afresh
+        {
+            let mut rng = self.world.write_resource::<RandomNumberGenerator>();
+            *rng = match self.new_game_settings.seed {
+                Some(seed) => RandomNumberGenerator::new(seed),
+                None => RandomNumberGenerator::new_with_random_seed(),
+            };
+        }
+        
+        // Decide where the side branches split off, then let the spawn director pick this level's monsters
+        let difficulty = self.new_game_settings.difficulty;
+        let clock = GameClock::default();
+        let daylight = !clock.is_night();
+        self.world.insert(clock);
+        let spawns = {
+            let mut rng = self.world.write_resource::<RandomNumberGenerator>();
+            let rng = rng.stream(MAPGEN_STREAM);
+            let mut layout = BranchLayout::generate(rng);
+            layout.record_visit(map.location);
+            place_branch_entrances(&mut map, &layout, rng, &[(player_x, player_y)]);
+            *self.world.write_resource::<BranchLayout>() = layout;
+            SpawnDirector::plan_level_at(&map, RunPhase::Descent, difficulty, daylight, rng, &[(player_x, player_y)])
+        };
+        self.world.write_resource::<SpawnHistory>().clear();
+        *self.world.write_resource::<DungeonLevels>() = DungeonLevels::default();
+        self.current_location = map.location;
         
         // Update the map resource
         self.world.insert(map);
         
         // Create the player entity
         let player = EntityFactory::create_player(&mut self.world, player_x, player_y);
+        self.world.write_storage::<GameSettings>()
+            .insert(player, self.new_game_settings.clone())
+            .expect("Unable to insert game settings");
         self.player = Some(player);
+        self.world.write_resource::<ParticleEmitter>().stair_transition((player_x, player_y));
         
-        // Generate a new seed for the RNG
-        {
-            let mut rng = self.world.write_resource::<RandomNumberGenerator>();
-            *rng = RandomNumberGenerator::new_with_random_seed();
-        }
+        // Now create the monsters, some of them elites
+        populate_level(&mut self.world, spawns, difficulty);
         
-        // Add monsters
-        {
+        // Each artifact can turn up once per run
+        let artifact = {
+            let database = self.world.read_resource::<ArtifactDatabase>();
+            let mut registry = self.world.write_resource::<ArtifactRegistry>();
             let mut rng = self.world.write_resource::<RandomNumberGenerator>();
-            let monster_type1 = rng.range(0, 3);
-            let monster_type2 = rng.range(0, 3);
-            
-            // Release the RNG borrow
-            drop(rng);
-            
-            // Now create the monsters
-            EntityFactory::create_monster(&mut self.world, 42, 23, monster_type1);
-            EntityFactory::create_monster(&mut self.world, 45, 27, monster_type2);
+            registry.clear();
+            registry.roll(&database, 1, rng.stream(LOOT_STREAM))
+        };
+        if let Some(definition) = artifact {
+            EntityFactory::create_artifact(&mut self.world, 42, 22, &definition);
         }
         
         // Add a health potion
@@ -111,27 +372,119 @@ impl GameState {
         // Add stairs down
         EntityFactory::create_stairs_down(&mut self.world, 48, 28);
         
+        // And a campfire by the entrance to rest at
+        EntityFactory::create_campfire(&mut self.world, 32, 22);
+        
         // Reset game state
         {
             let mut game_state = self.world.write_resource::<GameStateResource>();
             game_state.turn_count = 0;
             game_state.depth = 1;
+            game_state.location = self.current_location;
+            game_state.phase = RunPhase::Descent;
             game_state.game_over = false;
         }
         
+        self.revival = RevivalScreen::new();
+        self.prayer = PrayerScreen::new();
+        self.furniture_menu = FurnitureMenu::new();
+        self.rival_menu = RivalMenu::new();
+        self.town_menu = TownMenu::new();
+        self.enchanting = None;
+        self.alchemy = None;
+        self.agents = AgentScreen::new();
+        self.guild_hall = GuildOverview::new();
+        self.ironman_checkpoint = None;
+        
+        // Start a fresh run record
+        *self.world.write_resource::<RunStatistics>() = RunStatistics::default();
+        *self.world.write_resource::<AlchemyJournal>() = AlchemyJournal::default();
+        *self.world.write_resource::<Weather>() = Weather::default();
+        *self.world.write_resource::<LevelTension>() = LevelTension::default();
+        *self.world.write_resource::<WanderingSpawns>() = WanderingSpawns::default();
+        *self.world.write_resource::<NoiseEvents>() = NoiseEvents::default();
+        *self.world.write_resource::<GasReleases>() = GasReleases::default();
+        *self.world.write_resource::<DivineDeeds>() = DivineDeeds::default();
+        *self.world.write_resource::<Reputation>() = Reputation::default();
+        *self.world.write_resource::<TownWatch>() = TownWatch::default();
+        *self.world.write_resource::<Squads>() = Squads::default();
+        *self.world.write_resource::<TutorialEvents>() = TutorialEvents::default();
+        // Achievements are the player's, not the character's, so only the unheard events go
+        *self.world.write_resource::<GameEvents>() = GameEvents::default();
+        *self.world.write_resource::<PlayerHistorySystem>() = PlayerHistorySystem::default();
+        self.tutorial_popups.clear();
+        self.world.write_resource::<DijkstraMaps>().clear();
+        
         // Add a welcome message
         {
             let mut log = self.world.write_resource::<GameLog>();
             log.clear();
-            log.add_entry("Welcome to ASCII Dungeon Explorer!".to_string());
-            log.add_entry("Use arrow keys or HJKL to move.".to_string());
+            log.add_message(Message::new("log.welcome"));
+            log.add_message(Message::new("log.movement_hint"));
+        }
+        
+        // The world above has moved on since the last character
+        {
+            let mut world_events = self.world.write_resource::<WorldEvents>();
+            let mut rng = self.world.write_resource::<RandomNumberGenerator>();
+            let mut log = self.world.write_resource::<GameLog>();
+            for news in world_events.new_run(rng.stream(WORLD_STREAM)) {
+                report_world_news(news, &mut log);
+            }
         }
+        self.world.write_resource::<TutorialEvents>().publish(TutorialTrigger::GameStart);
         
         // Set the current state to playing
         self.state_stack.replace(StateType::Playing);
     }
     
+    /// Write the current character to a hero file, returning where it went
+    pub fn export_hero(&self) -> Result<PathBuf, HeroError> {
+        let player = self.player.ok_or(HeroError::NoCharacter)?;
+        HeroFile::export(&self.world, player)?.write(Path::new(HEROES_DIR))
+    }
+    
+    /// Start a new run as an imported hero instead of a fresh character
+    pub fn start_run_with_hero(&mut self, hero: HeroRecord) {
+        self.initialize_new_game();
+        let fresh = match self.player {
+            Some(fresh) => fresh,
+            None => return,
+        };
+        let (x, y) = self.world.read_storage::<Position>().get(fresh).map_or((40, 25), |pos| (pos.x, pos.y));
+        self.world.delete_entity(fresh).expect("Unable to replace the new character");
+        self.world.maintain();
+        
+        let player = hero.validated().spawn(&mut self.world, x, y);
+        self.world.write_storage::<GameSettings>()
+            .insert(player, self.new_game_settings.clone())
+            .expect("Unable to insert game settings");
+        self.player = Some(player);
+        let name = self.world.read_storage::<Name>().get(player).map(|name| name.name.clone()).unwrap_or_default();
+        self.world.write_resource::<GameLog>().add_message(Message::new("log.sets_out_again").arg("name", name));
+    }
+    
+    /// Save what is on screen under `screenshots/`, returning the files written
+    pub fn take_screenshot(&self) -> std::io::Result<Vec<PathBuf>> {
+        let screen = crate::rendering::with_terminal(|terminal| Ok(terminal.screen().clone()))?;
+        let directory = Path::new(SCREENSHOTS_DIR);
+        save_screenshot(&screen, directory, &screenshot_name(directory, "screenshot"), &self.screenshot_formats)
+    }
+    
     pub fn handle_input(&mut self, key_event: KeyEvent) {
+        // A screenshot can be taken from any screen
+        if key_event.code == KeyCode::F(2) {
+            let message = match self.take_screenshot() {
+                Ok(paths) => match paths.first() {
+                    Some(path) => Message::new("log.screenshot_saved").arg("path", path.with_extension("*").display()),
+                    None => Message::new("log.no_screenshot_formats"),
+                },
+                Err(e) => Message::new("log.screenshot_failed").arg("error", e),
+            };
+            self.world.write_resource::<GameLog>().add_message(message);
+            return;
+        }
+        
         // Handle character creation input if in character creation state
         if matches!(self.run_state, 
             RunState::CharacterName | 
@@ -152,10 +505,21 @@ impl GameState {
             StateType::Inventory => self.handle_inventory_input(key_event),
             StateType::CharacterSheet => self.handle_character_sheet_input(key_event),
             StateType::GameOver => self.handle_game_over_input(key_event),
+            StateType::Revival => self.handle_revival_input(key_event),
+            StateType::Prayer => self.handle_prayer_input(key_event),
+            StateType::Furniture => self.handle_furniture_input(key_event),
+            StateType::Parley => self.handle_rival_input(key_event),
+            StateType::TownTalk => self.handle_town_input(key_event),
+            StateType::Enchanting => self.handle_enchanting_input(key_event),
+            StateType::Alchemy => self.handle_alchemy_input(key_event),
+            StateType::Victory => self.handle_victory_input(key_event),
+            StateType::RunHistory => self.handle_run_history_input(key_event),
+            StateType::Bestiary => self.handle_bestiary_input(key_event),
             StateType::LevelUp => self.handle_level_up_input(key_event),
             StateType::Targeting => self.handle_targeting_input(key_event),
-            StateType::SaveGame => self.handle_save_game_input(key_event),
-            StateType::LoadGame => self.handle_load_game_input(key_event),
+            StateType::Look => self.handle_look_input(key_event),
+            StateType::Travel => self.handle_travel_input(key_event),
+            StateType::SaveGame | StateType::LoadGame => self.handle_save_load_input(key_event),
             StateType::Options => self.handle_options_input(key_event),
             StateType::Help => self.handle_help_input(key_event),
             StateType::Pause => self.handle_pause_input(key_event),
@@ -166,16 +530,42 @@ impl GameState {
     }
     
     fn handle_main_menu_input(&mut self, key_event: KeyEvent) {
+        self.main_menu_notice = None;
         match key_event.code {
+            KeyCode::Char('c') => {
+                // Pick up the most recent save without going through the load menu
+                if self.continue_save.is_some() {
+                    self.continue_game();
+                }
+            },
             KeyCode::Char('n') => {
                 // Start character creation
                 self.run_state = RunState::CharacterCreation;
                 self.character_creation = CharacterCreationState::new();
+                self.character_creation.apply_unlocks(&self.world.read_resource::<UnlockableContentSystem>());
+                self.character_creation.apply_estate(&self.world.read_resource::<Bank>());
                 self.run_state = RunState::CharacterName;
             },
+            KeyCode::Char('r') => {
+                // Quick-start with a random character, straight to confirmation
+                let mut creation = CharacterCreationState::new();
+                creation.apply_unlocks(&self.world.read_resource::<UnlockableContentSystem>());
+                creation.apply_estate(&self.world.read_resource::<Bank>());
+                creation.randomize(&mut self.world.write_resource::<RandomNumberGenerator>());
+                self.character_creation = creation;
+                self.run_state = RunState::CharacterConfirm;
+            },
+            KeyCode::Char('d') => {
+                // Cycle the difficulty for the next new game
+                let next = self.new_game_settings.difficulty.next();
+                self.new_game_settings.difficulty = next;
+            },
             KeyCode::Char('l') => {
                 // Load a game
-                self.state_stack.push(StateType::LoadGame);
+                match self.open_saves(StateType::LoadGame) {
+                    Ok(()) => self.state_stack.push(StateType::LoadGame),
+                    Err(e) => self.main_menu_notice = Some(Message::new("log.saves_unavailable").arg("error", e).text()),
+                }
             },
             KeyCode::Char('o') => {
                 // Options
@@ -183,8 +573,18 @@ impl GameState {
             },
             KeyCode::Char('h') => {
                 // Help
+                self.help.open(None);
                 self.state_stack.push(StateType::Help);
             },
+            KeyCode::Char('v') => {
+                // Past runs and found artifacts
+                self.state_stack.push(StateType::RunHistory);
+            },
+            KeyCode::Char('b') => {
+                // Every monster met in any run
+                self.bestiary.open(&self.world.read_resource::<Bestiary>(), None);
+                self.state_stack.push(StateType::Bestiary);
+            },
             KeyCode::Char('q') => {
                 // Quit the game
                 self.running = false;
@@ -194,17 +594,52 @@ impl GameState {
     }
     
     fn handle_playing_input(&mut self, key_event: KeyEvent) {
+        // Any key wakes a resting player or halts an exploring one
+        if let Some(player) = self.player {
+            if self.world.write_storage::<WantsToRest>().remove(player).is_some() {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.stop_resting"));
+                return;
+            }
+            if self.world.write_storage::<WantsToExplore>().remove(player).is_some() {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.stop_exploring"));
+                return;
+            }
+            if self.world.write_storage::<WantsToTravel>().remove(player).is_some() {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.stop_travelling"));
+                return;
+            }
+        }
+        
         match key_event.code {
             KeyCode::Char('i') => {
                 // Open inventory
+                if let Some(player) = self.player {
+                    self.inventory_ui.open(player);
+                    self.inventory_ui.update_filtered_items(&self.world);
+                }
                 self.state_stack.push(StateType::Inventory);
+                self.world.write_resource::<TutorialEvents>().publish(TutorialTrigger::OpenInventory);
             },
             KeyCode::Char('c') => {
                 // Open character sheet
+                if let Some(player) = self.player {
+                    self.character_sheet.open(player);
+                }
                 self.state_stack.push(StateType::CharacterSheet);
+                self.world.write_resource::<TutorialEvents>().publish(TutorialTrigger::OpenCharacterScreen);
+            },
+            KeyCode::Enter => {
+                // Dismiss the tutorial popup on screen
+                self.tutorial_popups.pop_front();
             },
-            KeyCode::Char('g') => {
-                // Open guild management
+            KeyCode::Char('G') => {
+                // Look over the guild's agents
+                self.agents.open();
+                self.state_stack.push(StateType::AgentConfiguration);
+            },
+            KeyCode::Char('H') => {
+                // Look over the guild's hall and stores
+                self.guild_hall.open();
                 self.state_stack.push(StateType::GuildManagement);
             },
             KeyCode::Esc => {
@@ -212,164 +647,1832 @@ impl GameState {
                 self.state_stack.push(StateType::Pause);
             },
             KeyCode::Char('s') => {
-                // Save game
-                self.state_stack.push(StateType::SaveGame);
+                // Save game, unless the run saves itself
+                if self.is_ironman() {
+                    self.world.write_resource::<GameLog>().add_message(Message::new("log.ironman_no_manual_save"));
+                } else if let Err(e) = self.open_saves(StateType::SaveGame) {
+                    self.world.write_resource::<GameLog>().add_message(Message::new("log.saves_unavailable").arg("error", e));
+                } else {
+                    self.state_stack.push(StateType::SaveGame);
+                }
+            },
+            KeyCode::Char('f') => {
+                // Line up a shot with the equipped bow or crossbow
+                if let Some(player) = self.player {
+                    match RangedTargeting::begin(&self.world, player) {
+                        Ok(targeting) => {
+                            self.targeting = targeting;
+                            self.state_stack.push(StateType::Targeting);
+                        },
+                        Err(reason) => self.world.write_resource::<GameLog>().add_entry(reason),
+                    }
+                }
+            },
+            KeyCode::Char('e') => {
+                // Search a corpse for loot
+                match (self.player, self.nearby_corpse()) {
+                    (Some(player), Some(corpse)) => {
+                        self.world.write_storage::<WantsToInteract>()
+                            .insert(player, WantsToInteract { target: corpse })
+                            .expect("Unable to insert interaction intent");
+                    },
+                    _ => self.world.write_resource::<GameLog>().add_message(Message::new("log.no_corpse_to_search")),
+                }
+            },
+            KeyCode::Char('B') => {
+                // Carve a corpse into food
+                match (self.player, self.nearby_corpse()) {
+                    (Some(player), Some(corpse)) => {
+                        self.world.write_storage::<WantsToButcher>()
+                            .insert(player, WantsToButcher { corpse })
+                            .expect("Unable to insert butcher intent");
+                    },
+                    _ => self.world.write_resource::<GameLog>().add_message(Message::new("log.no_corpse_to_butcher")),
+                }
+            },
+            KeyCode::Char('z') => {
+                // Aim an area ability such as Fireball
+                if let Some(player) = self.player {
+                    match RangedTargeting::begin_ability(&self.world, player) {
+                        Ok(targeting) => {
+                            self.targeting = targeting;
+                            self.state_stack.push(StateType::Targeting);
+                        },
+                        Err(reason) => self.world.write_resource::<GameLog>().add_entry(reason),
+                    }
+                }
+            },
+            KeyCode::Char(digit @ '1'..='9') => {
+                // Fire whatever is on that hotbar slot
+                if let Some(player) = self.player {
+                    let slot = digit as usize - '1' as usize;
+                    match crate::ui::activate_hotbar_slot(&self.world, player, slot) {
+                        Ok(HotbarAction::Used) => {},
+                        Ok(HotbarAction::Aim(ability)) => match RangedTargeting::aim_ability(&self.world, player, ability) {
+                            Ok(targeting) => {
+                                self.targeting = targeting;
+                                self.state_stack.push(StateType::Targeting);
+                            },
+                            Err(reason) => self.world.write_resource::<GameLog>().add_entry(reason),
+                        },
+                        Err(reason) => self.world.write_resource::<GameLog>().add_entry(reason),
+                    }
+                }
+            },
+            KeyCode::Char('Z') => {
+                // Zoom the map out to see more of the level at once
+                if !self.system_runner.render_system.context.toggle_zoom() {
+                    self.world.write_resource::<GameLog>().add_message(Message::new("log.screen_too_small"));
+                }
             },
+            KeyCode::Char('r') => {
+                // Rest a while
+                self.begin_rest(WantsToRest::turns(DEFAULT_REST_TURNS));
+            },
+            KeyCode::Char('R') => {
+                // Rest until healed or something interrupts
+                self.begin_rest(WantsToRest::until_healed());
+            },
+            KeyCode::Char('o') => {
+                // Explore until something turns up
+                if let Some(player) = self.player {
+                    self.world.write_storage::<WantsToExplore>()
+                        .insert(player, WantsToExplore::default())
+                        .expect("Unable to insert explore intent");
+                }
+            },
+            KeyCode::Char('x') => {
+                // Look around at whatever is in view
+                if let Some(player) = self.player {
+                    self.targeting = RangedTargeting::begin_look(&self.world, player);
+                    self.state_stack.push(StateType::Look);
+                }
+            },
+            KeyCode::Char('p') => {
+                // Pray to your god, or to the god of the altar beside you
+                self.open_prayer();
+            },
+            KeyCode::Char('T') => {
+                // Pick somewhere already seen and walk there
+                if let Some(player) = self.player {
+                    self.travel = TravelCursor::begin(&self.world, player);
+                    self.state_stack.push(StateType::Travel);
+                }
+            },
+            KeyCode::F(3) | KeyCode::F(4) => {
+                // Put the surroundings or the enemies in view into words, for the log and transcript
+                if let Some(player) = self.player {
+                    let lines = if key_event.code == KeyCode::F(3) {
+                        describe_surroundings(&self.world, player)
+                    } else {
+                        describe_visible_enemies(&self.world, player)
+                    };
+                    let mut log = self.world.write_resource::<GameLog>();
+                    for line in lines {
+                        log.add_entry(line);
+                    }
+                }
+            },
+            KeyCode::F(12) => {
+                // Toggle the wizard-mode debug overlays
+                self.wizard_mode = !self.wizard_mode;
+                let message = if self.wizard_mode { "log.wizard_on" } else { "log.wizard_off" };
+                self.world.write_resource::<GameLog>().add_message(Message::new(message));
+            },
+            KeyCode::F(10) if self.wizard_mode => self.inspect_model_cache(),
+            KeyCode::F(11) if self.wizard_mode => self.purge_model_cache(),
             KeyCode::Char('q') => {
                 // Return to main menu
+                self.save_between_runs();
                 self.state_stack.clear();
             },
+            KeyCode::Char('S') => self.toggle_sprint(),
+            KeyCode::Char('O') => self.operate_door(DoorAction::Open),
+            KeyCode::Char('C') => self.operate_door(DoorAction::Close),
+            KeyCode::Char('K') => self.operate_door(DoorAction::Bash),
+            KeyCode::Char('J') => self.operate_door(DoorAction::Jam),
             _ => {
                 // Handle movement and other actions
-                // Will be implemented later
+                match handle_player_input(key_event) {
+                    PlayerAction::UseStairs => self.use_stairs(),
+                    action => {
+                        if let (Some(step), Some(player)) = (action.direction(), self.player) {
+                            // Bumping into an enchanting or alchemy station sets to work at it
+                            if let Some(station) = self.station_at_step::<EnchantingStation>(player, step) {
+                                self.enchanting = Some(EnchantingUI::new(&self.world, station, player));
+                                self.state_stack.push(StateType::Enchanting);
+                                return;
+                            }
+                            if let Some(station) = self.station_at_step::<AlchemyStation>(player, step) {
+                                self.alchemy = Some(AlchemyUI::new(station, player));
+                                self.state_stack.push(StateType::Alchemy);
+                                return;
+                            }
+                            // Bumping into furniture offers to use it rather than walking into it
+                            if let Some(furniture) = self.furniture_at_step(player, step) {
+                                self.furniture_menu.open(player, furniture);
+                                self.state_stack.push(StateType::Furniture);
+                                return;
+                            }
+                            // Bumping into a rival who isn't yet an enemy opens talks rather than a fight
+                            if let Some(rival) = self.rival_at_step(player, step) {
+                                self.rival_menu.open(player, rival);
+                                self.state_stack.push(StateType::Parley);
+                                return;
+                            }
+                            // Bumping into someone in town talks to them, unless the watch is out for blood
+                            if let Some(townsperson) = self.townsperson_at_step(player, step) {
+                                self.town_menu.open(player, townsperson);
+                                self.state_stack.push(StateType::TownTalk);
+                                return;
+                            }
+                            // Walking into a captive sets them free rather than striking them
+                            if let Some(captive) = self.captive_at_step(player, step) {
+                                self.world.write_storage::<WantsToRescue>()
+                                    .insert(player, WantsToRescue { captive })
+                                    .expect("Unable to insert rescue intent");
+                                self.world.write_resource::<GameStateResource>().turn_count += 1;
+                                return;
+                            }
+                            if let Some(input) = self.world.write_storage::<PlayerInput>().get_mut(player) {
+                                input.move_intent = Some(step);
+                            }
+                            self.world.write_resource::<GameStateResource>().turn_count += 1;
+                        }
+                    },
+                }
             }
         }
     }
     
-    fn handle_inventory_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for inventory input handling
+    fn handle_inventory_input(&mut self, key_event: KeyEvent) {
+        let action = self.inventory_ui.handle_key(key_event.code, &self.world);
+        if !self.inventory_ui.is_open() {
+            self.state_stack.pop();
+            return;
+        }
+        let (action, player, item) = match (action, self.player, self.inventory_ui.get_selected_item()) {
+            (Some(action), Some(player), Some((item, _))) => (action, player, item),
+            _ => return,
+        };
+        
+        // Anything that takes a turn closes the inventory so the turn can play out
+        match action {
+            InventoryAction::Equip => {
+                self.world.write_storage::<WantsToEquip>()
+                    .insert(player, WantsToEquip { item, slot: None })
+                    .expect("Unable to insert equip intent");
+            },
+            InventoryAction::Unequip => {
+                let slot = match self.inventory_ui.equipped_slot(&self.world, item) {
+                    Some(slot) => slot,
+                    None => return,
+                };
+                self.world.write_storage::<WantsToUnequip>()
+                    .insert(player, WantsToUnequip { slot })
+                    .expect("Unable to insert unequip intent");
+            },
+            InventoryAction::Use => {
+                self.world.write_storage::<WantsToUseConsumable>()
+                    .insert(player, WantsToUseConsumable { item, target: None })
+                    .expect("Unable to insert use intent");
+            },
+            InventoryAction::Drop => {
+                self.world.write_storage::<WantsToDropItem>()
+                    .insert(player, WantsToDropItem { item, quantity: self.inventory_ui.quantity })
+                    .expect("Unable to insert drop intent");
+            },
+            InventoryAction::Split => {
+                // Splitting a stack takes no time, so the inventory stays open
+                let quantity = self.inventory_ui.quantity;
+                let message = match crate::items::split_stack(&mut self.world, player, item, quantity) {
+                    Some(_) => Message::new("log.stack_split").arg("count", quantity),
+                    None => Message::new("log.stack_not_split"),
+                };
+                self.world.write_resource::<GameLog>().add_message(message);
+                self.inventory_ui.update_filtered_items(&self.world);
+                return;
+            },
+            InventoryAction::Throw => {
+                // Aiming happens on the targeting screen, which sends the throw on its way
+                match RangedTargeting::begin_throw(&self.world, player, item) {
+                    Ok(targeting) => {
+                        self.inventory_ui.close();
+                        self.state_stack.pop();
+                        self.targeting = targeting;
+                        self.state_stack.push(StateType::Targeting);
+                    },
+                    Err(reason) => self.world.write_resource::<GameLog>().add_entry(reason),
+                }
+                return;
+            },
+            InventoryAction::Repair => {
+                // Mending gear in the field takes a kit from the pack; the blacksmith in town takes gold instead
+                let kit = match crate::items::repair_kit_for(&self.world, player, item) {
+                    Some(kit) => kit,
+                    None => {
+                        self.world.write_resource::<GameLog>().add_message(Message::new("log.no_repair_kit"));
+                        return;
+                    },
+                };
+                self.world.write_storage::<WantsToRepair>()
+                    .insert(player, WantsToRepair { item, source: RepairSource::Kit(kit) })
+                    .expect("Unable to insert repair intent");
+            },
+            other => {
+                self.world.write_resource::<GameLog>()
+                    .add_message(Message::new("log.not_available_here").arg("action", other.to_string()));
+                return;
+            },
+        }
+        self.inventory_ui.close();
+        self.state_stack.pop();
     }
     
-    fn handle_character_sheet_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for character sheet input handling
+    fn handle_character_sheet_input(&mut self, key_event: KeyEvent) {
+        self.character_sheet.handle_key(key_event.code, &self.world);
+        if !self.character_sheet.is_open() {
+            self.state_stack.pop();
+        }
     }
     
-    fn handle_game_over_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for game over input handling
+    fn handle_revival_input(&mut self, key_event: KeyEvent) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        match self.revival.handle_key(key_event.code, &self.world) {
+            Some(DeathAction::ReviveWithPenalty) => self.revive(player, None),
+            Some(DeathAction::UseRevivalItem(item)) => self.revive(player, Some(item)),
+            Some(DeathAction::GiveUp) => {
+                self.world.write_resource::<GameStateResource>().game_over = true;
+                self.end_run_in_death();
+            },
+            None => {},
+        }
     }
     
-    fn handle_level_up_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for level up input handling
+    fn open_prayer(&mut self) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let altar = self.world.read_storage::<Position>().get(player)
+            .and_then(|pos| altar_near(&self.world, (pos.x, pos.y)))
+            .map(|(_, deity)| deity);
+        if altar.is_none() && !self.world.read_storage::<Piety>().contains(player) {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.no_god"));
+            return;
+        }
+        self.prayer.open(player, altar);
+        self.state_stack.push(StateType::Prayer);
     }
-    
-    fn handle_targeting_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for targeting input handling
+
+    fn handle_prayer_input(&mut self, key_event: KeyEvent) {
+        let action = self.prayer.handle_key(key_event.code, &self.world);
+        let player = match (self.prayer.is_open(), self.player) {
+            (true, Some(player)) => player,
+            _ => {
+                self.state_stack.pop();
+                return;
+            },
+        };
+        let answer = match action {
+            Some(PrayerAction::Devote(deity)) => devote(&mut self.world, player, deity),
+            Some(PrayerAction::Ask(boon)) => match pray(&mut self.world, player, boon) {
+                Ok(answer) => answer,
+                // An unanswered prayer costs no time, so the player may ask for something else
+                Err(refusal) => {
+                    self.world.write_resource::<GameLog>().add_message(refusal);
+                    return;
+                },
+            },
+            None => return,
+        };
+        self.world.write_resource::<GameLog>().add_message(answer);
+        self.world.write_resource::<GameStateResource>().turn_count += 1;
+        self.prayer.close();
+        self.state_stack.pop();
     }
     
-    fn handle_save_game_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for save game input handling
+    /// The furniture one step from the player in the given direction
+    fn furniture_at_step(&self, player: Entity, step: (i32, i32)) -> Option<Entity> {
+        let target = self.world.read_storage::<Position>().get(player).map(|pos| (pos.x + step.0, pos.y + step.1))?;
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let furniture = self.world.read_storage::<Furniture>();
+        (&entities, &positions, &furniture).join()
+            .find(|(_, pos, _)| (pos.x, pos.y) == target)
+            .map(|(entity, _, _)| entity)
     }
-    
-    fn handle_load_game_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for load game input handling
+
+    /// The crafting station of kind `S` the player would bump into
+    fn station_at_step<S: Component>(&self, player: Entity, step: (i32, i32)) -> Option<Entity> {
+        let target = self.world.read_storage::<Position>().get(player).map(|pos| (pos.x + step.0, pos.y + step.1))?;
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let stations = self.world.read_storage::<S>();
+        (&entities, &positions, &stations).join()
+            .find(|(_, pos, _)| (pos.x, pos.y) == target)
+            .map(|(entity, _, _)| entity)
     }
-    
-    fn handle_options_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for options input handling
+
+    fn captive_at_step(&self, player: Entity, step: (i32, i32)) -> Option<Entity> {
+        let target = self.world.read_storage::<Position>().get(player).map(|pos| (pos.x + step.0, pos.y + step.1))?;
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let captives = self.world.read_storage::<Captive>();
+        (&entities, &positions, &captives).join()
+            .find(|(_, pos, _)| (pos.x, pos.y) == target)
+            .map(|(entity, _, _)| entity)
     }
-    
-    fn handle_help_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for help input handling
+
+    /// A rival one step from the player in the given direction, unless their party has turned hostile
+    fn rival_at_step(&self, player: Entity, step: (i32, i32)) -> Option<Entity> {
+        let target = self.world.read_storage::<Position>().get(player).map(|pos| (pos.x + step.0, pos.y + step.1))?;
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let rivals = self.world.read_storage::<Rival>();
+        (&entities, &positions, &rivals).join()
+            .find(|(_, pos, rival)| (pos.x, pos.y) == target && rival.stance != RivalStance::Hostile)
+            .map(|(entity, _, _)| entity)
+    }
+
+    /// Someone in town one step from the player in the given direction, leaving out guards once
+    /// the watch has turned on the player
+    fn townsperson_at_step(&self, player: Entity, step: (i32, i32)) -> Option<Entity> {
+        let target = self.world.read_storage::<Position>().get(player).map(|pos| (pos.x + step.0, pos.y + step.1))?;
+        let standing = self.world.read_resource::<Reputation>().standing(Faction::Townsfolk);
+        let hostile = self.world.read_resource::<TownWatch>().guards_hostile(standing);
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let townsfolk = self.world.read_storage::<Townsperson>();
+        (&entities, &positions, &townsfolk).join()
+            .find(|(_, pos, person)| (pos.x, pos.y) == target && !(hostile && person.role == TownRole::Guard))
+            .map(|(entity, _, _)| entity)
+    }
+
+    fn handle_furniture_input(&mut self, key_event: KeyEvent) {
+        let choice = self.furniture_menu.handle_key(key_event.code, &self.world);
+        let (player, furniture) = match (self.furniture_menu.is_open(), self.player, self.furniture_menu.furniture()) {
+            (true, Some(player), Some(furniture)) => (player, furniture),
+            _ => {
+                self.furniture_menu.close();
+                self.state_stack.pop();
+                return;
+            },
+        };
+        let sacrifice = match choice {
+            Some(FurnitureChoice::Use) => None,
+            Some(FurnitureChoice::Sacrifice(item)) => Some(item),
+            _ => return,
+        };
+        self.world.write_storage::<WantsToUseFurniture>()
+            .insert(player, WantsToUseFurniture { furniture, sacrifice })
+            .expect("Unable to insert furniture intent");
+        self.world.write_resource::<GameStateResource>().turn_count += 1;
+        self.furniture_menu.close();
+        self.state_stack.pop();
     }
     
-    fn handle_pause_input(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Esc | KeyCode::Char('p') => {
-                // Resume game
+    fn handle_rival_input(&mut self, key_event: KeyEvent) {
+        let choice = self.rival_menu.handle_key(key_event.code, &self.world);
+        let (player, rival) = match (self.rival_menu.is_open(), self.player, self.rival_menu.rival()) {
+            (true, Some(player), Some(rival)) => (player, rival),
+            _ => {
+                self.rival_menu.close();
                 self.state_stack.pop();
+                return;
             },
-            KeyCode::Char('s') => {
-                // Save game
-                self.state_stack.replace(StateType::SaveGame);
+        };
+        let offer = match choice.and_then(|choice| choice.offer()) {
+            Some(offer) => offer,
+            None => return,
+        };
+        self.world.write_storage::<WantsToParley>()
+            .insert(player, WantsToParley { rival, offer })
+            .expect("Unable to insert parley intent");
+        self.world.write_resource::<GameStateResource>().turn_count += 1;
+        self.rival_menu.close();
+        self.state_stack.pop();
+    }
+    
+    fn handle_town_input(&mut self, key_event: KeyEvent) {
+        let choice = self.town_menu.handle_key(key_event.code, &self.world);
+        let (player, townsperson) = match (self.town_menu.is_open(), self.player, self.town_menu.townsperson()) {
+            (true, Some(player), Some(townsperson)) => (player, townsperson),
+            _ => {
+                self.town_menu.close();
+                self.state_stack.pop();
+                return;
             },
-            KeyCode::Char('l') => {
-                // Load game
-                self.state_stack.replace(StateType::LoadGame);
+        };
+        if let Some(TownChoice::Train(training @ (Training::Skills | Training::Attributes))) = choice {
+            let cost = self.town_menu.training_cost(&self.world, training);
+            self.town_menu.close();
+            self.state_stack.pop();
+            self.level_up.open_training(&self.world, player, training, cost);
+            self.state_stack.push(StateType::LevelUp);
+            return;
+        }
+        if let Some(TownChoice::Repair(item)) = choice {
+            self.world.write_storage::<WantsToRepair>()
+                .insert(player, WantsToRepair { item, source: RepairSource::Blacksmith })
+                .expect("Unable to insert repair intent");
+            self.world.write_resource::<GameStateResource>().turn_count += 1;
+            self.town_menu.close();
+            self.state_stack.pop();
+            return;
+        }
+        if let Some(TownChoice::LiftCurse(item)) = choice {
+            self.world.write_storage::<WantsToRemoveCurse>()
+                .insert(player, WantsToRemoveCurse { item, source: CurseRemovalSource::Cleric })
+                .expect("Unable to insert curse removal intent");
+            self.world.write_resource::<GameStateResource>().turn_count += 1;
+            self.town_menu.close();
+            self.state_stack.pop();
+            return;
+        }
+        let deal = match choice.and_then(|choice| choice.deal()) {
+            Some(deal) => deal,
+            None => return,
+        };
+        self.world.write_storage::<WantsToDeal>()
+            .insert(player, WantsToDeal { townsperson, deal })
+            .expect("Unable to insert deal intent");
+        self.world.write_resource::<GameStateResource>().turn_count += 1;
+        self.town_menu.close();
+        self.state_stack.pop();
+    }
+    
+    fn handle_enchanting_input(&mut self, key_event: KeyEvent) {
+        let (action, player) = match (self.enchanting.as_mut(), self.player) {
+            (Some(enchanting), Some(player)) => (enchanting.handle_input(key_event, &self.world), player),
+            _ => {
+                self.enchanting = None;
+                self.state_stack.pop();
+                return;
             },
-            KeyCode::Char('o') => {
-                // Options
-                self.state_stack.replace(StateType::Options);
+        };
+        match action {
+            EnchantingAction::None => return,
+            EnchantingAction::Close => {},
+            EnchantingAction::Enchant(item, recipe, station) => {
+                self.world.write_storage::<WantsToEnchant>()
+                    .insert(player, WantsToEnchant { item, recipe, station })
+                    .expect("Unable to insert enchant intent");
+                self.world.write_resource::<GameStateResource>().turn_count += 1;
             },
-            KeyCode::Char('q') => {
-                // Return to main menu
-                self.state_stack.clear();
+            EnchantingAction::Disenchant(item) => {
+                self.world.write_storage::<WantsToDisenchant>()
+                    .insert(player, WantsToDisenchant { item })
+                    .expect("Unable to insert disenchant intent");
+                self.world.write_resource::<GameStateResource>().turn_count += 1;
             },
-            _ => {}
         }
+        self.enchanting = None;
+        self.state_stack.pop();
     }
     
-    fn handle_guild_management_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for guild management input handling
+    fn handle_alchemy_input(&mut self, key_event: KeyEvent) {
+        let (action, player) = match (self.alchemy.as_mut(), self.player) {
+            (Some(alchemy), Some(player)) => (alchemy.handle_key(key_event.code, &self.world), player),
+            _ => {
+                self.alchemy = None;
+                self.state_stack.pop();
+                return;
+            },
+        };
+        match action {
+            AlchemyAction::None => return,
+            AlchemyAction::Close => {},
+            AlchemyAction::Brew(first, second, station) => {
+                self.world.write_storage::<WantsToBrew>()
+                    .insert(player, WantsToBrew { first, second, station })
+                    .expect("Unable to insert brew intent");
+                self.world.write_resource::<GameStateResource>().turn_count += 1;
+            },
+        }
+        self.alchemy = None;
+        self.state_stack.pop();
     }
     
-    fn handle_mission_assignment_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for mission assignment input handling
+    fn handle_game_over_input(&mut self, _key_event: KeyEvent) {
+        // Placeholder for game over input handling
     }
     
-    fn handle_agent_configuration_input(&mut self, _key_event: KeyEvent) {
-        // Placeholder for agent configuration input handling
+    fn handle_victory_input(&mut self, key_event: KeyEvent) {
+        if matches!(key_event.code, KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q')) {
+            // Return to main menu
+            self.state_stack.clear();
+        }
     }
     
-    pub fn update(&mut self) {
-        match self.state_stack.current() {
-            StateType::MainMenu => self.update_main_menu(),
-            StateType::Playing => self.update_playing(),
-            StateType::Inventory => self.update_inventory(),
-            StateType::CharacterSheet => self.update_character_sheet(),
-            StateType::GameOver => self.update_game_over(),
-            StateType::LevelUp => self.update_level_up(),
-            StateType::Targeting => self.update_targeting(),
-            StateType::SaveGame => self.update_save_game(),
-            StateType::LoadGame => self.update_load_game(),
-            StateType::Options => self.update_options(),
-            StateType::Help => self.update_help(),
-            StateType::Pause => self.update_pause(),
-            StateType::GuildManagement => self.update_guild_management(),
-            StateType::MissionAssignment => self.update_mission_assignment(),
-            StateType::AgentConfiguration => self.update_agent_configuration(),
+    fn handle_run_history_input(&mut self, key_event: KeyEvent) {
+        if matches!(key_event.code, KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q')) {
+            self.state_stack.pop();
         }
     }
     
-    fn update_main_menu(&mut self) {
-        // Placeholder for main menu update logic
+    fn handle_bestiary_input(&mut self, key_event: KeyEvent) {
+        if self.bestiary.handle_key(key_event.code, &self.world.read_resource::<Bestiary>()) {
+            self.state_stack.pop();
+        }
     }
     
-    fn update_playing(&mut self) {
-        // Run the ECS systems
-        self.system_runner.run_systems(&mut self.world);
-        
-        // Update turn count if player has moved (will be implemented later)
-        
-        // Check for game over conditions (will be implemented later)
+    fn handle_level_up_input(&mut self, key_event: KeyEvent) {
+        if self.level_up.handle_key(key_event.code, &self.world) {
+            self.state_stack.pop();
+        }
     }
     
-    fn update_inventory(&mut self) {
-        // Placeholder for inventory update logic
+    fn begin_rest(&mut self, rest: WantsToRest) {
+        if let Some(player) = self.player {
+            self.world.write_storage::<WantsToRest>()
+                .insert(player, rest)
+                .expect("Unable to insert rest intent");
+        }
     }
     
-    fn update_character_sheet(&mut self) {
-        // Placeholder for character sheet update logic
+    /// The corpse under or next to the player, preferring the one underfoot
+    fn nearby_corpse(&self) -> Option<Entity> {
+        let player = self.player?;
+        let positions = self.world.read_storage::<Position>();
+        let here = positions.get(player)?;
+        (&self.world.entities(), &self.world.read_storage::<Corpse>(), &positions).join()
+            .filter(|(_, _, pos)| (pos.x - here.x).abs() <= 1 && (pos.y - here.y).abs() <= 1)
+            .min_by_key(|(_, _, pos)| (pos.x - here.x).abs() + (pos.y - here.y).abs())
+            .map(|(entity, _, _)| entity)
     }
     
-    fn update_game_over(&mut self) {
-        // Placeholder for game over update logic
+    fn handle_targeting_input(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Tab | KeyCode::Right | KeyCode::Down => self.targeting.cycle_target(true),
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Up => self.targeting.cycle_target(false),
+            KeyCode::Char('a') => self.targeting.cycle_ammo(),
+            KeyCode::Enter | KeyCode::Char('f') | KeyCode::Char('z') => {
+                match (self.player, self.targeting.current_target(), self.targeting.ability, self.targeting.thrown) {
+                    (Some(player), Some(target), _, Some(item)) => {
+                        let target = self.world.read_storage::<Position>().get(target).map(|pos| (pos.x, pos.y));
+                        if let Some(target) = target {
+                            self.world.write_storage::<WantsToThrow>()
+                                .insert(player, WantsToThrow { item, target })
+                                .expect("Unable to insert throw intent");
+                        }
+                    },
+                    (Some(player), Some(target), Some(ability), None) => {
+                        self.world.write_storage::<WantsToUseAbility>()
+                            .insert(player, WantsToUseAbility {
+                                ability,
+                                target: Some(target),
+                                mana_cost: ability.get_mana_cost(),
+                                stamina_cost: ability.get_stamina_cost(),
+                            })
+                            .expect("Unable to insert ability intent");
+                    },
+                    (Some(player), Some(target), None, None) => {
+                        self.world.write_storage::<crate::items::WantsToShoot>()
+                            .insert(player, crate::items::WantsToShoot { target, ammo: self.targeting.current_ammo() })
+                            .expect("Unable to insert shot intent");
+                    },
+                    _ => {},
+                }
+                self.state_stack.pop();
+            },
+            KeyCode::Esc => {
+                self.state_stack.pop();
+            },
+            _ => {}
+        }
     }
     
-    fn update_level_up(&mut self) {
-        // Placeholder for level up update logic
+    fn handle_look_input(&mut self, key_event: KeyEvent) {
+        let step = match key_event.code {
+            KeyCode::Left | KeyCode::Char('h') => Some((-1, 0)),
+            KeyCode::Right | KeyCode::Char('l') => Some((1, 0)),
+            KeyCode::Up | KeyCode::Char('k') => Some((0, -1)),
+            KeyCode::Down | KeyCode::Char('j') => Some((0, 1)),
+            KeyCode::Char('y') => Some((-1, -1)),
+            KeyCode::Char('u') => Some((1, -1)),
+            KeyCode::Char('b') => Some((-1, 1)),
+            KeyCode::Char('n') => Some((1, 1)),
+            _ => None,
+        };
+        if let Some((dx, dy)) = step {
+            let map = self.world.fetch::<Map>();
+            self.targeting.move_cursor(&map, dx, dy);
+            return;
+        }
+        
+        match key_event.code {
+            KeyCode::Tab => {
+                self.targeting.cycle_target(true);
+                self.targeting.snap_cursor(&self.world);
+            },
+            KeyCode::BackTab => {
+                self.targeting.cycle_target(false);
+                self.targeting.snap_cursor(&self.world);
+            },
+            KeyCode::Char('B') => {
+                // What the bestiary knows of the monster under the cursor
+                let name = self.targeting.cursor.and_then(|(x, y)| self.monster_name_at(x, y));
+                self.bestiary.open(&self.world.read_resource::<Bestiary>(), name.as_deref());
+                self.state_stack.push(StateType::Bestiary);
+            },
+            KeyCode::Char('D') => {
+                // Send away the companion under the cursor
+                let companion = self.targeting.cursor.and_then(|(x, y)| self.companion_at(x, y));
+                match (self.player, companion) {
+                    (Some(player), Some(companion)) => {
+                        self.world.write_storage::<WantsToDismissCompanion>()
+                            .insert(player, WantsToDismissCompanion { companion })
+                            .expect("Unable to insert dismissal intent");
+                        self.world.write_resource::<GameStateResource>().turn_count += 1;
+                        self.state_stack.pop();
+                    },
+                    _ => self.world.write_resource::<GameLog>().add_message(Message::new("log.no_companion_there")),
+                }
+            },
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('x') => {
+                self.state_stack.pop();
+            },
+            _ => {}
+        }
     }
     
-    fn update_targeting(&mut self) {
-        // Placeholder for targeting update logic
+    fn handle_travel_input(&mut self, key_event: KeyEvent) {
+        let step = match key_event.code {
+            KeyCode::Left | KeyCode::Char('h') => Some((-1, 0)),
+            KeyCode::Right | KeyCode::Char('l') => Some((1, 0)),
+            KeyCode::Up | KeyCode::Char('k') => Some((0, -1)),
+            KeyCode::Down | KeyCode::Char('j') => Some((0, 1)),
+            KeyCode::Char('y') => Some((-1, -1)),
+            KeyCode::Char('u') => Some((1, -1)),
+            KeyCode::Char('b') => Some((-1, 1)),
+            KeyCode::Char('n') => Some((1, 1)),
+            _ => None,
+        };
+        if let Some((dx, dy)) = step {
+            let map = self.world.fetch::<Map>();
+            self.travel.move_cursor(&map, dx, dy);
+            return;
+        }
+        
+        match key_event.code {
+            KeyCode::Tab => self.travel.cycle_point(true),
+            KeyCode::BackTab => self.travel.cycle_point(false),
+            KeyCode::Char('>') => self.travel.jump_to(&self.world.fetch::<Map>(), TileType::DownStairs),
+            KeyCode::Char('<') => self.travel.jump_to(&self.world.fetch::<Map>(), TileType::UpStairs),
+            KeyCode::Enter | KeyCode::Char('.') => {
+                let player = match self.player {
+                    Some(player) => player,
+                    None => return,
+                };
+                let destination = self.travel.cursor;
+                let known = {
+                    let map = self.world.fetch::<Map>();
+                    let here = self.world.read_storage::<Position>().get(player).map(|pos| (pos.x, pos.y));
+                    here.and_then(|here| map.find_known_path(here, destination)).is_some()
+                };
+                if !known {
+                    self.world.write_resource::<GameLog>().add_message(Message::new("log.travel_no_way"));
+                    return;
+                }
+                self.world.write_storage::<WantsToTravel>()
+                    .insert(player, WantsToTravel::to(destination))
+                    .expect("Unable to insert travel intent");
+                self.state_stack.pop();
+            },
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.state_stack.pop();
+            },
+            _ => {}
+        }
     }
     
-    fn update_save_game(&mut self) {
-        // Placeholder for save game update logic
+    /// The name of the monster standing on a tile, if any
+    fn monster_name_at(&self, x: i32, y: i32) -> Option<String> {
+        (&self.world.read_storage::<Monster>(), &self.world.read_storage::<Name>(), &self.world.read_storage::<Position>()).join()
+            .find(|(_, _, pos)| pos.x == x && pos.y == y)
+            .map(|(_, name, _)| name.name.clone())
     }
     
-    fn update_load_game(&mut self) {
-        // Placeholder for load game update logic
+    /// The player's own companion standing on a tile, if any
+    fn companion_at(&self, x: i32, y: i32) -> Option<Entity> {
+        let player = self.player?;
+        (&self.world.entities(), &self.world.read_storage::<Companion>(), &self.world.read_storage::<Position>()).join()
+            .find(|(_, companion, pos)| companion.owner == player && pos.x == x && pos.y == y)
+            .map(|(entity, _, _)| entity)
     }
     
-    fn update_options(&mut self) {
-        // Placeholder for options update logic
+    /// Open the save or load screen over the slots in `saves/`
+    fn open_saves(&mut self, state: StateType) -> Result<(), SaveError> {
+        let saves = SaveSystem::new(SAVES_DIR)?;
+        if state == StateType::LoadGame {
+            self.save_load.open_load_menu(saves);
+        } else {
+            let name = self.player
+                .and_then(|player| self.world.read_storage::<Name>().get(player).map(|name| name.name.clone()))
+                .unwrap_or_default();
+            self.save_load.open_save_menu(saves, name);
+        }
+        Ok(())
     }
     
-    fn update_help(&mut self) {
-        // Placeholder for help update logic
+    /// Swap the pause menu for the save or load screen, or back to the game when the saves can't be read
+    fn replace_with_saves(&mut self, state: StateType) {
+        match self.open_saves(state) {
+            Ok(()) => self.state_stack.replace(state),
+            Err(e) => {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.saves_unavailable").arg("error", e));
+                self.state_stack.pop();
+            },
+        }
     }
     
-    fn update_pause(&mut self) {
+    /// Keys for the save and load screens; a confirmed slot is saved or loaded straight away
+    fn handle_save_load_input(&mut self, key_event: KeyEvent) {
+        match self.save_load.handle_key(key_event.code) {
+            None | Some(SaveLoadResult::Cancelled) => {},
+            Some(SaveLoadResult::SaveRequested(slot)) => {
+                let message = match self.write_save(slot, false) {
+                    Ok(()) => Message::new("log.game_saved").arg("slot", slot + 1),
+                    Err(e) => Message::new("log.save_failed").arg("error", e),
+                };
+                self.world.write_resource::<GameLog>().add_message(message);
+                self.save_load.close();
+                self.refresh_continue_save();
+            },
+            Some(SaveLoadResult::LoadCompleted(save_file)) => {
+                self.save_load.close();
+                self.state_stack.pop();
+                match self.restore_save(&save_file) {
+                    Ok(()) => self.state_stack.replace(StateType::Playing),
+                    Err(e) if self.state_stack.current() == StateType::MainMenu => {
+                        self.main_menu_notice = Some(Message::new("log.load_failed").arg("error", e).text());
+                    },
+                    Err(e) => self.world.write_resource::<GameLog>().add_message(Message::new("log.load_failed").arg("error", e)),
+                }
+                return;
+            },
+            Some(SaveLoadResult::SlotDeleted(_)) => self.refresh_continue_save(),
+            Some(SaveLoadResult::HeroExportRequested) => {
+                let message = match self.export_hero() {
+                    Ok(path) => Message::new("log.hero_exported").arg("path", path.display()),
+                    Err(e) => Message::new("log.hero_export_failed").arg("error", e),
+                };
+                self.world.write_resource::<GameLog>().add_message(message);
+            },
+            Some(SaveLoadResult::HeroImportRequested(hero)) => {
+                self.state_stack.pop();
+                self.start_run_with_hero(hero);
+                return;
+            },
+        }
+        if !self.save_load.is_open() {
+            self.state_stack.pop();
+        }
+    }
+    
+    fn handle_options_input(&mut self, key_event: KeyEvent) {
+        match self.options.handle_key(key_event.code, &mut self.config.config) {
+            OptionsInput::Nothing => {},
+            OptionsInput::Close => { self.state_stack.pop(); },
+            OptionsInput::Changed => {
+                // Changes take effect straight away and are kept for next time
+                self.apply_config();
+                if let Err(e) = self.config.save() {
+                    self.world.write_resource::<GameLog>().add_message(Message::new("log.options_not_saved").arg("error", e));
+                }
+            },
+        }
+    }
+    
+    fn handle_help_input(&mut self, key_event: KeyEvent) {
+        self.help.handle_key(key_event.code);
+        if !self.help.is_open() {
+            self.state_stack.pop();
+        }
+    }
+    
+    fn handle_pause_input(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('p') => {
+                // Resume game
+                self.state_stack.pop();
+            },
+            KeyCode::Char('s') if self.is_ironman() => {
+                // The one save an ironman run allows, and it ends the session
+                self.save_and_quit();
+            },
+            KeyCode::Char('s') => {
+                // Save game
+                self.replace_with_saves(StateType::SaveGame);
+            },
+            KeyCode::Char('l') if self.is_ironman() => {
+                // No going back to an earlier save
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.ironman_no_load"));
+                self.state_stack.pop();
+            },
+            KeyCode::Char('l') => {
+                // Load game
+                self.replace_with_saves(StateType::LoadGame);
+            },
+            KeyCode::Char('o') => {
+                // Options, coming back to the pause menu after
+                self.state_stack.push(StateType::Options);
+            },
+            KeyCode::Char('x') => {
+                // Export the character to carry into another run
+                let message = match self.export_hero() {
+                    Ok(path) => Message::new("log.hero_exported").arg("path", path.display()),
+                    Err(e) => Message::new("log.hero_export_failed").arg("error", e),
+                };
+                self.world.write_resource::<GameLog>().add_message(message);
+                self.state_stack.pop();
+            },
+            KeyCode::Char('q') if self.is_ironman() => {
+                // Leaving an ironman run always saves it, so nothing can be taken back
+                self.save_and_quit();
+            },
+            KeyCode::Char('q') => {
+                // Return to main menu
+                self.save_between_runs();
+                self.state_stack.clear();
+            },
+            _ => {}
+        }
+    }
+    
+    fn handle_guild_management_input(&mut self, key_event: KeyEvent) {
+        match self.guild_hall.handle_key(key_event.code) {
+            Some(HallAction::BuySupplies) => self.buy_guild_supplies(),
+            Some(HallAction::Build(facility)) => self.build_guild_facility(facility),
+            Some(HallAction::Craft(service)) => self.craft_at_guild(service),
+            Some(HallAction::Agents) => {
+                self.agents.open();
+                self.state_stack.push(StateType::AgentConfiguration);
+            },
+            Some(HallAction::Close) => {
+                self.state_stack.pop();
+            },
+            None => {},
+        }
+    }
+    
+    fn handle_mission_assignment_input(&mut self, _key_event: KeyEvent) {
+        // Placeholder for mission assignment input handling
+    }
+    
+    fn handle_agent_configuration_input(&mut self, key_event: KeyEvent) {
+        let action = self.agents.handle_key(key_event.code, &self.world.read_resource::<GuildRoster>());
+        match action {
+            Some(AgentAction::Hire(index)) => self.hire_agent(index),
+            Some(AgentAction::Send(index, contract)) => {
+                let closing = self.world.read_resource::<WorldEvents>().closing(contract);
+                if let Some(event) = closing {
+                    self.world.write_resource::<GameLog>().add_message(Message::new("log.contract_closed")
+                        .arg("contract", contract.name())
+                        .arg("event", event.name()));
+                    return;
+                }
+                let mut roster = self.world.write_resource::<GuildRoster>();
+                let message = match roster.assign(index, contract) {
+                    Ok(()) => Message::new("log.agent_sent")
+                        .arg("agent", &roster.agents[index].name)
+                        .arg("contract", contract.name())
+                        .arg("days", contract.days()),
+                    Err(shortfall) => shortfall.message(),
+                };
+                self.world.write_resource::<GameLog>().add_message(message);
+            },
+            Some(AgentAction::Close) => {
+                self.state_stack.pop();
+            },
+            None => {},
+        }
+    }
+    
+    /// Sign on a candidate at the guild, paid for out of the character's purse
+    fn hire_agent(&mut self, index: usize) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let mut roster = self.world.write_resource::<GuildRoster>();
+        let (name, cost) = match roster.recruits.get(index) {
+            Some(recruit) => (recruit.name.clone(), recruit.hire_cost()),
+            None => return,
+        };
+        let mut inventories = self.world.write_storage::<AdvancedInventory>();
+        let purse = match inventories.get_mut(player) {
+            Some(inventory) => &mut inventory.gold,
+            None => return,
+        };
+        let message = match roster.hire(index, purse) {
+            Some(gold) => Message::new("log.agent_hired").arg("agent", name).arg("gold", gold),
+            None if roster.is_full() => Message::new("log.guild_full").arg("max", MAX_AGENTS),
+            None => Message::new("log.agent_too_dear").arg("agent", name).arg("gold", cost),
+        };
+        self.world.write_resource::<GameLog>().add_message(message);
+    }
+    
+    /// Buy the guild a crate of supplies out of the character's purse
+    fn buy_guild_supplies(&mut self) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let mut roster = self.world.write_resource::<GuildRoster>();
+        let mut inventories = self.world.write_storage::<AdvancedInventory>();
+        let purse = match inventories.get_mut(player) {
+            Some(inventory) => &mut inventory.gold,
+            None => return,
+        };
+        let message = match roster.stores.buy_supplies(purse) {
+            Ok(gold) => Message::new("log.supplies_bought").arg("supplies", roster.stores.supplies).arg("gold", gold),
+            Err(shortfall) => shortfall.message(),
+        };
+        self.world.write_resource::<GameLog>().add_message(message);
+    }
+
+    /// Add a room to the guild's hall out of the character's purse
+    fn build_guild_facility(&mut self, facility: HallFacility) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let mut roster = self.world.write_resource::<GuildRoster>();
+        let mut inventories = self.world.write_storage::<AdvancedInventory>();
+        let purse = match inventories.get_mut(player) {
+            Some(inventory) => &mut inventory.gold,
+            None => return,
+        };
+        let message = match roster.stores.build(facility, purse) {
+            Ok(gold) => Message::new("log.facility_built").arg("facility", facility.name()).arg("gold", gold),
+            Err(shortfall) => shortfall.message(),
+        };
+        self.world.write_resource::<GameLog>().add_message(message);
+    }
+
+    /// Have the guild's alchemist make something out of its reagents, straight into the
+    /// character's pack
+    fn craft_at_guild(&mut self, service: CraftingService) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        if let Err(shortfall) = self.world.write_resource::<GuildRoster>().stores.craft(service) {
+            self.world.write_resource::<GameLog>().add_message(shortfall.message());
+            return;
+        }
+        let position = Position { x: 0, y: 0 };
+        let item = match service {
+            CraftingService::HealingDraught => ConsumableFactory::new().create_health_potion(&mut self.world, position, PotionPotency::Lesser),
+            CraftingService::GreaterHealing => ConsumableFactory::new().create_health_potion(&mut self.world, position, PotionPotency::Greater),
+            CraftingService::Regeneration => ConsumableFactory::new().create_regeneration_potion(&mut self.world, position, 20.0, 2),
+        };
+        self.world.write_storage::<Position>().remove(item);
+        let weight = self.world.read_storage::<ItemProperties>().get(item).map_or(0.0, |props| props.weight);
+        let stowed = self.world.write_storage::<AdvancedInventory>()
+            .get_mut(player)
+            .map_or(false, |inventory| inventory.add_item(item, 1, weight));
+        let message = if stowed {
+            Message::new("log.guild_crafted").arg("item", service.name())
+        } else {
+            // Nowhere to put it, so the alchemist keeps the reagents for another time
+            self.world.delete_entity(item).expect("Unable to delete crafted item");
+            self.world.write_resource::<GuildRoster>().stores.reagents += service.reagents();
+            Message::new("log.guild_no_room").arg("item", service.name())
+        };
+        self.world.write_resource::<GameLog>().add_message(message);
+    }
+
+    pub fn update(&mut self) {
+        match self.state_stack.current() {
+            StateType::MainMenu => self.update_main_menu(),
+            StateType::Playing => self.update_playing(),
+            StateType::Inventory => self.update_inventory(),
+            StateType::CharacterSheet => self.update_character_sheet(),
+            StateType::GameOver => self.update_game_over(),
+            StateType::Revival => {},
+            StateType::Prayer => {},
+            StateType::Furniture => {},
+            StateType::Parley => {},
+            StateType::TownTalk => {},
+            StateType::Enchanting => {},
+            StateType::Alchemy => {},
+            StateType::Victory => self.update_victory(),
+            StateType::RunHistory => {},
+            StateType::Bestiary => {},
+            StateType::LevelUp => self.update_level_up(),
+            StateType::Targeting => self.update_targeting(),
+            StateType::Look => {},
+            StateType::Travel => {},
+            StateType::SaveGame => self.update_save_game(),
+            StateType::LoadGame => self.update_load_game(),
+            StateType::Options => self.update_options(),
+            StateType::Help => self.update_help(),
+            StateType::Pause => self.update_pause(),
+            StateType::GuildManagement => self.update_guild_management(),
+            StateType::MissionAssignment => self.update_mission_assignment(),
+            StateType::AgentConfiguration => self.update_agent_configuration(),
+        }
+        self.reload_config();
+        self.show_tutorials();
+        self.update_presence();
+        self.narrate();
+    }
+    
+    /// Turn the tutorial triggers published since the last update into popups, each step once
+    fn show_tutorials(&mut self) {
+        let triggers = self.world.write_resource::<TutorialEvents>().take();
+        for trigger in triggers {
+            if let Some(popup) = self.help.tutorial_popup(trigger) {
+                self.narrator.say(&Message::new("access.tutorial")
+                    .arg("title", &popup.title)
+                    .arg("text", &popup.message)
+                    .text());
+                self.tutorial_popups.push_back(popup);
+            }
+        }
+    }
+    
+    /// Pick up edits made to `config.toml` while the game is running
+    fn reload_config(&mut self) {
+        match self.config.poll() {
+            Ok(true) => {
+                self.apply_config();
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.options_reloaded").arg("path", CONFIG_PATH));
+            },
+            Ok(false) => {},
+            Err(e) => self.world.write_resource::<GameLog>().add_message(Message::new("log.options_not_reloaded").arg("error", e)),
+        }
+    }
+    
+    /// Bring the screen-reader transcript up to date with the log and the screen
+    fn narrate(&mut self) {
+        self.narrator.follow_log(&self.world.read_resource::<GameLog>());
+        if self.narrator.is_enabled() {
+            let announcement = self.screen_announcement();
+            self.narrator.announce(announcement);
+        }
+    }
+    
+    /// What the current screen shows, or has selected, in a line of text
+    fn screen_announcement(&self) -> Option<String> {
+        let join = |keys: &[&str]| keys.iter().map(|key| tr(key)).collect::<Vec<_>>().join(". ");
+        if matches!(self.run_state,
+            RunState::CharacterName |
+            RunState::CharacterClass |
+            RunState::CharacterBackground |
+            RunState::CharacterAttributes |
+            RunState::CharacterEquipment |
+            RunState::CharacterConfirm) {
+            return None;
+        }
+        let announcement = match self.state_stack.current() {
+            StateType::MainMenu => {
+                let mut keys = vec!["menu.title"];
+                if self.continue_save.is_some() {
+                    keys.push("menu.continue");
+                }
+                keys.extend(MAIN_MENU_OPTIONS);
+                let mut announcement = join(&keys);
+                if let Some(details) = self.continue_details() {
+                    announcement = format!("{}. {}", announcement, details);
+                }
+                announcement
+            },
+            StateType::Pause => join(&self.pause_options()),
+            StateType::Options => {
+                let option = self.options.selected_option();
+                Message::new("access.option")
+                    .arg("tab", self.options.tab.title())
+                    .arg("label", option.label())
+                    .arg("value", option.value(&self.config.config))
+                    .text()
+            },
+            StateType::Look => {
+                let (x, y) = self.targeting.cursor?;
+                let info = match examine(&self.world, x, y) {
+                    Some(info) => info,
+                    None => return Some(tr("look.cannot_see")),
+                };
+                let mut text = Message::new("access.look")
+                    .arg("name", localization::name(&info.name))
+                    .arg("faction", info.faction.name())
+                    .arg("description", &info.description)
+                    .text();
+                if let Some(health) = info.health {
+                    text.push(' ');
+                    text.push_str(&Message::new("access.health").arg("health", health.label()).text());
+                }
+                for effect in &info.statuses {
+                    text.push(' ');
+                    text.push_str(&status_detail(effect));
+                }
+                text
+            },
+            StateType::Travel => self.travel_destination().0,
+            StateType::Inventory => match self.inventory_ui.selected_line(&self.world) {
+                Some(item) => Message::new("access.inventory_item").arg("item", item).text(),
+                None => tr("access.inventory"),
+            },
+            StateType::CharacterSheet => self.character_sheet.announcement(&self.world),
+            StateType::GameOver => tr("access.game_over"),
+            StateType::Revival => self.revival.selected_line(&self.world).unwrap_or_else(|| tr("revival.title")),
+            StateType::Prayer => self.prayer.selected_line(&self.world).unwrap_or_else(|| tr("prayer.title")),
+            StateType::Furniture => self.furniture_menu.selected_line(&self.world).unwrap_or_else(|| tr("furniture.leave")),
+            StateType::Parley => self.rival_menu.selected_line(&self.world).unwrap_or_else(|| tr("rival.leave")),
+            StateType::TownTalk => self.town_menu.selected_line(&self.world).unwrap_or_else(|| tr("town.leave")),
+            StateType::Enchanting => self.enchanting.as_ref()
+                .and_then(|enchanting| enchanting.selected_line(&self.world))
+                .unwrap_or_else(|| tr("enchanting.title")),
+            StateType::Alchemy => self.alchemy.as_ref()
+                .and_then(|alchemy| alchemy.selected_line(&self.world))
+                .unwrap_or_else(|| tr("alchemy.title")),
+            StateType::Victory => tr("access.victory"),
+            StateType::RunHistory => tr("access.run_history"),
+            StateType::Bestiary => self.bestiary.selected_line(&self.world.read_resource::<Bestiary>()).unwrap_or_else(|| tr("bestiary.empty")),
+            StateType::LevelUp => self.level_up.selected_line().unwrap_or_else(|| tr("access.level_up")),
+            StateType::SaveGame => tr("access.save_game"),
+            StateType::LoadGame => tr("access.load_game"),
+            StateType::Help => tr("access.help"),
+            StateType::AgentConfiguration => self.agents.selected_line(&self.world.read_resource::<GuildRoster>(), &self.world.read_resource::<WorldEvents>()).unwrap_or_else(|| tr("access.guild")),
+            StateType::GuildManagement => self.guild_hall.selected_line(&self.world.read_resource::<GuildRoster>()).unwrap_or_else(|| tr("access.guild")),
+            StateType::MissionAssignment => tr("access.guild"),
+            StateType::Playing | StateType::Targeting => return None,
+        };
+        Some(announcement)
+    }
+    
+    /// What chat clients show about the game, and any guild news for a player who is elsewhere
+    fn update_presence(&mut self) {
+        let status = self.presence_status();
+        let at_guild = status.activity == Activity::ManagingGuild;
+        self.presence.update(status);
+        
+        for notification in crate::presence::take_posted() {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.notification")
+                .arg("title", &notification.title)
+                .arg("body", &notification.body));
+            if !at_guild {
+                self.presence.notify(&notification);
+            }
+        }
+    }
+    
+    fn presence_status(&self) -> PresenceStatus {
+        let activity = match self.state_stack.current() {
+            StateType::MainMenu | StateType::RunHistory | StateType::Options | StateType::Help | StateType::LoadGame => Activity::InMenus,
+            StateType::GameOver | StateType::Revival => Activity::Dead,
+            StateType::Victory => Activity::Victorious,
+            StateType::GuildManagement | StateType::MissionAssignment | StateType::AgentConfiguration => Activity::ManagingGuild,
+            _ => Activity::Exploring,
+        };
+        let player = match (activity, self.player) {
+            (Activity::InMenus, _) | (_, None) => return PresenceStatus::in_menus(),
+            (_, Some(player)) => player,
+        };
+        let game_state = self.world.read_resource::<GameStateResource>();
+        PresenceStatus {
+            activity,
+            character: self.world.read_storage::<Name>().get(player).map(|name| name.name.clone()),
+            class: self.world.read_storage::<CharacterClass>().get(player).map(|class| class.class_type.name().to_string()),
+            depth: Some(game_state.depth),
+            turn: game_state.turn_count,
+        }
+    }
+    
+    fn update_main_menu(&mut self) {
+        // Saves can come and go behind the menu's back; look again now and then
+        let stale = self.continue_checked.map_or(true, |checked| checked.elapsed() >= CONTINUE_REFRESH);
+        if stale {
+            self.refresh_continue_save();
+        }
+    }
+    
+    /// Find the save the Continue entry would load
+    pub fn refresh_continue_save(&mut self) {
+        self.continue_save = SaveSystem::new(SAVES_DIR).ok()
+            .and_then(|saves| saves.most_recent_slot().ok().flatten());
+        self.continue_checked = Some(Instant::now());
+    }
+    
+    /// Who and where the Continue entry would pick up
+    fn continue_details(&self) -> Option<String> {
+        self.continue_save.as_ref().map(|slot| {
+            Message::new("menu.continue_details")
+                .arg("name", &slot.metadata.player_name)
+                .arg("level", slot.metadata.character_level)
+                .arg("location", slot.metadata.current_location.display_name())
+                .text()
+        })
+    }
+    
+    /// Load the most recent save and drop straight into it
+    fn continue_game(&mut self) {
+        let slot = match &self.continue_save {
+            Some(slot) => slot.slot_id,
+            None => return,
+        };
+        match self.load_slot(slot) {
+            Ok(()) => self.state_stack.replace(StateType::Playing),
+            Err(e) => {
+                self.main_menu_notice = Some(Message::new("menu.continue_failed").arg("error", e).text());
+                self.refresh_continue_save();
+            },
+        }
+    }
+    
+    /// Replace the world with the one saved in `slot`
+    fn load_slot(&mut self, slot: u32) -> Result<(), SaveError> {
+        let save_file = SaveSystem::new(SAVES_DIR)?.load_from_slot(slot)?;
+        self.restore_save(&save_file)
+    }
+    
+    /// Replace the world with a save already read from disk
+    fn restore_save(&mut self, save_file: &SaveFile) -> Result<(), SaveError> {
+        // Saves from before levels were kept have none to restore
+        *self.world.write_resource::<DungeonLevels>() = DungeonLevels::default();
+        WorldSerializer::new(create_serialization_system())
+            .deserialize_world(&mut self.world, &WorldState::from_save_data(&save_file.data))?;
+        
+        self.player = {
+            let entities = self.world.entities();
+            let players = self.world.read_storage::<Player>();
+            (&entities, &players).join().map(|(entity, _)| entity).next()
+        };
+        if self.player.is_none() {
+            return Err(SaveError::CorruptedSave("no player in the save".to_string()));
+        }
+        self.current_location = save_file.metadata.current_location;
+        self.world.write_resource::<GameStateResource>().location = self.current_location;
+        let turn = self.world.read_resource::<GameStateResource>().turn_count;
+        self.ironman_checkpoint = Some((turn, self.current_location));
+        self.tutorial_popups.clear();
+        self.world.write_resource::<DijkstraMaps>().clear();
+        Ok(())
+    }
+    
+    /// Whether the last turn is still being animated; input and the next turn wait for it
+    pub fn is_animating(&self) -> bool {
+        self.state_stack.current() == StateType::Playing
+            && self.world.read_resource::<AnimationQueue>().is_blocking()
+    }
+    
+    /// Whether nothing on screen would change without a key press, so the main loop can wait
+    /// for one instead of drawing the same frame over and over
+    pub fn is_idle(&self) -> bool {
+        if !matches!(self.state_stack.current(), StateType::Playing | StateType::Targeting | StateType::Look | StateType::Travel) {
+            return true;
+        }
+        let player = match self.player {
+            Some(player) => player,
+            None => return true,
+        };
+        // Resting, exploring and travelling carry on a turn per update by themselves
+        if self.world.read_storage::<WantsToRest>().contains(player)
+            || self.world.read_storage::<WantsToExplore>().contains(player)
+            || self.world.read_storage::<WantsToTravel>().contains(player) {
+            return false;
+        }
+        if self.world.read_resource::<AnimationQueue>().is_blocking()
+            || !self.world.read_resource::<ParticleEmitter>().particles.is_empty()
+            || self.world.read_storage::<ParticleEffect>().join().next().is_some()
+            || self.world.read_resource::<ScreenShakeState>().current_intensity > 0.0 {
+            return false;
+        }
+        let weather_effects = self.world.read_storage::<GameSettings>().get(player)
+            .map_or(true, |settings| !settings.disable_weather_effects);
+        let weather = self.world.read_resource::<Weather>();
+        if weather_effects && (weather.kind.overlay().is_some() || weather.is_flashing()) {
+            return false;
+        }
+        if !self.world.read_resource::<MotionSettings>().no_flash && self.sidebar_blinks(player) {
+            return false;
+        }
+        self.world.read_storage::<Position>().get(player)
+            .map_or(true, |pos| self.system_runner.render_system.context.is_still((pos.x, pos.y)))
+    }
+    
+    /// Whether the sidebar has something blinking: a vital bar running low or an effect about to
+    /// wear off
+    fn sidebar_blinks(&self, player: Entity) -> bool {
+        let low = |current: i32, max: i32| max > 0 && UIBar::new(String::new(), current, max, 0, 0, 0).is_critical();
+        let stats_low = self.world.read_storage::<CombatStats>().get(player)
+            .map_or(false, |stats| low(stats.hp, stats.max_hp));
+        let resources_low = self.world.read_storage::<PlayerResources>().get(player)
+            .map_or(false, |resources| low(resources.mana, resources.max_mana) || low(resources.stamina, resources.max_stamina));
+        let expiring = self.world.read_storage::<StatusEffects>().get(player)
+            .map_or(false, |status| status.effects.iter().any(is_expiring));
+        stats_low || resources_low || expiring
+    }
+    
+    fn update_playing(&mut self) {
+        // Let the last turn finish playing out before anything else moves
+        if self.is_animating() {
+            return;
+        }
+        
+        // Run the ECS systems
+        self.system_runner.run_systems(&mut self.world);
+        
+        // Bring in any monsters that wandered onto the level this turn
+        self.spawn_wanderers();
+        
+        // Update turn count if player has moved (will be implemented later)
+        
+        // A fall ends the run, or asks how to come back while there is a way
+        if let Some(player) = self.player {
+            let dead = self.world.read_storage::<DeathState>().get(player).map_or(false, |state| state.is_dead);
+            if dead {
+                if self.world.read_resource::<GameStateResource>().game_over {
+                    self.end_run_in_death();
+                } else {
+                    // An ironman death is saved at once, so quitting can't undo it
+                    if self.is_ironman() {
+                        self.ironman_save();
+                    }
+                    self.revival.open(player);
+                    self.state_stack.push(StateType::Revival);
+                }
+                return;
+            }
+        }
+        
+        self.ironman_autosave();
+        
+        // Carrying the artifact out ends the run
+        let won = self.world.read_resource::<GameStateResource>().phase == RunPhase::Victory;
+        if won {
+            self.record_victory();
+            self.state_stack.replace(StateType::Victory);
+            return;
+        }
+        
+        // A new level waits for its points to be spent before play goes on
+        if let Some(player) = self.player {
+            if self.world.read_storage::<PendingLevelUp>().contains(player) {
+                self.level_up.open(&self.world, player);
+                self.state_stack.push(StateType::LevelUp);
+                return;
+            }
+        }
+        
+        // A guard who has caught up with a wanted player wants an answer
+        let confronting = self.world.write_resource::<TownWatch>().confronting.take();
+        if let (Some(player), Some(guard)) = (self.player, confronting) {
+            self.town_menu.open(player, guard);
+            self.state_stack.push(StateType::TownTalk);
+        }
+    }
+    
+    /// Come back from death as chosen on the revival screen, before anything else moves
+    fn revive(&mut self, player: Entity, item: Option<Entity>) {
+        self.world.write_storage::<WantsToRevive>()
+            .insert(player, WantsToRevive { item })
+            .expect("Unable to insert revival intent");
+        // Gear wears from the death itself however the player comes back
+        crate::items::DurabilitySystem.run_now(&self.world);
+        DeathPenaltySystem {}.run_now(&self.world);
+        RevivalSystem {}.run_now(&self.world);
+        self.world.maintain();
+        if self.is_ironman() {
+            self.ironman_save();
+        }
+        
+        self.revival.close();
+        self.state_stack.pop();
+    }
+    
+    /// Record the death and show the game over screen; a permadeath character's saves go with them
+    fn end_run_in_death(&mut self) {
+        self.revival.close();
+        self.record_death();
+        if self.is_ironman() {
+            self.delete_fallen_saves();
+        }
+        self.state_stack.clear();
+        self.state_stack.push(StateType::GameOver);
+    }
+    
+    /// Whether the player's run is an ironman one, saving only by itself
+    fn is_ironman(&self) -> bool {
+        self.player
+            .and_then(|player| self.world.read_storage::<GameSettings>().get(player).map(|settings| settings.is_ironman()))
+            .unwrap_or(false)
+    }
+    
+    /// Write the world to `slot`, described by the player's name, level and whereabouts
+    fn write_save(&self, slot: u32, ironman: bool) -> Result<(), SaveError> {
+        let player = self.player.ok_or_else(|| SaveError::InvalidSaveFile("no character to save".to_string()))?;
+        let name = self.world.read_storage::<Name>().get(player).map_or(String::new(), |name| name.name.clone());
+        let level = self.world.read_storage::<Experience>().get(player).map_or(1, |experience| experience.level);
+        let difficulty = self.world.read_storage::<GameSettings>().get(player)
+            .map_or(Difficulty::Normal, |settings| settings.difficulty);
+        let world_state = WorldSerializer::new(create_serialization_system()).serialize_world(&self.world)?;
+        
+        let mut data = SaveData::new("ASCII Dungeon Explorer".to_string(), name.clone())
+            .with_components(world_state.components)
+            .with_resources(world_state.resources.into_iter().map(|(key, resource)| (key, resource.data)).collect());
+        data.level = level;
+        let mut metadata = SaveMetadata::new(name.clone(), name);
+        metadata.character_level = level;
+        metadata.current_depth = self.world.read_resource::<GameStateResource>().depth;
+        metadata.current_location = self.current_location;
+        metadata.difficulty = difficulty.name().to_string();
+        metadata.ironman = ironman;
+        SaveSystem::new(SAVES_DIR)?.save_to_slot(slot, data, metadata)
+    }
+    
+    /// Roll the ironman run's one save forward to now
+    fn ironman_save(&mut self) -> bool {
+        let name = self.player
+            .and_then(|player| self.world.read_storage::<Name>().get(player).map(|name| name.name.clone()))
+            .unwrap_or_default();
+        let saved = SaveSystem::new(SAVES_DIR)
+            .and_then(|saves| saves.ironman_slot(&name))
+            .and_then(|slot| self.write_save(slot, true));
+        if let Err(e) = saved {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.ironman_save_failed").arg("error", e));
+            return false;
+        }
+        let turn = self.world.read_resource::<GameStateResource>().turn_count;
+        self.ironman_checkpoint = Some((turn, self.current_location));
+        true
+    }
+    
+    /// Save an ironman run every so many turns and on reaching a new level
+    fn ironman_autosave(&mut self) {
+        if !self.is_ironman() {
+            return;
+        }
+        let turn = self.world.read_resource::<GameStateResource>().turn_count;
+        let due = match self.ironman_checkpoint {
+            Some((saved_turn, location)) => turn >= saved_turn + IRONMAN_SAVE_TURNS || location != self.current_location,
+            None => true,
+        };
+        if due {
+            self.ironman_save();
+        }
+    }
+    
+    /// Save an ironman run and go back to the main menu, where Continue picks it up again
+    fn save_and_quit(&mut self) {
+        self.save_between_runs();
+        if !self.ironman_save() {
+            self.state_stack.pop();
+            return;
+        }
+        self.refresh_continue_save();
+        self.state_stack.clear();
+    }
+    
+    /// Delete every save of the character who just died
+    fn delete_fallen_saves(&mut self) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let name = self.world.read_storage::<Name>().get(player).map_or(String::new(), |name| name.name.clone());
+        let deleted = SaveSystem::new(SAVES_DIR).and_then(|saves| {
+            let mut deleted = 0;
+            for slot in saves.get_save_slots()? {
+                if slot.is_occupied && slot.metadata.player_name == name {
+                    saves.delete_slot(slot.slot_id)?;
+                    deleted += 1;
+                }
+            }
+            Ok(deleted)
+        });
+        let message = match deleted {
+            Ok(0) => return,
+            Ok(count) => Message::new("log.saves_deleted").arg("count", count),
+            Err(e) => Message::new("log.saves_not_deleted").arg("error", e),
+        };
+        self.world.write_resource::<GameLog>().add_message(message);
+        self.refresh_continue_save();
+    }
+    
+    /// Break into a sprint, or drop back to a walk; there's no starting one with too little stamina
+    fn toggle_sprint(&mut self) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let mut sprinting = self.world.write_storage::<Sprinting>();
+        let key = if sprinting.remove(player).is_some() {
+            "log.sprint_off"
+        } else if self.world.read_storage::<PlayerResources>().get(player).map_or(false, |resources| resources.stamina >= SPRINT_STAMINA_COST) {
+            sprinting.insert(player, Sprinting).expect("Unable to insert sprinting marker");
+            "log.sprint_on"
+        } else {
+            "log.too_winded_to_sprint"
+        };
+        self.world.write_resource::<GameLog>().add_message(Message::new(key));
+    }
+    
+    /// Work the door beside the player: the one open, for closing, or else the one shut
+    fn operate_door(&mut self, action: DoorAction) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let (x, y) = match self.world.read_storage::<Position>().get(player) {
+            Some(pos) => (pos.x, pos.y),
+            None => return,
+        };
+        let door = {
+            let map = self.world.fetch::<Map>();
+            let wanted = |dx: i32, dy: i32| match action {
+                DoorAction::Close => map.is_open_door(x + dx, y + dy),
+                _ => map.is_closed_door(x + dx, y + dy),
+            };
+            (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                .find(|&(dx, dy)| (dx, dy) != (0, 0) && wanted(dx, dy))
+                .map(|(dx, dy)| (x + dx, y + dy))
+        };
+        match door {
+            Some(target) => {
+                self.world.write_storage::<WantsToOperateDoor>()
+                    .insert(player, WantsToOperateDoor { target, action })
+                    .expect("Unable to insert door intent");
+                self.world.write_resource::<GameStateResource>().turn_count += 1;
+            },
+            None => self.world.write_resource::<GameLog>().add_message(Message::new("log.no_door_nearby")),
+        }
+    }
+    
+    /// Take the stairs the player stands on, if they lead anywhere, bringing any companions along
+    fn use_stairs(&mut self) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let (x, y) = match self.world.read_storage::<Position>().get(player) {
+            Some(pos) => (pos.x, pos.y),
+            None => return,
+        };
+        let destination = {
+            let map = self.world.fetch::<Map>();
+            if !matches!(map.get_tile(x, y), Some(TileType::DownStairs) | Some(TileType::UpStairs)) {
+                None
+            } else {
+                Some(stairs_destination(&map, &self.world.fetch::<BranchLayout>(), x, y))
+            }
+        };
+        let destination = match destination {
+            Some(Some(destination)) => destination,
+            Some(None) => {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.stairs_lead_nowhere"));
+                return;
+            },
+            None => {
+                self.world.write_resource::<GameLog>().add_message(Message::new("log.no_stairs_here"));
+                return;
+            },
+        };
+        
+        let from = self.current_location;
+        let difficulty = self.new_game_settings.difficulty;
+        let companions = change_level(&mut self.world, player, destination, difficulty);
+        self.current_location = destination;
+        self.world.write_resource::<GameStateResource>().turn_count += 1;
+        
+        let down = destination.depth > from.depth || (destination.branch != from.branch && !destination.is_main());
+        let mut log = self.world.write_resource::<GameLog>();
+        log.add_message(Message::new(if down { "log.descend" } else { "log.ascend" }).arg("location", destination.display_name()));
+        let names = self.world.read_storage::<Name>();
+        for companion in companions {
+            if let Some(name) = names.get(companion) {
+                log.add_message(Message::new("log.companion_follows").arg("name", localization::name(&name.name)));
+            }
+        }
+    }
+    
+    fn spawn_wanderers(&mut self) {
+        let spawns = self.world.write_resource::<WanderingSpawns>().take();
+        let difficulty = self.new_game_settings.difficulty;
+        for spawn in spawns {
+            if let SpawnType::Enemy(enemy) = spawn.entity_type {
+                EntityFactory::create_enemy(&mut self.world, spawn.x, spawn.y, enemy, difficulty);
+            }
+        }
+    }
+    
+    /// Keep what carries over to later runs: what has been learned of the monsters, what is held
+    /// at the bank, who is on the guild's books, what is going on in the world and what the
+    /// language model has already answered
+    fn save_between_runs(&mut self) {
+        let saved = self.world.read_resource::<Bestiary>().save(BESTIARY_PATH);
+        if let Err(e) = saved {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.bestiary_not_saved").arg("error", e));
+        }
+        let saved = self.world.read_resource::<Bank>().save(BANK_PATH);
+        if let Err(e) = saved {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.bank_not_saved").arg("error", e));
+        }
+        let saved = self.world.read_resource::<GuildRoster>().save(GUILD_PATH);
+        if let Err(e) = saved {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.guild_not_saved").arg("error", e));
+        }
+        let saved = self.world.read_resource::<WorldEvents>().save(WORLD_PATH);
+        if let Err(e) = saved {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.world_not_saved").arg("error", e));
+        }
+        let saved = self.world.read_resource::<ModelQueue>().save_cache(MODEL_CACHE_PATH);
+        if let Err(e) = saved {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.model_cache_not_saved").arg("error", e));
+        }
+    }
+    
+    /// Wizard mode: log how full the model cache is and its most reused answers
+    fn inspect_model_cache(&mut self) {
+        let lines = self.world.read_resource::<ModelQueue>().with_cache(|cache| {
+            let mut lines = vec![Message::new("log.model_cache_summary")
+                .arg("entries", cache.len())
+                .arg("capacity", cache.capacity)
+                .arg("hits", cache.hits)
+                .arg("misses", cache.misses)
+                .text()];
+            for (key, entry) in cache.most_used(MODEL_CACHE_SHOWN) {
+                lines.push(Message::new("log.model_cache_entry")
+                    .arg("key", key)
+                    .arg("hits", entry.hits)
+                    .arg("preview", &entry.preview)
+                    .text());
+            }
+            lines
+        });
+        let mut log = self.world.write_resource::<GameLog>();
+        match lines {
+            Some(lines) => {
+                for line in lines {
+                    log.add_entry(line);
+                }
+            },
+            None => log.add_message(Message::new("log.model_cache_missing")),
+        }
+    }
+
+    /// Wizard mode: drop every answer the model cache holds, on disk as well
+    fn purge_model_cache(&mut self) {
+        let purged = self.world.read_resource::<ModelQueue>().with_cache(|cache| cache.purge());
+        let message = match purged {
+            Some(count) => Message::new("log.model_cache_purged").arg("count", count),
+            None => Message::new("log.model_cache_missing"),
+        };
+        self.world.write_resource::<GameLog>().add_message(message);
+        if let Err(e) = self.world.read_resource::<ModelQueue>().save_cache(MODEL_CACHE_PATH) {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.model_cache_not_saved").arg("error", e));
+        }
+    }
+    
+    fn record_victory(&mut self) {
+        self.save_between_runs();
+        let (name, level) = match self.player {
+            Some(player) => (
+                self.world.read_storage::<Name>().get(player).map_or("Unknown".to_string(), |n| n.name.clone()),
+                self.world.read_storage::<Experience>().get(player).map_or(1, |exp| exp.level),
+            ),
+            None => ("Unknown".to_string(), 1),
+        };
+        let turns = self.world.read_resource::<GameStateResource>().turn_count;
+        let record = RunRecord::new(name, level, RunOutcome::Victory, turns, &self.world.read_resource::<RunStatistics>());
+        
+        let mut history = RunHistory::load(RUN_HISTORY_PATH).unwrap_or_default();
+        history.record(record);
+        if let Err(e) = history.save(RUN_HISTORY_PATH) {
+            self.world.write_resource::<GameLog>().add_message(Message::new("log.run_history_failed").arg("error", e));
+        }
+    }
+    
+    /// Keep the last frame and a morgue file of how the run ended, and add it to the run history
+    fn record_death(&mut self) {
+        self.save_between_runs();
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        let name = self.world.read_storage::<Name>().get(player).map_or("Unknown".to_string(), |n| n.name.clone());
+        let level = self.world.read_storage::<Experience>().get(player).map_or(1, |exp| exp.level);
+        let cause = self.world.read_storage::<LastAttacker>().get(player)
+            .and_then(|attacker| self.world.read_storage::<Name>().get(attacker.entity).map(|n| n.name.clone()))
+            .unwrap_or_else(|| "misadventure".to_string());
+        let (turns, depth) = {
+            let game_state = self.world.read_resource::<GameStateResource>();
+            (game_state.turn_count, game_state.depth)
+        };
+        
+        let screen = crate::rendering::with_terminal(|terminal| Ok(terminal.screen().clone()));
+        let directory = Path::new(SCREENSHOTS_DIR);
+        let stem = screenshot_name(directory, "morgue");
+        let written = screen.and_then(|screen| {
+            save_screenshot(&screen, directory, &stem, &self.screenshot_formats)?;
+            let morgue = morgue_text(&name, level, &cause, depth, turns, &self.world.read_resource::<GameLog>(), &screen);
+            let path = directory.join(format!("{}-morgue.txt", stem));
+            std::fs::write(&path, morgue)?;
+            Ok(path)
+        });
+        
+        let record = RunRecord::new(name, level, RunOutcome::Died { cause }, turns, &self.world.read_resource::<RunStatistics>());
+        let mut history = RunHistory::load(RUN_HISTORY_PATH).unwrap_or_default();
+        history.record(record);
+        let saved = history.save(RUN_HISTORY_PATH);
+        
+        let mut log = self.world.write_resource::<GameLog>();
+        match written {
+            Ok(path) => log.add_message(Message::new("log.morgue_written").arg("path", path.display())),
+            Err(e) => log.add_message(Message::new("log.morgue_failed").arg("error", e)),
+        }
+        if let Err(e) = saved {
+            log.add_message(Message::new("log.run_history_failed").arg("error", e));
+        }
+    }
+    
+    fn update_inventory(&mut self) {
+        // Placeholder for inventory update logic
+    }
+    
+    fn update_character_sheet(&mut self) {
+        // Placeholder for character sheet update logic
+    }
+    
+    fn update_game_over(&mut self) {
+        // Placeholder for game over update logic
+    }
+    
+    fn update_victory(&mut self) {
+        // Nothing to simulate once the run is won
+    }
+    
+    fn update_level_up(&mut self) {
+        // Placeholder for level up update logic
+    }
+    
+    fn update_targeting(&mut self) {
+        // Placeholder for targeting update logic
+    }
+    
+    fn update_save_game(&mut self) {
+        // Placeholder for save game update logic
+    }
+    
+    fn update_load_game(&mut self) {
+        // Placeholder for load game update logic
+    }
+    
+    fn update_options(&mut self) {
+        // Placeholder for options update logic
+    }
+    
+    fn update_help(&mut self) {
+        // Placeholder for help update logic
+    }
+    
+    fn update_pause(&mut self) {
         // Placeholder for pause update logic
     }
     
@@ -386,6 +2489,15 @@ impl GameState {
     }
     
     pub fn render(&mut self) {
+        use crate::rendering::with_terminal;
+        
+        // Every layer draws into one frame buffer, shown with a single flush at the end
+        let _ = with_terminal(|terminal| terminal.begin_frame());
+        self.render_frame();
+        let _ = with_terminal(|terminal| terminal.end_frame());
+    }
+    
+    fn render_frame(&mut self) {
         // Render character creation if in character creation state
         if matches!(self.run_state, 
             RunState::CharacterName | 
@@ -405,10 +2517,21 @@ impl GameState {
             StateType::Inventory => self.render_inventory(),
             StateType::CharacterSheet => self.render_character_sheet(),
             StateType::GameOver => self.render_game_over(),
+            StateType::Revival => self.render_revival(),
+            StateType::Prayer => self.render_prayer(),
+            StateType::Furniture => self.render_furniture(),
+            StateType::Parley => self.render_rival(),
+            StateType::TownTalk => self.render_town(),
+            StateType::Enchanting => self.render_enchanting(),
+            StateType::Alchemy => self.render_alchemy(),
+            StateType::Victory => self.render_victory(),
+            StateType::RunHistory => self.render_run_history(),
+            StateType::Bestiary => self.render_bestiary(),
             StateType::LevelUp => self.render_level_up(),
             StateType::Targeting => self.render_targeting(),
-            StateType::SaveGame => self.render_save_game(),
-            StateType::LoadGame => self.render_load_game(),
+            StateType::Look => self.render_look(),
+            StateType::Travel => self.render_travel(),
+            StateType::SaveGame | StateType::LoadGame => self.render_save_load(),
             StateType::Options => self.render_options(),
             StateType::Help => self.render_help(),
             StateType::Pause => self.render_pause(),
@@ -416,12 +2539,44 @@ impl GameState {
             StateType::MissionAssignment => self.render_mission_assignment(),
             StateType::AgentConfiguration => self.render_agent_configuration(),
         }
+        
+        if matches!(self.state_stack.current(), StateType::Playing | StateType::Inventory | StateType::CharacterSheet) {
+            self.render_tutorial_popup();
+        }
+        if matches!(self.state_stack.current(), StateType::Playing) {
+            self.render_achievement_notifications();
+        }
+    }
+    
+    /// The oldest undismissed tutorial popup, along the bottom of the map so the sidebar stays in view
+    fn render_tutorial_popup(&self) {
+        if let Some(popup) = self.tutorial_popups.front() {
+            let _ = crate::rendering::with_terminal(|terminal| {
+                let (width, height) = terminal.size();
+                draw_commands(terminal, &popup.render(map_viewport_width(width) as i32, height as i32))
+            });
+        }
+    }
+    
+    /// Banners for freshly unlocked achievements, drawn over the map until they fade
+    fn render_achievement_notifications(&self) {
+        let commands = {
+            let mut notifications = self.world.write_resource::<AchievementNotificationSystem>();
+            notifications.update();
+            notifications.render()
+        };
+        if !commands.is_empty() {
+            let _ = crate::rendering::with_terminal(|terminal| draw_commands(terminal, &commands));
+        }
     }
     
     fn render_main_menu(&mut self) {
         use crate::rendering::with_terminal;
         use crossterm::style::Color;
         
+        let difficulty = self.new_game_settings.difficulty;
+        let continue_details = self.continue_details();
+        
         let _ = with_terminal(|terminal| {
             // Clear the screen
             terminal.clear()?;
@@ -434,17 +2589,30 @@ impl GameState {
             let center_y = height / 2;
             
             // Draw title
-            terminal.draw_text_centered(center_y - 5, "ASCII DUNGEON EXPLORER", Color::Yellow, Color::Black)?;
+            terminal.draw_text_centered(center_y - 5, &tr("menu.title"), Color::Yellow, Color::Black)?;
             
-            // Draw menu options
-            terminal.draw_text(center_x - 10, center_y, "n - New Game", Color::White, Color::Black)?;
-            terminal.draw_text(center_x - 10, center_y + 1, "l - Load Game", Color::White, Color::Black)?;
-            terminal.draw_text(center_x - 10, center_y + 2, "o - Options", Color::White, Color::Black)?;
-            terminal.draw_text(center_x - 10, center_y + 3, "h - Help", Color::White, Color::Black)?;
-            terminal.draw_text(center_x - 10, center_y + 4, "q - Quit", Color::White, Color::Black)?;
+            // Draw the difficulty the next new game will use
+            terminal.draw_text_centered(center_y - 3, &Message::new("menu.difficulty").arg("difficulty", difficulty.name()).text(), Color::Cyan, Color::Black)?;
+            terminal.draw_text_centered(center_y - 2, difficulty.description(), Color::DarkGrey, Color::Black)?;
+            
+            // Draw menu options, Continue first when there is a save to pick up
+            let mut row = center_y;
+            if let Some(details) = &continue_details {
+                terminal.draw_text(center_x - 10, row, &tr("menu.continue"), Color::White, Color::Black)?;
+                terminal.draw_text(center_x + 6, row, details, Color::DarkGrey, Color::Black)?;
+                row += 1;
+            }
+            for key in MAIN_MENU_OPTIONS.iter() {
+                terminal.draw_text(center_x - 10, row, &tr(key), Color::White, Color::Black)?;
+                row += 1;
+            }
+            if let Some(notice) = &self.main_menu_notice {
+                terminal.draw_text_centered(row + 1, notice, Color::Red, Color::Black)?;
+            }
             
             // Draw version
-            terminal.draw_text(width - 20, height - 1, "Version 0.1.0", Color::DarkGrey, Color::Black)?;
+            let version = Message::new("menu.version").arg("version", env!("CARGO_PKG_VERSION")).text();
+            terminal.draw_text(width - 20, height - 1, &version, Color::DarkGrey, Color::Black)?;
             
             terminal.flush()
         });
@@ -453,46 +2621,448 @@ impl GameState {
     fn render_playing(&mut self) {
         // Use the render system to render the game
         self.system_runner.render(&self.world);
+        
+        // The hotbar along the bottom of the map
+        if let Some(player) = self.player {
+            let _ = crate::rendering::with_terminal(|terminal| {
+                let (width, height) = terminal.size();
+                draw_commands(terminal, &crate::ui::render_hotbar(&self.world, player, map_viewport_width(width) as i32, height as i32))
+            });
+        }
     }
     
     fn render_inventory(&mut self) {
-        // Placeholder for inventory rendering
+        let _ = crate::rendering::with_terminal(|terminal| terminal.clear());
+        let _ = self.inventory_ui.draw(&self.world, self.ui_backend.as_mut());
+        let _ = self.ui_backend.present();
     }
     
     fn render_character_sheet(&mut self) {
-        if let Some(player) = self.player {
-            crate::ui::render_character_sheet(&self.world, player);
-        }
+        let _ = crate::rendering::with_terminal(|terminal| {
+            terminal.clear()?;
+            let (width, height) = terminal.size();
+            self.character_sheet.fit_to(height as i32);
+            draw_commands(terminal, &self.character_sheet.render(&self.world, width as i32, height as i32))?;
+            terminal.flush()
+        });
+    }
+    
+    fn render_revival(&mut self) {
+        // The revival prompt sits over the level the player fell on
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.revival.render(&self.world, width as i32, height as i32))?;
+            terminal.flush()
+        });
+    }
+    
+    fn render_prayer(&mut self) {
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.prayer.render(&self.world, width as i32, height as i32))?;
+            terminal.flush()
+        });
+    }
+    
+    fn render_furniture(&mut self) {
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.furniture_menu.render(&self.world, width as i32, height as i32))?;
+            terminal.flush()
+        });
+    }
+    
+    fn render_rival(&mut self) {
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.rival_menu.render(&self.world, width as i32, height as i32))?;
+            terminal.flush()
+        });
+    }
+    
+    fn render_town(&mut self) {
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.town_menu.render(&self.world, width as i32, height as i32))?;
+            terminal.flush()
+        });
+    }
+    
+    fn render_enchanting(&mut self) {
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            if let Some(enchanting) = &self.enchanting {
+                draw_commands(terminal, &enchanting.render(&self.world, width as i32, height as i32))?;
+            }
+            terminal.flush()
+        });
+    }
+    
+    fn render_alchemy(&mut self) {
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            if let Some(alchemy) = &self.alchemy {
+                draw_commands(terminal, &alchemy.render(&self.world, width as i32, height as i32))?;
+            }
+            terminal.flush()
+        });
+    }
+    
+    fn render_bestiary(&mut self) {
+        let bestiary = self.world.read_resource::<Bestiary>();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            terminal.clear()?;
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.bestiary.render(&bestiary, width as i32, height as i32))?;
+            terminal.flush()
+        });
     }
     
     fn render_game_over(&mut self) {
         // Placeholder for game over rendering
     }
     
+    fn render_victory(&mut self) {
+        use crate::rendering::with_terminal;
+        use crossterm::style::Color;
+        
+        let stats = self.world.read_resource::<RunStatistics>().clone();
+        let turns = self.world.read_resource::<GameStateResource>().turn_count;
+        let victories = RunHistory::load(RUN_HISTORY_PATH).map_or(1, |history| history.victories());
+        
+        let _ = with_terminal(|terminal| {
+            terminal.clear()?;
+            
+            let (_, height) = terminal.size();
+            let center_y = height / 2;
+            
+            terminal.draw_text_centered(center_y - 8, "VICTORY!", Color::Yellow, Color::Black)?;
+            terminal.draw_text_centered(center_y - 6, "You escaped the dungeon with the Amulet of the Depths.", Color::White, Color::Black)?;
+            
+            let lines = [
+                format!("Turns taken: {}", turns),
+                format!("Enemies slain: {}", stats.kills),
+                format!("Bosses defeated: {}", stats.bosses_defeated),
+                format!("Deepest level: {}", stats.deepest_depth),
+                format!("Turns spent ascending: {}", stats.ascent_turns().map_or("-".to_string(), |t| t.to_string())),
+                format!("Total victories: {}", victories),
+            ];
+            for (i, line) in lines.iter().enumerate() {
+                terminal.draw_text_centered(center_y - 3 + i as u16, line, Color::Cyan, Color::Black)?;
+            }
+            
+            terminal.draw_text_centered(center_y + 5, "Press Enter to return to the main menu", Color::DarkGrey, Color::Black)?;
+            
+            terminal.flush()
+        });
+    }
+    
+    fn render_run_history(&mut self) {
+        use crate::rendering::with_terminal;
+        use crossterm::style::Color;
+        
+        let history = RunHistory::load(RUN_HISTORY_PATH).unwrap_or_default();
+        let found = history.found_artifacts();
+        let database = self.world.read_resource::<ArtifactDatabase>().clone();
+        
+        let _ = with_terminal(|terminal| {
+            terminal.clear()?;
+            
+            let (width, height) = terminal.size();
+            let center_x = width / 2;
+            
+            terminal.draw_text_centered(2, "RUN HISTORY", Color::Yellow, Color::Black)?;
+            terminal.draw_text_centered(4, &format!("Runs: {}   Victories: {}   Fastest victory: {}",
+                history.runs.len(),
+                history.victories(),
+                history.fastest_victory().map_or("-".to_string(), |run| format!("{} turns", run.turns))),
+                Color::Cyan, Color::Black)?;
+            
+            // Most recent runs first
+            terminal.draw_text(2, 6, "Recent runs", Color::White, Color::Black)?;
+            for (i, run) in history.runs.iter().rev().take(10).enumerate() {
+                let outcome = match &run.outcome {
+                    RunOutcome::Victory => "Victory".to_string(),
+                    RunOutcome::Died { cause } => format!("Killed by {}", cause),
+                    RunOutcome::Abandoned => "Abandoned".to_string(),
+                };
+                let line = format!("{} (lvl {}) - {} - depth {}, {} turns", run.character_name, run.character_level, outcome, run.deepest_depth, run.turns);
+                terminal.draw_text(2, 7 + i as u16, &line, Color::Grey, Color::Black)?;
+            }
+            
+            // Every known artifact, hidden until found in some run
+            terminal.draw_text(center_x, 6, &format!("Artifacts found: {}/{}", found.len(), database.artifacts.len()), Color::White, Color::Black)?;
+            for (i, artifact) in database.artifacts.iter().enumerate() {
+                let (line, color) = match found.get(&artifact.id) {
+                    Some(runs) => (format!("{} (found in {} runs)", artifact.name, runs), Color::DarkYellow),
+                    None => ("???".to_string(), Color::DarkGrey),
+                };
+                terminal.draw_text(center_x, 7 + i as u16, &line, color, Color::Black)?;
+            }
+            
+            terminal.draw_text_centered(height - 2, "Press Esc to return", Color::DarkGrey, Color::Black)?;
+            
+            terminal.flush()
+        });
+    }
+    
     fn render_level_up(&mut self) {
-        if let Some(player) = self.player {
-            crate::ui::render_level_up_screen(&self.world, player);
-        }
+        self.render_playing();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.level_up.render(width as i32, height as i32))?;
+            terminal.flush()
+        });
     }
     
     fn render_targeting(&mut self) {
-        // Placeholder for targeting rendering
+        use crate::rendering::with_terminal;
+        use crossterm::style::Color;
+        
+        self.render_playing();
+        
+        let names = self.world.read_storage::<Name>();
+        let stacks = self.world.read_storage::<crate::items::ItemStack>();
+        let target = self.targeting.current_target()
+            .and_then(|target| names.get(target))
+            .map_or("-".to_string(), |n| n.name.clone());
+        
+        // Odds against the hovered target, so the player can judge the attack before committing
+        let preview = match (self.player, self.targeting.current_target(), self.targeting.ability) {
+            (Some(_), Some(target), Some(ability)) => crate::combat::preview_area_ability(&self.world, target, ability),
+            (Some(player), Some(target), None) => self.targeting.current_ammo()
+                .and_then(|ammo| crate::combat::preview_shot(&self.world, player, target, ammo)),
+            _ => None,
+        };
+        let target = match preview {
+            Some(preview) => format!("{} ({})", target, preview.summary()),
+            None => target,
+        };
+        let line = match (self.targeting.thrown, self.targeting.ability) {
+            (Some(item), _) => format!("Throw {} - Target {}/{}: {}   [Tab] target  [Enter] throw  [Esc] cancel",
+                names.get(item).map_or("-".to_string(), |n| n.name.clone()),
+                self.targeting.target_index + 1, self.targeting.targets.len(), target),
+            (None, Some(ability)) => format!("{} - Target {}/{}: {}   [Tab] target  [Enter] cast  [Esc] cancel",
+                ability.name(), self.targeting.target_index + 1, self.targeting.targets.len(), target),
+            (None, None) => {
+                let ammo = self.targeting.current_ammo().map_or("-".to_string(), |ammo| {
+                    let name = names.get(ammo).map_or("Ammunition".to_string(), |n| n.name.clone());
+                    let count = stacks.get(ammo).map_or(1, |stack| stack.quantity);
+                    format!("{} x{}", name, count)
+                });
+                format!("Target {}/{}: {}   Ammo: {}   [Tab] target  [a] ammo  [Enter] fire  [Esc] cancel",
+                    self.targeting.target_index + 1, self.targeting.targets.len(), target, ammo)
+            },
+        };
+        
+        // Highlight every tile the aimed ability would catch
+        let preview = match self.player {
+            Some(player) => self.targeting.preview_tiles(&self.world, player),
+            None => Vec::new(),
+        };
+        let player_pos = self.player
+            .and_then(|player| self.world.read_storage::<Position>().get(player).map(|pos| (pos.x, pos.y)))
+            .unwrap_or((0, 0));
+        let glyphs: std::collections::HashMap<(i32, i32), char> = (&self.world.read_storage::<Position>(), &self.world.read_storage::<Renderable>())
+            .join()
+            .map(|(pos, render)| ((pos.x, pos.y), render.glyph))
+            .collect();
+        let map = self.world.fetch::<Map>();
+        
+        let _ = with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            let camera = crate::rendering::create_camera_for_map(&map, crate::rendering::map_viewport_width(width) as i32, height as i32, player_pos);
+            for &(x, y) in &preview {
+                if !camera.is_visible(x, y) || !map.visible_tiles[map.xy_idx(x, y)] {
+                    continue;
+                }
+                let glyph = glyphs.get(&(x, y)).copied().unwrap_or_else(|| map.tiles[map.xy_idx(x, y)].glyph());
+                let (screen_x, screen_y) = camera.world_to_screen(x, y);
+                terminal.draw_char_at(screen_x as u16, screen_y as u16, glyph, Color::Yellow, Color::DarkRed)?;
+            }
+            terminal.draw_text(0, height - 1, &line, Color::Yellow, Color::Black)?;
+            terminal.flush()
+        });
     }
     
-    fn render_save_game(&mut self) {
-        // Placeholder for save game rendering
+    fn render_look(&mut self) {
+        use crate::rendering::with_terminal;
+        use crossterm::style::Color;
+        
+        self.render_playing();
+        
+        let cursor = match self.targeting.cursor {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        let info = examine(&self.world, cursor.0, cursor.1);
+        let player_pos = self.player
+            .and_then(|player| self.world.read_storage::<Position>().get(player).map(|pos| (pos.x, pos.y)))
+            .unwrap_or(cursor);
+        let glyph = (&self.world.read_storage::<Position>(), &self.world.read_storage::<Renderable>())
+            .join()
+            .filter(|(pos, _)| (pos.x, pos.y) == cursor)
+            .max_by_key(|(_, render)| render.render_order)
+            .map(|(_, render)| render.glyph);
+        let map = self.world.fetch::<Map>();
+        let glyph = glyph.unwrap_or_else(|| map.get_tile(cursor.0, cursor.1).map_or(' ', |tile| tile.glyph()));
+        
+        let _ = with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            let camera = crate::rendering::create_camera_for_map(&map, crate::rendering::map_viewport_width(width) as i32, height as i32, player_pos);
+            if camera.is_visible(cursor.0, cursor.1) {
+                let (screen_x, screen_y) = camera.world_to_screen(cursor.0, cursor.1);
+                terminal.draw_char_at(screen_x as u16, screen_y as u16, glyph, Color::Black, Color::Yellow)?;
+            }
+            
+            // Details for whatever is under the cursor, over the top of the sidebar
+            let panel_width = crate::rendering::SIDEBAR_WIDTH;
+            let panel_x = crate::rendering::map_viewport_width(width);
+            terminal.fill_rect(panel_x, 0, panel_width, 14, ' ', Color::White, Color::Black)?;
+            terminal.draw_box(panel_x, 0, panel_width, 14, Color::White, Color::Black)?;
+            let text_width = (panel_width - 4) as usize;
+            let mut row = 1;
+            match &info {
+                Some(info) => {
+                    let name: String = localization::name(&info.name).chars().take(text_width).collect();
+                    terminal.draw_text(panel_x + 2, row, &name, Color::Yellow, Color::Black)?;
+                    row += 1;
+                    terminal.draw_text(panel_x + 2, row, info.faction.name(), info.faction.color(), Color::Black)?;
+                    row += 1;
+                    if let Some(health) = info.health {
+                        terminal.draw_text(panel_x + 2, row, &format!("{} {}", health.bar(10), health.label()), health.color(), Color::Black)?;
+                        row += 1;
+                    }
+                    // Each effect on its own line, icon first, as in the sidebar
+                    let icons = self.world.fetch::<StatusIconRegistry>();
+                    for effect in info.statuses.iter().take(LOOK_STATUS_LINES) {
+                        let icon = icons.icon(effect.effect_type);
+                        let label: String = format!("{} ({})", effect.effect_type.name(), effect.duration)
+                            .chars().take(text_width - 2).collect();
+                        terminal.draw_char_at(panel_x + 2, row, icon.glyph, icon.color, Color::Black)?;
+                        terminal.draw_text(panel_x + 4, row, &label, Color::Magenta, Color::Black)?;
+                        row += 1;
+                    }
+                    if let (true, Some(behavior)) = (self.wizard_mode, &info.behavior) {
+                        let behavior: String = format!("BT: {}", behavior).chars().take(text_width).collect();
+                        terminal.draw_text(panel_x + 2, row, &behavior, Color::Cyan, Color::Black)?;
+                        row += 1;
+                    }
+                    row += 1;
+                    for line in wrap_text(&info.description, text_width).iter().take((13 - row) as usize) {
+                        terminal.draw_text(panel_x + 2, row, line, Color::Grey, Color::Black)?;
+                        row += 1;
+                    }
+                },
+                None => {
+                    terminal.draw_text(panel_x + 2, row, &tr("look.cannot_see"), Color::DarkGrey, Color::Black)?;
+                },
+            }
+            
+            terminal.draw_text(0, height - 1, &tr("look.controls"), Color::Yellow, Color::Black)?;
+            terminal.flush()
+        });
     }
     
-    fn render_load_game(&mut self) {
-        // Placeholder for load game rendering
+    /// What lies at the travel cursor and how many steps away it is, if there is a known way
+    fn travel_destination(&self) -> (String, Option<usize>) {
+        let (x, y) = self.travel.cursor;
+        let map = self.world.fetch::<Map>();
+        if !map.is_revealed(x, y) {
+            return (tr("travel.unknown"), None);
+        }
+        let name = map.get_tile(x, y).map_or_else(|| tr("travel.unknown"), |tile| localization::name(tile.name()));
+        let steps = self.player
+            .and_then(|player| self.world.read_storage::<Position>().get(player).map(|pos| (pos.x, pos.y)))
+            .and_then(|here| map.find_known_path(here, (x, y)))
+            .map(|path| path.len());
+        (name, steps)
+    }
+    
+    fn render_travel(&mut self) {
+        use crate::rendering::with_terminal;
+        use crossterm::style::Color;
+        
+        self.render_playing();
+        
+        let cursor = self.travel.cursor;
+        let (name, steps) = self.travel_destination();
+        let player_pos = self.player
+            .and_then(|player| self.world.read_storage::<Position>().get(player).map(|pos| (pos.x, pos.y)))
+            .unwrap_or(cursor);
+        let map = self.world.fetch::<Map>();
+        let glyph = if map.is_revealed(cursor.0, cursor.1) {
+            map.get_tile(cursor.0, cursor.1).map_or(' ', |tile| tile.glyph())
+        } else {
+            ' '
+        };
+        
+        let _ = with_terminal(|terminal| {
+            let (width, height) = terminal.size();
+            let camera = crate::rendering::create_camera_for_map(&map, crate::rendering::map_viewport_width(width) as i32, height as i32, player_pos);
+            if camera.is_visible(cursor.0, cursor.1) {
+                let (screen_x, screen_y) = camera.world_to_screen(cursor.0, cursor.1);
+                terminal.draw_char_at(screen_x as u16, screen_y as u16, glyph, Color::Black, Color::Cyan)?;
+            }
+            
+            // The chosen place and the walk there, over the top of the sidebar
+            let panel_width = crate::rendering::SIDEBAR_WIDTH;
+            let panel_x = crate::rendering::map_viewport_width(width);
+            let text_width = (panel_width - 4) as usize;
+            terminal.fill_rect(panel_x, 0, panel_width, 6, ' ', Color::White, Color::Black)?;
+            terminal.draw_box(panel_x, 0, panel_width, 6, Color::White, Color::Black)?;
+            let title: String = tr("travel.title").chars().take(text_width).collect();
+            terminal.draw_text(panel_x + 2, 1, &title, Color::Cyan, Color::Black)?;
+            let name: String = name.chars().take(text_width).collect();
+            terminal.draw_text(panel_x + 2, 2, &name, Color::Yellow, Color::Black)?;
+            let (route, fg) = match steps {
+                Some(steps) => (Message::new("travel.steps").arg("steps", steps).text(), Color::White),
+                None => (tr("travel.no_way"), Color::DarkGrey),
+            };
+            let route: String = route.chars().take(text_width).collect();
+            terminal.draw_text(panel_x + 2, 3, &route, fg, Color::Black)?;
+            
+            terminal.draw_text(0, height - 1, &tr("travel.controls"), Color::Yellow, Color::Black)?;
+            terminal.flush()
+        });
+    }
+    
+    fn render_save_load(&mut self) {
+        let _ = crate::rendering::with_terminal(|terminal| terminal.clear());
+        let _ = self.save_load.draw(self.ui_backend.as_mut());
+        let _ = self.ui_backend.present();
     }
     
     fn render_options(&mut self) {
-        // Placeholder for options rendering
+        let path = self.config.path().display().to_string();
+        let _ = crate::rendering::with_terminal(|terminal| {
+            terminal.clear()?;
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.options.render(&self.config.config, &path, width as i32, height as i32))?;
+            terminal.flush()
+        });
     }
     
     fn render_help(&mut self) {
-        // Placeholder for help rendering
+        let _ = crate::rendering::with_terminal(|terminal| terminal.clear());
+        let _ = self.help.draw(self.ui_backend.as_mut());
+        let _ = self.ui_backend.present();
+    }
+    
+    /// The pause menu's title and entries; an ironman run can only save by quitting
+    fn pause_options(&self) -> Vec<&'static str> {
+        if self.is_ironman() {
+            vec!["pause.title", "pause.resume", "pause.save_and_quit", "pause.options", "pause.export_hero", "pause.main_menu"]
+        } else {
+            vec!["pause.title", "pause.resume", "pause.save_game", "pause.load_game", "pause.options", "pause.export_hero", "pause.main_menu"]
+        }
     }
     
     fn render_pause(&mut self) {
@@ -512,21 +3082,29 @@ impl GameState {
             terminal.draw_box(center_x - 15, center_y - 6, 30, 12, Color::White, Color::DarkBlue)?;
             
             // Draw title
-            terminal.draw_text(center_x - 5, center_y - 4, "GAME PAUSED", Color::Yellow, Color::DarkBlue)?;
+            terminal.draw_text(center_x - 5, center_y - 4, &tr("pause.title"), Color::Yellow, Color::DarkBlue)?;
             
             // Draw menu options
-            terminal.draw_text(center_x - 10, center_y - 1, "ESC - Resume Game", Color::White, Color::DarkBlue)?;
-            terminal.draw_text(center_x - 10, center_y, "s - Save Game", Color::White, Color::DarkBlue)?;
-            terminal.draw_text(center_x - 10, center_y + 1, "l - Load Game", Color::White, Color::DarkBlue)?;
-            terminal.draw_text(center_x - 10, center_y + 2, "o - Options", Color::White, Color::DarkBlue)?;
-            terminal.draw_text(center_x - 10, center_y + 3, "q - Return to Main Menu", Color::White, Color::DarkBlue)?;
+            for (i, key) in self.pause_options().iter().skip(1).enumerate() {
+                terminal.draw_text(center_x - 10, center_y - 1 + i as u16, &tr(key), Color::White, Color::DarkBlue)?;
+            }
             
             terminal.flush()
         });
     }
     
     fn render_guild_management(&mut self) {
-        // Placeholder for guild management rendering
+        let roster = self.world.read_resource::<GuildRoster>();
+        let world_events = self.world.read_resource::<WorldEvents>();
+        let purse = self.player
+            .and_then(|player| self.world.read_storage::<AdvancedInventory>().get(player).map(|inventory| inventory.gold))
+            .unwrap_or(0);
+        let _ = crate::rendering::with_terminal(|terminal| {
+            terminal.clear()?;
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.guild_hall.render(&roster, &world_events, purse, width as i32, height as i32))?;
+            terminal.flush()
+        });
     }
     
     fn render_mission_assignment(&mut self) {
@@ -534,6 +3112,53 @@ impl GameState {
     }
     
     fn render_agent_configuration(&mut self) {
-        // Placeholder for agent configuration rendering
+        let roster = self.world.read_resource::<GuildRoster>();
+        let world_events = self.world.read_resource::<WorldEvents>();
+        let purse = self.player
+            .and_then(|player| self.world.read_storage::<AdvancedInventory>().get(player).map(|inventory| inventory.gold))
+            .unwrap_or(0);
+        let _ = crate::rendering::with_terminal(|terminal| {
+            terminal.clear()?;
+            let (width, height) = terminal.size();
+            draw_commands(terminal, &self.agents.render(&roster, &world_events, purse, width as i32, height as i32))?;
+            terminal.flush()
+        });
+    }
+}
+
+/// Split text into lines no wider than `width`, breaking on spaces
+/// A character's last moments for the morgue file: who they were, what killed them, the last
+/// messages and the screen as they fell
+fn morgue_text(name: &str, level: i32, cause: &str, depth: i32, turns: u32, log: &GameLog, screen: &CellGrid) -> String {
+    let mut text = format!(
+        "ASCII Dungeon Explorer\n\n{}, level {}, killed by {} on depth {} after {} turns.\n\nLast messages:\n",
+        name, level, cause, depth, turns
+    );
+    let skip = log.entries.len().saturating_sub(10);
+    for entry in log.entries.iter().skip(skip) {
+        text.push_str("  ");
+        text.push_str(entry);
+        text.push('\n');
     }
-}
\ No newline at end of file
+    text.push('\n');
+    text.push_str(&to_text(screen));
+    text
+}
+
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,221 @@
+use specs::{World, WorldExt, Entity, Join};
+use crate::components::{Monster, Position, Viewshed, CombatStats, Abilities, AbilityType};
+use crate::items::{Ammunition, AmmoKind, AdvancedInventory, Equipment, EquipmentSlot, ItemProperties, ItemType};
+use crate::map::Map;
+use crate::systems::{launcher_range, ability_area};
+
+/// How far a potion or flask can be thrown
+pub const THROW_RANGE: i32 = 6;
+
+/// A shot or area ability being lined up on the targeting screen, or the look cursor
+#[derive(Debug, Clone, Default)]
+pub struct RangedTargeting {
+    /// Visible enemies in range, nearest first
+    pub targets: Vec<Entity>,
+    pub target_index: usize,
+    /// Ammunition that fits the launcher, quiver first
+    pub ammo: Vec<Entity>,
+    pub ammo_index: usize,
+    /// The area ability being aimed, when this isn't a bow shot
+    pub ability: Option<AbilityType>,
+    /// Free cursor used by look mode; snaps onto targets as they are cycled
+    pub cursor: Option<(i32, i32)>,
+    /// The potion or flask being thrown, when this is a throw
+    pub thrown: Option<Entity>,
+}
+
+impl RangedTargeting {
+    /// Gather targets and ammunition for the player's launcher, or say why they can't shoot
+    pub fn begin(world: &World, player: Entity) -> Result<Self, String> {
+        let equipment = world.read_storage::<Equipment>();
+        let item_properties = world.read_storage::<ItemProperties>();
+        let ammunition = world.read_storage::<Ammunition>();
+        let inventories = world.read_storage::<AdvancedInventory>();
+
+        let gear = equipment.get(player).ok_or("You have nothing to shoot with.")?;
+        let launcher = gear.get_equipped(&EquipmentSlot::MainHand)
+            .and_then(|weapon| item_properties.get(weapon))
+            .and_then(|props| match &props.item_type {
+                ItemType::Weapon(weapon_type) => AmmoKind::for_weapon(weapon_type),
+                _ => None,
+            })
+            .ok_or("You need a bow or crossbow in hand to shoot.")?;
+
+        let fits = |item: &Entity| ammunition.get(*item).map_or(false, |ammo| ammo.kind == launcher);
+        let mut ammo: Vec<Entity> = gear.get_equipped(&EquipmentSlot::Quiver).into_iter().filter(fits).collect();
+        if let Some(inventory) = inventories.get(player) {
+            for slot in &inventory.items {
+                if fits(&slot.entity) && !ammo.contains(&slot.entity) {
+                    ammo.push(slot.entity);
+                }
+            }
+        }
+        if ammo.is_empty() {
+            return Err(format!("You have no {}s.", launcher.name().to_lowercase()));
+        }
+
+        let targets = visible_targets(world, player, launcher_range(launcher));
+        if targets.is_empty() {
+            return Err("There is nothing in range to shoot at.".to_string());
+        }
+
+        Ok(RangedTargeting {
+            targets,
+            target_index: 0,
+            ammo,
+            ammo_index: 0,
+            ability: None,
+            cursor: None,
+            thrown: None,
+        })
+    }
+
+    /// Aim the first ready area ability the player knows that needs a target
+    pub fn begin_ability(world: &World, player: Entity) -> Result<Self, String> {
+        let abilities = world.read_storage::<Abilities>();
+        let known = abilities.get(player).ok_or("You have no abilities.")?;
+        let mut aimed: Vec<AbilityType> = AbilityType::get_all_abilities().into_iter()
+            .filter(|ability| known.has_ability(*ability))
+            .filter(|ability| matches!(ability_area(*ability), Some((template, _)) if template.needs_target()))
+            .collect();
+        if aimed.is_empty() {
+            return Err("You know no abilities that can be aimed.".to_string());
+        }
+        aimed.retain(|ability| !known.is_on_cooldown(*ability));
+        let ability = *aimed.first().ok_or("Your abilities are still recovering.")?;
+        RangedTargeting::aim_ability(world, player, ability)
+    }
+
+    /// Aim one particular ability, as fired from the hotbar
+    pub fn aim_ability(world: &World, player: Entity, ability: AbilityType) -> Result<Self, String> {
+        let targets = visible_targets(world, player, ability.get_range());
+        if targets.is_empty() {
+            return Err(format!("There is nothing in range of your {}.", ability.name()));
+        }
+
+        Ok(RangedTargeting {
+            targets,
+            target_index: 0,
+            ammo: Vec::new(),
+            ammo_index: 0,
+            ability: Some(ability),
+            cursor: None,
+            thrown: None,
+        })
+    }
+
+    /// Line up a throw of an item from the pack at the nearest enemy in reach
+    pub fn begin_throw(world: &World, player: Entity, item: Entity) -> Result<Self, String> {
+        let targets = visible_targets(world, player, THROW_RANGE);
+        if targets.is_empty() {
+            return Err("There is nothing in range to throw at.".to_string());
+        }
+
+        Ok(RangedTargeting {
+            targets,
+            target_index: 0,
+            thrown: Some(item),
+            ..Default::default()
+        })
+    }
+
+    /// Start looking around from the player's tile, with every visible creature one Tab away
+    pub fn begin_look(world: &World, player: Entity) -> Self {
+        let positions = world.read_storage::<Position>();
+        let origin = positions.get(player).map_or((0, 0), |pos| (pos.x, pos.y));
+        let map = world.fetch::<Map>();
+
+        let mut creatures: Vec<(i32, Entity)> = (&world.entities(), &world.read_storage::<CombatStats>(), &positions)
+            .join()
+            .filter(|(entity, stats, pos)| *entity != player && stats.hp > 0 && map.is_visible(pos.x, pos.y))
+            .map(|(entity, _, pos)| ((pos.x - origin.0).pow(2) + (pos.y - origin.1).pow(2), entity))
+            .collect();
+        creatures.sort_by_key(|(distance_sq, _)| *distance_sq);
+
+        RangedTargeting {
+            targets: creatures.into_iter().map(|(_, entity)| entity).collect(),
+            target_index: 0,
+            cursor: Some(origin),
+            ..Default::default()
+        }
+    }
+
+    /// Step the look cursor, refusing to leave the tiles the player can see
+    pub fn move_cursor(&mut self, map: &Map, dx: i32, dy: i32) {
+        if let Some((x, y)) = self.cursor {
+            let (nx, ny) = (x + dx, y + dy);
+            if map.is_visible(nx, ny) {
+                self.cursor = Some((nx, ny));
+            }
+        }
+    }
+
+    /// Put the look cursor on the current target
+    pub fn snap_cursor(&mut self, world: &World) {
+        if let Some(pos) = self.current_target().and_then(|target| world.read_storage::<Position>().get(target).cloned()) {
+            self.cursor = Some((pos.x, pos.y));
+        }
+    }
+
+    /// Tiles the aimed ability would hit at the current target
+    pub fn preview_tiles(&self, world: &World, player: Entity) -> Vec<(i32, i32)> {
+        let (template, _) = match self.ability.and_then(ability_area) {
+            Some(area) => area,
+            None => return Vec::new(),
+        };
+        let positions = world.read_storage::<Position>();
+        match (positions.get(player), self.current_target().and_then(|target| positions.get(target))) {
+            (Some(origin), Some(target)) => {
+                template.tiles(&world.fetch::<Map>(), (origin.x, origin.y), (target.x, target.y))
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn current_target(&self) -> Option<Entity> {
+        self.targets.get(self.target_index).copied()
+    }
+
+    pub fn current_ammo(&self) -> Option<Entity> {
+        self.ammo.get(self.ammo_index).copied()
+    }
+
+    pub fn cycle_target(&mut self, forward: bool) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let count = self.targets.len();
+        self.target_index = if forward {
+            (self.target_index + 1) % count
+        } else {
+            (self.target_index + count - 1) % count
+        };
+    }
+
+    pub fn cycle_ammo(&mut self) {
+        if !self.ammo.is_empty() {
+            self.ammo_index = (self.ammo_index + 1) % self.ammo.len();
+        }
+    }
+}
+
+/// Living, visible enemies within range of the player, nearest first
+fn visible_targets(world: &World, player: Entity, range: i32) -> Vec<Entity> {
+    let positions = world.read_storage::<Position>();
+    let origin = match positions.get(player) {
+        Some(pos) => pos,
+        None => return Vec::new(),
+    };
+    let visible = world.read_storage::<Viewshed>().get(player)
+        .map(|viewshed| viewshed.visible_tiles.clone())
+        .unwrap_or_default();
+
+    let mut targets: Vec<(i32, Entity)> = (&world.entities(), &world.read_storage::<Monster>(), &world.read_storage::<CombatStats>(), &positions)
+        .join()
+        .filter(|(_, _, stats, pos)| stats.hp > 0 && visible.contains(&(pos.x, pos.y)))
+        .map(|(entity, _, _, pos)| ((pos.x - origin.x).pow(2) + (pos.y - origin.y).pow(2), entity))
+        .filter(|(distance_sq, _)| *distance_sq <= range * range)
+        .collect();
+    targets.sort_by_key(|(distance_sq, _)| *distance_sq);
+    targets.into_iter().map(|(_, entity)| entity).collect()
+}
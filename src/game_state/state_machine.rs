@@ -7,8 +7,29 @@ pub enum StateType {
     Inventory,
     CharacterSheet,
     GameOver,
+    /// The player fell and may come back
+    Revival,
+    /// Kneeling before the gods
+    Prayer,
+    /// Using a fountain, shrine, lever or bookshelf
+    Furniture,
+    /// Dealing with a rival adventurer
+    Parley,
+    /// Dealing with someone in town
+    TownTalk,
+    /// Working at an enchanting station
+    Enchanting,
+    /// Mixing potions at an alchemy station
+    Alchemy,
+    Victory,
+    RunHistory,
+    /// Every monster met in any run
+    Bestiary,
     LevelUp,
     Targeting,
+    Look,
+    /// Choosing a known place to walk to
+    Travel,
     SaveGame,
     LoadGame,
     Options,
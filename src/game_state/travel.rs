@@ -0,0 +1,109 @@
+use specs::{World, WorldExt, Entity};
+use crate::components::Position;
+use crate::map::{Map, TileType};
+
+/// The cursor of the travel map, free to roam the level and quick to jump between the places
+/// worth walking to
+#[derive(Debug, Clone, Default)]
+pub struct TravelCursor {
+    pub cursor: (i32, i32),
+    /// Stairs and other landmarks already seen, nearest first
+    pub points: Vec<(i32, i32)>,
+    pub point_index: Option<usize>,
+}
+
+impl TravelCursor {
+    /// Start on the player's tile with every known landmark one Tab away
+    pub fn begin(world: &World, player: Entity) -> Self {
+        let origin = world.read_storage::<Position>().get(player).map_or((0, 0), |pos| (pos.x, pos.y));
+        let map = world.fetch::<Map>();
+        let mut points = points_of_interest(&map);
+        points.retain(|point| *point != origin);
+        points.sort_by_key(|(x, y)| (x - origin.0).abs().max((y - origin.1).abs()));
+        TravelCursor { cursor: origin, points, point_index: None }
+    }
+
+    /// Step the cursor anywhere on the level
+    pub fn move_cursor(&mut self, map: &Map, dx: i32, dy: i32) {
+        let (x, y) = (self.cursor.0 + dx, self.cursor.1 + dy);
+        if map.in_bounds(x, y) {
+            self.cursor = (x, y);
+        }
+    }
+
+    /// Jump to the next or previous landmark
+    pub fn cycle_point(&mut self, forward: bool) {
+        if self.points.is_empty() {
+            return;
+        }
+        let count = self.points.len();
+        let index = match (self.point_index, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(index), true) => (index + 1) % count,
+            (Some(index), false) => (index + count - 1) % count,
+        };
+        self.point_index = Some(index);
+        self.cursor = self.points[index];
+    }
+
+    /// Jump to the nearest known stairs of a kind
+    pub fn jump_to(&mut self, map: &Map, tile: TileType) {
+        if let Some(index) = self.points.iter().position(|(x, y)| map.get_tile(*x, *y) == Some(tile)) {
+            self.point_index = Some(index);
+            self.cursor = self.points[index];
+        }
+    }
+}
+
+/// Places on the level worth travelling to that the player has already seen
+pub fn points_of_interest(map: &Map) -> Vec<(i32, i32)> {
+    let mut points = Vec::new();
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let idx = map.xy_idx(x, y);
+            if map.revealed_tiles[idx] && matches!(map.tiles[idx], TileType::DownStairs | TileType::UpStairs) {
+                points.push((x, y));
+            }
+        }
+    }
+    // The way out, even where it is only marked on the floor
+    if map.is_revealed(map.exit.0, map.exit.1) && !points.contains(&map.exit) {
+        points.push(map.exit);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+
+    #[test]
+    fn test_landmarks_are_only_the_ones_seen_nearest_first() {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        let mut map = Map::new(20, 5, 1);
+        for x in 1..19 {
+            map.set_tile(x, 2, TileType::Floor);
+        }
+        map.set_tile(3, 2, TileType::UpStairs);
+        map.set_tile(15, 2, TileType::DownStairs);
+        map.set_tile(18, 2, TileType::DownStairs);
+        map.exit = (15, 2);
+        for x in 1..17 {
+            let idx = map.xy_idx(x, 2);
+            map.revealed_tiles[idx] = true;
+        }
+        world.insert(map);
+        let player = world.create_entity().with(Position { x: 12, y: 2 }).build();
+
+        let mut travel = TravelCursor::begin(&world, player);
+        assert_eq!(travel.points, vec![(15, 2), (3, 2)]);
+
+        travel.cycle_point(false);
+        assert_eq!(travel.cursor, (3, 2));
+        travel.jump_to(&world.fetch::<Map>(), TileType::DownStairs);
+        assert_eq!(travel.cursor, (15, 2));
+    }
+}
@@ -118,11 +118,13 @@ pub fn async_event_processing_system(
             },
             AsyncEventType::ExpeditionCompleted => {
                 if let Some(expedition_id) = &event.expedition_id {
+                    crate::presence::post(crate::presence::Notification::mission_completed(&expedition_name(&async_manager, expedition_id)));
                     process_expedition_completion(&mut async_manager, &mut guild_manager, &mut agent_query, expedition_id);
                 }
             },
             AsyncEventType::ExpeditionFailed => {
                 if let Some(expedition_id) = &event.expedition_id {
+                    crate::presence::post(crate::presence::Notification::mission_failed(&expedition_name(&async_manager, expedition_id)));
                     process_expedition_failure(&mut async_manager, &mut agent_query, expedition_id);
                 }
             },
@@ -142,6 +144,13 @@ pub fn async_event_processing_system(
     }
 }
 
+/// The mission an expedition went on, for notifications
+fn expedition_name(async_manager: &AsyncExplorationManager, expedition_id: &str) -> String {
+    async_manager.get_expedition(expedition_id)
+        .or_else(|| async_manager.completed_expeditions.iter().find(|exp| exp.id == expedition_id))
+        .map_or_else(|| expedition_id.to_string(), |exp| exp.mission_id.clone())
+}
+
 /// Process expedition completion
 fn process_expedition_completion(
     async_manager: &mut AsyncExplorationManager,
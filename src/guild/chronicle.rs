@@ -0,0 +1,166 @@
+use serde::{Serialize, Deserialize};
+use crate::language_model::{LlamaError, LlmRequest, Prompt, PromptKind, PromptTemplate};
+use crate::localization::{self, Message};
+use crate::resources::RandomNumberGenerator;
+use super::roster::{AgentTrait, ContractReport};
+
+/// Most reports the ledger keeps; the oldest go first
+pub const LEDGER_LIMIT: usize = 50;
+/// Template variants of each kind in the locale files
+const TEMPLATE_VARIANTS: i32 = 3;
+
+/// Every contract's report, newest last. Kept with the guild's books
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuildLedger {
+    pub reports: Vec<ContractReport>,
+    /// The number the last report was filed under
+    #[serde(default)]
+    pub last_id: u64,
+}
+
+impl GuildLedger {
+    /// File a report under the next number, dropping the oldest once the ledger is full. Returns
+    /// the number
+    pub fn record(&mut self, mut report: ContractReport) -> u64 {
+        self.last_id += 1;
+        report.id = self.last_id;
+        self.reports.push(report);
+        if self.reports.len() > LEDGER_LIMIT {
+            let excess = self.reports.len() - LEDGER_LIMIT;
+            self.reports.drain(..excess);
+        }
+        self.last_id
+    }
+
+    /// The report filed under `id`, if the ledger still has it
+    pub fn find_mut(&mut self, id: u64) -> Option<&mut ContractReport> {
+        self.reports.iter_mut().find(|report| report.id == id)
+    }
+
+    /// The reports, newest first
+    pub fn newest_first(&self) -> Vec<&ContractReport> {
+        self.reports.iter().rev().collect()
+    }
+}
+
+/// What the model is asked for to tell a contract's story, from the mission report prompt. Each
+/// report is filed with its story told from the templates; when the options pick a provider, this
+/// goes to the model queue and its answer takes the template's place
+pub fn narration_request(report: &ContractReport) -> Result<LlmRequest, LlamaError> {
+    let traits: Vec<&str> = report.traits.iter().map(|agent_trait| agent_trait.name()).collect();
+    let mut outcome = match (report.died, report.success) {
+        (true, _) => "the agent was killed and never came back".to_string(),
+        (false, true) => format!("success, bringing back {} gold", report.gold),
+        (false, false) => "failure, nothing brought back".to_string(),
+    };
+    if report.injury_days > 0 {
+        outcome.push_str(&format!("; the agent came back wounded and needs {} days in the infirmary", report.injury_days));
+    }
+    Prompt::new(PromptKind::MissionReport)
+        .arg("agent", &report.agent)
+        .arg("class", report.class.name())
+        .arg("traits", if traits.is_empty() { "none".to_string() } else { traits.join(", ") })
+        .arg("contract", report.contract.name())
+        .arg("outcome", outcome)
+        .arg("language", localization::language_name(&localization::language()))
+        .request()
+}
+
+/// The model's answer made fit for the ledger, or nothing if there is nothing left of it
+pub fn finish_narrative(text: &str) -> Option<String> {
+    PromptTemplate::latest(PromptKind::MissionReport).finish(text)
+}
+
+/// The report told from the locale's templates: how it went, then a word on the agent's nature
+/// and any wound
+pub fn template_narrative(report: &ContractReport, rng: &mut RandomNumberGenerator) -> String {
+    let variant = rng.range(1, TEMPLATE_VARIANTS);
+    let key = match (report.died, report.success) {
+        (true, _) => "narrative.died".to_string(),
+        (false, true) => format!("narrative.success_{}", variant),
+        (false, false) => format!("narrative.failure_{}", variant),
+    };
+    let line = |key: &str| Message::new(key)
+        .arg("agent", &report.agent)
+        .arg("class", report.class.name())
+        .arg("contract", report.contract.name())
+        .arg("days", report.injury_days)
+        .text();
+    let mut sentences = vec![line(&key)];
+    if !report.died {
+        let flavor = report.traits.iter().find_map(|agent_trait| match agent_trait {
+            AgentTrait::Brave if report.success => Some("narrative.brave"),
+            AgentTrait::Greedy if report.success => Some("narrative.greedy"),
+            AgentTrait::Clumsy if !report.success => Some("narrative.clumsy"),
+            _ => None,
+        });
+        if let Some(flavor) = flavor {
+            sentences.push(line(flavor));
+        }
+        if report.injury_days > 0 {
+            sentences.push(line("narrative.injured"));
+        }
+    }
+    sentences.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::ClassType;
+    use crate::guild::Contract;
+
+    fn report(success: bool, injury_days: u32, died: bool) -> ContractReport {
+        ContractReport {
+            id: 0,
+            day: 4,
+            agent: "Edda".to_string(),
+            class: ClassType::Ranger,
+            traits: vec![AgentTrait::Brave],
+            contract: Contract::Bounty,
+            success,
+            gold: if success { 100 } else { 0 },
+            experience: 60,
+            levels: 0,
+            injury_days,
+            died,
+            reagents: 0,
+            renown: 0,
+            milestones: Vec::new(),
+            narrative: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_the_templates_tell_the_story() {
+        let mut rng = RandomNumberGenerator::new(2);
+        let told = template_narrative(&report(true, 3, false), &mut rng);
+        assert!(told.contains("Edda"));
+        assert!(!told.contains('{'));
+        assert!(template_narrative(&report(false, 0, true), &mut rng).contains("Bounty hunt"));
+    }
+
+    #[test]
+    fn test_the_prompt_gives_the_facts_and_long_answers_are_cut_at_a_sentence() {
+        let request = narration_request(&report(false, 5, false)).unwrap();
+        assert!(request.prompt.contains("Ranger") && request.prompt.contains("Brave") && request.prompt.contains("5 days"));
+        assert_eq!(request.system.as_deref(), Some(PromptTemplate::latest(PromptKind::MissionReport).system));
+        assert_eq!(finish_narrative("  \n "), None);
+
+        let cut = finish_narrative(&"The road was long. ".repeat(30)).unwrap();
+        assert!(cut.chars().count() <= PromptTemplate::latest(PromptKind::MissionReport).max_chars);
+        assert!(cut.ends_with('.'));
+    }
+
+    #[test]
+    fn test_the_ledger_keeps_only_the_newest_reports() {
+        let mut ledger = GuildLedger::default();
+        for day in 0..(LEDGER_LIMIT as u64 + 5) {
+            ledger.record(ContractReport { day, ..report(true, 0, false) });
+        }
+        assert_eq!(ledger.reports.len(), LEDGER_LIMIT);
+        assert_eq!(ledger.newest_first()[0].day, LEDGER_LIMIT as u64 + 4);
+        assert_eq!(ledger.reports[0].day, 5);
+        assert_eq!(ledger.find_mut(LEDGER_LIMIT as u64 + 5).map(|report| report.day), Some(LEDGER_LIMIT as u64 + 4));
+    }
+}
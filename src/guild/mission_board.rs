@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use rand::{Rng, thread_rng};
 use crate::guild::mission_types::*;
 use crate::guild::mission::Mission;
+use crate::systems::Standing;
 
 /// Mission board resource
 #[derive(Resource, Default)]
@@ -47,6 +48,14 @@ impl MissionBoard {
             .collect()
     }
     
+    /// Get the available missions the guild will hand to someone of the given standing
+    pub fn get_missions_open_to(&self, standing: Standing) -> Vec<&Mission> {
+        self.get_available_missions()
+            .into_iter()
+            .filter(|m| m.difficulty.open_to(standing))
+            .collect()
+    }
+    
     /// Get missions by status
     pub fn get_missions_by_status(&self, status: MissionStatus) -> Vec<&Mission> {
         self.missions.values()
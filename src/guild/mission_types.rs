@@ -4,6 +4,7 @@ use std::collections::{HashMap, HashSet};
 use rand::{Rng, thread_rng};
 use crate::guild::guild_core::GuildResource;
 use crate::items::Item;
+use crate::systems::Standing;
 
 /// Mission difficulty levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -53,6 +54,18 @@ impl MissionDifficulty {
         }
     }
     
+    /// Whether the guild trusts someone it sees this way with missions this hard
+    pub fn open_to(&self, standing: Standing) -> bool {
+        let hardest = match standing {
+            Standing::Hated => return false,
+            Standing::Disliked => MissionDifficulty::Easy,
+            Standing::Neutral => MissionDifficulty::Hard,
+            Standing::Liked => MissionDifficulty::VeryHard,
+            Standing::Honored => MissionDifficulty::Extreme,
+        };
+        self.recommended_level() <= hardest.recommended_level()
+    }
+    
     /// Get all difficulty levels
     pub fn all() -> Vec<MissionDifficulty> {
         vec![
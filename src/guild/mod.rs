@@ -28,6 +28,9 @@ pub mod sync_exploration_ui;
 pub mod asynchronous_exploration;
 pub mod async_exploration_systems;
 pub mod async_exploration_ui;
+pub mod roster;
+pub mod chronicle;
+pub mod stores;
 
 
 pub use guild_core::*;
@@ -60,3 +63,6 @@ pub use sync_exploration_ui::*;
 pub use asynchronous_exploration::*;
 pub use async_exploration_systems::*;
 pub use async_exploration_ui::*;
+pub use roster::*;
+pub use chronicle::*;
+pub use stores::*;
@@ -0,0 +1,579 @@
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::Path;
+use crate::components::{ClassType, GameMode};
+use crate::resources::RandomNumberGenerator;
+use crate::utils::{NameGenerator, NameStyle};
+use super::chronicle::GuildLedger;
+use super::stores::{GuildStores, HallFacility, RenownMilestone, Shortfall};
+
+/// Default location of the guild's books, kept from one character to the next
+pub const GUILD_PATH: &str = "saves/guild.json";
+
+/// Candidates waiting at the guild hall to be taken on
+pub const RECRUIT_POOL: usize = 3;
+/// Most agents the guild keeps on its books at once
+pub const MAX_AGENTS: usize = 6;
+/// Gold an agent asks to sign on, for every level they have
+const HIRE_COST_PER_LEVEL: i32 = 60;
+/// Experience an agent needs for the next level, for every level they have
+const EXPERIENCE_PER_LEVEL: i32 = 100;
+/// Days in the infirmary for a wound that would have killed an agent, where it doesn't
+pub const MORTAL_WOUND_DAYS: u32 = 7;
+
+/// A quirk an agent brings to every mission
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AgentTrait {
+    /// Pushes on where others would turn back: better odds, and more wounds
+    Brave,
+    /// Asks more to sign on and pockets a share of whatever a mission brings back
+    Greedy,
+    /// Trips, drops and breaks things: worse odds, and more wounds
+    Clumsy,
+}
+
+impl AgentTrait {
+    pub fn all() -> [AgentTrait; 3] {
+        [AgentTrait::Brave, AgentTrait::Greedy, AgentTrait::Clumsy]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AgentTrait::Brave => "Brave",
+            AgentTrait::Greedy => "Greedy",
+            AgentTrait::Clumsy => "Clumsy",
+        }
+    }
+
+    /// Percent added to the odds of a mission going well
+    pub fn success_bonus(&self) -> i32 {
+        match self {
+            AgentTrait::Brave => 10,
+            AgentTrait::Greedy => 0,
+            AgentTrait::Clumsy => -10,
+        }
+    }
+
+    /// Percent added to the odds of coming back hurt
+    pub fn injury_bonus(&self) -> i32 {
+        match self {
+            AgentTrait::Brave => 10,
+            AgentTrait::Greedy => 0,
+            AgentTrait::Clumsy => 15,
+        }
+    }
+
+    /// Percent of a mission's gold the agent keeps for themselves, and adds to what they ask to
+    /// sign on
+    pub fn cut(&self) -> i32 {
+        match self {
+            AgentTrait::Greedy => 25,
+            _ => 0,
+        }
+    }
+}
+
+/// Work the guild sends its agents out on, longer and deadlier the better it pays
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Contract {
+    /// A day walking the roads around Ravenhollow
+    Patrol,
+    /// Running down a wanted outlaw or beast
+    Bounty,
+    /// Days in the old tunnels past the ones the character knows
+    Delve,
+    /// A week out past the edge of every map in Ravenhollow
+    Expedition,
+}
+
+impl Contract {
+    pub fn all() -> [Contract; 4] {
+        [Contract::Patrol, Contract::Bounty, Contract::Delve, Contract::Expedition]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Contract::Patrol => "Road patrol",
+            Contract::Bounty => "Bounty hunt",
+            Contract::Delve => "Deep delve",
+            Contract::Expedition => "Far expedition",
+        }
+    }
+
+    /// Days an agent is away on it
+    pub fn days(&self) -> u32 {
+        match self {
+            Contract::Patrol => 1,
+            Contract::Bounty => 2,
+            Contract::Delve => 4,
+            Contract::Expedition => 6,
+        }
+    }
+
+    /// Percent taken off the odds of success, and the odds of coming back hurt
+    pub fn danger(&self) -> i32 {
+        match self {
+            Contract::Patrol => 10,
+            Contract::Bounty => 25,
+            Contract::Delve => 45,
+            Contract::Expedition => 60,
+        }
+    }
+
+    /// Gold it brings back when it goes well
+    pub fn reward(&self) -> i32 {
+        match self {
+            Contract::Patrol => 40,
+            Contract::Bounty => 100,
+            Contract::Delve => 250,
+            Contract::Expedition => 600,
+        }
+    }
+
+    /// Experience it is worth when it goes well; half of that when it doesn't
+    pub fn experience(&self) -> i32 {
+        match self {
+            Contract::Patrol => 30,
+            Contract::Bounty => 60,
+            Contract::Delve => 120,
+            Contract::Expedition => 220,
+        }
+    }
+
+    /// Supplies an agent takes out on it
+    pub fn supplies(&self) -> u32 {
+        match self {
+            Contract::Patrol => 1,
+            Contract::Bounty => 2,
+            Contract::Delve => 4,
+            Contract::Expedition => 8,
+        }
+    }
+
+    /// Reagents it brings back when it goes well
+    pub fn reagents(&self) -> u32 {
+        match self {
+            Contract::Patrol => 0,
+            Contract::Bounty => 1,
+            Contract::Delve => 3,
+            Contract::Expedition => 5,
+        }
+    }
+
+    /// Renown it earns the guild when it goes well
+    pub fn renown(&self) -> u32 {
+        match self {
+            Contract::Patrol => 1,
+            Contract::Bounty => 2,
+            Contract::Delve => 4,
+            Contract::Expedition => 8,
+        }
+    }
+
+    /// Renown the guild needs before anyone will offer it
+    pub fn renown_required(&self) -> u32 {
+        match self {
+            Contract::Patrol | Contract::Bounty => 0,
+            Contract::Delve => 10,
+            Contract::Expedition => 30,
+        }
+    }
+}
+
+/// A contract an agent is out on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+    pub contract: Contract,
+    pub days_left: u32,
+}
+
+/// Someone who works for the guild, or would like to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Agent {
+    pub name: String,
+    pub class: ClassType,
+    pub traits: Vec<AgentTrait>,
+    pub level: i32,
+    /// Experience toward the next level
+    pub experience: i32,
+    pub max_hp: i32,
+    pub power: i32,
+    pub defense: i32,
+    /// Days left in the infirmary before the agent is fit to go out again
+    #[serde(default)]
+    pub recovery_days: u32,
+    /// The contract they are out on, if any
+    #[serde(default)]
+    pub assignment: Option<Assignment>,
+}
+
+impl Agent {
+    /// A candidate of a random class, with up to two traits and stats to match
+    pub fn generate(rng: &mut RandomNumberGenerator) -> Self {
+        let classes = ClassType::all();
+        let class = classes[rng.range(0, classes.len() as i32 - 1) as usize];
+        let traits = AgentTrait::all().into_iter().filter(|_| rng.range(1, 3) == 1).take(2).collect();
+        let mut agent = Agent {
+            name: NameGenerator::personal_name(NameStyle::Human, rng),
+            class,
+            traits,
+            level: 1,
+            experience: 0,
+            max_hp: class.starting_hp() + rng.range(0, 4),
+            power: rng.range(2, 5),
+            defense: rng.range(0, 2),
+            recovery_days: 0,
+            assignment: None,
+        };
+        if rng.range(1, 3) == 1 {
+            agent.level_up();
+        }
+        agent
+    }
+
+    pub fn has_trait(&self, agent_trait: AgentTrait) -> bool {
+        self.traits.contains(&agent_trait)
+    }
+
+    /// Gold they ask to sign on
+    pub fn hire_cost(&self) -> i32 {
+        let cut: i32 = self.traits.iter().map(|agent_trait| agent_trait.cut()).sum();
+        HIRE_COST_PER_LEVEL * self.level * (100 + cut) / 100
+    }
+
+    /// Neither out on a contract nor laid up in the infirmary
+    pub fn is_ready(&self) -> bool {
+        self.assignment.is_none() && self.recovery_days == 0
+    }
+
+    pub fn experience_to_level(&self) -> i32 {
+        EXPERIENCE_PER_LEVEL * self.level
+    }
+
+    fn level_up(&mut self) {
+        self.level += 1;
+        self.max_hp += (self.class.hp_per_level() / 2).max(1);
+        self.power += 1;
+        if self.level % 2 == 0 {
+            self.defense += 1;
+        }
+    }
+
+    /// Add experience, returning how many levels it brought
+    pub fn gain_experience(&mut self, amount: i32) -> i32 {
+        self.experience += amount.max(0);
+        let mut levels = 0;
+        while self.experience >= self.experience_to_level() {
+            self.experience -= self.experience_to_level();
+            self.level_up();
+            levels += 1;
+        }
+        levels
+    }
+
+    /// Percent chance of `contract` going well in their hands
+    pub fn success_chance(&self, contract: Contract) -> i32 {
+        let traits: i32 = self.traits.iter().map(|agent_trait| agent_trait.success_bonus()).sum();
+        (40 + self.level * 8 + self.power + traits - contract.danger()).clamp(5, 95)
+    }
+
+    /// Percent chance of coming back from `contract` hurt, worse after it has gone badly
+    pub fn injury_chance(&self, contract: Contract, success: bool) -> i32 {
+        let traits: i32 = self.traits.iter().map(|agent_trait| agent_trait.injury_bonus()).sum();
+        let setback = if success { 0 } else { 20 };
+        (contract.danger() + traits + setback - self.defense * 3).clamp(0, 90)
+    }
+}
+
+/// How a contract turned out for the agent who took it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractReport {
+    /// The number it is filed under in the ledger
+    #[serde(default)]
+    pub id: u64,
+    /// The day the agent came back, or would have
+    pub day: u64,
+    pub agent: String,
+    pub class: ClassType,
+    pub traits: Vec<AgentTrait>,
+    pub contract: Contract,
+    /// Whether the job got done and paid; never for an agent who didn't come back
+    pub success: bool,
+    /// Gold paid into the bank, after the agent's cut
+    pub gold: i32,
+    pub experience: i32,
+    pub levels: i32,
+    /// Days the agent is laid up in the infirmary, if they came back hurt
+    pub injury_days: u32,
+    /// Whether they came back at all
+    pub died: bool,
+    /// Reagents brought back for the guild's alchemist
+    #[serde(default)]
+    pub reagents: u32,
+    /// Renown earned for the guild, milestones aside
+    #[serde(default)]
+    pub renown: u32,
+    /// Milestones the guild made with it
+    #[serde(default)]
+    pub milestones: Vec<RenownMilestone>,
+    /// The story of it, as told in the ledger
+    pub narrative: String,
+}
+
+/// Everyone on the guild's books, and everyone who would like to be. Kept apart from saved games,
+/// like the bank, so agents outlive the character who hired them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuildRoster {
+    pub agents: Vec<Agent>,
+    /// Candidates waiting to be hired, replaced each day
+    #[serde(default)]
+    pub recruits: Vec<Agent>,
+    /// Agents lost on contracts for good
+    #[serde(default)]
+    pub fallen: Vec<Agent>,
+    /// How every contract of late turned out
+    #[serde(default)]
+    pub ledger: GuildLedger,
+    /// Supplies, reagents, renown and the rooms of the hall
+    #[serde(default)]
+    pub stores: GuildStores,
+}
+
+impl GuildRoster {
+    /// Load the guild's books, starting with no agents if the file is missing
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(GuildRoster::default());
+        }
+
+        let file_content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    /// Replace the candidates waiting at the guild hall with new ones
+    pub fn refresh_recruits(&mut self, rng: &mut RandomNumberGenerator) {
+        self.recruits = (0..RECRUIT_POOL).map(|_| Agent::generate(rng)).collect();
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.agents.len() >= MAX_AGENTS
+    }
+
+    /// Take on the candidate at `index` out of `purse`, returning what they cost; `None` if the
+    /// purse doesn't cover them or the guild has no room
+    pub fn hire(&mut self, index: usize, purse: &mut i32) -> Option<i32> {
+        let cost = self.recruits.get(index)?.hire_cost();
+        if self.is_full() || *purse < cost {
+            return None;
+        }
+        *purse -= cost;
+        let agent = self.recruits.remove(index);
+        self.agents.push(agent);
+        Some(cost)
+    }
+
+    /// Send the agent at `index` out on `contract` with the supplies for it, if they are fit to
+    /// go and the guild is known well enough to be offered it
+    pub fn assign(&mut self, index: usize, contract: Contract) -> Result<(), Shortfall> {
+        let agent = match self.agents.get_mut(index) {
+            Some(agent) if agent.is_ready() => agent,
+            _ => return Err(Shortfall::Busy),
+        };
+        self.stores.outfit(contract)?;
+        agent.assignment = Some(Assignment { contract, days_left: contract.days() });
+        Ok(())
+    }
+
+    /// Let `days` pass: the infirmary mends its patients, and every agent whose contract is up
+    /// comes back, or doesn't, returning how each of those went. Under `mode`, a wound that would
+    /// kill an agent either does or sends them to the infirmary for a long stay. What they bring
+    /// back goes into the guild's stores
+    pub fn pass_days(&mut self, days: u32, mode: &GameMode, rng: &mut RandomNumberGenerator) -> Vec<ContractReport> {
+        let mut reports = Vec::new();
+        let mending = if self.stores.has(HallFacility::Infirmary) { days * 2 } else { days };
+        for mut agent in std::mem::take(&mut self.agents) {
+            agent.recovery_days = agent.recovery_days.saturating_sub(mending);
+            let due = match agent.assignment.as_mut() {
+                Some(assignment) => {
+                    assignment.days_left = assignment.days_left.saturating_sub(days);
+                    (assignment.days_left == 0).then_some(assignment.contract)
+                },
+                None => None,
+            };
+            let contract = match due {
+                Some(contract) => contract,
+                None => {
+                    self.agents.push(agent);
+                    continue;
+                },
+            };
+            agent.assignment = None;
+            let mut report = resolve(&mut agent, contract, mode, rng);
+            report.milestones = self.stores.record(&report);
+            if report.died {
+                self.fallen.push(agent);
+            } else {
+                self.agents.push(agent);
+            }
+            reports.push(report);
+        }
+        reports
+    }
+}
+
+/// Roll how `contract` went for `agent`, and leave them the better or worse for it
+fn resolve(agent: &mut Agent, contract: Contract, mode: &GameMode, rng: &mut RandomNumberGenerator) -> ContractReport {
+    let success = rng.range(1, 100) <= agent.success_chance(contract);
+    let (gold, experience) = if success {
+        let cut: i32 = agent.traits.iter().map(|agent_trait| agent_trait.cut()).sum();
+        (contract.reward() * (100 - cut) / 100, contract.experience())
+    } else {
+        (0, contract.experience() / 2)
+    };
+
+    let mut injury_days = 0;
+    let mut died = false;
+    if rng.range(1, 100) <= agent.injury_chance(contract, success) {
+        // The worse the danger, the likelier a wound is to be one nobody walks away from
+        if rng.range(1, 100) <= contract.danger() / 3 {
+            if mode.agent_permadeath() {
+                died = true;
+            } else {
+                injury_days = MORTAL_WOUND_DAYS;
+            }
+        } else {
+            injury_days = rng.range(1, (contract.danger() / 10).max(1)) as u32;
+        }
+    }
+
+    let levels = if died { 0 } else { agent.gain_experience(experience) };
+    agent.recovery_days = agent.recovery_days.max(injury_days);
+    ContractReport {
+        id: 0,
+        day: 0,
+        agent: agent.name.clone(),
+        class: agent.class,
+        traits: agent.traits.clone(),
+        contract,
+        success: success && !died,
+        gold: if died { 0 } else { gold },
+        experience,
+        levels,
+        injury_days,
+        died,
+        reagents: if success && !died { contract.reagents() } else { 0 },
+        renown: if success && !died { contract.renown() } else { 0 },
+        milestones: Vec::new(),
+        narrative: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(traits: Vec<AgentTrait>) -> Agent {
+        Agent {
+            name: "Edda".to_string(),
+            class: ClassType::Fighter,
+            traits,
+            level: 1,
+            experience: 0,
+            max_hp: 12,
+            power: 4,
+            defense: 1,
+            recovery_days: 0,
+            assignment: None,
+        }
+    }
+
+    #[test]
+    fn test_traits_change_the_odds_and_the_price() {
+        let plain = agent(Vec::new());
+        let brave = agent(vec![AgentTrait::Brave]);
+        let clumsy = agent(vec![AgentTrait::Clumsy]);
+        assert!(brave.success_chance(Contract::Bounty) > plain.success_chance(Contract::Bounty));
+        assert!(clumsy.success_chance(Contract::Bounty) < plain.success_chance(Contract::Bounty));
+        assert!(clumsy.injury_chance(Contract::Bounty, true) > plain.injury_chance(Contract::Bounty, true));
+        assert!(plain.injury_chance(Contract::Bounty, false) > plain.injury_chance(Contract::Bounty, true));
+        assert_eq!(agent(vec![AgentTrait::Greedy]).hire_cost(), plain.hire_cost() * 125 / 100);
+    }
+
+    #[test]
+    fn test_experience_carries_over_into_levels() {
+        let mut edda = agent(Vec::new());
+        assert_eq!(edda.gain_experience(EXPERIENCE_PER_LEVEL + EXPERIENCE_PER_LEVEL * 2 + 10), 2);
+        assert_eq!((edda.level, edda.experience), (3, 10));
+        assert_eq!((edda.power, edda.defense), (6, 2));
+    }
+
+    #[test]
+    fn test_hiring_and_sending_out_an_agent() {
+        let mut roster = GuildRoster::default();
+        roster.recruits.push(agent(Vec::new()));
+        let mut purse = 50;
+        assert_eq!(roster.hire(0, &mut purse), None);
+        purse = 100;
+        assert_eq!(roster.hire(0, &mut purse), Some(HIRE_COST_PER_LEVEL));
+        assert_eq!((purse, roster.agents.len(), roster.recruits.len()), (40, 1, 0));
+
+        assert_eq!(roster.assign(0, Contract::Delve), Err(Shortfall::Renown(Contract::Delve.renown_required())));
+        assert_eq!(roster.assign(0, Contract::Bounty), Ok(()));
+        assert_eq!(roster.assign(0, Contract::Patrol), Err(Shortfall::Busy));
+        assert_eq!(roster.stores.supplies, crate::guild::STARTING_SUPPLIES - Contract::Bounty.supplies());
+        let mut rng = RandomNumberGenerator::new(7);
+        assert!(roster.pass_days(1, &GameMode::Normal, &mut rng).is_empty());
+        let reports = roster.pass_days(1, &GameMode::Normal, &mut rng);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].experience > 0);
+        assert!(roster.agents[0].assignment.is_none());
+    }
+
+    #[test]
+    fn test_agents_only_die_for_good_in_the_unforgiving_modes() {
+        let mut rng = RandomNumberGenerator::new(11);
+        let mut doomed = agent(vec![AgentTrait::Brave, AgentTrait::Clumsy]);
+        doomed.defense = 0;
+        let reports: Vec<ContractReport> = (0..200)
+            .map(|_| resolve(&mut doomed.clone(), Contract::Delve, &GameMode::Normal, &mut rng))
+            .collect();
+        assert!(reports.iter().all(|report| !report.died));
+        assert!(reports.iter().any(|report| report.injury_days == MORTAL_WOUND_DAYS));
+
+        let mut roster = GuildRoster::default();
+        for _ in 0..50 {
+            let mut agent = doomed.clone();
+            agent.assignment = Some(Assignment { contract: Contract::Delve, days_left: 1 });
+            roster.agents.push(agent);
+        }
+        let reports = roster.pass_days(1, &GameMode::Hardcore, &mut rng);
+        let deaths = reports.iter().filter(|report| report.died).count();
+        assert!(deaths > 0);
+        assert_eq!(roster.fallen.len(), deaths);
+        assert_eq!(roster.agents.len(), 50 - deaths);
+    }
+
+    #[test]
+    fn test_the_roster_survives_a_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("guild.json");
+        let path = path.to_str().unwrap();
+
+        let mut roster = GuildRoster::default();
+        roster.agents.push(agent(vec![AgentTrait::Greedy]));
+        roster.save(path).unwrap();
+
+        let loaded = GuildRoster::load(path).unwrap();
+        assert_eq!(loaded.agents, roster.agents);
+    }
+}
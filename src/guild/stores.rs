@@ -0,0 +1,395 @@
+use serde::{Serialize, Deserialize};
+use crate::localization::Message;
+use super::roster::{Contract, ContractReport};
+
+/// Supplies the guild has on hand before it has bought any
+pub const STARTING_SUPPLIES: u32 = 6;
+/// Gold for a crate of supplies
+pub const SUPPLY_CRATE_COST: i32 = 40;
+/// Supplies in a crate
+pub const SUPPLY_CRATE_SIZE: u32 = 5;
+
+/// Rooms the guild can add to its hall once it is known well enough
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum HallFacility {
+    /// Needed for anything past the simplest brews
+    Laboratory,
+    /// Wounded agents mend twice as fast
+    Infirmary,
+    /// Every contract takes a supply less
+    Storehouse,
+}
+
+impl HallFacility {
+    pub fn all() -> [HallFacility; 3] {
+        [HallFacility::Laboratory, HallFacility::Infirmary, HallFacility::Storehouse]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HallFacility::Laboratory => "Alchemy laboratory",
+            HallFacility::Infirmary => "Infirmary ward",
+            HallFacility::Storehouse => "Storehouse",
+        }
+    }
+
+    /// Locale key for what it does
+    pub fn description_key(&self) -> &'static str {
+        match self {
+            HallFacility::Laboratory => "guild.facility_laboratory",
+            HallFacility::Infirmary => "guild.facility_infirmary",
+            HallFacility::Storehouse => "guild.facility_storehouse",
+        }
+    }
+
+    /// Renown the guild needs before anyone will build it
+    pub fn renown_required(&self) -> u32 {
+        match self {
+            HallFacility::Laboratory => 5,
+            HallFacility::Infirmary => 10,
+            HallFacility::Storehouse => 20,
+        }
+    }
+
+    /// Gold it costs to build
+    pub fn cost(&self) -> i32 {
+        match self {
+            HallFacility::Laboratory => 200,
+            HallFacility::Infirmary => 300,
+            HallFacility::Storehouse => 400,
+        }
+    }
+}
+
+/// What the guild's alchemist will make for the character out of the reagents agents bring back
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CraftingService {
+    HealingDraught,
+    GreaterHealing,
+    Regeneration,
+}
+
+impl CraftingService {
+    pub fn all() -> [CraftingService; 3] {
+        [CraftingService::HealingDraught, CraftingService::GreaterHealing, CraftingService::Regeneration]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CraftingService::HealingDraught => "Healing draught",
+            CraftingService::GreaterHealing => "Greater healing potion",
+            CraftingService::Regeneration => "Regeneration tonic",
+        }
+    }
+
+    /// Reagents it uses up
+    pub fn reagents(&self) -> u32 {
+        match self {
+            CraftingService::HealingDraught => 2,
+            CraftingService::GreaterHealing => 5,
+            CraftingService::Regeneration => 4,
+        }
+    }
+
+    /// The room the alchemist needs to make it, if any
+    pub fn facility(&self) -> Option<HallFacility> {
+        match self {
+            CraftingService::HealingDraught => None,
+            CraftingService::GreaterHealing | CraftingService::Regeneration => Some(HallFacility::Laboratory),
+        }
+    }
+}
+
+/// Deeds that make the guild's name, each worth renown once
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RenownMilestone {
+    FirstContract,
+    TenContracts,
+    FirstDelve,
+    FirstExpedition,
+    FiftyContracts,
+}
+
+impl RenownMilestone {
+    pub fn all() -> [RenownMilestone; 5] {
+        [
+            RenownMilestone::FirstContract,
+            RenownMilestone::TenContracts,
+            RenownMilestone::FirstDelve,
+            RenownMilestone::FirstExpedition,
+            RenownMilestone::FiftyContracts,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenownMilestone::FirstContract => "Open for business",
+            RenownMilestone::TenContracts => "Known on the roads",
+            RenownMilestone::FirstDelve => "Into the deep",
+            RenownMilestone::FirstExpedition => "Beyond the map",
+            RenownMilestone::FiftyContracts => "A name in every tavern",
+        }
+    }
+
+    /// Renown it is worth
+    pub fn renown(&self) -> u32 {
+        match self {
+            RenownMilestone::FirstContract => 2,
+            RenownMilestone::TenContracts => 5,
+            RenownMilestone::FirstDelve => 5,
+            RenownMilestone::FirstExpedition => 10,
+            RenownMilestone::FiftyContracts => 15,
+        }
+    }
+
+    /// Whether the guild's record, with `report` just in, earns it
+    fn reached(&self, stores: &GuildStores, report: &ContractReport) -> bool {
+        match self {
+            RenownMilestone::FirstContract => stores.contracts_done >= 1,
+            RenownMilestone::TenContracts => stores.contracts_done >= 10,
+            RenownMilestone::FiftyContracts => stores.contracts_done >= 50,
+            RenownMilestone::FirstDelve => report.success && report.contract == Contract::Delve,
+            RenownMilestone::FirstExpedition => report.success && report.contract == Contract::Expedition,
+        }
+    }
+}
+
+/// Why the guild can't do what was asked of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortfall {
+    /// The agent isn't there, or isn't fit to go out
+    Busy,
+    /// Renown the guild needs
+    Renown(u32),
+    /// Supplies the guild needs
+    Supplies(u32),
+    /// Gold the purse needs
+    Gold(i32),
+    /// Reagents the guild needs
+    Reagents(u32),
+    /// A room the hall needs first
+    Facility(HallFacility),
+    /// The hall already has it
+    Built,
+}
+
+impl Shortfall {
+    /// What the player is told
+    pub fn message(&self) -> Message {
+        match self {
+            Shortfall::Busy => Message::new("log.guild_agent_busy"),
+            Shortfall::Renown(renown) => Message::new("log.guild_needs_renown").arg("renown", renown),
+            Shortfall::Supplies(supplies) => Message::new("log.guild_needs_supplies").arg("supplies", supplies),
+            Shortfall::Gold(gold) => Message::new("log.guild_needs_gold").arg("gold", gold),
+            Shortfall::Reagents(reagents) => Message::new("log.guild_needs_reagents").arg("reagents", reagents),
+            Shortfall::Facility(facility) => Message::new("log.guild_needs_facility").arg("facility", facility.name()),
+            Shortfall::Built => Message::new("log.guild_already_built"),
+        }
+    }
+}
+
+/// What the guild has beyond gold: supplies its agents take out on contracts, reagents they bring
+/// back for the alchemist, and the renown that opens the harder contracts and the hall's rooms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildStores {
+    pub supplies: u32,
+    #[serde(default)]
+    pub reagents: u32,
+    #[serde(default)]
+    pub renown: u32,
+    /// Contracts that went well, for the milestones
+    #[serde(default)]
+    pub contracts_done: u32,
+    #[serde(default)]
+    pub milestones: Vec<RenownMilestone>,
+    #[serde(default)]
+    pub facilities: Vec<HallFacility>,
+}
+
+impl Default for GuildStores {
+    fn default() -> Self {
+        GuildStores {
+            supplies: STARTING_SUPPLIES,
+            reagents: 0,
+            renown: 0,
+            contracts_done: 0,
+            milestones: Vec::new(),
+            facilities: Vec::new(),
+        }
+    }
+}
+
+impl GuildStores {
+    pub fn has(&self, facility: HallFacility) -> bool {
+        self.facilities.contains(&facility)
+    }
+
+    /// Supplies an agent takes out on `contract`
+    pub fn supplies_for(&self, contract: Contract) -> u32 {
+        if self.has(HallFacility::Storehouse) {
+            contract.supplies().saturating_sub(1).max(1)
+        } else {
+            contract.supplies()
+        }
+    }
+
+    /// Whether the guild may take on `contract` and has the supplies for it
+    pub fn can_take(&self, contract: Contract) -> Result<(), Shortfall> {
+        if self.renown < contract.renown_required() {
+            return Err(Shortfall::Renown(contract.renown_required()));
+        }
+        let supplies = self.supplies_for(contract);
+        if self.supplies < supplies {
+            return Err(Shortfall::Supplies(supplies));
+        }
+        Ok(())
+    }
+
+    /// Pack the supplies for `contract`
+    pub fn outfit(&mut self, contract: Contract) -> Result<(), Shortfall> {
+        self.can_take(contract)?;
+        self.supplies -= self.supplies_for(contract);
+        Ok(())
+    }
+
+    /// Buy a crate of supplies out of `purse`, returning what it cost
+    pub fn buy_supplies(&mut self, purse: &mut i32) -> Result<i32, Shortfall> {
+        if *purse < SUPPLY_CRATE_COST {
+            return Err(Shortfall::Gold(SUPPLY_CRATE_COST));
+        }
+        *purse -= SUPPLY_CRATE_COST;
+        self.supplies += SUPPLY_CRATE_SIZE;
+        Ok(SUPPLY_CRATE_COST)
+    }
+
+    /// Add `facility` to the hall out of `purse`, returning what it cost
+    pub fn build(&mut self, facility: HallFacility, purse: &mut i32) -> Result<i32, Shortfall> {
+        if self.has(facility) {
+            return Err(Shortfall::Built);
+        }
+        if self.renown < facility.renown_required() {
+            return Err(Shortfall::Renown(facility.renown_required()));
+        }
+        if *purse < facility.cost() {
+            return Err(Shortfall::Gold(facility.cost()));
+        }
+        *purse -= facility.cost();
+        self.facilities.push(facility);
+        Ok(facility.cost())
+    }
+
+    /// Whether the alchemist has the room and the reagents for `service`
+    pub fn can_craft(&self, service: CraftingService) -> Result<(), Shortfall> {
+        if let Some(facility) = service.facility() {
+            if !self.has(facility) {
+                return Err(Shortfall::Facility(facility));
+            }
+        }
+        if self.reagents < service.reagents() {
+            return Err(Shortfall::Reagents(service.reagents()));
+        }
+        Ok(())
+    }
+
+    /// Use up the reagents for `service`; the item itself is the caller's to make
+    pub fn craft(&mut self, service: CraftingService) -> Result<(), Shortfall> {
+        self.can_craft(service)?;
+        self.reagents -= service.reagents();
+        Ok(())
+    }
+
+    /// Take in what a returning agent brought back, returning any milestones it made
+    pub fn record(&mut self, report: &ContractReport) -> Vec<RenownMilestone> {
+        self.reagents += report.reagents;
+        self.renown += report.renown;
+        if report.success {
+            self.contracts_done += 1;
+        }
+        let reached: Vec<RenownMilestone> = RenownMilestone::all().into_iter()
+            .filter(|milestone| !self.milestones.contains(milestone) && milestone.reached(self, report))
+            .collect();
+        for milestone in &reached {
+            self.renown += milestone.renown();
+            self.milestones.push(*milestone);
+        }
+        reached
+    }
+
+    /// The first milestone the guild has yet to make
+    pub fn next_milestone(&self) -> Option<RenownMilestone> {
+        RenownMilestone::all().into_iter().find(|milestone| !self.milestones.contains(milestone))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::ClassType;
+
+    fn report(contract: Contract, success: bool) -> ContractReport {
+        ContractReport {
+            id: 0,
+            day: 1,
+            agent: "Edda".to_string(),
+            class: ClassType::Fighter,
+            traits: Vec::new(),
+            contract,
+            success,
+            gold: 0,
+            experience: 0,
+            levels: 0,
+            injury_days: 0,
+            died: false,
+            reagents: if success { contract.reagents() } else { 0 },
+            renown: if success { contract.renown() } else { 0 },
+            milestones: Vec::new(),
+            narrative: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_renown_opens_contracts_and_supplies_run_out() {
+        let mut stores = GuildStores::default();
+        assert_eq!(stores.can_take(Contract::Delve), Err(Shortfall::Renown(Contract::Delve.renown_required())));
+        assert!(stores.outfit(Contract::Bounty).is_ok());
+        assert!(stores.outfit(Contract::Bounty).is_ok());
+        assert!(stores.outfit(Contract::Bounty).is_ok());
+        assert_eq!(stores.outfit(Contract::Patrol), Err(Shortfall::Supplies(1)));
+
+        let mut purse = SUPPLY_CRATE_COST;
+        assert_eq!(stores.buy_supplies(&mut purse), Ok(SUPPLY_CRATE_COST));
+        assert_eq!((purse, stores.supplies), (0, SUPPLY_CRATE_SIZE));
+        assert_eq!(stores.buy_supplies(&mut purse), Err(Shortfall::Gold(SUPPLY_CRATE_COST)));
+    }
+
+    #[test]
+    fn test_milestones_are_worth_renown_once() {
+        let mut stores = GuildStores::default();
+        assert!(stores.record(&report(Contract::Patrol, false)).is_empty());
+        assert_eq!(stores.record(&report(Contract::Bounty, true)), vec![RenownMilestone::FirstContract]);
+        assert_eq!(stores.renown, Contract::Bounty.renown() + RenownMilestone::FirstContract.renown());
+        assert!(stores.record(&report(Contract::Bounty, true)).is_empty());
+        assert_eq!(stores.reagents, Contract::Bounty.reagents() * 2);
+        assert_eq!(stores.next_milestone(), Some(RenownMilestone::TenContracts));
+    }
+
+    #[test]
+    fn test_the_hall_and_the_alchemist_want_renown_rooms_and_reagents() {
+        let mut stores = GuildStores::default();
+        let mut purse = 1000;
+        assert_eq!(stores.craft(CraftingService::GreaterHealing), Err(Shortfall::Facility(HallFacility::Laboratory)));
+        assert_eq!(stores.craft(CraftingService::HealingDraught), Err(Shortfall::Reagents(2)));
+        assert_eq!(stores.build(HallFacility::Laboratory, &mut purse), Err(Shortfall::Renown(5)));
+
+        stores.renown = 20;
+        stores.reagents = 5;
+        assert_eq!(stores.build(HallFacility::Laboratory, &mut purse), Ok(200));
+        assert_eq!(stores.build(HallFacility::Laboratory, &mut purse), Err(Shortfall::Built));
+        assert_eq!(stores.craft(CraftingService::GreaterHealing), Ok(()));
+        assert_eq!(stores.reagents, 0);
+
+        assert_eq!(stores.build(HallFacility::Storehouse, &mut purse), Ok(400));
+        assert_eq!(stores.supplies_for(Contract::Delve), Contract::Delve.supplies() - 1);
+        assert_eq!(stores.supplies_for(Contract::Patrol), 1);
+    }
+}
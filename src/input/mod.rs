@@ -20,6 +20,23 @@ pub enum PlayerAction {
     NoAction,
 }
 
+impl PlayerAction {
+    /// The step a movement action takes, if it is one
+    pub fn direction(&self) -> Option<(i32, i32)> {
+        match self {
+            PlayerAction::MoveLeft => Some((-1, 0)),
+            PlayerAction::MoveRight => Some((1, 0)),
+            PlayerAction::MoveUp => Some((0, -1)),
+            PlayerAction::MoveDown => Some((0, 1)),
+            PlayerAction::MoveUpLeft => Some((-1, -1)),
+            PlayerAction::MoveUpRight => Some((1, -1)),
+            PlayerAction::MoveDownLeft => Some((-1, 1)),
+            PlayerAction::MoveDownRight => Some((1, 1)),
+            _ => None,
+        }
+    }
+}
+
 pub fn handle_player_input(key_event: KeyEvent) -> PlayerAction {
     match key_event.code {
         // Movement keys
@@ -37,7 +54,7 @@ pub fn handle_player_input(key_event: KeyEvent) -> PlayerAction {
         KeyCode::Char('g') => PlayerAction::PickupItem,
         KeyCode::Char('i') => PlayerAction::ShowInventory,
         KeyCode::Char('c') => PlayerAction::ShowCharacterSheet,
-        KeyCode::Char('>') => PlayerAction::UseStairs,
+        KeyCode::Char('>') | KeyCode::Char('<') => PlayerAction::UseStairs,
         
         // System keys
         KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => PlayerAction::SaveGame,
@@ -0,0 +1,355 @@
+use specs::{Component, VecStorage, NullStorage, System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeSet;
+use crate::components::{Item, Name, Player, Attributes, AttributeType, Skills, SkillType, StatusEffect, StatusEffects, StatusEffectType};
+use crate::items::{
+    ItemProperties, ItemType, ConsumableType, ItemStack, AdvancedInventory, Consumable, ConsumableEffect
+};
+use crate::resources::{GameLog, RandomNumberGenerator};
+
+/// Marker for dungeon furniture where potions can be mixed
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(NullStorage)]
+pub struct AlchemyStation;
+
+/// A consumable that a recipe brews
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlchemyProduct {
+    pub name: String,
+    pub description: String,
+    pub effects: Vec<ConsumableEffect>,
+    pub value: i32,
+}
+
+/// A combination of two ingredients, matched by item name in either order
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlchemyRecipe {
+    pub id: String,
+    pub ingredients: [String; 2],
+    pub product: AlchemyProduct,
+    pub difficulty: i32,
+}
+
+impl AlchemyRecipe {
+    pub fn all() -> Vec<AlchemyRecipe> {
+        vec![
+            AlchemyRecipe::new(
+                "vitality", "Health Potion", "Healing Herb", 10,
+                "Draught of Vitality", "A thick red draught that restores 60 health.",
+                vec![ConsumableEffect::Healing { amount: 60, over_time: false }], 120,
+            ),
+            AlchemyRecipe::new(
+                "salve", "Healing Herb", "Healing Herb", 0,
+                "Herbal Salve", "Slowly mends wounds over several turns.",
+                vec![ConsumableEffect::Healing { amount: 40, over_time: true }], 60,
+            ),
+            AlchemyRecipe::new(
+                "restoration", "Health Potion", "Mana Potion", 20,
+                "Elixir of Restoration", "Restores both health and mana.",
+                vec![
+                    ConsumableEffect::Healing { amount: 30, over_time: false },
+                    ConsumableEffect::ManaRestore { amount: 30, over_time: false },
+                ], 150,
+            ),
+            AlchemyRecipe::new(
+                "panacea", "Potion of Cure Poisoned", "Healing Herb", 25,
+                "Panacea", "Cures poison and disease alike.",
+                vec![
+                    ConsumableEffect::CureCondition { condition: StatusEffectType::Poisoned },
+                    ConsumableEffect::CureCondition { condition: StatusEffectType::Diseased },
+                ], 140,
+            ),
+            AlchemyRecipe::new(
+                "quicksilver", "Mana Potion", "Healing Herb", 30,
+                "Quicksilver Tonic", "Quickens the drinker for a short time.",
+                vec![ConsumableEffect::StatusEffect { effect_type: StatusEffectType::Haste, duration: 20.0, power: 1 }], 180,
+            ),
+        ]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        id: &str,
+        first: &str,
+        second: &str,
+        difficulty: i32,
+        name: &str,
+        description: &str,
+        effects: Vec<ConsumableEffect>,
+        value: i32,
+    ) -> Self {
+        AlchemyRecipe {
+            id: id.to_string(),
+            ingredients: [first.to_string(), second.to_string()],
+            product: AlchemyProduct {
+                name: name.to_string(),
+                description: description.to_string(),
+                effects,
+                value,
+            },
+            difficulty,
+        }
+    }
+
+    pub fn matches(&self, first: &str, second: &str) -> bool {
+        (self.ingredients[0] == first && self.ingredients[1] == second)
+            || (self.ingredients[0] == second && self.ingredients[1] == first)
+    }
+
+    /// The recipe two ingredients make, if any
+    pub fn find(first: &str, second: &str) -> Option<AlchemyRecipe> {
+        Self::all().into_iter().find(|recipe| recipe.matches(first, second))
+    }
+}
+
+/// Combinations the player has brewed successfully; saved with the world
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AlchemyJournal {
+    pub known: BTreeSet<String>,
+}
+
+impl AlchemyJournal {
+    pub fn knows(&self, recipe_id: &str) -> bool {
+        self.known.contains(recipe_id)
+    }
+
+    /// Record a recipe; returns true if it was new
+    pub fn learn(&mut self, recipe_id: &str) -> bool {
+        self.known.insert(recipe_id.to_string())
+    }
+
+    /// Known recipes, in the order they appear in the recipe list
+    pub fn known_recipes(&self) -> Vec<AlchemyRecipe> {
+        AlchemyRecipe::all().into_iter().filter(|recipe| self.knows(&recipe.id)).collect()
+    }
+}
+
+/// Intent component for mixing two ingredients at a station
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToBrew {
+    pub first: Entity,
+    pub second: Entity,
+    pub station: Entity,
+}
+
+/// Base chance of a successful brew before skill and attribute bonuses
+pub const BASE_BREW_CHANCE: i32 = 60;
+
+/// Chance (out of 100) that a failed brew releases toxic fumes
+pub const TOXIC_FUMES_CHANCE: i32 = 50;
+
+/// Percentage chance of brewing a recipe successfully; known recipes are easier
+pub fn brew_success_chance(skill_level: i32, intelligence_modifier: i32, recipe: &AlchemyRecipe, known: bool) -> i32 {
+    let chance = BASE_BREW_CHANCE
+        + skill_level * 8
+        + intelligence_modifier * 3
+        - recipe.difficulty
+        + if known { 15 } else { 0 };
+    chance.clamp(5, 95)
+}
+
+/// Doses produced by a successful brew
+pub fn brew_yield(skill_level: i32) -> i32 {
+    1 + skill_level / 2
+}
+
+/// System that resolves brewing requests
+pub struct AlchemySystem;
+
+impl<'a> System<'a> for AlchemySystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToBrew>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, ItemStack>,
+        WriteStorage<'a, ItemProperties>,
+        WriteStorage<'a, Consumable>,
+        WriteStorage<'a, Item>,
+        WriteStorage<'a, Name>,
+        WriteStorage<'a, StatusEffects>,
+        ReadStorage<'a, AlchemyStation>,
+        ReadStorage<'a, Skills>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Player>,
+        Write<'a, AlchemyJournal>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_brew,
+            mut inventories,
+            mut stacks,
+            mut item_properties,
+            mut consumables,
+            mut items,
+            mut names,
+            mut status_effects,
+            stations,
+            skills,
+            attributes,
+            players,
+            mut journal,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        for (entity, request) in (&entities, &wants_brew).join() {
+            if stations.get(request.station).is_none() {
+                gamelog.add_entry("You need an alchemy station to do that.".to_string());
+                continue;
+            }
+
+            let first_name = names.get(request.first).map(|n| n.name.clone());
+            let second_name = names.get(request.second).map(|n| n.name.clone());
+            let (first_name, second_name) = match (first_name, second_name) {
+                (Some(first), Some(second)) => (first, second),
+                _ => continue,
+            };
+
+            let inventory = match inventories.get_mut(entity) {
+                Some(inventory) => inventory,
+                None => continue,
+            };
+
+            // Mixing an item with itself needs two of it
+            let enough = if request.first == request.second {
+                inventory.find_item(request.first).map_or(false, |slot| inventory.items[slot].quantity >= 2)
+            } else {
+                inventory.find_item(request.first).is_some() && inventory.find_item(request.second).is_some()
+            };
+            if !enough {
+                gamelog.add_entry("You don't have the ingredients for that.".to_string());
+                continue;
+            }
+
+            // Ingredients are used up whether or not the brew works
+            for ingredient in [request.first, request.second] {
+                let weight = item_properties.get(ingredient).map_or(0.0, |props| props.weight);
+                let slot = inventory.find_item(ingredient).expect("Ingredient was just checked");
+                if let Some((_, removed)) = inventory.remove_item(slot, 1, weight) {
+                    if let Some(stack) = stacks.get_mut(ingredient) {
+                        stack.quantity -= removed;
+                    }
+                    if inventory.find_item(ingredient).is_none() {
+                        entities.delete(ingredient).expect("Unable to delete spent ingredient");
+                    }
+                }
+            }
+
+            let is_player = players.get(entity).is_some();
+            let recipe = match AlchemyRecipe::find(&first_name, &second_name) {
+                Some(recipe) => recipe,
+                None => {
+                    gamelog.add_entry(format!("The {} and {} curdle into useless sludge.", first_name, second_name));
+                    self.maybe_release_fumes(entity, &mut status_effects, is_player, &mut gamelog, &mut rng);
+                    continue;
+                }
+            };
+
+            let skill_level = skills.get(entity)
+                .map_or(0, |s| s.get_skill_level(SkillType::Alchemy));
+            let int_modifier = attributes.get(entity)
+                .map_or(0, |a| a.get_modifier(AttributeType::Intelligence));
+            let chance = brew_success_chance(skill_level, int_modifier, &recipe, journal.knows(&recipe.id));
+
+            if rng.roll_dice(1, 100) > chance {
+                gamelog.add_entry("The mixture boils over and the ingredients are lost.".to_string());
+                self.maybe_release_fumes(entity, &mut status_effects, is_player, &mut gamelog, &mut rng);
+                continue;
+            }
+
+            let doses = brew_yield(skill_level);
+            let product = &recipe.product;
+            let properties = ItemProperties::new(product.name.clone(), ItemType::Consumable(ConsumableType::Potion))
+                .with_description(product.description.clone())
+                .with_value(product.value)
+                .with_weight(0.5)
+                .with_stack_size(10);
+            let weight = properties.weight;
+
+            let potion = entities.create();
+            items.insert(potion, Item).expect("Unable to insert item");
+            names.insert(potion, Name { name: product.name.clone() }).expect("Unable to insert name");
+            item_properties.insert(potion, properties).expect("Unable to insert item properties");
+            consumables.insert(potion, Consumable::new(ConsumableType::Potion)
+                .with_effects(product.effects.clone())
+                .with_use_time(1.0)
+                .with_cooldown(2.0)).expect("Unable to insert consumable");
+            stacks.insert(potion, ItemStack::new(doses, 10)).expect("Unable to insert item stack");
+
+            if !inventory.add_item(potion, doses, weight) {
+                gamelog.add_entry(format!("You have no room for the {}; it spills away.", product.name));
+                entities.delete(potion).expect("Unable to delete brewed potion");
+                continue;
+            }
+
+            if journal.learn(&recipe.id) && is_player {
+                gamelog.add_entry(format!("Discovery! {} and {} make {}.", first_name, second_name, product.name));
+            }
+            if is_player {
+                let suffix = if doses > 1 { format!(" ({} doses)", doses) } else { String::new() };
+                gamelog.add_entry(format!("You brew {}{}.", product.name, suffix));
+            }
+        }
+        wants_brew.clear();
+    }
+}
+
+impl AlchemySystem {
+    fn maybe_release_fumes(
+        &self,
+        brewer: Entity,
+        status_effects: &mut WriteStorage<StatusEffects>,
+        is_player: bool,
+        gamelog: &mut GameLog,
+        rng: &mut RandomNumberGenerator,
+    ) {
+        if rng.roll_dice(1, 100) > TOXIC_FUMES_CHANCE {
+            return;
+        }
+
+        status_effects.entry(brewer)
+            .or_insert_with(StatusEffects::new)
+            .add_effect(StatusEffect::new(StatusEffectType::Poisoned, 5, 2));
+        if is_player {
+            gamelog.add_entry("Toxic fumes billow from the alembic! You are poisoned.".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipes_match_in_either_order() {
+        let forward = AlchemyRecipe::find("Health Potion", "Healing Herb").unwrap();
+        let backward = AlchemyRecipe::find("Healing Herb", "Health Potion").unwrap();
+        assert_eq!(forward.id, backward.id);
+        assert!(AlchemyRecipe::find("Healing Herb", "Scroll of Identify").is_none());
+    }
+
+    #[test]
+    fn test_skill_and_knowledge_improve_brewing() {
+        let recipe = AlchemyRecipe::find("Mana Potion", "Healing Herb").unwrap();
+        let novice = brew_success_chance(0, 0, &recipe, false);
+        assert!(brew_success_chance(3, 2, &recipe, false) > novice);
+        assert!(brew_success_chance(0, 0, &recipe, true) > novice);
+        assert!(brew_success_chance(5, 5, &recipe, true) <= 95);
+
+        assert_eq!(brew_yield(0), 1);
+        assert_eq!(brew_yield(4), 3);
+    }
+
+    #[test]
+    fn test_journal_records_discoveries_once() {
+        let mut journal = AlchemyJournal::default();
+        assert!(journal.learn("salve"));
+        assert!(!journal.learn("salve"));
+        assert_eq!(journal.known_recipes().len(), 1);
+    }
+}
@@ -0,0 +1,131 @@
+use crossterm::{event::KeyCode, style::Color};
+use specs::{World, Entity, WorldExt};
+use crate::items::{AdvancedInventory, ItemProperties, ItemType, MaterialType, get_item_display_name};
+use crate::items::alchemy_system::AlchemyJournal;
+use crate::localization::{tr, Message};
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// The screen shown when the player bumps into an alchemy station: two ingredients picked from
+/// the pack, one after the other, and mixed
+pub struct AlchemyUI {
+    pub station: Entity,
+    pub player: Entity,
+    pub selected: usize,
+    /// The ingredient picked first, waiting on a second
+    pub first: Option<Entity>,
+}
+
+impl AlchemyUI {
+    pub fn new(station: Entity, player: Entity) -> Self {
+        AlchemyUI { station, player, selected: 0, first: None }
+    }
+
+    /// Potions, herbs and anything else drinkable in the player's pack
+    fn ingredients(&self, world: &World) -> Vec<Entity> {
+        let properties = world.read_storage::<ItemProperties>();
+        world.read_storage::<AdvancedInventory>().get(self.player)
+            .map_or(Vec::new(), |pack| pack.items.iter().map(|slot| slot.entity).collect())
+            .into_iter()
+            .filter(|item| matches!(
+                properties.get(*item).map(|props| &props.item_type),
+                Some(ItemType::Consumable(_)) | Some(ItemType::Material(MaterialType::Herb))
+            ))
+            .collect()
+    }
+
+    fn ingredient_line(&self, world: &World, item: Entity) -> String {
+        let name = get_item_display_name(world, item).unwrap_or_default();
+        let quantity = world.read_storage::<AdvancedInventory>().get(self.player)
+            .and_then(|pack| pack.find_item(item).map(|slot| pack.items[slot].quantity))
+            .unwrap_or(1);
+        if quantity > 1 { format!("{} x{}", name, quantity) } else { name }
+    }
+
+    fn prompt(&self, world: &World) -> String {
+        match self.first {
+            Some(first) => Message::new("alchemy.pick_second").arg("item", self.ingredient_line(world, first)).text(),
+            None => tr("alchemy.pick_first"),
+        }
+    }
+
+    /// The highlighted ingredient, for the screen reader
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        self.ingredients(world).get(self.selected).map(|item| self.ingredient_line(world, *item))
+    }
+
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let ingredients = self.ingredients(world);
+        let known = world.read_resource::<AlchemyJournal>().known_recipes();
+        let width = 60.min(screen_width - 4);
+        let height = (10 + ingredients.len().max(1) as i32 + known.len() as i32).min(screen_height);
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(tr("alchemy.title"), x, y, width, height)
+            .with_colors(Color::Green, Color::Black, Color::Green)
+            .render();
+        let line = |commands: &mut Vec<UIRenderCommand>, row: i32, text: String, fg: Color| {
+            if row < height - 2 {
+                let text: String = text.chars().take((width - 4).max(0) as usize).collect();
+                commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text, fg, bg: Color::Black });
+            }
+        };
+
+        line(&mut commands, 2, self.prompt(world), Color::White);
+        let mut row = 4;
+        if ingredients.is_empty() {
+            line(&mut commands, row, tr("alchemy.no_ingredients"), Color::DarkGrey);
+            row += 1;
+        }
+        for (i, item) in ingredients.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = if i == self.selected { Color::Green } else { Color::White };
+            line(&mut commands, row, format!("{} {}", marker, self.ingredient_line(world, *item)), fg);
+            row += 1;
+        }
+
+        if !known.is_empty() {
+            line(&mut commands, row + 1, tr("alchemy.known"), Color::Cyan);
+            for (i, recipe) in known.iter().enumerate() {
+                let text = format!("  {} + {} = {}", recipe.ingredients[0], recipe.ingredients[1], recipe.product.name);
+                line(&mut commands, row + 2 + i as i32, text, Color::DarkGrey);
+            }
+        }
+
+        let controls = UIRenderCommand::DrawText {
+            x: x + 2,
+            y: y + height - 2,
+            text: tr("alchemy.controls").chars().take((width - 4).max(0) as usize).collect(),
+            fg: Color::DarkGrey,
+            bg: Color::Black,
+        };
+        commands.push(controls);
+        commands
+    }
+
+    /// Move the selection or pick an ingredient; the second pick mixes the two. Escape puts the
+    /// first ingredient back, or steps away from the station when none is picked
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) -> AlchemyAction {
+        let ingredients = self.ingredients(world);
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(ingredients.len().saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char(' ') => match (self.first, ingredients.get(self.selected).copied()) {
+                (None, Some(item)) => self.first = Some(item),
+                (Some(first), Some(second)) => return AlchemyAction::Brew(first, second, self.station),
+                _ => {},
+            },
+            KeyCode::Esc if self.first.is_some() => self.first = None,
+            KeyCode::Esc => return AlchemyAction::Close,
+            _ => {},
+        }
+        AlchemyAction::None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlchemyAction {
+    None,
+    /// Mix the first ingredient with the second at the station
+    Brew(Entity, Entity, Entity),
+    Close,
+}
@@ -0,0 +1,197 @@
+use specs::{Component, VecStorage, Entity};
+use serde::{Serialize, Deserialize};
+use crate::components::{DamageType, StatusEffectType};
+use crate::items::WeaponType;
+use crate::resources::RandomNumberGenerator;
+
+/// What kind of launcher a piece of ammunition is made for
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmmoKind {
+    Arrow,
+    Bolt,
+}
+
+impl AmmoKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AmmoKind::Arrow => "Arrow",
+            AmmoKind::Bolt => "Bolt",
+        }
+    }
+
+    pub fn fits(&self, weapon: &WeaponType) -> bool {
+        matches!(
+            (self, weapon),
+            (AmmoKind::Arrow, WeaponType::Bow) | (AmmoKind::Bolt, WeaponType::Crossbow)
+        )
+    }
+
+    /// The ammunition a launcher fires, if it is one
+    pub fn for_weapon(weapon: &WeaponType) -> Option<AmmoKind> {
+        match weapon {
+            WeaponType::Bow => Some(AmmoKind::Arrow),
+            WeaponType::Crossbow => Some(AmmoKind::Bolt),
+            _ => None,
+        }
+    }
+}
+
+/// What the head of an arrow or bolt is made of
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmmoMaterial {
+    Wooden,
+    Iron,
+    /// Deals double damage to the undead
+    Silver,
+    /// Deals fire damage and sets the target alight
+    Fire,
+    /// Leaves the target bleeding
+    Barbed,
+}
+
+impl AmmoMaterial {
+    pub fn all() -> [AmmoMaterial; 5] {
+        [AmmoMaterial::Wooden, AmmoMaterial::Iron, AmmoMaterial::Silver, AmmoMaterial::Fire, AmmoMaterial::Barbed]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AmmoMaterial::Wooden => "Wooden",
+            AmmoMaterial::Iron => "Iron",
+            AmmoMaterial::Silver => "Silver",
+            AmmoMaterial::Fire => "Fire",
+            AmmoMaterial::Barbed => "Barbed",
+        }
+    }
+
+    /// Color of the shot in flight
+    pub fn color(&self) -> crossterm::style::Color {
+        use crossterm::style::Color;
+        match self {
+            AmmoMaterial::Wooden => Color::DarkYellow,
+            AmmoMaterial::Iron => Color::Grey,
+            AmmoMaterial::Silver => Color::White,
+            AmmoMaterial::Fire => Color::Red,
+            AmmoMaterial::Barbed => Color::DarkGrey,
+        }
+    }
+
+    /// Flat damage added to each hit
+    pub fn damage_bonus(&self) -> i32 {
+        match self {
+            AmmoMaterial::Wooden => 0,
+            AmmoMaterial::Iron => 2,
+            AmmoMaterial::Silver => 1,
+            AmmoMaterial::Fire => 1,
+            AmmoMaterial::Barbed => 1,
+        }
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        match self {
+            AmmoMaterial::Fire => DamageType::Fire,
+            _ => DamageType::Physical,
+        }
+    }
+
+    /// Damage multiplier against undead targets
+    pub fn undead_multiplier(&self) -> i32 {
+        match self {
+            AmmoMaterial::Silver => 2,
+            _ => 1,
+        }
+    }
+
+    /// Status effect (type, duration, magnitude) applied to the target on a hit
+    pub fn on_hit_status(&self) -> Option<(StatusEffectType, i32, i32)> {
+        match self {
+            AmmoMaterial::Fire => Some((StatusEffectType::Burning, 3, 2)),
+            AmmoMaterial::Barbed => Some((StatusEffectType::Bleeding, 4, 1)),
+            _ => None,
+        }
+    }
+
+    /// A material for found ammunition; the special heads are rarer
+    pub fn random(rng: &mut RandomNumberGenerator) -> AmmoMaterial {
+        match rng.roll_dice(1, 100) {
+            1..=45 => AmmoMaterial::Wooden,
+            46..=75 => AmmoMaterial::Iron,
+            76..=85 => AmmoMaterial::Barbed,
+            86..=94 => AmmoMaterial::Fire,
+            _ => AmmoMaterial::Silver,
+        }
+    }
+
+    /// Gold value of a single piece
+    pub fn value(&self) -> i32 {
+        match self {
+            AmmoMaterial::Wooden => 1,
+            AmmoMaterial::Iron => 2,
+            AmmoMaterial::Silver => 8,
+            AmmoMaterial::Fire => 6,
+            AmmoMaterial::Barbed => 4,
+        }
+    }
+}
+
+/// Component for arrows and bolts; stacks are kept in `ItemStack`
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Ammunition {
+    pub kind: AmmoKind,
+    pub material: AmmoMaterial,
+}
+
+impl Ammunition {
+    pub fn new(kind: AmmoKind, material: AmmoMaterial) -> Self {
+        Ammunition { kind, material }
+    }
+
+    /// "Silver Arrow", "Barbed Bolt", ...
+    pub fn name(&self) -> String {
+        format!("{} {}", self.material.name(), self.kind.name())
+    }
+
+    /// Damage a hit with this ammunition deals, before the target's resistances
+    pub fn hit_damage(&self, base_damage: i32, target_is_undead: bool) -> i32 {
+        let damage = base_damage + self.material.damage_bonus();
+        if target_is_undead {
+            damage * self.material.undead_multiplier()
+        } else {
+            damage
+        }
+    }
+}
+
+/// Intent component for firing the equipped launcher at a target
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToShoot {
+    pub target: Entity,
+    /// Ammunition to fire; the quiver is used when this is None
+    pub ammo: Option<Entity>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ammo_fits_its_launcher() {
+        assert!(AmmoKind::Arrow.fits(&WeaponType::Bow));
+        assert!(!AmmoKind::Arrow.fits(&WeaponType::Crossbow));
+        assert_eq!(AmmoKind::for_weapon(&WeaponType::Crossbow), Some(AmmoKind::Bolt));
+        assert_eq!(AmmoKind::for_weapon(&WeaponType::Sword), None);
+    }
+
+    #[test]
+    fn test_silver_punishes_undead() {
+        let silver = Ammunition::new(AmmoKind::Arrow, AmmoMaterial::Silver);
+        let iron = Ammunition::new(AmmoKind::Arrow, AmmoMaterial::Iron);
+
+        assert_eq!(silver.hit_damage(5, false), 6);
+        assert_eq!(silver.hit_damage(5, true), 12);
+        assert_eq!(iron.hit_damage(5, true), 7);
+        assert_eq!(silver.name(), "Silver Arrow");
+    }
+}
@@ -0,0 +1,288 @@
+use specs::{Component, VecStorage, System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use crate::components::{CombatStats, DamageInfo, DamageType, Name, Player, Position, StatusEffect, StatusEffects, StatusEffectType};
+use crate::items::{CombatBonuses, Equipment, EquipmentSlot, ItemType};
+use crate::resources::{GameLog, RandomNumberGenerator, RunStatistics};
+
+/// Hand-authored artifacts shipped with the game
+const BUILTIN_ARTIFACTS: &str = include_str!("../../data/artifacts.json");
+
+/// Percent chance that a fresh level holds an artifact, when one is eligible
+pub const ARTIFACT_LEVEL_CHANCE: i32 = 10;
+
+/// A scripted effect attached to an artifact
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ArtifactEffect {
+    /// Extra damage added to a hit, `chance` percent of the time
+    OnHitDamage { damage_type: DamageType, amount: i32, chance: i32 },
+    /// Inflict a status effect on the target, `chance` percent of the time
+    OnHitStatus { effect: StatusEffectType, duration: i32, magnitude: i32, chance: i32 },
+    /// Heal the wielder by a share of the damage dealt
+    OnHitLifesteal { percent: i32 },
+    /// Shown to the wearer when the artifact is equipped
+    OnEquipMessage { text: String },
+    /// Granted to the wearer when the artifact is equipped
+    OnEquipStatus { effect: StatusEffectType, duration: i32, magnitude: i32 },
+}
+
+/// A unique item as written in the artifact data file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArtifactDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub item_type: ItemType,
+    pub glyph: char,
+    /// Shallowest level the artifact may be found on
+    pub min_depth: i32,
+    pub value: i32,
+    #[serde(default)]
+    pub bonuses: CombatBonuses,
+    #[serde(default)]
+    pub effects: Vec<ArtifactEffect>,
+}
+
+/// Every artifact the game knows about
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ArtifactDatabase {
+    pub artifacts: Vec<ArtifactDefinition>,
+}
+
+impl ArtifactDatabase {
+    /// The artifacts bundled with the game
+    pub fn builtin() -> Self {
+        serde_json::from_str(BUILTIN_ARTIFACTS).expect("Bundled artifact data is invalid")
+    }
+
+    /// Load artifacts from a data file, e.g. for mods or testing
+    pub fn load_from_file(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(filename)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ArtifactDefinition> {
+        self.artifacts.iter().find(|artifact| artifact.id == id)
+    }
+}
+
+/// Artifacts already generated this run; each may appear at most once
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ArtifactRegistry {
+    pub generated: HashSet<String>,
+}
+
+impl ArtifactRegistry {
+    /// Maybe pick an artifact for a new level at `depth`, marking it as used for the rest of the run
+    pub fn roll(&mut self, database: &ArtifactDatabase, depth: i32, rng: &mut RandomNumberGenerator) -> Option<ArtifactDefinition> {
+        if rng.roll_dice(1, 100) > ARTIFACT_LEVEL_CHANCE {
+            return None;
+        }
+        self.pick(database, depth, rng)
+    }
+
+    /// Pick an eligible artifact regardless of chance, e.g. as a guaranteed boss drop
+    pub fn pick(&mut self, database: &ArtifactDatabase, depth: i32, rng: &mut RandomNumberGenerator) -> Option<ArtifactDefinition> {
+        let eligible: Vec<&ArtifactDefinition> = database.artifacts.iter()
+            .filter(|artifact| artifact.min_depth <= depth && !self.generated.contains(&artifact.id))
+            .collect();
+        if eligible.is_empty() {
+            return None;
+        }
+
+        let artifact = eligible[rng.range(0, eligible.len() as i32 - 1) as usize].clone();
+        self.generated.insert(artifact.id.clone());
+        Some(artifact)
+    }
+
+    pub fn clear(&mut self) {
+        self.generated.clear();
+    }
+}
+
+/// Marks an item entity as a specific artifact and carries its scripted effects
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Artifact {
+    pub id: String,
+    pub effects: Vec<ArtifactEffect>,
+}
+
+impl Artifact {
+    pub fn from_definition(definition: &ArtifactDefinition) -> Self {
+        Artifact {
+            id: definition.id.clone(),
+            effects: definition.effects.clone(),
+        }
+    }
+}
+
+/// Runs artifact on-hit and on-equip effects, and notes which artifacts the player has found
+pub struct ArtifactSystem {
+    equipped: HashSet<Entity>,
+}
+
+impl ArtifactSystem {
+    pub fn new() -> Self {
+        ArtifactSystem { equipped: HashSet::new() }
+    }
+}
+
+impl<'a> System<'a> for ArtifactSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Artifact>,
+        ReadStorage<'a, Equipment>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, DamageInfo>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, StatusEffects>,
+        Write<'a, RunStatistics>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            artifacts,
+            equipment,
+            positions,
+            players,
+            names,
+            mut damage_info,
+            mut combat_stats,
+            mut status_effects,
+            mut stats,
+            mut rng,
+            mut gamelog,
+        ) = data;
+
+        // An artifact without a position has been picked up
+        for (entity, artifact) in (&entities, &artifacts).join() {
+            if !positions.contains(entity) && !stats.artifacts_found.contains(&artifact.id) {
+                stats.artifacts_found.push(artifact.id.clone());
+                let name = names.get(entity).map_or("an artifact", |n| n.name.as_str());
+                gamelog.add_entry(format!("You have found {}!", name));
+            }
+        }
+
+        // On-equip effects fire once each time an artifact is put on
+        let mut now_equipped = HashSet::new();
+        for (wearer, gear) in (&entities, &equipment).join() {
+            for item in gear.get_all_equipped() {
+                let artifact = match artifacts.get(item) {
+                    Some(artifact) => artifact,
+                    None => continue,
+                };
+                now_equipped.insert(item);
+                if self.equipped.contains(&item) {
+                    continue;
+                }
+
+                for effect in &artifact.effects {
+                    match effect {
+                        ArtifactEffect::OnEquipMessage { text } if players.contains(wearer) => {
+                            gamelog.add_entry(text.clone());
+                        },
+                        ArtifactEffect::OnEquipStatus { effect, duration, magnitude } => {
+                            if let Some(effects) = status_effects.get_mut(wearer) {
+                                effects.add_effect(StatusEffect {
+                                    effect_type: *effect,
+                                    duration: *duration,
+                                    magnitude: *magnitude,
+                                });
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+            }
+        }
+        self.equipped = now_equipped;
+
+        // On-hit effects ride along with damage dealt by an artifact in the main hand
+        let mut hits = Vec::new();
+        for (target, damage) in (&entities, &damage_info).join() {
+            let weapon = equipment.get(damage.source)
+                .and_then(|gear| gear.get_equipped(&EquipmentSlot::MainHand))
+                .and_then(|item| artifacts.get(item));
+            if let Some(artifact) = weapon {
+                hits.push((target, damage.source, artifact.effects.clone()));
+            }
+        }
+
+        for (target, wielder, effects) in hits {
+            for effect in effects {
+                match effect {
+                    ArtifactEffect::OnHitDamage { damage_type, amount, chance } => {
+                        if rng.roll_dice(1, 100) <= chance {
+                            if let Some(damage) = damage_info.get_mut(target) {
+                                damage.base_damage += amount;
+                            }
+                            if players.contains(wielder) {
+                                gamelog.add_entry(format!("Your weapon flares with {}!", damage_type.name().to_lowercase()));
+                            }
+                        }
+                    },
+                    ArtifactEffect::OnHitStatus { effect, duration, magnitude, chance } => {
+                        if rng.roll_dice(1, 100) <= chance {
+                            if let Some(effects) = status_effects.get_mut(target) {
+                                effects.add_effect(StatusEffect { effect_type: effect, duration, magnitude });
+                            }
+                        }
+                    },
+                    ArtifactEffect::OnHitLifesteal { percent } => {
+                        let dealt = damage_info.get(target).map_or(0, |damage| damage.base_damage);
+                        let healed = dealt * percent / 100;
+                        if let Some(wielder_stats) = combat_stats.get_mut(wielder) {
+                            if healed > 0 && wielder_stats.hp < wielder_stats.max_hp {
+                                wielder_stats.hp = i32::min(wielder_stats.max_hp, wielder_stats.hp + healed);
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_artifacts_parse() {
+        let database = ArtifactDatabase::builtin();
+        assert!(!database.artifacts.is_empty());
+        assert!(database.get("emberfang").is_some());
+    }
+
+    #[test]
+    fn test_each_artifact_generates_once_per_run() {
+        let database = ArtifactDatabase::builtin();
+        let mut registry = ArtifactRegistry::default();
+        let mut rng = RandomNumberGenerator::new(4);
+
+        let mut seen = HashSet::new();
+        while let Some(artifact) = registry.pick(&database, 100, &mut rng) {
+            assert!(seen.insert(artifact.id));
+        }
+        assert_eq!(seen.len(), database.artifacts.len());
+
+        registry.clear();
+        assert!(registry.pick(&database, 100, &mut rng).is_some());
+    }
+
+    #[test]
+    fn test_shallow_levels_hold_no_deep_artifacts() {
+        let database = ArtifactDatabase::builtin();
+        let mut registry = ArtifactRegistry::default();
+        let mut rng = RandomNumberGenerator::new(6);
+
+        assert!(registry.pick(&database, 1, &mut rng).is_none());
+    }
+}
@@ -1,15 +1,20 @@
 use specs::{World, WorldExt, Builder, Entity};
-use crate::components::{Position, Name, Renderable, Item};
+use crate::components::{Position, Name, Renderable, Item, StatusEffectType, RevivalItem, GasFlask, GasKind};
 use crate::items::{
     ItemProperties, ItemType, ConsumableType, ItemRarity, ItemStack,
+    Ammunition, AmmoKind, AmmoMaterial,
     consumable_system::{
-        Consumable, ConsumableEffect, StatusEffectType, StatusEffect,
-        ConsumableRestriction
+        Consumable, ConsumableEffect, ConsumableRestriction
     }
 };
+use crate::map::Climate;
 use crate::resources::RandomNumberGenerator;
+use crate::systems::FLASK_GAS_VOLUME;
 use std::collections::HashMap;
 
+/// Turns a warming draught or waterskin keeps the climate off
+pub const CLIMATE_PROVISION_TURNS: f32 = 150.0;
+
 /// Factory for creating different types of consumable items
 pub struct ConsumableFactory;
 
@@ -208,12 +213,28 @@ impl ConsumableFactory {
         position: Position,
         food_type: FoodType,
     ) -> Entity {
+        let (name, properties, consumable, stack, renderable) = Self::food_components(food_type);
+
+        world.create_entity()
+            .with(Item)
+            .with(name)
+            .with(properties)
+            .with(consumable)
+            .with(stack)
+            .with(position)
+            .with(renderable)
+            .build()
+    }
+
+    /// Components making up a food item, for systems that spawn food without a `World`
+    pub fn food_components(food_type: FoodType) -> (Name, ItemProperties, Consumable, ItemStack, Renderable) {
         let (name, healing, duration, value, weight, stack_size, glyph) = match food_type {
             FoodType::Bread => ("Bread", 8, 30.0, 5, 0.2, 20, '%'),
             FoodType::Cheese => ("Cheese", 12, 45.0, 8, 0.3, 15, '%'),
             FoodType::Meat => ("Cooked Meat", 20, 60.0, 15, 0.5, 10, '%'),
             FoodType::Apple => ("Apple", 5, 20.0, 3, 0.1, 25, '%'),
             FoodType::Rations => ("Travel Rations", 15, 120.0, 25, 1.0, 5, '%'),
+            FoodType::RawMeat => ("Raw Meat", 4, 40.0, 2, 0.5, 10, '%'),
         };
 
         let consumable = Consumable::new(ConsumableType::Food)
@@ -236,16 +257,67 @@ impl ConsumableFactory {
             .with_weight(weight)
             .with_stack_size(stack_size);
 
+        let fg = match food_type {
+            FoodType::RawMeat => crossterm::style::Color::Red,
+            _ => crossterm::style::Color::DarkYellow,
+        };
+
+        (
+            Name { name: name.to_string() },
+            properties,
+            consumable,
+            ItemStack::new(1, stack_size),
+            Renderable {
+                glyph,
+                fg,
+                bg: crossterm::style::Color::Black,
+                render_order: 2,
+            },
+        )
+    }
+
+    /// Create a bundle of arrows or bolts
+    pub fn create_ammunition(
+        &self,
+        world: &mut World,
+        position: Position,
+        kind: AmmoKind,
+        material: AmmoMaterial,
+        quantity: i32,
+    ) -> Entity {
+        let ammunition = Ammunition::new(kind, material);
+        let name = format!("{}s", ammunition.name());
+        let rarity = match material {
+            AmmoMaterial::Wooden | AmmoMaterial::Iron => ItemRarity::Common,
+            AmmoMaterial::Barbed | AmmoMaterial::Fire => ItemRarity::Uncommon,
+            AmmoMaterial::Silver => ItemRarity::Rare,
+        };
+        let color = match material {
+            AmmoMaterial::Wooden => crossterm::style::Color::DarkYellow,
+            AmmoMaterial::Iron => crossterm::style::Color::Grey,
+            AmmoMaterial::Silver => crossterm::style::Color::White,
+            AmmoMaterial::Fire => crossterm::style::Color::Red,
+            AmmoMaterial::Barbed => crossterm::style::Color::DarkRed,
+        };
+
+        let properties = ItemProperties::new(name.clone(), ItemType::Consumable(ConsumableType::Ammunition))
+            .with_description(format!("{}s for a {}. Keep them in your quiver.",
+                ammunition.name(), if kind == AmmoKind::Arrow { "bow" } else { "crossbow" }))
+            .with_rarity(rarity)
+            .with_value(material.value())
+            .with_weight(0.1)
+            .with_stack_size(50);
+
         world.create_entity()
             .with(Item)
-            .with(Name { name: name.to_string() })
+            .with(Name { name })
             .with(properties)
-            .with(consumable)
-            .with(ItemStack::new(1, stack_size))
+            .with(ammunition)
+            .with(ItemStack::new(quantity, 50))
             .with(position)
             .with(Renderable {
-                glyph,
-                fg: crossterm::style::Color::DarkYellow,
+                glyph: '|',
+                fg: color,
                 bg: crossterm::style::Color::Black,
                 render_order: 2,
             })
@@ -288,6 +360,13 @@ impl ConsumableFactory {
                 1,
                 ItemRarity::Common,
             ),
+            ScrollType::RemoveCurse => (
+                "Scroll of Remove Curse",
+                vec![ConsumableEffect::RemoveCurse],
+                120,
+                1,
+                ItemRarity::Uncommon,
+            ),
             ScrollType::MagicMapping => (
                 "Scroll of Magic Mapping",
                 vec![ConsumableEffect::RevealMap { radius: 20 }],
@@ -335,9 +414,9 @@ impl ConsumableFactory {
     ) -> Entity {
         let name = format!("Potion of Cure {:?}", condition);
         let value = match condition {
-            StatusEffectType::Poison => 40,
-            StatusEffectType::Disease => 60,
-            StatusEffectType::Curse => 100,
+            StatusEffectType::Poisoned => 40,
+            StatusEffectType::Diseased => 60,
+            StatusEffectType::Cursed => 100,
             _ => 30,
         };
 
@@ -411,6 +490,130 @@ impl ConsumableFactory {
             .build()
     }
 
+    /// Create a phoenix feather, offered on the revival screen after death
+    pub fn create_phoenix_feather(
+        &self,
+        world: &mut World,
+        position: Position,
+    ) -> Entity {
+        let properties = ItemProperties::new("Phoenix Feather".to_string(), ItemType::Miscellaneous)
+            .with_description("A smouldering feather that can burn away death itself, once.".to_string())
+            .with_rarity(ItemRarity::Legendary)
+            .with_value(1000)
+            .with_weight(0.1)
+            .with_stack_size(1);
+
+        world.create_entity()
+            .with(Item)
+            .with(Name { name: "Phoenix Feather".to_string() })
+            .with(properties)
+            .with(RevivalItem { revival_power: 30, auto_use: false, consumed_on_use: true })
+            .with(position)
+            .with(Renderable {
+                glyph: '~',
+                fg: crossterm::style::Color::Red,
+                bg: crossterm::style::Color::Black,
+                render_order: 2,
+            })
+            .build()
+    }
+
+    /// Create a provision against a harsh climate: a warming draught for the cold or a waterskin for the heat
+    pub fn create_climate_provision(
+        &self,
+        world: &mut World,
+        position: Position,
+        climate: Climate,
+    ) -> Entity {
+        let (name, effect_type, description, color) = match climate {
+            Climate::Hot => ("Waterskin", StatusEffectType::Hydrated,
+                "Cool water that keeps the heat at bay for a while.", crossterm::style::Color::Blue),
+            _ => ("Warming Draught", StatusEffectType::Warmed,
+                "A fiery spirit that keeps the cold at bay for a while.", crossterm::style::Color::DarkRed),
+        };
+
+        let consumable = Consumable::new(ConsumableType::Potion)
+            .with_effects(vec![
+                ConsumableEffect::StatusEffect {
+                    effect_type,
+                    duration: CLIMATE_PROVISION_TURNS,
+                    power: 1,
+                }
+            ])
+            .with_use_time(1.0)
+            .with_cooldown(1.0);
+
+        let properties = ItemProperties::new(name.to_string(), ItemType::Consumable(ConsumableType::Potion))
+            .with_description(description.to_string())
+            .with_value(20)
+            .with_weight(0.5)
+            .with_stack_size(5);
+
+        world.create_entity()
+            .with(Item)
+            .with(Name { name: name.to_string() })
+            .with(properties)
+            .with(consumable)
+            .with(ItemStack::new(1, 5))
+            .with(position)
+            .with(Renderable {
+                glyph: '!',
+                fg: color,
+                bg: crossterm::style::Color::Black,
+                render_order: 2,
+            })
+            .build()
+    }
+
+    /// Create a flask of gas, meant to be thrown rather than drunk
+    pub fn create_gas_flask(
+        &self,
+        world: &mut World,
+        position: Position,
+        kind: GasKind,
+    ) -> Entity {
+        let name = match kind {
+            GasKind::PoisonGas => "Flask of Poison Gas",
+            GasKind::Miasma => "Flask of Miasma",
+            GasKind::Steam => "Flask of Steam",
+            GasKind::Spores => "Flask of Spores",
+        };
+
+        // Drinking it gets you a lungful all the same
+        let consumable = Consumable::new(ConsumableType::Potion)
+            .with_effects(vec![
+                ConsumableEffect::StatusEffect {
+                    effect_type: kind.status(),
+                    duration: 5.0,
+                    power: 1,
+                }
+            ])
+            .with_use_time(1.0)
+            .with_cooldown(1.0);
+
+        let properties = ItemProperties::new(name.to_string(), ItemType::Consumable(ConsumableType::Potion))
+            .with_description(format!("A stoppered flask of {}. Throw it to let the cloud loose where it lands.", kind.name()))
+            .with_value(35)
+            .with_weight(0.4)
+            .with_stack_size(5);
+
+        world.create_entity()
+            .with(Item)
+            .with(Name { name: name.to_string() })
+            .with(properties)
+            .with(consumable)
+            .with(GasFlask { kind, volume: FLASK_GAS_VOLUME })
+            .with(ItemStack::new(1, 5))
+            .with(position)
+            .with(Renderable {
+                glyph: '!',
+                fg: kind.color(),
+                bg: crossterm::style::Color::Black,
+                render_order: 2,
+            })
+            .build()
+    }
+
     /// Create random consumable based on context
     pub fn create_random_consumable(
         &self,
@@ -426,10 +629,13 @@ impl ConsumableFactory {
                     self.create_health_potion(world, position, PotionPotency::Lesser)
                 } else if roll <= 80 {
                     self.create_mana_potion(world, position, PotionPotency::Lesser)
-                } else if roll <= 90 {
+                } else if roll <= 85 {
                     self.create_regeneration_potion(world, position, 30.0, 2)
+                } else if roll <= 92 {
+                    let kinds = [GasKind::PoisonGas, GasKind::Miasma, GasKind::Steam, GasKind::Spores];
+                    self.create_gas_flask(world, position, kinds[rng.roll_dice(1, kinds.len()) - 1])
                 } else {
-                    self.create_cure_potion(world, position, StatusEffectType::Poison)
+                    self.create_cure_potion(world, position, StatusEffectType::Poisoned)
                 }
             },
             ConsumableContext::Exploration => {
@@ -438,8 +644,14 @@ impl ConsumableFactory {
                     self.create_food(world, position, FoodType::Rations)
                 } else if roll <= 60 {
                     self.create_scroll(world, position, ScrollType::MagicMapping)
-                } else if roll <= 80 {
+                } else if roll <= 72 {
                     self.create_scroll(world, position, ScrollType::Identify)
+                } else if roll <= 80 {
+                    self.create_scroll(world, position, ScrollType::RemoveCurse)
+                } else if roll <= 85 {
+                    self.create_climate_provision(world, position, Climate::Cold)
+                } else if roll <= 90 {
+                    self.create_climate_provision(world, position, Climate::Hot)
                 } else {
                     self.create_health_potion(world, position, PotionPotency::Lesser)
                 }
@@ -456,8 +668,10 @@ impl ConsumableFactory {
                     self.create_scroll(world, position, ScrollType::Teleport)
                 } else if roll <= 90 {
                     self.create_regeneration_potion(world, position, 60.0, 3)
-                } else {
+                } else if roll <= 97 {
                     self.create_emergency_potion(world, position)
+                } else {
+                    self.create_phoenix_feather(world, position)
                 }
             },
         }
@@ -482,6 +696,8 @@ pub enum FoodType {
     Meat,
     Apple,
     Rations,
+    /// Carved from a fresh corpse
+    RawMeat,
 }
 
 /// Scroll types
@@ -491,6 +707,7 @@ pub enum ScrollType {
     Fireball,
     Teleport,
     Identify,
+    RemoveCurse,
     MagicMapping,
 }
 
@@ -1,13 +1,14 @@
 // Complete integration example for the consumable system
 
 use specs::{World, WorldExt, Builder, Entity, Join};
-use crate::components::{Position, Name, Player, CombatStats};
+use crate::components::{Position, Name, Player, CombatStats, StatusEffects, StatusEffectType};
 use crate::items::{
     ConsumableFactory, Consumable, ConsumableUsageSystem, ConsumableUpdateSystem,
-    WantsToUseConsumable, ConsumableCooldowns, StatusEffects, PotionPotency,
-    FoodType, ScrollType, ConsumableContext, StatusEffectType
+    WantsToUseConsumable, ConsumableCooldowns, PotionPotency,
+    FoodType, ScrollType, ConsumableContext
 };
-use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::resources::{GameLog, RandomNumberGenerator, GameStateResource};
+use crate::systems::StatusEffectSystem;
 
 /// Complete example showing how to integrate the consumable system
 pub struct ConsumableIntegration {
@@ -44,6 +45,7 @@ impl ConsumableIntegration {
         // Add resources
         world.insert(GameLog::new());
         world.insert(RandomNumberGenerator::new());
+        world.insert(GameStateResource::default());
         world.insert(0.016f32); // Delta time (60 FPS)
 
         world
@@ -158,29 +160,28 @@ impl ConsumableIntegration {
         world.maintain();
 
         // Check status effects
-        let status_effects = world.read_storage::<StatusEffects>();
-        if let Some(effects) = status_effects.get(player) {
-            if effects.has_effect(&StatusEffectType::Regeneration) {
-                println!("Regeneration effect applied!");
-                if let Some(effect) = effects.get_effect(&StatusEffectType::Regeneration) {
-                    println!("Duration: {:.1}s, Power: {}", effect.duration, effect.power);
+        {
+            let status_effects = world.read_storage::<StatusEffects>();
+            if let Some(effects) = status_effects.get(player) {
+                if let Some(effect) = effects.get_effect(StatusEffectType::Regeneration) {
+                    println!("Regeneration effect applied!");
+                    println!("Duration: {} turns, Power: {}", effect.duration, effect.magnitude);
                 }
             }
         }
 
-        // Simulate time passing and status effect ticks
-        println!("\nSimulating 5 seconds of regeneration...");
-        let mut update_system = ConsumableUpdateSystem;
+        // Simulate turns passing and status effect ticks
+        println!("\nSimulating 5 turns of regeneration...");
+        let mut status_effect_system = StatusEffectSystem::new();
         
         for i in 1..=5 {
-            // Update with 1 second delta time
-            *world.write_resource::<f32>() = 1.0;
-            update_system.run_now(world);
+            world.write_resource::<GameStateResource>().turn_count += 1;
+            status_effect_system.run_now(world);
             world.maintain();
 
             let combat_stats = world.read_storage::<CombatStats>();
             if let Some(stats) = combat_stats.get(player) {
-                println!("After {}s - Health: {}/{}", i, stats.hp, stats.max_hp);
+                println!("After turn {} - Health: {}/{}", i, stats.hp, stats.max_hp);
             }
         }
         println!();
@@ -398,8 +399,8 @@ impl ConsumableIntegration {
         // Count active status effects
         let mut effect_counts = std::collections::HashMap::new();
         for (_entity, effects) in (&entities, &status_effects).join() {
-            for effect_type in effects.effects.keys() {
-                let type_name = format!("{:?}", effect_type);
+            for effect in &effects.effects {
+                let type_name = effect.effect_type.name().to_string();
                 *effect_counts.entry(type_name).or_insert(0) += 1;
             }
         }
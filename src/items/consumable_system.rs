@@ -1,8 +1,8 @@
 use specs::{Component, VecStorage, System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
-use crate::components::{CombatStats, Player, Name, Position};
-use crate::items::{ItemProperties, ItemType, ConsumableType};
+use crate::components::{CombatStats, Player, Name, Position, StatusEffects, StatusEffect, StatusEffectType};
+use crate::items::{ItemProperties, ItemType, ConsumableType, ItemStack, Equipment, MagicalItem, AdvancedInventory, lift_equipped_curses};
 use crate::resources::{GameLog, RandomNumberGenerator};
 
 /// Component for consumable items
@@ -182,6 +182,8 @@ pub enum ConsumableEffect {
     Identify {
         count: i32,
     },
+    /// Lift the weaker curses on everything the target wears
+    RemoveCurse,
     /// Custom effect
     Custom {
         effect_id: String,
@@ -189,44 +191,6 @@ pub enum ConsumableEffect {
     },
 }
 
-/// Status effects that can be applied
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
-pub enum StatusEffectType {
-    // Beneficial effects
-    Regeneration,
-    ManaRegeneration,
-    Haste,
-    Strength,
-    Dexterity,
-    Constitution,
-    Intelligence,
-    Wisdom,
-    Charisma,
-    Protection,
-    Invisibility,
-    WellFed,
-    Blessed,
-    
-    // Harmful effects
-    Poison,
-    Disease,
-    Curse,
-    Weakness,
-    Slow,
-    Confusion,
-    Fear,
-    Paralysis,
-    Sleep,
-    Blind,
-    Deaf,
-    Mute,
-    
-    // Neutral effects
-    Detect,
-    Levitation,
-    WaterWalking,
-}
-
 /// Requirements to use a consumable
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ConsumableRequirements {
@@ -345,114 +309,6 @@ impl ConsumableCooldowns {
     }
 }
 
-/// Component for active status effects
-#[derive(Component, Debug, Serialize, Deserialize, Clone)]
-#[storage(VecStorage)]
-pub struct StatusEffects {
-    pub effects: HashMap<StatusEffectType, StatusEffect>,
-}
-
-impl StatusEffects {
-    pub fn new() -> Self {
-        StatusEffects {
-            effects: HashMap::new(),
-        }
-    }
-
-    pub fn add_effect(&mut self, effect_type: StatusEffectType, effect: StatusEffect) {
-        // If effect already exists, either stack or replace based on type
-        if let Some(existing) = self.effects.get_mut(&effect_type) {
-            match effect_type {
-                // Stackable effects
-                StatusEffectType::Poison | StatusEffectType::Regeneration => {
-                    existing.power += effect.power;
-                    existing.duration = existing.duration.max(effect.duration);
-                },
-                // Non-stackable effects (replace with longer duration)
-                _ => {
-                    if effect.duration > existing.duration {
-                        *existing = effect;
-                    }
-                }
-            }
-        } else {
-            self.effects.insert(effect_type, effect);
-        }
-    }
-
-    pub fn remove_effect(&mut self, effect_type: &StatusEffectType) {
-        self.effects.remove(effect_type);
-    }
-
-    pub fn has_effect(&self, effect_type: &StatusEffectType) -> bool {
-        self.effects.contains_key(effect_type)
-    }
-
-    pub fn get_effect(&self, effect_type: &StatusEffectType) -> Option<&StatusEffect> {
-        self.effects.get(effect_type)
-    }
-
-    pub fn update(&mut self, delta_time: f32) -> Vec<StatusEffectType> {
-        let mut expired = Vec::new();
-
-        for (effect_type, effect) in &mut self.effects {
-            effect.duration -= delta_time;
-            if effect.duration <= 0.0 {
-                expired.push(effect_type.clone());
-            }
-        }
-
-        // Remove expired effects
-        for effect_type in &expired {
-            self.effects.remove(effect_type);
-        }
-
-        expired
-    }
-}
-
-/// Individual status effect
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct StatusEffect {
-    pub power: i32,
-    pub duration: f32,
-    pub tick_interval: f32,
-    pub last_tick: f32,
-    pub source: Option<Entity>,
-}
-
-impl StatusEffect {
-    pub fn new(power: i32, duration: f32) -> Self {
-        StatusEffect {
-            power,
-            duration,
-            tick_interval: 1.0, // Default to 1 second ticks
-            last_tick: 0.0,
-            source: None,
-        }
-    }
-
-    pub fn with_tick_interval(mut self, interval: f32) -> Self {
-        self.tick_interval = interval;
-        self
-    }
-
-    pub fn with_source(mut self, source: Entity) -> Self {
-        self.source = Some(source);
-        self
-    }
-
-    pub fn should_tick(&mut self, delta_time: f32) -> bool {
-        self.last_tick += delta_time;
-        if self.last_tick >= self.tick_interval {
-            self.last_tick = 0.0;
-            true
-        } else {
-            false
-        }
-    }
-}
-
 /// Intent component for using consumables
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
@@ -472,6 +328,11 @@ impl<'a> System<'a> for ConsumableUsageSystem {
         WriteStorage<'a, ConsumableCooldowns>,
         WriteStorage<'a, StatusEffects>,
         WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, Equipment>,
+        WriteStorage<'a, MagicalItem>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, ItemStack>,
+        ReadStorage<'a, ItemProperties>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, Player>,
         Write<'a, GameLog>,
@@ -486,6 +347,11 @@ impl<'a> System<'a> for ConsumableUsageSystem {
             mut cooldowns,
             mut status_effects,
             mut combat_stats,
+            mut equipment,
+            mut magical_items,
+            mut inventories,
+            mut stacks,
+            properties,
             names,
             players,
             mut gamelog,
@@ -520,9 +386,10 @@ impl<'a> System<'a> for ConsumableUsageSystem {
                             self.apply_consumable_effects(
                                 &consumable.effects.clone(),
                                 target_entity,
-                                item_entity,
                                 &mut status_effects,
                                 &mut combat_stats,
+                                &mut equipment,
+                                &mut magical_items,
                                 &mut gamelog,
                                 &mut rng,
                             );
@@ -535,6 +402,7 @@ impl<'a> System<'a> for ConsumableUsageSystem {
                             }
 
                             // Use charge
+                            let charges = consumable.charges;
                             if !consumable.use_charge() {
                                 gamelog.entries.push("Item has no charges remaining".to_string());
                             }
@@ -553,8 +421,33 @@ impl<'a> System<'a> for ConsumableUsageSystem {
                                 gamelog.entries.push(format!("{} uses {}", user_name, item_name));
                             }
 
+                            // The next of a stack takes over from a spent one
+                            let rest_of_stack = match stacks.get_mut(item_entity) {
+                                Some(stack) if consumable.is_depleted() && stack.quantity > 1 => {
+                                    stack.quantity -= 1;
+                                    true
+                                },
+                                _ => false,
+                            };
+                            if rest_of_stack {
+                                consumable.charges = charges;
+                                if let Some(inventory) = inventories.get_mut(entity) {
+                                    if let Some(slot_index) = inventory.find_item(item_entity) {
+                                        let weight = properties.get(item_entity).map_or(0.0, |p| p.weight);
+                                        inventory.remove_item(slot_index, 1, weight);
+                                    }
+                                }
+                            }
+
                             // Remove item if depleted
                             if consumable.is_depleted() {
+                                if let Some(inventory) = inventories.get_mut(entity) {
+                                    if let Some(slot_index) = inventory.find_item(item_entity) {
+                                        let weight = properties.get(item_entity).map_or(0.0, |p| p.weight);
+                                        let quantity = inventory.items[slot_index].quantity;
+                                        inventory.remove_item(slot_index, quantity, weight);
+                                    }
+                                }
                                 entities.delete(item_entity).expect("Failed to delete depleted consumable");
                             }
                         }
@@ -580,9 +473,10 @@ impl ConsumableUsageSystem {
         &self,
         effects: &[ConsumableEffect],
         target: Entity,
-        source: Entity,
         status_effects: &mut WriteStorage<StatusEffects>,
         combat_stats: &mut WriteStorage<CombatStats>,
+        equipment: &mut WriteStorage<Equipment>,
+        magical_items: &mut WriteStorage<MagicalItem>,
         gamelog: &mut GameLog,
         rng: &mut RandomNumberGenerator,
     ) {
@@ -590,14 +484,12 @@ impl ConsumableUsageSystem {
             match effect {
                 ConsumableEffect::Healing { amount, over_time } => {
                     if *over_time {
-                        // Apply regeneration effect
-                        let regen_effect = StatusEffect::new(*amount / 10, 10.0) // Heal over 10 seconds
-                            .with_tick_interval(1.0)
-                            .with_source(source);
+                        // Apply regeneration effect, healing over 10 turns
+                        let regen_effect = StatusEffect::new(StatusEffectType::Regeneration, 10, (*amount / 10).max(1));
                         
                         status_effects.entry(target)
                             .or_insert_with(StatusEffects::new)
-                            .add_effect(StatusEffectType::Regeneration, regen_effect);
+                            .add_effect(regen_effect);
                         
                         gamelog.entries.push(format!("Regeneration effect applied"));
                     } else {
@@ -616,42 +508,60 @@ impl ConsumableUsageSystem {
                     }
                 },
                 ConsumableEffect::StatusEffect { effect_type, duration, power } => {
-                    let effect = StatusEffect::new(*power, *duration).with_source(source);
+                    let effect = StatusEffect::new(*effect_type, effect_turns(*duration), *power);
                     status_effects.entry(target)
                         .or_insert_with(StatusEffects::new)
-                        .add_effect(effect_type.clone(), effect);
+                        .add_effect(effect);
                     
-                    gamelog.entries.push(format!("{:?} effect applied", effect_type));
+                    gamelog.entries.push(format!("{} effect applied", effect_type.name()));
                 },
                 ConsumableEffect::AttributeBoost { attribute, amount, duration } => {
                     // Convert attribute boost to status effect
                     let effect_type = match attribute.as_str() {
-                        "Strength" => StatusEffectType::Strength,
-                        "Dexterity" => StatusEffectType::Dexterity,
-                        "Constitution" => StatusEffectType::Constitution,
-                        "Intelligence" => StatusEffectType::Intelligence,
-                        "Wisdom" => StatusEffectType::Wisdom,
-                        "Charisma" => StatusEffectType::Charisma,
+                        "Strength" => StatusEffectType::StrengthBoost,
+                        "Dexterity" => StatusEffectType::DexterityBoost,
+                        "Constitution" => StatusEffectType::ConstitutionBoost,
+                        "Intelligence" => StatusEffectType::IntelligenceBoost,
+                        "Wisdom" => StatusEffectType::WisdomBoost,
+                        "Charisma" => StatusEffectType::CharismaBoost,
                         _ => StatusEffectType::Blessed, // Generic boost
                     };
                     
-                    let effect = StatusEffect::new(*amount, *duration).with_source(source);
+                    let effect = StatusEffect::new(effect_type, effect_turns(*duration), *amount);
                     status_effects.entry(target)
                         .or_insert_with(StatusEffects::new)
-                        .add_effect(effect_type, effect);
+                        .add_effect(effect);
                     
                     gamelog.entries.push(format!("{} increased by {}", attribute, amount));
                 },
                 ConsumableEffect::CureCondition { condition } => {
                     if let Some(effects) = status_effects.get_mut(target) {
-                        if effects.has_effect(condition) {
-                            effects.remove_effect(condition);
-                            gamelog.entries.push(format!("{:?} cured", condition));
+                        if effects.has_effect(*condition) {
+                            effects.remove_effect(*condition);
+                            gamelog.entries.push(format!("{} cured", condition.name()));
                         } else {
                             gamelog.entries.push("No condition to cure".to_string());
                         }
                     }
                 },
+                ConsumableEffect::RemoveCurse => {
+                    let lifted = match equipment.get_mut(target) {
+                        Some(gear) => {
+                            let lifted = lift_equipped_curses(gear, magical_items);
+                            if lifted > 0 {
+                                gear.dirty = true;
+                            }
+                            lifted
+                        },
+                        None => 0,
+                    };
+
+                    if lifted > 0 {
+                        gamelog.entries.push("You feel as if someone is watching over you".to_string());
+                    } else {
+                        gamelog.entries.push("You feel a malignant aura resist the scroll".to_string());
+                    }
+                },
                 _ => {
                     // TODO: Implement other effect types
                     gamelog.entries.push("Effect not yet implemented".to_string());
@@ -661,77 +571,27 @@ impl ConsumableUsageSystem {
     }
 }
 
-/// System for updating cooldowns and status effects
+/// Consumable durations are authored in seconds; the status engine counts one turn per second
+fn effect_turns(duration: f32) -> i32 {
+    duration.ceil().max(1.0) as i32
+}
+
+/// System for updating consumable cooldowns; status effects tick in the status effect system
 pub struct ConsumableUpdateSystem;
 
 impl<'a> System<'a> for ConsumableUpdateSystem {
     type SystemData = (
         Entities<'a>,
         WriteStorage<'a, ConsumableCooldowns>,
-        WriteStorage<'a, StatusEffects>,
-        WriteStorage<'a, CombatStats>,
-        Write<'a, GameLog>,
         ReadExpect<'a, f32>, // Delta time
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut cooldowns, mut status_effects, mut combat_stats, mut gamelog, delta_time) = data;
+        let (entities, mut cooldowns, delta_time) = data;
 
-        // Update cooldowns
         for (_, cooldown) in (&entities, &mut cooldowns).join() {
             cooldown.update(*delta_time);
         }
-
-        // Update status effects
-        for (entity, effects) in (&entities, &mut status_effects).join() {
-            let expired = effects.update(*delta_time);
-            
-            // Apply status effect ticks
-            for (effect_type, effect) in &mut effects.effects {
-                if effect.should_tick(*delta_time) {
-                    self.apply_status_effect_tick(entity, effect_type, effect, &mut combat_stats, &mut gamelog);
-                }
-            }
-
-            // Log expired effects
-            for effect_type in expired {
-                gamelog.entries.push(format!("{:?} effect has worn off", effect_type));
-            }
-        }
-    }
-}
-
-impl ConsumableUpdateSystem {
-    fn apply_status_effect_tick(
-        &self,
-        entity: Entity,
-        effect_type: &StatusEffectType,
-        effect: &StatusEffect,
-        combat_stats: &mut WriteStorage<CombatStats>,
-        gamelog: &mut GameLog,
-    ) {
-        match effect_type {
-            StatusEffectType::Regeneration => {
-                if let Some(stats) = combat_stats.get_mut(entity) {
-                    let old_hp = stats.hp;
-                    stats.hp = (stats.hp + effect.power).min(stats.max_hp);
-                    let healed = stats.hp - old_hp;
-                    
-                    if healed > 0 {
-                        gamelog.entries.push(format!("Regenerated {} health", healed));
-                    }
-                }
-            },
-            StatusEffectType::Poison => {
-                if let Some(stats) = combat_stats.get_mut(entity) {
-                    stats.hp = (stats.hp - effect.power).max(0);
-                    gamelog.entries.push(format!("Poison deals {} damage", effect.power));
-                }
-            },
-            _ => {
-                // Other status effects don't tick
-            }
-        }
     }
 }
 
@@ -796,14 +656,13 @@ mod tests {
     #[test]
     fn test_status_effects() {
         let mut effects = StatusEffects::new();
-        let regen_effect = StatusEffect::new(5, 10.0);
-        
-        effects.add_effect(StatusEffectType::Regeneration, regen_effect);
-        assert!(effects.has_effect(&StatusEffectType::Regeneration));
+        effects.add_effect(StatusEffect::new(StatusEffectType::Regeneration, effect_turns(2.0), 5));
+        assert!(effects.has_effect(StatusEffectType::Regeneration));
         
-        let expired = effects.update(15.0); // More than duration
+        assert!(effects.update_effects().is_empty());
+        let expired = effects.update_effects();
         assert!(expired.contains(&StatusEffectType::Regeneration));
-        assert!(!effects.has_effect(&StatusEffectType::Regeneration));
+        assert!(!effects.has_effect(StatusEffectType::Regeneration));
     }
 
     #[test]
@@ -834,7 +693,7 @@ mod tests {
     fn test_consumable_effects() {
         let healing_effect = ConsumableEffect::Healing { amount: 25, over_time: false };
         let status_effect = ConsumableEffect::StatusEffect {
-            effect_type: StatusEffectType::Strength,
+            effect_type: StatusEffectType::StrengthBoost,
             duration: 60.0,
             power: 3,
         };
@@ -850,7 +709,7 @@ mod tests {
         
         match status_effect {
             ConsumableEffect::StatusEffect { effect_type, duration, power } => {
-                assert_eq!(effect_type, StatusEffectType::Strength);
+                assert_eq!(effect_type, StatusEffectType::StrengthBoost);
                 assert_eq!(duration, 60.0);
                 assert_eq!(power, 3);
             },
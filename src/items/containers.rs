@@ -1,14 +1,16 @@
 use specs::{Component, VecStorage, System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
-use crate::components::{Position, Player, Name, Renderable};
+use crate::components::{Position, Player, Name, Renderable, DamageType, GasKind, StatusEffectType};
 use crate::items::{ItemProperties, ItemType, ItemRarity};
 use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::systems::{AreaEffect, AreaEffectQueue, AreaTemplate, FriendlyFire, GasReleases, TRAP_GAS_VOLUME};
 
 /// Component for containers that can hold items
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct Container {
+    #[serde(with = "crate::persistence::entity_refs::vec")]
     pub contents: Vec<Entity>,
     pub capacity: usize,
     pub is_open: bool,
@@ -245,6 +247,9 @@ impl<'a> System<'a> for ContainerSystem {
         WriteStorage<'a, Container>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        Write<'a, AreaEffectQueue>,
+        Write<'a, GasReleases>,
         Write<'a, GameLog>,
         Write<'a, RandomNumberGenerator>,
     );
@@ -259,6 +264,9 @@ impl<'a> System<'a> for ContainerSystem {
             mut containers,
             names,
             players,
+            positions,
+            mut area_effects,
+            mut gas,
             mut gamelog,
             mut rng,
         ) = data;
@@ -314,7 +322,8 @@ impl<'a> System<'a> for ContainerSystem {
                         
                         if detect_roll < 15 {
                             // Trap triggers
-                            self.trigger_trap(entity, trap_type, &mut gamelog, &mut rng, &players);
+                            let trap_site = positions.get(container_entity).map(|pos| (pos.x, pos.y));
+                            self.trigger_trap(entity, trap_type, trap_site, &mut area_effects, &mut gas, &mut gamelog, &mut rng, &players);
                         } else {
                             if players.get(entity).is_some() {
                                 gamelog.entries.push(format!("You notice a {} on the {} and avoid it.", trap_type.name(), container_name));
@@ -471,12 +480,35 @@ impl ContainerSystem {
         &self,
         target: Entity,
         trap_type: TrapType,
+        trap_site: Option<(i32, i32)>,
+        area_effects: &mut AreaEffectQueue,
+        gas: &mut GasReleases,
         gamelog: &mut GameLog,
         rng: &mut RandomNumberGenerator,
         players: &ReadStorage<Player>,
     ) {
         let damage = trap_type.damage();
         
+        // Explosions catch everyone standing near the container, not just whoever opened it
+        if let (TrapType::Explosion, Some(site)) = (trap_type, trap_site) {
+            area_effects.push(AreaEffect {
+                source: None,
+                origin: site,
+                target: site,
+                template: AreaTemplate::Circle { radius: 2 },
+                friendly_fire: FriendlyFire::Everyone,
+                damage,
+                damage_type: DamageType::Fire,
+                status: Some((StatusEffectType::Burning, 2, 2)),
+                name: "explosion".to_string(),
+            });
+        }
+        
+        // Gas spills out and drifts through the room
+        if let (TrapType::Gas, Some(site)) = (trap_type, trap_site) {
+            gas.release(site, GasKind::PoisonGas, TRAP_GAS_VOLUME);
+        }
+        
         if players.get(target).is_some() {
             match trap_type {
                 TrapType::Poison => {
@@ -484,15 +516,14 @@ impl ContainerSystem {
                     // TODO: Apply poison status effect
                 },
                 TrapType::Explosion => {
-                    gamelog.entries.push(format!("The container explodes for {} damage!", damage));
+                    gamelog.entries.push("The container explodes!".to_string());
                 },
                 TrapType::Needle => {
                     gamelog.entries.push(format!("A poisoned needle pricks you for {} damage!", damage));
                     // TODO: Apply poison status effect
                 },
                 TrapType::Gas => {
-                    gamelog.entries.push(format!("Poisonous gas escapes, dealing {} damage!", damage));
-                    // TODO: Apply poison status effect
+                    gamelog.entries.push("Poisonous gas billows out of the container!".to_string());
                 },
                 TrapType::Curse => {
                     gamelog.entries.push("You feel a dark curse settle upon you!".to_string());
@@ -796,6 +827,7 @@ mod tests {
         world.register::<Renderable>();
         world.insert(GameLog::new());
         world.insert(RandomNumberGenerator::new());
+        world.insert(AreaEffectQueue::default());
         world
     }
 
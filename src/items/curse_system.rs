@@ -0,0 +1,309 @@
+use specs::{Component, VecStorage, System, WriteStorage, ReadStorage, ReadExpect, Entities, Entity, Join, Write, Read};
+use serde::{Serialize, Deserialize};
+use crate::components::{Player, Name, Position, Viewshed, Background, Difficulty, GameSettings};
+use crate::items::{
+    MagicalItem, Curse, ItemIdentification, Equipment, EquipmentWear, WearReason, AdvancedInventory
+};
+use crate::map::{Map, TileType};
+use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::systems::{Faction, Reputation, Standing};
+
+/// Where a curse removal is coming from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CurseRemovalSource {
+    /// A Remove Curse scroll read by the wearer
+    Scroll,
+    /// The temple cleric, for a fee
+    Cleric,
+}
+
+/// Intent component for lifting the curse on an item
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToRemoveCurse {
+    pub item: Entity,
+    pub source: CurseRemovalSource,
+}
+
+/// Temple service that lifts any curse for gold
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClericService {
+    pub base_cost: i32,
+    pub cost_per_power: i32,
+    /// Curses a scroll can't lift cost this many times more
+    pub stubborn_multiplier: i32,
+    pub price_multiplier: f32,
+}
+
+impl Default for ClericService {
+    fn default() -> Self {
+        ClericService {
+            base_cost: 50,
+            cost_per_power: 25,
+            stubborn_multiplier: 3,
+            price_multiplier: 1.0,
+        }
+    }
+}
+
+impl ClericService {
+    /// A cleric charging the prices of the given difficulty
+    pub fn for_difficulty(difficulty: Difficulty) -> Self {
+        ClericService {
+            price_multiplier: difficulty.price_multiplier(),
+            ..Default::default()
+        }
+    }
+
+    /// The cleric serving someone: the difficulty's prices, less whatever their background haggles off,
+    /// and more or less again for how the town sees them
+    pub fn for_customer(difficulty: Difficulty, background: Option<&Background>, standing: Standing) -> Self {
+        let mut service = ClericService::for_difficulty(difficulty);
+        service.price_multiplier *= background.map_or(1.0, |background| background.background_type.price_multiplier());
+        service.price_multiplier *= standing.price_multiplier();
+        service
+    }
+
+    pub fn remove_curse_cost(&self, curse: &Curse) -> i32 {
+        let mut cost = self.base_cost + curse.power * self.cost_per_power;
+        if !curse.removable {
+            cost *= self.stubborn_multiplier;
+        }
+        (cost as f32 * self.price_multiplier).ceil() as i32
+    }
+
+    /// Lift the item's curse, spending gold. Returns the gold spent.
+    pub fn remove_curse(&self, magical: &mut MagicalItem, gold: &mut i32) -> Result<i32, String> {
+        let cost = match &magical.curse {
+            Some(curse) => self.remove_curse_cost(curse),
+            None => return Err("The cleric finds no curse on that item".to_string()),
+        };
+        if *gold < cost {
+            return Err(format!("The cleric asks {} gold to lift the curse", cost));
+        }
+
+        *gold -= cost;
+        magical.remove_curse();
+        Ok(cost)
+    }
+}
+
+/// Lift every scroll-removable curse on an entity's equipment; returns how many were lifted
+pub fn lift_equipped_curses(gear: &Equipment, magical_items: &mut WriteStorage<MagicalItem>) -> i32 {
+    let mut lifted = 0;
+    for item in gear.get_all_equipped() {
+        if let Some(magical) = magical_items.get_mut(item) {
+            if magical.curse.as_ref().map_or(false, |curse| curse.removable) {
+                magical.remove_curse();
+                lifted += 1;
+            }
+        }
+    }
+    lifted
+}
+
+/// System that reveals curses, runs their on-hit effects and handles curse removal
+pub struct CurseSystem;
+
+impl<'a> System<'a> for CurseSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MagicalItem>,
+        WriteStorage<'a, WantsToRemoveCurse>,
+        ReadStorage<'a, ItemIdentification>,
+        WriteStorage<'a, Equipment>,
+        ReadStorage<'a, EquipmentWear>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, Background>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, Map>,
+        Read<'a, Reputation>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut magical_items,
+            mut wants_remove,
+            identifications,
+            mut equipment,
+            wear,
+            mut inventories,
+            mut positions,
+            mut viewsheds,
+            settings,
+            backgrounds,
+            players,
+            names,
+            map,
+            reputation,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        // Identifying an item shows any curse on it
+        for (item, identification, magical) in (&entities, &identifications, &mut magical_items).join() {
+            if identification.identified {
+                if let Some(curse) = magical.reveal_curse() {
+                    let item_name = names.get(item).map_or("item", |n| n.name.as_str());
+                    gamelog.add_entry(format!("The {} bears a curse: {}.", item_name, curse.name));
+                }
+            }
+        }
+
+        for (wearer, gear) in (&entities, &mut equipment).join() {
+            let is_player = players.contains(wearer);
+            let struck = wear.get(wearer).map_or(0, |pending| {
+                pending.events.iter().filter(|reason| **reason == WearReason::Struck).count()
+            });
+
+            let mut teleport = false;
+            for item in gear.get_all_equipped() {
+                let magical = match magical_items.get_mut(item) {
+                    Some(magical) if magical.is_cursed() => magical,
+                    _ => continue,
+                };
+                let curse = magical.curse.clone().expect("cursed item without a curse");
+
+                // Stat penalties bite the moment the item is worn; fragility the moment it wears
+                let triggered = curse.is_stat_penalty()
+                    || (curse.wear_multiplier() > 1 && wear.contains(wearer));
+
+                for _ in 0..struck {
+                    if rng.roll_dice(1, 100) <= curse.teleport_chance() {
+                        teleport = true;
+                    }
+                }
+
+                if (triggered || teleport) && magical.reveal_curse().is_some() {
+                    gear.dirty = true;
+                    if is_player {
+                        let item_name = names.get(item).map_or("item", |n| n.name.as_str());
+                        gamelog.add_entry(format!("Your {} is cursed! {}", item_name, curse.description));
+                    }
+                }
+            }
+
+            if teleport {
+                if let Some((x, y)) = random_floor_tile(&map, &mut rng) {
+                    if let Some(pos) = positions.get_mut(wearer) {
+                        pos.x = x;
+                        pos.y = y;
+                    }
+                    if let Some(viewshed) = viewsheds.get_mut(wearer) {
+                        viewshed.dirty = true;
+                    }
+                    if is_player {
+                        gamelog.add_entry("The blow tears you through space!".to_string());
+                    }
+                }
+            }
+        }
+
+        // Curse removal requests
+        for (entity, request) in (&entities, &wants_remove).join() {
+            let magical = match magical_items.get_mut(request.item) {
+                Some(magical) if magical.is_cursed() => magical,
+                _ => {
+                    gamelog.add_entry("There is no curse to lift.".to_string());
+                    continue;
+                },
+            };
+            let item_name = names.get(request.item).map_or("item".to_string(), |n| n.name.clone());
+
+            match request.source {
+                CurseRemovalSource::Scroll => {
+                    if magical.curse.as_ref().map_or(false, |curse| curse.removable) {
+                        magical.remove_curse();
+                        gamelog.add_entry(format!("The curse on your {} is lifted.", item_name));
+                    } else {
+                        magical.reveal_curse();
+                        gamelog.add_entry(format!("The curse on your {} is too strong for the scroll.", item_name));
+                    }
+                },
+                CurseRemovalSource::Cleric => {
+                    let difficulty = settings.get(entity).map_or(Difficulty::Normal, |s| s.difficulty);
+                    let cleric = ClericService::for_customer(difficulty, backgrounds.get(entity), reputation.standing(Faction::Townsfolk));
+                    match inventories.get_mut(entity).map(|inventory| cleric.remove_curse(magical, &mut inventory.gold)) {
+                        Some(Ok(cost)) => {
+                            gamelog.add_entry(format!("The cleric lifts the curse on your {} for {} gold.", item_name, cost));
+                        },
+                        Some(Err(message)) => gamelog.add_entry(message),
+                        None => {},
+                    }
+                },
+            }
+
+            if let Some(gear) = equipment.get_mut(entity) {
+                if gear.find_item_slot(request.item).is_some() {
+                    gear.dirty = true;
+                }
+            }
+        }
+        wants_remove.clear();
+    }
+}
+
+fn random_floor_tile(map: &Map, rng: &mut RandomNumberGenerator) -> Option<(i32, i32)> {
+    for _ in 0..100 {
+        let x = rng.range(1, map.width - 2);
+        let y = rng.range(1, map.height - 2);
+        let idx = map.xy_idx(x, y);
+        if map.tiles[idx] == TileType::Floor && !map.blocked[idx] {
+            return Some((x, y));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::CurseType;
+
+    fn curse(curse_type: CurseType, power: i32, removable: bool) -> Curse {
+        Curse {
+            name: "Test".to_string(),
+            description: String::new(),
+            curse_type,
+            power,
+            removable,
+            revealed: false,
+        }
+    }
+
+    #[test]
+    fn test_cleric_charges_more_for_stubborn_curses() {
+        let cleric = ClericService::default();
+        assert_eq!(cleric.remove_curse_cost(&curse(CurseType::Binding, 2, true)), 100);
+        assert_eq!(cleric.remove_curse_cost(&curse(CurseType::Binding, 2, false)), 300);
+
+        let mut magical = MagicalItem::new(1);
+        magical.add_curse(curse(CurseType::Fragility, 1, false));
+        let mut gold = 100;
+        assert!(cleric.remove_curse(&mut magical, &mut gold).is_err());
+
+        gold = 500;
+        assert_eq!(cleric.remove_curse(&mut magical, &mut gold), Ok(225));
+        assert!(!magical.is_cursed());
+    }
+
+    #[test]
+    fn test_curses_stay_hidden_until_revealed() {
+        let mut magical = MagicalItem::new(1);
+        magical.add_curse(curse(CurseType::Teleportation, 3, true));
+
+        assert!(magical.is_cursed());
+        assert!(!magical.has_known_curse());
+        assert!(magical.reveal_curse().is_some());
+        assert!(magical.reveal_curse().is_none());
+        assert!(magical.has_known_curse());
+        assert_eq!(magical.curse.as_ref().unwrap().teleport_chance(), 30);
+    }
+}
@@ -0,0 +1,524 @@
+use specs::{Component, VecStorage, System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read};
+use serde::{Serialize, Deserialize};
+use crate::components::{Player, Name, DeathState, DeathPenalty, Background, Difficulty, GameSettings};
+use crate::items::{ItemProperties, ItemType, Equipment, EquipmentSlot, AdvancedInventory, MagicalItem};
+use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::systems::{Faction, Reputation, Standing};
+
+/// Why an entity's equipment is losing durability
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum WearReason {
+    /// The entity landed a hit with its weapon
+    Attack,
+    /// The entity blocked an attack with its shield
+    Block,
+    /// The entity was hit and its armor absorbed the blow
+    Struck,
+}
+
+impl WearReason {
+    /// Equipment slots that take wear for this reason
+    pub fn affected_slots(&self) -> &'static [EquipmentSlot] {
+        match self {
+            WearReason::Attack => &[EquipmentSlot::MainHand],
+            WearReason::Block => &[EquipmentSlot::OffHand],
+            WearReason::Struck => &[
+                EquipmentSlot::Head, EquipmentSlot::Chest, EquipmentSlot::Legs,
+                EquipmentSlot::Feet, EquipmentSlot::Hands,
+            ],
+        }
+    }
+
+    /// Base durability lost per event
+    pub fn base_wear(&self) -> i32 {
+        match self {
+            WearReason::Attack => 1,
+            WearReason::Block => 2,
+            WearReason::Struck => 1,
+        }
+    }
+}
+
+/// Pending wear events for an entity's equipment, consumed by `DurabilitySystem`
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct EquipmentWear {
+    pub events: Vec<WearReason>,
+}
+
+impl EquipmentWear {
+    pub fn add(store: &mut WriteStorage<EquipmentWear>, entity: Entity, reason: WearReason) {
+        if let Some(wear) = store.get_mut(entity) {
+            wear.events.push(reason);
+        } else {
+            store.insert(entity, EquipmentWear { events: vec![reason] })
+                .expect("Unable to insert equipment wear");
+        }
+    }
+}
+
+/// Component for consumable repair kits
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct RepairKit {
+    pub repair_amount: i32,
+    pub uses_remaining: i32,
+    pub kit_type: RepairKitType,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum RepairKitType {
+    Weaponsmith,
+    Armorsmith,
+    Universal,
+}
+
+impl RepairKit {
+    pub fn new(kit_type: RepairKitType, repair_amount: i32, uses: i32) -> Self {
+        RepairKit {
+            repair_amount,
+            uses_remaining: uses,
+            kit_type,
+        }
+    }
+
+    pub fn can_repair(&self, item_type: &ItemType) -> bool {
+        if self.uses_remaining <= 0 {
+            return false;
+        }
+
+        match (self.kit_type, item_type) {
+            (RepairKitType::Universal, ItemType::Weapon(_) | ItemType::Armor(_)) => true,
+            (RepairKitType::Weaponsmith, ItemType::Weapon(_)) => true,
+            (RepairKitType::Armorsmith, ItemType::Armor(_)) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_used_up(&self) -> bool {
+        self.uses_remaining <= 0
+    }
+}
+
+/// Where a repair is coming from
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepairSource {
+    Kit(Entity),
+    Blacksmith,
+}
+
+/// Intent component for repairing an item
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToRepair {
+    pub item: Entity,
+    pub source: RepairSource,
+}
+
+/// Blacksmith repair service offered in town
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlacksmithService {
+    pub cost_per_point: f32,
+    pub minimum_cost: i32,
+    pub price_multiplier: f32,
+}
+
+impl Default for BlacksmithService {
+    fn default() -> Self {
+        BlacksmithService {
+            cost_per_point: 0.5,
+            minimum_cost: 5,
+            price_multiplier: 1.0,
+        }
+    }
+}
+
+impl BlacksmithService {
+    /// A blacksmith charging the prices of the given difficulty
+    pub fn for_difficulty(difficulty: Difficulty) -> Self {
+        BlacksmithService {
+            price_multiplier: difficulty.price_multiplier(),
+            ..Default::default()
+        }
+    }
+
+    /// The blacksmith serving someone: the difficulty's prices, less whatever their background haggles off,
+    /// and more or less again for how the town sees them
+    pub fn for_customer(difficulty: Difficulty, background: Option<&Background>, standing: Standing) -> Self {
+        let mut service = BlacksmithService::for_difficulty(difficulty);
+        service.price_multiplier *= background.map_or(1.0, |background| background.background_type.price_multiplier());
+        service.price_multiplier *= standing.price_multiplier();
+        service
+    }
+
+    /// Gold required to fully repair an item; zero if it needs no repair
+    pub fn repair_cost(&self, props: &ItemProperties) -> i32 {
+        match &props.durability {
+            Some(durability) if durability.current < durability.max => {
+                let missing = durability.max - durability.current;
+                let rarity_factor = props.rarity.value_multiplier().max(1.0);
+                let cost = (missing as f32 * self.cost_per_point * rarity_factor).ceil() as i32;
+                (cost.max(self.minimum_cost) as f32 * self.price_multiplier).ceil() as i32
+            },
+            _ => 0,
+        }
+    }
+
+    /// Fully repair an item, spending gold. Returns the gold spent.
+    pub fn repair(&self, props: &mut ItemProperties, gold: &mut i32) -> Result<i32, String> {
+        let cost = self.repair_cost(props);
+        if cost == 0 {
+            return Err(format!("The {} doesn't need repairs", props.name));
+        }
+        if *gold < cost {
+            return Err(format!("You need {} gold to repair the {}", cost, props.name));
+        }
+
+        *gold -= cost;
+        if let Some(durability) = &props.durability {
+            let missing = durability.max - durability.current;
+            props.repair(missing);
+        }
+        Ok(cost)
+    }
+}
+
+/// Whether an equipped item still provides its bonuses
+pub fn is_item_functional(props: Option<&ItemProperties>) -> bool {
+    props.map_or(true, |p| !p.is_broken())
+}
+
+/// System that applies wear to equipped items and death durability penalties
+pub struct DurabilitySystem;
+
+impl<'a> System<'a> for DurabilitySystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, EquipmentWear>,
+        WriteStorage<'a, Equipment>,
+        WriteStorage<'a, ItemProperties>,
+        WriteStorage<'a, DeathState>,
+        ReadStorage<'a, DeathPenalty>,
+        ReadStorage<'a, MagicalItem>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wear,
+            mut equipment,
+            mut item_properties,
+            mut death_states,
+            death_penalties,
+            magical_items,
+            players,
+            names,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        // Apply combat wear
+        for (entity, pending, equipment) in (&entities, &wear, &mut equipment).join() {
+            let is_player = players.get(entity).is_some();
+
+            for reason in &pending.events {
+                let slots = reason.affected_slots();
+                let candidates: Vec<Entity> = slots.iter()
+                    .filter_map(|slot| equipment.get_equipped(slot))
+                    .collect();
+
+                // Armor only takes wear on the piece that absorbed the blow
+                let targets = if *reason == WearReason::Struck && !candidates.is_empty() {
+                    let index = (rng.roll_dice(1, candidates.len() as i32) - 1) as usize;
+                    vec![candidates[index]]
+                } else {
+                    candidates
+                };
+
+                for item in targets {
+                    // Fragile curses make an item wear faster
+                    let multiplier = magical_items.get(item)
+                        .and_then(|magical| magical.curse.as_ref())
+                        .map_or(1, |curse| curse.wear_multiplier());
+                    if self.wear_item(item, reason.base_wear() * multiplier, &mut item_properties, &names, is_player, &mut gamelog) {
+                        equipment.dirty = true;
+                    }
+                }
+            }
+        }
+        wear.clear();
+
+        // Apply the durability loss from death penalties
+        for (entity, death_state, penalty, equipment) in (&entities, &mut death_states, &death_penalties, &mut equipment).join() {
+            if !death_state.is_dead || death_state.durability_penalty_applied {
+                continue;
+            }
+            death_state.durability_penalty_applied = true;
+
+            if penalty.equipment_durability_loss <= 0 {
+                continue;
+            }
+
+            let is_player = players.get(entity).is_some();
+            for item in equipment.get_all_equipped() {
+                if self.wear_item(item, penalty.equipment_durability_loss, &mut item_properties, &names, is_player, &mut gamelog) {
+                    equipment.dirty = true;
+                }
+            }
+
+            if is_player {
+                gamelog.add_entry("Your equipment has been damaged by your death.".to_string());
+            }
+        }
+    }
+}
+
+impl DurabilitySystem {
+    /// Damage an item; returns true if the item broke as a result
+    fn wear_item(
+        &self,
+        item: Entity,
+        amount: i32,
+        item_properties: &mut WriteStorage<ItemProperties>,
+        names: &ReadStorage<Name>,
+        is_player: bool,
+        gamelog: &mut GameLog,
+    ) -> bool {
+        let props = match item_properties.get_mut(item) {
+            Some(props) => props,
+            None => return false,
+        };
+
+        if props.durability.is_none() || props.is_broken() {
+            return false;
+        }
+
+        props.damage(amount);
+
+        if props.is_broken() {
+            if is_player {
+                let item_name = names.get(item)
+                    .map(|n| n.name.clone())
+                    .unwrap_or_else(|| props.name.clone());
+                gamelog.add_entry(format!("Your {} breaks!", item_name));
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// System that processes repair requests from kits and the blacksmith
+pub struct RepairSystem;
+
+impl<'a> System<'a> for RepairSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToRepair>,
+        WriteStorage<'a, ItemProperties>,
+        WriteStorage<'a, RepairKit>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, Equipment>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, Background>,
+        Read<'a, Reputation>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_repair,
+            mut item_properties,
+            mut repair_kits,
+            mut inventories,
+            mut equipment,
+            settings,
+            backgrounds,
+            reputation,
+            mut gamelog,
+        ) = data;
+
+        let mut used_up_kits = Vec::new();
+
+        for (entity, request) in (&entities, &wants_repair).join() {
+            let props = match item_properties.get_mut(request.item) {
+                Some(props) => props,
+                None => continue,
+            };
+
+            let repaired = match &request.source {
+                RepairSource::Kit(kit_entity) => {
+                    match repair_kits.get_mut(*kit_entity) {
+                        // A kit is only spent on something that is actually worn
+                        Some(_) if !props.needs_repair() => {
+                            gamelog.add_entry(format!("The {} doesn't need repairs.", props.name));
+                            false
+                        },
+                        Some(kit) if kit.can_repair(&props.item_type) => {
+                            props.repair(kit.repair_amount);
+                            kit.uses_remaining -= 1;
+                            if kit.is_used_up() {
+                                used_up_kits.push((entity, *kit_entity));
+                            }
+                            gamelog.add_entry(format!("You repair the {}.", props.name));
+                            true
+                        },
+                        Some(_) => {
+                            gamelog.add_entry(format!("That kit can't repair the {}.", props.name));
+                            false
+                        },
+                        None => false,
+                    }
+                },
+                RepairSource::Blacksmith => {
+                    if let Some(inventory) = inventories.get_mut(entity) {
+                        let difficulty = settings.get(entity).map_or(Difficulty::Normal, |s| s.difficulty);
+                        let blacksmith = BlacksmithService::for_customer(difficulty, backgrounds.get(entity), reputation.standing(Faction::Townsfolk));
+                        match blacksmith.repair(props, &mut inventory.gold) {
+                            Ok(cost) => {
+                                gamelog.add_entry(format!("The blacksmith repairs your {} for {} gold.", props.name, cost));
+                                true
+                            },
+                            Err(message) => {
+                                gamelog.add_entry(message);
+                                false
+                            },
+                        }
+                    } else {
+                        false
+                    }
+                },
+            };
+
+            if repaired {
+                if let Some(equipment) = equipment.get_mut(entity) {
+                    if equipment.find_item_slot(request.item).is_some() {
+                        equipment.dirty = true;
+                    }
+                }
+            }
+        }
+
+        wants_repair.clear();
+
+        for (owner, kit) in used_up_kits {
+            if let Some(inventory) = inventories.get_mut(owner) {
+                inventory.items.retain(|slot| slot.entity != kit);
+            }
+            gamelog.add_entry("Your repair kit is used up.".to_string());
+            entities.delete(kit).expect("Unable to delete repair kit");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::{ItemRarity, WeaponType, ArmorType};
+
+    #[test]
+    fn test_repair_kit_applicability() {
+        let kit = RepairKit::new(RepairKitType::Weaponsmith, 20, 3);
+        assert!(kit.can_repair(&ItemType::Weapon(WeaponType::Sword)));
+        assert!(!kit.can_repair(&ItemType::Armor(ArmorType::Chest)));
+
+        let universal = RepairKit::new(RepairKitType::Universal, 10, 0);
+        assert!(!universal.can_repair(&ItemType::Weapon(WeaponType::Axe)));
+        assert!(universal.is_used_up());
+    }
+
+    #[test]
+    fn test_blacksmith_repair_cost() {
+        let blacksmith = BlacksmithService::default();
+        let mut props = ItemProperties::new("Iron Sword".to_string(), ItemType::Weapon(WeaponType::Sword))
+            .with_rarity(ItemRarity::Uncommon)
+            .with_durability(100);
+
+        assert_eq!(blacksmith.repair_cost(&props), 0);
+
+        props.damage(40);
+        assert_eq!(blacksmith.repair_cost(&props), 40);
+
+        props.damage(1);
+        let mut gold = 10;
+        assert!(blacksmith.repair(&mut props, &mut gold).is_err());
+        assert_eq!(gold, 10);
+
+        gold = 100;
+        assert_eq!(blacksmith.repair(&mut props, &mut gold), Ok(41));
+        assert_eq!(gold, 59);
+        assert_eq!(props.durability.as_ref().unwrap().current, 100);
+    }
+
+    #[test]
+    fn test_blacksmith_prices_follow_difficulty() {
+        let mut props = ItemProperties::new("Iron Sword".to_string(), ItemType::Weapon(WeaponType::Sword))
+            .with_rarity(ItemRarity::Uncommon)
+            .with_durability(100);
+        props.damage(40);
+
+        assert_eq!(BlacksmithService::for_difficulty(Difficulty::Easy).repair_cost(&props), 32);
+        assert_eq!(BlacksmithService::for_difficulty(Difficulty::Nightmare).repair_cost(&props), 60);
+
+        // A merchant haggles the price down; anyone else pays in full
+        let merchant = Background { background_type: crate::components::BackgroundType::Merchant };
+        let soldier = Background { background_type: crate::components::BackgroundType::Soldier };
+        let full = BlacksmithService::for_difficulty(Difficulty::Normal).repair_cost(&props);
+        assert_eq!(BlacksmithService::for_customer(Difficulty::Normal, Some(&soldier), Standing::Neutral).repair_cost(&props), full);
+        assert!(BlacksmithService::for_customer(Difficulty::Normal, Some(&merchant), Standing::Neutral).repair_cost(&props) < full);
+
+        // The town charges those it thinks ill of more, and those it honors less
+        assert!(BlacksmithService::for_customer(Difficulty::Normal, Some(&soldier), Standing::Disliked).repair_cost(&props) > full);
+        assert!(BlacksmithService::for_customer(Difficulty::Normal, Some(&soldier), Standing::Honored).repair_cost(&props) < full);
+    }
+
+    #[test]
+    fn test_a_kit_is_not_spent_on_an_unworn_item() {
+        use specs::{Builder, RunNow, World, WorldExt};
+        let mut world = World::new();
+        world.register::<WantsToRepair>();
+        world.register::<ItemProperties>();
+        world.register::<RepairKit>();
+        world.register::<AdvancedInventory>();
+        world.register::<Equipment>();
+        world.register::<GameSettings>();
+        world.register::<Background>();
+        world.insert(Reputation::default());
+        world.insert(GameLog::new(10));
+
+        let sword = world.create_entity()
+            .with(ItemProperties::new("Iron Sword".to_string(), ItemType::Weapon(WeaponType::Sword)).with_durability(50))
+            .build();
+        let kit = world.create_entity().with(RepairKit::new(RepairKitType::Weaponsmith, 20, 2)).build();
+        let player = world.create_entity()
+            .with(WantsToRepair { item: sword, source: RepairSource::Kit(kit) })
+            .build();
+        RepairSystem.run_now(&world);
+        assert_eq!(world.read_storage::<RepairKit>().get(kit).unwrap().uses_remaining, 2);
+
+        world.write_storage::<ItemProperties>().get_mut(sword).unwrap().damage(30);
+        world.write_storage::<WantsToRepair>()
+            .insert(player, WantsToRepair { item: sword, source: RepairSource::Kit(kit) })
+            .unwrap();
+        RepairSystem.run_now(&world);
+        assert_eq!(world.read_storage::<RepairKit>().get(kit).unwrap().uses_remaining, 1);
+        assert_eq!(world.read_storage::<ItemProperties>().get(sword).unwrap().durability.as_ref().unwrap().current, 40);
+    }
+
+    #[test]
+    fn test_broken_items_are_not_functional() {
+        let mut props = ItemProperties::new("Shield".to_string(), ItemType::Armor(ArmorType::Shield))
+            .with_durability(5);
+        assert!(is_item_functional(Some(&props)));
+
+        props.damage(WearReason::Block.base_wear() * 3);
+        assert!(!is_item_functional(Some(&props)));
+        assert!(is_item_functional(None));
+    }
+}
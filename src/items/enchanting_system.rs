@@ -0,0 +1,424 @@
+use specs::{Component, VecStorage, NullStorage, System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::components::{Name, Player, Attributes, AttributeType, Skills, SkillType};
+use crate::items::{
+    ItemProperties, ItemType, ItemTag, MagicalItem, Enchantment, EnchantmentType, Curse, CurseType,
+    AdvancedInventory,
+};
+use crate::resources::{GameLog, RandomNumberGenerator};
+
+/// Magical essence recovered by disenchanting and spent on enchantments
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EssenceType {
+    Arcane,
+    Flame,
+    Frost,
+    Storm,
+    Venom,
+    Warding,
+    Vital,
+}
+
+impl EssenceType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EssenceType::Arcane => "Arcane Essence",
+            EssenceType::Flame => "Flame Essence",
+            EssenceType::Frost => "Frost Essence",
+            EssenceType::Storm => "Storm Essence",
+            EssenceType::Venom => "Venom Essence",
+            EssenceType::Warding => "Warding Essence",
+            EssenceType::Vital => "Vital Essence",
+        }
+    }
+
+    /// The essence an enchantment breaks down into
+    pub fn from_enchantment(enchantment_type: &EnchantmentType) -> EssenceType {
+        match enchantment_type {
+            EnchantmentType::Fire => EssenceType::Flame,
+            EnchantmentType::Ice => EssenceType::Frost,
+            EnchantmentType::Lightning => EssenceType::Storm,
+            EnchantmentType::Poison => EssenceType::Venom,
+            EnchantmentType::Protection | EnchantmentType::Resistance(_) => EssenceType::Warding,
+            EnchantmentType::Regeneration | EnchantmentType::Vampiric => EssenceType::Vital,
+            _ => EssenceType::Arcane,
+        }
+    }
+}
+
+/// Component holding a character's stock of essences
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct Essences {
+    pub amounts: HashMap<EssenceType, i32>,
+}
+
+impl Essences {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, essence: EssenceType) -> i32 {
+        *self.amounts.get(&essence).unwrap_or(&0)
+    }
+
+    pub fn add(&mut self, essence: EssenceType, amount: i32) {
+        *self.amounts.entry(essence).or_insert(0) += amount;
+    }
+
+    pub fn spend(&mut self, essence: EssenceType, amount: i32) -> bool {
+        let current = self.get(essence);
+        if current < amount {
+            return false;
+        }
+        self.amounts.insert(essence, current - amount);
+        true
+    }
+}
+
+/// Marker for dungeon furniture where enchanting can be performed
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+#[storage(NullStorage)]
+pub struct EnchantingStation;
+
+/// A known enchantment that can be applied at a station
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnchantmentRecipe {
+    pub name: String,
+    pub enchantment_type: EnchantmentType,
+    pub essence: EssenceType,
+    pub essence_cost: i32,
+    pub power: i32,
+    pub difficulty: i32,
+    pub applies_to_weapons: bool,
+}
+
+impl EnchantmentRecipe {
+    pub fn all() -> Vec<EnchantmentRecipe> {
+        vec![
+            EnchantmentRecipe::new("Keen Edge", EnchantmentType::Sharpness, EssenceType::Arcane, 3, 2, 10, true),
+            EnchantmentRecipe::new("Flametongue", EnchantmentType::Fire, EssenceType::Flame, 4, 3, 20, true),
+            EnchantmentRecipe::new("Frostbite", EnchantmentType::Ice, EssenceType::Frost, 4, 3, 20, true),
+            EnchantmentRecipe::new("Stormcaller", EnchantmentType::Lightning, EssenceType::Storm, 5, 3, 25, true),
+            EnchantmentRecipe::new("Venomous", EnchantmentType::Poison, EssenceType::Venom, 4, 2, 20, true),
+            EnchantmentRecipe::new("Warding", EnchantmentType::Protection, EssenceType::Warding, 3, 2, 10, false),
+            EnchantmentRecipe::new("Mending", EnchantmentType::Regeneration, EssenceType::Vital, 6, 1, 30, false),
+        ]
+    }
+
+    fn new(
+        name: &str,
+        enchantment_type: EnchantmentType,
+        essence: EssenceType,
+        essence_cost: i32,
+        power: i32,
+        difficulty: i32,
+        applies_to_weapons: bool,
+    ) -> Self {
+        EnchantmentRecipe {
+            name: name.to_string(),
+            enchantment_type,
+            essence,
+            essence_cost,
+            power,
+            difficulty,
+            applies_to_weapons,
+        }
+    }
+
+    pub fn can_apply_to(&self, item_type: &ItemType) -> bool {
+        match item_type {
+            ItemType::Weapon(_) => self.applies_to_weapons,
+            ItemType::Armor(_) => !self.applies_to_weapons,
+            _ => false,
+        }
+    }
+}
+
+/// Intent component for breaking an item down into essences
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToDisenchant {
+    pub item: Entity,
+}
+
+/// Intent component for enchanting an item at a station
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WantsToEnchant {
+    pub item: Entity,
+    pub recipe: EnchantmentRecipe,
+    pub station: Entity,
+}
+
+/// Base chance of success before skill and attribute bonuses
+pub const BASE_ENCHANT_CHANCE: i32 = 50;
+
+/// Chance (out of 100) that a failed enchantment curses the item
+pub const FAILURE_CURSE_CHANCE: i32 = 35;
+
+/// Percentage chance of enchanting successfully
+pub fn enchant_success_chance(skill_level: i32, intelligence_modifier: i32, recipe: &EnchantmentRecipe, existing_enchantments: usize) -> i32 {
+    let chance = BASE_ENCHANT_CHANCE
+        + skill_level * 10
+        + intelligence_modifier * 3
+        - recipe.difficulty
+        - existing_enchantments as i32 * 10;
+    chance.clamp(5, 95)
+}
+
+/// Essences recovered from breaking down an item
+pub fn disenchant_yield(magical: &MagicalItem, skill_level: i32) -> Vec<(EssenceType, i32)> {
+    let mut yields: HashMap<EssenceType, i32> = HashMap::new();
+
+    for enchantment in &magical.enchantments {
+        let essence = EssenceType::from_enchantment(&enchantment.enchantment_type);
+        let amount = (enchantment.power + skill_level / 2).max(1);
+        *yields.entry(essence).or_insert(0) += amount;
+    }
+
+    // Cursed items leave behind tainted, weaker essence
+    if magical.is_cursed() {
+        for amount in yields.values_mut() {
+            *amount = (*amount / 2).max(1);
+        }
+    }
+
+    let mut result: Vec<(EssenceType, i32)> = yields.into_iter().collect();
+    result.sort_by_key(|(essence, _)| essence.name());
+    result
+}
+
+/// System that resolves enchanting and disenchanting requests
+pub struct EnchantingSystem;
+
+impl<'a> System<'a> for EnchantingSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToDisenchant>,
+        WriteStorage<'a, WantsToEnchant>,
+        WriteStorage<'a, MagicalItem>,
+        WriteStorage<'a, ItemProperties>,
+        WriteStorage<'a, Essences>,
+        WriteStorage<'a, AdvancedInventory>,
+        ReadStorage<'a, EnchantingStation>,
+        ReadStorage<'a, Skills>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_disenchant,
+            mut wants_enchant,
+            mut magical_items,
+            mut item_properties,
+            mut essences,
+            mut inventories,
+            stations,
+            skills,
+            attributes,
+            players,
+            names,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        // Disenchanting
+        let mut destroyed_items = Vec::new();
+        for (entity, request) in (&entities, &wants_disenchant).join() {
+            let item_name = names.get(request.item)
+                .map(|n| n.name.clone())
+                .unwrap_or("item".to_string());
+
+            let magical = match magical_items.get(request.item) {
+                Some(magical) if !magical.enchantments.is_empty() => magical,
+                _ => {
+                    gamelog.add_entry(format!("The {} holds no magic to extract.", item_name));
+                    continue;
+                }
+            };
+
+            let skill_level = skills.get(entity)
+                .map_or(0, |s| s.get_skill_level(SkillType::Enchanting));
+            let yields = disenchant_yield(magical, skill_level);
+
+            if essences.get(entity).is_none() {
+                essences.insert(entity, Essences::new())
+                    .expect("Unable to insert essences");
+            }
+            if let Some(stock) = essences.get_mut(entity) {
+                for (essence, amount) in &yields {
+                    stock.add(*essence, *amount);
+                }
+            }
+
+            if players.get(entity).is_some() {
+                let recovered: Vec<String> = yields.iter()
+                    .map(|(essence, amount)| format!("{} {}", amount, essence.name()))
+                    .collect();
+                gamelog.add_entry(format!("You break down the {} into {}.", item_name, recovered.join(", ")));
+            }
+
+            destroyed_items.push((entity, request.item));
+        }
+        wants_disenchant.clear();
+
+        for (owner, item) in destroyed_items {
+            if let Some(inventory) = inventories.get_mut(owner) {
+                inventory.items.retain(|slot| slot.entity != item);
+            }
+            entities.delete(item).expect("Unable to delete disenchanted item");
+        }
+
+        // Enchanting
+        for (entity, request) in (&entities, &wants_enchant).join() {
+            if stations.get(request.station).is_none() {
+                gamelog.add_entry("You need an enchanting station to do that.".to_string());
+                continue;
+            }
+
+            let props = match item_properties.get_mut(request.item) {
+                Some(props) => props,
+                None => continue,
+            };
+
+            if !request.recipe.can_apply_to(&props.item_type) {
+                gamelog.add_entry(format!("{} cannot be applied to the {}.", request.recipe.name, props.name));
+                continue;
+            }
+
+            let paid = essences.get_mut(entity)
+                .map_or(false, |stock| stock.spend(request.recipe.essence, request.recipe.essence_cost));
+            if !paid {
+                gamelog.add_entry(format!("You need {} {} for {}.",
+                    request.recipe.essence_cost, request.recipe.essence.name(), request.recipe.name));
+                continue;
+            }
+
+            let skill_level = skills.get(entity)
+                .map_or(0, |s| s.get_skill_level(SkillType::Enchanting));
+            let int_modifier = attributes.get(entity)
+                .map_or(0, |a| a.get_modifier(AttributeType::Intelligence));
+
+            if magical_items.get(request.item).is_none() {
+                magical_items.insert(request.item, MagicalItem::new(1))
+                    .expect("Unable to insert magical item");
+            }
+            let magical = magical_items.get_mut(request.item).expect("Magical item was just inserted");
+
+            let chance = enchant_success_chance(skill_level, int_modifier, &request.recipe, magical.enchantments.len());
+
+            if rng.roll_dice(1, 100) <= chance {
+                magical.add_enchantment(Enchantment {
+                    name: request.recipe.name.clone(),
+                    description: format!("Enchanted with {}.", request.recipe.essence.name()),
+                    enchantment_type: request.recipe.enchantment_type.clone(),
+                    power: request.recipe.power + skill_level / 2,
+                    duration: None,
+                });
+                magical.magic_level += 1;
+                if !props.has_tag(&ItemTag::Magical) {
+                    props.tags.push(ItemTag::Magical);
+                }
+                gamelog.add_entry(format!("The {} glows as {} takes hold.", props.name, request.recipe.name));
+            } else if !magical.is_cursed() && rng.roll_dice(1, 100) <= FAILURE_CURSE_CHANCE {
+                magical.add_curse(self.failure_curse(&mut rng));
+                if !props.has_tag(&ItemTag::Cursed) {
+                    props.tags.push(ItemTag::Cursed);
+                }
+                gamelog.add_entry(format!("The enchantment twists! The {} is cursed.", props.name));
+            } else {
+                gamelog.add_entry("The enchantment fizzles and the essence is lost.".to_string());
+            }
+        }
+        wants_enchant.clear();
+    }
+}
+
+impl EnchantingSystem {
+    fn failure_curse(&self, rng: &mut RandomNumberGenerator) -> Curse {
+        let (name, description, curse_type) = match rng.roll_dice(1, 4) {
+            1 => ("Binding", "The item clings to its wearer.", CurseType::Binding),
+            2 => ("Brittleness", "The item wears out quickly.", CurseType::Fragility),
+            3 => ("Feebleness", "The item saps its wielder's strength.", CurseType::Weakness),
+            _ => ("Clumsiness", "The item throws off its wielder's aim.", CurseType::Accuracy),
+        };
+
+        Curse {
+            name: name.to_string(),
+            description: description.to_string(),
+            curse_type,
+            power: rng.roll_dice(1, 3),
+            removable: true,
+            // The failed enchantment makes the curse obvious
+            revealed: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::{WeaponType, ArmorType};
+
+    #[test]
+    fn test_essences_spend() {
+        let mut essences = Essences::new();
+        essences.add(EssenceType::Flame, 5);
+
+        assert!(!essences.spend(EssenceType::Flame, 6));
+        assert!(essences.spend(EssenceType::Flame, 4));
+        assert_eq!(essences.get(EssenceType::Flame), 1);
+        assert_eq!(essences.get(EssenceType::Frost), 0);
+    }
+
+    #[test]
+    fn test_success_chance_scales_with_skill() {
+        let recipe = &EnchantmentRecipe::all()[1];
+        let novice = enchant_success_chance(0, 0, recipe, 0);
+        let master = enchant_success_chance(5, 3, recipe, 0);
+        assert!(master > novice);
+
+        // Existing enchantments make further work harder, but never impossible
+        assert!(enchant_success_chance(0, -2, recipe, 5) >= 5);
+        assert!(enchant_success_chance(5, 5, recipe, 0) <= 95);
+    }
+
+    #[test]
+    fn test_disenchant_yield() {
+        let mut magical = MagicalItem::new(2);
+        magical.add_enchantment(Enchantment {
+            name: "Fire".to_string(),
+            description: String::new(),
+            enchantment_type: EnchantmentType::Fire,
+            power: 3,
+            duration: None,
+        });
+
+        assert_eq!(disenchant_yield(&magical, 2), vec![(EssenceType::Flame, 4)]);
+
+        magical.add_curse(Curse {
+            name: "Binding".to_string(),
+            description: String::new(),
+            curse_type: CurseType::Binding,
+            power: 1,
+            removable: false,
+            revealed: true,
+        });
+        assert_eq!(disenchant_yield(&magical, 2), vec![(EssenceType::Flame, 2)]);
+    }
+
+    #[test]
+    fn test_recipe_applicability() {
+        let recipes = EnchantmentRecipe::all();
+        let keen = recipes.iter().find(|r| r.name == "Keen Edge").unwrap();
+        assert!(keen.can_apply_to(&ItemType::Weapon(WeaponType::Sword)));
+        assert!(!keen.can_apply_to(&ItemType::Armor(ArmorType::Chest)));
+    }
+}
@@ -0,0 +1,255 @@
+use crossterm::{
+    event::{KeyCode, KeyEvent},
+    style::Color,
+};
+use specs::{World, Entity, WorldExt};
+use crate::components::{Attributes, AttributeType, Skills, SkillType};
+use crate::items::{AdvancedInventory, ItemProperties, ItemType, MagicalItem, get_item_display_name};
+use crate::items::enchanting_system::{EnchantmentRecipe, Essences, enchant_success_chance};
+use crate::localization::{tr, Message};
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// Which list the enchanting screen is focused on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnchantingMode {
+    Enchant,
+    Disenchant,
+}
+
+/// The screen shown when the player bumps into an enchanting station
+pub struct EnchantingUI {
+    pub station: Entity,
+    pub player: Entity,
+    pub item: Option<Entity>,
+    pub mode: EnchantingMode,
+    pub selected_recipe: usize,
+    recipes: Vec<EnchantmentRecipe>,
+}
+
+impl EnchantingUI {
+    /// The station's screen for `player`, with the first weapon or armor in their pack on the bench
+    pub fn new(world: &World, station: Entity, player: Entity) -> Self {
+        let mut ui = EnchantingUI {
+            station,
+            player,
+            item: None,
+            mode: EnchantingMode::Enchant,
+            selected_recipe: 0,
+            recipes: EnchantmentRecipe::all(),
+        };
+        ui.item = ui.workable_items(world).first().copied();
+        ui
+    }
+
+    pub fn set_item(&mut self, item: Entity) {
+        self.item = Some(item);
+        self.selected_recipe = 0;
+    }
+
+    /// Weapons and armor in the player's pack, the only things a station can work
+    fn workable_items(&self, world: &World) -> Vec<Entity> {
+        let properties = world.read_storage::<ItemProperties>();
+        world.read_storage::<AdvancedInventory>().get(self.player)
+            .map_or(Vec::new(), |pack| pack.items.iter().map(|slot| slot.entity).collect())
+            .into_iter()
+            .filter(|item| matches!(
+                properties.get(*item).map(|props| &props.item_type),
+                Some(ItemType::Weapon(_)) | Some(ItemType::Armor(_))
+            ))
+            .collect()
+    }
+
+    /// Put the next or previous workable item on the bench
+    fn cycle_item(&mut self, world: &World, forward: bool) {
+        let items = self.workable_items(world);
+        if items.is_empty() {
+            self.item = None;
+            return;
+        }
+        let next = match self.item.and_then(|item| items.iter().position(|other| *other == item)) {
+            Some(index) if forward => (index + 1) % items.len(),
+            Some(index) => (index + items.len() - 1) % items.len(),
+            None => 0,
+        };
+        self.set_item(items[next]);
+    }
+
+    /// Recipes that can be applied to the currently selected item
+    fn available_recipes(&self, world: &World) -> Vec<&EnchantmentRecipe> {
+        let properties = world.read_storage::<ItemProperties>();
+        match self.item.and_then(|item| properties.get(item)) {
+            Some(props) => self.recipes.iter().filter(|r| r.can_apply_to(&props.item_type)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn item_name(&self, world: &World) -> String {
+        self.item
+            .and_then(|item| get_item_display_name(world, item))
+            .unwrap_or_else(|| tr("enchanting.no_item"))
+    }
+
+    fn essences_line(&self, world: &World) -> String {
+        let essences = world.read_storage::<Essences>();
+        let mut held: Vec<String> = essences.get(self.player)
+            .map_or(Vec::new(), |stock| stock.amounts.iter()
+                .filter(|(_, &amount)| amount > 0)
+                .map(|(essence, amount)| format!("{} x{}", essence.name(), amount))
+                .collect());
+        if held.is_empty() {
+            return tr("enchanting.no_essences");
+        }
+        held.sort();
+        Message::new("enchanting.essences").arg("essences", held.join(", ")).text()
+    }
+
+    /// Each recipe for the item on the bench, its cost and its chance, and whether the player
+    /// holds the essence for it
+    fn recipe_lines(&self, world: &World) -> Vec<(String, bool)> {
+        let skills = world.read_storage::<Skills>();
+        let attributes = world.read_storage::<Attributes>();
+        let essences = world.read_storage::<Essences>();
+        let magical_items = world.read_storage::<MagicalItem>();
+
+        let skill_level = skills.get(self.player).map_or(0, |s| s.get_skill_level(SkillType::Enchanting));
+        let int_modifier = attributes.get(self.player).map_or(0, |a| a.get_modifier(AttributeType::Intelligence));
+        let existing = self.item
+            .and_then(|item| magical_items.get(item))
+            .map_or(0, |m| m.enchantments.len());
+
+        self.available_recipes(world).into_iter()
+            .map(|recipe| {
+                let chance = enchant_success_chance(skill_level, int_modifier, recipe, existing);
+                let affordable = essences.get(self.player)
+                    .map_or(false, |stock| stock.get(recipe.essence) >= recipe.essence_cost);
+                (format!("{:14} {:2} {:16} {:3}%", recipe.name, recipe.essence_cost, recipe.essence.name(), chance), affordable)
+            })
+            .collect()
+    }
+
+    /// What breaking the item down would give back, and a warning when its curse would taint it
+    fn disenchant_lines(&self, world: &World) -> Vec<(String, Color)> {
+        let magical_items = world.read_storage::<MagicalItem>();
+        match self.item.and_then(|item| magical_items.get(item)) {
+            Some(magical) if !magical.enchantments.is_empty() => {
+                let mut lines = vec![(tr("enchanting.disenchant_warning"), Color::White)];
+                for enchantment in &magical.enchantments {
+                    lines.push((format!("  {} (power {})", enchantment.name, enchantment.power), Color::White));
+                }
+                if magical.is_cursed() {
+                    lines.push((tr("enchanting.tainted"), Color::Red));
+                }
+                lines
+            },
+            _ => vec![(tr("enchanting.no_magic"), Color::DarkGrey)],
+        }
+    }
+
+    /// The highlighted recipe, or what disenchanting would do, for the screen reader
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        match self.mode {
+            EnchantingMode::Enchant => self.recipe_lines(world).into_iter().nth(self.selected_recipe).map(|(line, _)| line),
+            EnchantingMode::Disenchant => self.disenchant_lines(world).into_iter().next().map(|(line, _)| line),
+        }
+    }
+
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let width = 60.min(screen_width - 4);
+        let height = 20.min(screen_height);
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(tr("enchanting.title"), x, y, width, height)
+            .with_colors(Color::Magenta, Color::Black, Color::Magenta)
+            .render();
+        let line = |commands: &mut Vec<UIRenderCommand>, row: i32, text: String, fg: Color, bg: Color| {
+            let text: String = text.chars().take((width - 4).max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text, fg, bg });
+        };
+
+        line(&mut commands, 2, Message::new("enchanting.item").arg("item", self.item_name(world)).text(), Color::White, Color::Black);
+        line(&mut commands, 3, self.essences_line(world), Color::Cyan, Color::Black);
+
+        let rows = (height - 8).max(0) as usize;
+        match self.mode {
+            EnchantingMode::Enchant => {
+                let recipes = self.recipe_lines(world);
+                if recipes.is_empty() {
+                    line(&mut commands, 5, tr("enchanting.no_recipes"), Color::DarkGrey, Color::Black);
+                }
+                for (i, (text, affordable)) in recipes.into_iter().take(rows).enumerate() {
+                    let fg = if affordable { Color::White } else { Color::DarkRed };
+                    let bg = if i == self.selected_recipe { Color::DarkGrey } else { Color::Black };
+                    line(&mut commands, 5 + i as i32, text, fg, bg);
+                }
+            },
+            EnchantingMode::Disenchant => {
+                for (i, (text, fg)) in self.disenchant_lines(world).into_iter().take(rows).enumerate() {
+                    line(&mut commands, 5 + i as i32, text, fg, Color::Black);
+                }
+            },
+        }
+
+        let controls = match self.mode {
+            EnchantingMode::Enchant => tr("enchanting.controls_enchant"),
+            EnchantingMode::Disenchant => tr("enchanting.controls_disenchant"),
+        };
+        line(&mut commands, height - 2, controls, Color::DarkGrey, Color::Black);
+        commands
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent, world: &World) -> EnchantingAction {
+        match key.code {
+            KeyCode::Up => {
+                if self.selected_recipe > 0 {
+                    self.selected_recipe -= 1;
+                }
+                EnchantingAction::None
+            },
+            KeyCode::Down => {
+                let count = self.available_recipes(world).len();
+                if self.selected_recipe + 1 < count {
+                    self.selected_recipe += 1;
+                }
+                EnchantingAction::None
+            },
+            KeyCode::Left | KeyCode::Right => {
+                self.cycle_item(world, key.code == KeyCode::Right);
+                EnchantingAction::None
+            },
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                self.mode = EnchantingMode::Disenchant;
+                EnchantingAction::None
+            },
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                self.mode = EnchantingMode::Enchant;
+                EnchantingAction::None
+            },
+            KeyCode::Enter => {
+                let item = match self.item {
+                    Some(item) => item,
+                    None => return EnchantingAction::None,
+                };
+
+                match self.mode {
+                    EnchantingMode::Enchant => {
+                        match self.available_recipes(world).get(self.selected_recipe) {
+                            Some(recipe) => EnchantingAction::Enchant(item, (*recipe).clone(), self.station),
+                            None => EnchantingAction::None,
+                        }
+                    },
+                    EnchantingMode::Disenchant => EnchantingAction::Disenchant(item),
+                }
+            },
+            KeyCode::Esc => EnchantingAction::Close,
+            _ => EnchantingAction::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EnchantingAction {
+    None,
+    Enchant(Entity, EnchantmentRecipe, Entity),
+    Disenchant(Entity),
+    Close,
+}
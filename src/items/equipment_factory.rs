@@ -1,12 +1,16 @@
 use specs::{World, WorldExt, Builder, Entity};
 use crate::components::{Position, Name, Renderable, Item};
 use crate::items::{
-    ItemProperties, ItemType, WeaponType, ArmorType, ItemRarity, ItemBonuses,
+    ItemProperties, ItemType, WeaponType, ArmorType, ItemRarity, ItemBonuses, SpecialBonus, SpecialBonusType,
     equipment_system::{Equippable, EquipmentSlot, EquipmentRequirements, EquipmentSet, SetBonus}
 };
+use crate::map::Climate;
 use crate::resources::RandomNumberGenerator;
 use std::collections::HashMap;
 
+/// Percent resistance a climate cloak gives, enough on its own to shrug off the climate
+pub const CLIMATE_CLOAK_RESISTANCE: i32 = 60;
+
 /// Factory for creating different types of equipment
 pub struct EquipmentFactory;
 
@@ -157,6 +161,51 @@ impl EquipmentFactory {
         entity
     }
 
+    /// Create a cloak made for a harsh climate: furs against the cold, salamander hide against the heat
+    pub fn create_climate_cloak(
+        &self,
+        world: &mut World,
+        position: Position,
+        climate: Climate,
+    ) -> Entity {
+        let (name, resistance, description, color) = match climate {
+            Climate::Hot => ("Salamander Cloak", "Fire",
+                "Scaly hide that shrugs off the heat of the deep fires.", crossterm::style::Color::Red),
+            _ => ("Fur-Lined Cloak", "Cold",
+                "Thick furs that keep the worst of the cold out.", crossterm::style::Color::White),
+        };
+
+        let properties = ItemProperties::new(name.to_string(), ItemType::Armor(ArmorType::Cloak))
+            .with_description(description.to_string())
+            .with_rarity(ItemRarity::Uncommon)
+            .with_value(90)
+            .with_weight(2.0)
+            .with_durability(100);
+
+        let mut bonuses = ItemBonuses::new();
+        bonuses.combat_bonuses.defense_bonus = 1;
+        bonuses.add_special_bonus(SpecialBonus {
+            name: format!("{} Resistance", resistance),
+            description: format!("Resists {} by {}%", resistance.to_lowercase(), CLIMATE_CLOAK_RESISTANCE),
+            bonus_type: SpecialBonusType::DamageResistance(resistance.to_string(), CLIMATE_CLOAK_RESISTANCE),
+        });
+
+        world.create_entity()
+            .with(Item)
+            .with(Name { name: name.to_string() })
+            .with(properties)
+            .with(bonuses)
+            .with(Equippable::new(EquipmentSlot::Cloak))
+            .with(position)
+            .with(Renderable {
+                glyph: '(',
+                fg: color,
+                bg: crossterm::style::Color::Black,
+                render_order: 2,
+            })
+            .build()
+    }
+
     /// Create a random piece of equipment
     pub fn create_random_equipment(
         &self,
@@ -1,15 +1,15 @@
 use specs::{Component, VecStorage, System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
-use crate::components::{CombatStats, Player, Name};
-use crate::items::{ItemProperties, ItemType, ArmorType, WeaponType, ItemBonuses};
+use crate::components::{CombatStats, Player, Name, Attributes, AttributeType};
+use crate::items::{ItemProperties, ItemType, ItemTag, ArmorType, ConsumableType, ItemBonuses, MagicalItem, CurseType, SpecialBonusType};
 use crate::resources::GameLog;
 
 /// Equipment slots available for characters
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum EquipmentSlot {
     MainHand, OffHand, Head, Chest, Legs, Feet, Hands,
-    Ring1, Ring2, Amulet, Cloak, Belt,
+    Ring1, Ring2, Amulet, Cloak, Belt, Quiver,
 }
 
 impl EquipmentSlot {
@@ -27,13 +27,34 @@ impl EquipmentSlot {
             EquipmentSlot::Amulet => "Amulet",
             EquipmentSlot::Cloak => "Cloak",
             EquipmentSlot::Belt => "Belt",
+            EquipmentSlot::Quiver => "Quiver",
+        }
+    }
+
+    /// The slot an item of this type goes in when none is asked for
+    pub fn for_item_type(item_type: &ItemType) -> Option<EquipmentSlot> {
+        match item_type {
+            ItemType::Weapon(_) => Some(EquipmentSlot::MainHand),
+            ItemType::Armor(armor_type) => match armor_type {
+                ArmorType::Helmet => Some(EquipmentSlot::Head),
+                ArmorType::Chest => Some(EquipmentSlot::Chest),
+                ArmorType::Legs => Some(EquipmentSlot::Legs),
+                ArmorType::Boots => Some(EquipmentSlot::Feet),
+                ArmorType::Gloves => Some(EquipmentSlot::Hands),
+                ArmorType::Shield => Some(EquipmentSlot::OffHand),
+                ArmorType::Ring => Some(EquipmentSlot::Ring1),
+                ArmorType::Amulet => Some(EquipmentSlot::Amulet),
+                ArmorType::Cloak => Some(EquipmentSlot::Cloak),
+            },
+            ItemType::Consumable(ConsumableType::Ammunition) => Some(EquipmentSlot::Quiver),
+            _ => None,
         }
     }
 
     pub fn can_equip_item_type(&self, item_type: &ItemType) -> bool {
         match (self, item_type) {
             (EquipmentSlot::MainHand, ItemType::Weapon(_)) => true,
-            (EquipmentSlot::OffHand, ItemType::Weapon(WeaponType::Dagger)) => true,
+            (EquipmentSlot::OffHand, ItemType::Weapon(weapon_type)) => weapon_type.can_dual_wield(),
             (EquipmentSlot::OffHand, ItemType::Armor(ArmorType::Shield)) => true,
             (EquipmentSlot::Head, ItemType::Armor(ArmorType::Helmet)) => true,
             (EquipmentSlot::Chest, ItemType::Armor(ArmorType::Chest)) => true,
@@ -43,6 +64,7 @@ impl EquipmentSlot {
             (EquipmentSlot::Ring1 | EquipmentSlot::Ring2, ItemType::Armor(ArmorType::Ring)) => true,
             (EquipmentSlot::Amulet, ItemType::Armor(ArmorType::Amulet)) => true,
             (EquipmentSlot::Cloak, ItemType::Armor(ArmorType::Cloak)) => true,
+            (EquipmentSlot::Quiver, ItemType::Consumable(ConsumableType::Ammunition)) => true,
             _ => false,
         }
     }
@@ -52,6 +74,7 @@ impl EquipmentSlot {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct Equipment {
+    #[serde(with = "crate::persistence::entity_refs::map")]
     pub slots: HashMap<EquipmentSlot, Option<Entity>>,
     pub stat_cache: EquipmentStats,
     pub dirty: bool,
@@ -67,6 +90,7 @@ impl Equipment {
             EquipmentSlot::Feet, EquipmentSlot::Hands,
             EquipmentSlot::Ring1, EquipmentSlot::Ring2,
             EquipmentSlot::Amulet, EquipmentSlot::Cloak, EquipmentSlot::Belt,
+            EquipmentSlot::Quiver,
         ] {
             slots.insert(slot, None);
         }
@@ -162,6 +186,178 @@ impl EquipmentStats {
     }
 }
 
+/// The stats an item adds while worn, counted the way `EquipmentBonusSystem` counts them
+pub fn item_equipment_stats(bonuses: Option<&ItemBonuses>, props: Option<&ItemProperties>) -> EquipmentStats {
+    let mut item_stats = EquipmentStats::default();
+
+    // Broken items provide no bonuses until repaired
+    let bonuses = match bonuses {
+        Some(bonuses) if crate::items::is_item_functional(props) => bonuses,
+        _ => return item_stats,
+    };
+
+    item_stats.attack_bonus = bonuses.combat_bonuses.attack_bonus;
+    item_stats.damage_bonus = bonuses.combat_bonuses.damage_bonus;
+    item_stats.defense_bonus = bonuses.combat_bonuses.defense_bonus;
+    item_stats.critical_chance_bonus = bonuses.combat_bonuses.critical_chance_bonus;
+    item_stats.critical_damage_bonus = bonuses.combat_bonuses.critical_damage_bonus;
+
+    for (attr, value) in &bonuses.attribute_bonuses {
+        match attr.as_str() {
+            "Strength" => item_stats.strength_bonus += value,
+            "Dexterity" => item_stats.dexterity_bonus += value,
+            "Constitution" => item_stats.constitution_bonus += value,
+            "Intelligence" => item_stats.intelligence_bonus += value,
+            "Wisdom" => item_stats.wisdom_bonus += value,
+            "Charisma" => item_stats.charisma_bonus += value,
+            _ => {}
+        }
+    }
+
+    for bonus in &bonuses.special_bonuses {
+        if let SpecialBonusType::DamageResistance(damage_type, percent) = &bonus.bonus_type {
+            match damage_type.as_str() {
+                "Fire" => item_stats.fire_resistance += percent,
+                "Ice" | "Cold" => item_stats.cold_resistance += percent,
+                "Lightning" => item_stats.lightning_resistance += percent,
+                "Poison" => item_stats.poison_resistance += percent,
+                "Magic" => item_stats.magic_resistance += percent,
+                _ => {}
+            }
+        }
+    }
+
+    item_stats
+}
+
+/// One stat of an item set against the same stat of what it would replace
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatDelta {
+    pub label: &'static str,
+    pub candidate: f32,
+    pub equipped: f32,
+    /// Weight is the one stat where less is better
+    pub higher_is_better: bool,
+    /// Decimal places to show
+    pub precision: usize,
+}
+
+impl StatDelta {
+    pub fn delta(&self) -> f32 {
+        self.candidate - self.equipped
+    }
+
+    /// Better or worse for the wearer, or `None` when it makes no difference
+    pub fn is_improvement(&self) -> Option<bool> {
+        let delta = self.delta();
+        if delta.abs() < f32::EPSILON {
+            None
+        } else {
+            Some((delta > 0.0) == self.higher_is_better)
+        }
+    }
+}
+
+/// What putting on an item would change, against whatever is in its slot now
+#[derive(Debug, Clone)]
+pub struct EquipmentComparison {
+    pub slot: EquipmentSlot,
+    pub candidate: Entity,
+    pub equipped: Option<Entity>,
+    /// Power and defense, then any other bonus either item has, then weight
+    pub deltas: Vec<StatDelta>,
+}
+
+/// Compare `candidate` with the item in the slot it would go in; `None` for things that can't be worn
+pub fn compare_equipment(
+    candidate: Entity,
+    equipment: &Equipment,
+    item_properties: &ReadStorage<ItemProperties>,
+    item_bonuses: &ReadStorage<ItemBonuses>,
+) -> Option<EquipmentComparison> {
+    let slot = EquipmentSlot::for_item_type(&item_properties.get(candidate)?.item_type)?;
+    let equipped = equipment.get_equipped(&slot);
+
+    let stats_of = |item: Option<Entity>| match item {
+        Some(item) => item_equipment_stats(item_bonuses.get(item), item_properties.get(item)),
+        None => EquipmentStats::default(),
+    };
+    let weight_of = |item: Option<Entity>| item.and_then(|item| item_properties.get(item)).map_or(0.0, |props| props.weight);
+    let (new, old) = (stats_of(Some(candidate)), stats_of(equipped));
+
+    let stat = |label, candidate: i32, equipped: i32| StatDelta {
+        label,
+        candidate: candidate as f32,
+        equipped: equipped as f32,
+        higher_is_better: true,
+        precision: 0,
+    };
+    let mut deltas = vec![
+        stat("Power", new.attack_bonus + new.damage_bonus, old.attack_bonus + old.damage_bonus),
+        stat("Defense", new.defense_bonus, old.defense_bonus),
+    ];
+    let bonuses = [
+        stat("Critical", new.critical_chance_bonus, old.critical_chance_bonus),
+        stat("Crit Damage", new.critical_damage_bonus, old.critical_damage_bonus),
+        stat("Strength", new.strength_bonus, old.strength_bonus),
+        stat("Dexterity", new.dexterity_bonus, old.dexterity_bonus),
+        stat("Constitution", new.constitution_bonus, old.constitution_bonus),
+        stat("Intelligence", new.intelligence_bonus, old.intelligence_bonus),
+        stat("Wisdom", new.wisdom_bonus, old.wisdom_bonus),
+        stat("Charisma", new.charisma_bonus, old.charisma_bonus),
+    ];
+    deltas.extend(bonuses.into_iter().filter(|delta| delta.candidate != 0.0 || delta.equipped != 0.0));
+    deltas.push(StatDelta {
+        label: "Weight",
+        candidate: weight_of(Some(candidate)),
+        equipped: weight_of(equipped),
+        higher_is_better: false,
+        precision: 1,
+    });
+
+    Some(EquipmentComparison { slot, candidate, equipped, deltas })
+}
+
+/// Attack penalty for fighting with a weapon in each hand, before Dexterity
+pub const DUAL_WIELD_PENALTY: i32 = 2;
+
+/// Whether an item takes up both hands, by weapon type or the `TwoHanded` tag
+pub fn is_two_handed(props: &ItemProperties) -> bool {
+    match &props.item_type {
+        ItemType::Weapon(weapon_type) => weapon_type.is_two_handed() || props.has_tag(&ItemTag::TwoHanded),
+        _ => false,
+    }
+}
+
+/// Attack modifier for dual wielding; enough Dexterity turns the penalty into a bonus
+pub fn dual_wield_attack_modifier(dex_modifier: i32) -> i32 {
+    dex_modifier - DUAL_WIELD_PENALTY
+}
+
+/// Check that an item can go into a hand slot given what the other hand is holding
+pub fn check_hand_occupancy(
+    slot: &EquipmentSlot,
+    item: &ItemProperties,
+    other_hand: Option<&ItemProperties>,
+) -> Result<(), String> {
+    if *slot == EquipmentSlot::OffHand && is_two_handed(item) {
+        return Err(format!("The {} is too unwieldy for your off hand", item.name));
+    }
+    let other = match other_hand {
+        Some(other) => other,
+        None => return Ok(()),
+    };
+    match slot {
+        EquipmentSlot::MainHand if is_two_handed(item) => Err(format!(
+            "You need both hands free for the {} - put away the {} first", item.name, other.name
+        )),
+        EquipmentSlot::OffHand if is_two_handed(other) => Err(format!(
+            "The {} needs both hands - there's no room for the {}", other.name, item.name
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Intent component for equipping items
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
@@ -188,6 +384,7 @@ impl<'a> System<'a> for EquipmentSystem {
         WriteStorage<'a, WantsToUnequip>,
         ReadStorage<'a, ItemProperties>,
         ReadStorage<'a, ItemBonuses>,
+        WriteStorage<'a, MagicalItem>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, Player>,
         Write<'a, GameLog>,
@@ -201,6 +398,7 @@ impl<'a> System<'a> for EquipmentSystem {
             mut wants_to_unequip,
             item_properties,
             item_bonuses,
+            mut magical_items,
             names,
             players,
             mut gamelog,
@@ -239,6 +437,27 @@ impl<'a> System<'a> for EquipmentSystem {
                         to_remove_equip.push(entity);
                         continue;
                     }
+
+                    // A two-handed weapon and anything in the off hand exclude each other
+                    let other_hand = match slot {
+                        EquipmentSlot::MainHand => equipment.get_equipped(&EquipmentSlot::OffHand),
+                        EquipmentSlot::OffHand => equipment.get_equipped(&EquipmentSlot::MainHand),
+                        _ => None,
+                    };
+                    if let Err(reason) = check_hand_occupancy(&slot, props, other_hand.and_then(|other| item_properties.get(other))) {
+                        if players.get(entity).is_some() {
+                            gamelog.entries.push(reason);
+                        }
+                        to_remove_equip.push(entity);
+                        continue;
+                    }
+                }
+
+                if let Some(current) = equipment.get_equipped(&slot) {
+                    if self.is_stuck(current, &mut magical_items, &names, &mut gamelog) {
+                        to_remove_equip.push(entity);
+                        continue;
+                    }
                 }
 
                 let old_item = equipment.equip_item(slot.clone(), item_entity);
@@ -272,6 +491,13 @@ impl<'a> System<'a> for EquipmentSystem {
         for (entity, unequip_intent) in (&entities, &wants_to_unequip).join() {
             if let Some(equipment) = equipment.get_mut(entity) {
                 let slot = &unequip_intent.slot;
+
+                if let Some(current) = equipment.get_equipped(slot) {
+                    if self.is_stuck(current, &mut magical_items, &names, &mut gamelog) {
+                        to_remove_unequip.push(entity);
+                        continue;
+                    }
+                }
                 
                 if let Some(item_entity) = equipment.unequip_item(slot) {
                     let item_name = names.get(item_entity)
@@ -304,23 +530,30 @@ impl<'a> System<'a> for EquipmentSystem {
 }
 
 impl EquipmentSystem {
-    fn detect_equipment_slot(&self, item_type: &ItemType) -> Option<EquipmentSlot> {
-        match item_type {
-            ItemType::Weapon(_) => Some(EquipmentSlot::MainHand),
-            ItemType::Armor(armor_type) => match armor_type {
-                ArmorType::Helmet => Some(EquipmentSlot::Head),
-                ArmorType::Chest => Some(EquipmentSlot::Chest),
-                ArmorType::Legs => Some(EquipmentSlot::Legs),
-                ArmorType::Boots => Some(EquipmentSlot::Feet),
-                ArmorType::Gloves => Some(EquipmentSlot::Hands),
-                ArmorType::Shield => Some(EquipmentSlot::OffHand),
-                ArmorType::Ring => Some(EquipmentSlot::Ring1),
-                ArmorType::Amulet => Some(EquipmentSlot::Amulet),
-                ArmorType::Cloak => Some(EquipmentSlot::Cloak),
+    /// Cursed items can't be taken off; trying to do so reveals the curse
+    fn is_stuck(
+        &self,
+        item: Entity,
+        magical_items: &mut WriteStorage<MagicalItem>,
+        names: &ReadStorage<Name>,
+        gamelog: &mut GameLog,
+    ) -> bool {
+        match magical_items.get_mut(item) {
+            Some(magical) if magical.is_cursed() => {
+                magical.reveal_curse();
+                let item_name = names.get(item)
+                    .map(|n| n.name.clone())
+                    .unwrap_or("Unknown Item".to_string());
+                gamelog.entries.push(format!("You can't remove the {} - it's cursed!", item_name));
+                true
             },
-            _ => None,
+            _ => false,
         }
     }
+
+    fn detect_equipment_slot(&self, item_type: &ItemType) -> Option<EquipmentSlot> {
+        EquipmentSlot::for_item_type(item_type)
+    }
 }
 
 /// System for calculating equipment bonuses
@@ -333,14 +566,18 @@ impl<'a> System<'a> for EquipmentBonusSystem {
         WriteStorage<'a, CombatStats>,
         ReadStorage<'a, ItemBonuses>,
         ReadStorage<'a, ItemProperties>,
+        ReadStorage<'a, MagicalItem>,
+        ReadStorage<'a, Attributes>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut equipment, mut combat_stats, item_bonuses, item_properties) = data;
+        let (entities, mut equipment, mut combat_stats, item_bonuses, item_properties, magical_items, attributes) = data;
 
         for (entity, equipment, combat_stats) in (&entities, &mut equipment, &mut combat_stats).join() {
             if equipment.dirty {
-                equipment.stat_cache = self.calculate_equipment_stats(equipment, &item_bonuses, &item_properties);
+                let dex_modifier = attributes.get(entity)
+                    .map_or(0, |attrs| attrs.get_modifier(AttributeType::Dexterity));
+                equipment.stat_cache = self.calculate_equipment_stats(equipment, &item_bonuses, &item_properties, &magical_items, dex_modifier);
                 equipment.dirty = false;
 
                 combat_stats.power = 10 + equipment.stat_cache.attack_bonus + equipment.stat_cache.damage_bonus;
@@ -358,35 +595,127 @@ impl EquipmentBonusSystem {
         equipment: &Equipment,
         item_bonuses: &ReadStorage<ItemBonuses>,
         item_properties: &ReadStorage<ItemProperties>,
+        magical_items: &ReadStorage<MagicalItem>,
+        dex_modifier: i32,
     ) -> EquipmentStats {
         let mut total_stats = EquipmentStats::default();
 
+        // A weapon in each hand: the off-hand weapon adds half its damage, and Dexterity sets the attack modifier
+        let holds_weapon = |slot: &EquipmentSlot| equipment.get_equipped(slot)
+            .filter(|&item| matches!(item_properties.get(item).map(|props| &props.item_type), Some(ItemType::Weapon(_))));
+        let off_hand_weapon = holds_weapon(&EquipmentSlot::OffHand)
+            .filter(|_| holds_weapon(&EquipmentSlot::MainHand).is_some());
+        if off_hand_weapon.is_some() {
+            total_stats.attack_bonus += dual_wield_attack_modifier(dex_modifier);
+        }
+
         for &item_entity in equipment.slots.values().flatten() {
-            if let Some(bonuses) = item_bonuses.get(item_entity) {
-                let mut item_stats = EquipmentStats::default();
-                
-                item_stats.attack_bonus = bonuses.combat_bonuses.attack_bonus;
-                item_stats.damage_bonus = bonuses.combat_bonuses.damage_bonus;
-                item_stats.defense_bonus = bonuses.combat_bonuses.defense_bonus;
-                item_stats.critical_chance_bonus = bonuses.combat_bonuses.critical_chance_bonus;
-                item_stats.critical_damage_bonus = bonuses.combat_bonuses.critical_damage_bonus;
-
-                for (attr, value) in &bonuses.attribute_bonuses {
-                    match attr.as_str() {
-                        "Strength" => item_stats.strength_bonus += value,
-                        "Dexterity" => item_stats.dexterity_bonus += value,
-                        "Constitution" => item_stats.constitution_bonus += value,
-                        "Intelligence" => item_stats.intelligence_bonus += value,
-                        "Wisdom" => item_stats.wisdom_bonus += value,
-                        "Charisma" => item_stats.charisma_bonus += value,
+            // Curses drain the wearer even when the item itself is broken
+            if let Some(curse) = magical_items.get(item_entity).and_then(|magical| magical.curse.as_ref()) {
+                let mut penalty = EquipmentStats::default();
+                match &curse.curse_type {
+                    CurseType::Weakness => penalty.damage_bonus -= curse.power,
+                    CurseType::Accuracy => penalty.attack_bonus -= curse.power,
+                    CurseType::Vulnerability => penalty.defense_bonus -= curse.power,
+                    CurseType::AttributePenalty(attr, amount) => match attr.as_str() {
+                        "Strength" => penalty.strength_bonus -= amount,
+                        "Dexterity" => penalty.dexterity_bonus -= amount,
+                        "Constitution" => penalty.constitution_bonus -= amount,
+                        "Intelligence" => penalty.intelligence_bonus -= amount,
+                        "Wisdom" => penalty.wisdom_bonus -= amount,
+                        "Charisma" => penalty.charisma_bonus -= amount,
                         _ => {}
-                    }
+                    },
+                    _ => {}
                 }
+                total_stats.add(&penalty);
+            }
 
-                total_stats.add(&item_stats);
+            let mut item_stats = item_equipment_stats(item_bonuses.get(item_entity), item_properties.get(item_entity));
+            if off_hand_weapon == Some(item_entity) {
+                item_stats.damage_bonus /= 2;
             }
+            total_stats.add(&item_stats);
         }
 
         total_stats
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::{WeaponType, ItemTag};
+
+    fn weapon(name: &str, weapon_type: WeaponType) -> ItemProperties {
+        ItemProperties::new(name.to_string(), ItemType::Weapon(weapon_type))
+    }
+
+    #[test]
+    fn test_two_handed_weapons_need_a_free_off_hand() {
+        let bow = weapon("Longbow", WeaponType::Bow);
+        let shield = ItemProperties::new("Buckler".to_string(), ItemType::Armor(ArmorType::Shield));
+
+        assert!(check_hand_occupancy(&EquipmentSlot::MainHand, &bow, None).is_ok());
+        assert!(check_hand_occupancy(&EquipmentSlot::MainHand, &bow, Some(&shield)).is_err());
+        assert!(check_hand_occupancy(&EquipmentSlot::OffHand, &shield, Some(&bow)).is_err());
+
+        let greatsword = weapon("Greatsword", WeaponType::Sword).add_tag(ItemTag::TwoHanded);
+        assert!(is_two_handed(&greatsword));
+        assert!(check_hand_occupancy(&EquipmentSlot::OffHand, &shield, Some(&greatsword)).is_err());
+        assert!(check_hand_occupancy(&EquipmentSlot::OffHand, &greatsword, None).is_err());
+    }
+
+    #[test]
+    fn test_dual_wielding_light_weapons() {
+        let sword = weapon("Short Sword", WeaponType::Sword);
+        let dagger = weapon("Dagger", WeaponType::Dagger);
+
+        assert!(EquipmentSlot::OffHand.can_equip_item_type(&dagger.item_type));
+        assert!(EquipmentSlot::OffHand.can_equip_item_type(&sword.item_type));
+        assert!(!EquipmentSlot::OffHand.can_equip_item_type(&ItemType::Weapon(WeaponType::Staff)));
+        assert!(check_hand_occupancy(&EquipmentSlot::OffHand, &dagger, Some(&sword)).is_ok());
+
+        assert_eq!(dual_wield_attack_modifier(0), -2);
+        assert_eq!(dual_wield_attack_modifier(3), 1);
+    }
+
+    #[test]
+    fn test_comparison_against_the_equipped_item_in_the_same_slot() {
+        use specs::{World, WorldExt, Builder};
+
+        let mut world = World::new();
+        world.register::<ItemProperties>();
+        world.register::<ItemBonuses>();
+
+        let sword = |world: &mut World, name: &str, damage: i32, weight: f32| {
+            let mut bonuses = ItemBonuses::new();
+            bonuses.combat_bonuses.damage_bonus = damage;
+            world.create_entity()
+                .with(weapon(name, WeaponType::Sword).with_weight(weight))
+                .with(bonuses)
+                .build()
+        };
+        let old_sword = sword(&mut world, "Rusty Sword", 4, 3.0);
+        let new_sword = sword(&mut world, "Fine Sword", 7, 3.5);
+        let potion = world.create_entity()
+            .with(ItemProperties::new("Potion".to_string(), ItemType::Consumable(ConsumableType::Potion)))
+            .build();
+
+        let mut equipment = Equipment::new();
+        equipment.equip_item(EquipmentSlot::MainHand, old_sword);
+
+        let properties = world.read_storage::<ItemProperties>();
+        let bonuses = world.read_storage::<ItemBonuses>();
+        let comparison = compare_equipment(new_sword, &equipment, &properties, &bonuses).unwrap();
+        assert_eq!(comparison.slot, EquipmentSlot::MainHand);
+        assert_eq!(comparison.equipped, Some(old_sword));
+
+        let power = &comparison.deltas[0];
+        assert_eq!((power.label, power.delta(), power.is_improvement()), ("Power", 3.0, Some(true)));
+        assert_eq!(comparison.deltas[1].is_improvement(), None);
+        let weight = comparison.deltas.last().unwrap();
+        assert_eq!((weight.label, weight.is_improvement()), ("Weight", Some(false)));
+
+        assert!(compare_equipment(potion, &equipment, &properties, &bonuses).is_none());
+    }
+}
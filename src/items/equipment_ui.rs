@@ -284,6 +284,7 @@ impl EquipmentUI {
             EquipmentSlot::Amulet,
             EquipmentSlot::Cloak,
             EquipmentSlot::Belt,
+            EquipmentSlot::Quiver,
         ]
     }
 
@@ -301,6 +302,7 @@ impl EquipmentUI {
                 crate::items::ArmorType::Amulet => Some(EquipmentSlot::Amulet),
                 crate::items::ArmorType::Cloak => Some(EquipmentSlot::Cloak),
             },
+            crate::items::ItemType::Consumable(crate::items::ConsumableType::Ammunition) => Some(EquipmentSlot::Quiver),
             _ => None,
         }
     }
@@ -58,6 +58,7 @@ impl InventoryIntegrationExample {
         world.register::<ItemProperties>();
         world.register::<crate::items::ItemStack>();
         world.register::<crate::items::ItemIdentification>();
+        world.register::<crate::items::Ammunition>();
         world.register::<crate::items::MagicalItem>();
         world.register::<crate::items::ItemBonuses>();
 
@@ -423,7 +424,7 @@ impl InventoryGameState {
     fn drop_item(&mut self, item_entity: Entity) {
         // Create drop intent
         self.world.write_storage::<WantsToDropItem>()
-            .insert(self.player_entity, WantsToDropItem { item: item_entity })
+            .insert(self.player_entity, WantsToDropItem { item: item_entity, quantity: 1 })
             .expect("Failed to insert drop intent");
     }
 
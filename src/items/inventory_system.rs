@@ -1,8 +1,11 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
-use crate::components::{Position, Player, Name, Item, Inventory, WantsToPickupItem, WantsToDropItem};
-use crate::items::{ItemProperties, ItemStack, get_item_display_name};
-use crate::resources::{GameLog, RandomNumberGenerator};
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect, Builder, LazyUpdate};
+use crate::components::{Position, Player, Name, Item, Inventory, Renderable, WantsToPickupItem, WantsToDropItem, ForSale};
+use crate::items::{ItemProperties, ItemStack, ItemIdentification, Consumable, Ammunition, get_item_display_name, stacks_match};
+use crate::resources::{GameLog, GameEvents, RandomNumberGenerator};
+use crate::achievements::GameEvent;
 use crate::map::Map;
+use crate::ui::{TutorialEvents, TutorialTrigger};
+use crate::localization::{self, Message};
 
 // Enhanced Inventory component with more features
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -98,9 +101,7 @@ impl AdvancedInventory {
         let mut total = self.gold;
         
         for slot in &self.items {
-            if let Some(value) = crate::items::get_item_current_value(world, slot.entity) {
-                total += value * slot.quantity;
-            }
+            total += crate::items::get_item_current_value(world, slot.entity) * slot.quantity;
         }
         
         total
@@ -126,16 +127,16 @@ impl AdvancedInventory {
             },
             InventorySortMode::Value => {
                 self.items.sort_by(|a, b| {
-                    let value_a = crate::items::get_item_current_value(world, a.entity);
-                    let value_b = crate::items::get_item_current_value(world, b.entity);
+                    let value_a = crate::items::get_item_current_value(world, a.entity) * a.quantity;
+                    let value_b = crate::items::get_item_current_value(world, b.entity) * b.quantity;
                     value_b.cmp(&value_a) // Descending order
                 });
             },
             InventorySortMode::Weight => {
                 let properties = world.read_storage::<ItemProperties>();
                 self.items.sort_by(|a, b| {
-                    let weight_a = properties.get(a.entity).map(|p| p.weight).unwrap_or(0.0);
-                    let weight_b = properties.get(b.entity).map(|p| p.weight).unwrap_or(0.0);
+                    let weight_a = properties.get(a.entity).map(|p| p.weight * a.quantity as f32).unwrap_or(0.0);
+                    let weight_b = properties.get(b.entity).map(|p| p.weight * b.quantity as f32).unwrap_or(0.0);
                     weight_a.partial_cmp(&weight_b).unwrap_or(std::cmp::Ordering::Equal)
                 });
             },
@@ -162,6 +163,7 @@ impl AdvancedInventory {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InventorySlot {
+    #[serde(with = "crate::persistence::entity_refs")]
     pub entity: Entity,
     pub quantity: i32,
     pub locked: bool, // Prevents accidental dropping
@@ -216,8 +218,13 @@ impl<'a> System<'a> for ItemPickupSystem {
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, ItemProperties>,
+        ReadStorage<'a, ItemIdentification>,
         WriteStorage<'a, ItemStack>,
+        ReadStorage<'a, ForSale>,
+        ReadStorage<'a, Player>,
         Write<'a, GameLog>,
+        Write<'a, TutorialEvents>,
+        Write<'a, GameEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -228,52 +235,87 @@ impl<'a> System<'a> for ItemPickupSystem {
             mut positions,
             names,
             properties,
+            identifications,
             mut stacks,
+            for_sale,
+            players,
             mut gamelog,
+            mut tutorials,
+            mut events,
         ) = data;
 
         let mut to_remove = Vec::new();
 
         for (entity, pickup, inventory) in (&entities, &wants_pickup, &mut inventories).join() {
             let item_entity = pickup.item;
+            to_remove.push(entity);
+            // Rival adventurers pick things up too, without a word in the log
+            let is_player = players.contains(entity);
             
             // Get item properties
-            if let Some(props) = properties.get(item_entity) {
-                let item_name = names.get(item_entity)
-                    .map(|n| n.name.clone())
-                    .unwrap_or("Unknown Item".to_string());
-
-                // Check if item has a stack component
-                let quantity = if let Some(stack) = stacks.get(item_entity) {
-                    stack.quantity
-                } else {
-                    1
-                };
-
-                let total_weight = props.weight * quantity as f32;
+            let props = match properties.get(item_entity) {
+                Some(props) => props,
+                None => continue,
+            };
+            let item_name = names.get(item_entity)
+                .map(|n| n.name.clone())
+                .unwrap_or("Unknown Item".to_string());
+
+            // Check if item has a stack component
+            let quantity = if let Some(stack) = stacks.get(item_entity) {
+                stack.quantity
+            } else {
+                1
+            };
+
+            let total_weight = props.weight * quantity as f32;
+            if inventory.current_weight + total_weight > inventory.weight_limit {
+                if is_player {
+                    gamelog.add_message(Message::new("log.too_heavy"));
+                }
+                continue;
+            }
 
-                // Try to add to inventory
-                if inventory.add_item(item_entity, quantity, props.weight) {
-                    // Remove item from world position
+            // Fold into matching stacks already carried before taking up a slot of its own
+            let left = merge_into_carried_stacks(inventory, item_entity, quantity, &properties, &identifications, &mut stacks, &for_sale);
+            inventory.current_weight += props.weight * (quantity - left) as f32;
+            let picked_up = if left == 0 {
+                entities.delete(item_entity).expect("Unable to delete merged item");
+                quantity
+            } else {
+                if let Some(stack) = stacks.get_mut(item_entity) {
+                    stack.quantity = left;
+                }
+                if inventory.add_item(item_entity, left, props.weight) {
                     positions.remove(item_entity);
-                    
-                    // Log the pickup
-                    if quantity > 1 {
-                        gamelog.entries.push(format!("You pick up {} {}s.", quantity, item_name));
-                    } else {
-                        gamelog.entries.push(format!("You pick up the {}.", item_name));
-                    }
+                    quantity
                 } else {
-                    // Inventory full or overweight
-                    if inventory.is_full() {
-                        gamelog.entries.push("Your inventory is full!".to_string());
-                    } else if inventory.current_weight + total_weight > inventory.weight_limit {
-                        gamelog.entries.push("That would be too heavy to carry!".to_string());
-                    }
+                    // Whatever didn't fit onto a carried stack stays on the floor
+                    quantity - left
                 }
+            };
+
+            if picked_up == 0 {
+                if is_player {
+                    gamelog.add_message(Message::new("log.inventory_full"));
+                }
+                continue;
+            }
+            if !is_player {
+                continue;
             }
 
-            to_remove.push(entity);
+            // Log the pickup
+            if picked_up > 1 {
+                gamelog.add_message(Message::new("log.pick_up_many").arg("count", picked_up).arg("item", localization::name(&item_name)));
+            } else {
+                gamelog.add_message(Message::new("log.pick_up").arg("item", localization::name(&item_name)));
+            }
+            if picked_up < quantity {
+                gamelog.add_message(Message::new("log.no_room_for_rest").arg("count", quantity - picked_up));
+            }
+            tutorials.publish(TutorialTrigger::FirstItemPickup);
+            events.publish(GameEvent::ItemCollected);
         }
 
         // Clean up pickup intents
@@ -283,6 +325,37 @@ impl<'a> System<'a> for ItemPickupSystem {
     }
 }
 
+/// Add as much of an item as fits onto the matching stacks in an inventory, returning how many
+/// are left over
+fn merge_into_carried_stacks(
+    inventory: &mut AdvancedInventory,
+    item: Entity,
+    quantity: i32,
+    properties: &ReadStorage<ItemProperties>,
+    identifications: &ReadStorage<ItemIdentification>,
+    stacks: &mut WriteStorage<ItemStack>,
+    for_sale: &ReadStorage<ForSale>,
+) -> i32 {
+    // Unpaid wares keep to a slot of their own, so paid and unpaid never share a stack
+    if !stacks.contains(item) || for_sale.contains(item) {
+        return quantity;
+    }
+    let mut left = quantity;
+    for slot in inventory.items.iter_mut() {
+        if left == 0 {
+            break;
+        }
+        if for_sale.contains(slot.entity) || !stacks_match(properties, identifications, slot.entity, item) {
+            continue;
+        }
+        if let Some(stack) = stacks.get_mut(slot.entity) {
+            left = stack.add(left);
+            slot.quantity = stack.quantity;
+        }
+    }
+    left
+}
+
 // System for handling item dropping
 pub struct ItemDropSystem;
 
@@ -294,10 +367,15 @@ impl<'a> System<'a> for ItemDropSystem {
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, ItemProperties>,
+        ReadStorage<'a, ItemIdentification>,
         WriteStorage<'a, ItemStack>,
         ReadStorage<'a, Player>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Consumable>,
+        ReadStorage<'a, Ammunition>,
         Write<'a, GameLog>,
         ReadExpect<'a, Map>,
+        Read<'a, LazyUpdate>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -308,10 +386,15 @@ impl<'a> System<'a> for ItemDropSystem {
             mut positions,
             names,
             properties,
+            identifications,
             mut stacks,
             players,
+            renderables,
+            consumables,
+            ammunition,
             mut gamelog,
             map,
+            lazy,
         ) = data;
 
         let mut to_remove = Vec::new();
@@ -330,19 +413,66 @@ impl<'a> System<'a> for ItemDropSystem {
                     if let Some(player_pos) = positions.get(entity) {
                         let drop_pos = self.find_drop_position(player_pos, &map);
                         
-                        // Remove from inventory (drop 1 item by default)
-                        if let Some((dropped_entity, quantity)) = inventory.remove_item(slot_index, 1, props.weight) {
-                            // Place item in world
-                            positions.insert(dropped_entity, drop_pos)
-                                .expect("Failed to set dropped item position");
-
-                            // Update stack if necessary
-                            if let Some(stack) = stacks.get_mut(dropped_entity) {
-                                stack.quantity = quantity;
+                        let left_in_slot = inventory.items[slot_index].quantity - drop_intent.quantity.max(1);
+                        if let Some((dropped_entity, quantity)) = inventory.remove_item(slot_index, drop_intent.quantity.max(1), props.weight) {
+                            // Whatever fits joins a matching stack already lying there
+                            let floor_stack = (&entities, &positions, &stacks).join()
+                                .find(|(other, pos, stack)| pos.x == drop_pos.x && pos.y == drop_pos.y && !stack.is_full()
+                                    && stacks_match(&properties, &identifications, *other, dropped_entity))
+                                .map(|(other, _, _)| other);
+                            let onto_floor = match floor_stack.and_then(|floor| stacks.get_mut(floor)) {
+                                Some(stack) => stack.add(quantity),
+                                None => quantity,
+                            };
+
+                            if left_in_slot > 0 {
+                                // Part of a stack: what stays behind keeps the entity, the
+                                // dropped part becomes a new stack on the floor
+                                if let Some(stack) = stacks.get_mut(dropped_entity) {
+                                    stack.quantity = left_in_slot;
+                                }
+                                if onto_floor > 0 {
+                                    let mut dropped = lazy.create_entity(&entities)
+                                        .with(Item)
+                                        .with(props.clone())
+                                        .with(ItemStack::new(onto_floor, props.stack_size.max(onto_floor)))
+                                        .with(drop_pos);
+                                    if let Some(name) = names.get(dropped_entity) {
+                                        dropped = dropped.with(name.clone());
+                                    }
+                                    if let Some(renderable) = renderables.get(dropped_entity) {
+                                        dropped = dropped.with(renderable.clone());
+                                    }
+                                    if let Some(consumable) = consumables.get(dropped_entity) {
+                                        dropped = dropped.with(consumable.clone());
+                                    }
+                                    if let Some(identification) = identifications.get(dropped_entity) {
+                                        dropped = dropped.with(identification.clone());
+                                    }
+                                    if let Some(ammo) = ammunition.get(dropped_entity) {
+                                        dropped = dropped.with(ammo.clone());
+                                    }
+                                    dropped.build();
+                                }
+                            } else if onto_floor > 0 {
+                                // Place item in world
+                                positions.insert(dropped_entity, drop_pos)
+                                    .expect("Failed to set dropped item position");
+
+                                if let Some(stack) = stacks.get_mut(dropped_entity) {
+                                    stack.quantity = onto_floor;
+                                }
+                            } else {
+                                // All of it went onto the stack on the floor
+                                entities.delete(dropped_entity).expect("Unable to delete merged item");
                             }
 
                             // Log the drop
-                            gamelog.entries.push(format!("You drop the {}.", item_name));
+                            if quantity > 1 {
+                                gamelog.add_message(Message::new("log.drop_many").arg("count", quantity).arg("item", localization::name(&item_name)));
+                            } else {
+                                gamelog.add_message(Message::new("log.drop").arg("item", localization::name(&item_name)));
+                            }
                         }
                     }
                 }
@@ -379,7 +509,7 @@ impl ItemDropSystem {
 
             if x >= 0 && x < map.width && y >= 0 && y < map.height {
                 let idx = map.xy_idx(x, y);
-                if map.tiles[idx].walkable {
+                if !map.tiles[idx].blocks_movement() {
                     return Position { x, y };
                 }
             }
@@ -511,9 +641,11 @@ pub struct Pickupable;
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 #[storage(VecStorage)]
 pub struct Container {
+    #[serde(with = "crate::persistence::entity_refs::vec")]
     pub items: Vec<Entity>,
     pub capacity: usize,
     pub locked: bool,
+    #[serde(with = "crate::persistence::entity_refs::option")]
     pub key_required: Option<Entity>,
     pub container_type: ContainerType,
 }
@@ -573,7 +705,85 @@ pub struct InventoryBonus {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use specs::{World, WorldExt, Builder};
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::items::{ItemType, ConsumableType};
+    use crate::map::TileType;
+
+    fn stack_world() -> (World, Entity) {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<ItemProperties>();
+        world.register::<ItemStack>();
+        world.register::<ItemIdentification>();
+        world.register::<Consumable>();
+        world.insert(GameLog::new(10));
+        world.insert(TutorialEvents::default());
+        world.insert(GameEvents::default());
+        let mut map = Map::new(10, 10, 1);
+        for tile in map.tiles.iter_mut() {
+            *tile = TileType::Floor;
+        }
+        world.insert(map);
+        let player = world.create_entity()
+            .with(Player {})
+            .with(Position { x: 5, y: 5 })
+            .with(AdvancedInventory::new(10, 50.0))
+            .build();
+        (world, player)
+    }
+
+    fn potions(world: &mut World, quantity: i32) -> Entity {
+        let props = ItemProperties::new("Health Potion".to_string(), ItemType::Consumable(ConsumableType::Potion))
+            .with_weight(0.5)
+            .with_stack_size(10);
+        world.create_entity()
+            .with(Item)
+            .with(props)
+            .with(ItemStack::new(quantity, 10))
+            .with(Position { x: 5, y: 5 })
+            .build()
+    }
+
+    #[test]
+    fn test_picked_up_stacks_merge_with_carried_ones() {
+        let (mut world, player) = stack_world();
+        let first = potions(&mut world, 3);
+        let second = potions(&mut world, 4);
+
+        for item in [first, second] {
+            world.write_storage::<WantsToPickupItem>().insert(player, WantsToPickupItem { item }).unwrap();
+            ItemPickupSystem.run_now(&world);
+            world.maintain();
+        }
+
+        let inventories = world.read_storage::<AdvancedInventory>();
+        let inventory = inventories.get(player).unwrap();
+        assert_eq!(inventory.items.len(), 1);
+        assert_eq!(inventory.items[0].quantity, 7);
+        assert_eq!(inventory.current_weight, 3.5);
+        assert_eq!(world.read_storage::<ItemStack>().get(first).map(|stack| stack.quantity), Some(7));
+        assert!(!world.is_alive(second));
+    }
+
+    #[test]
+    fn test_dropped_stacks_merge_with_one_on_the_floor() {
+        let (mut world, player) = stack_world();
+        let carried = potions(&mut world, 5);
+        let on_floor = potions(&mut world, 2);
+        world.write_storage::<Position>().remove(carried);
+        world.write_storage::<AdvancedInventory>().get_mut(player).unwrap().add_item(carried, 5, 0.5);
+
+        world.write_storage::<WantsToDropItem>().insert(player, WantsToDropItem { item: carried, quantity: 3 }).unwrap();
+        ItemDropSystem.run_now(&world);
+        world.maintain();
+
+        assert_eq!(world.read_storage::<ItemStack>().get(on_floor).map(|stack| stack.quantity), Some(5));
+        assert_eq!(world.read_storage::<ItemStack>().get(carried).map(|stack| stack.quantity), Some(2));
+        let inventories = world.read_storage::<AdvancedInventory>();
+        assert_eq!(inventories.get(player).unwrap().items[0].quantity, 2);
+        assert_eq!((&world.read_storage::<Item>(), &world.read_storage::<Position>()).join().count(), 1);
+    }
 
     #[test]
     fn test_advanced_inventory() {
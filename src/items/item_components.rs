@@ -89,6 +89,11 @@ impl ItemProperties {
         }
     }
 
+    /// Worn below its full durability; items without durability never need repairs
+    pub fn needs_repair(&self) -> bool {
+        self.durability.as_ref().map_or(false, |durability| durability.current < durability.max)
+    }
+
     pub fn repair(&mut self, amount: i32) {
         if let Some(durability) = &mut self.durability {
             durability.current = (durability.current + amount).min(durability.max);
@@ -162,6 +167,7 @@ pub enum ToolType {
     Rope,
     Key,
     Container,
+    RepairKit,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -374,7 +380,7 @@ impl ItemStack {
     }
 
     pub fn add(&mut self, amount: i32) -> i32 {
-        let can_add = (self.max_stack - self.quantity).min(amount);
+        let can_add = (self.max_stack - self.quantity).max(0).min(amount);
         self.quantity += can_add;
         amount - can_add // Return overflow
     }
@@ -448,6 +454,26 @@ impl MagicalItem {
         self.curse.is_some()
     }
 
+    /// Whether the item carries a curse its owner knows about
+    pub fn has_known_curse(&self) -> bool {
+        self.curse.as_ref().map_or(false, |curse| curse.revealed)
+    }
+
+    /// Reveal a hidden curse; returns the curse if it was newly revealed
+    pub fn reveal_curse(&mut self) -> Option<&Curse> {
+        match &mut self.curse {
+            Some(curse) if !curse.revealed => {
+                curse.revealed = true;
+                Some(curse)
+            },
+            _ => None,
+        }
+    }
+
+    pub fn remove_curse(&mut self) -> Option<Curse> {
+        self.curse.take()
+    }
+
     pub fn total_enchantment_power(&self) -> i32 {
         self.enchantments.iter().map(|e| e.power).sum()
     }
@@ -497,7 +523,37 @@ pub struct Curse {
     pub description: String,
     pub curse_type: CurseType,
     pub power: i32,
+    /// Whether a scroll can lift it; anything else needs a cleric
     pub removable: bool,
+    /// Hidden curses show up on identification or the first time they take effect
+    #[serde(default)]
+    pub revealed: bool,
+}
+
+impl Curse {
+    /// Durability lost per point of wear
+    pub fn wear_multiplier(&self) -> i32 {
+        match self.curse_type {
+            CurseType::Fragility => 1 + self.power,
+            _ => 1,
+        }
+    }
+
+    /// Percent chance per hit taken that the wearer is teleported away
+    pub fn teleport_chance(&self) -> i32 {
+        match self.curse_type {
+            CurseType::Teleportation => i32::min(100, self.power * 10),
+            _ => 0,
+        }
+    }
+
+    /// Whether the curse changes the wearer's stats as soon as it is worn
+    pub fn is_stat_penalty(&self) -> bool {
+        matches!(
+            self.curse_type,
+            CurseType::Weakness | CurseType::Vulnerability | CurseType::Accuracy | CurseType::AttributePenalty(_, _)
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -520,6 +576,7 @@ pub enum CurseType {
     Hunger, // Increases food consumption
     Thirst, // Increases water consumption
     Madness, // Random actions
+    Teleportation, // Teleports the wearer at random when struck
     
     // Custom curses
     Custom(String),
@@ -559,6 +616,7 @@ impl ItemBonuses {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct CombatBonuses {
     pub attack_bonus: i32,
     pub damage_bonus: i32,
@@ -1,6 +1,9 @@
 use specs::{World, WorldExt, Builder, Entity};
 use crate::components::{Position, Renderable, Name, Item};
 use crate::items::item_components::*;
+use crate::items::durability_system::{RepairKit, RepairKitType};
+use crate::items::ammunition::{AmmoKind, AmmoMaterial};
+use crate::items::consumable_factory::ConsumableFactory;
 use crate::resources::RandomNumberGenerator;
 
 pub struct ItemFactory;
@@ -42,18 +45,19 @@ impl ItemFactory {
         position: Position,
         rng: &mut RandomNumberGenerator,
     ) -> Entity {
-        let (name, glyph, color, base_value, weight) = match weapon_type {
-            WeaponType::Sword => ("Iron Sword", '/', crossterm::style::Color::Grey, 50, 3.0),
-            WeaponType::Axe => ("Battle Axe", 'P', crossterm::style::Color::DarkGrey, 60, 4.0),
-            WeaponType::Mace => ("War Mace", 'T', crossterm::style::Color::Grey, 45, 3.5),
-            WeaponType::Dagger => ("Steel Dagger", '-', crossterm::style::Color::White, 25, 1.0),
-            WeaponType::Spear => ("Iron Spear", '|', crossterm::style::Color::DarkYellow, 40, 2.5),
-            WeaponType::Bow => ("Hunting Bow", ')', crossterm::style::Color::DarkYellow, 75, 2.0),
-            WeaponType::Crossbow => ("Light Crossbow", '}', crossterm::style::Color::DarkGrey, 100, 4.0),
-            WeaponType::Staff => ("Wooden Staff", '\\', crossterm::style::Color::DarkYellow, 30, 2.0),
-            WeaponType::Wand => ("Magic Wand", '/', crossterm::style::Color::Magenta, 80, 0.5),
-            WeaponType::Thrown => ("Throwing Knife", '-', crossterm::style::Color::Grey, 15, 0.5),
+        let (glyph, color, base_value) = match weapon_type {
+            WeaponType::Sword => ('/', crossterm::style::Color::Grey, 50),
+            WeaponType::Axe => ('P', crossterm::style::Color::DarkGrey, 60),
+            WeaponType::Mace => ('T', crossterm::style::Color::Grey, 45),
+            WeaponType::Dagger => ('-', crossterm::style::Color::White, 25),
+            WeaponType::Spear => ('|', crossterm::style::Color::DarkYellow, 40),
+            WeaponType::Bow => (')', crossterm::style::Color::DarkYellow, 75),
+            WeaponType::Crossbow => ('}', crossterm::style::Color::DarkGrey, 100),
+            WeaponType::Staff => ('\\', crossterm::style::Color::DarkYellow, 30),
+            WeaponType::Wand => ('/', crossterm::style::Color::Magenta, 80),
+            WeaponType::Thrown => ('-', crossterm::style::Color::Grey, 15),
         };
+        let name = weapon_type.item_name();
 
         let rarity = self.generate_rarity(rng);
         let final_value = (base_value as f32 * rarity.value_multiplier()) as i32;
@@ -62,35 +66,13 @@ impl ItemFactory {
             .with_description(format!("A {} weapon suitable for combat.", weapon_type.name()))
             .with_rarity(rarity)
             .with_value(final_value)
-            .with_weight(weight)
+            .with_weight(weapon_type.base_weight())
             .with_durability(100);
 
         let mut bonuses = ItemBonuses::new();
-        bonuses.combat_bonuses.attack_bonus = match weapon_type {
-            WeaponType::Sword => 5,
-            WeaponType::Axe => 7,
-            WeaponType::Mace => 6,
-            WeaponType::Dagger => 3,
-            WeaponType::Spear => 4,
-            WeaponType::Bow => 6,
-            WeaponType::Crossbow => 8,
-            WeaponType::Staff => 2,
-            WeaponType::Wand => 1,
-            WeaponType::Thrown => 2,
-        };
-
-        bonuses.combat_bonuses.damage_bonus = match weapon_type {
-            WeaponType::Sword => 8,
-            WeaponType::Axe => 12,
-            WeaponType::Mace => 10,
-            WeaponType::Dagger => 4,
-            WeaponType::Spear => 6,
-            WeaponType::Bow => 7,
-            WeaponType::Crossbow => 10,
-            WeaponType::Staff => 3,
-            WeaponType::Wand => 2,
-            WeaponType::Thrown => 3,
-        };
+        let (attack, damage) = weapon_type.base_bonuses();
+        bonuses.combat_bonuses.attack_bonus = attack;
+        bonuses.combat_bonuses.damage_bonus = damage;
 
         world.create_entity()
             .with(Item)
@@ -115,17 +97,18 @@ impl ItemFactory {
         position: Position,
         rng: &mut RandomNumberGenerator,
     ) -> Entity {
-        let (name, glyph, color, base_value, weight) = match armor_type {
-            ArmorType::Helmet => ("Iron Helmet", '^', crossterm::style::Color::Grey, 40, 2.0),
-            ArmorType::Chest => ("Chain Mail", '[', crossterm::style::Color::Grey, 80, 15.0),
-            ArmorType::Legs => ("Iron Greaves", '[', crossterm::style::Color::DarkGrey, 60, 8.0),
-            ArmorType::Boots => ("Leather Boots", '[', crossterm::style::Color::DarkYellow, 25, 2.0),
-            ArmorType::Gloves => ("Leather Gloves", '[', crossterm::style::Color::DarkYellow, 20, 1.0),
-            ArmorType::Shield => ("Iron Shield", ')', crossterm::style::Color::Grey, 50, 5.0),
-            ArmorType::Cloak => ("Traveler's Cloak", '(', crossterm::style::Color::DarkGreen, 30, 2.0),
-            ArmorType::Ring => ("Simple Ring", '=', crossterm::style::Color::Yellow, 100, 0.1),
-            ArmorType::Amulet => ("Bone Amulet", '"', crossterm::style::Color::White, 75, 0.2),
+        let (glyph, color, base_value) = match armor_type {
+            ArmorType::Helmet => ('^', crossterm::style::Color::Grey, 40),
+            ArmorType::Chest => ('[', crossterm::style::Color::Grey, 80),
+            ArmorType::Legs => ('[', crossterm::style::Color::DarkGrey, 60),
+            ArmorType::Boots => ('[', crossterm::style::Color::DarkYellow, 25),
+            ArmorType::Gloves => ('[', crossterm::style::Color::DarkYellow, 20),
+            ArmorType::Shield => (')', crossterm::style::Color::Grey, 50),
+            ArmorType::Cloak => ('(', crossterm::style::Color::DarkGreen, 30),
+            ArmorType::Ring => ('=', crossterm::style::Color::Yellow, 100),
+            ArmorType::Amulet => ('"', crossterm::style::Color::White, 75),
         };
+        let name = armor_type.item_name();
 
         let rarity = self.generate_rarity(rng);
         let final_value = (base_value as f32 * rarity.value_multiplier()) as i32;
@@ -134,21 +117,11 @@ impl ItemFactory {
             .with_description(format!("A piece of {} armor for protection.", armor_type.name()))
             .with_rarity(rarity)
             .with_value(final_value)
-            .with_weight(weight)
+            .with_weight(armor_type.base_weight())
             .with_durability(80);
 
         let mut bonuses = ItemBonuses::new();
-        bonuses.combat_bonuses.defense_bonus = match armor_type {
-            ArmorType::Helmet => 3,
-            ArmorType::Chest => 8,
-            ArmorType::Legs => 5,
-            ArmorType::Boots => 2,
-            ArmorType::Gloves => 1,
-            ArmorType::Shield => 6,
-            ArmorType::Cloak => 2,
-            ArmorType::Ring => 1,
-            ArmorType::Amulet => 1,
-        };
+        bonuses.combat_bonuses.defense_bonus = armor_type.base_defense();
 
         world.create_entity()
             .with(Item)
@@ -173,6 +146,14 @@ impl ItemFactory {
         position: Position,
         rng: &mut RandomNumberGenerator,
     ) -> Entity {
+        // Arrows and bolts come in bundles of a random material
+        if consumable_type == ConsumableType::Ammunition {
+            let kind = if rng.roll_dice(1, 3) == 1 { AmmoKind::Bolt } else { AmmoKind::Arrow };
+            let material = AmmoMaterial::random(rng);
+            let quantity = rng.range(10, 20);
+            return ConsumableFactory::new().create_ammunition(world, position, kind, material, quantity);
+        }
+
         let (name, glyph, color, base_value, weight, stack_size) = match consumable_type {
             ConsumableType::Potion => ("Health Potion", '!', crossterm::style::Color::Red, 25, 0.5, 10),
             ConsumableType::Food => ("Bread", '%', crossterm::style::Color::DarkYellow, 5, 0.2, 20),
@@ -225,6 +206,7 @@ impl ItemFactory {
             ToolType::Rope => ("Rope", '~', crossterm::style::Color::DarkYellow, 10, 2.0, "Strong rope for climbing."),
             ToolType::Key => ("Iron Key", '-', crossterm::style::Color::Yellow, 1, 0.1, "A key that opens something."),
             ToolType::Container => ("Wooden Chest", '=', crossterm::style::Color::DarkYellow, 50, 10.0, "A container for storing items."),
+            ToolType::RepairKit => ("Tinker's Kit", '(', crossterm::style::Color::DarkCyan, 60, 1.5, "A set of tools for repairing gear."),
         };
 
         let properties = ItemProperties::new(name.to_string(), ItemType::Tool(tool_type))
@@ -246,6 +228,39 @@ impl ItemFactory {
             .build()
     }
 
+    // Create repair kits
+    pub fn create_repair_kit(
+        &self,
+        world: &mut World,
+        kit_type: RepairKitType,
+        position: Position,
+    ) -> Entity {
+        let (name, value, repair_amount, uses, description) = match kit_type {
+            RepairKitType::Weaponsmith => ("Whetstone Kit", 25, 30, 3, "Whetstones and oil for mending weapons."),
+            RepairKitType::Armorsmith => ("Armorer's Kit", 30, 25, 3, "Rivets and leather straps for patching armor."),
+            RepairKitType::Universal => ("Tinker's Kit", 60, 20, 5, "A set of tools for repairing any gear."),
+        };
+
+        let properties = ItemProperties::new(name.to_string(), ItemType::Tool(ToolType::RepairKit))
+            .with_description(description.to_string())
+            .with_value(value)
+            .with_weight(1.5);
+
+        world.create_entity()
+            .with(Item)
+            .with(Name { name: name.to_string() })
+            .with(properties)
+            .with(RepairKit::new(kit_type, repair_amount, uses))
+            .with(position)
+            .with(Renderable {
+                glyph: '(',
+                fg: crossterm::style::Color::DarkCyan,
+                bg: crossterm::style::Color::Black,
+                render_order: 2,
+            })
+            .build()
+    }
+
     // Create materials
     pub fn create_material(
         &self,
@@ -407,6 +422,8 @@ impl ItemFactory {
             CurseType::Binding,
             CurseType::Fragility,
             CurseType::Weakness,
+            CurseType::Teleportation,
+            CurseType::AttributePenalty("Strength".to_string(), rng.roll_dice(1, 2)),
         ];
 
         let curse_type = curse_types[rng.roll_dice(1, curse_types.len()) - 1].clone();
@@ -418,6 +435,7 @@ impl ItemFactory {
             curse_type,
             power,
             removable: rng.roll_dice(1, 2) == 1,
+            revealed: false,
         }
     }
 }
@@ -438,9 +456,110 @@ impl WeaponType {
             WeaponType::Thrown => "thrown weapon",
         }
     }
+
+    /// What the item factory calls a plain one of these
+    pub fn item_name(&self) -> &'static str {
+        match self {
+            WeaponType::Sword => "Iron Sword",
+            WeaponType::Axe => "Battle Axe",
+            WeaponType::Mace => "War Mace",
+            WeaponType::Dagger => "Steel Dagger",
+            WeaponType::Spear => "Iron Spear",
+            WeaponType::Bow => "Hunting Bow",
+            WeaponType::Crossbow => "Light Crossbow",
+            WeaponType::Staff => "Wooden Staff",
+            WeaponType::Wand => "Magic Wand",
+            WeaponType::Thrown => "Throwing Knife",
+        }
+    }
+
+    pub fn base_weight(&self) -> f32 {
+        match self {
+            WeaponType::Sword => 3.0,
+            WeaponType::Axe => 4.0,
+            WeaponType::Mace => 3.5,
+            WeaponType::Dagger => 1.0,
+            WeaponType::Spear => 2.5,
+            WeaponType::Bow => 2.0,
+            WeaponType::Crossbow => 4.0,
+            WeaponType::Staff => 2.0,
+            WeaponType::Wand => 0.5,
+            WeaponType::Thrown => 0.5,
+        }
+    }
+
+    /// Attack and damage bonuses of a plain one of these, before rarity and depth
+    pub fn base_bonuses(&self) -> (i32, i32) {
+        match self {
+            WeaponType::Sword => (5, 8),
+            WeaponType::Axe => (7, 12),
+            WeaponType::Mace => (6, 10),
+            WeaponType::Dagger => (3, 4),
+            WeaponType::Spear => (4, 6),
+            WeaponType::Bow => (6, 7),
+            WeaponType::Crossbow => (8, 10),
+            WeaponType::Staff => (2, 3),
+            WeaponType::Wand => (1, 2),
+            WeaponType::Thrown => (2, 3),
+        }
+    }
+
+    /// Launchers and staves need both hands
+    pub fn is_two_handed(&self) -> bool {
+        matches!(self, WeaponType::Bow | WeaponType::Crossbow | WeaponType::Staff)
+    }
+
+    /// One-handed melee weapons light enough to carry in the off hand
+    pub fn can_dual_wield(&self) -> bool {
+        matches!(self, WeaponType::Sword | WeaponType::Axe | WeaponType::Mace | WeaponType::Dagger)
+    }
 }
 
 impl ArmorType {
+    /// What the item factory calls a plain one of these
+    pub fn item_name(&self) -> &'static str {
+        match self {
+            ArmorType::Helmet => "Iron Helmet",
+            ArmorType::Chest => "Chain Mail",
+            ArmorType::Legs => "Iron Greaves",
+            ArmorType::Boots => "Leather Boots",
+            ArmorType::Gloves => "Leather Gloves",
+            ArmorType::Shield => "Iron Shield",
+            ArmorType::Cloak => "Traveler's Cloak",
+            ArmorType::Ring => "Simple Ring",
+            ArmorType::Amulet => "Bone Amulet",
+        }
+    }
+
+    pub fn base_weight(&self) -> f32 {
+        match self {
+            ArmorType::Helmet => 2.0,
+            ArmorType::Chest => 15.0,
+            ArmorType::Legs => 8.0,
+            ArmorType::Boots => 2.0,
+            ArmorType::Gloves => 1.0,
+            ArmorType::Shield => 5.0,
+            ArmorType::Cloak => 2.0,
+            ArmorType::Ring => 0.1,
+            ArmorType::Amulet => 0.2,
+        }
+    }
+
+    /// Defense of a plain one of these, before rarity and depth
+    pub fn base_defense(&self) -> i32 {
+        match self {
+            ArmorType::Helmet => 3,
+            ArmorType::Chest => 8,
+            ArmorType::Legs => 5,
+            ArmorType::Boots => 2,
+            ArmorType::Gloves => 1,
+            ArmorType::Shield => 6,
+            ArmorType::Cloak => 2,
+            ArmorType::Ring => 1,
+            ArmorType::Amulet => 1,
+        }
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             ArmorType::Helmet => "helmet",
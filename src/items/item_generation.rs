@@ -1,7 +1,7 @@
 use specs::{World, WorldExt, Entity};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
-use crate::components::{Position, Name, Renderable, Item};
+use crate::components::{Position, Name, Renderable, Item, Difficulty};
 use crate::items::{
     ItemProperties, ItemType, ItemRarity, WeaponType, ArmorType, ConsumableType,
     ItemBonuses, MagicalItem, Enchantment, EnchantmentType, ItemStack, ItemFactory
@@ -14,6 +14,7 @@ pub struct ItemGenerator {
     pub affix_tables: HashMap<ItemType, AffixTable>,
     pub rarity_weights: RarityWeights,
     pub depth_scaling: DepthScaling,
+    pub difficulty: Difficulty,
 }
 
 impl ItemGenerator {
@@ -23,12 +24,19 @@ impl ItemGenerator {
             affix_tables: HashMap::new(),
             rarity_weights: RarityWeights::default(),
             depth_scaling: DepthScaling::default(),
+            difficulty: Difficulty::Normal,
         };
         
         generator.initialize_default_tables();
         generator
     }
 
+    /// Generator whose rarity weights follow the run's difficulty
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
     fn initialize_default_tables(&mut self) {
         self.create_default_loot_tables();
         self.create_default_affix_tables();
@@ -168,6 +176,14 @@ impl ItemGenerator {
             _ => {},
         }
 
+        // Easier runs find good loot more often, harder ones less
+        let rare_multiplier = self.difficulty.rare_loot_multiplier();
+        weights.iter_mut().for_each(|(rarity, weight)| {
+            if *rarity >= ItemRarity::Uncommon {
+                *weight = (*weight as f32 * rare_multiplier) as i32;
+            }
+        });
+
         let weight_vec: Vec<(ItemRarity, i32)> = weights.into_iter().collect();
         self.weighted_choice(&weight_vec, rng)
     }
@@ -15,6 +15,14 @@ pub mod integration_example;
 pub mod equipment_system;
 pub mod equipment_factory;
 pub mod containers;
+pub mod durability_system;
+pub mod enchanting_system;
+pub mod enchanting_ui;
+pub mod artifacts;
+pub mod curse_system;
+pub mod alchemy_system;
+pub mod alchemy_ui;
+pub mod ammunition;
 
 #[cfg(test)]
 mod tests;
@@ -48,8 +56,8 @@ pub use name_generator::{
 };
 pub use generation_integration::ItemGenerationIntegration;
 pub use consumable_system::{
-    Consumable, ConsumableEffect, StatusEffectType, StatusEffect, ConsumableRequirements,
-    ConsumableRestriction, ConsumableCooldowns, StatusEffects, WantsToUseConsumable,
+    Consumable, ConsumableEffect, ConsumableRequirements,
+    ConsumableRestriction, ConsumableCooldowns, WantsToUseConsumable,
     ConsumableUsageSystem, ConsumableUpdateSystem
 };
 pub use consumable_factory::{
@@ -58,9 +66,33 @@ pub use consumable_factory::{
 pub use consumable_integration::ConsumableIntegration;
 pub use equipment_system::{
     Equippable, Equipment, EquipmentSlot, EquipmentRequirements, EquipmentSet, SetBonus,
-    WantsToEquip, WantsToUnequip, EquipmentSystem, EquipmentStatsSystem, EquipmentSetSystem
+    WantsToEquip, WantsToUnequip, EquipmentSystem, EquipmentStatsSystem, EquipmentSetSystem,
+    is_two_handed, check_hand_occupancy, dual_wield_attack_modifier,
+    StatDelta, EquipmentComparison, compare_equipment, item_equipment_stats
 };
-pub use equipment_factory::{EquipmentFactory, EquipmentQuality};
+pub use equipment_factory::{EquipmentFactory, EquipmentQuality, CLIMATE_CLOAK_RESISTANCE};
+pub use durability_system::{
+    WearReason, EquipmentWear, RepairKit, RepairKitType, RepairSource, WantsToRepair,
+    BlacksmithService, DurabilitySystem, RepairSystem, is_item_functional
+};
+pub use enchanting_system::{
+    EssenceType, Essences, EnchantingStation, EnchantmentRecipe, WantsToDisenchant, WantsToEnchant,
+    EnchantingSystem, enchant_success_chance, disenchant_yield
+};
+pub use enchanting_ui::{EnchantingUI, EnchantingAction, EnchantingMode};
+pub use artifacts::{
+    ArtifactEffect, ArtifactDefinition, ArtifactDatabase, ArtifactRegistry, Artifact, ArtifactSystem,
+    ARTIFACT_LEVEL_CHANCE
+};
+pub use curse_system::{
+    CurseRemovalSource, WantsToRemoveCurse, ClericService, CurseSystem, lift_equipped_curses
+};
+pub use alchemy_system::{
+    AlchemyStation, AlchemyProduct, AlchemyRecipe, AlchemyJournal, WantsToBrew, AlchemySystem,
+    brew_success_chance, brew_yield
+};
+pub use alchemy_ui::{AlchemyUI, AlchemyAction};
+pub use ammunition::{AmmoKind, AmmoMaterial, Ammunition, WantsToShoot};
 pub use containers::{
     Container, ContainerType, TrapType, WantsToOpenContainer, WantsToCloseContainer,
     WantsToTakeFromContainer, WantsToPutInContainer, ContainerSystem, LootTable, LootEntry,
@@ -75,8 +107,8 @@ pub use item_components::{
 };
 
 // Utility functions for working with items
-use specs::{World, Entity, Join, WorldExt};
-use crate::components::{Item, Name, Position};
+use specs::{World, Entity, Join, WorldExt, ReadStorage, Builder};
+use crate::components::{Item, Name, Position, Renderable};
 
 /// Get the display name of an item, considering identification status
 pub fn get_item_display_name(world: &World, entity: Entity) -> Option<String> {
@@ -118,27 +150,123 @@ pub fn get_item_display_description(world: &World, entity: Entity) -> Option<Str
     }
 }
 
+/// Whether two items are the same stackable thing, so one stack can be merged into the other:
+/// identical consumables, ammunition and materials that are both known or both unidentified
+pub fn stacks_match(
+    properties: &ReadStorage<ItemProperties>,
+    identifications: &ReadStorage<ItemIdentification>,
+    item1: Entity,
+    item2: Entity,
+) -> bool {
+    let (props1, props2) = match (properties.get(item1), properties.get(item2)) {
+        (Some(props1), Some(props2)) => (props1, props2),
+        _ => return false,
+    };
+    let identified = |item| identifications.get(item).map(|identification| identification.identified);
+    item1 != item2 &&
+        props1.stack_size > 1 &&
+        props1.name == props2.name &&
+        props1.item_type == props2.item_type &&
+        props1.rarity == props2.rarity &&
+        identified(item1) == identified(item2)
+}
+
 /// Check if an item can be stacked with another item
 pub fn can_stack_items(world: &World, item1: Entity, item2: Entity) -> bool {
-    let properties = world.read_storage::<ItemProperties>();
     let stacks = world.read_storage::<ItemStack>();
+    if !stacks_match(&world.read_storage::<ItemProperties>(), &world.read_storage::<ItemIdentification>(), item1, item2) {
+        return false;
+    }
     
-    if let (Some(props1), Some(props2)) = (properties.get(item1), properties.get(item2)) {
-        // Items must be the same type and have the same properties
-        if props1.name == props2.name && 
-           props1.item_type == props2.item_type &&
-           props1.rarity == props2.rarity {
-            
-            // Both items must have stack components
-            if let (Some(stack1), Some(stack2)) = (stacks.get(item1), stacks.get(item2)) {
-                return !stack1.is_full() && !stack2.is_full();
-            }
-        }
+    // Both items must have stack components
+    if let (Some(stack1), Some(stack2)) = (stacks.get(item1), stacks.get(item2)) {
+        return !stack1.is_full() && !stack2.is_full();
     }
     
     false
 }
 
+/// How many of an item an entity stands for: the size of its stack, or one
+pub fn get_item_quantity(world: &World, entity: Entity) -> i32 {
+    world.read_storage::<ItemStack>().get(entity).map_or(1, |stack| stack.quantity.max(1))
+}
+
+/// Weight of a whole stack
+pub fn get_stack_weight(world: &World, entity: Entity) -> f32 {
+    let weight = world.read_storage::<ItemProperties>().get(entity).map_or(0.0, |props| props.weight);
+    weight * get_item_quantity(world, entity) as f32
+}
+
+/// Value of a whole stack, considering its condition and rarity
+pub fn get_stack_value(world: &World, entity: Entity) -> i32 {
+    get_item_current_value(world, entity) * get_item_quantity(world, entity)
+}
+
+/// The first repair kit in `owner`'s pack that can mend `item`
+pub fn repair_kit_for(world: &World, owner: Entity, item: Entity) -> Option<Entity> {
+    let properties = world.read_storage::<ItemProperties>();
+    let item_type = &properties.get(item)?.item_type;
+    let kits = world.read_storage::<RepairKit>();
+    let inventories = world.read_storage::<AdvancedInventory>();
+    inventories.get(owner)?.items.iter()
+        .map(|slot| slot.entity)
+        .find(|kit| kits.get(*kit).map_or(false, |kit| kit.can_repair(item_type)))
+}
+
+/// Split some of a carried stack off into a stack of its own in the same inventory, returning
+/// the new stack. Nothing is split off when the inventory has no slot free
+pub fn split_stack(world: &mut World, owner: Entity, item: Entity, quantity: i32) -> Option<Entity> {
+    let (slot_index, carried) = {
+        let inventories = world.read_storage::<AdvancedInventory>();
+        let inventory = inventories.get(owner)?;
+        let slot_index = inventory.find_item(item)?;
+        if inventory.is_full() {
+            return None;
+        }
+        (slot_index, inventory.items[slot_index].quantity)
+    };
+    if quantity <= 0 || quantity >= carried {
+        return None;
+    }
+    
+    let props = world.read_storage::<ItemProperties>().get(item)?.clone();
+    let name = world.read_storage::<Name>().get(item).cloned();
+    let renderable = world.read_storage::<Renderable>().get(item).cloned();
+    let consumable = world.read_storage::<Consumable>().get(item).cloned();
+    let identification = world.read_storage::<ItemIdentification>().get(item).cloned();
+    let ammunition = world.read_storage::<Ammunition>().get(item).cloned();
+    
+    let mut split = world.create_entity()
+        .with(Item)
+        .with(ItemStack::new(quantity, props.stack_size.max(quantity)))
+        .with(props);
+    if let Some(name) = name {
+        split = split.with(name);
+    }
+    if let Some(renderable) = renderable {
+        split = split.with(renderable);
+    }
+    if let Some(consumable) = consumable {
+        split = split.with(consumable);
+    }
+    if let Some(identification) = identification {
+        split = split.with(identification);
+    }
+    if let Some(ammunition) = ammunition {
+        split = split.with(ammunition);
+    }
+    let split = split.build();
+    
+    if let Some(stack) = world.write_storage::<ItemStack>().get_mut(item) {
+        stack.quantity = carried - quantity;
+    }
+    let mut inventories = world.write_storage::<AdvancedInventory>();
+    let inventory = inventories.get_mut(owner)?;
+    inventory.items[slot_index].quantity = carried - quantity;
+    inventory.items.insert(slot_index + 1, InventorySlot::new(split, quantity));
+    Some(split)
+}
+
 /// Get the total weight of all items at a position
 pub fn get_total_weight_at_position(world: &World, x: i32, y: i32) -> f32 {
     let entities = world.entities();
@@ -245,8 +373,15 @@ pub fn get_item_info_string(world: &World, entity: Entity) -> String {
     if let Some(props) = properties.get(entity) {
         info.push_str(&format!("Type: {:?}\n", props.item_type));
         info.push_str(&format!("Rarity: {}\n", props.rarity.name()));
-        info.push_str(&format!("Value: {} gold\n", get_item_current_value(world, entity)));
-        info.push_str(&format!("Weight: {:.1} lbs\n", props.weight));
+        let quantity = get_item_quantity(world, entity);
+        if quantity > 1 {
+            info.push_str(&format!("Quantity: {}\n", quantity));
+            info.push_str(&format!("Value: {} gold ({} each)\n", get_stack_value(world, entity), get_item_current_value(world, entity)));
+            info.push_str(&format!("Weight: {:.1} lbs ({:.1} each)\n", props.weight * quantity as f32, props.weight));
+        } else {
+            info.push_str(&format!("Value: {} gold\n", get_item_current_value(world, entity)));
+            info.push_str(&format!("Weight: {:.1} lbs\n", props.weight));
+        }
         
         if let Some(durability) = &props.durability {
             info.push_str(&format!("Condition: {} ({}/{})\n", 
@@ -273,7 +408,7 @@ pub fn get_item_info_string(world: &World, entity: Entity) -> String {
             info.push_str(&format!("  {}: {}\n", enchantment.name, enchantment.description));
         }
         
-        if let Some(curse) = &magical.curse {
+        if let Some(curse) = magical.curse.as_ref().filter(|curse| curse.revealed) {
             info.push_str(&format!("Cursed: {} - {}\n", curse.name, curse.description));
         }
     }
@@ -2,6 +2,7 @@ use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use crate::items::{ItemType, ItemRarity, WeaponType, ArmorType, ConsumableType, MaterialType};
 use crate::resources::RandomNumberGenerator;
+use crate::utils::NameGenerator;
 
 /// Procedural name generator for items
 pub struct ItemNameGenerator {
@@ -140,18 +141,12 @@ impl ItemNameGenerator {
     }
 
     fn generate_artifact_name(&self, rng: &mut RandomNumberGenerator) -> String {
-        if !self.artifact_names.is_empty() {
+        // Half of all artifacts come from the known list, the rest get a freshly minted name
+        if !self.artifact_names.is_empty() && rng.roll_dice(1, 2) == 1 {
             let artifact_name = &self.artifact_names[rng.roll_dice(1, self.artifact_names.len()) - 1];
             artifact_name.clone()
         } else {
-            // Fallback to generated name
-            let titles = vec![
-                "The Worldbreaker", "The Eternal Flame", "The Void Walker",
-                "The Star Forge", "The Time Render", "The Soul Keeper",
-                "The Dream Weaver", "The Reality Shaper", "The Fate Binder"
-            ];
-            let title = &titles[rng.roll_dice(1, titles.len()) - 1];
-            title.clone()
+            NameGenerator::artifact_name(rng)
         }
     }
 
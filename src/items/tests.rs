@@ -160,6 +160,7 @@ mod tests {
             curse_type: CurseType::Binding,
             power: 1,
             removable: false,
+            revealed: false,
         };
         
         magical_item.add_curse(curse);
@@ -0,0 +1,237 @@
+//! Answers the model has already given, kept between sessions.
+//!
+//! Asking the same thing twice, such as a description of the same kind of monster or room, gets
+//! the first answer back without calling the model again. Answers are keyed by a hash of the
+//! provider and model asked and everything sent, so a changed prompt template or a switch of
+//! backend never gets a stale answer; once the cache is full the answer used longest ago makes
+//! room for the new one.
+
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use crate::utils::{fnv1a, FNV_OFFSET_BASIS};
+use super::provider::{LlmRequest, LlmResponse};
+
+/// Where the cache is kept between sessions
+pub const MODEL_CACHE_PATH: &str = "saves/model_cache.json";
+/// Characters of a prompt kept alongside its answer, to tell entries apart when inspecting
+const PREVIEW_CHARS: usize = 60;
+
+/// One answer, and when it was last handed out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub text: String,
+    pub tokens: u32,
+    /// The start of the prompt it answered
+    pub preview: String,
+    /// Times it has been handed out instead of asking the model
+    pub hits: u32,
+    last_used: u64,
+}
+
+/// Answers kept by a hash of what was asked
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseCache {
+    /// Most answers kept; 0 keeps none
+    #[serde(skip)]
+    pub capacity: usize,
+    entries: HashMap<String, CachedResponse>,
+    /// Counts every lookup and insert, to tell which answer was used longest ago
+    clock: u64,
+    /// Lookups this session that found an answer, and that didn't
+    #[serde(skip)]
+    pub hits: u64,
+    #[serde(skip)]
+    pub misses: u64,
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize) -> Self {
+        ResponseCache { capacity, ..ResponseCache::default() }
+    }
+
+    /// The cache kept at `path`, cut down to `capacity`; empty if there is none yet
+    pub fn load(path: &str, capacity: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(ResponseCache::new(capacity));
+        }
+
+        let file_content = fs::read_to_string(path)?;
+        let mut cache: ResponseCache = serde_json::from_str(&file_content)?;
+        cache.resize(capacity);
+        Ok(cache)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    /// The key `request` to `model` is kept under: a 64-bit FNV-1a hash of the model, as
+    /// `ModelClient::model_id` names it, and everything sent
+    pub fn key(model: &str, request: &LlmRequest) -> String {
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut feed = |bytes: &[u8]| hash = fnv1a(hash, bytes);
+        feed(model.as_bytes());
+        feed(&[0]);
+        feed(request.system.as_deref().unwrap_or("").as_bytes());
+        feed(&[0]);
+        feed(request.prompt.as_bytes());
+        feed(&[0]);
+        feed(&request.max_tokens.to_le_bytes());
+        feed(&request.temperature.to_bits().to_le_bytes());
+        for stop in &request.stop {
+            feed(&[0]);
+            feed(stop.as_bytes());
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// The answer `model` gave to `request`, if one is kept
+    pub fn get(&mut self, model: &str, request: &LlmRequest) -> Option<LlmResponse> {
+        self.clock += 1;
+        match self.entries.get_mut(&ResponseCache::key(model, request)) {
+            Some(entry) => {
+                entry.hits += 1;
+                entry.last_used = self.clock;
+                self.hits += 1;
+                Some(LlmResponse { text: entry.text.clone(), tokens: entry.tokens })
+            },
+            None => {
+                self.misses += 1;
+                None
+            },
+        }
+    }
+
+    /// Keep `response` as the answer `model` gave to `request`, making room if the cache is full
+    pub fn insert(&mut self, model: &str, request: &LlmRequest, response: &LlmResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        let preview: String = request.prompt.lines().next().unwrap_or("").chars().take(PREVIEW_CHARS).collect();
+        self.entries.insert(ResponseCache::key(model, request), CachedResponse {
+            text: response.text.clone(),
+            tokens: response.tokens,
+            preview,
+            hits: 0,
+            last_used: self.clock,
+        });
+        self.evict();
+    }
+
+    /// Change how many answers are kept, dropping the least recently used if there are too many
+    pub fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Drop every answer. Returns how many went
+    pub fn purge(&mut self) -> usize {
+        let purged = self.entries.len();
+        self.entries.clear();
+        purged
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Up to `count` entries with their keys, the most handed out first
+    pub fn most_used(&self, count: usize) -> Vec<(&str, &CachedResponse)> {
+        let mut entries: Vec<(&str, &CachedResponse)> = self.entries.iter()
+            .map(|(key, entry)| (key.as_str(), entry))
+            .collect();
+        entries.sort_by(|a, b| b.1.hits.cmp(&a.1.hits).then(b.1.last_used.cmp(&a.1.last_used)));
+        entries.truncate(count);
+        entries
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let oldest = self.entries.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.entries.remove(&key),
+                None => return,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "mock:";
+
+    fn answer(text: &str) -> LlmResponse {
+        LlmResponse { text: text.to_string(), tokens: 10 }
+    }
+
+    #[test]
+    fn test_the_same_request_gets_the_same_answer_and_the_least_used_goes_first() {
+        let troll = LlmRequest::new("Describe this monster: Cave troll");
+        let crypt = LlmRequest::new("Describe this room: Crypt");
+        let ford = LlmRequest::new("Describe this room: Ford");
+        assert_eq!(ResponseCache::key(MODEL, &troll), ResponseCache::key(MODEL, &troll.clone()));
+        assert_ne!(ResponseCache::key(MODEL, &troll), ResponseCache::key(MODEL, &troll.clone().with_max_tokens(8)));
+
+        let mut cache = ResponseCache::new(2);
+        assert_eq!(cache.get(MODEL, &troll), None);
+        cache.insert(MODEL, &troll, &answer("It reeks of moss."));
+        cache.insert(MODEL, &crypt, &answer("Cold stone, colder air."));
+        assert_eq!(cache.get(MODEL, &troll).unwrap().text, "It reeks of moss.");
+        cache.insert(MODEL, &ford, &answer("The water runs fast."));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(MODEL, &crypt), None);
+        assert!(cache.get(MODEL, &ford).is_some());
+        assert!(cache.get(MODEL, &troll).is_some());
+        assert_eq!((cache.hits, cache.misses), (3, 2));
+        assert_eq!(cache.most_used(1)[0].1.preview, "Describe this monster: Cave troll");
+    }
+
+    #[test]
+    fn test_the_cache_keeps_its_order_between_sessions_and_can_be_emptied() {
+        let mut cache = ResponseCache::new(3);
+        let requests: Vec<LlmRequest> = (0..3).map(|i| LlmRequest::new(&format!("Describe room {}", i))).collect();
+        for request in &requests {
+            cache.insert(MODEL, request, &answer("Dust."));
+        }
+        cache.get(MODEL, &requests[0]);
+
+        let mut loaded: ResponseCache = serde_json::from_str(&serde_json::to_string(&cache).unwrap()).unwrap();
+        loaded.resize(2);
+        assert!(loaded.get(MODEL, &requests[0]).is_some());
+        assert!(loaded.get(MODEL, &requests[1]).is_none());
+        assert_eq!(loaded.purge(), 2);
+        assert!(loaded.is_empty());
+
+        let mut off = ResponseCache::new(0);
+        off.insert(MODEL, &requests[0], &answer("Dust."));
+        assert!(off.is_empty());
+    }
+    #[test]
+    fn test_answers_from_one_model_are_not_handed_out_for_another() {
+        let troll = LlmRequest::new("Describe this monster: Cave troll");
+        let mut cache = ResponseCache::new(4);
+        cache.insert("openai:gpt-4o-mini", &troll, &answer("It reeks of moss."));
+
+        assert!(cache.get("openai:gpt-4o-mini", &troll).is_some());
+        assert_eq!(cache.get("openai:gpt-4o", &troll), None);
+        assert_eq!(cache.get("llama_server:", &troll), None);
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 
 use super::llama_integration::LlamaError;
+use super::prompts::{PromptKind, PromptTemplate};
 
 /// Dialogue entry representing a conversation turn
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,9 +105,9 @@ impl Default for DialogueConfig {
     fn default() -> Self {
         DialogueConfig {
             max_history_length: 10,
-            system_prompt: "You are an NPC in a fantasy roguelike game. Respond in character based on your personality, knowledge, and relationship with the player. Keep responses concise (1-3 sentences) and appropriate to the fantasy setting.".to_string(),
+            system_prompt: PromptTemplate::latest(PromptKind::Dialogue).system.to_string(),
             temperature: 0.7,
-            max_tokens: 100,
+            max_tokens: PromptTemplate::latest(PromptKind::Dialogue).max_tokens,
             stop_sequences: vec!["\n".to_string(), "Player:".to_string()],
             model_name: None,
             timeout_seconds: 10,
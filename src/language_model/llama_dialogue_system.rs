@@ -13,6 +13,7 @@ use super::dialogue_system_trait::{
 };
 use super::llama_integration::{LlamaError, LlamaRequest, LlamaConfig};
 use super::model_manager::ModelManager;
+use super::prompts::{Prompt, PromptKind, PromptTemplate};
 
 /// Llama-based dialogue system implementation
 pub struct LlamaDialogueSystem {
@@ -36,39 +37,36 @@ impl LlamaDialogueSystem {
         self.persona_directory = path;
     }
     
-    /// Build a prompt from the dialogue context
-    fn build_prompt(&self, context: &DialogueContext, player_input: &str) -> String {
-        let mut prompt = String::new();
+    /// Build a prompt from the dialogue context, through the dialogue prompt template
+    fn build_prompt(&self, context: &DialogueContext, player_input: &str) -> Result<String, LlamaError> {
+        let mut character = String::new();
         
         // Add character information
-        prompt.push_str(&format!("Character: {}\n", context.character_name));
-        prompt.push_str(&format!("Description: {}\n", context.character_description));
-        prompt.push_str(&format!("Location: {}\n", context.location));
-        prompt.push_str(&format!("Relationship with player: {}\n", self.relationship_description(context.relationship)));
+        character.push_str(&format!("Character: {}\n", context.character_name));
+        character.push_str(&format!("Description: {}\n", context.character_description));
+        character.push_str(&format!("Location: {}\n", context.location));
+        character.push_str(&format!("Relationship with player: {}\n", self.relationship_description(context.relationship)));
         
         // Add traits
         if !context.traits.is_empty() {
-            prompt.push_str("Traits: ");
-            prompt.push_str(&context.traits.join(", "));
-            prompt.push_str("\n");
+            character.push_str("Traits: ");
+            character.push_str(&context.traits.join(", "));
+            character.push_str("\n");
         }
         
         // Add faction
         if let Some(faction) = &context.faction {
-            prompt.push_str(&format!("Faction: {}\n", faction));
+            character.push_str(&format!("Faction: {}\n", faction));
         }
         
         // Add knowledge
         if !context.knowledge.is_empty() {
-            prompt.push_str("Knowledge:\n");
+            character.push_str("Knowledge:\n");
             for item in &context.knowledge {
-                prompt.push_str(&format!("- {}\n", item));
+                character.push_str(&format!("- {}\n", item));
             }
         }
         
-        // Add conversation history
-        prompt.push_str("\nConversation:\n");
-        
         // Limit history to max_history_length
         let start_idx = if context.history.len() > self.config.max_history_length {
             context.history.len() - self.config.max_history_length
@@ -76,19 +74,22 @@ impl LlamaDialogueSystem {
             0
         };
         
+        let mut conversation = String::new();
         for entry in &context.history[start_idx..] {
             if let Some(emotion) = &entry.emotion {
-                prompt.push_str(&format!("{} [{}]: {}\n", entry.speaker, emotion, entry.text));
+                conversation.push_str(&format!("{} [{}]: {}\n", entry.speaker, emotion, entry.text));
             } else {
-                prompt.push_str(&format!("{}: {}\n", entry.speaker, entry.text));
+                conversation.push_str(&format!("{}: {}\n", entry.speaker, entry.text));
             }
         }
         
-        // Add player input
-        prompt.push_str(&format!("Player: {}\n", player_input));
-        prompt.push_str(&format!("{}:", context.character_name));
-        
-        prompt
+        let request = Prompt::new(PromptKind::Dialogue)
+            .arg("character", character)
+            .arg("conversation", conversation)
+            .arg("input", player_input)
+            .arg("speaker", &context.character_name)
+            .request()?;
+        Ok(request.prompt)
     }
     
     /// Build a prompt for generating dialogue options
@@ -248,7 +249,7 @@ impl DialogueSystem for LlamaDialogueSystem {
         };
         
         // Build the prompt
-        let prompt = self.build_prompt(context, player_input);
+        let prompt = self.build_prompt(context, player_input)?;
         
         // Create the request
         let request = LlamaRequest {
@@ -274,8 +275,10 @@ impl DialogueSystem for LlamaDialogueSystem {
             return Err(response.error.unwrap_or(LlamaError::InferenceFailed("Unknown error".to_string())));
         }
         
-        // Parse the response
+        // Parse the response, then make it fit to show
         let (text, emotion) = self.parse_response(&response.text);
+        let text = PromptTemplate::latest(PromptKind::Dialogue).finish(&text)
+            .ok_or_else(|| LlamaError::InferenceFailed("Nothing was left of the response once cleaned up".to_string()))?;
         
         // Create dialogue entry
         let entry = DialogueEntry {
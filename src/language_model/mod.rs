@@ -22,6 +22,11 @@ pub mod dialogue_ui_example;
 pub mod config_system;
 pub mod config_ui;
 pub mod config_example;
+pub mod provider;
+pub mod remote;
+pub mod queue;
+pub mod prompts;
+pub mod cache;
 
 pub use llama_integration::*;
 pub use model_manager::*;
@@ -46,4 +51,9 @@ pub use dialogue_ui_manager::*;
 pub use dialogue_ui_example::*;
 pub use config_system::*;
 pub use config_ui::*;
-pub use config_example::*;
\ No newline at end of file
+pub use config_example::*;
+pub use provider::*;
+pub use remote::*;
+pub use queue::*;
+pub use prompts::*;
+pub use cache::*;
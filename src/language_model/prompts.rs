@@ -0,0 +1,300 @@
+//! What the model is asked, and what is done with its answer before anyone sees it.
+//!
+//! Every use of the model has its prompt in a `PromptTemplate` here, numbered so a change of
+//! wording is a new version rather than an edit no one notices: the newest version of each is the
+//! one used. Answers go through `PromptTemplate::finish`, which strips markdown, stars out the
+//! words in `data/profanity.txt` and cuts the text back to the template's length. Each template is
+//! rendered with sample values into `data/prompt_snapshots`, and a test fails when one no longer
+//! matches, so changed prompts are reviewed like any other diff.
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use super::llama_integration::{utils, LlamaError};
+use super::provider::LlmRequest;
+
+/// Where each template's rendered sample is kept for review
+pub const PROMPT_SNAPSHOTS_DIR: &str = "data/prompt_snapshots";
+const WORD_LIST: &str = include_str!("../../data/profanity.txt");
+
+/// What the model is being asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PromptKind {
+    /// A townsperson's answer to the player
+    Dialogue,
+    /// A few words on a creature, item or place
+    Description,
+    /// The story of a guild contract, for the ledger
+    MissionReport,
+}
+
+impl PromptKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PromptKind::Dialogue => "dialogue",
+            PromptKind::Description => "description",
+            PromptKind::MissionReport => "mission_report",
+        }
+    }
+}
+
+/// One version of a prompt. `{name}` in the body is filled in from the prompt's arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    pub kind: PromptKind,
+    pub version: u32,
+    pub system: &'static str,
+    pub body: &'static str,
+    pub max_tokens: u32,
+    /// Longest an answer may run once finished
+    pub max_chars: usize,
+}
+
+/// Every template, old versions kept so their snapshots stay reviewable
+pub const TEMPLATES: &[PromptTemplate] = &[
+    PromptTemplate {
+        kind: PromptKind::Dialogue,
+        version: 1,
+        system: "You are an NPC in a fantasy roguelike game. Respond in character based on your personality, \
+            knowledge, and relationship with the player. Keep responses concise (1-3 sentences) and appropriate \
+            to the fantasy setting.",
+        body: "{character}\nConversation:\n{conversation}Player: {input}\n{speaker}:",
+        max_tokens: 100,
+        max_chars: 300,
+    },
+    PromptTemplate {
+        kind: PromptKind::Description,
+        version: 1,
+        system: "You describe things for a grim fantasy roguelike played in a terminal. Write one or two plain \
+            sentences, no more than 40 words, in the second person and the present tense, telling only what \
+            the player could see, hear or smell.",
+        body: "Describe this {kind}: {subject}\nDetails: {details}\nWrite it in {language}",
+        max_tokens: 64,
+        max_chars: 200,
+    },
+    PromptTemplate {
+        kind: PromptKind::MissionReport,
+        version: 1,
+        system: "You keep the ledger of an adventurers' guild in a grim fantasy town. Write two or three plain \
+            sentences, no more than 60 words, telling what happened to one of the guild's agents on a \
+            contract. Stick to the facts you are given and make up only small details.",
+        body: "Agent: {agent}, a {class}\nTraits: {traits}\nContract: {contract}\nOutcome: {outcome}\nWrite it in {language}",
+        max_tokens: 96,
+        max_chars: 280,
+    },
+];
+
+impl PromptTemplate {
+    /// The newest version of `kind`'s template
+    pub fn latest(kind: PromptKind) -> &'static PromptTemplate {
+        TEMPLATES.iter()
+            .filter(|template| template.kind == kind)
+            .max_by_key(|template| template.version)
+            .expect("every kind of prompt has a template")
+    }
+
+    pub fn get(kind: PromptKind, version: u32) -> Option<&'static PromptTemplate> {
+        TEMPLATES.iter().find(|template| template.kind == kind && template.version == version)
+    }
+
+    /// The body with every `{name}` filled in from `args`, or the first one missing
+    pub fn render(&self, args: &[(&str, String)]) -> Result<String, LlamaError> {
+        let mut rendered = String::with_capacity(self.body.len());
+        let mut rest = self.body;
+        while let Some(open) = rest.find('{') {
+            let close = match rest[open..].find('}') {
+                Some(close) => open + close,
+                None => break,
+            };
+            rendered.push_str(&rest[..open]);
+            let name = &rest[open + 1..close];
+            match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => rendered.push_str(value),
+                None => return Err(LlamaError::InvalidParameters(
+                    format!("the {} v{} prompt has nothing for {{{}}}", self.kind.name(), self.version, name))),
+            }
+            rest = &rest[close + 1..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+
+    /// The model's answer made fit to show: markdown stripped, profanity starred out and the
+    /// length cut back to a full sentence. Nothing if nothing is left
+    pub fn finish(&self, text: &str) -> Option<String> {
+        let finished = clamp_length(&filter_profanity(&utils::clean_response(&strip_markdown(text))), self.max_chars);
+        if finished.is_empty() { None } else { Some(finished) }
+    }
+
+    /// The file this version's sample is kept in
+    pub fn snapshot_name(&self) -> String {
+        format!("{}_v{}.txt", self.kind.name(), self.version)
+    }
+
+    /// Everything the model would be sent with `args`, laid out for review
+    pub fn snapshot(&self, args: &[(&str, String)]) -> Result<String, LlamaError> {
+        Ok(format!(
+            "{} v{}, {} tokens, {} characters\n\n--- system ---\n{}\n\n--- prompt ---\n{}\n",
+            self.kind.name(), self.version, self.max_tokens, self.max_chars, self.system, self.render(args)?
+        ))
+    }
+}
+
+/// A request built from the newest template of a kind, the way a `Message` is built from a key
+pub struct Prompt {
+    template: &'static PromptTemplate,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind) -> Self {
+        Prompt { template: PromptTemplate::latest(kind), args: Vec::new() }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    pub fn template(&self) -> &'static PromptTemplate {
+        self.template
+    }
+
+    /// The request for the model, or what the template wanted and wasn't given
+    pub fn request(&self) -> Result<LlmRequest, LlamaError> {
+        Ok(LlmRequest::new(&self.template.render(&self.args)?)
+            .with_system(self.template.system)
+            .with_max_tokens(self.template.max_tokens))
+    }
+}
+
+lazy_static! {
+    static ref LINE_MARKER: Regex = Regex::new(r"(?m)^[ \t]*(?:#{1,6}|>|[-*+]|\d+[.)])[ \t]+").unwrap();
+    static ref LINK: Regex = Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap();
+    static ref EMPHASIS: Regex = Regex::new(r"[*`]+").unwrap();
+    static ref UNDERSCORE_OPEN: Regex = Regex::new(r"(^|\W)_+").unwrap();
+    static ref UNDERSCORE_CLOSE: Regex = Regex::new(r"_+(\W|$)").unwrap();
+    static ref PROFANITY: Regex = {
+        let words: Vec<String> = profanity_words().iter().map(|word| regex::escape(word)).collect();
+        Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|"))).unwrap()
+    };
+}
+
+/// The words `filter_profanity` stars out
+pub fn profanity_words() -> Vec<&'static str> {
+    WORD_LIST.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// Text without headings, list and quote markers, links or emphasis
+pub fn strip_markdown(text: &str) -> String {
+    let text = LINE_MARKER.replace_all(text, "");
+    let text = LINK.replace_all(&text, "$1");
+    let text = EMPHASIS.replace_all(&text, "");
+    let text = UNDERSCORE_OPEN.replace_all(&text, "$1");
+    UNDERSCORE_CLOSE.replace_all(&text, "$1").into_owned()
+}
+
+/// Text with every listed word starred out but for its first letter
+pub fn filter_profanity(text: &str) -> String {
+    PROFANITY.replace_all(text, |caps: &Captures| {
+        let mut letters = caps[0].chars();
+        let first = letters.next().map_or(String::new(), String::from);
+        first + &"*".repeat(letters.count())
+    }).into_owned()
+}
+
+/// Text cut back to its last full sentence within `max_chars` characters
+pub fn clamp_length(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    match cut.rfind(|c| matches!(c, '.' | '!' | '?')) {
+        Some(end) => cut[..=end].to_string(),
+        None => format!("{}...", cut.trim_end()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    /// The values each kind's snapshot is rendered with
+    fn sample_args(kind: PromptKind) -> Vec<(&'static str, String)> {
+        let args: &[(&str, &str)] = match kind {
+            PromptKind::Dialogue => &[
+                ("character", "Character: Mira\nDescription: A tired innkeeper\nLocation: The Rusty Lantern\nRelationship with player: Friendly\n"),
+                ("conversation", "Mira: Back again?\n"),
+                ("input", "Any rooms free tonight?"),
+                ("speaker", "Mira"),
+            ],
+            PromptKind::Description => &[
+                ("kind", "monster"),
+                ("subject", "Cave troll"),
+                ("details", "hulking, mossy, guarding a ford"),
+                ("language", "English"),
+            ],
+            PromptKind::MissionReport => &[
+                ("agent", "Edda"),
+                ("class", "Ranger"),
+                ("traits", "Brave"),
+                ("contract", "Bounty hunt"),
+                ("outcome", "success, bringing back 100 gold"),
+                ("language", "English"),
+            ],
+        };
+        args.iter().map(|(name, value)| (*name, value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_every_template_matches_its_snapshot() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(PROMPT_SNAPSHOTS_DIR);
+        let update = std::env::var_os("UPDATE_PROMPT_SNAPSHOTS").is_some();
+        let mut stale = Vec::new();
+        for template in TEMPLATES {
+            let snapshot = template.snapshot(&sample_args(template.kind)).unwrap();
+            let path = dir.join(template.snapshot_name());
+            if update {
+                fs::create_dir_all(&dir).unwrap();
+                fs::write(&path, &snapshot).unwrap();
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(saved) if saved.replace("\r\n", "\n") == snapshot => {},
+                _ => stale.push(template.snapshot_name()),
+            }
+        }
+        assert!(stale.is_empty(), "prompts changed: review {:?} and rerun with UPDATE_PROMPT_SNAPSHOTS=1", stale);
+    }
+
+    #[test]
+    fn test_the_newest_version_is_used_and_nothing_is_left_unfilled() {
+        for template in TEMPLATES {
+            assert_eq!(PromptTemplate::get(template.kind, template.version), Some(template));
+            assert!(PromptTemplate::latest(template.kind).version >= template.version);
+        }
+        let prompt = Prompt::new(PromptKind::Description).arg("kind", "room").arg("subject", "Crypt");
+        assert!(matches!(prompt.request(), Err(LlamaError::InvalidParameters(_))));
+        let request = prompt.arg("details", "cold").arg("language", "English").request().unwrap();
+        assert_eq!(request.prompt, "Describe this room: Crypt\nDetails: cold\nWrite it in English");
+        assert_eq!(request.max_tokens, 64);
+    }
+
+    #[test]
+    fn test_answers_are_stripped_filtered_and_cut_to_length() {
+        assert_eq!(strip_markdown("## The Crypt\n- It is **very** cold, says [the guide](http://x).\n> _Brr_"),
+            "The Crypt\nIt is very cold, says the guide.\nBrr");
+        assert_eq!(strip_markdown("snake_case stays"), "snake_case stays");
+        assert_eq!(filter_profanity("Well, SHIT. A shitake and a puta."), "Well, S***. A shitake and a p***.");
+
+        let template = PromptTemplate::latest(PromptKind::MissionReport);
+        let told = template.finish(&"*The road was long.* ".repeat(30)).unwrap();
+        assert!(told.chars().count() <= template.max_chars);
+        assert!(told.ends_with('.') && !told.contains('*'));
+        assert_eq!(template.finish(" **  ** \n"), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Where generated text comes from, behind one trait.
+//!
+//! A `LlmProvider` answers one request at a time: a model file loaded in-process through
+//! llama.cpp, a llama.cpp server, anything that speaks the OpenAI chat completions API, or a
+//! deterministic mock for tests. `ModelClient` wraps whichever the options pick with retries and a
+//! token budget for the session, so callers only ever see an answer or a reason there isn't one.
+
+use serde::{Serialize, Deserialize};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use crate::config::LlmConfig;
+use super::llama_integration::{LlamaConfig, LlamaContext, LlamaError, LlamaRequest};
+use super::remote::{LlamaServerProvider, OpenAiProvider};
+
+/// Tokens a request asks for unless it says otherwise
+pub const DEFAULT_MAX_TOKENS: u32 = 256;
+/// Wait before the first retry; each one after waits twice as long
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Which backend the options pick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    /// No model; everything falls back to templates
+    None,
+    /// A model file loaded in-process, with the `language_model` feature
+    Local,
+    /// A llama.cpp server's `/completion` endpoint
+    LlamaServer,
+    /// Any OpenAI-compatible `/chat/completions` endpoint
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Canned answers, the same every time
+    Mock,
+}
+
+/// What to ask a model for
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmRequest {
+    /// Standing instructions, sent apart from the prompt where the API allows it
+    pub system: Option<String>,
+    pub prompt: String,
+    pub max_tokens: u32,
+    pub temperature: f32,
+    pub stop: Vec<String>,
+}
+
+impl LlmRequest {
+    pub fn new(prompt: &str) -> Self {
+        LlmRequest {
+            system: None,
+            prompt: prompt.to_string(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            temperature: 0.7,
+            stop: Vec::new(),
+        }
+    }
+
+    pub fn with_system(mut self, system: &str) -> Self {
+        self.system = Some(system.to_string());
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+}
+
+/// What a model answered, and the tokens it took, prompt included
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmResponse {
+    pub text: String,
+    pub tokens: u32,
+}
+
+/// Something that turns a request into text
+pub trait LlmProvider: Send {
+    /// Short name for logs and the options
+    fn name(&self) -> &'static str;
+
+    /// The model it asks for, where it picks one rather than the server
+    fn model(&self) -> &str {
+        ""
+    }
+
+    fn complete(&mut self, request: &LlmRequest) -> Result<LlmResponse, LlamaError>;
+}
+
+/// Rough token count for text whose real count the backend doesn't report: about four
+/// characters a token in English
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32 + 3) / 4
+}
+
+/// Whether an error might not happen again if asked a moment later
+pub fn retryable(error: &LlamaError) -> bool {
+    matches!(error, LlamaError::Timeout(_) | LlamaError::InferenceFailed(_) | LlamaError::ResourceExhausted(_))
+}
+
+/// A model file run in-process through the llama.cpp bindings
+pub struct LocalProvider {
+    config: LlamaConfig,
+    context: LlamaContext,
+}
+
+impl LocalProvider {
+    pub fn load(model: &str, timeout: Duration) -> Result<Self, LlamaError> {
+        if !cfg!(feature = "language_model") {
+            return Err(LlamaError::InitializationFailed("built without the language_model feature".to_string()));
+        }
+        let config = LlamaConfig {
+            model_path: PathBuf::from(model),
+            timeout_seconds: timeout.as_secs().max(1),
+            ..LlamaConfig::default()
+        };
+        let mut context = LlamaContext::new(config.clone())?;
+        context.load_model()?;
+        Ok(LocalProvider { config, context })
+    }
+}
+
+impl LlmProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn model(&self) -> &str {
+        self.config.model_path.to_str().unwrap_or("")
+    }
+
+    fn complete(&mut self, request: &LlmRequest) -> Result<LlmResponse, LlamaError> {
+        let llama = LlamaRequest {
+            id: "provider".to_string(),
+            prompt: request.prompt.clone(),
+            config: Some(LlamaConfig {
+                max_tokens: request.max_tokens,
+                temperature: request.temperature,
+                ..self.config.clone()
+            }),
+            system_prompt: request.system.clone(),
+            stop_sequences: request.stop.clone(),
+        };
+        let response = self.context.generate(&llama)?;
+        let prompt_tokens = estimate_tokens(&request.prompt) + request.system.as_deref().map_or(0, estimate_tokens);
+        Ok(LlmResponse { tokens: prompt_tokens + response.tokens_generated, text: response.text })
+    }
+}
+
+/// Answers without a model, the same way for the same prompt: from a list of replies in turn, or
+/// by echoing the prompt's last line. Can be told to fail a few times first
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    pub replies: Vec<String>,
+    /// Calls left that time out before any answer
+    pub failures: u32,
+    /// Requests it has been sent, failed ones included
+    pub calls: u32,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        MockProvider::default()
+    }
+
+    pub fn with_replies(replies: &[&str]) -> Self {
+        MockProvider { replies: replies.iter().map(|reply| reply.to_string()).collect(), ..MockProvider::default() }
+    }
+
+    pub fn failing(mut self, failures: u32) -> Self {
+        self.failures = failures;
+        self
+    }
+}
+
+impl LlmProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn complete(&mut self, request: &LlmRequest) -> Result<LlmResponse, LlamaError> {
+        self.calls += 1;
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(LlamaError::Timeout("the mock provider was told to fail".to_string()));
+        }
+        let text = if self.replies.is_empty() {
+            format!("[mock] {}", request.prompt.lines().last().unwrap_or("").trim())
+        } else {
+            self.replies[(self.calls as usize - 1) % self.replies.len()].clone()
+        };
+        Ok(LlmResponse { tokens: estimate_tokens(&request.prompt) + estimate_tokens(&text), text })
+    }
+}
+
+/// Tokens a session may spend across every request. Zero leaves it unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenBudget {
+    pub limit: u32,
+    pub spent: u32,
+}
+
+impl TokenBudget {
+    pub fn new(limit: u32) -> Self {
+        TokenBudget { limit, spent: 0 }
+    }
+
+    pub fn remaining(&self) -> Option<u32> {
+        match self.limit {
+            0 => None,
+            limit => Some(limit.saturating_sub(self.spent)),
+        }
+    }
+
+    /// Tokens a request wanting `wanted` may have, or why it may have none
+    pub fn allow(&self, wanted: u32) -> Result<u32, LlamaError> {
+        match self.remaining() {
+            None => Ok(wanted),
+            Some(0) => Err(LlamaError::ResourceExhausted(format!("the token budget of {} is spent", self.limit))),
+            Some(left) => Ok(wanted.min(left)),
+        }
+    }
+
+    pub fn spend(&mut self, tokens: u32) {
+        self.spent = self.spent.saturating_add(tokens);
+    }
+}
+
+/// A provider with retries and a token budget around it
+pub struct ModelClient {
+    provider: Box<dyn LlmProvider>,
+    retries: u32,
+    pub budget: TokenBudget,
+}
+
+impl ModelClient {
+    pub fn new(provider: Box<dyn LlmProvider>, retries: u32, budget: TokenBudget) -> Self {
+        ModelClient { provider, retries, budget }
+    }
+
+    /// The provider the options pick, or none when they pick none
+    pub fn from_config(config: &LlmConfig) -> Result<Option<Self>, LlamaError> {
+        let timeout = Duration::from_secs(config.timeout_seconds);
+        let provider: Box<dyn LlmProvider> = match config.provider {
+            ProviderKind::None => return Ok(None),
+            ProviderKind::Local => Box::new(LocalProvider::load(&config.model, timeout)?),
+            ProviderKind::LlamaServer => Box::new(LlamaServerProvider::new(&config.endpoint, timeout)?),
+            ProviderKind::OpenAi => {
+                let api_key = std::env::var(&config.api_key_env).ok().filter(|key| !key.is_empty());
+                Box::new(OpenAiProvider::new(&config.endpoint, &config.model, api_key, timeout)?)
+            },
+            ProviderKind::Mock => Box::new(MockProvider::new()),
+        };
+        Ok(Some(ModelClient::new(provider, config.retries, TokenBudget::new(config.token_budget))))
+    }
+
+    pub fn provider_name(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    /// The provider and model answering, which the cache keeps their answers apart by
+    pub fn model_id(&self) -> String {
+        format!("{}:{}", self.provider.name(), self.provider.model())
+    }
+
+    /// Ask the provider, within what is left of the budget, trying again after errors that may
+    /// pass
+    pub fn complete(&mut self, request: &LlmRequest) -> Result<LlmResponse, LlamaError> {
+        let request = LlmRequest { max_tokens: self.budget.allow(request.max_tokens)?, ..request.clone() };
+        let mut attempt = 0;
+        loop {
+            match self.provider.complete(&request) {
+                Ok(response) => {
+                    self.budget.spend(response.tokens);
+                    return Ok(response);
+                },
+                Err(e) if attempt < self.retries && retryable(&e) => {
+                    log::warn!("{} provider failed, trying again: {}", self.provider.name(), e);
+                    thread::sleep(RETRY_DELAY * 2u32.pow(attempt));
+                    attempt += 1;
+                },
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_the_client_retries_what_may_pass_and_no_more() {
+        let mut client = ModelClient::new(Box::new(MockProvider::with_replies(&["Aye."]).failing(2)), 2, TokenBudget::default());
+        assert_eq!(client.complete(&LlmRequest::new("Well met")).unwrap().text, "Aye.");
+
+        let mut client = ModelClient::new(Box::new(MockProvider::new().failing(3)), 2, TokenBudget::default());
+        assert!(matches!(client.complete(&LlmRequest::new("Well met")), Err(LlamaError::Timeout(_))));
+    }
+
+    #[test]
+    fn test_the_budget_caps_each_request_and_then_refuses() {
+        let mut budget = TokenBudget::new(100);
+        assert_eq!(budget.allow(256).unwrap(), 100);
+        budget.spend(90);
+        assert_eq!(budget.allow(256).unwrap(), 10);
+
+        let mut client = ModelClient::new(Box::new(MockProvider::new()), 0, TokenBudget::new(8));
+        let answer = client.complete(&LlmRequest::new("Who goes there?\nA friend of the guild")).unwrap();
+        assert_eq!(answer.text, "[mock] A friend of the guild");
+        assert!(client.budget.spent >= 8);
+        assert!(matches!(client.complete(&LlmRequest::new("Again")), Err(LlamaError::ResourceExhausted(_))));
+    }
+
+    #[test]
+    fn test_the_options_pick_the_provider() {
+        let config = LlmConfig { provider: ProviderKind::Mock, ..LlmConfig::default() };
+        assert_eq!(ModelClient::from_config(&config).unwrap().unwrap().provider_name(), "mock");
+        assert!(ModelClient::from_config(&LlmConfig::default()).unwrap().is_none());
+
+        let config = LlmConfig { provider: ProviderKind::OpenAi, model: String::new(), ..LlmConfig::default() };
+        assert!(ModelClient::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+//! Every language model call, made on a worker thread of its own.
+//!
+//! Systems `submit` requests and carry on; the worker answers them one at a time, highest
+//! priority first, and `ModelResultSystem` collects the answers each turn. The queue is bounded,
+//! so a slow model turns work away rather than piling it up, and requests whose entity is gone
+//! are dropped before the model spends any time on them. Anything asked before is answered from
+//! the `ResponseCache` without troubling the model at all. Nothing here ever waits on the model.
+
+use specs::Entity;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use crate::config::LlmConfig;
+use super::cache::ResponseCache;
+use super::llama_integration::LlamaError;
+use super::provider::{LlmRequest, LlmResponse, ModelClient};
+
+/// How soon a request wants answering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Whenever the model is free, such as the guild's reports
+    Low,
+    Normal,
+    /// Someone on screen is waiting on it
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(pub u64);
+
+/// What an answer is for, so it can find its way back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelPurpose {
+    /// The story of the guild ledger's report filed under this number
+    Narration(u64),
+}
+
+/// A request's answer, or why there isn't one
+#[derive(Debug)]
+pub struct ModelResult {
+    pub id: RequestId,
+    /// The entity that asked, if the answer is no use once it is gone
+    pub owner: Option<Entity>,
+    pub purpose: ModelPurpose,
+    pub outcome: Result<LlmResponse, LlamaError>,
+}
+
+struct Queued {
+    id: RequestId,
+    priority: Priority,
+    owner: Option<Entity>,
+    purpose: ModelPurpose,
+    request: LlmRequest,
+}
+
+/// What the game and the worker share
+#[derive(Default)]
+struct Pending {
+    queued: Vec<Queued>,
+    /// The request the model is on, and whether it has been cancelled since
+    in_flight: Option<(RequestId, Option<Entity>)>,
+    in_flight_cancelled: bool,
+    next_id: u64,
+    /// Set when the queue is dropped, to let the worker go
+    closed: bool,
+}
+
+impl Pending {
+    /// The next request to answer: the highest priority, and the oldest among equals
+    fn take_next(&mut self) -> Option<Queued> {
+        let next = (0..self.queued.len())
+            .max_by_key(|&i| (self.queued[i].priority, std::cmp::Reverse(self.queued[i].id.0)))?;
+        Some(self.queued.remove(next))
+    }
+
+    /// Drop every request `doomed` picks, including the one in flight, whose answer will be
+    /// thrown away. Returns how many went
+    fn cancel_where(&mut self, doomed: impl Fn(RequestId, Option<Entity>) -> bool) -> usize {
+        let before = self.queued.len();
+        self.queued.retain(|queued| !doomed(queued.id, queued.owner));
+        let mut cancelled = before - self.queued.len();
+        if let Some((id, owner)) = self.in_flight {
+            if !self.in_flight_cancelled && doomed(id, owner) {
+                self.in_flight_cancelled = true;
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+}
+
+struct Shared {
+    pending: Mutex<Pending>,
+    wake: Condvar,
+}
+
+/// The queue in front of the worker that owns the model. Without a provider there is no worker,
+/// and every request is turned away so callers fall back at once
+pub struct ModelQueue {
+    /// The options the worker was set up from, to tell when they change
+    pub config: LlmConfig,
+    shared: Option<Arc<Shared>>,
+    results: Option<Mutex<Receiver<ModelResult>>>,
+    /// Shared with the worker; none until the options have been read and the cache loaded
+    cache: Option<Arc<Mutex<ResponseCache>>>,
+}
+
+impl Default for ModelQueue {
+    fn default() -> Self {
+        ModelQueue { config: LlmConfig::default(), shared: None, results: None, cache: None }
+    }
+}
+
+impl ModelQueue {
+    /// A queue for the provider `config` picks, with the answers kept at `cache_path`. A provider
+    /// that can't be set up leaves the queue without a worker, and says why
+    pub fn from_config(config: &LlmConfig, cache_path: &str) -> (Self, Option<LlamaError>) {
+        let cache = ResponseCache::load(cache_path, config.cache_size).unwrap_or_else(|e| {
+            log::warn!("Starting an empty model cache, {} could not be read: {}", cache_path, e);
+            ResponseCache::new(config.cache_size)
+        });
+        match ModelClient::from_config(config) {
+            Ok(Some(client)) => (ModelQueue::start(client, config.clone(), cache), None),
+            Ok(None) => (ModelQueue::idle(config, cache), None),
+            Err(e) => (ModelQueue::idle(config, cache), Some(e)),
+        }
+    }
+
+    /// Hand `client` to a new worker thread, answering from `cache` where it can
+    pub fn start(client: ModelClient, config: LlmConfig, cache: ResponseCache) -> Self {
+        let shared = Arc::new(Shared { pending: Mutex::new(Pending::default()), wake: Condvar::new() });
+        let cache = Arc::new(Mutex::new(cache));
+        let (sender, receiver) = mpsc::channel();
+        let (worker, worker_cache) = (Arc::clone(&shared), Arc::clone(&cache));
+        thread::spawn(move || work(client, worker, sender, worker_cache));
+        ModelQueue { config, shared: Some(shared), results: Some(Mutex::new(receiver)), cache: Some(cache) }
+    }
+
+    /// A queue with no model behind it, keeping its cache to inspect
+    fn idle(config: &LlmConfig, cache: ResponseCache) -> Self {
+        ModelQueue { config: config.clone(), shared: None, results: None, cache: Some(Arc::new(Mutex::new(cache))) }
+    }
+
+    pub fn has_model(&self) -> bool {
+        self.shared.is_some()
+    }
+
+    /// Queue a request, or say why it can't be: no model, or a full queue
+    pub fn submit(&self, request: LlmRequest, priority: Priority, owner: Option<Entity>, purpose: ModelPurpose) -> Result<RequestId, LlamaError> {
+        let shared = match &self.shared {
+            Some(shared) => shared,
+            None => return Err(LlamaError::InitializationFailed("no language model is set up".to_string())),
+        };
+        let mut pending = shared.pending.lock()
+            .map_err(|_| LlamaError::ResourceExhausted("the request queue is poisoned".to_string()))?;
+        if pending.queued.len() >= self.config.queue_size {
+            return Err(LlamaError::ResourceExhausted(format!("{} requests are already waiting", pending.queued.len())));
+        }
+        pending.next_id += 1;
+        let id = RequestId(pending.next_id);
+        pending.queued.push(Queued { id, priority, owner, purpose, request });
+        shared.wake.notify_one();
+        Ok(id)
+    }
+
+    /// Every answer that has come back since the last call
+    pub fn poll(&self) -> Vec<ModelResult> {
+        self.results.as_ref()
+            .and_then(|results| results.lock().ok())
+            .map_or(Vec::new(), |results| results.try_iter().collect())
+    }
+
+    /// Requests waiting, not counting the one the model is on
+    pub fn waiting(&self) -> usize {
+        self.lock().map_or(0, |pending| pending.queued.len())
+    }
+
+    pub fn cancel(&self, id: RequestId) -> bool {
+        self.lock().map_or(false, |mut pending| pending.cancel_where(|queued, _| queued == id) > 0)
+    }
+
+    /// Drop the requests of entities no longer `alive`
+    pub fn cancel_dead(&self, alive: impl Fn(Entity) -> bool) -> usize {
+        self.lock().map_or(0, |mut pending| pending.cancel_where(|_, owner| owner.map_or(false, |owner| !alive(owner))))
+    }
+
+    /// Drop every request some entity made, for when the world they lived in is gone
+    pub fn cancel_owned(&self) -> usize {
+        self.lock().map_or(0, |mut pending| pending.cancel_where(|_, owner| owner.is_some()))
+    }
+
+    /// Do something with the cache, if it has been loaded
+    pub fn with_cache<R>(&self, f: impl FnOnce(&mut ResponseCache) -> R) -> Option<R> {
+        let mut cache = self.cache.as_ref()?.lock().ok()?;
+        Some(f(&mut cache))
+    }
+
+    /// Keep the cache at `path`. A queue whose cache was never loaded leaves the file alone
+    pub fn save_cache(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.with_cache(|cache| cache.save(path)).unwrap_or(Ok(()))
+    }
+
+    fn lock(&self) -> Option<std::sync::MutexGuard<'_, Pending>> {
+        self.shared.as_ref()?.pending.lock().ok()
+    }
+}
+
+impl Drop for ModelQueue {
+    /// Let the worker go once it is done with whatever it is on, without waiting for it
+    fn drop(&mut self) {
+        if let Some(shared) = &self.shared {
+            if let Ok(mut pending) = shared.pending.lock() {
+                pending.closed = true;
+            }
+            shared.wake.notify_all();
+        }
+    }
+}
+
+/// The worker's loop: take the most pressing request, answer it from the cache or the model, send
+/// the answer back unless it was cancelled meanwhile. Ends when the queue is dropped
+fn work(mut client: ModelClient, shared: Arc<Shared>, results: Sender<ModelResult>, cache: Arc<Mutex<ResponseCache>>) {
+    let model = client.model_id();
+    loop {
+        let next = {
+            let mut pending = match shared.pending.lock() {
+                Ok(pending) => pending,
+                Err(_) => return,
+            };
+            loop {
+                if pending.closed {
+                    return;
+                }
+                if let Some(next) = pending.take_next() {
+                    pending.in_flight = Some((next.id, next.owner));
+                    pending.in_flight_cancelled = false;
+                    break next;
+                }
+                pending = match shared.wake.wait(pending) {
+                    Ok(pending) => pending,
+                    Err(_) => return,
+                };
+            }
+        };
+        let cached = cache.lock().ok().and_then(|mut cache| cache.get(&model, &next.request));
+        let outcome = match cached {
+            Some(response) => Ok(response),
+            None => {
+                let outcome = client.complete(&next.request);
+                if let (Ok(response), Ok(mut cache)) = (&outcome, cache.lock()) {
+                    cache.insert(&model, &next.request, response);
+                }
+                outcome
+            },
+        };
+        let cancelled = match shared.pending.lock() {
+            Ok(mut pending) => {
+                pending.in_flight = None;
+                pending.in_flight_cancelled
+            },
+            Err(_) => return,
+        };
+        if cancelled {
+            continue;
+        }
+        let result = ModelResult { id: next.id, owner: next.owner, purpose: next.purpose, outcome };
+        if results.send(result).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use specs::{World, WorldExt, Builder};
+    use crate::language_model::{MockProvider, TokenBudget};
+
+    fn queued(id: u64, priority: Priority, owner: Option<Entity>) -> Queued {
+        Queued { id: RequestId(id), priority, owner, purpose: ModelPurpose::Narration(id), request: LlmRequest::new("") }
+    }
+
+    /// Whatever comes back within a couple of seconds
+    fn answers(queue: &ModelQueue) -> Vec<ModelResult> {
+        let started = Instant::now();
+        let mut results = Vec::new();
+        while results.is_empty() && started.elapsed() < Duration::from_secs(2) {
+            results = queue.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+        results
+    }
+
+    #[test]
+    fn test_the_most_pressing_request_goes_first_and_the_dead_lose_theirs() {
+        let mut world = World::new();
+        let gone = world.create_entity().build();
+        let mut pending = Pending::default();
+        pending.queued = vec![
+            queued(1, Priority::Low, None),
+            queued(2, Priority::High, Some(gone)),
+            queued(3, Priority::Normal, None),
+            queued(4, Priority::High, None),
+        ];
+        assert_eq!(pending.take_next().unwrap().id, RequestId(2));
+        assert_eq!(pending.take_next().unwrap().id, RequestId(4));
+
+        pending.in_flight = Some((RequestId(5), Some(gone)));
+        pending.queued.push(queued(6, Priority::Low, Some(gone)));
+        assert_eq!(pending.cancel_where(|_, owner| owner == Some(gone)), 2);
+        assert!(pending.in_flight_cancelled);
+        assert_eq!(pending.queued.iter().map(|queued| queued.id.0).collect::<Vec<_>>(), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_answers_come_back_without_waiting_are_cached_and_a_full_queue_turns_work_away() {
+        let queue = ModelQueue::default();
+        assert!(queue.submit(LlmRequest::new("Hello"), Priority::Normal, None, ModelPurpose::Narration(1)).is_err());
+
+        let client = ModelClient::new(Box::new(MockProvider::with_replies(&["Well met."])), 0, TokenBudget::default());
+        let queue = ModelQueue::start(client, LlmConfig { queue_size: 4, ..LlmConfig::default() }, ResponseCache::new(4));
+        let id = queue.submit(LlmRequest::new("Hello"), Priority::Normal, None, ModelPurpose::Narration(1)).unwrap();
+        let results = answers(&queue);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, id);
+        assert_eq!(results[0].outcome.as_ref().unwrap().text, "Well met.");
+
+        queue.submit(LlmRequest::new("Hello"), Priority::Normal, None, ModelPurpose::Narration(2)).unwrap();
+        assert_eq!(answers(&queue)[0].outcome.as_ref().unwrap().text, "Well met.");
+        assert_eq!(queue.with_cache(|cache| (cache.len(), cache.hits)), Some((1, 1)));
+
+        let mut pending = queue.lock().unwrap();
+        pending.closed = true;
+        pending.queued = (10..14).map(|id| queued(id, Priority::Low, None)).collect();
+        drop(pending);
+        assert!(queue.submit(LlmRequest::new("One more"), Priority::High, None, ModelPurpose::Narration(2)).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Providers reached over HTTP: a llama.cpp server and OpenAI-compatible APIs.
+//!
+//! Requests go out through `ureq` with the `remote_model` feature; without it the providers can
+//! still be built, and every request fails saying so. Building and reading the JSON is kept apart
+//! from sending it so both can be checked without a server.
+
+use serde_json::{json, Value};
+use std::time::Duration;
+use super::llama_integration::{utils, LlamaError};
+use super::provider::{estimate_tokens, LlmProvider, LlmRequest, LlmResponse};
+
+/// Where a llama.cpp server listens unless the options say otherwise
+pub const LLAMA_SERVER_ENDPOINT: &str = "http://127.0.0.1:8080";
+/// Where OpenAI's API lives unless the options point at another compatible one
+pub const OPENAI_ENDPOINT: &str = "https://api.openai.com/v1";
+
+/// A llama.cpp server, asked through its `/completion` endpoint
+pub struct LlamaServerProvider {
+    url: String,
+    timeout: Duration,
+}
+
+impl LlamaServerProvider {
+    pub fn new(endpoint: &str, timeout: Duration) -> Result<Self, LlamaError> {
+        let endpoint = if endpoint.is_empty() { LLAMA_SERVER_ENDPOINT } else { endpoint };
+        Ok(LlamaServerProvider { url: format!("{}/completion", endpoint.trim_end_matches('/')), timeout })
+    }
+}
+
+impl LlmProvider for LlamaServerProvider {
+    fn name(&self) -> &'static str {
+        "llama_server"
+    }
+
+    fn complete(&mut self, request: &LlmRequest) -> Result<LlmResponse, LlamaError> {
+        let answer = post(&self.url, None, &llama_server_body(request), self.timeout)?;
+        parse_llama_server(&answer, request)
+    }
+}
+
+/// Anything that speaks the OpenAI chat completions API
+pub struct OpenAiProvider {
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+impl OpenAiProvider {
+    pub fn new(endpoint: &str, model: &str, api_key: Option<String>, timeout: Duration) -> Result<Self, LlamaError> {
+        if model.is_empty() {
+            return Err(LlamaError::InvalidParameters("an OpenAI-compatible provider needs a model name".to_string()));
+        }
+        let endpoint = if endpoint.is_empty() { OPENAI_ENDPOINT } else { endpoint };
+        Ok(OpenAiProvider {
+            url: format!("{}/chat/completions", endpoint.trim_end_matches('/')),
+            model: model.to_string(),
+            api_key,
+            timeout,
+        })
+    }
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete(&mut self, request: &LlmRequest) -> Result<LlmResponse, LlamaError> {
+        let answer = post(&self.url, self.api_key.as_deref(), &openai_body(&self.model, request), self.timeout)?;
+        parse_openai(&answer, request)
+    }
+}
+
+/// The body of a llama.cpp server request, the system prompt folded into the prompt
+pub fn llama_server_body(request: &LlmRequest) -> Value {
+    json!({
+        "prompt": utils::format_prompt(request.system.as_deref(), &request.prompt),
+        "n_predict": request.max_tokens,
+        "temperature": request.temperature,
+        "stop": request.stop,
+        "stream": false,
+    })
+}
+
+/// A llama.cpp server's answer, with the tokens it says it used
+pub fn parse_llama_server(answer: &str, request: &LlmRequest) -> Result<LlmResponse, LlamaError> {
+    let answer: Value = serde_json::from_str(answer)
+        .map_err(|e| LlamaError::InferenceFailed(format!("unreadable answer from the llama.cpp server: {}", e)))?;
+    let text = answer["content"].as_str()
+        .ok_or_else(|| LlamaError::InferenceFailed("the llama.cpp server answered without any content".to_string()))?
+        .to_string();
+    let tokens = match (answer["tokens_evaluated"].as_u64(), answer["tokens_predicted"].as_u64()) {
+        (Some(evaluated), Some(predicted)) => (evaluated + predicted) as u32,
+        _ => estimate_tokens(&request.prompt) + estimate_tokens(&text),
+    };
+    Ok(LlmResponse { text, tokens })
+}
+
+/// The body of a chat completions request
+pub fn openai_body(model: &str, request: &LlmRequest) -> Value {
+    let mut messages = Vec::new();
+    if let Some(system) = &request.system {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    messages.push(json!({ "role": "user", "content": request.prompt }));
+    let mut body = json!({
+        "model": model,
+        "messages": messages,
+        "max_tokens": request.max_tokens,
+        "temperature": request.temperature,
+    });
+    if !request.stop.is_empty() {
+        body["stop"] = json!(request.stop);
+    }
+    body
+}
+
+/// A chat completion's first choice, with the tokens the API says it used
+pub fn parse_openai(answer: &str, request: &LlmRequest) -> Result<LlmResponse, LlamaError> {
+    let answer: Value = serde_json::from_str(answer)
+        .map_err(|e| LlamaError::InferenceFailed(format!("unreadable answer from the API: {}", e)))?;
+    if let Some(message) = answer["error"]["message"].as_str() {
+        return Err(LlamaError::InferenceFailed(message.to_string()));
+    }
+    let text = answer["choices"][0]["message"]["content"].as_str()
+        .ok_or_else(|| LlamaError::InferenceFailed("the API answered without a choice".to_string()))?
+        .to_string();
+    let tokens = match answer["usage"]["total_tokens"].as_u64() {
+        Some(total) => total as u32,
+        None => estimate_tokens(&request.prompt) + estimate_tokens(&text),
+    };
+    Ok(LlmResponse { text, tokens })
+}
+
+/// Send `body` to `url` and return the answer, giving up after `timeout`
+#[cfg(feature = "remote_model")]
+fn post(url: &str, api_key: Option<&str>, body: &Value, timeout: Duration) -> Result<String, LlamaError> {
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    let mut request = agent.post(url).set("Content-Type", "application/json");
+    if let Some(key) = api_key {
+        request = request.set("Authorization", &format!("Bearer {}", key));
+    }
+    match request.send_string(&body.to_string()) {
+        Ok(response) => response.into_string().map_err(|e| LlamaError::InferenceFailed(e.to_string())),
+        Err(ureq::Error::Status(429, _)) => Err(LlamaError::ResourceExhausted(format!("{} is turning requests away", url))),
+        Err(ureq::Error::Status(code, _)) if code >= 500 => Err(LlamaError::InferenceFailed(format!("{} answered {}", url, code))),
+        Err(ureq::Error::Status(code, _)) => Err(LlamaError::InvalidParameters(format!("{} answered {}", url, code))),
+        Err(ureq::Error::Transport(e)) => {
+            let timed_out = std::error::Error::source(&e)
+                .and_then(|source| source.downcast_ref::<std::io::Error>())
+                .map_or(false, |io| matches!(io.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock));
+            if timed_out {
+                Err(LlamaError::Timeout(format!("{} took longer than {} seconds", url, timeout.as_secs())))
+            } else {
+                Err(LlamaError::InferenceFailed(format!("could not reach {}: {}", url, e)))
+            }
+        },
+    }
+}
+
+#[cfg(not(feature = "remote_model"))]
+fn post(_url: &str, _api_key: Option<&str>, _body: &Value, _timeout: Duration) -> Result<String, LlamaError> {
+    Err(LlamaError::InitializationFailed("built without the remote_model feature".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_requests_carry_the_system_prompt_as_a_message() {
+        let request = LlmRequest::new("Tell the tale").with_system("You keep the ledger").with_max_tokens(64);
+        let body = openai_body("gpt-4o-mini", &request);
+        assert_eq!(body["model"], "gpt-4o-mini");
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "Tell the tale");
+        assert_eq!(body["max_tokens"], 64);
+
+        let answer = r#"{"choices":[{"message":{"role":"assistant","content":"It went well."}}],"usage":{"total_tokens":42}}"#;
+        assert_eq!(parse_openai(answer, &request).unwrap(), LlmResponse { text: "It went well.".to_string(), tokens: 42 });
+        assert!(parse_openai(r#"{"error":{"message":"no such model"}}"#, &request).is_err());
+    }
+
+    #[test]
+    fn test_llama_server_answers_are_read_with_their_token_counts() {
+        let request = LlmRequest::new("Tell the tale").with_system("You keep the ledger");
+        let body = llama_server_body(&request);
+        assert!(body["prompt"].as_str().unwrap().starts_with("System: You keep the ledger"));
+        assert_eq!(body["n_predict"], 256);
+
+        let answer = r#"{"content":" It went badly.","tokens_evaluated":30,"tokens_predicted":5}"#;
+        assert_eq!(parse_llama_server(answer, &request).unwrap().tokens, 35);
+        assert!(parse_llama_server("not json", &request).is_err());
+    }
+
+    #[test]
+    fn test_endpoints_default_and_an_openai_provider_needs_a_model() {
+        let server = LlamaServerProvider::new("", Duration::from_secs(5)).unwrap();
+        assert_eq!(server.url, "http://127.0.0.1:8080/completion");
+        let api = OpenAiProvider::new("http://localhost:11434/v1/", "llama3", None, Duration::from_secs(5)).unwrap();
+        assert_eq!(api.url, "http://localhost:11434/v1/chat/completions");
+        assert!(OpenAiProvider::new("", "", None, Duration::from_secs(5)).is_err());
+    }
+}
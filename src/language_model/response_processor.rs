@@ -4,6 +4,7 @@ use regex::Regex;
 use log::{info, warn, error};
 
 use super::dialogue_system_trait::DialogueEntry;
+use super::prompts::profanity_words;
 
 /// Emotion detected in a response
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -203,15 +204,9 @@ impl ResponseProcessor {
         ]);
     }
     
-    /// Initialize profanity list
+    /// Initialize profanity list from the word list the prompt templates filter with
     fn initialize_profanity_list(&mut self) {
-        // Basic profanity list - in a real implementation, this would be more comprehensive
-        let profanity_words = vec![
-            "damn", "hell", "bastard", "bitch", "shit", "fuck", "ass", "crap",
-            // Add more as needed, but keep it appropriate for the fantasy setting
-        ];
-        
-        for word in profanity_words {
+        for word in profanity_words() {
             self.profanity_list.insert(word.to_string());
         }
     }
@@ -17,4 +17,8 @@ pub mod entity_factory;
 pub mod persistence;
 pub mod achievements;
 pub mod progression;
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+pub mod presence;
+pub mod config;
+pub mod localization;
+pub mod accessibility;
\ No newline at end of file
@@ -0,0 +1,352 @@
+//! Everything the game says, looked up by key in the player's language.
+//!
+//! Catalogs are TOML files of strings grouped into tables, so `[menu] new_game = "..."` is the
+//! key `menu.new_game`. English and Spanish are built in; a `<language>.toml` in `locales/` adds a
+//! language or replaces built-in strings. A key missing from the chosen language falls back to
+//! its base language (`pt-br` to `pt`), then to English, then to the key itself.
+//!
+//! Text may name parameters in braces, `"{name} sets out again."`, which a `Message` fills in.
+//! Code that generates content emits a `Message` rather than finished English so the words are
+//! chosen where they are shown.
+
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Where players put catalogs of their own
+pub const LOCALES_DIR: &str = "locales";
+
+/// The language every other falls back to
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+const BUILT_IN: [(&str, &str); 2] = [
+    ("en", include_str!("../../data/locales/en.toml")),
+    ("es", include_str!("../../data/locales/es.toml")),
+];
+
+/// One language's strings
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    strings: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let table = toml::from_str::<toml::Value>(text).map_err(|e| e.to_string())?;
+        let mut catalog = Catalog::default();
+        catalog.flatten("", &table);
+        Ok(catalog)
+    }
+
+    fn flatten(&mut self, prefix: &str, value: &toml::Value) {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    self.flatten(&key, value);
+                }
+            },
+            toml::Value::String(text) => {
+                self.strings.insert(prefix.to_string(), text.clone());
+            },
+            _ => {},
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+
+    /// Strings from `other` win over ours
+    pub fn merge(&mut self, other: Catalog) {
+        self.strings.extend(other.strings);
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// Language tags compare as lowercase with hyphens, so `pt_BR` and `pt-br` are the same
+pub fn normalize_language(language: &str) -> String {
+    language.trim().replace('_', "-").to_lowercase()
+}
+
+/// Where a key is looked for, in order: the language, its base language, then English
+pub fn fallback_chain(language: &str) -> Vec<String> {
+    let language = normalize_language(language);
+    let mut chain = Vec::new();
+    if !language.is_empty() {
+        chain.push(language.clone());
+    }
+    if let Some((base, _)) = language.split_once('-') {
+        chain.push(base.to_string());
+    }
+    chain.push(FALLBACK_LANGUAGE.to_string());
+    chain.dedup();
+    chain
+}
+
+/// Fill `{name}` in `template` from `args`; braces naming nothing given are left as written
+pub fn fill(template: &str, args: &[(String, String)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            let name = &after[..close];
+            args.iter().find(|(arg, _)| arg == name).map(|(_, value)| (value, close))
+        });
+        match value {
+            Some((value, close)) => {
+                text.push_str(value);
+                rest = &after[close + 1..];
+            },
+            None => {
+                text.push('{');
+                rest = after;
+            },
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// The key an item or creature name is translated under: `name.healing_potion`
+pub fn name_key(name: &str) -> String {
+    let slug: String = name.trim().to_lowercase().chars()
+        .map(|ch| if ch.is_alphanumeric() { ch } else { '_' })
+        .collect();
+    format!("name.{}", slug)
+}
+
+/// Every catalog and the language in use
+#[derive(Debug, Clone)]
+pub struct Localizer {
+    catalogs: HashMap<String, Catalog>,
+    chain: Vec<String>,
+}
+
+impl Localizer {
+    /// The built-in languages, in English
+    pub fn built_in() -> Self {
+        let mut localizer = Localizer { catalogs: HashMap::new(), chain: fallback_chain(FALLBACK_LANGUAGE) };
+        for (language, text) in BUILT_IN {
+            let catalog = Catalog::from_toml(text).expect("Built-in catalog is not valid TOML");
+            localizer.add_catalog(language, catalog);
+        }
+        localizer
+    }
+
+    /// The built-in languages with anything in `directory` added over them; catalogs that fail
+    /// to read are returned with their reasons and otherwise skipped
+    pub fn with_directory(directory: &Path) -> (Self, Vec<String>) {
+        let mut localizer = Localizer::built_in();
+        let mut errors = Vec::new();
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(_) => return (localizer, errors),
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().map_or(true, |extension| extension != "toml") {
+                continue;
+            }
+            let language = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem.to_string(),
+                None => continue,
+            };
+            match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| Catalog::from_toml(&text)) {
+                Ok(catalog) => localizer.add_catalog(&language, catalog),
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        (localizer, errors)
+    }
+
+    pub fn add_catalog(&mut self, language: &str, catalog: Catalog) {
+        self.catalogs.entry(normalize_language(language)).or_default().merge(catalog);
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        self.chain = fallback_chain(language);
+    }
+
+    pub fn language(&self) -> &str {
+        &self.chain[0]
+    }
+
+    /// Every language with a catalog, English first and the rest in order
+    pub fn languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self.catalogs.keys()
+            .filter(|language| language.as_str() != FALLBACK_LANGUAGE)
+            .cloned()
+            .collect();
+        languages.sort();
+        languages.insert(0, FALLBACK_LANGUAGE.to_string());
+        languages
+    }
+
+    /// The language's name for itself, for the Options screen
+    pub fn language_name(&self, language: &str) -> String {
+        let language = normalize_language(language);
+        self.catalogs.get(&language)
+            .and_then(|catalog| catalog.get("language.name"))
+            .map_or(language.clone(), str::to_string)
+    }
+
+    /// The text for `key` from the first catalog in the chain that has it
+    pub fn lookup(&self, key: &str) -> Option<&str> {
+        self.chain.iter()
+            .filter_map(|language| self.catalogs.get(language))
+            .find_map(|catalog| catalog.get(key))
+    }
+
+    pub fn text(&self, key: &str) -> String {
+        self.lookup(key).unwrap_or(key).to_string()
+    }
+
+    pub fn format(&self, message: &Message) -> String {
+        fill(self.lookup(&message.key).unwrap_or(&message.key), &message.args)
+    }
+
+    /// A name as the player's language has it, or as given when it has no translation
+    pub fn name(&self, name: &str) -> String {
+        self.lookup(&name_key(name)).unwrap_or(name).to_string()
+    }
+}
+
+/// The localizer everything draws from, loaded on first use
+static LOCALIZER: RwLock<Option<Localizer>> = RwLock::new(None);
+
+fn with_localizer<R>(f: impl FnOnce(&Localizer) -> R) -> R {
+    if let Ok(localizer) = LOCALIZER.read() {
+        if let Some(localizer) = localizer.as_ref() {
+            return f(localizer);
+        }
+    }
+    let mut localizer = LOCALIZER.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(localizer.get_or_insert_with(|| Localizer::with_directory(Path::new(LOCALES_DIR)).0))
+}
+
+/// Load the built-in catalogs and any in `locales/`, returning catalogs that could not be read
+pub fn init() -> Vec<String> {
+    let (localizer, errors) = Localizer::with_directory(Path::new(LOCALES_DIR));
+    let mut current = LOCALIZER.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let language = current.as_ref().map(|current| current.language().to_string());
+    *current = Some(localizer);
+    if let (Some(language), Some(localizer)) = (language, current.as_mut()) {
+        localizer.set_language(&language);
+    }
+    errors
+}
+
+pub fn set_language(language: &str) {
+    with_localizer(|_| {});
+    if let Ok(mut localizer) = LOCALIZER.write() {
+        if let Some(localizer) = localizer.as_mut() {
+            localizer.set_language(language);
+        }
+    }
+}
+
+pub fn language() -> String {
+    with_localizer(|localizer| localizer.language().to_string())
+}
+
+pub fn languages() -> Vec<String> {
+    with_localizer(Localizer::languages)
+}
+
+pub fn language_name(language: &str) -> String {
+    with_localizer(|localizer| localizer.language_name(language))
+}
+
+/// The text for `key` in the player's language
+pub fn tr(key: &str) -> String {
+    with_localizer(|localizer| localizer.text(key))
+}
+
+/// An item or creature name in the player's language
+pub fn name(name: &str) -> String {
+    with_localizer(|localizer| localizer.name(name))
+}
+
+/// A message key and the values for its parameters, put into words when shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub key: String,
+    pub args: Vec<(String, String)>,
+}
+
+impl Message {
+    pub fn new(key: impl Into<String>) -> Self {
+        Message { key: key.into(), args: Vec::new() }
+    }
+
+    pub fn arg(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.args.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// The message in the player's language
+    pub fn text(&self) -> String {
+        with_localizer(|localizer| localizer.format(self))
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_fall_back_through_the_base_language_to_english_then_the_key() {
+        let mut localizer = Localizer::built_in();
+        localizer.add_catalog("pt", Catalog::from_toml("[menu]\nnew_game = \"n - Novo Jogo\"\n").unwrap());
+        localizer.add_catalog("pt_BR", Catalog::from_toml("[menu]\nquit = \"q - Sair\"\n").unwrap());
+        localizer.set_language("pt-BR");
+
+        assert_eq!(fallback_chain("pt_BR"), vec!["pt-br", "pt", "en"]);
+        assert_eq!(localizer.text("menu.quit"), "q - Sair");
+        assert_eq!(localizer.text("menu.new_game"), "n - Novo Jogo");
+        assert_eq!(localizer.text("menu.load_game"), "l - Load Game");
+        assert_eq!(localizer.text("menu.no_such_line"), "menu.no_such_line");
+        assert_eq!(localizer.languages(), vec!["en", "es", "pt", "pt-br"]);
+    }
+
+    #[test]
+    fn test_messages_fill_their_parameters_and_names_translate_when_they_can() {
+        let mut localizer = Localizer::built_in();
+        let message = Message::new("log.sets_out_again").arg("name", "Ada");
+        assert_eq!(localizer.format(&message), "Ada sets out again.");
+        assert_eq!(fill("{a} and {b} and {c", &[("a".to_string(), "1".to_string())]), "1 and {b} and {c");
+
+        localizer.set_language("es");
+        assert_eq!(localizer.language_name("es"), "Español");
+        assert_eq!(localizer.name("Health Potion"), "Poción de salud");
+        assert_eq!(localizer.name("Nameless Thing"), "Nameless Thing");
+    }
+
+    #[test]
+    fn test_every_built_in_language_has_the_english_keys() {
+        let english = Catalog::from_toml(BUILT_IN[0].1).unwrap();
+        for (language, text) in BUILT_IN.iter().skip(1) {
+            let catalog = Catalog::from_toml(text).unwrap();
+            let missing: Vec<&String> = english.strings.keys().filter(|key| catalog.get(key).is_none()).collect();
+            assert!(missing.is_empty(), "{} is missing {:?}", language, missing);
+        }
+    }
+}
@@ -14,7 +14,13 @@ mod utils;
 mod resources;
 mod entity_factory;
 mod character_creation;
-mod inventory;
+mod persistence;
+mod achievements;
+mod progression;
+mod presence;
+mod config;
+mod localization;
+mod accessibility;
 
 use crossterm::event::{Event, KeyCode};
 use std::{
@@ -26,9 +32,10 @@ use simplelog::{Config, LevelFilter, WriteLogger};
 use std::fs::File;
 
 use game_state::{GameState, StateType};
-use rendering::terminal::with_terminal;
+use rendering::terminal::{with_terminal, shutdown_terminal};
 
 const FRAME_DURATION: Duration = Duration::from_millis(33); // ~30 FPS
+const IDLE_POLL_TIMEOUT_MS: u64 = 1000; // Longest wait for a key when nothing on screen moves
 const PERFORMANCE_SAMPLE_COUNT: usize = 100;
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -62,9 +69,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         // Handle timing
         let frame_start = Instant::now();
         let elapsed = last_frame_time.elapsed();
+        let idle = game_state.is_idle();
         
-        // Fixed time step
-        if elapsed < FRAME_DURATION {
+        // Fixed time step, unless idle, when waiting for input below paces the loop instead
+        if !idle && elapsed < FRAME_DURATION {
             std::thread::sleep(FRAME_DURATION - elapsed);
         }
         
@@ -100,9 +108,17 @@ fn main() -> Result<(), Box<dyn Error>> {
         
         // Handle input
         let input_start = Instant::now();
-        let key_event_opt = with_terminal(|terminal| {
-            terminal.poll_key(0)
-        }).unwrap_or(None);
+        // Keys pressed while a turn is animating wait until it has played out
+        let key_event_opt = if game_state.is_animating() {
+            None
+        } else {
+            // With nothing to animate, sleep until a key arrives instead of spinning; any
+            // terminal event, a resize included, wakes the loop straight away
+            let timeout_ms = if idle { IDLE_POLL_TIMEOUT_MS } else { 0 };
+            with_terminal(|terminal| {
+                terminal.poll_key(timeout_ms)
+            }).unwrap_or(None)
+        };
         
         if let Some(key_event) = key_event_opt {
                     match key_event.code {
@@ -149,7 +165,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
     
-    // Cleanup terminal is handled by with_terminal
+    // Let chat clients know the game has closed
+    game_state.presence.shutdown();
+    
+    // Leave the alternate screen and restore the terminal
+    shutdown_terminal()?;
     
     info!("Exiting ASCII Dungeon Explorer");
     
@@ -0,0 +1,402 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use super::{Map, MapTheme, TileType};
+use super::entity_placement::{EnemyType, ItemType};
+use crate::resources::RandomNumberGenerator;
+
+/// A connected run of levels; the main dungeon plus optional side areas
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum DungeonBranch {
+    Main,
+    Mines,
+    Crypt,
+    Sewer,
+    /// Frozen caverns where the cold wears down the unprepared
+    Glacier,
+    /// Sweltering depths around the lava
+    Caldera,
+}
+
+impl Default for DungeonBranch {
+    fn default() -> Self {
+        DungeonBranch::Main
+    }
+}
+
+impl DungeonBranch {
+    pub fn side_branches() -> [DungeonBranch; 5] {
+        [DungeonBranch::Sewer, DungeonBranch::Mines, DungeonBranch::Glacier, DungeonBranch::Crypt, DungeonBranch::Caldera]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DungeonBranch::Main => "Dungeon",
+            DungeonBranch::Mines => "Mines",
+            DungeonBranch::Crypt => "Crypt",
+            DungeonBranch::Sewer => "Sewer",
+            DungeonBranch::Glacier => "Glacier",
+            DungeonBranch::Caldera => "Caldera",
+        }
+    }
+
+    pub fn theme(&self) -> MapTheme {
+        match self {
+            DungeonBranch::Main => MapTheme::Dungeon,
+            DungeonBranch::Mines => MapTheme::Mines,
+            DungeonBranch::Crypt => MapTheme::Crypt,
+            DungeonBranch::Sewer => MapTheme::Sewer,
+            DungeonBranch::Glacier => MapTheme::Ice,
+            DungeonBranch::Caldera => MapTheme::Volcanic,
+        }
+    }
+
+    /// Number of levels in the branch
+    pub fn levels(&self) -> i32 {
+        match self {
+            DungeonBranch::Main => 20,
+            DungeonBranch::Mines => 4,
+            DungeonBranch::Crypt => 3,
+            DungeonBranch::Sewer => 3,
+            DungeonBranch::Glacier => 3,
+            DungeonBranch::Caldera => 3,
+        }
+    }
+
+    /// Main-dungeon depths (inclusive) where the branch entrance may appear
+    pub fn entrance_depths(&self) -> (i32, i32) {
+        match self {
+            DungeonBranch::Main => (0, 0),
+            DungeonBranch::Sewer => (2, 3),
+            DungeonBranch::Mines => (3, 5),
+            DungeonBranch::Crypt => (6, 8),
+            DungeonBranch::Glacier => (4, 6),
+            DungeonBranch::Caldera => (9, 11),
+        }
+    }
+
+    /// The guardian waiting on the branch's last level; the main dungeon's is the final boss
+    pub fn boss(&self) -> Option<(EnemyType, &'static str)> {
+        match self {
+            DungeonBranch::Main => Some((EnemyType::Dragon, "The Wyrm Below")),
+            DungeonBranch::Mines => Some((EnemyType::Troll, "Grukk the Mine King")),
+            DungeonBranch::Crypt => Some((EnemyType::Skeleton, "The Bone Lord")),
+            DungeonBranch::Sewer => Some((EnemyType::Slime, "The Ooze Mother")),
+            DungeonBranch::Glacier => Some((EnemyType::Troll, "Hrimgar the Frostbound")),
+            DungeonBranch::Caldera => Some((EnemyType::Demon, "The Cinder Tyrant")),
+        }
+    }
+
+    /// Items that turn up more often in this branch
+    pub fn loot_bias(&self) -> Vec<ItemType> {
+        match self {
+            DungeonBranch::Main => Vec::new(),
+            DungeonBranch::Mines => vec![ItemType::Gem, ItemType::Gold, ItemType::Weapon],
+            DungeonBranch::Crypt => vec![ItemType::Amulet, ItemType::Ring, ItemType::Scroll],
+            DungeonBranch::Sewer => vec![ItemType::Key, ItemType::HealthPotion, ItemType::ManaPotion],
+            DungeonBranch::Glacier => vec![ItemType::Armor, ItemType::HealthPotion, ItemType::Gem],
+            DungeonBranch::Caldera => vec![ItemType::Gem, ItemType::Weapon, ItemType::Amulet],
+        }
+    }
+
+    pub fn entrance_color(&self) -> crossterm::style::Color {
+        match self {
+            DungeonBranch::Main => crossterm::style::Color::Cyan,
+            DungeonBranch::Mines => crossterm::style::Color::DarkYellow,
+            DungeonBranch::Crypt => crossterm::style::Color::Magenta,
+            DungeonBranch::Sewer => crossterm::style::Color::DarkGreen,
+            DungeonBranch::Glacier => crossterm::style::Color::White,
+            DungeonBranch::Caldera => crossterm::style::Color::Red,
+        }
+    }
+}
+
+/// Identifies a single level: which branch, and how deep within it
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Location {
+    pub branch: DungeonBranch,
+    pub depth: i32,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Location::main(1)
+    }
+}
+
+impl Location {
+    pub fn new(branch: DungeonBranch, depth: i32) -> Self {
+        Location { branch, depth }
+    }
+
+    pub fn main(depth: i32) -> Self {
+        Location::new(DungeonBranch::Main, depth)
+    }
+
+    /// Ravenhollow, on the surface above the first level of the dungeon
+    pub fn town() -> Self {
+        Location::main(0)
+    }
+
+    pub fn is_main(&self) -> bool {
+        self.branch == DungeonBranch::Main
+    }
+
+    pub fn is_town(&self) -> bool {
+        *self == Location::town()
+    }
+
+    /// True on the last level of a side branch, where its boss lives
+    pub fn is_branch_bottom(&self) -> bool {
+        !self.is_main() && self.depth >= self.branch.levels()
+    }
+
+    /// True on the deepest main dungeon level, where the final boss guards the artifact
+    pub fn is_final_level(&self) -> bool {
+        self.is_main() && self.depth >= self.branch.levels()
+    }
+
+    /// Depth used for difficulty: side branches continue from their entrance level
+    pub fn difficulty_depth(&self, layout: &BranchLayout) -> i32 {
+        match layout.entrance_depth(self.branch) {
+            Some(entrance) if !self.is_main() => entrance + self.depth,
+            _ => self.depth,
+        }
+    }
+
+    /// The level reached by going down, if any
+    pub fn below(&self) -> Option<Location> {
+        if self.depth >= self.branch.levels() {
+            None
+        } else {
+            Some(Location::new(self.branch, self.depth + 1))
+        }
+    }
+
+    /// The level reached by going up; the top of a branch leads back to its entrance, and the
+    /// top of the dungeon out to the town
+    pub fn above(&self, layout: &BranchLayout) -> Option<Location> {
+        if self.depth > 1 {
+            return Some(Location::new(self.branch, self.depth - 1));
+        }
+        if self.is_main() {
+            return if self.is_town() { None } else { Some(Location::town()) };
+        }
+        layout.entrance_depth(self.branch).map(Location::main)
+    }
+
+    pub fn display_name(&self) -> String {
+        if self.is_town() {
+            return "Ravenhollow".to_string();
+        }
+        format!("{} {}", self.branch.name(), self.depth)
+    }
+}
+
+/// A staircase into a side branch placed on a main dungeon level
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BranchEntrance {
+    pub x: i32,
+    pub y: i32,
+    pub branch: DungeonBranch,
+}
+
+/// Where each side branch connects to the main dungeon, and how far each has been explored
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BranchLayout {
+    pub entrances: HashMap<DungeonBranch, i32>,
+    pub deepest_reached: HashMap<DungeonBranch, i32>,
+}
+
+impl BranchLayout {
+    /// Roll entrance depths for every side branch
+    pub fn generate(rng: &mut RandomNumberGenerator) -> Self {
+        let mut layout = BranchLayout::default();
+        for branch in DungeonBranch::side_branches().iter() {
+            let (min_depth, max_depth) = branch.entrance_depths();
+            layout.entrances.insert(*branch, rng.range(min_depth, max_depth));
+        }
+        layout
+    }
+
+    pub fn entrance_depth(&self, branch: DungeonBranch) -> Option<i32> {
+        self.entrances.get(&branch).copied()
+    }
+
+    /// Side branches whose entrance is on the given main dungeon level
+    pub fn branches_at(&self, main_depth: i32) -> Vec<DungeonBranch> {
+        DungeonBranch::side_branches().iter()
+            .filter(|branch| self.entrance_depth(**branch) == Some(main_depth))
+            .copied()
+            .collect()
+    }
+
+    pub fn record_visit(&mut self, location: Location) {
+        let deepest = self.deepest_reached.entry(location.branch).or_insert(0);
+        *deepest = i32::max(*deepest, location.depth);
+    }
+
+    pub fn deepest(&self, branch: DungeonBranch) -> i32 {
+        self.deepest_reached.get(&branch).copied().unwrap_or(0)
+    }
+}
+
+/// Levels the player has been to and left, kept as they were left
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DungeonLevels {
+    pub levels: Vec<Map>,
+}
+
+impl DungeonLevels {
+    /// Keep a level being left, in place of any older copy of it
+    pub fn store(&mut self, mut map: Map) {
+        map.visible_tiles.iter_mut().for_each(|visible| *visible = false);
+        self.levels.retain(|level| level.location != map.location);
+        self.levels.push(map);
+    }
+
+    /// Take back a level being returned to
+    pub fn take(&mut self, location: Location) -> Option<Map> {
+        let idx = self.levels.iter().position(|level| level.location == location)?;
+        Some(self.levels.swap_remove(idx))
+    }
+
+    pub fn has_visited(&self, location: Location) -> bool {
+        self.levels.iter().any(|level| level.location == location)
+    }
+}
+
+/// Where the stairs at (x, y) lead, if there are stairs there and they lead anywhere
+pub fn stairs_destination(map: &Map, layout: &BranchLayout, x: i32, y: i32) -> Option<Location> {
+    match map.get_tile(x, y) {
+        Some(TileType::DownStairs) => match map.branch_entrance_at(x, y) {
+            Some(branch) => Some(Location::new(branch, 1)),
+            None => map.location.below(),
+        },
+        Some(TileType::UpStairs) => map.location.above(layout),
+        _ => None,
+    }
+}
+
+/// Where someone coming from `from` arrives on `map`: on the stairs leading back the way they came
+pub fn arrival_point(map: &Map, from: Location) -> (i32, i32) {
+    let to = map.location;
+    if from.branch != to.branch && to.is_main() {
+        // Climbing out of a side branch comes up through its entrance
+        return map.branch_entrances.iter()
+            .find(|entrance| entrance.branch == from.branch)
+            .map_or(map.exit, |entrance| (entrance.x, entrance.y));
+    }
+    if from.branch != to.branch || from.depth < to.depth {
+        map.entrance
+    } else {
+        map.exit
+    }
+}
+
+/// Put the entrances for any branches that start on this main level onto free floor,
+/// away from the regular stairs and the given positions
+pub fn place_branch_entrances(map: &mut Map, layout: &BranchLayout, rng: &mut RandomNumberGenerator, avoid: &[(i32, i32)]) {
+    if !map.location.is_main() {
+        return;
+    }
+
+    for branch in layout.branches_at(map.location.depth) {
+        let mut candidates = Vec::new();
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
+                let taken = (x, y) == map.entrance
+                    || (x, y) == map.exit
+                    || avoid.contains(&(x, y))
+                    || map.branch_entrance_at(x, y).is_some();
+                if !taken && map.get_tile(x, y) == Some(TileType::Floor) {
+                    candidates.push((x, y));
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            continue;
+        }
+        let (x, y) = candidates[rng.range(0, candidates.len() as i32 - 1) as usize];
+        map.set_tile(x, y, TileType::DownStairs);
+        map.branch_entrances.push(BranchEntrance { x, y, branch });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_depth_continues_from_entrance() {
+        let mut layout = BranchLayout::default();
+        layout.entrances.insert(DungeonBranch::Mines, 4);
+
+        assert_eq!(Location::new(DungeonBranch::Mines, 2).difficulty_depth(&layout), 6);
+        assert_eq!(Location::main(3).difficulty_depth(&layout), 3);
+    }
+
+    #[test]
+    fn test_leaving_top_of_branch_returns_to_entrance() {
+        let mut layout = BranchLayout::default();
+        layout.entrances.insert(DungeonBranch::Crypt, 7);
+
+        assert_eq!(Location::new(DungeonBranch::Crypt, 1).above(&layout), Some(Location::main(7)));
+        assert_eq!(Location::main(1).above(&layout), Some(Location::town()));
+        assert_eq!(Location::town().above(&layout), None);
+        assert_eq!(Location::town().below(), Some(Location::main(1)));
+        assert!(Location::new(DungeonBranch::Crypt, 3).below().is_none());
+        assert!(Location::new(DungeonBranch::Crypt, 3).is_branch_bottom());
+    }
+
+    #[test]
+    fn test_stairs_lead_between_levels_and_into_branches() {
+        let mut layout = BranchLayout::default();
+        layout.entrances.insert(DungeonBranch::Sewer, 2);
+        let mut main = Map::new(10, 10, 2);
+        main.location = Location::main(2);
+        main.set_tile(1, 1, TileType::UpStairs);
+        main.set_tile(8, 8, TileType::DownStairs);
+        main.set_tile(5, 5, TileType::DownStairs);
+        main.entrance = (1, 1);
+        main.exit = (8, 8);
+        main.branch_entrances.push(BranchEntrance { x: 5, y: 5, branch: DungeonBranch::Sewer });
+
+        assert_eq!(stairs_destination(&main, &layout, 8, 8), Some(Location::main(3)));
+        assert_eq!(stairs_destination(&main, &layout, 1, 1), Some(Location::main(1)));
+        assert_eq!(stairs_destination(&main, &layout, 5, 5), Some(Location::new(DungeonBranch::Sewer, 1)));
+        assert_eq!(stairs_destination(&main, &layout, 3, 3), None);
+
+        // Coming down arrives on the way up, coming up on the way down, and leaving the
+        // sewer on its entrance
+        assert_eq!(arrival_point(&main, Location::main(1)), (1, 1));
+        assert_eq!(arrival_point(&main, Location::main(3)), (8, 8));
+        assert_eq!(arrival_point(&main, Location::new(DungeonBranch::Sewer, 1)), (5, 5));
+    }
+
+    #[test]
+    fn test_levels_are_kept_one_copy_each() {
+        let mut levels = DungeonLevels::default();
+        let mut first = Map::new(10, 10, 1);
+        first.visible_tiles[0] = true;
+        levels.store(first.clone());
+        first.revealed_tiles[0] = true;
+        levels.store(first);
+
+        assert_eq!(levels.levels.len(), 1);
+        let kept = levels.take(Location::main(1)).unwrap();
+        assert!(kept.revealed_tiles[0] && !kept.visible_tiles[0]);
+        assert!(!levels.has_visited(Location::main(1)));
+    }
+
+    #[test]
+    fn test_generated_entrances_fall_in_range() {
+        let mut rng = RandomNumberGenerator::new(11);
+        let layout = BranchLayout::generate(&mut rng);
+        for branch in DungeonBranch::side_branches().iter() {
+            let (min_depth, max_depth) = branch.entrance_depths();
+            let depth = layout.entrance_depth(*branch).unwrap();
+            assert!(depth >= min_depth && depth <= max_depth);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Serialize, Deserialize};
+use super::TileType;
+
+/// Lasting marks left on the floor by fights and deaths, drawn beneath entities
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Decal {
+    Blood,
+    Scorch,
+    Bones,
+}
+
+impl Decal {
+    /// Glyph drawn in place of the tile; `None` keeps the tile's glyph and only tints it
+    pub fn glyph(&self) -> Option<char> {
+        match self {
+            Decal::Blood => None,
+            Decal::Scorch => None,
+            Decal::Bones => Some(','),
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            Decal::Blood => crossterm::style::Color::DarkRed,
+            Decal::Scorch => crossterm::style::Color::DarkGrey,
+            Decal::Bones => crossterm::style::Color::White,
+        }
+    }
+
+    /// Appended to the tile description in look mode
+    pub fn description(&self) -> &'static str {
+        match self {
+            Decal::Blood => "It is stained with blood.",
+            Decal::Scorch => "It is blackened by fire.",
+            Decal::Bones => "Old bones are scattered here.",
+        }
+    }
+
+    /// Blood and bones are hidden when gore is turned off; scorch marks are not
+    pub fn is_gore(&self) -> bool {
+        matches!(self, Decal::Blood | Decal::Bones)
+    }
+
+    /// A new decal only replaces an existing one of equal or lower priority
+    fn priority(&self) -> u8 {
+        match self {
+            Decal::Blood => 0,
+            Decal::Scorch => 1,
+            Decal::Bones => 2,
+        }
+    }
+
+    pub fn covers(&self, existing: Decal) -> bool {
+        self.priority() >= existing.priority()
+    }
+
+    /// Decals only stick to open ground, never to walls, stairs, doors or liquids
+    pub fn sticks_to(tile: TileType) -> bool {
+        matches!(tile, TileType::Floor | TileType::Grass | TileType::Sand | TileType::Ice | TileType::Bridge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+
+    #[test]
+    fn test_decals_only_stick_to_open_ground() {
+        let mut map = Map::new(10, 10, 1);
+        map.set_tile(3, 3, TileType::Floor);
+        map.set_tile(4, 3, TileType::DownStairs);
+
+        assert!(map.add_decal(3, 3, Decal::Blood));
+        assert!(!map.add_decal(4, 3, Decal::Blood));
+        assert!(!map.add_decal(5, 3, Decal::Blood));
+        assert_eq!(map.get_decal(3, 3), Some(Decal::Blood));
+        assert_eq!(map.get_decal(4, 3), None);
+    }
+
+    #[test]
+    fn test_bones_are_not_covered_by_blood() {
+        let mut map = Map::new(10, 10, 1);
+        map.set_tile(3, 3, TileType::Floor);
+
+        assert!(map.add_decal(3, 3, Decal::Bones));
+        assert!(!map.add_decal(3, 3, Decal::Blood));
+        assert_eq!(map.get_decal(3, 3), Some(Decal::Bones));
+    }
+}
@@ -0,0 +1,223 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::{Hash, Hasher};
+use super::Map;
+
+/// Neighbouring steps, diagonals included
+const DIRECTIONS: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// How much further a fleeing creature will go to get clear rather than just backing away;
+/// applied as a ratio to the approach map, as in the classic "flee map"
+const FLEE_NUMERATOR: i32 = -6;
+const FLEE_DENOMINATOR: i32 = 5;
+
+/// What a Dijkstra map measures the distance to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DijkstraSource {
+    Player,
+    /// The ways up and down
+    Stairs,
+    /// Items lying on the floor
+    Items,
+    /// Away from the player: lowest where the player is hardest to reach
+    Safety,
+    /// The edge of what the player has seen so far
+    Unexplored,
+}
+
+/// Walking distance from every tile to the nearest of a set of sources, ignoring creatures
+#[derive(Debug, Clone, PartialEq)]
+pub struct DijkstraMap {
+    pub width: i32,
+    pub height: i32,
+    pub values: Vec<i32>,
+}
+
+impl DijkstraMap {
+    /// Value of tiles no source can be reached from
+    pub const UNREACHABLE: i32 = i32::MAX;
+
+    pub fn new(map: &Map, sources: &[(i32, i32)]) -> Self {
+        let mut dijkstra = DijkstraMap {
+            width: map.width,
+            height: map.height,
+            values: vec![Self::UNREACHABLE; (map.width * map.height) as usize],
+        };
+        for &(x, y) in sources {
+            if map.in_bounds(x, y) {
+                dijkstra.values[map.xy_idx(x, y)] = 0;
+            }
+        }
+        dijkstra.relax(map);
+        dijkstra
+    }
+
+    /// A map for running away from whatever `approach` leads to
+    pub fn flee_from(map: &Map, approach: &DijkstraMap) -> Self {
+        let mut dijkstra = approach.clone();
+        for value in dijkstra.values.iter_mut().filter(|value| **value != Self::UNREACHABLE) {
+            *value = *value * FLEE_NUMERATOR / FLEE_DENOMINATOR;
+        }
+        dijkstra.relax(map);
+        dijkstra
+    }
+
+    pub fn value(&self, x: i32, y: i32) -> Option<i32> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.values[(y * self.width + x) as usize]).filter(|&value| value != Self::UNREACHABLE)
+    }
+
+    /// The neighbouring tile that leads most directly toward a source, stepping around anything
+    /// standing in the way so crowds spread out instead of queueing
+    pub fn downhill(&self, map: &Map, from: (i32, i32)) -> Option<(i32, i32)> {
+        let here = self.value(from.0, from.1)?;
+        DIRECTIONS.iter()
+            .map(|(dx, dy)| (from.0 + dx, from.1 + dy))
+            .filter(|&(x, y)| !map.is_blocked(x, y))
+            .filter_map(|(x, y)| self.value(x, y).map(|value| ((x, y), value)))
+            .filter(|&(_, value)| value < here)
+            .min_by_key(|&(_, value)| value)
+            .map(|(step, _)| step)
+    }
+
+    /// Spread the values out from the lowest until every reachable tile holds its true distance
+    fn relax(&mut self, map: &Map) {
+        let mut open: BinaryHeap<Reverse<(i32, usize)>> = self.values.iter().copied().enumerate()
+            .filter(|&(_, value)| value != Self::UNREACHABLE)
+            .map(|(idx, value)| Reverse((value, idx)))
+            .collect();
+        while let Some(Reverse((value, idx))) = open.pop() {
+            if value > self.values[idx] {
+                continue;
+            }
+            let (x, y) = (idx as i32 % self.width, idx as i32 / self.width);
+            for (dx, dy) in DIRECTIONS.iter() {
+                let (nx, ny) = (x + dx, y + dy);
+                if !map.in_bounds(nx, ny) {
+                    continue;
+                }
+                let next = map.xy_idx(nx, ny);
+                if map.tiles[next].blocks_movement() || self.values[next] <= value + 1 {
+                    continue;
+                }
+                self.values[next] = value + 1;
+                open.push(Reverse((value + 1, next)));
+            }
+        }
+    }
+}
+
+impl Map {
+    /// Fingerprint of the tile layout, which changes whenever a wall falls or a door is built
+    pub fn layout_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.width.hash(&mut hasher);
+        self.tiles.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// The Dijkstra maps in use this turn, rebuilt only when the level or their sources change
+#[derive(Debug, Clone, Default)]
+pub struct DijkstraMaps {
+    layout: u64,
+    maps: HashMap<DijkstraSource, (Vec<(i32, i32)>, DijkstraMap)>,
+    /// Maps rebuilt since the start, for the curious and for tests
+    pub rebuilds: u64,
+}
+
+impl DijkstraMaps {
+    pub fn get(&self, source: DijkstraSource) -> Option<&DijkstraMap> {
+        self.maps.get(&source).map(|(_, dijkstra)| dijkstra)
+    }
+
+    /// Note the level's current layout, dropping every map if it has changed
+    pub fn set_layout(&mut self, map: &Map) {
+        let layout = map.layout_hash();
+        if layout != self.layout {
+            self.layout = layout;
+            self.maps.clear();
+        }
+    }
+
+    /// Make sure the map for `source` matches these source tiles, rebuilding it only if not;
+    /// returns whether it was rebuilt
+    pub fn update(&mut self, source: DijkstraSource, map: &Map, mut sources: Vec<(i32, i32)>) -> bool {
+        sources.sort();
+        if self.maps.get(&source).map_or(false, |(current, _)| *current == sources) {
+            return false;
+        }
+        let dijkstra = DijkstraMap::new(map, &sources);
+        self.maps.insert(source, (sources, dijkstra));
+        self.rebuilds += 1;
+        true
+    }
+
+    /// Rebuild the safety map from the player map; only needed when the player map changed
+    pub fn update_safety(&mut self, map: &Map) {
+        let safety = match self.maps.get(&DijkstraSource::Player) {
+            Some((sources, player)) => (sources.clone(), DijkstraMap::flee_from(map, player)),
+            None => return,
+        };
+        self.maps.insert(DijkstraSource::Safety, safety);
+        self.rebuilds += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.maps.clear();
+        self.layout = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    fn corridor() -> Map {
+        let mut map = Map::new(12, 5, 1);
+        for x in 1..11 {
+            map.set_tile(x, 2, TileType::Floor);
+        }
+        map
+    }
+
+    #[test]
+    fn test_values_count_steps_from_the_nearest_source() {
+        let map = corridor();
+        let dijkstra = DijkstraMap::new(&map, &[(1, 2), (10, 2)]);
+        assert_eq!(dijkstra.value(1, 2), Some(0));
+        assert_eq!(dijkstra.value(4, 2), Some(3));
+        assert_eq!(dijkstra.value(8, 2), Some(2));
+        assert_eq!(dijkstra.value(4, 1), None);
+        assert_eq!(dijkstra.downhill(&map, (4, 2)), Some((3, 2)));
+    }
+
+    #[test]
+    fn test_fleeing_heads_away_from_the_source() {
+        let map = corridor();
+        let player = DijkstraMap::new(&map, &[(3, 2)]);
+        let safety = DijkstraMap::flee_from(&map, &player);
+        assert_eq!(safety.downhill(&map, (5, 2)), Some((6, 2)));
+        assert_eq!(safety.downhill(&map, (2, 2)), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_maps_rebuild_only_when_something_changes() {
+        let mut map = corridor();
+        let mut maps = DijkstraMaps::default();
+        maps.set_layout(&map);
+        assert!(maps.update(DijkstraSource::Player, &map, vec![(2, 2)]));
+        assert!(!maps.update(DijkstraSource::Player, &map, vec![(2, 2)]));
+        assert!(maps.update(DijkstraSource::Player, &map, vec![(3, 2)]));
+
+        map.set_tile(5, 2, TileType::Wall);
+        maps.set_layout(&map);
+        assert!(maps.get(DijkstraSource::Player).is_none());
+        assert!(maps.update(DijkstraSource::Player, &map, vec![(3, 2)]));
+        assert_eq!(maps.get(DijkstraSource::Player).and_then(|player| player.value(8, 2)), None);
+    }
+}
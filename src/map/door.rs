@@ -0,0 +1,99 @@
+use serde::{Serialize, Deserialize};
+use super::{Map, TileType};
+
+/// Strength check needed to bash open a closed door that is merely shut
+pub const DOOR_BASH_DIFFICULTY: i32 = 8;
+
+/// What keeps a closed door from opening to a push
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorLock {
+    /// Locked; it has to be broken down
+    Locked,
+    /// Swollen in its frame; a hard shove breaks it loose
+    Stuck,
+    /// Wedged shut; the player can pull the wedge back out, but monsters can't get past it
+    Jammed,
+}
+
+impl DoorLock {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DoorLock::Locked => "locked",
+            DoorLock::Stuck => "stuck",
+            DoorLock::Jammed => "jammed",
+        }
+    }
+
+    /// Strength check needed to bash the door open
+    pub fn bash_difficulty(&self) -> i32 {
+        match self {
+            DoorLock::Locked => 16,
+            DoorLock::Stuck => 11,
+            DoorLock::Jammed => 14,
+        }
+    }
+}
+
+impl Map {
+    pub fn is_closed_door(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y) == Some(TileType::Door(false))
+    }
+
+    pub fn is_open_door(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y) == Some(TileType::Door(true))
+    }
+
+    pub fn door_lock(&self, x: i32, y: i32) -> Option<DoorLock> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+        self.door_locks.get(&self.xy_idx(x, y)).copied()
+    }
+
+    /// Lock, wedge or free a door
+    pub fn set_door_lock(&mut self, x: i32, y: i32, lock: Option<DoorLock>) {
+        if !self.in_bounds(x, y) {
+            return;
+        }
+        let idx = self.xy_idx(x, y);
+        match lock {
+            Some(lock) => self.door_locks.insert(idx, lock),
+            None => self.door_locks.remove(&idx),
+        };
+    }
+
+    /// Whether a closed door here gives to an ordinary push
+    pub fn opens_to_a_push(&self, x: i32, y: i32) -> bool {
+        self.is_closed_door(x, y) && self.door_lock(x, y).is_none()
+    }
+
+    /// Swing a door open or shut; an opened door loses whatever held it
+    pub fn set_door(&mut self, x: i32, y: i32, open: bool) {
+        self.set_tile(x, y, TileType::Door(open));
+        if open {
+            self.set_door_lock(x, y, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opening_a_door_clears_its_lock_and_sight_line() {
+        let mut map = Map::new(5, 5, 1);
+        map.set_tile(2, 2, TileType::Door(false));
+        map.set_door_lock(2, 2, Some(DoorLock::Stuck));
+        assert!(map.is_closed_door(2, 2) && !map.opens_to_a_push(2, 2));
+        assert!(map.is_blocked(2, 2) && map.is_opaque(2, 2));
+
+        map.set_door(2, 2, true);
+        assert!(map.is_open_door(2, 2));
+        assert_eq!(map.door_lock(2, 2), None);
+        assert!(!map.is_blocked(2, 2) && !map.is_opaque(2, 2));
+
+        map.set_door(2, 2, false);
+        assert!(map.opens_to_a_push(2, 2));
+    }
+}
@@ -1,12 +1,34 @@
 use rand::Rng;
 use std::cmp::{max, min};
-use crate::map::{Map, Rect, TileType, MapTheme, Direction};
-use crate::resources::RandomNumberGenerator;
+use crate::map::{Map, Rect, TileType, MapTheme, Direction, DoorLock};
+use crate::resources::{RandomNumberGenerator, MAPGEN_STREAM};
+use super::cave_generator::CellularAutomataCaveGenerator;
 
 pub trait MapGenerator {
     fn generate_map(&mut self, width: i32, height: i32, depth: i32) -> Map;
 }
 
+/// The level generators by name, for running one on its own from nothing but a seed, as the
+/// benchmarks and tools do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorKind {
+    Rooms,
+    Caves,
+}
+
+impl GeneratorKind {
+    pub const ALL: [GeneratorKind; 2] = [GeneratorKind::Rooms, GeneratorKind::Caves];
+
+    /// A level laid out from the map generation stream of `seed`
+    pub fn generate(self, width: i32, height: i32, depth: i32, seed: u64) -> Map {
+        let rng = RandomNumberGenerator::new(seed).fork(MAPGEN_STREAM);
+        match self {
+            GeneratorKind::Rooms => RoomBasedDungeonGenerator::new(rng).generate_map(width, height, depth),
+            GeneratorKind::Caves => CellularAutomataCaveGenerator::new(rng).generate_map(width, height, depth),
+        }
+    }
+}
+
 pub struct RoomBasedDungeonGenerator {
     pub rng: RandomNumberGenerator,
     pub max_rooms: i32,
@@ -121,6 +143,12 @@ impl RoomBasedDungeonGenerator {
             // 30% chance to place a door
             if self.rng.range(0, 100) < 30 {
                 map.set_tile(x, y, TileType::Door(false)); // Closed door
+                // Some won't simply swing open
+                match self.rng.range(0, 100) {
+                    roll if roll < 10 => map.set_door_lock(x, y, Some(DoorLock::Locked)),
+                    roll if roll < 20 => map.set_door_lock(x, y, Some(DoorLock::Stuck)),
+                    _ => {},
+                }
             }
         }
     }
@@ -199,4 +227,17 @@ impl MapGenerator for RoomBasedDungeonGenerator {
         
         map
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_lays_out_the_same_level() {
+        for kind in GeneratorKind::ALL {
+            let first = kind.generate(60, 40, 1, 99);
+            assert_eq!(first.tiles, kind.generate(60, 40, 1, 99).tiles);
+            assert_ne!(first.tiles, kind.generate(60, 40, 1, 100).tiles);
+        }
+    }
+}
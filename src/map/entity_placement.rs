@@ -1,6 +1,10 @@
 use rand::Rng;
-use crate::map::{Map, TileType, MapTheme};
+use crate::map::{Map, TileType, MapTheme, DungeonBranch};
 use crate::resources::RandomNumberGenerator;
+use crate::components::{GasKind, MovementMode, PackTactics};
+
+/// Intelligence a monster needs to work a door handle
+pub const DOOR_OPENING_INTELLIGENCE: i32 = 4;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum EnemyType {
@@ -19,6 +23,251 @@ pub enum EnemyType {
     Slime,
 }
 
+impl EnemyType {
+    pub fn all() -> [EnemyType; 13] {
+        [
+            EnemyType::Goblin, EnemyType::Orc, EnemyType::Troll, EnemyType::Skeleton, EnemyType::Zombie,
+            EnemyType::Ghost, EnemyType::Demon, EnemyType::Dragon, EnemyType::Spider, EnemyType::Bat,
+            EnemyType::Rat, EnemyType::Snake, EnemyType::Slime,
+        ]
+    }
+
+    /// The enemy type a monster's name belongs to, if it is one of the common kinds
+    pub fn from_name(name: &str) -> Option<EnemyType> {
+        EnemyType::all().iter().copied().find(|enemy| enemy.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyType::Goblin => "Goblin",
+            EnemyType::Orc => "Orc",
+            EnemyType::Troll => "Troll",
+            EnemyType::Skeleton => "Skeleton",
+            EnemyType::Zombie => "Zombie",
+            EnemyType::Ghost => "Ghost",
+            EnemyType::Demon => "Demon",
+            EnemyType::Dragon => "Dragon",
+            EnemyType::Spider => "Spider",
+            EnemyType::Bat => "Bat",
+            EnemyType::Rat => "Rat",
+            EnemyType::Snake => "Snake",
+            EnemyType::Slime => "Slime",
+        }
+    }
+
+    pub fn glyph(&self) -> char {
+        match self {
+            EnemyType::Goblin => 'g',
+            EnemyType::Orc => 'o',
+            EnemyType::Troll => 'T',
+            EnemyType::Skeleton => 's',
+            EnemyType::Zombie => 'z',
+            EnemyType::Ghost => 'G',
+            EnemyType::Demon => '&',
+            EnemyType::Dragon => 'D',
+            EnemyType::Spider => 'x',
+            EnemyType::Bat => 'b',
+            EnemyType::Rat => 'r',
+            EnemyType::Snake => 'S',
+            EnemyType::Slime => 'j',
+        }
+    }
+
+    pub fn is_undead(&self) -> bool {
+        matches!(self, EnemyType::Skeleton | EnemyType::Zombie | EnemyType::Ghost)
+    }
+
+    /// Base (max_hp, defense, power) before any depth scaling
+    pub fn base_stats(&self) -> (i32, i32, i32) {
+        match self {
+            EnemyType::Rat => (3, 0, 3),
+            EnemyType::Bat => (4, 0, 2),
+            EnemyType::Snake => (5, 0, 4),
+            EnemyType::Goblin => (6, 1, 4),
+            EnemyType::Spider => (6, 1, 4),
+            EnemyType::Slime => (10, 0, 3),
+            EnemyType::Orc => (10, 1, 6),
+            EnemyType::Skeleton => (10, 2, 5),
+            EnemyType::Zombie => (14, 1, 5),
+            EnemyType::Ghost => (12, 3, 7),
+            EnemyType::Troll => (24, 3, 9),
+            EnemyType::Demon => (30, 4, 11),
+            EnemyType::Dragon => (50, 6, 14),
+        }
+    }
+
+    /// How much of a level's encounter budget this enemy uses up
+    pub fn threat(&self) -> i32 {
+        match self {
+            EnemyType::Rat | EnemyType::Bat => 1,
+            EnemyType::Snake | EnemyType::Goblin | EnemyType::Spider | EnemyType::Slime => 2,
+            EnemyType::Orc | EnemyType::Skeleton | EnemyType::Zombie => 3,
+            EnemyType::Ghost => 5,
+            EnemyType::Troll => 6,
+            EnemyType::Demon => 8,
+            EnemyType::Dragon => 12,
+        }
+    }
+
+    /// Shallowest depth at which this enemy normally appears
+    pub fn native_depth(&self) -> i32 {
+        match self {
+            EnemyType::Rat | EnemyType::Bat | EnemyType::Snake | EnemyType::Goblin => 1,
+            EnemyType::Spider | EnemyType::Slime | EnemyType::Orc => 2,
+            EnemyType::Skeleton | EnemyType::Zombie => 3,
+            EnemyType::Ghost => 5,
+            EnemyType::Troll => 6,
+            EnemyType::Demon => 8,
+            EnemyType::Dragon => 10,
+        }
+    }
+
+    /// Only comes out on the surface after dark
+    pub fn is_nocturnal(&self) -> bool {
+        matches!(self, EnemyType::Bat | EnemyType::Ghost | EnemyType::Zombie | EnemyType::Skeleton)
+    }
+
+    /// Health percent below which it turns and runs; the undead and the mindless never do
+    pub fn flee_below(&self) -> Option<i32> {
+        if self.is_undead() {
+            return None;
+        }
+        match self {
+            EnemyType::Slime | EnemyType::Demon | EnemyType::Dragon => None,
+            EnemyType::Goblin | EnemyType::Rat | EnemyType::Bat | EnemyType::Snake | EnemyType::Spider => Some(50),
+            _ => Some(25),
+        }
+    }
+
+    /// Whether, once caught fleeing, it might give up and drop what it carries
+    pub fn can_surrender(&self) -> bool {
+        matches!(self, EnemyType::Goblin | EnemyType::Orc)
+    }
+
+    /// How it fights when it runs in a pack, and how many followers a leader brings at most
+    pub fn pack(&self) -> Option<(PackTactics, i32)> {
+        match self {
+            EnemyType::Rat => Some((PackTactics::Swarm, 4)),
+            EnemyType::Spider => Some((PackTactics::Swarm, 2)),
+            EnemyType::Goblin => Some((PackTactics::Warband, 3)),
+            EnemyType::Orc => Some((PackTactics::Warband, 2)),
+            _ => None,
+        }
+    }
+
+    /// What adventurers tell of it, revealed in the bestiary once enough have been killed
+    pub fn lore(&self) -> &'static str {
+        match self {
+            EnemyType::Goblin => "Cowardly alone and brave in a crowd, goblins fight in warbands and bolt when the tide turns.",
+            EnemyType::Orc => "Orcs march in small warbands, and one caught fleeing will sometimes throw down its loot to live.",
+            EnemyType::Troll => "A troll shrugs off blows that would fell three men. Do not trade hits with one for long.",
+            EnemyType::Skeleton => "Bones bound by old magic. It feels no fear and will never break and run.",
+            EnemyType::Zombie => "Slow and half deaf, but it keeps coming until it is cut apart.",
+            EnemyType::Ghost => "A cold shape that hears the faintest footstep, and walks the surface only by night.",
+            EnemyType::Demon => "Nothing frightens a demon. It fights to the end, and its end comes hard.",
+            EnemyType::Dragon => "The dungeon's oldest terror breathes fire across whole rooms. Never stand in a line with it.",
+            EnemyType::Spider => "Spiders hunt in small swarms and are quick to scuttle off when hurt.",
+            EnemyType::Bat => "Bats hear everything and come out after dark, but a single blow sends them flapping away.",
+            EnemyType::Rat => "One rat is a nuisance; the swarm that follows it is the danger.",
+            EnemyType::Snake => "A snake strikes harder than its size suggests, then slithers off once wounded.",
+            EnemyType::Slime => "Mindless and slow to notice you, a slime never retreats and takes a long time to wear down.",
+        }
+    }
+
+    /// Rough wits, from the mindless slime at 1 to the cunning dragon at 10
+    pub fn intelligence(&self) -> i32 {
+        match self {
+            EnemyType::Slime => 1,
+            EnemyType::Rat | EnemyType::Bat | EnemyType::Spider | EnemyType::Snake | EnemyType::Zombie => 2,
+            EnemyType::Skeleton => 3,
+            EnemyType::Troll => 4,
+            EnemyType::Goblin | EnemyType::Orc => 5,
+            EnemyType::Ghost => 6,
+            EnemyType::Demon => 8,
+            EnemyType::Dragon => 10,
+        }
+    }
+
+    /// Whether it is bright enough to open a closed door rather than wait behind it
+    pub fn opens_doors(&self) -> bool {
+        self.intelligence() >= DOOR_OPENING_INTELLIGENCE
+    }
+
+    /// How it gets about: bats and dragons on the wing, ghosts straight through the walls
+    pub fn movement_mode(&self) -> MovementMode {
+        match self {
+            EnemyType::Bat | EnemyType::Dragon => MovementMode::Flying,
+            EnemyType::Ghost => MovementMode::Phasing,
+            _ => MovementMode::Walking,
+        }
+    }
+
+    /// The gas it gives off as it dies, and how much: zombies burst with rot, slimes with spores
+    pub fn death_gas(&self) -> Option<(GasKind, i32)> {
+        match self {
+            EnemyType::Zombie => Some((GasKind::Miasma, 12)),
+            EnemyType::Slime => Some((GasKind::Spores, 10)),
+            _ => None,
+        }
+    }
+
+    /// Extra tiles away it can hear a noise from
+    pub fn alertness(&self) -> i32 {
+        match self {
+            EnemyType::Bat => 4,
+            EnemyType::Goblin | EnemyType::Ghost | EnemyType::Spider => 2,
+            EnemyType::Zombie | EnemyType::Slime => -2,
+            _ => 0,
+        }
+    }
+}
+
+/// Enemies that fit the look and feel of a map theme
+pub fn theme_enemies(theme: MapTheme) -> Vec<EnemyType> {
+    match theme {
+        MapTheme::Dungeon => vec![
+            EnemyType::Goblin, EnemyType::Orc, EnemyType::Skeleton, 
+            EnemyType::Zombie, EnemyType::Rat, EnemyType::Troll
+        ],
+        MapTheme::Cave => vec![
+            EnemyType::Bat, EnemyType::Spider, EnemyType::Slime,
+            EnemyType::Troll, EnemyType::Rat, EnemyType::Snake
+        ],
+        MapTheme::Forest => vec![
+            EnemyType::Spider, EnemyType::Snake, EnemyType::Goblin,
+            EnemyType::Bat, EnemyType::Slime
+        ],
+        MapTheme::Desert => vec![
+            EnemyType::Snake, EnemyType::Skeleton, EnemyType::Zombie,
+            EnemyType::Demon
+        ],
+        MapTheme::Ice => vec![
+            EnemyType::Troll, EnemyType::Ghost, EnemyType::Zombie,
+            EnemyType::Dragon
+        ],
+        MapTheme::Volcanic => vec![
+            EnemyType::Demon, EnemyType::Dragon, EnemyType::Slime,
+            EnemyType::Troll
+        ],
+        MapTheme::Underwater => vec![
+            EnemyType::Slime, EnemyType::Snake, EnemyType::Ghost
+        ],
+        MapTheme::Mines => vec![
+            EnemyType::Goblin, EnemyType::Orc, EnemyType::Bat,
+            EnemyType::Spider, EnemyType::Troll
+        ],
+        MapTheme::Crypt => vec![
+            EnemyType::Skeleton, EnemyType::Zombie, EnemyType::Ghost,
+            EnemyType::Rat, EnemyType::Demon
+        ],
+        MapTheme::Sewer => vec![
+            EnemyType::Rat, EnemyType::Slime, EnemyType::Snake,
+            EnemyType::Spider, EnemyType::Zombie
+        ],
+        MapTheme::Town => Vec::new(),
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ItemType {
     HealthPotion,
@@ -73,6 +322,7 @@ impl EntityPlacementSystem {
                     entity_type: SpawnType::Enemy(enemy_type),
                     x: pos.0,
                     y: pos.1,
+                    leader: None,
                 });
             }
         }
@@ -92,6 +342,7 @@ impl EntityPlacementSystem {
                     entity_type: SpawnType::Item(item_type),
                     x: pos.0,
                     y: pos.1,
+                    leader: None,
                 });
             }
         }
@@ -108,6 +359,7 @@ impl EntityPlacementSystem {
                     entity_type: SpawnType::Special(feature_type),
                     x: pos.0,
                     y: pos.1,
+                    leader: None,
                 });
             }
         }
@@ -188,41 +440,19 @@ impl EntityPlacementSystem {
     }
     
     fn get_theme_appropriate_enemies(&self, theme: MapTheme) -> Vec<EnemyType> {
-        match theme {
-            MapTheme::Dungeon => vec![
-                EnemyType::Goblin, EnemyType::Orc, EnemyType::Skeleton, 
-                EnemyType::Zombie, EnemyType::Rat, EnemyType::Troll
-            ],
-            MapTheme::Cave => vec![
-                EnemyType::Bat, EnemyType::Spider, EnemyType::Slime,
-                EnemyType::Troll, EnemyType::Rat, EnemyType::Snake
-            ],
-            MapTheme::Forest => vec![
-                EnemyType::Spider, EnemyType::Snake, EnemyType::Goblin,
-                EnemyType::Bat, EnemyType::Slime
-            ],
-            MapTheme::Desert => vec![
-                EnemyType::Snake, EnemyType::Skeleton, EnemyType::Zombie,
-                EnemyType::Demon
-            ],
-            MapTheme::Ice => vec![
-                EnemyType::Troll, EnemyType::Ghost, EnemyType::Zombie,
-                EnemyType::Dragon
-            ],
-            MapTheme::Volcanic => vec![
-                EnemyType::Demon, EnemyType::Dragon, EnemyType::Slime,
-                EnemyType::Troll
-            ],
-            MapTheme::Underwater => vec![
-                EnemyType::Slime, EnemyType::Snake, EnemyType::Ghost
-            ],
-        }
+        theme_enemies(theme)
     }
     
     fn choose_item_type(&mut self, map: &Map, difficulty: i32) -> ItemType {
         // Choose item type based on map theme and difficulty
         let roll = self.rng.range(0, 100);
         
+        // Side branches favor their own kind of treasure
+        let branch_loot = map.location.branch.loot_bias();
+        if roll < 30 && !branch_loot.is_empty() {
+            return branch_loot[self.rng.range(0, branch_loot.len() as i32 - 1) as usize];
+        }
+        
         // Common items (50%)
         if roll < 50 {
             let common_items = vec![
@@ -273,6 +503,9 @@ impl EntityPlacementSystem {
 #[derive(Clone, Copy, Debug)]
 pub enum SpawnType {
     Enemy(EnemyType),
+    Boss(DungeonBranch),
+    /// The out-of-depth elite shut in a greed vault
+    Guardian(EnemyType),
     Item(ItemType),
     Special(SpecialFeatureType),
 }
@@ -290,4 +523,6 @@ pub struct EntitySpawn {
     pub entity_type: SpawnType,
     pub x: i32,
     pub y: i32,
+    /// Index of the pack leader's spawn in the same plan, for pack followers
+    pub leader: Option<usize>,
 }
\ No newline at end of file
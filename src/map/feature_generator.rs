@@ -1,9 +1,93 @@
 use rand::Rng;
-use crate::map::{Map, Rect, TileType, MapTheme};
+use crate::components::{CaptiveKind, FurnitureKind};
+use crate::map::{DoorLock, Map, Rect, TileType, MapTheme, Vault};
 use crate::resources::RandomNumberGenerator;
 
+/// Percent chance that a room between the entrance and the exit is furnished
+const FURNISHED_ROOM_CHANCE: i32 = 20;
+/// Most bookshelves lining one library wall
+const MAX_BOOKSHELVES: usize = 3;
+/// Furthest from a hidden door its lever is set
+const LEVER_REACH: i32 = 2;
+/// Percent chance that a level deep enough holds a greed vault
+const GREED_VAULT_CHANCE: i32 = 12;
+/// Shallowest level a greed vault turns up on
+const GREED_VAULT_MIN_DEPTH: i32 = 2;
+/// Width and height of the floor inside a greed vault's walls
+const GREED_VAULT_SIZE: i32 = 3;
+/// Percent chance that a level holds someone waiting to be rescued
+const CAPTIVE_CHANCE: i32 = 15;
+/// Percent chance that a level holds an enchanting station
+const ENCHANTING_STATION_CHANCE: i32 = 25;
+/// Percent chance that a level holds an alchemy station
+const ALCHEMY_STATION_CHANCE: i32 = 25;
+
 pub struct DungeonFeatureGenerator {
     pub rng: RandomNumberGenerator,
+    /// Furniture placed so far, for whoever populates the level
+    pub furniture: Vec<FurniturePlacement>,
+    /// Captives left for whoever populates the level
+    pub captives: Vec<CaptivePlacement>,
+    /// Crafting stations left for whoever populates the level
+    pub stations: Vec<StationPlacement>,
+}
+
+/// A piece of furniture the generator wants standing on a tile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FurniturePlacement {
+    pub kind: FurnitureKind,
+    pub x: i32,
+    pub y: i32,
+    /// The hidden door a lever opens
+    pub opens: Option<(i32, i32)>,
+}
+
+/// A workbench the player crafts at rather than uses outright
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StationKind {
+    /// Binds essences into weapons and armor, or breaks magic items down into them
+    Enchanting,
+    /// Mixes two ingredients into a potion
+    Alchemy,
+}
+
+impl StationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StationKind::Enchanting => "Enchanting Station",
+            StationKind::Alchemy => "Alchemy Station",
+        }
+    }
+
+    pub fn glyph(&self) -> char {
+        match self {
+            StationKind::Enchanting => '&',
+            StationKind::Alchemy => '&',
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            StationKind::Enchanting => crossterm::style::Color::Magenta,
+            StationKind::Alchemy => crossterm::style::Color::Green,
+        }
+    }
+}
+
+/// A crafting station the generator wants standing on a tile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StationPlacement {
+    pub kind: StationKind,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Someone held or stranded on a tile, waiting for the player
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaptivePlacement {
+    pub kind: CaptiveKind,
+    pub x: i32,
+    pub y: i32,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -29,7 +113,104 @@ pub enum EnvironmentalHazard {
 
 impl DungeonFeatureGenerator {
     pub fn new(rng: RandomNumberGenerator) -> Self {
-        DungeonFeatureGenerator { rng }
+        DungeonFeatureGenerator { rng, furniture: Vec::new(), captives: Vec::new(), stations: Vec::new() }
+    }
+    
+    /// Furnish a level without reshaping it: libraries, shrines and fountains in some of its
+    /// rooms, perhaps a hidden nook behind a lever, an enchanting or alchemy station, and now
+    /// and then a greed vault or a captive waiting in a room of their own. The furniture's, stations' and
+    /// captives' tiles are marked blocked
+    pub fn furnish(&mut self, map: &mut Map) -> Vec<FurniturePlacement> {
+        // Skip the first and last rooms (entrance and exit)
+        let rooms: Vec<Rect> = if map.rooms.len() > 2 {
+            map.rooms[1..map.rooms.len()-1].to_vec()
+        } else {
+            Vec::new()
+        };
+        for room in &rooms {
+            if self.rng.range(1, 100) > FURNISHED_ROOM_CHANCE {
+                continue;
+            }
+            match self.rng.range(0, 2) {
+                0 => self.create_library(map, room),
+                1 => self.create_shrine(map, room),
+                _ => self.create_fountain(map, room),
+            }
+        }
+        if self.rng.range(0, 1) == 0 {
+            self.create_secret_area(map);
+        }
+        if map.depth >= GREED_VAULT_MIN_DEPTH && self.rng.range(1, 100) <= GREED_VAULT_CHANCE {
+            self.create_greed_vault(map);
+        }
+        if !rooms.is_empty() && self.rng.range(1, 100) <= ENCHANTING_STATION_CHANCE {
+            let room = rooms[self.rng.range(0, rooms.len() as i32 - 1) as usize];
+            self.place_station(map, StationKind::Enchanting, &room);
+        }
+        if !rooms.is_empty() && self.rng.range(1, 100) <= ALCHEMY_STATION_CHANCE {
+            let room = rooms[self.rng.range(0, rooms.len() as i32 - 1) as usize];
+            self.place_station(map, StationKind::Alchemy, &room);
+        }
+        if !rooms.is_empty() && self.rng.range(1, 100) <= CAPTIVE_CHANCE {
+            let room = rooms[self.rng.range(0, rooms.len() as i32 - 1) as usize];
+            self.place_captive(map, &room);
+        }
+        
+        let taken = self.furniture.iter().map(|piece| (piece.x, piece.y))
+            .chain(self.stations.iter().map(|station| (station.x, station.y)))
+            .chain(self.captives.iter().map(|captive| (captive.x, captive.y)));
+        for (x, y) in taken {
+            let idx = map.xy_idx(x, y);
+            map.blocked[idx] = true;
+        }
+        std::mem::take(&mut self.furniture)
+    }
+    
+    /// Set a piece of furniture on an open floor tile, unless something is already there
+    fn place_furniture(&mut self, map: &Map, kind: FurnitureKind, x: i32, y: i32, opens: Option<(i32, i32)>) -> bool {
+        if map.get_tile(x, y) != Some(TileType::Floor) || (x, y) == map.entrance || (x, y) == map.exit {
+            return false;
+        }
+        if self.furniture.iter().any(|piece| (piece.x, piece.y) == (x, y)) {
+            return false;
+        }
+        self.furniture.push(FurniturePlacement { kind, x, y, opens });
+        true
+    }
+    
+    /// Whether furniture or a station already stands on (x, y)
+    fn is_furnished(&self, x: i32, y: i32) -> bool {
+        self.furniture.iter().any(|piece| (piece.x, piece.y) == (x, y))
+            || self.stations.iter().any(|station| (station.x, station.y) == (x, y))
+    }
+    
+    /// Set a crafting station against the back wall of the room, on the first open floor tile
+    /// clear of its furniture
+    fn place_station(&mut self, map: &Map, kind: StationKind, room: &Rect) -> bool {
+        for x in room.x1 + 1..room.x2 - 1 {
+            let y = room.y2 - 2;
+            if map.get_tile(x, y) != Some(TileType::Floor) || (x, y) == map.entrance || (x, y) == map.exit || self.is_furnished(x, y) {
+                continue;
+            }
+            self.stations.push(StationPlacement { kind, x, y });
+            return true;
+        }
+        false
+    }
+    
+    /// Leave a captured merchant or a lost adventurer in a corner of the room, clear of its furniture
+    fn place_captive(&mut self, map: &Map, room: &Rect) -> bool {
+        let kind = if self.rng.range(0, 1) == 0 { CaptiveKind::Merchant } else { CaptiveKind::Adventurer };
+        let corners = [(room.x1, room.y1), (room.x2 - 1, room.y1), (room.x1, room.y2 - 1), (room.x2 - 1, room.y2 - 1)];
+        for &(x, y) in &corners {
+            let taken = self.is_furnished(x, y);
+            if taken || map.get_tile(x, y) != Some(TileType::Floor) || (x, y) == map.entrance || (x, y) == map.exit {
+                continue;
+            }
+            self.captives.push(CaptivePlacement { kind, x, y });
+            return true;
+        }
+        false
     }
     
     /// Add special features to an existing map
@@ -149,9 +330,22 @@ impl DungeonFeatureGenerator {
         }
     }
     
-    fn create_library(&mut self, _map: &mut Map, _room: &Rect) {
-        // Libraries would have bookshelves, but we'll keep it simple for ASCII
-        // Could add special floor patterns or decorative elements
+    fn create_library(&mut self, map: &mut Map, room: &Rect) {
+        // Bookshelves along the top wall, with gaps to reach each one
+        let mut shelves = 0;
+        for x in (room.x1 + 1..room.x2 - 1).step_by(2) {
+            if shelves >= MAX_BOOKSHELVES {
+                break;
+            }
+            if self.place_furniture(map, FurnitureKind::Bookshelf, x, room.y1 + 1, None) {
+                shelves += 1;
+            }
+        }
+    }
+    
+    fn create_fountain(&mut self, map: &mut Map, room: &Rect) {
+        let center = room.center();
+        self.place_furniture(map, FurnitureKind::Fountain, center.0, center.1, None);
     }
     
     fn create_armory(&mut self, map: &mut Map, room: &Rect) {
@@ -170,7 +364,7 @@ impl DungeonFeatureGenerator {
         let center = room.center();
         
         // Create a small shrine in the center
-        map.set_tile(center.0, center.1, TileType::Rock);
+        self.place_furniture(map, FurnitureKind::Shrine, center.0, center.1, None);
         
         // Add some decorative elements around it
         for &(dx, dy) in &[(0, -1), (1, 0), (0, 1), (-1, 0)] {
@@ -470,6 +664,24 @@ impl DungeonFeatureGenerator {
         }
     }
     
+    /// A lever on the floor near the hidden door, but not in front of it
+    fn place_lever(&mut self, map: &Map, door: (i32, i32), outside: (i32, i32)) -> bool {
+        let mut spots = Vec::new();
+        for dy in -LEVER_REACH..=LEVER_REACH {
+            for dx in -LEVER_REACH..=LEVER_REACH {
+                let spot = (outside.0 + dx, outside.1 + dy);
+                if spot != outside && map.get_tile(spot.0, spot.1) == Some(TileType::Floor) {
+                    spots.push(spot);
+                }
+            }
+        }
+        if spots.is_empty() {
+            return false;
+        }
+        let (x, y) = spots[self.rng.range(0, spots.len() as i32 - 1) as usize];
+        self.place_furniture(map, FurnitureKind::Lever, x, y, Some(door))
+    }
+    
     fn add_secret_areas(&mut self, map: &mut Map) {
         // Add 1-2 secret areas
         let num_secrets = self.rng.range(1, 3);
@@ -504,29 +716,19 @@ impl DungeonFeatureGenerator {
             }
             
             if can_create {
-                // Check if it's adjacent to a floor tile (for access)
-                let mut has_access = false;
-                for dy in -1..=3 {
-                    for dx in -1..=3 {
-                        if dx >= 0 && dx < 3 && dy >= 0 && dy < 3 {
-                            continue; // Skip the room itself
-                        }
-                        
-                        let check_x = x + dx;
-                        let check_y = y + dy;
-                        
-                        if map.in_bounds(check_x, check_y) {
-                            let idx = map.xy_idx(check_x, check_y);
-                            if map.tiles[idx] == TileType::Floor {
-                                has_access = true;
-                                break;
-                            }
-                        }
-                    }
-                    if has_access { break; }
-                }
+                // The door goes in a side with floor beyond it
+                let sides = [
+                    ((x + 1, y), (x + 1, y - 1)),     // Top
+                    ((x + 2, y + 1), (x + 3, y + 1)), // Right
+                    ((x + 1, y + 2), (x + 1, y + 3)), // Bottom
+                    ((x, y + 1), (x - 1, y + 1)),     // Left
+                ];
+                let open: Vec<((i32, i32), (i32, i32))> = sides.iter()
+                    .copied()
+                    .filter(|(_, (ox, oy))| map.get_tile(*ox, *oy) == Some(TileType::Floor))
+                    .collect();
                 
-                if has_access {
+                if !open.is_empty() {
                     // Create the secret room
                     for dy in 0..3 {
                         for dx in 0..3 {
@@ -546,16 +748,11 @@ impl DungeonFeatureGenerator {
                         }
                     }
                     
-                    // Add a secret door
-                    let door_side = self.rng.range(0, 4);
-                    let (door_x, door_y) = match door_side {
-                        0 => (x + 1, y),     // Top
-                        1 => (x + 2, y + 1), // Right
-                        2 => (x + 1, y + 2), // Bottom
-                        _ => (x, y + 1),     // Left
-                    };
-                    
-                    map.set_tile(door_x, door_y, TileType::Door(false)); // Secret door (closed)
+                    // The door stays hidden in the wall until a lever close by is pulled
+                    let ((door_x, door_y), (out_x, out_y)) = open[self.rng.range(0, open.len() as i32 - 1) as usize];
+                    if !self.place_lever(map, (door_x, door_y), (out_x, out_y)) {
+                        map.set_tile(door_x, door_y, TileType::Door(false)); // No room for a lever
+                    }
                     break;
                 }
             }
@@ -563,4 +760,49 @@ impl DungeonFeatureGenerator {
             attempts += 1;
         }
     }
-}
\ No newline at end of file
+    
+    /// Wall off a sealed room in solid rock beside an open floor tile, for a hoard and its
+    /// guardian. The door is locked; a lever outside opens it, or failing room for one, a key
+    /// left elsewhere on the level does
+    fn create_greed_vault(&mut self, map: &mut Map) -> bool {
+        let span = GREED_VAULT_SIZE + 2;
+        for _ in 0..200 {
+            let x = self.rng.range(1, map.width - span - 1);
+            let y = self.rng.range(1, map.height - span - 1);
+            let solid = (y..y + span).all(|cy| (x..x + span).all(|cx| map.get_tile(cx, cy) == Some(TileType::Wall)));
+            if !solid {
+                continue;
+            }
+
+            // The door sits mid-wall, on a side with floor just beyond it
+            let mid = span / 2;
+            let sides = [
+                ((x + mid, y), (x + mid, y - 1)),
+                ((x + span - 1, y + mid), (x + span, y + mid)),
+                ((x + mid, y + span - 1), (x + mid, y + span)),
+                ((x, y + mid), (x - 1, y + mid)),
+            ];
+            let open: Vec<((i32, i32), (i32, i32))> = sides.iter()
+                .copied()
+                .filter(|(_, (ox, oy))| map.get_tile(*ox, *oy) == Some(TileType::Floor) && !map.in_vault(*ox, *oy))
+                .collect();
+            if open.is_empty() {
+                continue;
+            }
+
+            let room = Rect::new(x + 1, y + 1, GREED_VAULT_SIZE, GREED_VAULT_SIZE);
+            for fy in room.y1..room.y2 {
+                for fx in room.x1..room.x2 {
+                    map.set_tile(fx, fy, TileType::Floor);
+                }
+            }
+            let (door, outside) = open[self.rng.range(0, open.len() as i32 - 1) as usize];
+            map.set_tile(door.0, door.1, TileType::Door(false));
+            map.set_door_lock(door.0, door.1, Some(DoorLock::Locked));
+            let keyed = !self.place_lever(map, door, outside);
+            map.add_vault(Vault { room, door, keyed });
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Decal, Map, TileEffect, TileType, TransientTileState};
+
+/// Turns a web burns before it is gone
+const WEB_BURN_TURNS: i32 = 1;
+/// Turns the smoke from a fire hangs over a tile
+pub const SMOKE_TURNS: i32 = 3;
+
+/// Turns a flammable tile burns before it is spent
+pub fn burn_turns(tile: TileType) -> i32 {
+    match tile {
+        TileType::Grass => 3,
+        TileType::Door(_) => 5,
+        TileType::Tree => 8,
+        _ => 0,
+    }
+}
+
+impl Map {
+    /// Whether fire could take hold here right now: dry ground, wood or webs not already burning
+    pub fn can_ignite(&self, x: i32, y: i32) -> bool {
+        match self.get_tile_state(x, y).map(|state| state.effect) {
+            Some(TileEffect::Webbed) => true,
+            Some(TileEffect::Smoke) | None => self.get_tile(x, y).map_or(false, |tile| tile.is_flammable()),
+            Some(_) => false,
+        }
+    }
+
+    /// Set a tile alight; returns false if there was nothing there to burn
+    pub fn ignite(&mut self, x: i32, y: i32) -> bool {
+        if !self.can_ignite(x, y) {
+            return false;
+        }
+        let turns = match (self.has_tile_effect(x, y, TileEffect::Webbed), self.get_tile(x, y)) {
+            (true, _) => WEB_BURN_TURNS,
+            (false, Some(tile)) => burn_turns(tile),
+            (false, None) => return false,
+        };
+        self.set_tile_state(x, y, TransientTileState::new(TileEffect::Burning, turns));
+        true
+    }
+
+    /// What a spent fire leaves behind: blackened bare floor under a pall of smoke. Doors burn
+    /// away to an open doorway
+    pub fn burn_out(&mut self, x: i32, y: i32) {
+        if self.get_tile(x, y).map_or(false, |tile| tile.is_flammable()) {
+            self.set_door_lock(x, y, None);
+            self.set_tile(x, y, TileType::Floor);
+        }
+        self.add_decal(x, y, Decal::Scorch);
+        self.set_tile_state(x, y, TransientTileState::new(TileEffect::Smoke, SMOKE_TURNS));
+    }
+
+    /// Whether anything on the tile, be it the tile itself or smoke hanging over it, hides what lies beyond
+    pub fn blocks_sight_at(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).map_or(true, |tile| tile.blocks_sight()) || self.has_tile_effect(x, y, TileEffect::Smoke)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burnt_grass_leaves_scorched_floor_under_smoke() {
+        let mut map = Map::new(5, 5, 1);
+        map.set_tile(2, 2, TileType::Grass);
+        map.set_tile(3, 2, TileType::Floor);
+
+        assert!(map.ignite(2, 2));
+        assert!(!map.ignite(2, 2));
+        assert!(!map.ignite(3, 2));
+
+        map.burn_out(2, 2);
+        assert_eq!(map.get_tile(2, 2), Some(TileType::Floor));
+        assert_eq!(map.get_decal(2, 2), Some(Decal::Scorch));
+        assert!(map.blocks_sight_at(2, 2) && map.is_opaque(2, 2));
+        assert!(!map.can_ignite(2, 2));
+    }
+
+    #[test]
+    fn test_webs_burn_over_any_ground() {
+        let mut map = Map::new(5, 5, 1);
+        map.set_tile(2, 2, TileType::Floor);
+        map.set_tile_state(2, 2, TransientTileState::permanent(TileEffect::Webbed));
+
+        assert!(map.ignite(2, 2));
+        assert_eq!(map.get_tile_state(2, 2).and_then(|state| state.turns_remaining), Some(WEB_BURN_TURNS));
+    }
+}
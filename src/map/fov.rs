@@ -0,0 +1,144 @@
+use super::Map;
+
+/// A quarter of the view, fanning out from the origin in one direction
+#[derive(Debug, Clone, Copy)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    const ALL: [Quadrant; 4] = [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West];
+
+    /// The map tile `depth` rows out from the origin and `col` across
+    fn tile(self, origin: (i32, i32), depth: i32, col: i32) -> (i32, i32) {
+        match self {
+            Quadrant::North => (origin.0 + col, origin.1 - depth),
+            Quadrant::South => (origin.0 + col, origin.1 + depth),
+            Quadrant::East => (origin.0 + depth, origin.1 + col),
+            Quadrant::West => (origin.0 - depth, origin.1 + col),
+        }
+    }
+}
+
+/// A slope as a fraction, kept exact so the edges of the view never drift with rounding;
+/// the denominator is always positive
+#[derive(Debug, Clone, Copy)]
+struct Slope {
+    num: i32,
+    den: i32,
+}
+
+impl Slope {
+    /// The slope through the near edge of a tile
+    fn edge(depth: i32, col: i32) -> Self {
+        Slope { num: 2 * col - 1, den: 2 * depth }
+    }
+}
+
+/// One row of a quadrant still being scanned, between two slopes
+#[derive(Debug, Clone, Copy)]
+struct Row {
+    depth: i32,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    /// First and last column the row covers, rounding half a tile in at either end
+    fn columns(&self) -> (i32, i32) {
+        let first = (2 * self.depth * self.start.num + self.start.den).div_euclid(2 * self.start.den);
+        let last = -(-2 * self.depth * self.end.num + self.end.den).div_euclid(2 * self.end.den);
+        (first, last)
+    }
+
+    /// Whether the centre of a floor tile lies within the row, so that whoever stands there
+    /// can see back the other way
+    fn is_symmetric(&self, col: i32) -> bool {
+        col * self.start.den >= self.depth * self.start.num && col * self.end.den <= self.depth * self.end.num
+    }
+
+    fn next(&self) -> Row {
+        Row { depth: self.depth + 1, ..*self }
+    }
+}
+
+impl Map {
+    /// Tiles in sight from `origin` within `range`, by symmetric shadowcasting: if one tile can
+    /// see another, that one can see it back. Walls that face the origin are included.
+    pub fn field_of_view(&self, origin: (i32, i32), range: i32) -> Vec<(i32, i32)> {
+        let mut visible = vec![origin];
+        for quadrant in Quadrant::ALL {
+            let mut rows = vec![Row { depth: 1, start: Slope { num: -1, den: 1 }, end: Slope { num: 1, den: 1 } }];
+            while let Some(mut row) = rows.pop() {
+                if row.depth > range {
+                    continue;
+                }
+                let (first, last) = row.columns();
+                let mut previous_wall = None;
+                for col in first..=last {
+                    let (x, y) = quadrant.tile(origin, row.depth, col);
+                    let wall = self.blocks_sight_at(x, y);
+                    let in_range = row.depth * row.depth + col * col <= range * range;
+                    if self.in_bounds(x, y) && in_range && (wall || row.is_symmetric(col)) {
+                        visible.push((x, y));
+                    }
+                    match (previous_wall, wall) {
+                        (Some(true), false) => row.start = Slope::edge(row.depth, col),
+                        (Some(false), true) => rows.push(Row { end: Slope::edge(row.depth, col), ..row.next() }),
+                        _ => {},
+                    }
+                    previous_wall = Some(wall);
+                }
+                if previous_wall == Some(false) {
+                    rows.push(row.next());
+                }
+            }
+        }
+        // Neighbouring quadrants share their diagonals
+        visible.sort_unstable();
+        visible.dedup();
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::TileType;
+    use super::*;
+
+    fn room_with_pillar() -> Map {
+        let mut map = Map::new(20, 20, 1);
+        for y in 1..19 {
+            for x in 1..19 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        map.set_tile(10, 8, TileType::Wall);
+        map
+    }
+
+    #[test]
+    fn test_walls_cast_shadows() {
+        let map = room_with_pillar();
+        let visible = map.field_of_view((10, 10), 12);
+        assert!(visible.contains(&(10, 10)));
+        assert!(visible.contains(&(10, 8)));
+        assert!(!visible.contains(&(10, 6)));
+        assert!(visible.contains(&(4, 10)));
+        assert!(visible.contains(&(0, 10)));
+    }
+
+    #[test]
+    fn test_sight_is_symmetric_and_limited_by_range() {
+        let map = room_with_pillar();
+        let from = (7, 12);
+        let visible = map.field_of_view(from, 8);
+        assert!(!visible.contains(&(7, 3)));
+        for &(x, y) in visible.iter().filter(|&&(x, y)| map.get_tile(x, y) == Some(TileType::Floor)) {
+            assert!(map.field_of_view((x, y), 8).contains(&from), "{:?} sees {:?} but not back", from, (x, y));
+        }
+    }
+}
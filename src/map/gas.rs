@@ -0,0 +1,117 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::components::GasKind;
+use super::Map;
+
+/// Density every cloud loses each turn as it thins out
+const GAS_DISSIPATION: i32 = 1;
+/// Density from which a cloud is drawn thick
+const THICK_GAS: i32 = 6;
+
+/// The gas hanging over one tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasCloud {
+    pub kind: GasKind,
+    pub density: i32,
+}
+
+impl GasCloud {
+    /// Overlay glyph, heavier where the gas is thick
+    pub fn glyph(&self) -> char {
+        if self.density >= THICK_GAS { '▓' } else { '▒' }
+    }
+}
+
+/// Add gas to a tile of a cloud layer, the stronger gas taking over where two kinds meet
+fn pour(layer: &mut HashMap<usize, GasCloud>, idx: usize, kind: GasKind, amount: i32) {
+    if amount <= 0 {
+        return;
+    }
+    let cloud = layer.entry(idx).or_insert(GasCloud { kind, density: 0 });
+    if cloud.kind != kind && amount > cloud.density {
+        cloud.kind = kind;
+    }
+    cloud.density += amount;
+}
+
+impl Map {
+    /// Let loose `volume` of gas on a tile; walls and closed doors hold none
+    pub fn release_gas(&mut self, x: i32, y: i32, kind: GasKind, volume: i32) -> bool {
+        match self.get_tile(x, y) {
+            Some(tile) if !tile.blocks_movement() => {},
+            _ => return false,
+        }
+        let idx = self.xy_idx(x, y);
+        pour(&mut self.gas, idx, kind, volume);
+        true
+    }
+
+    /// The gas over a tile, if any
+    pub fn gas_at(&self, x: i32, y: i32) -> Option<GasCloud> {
+        if self.in_bounds(x, y) {
+            self.gas.get(&self.xy_idx(x, y)).copied()
+        } else {
+            None
+        }
+    }
+
+    /// One turn of drift: each cloud shares its gas evenly with the open tiles around it, then
+    /// every cloud thins out and the faintest vanish
+    pub fn spread_gas(&mut self) {
+        let mut next = HashMap::new();
+        for (&idx, cloud) in &self.gas {
+            let (x, y) = self.idx_xy(idx);
+            let open: Vec<usize> = self.get_orthogonal_neighbors(x, y).into_iter()
+                .filter(|(nx, ny)| self.get_tile(*nx, *ny).map_or(false, |tile| !tile.blocks_movement()))
+                .map(|(nx, ny)| self.xy_idx(nx, ny))
+                .collect();
+            let share = cloud.density / (open.len() as i32 + 1);
+            pour(&mut next, idx, cloud.kind, cloud.density - share * open.len() as i32);
+            for neighbor in open {
+                pour(&mut next, neighbor, cloud.kind, share);
+            }
+        }
+        next.retain(|_, cloud| {
+            cloud.density -= GAS_DISSIPATION;
+            cloud.density > 0
+        });
+        self.gas = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    #[test]
+    fn test_gas_spreads_through_open_tiles_and_dissipates() {
+        let mut map = Map::new(7, 3, 1);
+        for x in 1..6 {
+            map.set_tile(x, 1, TileType::Floor);
+        }
+        map.set_tile(4, 1, TileType::Door(false));
+
+        assert!(!map.release_gas(0, 1, GasKind::PoisonGas, 10));
+        assert!(map.release_gas(2, 1, GasKind::PoisonGas, 12));
+        map.spread_gas();
+        assert_eq!(map.gas_at(1, 1).map(|cloud| cloud.density), Some(3));
+        assert_eq!(map.gas_at(2, 1).map(|cloud| cloud.density), Some(3));
+        assert_eq!(map.gas_at(3, 1).map(|cloud| cloud.density), Some(3));
+        assert_eq!(map.gas_at(4, 1), None);
+
+        for _ in 0..10 {
+            map.spread_gas();
+        }
+        assert!(map.gas.is_empty());
+    }
+
+    #[test]
+    fn test_the_stronger_gas_takes_over() {
+        let mut map = Map::new(3, 3, 1);
+        map.set_tile(1, 1, TileType::Floor);
+        map.release_gas(1, 1, GasKind::Steam, 2);
+        map.release_gas(1, 1, GasKind::Spores, 5);
+        assert_eq!(map.gas_at(1, 1), Some(GasCloud { kind: GasKind::Spores, density: 7 }));
+    }
+}
@@ -1,15 +1,41 @@
 use serde::{Serialize, Deserialize};
 use std::cmp::{max, min};
+use std::collections::HashMap;
 
 mod dungeon_generator;
 mod cave_generator;
 mod feature_generator;
 mod entity_placement;
+mod tile_state;
+mod decal;
+mod spawn_director;
+mod branch;
+mod weather;
+mod pathing;
+mod dijkstra;
+mod fov;
+mod door;
+mod fire;
+mod gas;
+mod vault;
+mod movement;
+mod town;
 
-pub use dungeon_generator::{MapGenerator, RoomBasedDungeonGenerator};
+pub use dungeon_generator::{MapGenerator, RoomBasedDungeonGenerator, GeneratorKind};
 pub use cave_generator::CellularAutomataCaveGenerator;
-pub use feature_generator::{DungeonFeatureGenerator, SpecialRoomType, EnvironmentalHazard};
-pub use entity_placement::{EntityPlacementSystem, EnemyType, ItemType};
+pub use feature_generator::{DungeonFeatureGenerator, FurniturePlacement, CaptivePlacement, StationKind, StationPlacement, SpecialRoomType, EnvironmentalHazard};
+pub use entity_placement::{EntityPlacementSystem, EnemyType, ItemType, EntitySpawn, SpawnType, SpecialFeatureType};
+pub use tile_state::{TileEffect, TransientTileState};
+pub use decal::Decal;
+pub use dijkstra::{DijkstraMap, DijkstraMaps, DijkstraSource};
+pub use spawn_director::{SpawnDirector, SpawnTable, SpawnEntry, SpawnHistory, LevelTension, DangerLevel, encounter_budget};
+pub use branch::{DungeonBranch, Location, BranchEntrance, BranchLayout, DungeonLevels, place_branch_entrances, stairs_destination, arrival_point};
+pub use weather::{Weather, WeatherKind, WeatherOverlay, LIGHTNING_FLASH_FRAMES};
+pub use door::{DoorLock, DOOR_BASH_DIFFICULTY};
+pub use fire::{burn_turns, SMOKE_TURNS};
+pub use gas::GasCloud;
+pub use vault::Vault;
+pub use town::{TownLayout, Building, BuildingKind};
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum TileType {
@@ -38,7 +64,7 @@ impl TileType {
     
     /// Returns true if this tile blocks line of sight
     pub fn blocks_sight(&self) -> bool {
-        matches!(self, TileType::Wall | TileType::Tree | TileType::Rock)
+        matches!(self, TileType::Wall | TileType::Tree | TileType::Rock | TileType::Door(false))
     }
     
     /// Returns true if this tile is dangerous to walk on
@@ -46,6 +72,16 @@ impl TileType {
         matches!(self, TileType::Lava | TileType::Void)
     }
     
+    /// Returns true if fire can set this tile alight
+    pub fn is_flammable(&self) -> bool {
+        matches!(self, TileType::Grass | TileType::Tree | TileType::Door(_))
+    }
+    
+    /// Returns true if lightning arcs through this tile
+    pub fn conducts_electricity(&self) -> bool {
+        matches!(self, TileType::Water)
+    }
+    
     /// Returns the movement cost for this tile (1.0 = normal, higher = slower)
     pub fn movement_cost(&self) -> f32 {
         match self {
@@ -62,6 +98,51 @@ impl TileType {
         }
     }
     
+    /// Returns the display name shown when looking at this tile
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileType::Wall => "Wall",
+            TileType::Floor => "Floor",
+            TileType::DownStairs => "Stairs down",
+            TileType::UpStairs => "Stairs up",
+            TileType::Door(true) => "Open door",
+            TileType::Door(false) => "Closed door",
+            TileType::Water => "Water",
+            TileType::Lava => "Lava",
+            TileType::Trap(true) => "Trap",
+            TileType::Trap(false) => "Floor",
+            TileType::Bridge => "Bridge",
+            TileType::Grass => "Grass",
+            TileType::Tree => "Tree",
+            TileType::Rock => "Rock",
+            TileType::Sand => "Sand",
+            TileType::Ice => "Ice",
+            TileType::Void => "Void",
+        }
+    }
+    
+    /// Returns a short description shown when looking at this tile
+    pub fn description(&self) -> &'static str {
+        match self {
+            TileType::Wall => "Solid stone.",
+            TileType::Floor | TileType::Trap(false) => "Bare dungeon floor.",
+            TileType::DownStairs => "A stairway leading deeper into the dungeon.",
+            TileType::UpStairs => "A stairway leading back up.",
+            TileType::Door(true) => "An open wooden door.",
+            TileType::Door(false) => "A closed wooden door.",
+            TileType::Water => "Deep water. Swimming it takes strength and stamina.",
+            TileType::Lava => "Molten rock. Stepping in it would be a mistake.",
+            TileType::Trap(true) => "A trap you have spotted.",
+            TileType::Bridge => "A narrow bridge.",
+            TileType::Grass => "Dry grass that would catch fire easily.",
+            TileType::Tree => "A tree blocking the way and the view.",
+            TileType::Rock => "A boulder too heavy to move.",
+            TileType::Sand => "Loose sand.",
+            TileType::Ice => "Slick ice.",
+            TileType::Void => "A bottomless drop.",
+        }
+    }
+    
     /// Returns the ASCII character representation of this tile
     pub fn glyph(&self) -> char {
         match self {
@@ -103,6 +184,26 @@ pub struct Map {
     pub theme: MapTheme,
     pub generation_seed: u64,
     pub tile_content: Vec<Vec<u32>>, // Entity IDs at each tile
+    #[serde(default)]
+    pub tile_states: HashMap<usize, TransientTileState>,
+    #[serde(default)]
+    pub decals: HashMap<usize, Decal>,
+    #[serde(default)]
+    pub location: Location,
+    #[serde(default)]
+    pub branch_entrances: Vec<BranchEntrance>,
+    /// Rooms warded against wandering monsters, where resting is safe
+    #[serde(default)]
+    pub safe_rooms: Vec<Rect>,
+    /// Closed doors that won't open to a push, by tile index
+    #[serde(default)]
+    pub door_locks: HashMap<usize, DoorLock>,
+    /// Clouds of gas drifting over the level, by tile index
+    #[serde(default)]
+    pub gas: HashMap<usize, GasCloud>,
+    /// Sealed treasure rooms with a guardian inside
+    #[serde(default)]
+    pub vaults: Vec<Vault>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -114,6 +215,37 @@ pub enum MapTheme {
     Ice,
     Volcanic,
     Underwater,
+    Mines,
+    Crypt,
+    Sewer,
+    /// The streets and commons of Ravenhollow
+    Town,
+}
+
+impl MapTheme {
+    /// Open to the sky, so lit by the time of day
+    pub fn is_outdoors(&self) -> bool {
+        matches!(self, MapTheme::Forest | MapTheme::Desert | MapTheme::Ice | MapTheme::Town)
+    }
+
+    /// How the air feels on the level, and what it takes to last in it
+    pub fn climate(&self) -> Climate {
+        match self {
+            MapTheme::Ice => Climate::Cold,
+            MapTheme::Volcanic => Climate::Hot,
+            _ => Climate::Temperate,
+        }
+    }
+}
+
+/// Levels that wear the player down for being there unprepared
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Climate {
+    Temperate,
+    /// Drains stamina, then health, without warm gear, a torch or something warming inside
+    Cold,
+    /// Drains stamina, then health, without heat-proof gear or water
+    Hot,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -146,6 +278,14 @@ impl Map {
             theme,
             generation_seed: seed,
             tile_content: vec![Vec::new(); size],
+            tile_states: HashMap::new(),
+            decals: HashMap::new(),
+            location: Location::main(depth),
+            branch_entrances: Vec::new(),
+            safe_rooms: Vec::new(),
+            door_locks: HashMap::new(),
+            gas: HashMap::new(),
+            vaults: Vec::new(),
         }
     }
     
@@ -161,6 +301,13 @@ impl Map {
         (x, y)
     }
     
+    /// The side branch whose entrance is at (x, y), if any
+    pub fn branch_entrance_at(&self, x: i32, y: i32) -> Option<DungeonBranch> {
+        self.branch_entrances.iter()
+            .find(|entrance| entrance.x == x && entrance.y == y)
+            .map(|entrance| entrance.branch)
+    }
+    
     /// Check if coordinates are within map bounds
     pub fn in_bounds(&self, x: i32, y: i32) -> bool {
         x >= 0 && x < self.width && y >= 0 && y < self.height
@@ -175,13 +322,13 @@ impl Map {
         self.blocked[idx]
     }
     
-    /// Check if a tile blocks line of sight
+    /// Check if a tile blocks line of sight, smoke included
     pub fn is_opaque(&self, x: i32, y: i32) -> bool {
         if !self.in_bounds(x, y) {
             return true;
         }
         let idx = self.xy_idx(x, y);
-        self.opaque[idx]
+        self.opaque[idx] || self.has_tile_effect(x, y, TileEffect::Smoke)
     }
     
     /// Set a tile at the given coordinates
@@ -379,12 +526,108 @@ impl Map {
     /// Get the movement cost for a tile
     pub fn get_movement_cost(&self, x: i32, y: i32) -> f32 {
         if let Some(tile) = self.get_tile(x, y) {
-            tile.movement_cost()
+            let penalty = self.get_tile_state(x, y)
+                .map_or(0.0, |state| state.effect.movement_penalty());
+            tile.movement_cost() + penalty
         } else {
             f32::INFINITY
         }
     }
     
+    /// Get the transient state of a tile, if any
+    pub fn get_tile_state(&self, x: i32, y: i32) -> Option<&TransientTileState> {
+        if self.in_bounds(x, y) {
+            self.tile_states.get(&self.xy_idx(x, y))
+        } else {
+            None
+        }
+    }
+    
+    /// Check whether a tile currently has the given effect
+    pub fn has_tile_effect(&self, x: i32, y: i32, effect: TileEffect) -> bool {
+        self.get_tile_state(x, y).map_or(false, |state| state.effect == effect)
+    }
+    
+    /// Apply a transient state to a tile, replacing any existing one
+    pub fn set_tile_state(&mut self, x: i32, y: i32, state: TransientTileState) {
+        if self.in_bounds(x, y) {
+            let idx = self.xy_idx(x, y);
+            self.tile_states.insert(idx, state);
+        }
+    }
+    
+    /// Remove a tile's transient state, restoring its original tile if one was saved
+    pub fn clear_tile_state(&mut self, x: i32, y: i32) -> Option<TransientTileState> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+        let idx = self.xy_idx(x, y);
+        let state = self.tile_states.remove(&idx)?;
+        if let Some(original) = state.original_tile {
+            self.set_tile(x, y, original);
+        }
+        Some(state)
+    }
+    
+    /// Freeze a water tile into walkable ice for a number of turns
+    pub fn freeze_tile(&mut self, x: i32, y: i32, turns: i32) -> bool {
+        if self.get_tile(x, y) != Some(TileType::Water) {
+            return false;
+        }
+        self.set_tile(x, y, TileType::Ice);
+        self.set_tile_state(x, y, TransientTileState::new(TileEffect::Frozen, turns).with_original(TileType::Water));
+        true
+    }
+    
+    /// Advance all transient tile states by one turn, returning the positions and effects that expired
+    pub fn tick_tile_states(&mut self) -> Vec<(i32, i32, TileEffect)> {
+        let mut expired = Vec::new();
+        for (idx, state) in self.tile_states.iter_mut() {
+            if state.tick() {
+                expired.push(*idx);
+            }
+        }
+        
+        let mut result = Vec::new();
+        for idx in expired {
+            let (x, y) = self.idx_xy(idx);
+            if let Some(state) = self.clear_tile_state(x, y) {
+                result.push((x, y, state.effect));
+            }
+        }
+        result
+    }
+    
+    /// Whether a tile lies inside one of the level's safe rooms
+    pub fn in_safe_room(&self, x: i32, y: i32) -> bool {
+        self.safe_rooms.iter().any(|room| room.contains(x, y))
+    }
+    
+    /// Leave a decal on a tile, unless the ground won't take it or a more lasting mark is already there
+    pub fn add_decal(&mut self, x: i32, y: i32, decal: Decal) -> bool {
+        match self.get_tile(x, y) {
+            Some(tile) if Decal::sticks_to(tile) => {},
+            _ => return false,
+        }
+        let idx = self.xy_idx(x, y);
+        match self.decals.get(&idx) {
+            Some(existing) if !decal.covers(*existing) => false,
+            _ => {
+                self.decals.insert(idx, decal);
+                true
+            },
+        }
+    }
+    
+    /// Get the decal on a tile, if any
+    pub fn get_decal(&self, x: i32, y: i32) -> Option<Decal> {
+        if self.in_bounds(x, y) {
+            self.decals.get(&self.xy_idx(x, y)).copied()
+        } else {
+            None
+        }
+    }
+    
     /// Check if a tile is dangerous
     pub fn is_dangerous(&self, x: i32, y: i32) -> bool {
         if let Some(tile) = self.get_tile(x, y) {
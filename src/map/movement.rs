@@ -0,0 +1,51 @@
+use crate::components::MovementMode;
+use super::{Map, TileEffect, TileType};
+
+impl Map {
+    /// Whether a creature getting about this way can move onto the tile
+    pub fn can_enter(&self, x: i32, y: i32, mode: MovementMode) -> bool {
+        let tile = match self.get_tile(x, y) {
+            Some(tile) => tile,
+            None => return false,
+        };
+        match mode {
+            MovementMode::Walking => !self.is_blocked(x, y),
+            MovementMode::Flying | MovementMode::Levitating => !self.is_blocked(x, y) || tile == TileType::Void,
+            MovementMode::Phasing => !self.is_blocked(x, y) || matches!(tile, TileType::Wall | TileType::Rock | TileType::Tree),
+        }
+    }
+
+    /// Whether ending up on the tile would hurt a creature getting about this way
+    pub fn is_dangerous_for(&self, x: i32, y: i32, mode: MovementMode) -> bool {
+        !mode.is_airborne() && self.is_dangerous(x, y)
+    }
+
+    /// Whether the tile hides what lies beyond from a creature getting about this way; anything
+    /// off the ground sees over boulders, but not through smoke
+    pub fn blocks_sight_for(&self, x: i32, y: i32, mode: MovementMode) -> bool {
+        match self.get_tile(x, y) {
+            Some(TileType::Rock) if !self.has_tile_effect(x, y, TileEffect::Smoke) => !mode.is_airborne(),
+            Some(_) => self.blocks_sight_at(x, y),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_mode_crosses_its_own_ground() {
+        let mut map = Map::new(6, 3, 1);
+        map.set_tile(1, 1, TileType::Floor);
+        map.set_tile(2, 1, TileType::Void);
+        map.set_tile(3, 1, TileType::Lava);
+        map.set_tile(4, 1, TileType::Door(false));
+
+        assert!(!map.can_enter(2, 1, MovementMode::Walking) && map.can_enter(2, 1, MovementMode::Flying));
+        assert!(map.is_dangerous_for(3, 1, MovementMode::Walking) && !map.is_dangerous_for(3, 1, MovementMode::Levitating));
+        assert!(map.can_enter(0, 1, MovementMode::Phasing) && !map.can_enter(0, 1, MovementMode::Flying));
+        assert!(!map.can_enter(4, 1, MovementMode::Phasing));
+    }
+}
@@ -0,0 +1,160 @@
+use pathfinding::prelude::astar;
+use crate::components::MovementMode;
+use super::Map;
+
+/// Neighbouring steps, diagonals included
+const DIRECTIONS: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+impl Map {
+    /// The steps from `from` to `to` around walls, not counting the start; the goal itself may be blocked
+    pub fn find_path(&self, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        self.path_over(from, to, |x, y| !self.is_blocked(x, y))
+    }
+
+    /// As `find_path`, for walkers who can open doors: closed doors that give to a push are
+    /// no obstacle
+    pub fn find_path_through_doors(&self, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        self.path_over(from, to, |x, y| !self.is_blocked(x, y) || self.opens_to_a_push(x, y))
+    }
+
+    /// As `find_path`, for a creature getting about in its own way, and opening doors if it can.
+    /// Ground that would hurt it is kept clear of
+    pub fn find_path_for(&self, from: (i32, i32), to: (i32, i32), mode: MovementMode, opens_doors: bool) -> Option<Vec<(i32, i32)>> {
+        self.path_over(from, to, |x, y| {
+            (self.can_enter(x, y, mode) && !self.is_dangerous_for(x, y, mode)) || (opens_doors && self.opens_to_a_push(x, y))
+        })
+    }
+
+    /// As `find_path`, keeping off any tile `avoid` rules out, for those who can't look after
+    /// themselves
+    pub fn find_path_avoiding(&self, from: (i32, i32), to: (i32, i32), avoid: impl Fn(i32, i32) -> bool) -> Option<Vec<(i32, i32)>> {
+        self.path_over(from, to, |x, y| !self.is_blocked(x, y) && !avoid(x, y))
+    }
+
+    /// The steps from `from` to `to` over tiles the player has already seen, for travelling by
+    /// the known map; there is no path to a tile never seen
+    pub fn find_known_path(&self, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        if !self.is_revealed(to.0, to.1) {
+            return None;
+        }
+        self.path_over(from, to, |x, y| !self.is_blocked(x, y) && self.is_revealed(x, y))
+    }
+
+    fn path_over(&self, from: (i32, i32), to: (i32, i32), passable: impl Fn(i32, i32) -> bool) -> Option<Vec<(i32, i32)>> {
+        if from == to || !self.in_bounds(to.0, to.1) {
+            return None;
+        }
+        let (path, _) = astar(
+            &from,
+            |&(x, y)| {
+                DIRECTIONS.iter()
+                    .map(move |(dx, dy)| (x + dx, y + dy))
+                    .filter(|&(nx, ny)| (nx, ny) == to || (self.in_bounds(nx, ny) && passable(nx, ny)))
+                    .map(|step| (step, 1))
+                    .collect::<Vec<_>>()
+            },
+            |&(x, y)| (x - to.0).abs().max((y - to.1).abs()),
+            |&step| step == to,
+        )?;
+        Some(path.into_iter().skip(1).collect())
+    }
+
+    /// Whether nothing opaque stands on the straight line between two tiles
+    pub fn has_line_of_sight(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        self.has_line_of_sight_as(from, to, MovementMode::Walking)
+    }
+
+    /// As `has_line_of_sight`, for eyes carried by a creature getting about this way
+    pub fn has_line_of_sight_as(&self, from: (i32, i32), to: (i32, i32), mode: MovementMode) -> bool {
+        let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+        let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+        let (mut x, mut y) = from;
+        let mut error = dx + dy;
+        loop {
+            if (x, y) == to {
+                return true;
+            }
+            if (x, y) != from && self.blocks_sight_for(x, y, mode) {
+                return false;
+            }
+            let doubled = error * 2;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    fn room_with_wall() -> Map {
+        let mut map = Map::new(10, 10, 1);
+        for y in 1..9 {
+            for x in 1..9 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        // A wall down the middle with a gap at the bottom
+        for y in 1..8 {
+            map.set_tile(5, y, TileType::Wall);
+        }
+        map
+    }
+
+    #[test]
+    fn test_paths_go_around_walls() {
+        let map = room_with_wall();
+        let path = map.find_path((2, 2), (8, 2)).expect("there is a way round");
+        assert_eq!(path.last(), Some(&(8, 2)));
+        assert!(path.iter().any(|&(x, y)| x == 5 && y == 8));
+        assert!(path.iter().all(|&(x, y)| !map.is_blocked(x, y)));
+        assert_eq!(map.find_path((2, 2), (2, 2)), None);
+    }
+
+    #[test]
+    fn test_known_paths_keep_to_tiles_already_seen() {
+        let mut map = room_with_wall();
+        for y in 1..9 {
+            for x in 1..9 {
+                let idx = map.xy_idx(x, y);
+                map.revealed_tiles[idx] = y < 8;
+            }
+        }
+        // The only way round is along the bottom row, which hasn't been seen
+        assert_eq!(map.find_known_path((2, 2), (8, 2)), None);
+
+        for x in 1..9 {
+            let idx = map.xy_idx(x, 8);
+            map.revealed_tiles[idx] = true;
+        }
+        assert_eq!(map.find_known_path((2, 2), (8, 2)).and_then(|path| path.last().copied()), Some((8, 2)));
+    }
+
+    #[test]
+    fn test_only_door_openers_path_through_closed_doors() {
+        let mut map = room_with_wall();
+        map.set_tile(5, 8, TileType::Wall);
+        map.set_tile(5, 2, TileType::Door(false));
+        assert_eq!(map.find_path((2, 2), (8, 2)), None);
+        assert_eq!(map.find_path_through_doors((2, 2), (8, 2)).map(|path| path.len()), Some(6));
+
+        map.set_door_lock(5, 2, Some(crate::map::DoorLock::Locked));
+        assert_eq!(map.find_path_through_doors((2, 2), (8, 2)), None);
+    }
+
+    #[test]
+    fn test_walls_block_line_of_sight() {
+        let map = room_with_wall();
+        assert!(!map.has_line_of_sight((2, 2), (8, 2)));
+        assert!(map.has_line_of_sight((2, 2), (4, 7)));
+        assert!(map.has_line_of_sight((2, 8), (8, 8)));
+    }
+}
@@ -0,0 +1,699 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::map::{Map, MapTheme, TileType, Location, DungeonBranch};
+use crate::map::entity_placement::{EnemyType, EntitySpawn, SpawnType, SpecialFeatureType, theme_enemies};
+use crate::components::Difficulty;
+use crate::resources::{RandomNumberGenerator, RunPhase};
+
+/// Percent chance that a fresh level gets one monster from deeper down
+const OUT_OF_DEPTH_CHANCE: i32 = 5;
+/// How many levels deeper an out-of-depth spawn is drawn from
+const OUT_OF_DEPTH_LEVELS: i32 = 3;
+/// Spawns never appear this close to the player or the stairs
+const SAFE_RADIUS: i32 = 3;
+/// Turns away from a level before any of its monsters return
+const RESPAWN_INTERVAL: u32 = 100;
+/// Threat restored per full respawn interval spent away from a level
+const RESPAWN_THREAT_PER_INTERVAL: i32 = 3;
+/// Rolls allowed to find an enemy that fits the remaining budget
+const BUDGET_FIT_ATTEMPTS: i32 = 10;
+/// Out-of-depth chance once the artifact has been taken
+const ASCENT_OUT_OF_DEPTH_CHANCE: i32 = 25;
+/// Turns on a level before anything starts wandering in
+const TENSION_GRACE_TURNS: u32 = 150;
+/// Turns for each step up in the chance of a wanderer arriving
+const TENSION_RAMP_TURNS: u32 = 50;
+/// Chance in a thousand per turn added with each step
+const TENSION_CHANCE_PER_STEP: u32 = 2;
+/// Highest chance in a thousand per turn, however long the player stays
+const TENSION_MAX_CHANCE: u32 = 40;
+/// Wanderers come in from this close to the edge of the map when they can
+const WANDER_EDGE_BAND: i32 = 8;
+/// Pack followers start within this many tiles of their leader
+const PACK_SPREAD: i32 = 2;
+/// Percent chance that a fresh level has an altar to one of the gods
+const ALTAR_CHANCE: i32 = 30;
+/// How many levels deeper a greed vault's guardian is drawn from
+const VAULT_GUARDIAN_LEVELS: i32 = 4;
+
+/// Total threat a level at `depth` may hold
+pub fn encounter_budget(depth: i32) -> i32 {
+    4 + depth.max(1) * 3
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnEntry {
+    pub enemy: EnemyType,
+    pub weight: i32,
+}
+
+/// Weighted list of enemies that can appear at a given depth and theme
+#[derive(Clone, Debug, Default)]
+pub struct SpawnTable {
+    pub entries: Vec<SpawnEntry>,
+}
+
+impl SpawnTable {
+    pub fn new() -> Self {
+        SpawnTable { entries: Vec::new() }
+    }
+
+    /// Theme enemies native to `depth` or shallower, favoring those closest to their home depth
+    pub fn for_level(depth: i32, theme: MapTheme) -> Self {
+        let candidates = theme_enemies(theme);
+        let mut table = SpawnTable::new();
+
+        for enemy in &candidates {
+            if enemy.native_depth() <= depth {
+                let weight = i32::max(1, 10 - (depth - enemy.native_depth()) * 2);
+                table = table.add(*enemy, weight);
+            }
+        }
+
+        // Deep themes still need something to put on their first level
+        if table.is_empty() {
+            if let Some(shallowest) = candidates.iter().min_by_key(|enemy| enemy.native_depth()) {
+                table = table.add(*shallowest, 1);
+            }
+        }
+
+        table
+    }
+
+    /// As `for_level`, but outdoor levels in daylight keep their night creatures hidden
+    pub fn for_level_at(depth: i32, theme: MapTheme, daylight: bool) -> Self {
+        let table = Self::for_level(depth, theme);
+        if !daylight || !theme.is_outdoors() {
+            return table;
+        }
+        let day_table = SpawnTable {
+            entries: table.entries.iter().filter(|entry| !entry.enemy.is_nocturnal()).cloned().collect(),
+        };
+        if day_table.is_empty() { table } else { day_table }
+    }
+
+    pub fn add(mut self, enemy: EnemyType, weight: i32) -> Self {
+        if weight > 0 {
+            self.entries.push(SpawnEntry { enemy, weight });
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn total_weight(&self) -> i32 {
+        self.entries.iter().map(|entry| entry.weight).sum()
+    }
+
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> Option<EnemyType> {
+        let total = self.total_weight();
+        if total <= 0 {
+            return None;
+        }
+
+        let mut roll = rng.range(1, total);
+        for entry in &self.entries {
+            if roll <= entry.weight {
+                return Some(entry.enemy);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+}
+
+/// How restless a level has grown, as told to the player
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DangerLevel {
+    Calm,
+    Uneasy,
+    Dangerous,
+    Deadly,
+}
+
+impl DangerLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DangerLevel::Calm => "Calm",
+            DangerLevel::Uneasy => "Uneasy",
+            DangerLevel::Dangerous => "Dangerous",
+            DangerLevel::Deadly => "Deadly",
+        }
+    }
+
+    /// Logged as the level grows into this danger
+    pub fn warning(&self) -> Option<&'static str> {
+        match self {
+            DangerLevel::Calm => None,
+            DangerLevel::Uneasy => Some("You feel you have lingered here too long."),
+            DangerLevel::Dangerous => Some("Footsteps echo from the far corners of the level."),
+            DangerLevel::Deadly => Some("The whole level is stirring. Move on while you can!"),
+        }
+    }
+}
+
+/// How long the player has lingered on the current level, which draws wandering monsters in
+///
+/// Nothing wanders in for a grace period; after that the chance of a wanderer each turn climbs
+/// steadily, so camping a level to grind is met with ever more company.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LevelTension {
+    pub location: Option<Location>,
+    pub turns_on_level: u32,
+    /// Wanderers that have arrived on this level
+    pub wanderers: u32,
+}
+
+impl LevelTension {
+    /// Start counting afresh when the player reaches a different level; returns whether it changed
+    pub fn enter(&mut self, location: Location) -> bool {
+        if self.location == Some(location) {
+            return false;
+        }
+        *self = LevelTension { location: Some(location), ..Default::default() };
+        true
+    }
+
+    pub fn tick(&mut self, turns: u32) {
+        self.turns_on_level = self.turns_on_level.saturating_add(turns);
+    }
+
+    /// Chance in a thousand that a wanderer arrives this turn
+    pub fn wander_chance(&self) -> u32 {
+        if self.turns_on_level < TENSION_GRACE_TURNS {
+            return 0;
+        }
+        let lingering = self.turns_on_level - TENSION_GRACE_TURNS;
+        ((lingering / TENSION_RAMP_TURNS + 1) * TENSION_CHANCE_PER_STEP).min(TENSION_MAX_CHANCE)
+    }
+
+    pub fn danger(&self) -> DangerLevel {
+        match self.wander_chance() {
+            0 => DangerLevel::Calm,
+            chance if chance < TENSION_MAX_CHANCE / 2 => DangerLevel::Uneasy,
+            chance if chance < TENSION_MAX_CHANCE => DangerLevel::Dangerous,
+            _ => DangerLevel::Deadly,
+        }
+    }
+
+    pub fn roll_wanderer(&self, rng: &mut RandomNumberGenerator) -> bool {
+        let chance = self.wander_chance();
+        chance > 0 && rng.range(1, 1000) as u32 <= chance
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct LevelVisit {
+    left_turn: u32,
+    remaining_threat: i32,
+    full_budget: i32,
+}
+
+/// Remembers how each visited level was left so it can be re-populated on return
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SpawnHistory {
+    visits: HashMap<DungeonBranch, HashMap<i32, LevelVisit>>,
+}
+
+impl SpawnHistory {
+    pub fn new() -> Self {
+        SpawnHistory { visits: HashMap::new() }
+    }
+
+    fn visit(&self, location: Location) -> Option<&LevelVisit> {
+        self.visits.get(&location.branch).and_then(|levels| levels.get(&location.depth))
+    }
+
+    /// Call when the player leaves a level, with the threat of the monsters still alive on it
+    pub fn record_departure(&mut self, map: &Map, turn: u32, remaining_threat: i32) {
+        self.visits.entry(map.location.branch).or_default().insert(map.location.depth, LevelVisit {
+            left_turn: turn,
+            remaining_threat,
+            full_budget: encounter_budget(map.depth),
+        });
+    }
+
+    pub fn has_visited(&self, location: Location) -> bool {
+        self.visit(location).is_some()
+    }
+
+    /// Threat that has crept back into a level since the player left it
+    pub fn respawn_budget(&self, location: Location, turn: u32) -> i32 {
+        let visit = match self.visit(location) {
+            Some(visit) => visit,
+            None => return 0,
+        };
+
+        let intervals = turn.saturating_sub(visit.left_turn) / RESPAWN_INTERVAL;
+        i32::min(intervals as i32 * RESPAWN_THREAT_PER_INTERVAL, self.missing_threat(location))
+    }
+
+    /// Threat killed off a level since it was first populated
+    pub fn missing_threat(&self, location: Location) -> i32 {
+        self.visit(location).map_or(0, |visit| (visit.full_budget - visit.remaining_threat).max(0))
+    }
+
+    pub fn clear(&mut self) {
+        self.visits.clear();
+    }
+}
+
+/// Decides which monsters a level starts with, and which return when it is revisited
+pub struct SpawnDirector;
+
+impl SpawnDirector {
+    /// Fill a freshly generated level up to its encounter budget
+    pub fn plan_level(
+        map: &Map,
+        phase: RunPhase,
+        difficulty: Difficulty,
+        rng: &mut RandomNumberGenerator,
+        avoid: &[(i32, i32)],
+    ) -> Vec<EntitySpawn> {
+        Self::plan_level_at(map, phase, difficulty, false, rng, avoid)
+    }
+
+    /// As `plan_level`, leaving out night creatures when an outdoor level is entered by day
+    pub fn plan_level_at(
+        map: &Map,
+        phase: RunPhase,
+        difficulty: Difficulty,
+        daylight: bool,
+        rng: &mut RandomNumberGenerator,
+        avoid: &[(i32, i32)],
+    ) -> Vec<EntitySpawn> {
+        let mut open_tiles = Self::open_tiles(map, avoid);
+        let table = SpawnTable::for_level_at(map.depth, map.theme, daylight);
+        let budget = Self::level_budget(encounter_budget(map.depth), phase, difficulty);
+        let mut spawns = Self::spend_budget(&table, budget, &mut open_tiles, rng);
+
+        // Branches end in a guardian, placed as far from the way in as possible
+        if map.location.is_branch_bottom() || map.location.is_final_level() {
+            let entrance = map.entrance;
+            let lair = open_tiles.iter().enumerate()
+                .max_by_key(|(_, (x, y))| (x - entrance.0).abs() + (y - entrance.1).abs())
+                .map(|(idx, _)| idx);
+            if let Some(idx) = lair {
+                let (x, y) = open_tiles.swap_remove(idx);
+                spawns.push(EntitySpawn { entity_type: SpawnType::Boss(map.location.branch), x, y, leader: None });
+            }
+        }
+
+        // The occasional monster that wandered up from below, on top of the budget
+        let out_of_depth_chance = match phase {
+            RunPhase::Ascent => ASCENT_OUT_OF_DEPTH_CHANCE,
+            _ => OUT_OF_DEPTH_CHANCE,
+        };
+        if rng.roll_dice(1, 100) <= out_of_depth_chance {
+            let deeper = SpawnTable::for_level(map.depth + OUT_OF_DEPTH_LEVELS, map.theme);
+            let mut rare = SpawnTable::new();
+            for entry in &deeper.entries {
+                if entry.enemy.native_depth() > map.depth {
+                    rare = rare.add(entry.enemy, entry.weight);
+                }
+            }
+            if let (Some(enemy), Some((x, y))) = (rare.roll(rng), Self::take_tile(&mut open_tiles, rng)) {
+                spawns.push(EntitySpawn { entity_type: SpawnType::Enemy(enemy), x, y, leader: None });
+            }
+        }
+
+        // Greed vaults hold a guardian from far deeper down, kept apart from the budget like their hoard
+        for vault in &map.vaults {
+            if let Some(enemy) = Self::vault_guardian(map.depth, map.theme, rng) {
+                let (x, y) = vault.guard_post();
+                spawns.push(EntitySpawn { entity_type: SpawnType::Guardian(enemy), x, y, leader: None });
+            }
+        }
+
+        if rng.roll_dice(1, 100) <= ALTAR_CHANCE {
+            if let Some((x, y)) = Self::take_tile(&mut open_tiles, rng) {
+                spawns.push(EntitySpawn { entity_type: SpawnType::Special(SpecialFeatureType::Altar), x, y, leader: None });
+            }
+        }
+
+        spawns
+    }
+
+    /// Monsters that have returned to a previously visited level
+    pub fn repopulate(
+        map: &Map,
+        history: &SpawnHistory,
+        turn: u32,
+        phase: RunPhase,
+        difficulty: Difficulty,
+        rng: &mut RandomNumberGenerator,
+        avoid: &[(i32, i32)],
+    ) -> Vec<EntitySpawn> {
+        let budget = match phase {
+            // The dungeon fights back: cleared levels restock at once for the climb out
+            RunPhase::Ascent => Self::level_budget(history.missing_threat(map.location), phase, difficulty),
+            _ => difficulty.scale_budget(history.respawn_budget(map.location, turn)),
+        };
+        if budget <= 0 {
+            return Vec::new();
+        }
+
+        let mut open_tiles = Self::open_tiles(map, avoid);
+        let table = SpawnTable::for_level(map.depth, map.theme);
+        Self::spend_budget(&table, budget, &mut open_tiles, rng)
+    }
+
+    /// A monster wandering in from the edges of the map, somewhere out of the player's sight
+    pub fn wandering_spawn(
+        map: &Map,
+        daylight: bool,
+        rng: &mut RandomNumberGenerator,
+        avoid: &[(i32, i32)],
+    ) -> Option<EntitySpawn> {
+        let hidden: Vec<(i32, i32)> = Self::open_tiles(map, avoid).into_iter()
+            .filter(|(x, y)| !map.visible_tiles[map.xy_idx(*x, *y)])
+            .collect();
+        let edge: Vec<(i32, i32)> = hidden.iter()
+            .filter(|(x, y)| i32::min(i32::min(*x, map.width - 1 - x), i32::min(*y, map.height - 1 - y)) < WANDER_EDGE_BAND)
+            .cloned()
+            .collect();
+        let mut open_tiles = if edge.is_empty() { hidden } else { edge };
+
+        let enemy = SpawnTable::for_level_at(map.depth, map.theme, daylight).roll(rng)?;
+        let (x, y) = Self::take_tile(&mut open_tiles, rng)?;
+        Some(EntitySpawn { entity_type: SpawnType::Enemy(enemy), x, y, leader: None })
+    }
+
+    /// A guardian for a greed vault: something native well below `depth`, or failing that
+    /// anything from that far down
+    pub fn vault_guardian(depth: i32, theme: MapTheme, rng: &mut RandomNumberGenerator) -> Option<EnemyType> {
+        let deeper = SpawnTable::for_level(depth + VAULT_GUARDIAN_LEVELS, theme);
+        let mut rare = SpawnTable::new();
+        for entry in &deeper.entries {
+            if entry.enemy.native_depth() > depth {
+                rare = rare.add(entry.enemy, entry.weight);
+            }
+        }
+        if rare.is_empty() {
+            deeper.roll(rng)
+        } else {
+            rare.roll(rng)
+        }
+    }
+
+    fn level_budget(budget: i32, phase: RunPhase, difficulty: Difficulty) -> i32 {
+        let budget = difficulty.scale_budget(budget);
+        match phase {
+            RunPhase::Ascent => budget * 3 / 2,
+            _ => budget,
+        }
+    }
+
+    fn spend_budget(
+        table: &SpawnTable,
+        budget: i32,
+        open_tiles: &mut Vec<(i32, i32)>,
+        rng: &mut RandomNumberGenerator,
+    ) -> Vec<EntitySpawn> {
+        let mut spawns = Vec::new();
+        let mut remaining = budget;
+
+        'spending: while remaining > 0 && !open_tiles.is_empty() {
+            for _ in 0..BUDGET_FIT_ATTEMPTS {
+                let enemy = match table.roll(rng) {
+                    Some(enemy) => enemy,
+                    None => break 'spending,
+                };
+                if enemy.threat() > remaining {
+                    continue;
+                }
+                if let Some((x, y)) = Self::take_tile(open_tiles, rng) {
+                    spawns.push(EntitySpawn { entity_type: SpawnType::Enemy(enemy), x, y, leader: None });
+                    remaining -= enemy.threat();
+                    remaining -= Self::gather_pack(enemy, spawns.len() - 1, remaining, &mut spawns, open_tiles, rng);
+                    continue 'spending;
+                }
+            }
+            break;
+        }
+
+        spawns
+    }
+
+    /// Followers for a pack leader, placed close around it; returns the threat they cost
+    fn gather_pack(
+        enemy: EnemyType,
+        leader: usize,
+        budget: i32,
+        spawns: &mut Vec<EntitySpawn>,
+        open_tiles: &mut Vec<(i32, i32)>,
+        rng: &mut RandomNumberGenerator,
+    ) -> i32 {
+        let max_followers = match enemy.pack() {
+            Some((_, max_followers)) => max_followers,
+            None => return 0,
+        };
+        let (lx, ly) = (spawns[leader].x, spawns[leader].y);
+        let mut spent = 0;
+        for _ in 0..rng.range(0, max_followers) {
+            if spent + enemy.threat() > budget {
+                break;
+            }
+            let near = open_tiles.iter()
+                .position(|(x, y)| (x - lx).abs() <= PACK_SPREAD && (y - ly).abs() <= PACK_SPREAD);
+            match near {
+                Some(idx) => {
+                    let (x, y) = open_tiles.swap_remove(idx);
+                    spawns.push(EntitySpawn { entity_type: SpawnType::Enemy(enemy), x, y, leader: Some(leader) });
+                    spent += enemy.threat();
+                },
+                None => break,
+            }
+        }
+        spent
+    }
+
+    fn open_tiles(map: &Map, avoid: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        let near = |x: i32, y: i32, (ax, ay): (i32, i32)| (x - ax).abs() <= SAFE_RADIUS && (y - ay).abs() <= SAFE_RADIUS;
+
+        let mut tiles = Vec::new();
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
+                let idx = map.xy_idx(x, y);
+                if map.tiles[idx] != TileType::Floor || map.blocked[idx] || map.in_vault(x, y) {
+                    continue;
+                }
+                if near(x, y, map.entrance) || near(x, y, map.exit) || avoid.iter().any(|pos| near(x, y, *pos)) {
+                    continue;
+                }
+                tiles.push((x, y));
+            }
+        }
+        tiles
+    }
+
+    fn take_tile(open_tiles: &mut Vec<(i32, i32)>, rng: &mut RandomNumberGenerator) -> Option<(i32, i32)> {
+        if open_tiles.is_empty() {
+            return None;
+        }
+        let idx = rng.range(0, open_tiles.len() as i32 - 1) as usize;
+        Some(open_tiles.swap_remove(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_room(depth: i32) -> Map {
+        let mut map = Map::new(40, 30, depth);
+        for y in 5..25 {
+            for x in 5..35 {
+                let idx = map.xy_idx(x, y);
+                map.tiles[idx] = TileType::Floor;
+                map.blocked[idx] = false;
+            }
+        }
+        map.entrance = (6, 6);
+        map.exit = (33, 23);
+        map
+    }
+
+    fn total_threat(spawns: &[EntitySpawn]) -> i32 {
+        spawns.iter().map(|spawn| match spawn.entity_type {
+            SpawnType::Enemy(enemy) => enemy.threat(),
+            _ => 0,
+        }).sum()
+    }
+
+    #[test]
+    fn test_tables_exclude_deeper_enemies() {
+        let table = SpawnTable::for_level(1, MapTheme::Dungeon);
+        assert!(!table.is_empty());
+        assert!(table.entries.iter().all(|entry| entry.enemy.native_depth() <= 1));
+
+        // A theme with nothing native to depth 1 still has a fallback
+        assert!(!SpawnTable::for_level(1, MapTheme::Volcanic).is_empty());
+    }
+
+    #[test]
+    fn test_plan_stays_within_budget_and_clear_of_player() {
+        let map = open_room(2);
+        let player = (20, 15);
+        let mut rng = RandomNumberGenerator::new(7);
+
+        for _ in 0..20 {
+            let spawns = SpawnDirector::plan_level(&map, RunPhase::Descent, Difficulty::Normal, &mut rng, &[player]);
+            let out_of_depth_allowance = EnemyType::Dragon.threat();
+            assert!(total_threat(&spawns) <= encounter_budget(2) + out_of_depth_allowance);
+            for spawn in &spawns {
+                assert!((spawn.x - player.0).abs() > SAFE_RADIUS || (spawn.y - player.1).abs() > SAFE_RADIUS);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pack_followers_start_beside_their_leader() {
+        let map = open_room(1);
+        let mut rng = RandomNumberGenerator::new(11);
+        let table = SpawnTable::new().add(EnemyType::Rat, 1);
+        let mut open_tiles = SpawnDirector::open_tiles(&map, &[]);
+
+        let spawns = SpawnDirector::spend_budget(&table, 40, &mut open_tiles, &mut rng);
+        assert!(total_threat(&spawns) <= 40);
+        assert!(spawns.iter().any(|spawn| spawn.leader.is_some()));
+        for spawn in spawns.iter().filter(|spawn| spawn.leader.is_some()) {
+            let leader = &spawns[spawn.leader.unwrap()];
+            assert_eq!(leader.leader, None);
+            assert!((spawn.x - leader.x).abs() <= PACK_SPREAD && (spawn.y - leader.y).abs() <= PACK_SPREAD);
+        }
+    }
+
+    #[test]
+    fn test_respawn_budget_grows_with_time_away() {
+        let map = open_room(3);
+        let mut history = SpawnHistory::new();
+        assert_eq!(history.respawn_budget(map.location, 500), 0);
+
+        history.record_departure(&map, 100, 0);
+        assert_eq!(history.respawn_budget(map.location, 150), 0);
+        assert_eq!(history.respawn_budget(map.location, 200), RESPAWN_THREAT_PER_INTERVAL);
+        assert_eq!(history.respawn_budget(map.location, 100_000), encounter_budget(3));
+
+        // Other branches at the same depth are tracked separately
+        assert_eq!(history.respawn_budget(Location::new(DungeonBranch::Sewer, 3), 100_000), 0);
+    }
+
+    #[test]
+    fn test_ascent_restocks_cleared_levels_immediately() {
+        let map = open_room(4);
+        let mut history = SpawnHistory::new();
+        history.record_departure(&map, 100, 0);
+        let mut rng = RandomNumberGenerator::new(5);
+
+        assert!(SpawnDirector::repopulate(&map, &history, 101, RunPhase::Descent, Difficulty::Normal, &mut rng, &[]).is_empty());
+        assert!(!SpawnDirector::repopulate(&map, &history, 101, RunPhase::Ascent, Difficulty::Normal, &mut rng, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_branch_bottom_gets_a_boss() {
+        let mut map = open_room(6);
+        map.location = Location::new(DungeonBranch::Mines, DungeonBranch::Mines.levels());
+        let mut rng = RandomNumberGenerator::new(3);
+
+        let spawns = SpawnDirector::plan_level(&map, RunPhase::Descent, Difficulty::Normal, &mut rng, &[]);
+        assert!(spawns.iter().any(|spawn| matches!(spawn.entity_type, SpawnType::Boss(DungeonBranch::Mines))));
+    }
+
+    #[test]
+    fn test_vault_guardians_come_from_deeper_and_outside_the_budget() {
+        let mut map = open_room(2);
+        let vault = crate::map::Vault { room: crate::map::Rect::new(10, 10, 3, 3), door: (11, 9), keyed: false };
+        map.add_vault(vault);
+        let mut rng = RandomNumberGenerator::new(4);
+
+        for _ in 0..20 {
+            let spawns = SpawnDirector::plan_level(&map, RunPhase::Descent, Difficulty::Normal, &mut rng, &[]);
+            let guardians: Vec<&EntitySpawn> = spawns.iter()
+                .filter(|spawn| matches!(spawn.entity_type, SpawnType::Guardian(_)))
+                .collect();
+            assert_eq!(guardians.len(), 1);
+            assert_eq!((guardians[0].x, guardians[0].y), vault.guard_post());
+            if let SpawnType::Guardian(enemy) = guardians[0].entity_type {
+                assert!(enemy.native_depth() > 2);
+            }
+            // Nothing else is planned inside, and the guardian costs the level nothing
+            assert!(spawns.iter()
+                .filter(|spawn| !matches!(spawn.entity_type, SpawnType::Guardian(_)))
+                .all(|spawn| !map.in_vault(spawn.x, spawn.y)));
+            assert!(total_threat(&spawns) <= encounter_budget(2) + EnemyType::Dragon.threat());
+        }
+    }
+
+    #[test]
+    fn test_harder_difficulty_spawns_more_threat() {
+        let map = open_room(5);
+        let spawn_threat = |difficulty: Difficulty| -> i32 {
+            let mut rng = RandomNumberGenerator::new(9);
+            (0..20)
+                .map(|_| total_threat(&SpawnDirector::plan_level(&map, RunPhase::Descent, difficulty, &mut rng, &[])))
+                .sum()
+        };
+
+        assert!(spawn_threat(Difficulty::Easy) < spawn_threat(Difficulty::Normal));
+        assert!(spawn_threat(Difficulty::Normal) < spawn_threat(Difficulty::Nightmare));
+    }
+
+    #[test]
+    fn test_tension_climbs_the_longer_the_player_lingers() {
+        let mut tension = LevelTension::default();
+        assert!(tension.enter(Location::main(2)));
+        tension.tick(TENSION_GRACE_TURNS - 1);
+        assert_eq!(tension.wander_chance(), 0);
+        assert_eq!(tension.danger(), DangerLevel::Calm);
+
+        tension.tick(1);
+        let early = tension.wander_chance();
+        tension.tick(TENSION_RAMP_TURNS * 5);
+        assert!(tension.wander_chance() > early);
+        tension.tick(100_000);
+        assert_eq!(tension.wander_chance(), TENSION_MAX_CHANCE);
+        assert_eq!(tension.danger(), DangerLevel::Deadly);
+
+        // Staying put keeps the count; a new level starts it over
+        assert!(!tension.enter(Location::main(2)));
+        assert!(tension.enter(Location::main(3)));
+        assert_eq!(tension.turns_on_level, 0);
+    }
+
+    #[test]
+    fn test_wanderers_arrive_out_of_sight_near_the_edge() {
+        let mut map = open_room(2);
+        let player = (20, 15);
+        for y in 10..20 {
+            for x in 15..25 {
+                let idx = map.xy_idx(x, y);
+                map.visible_tiles[idx] = true;
+            }
+        }
+        let mut rng = RandomNumberGenerator::new(11);
+
+        for _ in 0..20 {
+            let spawn = SpawnDirector::wandering_spawn(&map, false, &mut rng, &[player]).expect("room has space");
+            assert!(!map.visible_tiles[map.xy_idx(spawn.x, spawn.y)]);
+            let from_edge = spawn.x.min(map.width - 1 - spawn.x).min(spawn.y).min(map.height - 1 - spawn.y);
+            assert!(from_edge < WANDER_EDGE_BAND);
+        }
+    }
+
+    #[test]
+    fn test_night_creatures_stay_hidden_outdoors_by_day() {
+        let day = SpawnTable::for_level_at(6, MapTheme::Desert, true);
+        assert!(!day.is_empty());
+        assert!(day.entries.iter().all(|entry| !entry.enemy.is_nocturnal()));
+
+        let night = SpawnTable::for_level_at(6, MapTheme::Desert, false);
+        assert!(night.entries.iter().any(|entry| entry.enemy == EnemyType::Skeleton));
+
+        // Underground it is always dark enough
+        let crypt = SpawnTable::for_level_at(6, MapTheme::Crypt, true);
+        assert!(crypt.entries.iter().any(|entry| entry.enemy.is_nocturnal()));
+    }
+}
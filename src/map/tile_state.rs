@@ -0,0 +1,137 @@
+use serde::{Serialize, Deserialize};
+use super::TileType;
+
+/// Temporary conditions layered on top of a tile's base type
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum TileEffect {
+    Burning,
+    Frozen,
+    Webbed,
+    Electrified,
+    /// Smoke off a fire, hiding whatever lies beyond
+    Smoke,
+}
+
+impl TileEffect {
+    /// Glyph drawn in place of the tile while the effect is active
+    pub fn glyph(&self) -> Option<char> {
+        match self {
+            TileEffect::Burning => Some('^'),
+            TileEffect::Frozen => None, // Frozen tiles already render as ice
+            TileEffect::Webbed => Some('#'),
+            TileEffect::Electrified => Some('%'),
+            TileEffect::Smoke => Some('░'),
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            TileEffect::Burning => crossterm::style::Color::Red,
+            TileEffect::Frozen => crossterm::style::Color::Cyan,
+            TileEffect::Webbed => crossterm::style::Color::White,
+            TileEffect::Electrified => crossterm::style::Color::Yellow,
+            TileEffect::Smoke => crossterm::style::Color::DarkGrey,
+        }
+    }
+
+    /// Appended to the tile description in look mode
+    pub fn description(&self) -> &'static str {
+        match self {
+            TileEffect::Burning => "It is on fire.",
+            TileEffect::Frozen => "It is frozen over.",
+            TileEffect::Webbed => "Sticky webs are strung across it.",
+            TileEffect::Electrified => "It crackles with lightning.",
+            TileEffect::Smoke => "Thick smoke hides what lies beyond.",
+        }
+    }
+
+    /// Extra movement cost imposed by the effect
+    pub fn movement_penalty(&self) -> f32 {
+        match self {
+            TileEffect::Webbed => 2.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// A transient effect on a single tile
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransientTileState {
+    pub effect: TileEffect,
+    /// Turns until the effect ends; `None` lasts until something removes it
+    pub turns_remaining: Option<i32>,
+    /// Tile restored when the effect expires (e.g. water under ice)
+    pub original_tile: Option<TileType>,
+}
+
+impl TransientTileState {
+    pub fn new(effect: TileEffect, turns: i32) -> Self {
+        TransientTileState {
+            effect,
+            turns_remaining: Some(turns),
+            original_tile: None,
+        }
+    }
+
+    pub fn permanent(effect: TileEffect) -> Self {
+        TransientTileState {
+            effect,
+            turns_remaining: None,
+            original_tile: None,
+        }
+    }
+
+    pub fn with_original(mut self, tile: TileType) -> Self {
+        self.original_tile = Some(tile);
+        self
+    }
+
+    /// Advance one turn; returns true once the effect has expired
+    pub fn tick(&mut self) -> bool {
+        match &mut self.turns_remaining {
+            Some(turns) => {
+                *turns -= 1;
+                *turns <= 0
+            },
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+
+    #[test]
+    fn test_frozen_water_thaws_back() {
+        let mut map = Map::new(10, 10, 1);
+        map.set_tile(3, 3, TileType::Water);
+
+        assert!(map.freeze_tile(3, 3, 2));
+        assert_eq!(map.get_tile(3, 3), Some(TileType::Ice));
+        assert!(map.has_tile_effect(3, 3, TileEffect::Frozen));
+
+        assert!(map.tick_tile_states().is_empty());
+        let expired = map.tick_tile_states();
+        assert_eq!(expired, vec![(3, 3, TileEffect::Frozen)]);
+        assert_eq!(map.get_tile(3, 3), Some(TileType::Water));
+        assert!(map.get_tile_state(3, 3).is_none());
+    }
+
+    #[test]
+    fn test_only_water_freezes() {
+        let mut map = Map::new(10, 10, 1);
+        map.set_tile(3, 3, TileType::Floor);
+        assert!(!map.freeze_tile(3, 3, 5));
+        assert!(map.get_tile_state(3, 3).is_none());
+    }
+
+    #[test]
+    fn test_permanent_state_never_expires() {
+        let mut state = TransientTileState::permanent(TileEffect::Webbed);
+        for _ in 0..100 {
+            assert!(!state.tick());
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use super::{Map, MapTheme, Rect, TileType, Location};
+use crate::resources::{RandomNumberGenerator, MAPGEN_STREAM};
+
+/// Trees planted about the commons, each only where it leaves the way clear
+const TOWN_TREES: i32 = 60;
+
+/// What a building in town is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildingKind {
+    /// The general store, its wares laid out for the taking and paying for
+    Shop,
+    /// Where gold is kept safe from the dungeon, and from dying in it
+    Bank,
+    /// Where the gods have altars, and the priest takes offerings
+    Temple,
+    /// Where the town watch is quartered, and where those it arrests serve their time
+    Guardhouse,
+    /// Where the Adventurers' Guild keeps its trainers
+    GuildHall,
+    /// Where the blacksmith mends gear worn down in the dungeon
+    Smithy,
+}
+
+impl BuildingKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuildingKind::Shop => "General store",
+            BuildingKind::Bank => "Bank",
+            BuildingKind::Temple => "Temple",
+            BuildingKind::Guardhouse => "Guardhouse",
+            BuildingKind::GuildHall => "Guild hall",
+            BuildingKind::Smithy => "Smithy",
+        }
+    }
+}
+
+/// A walled building with a single door on its south side
+#[derive(Debug, Clone, Copy)]
+pub struct Building {
+    pub kind: BuildingKind,
+    /// The floor inside its walls
+    pub room: Rect,
+    pub door: (i32, i32),
+}
+
+impl Building {
+    /// The tile just outside the door
+    pub fn doorstep(&self) -> (i32, i32) {
+        (self.door.0, self.door.1 + 1)
+    }
+
+    /// Floor inside, one tile clear of the walls
+    pub fn inner_tiles(&self) -> Vec<(i32, i32)> {
+        let mut tiles = Vec::new();
+        for y in self.room.y1 + 1..self.room.y2 - 1 {
+            for x in self.room.x1 + 1..self.room.x2 - 1 {
+                tiles.push((x, y));
+            }
+        }
+        tiles
+    }
+}
+
+/// Ravenhollow, the town above the dungeon: a walled common of grass around a paved square with
+/// the stairs down in the middle, and the buildings the townsfolk keep. The layout never
+/// changes, so anything that needs to know where a building stands asks for it again rather
+/// than having it saved
+#[derive(Debug, Clone)]
+pub struct TownLayout {
+    pub buildings: Vec<Building>,
+    pub square: Rect,
+    pub stairs: (i32, i32),
+}
+
+impl TownLayout {
+    pub fn ravenhollow() -> Self {
+        TownLayout {
+            buildings: vec![
+                Building { kind: BuildingKind::Shop, room: Rect::new(10, 8, 13, 7), door: (16, 15) },
+                Building { kind: BuildingKind::Bank, room: Rect::new(26, 8, 11, 7), door: (31, 15) },
+                Building { kind: BuildingKind::Temple, room: Rect::new(43, 8, 11, 7), door: (48, 15) },
+                Building { kind: BuildingKind::Guardhouse, room: Rect::new(57, 8, 13, 7), door: (63, 15) },
+                Building { kind: BuildingKind::GuildHall, room: Rect::new(10, 22, 13, 7), door: (16, 29) },
+                Building { kind: BuildingKind::Smithy, room: Rect::new(57, 22, 13, 7), door: (63, 29) },
+            ],
+            square: Rect::new(30, 19, 21, 13),
+            stairs: (40, 25),
+        }
+    }
+
+    pub fn building(&self, kind: BuildingKind) -> Option<&Building> {
+        self.buildings.iter().find(|building| building.kind == kind)
+    }
+
+    /// The building whose floor (x, y) is on, if any
+    pub fn building_at(&self, x: i32, y: i32) -> Option<&Building> {
+        self.buildings.iter().find(|building| building.room.contains(x, y))
+    }
+
+    /// Lay out the town on a map of the given size, scattering its trees from the map
+    /// generation stream of `seed`
+    pub fn generate(&self, width: i32, height: i32, seed: u64) -> Map {
+        let mut rng = RandomNumberGenerator::new(seed).fork(MAPGEN_STREAM);
+        let mut map = Map::new_with_theme(width, height, 0, MapTheme::Town, seed);
+        map.location = Location::town();
+        map.fill_rect(&Rect::new(1, 1, width - 2, height - 2), TileType::Grass);
+        map.fill_rect(&self.square, TileType::Floor);
+        map.rooms.push(self.square);
+
+        let (cx, cy) = self.square.center();
+        for building in &self.buildings {
+            let room = building.room;
+            map.fill_rect(&Rect::from_corners(room.x1 - 1, room.y1 - 1, room.x2 + 1, room.y2 + 1), TileType::Wall);
+            map.fill_rect(&room, TileType::Floor);
+            map.set_tile(building.door.0, building.door.1, TileType::Door(true));
+            let (sx, sy) = building.doorstep();
+            let corner = (if sx < cx { self.square.x1 } else { self.square.x2 - 1 }, self.square.y1);
+            map.create_l_corridor((sx, sy), corner);
+            map.rooms.push(room);
+        }
+
+        map.set_tile(self.stairs.0, self.stairs.1, TileType::DownStairs);
+        map.entrance = self.stairs;
+        map.exit = self.stairs;
+
+        for _ in 0..TOWN_TREES {
+            let (x, y) = (rng.range(2, width - 3), rng.range(2, height - 3));
+            let clear = map.get_neighbors(x, y).iter()
+                .all(|&(nx, ny)| map.get_tile(nx, ny) == Some(TileType::Grass));
+            if clear && map.get_tile(x, y) == Some(TileType::Grass) {
+                map.set_tile(x, y, TileType::Tree);
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_door_in_town_leads_to_the_stairs() {
+        let layout = TownLayout::ravenhollow();
+        let map = layout.generate(80, 50, 7);
+        assert!(map.location.is_town());
+        assert_eq!(map.get_tile(40, 25), Some(TileType::DownStairs));
+        for building in &layout.buildings {
+            let inside = building.room.center();
+            assert!(map.find_path(inside, layout.stairs).is_some(), "{} is cut off", building.kind.name());
+            assert_eq!(layout.building_at(inside.0, inside.1).map(|found| found.kind), Some(building.kind));
+        }
+        assert!(layout.building_at(layout.stairs.0, layout.stairs.1).is_none());
+    }
+}
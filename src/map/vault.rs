@@ -0,0 +1,78 @@
+use serde::{Serialize, Deserialize};
+use super::{Map, Rect};
+
+/// A greed vault: a sealed room of high-tier loot and an out-of-depth guardian, drawn on the map
+/// from the moment the level is entered. Its door stays locked until the lever beside it is
+/// pulled or a key is turned in it
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Vault {
+    /// The floor inside its walls
+    pub room: Rect,
+    pub door: (i32, i32),
+    /// No lever could be set beside it, so a key to it lies somewhere on the level
+    pub keyed: bool,
+}
+
+impl Vault {
+    /// Where the guardian stands watch, in the middle of the hoard
+    pub fn guard_post(&self) -> (i32, i32) {
+        self.room.center()
+    }
+
+    /// Floor tiles for the hoard, leaving the guard post clear
+    pub fn treasure_tiles(&self) -> Vec<(i32, i32)> {
+        let post = self.guard_post();
+        let mut tiles = Vec::new();
+        for y in self.room.y1..self.room.y2 {
+            for x in self.room.x1..self.room.x2 {
+                if (x, y) != post {
+                    tiles.push((x, y));
+                }
+            }
+        }
+        tiles
+    }
+}
+
+impl Map {
+    /// Whether a tile lies inside one of the level's greed vaults
+    pub fn in_vault(&self, x: i32, y: i32) -> bool {
+        self.vaults.iter().any(|vault| vault.room.contains(x, y))
+    }
+
+    /// Whether the tile is the door sealing a greed vault
+    pub fn is_vault_door(&self, x: i32, y: i32) -> bool {
+        self.vaults.iter().any(|vault| vault.door == (x, y))
+    }
+
+    /// Record a vault and mark it, walls and all, as seen so it shows on the map straight away
+    pub fn add_vault(&mut self, vault: Vault) {
+        for y in vault.room.y1 - 1..=vault.room.y2 {
+            for x in vault.room.x1 - 1..=vault.room.x2 {
+                self.reveal_tile(x, y);
+            }
+        }
+        self.vaults.push(vault);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    #[test]
+    fn test_a_vault_is_revealed_whole_and_keeps_its_post_clear() {
+        let mut map = Map::new(10, 10, 3);
+        let vault = Vault { room: Rect::new(3, 3, 3, 3), door: (4, 2), keyed: false };
+        map.set_tile(4, 2, TileType::Door(false));
+        map.add_vault(vault);
+
+        assert!(map.is_revealed(2, 2) && map.is_revealed(6, 6) && !map.is_revealed(7, 7));
+        assert!(map.in_vault(5, 5) && !map.in_vault(6, 5));
+        assert!(map.is_vault_door(4, 2));
+        assert_eq!(vault.guard_post(), (4, 4));
+        assert_eq!(vault.treasure_tiles().len(), 8);
+        assert!(!vault.treasure_tiles().contains(&(4, 4)));
+    }
+}
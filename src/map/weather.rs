@@ -0,0 +1,219 @@
+use crossterm::style::Color;
+use serde::{Serialize, Deserialize};
+use super::MapTheme;
+use crate::resources::RandomNumberGenerator;
+
+/// Frames the screen stays lit after a lightning strike
+pub const LIGHTNING_FLASH_FRAMES: u32 = 3;
+
+/// What the sky, or the cave ceiling, is doing on the current level
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    /// Rain driven by wind, with lightning
+    Storm,
+    Snow,
+    /// Cinders drifting down near lava
+    Ash,
+    /// Shortens how far anyone can see
+    Fog,
+}
+
+/// How falling weather is drawn over the map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WeatherOverlay {
+    pub glyphs: &'static [char],
+    pub color: Color,
+    /// Chance in a thousand that any cell shows a drop or flake
+    pub density: u32,
+    /// Frames a drop takes to fall one row
+    pub frames_per_row: u64,
+    /// Columns the wind pushes a drop for every row it falls
+    pub drift: i32,
+}
+
+impl WeatherKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeatherKind::Clear => "Clear",
+            WeatherKind::Rain => "Rain",
+            WeatherKind::Storm => "Storm",
+            WeatherKind::Snow => "Snow",
+            WeatherKind::Ash => "Ash",
+            WeatherKind::Fog => "Fog",
+        }
+    }
+
+    /// Logged when this weather sets in
+    pub fn arrival_message(&self) -> Option<&'static str> {
+        match self {
+            WeatherKind::Clear => None,
+            WeatherKind::Rain => Some("Water drips and then pours from above."),
+            WeatherKind::Storm => Some("Thunder rumbles as a storm breaks."),
+            WeatherKind::Snow => Some("Snow begins to fall."),
+            WeatherKind::Ash => Some("Ash drifts down from the smouldering dark."),
+            WeatherKind::Fog => Some("A thick fog rolls in."),
+        }
+    }
+
+    /// Pick the weather for a level; each theme has its own mix
+    pub fn roll(theme: MapTheme, rng: &mut RandomNumberGenerator) -> Self {
+        let table: &[(WeatherKind, i32)] = match theme {
+            MapTheme::Dungeon => &[(WeatherKind::Clear, 80), (WeatherKind::Fog, 20)],
+            MapTheme::Cave | MapTheme::Mines => &[(WeatherKind::Clear, 85), (WeatherKind::Fog, 15)],
+            MapTheme::Forest => &[
+                (WeatherKind::Clear, 40), (WeatherKind::Rain, 30), (WeatherKind::Storm, 15), (WeatherKind::Fog, 15),
+            ],
+            MapTheme::Ice => &[(WeatherKind::Snow, 70), (WeatherKind::Fog, 10), (WeatherKind::Clear, 20)],
+            MapTheme::Volcanic => &[(WeatherKind::Ash, 80), (WeatherKind::Clear, 20)],
+            MapTheme::Crypt => &[(WeatherKind::Fog, 50), (WeatherKind::Clear, 50)],
+            MapTheme::Sewer => &[(WeatherKind::Fog, 30), (WeatherKind::Rain, 20), (WeatherKind::Clear, 50)],
+            MapTheme::Town => &[(WeatherKind::Clear, 60), (WeatherKind::Rain, 25), (WeatherKind::Fog, 15)],
+            MapTheme::Desert | MapTheme::Underwater => &[(WeatherKind::Clear, 100)],
+        };
+        let mut roll = rng.roll_dice(1, 100);
+        for (kind, weight) in table {
+            if roll <= *weight {
+                return *kind;
+            }
+            roll -= weight;
+        }
+        WeatherKind::Clear
+    }
+
+    /// Tiles taken off every viewshed's range
+    pub fn sight_penalty(&self) -> i32 {
+        match self {
+            WeatherKind::Fog => 4,
+            WeatherKind::Storm => 2,
+            WeatherKind::Snow | WeatherKind::Ash => 1,
+            WeatherKind::Clear | WeatherKind::Rain => 0,
+        }
+    }
+
+    /// Whether anyone out in it gets soaked
+    pub fn soaks(&self) -> bool {
+        matches!(self, WeatherKind::Rain | WeatherKind::Storm)
+    }
+
+    /// Percent chance of sliding an extra tile when stepping onto ice
+    pub fn slip_chance(&self) -> i32 {
+        match self {
+            WeatherKind::Snow => 50,
+            _ => 25,
+        }
+    }
+
+    pub fn overlay(&self) -> Option<WeatherOverlay> {
+        match self {
+            WeatherKind::Rain => Some(WeatherOverlay {
+                glyphs: &['|', ','], color: Color::Blue, density: 60, frames_per_row: 1, drift: 0,
+            }),
+            WeatherKind::Storm => Some(WeatherOverlay {
+                glyphs: &['/', '/', ','], color: Color::Blue, density: 100, frames_per_row: 1, drift: 1,
+            }),
+            WeatherKind::Snow => Some(WeatherOverlay {
+                glyphs: &['*', '.'], color: Color::White, density: 40, frames_per_row: 3, drift: 0,
+            }),
+            WeatherKind::Ash => Some(WeatherOverlay {
+                glyphs: &['.', '`'], color: Color::DarkGrey, density: 40, frames_per_row: 4, drift: -1,
+            }),
+            WeatherKind::Clear | WeatherKind::Fog => None,
+        }
+    }
+}
+
+/// The current level's weather, rerolled whenever the map's theme changes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Weather {
+    pub kind: WeatherKind,
+    /// Theme the weather was rolled for
+    pub theme: Option<MapTheme>,
+    /// Frames left in the current lightning flash
+    #[serde(skip)]
+    pub flash_frames: u32,
+    #[serde(skip)]
+    frame: u64,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Weather { kind: WeatherKind::Clear, theme: None, flash_frames: 0, frame: 0 }
+    }
+}
+
+impl Weather {
+    /// Roll fresh weather for a theme
+    pub fn change(&mut self, theme: MapTheme, rng: &mut RandomNumberGenerator) {
+        self.kind = WeatherKind::roll(theme, rng);
+        self.theme = Some(theme);
+        self.flash_frames = 0;
+    }
+
+    pub fn strike_lightning(&mut self) {
+        self.flash_frames = LIGHTNING_FLASH_FRAMES;
+    }
+
+    pub fn is_flashing(&self) -> bool {
+        self.flash_frames > 0
+    }
+
+    /// Let falling weather move on a frame and lightning fade
+    pub fn tick_frame(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+        self.flash_frames = self.flash_frames.saturating_sub(1);
+    }
+
+    /// The drop or flake shown over a map cell this frame, if any
+    pub fn overlay_at(&self, x: i32, y: i32) -> Option<(char, Color)> {
+        let overlay = self.kind.overlay()?;
+        let rows_fallen = (self.frame / overlay.frames_per_row) as i32;
+        // Hashing where the drop started makes the same drops fall a row at a time
+        let hash = cell_hash(x - overlay.drift * rows_fallen, y - rows_fallen);
+        if hash % 1000 < overlay.density {
+            Some((overlay.glyphs[(hash / 1000) as usize % overlay.glyphs.len()], overlay.color))
+        } else {
+            None
+        }
+    }
+}
+
+fn cell_hash(x: i32, y: i32) -> u32 {
+    let mut hash = (x as u32).wrapping_mul(0x9E37_79B1) ^ (y as u32).wrapping_mul(0x85EB_CA77);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^ (hash >> 12)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rain_falls_a_row_per_frame() {
+        let weather = Weather { kind: WeatherKind::Rain, ..Default::default() };
+        let drops: Vec<(i32, i32)> = (0..40).flat_map(|x| (0..40).map(move |y| (x, y)))
+            .filter(|&(x, y)| weather.overlay_at(x, y).is_some())
+            .collect();
+        assert!(!drops.is_empty());
+
+        let mut later = weather.clone();
+        later.tick_frame();
+        for &(x, y) in &drops {
+            assert!(later.overlay_at(x, y + 1).is_some());
+        }
+        assert!(Weather::default().overlay_at(3, 3).is_none());
+    }
+
+    #[test]
+    fn test_themes_pick_fitting_weather() {
+        let mut rng = RandomNumberGenerator::new(7);
+        for _ in 0..50 {
+            assert_eq!(WeatherKind::roll(MapTheme::Desert, &mut rng), WeatherKind::Clear);
+            assert!(matches!(WeatherKind::roll(MapTheme::Volcanic, &mut rng), WeatherKind::Ash | WeatherKind::Clear));
+        }
+        assert!(WeatherKind::Fog.sight_penalty() > WeatherKind::Rain.sight_penalty());
+        assert!(WeatherKind::Storm.soaks() && !WeatherKind::Snow.soaks());
+    }
+}
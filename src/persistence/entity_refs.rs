@@ -0,0 +1,164 @@
+//! Saving references between entities.
+//!
+//! `Entity` ids are handed out afresh when a save is loaded, so a component that points at
+//! another entity (an inventory's items, a corpse's original, a companion's owner) cannot keep the
+//! raw id. Saved entities carry a `SimpleMarker<SerializeMe>` instead, and fields holding an
+//! `Entity` are written as that marker's id with `#[serde(with = "entity_refs")]` (or
+//! `entity_refs::option`, `entity_refs::vec`, `entity_refs::map`). The world serializer sets up
+//! which marker belongs to which entity around each save and load.
+//!
+//! A reference to an entity that was deleted before saving comes back pointing at an entity that
+//! is already dead, just as it was.
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use specs::Entity;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Marker id written for references to entities that no longer exist
+pub const DANGLING_MARKER: u64 = u64::MAX;
+
+enum Context {
+    Saving(HashMap<Entity, u64>),
+    Loading { entities: HashMap<u64, Entity>, dead: Entity },
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = RefCell::new(None);
+}
+
+fn with_context<R>(context: Context, f: impl FnOnce() -> R) -> R {
+    CONTEXT.with(|current| *current.borrow_mut() = Some(context));
+    let result = f();
+    CONTEXT.with(|current| *current.borrow_mut() = None);
+    result
+}
+
+/// Run `f` writing each entity reference as the marker id given for it
+pub fn saving<R>(markers: HashMap<Entity, u64>, f: impl FnOnce() -> R) -> R {
+    with_context(Context::Saving(markers), f)
+}
+
+/// Run `f` reading each marker id back as the entity now carrying it, or as `dead` when no
+/// loaded entity does
+pub fn loading<R>(entities: HashMap<u64, Entity>, dead: Entity, f: impl FnOnce() -> R) -> R {
+    with_context(Context::Loading { entities, dead }, f)
+}
+
+/// An entity reference as it goes into a save
+struct SavedEntity(Entity);
+
+impl Serialize for SavedEntity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let marker = CONTEXT.with(|current| match &*current.borrow() {
+            Some(Context::Saving(markers)) => Ok(markers.get(&self.0).copied().unwrap_or(DANGLING_MARKER)),
+            _ => Err(S::Error::custom("entity references can only be saved through the world serializer")),
+        })?;
+        serializer.serialize_u64(marker)
+    }
+}
+
+impl<'de> Deserialize<'de> for SavedEntity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let marker = u64::deserialize(deserializer)?;
+        CONTEXT.with(|current| match &*current.borrow() {
+            Some(Context::Loading { entities, dead }) => Ok(SavedEntity(entities.get(&marker).copied().unwrap_or(*dead))),
+            _ => Err(D::Error::custom("entity references can only be loaded through the world serializer")),
+        })
+    }
+}
+
+pub fn serialize<S: Serializer>(entity: &Entity, serializer: S) -> Result<S::Ok, S::Error> {
+    SavedEntity(*entity).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Entity, D::Error> {
+    SavedEntity::deserialize(deserializer).map(|saved| saved.0)
+}
+
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(entity: &Option<Entity>, serializer: S) -> Result<S::Ok, S::Error> {
+        entity.map(SavedEntity).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Entity>, D::Error> {
+        Option::<SavedEntity>::deserialize(deserializer).map(|saved| saved.map(|saved| saved.0))
+    }
+}
+
+pub mod vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(entities: &[Entity], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(entities.iter().map(|&entity| SavedEntity(entity)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Entity>, D::Error> {
+        Vec::<SavedEntity>::deserialize(deserializer).map(|saved| saved.into_iter().map(|saved| saved.0).collect())
+    }
+}
+
+/// Maps from anything to an optional entity, such as equipment slots
+pub mod map {
+    use super::*;
+
+    pub fn serialize<K, S>(map: &HashMap<K, Option<Entity>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_map(map.iter().map(|(key, entity)| (key, entity.map(SavedEntity))))
+    }
+
+    pub fn deserialize<'de, K, D>(deserializer: D) -> Result<HashMap<K, Option<Entity>>, D::Error>
+    where
+        K: DeserializeOwned + Eq + Hash,
+        D: Deserializer<'de>,
+    {
+        HashMap::<K, Option<SavedEntity>>::deserialize(deserializer)
+            .map(|saved| saved.into_iter().map(|(key, entity)| (key, entity.map(|saved| saved.0))).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{Builder, World, WorldExt};
+
+    #[derive(Serialize, Deserialize)]
+    struct Holder {
+        #[serde(with = "super")]
+        one: Entity,
+        #[serde(with = "super::option")]
+        maybe: Option<Entity>,
+        #[serde(with = "super::vec")]
+        many: Vec<Entity>,
+    }
+
+    #[test]
+    fn test_references_are_saved_as_markers_and_loaded_as_the_new_entities() {
+        let mut world = World::new();
+        let (a, b, gone) = (world.create_entity().build(), world.create_entity().build(), world.create_entity().build());
+        world.delete_entity(gone).unwrap();
+        let holder = Holder { one: a, maybe: Some(gone), many: vec![b, a] };
+
+        let markers = [(a, 1), (b, 2)].into_iter().collect();
+        let saved = saving(markers, || serde_json::to_string(&holder)).unwrap();
+        assert_eq!(saved, format!(r#"{{"one":1,"maybe":{},"many":[2,1]}}"#, DANGLING_MARKER));
+        assert!(serde_json::to_string(&holder).is_err());
+
+        let mut loaded_world = World::new();
+        let (new_a, new_b, dead) = (loaded_world.create_entity().build(), loaded_world.create_entity().build(), loaded_world.create_entity().build());
+        loaded_world.delete_entity(dead).unwrap();
+        let entities = [(1, new_a), (2, new_b)].into_iter().collect();
+        let loaded: Holder = loading(entities, dead, || serde_json::from_str(&saved)).unwrap();
+        assert_eq!(loaded.one, new_a);
+        assert_eq!(loaded.maybe, Some(dead));
+        assert_eq!(loaded.many, vec![new_b, new_a]);
+    }
+}
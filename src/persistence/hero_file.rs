@@ -0,0 +1,397 @@
+//! Characters exported on their own, to carry into a new run or hand to a friend.
+//!
+//! A `.hero` file holds the character and what they carry, never the world around them: no
+//! position, no level, no status effects. On import the hero is checked over again, since the file
+//! may have come from anywhere, and anything tied to the run it came from is left behind.
+
+use serde::{Serialize, Deserialize};
+use specs::{Builder, Entity, World, WorldExt};
+use specs::saveload::{MarkedBuilder, SimpleMarker};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::components::{
+    Abilities, Attributes, Background, CharacterClass, CombatStats, Contact, Consumable, DefenseBonus,
+    EquipmentSlot, Equippable, Equipped, Experience, Inventory, Item, MeleePowerBonus, Name,
+    Piety, ProvidesHealing, Renderable, SerializeMe, Skills, VictoryArtifact,
+};
+use crate::entity_factory::EntityFactory;
+use crate::items::Artifact;
+use crate::utils::{fnv1a, FNV_OFFSET_BASIS};
+
+/// Extension of exported heroes
+pub const HERO_EXTENSION: &str = "hero";
+/// Where heroes are exported to and imported from
+pub const HEROES_DIR: &str = "heroes";
+/// Layout of the hero files this build writes
+pub const HERO_FORMAT: u32 = 1;
+
+const HERO_MAGIC: &[u8; 4] = b"HERO";
+/// Magic, format and checksum ahead of the hero itself
+const HEADER_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeroError {
+    Io(String),
+    NotAHero,
+    UnsupportedFormat(u32),
+    ChecksumMismatch,
+    Corrupted(String),
+    NoCharacter,
+}
+
+impl fmt::Display for HeroError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeroError::Io(msg) => write!(f, "{}", msg),
+            HeroError::NotAHero => write!(f, "Not a hero file"),
+            HeroError::UnsupportedFormat(format) => write!(f, "Hero file format {} is newer than this game", format),
+            HeroError::ChecksumMismatch => write!(f, "Hero file is damaged or has been edited"),
+            HeroError::Corrupted(msg) => write!(f, "Hero file is corrupted: {}", msg),
+            HeroError::NoCharacter => write!(f, "There is no character to export"),
+        }
+    }
+}
+
+impl std::error::Error for HeroError {}
+
+impl From<std::io::Error> for HeroError {
+    fn from(error: std::io::Error) -> Self {
+        HeroError::Io(error.to_string())
+    }
+}
+
+/// Something the hero carries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeroItem {
+    pub name: String,
+    pub renderable: Option<Renderable>,
+    pub slot: Option<EquipmentSlot>,
+    pub equipped: bool,
+    pub power_bonus: Option<i32>,
+    pub defense_bonus: Option<i32>,
+    pub healing: Option<i32>,
+    pub consumable: bool,
+    /// Unique artifacts turn up once per run, and belong to it
+    pub artifact: Option<String>,
+    /// The artifact the run was for
+    pub victory_artifact: bool,
+}
+
+impl HeroItem {
+    fn capture(world: &World, item: Entity, owner: Entity) -> Option<Self> {
+        let name = world.read_storage::<Name>().get(item)?.name.clone();
+        Some(HeroItem {
+            name,
+            renderable: world.read_storage::<Renderable>().get(item).cloned(),
+            slot: world.read_storage::<Equippable>().get(item).map(|equippable| equippable.slot.clone()),
+            equipped: world.read_storage::<Equipped>().get(item).map_or(false, |equipped| equipped.owner == owner),
+            power_bonus: world.read_storage::<MeleePowerBonus>().get(item).map(|bonus| bonus.power),
+            defense_bonus: world.read_storage::<DefenseBonus>().get(item).map(|bonus| bonus.defense),
+            healing: world.read_storage::<ProvidesHealing>().get(item).map(|healing| healing.heal_amount),
+            consumable: world.read_storage::<Consumable>().get(item).is_some(),
+            artifact: world.read_storage::<Artifact>().get(item).map(|artifact| artifact.id.clone()),
+            victory_artifact: world.read_storage::<VictoryArtifact>().get(item).is_some(),
+        })
+    }
+
+    /// Whether the item belongs to the run it was found in rather than to the hero
+    pub fn is_bound_to_run(&self) -> bool {
+        self.artifact.is_some() || self.victory_artifact
+    }
+
+    fn spawn(&self, world: &mut World, owner: Entity) -> Entity {
+        let mut builder = world.create_entity()
+            .marked::<SimpleMarker<SerializeMe>>()
+            .with(Item)
+            .with(Name { name: self.name.clone() });
+        if let Some(renderable) = &self.renderable {
+            builder = builder.with(renderable.clone());
+        }
+        if let Some(slot) = &self.slot {
+            builder = builder.with(Equippable { slot: slot.clone() });
+        }
+        if let Some(power) = self.power_bonus {
+            builder = builder.with(MeleePowerBonus { power });
+        }
+        if let Some(defense) = self.defense_bonus {
+            builder = builder.with(DefenseBonus { defense });
+        }
+        if let Some(heal_amount) = self.healing {
+            builder = builder.with(ProvidesHealing { heal_amount });
+        }
+        if self.consumable {
+            builder = builder.with(Consumable);
+        }
+        let item = builder.build();
+
+        if let (true, Some(slot)) = (self.equipped, &self.slot) {
+            world.write_storage::<Equipped>()
+                .insert(item, Equipped { owner, slot: slot.clone() })
+                .expect("Unable to equip imported item");
+        }
+        item
+    }
+}
+
+/// A character, apart from any world
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeroRecord {
+    pub name: String,
+    pub stats: CombatStats,
+    pub experience: Option<Experience>,
+    pub attributes: Option<Attributes>,
+    pub class: Option<CharacterClass>,
+    pub background: Option<Background>,
+    pub contact: Option<Contact>,
+    pub piety: Option<Piety>,
+    pub skills: Option<Skills>,
+    pub abilities: Option<Abilities>,
+    pub items: Vec<HeroItem>,
+}
+
+impl HeroRecord {
+    /// The character `player` is, and everything in their pack
+    pub fn capture(world: &World, player: Entity) -> Option<Self> {
+        let name = world.read_storage::<Name>().get(player)?.name.clone();
+        let stats = world.read_storage::<CombatStats>().get(player)?.clone();
+        let carried: Vec<Entity> = world.read_storage::<Inventory>().get(player)
+            .map(|inventory| inventory.items.clone())
+            .unwrap_or_default();
+        Some(HeroRecord {
+            name,
+            stats,
+            experience: world.read_storage::<Experience>().get(player).cloned(),
+            attributes: world.read_storage::<Attributes>().get(player).cloned(),
+            class: world.read_storage::<CharacterClass>().get(player).cloned(),
+            background: world.read_storage::<Background>().get(player).cloned(),
+            contact: world.read_storage::<Contact>().get(player).cloned(),
+            piety: world.read_storage::<Piety>().get(player).cloned(),
+            skills: world.read_storage::<Skills>().get(player).cloned(),
+            abilities: world.read_storage::<Abilities>().get(player).cloned(),
+            items: carried.into_iter().filter_map(|item| HeroItem::capture(world, item, player)).collect(),
+        })
+    }
+
+    /// Ready to start a new run: run-bound items left behind, wounds healed, cooldowns reset,
+    /// one item worn per slot and no more carried than a pack holds
+    pub fn validated(mut self) -> Self {
+        self.items.retain(|item| !item.is_bound_to_run());
+        self.items.truncate(Inventory::new(26).capacity);
+
+        let mut worn: Vec<EquipmentSlot> = Vec::new();
+        for item in self.items.iter_mut() {
+            match &item.slot {
+                Some(slot) if item.equipped && !worn.contains(slot) => worn.push(slot.clone()),
+                _ => item.equipped = false,
+            }
+        }
+
+        self.stats.max_hp = self.stats.max_hp.max(1);
+        self.stats.hp = self.stats.max_hp;
+        if let Some(experience) = self.experience.as_mut() {
+            experience.level = experience.level.max(1);
+        }
+        if let Some(abilities) = self.abilities.as_mut() {
+            abilities.ability_cooldowns.clear();
+        }
+        self
+    }
+
+    /// Bring the hero into the world at a spot, with their pack
+    pub fn spawn(&self, world: &mut World, x: i32, y: i32) -> Entity {
+        let player = EntityFactory::create_player(world, x, y);
+        world.write_storage::<Name>().insert(player, Name { name: self.name.clone() }).expect("Unable to name hero");
+        world.write_storage::<CombatStats>().insert(player, self.stats.clone()).expect("Unable to insert hero stats");
+        if let Some(experience) = &self.experience {
+            world.write_storage::<Experience>().insert(player, experience.clone()).expect("Unable to insert hero experience");
+        }
+        if let Some(attributes) = &self.attributes {
+            world.write_storage::<Attributes>().insert(player, attributes.clone()).expect("Unable to insert hero attributes");
+        }
+        if let Some(class) = &self.class {
+            world.write_storage::<CharacterClass>().insert(player, class.clone()).expect("Unable to insert hero class");
+        }
+        if let Some(background) = &self.background {
+            world.write_storage::<Background>().insert(player, background.clone()).expect("Unable to insert hero background");
+        }
+        if let Some(contact) = &self.contact {
+            world.write_storage::<Contact>().insert(player, contact.clone()).expect("Unable to insert hero contact");
+        }
+        if let Some(piety) = &self.piety {
+            world.write_storage::<Piety>().insert(player, piety.clone()).expect("Unable to insert hero piety");
+        }
+        if let Some(skills) = &self.skills {
+            world.write_storage::<Skills>().insert(player, skills.clone()).expect("Unable to insert hero skills");
+        }
+        if let Some(abilities) = &self.abilities {
+            world.write_storage::<Abilities>().insert(player, abilities.clone()).expect("Unable to insert hero abilities");
+        }
+
+        let items: Vec<Entity> = self.items.iter().map(|item| item.spawn(world, player)).collect();
+        if let Some(inventory) = world.write_storage::<Inventory>().get_mut(player) {
+            inventory.items = items;
+        }
+        player
+    }
+
+    pub fn level(&self) -> i32 {
+        self.experience.as_ref().map_or(1, |experience| experience.level)
+    }
+
+    pub fn class_name(&self) -> &'static str {
+        self.class.as_ref().map_or("Adventurer", |class| class.class_type.name())
+    }
+}
+
+/// A hero as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeroFile {
+    pub game_version: String,
+    pub exported_at: u64,
+    pub hero: HeroRecord,
+}
+
+impl HeroFile {
+    pub fn new(hero: HeroRecord) -> Self {
+        HeroFile {
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            hero,
+        }
+    }
+
+    /// Export the character `player` is
+    pub fn export(world: &World, player: Entity) -> Result<Self, HeroError> {
+        HeroRecord::capture(world, player).map(HeroFile::new).ok_or(HeroError::NoCharacter)
+    }
+
+    /// Magic and format, then a checksum over the bincode of the hero that follows it
+    pub fn to_bytes(&self) -> Result<Vec<u8>, HeroError> {
+        let payload = bincode::serialize(self).map_err(|e| HeroError::Corrupted(e.to_string()))?;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+        bytes.extend_from_slice(HERO_MAGIC);
+        bytes.extend_from_slice(&HERO_FORMAT.to_le_bytes());
+        bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, HeroError> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != HERO_MAGIC {
+            return Err(HeroError::NotAHero);
+        }
+        let format = u32::from_le_bytes(bytes[4..8].try_into().expect("four bytes"));
+        if format > HERO_FORMAT {
+            return Err(HeroError::UnsupportedFormat(format));
+        }
+        let stored = u64::from_le_bytes(bytes[8..16].try_into().expect("eight bytes"));
+        let payload = &bytes[HEADER_LEN..];
+        if checksum(payload) != stored {
+            return Err(HeroError::ChecksumMismatch);
+        }
+        bincode::deserialize(payload).map_err(|e| HeroError::Corrupted(e.to_string()))
+    }
+
+    /// Write into `directory`, named after the hero; returns where it went
+    pub fn write(&self, directory: &Path) -> Result<PathBuf, HeroError> {
+        fs::create_dir_all(directory)?;
+        let path = directory.join(format!("{}.{}", file_stem(&self.hero.name), HERO_EXTENSION));
+        fs::write(&path, self.to_bytes()?)?;
+        Ok(path)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, HeroError> {
+        HeroFile::from_bytes(&fs::read(path)?)
+    }
+}
+
+/// Every hero file in `directory`, by name
+pub fn list_hero_files(directory: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(directory)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().map_or(false, |extension| extension == HERO_EXTENSION));
+    files.sort();
+    files
+}
+
+/// The hero's name, made safe to use as a file name
+fn file_stem(name: &str) -> String {
+    let stem: String = name.trim().chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if stem.is_empty() { "hero".to_string() } else { stem }
+}
+
+// FNV-1a; enough to catch a damaged or hand-edited file, and the same on every build
+fn checksum(bytes: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET_BASIS, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::register_components;
+    use tempfile::TempDir;
+
+    fn world_with_hero() -> (World, Entity) {
+        let mut world = World::new();
+        register_components(&mut world);
+        let player = EntityFactory::create_player(&mut world, 5, 5);
+        world.write_storage::<Name>().insert(player, Name { name: "Ada Quill".to_string() }).unwrap();
+        world.write_storage::<CombatStats>().get_mut(player).unwrap().hp = 4;
+
+        let sword = world.create_entity()
+            .with(Item)
+            .with(Name { name: "Longsword".to_string() })
+            .with(Equippable { slot: EquipmentSlot::Melee })
+            .with(MeleePowerBonus { power: 3 })
+            .build();
+        let relic = world.create_entity()
+            .with(Item)
+            .with(Name { name: "Amulet of the Deep".to_string() })
+            .with(VictoryArtifact)
+            .build();
+        world.write_storage::<Equipped>().insert(sword, Equipped { owner: player, slot: EquipmentSlot::Melee }).unwrap();
+        world.write_storage::<Inventory>().get_mut(player).unwrap().items = vec![sword, relic];
+        (world, player)
+    }
+
+    #[test]
+    fn test_exported_heroes_come_back_without_what_belonged_to_their_run() {
+        let (world, player) = world_with_hero();
+        let dir = TempDir::new().unwrap();
+        let path = HeroFile::export(&world, player).unwrap().write(dir.path()).unwrap();
+        assert_eq!(path.file_name().and_then(|name| name.to_str()), Some("Ada_Quill.hero"));
+        assert_eq!(list_hero_files(dir.path()), vec![path.clone()]);
+
+        let hero = HeroFile::read(&path).unwrap().hero.validated();
+        assert_eq!(hero.items.len(), 1);
+        assert_eq!(hero.stats.hp, hero.stats.max_hp);
+
+        let mut new_run = World::new();
+        register_components(&mut new_run);
+        let imported = hero.spawn(&mut new_run, 1, 2);
+        assert_eq!(new_run.read_storage::<Name>().get(imported).unwrap().name, "Ada Quill");
+        let items = new_run.read_storage::<Inventory>().get(imported).unwrap().items.clone();
+        assert_eq!(items.len(), 1);
+        assert_eq!(new_run.read_storage::<Equipped>().get(items[0]).map(|equipped| equipped.owner), Some(imported));
+        assert_eq!(new_run.read_storage::<MeleePowerBonus>().get(items[0]).map(|bonus| bonus.power), Some(3));
+    }
+
+    #[test]
+    fn test_damaged_or_foreign_files_are_refused() {
+        let (world, player) = world_with_hero();
+        let mut bytes = HeroFile::export(&world, player).unwrap().to_bytes().unwrap();
+        assert!(HeroFile::from_bytes(&bytes).is_ok());
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert_eq!(HeroFile::from_bytes(&bytes).unwrap_err(), HeroError::ChecksumMismatch);
+        assert_eq!(HeroFile::from_bytes(b"not a hero at all").unwrap_err(), HeroError::NotAHero);
+    }
+}
@@ -0,0 +1,307 @@
+//! Every change to what a save holds, as a step from one schema to the next.
+//!
+//! | Schema | What changed |
+//! |--------|--------------|
+//! | 1 | The first release. Saves from before schemas were recorded are read as this |
+//! | 2 | The map keeps terrain states, decals, its location, branch entrances and safe rooms |
+//! | 3 | The random number generator keeps its place in its stream and its sub-streams |
+//! | 4 | The map keeps which doors are locked, jammed or broken |
+//! | 5 | The map keeps the clouds of gas drifting over it |
+//! | 6 | The map keeps its vaults and their guardians |
+//!
+//! Saves are bincode, which has no field names or defaults, so a step rewrites the bytes of
+//! whatever changed into the layout of the next schema. Add a new step (and bump
+//! `CURRENT_SAVE_SCHEMA`) whenever a saved component or resource changes shape.
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::map::{BranchEntrance, Decal, DoorLock, GasCloud, Location, MapTheme, Rect, TileType, TransientTileState, Vault};
+use crate::persistence::serialization::{SaveData, SerializationError, SerializationResult};
+use crate::persistence::version_manager::{SaveMigration, VersionManager};
+use crate::resources::RandomNumberGenerator;
+
+/// Register every step, oldest first
+pub fn register_migrations(manager: &mut VersionManager) {
+    manager.register_migration(SaveMigration::new(1, "Map gains terrain states, decals, location and safe rooms", map_layers));
+    manager.register_migration(SaveMigration::new(2, "Random numbers carry on from where they left off", rng_streams));
+    manager.register_migration(SaveMigration::new(3, "Map keeps its door locks", door_locks));
+    manager.register_migration(SaveMigration::new(4, "Map keeps its gas clouds", gas_clouds));
+    manager.register_migration(SaveMigration::new(5, "Map keeps its vaults", vaults));
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> SerializationResult<T> {
+    bincode::deserialize(bytes).map_err(|e| SerializationError::CorruptedData(e.to_string()))
+}
+
+fn encode<T: Serialize>(value: &T) -> SerializationResult<Vec<u8>> {
+    bincode::serialize(value).map_err(|e| SerializationError::SerializationFailed(e.to_string()))
+}
+
+/// The start of a map as the first release saved it, up to its depth
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct MapHead {
+    tiles: Vec<TileType>,
+    width: i32,
+    height: i32,
+    revealed_tiles: Vec<bool>,
+    visible_tiles: Vec<bool>,
+    blocked: Vec<bool>,
+    opaque: Vec<bool>,
+    depth: i32,
+}
+
+/// 1 → 2: the map's later layers follow its tile contents. Levels from before branches were all
+/// on the main dungeon.
+fn map_layers(mut save_data: SaveData) -> SerializationResult<SaveData> {
+    if let Some(bytes) = save_data.resources.get_mut("Map") {
+        let head: MapHead = decode(bytes)?;
+        let layers = (
+            HashMap::<usize, TransientTileState>::new(),
+            HashMap::<usize, Decal>::new(),
+            Location::main(head.depth),
+            Vec::<BranchEntrance>::new(),
+            Vec::<Rect>::new(),
+        );
+        bytes.extend(encode(&layers)?);
+    }
+    Ok(save_data)
+}
+
+/// 2 → 3: the generator only kept its seed, so it starts its stream over from there
+fn rng_streams(mut save_data: SaveData) -> SerializationResult<SaveData> {
+    if let Some(bytes) = save_data.resources.get_mut("RandomNumberGenerator") {
+        let seed: u64 = decode(bytes)?;
+        *bytes = encode(&RandomNumberGenerator::new(seed))?;
+    }
+    Ok(save_data)
+}
+
+/// A map as schemas 2 and 3 saved it, before any layer was added at its end
+#[derive(Serialize, Deserialize)]
+struct MapV3 {
+    tiles: Vec<TileType>,
+    width: i32,
+    height: i32,
+    revealed_tiles: Vec<bool>,
+    visible_tiles: Vec<bool>,
+    blocked: Vec<bool>,
+    opaque: Vec<bool>,
+    depth: i32,
+    rooms: Vec<Rect>,
+    corridors: Vec<Vec<(i32, i32)>>,
+    entrance: (i32, i32),
+    exit: (i32, i32),
+    theme: MapTheme,
+    generation_seed: u64,
+    tile_content: Vec<Vec<u32>>,
+    tile_states: HashMap<usize, TransientTileState>,
+    decals: HashMap<usize, Decal>,
+    location: Location,
+    branch_entrances: Vec<BranchEntrance>,
+    safe_rooms: Vec<Rect>,
+}
+
+/// A map as schema 4 saved it
+type MapV4 = (MapV3, HashMap<usize, DoorLock>);
+
+/// A map as schema 5 saved it
+type MapV5 = (MapV3, HashMap<usize, DoorLock>, HashMap<usize, GasCloud>);
+
+/// 3 → 4: no door was locked, jammed or broken before doors could be
+fn door_locks(save_data: SaveData) -> SerializationResult<SaveData> {
+    append_map_layer::<MapV3, _>(save_data, &HashMap::<usize, DoorLock>::new())
+}
+
+/// 4 → 5: no gas hung over a level saved before it could drift
+fn gas_clouds(save_data: SaveData) -> SerializationResult<SaveData> {
+    append_map_layer::<MapV4, _>(save_data, &HashMap::<usize, GasCloud>::new())
+}
+
+/// 5 → 6: levels saved before vaults were dug have none
+fn vaults(save_data: SaveData) -> SerializationResult<SaveData> {
+    append_map_layer::<MapV5, _>(save_data, &Vec::<Vault>::new())
+}
+
+/// Add a layer to the end of the saved map and of every kept level, where a new field of `Map`
+/// lands in bincode. Kept levels sit back to back, so each is read as the `Old` map it was.
+fn append_map_layer<Old, T>(mut save_data: SaveData, layer: &T) -> SerializationResult<SaveData>
+where
+    Old: Serialize + for<'de> Deserialize<'de>,
+    T: Serialize,
+{
+    if let Some(bytes) = save_data.resources.get_mut("Map") {
+        bytes.extend(encode(layer)?);
+    }
+    if let Some(bytes) = save_data.resources.get_mut("DungeonLevels") {
+        let levels: Vec<Old> = decode(bytes)?;
+        let levels: Vec<(Old, &T)> = levels.into_iter().map(|level| (level, layer)).collect();
+        *bytes = encode(&levels)?;
+    }
+    Ok(save_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{DungeonLevels, Map};
+    use crate::persistence::{SaveMetadata, SaveSystem, VersionCompatibility};
+    use crate::persistence::version_manager::{create_version_manager, save_schema, set_save_schema, CURRENT_SAVE_SCHEMA};
+    use tempfile::TempDir;
+
+    /// A map as the first release saved it
+    #[derive(Serialize)]
+    struct MapV1 {
+        tiles: Vec<TileType>,
+        width: i32,
+        height: i32,
+        revealed_tiles: Vec<bool>,
+        visible_tiles: Vec<bool>,
+        blocked: Vec<bool>,
+        opaque: Vec<bool>,
+        depth: i32,
+        rooms: Vec<Rect>,
+        corridors: Vec<Vec<(i32, i32)>>,
+        entrance: (i32, i32),
+        exit: (i32, i32),
+        theme: MapTheme,
+        generation_seed: u64,
+        tile_content: Vec<Vec<u32>>,
+    }
+
+    /// A save from before schemas were recorded: a small cave and a generator that kept only its seed
+    fn first_release_save() -> SaveData {
+        let map = MapV1 {
+            tiles: vec![TileType::Floor; 12],
+            width: 4,
+            height: 3,
+            revealed_tiles: vec![true; 12],
+            visible_tiles: vec![false; 12],
+            blocked: vec![false; 12],
+            opaque: vec![false; 12],
+            depth: 4,
+            rooms: Vec::new(),
+            corridors: Vec::new(),
+            entrance: (0, 0),
+            exit: (3, 2),
+            theme: MapTheme::Cave,
+            generation_seed: 99,
+            tile_content: vec![Vec::new(); 12],
+        };
+        let mut save_data = SaveData::new("Old Game".to_string(), "Old Player".to_string());
+        save_data.version = "0.0.9".to_string();
+        save_data.metadata.clear();
+        save_data.resources.insert("Map".to_string(), encode(&map).unwrap());
+        save_data.resources.insert("RandomNumberGenerator".to_string(), encode(&7u64).unwrap());
+        save_data
+    }
+
+    /// Bytes an empty map layer takes: a length of 0
+    const EMPTY_LAYER: usize = 8;
+
+    /// A map as a save at `schema` wrote it: today's, less the layers added since. The map didn't
+    /// change from schema 2 to 3, and every step since has added one layer, empty on old levels
+    fn map_at_schema(map: &Map, schema: u32) -> Vec<u8> {
+        let mut bytes = encode(map).unwrap();
+        let missing = (CURRENT_SAVE_SCHEMA - schema.max(3)) as usize;
+        bytes.truncate(bytes.len() - EMPTY_LAYER * missing);
+        bytes
+    }
+
+    /// Kept levels as a save at `schema` wrote them, one after another behind their count
+    fn levels_at_schema(levels: &[Map], schema: u32) -> Vec<u8> {
+        let mut bytes = encode(&(levels.len() as u64)).unwrap();
+        for level in levels {
+            bytes.extend(map_at_schema(level, schema));
+        }
+        bytes
+    }
+
+    /// A save at `schema` from 2 on, with a generator that kept only its seed where it still did
+    fn save_at_schema(schema: u32) -> SaveData {
+        let mut save_data = first_release_save();
+        set_save_schema(&mut save_data, schema);
+        save_data.resources.insert("Map".to_string(), map_at_schema(&Map::new(4, 3, 2), schema));
+        if schema >= 3 {
+            save_data.resources.insert("RandomNumberGenerator".to_string(), encode(&RandomNumberGenerator::new(7)).unwrap());
+        }
+        save_data
+    }
+
+    #[test]
+    fn test_first_release_saves_load_as_the_current_schema() {
+        let migrated = create_version_manager().upgrade(first_release_save()).unwrap();
+        assert_eq!(save_schema(&migrated), CURRENT_SAVE_SCHEMA);
+
+        let map: Map = decode(&migrated.resources["Map"]).unwrap();
+        assert_eq!((map.width, map.depth, map.theme, map.exit), (4, 4, MapTheme::Cave, (3, 2)));
+        assert_eq!(map.location, Location::main(4));
+        assert!(map.decals.is_empty() && map.safe_rooms.is_empty());
+        assert!(map.door_locks.is_empty() && map.gas.is_empty() && map.vaults.is_empty());
+        // Nothing left over or missing
+        assert_eq!(encode(&map).unwrap(), migrated.resources["Map"]);
+
+        let mut rng: RandomNumberGenerator = decode(&migrated.resources["RandomNumberGenerator"]).unwrap();
+        assert_eq!(rng.roll_dice(3, 6), RandomNumberGenerator::new(7).roll_dice(3, 6));
+    }
+
+    #[test]
+    fn test_saves_from_before_each_map_layer_load_with_it_empty() {
+        for schema in 2..CURRENT_SAVE_SCHEMA {
+            let migrated = create_version_manager().upgrade(save_at_schema(schema)).unwrap();
+            assert_eq!(save_schema(&migrated), CURRENT_SAVE_SCHEMA);
+
+            let map: Map = decode(&migrated.resources["Map"]).unwrap();
+            assert_eq!((map.width, map.height, map.depth), (4, 3, 2));
+            assert!(map.door_locks.is_empty(), "schema {}", schema);
+            assert!(map.gas.is_empty() && map.vaults.is_empty(), "schema {}", schema);
+            assert_eq!(encode(&map).unwrap(), migrated.resources["Map"], "schema {}", schema);
+        }
+    }
+
+    #[test]
+    fn test_kept_levels_from_before_each_map_layer_load_with_it_empty() {
+        let kept = [Map::new(5, 4, 1), Map::new_with_theme(6, 2, 3, MapTheme::Ice, 11)];
+        for schema in 3..CURRENT_SAVE_SCHEMA {
+            let mut save_data = save_at_schema(schema);
+            save_data.resources.insert("DungeonLevels".to_string(), levels_at_schema(&kept, schema));
+            let migrated = create_version_manager().upgrade(save_data).unwrap();
+
+            let levels: DungeonLevels = decode(&migrated.resources["DungeonLevels"]).unwrap();
+            let shapes: Vec<_> = levels.levels.iter().map(|map| (map.width, map.height, map.depth, map.theme)).collect();
+            assert_eq!(shapes, vec![(5, 4, 1, MapTheme::Dungeon), (6, 2, 3, MapTheme::Ice)], "schema {}", schema);
+            for map in &levels.levels {
+                assert!(map.door_locks.is_empty(), "schema {}", schema);
+                assert!(map.gas.is_empty() && map.vaults.is_empty(), "schema {}", schema);
+            }
+            assert_eq!(encode(&levels).unwrap(), migrated.resources["DungeonLevels"], "schema {}", schema);
+        }
+    }
+
+    #[test]
+    fn test_old_saves_in_slots_are_reported_upgraded_and_then_load_as_they_are() {
+        let dir = TempDir::new().unwrap();
+        let saves = SaveSystem::new(dir.path()).unwrap();
+        let metadata = SaveMetadata::new("Old".to_string(), "Old Player".to_string());
+        saves.save_to_slot(0, first_release_save(), metadata.clone()).unwrap();
+        saves.save_to_slot(1, save_at_schema(2), metadata).unwrap();
+
+        let slots = saves.get_save_slots().unwrap();
+        for (slot, steps) in [(0, CURRENT_SAVE_SCHEMA - 1), (1, CURRENT_SAVE_SCHEMA - 2)] {
+            let report = slots[slot].compatibility.clone().unwrap();
+            assert_eq!(report.status, VersionCompatibility::NeedsMigration);
+            assert_eq!(report.migrations.len() as u32, steps);
+        }
+
+        for slot in 0..2 {
+            let loaded = saves.load_from_slot(slot).unwrap();
+            assert_eq!(save_schema(&loaded.data), CURRENT_SAVE_SCHEMA);
+            assert!(decode::<Map>(&loaded.data.resources["Map"]).is_ok());
+
+            saves.save_to_slot(slot, loaded.data.clone(), loaded.metadata).unwrap();
+            let report = saves.get_save_slots().unwrap()[slot as usize].compatibility.clone().unwrap();
+            assert_eq!(report.status, VersionCompatibility::Exact);
+            assert_eq!(saves.load_from_slot(slot).unwrap().data.resources, loaded.data.resources);
+        }
+    }
+}
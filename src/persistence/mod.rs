@@ -1,7 +1,9 @@
 pub mod serialization;
+pub mod entity_refs;
 pub mod save_system;
 pub mod world_serializer;
 pub mod version_manager;
+pub mod migrations;
 pub mod save_load_system;
 pub mod autosave_system;
 pub mod crash_recovery;
@@ -10,17 +12,26 @@ pub mod save_cleanup;
 pub mod game_persistence_integration;
 pub mod usage_example;
 pub mod autosave_integration_example;
+pub mod run_history;
+pub mod hero_file;
 
 pub use serialization::{
     SerializationSystem, SerializableComponent, ComponentSerializer, SerializationError,
-    SerializationResult, SaveData, LoadData
+    SerializationResult, SaveData, LoadData, SaveMarker, SaveMarkerAllocator
 };
 pub use save_system::{
-    SaveSystem, SaveSlot, SaveMetadata, SaveFile, SaveError, SaveResult
+    SaveSystem, SaveSlot, SaveMetadata, SaveFile, SaveError, SaveResult, SAVES_DIR
 };
 pub use world_serializer::{
     WorldSerializer, WorldState, EntityData, ComponentData, ResourceData
 };
 pub use version_manager::{
-    VersionManager, SaveVersion, VersionCompatibility, MigrationResult
-};
\ No newline at end of file
+    VersionManager, SaveVersion, VersionCompatibility, MigrationResult, SaveMigration,
+    CompatibilityReport, CURRENT_SAVE_SCHEMA
+};
+pub use run_history::{
+    RunHistory, RunRecord, RunOutcome, RUN_HISTORY_PATH
+};
+pub use hero_file::{
+    HeroFile, HeroRecord, HeroItem, HeroError, HEROES_DIR, HERO_EXTENSION
+};
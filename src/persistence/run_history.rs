@@ -0,0 +1,156 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::resources::RunStatistics;
+
+/// Default location of the run history file
+pub const RUN_HISTORY_PATH: &str = "saves/run_history.json";
+
+/// How a run ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RunOutcome {
+    Victory,
+    Died { cause: String },
+    Abandoned,
+}
+
+/// Summary of a single finished run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub character_name: String,
+    pub character_level: i32,
+    pub outcome: RunOutcome,
+    pub turns: u32,
+    pub kills: u32,
+    pub bosses_defeated: u32,
+    pub deepest_depth: i32,
+    pub ascent_turns: Option<u32>,
+    #[serde(default)]
+    pub artifacts_found: Vec<String>,
+    pub finished_at: u64,
+}
+
+impl RunRecord {
+    pub fn new(character_name: String, character_level: i32, outcome: RunOutcome, turns: u32, stats: &RunStatistics) -> Self {
+        RunRecord {
+            character_name,
+            character_level,
+            outcome,
+            turns,
+            kills: stats.kills,
+            bosses_defeated: stats.bosses_defeated,
+            deepest_depth: stats.deepest_depth,
+            ascent_turns: stats.ascent_turns(),
+            artifacts_found: stats.artifacts_found.clone(),
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+}
+
+/// Every finished run, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunHistory {
+    pub runs: Vec<RunRecord>,
+}
+
+impl RunHistory {
+    /// Load the history, starting fresh if the file is missing
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(RunHistory::default());
+        }
+
+        let file_content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, run: RunRecord) {
+        self.runs.push(run);
+    }
+
+    pub fn victories(&self) -> usize {
+        self.runs.iter().filter(|run| run.outcome == RunOutcome::Victory).count()
+    }
+
+    /// Fastest winning run, by total turns
+    pub fn fastest_victory(&self) -> Option<&RunRecord> {
+        self.runs.iter()
+            .filter(|run| run.outcome == RunOutcome::Victory)
+            .min_by_key(|run| run.turns)
+    }
+
+    /// Every artifact found in any run, with the number of runs it turned up in
+    pub fn found_artifacts(&self) -> BTreeMap<String, usize> {
+        let mut found = BTreeMap::new();
+        for run in &self.runs {
+            for id in &run.artifacts_found {
+                *found.entry(id.clone()).or_insert(0) += 1;
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(outcome: RunOutcome, turns: u32) -> RunRecord {
+        RunRecord::new("Tester".to_string(), 5, outcome, turns, &RunStatistics::default())
+    }
+
+    #[test]
+    fn test_fastest_victory_ignores_deaths() {
+        let mut history = RunHistory::default();
+        history.record(record(RunOutcome::Died { cause: "Orc".to_string() }, 100));
+        history.record(record(RunOutcome::Victory, 9000));
+        history.record(record(RunOutcome::Victory, 7000));
+
+        assert_eq!(history.victories(), 2);
+        assert_eq!(history.fastest_victory().unwrap().turns, 7000);
+    }
+
+    #[test]
+    fn test_found_artifacts_collect_across_runs() {
+        let mut history = RunHistory::default();
+        let mut stats = RunStatistics::default();
+        stats.artifacts_found = vec!["emberfang".to_string()];
+        history.record(RunRecord::new("A".to_string(), 3, RunOutcome::Abandoned, 10, &stats));
+        stats.artifacts_found.push("thirstblade".to_string());
+        history.record(RunRecord::new("B".to_string(), 7, RunOutcome::Victory, 20, &stats));
+
+        let found = history.found_artifacts();
+        assert_eq!(found.get("emberfang"), Some(&2));
+        assert_eq!(found.get("thirstblade"), Some(&1));
+    }
+
+    #[test]
+    fn test_history_round_trips_through_disk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.json");
+        let path = path.to_str().unwrap();
+
+        let mut history = RunHistory::default();
+        history.record(record(RunOutcome::Victory, 1234));
+        history.save(path).unwrap();
+
+        let loaded = RunHistory::load(path).unwrap();
+        assert_eq!(loaded.runs.len(), 1);
+        assert_eq!(loaded.runs[0].turns, 1234);
+    }
+}
@@ -4,6 +4,11 @@ use std::io::{Read, Write, BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use crate::persistence::serialization::{SaveData, SerializationResult, SerializationError};
+use crate::persistence::version_manager::{VersionManager, CompatibilityReport, create_version_manager};
+use crate::map::Location;
+
+/// Where the game keeps its save slots, beside the game
+pub const SAVES_DIR: &str = "saves";
 
 /// Save system errors
 #[derive(Debug, Clone)]
@@ -15,8 +20,27 @@ pub enum SaveError {
     PermissionDenied(String),
     DiskFull,
     CorruptedSave(String),
+    /// Every slot already holds a save
+    NoFreeSlot,
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::IoError(msg) => write!(f, "IO error: {}", msg),
+            SaveError::SerializationError(error) => write!(f, "{}", error),
+            SaveError::SlotNotFound(slot) => write!(f, "Save slot {} not found", slot + 1),
+            SaveError::InvalidSaveFile(msg) => write!(f, "Invalid save file: {}", msg),
+            SaveError::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
+            SaveError::DiskFull => write!(f, "Disk full"),
+            SaveError::CorruptedSave(msg) => write!(f, "Corrupted save: {}", msg),
+            SaveError::NoFreeSlot => write!(f, "Every save slot is in use"),
+        }
+    }
 }
 
+impl std::error::Error for SaveError {}
+
 impl From<SerializationError> for SaveError {
     fn from(error: SerializationError) -> Self {
         SaveError::SerializationError(error)
@@ -40,6 +64,9 @@ pub struct SaveSlot {
     pub is_occupied: bool,
     pub is_corrupted: bool,
     pub backup_available: bool,
+    /// Whether this build can load it, and what loading it would upgrade
+    #[serde(default)]
+    pub compatibility: Option<CompatibilityReport>,
 }
 
 /// Save file metadata
@@ -49,6 +76,8 @@ pub struct SaveMetadata {
     pub player_name: String,
     pub character_level: i32,
     pub current_depth: i32,
+    #[serde(default)]
+    pub current_location: Location,
     pub playtime_seconds: u64,
     pub created_at: u64,
     pub last_saved: u64,
@@ -57,6 +86,9 @@ pub struct SaveMetadata {
     pub achievements_count: u32,
     pub difficulty: String,
     pub seed: Option<u64>,
+    /// Written by an ironman run, which keeps this one slot and nothing else
+    #[serde(default)]
+    pub ironman: bool,
 }
 
 impl SaveMetadata {
@@ -71,6 +103,7 @@ impl SaveMetadata {
             player_name,
             character_level: 1,
             current_depth: 1,
+            current_location: Location::default(),
             playtime_seconds: 0,
             created_at: now,
             last_saved: now,
@@ -79,6 +112,7 @@ impl SaveMetadata {
             achievements_count: 0,
             difficulty: "Normal".to_string(),
             seed: None,
+            ironman: false,
         }
     }
 
@@ -140,12 +174,14 @@ impl SaveFile {
 }
 
 /// Main save system
+#[derive(Clone)]
 pub struct SaveSystem {
     save_directory: PathBuf,
     max_save_slots: u32,
     backup_count: u32,
     auto_backup: bool,
     compression_enabled: bool,
+    version_manager: VersionManager,
 }
 
 impl SaveSystem {
@@ -161,6 +197,7 @@ impl SaveSystem {
             backup_count: 3,
             auto_backup: true,
             compression_enabled: true,
+            version_manager: create_version_manager(),
         })
     }
 
@@ -207,8 +244,16 @@ impl SaveSystem {
         Ok(())
     }
 
-    /// Load game from a specific slot
+    /// Load game from a specific slot, upgraded to the current save format
     pub fn load_from_slot(&self, slot_id: u32) -> SaveResult<SaveFile> {
+        let mut save_file = self.read_slot(slot_id)?;
+        save_file.data = self.version_manager.upgrade(save_file.data)?;
+        save_file.calculate_checksum();
+        Ok(save_file)
+    }
+
+    /// Read a slot as it was saved
+    fn read_slot(&self, slot_id: u32) -> SaveResult<SaveFile> {
         if slot_id >= self.max_save_slots {
             return Err(SaveError::SlotNotFound(slot_id));
         }
@@ -242,16 +287,20 @@ impl SaveSystem {
             let is_occupied = file_path.exists();
             let backup_available = self.get_backup_file_path(slot_id, 0).exists();
 
-            let (metadata, is_corrupted) = if is_occupied {
-                match self.load_slot_metadata(slot_id) {
-                    Ok(meta) => (meta, false),
+            let (metadata, is_corrupted, compatibility) = if is_occupied {
+                match self.read_slot(slot_id) {
+                    Ok(save_file) => {
+                        let report = self.version_manager.compatibility_report(&save_file.data);
+                        (save_file.metadata, false, Some(report))
+                    }
                     Err(_) => (
                         SaveMetadata::new("Corrupted Save".to_string(), "Unknown".to_string()),
-                        true
+                        true,
+                        None,
                     ),
                 }
             } else {
-                (SaveMetadata::new("Empty Slot".to_string(), "".to_string()), false)
+                (SaveMetadata::new("Empty Slot".to_string(), "".to_string()), false, None)
             };
 
             slots.push(SaveSlot {
@@ -261,12 +310,31 @@ impl SaveSystem {
                 is_occupied,
                 is_corrupted,
                 backup_available,
+                compatibility,
             });
         }
 
         Ok(slots)
     }
 
+    /// The loadable slot whose file was written to last, for picking up where the player left off
+    pub fn most_recent_slot(&self) -> SaveResult<Option<SaveSlot>> {
+        let modified = |slot: &SaveSlot| std::fs::metadata(&slot.file_path).and_then(|meta| meta.modified()).ok();
+        Ok(self.get_save_slots()?.into_iter()
+            .filter(|slot| slot.is_occupied && !slot.is_corrupted)
+            .max_by_key(|slot| (modified(slot), slot.metadata.last_saved)))
+    }
+
+    /// The slot an ironman character saves to: the one it already holds, or else the first empty one
+    pub fn ironman_slot(&self, player_name: &str) -> SaveResult<u32> {
+        let slots = self.get_save_slots()?;
+        slots.iter()
+            .find(|slot| slot.is_occupied && slot.metadata.ironman && slot.metadata.player_name == player_name)
+            .or_else(|| slots.iter().find(|slot| !slot.is_occupied))
+            .map(|slot| slot.slot_id)
+            .ok_or(SaveError::NoFreeSlot)
+    }
+
     /// Delete a save slot
     pub fn delete_slot(&self, slot_id: u32) -> SaveResult<()> {
         if slot_id >= self.max_save_slots {
@@ -448,11 +516,6 @@ impl SaveSystem {
         Ok(save_file)
     }
 
-    fn load_slot_metadata(&self, slot_id: u32) -> SaveResult<SaveMetadata> {
-        let save_file = self.load_from_slot(slot_id)?;
-        Ok(save_file.metadata)
-    }
-
     fn update_slot_metadata(&self, slot_id: u32, metadata: &SaveMetadata) -> SaveResult<()> {
         let metadata_path = self.get_metadata_file_path(slot_id);
         let file = File::create(&metadata_path)?;
@@ -576,6 +639,22 @@ mod tests {
         assert!(slots[1..].iter().all(|slot| !slot.is_occupied));
     }
 
+    #[test]
+    fn test_most_recent_slot_is_the_last_one_written() {
+        let (save_system, _temp_dir) = create_test_save_system();
+        assert!(save_system.most_recent_slot().unwrap().is_none());
+
+        for (slot, player) in [(3, "Ada"), (1, "Brom")] {
+            let save_data = SaveData::new("Test Game".to_string(), player.to_string());
+            let metadata = SaveMetadata::new("Test Save".to_string(), player.to_string());
+            save_system.save_to_slot(slot, save_data, metadata).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        let latest = save_system.most_recent_slot().unwrap().unwrap();
+        assert_eq!((latest.slot_id, latest.metadata.player_name.as_str()), (1, "Brom"));
+    }
+
     #[test]
     fn test_delete_slot() {
         let (save_system, _temp_dir) = create_test_save_system();
@@ -595,6 +674,30 @@ mod tests {
         assert!(save_system.load_from_slot(0).is_err());
     }
 
+    #[test]
+    fn test_ironman_characters_keep_rolling_the_same_slot() {
+        let (save_system, _temp_dir) = create_test_save_system();
+        let save = |slot: u32, player: &str, ironman: bool| {
+            let mut metadata = SaveMetadata::new("Test Save".to_string(), player.to_string());
+            metadata.ironman = ironman;
+            save_system.save_to_slot(slot, SaveData::new("Test Game".to_string(), player.to_string()), metadata).unwrap();
+        };
+        save(0, "Aria", false);
+        assert_eq!(save_system.ironman_slot("Aria").unwrap(), 1);
+
+        save(1, "Aria", true);
+        save(2, "Brom", true);
+        assert_eq!(save_system.ironman_slot("Aria").unwrap(), 1);
+        assert_eq!(save_system.ironman_slot("Brom").unwrap(), 2);
+        assert_eq!(save_system.ironman_slot("Cale").unwrap(), 3);
+
+        for slot in 3..save_system.max_save_slots {
+            save(slot, "Dara", false);
+        }
+        assert!(matches!(save_system.ironman_slot("Cale"), Err(SaveError::NoFreeSlot)));
+        assert_eq!(save_system.ironman_slot("Brom").unwrap(), 2);
+    }
+
     #[test]
     fn test_invalid_slot() {
         let (save_system, _temp_dir) = create_test_save_system();
@@ -1,8 +1,16 @@
 use serde::{Serialize, Deserialize, de::DeserializeOwned};
-use specs::{World, Entity, Component, VecStorage, DenseVecStorage, HashMapStorage, NullStorage, Join, WorldExt, ReadStorage, WriteStorage};
+use specs::{World, Entity, Component, VecStorage, DenseVecStorage, HashMapStorage, NullStorage, Join, WorldExt, ReadStorage, WriteStorage, Builder};
+use specs::saveload::{Marker, MarkerAllocator, SimpleMarker, SimpleMarkerAllocator};
 use std::collections::HashMap;
 use std::any::{Any, TypeId};
 use std::fmt;
+use crate::components::SerializeMe;
+use super::entity_refs;
+use super::version_manager::{CURRENT_SAVE_SCHEMA, SCHEMA_KEY};
+
+/// The marker saved entities carry, and what hands them out
+pub type SaveMarker = SimpleMarker<SerializeMe>;
+pub type SaveMarkerAllocator = SimpleMarkerAllocator<SerializeMe>;
 
 /// Serialization errors
 #[derive(Debug, Clone)]
@@ -58,14 +66,47 @@ pub enum StorageType {
 pub struct SerializedComponent {
     pub component_name: String,
     pub storage_type: StorageType,
-    pub data: Vec<u8>,
-    pub entity_mapping: HashMap<u32, usize>, // Entity ID -> Index in data
+    /// Each entity's component, by the entity's save marker
+    pub entries: Vec<(u64, Vec<u8>)>,
 }
 
 /// Component serializer trait
 pub trait ComponentSerializer {
     fn serialize_component(&self, world: &World, component_name: &str) -> SerializationResult<SerializedComponent>;
-    fn deserialize_component(&self, world: &mut World, data: &SerializedComponent) -> SerializationResult<()>;
+    /// Load the component back onto the entities now carrying each saved marker
+    fn deserialize_component(&self, world: &mut World, data: &SerializedComponent, entities: &HashMap<u64, Entity>) -> SerializationResult<()>;
+}
+
+/// Give a save marker to every living entity that lacks one, such as those systems create
+/// on the fly, so that anything can be saved and referred to
+pub fn mark_entities(world: &World) {
+    let entities = world.entities();
+    let mut markers = world.write_storage::<SaveMarker>();
+    let mut allocator = world.write_resource::<SaveMarkerAllocator>();
+    let unmarked: Vec<Entity> = (&entities, !&markers).join().map(|(entity, _)| entity).collect();
+    for entity in unmarked {
+        allocator.mark(entity, &mut markers);
+    }
+}
+
+/// The save marker of every marked entity
+pub fn saved_markers(world: &World) -> HashMap<Entity, u64> {
+    let entities = world.entities();
+    let markers = world.read_storage::<SaveMarker>();
+    (&entities, &markers).join().map(|(entity, marker)| (entity, marker.id())).collect()
+}
+
+/// Bring back the entity carrying each of these save markers, creating those not yet loaded
+pub fn restore_entities(world: &mut World, markers: impl IntoIterator<Item = u64>) -> HashMap<u64, Entity> {
+    // Forget entities deleted since the markers were last handed out
+    world.maintain();
+    let entities = world.entities();
+    let mut storage = world.write_storage::<SaveMarker>();
+    let mut allocator = world.write_resource::<SaveMarkerAllocator>();
+    allocator.maintain(&entities, &storage);
+    markers.into_iter()
+        .map(|marker| (marker, allocator.retrieve_entity(marker, &mut storage, &entities)))
+        .collect()
 }
 
 /// Main serialization system
@@ -97,37 +138,52 @@ impl SerializationSystem {
         );
     }
 
-    /// Serialize all registered components from the world
+    /// Serialize all registered components from the world, marking any entity not yet marked
     pub fn serialize_world(&self, world: &World) -> SerializationResult<Vec<SerializedComponent>> {
+        mark_entities(world);
         let mut serialized_components = Vec::new();
 
-        for component_name in self.registered_components.keys() {
-            if let Some(serializer) = self.component_serializers.get(component_name) {
-                match serializer.serialize_component(world, component_name) {
-                    Ok(serialized) => serialized_components.push(serialized),
-                    Err(e) => {
-                        eprintln!("Warning: Failed to serialize component {}: {}", component_name, e);
-                        // Continue with other components instead of failing completely
+        entity_refs::saving(saved_markers(world), || {
+            for component_name in self.registered_components.keys() {
+                if let Some(serializer) = self.component_serializers.get(component_name) {
+                    match serializer.serialize_component(world, component_name) {
+                        Ok(serialized) => serialized_components.push(serialized),
+                        Err(e) => {
+                            eprintln!("Warning: Failed to serialize component {}: {}", component_name, e);
+                            // Continue with other components instead of failing completely
+                        }
                     }
                 }
             }
-        }
+        });
 
         Ok(serialized_components)
     }
 
-    /// Deserialize components into the world
+    /// Deserialize components into the world, onto the entities carrying their saved markers
+    /// and with every entity reference pointed at the entity now carrying the marker saved for it
     pub fn deserialize_world(&self, world: &mut World, components: &[SerializedComponent]) -> SerializationResult<()> {
-        for component_data in components {
-            if let Some(serializer) = self.component_serializers.get(&component_data.component_name) {
-                serializer.deserialize_component(world, component_data)?;
-            } else {
-                eprintln!("Warning: No serializer found for component: {}", component_data.component_name);
-                // Continue with other components
+        let markers: Vec<u64> = components.iter()
+            .flat_map(|component| component.entries.iter().map(|(marker, _)| *marker))
+            .collect();
+        let entities = restore_entities(world, markers);
+
+        // References to entities that were gone before saving point at one that is gone now
+        let dead = world.create_entity().build();
+        world.delete_entity(dead)
+            .map_err(|e| SerializationError::DeserializationFailed(e.to_string()))?;
+
+        entity_refs::loading(entities.clone(), dead, || {
+            for component_data in components {
+                if let Some(serializer) = self.component_serializers.get(&component_data.component_name) {
+                    serializer.deserialize_component(world, component_data, &entities)?;
+                } else {
+                    eprintln!("Warning: No serializer found for component: {}", component_data.component_name);
+                    // Continue with other components
+                }
             }
-        }
-
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Get list of registered component names
@@ -163,47 +219,34 @@ where
 {
     fn serialize_component(&self, world: &World, component_name: &str) -> SerializationResult<SerializedComponent> {
         let storage = world.read_storage::<T>();
-        let entities = world.entities();
+        let markers = world.read_storage::<SaveMarker>();
         
-        let mut component_data = Vec::new();
-        let mut entity_mapping = HashMap::new();
-        let mut index = 0;
-
-        for (entity, component) in (&entities, &storage).join() {
+        let mut entries = Vec::new();
+        for (component, marker) in (&storage, &markers).join() {
             let serialized = bincode::serialize(component)
                 .map_err(|e| SerializationError::SerializationFailed(e.to_string()))?;
-            
-            component_data.extend(serialized);
-            entity_mapping.insert(entity.id(), index);
-            index += 1;
+            entries.push((marker.id(), serialized));
         }
 
         Ok(SerializedComponent {
             component_name: component_name.to_string(),
             storage_type: T::storage_type(),
-            data: component_data,
-            entity_mapping,
+            entries,
         })
     }
 
-    fn deserialize_component(&self, world: &mut World, data: &SerializedComponent) -> SerializationResult<()> {
+    fn deserialize_component(&self, world: &mut World, data: &SerializedComponent, entities: &HashMap<u64, Entity>) -> SerializationResult<()> {
         let mut storage = world.write_storage::<T>();
-        let entities = world.entities();
 
         // Clear existing components of this type
         storage.clear();
 
-        // Deserialize each component
-        for (entity_id, &index) in &data.entity_mapping {
-            if let Some(entity) = entities.entity(*entity_id) {
-                // Extract component data from the serialized data
-                // This is a simplified approach - in practice, you'd need to track data boundaries
-                let component: T = bincode::deserialize(&data.data)
-                    .map_err(|e| SerializationError::DeserializationFailed(e.to_string()))?;
-                
-                storage.insert(entity, component)
-                    .map_err(|e| SerializationError::DeserializationFailed(format!("Failed to insert component: {:?}", e)))?;
-            }
+        for (marker, bytes) in &data.entries {
+            let entity = *entities.get(marker).ok_or(SerializationError::EntityNotFound(*marker as u32))?;
+            let component: T = bincode::deserialize(bytes)
+                .map_err(|e| SerializationError::DeserializationFailed(e.to_string()))?;
+            storage.insert(entity, component)
+                .map_err(|e| SerializationError::DeserializationFailed(format!("Failed to insert component: {:?}", e)))?;
         }
 
         Ok(())
@@ -238,7 +281,7 @@ impl SaveData {
             playtime: 0,
             components: Vec::new(),
             resources: HashMap::new(),
-            metadata: HashMap::new(),
+            metadata: [(SCHEMA_KEY.to_string(), CURRENT_SAVE_SCHEMA.to_string())].into_iter().collect(),
         }
     }
 
@@ -394,12 +437,114 @@ impl SerializableComponent for Container {
     fn storage_type() -> StorageType { StorageType::VecStorage }
 }
 
+// Components that point at other entities
+impl SerializableComponent for Inventory {
+    fn component_name() -> &'static str { "Inventory" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Equipped {
+    fn component_name() -> &'static str { "Equipped" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Corpse {
+    fn component_name() -> &'static str { "Corpse" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Companion {
+    fn component_name() -> &'static str { "Companion" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for PackMember {
+    fn component_name() -> &'static str { "PackMember" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for OtherLevelPosition {
+    fn component_name() -> &'static str { "OtherLevelPosition" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Piety {
+    fn component_name() -> &'static str { "Piety" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Altar {
+    fn component_name() -> &'static str { "Altar" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Furniture {
+    fn component_name() -> &'static str { "Furniture" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for EnchantingStation {
+    fn component_name() -> &'static str { "EnchantingStation" }
+    fn storage_type() -> StorageType { StorageType::NullStorage }
+}
+
+impl SerializableComponent for AlchemyStation {
+    fn component_name() -> &'static str { "AlchemyStation" }
+    fn storage_type() -> StorageType { StorageType::NullStorage }
+}
+
+impl SerializableComponent for ReleasesGas {
+    fn component_name() -> &'static str { "ReleasesGas" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for GasFlask {
+    fn component_name() -> &'static str { "GasFlask" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Exposure {
+    fn component_name() -> &'static str { "Exposure" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for VaultGuardian {
+    fn component_name() -> &'static str { "VaultGuardian" }
+    fn storage_type() -> StorageType { StorageType::NullStorage }
+}
+
+impl SerializableComponent for Captive {
+    fn component_name() -> &'static str { "Captive" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Escort {
+    fn component_name() -> &'static str { "Escort" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Rival {
+    fn component_name() -> &'static str { "Rival" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for Townsperson {
+    fn component_name() -> &'static str { "Townsperson" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
+impl SerializableComponent for ForSale {
+    fn component_name() -> &'static str { "ForSale" }
+    fn storage_type() -> StorageType { StorageType::VecStorage }
+}
+
 /// Helper function to create a fully configured serialization system
 pub fn create_serialization_system() -> SerializationSystem {
     let mut system = SerializationSystem::new();
 
     // Register core components
     system.register_component::<Position>();
+    system.register_component::<OtherLevelPosition>();
     system.register_component::<Renderable>();
     system.register_component::<Player>();
     system.register_component::<Monster>();
@@ -408,6 +553,19 @@ pub fn create_serialization_system() -> SerializationSystem {
     system.register_component::<Viewshed>();
     system.register_component::<BlocksTile>();
     system.register_component::<Item>();
+    system.register_component::<Piety>();
+    system.register_component::<Altar>();
+    system.register_component::<Furniture>();
+    system.register_component::<EnchantingStation>();
+    system.register_component::<AlchemyStation>();
+    system.register_component::<ReleasesGas>();
+    system.register_component::<GasFlask>();
+    system.register_component::<Exposure>();
+    system.register_component::<VaultGuardian>();
+    system.register_component::<Captive>();
+    system.register_component::<Rival>();
+    system.register_component::<Townsperson>();
+    system.register_component::<ForSale>();
 
     // Register item components
     system.register_component::<ItemProperties>();
@@ -419,6 +577,14 @@ pub fn create_serialization_system() -> SerializationSystem {
     system.register_component::<StatusEffects>();
     system.register_component::<Container>();
 
+    // Register components that point at other entities
+    system.register_component::<Inventory>();
+    system.register_component::<Equipped>();
+    system.register_component::<Corpse>();
+    system.register_component::<Companion>();
+    system.register_component::<PackMember>();
+    system.register_component::<Escort>();
+
     system
 }
 
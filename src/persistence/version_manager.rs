@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use crate::persistence::serialization::{SerializationResult, SerializationError, SaveData};
+use crate::persistence::migrations::register_migrations;
 
 /// Save file version information
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -93,7 +94,7 @@ impl SaveVersion {
 }
 
 /// Version compatibility status
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum VersionCompatibility {
     Exact,           // Same version
     Compatible,      // Compatible, no migration needed
@@ -110,37 +111,117 @@ pub enum MigrationResult {
     NotNeeded,
 }
 
+/// Schema of the saves this build writes; see `persistence::migrations` for what each one changed
+pub const CURRENT_SAVE_SCHEMA: u32 = 6;
+
+/// Save metadata key holding its schema. It lives in the metadata rather than a field of its own
+/// so that saves written before schemas existed still decode.
+pub const SCHEMA_KEY: &str = "schema";
+
+/// Schema a save was written with; saves that never recorded one are from the first release
+pub fn save_schema(save_data: &SaveData) -> u32 {
+    save_data.metadata.get(SCHEMA_KEY)
+        .and_then(|schema| schema.parse().ok())
+        .unwrap_or(1)
+}
+
+pub fn set_save_schema(save_data: &mut SaveData, schema: u32) {
+    save_data.metadata.insert(SCHEMA_KEY.to_string(), schema.to_string());
+}
+
+/// One step between schemas, from `from` to the one after it
+#[derive(Clone)]
+pub struct SaveMigration {
+    pub from: u32,
+    pub description: &'static str,
+    pub migrate: fn(SaveData) -> SerializationResult<SaveData>,
+}
+
+impl SaveMigration {
+    pub fn new(from: u32, description: &'static str, migrate: fn(SaveData) -> SerializationResult<SaveData>) -> Self {
+        SaveMigration { from, description, migrate }
+    }
+
+    pub fn key(&self) -> String {
+        format!("{}->{}", self.from, self.from + 1)
+    }
+}
+
+/// What this build makes of a save, for the load screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompatibilityReport {
+    /// Version of the game that wrote it
+    pub game_version: String,
+    pub schema: u32,
+    pub status: VersionCompatibility,
+    /// What loading it will change, in the order it happens
+    pub migrations: Vec<String>,
+}
+
+impl CompatibilityReport {
+    pub fn can_load(&self) -> bool {
+        !matches!(self.status, VersionCompatibility::TooNew | VersionCompatibility::Incompatible)
+    }
+
+    /// A word or two for the list of saves
+    pub fn summary(&self) -> &'static str {
+        match self.status {
+            VersionCompatibility::Exact | VersionCompatibility::Compatible => "OK",
+            VersionCompatibility::NeedsMigration => "Upgrade",
+            VersionCompatibility::TooNew => "Too new",
+            VersionCompatibility::Incompatible => "Unsupported",
+        }
+    }
+
+    /// The full story, for the save's details
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Saved by version {} (save format {})", self.game_version, self.schema)];
+        match self.status {
+            VersionCompatibility::Exact | VersionCompatibility::Compatible => {
+                lines.push("Loads as it is.".to_string());
+            }
+            VersionCompatibility::NeedsMigration => {
+                lines.push(format!("Will be upgraded to save format {} on loading:", CURRENT_SAVE_SCHEMA));
+                lines.extend(self.migrations.iter().map(|step| format!("  {}", step)));
+            }
+            VersionCompatibility::TooNew => {
+                lines.push("Made by a newer version of the game; update to load it.".to_string());
+            }
+            VersionCompatibility::Incompatible => {
+                lines.push("This version of the game cannot read it.".to_string());
+            }
+        }
+        lines
+    }
+}
+
 /// Version manager for handling save compatibility and migrations
+#[derive(Clone)]
 pub struct VersionManager {
     current_version: SaveVersion,
-    migrations: HashMap<String, Box<dyn SaveMigration>>,
+    current_schema: u32,
+    migrations: BTreeMap<u32, SaveMigration>,
     compatibility_rules: HashMap<String, VersionCompatibility>,
 }
 
-/// Trait for save file migrations
-pub trait SaveMigration: Send + Sync {
-    fn migrate(&self, save_data: SaveData) -> SerializationResult<SaveData>;
-    fn from_version(&self) -> SaveVersion;
-    fn to_version(&self) -> SaveVersion;
-    fn description(&self) -> &str;
-}
-
 impl VersionManager {
     pub fn new(current_version: SaveVersion) -> Self {
         VersionManager {
             current_version,
-            migrations: HashMap::new(),
+            current_schema: CURRENT_SAVE_SCHEMA,
+            migrations: BTreeMap::new(),
             compatibility_rules: HashMap::new(),
         }
     }
 
+    pub fn with_schema(mut self, schema: u32) -> Self {
+        self.current_schema = schema;
+        self
+    }
+
     /// Register a migration
-    pub fn register_migration(&mut self, migration: Box<dyn SaveMigration>) {
-        let key = format!("{}->{}", 
-            migration.from_version().to_string(), 
-            migration.to_version().to_string()
-        );
-        self.migrations.insert(key, migration);
+    pub fn register_migration(&mut self, migration: SaveMigration) {
+        self.migrations.insert(migration.from, migration);
     }
 
     /// Set compatibility rule for a specific version
@@ -152,101 +233,80 @@ impl VersionManager {
     pub fn check_compatibility(&self, save_version: &SaveVersion) -> VersionCompatibility {
         // Check explicit compatibility rules first
         if let Some(compatibility) = self.compatibility_rules.get(&save_version.to_string()) {
-            return compatibility.clone();
+            return *compatibility;
         }
 
         // Use default compatibility logic
         self.current_version.is_compatible_with(save_version)
     }
 
-    /// Migrate save data to current version
-    pub fn migrate_save(&self, mut save_data: SaveData) -> SerializationResult<MigrationResult> {
-        let save_version = SaveVersion::from_string(&save_data.version)
-            .map_err(|e| SerializationError::InvalidFormat(e))?;
-
-        let compatibility = self.check_compatibility(&save_version);
+    /// How a save stands against this build. The schema decides whether it can be read; the
+    /// game version only tells an exact match from a compatible one.
+    pub fn compatibility_report(&self, save_data: &SaveData) -> CompatibilityReport {
+        let schema = save_schema(save_data);
+        let path = self.migration_path(schema);
+        let status = if schema > self.current_schema {
+            VersionCompatibility::TooNew
+        } else if path.is_none() {
+            VersionCompatibility::Incompatible
+        } else if schema < self.current_schema {
+            VersionCompatibility::NeedsMigration
+        } else if save_data.version == self.current_version.to_string() {
+            VersionCompatibility::Exact
+        } else {
+            VersionCompatibility::Compatible
+        };
+        CompatibilityReport {
+            game_version: save_data.version.clone(),
+            schema,
+            status,
+            migrations: path.unwrap_or_default().iter().map(|step| step.description.to_string()).collect(),
+        }
+    }
 
-        match compatibility {
-            VersionCompatibility::Exact | VersionCompatibility::Compatible => {
-                return Ok(MigrationResult::NotNeeded);
-            }
-            VersionCompatibility::TooNew => {
-                return Err(SerializationError::VersionMismatch {
-                    expected: self.current_version.to_string(),
-                    found: save_version.to_string(),
-                });
-            }
-            VersionCompatibility::Incompatible => {
+    /// Migrate save data to the current schema, one step at a time
+    pub fn migrate_save(&self, mut save_data: SaveData) -> SerializationResult<MigrationResult> {
+        let schema = save_schema(&save_data);
+        if schema == self.current_schema {
+            return Ok(MigrationResult::NotNeeded);
+        }
+        let path = match self.migration_path(schema) {
+            Some(path) if schema < self.current_schema => path,
+            _ => {
                 return Err(SerializationError::VersionMismatch {
-                    expected: self.current_version.to_string(),
-                    found: save_version.to_string(),
+                    expected: format!("save format {} or older", self.current_schema),
+                    found: format!("save format {}", schema),
                 });
             }
-            VersionCompatibility::NeedsMigration => {
-                // Continue with migration
-            }
-        }
-
-        // Find migration path
-        let migration_path = self.find_migration_path(&save_version, &self.current_version)?;
+        };
 
-        // Apply migrations in sequence
-        for migration_key in migration_path {
-            if let Some(migration) = self.migrations.get(&migration_key) {
-                save_data = migration.migrate(save_data)
-                    .map_err(|e| SerializationError::DeserializationFailed(
-                        format!("Migration failed: {}", e)
-                    ))?;
-            } else {
-                return Err(SerializationError::DeserializationFailed(
-                    format!("Migration not found: {}", migration_key)
-                ));
-            }
+        for step in path {
+            save_data = (step.migrate)(save_data)
+                .map_err(|e| SerializationError::DeserializationFailed(
+                    format!("Migration {} failed: {}", step.key(), e)
+                ))?;
+            set_save_schema(&mut save_data, step.from + 1);
         }
-
-        // Update version in save data
         save_data.version = self.current_version.to_string();
 
         Ok(MigrationResult::Success(save_data))
     }
 
-    /// Find migration path from one version to another
-    fn find_migration_path(&self, from: &SaveVersion, to: &SaveVersion) -> SerializationResult<Vec<String>> {
-        // This is a simplified implementation
-        // In practice, you might need a more sophisticated pathfinding algorithm
-        
-        let mut path = Vec::new();
-        let mut current = from.clone();
-
-        // For now, we'll only handle direct migrations
-        while current != *to {
-            let next_version = self.find_next_migration_step(&current, to)?;
-            let migration_key = format!("{}->{}", current.to_string(), next_version.to_string());
-            
-            if self.migrations.contains_key(&migration_key) {
-                path.push(migration_key);
-                current = next_version;
-            } else {
-                return Err(SerializationError::DeserializationFailed(
-                    format!("No migration path found from {} to {}", from.to_string(), to.to_string())
-                ));
-            }
+    /// Bring a save up to the current schema, whether or not it needed it
+    pub fn upgrade(&self, save_data: SaveData) -> SerializationResult<SaveData> {
+        match self.migrate_save(save_data.clone())? {
+            MigrationResult::Success(migrated) => Ok(migrated),
+            MigrationResult::NotNeeded => Ok(save_data),
+            MigrationResult::Failed(reason) => Err(SerializationError::DeserializationFailed(reason)),
         }
-
-        Ok(path)
     }
 
-    fn find_next_migration_step(&self, from: &SaveVersion, to: &SaveVersion) -> SerializationResult<SaveVersion> {
-        // Simple strategy: increment minor version if different, otherwise patch
-        if from.minor < to.minor {
-            Ok(SaveVersion::new(from.major, from.minor + 1, 0))
-        } else if from.patch < to.patch {
-            Ok(SaveVersion::new(from.major, from.minor, from.patch + 1))
-        } else {
-            Err(SerializationError::DeserializationFailed(
-                "Cannot determine next migration step".to_string()
-            ))
+    /// The steps from `schema` to the current one, or None if one is missing
+    fn migration_path(&self, schema: u32) -> Option<Vec<&SaveMigration>> {
+        if schema == 0 {
+            return None;
         }
+        (schema..self.current_schema).map(|from| self.migrations.get(&from)).collect()
     }
 
     /// Get current version
@@ -254,9 +314,13 @@ impl VersionManager {
         &self.current_version
     }
 
+    pub fn current_schema(&self) -> u32 {
+        self.current_schema
+    }
+
     /// Get available migrations
     pub fn get_available_migrations(&self) -> Vec<String> {
-        self.migrations.keys().cloned().collect()
+        self.migrations.values().map(SaveMigration::key).collect()
     }
 
     /// Validate save data version
@@ -284,85 +348,13 @@ impl VersionManager {
     }
 }
 
-/// Example migration implementations
-pub struct Migration_0_1_0_to_0_2_0;
-
-impl SaveMigration for Migration_0_1_0_to_0_2_0 {
-    fn migrate(&self, mut save_data: SaveData) -> SerializationResult<SaveData> {
-        // Example migration: add new metadata field
-        save_data.metadata.insert("migration_applied".to_string(), "0.1.0->0.2.0".to_string());
-        
-        // Example: modify component data structure
-        for component in &mut save_data.components {
-            if component.component_name == "CombatStats" {
-                // Migrate old combat stats format to new format
-                // This would involve deserializing, modifying, and re-serializing
-            }
-        }
-
-        Ok(save_data)
-    }
-
-    fn from_version(&self) -> SaveVersion {
-        SaveVersion::new(0, 1, 0)
-    }
-
-    fn to_version(&self) -> SaveVersion {
-        SaveVersion::new(0, 2, 0)
-    }
-
-    fn description(&self) -> &str {
-        "Migrate from 0.1.0 to 0.2.0: Add new combat stats fields"
-    }
-}
-
-pub struct Migration_0_2_0_to_0_3_0;
-
-impl SaveMigration for Migration_0_2_0_to_0_3_0 {
-    fn migrate(&self, mut save_data: SaveData) -> SerializationResult<SaveData> {
-        // Example migration: update inventory system
-        save_data.metadata.insert("inventory_system_updated".to_string(), "true".to_string());
-        
-        // Remove deprecated components
-        save_data.components.retain(|c| c.component_name != "OldInventory");
-        
-        Ok(save_data)
-    }
-
-    fn from_version(&self) -> SaveVersion {
-        SaveVersion::new(0, 2, 0)
-    }
-
-    fn to_version(&self) -> SaveVersion {
-        SaveVersion::new(0, 3, 0)
-    }
-
-    fn description(&self) -> &str {
-        "Migrate from 0.2.0 to 0.3.0: Update inventory system"
-    }
-}
-
-/// Helper function to create a version manager with common migrations
+/// Helper function to create a version manager with every migration registered
 pub fn create_version_manager() -> VersionManager {
     let current_version = SaveVersion::from_string(env!("CARGO_PKG_VERSION"))
         .unwrap_or_else(|_| SaveVersion::new(0, 1, 0));
 
     let mut manager = VersionManager::new(current_version);
-
-    // Register migrations
-    manager.register_migration(Box::new(Migration_0_1_0_to_0_2_0));
-    manager.register_migration(Box::new(Migration_0_2_0_to_0_3_0));
-
-    // Set compatibility rules
-    manager.set_compatibility_rule(
-        SaveVersion::new(0, 1, 0),
-        VersionCompatibility::NeedsMigration
-    );
-    manager.set_compatibility_rule(
-        SaveVersion::new(0, 2, 0),
-        VersionCompatibility::NeedsMigration
-    );
-
+    register_migrations(&mut manager);
     manager
 }
 
@@ -437,14 +429,31 @@ mod tests {
         assert!(manager.get_available_migrations().is_empty());
     }
 
+    /// Notes each step it is run for in the save's metadata
+    fn note_step(mut save_data: SaveData) -> SerializationResult<SaveData> {
+        let step = save_schema(&save_data).to_string();
+        save_data.metadata.entry("steps".to_string()).or_default().push_str(&step);
+        Ok(save_data)
+    }
+
+    fn manager_with_steps(steps: &[u32]) -> VersionManager {
+        let mut manager = VersionManager::new(SaveVersion::new(1, 0, 0)).with_schema(3);
+        for &from in steps {
+            manager.register_migration(SaveMigration::new(from, "Note the step", note_step));
+        }
+        manager
+    }
+
+    fn save_with_schema(schema: u32) -> SaveData {
+        let mut save_data = SaveData::new("Test".to_string(), "Player".to_string());
+        set_save_schema(&mut save_data, schema);
+        save_data
+    }
+
     #[test]
     fn test_migration_registration() {
-        let mut manager = VersionManager::new(SaveVersion::new(0, 2, 0));
-        manager.register_migration(Box::new(Migration_0_1_0_to_0_2_0));
-        
-        let migrations = manager.get_available_migrations();
-        assert_eq!(migrations.len(), 1);
-        assert!(migrations.contains(&"0.1.0->0.2.0".to_string()));
+        let manager = manager_with_steps(&[2, 1]);
+        assert_eq!(manager.get_available_migrations(), vec!["1->2".to_string(), "2->3".to_string()]);
     }
 
     #[test]
@@ -475,25 +484,48 @@ mod tests {
     }
 
     #[test]
-    fn test_migration_execution() {
-        let migration = Migration_0_1_0_to_0_2_0;
-        let save_data = SaveData::new("Test".to_string(), "Player".to_string());
-        
-        let migrated = migration.migrate(save_data).unwrap();
-        assert!(migrated.metadata.contains_key("migration_applied"));
-        assert_eq!(migrated.metadata.get("migration_applied"), Some(&"0.1.0->0.2.0".to_string()));
+    fn test_migrations_run_in_order_up_to_the_current_schema() {
+        let manager = manager_with_steps(&[1, 2]);
+        let old = save_with_schema(1);
+        let report = manager.compatibility_report(&old);
+        assert_eq!(report.status, VersionCompatibility::NeedsMigration);
+        assert_eq!(report.migrations.len(), 2);
+        assert!(report.can_load());
+
+        let migrated = manager.upgrade(old).unwrap();
+        assert_eq!(migrated.metadata.get("steps").map(String::as_str), Some("12"));
+        assert_eq!(save_schema(&migrated), 3);
+        assert_eq!(manager.compatibility_report(&migrated).status, VersionCompatibility::Exact);
+        assert!(matches!(manager.migrate_save(migrated), Ok(MigrationResult::NotNeeded)));
+    }
+
+    #[test]
+    fn test_saves_without_a_path_forward_are_refused() {
+        let manager = manager_with_steps(&[2]);
+        let report = manager.compatibility_report(&save_with_schema(1));
+        assert_eq!(report.status, VersionCompatibility::Incompatible);
+        assert!(!report.can_load());
+        assert!(manager.upgrade(save_with_schema(1)).is_err());
+
+        let report = manager.compatibility_report(&save_with_schema(4));
+        assert_eq!(report.status, VersionCompatibility::TooNew);
+        assert!(manager.upgrade(save_with_schema(4)).is_err());
+
+        let mut other_build = save_with_schema(3);
+        other_build.version = "1.0.7".to_string();
+        assert_eq!(manager.compatibility_report(&other_build).status, VersionCompatibility::Compatible);
     }
 
     #[test]
     fn test_create_version_manager() {
         let manager = create_version_manager();
-        
-        assert!(!manager.get_available_migrations().is_empty());
-        
-        // Should have registered migrations
+        assert_eq!(manager.current_schema(), CURRENT_SAVE_SCHEMA);
+
+        // Every schema before this one can be brought up to it
         let migrations = manager.get_available_migrations();
-        assert!(migrations.iter().any(|m| m.contains("0.1.0->0.2.0")));
-        assert!(migrations.iter().any(|m| m.contains("0.2.0->0.3.0")));
+        assert_eq!(migrations.len() as u32, CURRENT_SAVE_SCHEMA - 1);
+        let report = manager.compatibility_report(&SaveData::new("Test".to_string(), "Player".to_string()));
+        assert_eq!(report.status, VersionCompatibility::Exact);
     }
 
     #[test]
@@ -1,9 +1,15 @@
 use serde::{Serialize, Deserialize};
 use specs::{World, Entity, WorldExt, Builder, Join};
 use std::collections::HashMap;
-use crate::persistence::serialization::{SerializationSystem, SerializationResult, SerializationError, SerializedComponent};
-use crate::map::Map;
-use crate::resources::{GameLog, RandomNumberGenerator};
+use specs::saveload::Marker;
+use crate::persistence::serialization::{
+    SerializationSystem, SerializationResult, SerializationError, SerializedComponent, SaveMarker,
+    SaveData, mark_entities, restore_entities,
+};
+use crate::map::{Map, LevelTension, DungeonLevels};
+use crate::resources::{GameLog, RandomNumberGenerator, GameClock};
+use crate::items::AlchemyJournal;
+use crate::systems::{Reputation, TownWatch};
 
 /// Complete world state for serialization
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,9 +22,27 @@ pub struct WorldState {
     pub metadata: HashMap<String, String>,
 }
 
+impl WorldState {
+    /// The world held in a save slot; its entities come back from the markers its components carry
+    pub fn from_save_data(data: &SaveData) -> Self {
+        WorldState {
+            entities: Vec::new(),
+            components: data.components.clone(),
+            resources: data.resources.iter()
+                .map(|(name, bytes)| (name.clone(), ResourceData { resource_name: name.clone(), data: bytes.clone() }))
+                .collect(),
+            next_entity_id: 0,
+            generation: 0,
+            metadata: data.metadata.clone(),
+        }
+    }
+}
+
 /// Entity data for serialization
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntityData {
+    /// Save marker the entity is known by in the save; its components and references to it use this
+    pub marker: u64,
     pub id: u32,
     pub generation: u32,
     pub component_mask: Vec<String>, // List of component names this entity has
@@ -62,6 +86,12 @@ impl WorldSerializer {
         serializer.register_resource_serializer::<Map>("Map");
         serializer.register_resource_serializer::<GameLog>("GameLog");
         serializer.register_resource_serializer::<RandomNumberGenerator>("RandomNumberGenerator");
+        serializer.register_resource_serializer::<AlchemyJournal>("AlchemyJournal");
+        serializer.register_resource_serializer::<GameClock>("GameClock");
+        serializer.register_resource_serializer::<LevelTension>("LevelTension");
+        serializer.register_resource_serializer::<DungeonLevels>("DungeonLevels");
+        serializer.register_resource_serializer::<Reputation>("Reputation");
+        serializer.register_resource_serializer::<TownWatch>("TownWatch");
 
         serializer
     }
@@ -79,6 +109,9 @@ impl WorldSerializer {
 
     /// Serialize the entire world state
     pub fn serialize_world(&self, world: &World) -> SerializationResult<WorldState> {
+        // Every entity needs a marker before anything can refer to it
+        mark_entities(world);
+
         // Serialize entities
         let entities = self.serialize_entities(world)?;
 
@@ -109,10 +142,10 @@ impl WorldSerializer {
         // Restore world metadata
         self.restore_world_metadata(world, world_state.next_entity_id, world_state.generation);
 
-        // Recreate entities
+        // Recreate entities under their saved markers
         self.deserialize_entities(world, &world_state.entities)?;
 
-        // Deserialize components
+        // Deserialize components onto them, remapping references between entities
         self.serialization_system.deserialize_world(world, &world_state.components)?;
 
         // Deserialize resources
@@ -127,9 +160,11 @@ impl WorldSerializer {
         let mut selected_entities = Vec::new();
 
         // Filter entities
-        for (entity, _) in (&entities, &world.read_storage::<crate::components::Position>()).join() {
+        let markers = world.read_storage::<SaveMarker>();
+        for (entity, _, marker) in (&entities, &world.read_storage::<crate::components::Position>(), &markers).join() {
             if entity_ids.contains(&entity.id()) {
                 selected_entities.push(EntityData {
+                    marker: marker.id(),
                     id: entity.id(),
                     generation: entity.gen().id(),
                     component_mask: self.get_entity_component_mask(world, entity),
@@ -152,10 +187,12 @@ impl WorldSerializer {
 
     fn serialize_entities(&self, world: &World) -> SerializationResult<Vec<EntityData>> {
         let entities = world.entities();
+        let markers = world.read_storage::<SaveMarker>();
         let mut entity_data = Vec::new();
 
-        for entity in entities.join() {
+        for (entity, marker) in (&entities, &markers).join() {
             entity_data.push(EntityData {
+                marker: marker.id(),
                 id: entity.id(),
                 generation: entity.gen().id(),
                 component_mask: self.get_entity_component_mask(world, entity),
@@ -166,14 +203,8 @@ impl WorldSerializer {
     }
 
     fn deserialize_entities(&self, world: &mut World, entities: &[EntityData]) -> SerializationResult<()> {
-        for entity_data in entities {
-            // Create entity with specific ID (this is a simplified approach)
-            let entity = world.create_entity().build();
-            
-            // In a real implementation, you'd need to ensure the entity ID matches
-            // This might require custom entity creation or ID mapping
-        }
-
+        // The new entities get new ids; the markers are what stay the same
+        restore_entities(world, entities.iter().map(|entity_data| entity_data.marker));
         Ok(())
     }
 
@@ -259,6 +290,7 @@ impl WorldSerializer {
     fn clear_world(&self, world: &mut World) {
         // Clear all entities and components
         world.delete_all();
+        world.maintain();
         
         // Clear resources would need to be done manually for each resource type
         // This is a limitation of the current approach
@@ -376,10 +408,16 @@ mod tests {
     use super::*;
     use specs::{World, WorldExt, Builder};
     use crate::components::*;
-    use crate::persistence::serialization::create_serialization_system;
+    use crate::persistence::serialization::{create_serialization_system, SaveMarkerAllocator};
+
+    fn register_save_markers(world: &mut World) {
+        world.register::<SaveMarker>();
+        world.insert(SaveMarkerAllocator::new());
+    }
 
     fn setup_test_world() -> World {
         let mut world = World::new();
+        register_save_markers(&mut world);
         world.register::<Position>();
         world.register::<Name>();
         world.register::<Player>();
@@ -456,6 +494,7 @@ mod tests {
         
         // Create new world and restore from snapshot
         let mut new_world = World::new();
+        register_save_markers(&mut new_world);
         new_world.register::<Position>();
         new_world.register::<Name>();
         new_world.register::<Player>();
@@ -528,4 +567,59 @@ mod tests {
             assert!(mask.contains(&"CombatStats".to_string()));
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_references_between_entities_survive_a_snapshot() {
+        let mut world = World::new();
+        register_save_markers(&mut world);
+        world.register::<Name>();
+        world.register::<Inventory>();
+        world.register::<Equipped>();
+        world.register::<Corpse>();
+
+        let sword = world.create_entity().with(Name { name: "Sword".to_string() }).build();
+        let gone = world.create_entity().with(Name { name: "Ghost".to_string() }).build();
+        let player = world.create_entity()
+            .with(Name { name: "Player".to_string() })
+            .with(Inventory { items: vec![sword], capacity: 10 })
+            .build();
+        world.write_storage::<Equipped>()
+            .insert(sword, Equipped { owner: player, slot: EquipmentSlot::Melee })
+            .unwrap();
+        world.create_entity()
+            .with(Name { name: "Ghost corpse".to_string() })
+            .with(Corpse::new(Some(gone), "Ghost", 10, false))
+            .build();
+        world.delete_entity(gone).unwrap();
+        world.maintain();
+
+        let world_serializer = WorldSerializer::new(create_serialization_system());
+        let snapshot = world_serializer.create_snapshot(&world).unwrap();
+
+        // Entities already in the new world push the loaded ones onto other ids
+        let mut new_world = World::new();
+        register_save_markers(&mut new_world);
+        new_world.register::<Name>();
+        new_world.register::<Inventory>();
+        new_world.register::<Equipped>();
+        new_world.register::<Corpse>();
+        for _ in 0..5 {
+            new_world.create_entity().build();
+        }
+        world_serializer.restore_from_snapshot(&mut new_world, &snapshot).unwrap();
+
+        let entities = new_world.entities();
+        let names = new_world.read_storage::<Name>();
+        let named = |wanted: &str| (&entities, &names).join()
+            .find(|(_, name)| name.name == wanted)
+            .map(|(entity, _)| entity)
+            .expect("entity was loaded");
+        let (player, sword) = (named("Player"), named("Sword"));
+
+        assert_eq!(new_world.read_storage::<Inventory>().get(player).unwrap().items, vec![sword]);
+        assert_eq!(new_world.read_storage::<Equipped>().get(sword).unwrap().owner, player);
+        let corpse = named("Ghost corpse");
+        let original = new_world.read_storage::<Corpse>().get(corpse).unwrap().original_entity.unwrap();
+        assert!(!entities.is_alive(original));
+    }
+}
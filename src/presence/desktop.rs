@@ -0,0 +1,19 @@
+//! Desktop notifications through the operating system's notification center
+
+use log::warn;
+use super::{Notification, PresenceHook};
+
+pub struct DesktopNotifications;
+
+impl PresenceHook for DesktopNotifications {
+    fn notify(&mut self, notification: &Notification) {
+        let shown = notify_rust::Notification::new()
+            .appname("ASCII Dungeon Explorer")
+            .summary(&notification.title)
+            .body(&notification.body)
+            .show();
+        if let Err(e) = shown {
+            warn!("Could not show desktop notification: {}", e);
+        }
+    }
+}
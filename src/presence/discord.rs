@@ -0,0 +1,70 @@
+//! Discord Rich Presence: the character, their class, depth and turn on the player's profile.
+//!
+//! Needs the id of a Discord application, given as `DISCORD_APPLICATION_ID` when building.
+//! Without one, or without Discord running, the hook is simply left out.
+
+use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+use log::warn;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use super::{PresenceHook, PresenceStatus};
+
+const APPLICATION_ID: Option<&str> = option_env!("DISCORD_APPLICATION_ID");
+
+/// Discord drops updates sent more often than this
+const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+
+pub struct DiscordPresence {
+    client: DiscordIpcClient,
+    started: i64,
+    last_sent: Option<Instant>,
+    /// The newest status, held back until Discord will take it
+    pending: Option<PresenceStatus>,
+}
+
+impl DiscordPresence {
+    /// Connect to the running Discord client, if there is one
+    pub fn connect() -> Option<Self> {
+        let application_id = APPLICATION_ID?;
+        let mut client = DiscordIpcClient::new(application_id)
+            .map_err(|e| warn!("Discord presence unavailable: {}", e))
+            .ok()?;
+        client.connect().map_err(|e| warn!("Could not connect to Discord: {}", e)).ok()?;
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64);
+        Some(DiscordPresence { client, started, last_sent: None, pending: None })
+    }
+
+    fn send(&mut self, status: &PresenceStatus) {
+        let details = status.details();
+        let state = status.state();
+        let mut activity = activity::Activity::new()
+            .details(&details)
+            .timestamps(activity::Timestamps::new().start(self.started));
+        if !state.is_empty() {
+            activity = activity.state(&state);
+        }
+        if let Err(e) = self.client.set_activity(activity) {
+            warn!("Could not update Discord presence: {}", e);
+        }
+        self.last_sent = Some(Instant::now());
+    }
+}
+
+impl PresenceHook for DiscordPresence {
+    fn set_status(&mut self, status: &PresenceStatus) {
+        self.pending = Some(status.clone());
+        self.poll();
+    }
+
+    fn poll(&mut self) {
+        if self.last_sent.map_or(true, |sent| sent.elapsed() >= MIN_UPDATE_INTERVAL) {
+            if let Some(status) = self.pending.take() {
+                self.send(&status);
+            }
+        }
+    }
+
+    fn shutdown(&mut self) {
+        let _ = self.client.clear_activity();
+        let _ = self.client.close();
+    }
+}
@@ -0,0 +1,217 @@
+//! Telling the world outside the terminal what the game is up to: rich presence on chat clients
+//! and desktop notifications when guild missions finish while the player is elsewhere.
+//!
+//! Each integration is a `PresenceHook`; the hooks built in are picked by feature flags
+//! (`discord`, `desktop_notifications`) and without either the game runs with none.
+
+#[cfg(feature = "discord")]
+pub mod discord;
+#[cfg(feature = "desktop_notifications")]
+pub mod desktop;
+
+use std::sync::Mutex;
+
+/// What the player is doing, broadly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    InMenus,
+    Exploring,
+    ManagingGuild,
+    Dead,
+    Victorious,
+}
+
+/// What presence integrations show about the current game
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresenceStatus {
+    pub activity: Activity,
+    pub character: Option<String>,
+    pub class: Option<String>,
+    pub depth: Option<i32>,
+    pub turn: u32,
+}
+
+impl PresenceStatus {
+    pub fn in_menus() -> Self {
+        PresenceStatus { activity: Activity::InMenus, character: None, class: None, depth: None, turn: 0 }
+    }
+
+    /// The headline: who is playing and what they are doing
+    pub fn details(&self) -> String {
+        let who = match (&self.character, &self.class) {
+            (Some(name), Some(class)) => format!("{} the {}", name, class),
+            (Some(name), None) => name.clone(),
+            (None, Some(class)) => format!("A {}", class),
+            (None, None) => "An adventurer".to_string(),
+        };
+        match self.activity {
+            Activity::InMenus => "In the menus".to_string(),
+            Activity::Exploring => format!("{} is exploring", who),
+            Activity::ManagingGuild => format!("{} is managing the guild", who),
+            Activity::Dead => format!("{} has died", who),
+            Activity::Victorious => format!("{} escaped with the artifact", who),
+        }
+    }
+
+    /// The second line: where and how long
+    pub fn state(&self) -> String {
+        match self.depth {
+            Some(depth) => format!("Depth {}, turn {}", depth, self.turn),
+            None => String::new(),
+        }
+    }
+}
+
+/// Something worth interrupting the player for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Notification { title: title.into(), body: body.into() }
+    }
+
+    pub fn mission_completed(mission: &str) -> Self {
+        Notification::new("Guild mission complete", format!("Your agents are back from {}.", mission))
+    }
+
+    pub fn mission_failed(mission: &str) -> Self {
+        Notification::new("Guild mission failed", format!("{} went badly. Check on your agents.", mission))
+    }
+}
+
+/// One integration; everything is a no-op unless it says otherwise
+pub trait PresenceHook {
+    /// The status changed
+    fn set_status(&mut self, _status: &PresenceStatus) {}
+    /// Show a notification to a player who is elsewhere
+    fn notify(&mut self, _notification: &Notification) {}
+    /// Called every update, for hooks holding work back
+    fn poll(&mut self) {}
+    /// The game is closing
+    fn shutdown(&mut self) {}
+}
+
+/// The hook used when no integration is built in
+pub struct NoPresence;
+
+impl PresenceHook for NoPresence {}
+
+/// Notifications posted from anywhere, such as missions finishing on other threads, until the
+/// game next updates
+static POSTED: Mutex<Vec<Notification>> = Mutex::new(Vec::new());
+
+/// Queue a notification for the game to deliver
+pub fn post(notification: Notification) {
+    if let Ok(mut posted) = POSTED.lock() {
+        posted.push(notification);
+    }
+}
+
+/// Everything posted since the last call
+pub fn take_posted() -> Vec<Notification> {
+    POSTED.lock().map(|mut posted| std::mem::take(&mut *posted)).unwrap_or_default()
+}
+
+/// Every hook in use, told about the status only when it changes
+pub struct Presence {
+    hooks: Vec<Box<dyn PresenceHook>>,
+    last_status: Option<PresenceStatus>,
+}
+
+impl Presence {
+    pub fn new(hooks: Vec<Box<dyn PresenceHook>>) -> Self {
+        Presence { hooks, last_status: None }
+    }
+
+    /// The hooks this build was compiled with
+    pub fn from_features() -> Self {
+        #[allow(unused_mut)]
+        let mut hooks: Vec<Box<dyn PresenceHook>> = Vec::new();
+        #[cfg(feature = "discord")]
+        if let Some(hook) = discord::DiscordPresence::connect() {
+            hooks.push(Box::new(hook));
+        }
+        #[cfg(feature = "desktop_notifications")]
+        hooks.push(Box::new(desktop::DesktopNotifications));
+        if hooks.is_empty() {
+            hooks.push(Box::new(NoPresence));
+        }
+        Presence::new(hooks)
+    }
+
+    pub fn update(&mut self, status: PresenceStatus) {
+        for hook in self.hooks.iter_mut() {
+            hook.poll();
+        }
+        if self.last_status.as_ref() == Some(&status) {
+            return;
+        }
+        for hook in self.hooks.iter_mut() {
+            hook.set_status(&status);
+        }
+        self.last_status = Some(status);
+    }
+
+    pub fn notify(&mut self, notification: &Notification) {
+        for hook in self.hooks.iter_mut() {
+            hook.notify(notification);
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        for hook in self.hooks.iter_mut() {
+            hook.shutdown();
+        }
+    }
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Presence::new(vec![Box::new(NoPresence)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder {
+        statuses: Arc<Mutex<Vec<PresenceStatus>>>,
+    }
+
+    impl PresenceHook for Recorder {
+        fn set_status(&mut self, status: &PresenceStatus) {
+            self.statuses.lock().unwrap().push(status.clone());
+        }
+    }
+
+    #[test]
+    fn test_hooks_only_hear_about_changes() {
+        let recorder = Recorder::default();
+        let statuses = recorder.statuses.clone();
+        let mut presence = Presence::new(vec![Box::new(recorder), Box::new(NoPresence)]);
+
+        let exploring = PresenceStatus {
+            activity: Activity::Exploring,
+            character: Some("Ada".to_string()),
+            class: Some("Rogue".to_string()),
+            depth: Some(3),
+            turn: 120,
+        };
+        presence.update(PresenceStatus::in_menus());
+        presence.update(exploring.clone());
+        presence.update(exploring.clone());
+        presence.notify(&Notification::mission_completed("the Sunken Crypt"));
+
+        let statuses = statuses.lock().unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[1].details(), "Ada the Rogue is exploring");
+        assert_eq!(statuses[1].state(), "Depth 3, turn 120");
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::Path;
+use crate::components::GameMode;
+
+/// Default location of the bank's books, kept from one character to the next
+pub const BANK_PATH: &str = "saves/bank.json";
+
+/// Extra percent a day the bank pays on an account kept in a strongroom
+pub const STRONGROOM_BONUS: i32 = 1;
+
+/// Property in Ravenhollow bought through the bank. It outlasts the character who bought it, and
+/// every character after them gets the good of it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    /// A stocked pantry: every new character sets out with a healing potion from it
+    Larder,
+    /// A rack of spare gear: every new character sets out with a piece from it
+    Armory,
+    /// A vault of one's own at the bank: no fees, and a little more interest
+    Strongroom,
+}
+
+impl Property {
+    pub fn all() -> [Property; 3] {
+        [Property::Larder, Property::Armory, Property::Strongroom]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Property::Larder => "Larder",
+            Property::Armory => "Armory",
+            Property::Strongroom => "Strongroom",
+        }
+    }
+
+    pub fn cost(&self) -> i32 {
+        match self {
+            Property::Larder => 150,
+            Property::Armory => 400,
+            Property::Strongroom => 600,
+        }
+    }
+
+    /// The starting item every new character takes from it, if any
+    pub fn starting_item(&self) -> Option<&'static str> {
+        match self {
+            Property::Larder => Some("health_potion"),
+            Property::Armory => Some("armory_piece"),
+            Property::Strongroom => None,
+        }
+    }
+}
+
+/// Gold left with the bank in Ravenhollow and the property bought through it, shared by every
+/// character. Kept apart from saved games, so it survives a character's death
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bank {
+    pub balance: i32,
+    #[serde(default)]
+    pub properties: Vec<Property>,
+}
+
+impl Bank {
+    /// Load the bank's books, starting with an empty account if the file is missing
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Bank::default());
+        }
+
+        let file_content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    pub fn owns(&self, property: Property) -> bool {
+        self.properties.contains(&property)
+    }
+
+    /// Buy `property` out of the account, if it is not already owned and the account covers it
+    pub fn buy(&mut self, property: Property) -> bool {
+        if self.owns(property) || self.balance < property.cost() {
+            return false;
+        }
+        self.balance -= property.cost();
+        self.properties.push(property);
+        true
+    }
+
+    /// Percent the account grows each day under `mode`, or shrinks when below zero
+    pub fn daily_rate(&self, mode: &GameMode) -> i32 {
+        if self.owns(Property::Strongroom) {
+            mode.bank_rate().max(0) + STRONGROOM_BONUS
+        } else {
+            mode.bank_rate()
+        }
+    }
+
+    /// Apply `days` of interest or fees at `rate` percent, returning how much the balance moved
+    pub fn accrue(&mut self, rate: i32, days: u64) -> i32 {
+        let before = self.balance;
+        for _ in 0..days {
+            self.balance += self.balance * rate / 100;
+        }
+        self.balance - before
+    }
+
+    /// The starting items every new character takes from the property owned
+    pub fn starting_items(&self) -> Vec<&'static str> {
+        self.properties.iter().filter_map(|property| property.starting_item()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_the_mode_decides_between_interest_and_fees_and_a_strongroom_waives_fees() {
+        let mut bank = Bank { balance: 1000, properties: Vec::new() };
+        assert_eq!(bank.accrue(bank.daily_rate(&GameMode::Casual), 2), 1000 * 102 / 100 * 102 / 100 - 1000);
+        bank.balance = 1000;
+        assert!(bank.accrue(bank.daily_rate(&GameMode::Permadeath), 1) < 0);
+
+        bank.balance = 100;
+        assert!(!bank.buy(Property::Strongroom));
+        bank.balance = Property::Strongroom.cost() + 100;
+        assert!(bank.buy(Property::Strongroom));
+        assert!(!bank.buy(Property::Strongroom));
+        assert_eq!(bank.balance, 100);
+        assert_eq!(bank.daily_rate(&GameMode::Permadeath), STRONGROOM_BONUS);
+        assert!(bank.starting_items().is_empty());
+    }
+
+    #[test]
+    fn test_the_bank_survives_a_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("bank.json");
+        let path = path.to_str().unwrap();
+
+        let bank = Bank { balance: 250, properties: vec![Property::Larder] };
+        bank.save(path).unwrap();
+
+        let loaded = Bank::load(path).unwrap();
+        assert_eq!(loaded.balance, 250);
+        assert_eq!(loaded.starting_items(), vec!["health_potion"]);
+    }
+}
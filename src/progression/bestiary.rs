@@ -0,0 +1,191 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::components::{CombatStats, DamageResistances};
+use crate::map::EnemyType;
+
+/// Default location of the bestiary, kept from one run to the next
+pub const BESTIARY_PATH: &str = "saves/bestiary.json";
+
+/// Kills of a kind needed before the bestiary shows its hit points, defense and power
+pub const STATS_REVEAL_KILLS: u32 = 1;
+/// Kills of a kind needed before the bestiary shows its resistances and nature
+pub const RESISTANCES_REVEAL_KILLS: u32 = 3;
+/// Kills of a kind needed before the bestiary tells what is known of it
+pub const LORE_REVEAL_KILLS: u32 = 5;
+
+/// What has been learned about one kind of monster
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BestiaryEntry {
+    pub name: String,
+    pub glyph: char,
+    pub seen: u32,
+    pub killed: u32,
+    /// Hit points, defense and power of the last ordinary one seen
+    pub stats: Option<(i32, i32, i32)>,
+    /// Damage types it resists, as percentages
+    #[serde(default)]
+    pub resistances: BTreeMap<String, i32>,
+}
+
+impl BestiaryEntry {
+    pub fn new(name: String, glyph: char) -> Self {
+        BestiaryEntry {
+            name,
+            glyph,
+            seen: 0,
+            killed: 0,
+            stats: None,
+            resistances: BTreeMap::new(),
+        }
+    }
+
+    pub fn reveals_stats(&self) -> bool {
+        self.killed >= STATS_REVEAL_KILLS
+    }
+
+    pub fn reveals_resistances(&self) -> bool {
+        self.killed >= RESISTANCES_REVEAL_KILLS
+    }
+
+    pub fn reveals_lore(&self) -> bool {
+        self.killed >= LORE_REVEAL_KILLS
+    }
+
+    /// Kills still needed to learn more, if there is more to learn
+    pub fn kills_to_next_reveal(&self) -> Option<u32> {
+        [STATS_REVEAL_KILLS, RESISTANCES_REVEAL_KILLS, LORE_REVEAL_KILLS].iter()
+            .find(|needed| self.killed < **needed)
+            .map(|needed| needed - self.killed)
+    }
+
+    /// The common kind this entry describes, for its nature and lore
+    pub fn enemy_type(&self) -> Option<EnemyType> {
+        EnemyType::from_name(&self.name)
+    }
+}
+
+/// Every kind of monster met in any run, by name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bestiary {
+    pub entries: BTreeMap<String, BestiaryEntry>,
+}
+
+impl Bestiary {
+    /// Load the bestiary, starting empty if the file is missing
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Bestiary::default());
+        }
+
+        let file_content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    /// Note one more monster of a kind coming into view. Elites are counted but their
+    /// strengthened stats are not taken as the kind's own
+    pub fn record_seen(&mut self, name: &str, glyph: char, stats: Option<&CombatStats>, resistances: Option<&DamageResistances>, elite: bool) {
+        let entry = self.entries.entry(name.to_string())
+            .or_insert_with(|| BestiaryEntry::new(name.to_string(), glyph));
+        entry.seen += 1;
+        if elite {
+            return;
+        }
+        if let Some(stats) = stats {
+            entry.stats = Some((stats.max_hp, stats.defense, stats.power));
+        }
+        if let Some(resistances) = resistances {
+            entry.resistances = resistances.resistances.iter()
+                .filter(|(_, resistance)| **resistance > 0.0)
+                .map(|(damage_type, resistance)| (damage_type.name().to_string(), (resistance * 100.0).round() as i32))
+                .collect();
+        }
+    }
+
+    pub fn record_kill(&mut self, name: &str, glyph: char) {
+        self.entries.entry(name.to_string())
+            .or_insert_with(|| BestiaryEntry::new(name.to_string(), glyph))
+            .killed += 1;
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&BestiaryEntry> {
+        self.entries.get(name)
+    }
+
+    /// Entries in the order the bestiary lists them
+    pub fn sorted(&self) -> Vec<&BestiaryEntry> {
+        self.entries.values().collect()
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.entries.values().map(|entry| entry.killed).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::DamageType;
+
+    #[test]
+    fn test_kills_reveal_more_of_a_kind() {
+        let mut bestiary = Bestiary::default();
+        let stats = CombatStats { max_hp: 6, hp: 6, defense: 1, power: 4 };
+        let mut resistances = DamageResistances::new();
+        resistances.add_resistance(DamageType::Poison, 0.5);
+        bestiary.record_seen("Goblin", 'g', Some(&stats), Some(&resistances), false);
+
+        let entry = bestiary.entry("Goblin").unwrap();
+        assert_eq!((entry.seen, entry.killed), (1, 0));
+        assert!(!entry.reveals_stats());
+        assert_eq!(entry.kills_to_next_reveal(), Some(1));
+        assert_eq!(entry.resistances.get("Poison"), Some(&50));
+
+        for _ in 0..LORE_REVEAL_KILLS {
+            bestiary.record_kill("Goblin", 'g');
+        }
+        let entry = bestiary.entry("Goblin").unwrap();
+        assert!(entry.reveals_stats() && entry.reveals_resistances() && entry.reveals_lore());
+        assert_eq!(entry.kills_to_next_reveal(), None);
+        assert_eq!(entry.enemy_type(), Some(EnemyType::Goblin));
+    }
+
+    #[test]
+    fn test_elites_do_not_overwrite_the_kinds_stats() {
+        let mut bestiary = Bestiary::default();
+        let ordinary = CombatStats { max_hp: 10, hp: 10, defense: 1, power: 6 };
+        let elite = CombatStats { max_hp: 15, hp: 15, defense: 4, power: 7 };
+        bestiary.record_seen("Orc", 'o', Some(&ordinary), None, false);
+        bestiary.record_seen("Orc", 'o', Some(&elite), None, true);
+
+        let entry = bestiary.entry("Orc").unwrap();
+        assert_eq!(entry.seen, 2);
+        assert_eq!(entry.stats, Some((10, 1, 6)));
+    }
+
+    #[test]
+    fn test_bestiary_survives_a_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("bestiary.json");
+        let path = path.to_str().unwrap();
+
+        let mut bestiary = Bestiary::default();
+        bestiary.record_kill("Rat", 'r');
+        bestiary.save(path).unwrap();
+
+        let loaded = Bestiary::load(path).unwrap();
+        assert_eq!(loaded.entry("Rat").map(|entry| entry.killed), Some(1));
+        assert_eq!(loaded.total_kills(), 1);
+    }
+}
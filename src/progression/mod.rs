@@ -3,6 +3,9 @@ pub mod unlockable_content;
 pub mod world_changes;
 pub mod player_history;
 pub mod progression_integration;
+pub mod bestiary;
+pub mod bank;
+pub mod world_events;
 
 pub use milestone_system::{
     MilestoneSystem, Milestone, MilestoneType, MilestoneImportance, MilestoneStatus,
@@ -13,7 +16,8 @@ pub use milestone_system::{
 pub use unlockable_content::{
     UnlockableContentSystem, UnlockableContent, ContentType, ContentRarity,
     UnlockCondition, UnlockedContentRecord, ContentUnlockStatistics,
-    UnlockableContentSaveData,
+    UnlockableContentSaveData, UNLOCKS_PATH, UNLOCKS_CLASS, UNLOCKS_BACKGROUND,
+    UNLOCKS_GLYPH_COLOR, UNLOCKS_STARTING_ITEM,
 };
 
 pub use world_changes::{
@@ -28,4 +32,16 @@ pub use player_history::{
 
 pub use progression_integration::{
     ProgressionIntegration, ProgressionStatistics, ProgressionSaveData,
-};
\ No newline at end of file
+};
+
+pub use bestiary::{
+    Bestiary, BestiaryEntry, BESTIARY_PATH, STATS_REVEAL_KILLS, RESISTANCES_REVEAL_KILLS,
+    LORE_REVEAL_KILLS,
+};
+
+pub use bank::{Bank, Property, BANK_PATH, STRONGROOM_BONUS};
+
+pub use world_events::{
+    WorldEvents, WorldEvent, WorldEventKind, WorldNews, TimelineEntry, flood, WORLD_PATH,
+    TIMELINE_LIMIT,
+};
@@ -203,6 +203,9 @@ pub struct HistoryStatistics {
     pub milestone_events: Vec<String>, // Important milestone event IDs
 }
 
+/// Events a run's history keeps before the oldest are forgotten
+pub const DEFAULT_MAX_EVENTS: usize = 1000;
+
 /// Player history system
 pub struct PlayerHistorySystem {
     events: VecDeque<HistoryEvent>,
@@ -213,9 +216,26 @@ pub struct PlayerHistorySystem {
     statistics: HistoryStatistics,
 }
 
+impl Default for PlayerHistorySystem {
+    fn default() -> Self {
+        PlayerHistorySystem::empty(DEFAULT_MAX_EVENTS)
+    }
+}
+
 impl PlayerHistorySystem {
     pub fn new(max_events: usize) -> Self {
-        let mut system = PlayerHistorySystem {
+        let mut system = PlayerHistorySystem::empty(max_events);
+
+        // Create initial example events
+        system.initialize_example_events();
+        system.update_statistics();
+
+        system
+    }
+
+    /// A history with nothing in it yet, as a new run starts
+    pub fn empty(max_events: usize) -> Self {
+        PlayerHistorySystem {
             events: VecDeque::new(),
             sessions: Vec::new(),
             current_session: None,
@@ -233,13 +253,7 @@ impl PlayerHistorySystem {
                 recent_events: Vec::new(),
                 milestone_events: Vec::new(),
             },
-        };
-
-        // Create initial example events
-        system.initialize_example_events();
-        system.update_statistics();
-
-        system
+        }
     }
 
     /// Initialize with example events
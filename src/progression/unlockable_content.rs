@@ -1,6 +1,21 @@
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
 use serde::{Serialize, Deserialize};
 use crate::progression::milestone_system::MilestoneReward;
+use crate::achievements::AchievementReward;
+
+/// Default location of the content unlocked so far, kept from one character to the next
+pub const UNLOCKS_PATH: &str = "saves/unlocks.json";
+
+/// Metadata key naming the class a piece of content opens up in character creation
+pub const UNLOCKS_CLASS: &str = "class";
+/// Metadata key naming the background a piece of content opens up in character creation
+pub const UNLOCKS_BACKGROUND: &str = "background";
+/// Metadata key naming a color the player's glyph may be drawn in
+pub const UNLOCKS_GLYPH_COLOR: &str = "glyph_color";
+/// Metadata key naming an item every new character starts with
+pub const UNLOCKS_STARTING_ITEM: &str = "starting_item";
 
 /// Types of unlockable content
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -379,6 +394,73 @@ impl UnlockableContentSystem {
                 UnlockCondition::Milestone("secret_keeper".to_string()),
             ).with_icon("🔍".to_string())
             .with_hint("Find the hidden chamber".to_string()),
+
+            // Character creation, opened up by achievement rewards
+            UnlockableContent::new(
+                "class_ranger".to_string(),
+                "Ranger Class".to_string(),
+                "Start new characters as a Ranger".to_string(),
+                ContentType::Character,
+                ContentRarity::Uncommon,
+                UnlockCondition::Achievement("explorer".to_string()),
+            ).with_icon("🏹".to_string())
+            .with_hint("Visit 50 different rooms".to_string())
+            .with_metadata(UNLOCKS_CLASS.to_string(), "Ranger".to_string()),
+
+            UnlockableContent::new(
+                "background_merchant".to_string(),
+                "Merchant Background".to_string(),
+                "Start new characters with a Merchant's upbringing".to_string(),
+                ContentType::Character,
+                ContentRarity::Common,
+                UnlockCondition::Achievement("treasure_hunter".to_string()),
+            ).with_icon("⚖️".to_string())
+            .with_hint("Collect 100 gold".to_string())
+            .with_metadata(UNLOCKS_BACKGROUND.to_string(), "Merchant".to_string()),
+
+            UnlockableContent::new(
+                "glyph_crimson".to_string(),
+                "Crimson Glyph".to_string(),
+                "Draw your character in blood red".to_string(),
+                ContentType::Cosmetic,
+                ContentRarity::Uncommon,
+                UnlockCondition::Achievement("kill_100_enemies".to_string()),
+            ).with_icon("🟥".to_string())
+            .with_hint("Defeat 100 enemies".to_string())
+            .with_metadata(UNLOCKS_GLYPH_COLOR.to_string(), "red".to_string()),
+
+            UnlockableContent::new(
+                "glyph_gold".to_string(),
+                "Golden Glyph".to_string(),
+                "Draw your character in gold".to_string(),
+                ContentType::Cosmetic,
+                ContentRarity::Rare,
+                UnlockCondition::Achievement("boss_slayer".to_string()),
+            ).with_icon("🟨".to_string())
+            .with_hint("Defeat your first boss".to_string())
+            .with_metadata(UNLOCKS_GLYPH_COLOR.to_string(), "yellow".to_string()),
+
+            UnlockableContent::new(
+                "starting_health_potion".to_string(),
+                "Starting Health Potion".to_string(),
+                "New characters start with a health potion".to_string(),
+                ContentType::Item,
+                ContentRarity::Common,
+                UnlockCondition::Achievement("level_up".to_string()),
+            ).with_icon("🧪".to_string())
+            .with_hint("Reach level 2".to_string())
+            .with_metadata(UNLOCKS_STARTING_ITEM.to_string(), "health_potion".to_string()),
+
+            UnlockableContent::new(
+                "starting_phoenix_feather".to_string(),
+                "Starting Phoenix Feather".to_string(),
+                "New characters start with a Phoenix Feather".to_string(),
+                ContentType::Item,
+                ContentRarity::Legendary,
+                UnlockCondition::Achievement("victory".to_string()),
+            ).with_icon("🪶".to_string())
+            .with_hint("Escape the dungeon with the Amulet of the Depths".to_string())
+            .with_metadata(UNLOCKS_STARTING_ITEM.to_string(), "phoenix_feather".to_string()),
         ];
 
         for content in content_items {
@@ -514,6 +596,41 @@ impl UnlockableContentSystem {
         }
     }
 
+    /// Unlock whatever content an achievement's rewards name, returning what was newly unlocked
+    pub fn apply_achievement_rewards(&mut self, achievement_id: &str, rewards: &[AchievementReward]) -> Vec<String> {
+        let source = format!("achievement:{}", achievement_id);
+        rewards.iter()
+            .filter_map(|reward| match reward {
+                AchievementReward::Unlock(id) | AchievementReward::Cosmetic(id) | AchievementReward::Item(id) => Some(id),
+                _ => None,
+            })
+            .filter(|id| self.unlock_content(id, &source))
+            .cloned()
+            .collect()
+    }
+
+    /// Get a single piece of content
+    pub fn get_content(&self, content_id: &str) -> Option<&UnlockableContent> {
+        self.content.get(content_id)
+    }
+
+    /// The still locked content that gates `value` under a metadata `key`, such as a class
+    pub fn locked_by(&self, key: &str, value: &str) -> Option<&UnlockableContent> {
+        self.content.values()
+            .filter(|content| !self.is_content_unlocked(&content.id))
+            .find(|content| content.metadata.get(key).map_or(false, |v| v == value))
+    }
+
+    /// Every value under a metadata `key` that unlocked content provides, in a stable order
+    pub fn unlocked_values(&self, key: &str) -> Vec<String> {
+        let mut values: Vec<String> = self.get_unlocked_content().into_iter()
+            .filter_map(|(content, _)| content.metadata.get(key).cloned())
+            .collect();
+        values.sort();
+        values.dedup();
+        values
+    }
+
     /// Access content (for tracking usage)
     pub fn access_content(&mut self, content_id: &str) {
         if let Some(record) = self.unlocked_content.get_mut(content_id) {
@@ -652,11 +769,33 @@ impl UnlockableContentSystem {
 }
 
 /// Save data for unlockable content
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UnlockableContentSaveData {
     pub unlocked_content: HashMap<String, UnlockedContentRecord>,
 }
 
+impl UnlockableContentSaveData {
+    /// Load the unlocks, starting with none if the file is missing
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(UnlockableContentSaveData::default());
+        }
+
+        let file_content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -758,6 +897,26 @@ mod tests {
         assert!(updated_stats.unlock_percentage > 0.0);
     }
 
+    #[test]
+    fn test_achievement_rewards_open_up_character_creation() {
+        let mut system = UnlockableContentSystem::new();
+        assert_eq!(system.locked_by(UNLOCKS_CLASS, "Ranger").map(|c| c.id.as_str()), Some("class_ranger"));
+        assert!(system.unlocked_values(UNLOCKS_GLYPH_COLOR).is_empty());
+
+        let rewards = vec![
+            AchievementReward::Unlock("class_ranger".to_string()),
+            AchievementReward::Cosmetic("glyph_gold".to_string()),
+            AchievementReward::Gold(50),
+        ];
+        let unlocked = system.apply_achievement_rewards("explorer", &rewards);
+        assert_eq!(unlocked, vec!["class_ranger".to_string(), "glyph_gold".to_string()]);
+        assert!(system.locked_by(UNLOCKS_CLASS, "Ranger").is_none());
+        assert_eq!(system.unlocked_values(UNLOCKS_GLYPH_COLOR), vec!["yellow".to_string()]);
+
+        // Claiming the same rewards again unlocks nothing new
+        assert!(system.apply_achievement_rewards("explorer", &rewards).is_empty());
+    }
+
     #[test]
     fn test_save_and_load() {
         let mut system = UnlockableContentSystem::new();
@@ -0,0 +1,285 @@
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::Path;
+use crate::guild::Contract;
+use crate::map::{Map, TileType};
+use crate::resources::RandomNumberGenerator;
+
+/// Default location of the world's doings, kept from one character to the next
+pub const WORLD_PATH: &str = "saves/world.json";
+
+/// Most events the timeline remembers
+pub const TIMELINE_LIMIT: usize = 30;
+/// Fewest days between one event beginning and the chance of the next
+const MIN_EVENT_GAP: i32 = 2;
+/// Most days between one event beginning and the chance of the next
+const MAX_EVENT_GAP: i32 = 4;
+/// Places on a fresh level a flood pools
+const FLOOD_POOLS: usize = 3;
+/// How far a pool spreads from where it starts
+const FLOOD_RADIUS: i32 = 2;
+
+/// Something that happens up on the surface while the character is down below
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WorldEventKind {
+    /// Goblins come up out of the hills after Ravenhollow's stores: prices climb, and the guild
+    /// pays more for patrols and bounties
+    GoblinRaid,
+    /// A merchant caravan camps outside the walls: prices fall, and escorting it pays well
+    MerchantCaravan,
+    /// Underground rivers break their banks: no delves or expeditions, and new levels flood
+    DungeonFlood,
+}
+
+impl WorldEventKind {
+    pub fn all() -> [WorldEventKind; 3] {
+        [WorldEventKind::GoblinRaid, WorldEventKind::MerchantCaravan, WorldEventKind::DungeonFlood]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorldEventKind::GoblinRaid => "Goblin raid",
+            WorldEventKind::MerchantCaravan => "Merchant caravan",
+            WorldEventKind::DungeonFlood => "Dungeon flood",
+        }
+    }
+
+    /// Locale key for the news when it begins
+    pub fn news_key(&self) -> &'static str {
+        match self {
+            WorldEventKind::GoblinRaid => "log.event_goblin_raid",
+            WorldEventKind::MerchantCaravan => "log.event_merchant_caravan",
+            WorldEventKind::DungeonFlood => "log.event_dungeon_flood",
+        }
+    }
+
+    /// Days it lasts
+    pub fn days(&self) -> u32 {
+        match self {
+            WorldEventKind::GoblinRaid => 2,
+            WorldEventKind::MerchantCaravan => 3,
+            WorldEventKind::DungeonFlood => 2,
+        }
+    }
+
+    /// Percent of the usual price Ravenhollow's shop asks while it lasts
+    pub fn price_percent(&self) -> i32 {
+        match self {
+            WorldEventKind::GoblinRaid => 125,
+            WorldEventKind::MerchantCaravan => 80,
+            WorldEventKind::DungeonFlood => 100,
+        }
+    }
+
+    /// Percent of the usual reward `contract` pays while it lasts
+    pub fn reward_percent(&self, contract: Contract) -> i32 {
+        match (self, contract) {
+            (WorldEventKind::GoblinRaid, Contract::Patrol | Contract::Bounty) => 150,
+            (WorldEventKind::MerchantCaravan, Contract::Patrol) => 200,
+            _ => 100,
+        }
+    }
+
+    /// Whether nobody offers `contract` while it lasts
+    pub fn closes(&self, contract: Contract) -> bool {
+        matches!((self, contract), (WorldEventKind::DungeonFlood, Contract::Delve | Contract::Expedition))
+    }
+}
+
+/// An event under way
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct WorldEvent {
+    pub kind: WorldEventKind,
+    pub days_left: u32,
+}
+
+/// When an event began, for the guild's timeline
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub kind: WorldEventKind,
+    /// The character it happened under, counting from the first
+    pub run: u32,
+    pub day: u64,
+}
+
+/// An event beginning or coming to an end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldNews {
+    Began(WorldEventKind),
+    Ended(WorldEventKind),
+}
+
+/// What is going on in the world above, and what has, shared by every character. Kept apart from
+/// saved games like the bank, so raids, caravans and floods carry on from one character to the
+/// next
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorldEvents {
+    pub active: Vec<WorldEvent>,
+    /// Every event that has begun, oldest first
+    #[serde(default)]
+    pub timeline: Vec<TimelineEntry>,
+    /// Days before the next event may begin
+    #[serde(default)]
+    pub days_until_next: u32,
+    /// Characters the world has seen, counting the one playing now
+    #[serde(default)]
+    pub run: u32,
+}
+
+impl WorldEvents {
+    /// Load the world's doings, starting from a quiet world if the file is missing
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(WorldEvents::default());
+        }
+
+        let file_content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file_content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    pub fn is_active(&self, kind: WorldEventKind) -> bool {
+        self.active.iter().any(|event| event.kind == kind)
+    }
+
+    /// Percent of the usual price Ravenhollow's shop asks, with every event under way
+    pub fn price_percent(&self) -> i32 {
+        self.active.iter().fold(100, |percent, event| percent * event.kind.price_percent() / 100)
+    }
+
+    /// Percent of the usual reward `contract` pays, with every event under way
+    pub fn reward_percent(&self, contract: Contract) -> i32 {
+        self.active.iter().fold(100, |percent, event| percent * event.kind.reward_percent(contract) / 100)
+    }
+
+    /// The event keeping `contract` off the guild's books, if any
+    pub fn closing(&self, contract: Contract) -> Option<WorldEventKind> {
+        self.active.iter().map(|event| event.kind).find(|kind| kind.closes(contract))
+    }
+
+    /// Let `days` pass up to `day`: events under way run down, and while the character is
+    /// `underground`, new ones begin every few days. Returns what began and what ended
+    pub fn pass_days(&mut self, days: u32, day: u64, underground: bool, rng: &mut RandomNumberGenerator) -> Vec<WorldNews> {
+        let mut changes = Vec::new();
+        for _ in 0..days {
+            for event in self.active.iter_mut() {
+                event.days_left = event.days_left.saturating_sub(1);
+            }
+            changes.extend(self.active.iter().filter(|event| event.days_left == 0).map(|event| WorldNews::Ended(event.kind)));
+            self.active.retain(|event| event.days_left > 0);
+
+            if !underground {
+                continue;
+            }
+            if self.days_until_next > 0 {
+                self.days_until_next -= 1;
+                continue;
+            }
+            let quiet: Vec<WorldEventKind> = WorldEventKind::all().into_iter().filter(|kind| !self.is_active(*kind)).collect();
+            if quiet.is_empty() {
+                continue;
+            }
+            let kind = quiet[rng.range(0, quiet.len() as i32 - 1) as usize];
+            self.begin(kind, day);
+            changes.push(WorldNews::Began(kind));
+            self.days_until_next = rng.range(MIN_EVENT_GAP, MAX_EVENT_GAP) as u32;
+        }
+        changes
+    }
+
+    /// A new character sets out, a day on from the last: the world moves on without anyone
+    /// watching
+    pub fn new_run(&mut self, rng: &mut RandomNumberGenerator) -> Vec<WorldNews> {
+        self.run += 1;
+        self.pass_days(1, 0, true, rng)
+    }
+
+    fn begin(&mut self, kind: WorldEventKind, day: u64) {
+        self.active.push(WorldEvent { kind, days_left: kind.days() });
+        self.timeline.push(TimelineEntry { kind, run: self.run, day });
+        if self.timeline.len() > TIMELINE_LIMIT {
+            let excess = self.timeline.len() - TIMELINE_LIMIT;
+            self.timeline.drain(..excess);
+        }
+    }
+}
+
+/// Let water into a fresh level: a few pools of it over the floor, spreading from random spots.
+/// Returns how many tiles went under
+pub fn flood(map: &mut Map, rng: &mut RandomNumberGenerator) -> usize {
+    let mut flooded = 0;
+    for _ in 0..FLOOD_POOLS {
+        let (cx, cy) = match map.find_random_floor_tile(rng) {
+            Some(tile) => tile,
+            None => break,
+        };
+        for y in cy - FLOOD_RADIUS..=cy + FLOOD_RADIUS {
+            for x in cx - FLOOD_RADIUS..=cx + FLOOD_RADIUS {
+                if map.get_tile(x, y) == Some(TileType::Floor) && rng.range(1, 100) <= 75 {
+                    map.set_tile(x, y, TileType::Water);
+                    flooded += 1;
+                }
+            }
+        }
+    }
+    flooded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_only_begin_underground_and_run_their_course() {
+        let mut rng = RandomNumberGenerator::new(4);
+        let mut world = WorldEvents::default();
+        assert!(world.pass_days(5, 5, false, &mut rng).is_empty());
+
+        let changes = world.pass_days(1, 6, true, &mut rng);
+        let kind = match changes.as_slice() {
+            [WorldNews::Began(kind)] => *kind,
+            other => panic!("expected one event to begin, got {:?}", other),
+        };
+        assert!(world.is_active(kind));
+        assert_eq!(world.timeline, vec![TimelineEntry { kind, run: 0, day: 6 }]);
+
+        let changes = world.pass_days(kind.days(), 8, false, &mut rng);
+        assert_eq!(changes, vec![WorldNews::Ended(kind)]);
+        assert!(world.active.is_empty());
+    }
+
+    #[test]
+    fn test_events_change_prices_and_contracts() {
+        let mut world = WorldEvents::default();
+        world.active.push(WorldEvent { kind: WorldEventKind::GoblinRaid, days_left: 1 });
+        world.active.push(WorldEvent { kind: WorldEventKind::DungeonFlood, days_left: 1 });
+        assert_eq!(world.price_percent(), 125);
+        assert_eq!(world.reward_percent(Contract::Bounty), 150);
+        assert_eq!(world.closing(Contract::Delve), Some(WorldEventKind::DungeonFlood));
+        assert_eq!(world.closing(Contract::Patrol), None);
+    }
+
+    #[test]
+    fn test_a_flood_only_covers_floor() {
+        let mut map = Map::new(20, 20, 1);
+        for y in 1..19 {
+            for x in 1..19 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        map.set_tile(10, 10, TileType::DownStairs);
+        let flooded = flood(&mut map, &mut RandomNumberGenerator::new(9));
+        assert!(flooded > 0);
+        assert_eq!(map.get_tile(10, 10), Some(TileType::DownStairs));
+        assert_eq!(map.get_tile(0, 0), Some(TileType::Wall));
+    }
+}
@@ -0,0 +1,273 @@
+use crossterm::style::Color;
+use std::collections::VecDeque;
+use std::time::Instant;
+use crate::components::DeathAnimationType;
+
+/// Seconds a projectile takes to cross one tile at normal speed
+pub const PROJECTILE_SECS_PER_TILE: f32 = 0.03;
+/// Seconds for an attacker to lunge at its target and recoil
+pub const MELEE_BUMP_SECS: f32 = 0.15;
+/// Seconds a creature takes to fall
+pub const DEATH_SECS: f32 = 0.4;
+/// Slowest and fastest playback the speed setting allows
+pub const ANIMATION_SPEED_RANGE: (f32, f32) = (0.25, 4.0);
+/// Longest gap between two updates counted as playback time, so a stalled frame doesn't skip ahead
+const MAX_STEP_SECS: f32 = 0.1;
+
+/// Something the player should see happen before the next action is taken
+#[derive(Debug, Clone, PartialEq)]
+pub enum Animation {
+    /// A shot flying from the shooter to its target
+    Projectile { from: (i32, i32), to: (i32, i32), color: Color },
+    /// An attacker lunging at the tile it struck
+    MeleeBump { from: (i32, i32), to: (i32, i32), glyph: char, color: Color },
+    /// A creature dying where it stood
+    Death { pos: (i32, i32), glyph: char, color: Color, style: DeathAnimationType },
+}
+
+/// One cell drawn over the map while an animation plays
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationCell {
+    pub x: i32,
+    pub y: i32,
+    pub glyph: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl AnimationCell {
+    fn new(pos: (i32, i32), glyph: char, fg: Color) -> Self {
+        AnimationCell { x: pos.0, y: pos.1, glyph, fg, bg: Color::Black }
+    }
+}
+
+impl Animation {
+    /// How long the animation lasts at normal speed
+    pub fn base_duration(&self) -> f32 {
+        match self {
+            Animation::Projectile { from, to, .. } => {
+                let tiles = (to.0 - from.0).abs().max((to.1 - from.1).abs()).max(1);
+                tiles as f32 * PROJECTILE_SECS_PER_TILE
+            },
+            Animation::MeleeBump { .. } => MELEE_BUMP_SECS,
+            Animation::Death { .. } => DEATH_SECS,
+        }
+    }
+
+    /// The cells to draw once `progress` (0.0 to 1.0) of the animation has played
+    pub fn cells(&self, progress: f32) -> Vec<AnimationCell> {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            Animation::Projectile { from, to, color } => {
+                let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+                let steps = dx.abs().max(dy.abs()).max(1);
+                // The shot leaves the shooter's tile straight away and lands on the target's
+                let step = ((progress * steps as f32).floor() as i32 + 1).min(steps);
+                let x = from.0 + (dx as f32 * step as f32 / steps as f32).round() as i32;
+                let y = from.1 + (dy as f32 * step as f32 / steps as f32).round() as i32;
+                vec![AnimationCell::new((x, y), projectile_glyph(dx, dy), *color)]
+            },
+            Animation::MeleeBump { from, to, glyph, color } => {
+                if progress < 0.5 {
+                    // The attacker lunges, highlighted where it stands
+                    vec![AnimationCell { bg: Color::DarkGrey, ..AnimationCell::new(*from, *glyph, *color) }]
+                } else {
+                    vec![AnimationCell::new(*to, '*', Color::Red)]
+                }
+            },
+            Animation::Death { pos, glyph, color, style } => match style {
+                DeathAnimationType::Fade => {
+                    let fg = if progress < 0.5 { *color } else { Color::DarkGrey };
+                    vec![AnimationCell::new(*pos, *glyph, fg)]
+                },
+                DeathAnimationType::Dissolve => {
+                    let glyph = match (progress * 4.0) as i32 {
+                        0 => *glyph,
+                        1 => '%',
+                        2 => ':',
+                        _ => '.',
+                    };
+                    vec![AnimationCell::new(*pos, glyph, *color)]
+                },
+                DeathAnimationType::Explosion => {
+                    if progress < 0.4 {
+                        vec![AnimationCell::new(*pos, '*', Color::Yellow)]
+                    } else {
+                        let mut cells = Vec::new();
+                        for dy in -1..=1 {
+                            for dx in -1..=1 {
+                                if dx != 0 || dy != 0 {
+                                    cells.push(AnimationCell::new((pos.0 + dx, pos.1 + dy), '*', Color::Red));
+                                }
+                            }
+                        }
+                        cells
+                    }
+                },
+                DeathAnimationType::Collapse => {
+                    let glyph = if progress < 0.5 { *glyph } else { '_' };
+                    vec![AnimationCell::new(*pos, glyph, *color)]
+                },
+            },
+        }
+    }
+}
+
+/// The line character that best matches a shot's direction
+fn projectile_glyph(dx: i32, dy: i32) -> char {
+    if dx == 0 {
+        '|'
+    } else if dy == 0 || dy.abs() * 2 < dx.abs() {
+        '-'
+    } else if dx.abs() * 2 < dy.abs() {
+        '|'
+    } else if (dx > 0) == (dy > 0) {
+        '\\'
+    } else {
+        '/'
+    }
+}
+
+/// Animations waiting to be shown, played one after another
+///
+/// Combat systems queue what happened during a turn; the render system plays it back in order and the
+/// game holds off on input and the next turn until the queue has drained.
+#[derive(Debug, Clone)]
+pub struct AnimationQueue {
+    /// Playback speed multiplier; 2.0 plays twice as fast
+    pub speed: f32,
+    /// Drop animations as they are queued so turns resolve instantly
+    pub skip: bool,
+    pending: VecDeque<Animation>,
+    /// The playing animation and how many seconds of it have been shown
+    current: Option<(Animation, f32)>,
+    last_update: Option<Instant>,
+}
+
+impl Default for AnimationQueue {
+    fn default() -> Self {
+        AnimationQueue {
+            speed: 1.0,
+            skip: false,
+            pending: VecDeque::new(),
+            current: None,
+            last_update: None,
+        }
+    }
+}
+
+impl AnimationQueue {
+    /// Apply the player's speed and skip settings
+    pub fn configure(&mut self, speed: f32, skip: bool) {
+        self.speed = speed.clamp(ANIMATION_SPEED_RANGE.0, ANIMATION_SPEED_RANGE.1);
+        self.skip = skip;
+        if skip {
+            self.skip_all();
+        }
+    }
+
+    pub fn push(&mut self, animation: Animation) {
+        if !self.skip {
+            self.pending.push_back(animation);
+        }
+    }
+
+    /// Whether anything is still playing or waiting to play
+    pub fn is_blocking(&self) -> bool {
+        self.current.is_some() || !self.pending.is_empty()
+    }
+
+    /// Drop everything queued, finishing the turn at once
+    pub fn skip_all(&mut self) {
+        self.pending.clear();
+        self.current = None;
+        self.last_update = None;
+    }
+
+    /// Play forward by the real time passed since the last update
+    pub fn update(&mut self, now: Instant) {
+        if !self.is_blocking() {
+            self.last_update = None;
+            return;
+        }
+        let elapsed = self.last_update.map_or(0.0, |last| now.saturating_duration_since(last).as_secs_f32());
+        self.last_update = Some(now);
+        self.advance(elapsed.min(MAX_STEP_SECS));
+    }
+
+    /// Play forward by `seconds`, moving on to the next animation as each one finishes
+    pub fn advance(&mut self, seconds: f32) {
+        let mut remaining = seconds * self.speed;
+        loop {
+            if self.current.is_none() {
+                match self.pending.pop_front() {
+                    Some(animation) => self.current = Some((animation, 0.0)),
+                    None => {
+                        self.last_update = None;
+                        return;
+                    },
+                }
+            }
+            let (animation, shown) = self.current.as_mut().expect("an animation is playing");
+            *shown += remaining;
+            let duration = animation.base_duration();
+            if *shown < duration {
+                return;
+            }
+            remaining = *shown - duration;
+            self.current = None;
+        }
+    }
+
+    /// What to draw for the playing animation this frame
+    pub fn cells(&self) -> Vec<AnimationCell> {
+        match &self.current {
+            Some((animation, shown)) => animation.cells(shown / animation.base_duration()),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_animations_play_in_order() {
+        let mut queue = AnimationQueue::default();
+        queue.push(Animation::MeleeBump { from: (1, 1), to: (2, 1), glyph: 'g', color: Color::Green });
+        queue.push(Animation::Death { pos: (2, 1), glyph: 'r', color: Color::Red, style: DeathAnimationType::Collapse });
+        assert!(queue.is_blocking());
+
+        queue.advance(0.0);
+        assert_eq!(queue.cells()[0].glyph, 'g');
+        queue.advance(MELEE_BUMP_SECS * 0.75);
+        assert_eq!(queue.cells()[0].glyph, '*');
+
+        // Leftover time carries into the death that follows
+        queue.advance(MELEE_BUMP_SECS * 0.25 + DEATH_SECS * 0.75);
+        assert_eq!(queue.cells()[0].glyph, '_');
+        queue.advance(DEATH_SECS);
+        assert!(!queue.is_blocking());
+        assert!(queue.cells().is_empty());
+    }
+
+    #[test]
+    fn test_projectile_flies_towards_target_and_speed_settings_apply() {
+        let shot = Animation::Projectile { from: (0, 0), to: (4, 0), color: Color::White };
+        assert_eq!(shot.cells(0.0)[0].x, 1);
+        assert_eq!(shot.cells(0.6)[0].x, 3);
+        assert_eq!(shot.cells(1.0)[0].x, 4);
+        assert_eq!(shot.cells(0.5)[0].glyph, '-');
+
+        let mut queue = AnimationQueue::default();
+        queue.configure(2.0, false);
+        queue.push(shot.clone());
+        queue.advance(shot.base_duration() / 2.0);
+        assert!(!queue.is_blocking());
+
+        queue.configure(1.0, true);
+        queue.push(shot);
+        assert!(!queue.is_blocking());
+    }
+}
@@ -1,6 +1,32 @@
 use crate::map::Map;
 
+/// Share of the remaining distance the camera closes each frame while following
+pub const DEFAULT_SMOOTHING: f32 = 0.35;
+
+/// Smallest map viewport, in cells, worth zooming out on
+pub const MIN_ZOOM_VIEWPORT: (i32, i32) = (40, 20);
+
+/// How many world tiles each screen cell covers along each axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraZoom {
+    Normal,
+    /// Each cell stands for a 2x2 block of tiles
+    ZoomedOut,
+}
+
+impl CameraZoom {
+    pub fn scale(&self) -> i32 {
+        match self {
+            CameraZoom::Normal => 1,
+            CameraZoom::ZoomedOut => 2,
+        }
+    }
+}
+
 /// Camera struct for handling viewport calculations
+///
+/// `x` and `y` are the world tile at the top-left of the viewport. While following a target the
+/// camera glides there from its fractional position rather than jumping.
 #[derive(Clone)]
 pub struct Camera {
     pub x: i32,
@@ -9,6 +35,13 @@ pub struct Camera {
     pub height: i32,
     pub map_width: i32,
     pub map_height: i32,
+    pub zoom: CameraZoom,
+    /// 1.0 snaps straight to the target; smaller values glide
+    pub smoothing: f32,
+    /// Screen offset from screen shake, in cells
+    pub shake: (i32, i32),
+    pos_x: f32,
+    pos_y: f32,
 }
 
 impl Camera {
@@ -21,29 +54,84 @@ impl Camera {
             height,
             map_width,
             map_height,
+            zoom: CameraZoom::Normal,
+            smoothing: DEFAULT_SMOOTHING,
+            shake: (0, 0),
+            pos_x: 0.0,
+            pos_y: 0.0,
         }
     }
 
+    /// World tiles covered by the viewport
+    fn world_size(&self) -> (i32, i32) {
+        let scale = self.zoom.scale();
+        (self.width * scale, self.height * scale)
+    }
+
+    /// Top-left tile that would center the viewport on a position, kept inside the map
+    fn centered_origin(&self, x: i32, y: i32) -> (i32, i32) {
+        let (world_width, world_height) = self.world_size();
+        let clamp = |origin: i32, size: i32, map_size: i32| {
+            if size >= map_size { 0 } else { origin.clamp(0, map_size - size) }
+        };
+        (
+            clamp(x - world_width / 2, world_width, self.map_width),
+            clamp(y - world_height / 2, world_height, self.map_height),
+        )
+    }
+
     /// Center the camera on a specific position
     pub fn center_on(&mut self, x: i32, y: i32) {
-        self.x = x - self.width / 2;
-        self.y = y - self.height / 2;
-        self.constrain();
+        let (origin_x, origin_y) = self.centered_origin(x, y);
+        self.x = origin_x;
+        self.y = origin_y;
+        self.pos_x = origin_x as f32;
+        self.pos_y = origin_y as f32;
+    }
+
+    /// Glide towards centering on a position; long jumps such as changing level still snap
+    pub fn follow(&mut self, x: i32, y: i32) {
+        let (target_x, target_y) = self.centered_origin(x, y);
+        let (dx, dy) = (target_x as f32 - self.pos_x, target_y as f32 - self.pos_y);
+        let (world_width, world_height) = self.world_size();
+        if self.smoothing >= 1.0 || dx.abs() > world_width as f32 || dy.abs() > world_height as f32 {
+            self.center_on(x, y);
+            return;
+        }
+
+        self.pos_x += dx * self.smoothing;
+        self.pos_y += dy * self.smoothing;
+        if (target_x as f32 - self.pos_x).abs() < 0.5 {
+            self.pos_x = target_x as f32;
+        }
+        if (target_y as f32 - self.pos_y).abs() < 0.5 {
+            self.pos_y = target_y as f32;
+        }
+        self.x = self.pos_x.round() as i32;
+        self.y = self.pos_y.round() as i32;
+    }
+
+    /// Whether the camera is still gliding towards its target
+    pub fn is_settling(&self, x: i32, y: i32) -> bool {
+        self.centered_origin(x, y) != (self.x, self.y)
     }
 
     /// Constrain the camera to the map boundaries
     pub fn constrain(&mut self) {
+        let (world_width, world_height) = self.world_size();
         if self.x < 0 {
             self.x = 0;
-        } else if self.x + self.width > self.map_width {
-            self.x = self.map_width - self.width;
+        } else if self.x + world_width > self.map_width {
+            self.x = (self.map_width - world_width).max(0);
         }
 
         if self.y < 0 {
             self.y = 0;
-        } else if self.y + self.height > self.map_height {
-            self.y = self.map_height - self.height;
+        } else if self.y + world_height > self.map_height {
+            self.y = (self.map_height - world_height).max(0);
         }
+        self.pos_x = self.x as f32;
+        self.pos_y = self.y as f32;
     }
 
     /// Move the camera by the given delta
@@ -53,19 +141,52 @@ impl Camera {
         self.constrain();
     }
 
+    /// Set how far screen shake pushes the view this frame
+    pub fn set_shake(&mut self, offset_x: f32, offset_y: f32) {
+        self.shake = (offset_x.round() as i32, offset_y.round() as i32);
+    }
+
+    /// Switch between normal and zoomed-out views, keeping the same spot centered
+    pub fn set_zoom(&mut self, zoom: CameraZoom) {
+        let (world_width, world_height) = self.world_size();
+        let center = (self.x + world_width / 2, self.y + world_height / 2);
+        self.zoom = zoom;
+        self.center_on(center.0, center.1);
+    }
+
+    /// Whether the viewport is large enough for the zoomed-out view to be readable
+    pub fn can_zoom_out(&self) -> bool {
+        self.width >= MIN_ZOOM_VIEWPORT.0 && self.height >= MIN_ZOOM_VIEWPORT.1
+    }
+
     /// Check if a world position is visible in the camera viewport
     pub fn is_visible(&self, x: i32, y: i32) -> bool {
-        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+        let (screen_x, screen_y) = self.world_to_screen(x, y);
+        screen_x >= 0 && screen_x < self.width && screen_y >= 0 && screen_y < self.height
     }
 
     /// Convert a world position to a screen position
     pub fn world_to_screen(&self, x: i32, y: i32) -> (i32, i32) {
-        (x - self.x, y - self.y)
+        let scale = self.zoom.scale();
+        (
+            (x - self.x).div_euclid(scale) + self.shake.0,
+            (y - self.y).div_euclid(scale) + self.shake.1,
+        )
     }
 
-    /// Convert a screen position to a world position
+    /// Convert a screen position to a world position; zoomed out, this is the block's top-left tile
     pub fn screen_to_world(&self, x: i32, y: i32) -> (i32, i32) {
-        (x + self.x, y + self.y)
+        let scale = self.zoom.scale();
+        ((x - self.shake.0) * scale + self.x, (y - self.shake.1) * scale + self.y)
+    }
+
+    /// Every world tile drawn in one screen cell
+    pub fn tiles_at_screen(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        let scale = self.zoom.scale();
+        let (world_x, world_y) = self.screen_to_world(x, y);
+        (0..scale)
+            .flat_map(|dy| (0..scale).map(move |dx| (world_x + dx, world_y + dy)))
+            .collect()
     }
 
     /// Update the camera dimensions
@@ -88,4 +209,43 @@ pub fn create_camera_for_map(map: &Map, viewport_width: i32, viewport_height: i3
     let mut camera = Camera::new(viewport_width, viewport_height, map.width, map.height);
     camera.center_on(player_pos.0, player_pos.1);
     camera
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_glides_towards_target() {
+        let mut camera = Camera::new(20, 10, 100, 100);
+        camera.center_on(10, 5);
+        assert_eq!((camera.x, camera.y), (0, 0));
+
+        camera.follow(20, 5);
+        assert!(camera.x > 0 && camera.x < 10);
+        for _ in 0..20 {
+            camera.follow(20, 5);
+        }
+        assert_eq!((camera.x, camera.y), (10, 0));
+        assert!(!camera.is_settling(20, 5));
+
+        // Jumps further than a screen, like taking the stairs, snap
+        camera.follow(90, 90);
+        assert_eq!((camera.x, camera.y), (80, 85));
+    }
+
+    #[test]
+    fn test_zoom_and_shake_shift_screen_positions() {
+        let mut camera = Camera::new(20, 10, 100, 100);
+        camera.center_on(0, 0);
+        camera.set_zoom(CameraZoom::ZoomedOut);
+        assert_eq!(camera.world_to_screen(7, 3), (3, 1));
+        assert_eq!(camera.tiles_at_screen(3, 1), vec![(6, 2), (7, 2), (6, 3), (7, 3)]);
+        assert!(camera.is_visible(39, 19));
+        assert!(!camera.is_visible(40, 0));
+
+        camera.set_shake(1.4, -0.6);
+        assert_eq!(camera.world_to_screen(7, 3), (4, 0));
+        assert_eq!(camera.screen_to_world(4, 0), (6, 2));
+    }
+}
@@ -1,5 +1,70 @@
 use crossterm::style::Color;
 use std::time::{Duration, Instant};
+use crate::components::ParticleEffect;
+
+/// How much movement and flashing the player can take. Set from the options and asked by
+/// whatever shakes, flashes or scatters, so the choices are made here and not at each effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MotionSettings {
+    /// Hold the screen still: no shake, and particles stay where they were thrown from
+    pub reduced_motion: bool,
+    /// No flashing or blinking: flashes hold one color and lightning is left out
+    pub no_flash: bool,
+}
+
+impl MotionSettings {
+    /// How hard a hit may shake the screen
+    pub fn shake_intensity(&self, intensity: f32) -> f32 {
+        if self.reduced_motion { 0.0 } else { intensity }
+    }
+
+    /// Whether something blinking shows its lit half, which it keeps when flashing is off
+    pub fn blink(&self, lit: bool) -> bool {
+        lit || self.no_flash
+    }
+
+    /// Whether whole-screen flashes such as lightning are drawn
+    pub fn screen_flashes(&self) -> bool {
+        !self.no_flash
+    }
+
+    /// The color a cycling flash shows `elapsed` into it, or its first throughout
+    pub fn flash_color(&self, colors: &[Color], elapsed: Duration, interval: Duration) -> Color {
+        if self.no_flash || colors.len() < 2 {
+            return colors.first().copied().unwrap_or(Color::White);
+        }
+        let index = (elapsed.as_secs_f32() / interval.as_secs_f32()) as usize % colors.len();
+        colors[index]
+    }
+
+    /// Background behind something just hit, `intensity` running from 1.0 down to 0.0; a steady
+    /// grey marks the hit instead of a burst of color when flashing is off
+    pub fn hit_background(&self, color: Color, intensity: f32) -> Color {
+        if intensity <= 0.0 {
+            Color::Black
+        } else if intensity > 0.5 && !self.no_flash {
+            color
+        } else {
+            Color::DarkGrey
+        }
+    }
+
+    /// The tile to draw a particle on
+    pub fn particle_position(&self, particle: &ParticleEffect) -> (i32, i32) {
+        if self.reduced_motion {
+            return particle.origin;
+        }
+        (
+            (particle.position.x + particle.position.offset_x).round() as i32,
+            (particle.position.y + particle.position.offset_y).round() as i32,
+        )
+    }
+
+    /// How far an explosion's ring has spread; it stays a mark at its center when motion is reduced
+    pub fn explosion_radius(&self, radius: i32, progress: f32) -> i32 {
+        if self.reduced_motion { 0 } else { (progress * radius as f32) as i32 }
+    }
+}
 
 /// A visual effect that can be displayed on the screen
 #[derive(Clone)]
@@ -239,6 +304,7 @@ impl VisualEffect {
 /// A manager for visual effects
 pub struct EffectManager {
     pub effects: Vec<VisualEffect>,
+    pub motion: MotionSettings,
 }
 
 impl EffectManager {
@@ -246,6 +312,7 @@ impl EffectManager {
     pub fn new() -> Self {
         EffectManager {
             effects: Vec::new(),
+            motion: MotionSettings::default(),
         }
     }
 
@@ -269,4 +336,43 @@ impl EffectManager {
     pub fn clear(&mut self) {
         self.effects.clear();
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{FloatingPosition, ParticleVelocity};
+
+    #[test]
+    fn test_reduced_motion_and_no_flash_hold_effects_still() {
+        let spark = ParticleEffect {
+            position: FloatingPosition { x: 7.6, y: 2.2, offset_x: 0.0, offset_y: 0.0 },
+            origin: (5, 5),
+            velocity: ParticleVelocity { x: 6.0, y: 0.0 },
+            color: Color::Yellow,
+            character: '*',
+            lifetime: 0.3,
+            max_lifetime: 0.5,
+            gravity: 0.0,
+            depth: 1,
+        };
+        let colors = [Color::Red, Color::Yellow];
+        let later = Duration::from_millis(150);
+        let interval = Duration::from_millis(100);
+
+        let full = MotionSettings::default();
+        assert_eq!(full.particle_position(&spark), (8, 2));
+        assert_eq!(full.flash_color(&colors, later, interval), Color::Yellow);
+        assert_eq!(full.explosion_radius(4, 0.5), 2);
+        assert!(!full.blink(false));
+
+        let calm = MotionSettings { reduced_motion: true, no_flash: true };
+        assert_eq!(calm.particle_position(&spark), (5, 5));
+        assert_eq!(calm.shake_intensity(2.0), 0.0);
+        assert_eq!(calm.flash_color(&colors, later, interval), Color::Red);
+        assert_eq!(calm.hit_background(Color::Red, 0.9), Color::DarkGrey);
+        assert_eq!(calm.explosion_radius(4, 0.5), 0);
+        assert!(calm.blink(false));
+        assert!(!calm.screen_flashes());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Which characters the display can be trusted to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphMode {
+    /// Anything goes; wide characters take two cells
+    Unicode,
+    /// Plain ASCII only; everything else is swapped for its fallback
+    Ascii,
+}
+
+impl GlyphMode {
+    /// `ASCII_DUNGEON_GLYPHS=ascii|unicode` wins; otherwise Unicode when the locale says UTF-8
+    pub fn detect() -> Self {
+        if let Some(mode) = std::env::var("ASCII_DUNGEON_GLYPHS").ok().and_then(|value| GlyphMode::parse(&value)) {
+            return mode;
+        }
+        let utf8_locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map_or(false, |value| {
+                let value = value.to_lowercase();
+                value.contains("utf-8") || value.contains("utf8")
+            });
+        // Windows terminals don't set a locale but have handled Unicode for years
+        if utf8_locale || cfg!(windows) {
+            GlyphMode::Unicode
+        } else {
+            GlyphMode::Ascii
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "unicode" | "utf8" | "utf-8" => Some(GlyphMode::Unicode),
+            "ascii" => Some(GlyphMode::Ascii),
+            _ => None,
+        }
+    }
+
+    /// The character to actually draw for `ch` in this mode
+    pub fn glyph(&self, ch: char) -> char {
+        match self {
+            GlyphMode::Unicode => ch,
+            GlyphMode::Ascii => ascii_fallback(ch),
+        }
+    }
+}
+
+/// Cells a character takes up: 0 for combining marks, 2 for wide CJK and emoji
+pub fn char_width(ch: char) -> u16 {
+    ch.width().unwrap_or(0) as u16
+}
+
+/// Cells a string takes up
+pub fn text_width(text: &str) -> u16 {
+    text.chars().map(char_width).sum()
+}
+
+/// The closest plain ASCII stand-in for a glyph
+///
+/// Tiles, monsters, items and status icons all draw from the same few symbols, so one table covers them.
+pub fn ascii_fallback(ch: char) -> char {
+    if ch.is_ascii() {
+        return ch;
+    }
+    match ch {
+        // Terrain: trees, lava, sand, boulders
+        '♠' => 'T',
+        '≈' => '~',
+        '·' | '∙' | '•' => '.',
+        '○' | '◘' | '◙' => 'o',
+        // Status icons
+        '♣' => '%',
+        // Shading and blocks
+        '█' | '▓' | '■' => '#',
+        '▒' | '░' => ':',
+        '▀' | '▄' | '▌' | '▐' => '#',
+        // Arrows and markers
+        '↑' | '▲' => '^',
+        '↓' | '▼' => 'v',
+        '←' | '◄' => '<',
+        '→' | '►' => '>',
+        '♥' => '*',
+        '♦' => '$',
+        '☺' | '☻' => '@',
+        '⌂' => 'n',
+        // Box drawing
+        '─' | '═' | '━' => '-',
+        '│' | '║' | '┃' => '|',
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼'
+        | '╔' | '╗' | '╚' | '╝' | '╠' | '╣' | '╦' | '╩' | '╬' => '+',
+        _ => '?',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_mode_replaces_unicode_glyphs() {
+        assert_eq!(GlyphMode::Ascii.glyph('♠'), 'T');
+        assert_eq!(GlyphMode::Ascii.glyph('≈'), '~');
+        assert_eq!(GlyphMode::Ascii.glyph('┌'), '+');
+        assert_eq!(GlyphMode::Ascii.glyph('#'), '#');
+        assert_eq!(GlyphMode::Ascii.glyph('🧪'), '?');
+        assert_eq!(GlyphMode::Unicode.glyph('♠'), '♠');
+    }
+
+    #[test]
+    fn test_character_widths() {
+        assert_eq!(char_width('@'), 1);
+        assert_eq!(char_width('≈'), 1);
+        assert_eq!(char_width('🧪'), 2);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(text_width("a🧪b"), 4);
+        assert_eq!(GlyphMode::parse(" ASCII "), Some(GlyphMode::Ascii));
+    }
+}
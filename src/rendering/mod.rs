@@ -1,13 +1,27 @@
 pub mod terminal;
 pub mod camera;
 pub mod effects;
+pub mod sidebar;
+pub mod renderer;
+pub mod glyphs;
+pub mod animation;
+pub mod screenshot;
+#[cfg(feature = "tiles")]
+pub mod tiles;
 
 use crossterm::style::Color;
-use crate::map::{Map, TileType};
-use crate::components::{Position, Renderable};
-pub use terminal::{Terminal, with_terminal};
-pub use camera::{Camera, create_camera_for_map};
-pub use effects::{VisualEffect, EffectType, EffectManager};
+use crate::map::{Map, TileType, Weather};
+use crate::components::{Position, Renderable, ParticleEffect};
+pub use terminal::{Terminal, CellGrid, ColorMode, with_terminal};
+pub use renderer::{Renderer, CrosstermRenderer, default_renderer};
+pub use glyphs::{GlyphMode, char_width, text_width, ascii_fallback};
+#[cfg(feature = "tiles")]
+pub use tiles::TilesRenderer;
+pub use camera::{Camera, CameraZoom, create_camera_for_map};
+pub use effects::{VisualEffect, EffectType, EffectManager, MotionSettings};
+pub use animation::{Animation, AnimationCell, AnimationQueue, ANIMATION_SPEED_RANGE};
+pub use screenshot::{ScreenshotFormat, SCREENSHOTS_DIR, DEFAULT_FORMATS, save_screenshot, screenshot_name};
+pub use sidebar::{SidebarInfo, SidebarEnemy, SidebarStatus, SIDEBAR_WIDTH, map_viewport_width, draw_commands};
 
 pub struct RenderContext {
     pub width: u16,
@@ -30,27 +44,71 @@ impl RenderContext {
         }
     }
     
+    /// Start the frame from a blank buffer; nothing reaches the screen until the frame ends
     pub fn clear(&self) {
-        let _ = with_terminal(|terminal| {
-            terminal.clear()?;
-            terminal.flush()
+        let _ = with_terminal(|terminal| terminal.clear());
+    }
+    
+    /// Show everything drawn since `clear`; the game does this once per frame in `GameState::render`
+    pub fn present(&self) {
+        let _ = with_terminal(|terminal| terminal.flush());
+    }
+    
+    /// The camera following the player, or a fresh one centered on them before the first update
+    pub fn camera_for(&self, map: &Map, player_pos: (i32, i32)) -> Camera {
+        self.camera.clone().unwrap_or_else(|| {
+            create_camera_for_map(map, map_viewport_width(self.width) as i32, self.height as i32, player_pos)
+        })
+    }
+    
+    /// Move the camera one frame closer to the player and apply this frame's screen shake
+    pub fn update_camera(&mut self, map: &Map, player_pos: (i32, i32), shake: (f32, f32)) {
+        let viewport = (map_viewport_width(self.width) as i32, self.height as i32);
+        let camera = self.camera.get_or_insert_with(|| {
+            create_camera_for_map(map, viewport.0, viewport.1, player_pos)
         });
+        if (camera.width, camera.height) != viewport {
+            camera.resize(viewport.0, viewport.1);
+        }
+        if (camera.map_width, camera.map_height) != (map.width, map.height) {
+            camera.update_map_size(map.width, map.height);
+        }
+        if camera.zoom == CameraZoom::ZoomedOut && !camera.can_zoom_out() {
+            camera.set_zoom(CameraZoom::Normal);
+        }
+        camera.follow(player_pos.0, player_pos.1);
+        camera.set_shake(shake.0, shake.1);
+    }
+    
+    /// Flip between the normal and zoomed-out map; returns false when the terminal is too small
+    pub fn toggle_zoom(&mut self) -> bool {
+        let camera = match self.camera.as_mut() {
+            Some(camera) => camera,
+            None => return false,
+        };
+        match camera.zoom {
+            CameraZoom::ZoomedOut => camera.set_zoom(CameraZoom::Normal),
+            CameraZoom::Normal if camera.can_zoom_out() => camera.set_zoom(CameraZoom::ZoomedOut),
+            CameraZoom::Normal => return false,
+        }
+        true
     }
     
     pub fn render_map(&self, map: &Map, player_pos: (i32, i32)) {
         let _ = with_terminal(|terminal| {
-            // Create camera
-            let mut camera = self.camera.clone().unwrap_or_else(|| {
-                create_camera_for_map(map, self.width as i32, self.height as i32, player_pos)
-            });
-            
-            // Center camera on player
-            camera.center_on(player_pos.0, player_pos.1);
+            let camera = self.camera_for(map, player_pos);
             
             // Render the map
             for screen_y in 0..camera.height {
                 for screen_x in 0..camera.width {
-                    let world_pos = camera.screen_to_world(screen_x, screen_y);
+                    // Zoomed out, each cell shows the most telling tile of its block
+                    let world_pos = camera.tiles_at_screen(screen_x, screen_y).into_iter()
+                        .filter(|(x, y)| *x >= 0 && *x < map.width && *y >= 0 && *y < map.height)
+                        .max_by_key(|(x, y)| {
+                            let idx = map.xy_idx(*x, *y);
+                            (map.visible_tiles[idx], map.revealed_tiles[idx], zoom_priority(map.tiles[idx]))
+                        })
+                        .unwrap_or_else(|| camera.screen_to_world(screen_x, screen_y));
                     let map_x = world_pos.0;
                     let map_y = world_pos.1;
                     
@@ -78,6 +136,25 @@ impl RenderContext {
                     TileType::Bridge => Color::DarkYellow,
                             };
                             
+                            // Branch stairs take the color of the area they lead to
+                            let fg = match map.branch_entrance_at(map_x, map_y) {
+                                Some(branch) => branch.entrance_color(),
+                                None => fg,
+                            };
+                            
+                            // Transient effects such as fire override the base tile
+                            let (glyph, fg) = match (map.tile_states.get(&idx), map.decals.get(&idx)) {
+                                (Some(state), _) => (state.effect.glyph().unwrap_or(glyph), state.effect.color()),
+                                (None, Some(decal)) => (decal.glyph().unwrap_or(glyph), decal.color()),
+                                (None, None) => (glyph, fg),
+                            };
+                            
+                            // Gas hangs over everything beneath it
+                            let (glyph, fg) = match map.gas.get(&idx) {
+                                Some(cloud) => (cloud.glyph(), cloud.kind.color()),
+                                None => (glyph, fg),
+                            };
+                            
                             terminal.draw_char_at(screen_x as u16, screen_y as u16, glyph, fg, Color::Black)?;
                         } else if map.revealed_tiles[idx] {
                             let glyph = match map.tiles[idx] {
@@ -94,19 +171,13 @@ impl RenderContext {
                 }
             }
             
-            terminal.flush()
+            Ok(())
         });
     }
     
     pub fn render_entities(&self, entities: &[(Position, Renderable)], map: &Map, player_pos: (i32, i32)) {
         let _ = with_terminal(|terminal| {
-            // Create camera
-            let mut camera = self.camera.clone().unwrap_or_else(|| {
-                create_camera_for_map(map, self.width as i32, self.height as i32, player_pos)
-            });
-            
-            // Center camera on player
-            camera.center_on(player_pos.0, player_pos.1);
+            let camera = self.camera_for(map, player_pos);
             
             // Render entities
             for (pos, render) in entities.iter() {
@@ -130,34 +201,27 @@ impl RenderContext {
                 }
             }
             
-            terminal.flush()
+            Ok(())
         });
     }
     
-    pub fn render_ui(&self, player_stats: &str, log_messages: &[String]) {
+    pub fn render_ui(&self, log_messages: &[String]) {
+        let log_width = map_viewport_width(self.width) as usize;
         let _ = with_terminal(|terminal| {
-            // Render player stats at the top
-            terminal.draw_text(0, 0, player_stats, Color::White, Color::Black)?;
-            
-            // Render log messages at the bottom
-            let log_start_y = self.height.saturating_sub(log_messages.len() as u16);
+            // Render log messages at the bottom, left of the sidebar and above the hotbar
+            let log_start_y = self.height.saturating_sub(crate::ui::HOTBAR_ROWS + log_messages.len() as u16);
             for (i, message) in log_messages.iter().enumerate() {
-                terminal.draw_text(0, log_start_y + i as u16, message, Color::White, Color::Black)?;
+                let message: String = message.chars().take(log_width).collect();
+                terminal.draw_text(0, log_start_y + i as u16, &message, Color::White, Color::Black)?;
             }
             
-            terminal.flush()
+            Ok(())
         });
     }
     
     pub fn render_effects(&self, map: &Map, player_pos: (i32, i32)) {
         let _ = with_terminal(|terminal| {
-            // Create camera
-            let mut camera = self.camera.clone().unwrap_or_else(|| {
-                create_camera_for_map(map, self.width as i32, self.height as i32, player_pos)
-            });
-            
-            // Center camera on player
-            camera.center_on(player_pos.0, player_pos.1);
+            let camera = self.camera_for(map, player_pos);
             
             // Render each effect
             for effect in &self.effect_manager.effects {
@@ -197,15 +261,13 @@ impl RenderContext {
                             let idx = map.xy_idx(effect.position.0, effect.position.1);
                             if idx < map.visible_tiles.len() && map.visible_tiles[idx] {
                                 // Calculate which color to use based on time
-                                let interval_secs = interval.as_secs_f32();
-                                let elapsed = effect.start_time.elapsed().as_secs_f32();
-                                let index = (elapsed / interval_secs) as usize % colors.len();
+                                let color = self.effect_manager.motion.flash_color(colors, effect.start_time.elapsed(), *interval);
                                 
                                 terminal.draw_char_at(
                                     screen_pos.0 as u16,
                                     screen_pos.1 as u16,
                                     *glyph,
-                                    colors[index],
+                                    color,
                                     Color::Black
                                 )?;
                             }
@@ -237,7 +299,7 @@ impl RenderContext {
                     EffectType::Explosion { glyph, color, radius } => {
                         // Calculate the progress of the effect
                         let progress = effect.start_time.elapsed().as_secs_f32() / effect.duration.as_secs_f32();
-                        let current_radius = (progress * *radius as f32) as i32;
+                        let current_radius = self.effect_manager.motion.explosion_radius(*radius, progress);
                         
                         // Render the explosion
                         for y in -current_radius..=current_radius {
@@ -268,7 +330,81 @@ impl RenderContext {
                 }
             }
             
-            terminal.flush()
+            Ok(())
+        });
+    }
+    
+    /// Draw the playing animation over the map, wherever the player can see it
+    pub fn render_animation(&self, cells: &[AnimationCell], map: &Map, player_pos: (i32, i32)) {
+        let _ = with_terminal(|terminal| {
+            let camera = self.camera_for(map, player_pos);
+            for cell in cells {
+                if !camera.is_visible(cell.x, cell.y) || !map.is_visible(cell.x, cell.y) {
+                    continue;
+                }
+                let screen_pos = camera.world_to_screen(cell.x, cell.y);
+                terminal.draw_char_at(screen_pos.0 as u16, screen_pos.1 as u16, cell.glyph, cell.fg, cell.bg)?;
+            }
+            Ok(())
+        });
+    }
+    
+    /// Draw particles in the order given, dimming those about to burn out
+    pub fn render_particles(&self, particles: &[ParticleEffect], map: &Map, player_pos: (i32, i32)) {
+        let _ = with_terminal(|terminal| {
+            let camera = self.camera_for(map, player_pos);
+            for particle in particles {
+                let (x, y) = self.effect_manager.motion.particle_position(particle);
+                if !camera.is_visible(x, y) || !map.is_visible(x, y) {
+                    continue;
+                }
+                let fg = if particle.lifetime < particle.max_lifetime * 0.3 { Color::DarkGrey } else { particle.color };
+                let screen_pos = camera.world_to_screen(x, y);
+                terminal.draw_char_at(screen_pos.0 as u16, screen_pos.1 as u16, particle.character, fg, Color::Black)?;
+            }
+            Ok(())
+        });
+    }
+    
+    /// Light up every visible tile while a lightning flash lasts
+    pub fn render_lightning(&self, weather: &Weather, map: &Map, player_pos: (i32, i32)) {
+        if !weather.is_flashing() || !self.effect_manager.motion.screen_flashes() {
+            return;
+        }
+        let _ = with_terminal(|terminal| {
+            let camera = self.camera_for(map, player_pos);
+            for screen_y in 0..camera.height {
+                for screen_x in 0..camera.width {
+                    let (x, y) = camera.screen_to_world(screen_x, screen_y);
+                    if map.is_visible(x, y) {
+                        let glyph = map.tiles[map.xy_idx(x, y)].glyph();
+                        terminal.draw_char_at(screen_x as u16, screen_y as u16, glyph, Color::Black, Color::White)?;
+                    }
+                }
+            }
+            Ok(())
+        });
+    }
+    
+    /// Draw falling rain, snow or ash over the open ground the player can see
+    pub fn render_weather(&self, weather: &Weather, map: &Map, player_pos: (i32, i32)) {
+        if weather.kind.overlay().is_none() {
+            return;
+        }
+        let _ = with_terminal(|terminal| {
+            let camera = self.camera_for(map, player_pos);
+            for screen_y in 0..camera.height {
+                for screen_x in 0..camera.width {
+                    let (x, y) = camera.screen_to_world(screen_x, screen_y);
+                    if !map.is_visible(x, y) || map.is_blocked(x, y) {
+                        continue;
+                    }
+                    if let Some((glyph, color)) = weather.overlay_at(x, y) {
+                        terminal.draw_char_at(screen_x as u16, screen_y as u16, glyph, color, Color::Black)?;
+                    }
+                }
+            }
+            Ok(())
         });
     }
     
@@ -276,6 +412,13 @@ impl RenderContext {
         self.effect_manager.add_effect(effect);
     }
     
+    /// Whether the last frame drawn would come out the same again: no effects playing and the
+    /// camera at rest on the player
+    pub fn is_still(&self, player_pos: (i32, i32)) -> bool {
+        self.effect_manager.effects.is_empty()
+            && self.camera.as_ref().map_or(true, |camera| !camera.is_settling(player_pos.0, player_pos.1))
+    }
+    
     pub fn update_effects(&mut self) {
         self.effect_manager.update();
     }
@@ -283,4 +426,16 @@ impl RenderContext {
     pub fn clear_effects(&mut self) {
         self.effect_manager.clear();
     }
-}
\ No newline at end of file
+}
+
+/// Which tile of a 2x2 block the zoomed-out map shows; rooms read better than their walls
+fn zoom_priority(tile: TileType) -> u8 {
+    match tile {
+        TileType::DownStairs | TileType::UpStairs => 5,
+        TileType::Door(_) => 4,
+        TileType::Trap(true) | TileType::Lava => 3,
+        TileType::Water | TileType::Bridge => 2,
+        TileType::Wall | TileType::Void => 0,
+        _ => 1,
+    }
+}
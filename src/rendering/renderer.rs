@@ -0,0 +1,116 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyEvent},
+    execute, queue,
+    style::{self, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, ClearType},
+    Result as CrosstermResult,
+};
+use std::io::{stdout, Stdout, Write};
+use super::glyphs;
+use super::terminal::CellGrid;
+
+/// Where finished frames end up and where keys come from
+///
+/// The terminal keeps the cell buffers; a renderer only has to show them and report input.
+pub trait Renderer {
+    /// Take over the display
+    fn init(&mut self) -> CrosstermResult<()>;
+    /// Give the display back
+    fn cleanup(&mut self) -> CrosstermResult<()>;
+    /// Display size in cells
+    fn size(&self) -> CrosstermResult<(u16, u16)>;
+    /// Show `frame`; `previous` is what the last present showed, for renderers that can update in place
+    fn present(&mut self, frame: &CellGrid, previous: &CellGrid) -> CrosstermResult<()>;
+    /// Wipe the display after a resize, before the next full present
+    fn clear(&mut self) -> CrosstermResult<()>;
+    /// Wait up to `timeout_ms` for a key press
+    fn poll_key(&mut self, timeout_ms: u64) -> CrosstermResult<Option<KeyEvent>>;
+}
+
+/// The default renderer, drawing characters in the terminal through crossterm
+pub struct CrosstermRenderer {
+    stdout: Stdout,
+}
+
+impl CrosstermRenderer {
+    pub fn new() -> Self {
+        CrosstermRenderer { stdout: stdout() }
+    }
+}
+
+impl Renderer for CrosstermRenderer {
+    fn init(&mut self) -> CrosstermResult<()> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            self.stdout,
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            terminal::Clear(ClearType::All)
+        )?;
+        Ok(())
+    }
+
+    fn cleanup(&mut self) -> CrosstermResult<()> {
+        terminal::disable_raw_mode()?;
+        execute!(
+            self.stdout,
+            style::ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        )?;
+        Ok(())
+    }
+
+    fn size(&self) -> CrosstermResult<(u16, u16)> {
+        terminal::size()
+    }
+
+    /// Send only the cells that changed since the last present
+    fn present(&mut self, frame: &CellGrid, previous: &CellGrid) -> CrosstermResult<()> {
+        let mut next_position = None;
+        let mut colors = None;
+        for (x, y, cell) in frame.diff(previous) {
+            // The wide glyph to the left already covers this cell
+            if cell.is_continuation() {
+                continue;
+            }
+            // Runs of changed cells on a row need only one cursor move and one color change
+            if next_position != Some((x, y)) {
+                queue!(self.stdout, cursor::MoveTo(x, y))?;
+            }
+            if colors != Some((cell.fg, cell.bg)) {
+                queue!(self.stdout, SetForegroundColor(cell.fg), SetBackgroundColor(cell.bg))?;
+                colors = Some((cell.fg, cell.bg));
+            }
+            queue!(self.stdout, style::Print(cell.ch))?;
+            next_position = Some((x + glyphs::char_width(cell.ch).max(1), y));
+        }
+        self.stdout.flush()
+    }
+
+    fn clear(&mut self) -> CrosstermResult<()> {
+        execute!(self.stdout, terminal::Clear(ClearType::All))
+    }
+
+    fn poll_key(&mut self, timeout_ms: u64) -> CrosstermResult<Option<KeyEvent>> {
+        if event::poll(std::time::Duration::from_millis(timeout_ms))? {
+            if let Event::Key(key_event) = event::read()? {
+                return Ok(Some(key_event));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The renderer picked at compile time: graphical tiles with the `tiles` feature, the terminal otherwise
+pub fn default_renderer() -> CrosstermResult<Box<dyn Renderer>> {
+    #[cfg(feature = "tiles")]
+    {
+        Ok(Box::new(super::tiles::TilesRenderer::new(&super::tiles::tileset_path())?))
+    }
+    #[cfg(not(feature = "tiles"))]
+    {
+        Ok(Box::new(CrosstermRenderer::new()))
+    }
+}
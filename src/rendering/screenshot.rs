@@ -0,0 +1,265 @@
+//! Screenshots of the terminal: the frame on screen written out as plain text to paste
+//! anywhere, as ANSI art that keeps its colors, and optionally as a page of HTML.
+
+use crossterm::style::Color;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use super::terminal::CellGrid;
+
+/// Where screenshots and death snapshots are written
+pub const SCREENSHOTS_DIR: &str = "screenshots";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotFormat {
+    /// UTF-8 text, for sharing a run anywhere
+    Text,
+    /// Text with ANSI color escapes, which `cat` shows as it looked
+    Ansi,
+    /// A standalone page
+    Html,
+}
+
+impl ScreenshotFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ScreenshotFormat::Text => "txt",
+            ScreenshotFormat::Ansi => "ans",
+            ScreenshotFormat::Html => "html",
+        }
+    }
+
+    pub fn render(self, grid: &CellGrid) -> String {
+        match self {
+            ScreenshotFormat::Text => to_text(grid),
+            ScreenshotFormat::Ansi => to_ansi(grid),
+            ScreenshotFormat::Html => to_html(grid),
+        }
+    }
+}
+
+/// Formats written when none are asked for
+pub const DEFAULT_FORMATS: [ScreenshotFormat; 2] = [ScreenshotFormat::Text, ScreenshotFormat::Ansi];
+
+/// The glyphs of each cell that starts one, row by row; cells never drawn read as spaces
+fn cells(grid: &CellGrid, y: u16) -> impl Iterator<Item = (char, Color, Color)> + '_ {
+    let (width, _) = grid.size();
+    (0..width)
+        .filter_map(move |x| grid.get(x, y))
+        .filter(|cell| !cell.is_continuation())
+        .map(|cell| (if cell.ch == '\0' { ' ' } else { cell.ch }, cell.fg, cell.bg))
+}
+
+pub fn to_text(grid: &CellGrid) -> String {
+    let (_, height) = grid.size();
+    let mut text = String::new();
+    for y in 0..height {
+        let line: String = cells(grid, y).map(|(ch, _, _)| ch).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// SGR parameters for a foreground color; backgrounds are ten higher
+fn sgr(color: Color, background: bool) -> String {
+    let offset = if background { 10 } else { 0 };
+    let code = match color {
+        Color::Reset => return (39 + offset).to_string(),
+        Color::Rgb { r, g, b } => return format!("{};2;{};{};{}", 38 + offset, r, g, b),
+        Color::AnsiValue(value) => return format!("{};5;{}", 38 + offset, value),
+        Color::Black => 30,
+        Color::DarkRed => 31,
+        Color::DarkGreen => 32,
+        Color::DarkYellow => 33,
+        Color::DarkBlue => 34,
+        Color::DarkMagenta => 35,
+        Color::DarkCyan => 36,
+        Color::Grey => 37,
+        Color::DarkGrey => 90,
+        Color::Red => 91,
+        Color::Green => 92,
+        Color::Yellow => 93,
+        Color::Blue => 94,
+        Color::Magenta => 95,
+        Color::Cyan => 96,
+        Color::White => 97,
+    };
+    (code + offset).to_string()
+}
+
+pub fn to_ansi(grid: &CellGrid) -> String {
+    let (_, height) = grid.size();
+    let mut ansi = String::new();
+    for y in 0..height {
+        let mut current = None;
+        for (ch, fg, bg) in cells(grid, y) {
+            if current != Some((fg, bg)) {
+                let _ = write!(ansi, "\x1b[{};{}m", sgr(fg, false), sgr(bg, true));
+                current = Some((fg, bg));
+            }
+            ansi.push(ch);
+        }
+        ansi.push_str("\x1b[0m\n");
+    }
+    ansi
+}
+
+/// The xterm palette, in the order of the sixteen named colors
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (128, 0, 0), (0, 128, 0), (128, 128, 0),
+    (0, 0, 128), (128, 0, 128), (0, 128, 128), (192, 192, 192),
+    (128, 128, 128), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (0, 0, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+fn rgb(color: Color, background: bool) -> (u8, u8, u8) {
+    let index = match color {
+        Color::Reset => if background { 0 } else { 7 },
+        Color::Rgb { r, g, b } => return (r, g, b),
+        Color::AnsiValue(value @ 16..=231) => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let value = value - 16;
+            return (level(value / 36), level(value / 6 % 6), level(value % 6));
+        },
+        Color::AnsiValue(value @ 232..=255) => {
+            let grey = 8 + (value - 232) * 10;
+            return (grey, grey, grey);
+        },
+        Color::AnsiValue(value) => value as usize,
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+    };
+    PALETTE[index]
+}
+
+/// The named color nearest to `color`, for displays limited to the sixteen
+pub fn nearest_basic(color: Color) -> Color {
+    const NAMED: [Color; 16] = [
+        Color::Black, Color::DarkRed, Color::DarkGreen, Color::DarkYellow,
+        Color::DarkBlue, Color::DarkMagenta, Color::DarkCyan, Color::Grey,
+        Color::DarkGrey, Color::Red, Color::Green, Color::Yellow,
+        Color::Blue, Color::Magenta, Color::Cyan, Color::White,
+    ];
+    match color {
+        Color::Rgb { .. } | Color::AnsiValue(_) => {
+            let (r, g, b) = rgb(color, false);
+            let distance = |&(pr, pg, pb): &(u8, u8, u8)| {
+                let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+                d(r, pr) + d(g, pg) + d(b, pb)
+            };
+            let nearest = (0..PALETTE.len()).min_by_key(|&i| distance(&PALETTE[i])).unwrap_or(7);
+            NAMED[nearest]
+        },
+        named => named,
+    }
+}
+
+fn hex(color: Color, background: bool) -> String {
+    let (r, g, b) = rgb(color, background);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub fn to_html(grid: &CellGrid) -> String {
+    let (_, height) = grid.size();
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>ASCII Dungeon Explorer</title></head>\n\
+         <body style=\"background:#000000\">\n<pre style=\"font-family:monospace;line-height:1.1\">\n",
+    );
+    for y in 0..height {
+        let mut current = None;
+        for (ch, fg, bg) in cells(grid, y) {
+            if current != Some((fg, bg)) {
+                if current.is_some() {
+                    html.push_str("</span>");
+                }
+                let _ = write!(html, "<span style=\"color:{};background:{}\">", hex(fg, false), hex(bg, true));
+                current = Some((fg, bg));
+            }
+            match ch {
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                '&' => html.push_str("&amp;"),
+                _ => html.push(ch),
+            }
+        }
+        if current.is_some() {
+            html.push_str("</span>");
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+/// Write `grid` into `directory` once per format as `<name>.<extension>`, returning the files
+pub fn save_screenshot(grid: &CellGrid, directory: &Path, name: &str, formats: &[ScreenshotFormat]) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(directory)?;
+    formats.iter().map(|format| {
+        let path = directory.join(format!("{}.{}", name, format.extension()));
+        fs::write(&path, format.render(grid))?;
+        Ok(path)
+    }).collect()
+}
+
+/// A name for a new screenshot taken now that no earlier one in `directory` has
+pub fn screenshot_name(directory: &Path, prefix: &str) -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let taken = |name: &str| fs::read_dir(directory)
+        .map(|entries| entries.flatten().any(|entry| entry.path().file_stem().map_or(false, |stem| stem == name)))
+        .unwrap_or(false);
+    let mut name = format!("{}-{}", prefix, seconds);
+    let mut copy = 1;
+    while taken(&name) {
+        copy += 1;
+        name = format!("{}-{}-{}", prefix, seconds, copy);
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::terminal::Cell;
+
+    fn frame() -> CellGrid {
+        let mut grid = CellGrid::new(6, 2);
+        grid.put(0, 0, Cell::new('@', Color::Yellow, Color::Black));
+        grid.put(1, 0, Cell::new('<', Color::White, Color::Black));
+        grid.put(0, 1, Cell::new('🧪', Color::Rgb { r: 10, g: 200, b: 30 }, Color::DarkBlue));
+        grid
+    }
+
+    #[test]
+    fn test_frames_are_written_as_text_ansi_and_html() {
+        let grid = frame();
+        assert_eq!(to_text(&grid), "@<\n🧪\n");
+
+        let ansi = to_ansi(&grid);
+        assert!(ansi.starts_with("\x1b[93;40m@\x1b[97;40m<"));
+        assert!(ansi.contains("\x1b[38;2;10;200;30;44m🧪"));
+        assert_eq!(ansi.matches("\x1b[0m\n").count(), 2);
+
+        let html = to_html(&grid);
+        assert!(html.contains("<span style=\"color:#ffff00;background:#000000\">@</span>"));
+        assert!(html.contains("&lt;"));
+        assert!(html.contains("color:#0ac81e;background:#000080\">🧪"));
+    }
+}
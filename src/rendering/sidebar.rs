@@ -0,0 +1,176 @@
+use crossterm::style::Color;
+use crate::game_state::HealthEstimate;
+use crate::ui::{StatusIcon, UIBar, UIComponent, UIRenderCommand};
+use super::{RenderContext, Terminal, with_terminal};
+
+/// Columns reserved on the right of the screen for the sidebar
+pub const SIDEBAR_WIDTH: u16 = 26;
+
+/// Segments in each of the sidebar's vital bars
+const BAR_SEGMENTS: i32 = 10;
+/// How long each half of a low-value blink lasts
+const FLASH_INTERVAL_MS: u128 = 400;
+
+/// Width left for the map once the sidebar has taken its share
+pub fn map_viewport_width(screen_width: u16) -> u16 {
+    screen_width.saturating_sub(SIDEBAR_WIDTH)
+}
+
+/// A visible enemy as listed in the sidebar
+#[derive(Debug, Clone)]
+pub struct SidebarEnemy {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub health: HealthEstimate,
+}
+
+/// An active effect in the icon strip under the health bar
+#[derive(Debug, Clone)]
+pub struct SidebarStatus {
+    pub name: String,
+    pub icon: StatusIcon,
+    pub turns: i32,
+    /// Close to wearing off, so the icon blinks
+    pub expiring: bool,
+}
+
+/// Everything the sidebar shows, gathered by the render system each frame
+#[derive(Debug, Clone, Default)]
+pub struct SidebarInfo {
+    pub name: String,
+    pub level: i32,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub mana: Option<(i32, i32)>,
+    pub stamina: Option<(i32, i32)>,
+    /// Progress towards the next level, in percent
+    pub xp_progress: f32,
+    /// Active effects and the turns they have left
+    pub statuses: Vec<SidebarStatus>,
+    /// Visible enemies, nearest first
+    pub enemies: Vec<SidebarEnemy>,
+    pub location: String,
+    pub turn: u32,
+    /// Day and time, such as "Day 2, 21:05"
+    pub clock: String,
+    pub night: bool,
+    /// Spell out in words what colors show, for players using a screen reader
+    pub text_labels: bool,
+}
+
+impl RenderContext {
+    pub fn render_sidebar(&self, info: &SidebarInfo) {
+        let x = map_viewport_width(self.width);
+        let text_width = SIDEBAR_WIDTH.saturating_sub(2) as usize;
+        let fit = |text: String| -> String { text.chars().take(text_width).collect() };
+        let flash_on = self.effect_manager.motion.blink(std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(true, |elapsed| (elapsed.as_millis() / FLASH_INTERVAL_MS) % 2 == 0));
+
+        let _ = with_terminal(|terminal| {
+            terminal.fill_rect(x, 0, SIDEBAR_WIDTH, self.height, ' ', Color::White, Color::Black)?;
+            terminal.draw_vertical_line(x, 0, self.height, Color::DarkGrey, Color::Black)?;
+            let x = x + 2;
+            let bottom = self.height.saturating_sub(1);
+            let mut row = 0;
+
+            // Vitals
+            terminal.draw_text(x, row, &fit(format!("{} (lvl {})", info.name, info.level)), Color::Yellow, Color::Black)?;
+            row += 1;
+            let mut bars = vec![UIBar::new("HP".to_string(), info.hp, info.max_hp, x as i32, 0, BAR_SEGMENTS)];
+            if let Some((mana, max_mana)) = info.mana {
+                bars.push(UIBar::new("MP".to_string(), mana, max_mana, x as i32, 0, BAR_SEGMENTS).with_fill_color(Color::Blue));
+            }
+            if let Some((stamina, max_stamina)) = info.stamina {
+                bars.push(UIBar::new("SP".to_string(), stamina, max_stamina, x as i32, 0, BAR_SEGMENTS));
+            }
+            bars.push(UIBar::experience(info.xp_progress, x as i32, 0, BAR_SEGMENTS));
+            for (i, mut bar) in bars.into_iter().enumerate() {
+                bar.y = row as i32;
+                draw_commands(terminal, &bar.with_flash(flash_on).render(0, 0, 0, 0))?;
+                row += 1;
+
+                // Active effects as icons with their turns left, right under the health bar
+                if i == 0 && !info.statuses.is_empty() {
+                    let mut column = x;
+                    for status in &info.statuses {
+                        let turns = status.turns.to_string();
+                        let width = 1 + turns.len() as u16;
+                        if column + width > x + text_width as u16 {
+                            break;
+                        }
+                        let color = if status.expiring && !flash_on { Color::DarkGrey } else { status.icon.color };
+                        terminal.draw_char_at(column, row, status.icon.glyph, color, Color::Black)?;
+                        terminal.draw_text(column + 1, row, &turns, Color::Grey, Color::Black)?;
+                        column += width + 1;
+                    }
+                    row += 1;
+                }
+            }
+            terminal.draw_text(x, row, &fit(info.location.clone()), Color::Cyan, Color::Black)?;
+            row += 1;
+            terminal.draw_text(x, row, &fit(format!("Turn {}", info.turn)), Color::Cyan, Color::Black)?;
+            row += 1;
+            let clock_color = if info.night { Color::Blue } else { Color::DarkYellow };
+            terminal.draw_text(x, row, &fit(info.clock.clone()), clock_color, Color::Black)?;
+            row += 2;
+
+            // Status effects spelled out, for those who can't go by the icons
+            if info.text_labels && !info.statuses.is_empty() {
+                terminal.draw_text(x, row, "Effects", Color::White, Color::Black)?;
+                row += 1;
+                for status in &info.statuses {
+                    if row >= bottom {
+                        break;
+                    }
+                    terminal.draw_text(x, row, &fit(format!("{} ({})", status.name, status.turns)), Color::Magenta, Color::Black)?;
+                    row += 1;
+                }
+                row += 1;
+            }
+
+            // Visible enemies
+            if !info.enemies.is_empty() && row < bottom {
+                terminal.draw_text(x, row, "In view", Color::White, Color::Black)?;
+                row += 1;
+                for enemy in &info.enemies {
+                    if row + 1 >= bottom {
+                        break;
+                    }
+                    terminal.draw_char_at(x, row, enemy.glyph, enemy.color, Color::Black)?;
+                    terminal.draw_text(x + 2, row, &fit(enemy.name.clone()), Color::Grey, Color::Black)?;
+                    row += 1;
+                    let health = if info.text_labels {
+                        format!("{} {}", enemy.health.bar(6), enemy.health.label())
+                    } else {
+                        enemy.health.bar(10)
+                    };
+                    terminal.draw_text(x + 2, row, &health, enemy.health.color(), Color::Black)?;
+                    row += 1;
+                }
+            }
+
+            Ok(())
+        });
+    }
+}
+
+/// Draw the text and panel commands produced by UI widgets
+pub fn draw_commands(terminal: &mut Terminal, commands: &[UIRenderCommand]) -> crossterm::Result<()> {
+    for command in commands {
+        match command {
+            UIRenderCommand::DrawText { x, y, text, fg, bg } if *x >= 0 && *y >= 0 => {
+                terminal.draw_text(*x as u16, *y as u16, text, *fg, *bg)?;
+            },
+            UIRenderCommand::DrawBox { x, y, width, height, border_color, fill_color }
+                if *x >= 0 && *y >= 0 && *width >= 2 && *height >= 2 => {
+                let (x, y, width, height) = (*x as u16, *y as u16, *width as u16, *height as u16);
+                terminal.fill_rect(x, y, width, height, ' ', *border_color, *fill_color)?;
+                terminal.draw_box(x, y, width, height, *border_color, *fill_color)?;
+            },
+            _ => {},
+        }
+    }
+    Ok(())
+}
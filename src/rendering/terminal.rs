@@ -1,115 +1,306 @@
 use crossterm::{
-    cursor,
-    event::{self, Event, KeyCode, KeyEvent},
-    execute, queue,
-    style::{self, Color, SetBackgroundColor, SetForegroundColor},
-    terminal::{self, ClearType},
+    event::{KeyCode, KeyEvent},
+    style::Color,
     Result as CrosstermResult,
 };
-use std::io::{stdout, Write};
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::sync::Once;
+use super::glyphs::{self, GlyphMode};
+use super::renderer::{Renderer, CrosstermRenderer, default_renderer};
+use super::screenshot::nearest_basic;
+
+/// Stands in the cell covered by the right half of a wide glyph; renderers skip it
+const CONTINUATION: char = '\u{1}';
+
+/// One character cell of the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Cell {
+    pub fn new(ch: char, fg: Color, bg: Color) -> Self {
+        Cell { ch, fg, bg }
+    }
+
+    /// A cell that never matches anything drawn, forcing it to be repainted
+    fn unknown() -> Self {
+        Cell::new('\0', Color::Reset, Color::Reset)
+    }
+
+    /// Whether this cell is the right half of the wide glyph before it
+    pub fn is_continuation(&self) -> bool {
+        self.ch == CONTINUATION
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::new(' ', Color::White, Color::Black)
+    }
+}
+
+/// How many colors the display can be trusted to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// Every color as drawn
+    Full,
+    /// The sixteen named colors, for terminals without 256 colors or true color
+    Basic,
+    /// White on black, with highlighted cells inverted
+    Monochrome,
+}
+
+impl ColorMode {
+    /// The colors to actually draw with for `fg` on `bg` in this mode
+    pub fn apply(self, fg: Color, bg: Color) -> (Color, Color) {
+        match self {
+            ColorMode::Full => (fg, bg),
+            ColorMode::Basic => (nearest_basic(fg), nearest_basic(bg)),
+            ColorMode::Monochrome => {
+                if !matches!(bg, Color::Black | Color::Reset) {
+                    (Color::Black, Color::White)
+                } else if matches!(fg, Color::Black | Color::DarkGrey) {
+                    (Color::DarkGrey, Color::Black)
+                } else {
+                    (Color::White, Color::Black)
+                }
+            },
+        }
+    }
+}
+
+impl Default for ColorMode {
+    fn default() -> Self {
+        ColorMode::Full
+    }
+}
+
+/// A full screen of cells; the terminal keeps one for the frame being drawn and one for what is shown
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellGrid {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl CellGrid {
+    pub fn new(width: u16, height: u16) -> Self {
+        CellGrid::filled(width, height, Cell::default())
+    }
+
+    fn filled(width: u16, height: u16, cell: Cell) -> Self {
+        CellGrid {
+            width,
+            height,
+            cells: vec![cell; width as usize * height as usize],
+        }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Set a cell, ignoring anything outside the grid
+    pub fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        if x < self.width && y < self.height {
+            self.cells[y as usize * self.width as usize + x as usize] = cell;
+        }
+    }
+
+    /// Place a glyph, giving wide ones two cells, and return how many cells it took
+    ///
+    /// Wide glyphs partly overwritten lose their other half, so nothing is left half-drawn.
+    pub fn put(&mut self, x: u16, y: u16, cell: Cell) -> u16 {
+        let mut width = glyphs::char_width(cell.ch);
+        if width == 0 || x >= self.width || y >= self.height {
+            return width;
+        }
+        let mut cell = cell;
+        if width == 2 && x + 1 >= self.width {
+            // No room for the right half at the edge of the screen
+            cell.ch = glyphs::ascii_fallback(cell.ch);
+            width = 1;
+        }
+
+        let end = x + width;
+        if self.get(x, y).map_or(false, |old| old.is_continuation()) && x > 0 {
+            self.set(x - 1, y, Cell::new(' ', cell.fg, cell.bg));
+        }
+        if self.get(end, y).map_or(false, |old| old.is_continuation()) {
+            self.set(end, y, Cell::new(' ', cell.fg, cell.bg));
+        }
+        self.set(x, y, cell);
+        if width == 2 {
+            self.set(x + 1, y, Cell::new(CONTINUATION, cell.fg, cell.bg));
+        }
+        width
+    }
+
+    pub fn fill(&mut self, cell: Cell) {
+        self.cells.iter_mut().for_each(|existing| *existing = cell);
+    }
+
+    /// Cells that differ from `previous`, row by row; everything when the sizes differ
+    pub fn diff(&self, previous: &CellGrid) -> Vec<(u16, u16, Cell)> {
+        let same_size = self.size() == previous.size();
+        let mut changed = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let idx = y as usize * self.width as usize + x as usize;
+                if !same_size || self.cells[idx] != previous.cells[idx] {
+                    changed.push((x, y, self.cells[idx]));
+                }
+            }
+        }
+        changed
+    }
+}
 
 /// A wrapper around terminal functionality to provide a clean interface
+///
+/// Drawing only touches the back buffer; `flush` hands the finished frame to the renderer.
 pub struct Terminal {
     width: u16,
     height: u16,
-    stdout: std::io::Stdout,
+    renderer: Box<dyn Renderer>,
+    /// The frame being drawn
+    back: CellGrid,
+    /// What the terminal is currently showing
+    front: CellGrid,
+    cursor: (u16, u16),
+    /// Inside a frame, flushes are deferred until `end_frame`
+    in_frame: bool,
+    glyph_mode: GlyphMode,
+    color_mode: ColorMode,
+    /// Whether the renderer has the display and must give it back
+    active: bool,
 }
 
 impl Terminal {
-    /// Create a new terminal instance
+    /// Create a new terminal instance showing frames through the default renderer
     pub fn new() -> CrosstermResult<Self> {
-        let (width, height) = terminal::size()?;
+        Terminal::with_renderer(default_renderer()?)
+    }
+
+    /// Create a terminal that shows its frames through `renderer`
+    pub fn with_renderer(renderer: Box<dyn Renderer>) -> CrosstermResult<Self> {
+        let (width, height) = renderer.size()?;
         Ok(Terminal {
             width,
             height,
-            stdout: stdout(),
+            renderer,
+            back: CellGrid::new(width, height),
+            front: CellGrid::filled(width, height, Cell::unknown()),
+            cursor: (0, 0),
+            in_frame: false,
+            glyph_mode: GlyphMode::detect(),
+            color_mode: ColorMode::Full,
+            active: false,
         })
     }
 
+    pub fn glyph_mode(&self) -> GlyphMode {
+        self.glyph_mode
+    }
+
+    /// Switch between Unicode and ASCII glyphs, repainting everything
+    pub fn set_glyph_mode(&mut self, mode: GlyphMode) {
+        if mode != self.glyph_mode {
+            self.glyph_mode = mode;
+            self.front.fill(Cell::unknown());
+        }
+    }
+
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    /// Switch how many colors are used, repainting everything
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        if mode != self.color_mode {
+            self.color_mode = mode;
+            self.front.fill(Cell::unknown());
+        }
+    }
+
+    /// Put one glyph in the back buffer as the current glyph and color modes allow, returning its width
+    fn put(&mut self, x: u16, y: u16, c: char, fg: Color, bg: Color) -> u16 {
+        let (fg, bg) = self.color_mode.apply(fg, bg);
+        self.back.put(x, y, Cell::new(self.glyph_mode.glyph(c), fg, bg))
+    }
+
     /// Initialize the terminal for rendering
     pub fn init(&mut self) -> CrosstermResult<()> {
-        terminal::enable_raw_mode()?;
-        execute!(
-            self.stdout,
-            terminal::EnterAlternateScreen,
-            cursor::Hide,
-            terminal::Clear(ClearType::All)
-        )?;
+        self.renderer.init()?;
+        self.active = true;
         Ok(())
     }
 
     /// Clean up the terminal when the program exits
     pub fn cleanup(&mut self) -> CrosstermResult<()> {
-        terminal::disable_raw_mode()?;
-        execute!(
-            self.stdout,
-            style::ResetColor,
-            cursor::Show,
-            terminal::LeaveAlternateScreen
-        )?;
-        Ok(())
+        self.active = false;
+        self.renderer.cleanup()
     }
 
     /// Clear the entire screen
     pub fn clear(&mut self) -> CrosstermResult<()> {
-        execute!(self.stdout, terminal::Clear(ClearType::All))?;
+        self.back.fill(Cell::default());
         Ok(())
     }
 
     /// Clear a specific line
     pub fn clear_line(&mut self, y: u16) -> CrosstermResult<()> {
-        execute!(
-            self.stdout,
-            cursor::MoveTo(0, y),
-            terminal::Clear(ClearType::CurrentLine)
-        )?;
+        for x in 0..self.width {
+            self.back.set(x, y, Cell::default());
+        }
         Ok(())
     }
 
     /// Move the cursor to a specific position
     pub fn move_cursor(&mut self, x: u16, y: u16) -> CrosstermResult<()> {
-        execute!(self.stdout, cursor::MoveTo(x, y))?;
+        self.cursor = (x, y);
         Ok(())
     }
 
     /// Draw a single character at the current cursor position
     pub fn draw_char(&mut self, c: char, fg: Color, bg: Color) -> CrosstermResult<()> {
-        queue!(
-            self.stdout,
-            SetForegroundColor(fg),
-            SetBackgroundColor(bg),
-            style::Print(c)
-        )?;
+        let (x, y) = self.cursor;
+        let width = self.put(x, y, c, fg, bg);
+        self.cursor = (x.saturating_add(width), y);
         Ok(())
     }
 
     /// Draw a character at a specific position
     pub fn draw_char_at(&mut self, x: u16, y: u16, c: char, fg: Color, bg: Color) -> CrosstermResult<()> {
-        queue!(
-            self.stdout,
-            cursor::MoveTo(x, y),
-            SetForegroundColor(fg),
-            SetBackgroundColor(bg),
-            style::Print(c)
-        )?;
+        self.put(x, y, c, fg, bg);
         Ok(())
     }
 
     /// Draw text at a specific position
     pub fn draw_text(&mut self, x: u16, y: u16, text: &str, fg: Color, bg: Color) -> CrosstermResult<()> {
-        queue!(
-            self.stdout,
-            cursor::MoveTo(x, y),
-            SetForegroundColor(fg),
-            SetBackgroundColor(bg),
-            style::Print(text)
-        )?;
+        let mut x = x;
+        for c in text.chars() {
+            x = x.saturating_add(self.put(x, y, c, fg, bg));
+        }
         Ok(())
     }
 
     /// Draw text centered horizontally at a specific y position
     pub fn draw_text_centered(&mut self, y: u16, text: &str, fg: Color, bg: Color) -> CrosstermResult<()> {
-        let x = self.width.saturating_sub(text.len() as u16) / 2;
+        let x = self.width.saturating_sub(glyphs::text_width(text)) / 2;
         self.draw_text(x, y, text, fg, bg)?;
         Ok(())
     }
@@ -163,20 +354,41 @@ impl Terminal {
         Ok(())
     }
 
-    /// Flush the output buffer to the terminal
+    /// Start a frame: every layer draws into the back buffer and flushes wait for `end_frame`
+    pub fn begin_frame(&mut self) -> CrosstermResult<()> {
+        self.in_frame = true;
+        Ok(())
+    }
+
+    /// Finish the frame and show it in a single write
+    pub fn end_frame(&mut self) -> CrosstermResult<()> {
+        self.in_frame = false;
+        self.present()
+    }
+
+    /// Flush the output buffer to the terminal, unless a frame is still being drawn
     pub fn flush(&mut self) -> CrosstermResult<()> {
-        self.stdout.flush()?;
+        if self.in_frame {
+            return Ok(());
+        }
+        self.present()
+    }
+
+    /// Show the back buffer and remember it as what is on screen
+    fn present(&mut self) -> CrosstermResult<()> {
+        self.renderer.present(&self.back, &self.front)?;
+        self.front.clone_from(&self.back);
         Ok(())
     }
 
     /// Check if a key is pressed and return the key event
-    pub fn poll_key(&self, timeout_ms: u64) -> CrosstermResult<Option<KeyEvent>> {
-        if event::poll(std::time::Duration::from_millis(timeout_ms))? {
-            if let Event::Key(key_event) = event::read()? {
-                return Ok(Some(key_event));
-            }
-        }
-        Ok(None)
+    pub fn poll_key(&mut self, timeout_ms: u64) -> CrosstermResult<Option<KeyEvent>> {
+        self.renderer.poll_key(timeout_ms)
+    }
+
+    /// The frame last shown, as it appears on screen
+    pub fn screen(&self) -> &CellGrid {
+        &self.front
     }
 
     /// Get the terminal size
@@ -184,25 +396,169 @@ impl Terminal {
         (self.width, self.height)
     }
 
-    /// Update the stored terminal size
+    /// Update the stored terminal size, repainting everything if it changed
     pub fn update_size(&mut self) -> CrosstermResult<()> {
-        let (width, height) = terminal::size()?;
-        self.width = width;
-        self.height = height;
+        let (width, height) = self.renderer.size()?;
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.back = CellGrid::new(width, height);
+            self.front = CellGrid::filled(width, height, Cell::unknown());
+            self.renderer.clear()?;
+        }
         Ok(())
     }
 }
 
+impl Drop for Terminal {
+    /// Give the display back even when the game exits without `shutdown_terminal`
+    fn drop(&mut self) {
+        if self.active {
+            let _ = self.cleanup();
+        }
+    }
+}
+
+thread_local! {
+    /// The one terminal, kept alive between frames so its buffers can be diffed
+    static TERMINAL: RefCell<Option<Terminal>> = RefCell::new(None);
+}
+
 /// A helper function to execute code with a terminal
+///
+/// The terminal is set up on first use and stays in the alternate screen until `shutdown_terminal`.
 pub fn with_terminal<F, T>(f: F) -> CrosstermResult<T>
 where
     F: FnOnce(&mut Terminal) -> CrosstermResult<T>,
 {
-    let mut terminal = Terminal::new()?;
-    terminal.init()?;
-    
-    let result = f(&mut terminal);
-    
-    terminal.cleanup()?;
-    result
-}
\ No newline at end of file
+    TERMINAL.with(|slot| {
+        let mut slot = slot.try_borrow_mut().map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::WouldBlock, "the terminal is already in use")
+        })?;
+        if slot.is_none() {
+            let mut terminal = Terminal::new()?;
+            terminal.init()?;
+            install_panic_hook();
+            *slot = Some(terminal);
+        }
+        let terminal = slot.as_mut().expect("terminal was just initialized");
+        terminal.update_size()?;
+        f(terminal)
+    })
+}
+
+/// Restore the terminal to its normal state when the game exits
+pub fn shutdown_terminal() -> CrosstermResult<()> {
+    let terminal = TERMINAL.with(|slot| slot.borrow_mut().take());
+    match terminal {
+        Some(mut terminal) => terminal.cleanup(),
+        None => Ok(()),
+    }
+}
+
+/// Drop the terminal so it gives the display back; a panic in the middle of drawing leaves it
+/// borrowed, and then the display is restored directly
+fn restore_terminal() {
+    let restored = TERMINAL.try_with(|slot| match slot.try_borrow_mut() {
+        Ok(mut slot) => slot.take().is_some(),
+        Err(_) => false,
+    });
+    if !restored.unwrap_or(false) {
+        let _ = CrosstermRenderer::new().cleanup();
+    }
+}
+
+/// Restore the terminal before a panic message is printed, so it isn't lost in the alternate screen
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    /// Counts how often the display is given back
+    struct CountingRenderer {
+        cleanups: Rc<std::cell::Cell<u32>>,
+    }
+
+    impl Renderer for CountingRenderer {
+        fn init(&mut self) -> CrosstermResult<()> { Ok(()) }
+        fn cleanup(&mut self) -> CrosstermResult<()> {
+            self.cleanups.set(self.cleanups.get() + 1);
+            Ok(())
+        }
+        fn size(&self) -> CrosstermResult<(u16, u16)> { Ok((4, 2)) }
+        fn present(&mut self, _frame: &CellGrid, _previous: &CellGrid) -> CrosstermResult<()> { Ok(()) }
+        fn clear(&mut self) -> CrosstermResult<()> { Ok(()) }
+        fn poll_key(&mut self, _timeout_ms: u64) -> CrosstermResult<Option<KeyEvent>> { Ok(None) }
+    }
+
+    #[test]
+    fn test_dropping_the_terminal_restores_the_display_once() {
+        let cleanups = Rc::new(std::cell::Cell::new(0));
+        let renderer = || Box::new(CountingRenderer { cleanups: cleanups.clone() });
+
+        // Never initialized, so nothing to give back
+        drop(Terminal::with_renderer(renderer()).unwrap());
+        assert_eq!(cleanups.get(), 0);
+
+        let mut terminal = Terminal::with_renderer(renderer()).unwrap();
+        terminal.init().unwrap();
+        drop(terminal);
+        assert_eq!(cleanups.get(), 1);
+
+        // Already cleaned up by hand, so dropping doesn't do it again
+        let mut terminal = Terminal::with_renderer(renderer()).unwrap();
+        terminal.init().unwrap();
+        terminal.cleanup().unwrap();
+        drop(terminal);
+        assert_eq!(cleanups.get(), 2);
+    }
+
+    #[test]
+    fn test_diff_only_reports_changed_cells() {
+        let previous = CellGrid::new(4, 2);
+        let mut next = previous.clone();
+        next.set(1, 0, Cell::new('@', Color::Yellow, Color::Black));
+        next.set(3, 1, Cell::new('#', Color::White, Color::Black));
+        next.set(9, 9, Cell::new('x', Color::White, Color::Black));
+
+        let changed = next.diff(&previous);
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[0], (1, 0, Cell::new('@', Color::Yellow, Color::Black)));
+        assert_eq!(changed[1].0, 3);
+        assert!(previous.diff(&previous).is_empty());
+    }
+
+    #[test]
+    fn test_wide_glyphs_take_two_cells() {
+        let mut grid = CellGrid::new(4, 1);
+        assert_eq!(grid.put(0, 0, Cell::new('🧪', Color::White, Color::Black)), 2);
+        assert!(grid.get(1, 0).unwrap().is_continuation());
+
+        // Overwriting either half clears the other
+        grid.put(1, 0, Cell::new('x', Color::White, Color::Black));
+        assert_eq!(grid.get(0, 0).unwrap().ch, ' ');
+        assert_eq!(grid.get(1, 0).unwrap().ch, 'x');
+
+        // No room at the right edge, so the fallback is drawn instead
+        assert_eq!(grid.put(3, 0, Cell::new('🧪', Color::White, Color::Black)), 1);
+        assert_eq!(grid.get(3, 0).unwrap().ch, '?');
+    }
+
+    #[test]
+    fn test_resized_grid_repaints_everything() {
+        let previous = CellGrid::new(4, 2);
+        let next = CellGrid::new(3, 2);
+        assert_eq!(next.diff(&previous).len(), 6);
+    }
+}
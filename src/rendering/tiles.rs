@@ -0,0 +1,316 @@
+use crossterm::{
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    style::Color,
+    Result as CrosstermResult,
+};
+use sdl2::{
+    event::Event as SdlEvent,
+    keyboard::{Keycode, Mod},
+    pixels::{Color as SdlColor, PixelFormatEnum},
+    rect::Rect,
+    render::{BlendMode, Canvas, Texture},
+    video::Window,
+    EventPump, Sdl,
+};
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind};
+use super::renderer::Renderer;
+use super::terminal::CellGrid;
+
+/// Tileset used when `ASCII_DUNGEON_TILESET` doesn't name another one
+pub const DEFAULT_TILESET: &str = "assets/tileset.png";
+
+/// Cells shown when the window first opens
+const START_COLUMNS: u32 = 80;
+const START_ROWS: u32 = 50;
+
+/// Where to load the tileset from
+pub fn tileset_path() -> String {
+    std::env::var("ASCII_DUNGEON_TILESET").unwrap_or_else(|_| DEFAULT_TILESET.to_string())
+}
+
+fn sdl_error(message: impl ToString) -> Error {
+    Error::new(ErrorKind::Other, message.to_string())
+}
+
+/// Draws the cell grid in a window, one tile from a 16x16 code page 437 tileset per glyph
+///
+/// Tiles are tinted with the cell's foreground color, so the tileset should be drawn in white on
+/// black or magenta; either background is treated as transparent.
+pub struct TilesRenderer {
+    _context: Sdl,
+    canvas: Canvas<Window>,
+    events: EventPump,
+    tileset: Texture,
+    tile_width: u32,
+    tile_height: u32,
+    pending_keys: VecDeque<KeyEvent>,
+}
+
+impl TilesRenderer {
+    pub fn new(tileset: &str) -> CrosstermResult<Self> {
+        let image = image::open(tileset)
+            .map_err(|e| sdl_error(format!("Unable to load tileset {}: {}", tileset, e)))?
+            .to_rgba8();
+        let (image_width, image_height) = image.dimensions();
+        let mut pixels = image.into_raw();
+        for pixel in pixels.chunks_exact_mut(4) {
+            if matches!(pixel[..3], [0, 0, 0] | [255, 0, 255]) {
+                pixel[3] = 0;
+            }
+        }
+
+        let context = sdl2::init().map_err(sdl_error)?;
+        let video = context.video().map_err(sdl_error)?;
+        let tile_width = image_width / 16;
+        let tile_height = image_height / 16;
+        let window = video.window("ASCII Dungeon Explorer", START_COLUMNS * tile_width, START_ROWS * tile_height)
+            .position_centered()
+            .resizable()
+            .build()
+            .map_err(sdl_error)?;
+        let canvas = window.into_canvas().present_vsync().build().map_err(sdl_error)?;
+
+        let mut texture = canvas.texture_creator()
+            .create_texture_static(PixelFormatEnum::RGBA32, image_width, image_height)
+            .map_err(sdl_error)?;
+        texture.update(None, &pixels, image_width as usize * 4).map_err(sdl_error)?;
+        texture.set_blend_mode(BlendMode::Blend);
+
+        video.text_input().start();
+        let events = context.event_pump().map_err(sdl_error)?;
+
+        Ok(TilesRenderer {
+            _context: context,
+            canvas,
+            events,
+            tileset: texture,
+            tile_width,
+            tile_height,
+            pending_keys: VecDeque::new(),
+        })
+    }
+}
+
+impl Renderer for TilesRenderer {
+    fn init(&mut self) -> CrosstermResult<()> {
+        self.canvas.window_mut().show();
+        Ok(())
+    }
+
+    fn cleanup(&mut self) -> CrosstermResult<()> {
+        self.canvas.window_mut().hide();
+        Ok(())
+    }
+
+    fn size(&self) -> CrosstermResult<(u16, u16)> {
+        let (width, height) = self.canvas.output_size().map_err(sdl_error)?;
+        Ok(((width / self.tile_width) as u16, (height / self.tile_height) as u16))
+    }
+
+    /// Window contents don't survive a buffer swap, so every present redraws the whole grid
+    fn present(&mut self, frame: &CellGrid, _previous: &CellGrid) -> CrosstermResult<()> {
+        self.canvas.set_draw_color(SdlColor::RGB(0, 0, 0));
+        self.canvas.clear();
+
+        let (columns, rows) = frame.size();
+        for y in 0..rows {
+            for x in 0..columns {
+                let cell = match frame.get(x, y) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                let target = Rect::new(
+                    (x as u32 * self.tile_width) as i32,
+                    (y as u32 * self.tile_height) as i32,
+                    self.tile_width,
+                    self.tile_height,
+                );
+                let (r, g, b) = rgb(cell.bg, (0, 0, 0));
+                if (r, g, b) != (0, 0, 0) {
+                    self.canvas.set_draw_color(SdlColor::RGB(r, g, b));
+                    self.canvas.fill_rect(target).map_err(sdl_error)?;
+                }
+                if cell.ch == ' ' || cell.is_continuation() {
+                    continue;
+                }
+
+                let index = cp437_index(cell.ch);
+                let source = Rect::new(
+                    ((index % 16) * self.tile_width) as i32,
+                    ((index / 16) * self.tile_height) as i32,
+                    self.tile_width,
+                    self.tile_height,
+                );
+                let (r, g, b) = rgb(cell.fg, (192, 192, 192));
+                self.tileset.set_color_mod(r, g, b);
+                self.canvas.copy(&self.tileset, source, target).map_err(sdl_error)?;
+            }
+        }
+
+        self.canvas.present();
+        Ok(())
+    }
+
+    fn clear(&mut self) -> CrosstermResult<()> {
+        self.canvas.set_draw_color(SdlColor::RGB(0, 0, 0));
+        self.canvas.clear();
+        self.canvas.present();
+        Ok(())
+    }
+
+    fn poll_key(&mut self, timeout_ms: u64) -> CrosstermResult<Option<KeyEvent>> {
+        if self.pending_keys.is_empty() {
+            let first = if timeout_ms == 0 {
+                self.events.poll_event()
+            } else {
+                self.events.wait_event_timeout(timeout_ms as u32)
+            };
+            for event in first.into_iter().chain(std::iter::from_fn(|| self.events.poll_event())) {
+                self.pending_keys.extend(key_events(event));
+            }
+        }
+        Ok(self.pending_keys.pop_front())
+    }
+}
+
+/// Translate a window event into the key presses the game understands
+///
+/// Printable characters come from text input, so shifted and layout-specific keys arrive as typed.
+fn key_events(event: SdlEvent) -> Vec<KeyEvent> {
+    match event {
+        SdlEvent::TextInput { text, .. } => text.chars()
+            .map(|ch| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))
+            .collect(),
+        SdlEvent::KeyDown { keycode: Some(keycode), keymod, .. } => {
+            let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+            let code = match keycode {
+                Keycode::Return | Keycode::KpEnter => KeyCode::Enter,
+                Keycode::Escape => KeyCode::Esc,
+                Keycode::Backspace => KeyCode::Backspace,
+                Keycode::Tab if shift => KeyCode::BackTab,
+                Keycode::Tab => KeyCode::Tab,
+                Keycode::Up => KeyCode::Up,
+                Keycode::Down => KeyCode::Down,
+                Keycode::Left => KeyCode::Left,
+                Keycode::Right => KeyCode::Right,
+                Keycode::Home => KeyCode::Home,
+                Keycode::End => KeyCode::End,
+                Keycode::PageUp => KeyCode::PageUp,
+                Keycode::PageDown => KeyCode::PageDown,
+                Keycode::Delete => KeyCode::Delete,
+                Keycode::F1 => KeyCode::F(1),
+                Keycode::F2 => KeyCode::F(2),
+                Keycode::F3 => KeyCode::F(3),
+                Keycode::F4 => KeyCode::F(4),
+                Keycode::F5 => KeyCode::F(5),
+                Keycode::F6 => KeyCode::F(6),
+                Keycode::F7 => KeyCode::F(7),
+                Keycode::F8 => KeyCode::F(8),
+                Keycode::F9 => KeyCode::F(9),
+                Keycode::F10 => KeyCode::F(10),
+                Keycode::F11 => KeyCode::F(11),
+                Keycode::F12 => KeyCode::F(12),
+                _ => return Vec::new(),
+            };
+            let modifiers = if shift { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+            vec![KeyEvent::new(code, modifiers)]
+        }
+        // Closing the window asks the game to quit, the same as pressing q
+        SdlEvent::Quit { .. } => vec![KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)],
+        _ => Vec::new(),
+    }
+}
+
+/// The standard 16-color terminal palette, plus the 256-color cube and greys
+fn rgb(color: Color, reset: (u8, u8, u8)) -> (u8, u8, u8) {
+    match color {
+        Color::Reset => reset,
+        Color::Black => (0, 0, 0),
+        Color::DarkRed => (128, 0, 0),
+        Color::DarkGreen => (0, 128, 0),
+        Color::DarkYellow => (128, 128, 0),
+        Color::DarkBlue => (0, 0, 128),
+        Color::DarkMagenta => (128, 0, 128),
+        Color::DarkCyan => (0, 128, 128),
+        Color::Grey => (192, 192, 192),
+        Color::DarkGrey => (128, 128, 128),
+        Color::Red => (255, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::Blue => (0, 0, 255),
+        Color::Magenta => (255, 0, 255),
+        Color::Cyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::AnsiValue(value) => match value {
+            0..=15 => {
+                const BASIC: [Color; 16] = [
+                    Color::Black, Color::DarkRed, Color::DarkGreen, Color::DarkYellow,
+                    Color::DarkBlue, Color::DarkMagenta, Color::DarkCyan, Color::Grey,
+                    Color::DarkGrey, Color::Red, Color::Green, Color::Yellow,
+                    Color::Blue, Color::Magenta, Color::Cyan, Color::White,
+                ];
+                rgb(BASIC[value as usize], reset)
+            }
+            16..=231 => {
+                let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+                let n = value - 16;
+                (level(n / 36), level(n / 6 % 6), level(n % 6))
+            }
+            _ => {
+                let grey = 8 + (value - 232) * 10;
+                (grey, grey, grey)
+            }
+        },
+    }
+}
+
+/// Position of a glyph in a code page 437 tileset; anything it lacks shows as '?'
+fn cp437_index(ch: char) -> u32 {
+    if (' '..='~').contains(&ch) {
+        return ch as u32;
+    }
+    match ch {
+        '☺' => 1, '☻' => 2, '♥' => 3, '♦' => 4, '♣' => 5, '♠' => 6, '•' => 7, '◘' => 8,
+        '○' => 9, '◙' => 10, '♂' => 11, '♀' => 12, '♪' => 13, '♫' => 14, '☼' => 15,
+        '►' => 16, '◄' => 17, '↕' => 18, '‼' => 19, '¶' => 20, '§' => 21, '▬' => 22, '↨' => 23,
+        '↑' => 24, '↓' => 25, '→' => 26, '←' => 27, '∟' => 28, '↔' => 29, '▲' => 30, '▼' => 31,
+        '⌂' => 127,
+        '░' => 176, '▒' => 177, '▓' => 178, '│' => 179, '┤' => 180, '╡' => 181, '╢' => 182, '╖' => 183,
+        '╕' => 184, '╣' => 185, '║' => 186, '╗' => 187, '╝' => 188, '╜' => 189, '╛' => 190, '┐' => 191,
+        '└' => 192, '┴' => 193, '┬' => 194, '├' => 195, '─' => 196, '┼' => 197, '╞' => 198, '╟' => 199,
+        '╚' => 200, '╔' => 201, '╩' => 202, '╦' => 203, '╠' => 204, '═' => 205, '╬' => 206, '╧' => 207,
+        '╨' => 208, '╤' => 209, '╥' => 210, '╙' => 211, '╘' => 212, '╒' => 213, '╓' => 214, '╫' => 215,
+        '╪' => 216, '┘' => 217, '┌' => 218, '█' => 219, '▄' => 220, '▌' => 221, '▐' => 222, '▀' => 223,
+        'α' => 224, 'ß' => 225, 'Γ' => 226, 'π' => 227, 'Σ' => 228, 'σ' => 229, 'µ' => 230, 'τ' => 231,
+        'Φ' => 232, 'Θ' => 233, 'Ω' => 234, 'δ' => 235, '∞' => 236, 'φ' => 237, 'ε' => 238, '∩' => 239,
+        '≡' => 240, '±' => 241, '≥' => 242, '≤' => 243, '⌠' => 244, '⌡' => 245, '÷' => 246, '≈' => 247,
+        '°' => 248, '∙' => 249, '·' => 250, '√' => 251, 'ⁿ' => 252, '²' => 253, '■' => 254,
+        _ => '?' as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyphs_map_to_code_page_437() {
+        assert_eq!(cp437_index('@'), 64);
+        assert_eq!(cp437_index('#'), 35);
+        assert_eq!(cp437_index('♠'), 6);
+        assert_eq!(cp437_index('≈'), 247);
+        assert_eq!(cp437_index('█'), 219);
+        assert_eq!(cp437_index('🗡'), '?' as u32);
+    }
+
+    #[test]
+    fn test_ansi_palette() {
+        assert_eq!(rgb(Color::AnsiValue(9), (0, 0, 0)), (255, 0, 0));
+        assert_eq!(rgb(Color::AnsiValue(16), (0, 0, 0)), (0, 0, 0));
+        assert_eq!(rgb(Color::AnsiValue(231), (0, 0, 0)), (255, 255, 255));
+        assert_eq!(rgb(Color::AnsiValue(232), (0, 0, 0)), (8, 8, 8));
+        assert_eq!(rgb(Color::Reset, (1, 2, 3)), (1, 2, 3));
+    }
+}
@@ -1,11 +1,21 @@
 use serde::{Serialize, Deserialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use crate::components::CaptiveKind;
+use crate::map::Location;
+use crate::localization::Message;
+use crate::achievements::GameEvent;
+use crate::utils::{fnv1a, FNV_OFFSET_BASIS};
 
 // Game log resource
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct GameLog {
     pub entries: VecDeque<String>,
     pub max_entries: usize,
+    /// Entries ever added, for readers following the log as it scrolls
+    #[serde(skip)]
+    pub written: u64,
 }
 
 impl GameLog {
@@ -13,63 +23,142 @@ impl GameLog {
         GameLog {
             entries: VecDeque::with_capacity(max_entries),
             max_entries,
+            written: 0,
         }
     }
     
     pub fn add_entry(&mut self, entry: String) {
         self.entries.push_back(entry);
+        self.written += 1;
         if self.entries.len() > self.max_entries {
             self.entries.pop_front();
         }
     }
     
+    /// Add a message in the player's language
+    pub fn add_message(&mut self, message: Message) {
+        self.add_entry(message.text());
+    }
+    
     pub fn clear(&mut self) {
         self.entries.clear();
     }
 }
 
-// Random number generator resource
-#[derive(Serialize, Deserialize, Clone)]
+// Game event bus: what happened this turn, published by the systems that saw it and read by the
+// achievement evaluation at the end of the turn
+#[derive(Default, Clone, Debug)]
+pub struct GameEvents {
+    pub events: Vec<GameEvent>,
+}
+
+impl GameEvents {
+    pub fn publish(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn take(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Sub-stream for laying out levels and deciding what lives on them
+pub const MAPGEN_STREAM: &str = "mapgen";
+/// Sub-stream for what treasure and artifacts turn up
+pub const LOOT_STREAM: &str = "loot";
+/// Sub-stream for attack, defense and damage rolls
+pub const COMBAT_STREAM: &str = "combat";
+/// Sub-stream for the guild's recruits and how its agents fare on their contracts
+pub const GUILD_STREAM: &str = "guild";
+/// Sub-stream for what happens in the world above while the character is below
+pub const WORLD_STREAM: &str = "world";
+
+// Random number generator resource: one ChaCha stream per run, seeded once and carried on call
+// to call. Named sub-streams fork off the same seed, so rolling more often in one of them never
+// shifts what another produces; the same seed always makes the same run.
+// Saves from before the stream was kept hold only the seed; the save migrations fill in the rest.
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RandomNumberGenerator {
     pub seed: u64,
+    rng: ChaCha8Rng,
+    streams: BTreeMap<String, RandomNumberGenerator>,
+}
+
+impl Default for RandomNumberGenerator {
+    fn default() -> Self {
+        Self::new_with_random_seed()
+    }
 }
 
 impl RandomNumberGenerator {
     pub fn new(seed: u64) -> Self {
-        RandomNumberGenerator { seed }
+        RandomNumberGenerator {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            streams: BTreeMap::new(),
+        }
     }
     
     pub fn new_with_random_seed() -> Self {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        RandomNumberGenerator { seed: rng.gen() }
+        Self::new(rand::thread_rng().gen())
     }
     
     pub fn roll_dice(&mut self, num: i32, sides: i32) -> i32 {
-        use rand::Rng;
-        use rand_chacha::ChaCha8Rng;
-        use rand::SeedableRng;
-        
-        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
-        self.seed = rng.gen();
-        
         let mut total = 0;
         for _ in 0..num {
-            total += rng.gen_range(1..=sides);
+            total += self.rng.gen_range(1..=sides);
         }
         total
     }
     
     pub fn range(&mut self, min: i32, max: i32) -> i32 {
-        use rand::Rng;
-        use rand_chacha::ChaCha8Rng;
-        use rand::SeedableRng;
-        
+        self.rng.gen_range(min..=max)
+    }
+    
+    /// The named sub-stream, started on first use and carried on from there
+    pub fn stream(&mut self, name: &str) -> &mut RandomNumberGenerator {
+        if !self.streams.contains_key(name) {
+            let forked = self.fork(name);
+            self.streams.insert(name.to_string(), forked);
+        }
+        self.streams.get_mut(name).expect("stream was just added")
+    }
+    
+    /// A fresh generator for `name`, drawn from the same seed but on a stream of its own;
+    /// it depends only on the seed and the name, never on how much this one has been used
+    pub fn fork(&self, name: &str) -> RandomNumberGenerator {
         let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
-        self.seed = rng.gen();
-        
-        rng.gen_range(min..=max)
+        rng.set_stream(stream_id(self.rng.get_stream(), name));
+        RandomNumberGenerator {
+            seed: self.seed,
+            rng,
+            streams: BTreeMap::new(),
+        }
+    }
+}
+
+impl RngCore for RandomNumberGenerator {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
     }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+// FNV-1a over the name, starting from the parent stream so forks of forks stay apart; it is the
+// same on every build, which seeds shared between players rely on
+fn stream_id(parent: u64, name: &str) -> u64 {
+    fnv1a(FNV_OFFSET_BASIS ^ parent, name.as_bytes())
 }
 
 // Player resource
@@ -81,11 +170,69 @@ pub struct PlayerResource {
 
 // Map resource is already defined in the map module
 
+// Stage of the run: heading down for the artifact, carrying it back up, or done
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunPhase {
+    Descent,
+    Ascent,
+    Victory,
+}
+
+impl Default for RunPhase {
+    fn default() -> Self {
+        RunPhase::Descent
+    }
+}
+
+// Running totals for the current character, shown on the victory screen
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct RunStatistics {
+    pub kills: u32,
+    pub bosses_defeated: u32,
+    pub deepest_depth: i32,
+    pub artifact_taken_turn: Option<u32>,
+    pub victory_turn: Option<u32>,
+    #[serde(default)]
+    pub artifacts_found: Vec<String>,
+    /// Adventurers freed this run, by name, who owe the rescuer a favour
+    #[serde(default)]
+    pub rescued_adventurers: Vec<String>,
+    #[serde(default)]
+    pub captives_rescued: u32,
+}
+
+impl RunStatistics {
+    pub fn record_depth(&mut self, depth: i32) {
+        self.deepest_depth = i32::max(self.deepest_depth, depth);
+    }
+
+    /// Someone freed and seen safely on their way
+    pub fn record_rescue(&mut self, name: &str, kind: CaptiveKind) {
+        self.captives_rescued += 1;
+        if kind == CaptiveKind::Adventurer {
+            self.rescued_adventurers.push(name.to_string());
+        }
+    }
+
+    /// Turns spent carrying the artifact back to the surface
+    pub fn ascent_turns(&self) -> Option<u32> {
+        match (self.artifact_taken_turn, self.victory_turn) {
+            (Some(taken), Some(won)) => Some(won.saturating_sub(taken)),
+            _ => None,
+        }
+    }
+}
+
 // Game state resource
 #[derive(Serialize, Deserialize, Clone)]
 pub struct GameStateResource {
     pub turn_count: u32,
+    /// Difficulty depth of the current level; side branches continue from their entrance
     pub depth: i32,
+    #[serde(default)]
+    pub location: Location,
+    #[serde(default)]
+    pub phase: RunPhase,
     pub game_over: bool,
 }
 
@@ -94,7 +241,186 @@ impl Default for GameStateResource {
         GameStateResource {
             turn_count: 0,
             depth: 1,
+            location: Location::default(),
+            phase: RunPhase::Descent,
             game_over: false,
         }
     }
-}
\ No newline at end of file
+}
+/// Minutes of game time that pass with each turn
+pub const MINUTES_PER_TURN: u64 = 1;
+pub const MINUTES_PER_DAY: u64 = 24 * 60;
+/// Runs start at eight in the morning
+pub const START_MINUTE: u64 = 8 * 60;
+/// Hours anyone can go without resting before weariness sets in
+pub const HOURS_BEFORE_WEARY: u64 = 18;
+/// Hours, from and until, that merchants keep their doors open
+pub const SHOP_HOURS: (u64, u64) = (8, 20);
+
+/// Broad stretches of the day, each lit differently on the surface
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl TimeOfDay {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeOfDay::Dawn => "Dawn",
+            TimeOfDay::Day => "Day",
+            TimeOfDay::Dusk => "Dusk",
+            TimeOfDay::Night => "Night",
+        }
+    }
+
+    /// Tiles taken off sight on outdoor levels; underground it is always as dark as the torches allow
+    pub fn sight_penalty(&self) -> i32 {
+        match self {
+            TimeOfDay::Day => 0,
+            TimeOfDay::Dawn | TimeOfDay::Dusk => 1,
+            TimeOfDay::Night => 3,
+        }
+    }
+}
+
+/// The game clock, advanced every turn and saved with the run
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameClock {
+    /// Minutes since midnight of the first day
+    pub minutes: u64,
+    /// When the player last rested
+    #[serde(default)]
+    pub last_rest: u64,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock { minutes: START_MINUTE, last_rest: START_MINUTE }
+    }
+}
+
+impl GameClock {
+    pub fn advance_turns(&mut self, turns: u64) {
+        self.minutes += turns * MINUTES_PER_TURN;
+    }
+
+    /// Counting from day 1
+    pub fn day(&self) -> u64 {
+        self.minutes / MINUTES_PER_DAY + 1
+    }
+
+    pub fn hour(&self) -> u64 {
+        (self.minutes % MINUTES_PER_DAY) / 60
+    }
+
+    pub fn minute(&self) -> u64 {
+        self.minutes % 60
+    }
+
+    pub fn time_of_day(&self) -> TimeOfDay {
+        match self.hour() {
+            5..=6 => TimeOfDay::Dawn,
+            7..=18 => TimeOfDay::Day,
+            19..=20 => TimeOfDay::Dusk,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.time_of_day() == TimeOfDay::Night
+    }
+
+    pub fn shops_open(&self) -> bool {
+        (SHOP_HOURS.0..SHOP_HOURS.1).contains(&self.hour())
+    }
+
+    /// "Day 2, 21:05"
+    pub fn label(&self) -> String {
+        format!("Day {}, {:02}:{:02}", self.day(), self.hour(), self.minute())
+    }
+
+    pub fn hours_awake(&self) -> u64 {
+        self.minutes.saturating_sub(self.last_rest) / 60
+    }
+
+    pub fn is_weary(&self) -> bool {
+        self.hours_awake() >= HOURS_BEFORE_WEARY
+    }
+
+    /// Mark a rest as finished now
+    pub fn rested(&mut self) {
+        self.last_rest = self.minutes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_runs_through_the_day() {
+        let mut clock = GameClock::default();
+        assert_eq!(clock.label(), "Day 1, 08:00");
+        assert_eq!(clock.time_of_day(), TimeOfDay::Day);
+        assert!(clock.shops_open());
+
+        clock.advance_turns(13 * 60 + 5);
+        assert_eq!(clock.label(), "Day 1, 21:05");
+        assert!(clock.is_night());
+        assert!(!clock.shops_open());
+
+        clock.advance_turns(9 * 60);
+        assert_eq!(clock.day(), 2);
+        assert_eq!(clock.time_of_day(), TimeOfDay::Dawn);
+    }
+
+    #[test]
+    fn test_weariness_builds_until_rest() {
+        let mut clock = GameClock::default();
+        clock.advance_turns(HOURS_BEFORE_WEARY * 60 - 1);
+        assert!(!clock.is_weary());
+        clock.advance_turns(1);
+        assert!(clock.is_weary());
+        clock.rested();
+        assert_eq!(clock.hours_awake(), 0);
+    }
+
+    #[test]
+    fn test_same_seed_same_rolls() {
+        let mut first = RandomNumberGenerator::new(42);
+        let mut second = RandomNumberGenerator::new(42);
+        let rolls: Vec<i32> = (0..20).map(|_| first.roll_dice(3, 6)).collect();
+        assert_eq!(rolls, (0..20).map(|_| second.roll_dice(3, 6)).collect::<Vec<_>>());
+        assert!(rolls.iter().all(|&roll| (3..=18).contains(&roll)));
+    }
+
+    #[test]
+    fn test_streams_do_not_disturb_each_other() {
+        let mut busy = RandomNumberGenerator::new(7);
+        let mut quiet = RandomNumberGenerator::new(7);
+        for _ in 0..50 {
+            busy.roll_dice(1, 20);
+            busy.stream(COMBAT_STREAM).roll_dice(1, 20);
+        }
+        let busy_maps: Vec<i32> = (0..10).map(|_| busy.stream(MAPGEN_STREAM).range(0, 1000)).collect();
+        let quiet_maps: Vec<i32> = (0..10).map(|_| quiet.stream(MAPGEN_STREAM).range(0, 1000)).collect();
+        assert_eq!(busy_maps, quiet_maps);
+
+        let loot: Vec<i32> = (0..10).map(|_| quiet.stream(LOOT_STREAM).range(0, 1000)).collect();
+        assert_ne!(loot, quiet_maps);
+    }
+
+    #[test]
+    fn test_saved_generator_carries_on_where_it_left_off() {
+        let mut rng = RandomNumberGenerator::new(3);
+        rng.roll_dice(5, 6);
+        rng.stream(LOOT_STREAM).roll_dice(5, 6);
+        let mut restored: RandomNumberGenerator =
+            serde_json::from_str(&serde_json::to_string(&rng).unwrap()).unwrap();
+        assert_eq!(rng.roll_dice(1, 100), restored.roll_dice(1, 100));
+        assert_eq!(rng.stream(LOOT_STREAM).roll_dice(1, 100), restored.stream(LOOT_STREAM).roll_dice(1, 100));
+    }
+}
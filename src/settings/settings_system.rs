@@ -207,6 +207,30 @@ impl SettingsSystem {
             SettingValue::IntRange(60, 30, 240),
         ));
 
+        self.add_setting(Setting::new(
+            "animation_speed".to_string(),
+            "Animation Speed".to_string(),
+            "How fast attacks, shots and deaths play out".to_string(),
+            SettingsCategory::Graphics,
+            SettingValue::FloatRange(1.0, 0.25, 4.0),
+        ));
+
+        self.add_setting(Setting::new(
+            "skip_animations".to_string(),
+            "Skip Animations".to_string(),
+            "Resolve each turn instantly without animating it".to_string(),
+            SettingsCategory::Graphics,
+            SettingValue::Bool(false),
+        ));
+
+        self.add_setting(Setting::new(
+            "weather_effects".to_string(),
+            "Weather Effects".to_string(),
+            "Rain, snow and ash overlays and lightning flashes; turn off on slow terminals".to_string(),
+            SettingsCategory::Graphics,
+            SettingValue::Bool(true),
+        ));
+
         // Audio settings
         self.add_setting(Setting::new(
             "master_volume".to_string(),
@@ -273,6 +297,22 @@ impl SettingsSystem {
             SettingsCategory::Gameplay,
             SettingValue::IntRange(5, 1, 60),
         ));
+
+        self.add_setting(Setting::new(
+            "verbose_combat".to_string(),
+            "Verbose Combat".to_string(),
+            "Show attack rolls and damage breakdowns in the combat log".to_string(),
+            SettingsCategory::Gameplay,
+            SettingValue::Bool(false),
+        ));
+
+        self.add_setting(Setting::new(
+            "show_gore".to_string(),
+            "Blood and Gore".to_string(),
+            "Leave blood splatter and bones where creatures fall".to_string(),
+            SettingsCategory::Gameplay,
+            SettingValue::Bool(true),
+        ));
     }
 
     /// Add a setting
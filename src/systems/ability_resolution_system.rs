@@ -0,0 +1,474 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
+use crate::components::{
+    WantsToUseAbility, Abilities, AbilityType, PlayerResources, CombatStats, DamageResistances,
+    Name, Player, Undead, Position, Viewshed, DamageInfo, DamageType, StatusEffects,
+    StatusEffect, StatusEffectType, WantsToSummon, CompanionKind, ForcedMovement, Piety
+};
+use crate::localization::Message;
+use crate::map::Map;
+use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::systems::{AreaEffect, AreaEffectQueue, ParticleEmitter, DivineDeeds, Deed, ability_area, ability_damage, piety_bonus};
+use super::status_effect_system::apply_status_effect;
+
+/// Furthest a teleport can land from where it was cast
+pub const TELEPORT_RANGE: i32 = 8;
+/// Reach of Turn Undead, in tiles
+pub const TURN_UNDEAD_RANGE: i32 = 6;
+
+/// Why `ability` can't be used right now by someone who knows `known` and has `resources` in
+/// reserve, if it can't. Creatures without reserves pay nothing
+pub fn ability_problem(
+    ability: AbilityType,
+    known: Option<&Abilities>,
+    resources: Option<&PlayerResources>,
+    mana_cost: i32,
+    stamina_cost: i32,
+) -> Option<Message> {
+    let known = match known.filter(|abilities| abilities.has_ability(ability)) {
+        Some(known) => known,
+        None => return Some(Message::new("hotbar.not_known").arg("ability", ability.name())),
+    };
+    if known.is_on_cooldown(ability) {
+        return Some(Message::new("hotbar.cooling_down")
+            .arg("ability", ability.name())
+            .arg("turns", known.get_cooldown(ability)));
+    }
+    let affordable = resources.map_or(true, |resources| resources.mana >= mana_cost && resources.stamina >= stamina_cost);
+    if !affordable {
+        return Some(Message::new("hotbar.too_tired")
+            .arg("ability", ability.name())
+            .arg("mana", mana_cost)
+            .arg("stamina", stamina_cost));
+    }
+    None
+}
+
+/// The one place abilities are used: checks each request against the caster's cooldowns and
+/// reserves, pays the cost, starts the cooldown and then lets the ability loose. Only the player
+/// hears why a request was refused. A Cleric's prayers grow with their god's favour
+pub struct AbilityResolutionSystem {}
+
+impl<'a> System<'a> for AbilityResolutionSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToUseAbility>,
+        WriteStorage<'a, Abilities>,
+        WriteStorage<'a, PlayerResources>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, DamageInfo>,
+        WriteStorage<'a, StatusEffects>,
+        ReadStorage<'a, DamageResistances>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Undead>,
+        ReadStorage<'a, Piety>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, WantsToSummon>,
+        WriteStorage<'a, ForcedMovement>,
+        ReadExpect<'a, Map>,
+        Write<'a, AreaEffectQueue>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, ParticleEmitter>,
+        Write<'a, DivineDeeds>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_use_ability,
+            mut abilities,
+            mut resources,
+            mut combat_stats,
+            mut damage_info,
+            mut status_effects,
+            resistances,
+            names,
+            players,
+            undead,
+            pieties,
+            mut positions,
+            mut viewsheds,
+            mut wants_summon,
+            mut forced_movement,
+            map,
+            mut area_effects,
+            mut gamelog,
+            mut rng,
+            mut particles,
+            mut deeds,
+        ) = data;
+
+        let requests: Vec<(Entity, WantsToUseAbility)> = (&entities, &wants_use_ability).join()
+            .map(|(entity, request)| (entity, request.clone()))
+            .collect();
+        wants_use_ability.clear();
+
+        for (caster, request) in requests {
+            let ability = request.ability;
+            if combat_stats.get(caster).map_or(false, |stats| stats.hp <= 0) {
+                continue;
+            }
+            let problem = ability_problem(ability, abilities.get(caster), resources.get(caster), request.mana_cost, request.stamina_cost);
+            if let Some(problem) = problem {
+                if players.contains(caster) {
+                    gamelog.add_message(problem);
+                }
+                continue;
+            }
+
+            if let Some(reserves) = resources.get_mut(caster) {
+                reserves.consume_mana(request.mana_cost);
+                reserves.consume_stamina(request.stamina_cost);
+            }
+            if let Some(known) = abilities.get_mut(caster) {
+                known.set_cooldown(ability, ability.cooldown());
+            }
+            deeds.publish(caster, Deed::Used { ability, spell: request.mana_cost > 0 });
+            let favour = piety_bonus(pieties.get(caster));
+
+            // Spells glitter around the caster
+            let caster_pos = positions.get(caster).map(|pos| (pos.x, pos.y));
+            if let (true, Some(pos)) = (request.mana_cost > 0, caster_pos) {
+                particles.sparkles(pos, crossterm::style::Color::Magenta);
+            }
+
+            let caster_name = names.get(caster).map_or("Unknown", |n| &n.name).to_string();
+            let target = request.target;
+            let target_pos = target.and_then(|t| positions.get(t)).map(|pos| (pos.x, pos.y));
+
+            match ability {
+                // Blows landed on a single target
+                AbilityType::PowerAttack | AbilityType::Backstab | AbilityType::PreciseShot |
+                AbilityType::MagicMissile | AbilityType::ShieldBash | AbilityType::IceSpike => {
+                    let victim = match target {
+                        Some(victim) => victim,
+                        None => continue,
+                    };
+                    let hit = strike(ability, caster, &mut rng);
+                    damage_info.insert(victim, hit).expect("Failed to insert ability damage");
+
+                    match ability {
+                        AbilityType::ShieldBash => {
+                            let stun = StatusEffect::new(StatusEffectType::Stunned, 2, 3);
+                            apply_status_effect(victim, stun, &mut status_effects, resistances.get(victim), &mut rng);
+                            // Knock the target back a tile
+                            if let (Some(from), Some(to)) = (caster_pos, target_pos) {
+                                forced_movement.insert(victim, ForcedMovement::knockback(caster, from, to, 1))
+                                    .expect("Failed to insert knockback");
+                            }
+                        },
+                        AbilityType::IceSpike => {
+                            let slow = StatusEffect::new(StatusEffectType::Slow, 4, 2);
+                            apply_status_effect(victim, slow, &mut status_effects, resistances.get(victim), &mut rng);
+                        },
+                        _ => {},
+                    }
+                    gamelog.add_entry(strike_message(ability, &caster_name));
+                },
+
+                // Areas handed on to the area effect system
+                AbilityType::Cleave | AbilityType::Fireball => {
+                    let origin = match caster_pos {
+                        Some(pos) => pos,
+                        None => continue,
+                    };
+                    // Cleave sweeps around the caster; a fireball needs somewhere to land
+                    let center = match (ability, target_pos) {
+                        (AbilityType::Cleave, _) => origin,
+                        (_, Some(pos)) => pos,
+                        _ => continue,
+                    };
+                    let (template, friendly_fire) = ability_area(ability).expect("Area abilities have an area");
+                    let damage = ability_damage(ability).expect("Area abilities deal damage");
+                    let burning = (ability == AbilityType::Fireball).then_some((StatusEffectType::Burning, 3, 3));
+                    area_effects.push(AreaEffect {
+                        source: Some(caster),
+                        origin,
+                        target: center,
+                        template,
+                        friendly_fire,
+                        damage: damage.roll(&mut rng),
+                        damage_type: damage.damage_type,
+                        status: burning,
+                        name: ability.name().to_lowercase(),
+                    });
+                    if ability == AbilityType::Cleave {
+                        gamelog.add_entry(format!("{} swings in a wide arc!", caster_name));
+                    } else {
+                        gamelog.add_entry(format!("{} hurls a blazing fireball!", caster_name));
+                    }
+                },
+
+                // Healing
+                AbilityType::Heal | AbilityType::SecondWind => {
+                    let patient = if ability == AbilityType::Heal { target.unwrap_or(caster) } else { caster };
+                    let stats = match combat_stats.get_mut(patient) {
+                        Some(stats) => stats,
+                        None => continue,
+                    };
+                    let amount = match ability {
+                        AbilityType::Heal => 5 + rng.roll_dice(2, 8) + 2 * favour,
+                        _ => stats.max_hp / 4,
+                    };
+                    let healed = amount.min(stats.max_hp - stats.hp).max(0);
+                    stats.hp += healed;
+                    if let Some(pos) = positions.get(patient) {
+                        particles.sparkles((pos.x, pos.y), crossterm::style::Color::Green);
+                    }
+                    if ability == AbilityType::Heal {
+                        gamelog.add_entry(format!("{} channels divine energy, healing {} hit points!", caster_name, healed));
+                    } else {
+                        gamelog.add_entry(format!("{} catches their second wind and recovers {} hit points!", caster_name, healed));
+                    }
+                },
+
+                // Boons the caster gives itself
+                AbilityType::ShadowStep | AbilityType::Evasion | AbilityType::BlessWeapon |
+                AbilityType::DivineProtection | AbilityType::TrackEnemy => {
+                    let (effect, duration, magnitude, line) = match ability {
+                        AbilityType::ShadowStep => (StatusEffectType::Invisible, 3, 1, "{} melts into the shadows!"),
+                        AbilityType::Evasion => (StatusEffectType::DexterityBoost, 5, 4, "{} becomes incredibly evasive!"),
+                        AbilityType::BlessWeapon => (StatusEffectType::Blessed, 10 + 2 * favour, 2, "{}'s weapon glows with holy light!"),
+                        AbilityType::DivineProtection => (StatusEffectType::DefenseBoost, 10 + 2 * favour, 3, "{} is surrounded by divine protection!"),
+                        _ => (StatusEffectType::Detecting, 20, 1, "{} studies the ground for tracks and signs!"),
+                    };
+                    grant(caster, StatusEffect::new(effect, duration, magnitude), &mut status_effects);
+                    gamelog.add_entry(line.replace("{}", &caster_name));
+                },
+
+                AbilityType::NaturalRemedy => {
+                    if let Some(effects) = status_effects.get_mut(caster) {
+                        let ailments: Vec<StatusEffectType> = effects.effects.iter()
+                            .map(|effect| effect.effect_type)
+                            .filter(|effect| !effect.is_beneficial())
+                            .collect();
+                        for ailment in ailments {
+                            effects.remove_effect(ailment);
+                        }
+                    }
+                    gamelog.add_entry(format!("{} uses natural remedies to cure ailments!", caster_name));
+                },
+
+                AbilityType::TurnUndead => {
+                    let origin = match caster_pos {
+                        Some(pos) => pos,
+                        None => continue,
+                    };
+                    let turned: Vec<Entity> = (&entities, &undead, &positions).join()
+                        .filter(|(_, _, pos)| (pos.x - origin.0).abs().max((pos.y - origin.1).abs()) <= TURN_UNDEAD_RANGE + favour)
+                        .map(|(entity, _, _)| entity)
+                        .collect();
+                    for victim in turned {
+                        let fear = StatusEffect::new(StatusEffectType::Feared, 5 + favour, 1);
+                        apply_status_effect(victim, fear, &mut status_effects, resistances.get(victim), &mut rng);
+                    }
+                    gamelog.add_entry(format!("{} channels holy power to turn undead!", caster_name));
+                },
+
+                // Movement
+                AbilityType::Charge => {
+                    let (from, to) = match (caster_pos, target_pos) {
+                        (Some(from), Some(to)) => (from, to),
+                        _ => continue,
+                    };
+                    // Charges run in a straight line, so stop at whatever is in the way
+                    let distance = i32::max((to.0 - from.0).abs(), (to.1 - from.1).abs());
+                    forced_movement.insert(caster, ForcedMovement::charge(caster, from, to, distance))
+                        .expect("Failed to insert charge");
+                    gamelog.add_entry(format!("{} lowers their head and charges!", caster_name));
+                },
+                AbilityType::Teleport => {
+                    let from = match caster_pos {
+                        Some(pos) => pos,
+                        None => continue,
+                    };
+                    let landing = match teleport_landing(from, &map, &positions, &mut rng) {
+                        Some(landing) => landing,
+                        None => {
+                            gamelog.add_entry(format!("{} flickers but has nowhere to go!", caster_name));
+                            continue;
+                        },
+                    };
+                    if let Some(pos) = positions.get_mut(caster) {
+                        pos.x = landing.0;
+                        pos.y = landing.1;
+                    }
+                    if let Some(viewshed) = viewsheds.get_mut(caster) {
+                        viewshed.dirty = true;
+                    }
+                    particles.sparkles(landing, crossterm::style::Color::Magenta);
+                    gamelog.add_entry(format!("{} vanishes and reappears elsewhere!", caster_name));
+                },
+
+                AbilityType::AnimalCompanion => {
+                    wants_summon.insert(caster, WantsToSummon { kind: CompanionKind::Wolf })
+                        .expect("Failed to insert summon request");
+                    gamelog.add_entry(format!("{} whistles for an animal companion!", caster_name));
+                },
+
+                _ => {
+                    gamelog.add_entry(format!("{} uses {} but nothing happens!", caster_name, ability.name()));
+                },
+            }
+        }
+    }
+}
+
+/// The damage of a single-target ability as it leaves the caster
+fn strike(ability: AbilityType, caster: Entity, rng: &mut RandomNumberGenerator) -> DamageInfo {
+    let (base_damage, damage_type, is_critical, penetration) = match ability {
+        AbilityType::PowerAttack => (15 + rng.roll_dice(1, 10), DamageType::Physical, false, 5),
+        // Backstabs always land where it hurts
+        AbilityType::Backstab => (20 + rng.roll_dice(2, 8), DamageType::Physical, true, 8),
+        AbilityType::PreciseShot => (12 + rng.roll_dice(1, 8), DamageType::Physical, rng.roll_dice(1, 100) <= 30, 3),
+        // Three missiles that never miss and ignore armor
+        AbilityType::MagicMissile => (3 * (2 + rng.roll_dice(1, 4)), DamageType::Psychic, false, 10),
+        AbilityType::ShieldBash => (5 + rng.roll_dice(1, 6), DamageType::Physical, false, 0),
+        _ => (10 + rng.roll_dice(1, 8), DamageType::Ice, false, 0),
+    };
+    DamageInfo { base_damage, damage_type, source: caster, is_critical, penetration }
+}
+
+fn strike_message(ability: AbilityType, caster_name: &str) -> String {
+    match ability {
+        AbilityType::PowerAttack => format!("{} unleashes a devastating Power Attack!", caster_name),
+        AbilityType::Backstab => format!("{} strikes from the shadows with a deadly backstab!", caster_name),
+        AbilityType::PreciseShot => format!("{} takes careful aim and fires a precise shot!", caster_name),
+        AbilityType::MagicMissile => format!("{} fires 3 unerring magic missiles!", caster_name),
+        AbilityType::ShieldBash => format!("{} bashes with their shield, stunning the target!", caster_name),
+        _ => format!("{} conjures a piercing ice spike!", caster_name),
+    }
+}
+
+/// Give `entity` a boon, whether or not it had any effects before
+fn grant(entity: Entity, effect: StatusEffect, status_effects: &mut WriteStorage<StatusEffects>) {
+    if let Some(effects) = status_effects.get_mut(entity) {
+        effects.add_effect(effect);
+    } else {
+        let mut effects = StatusEffects::new();
+        effects.add_effect(effect);
+        status_effects.insert(entity, effects).expect("Failed to insert status effects");
+    }
+}
+
+/// A random open, unoccupied tile within teleport range of `from`
+fn teleport_landing(
+    from: (i32, i32),
+    map: &Map,
+    positions: &WriteStorage<Position>,
+    rng: &mut RandomNumberGenerator,
+) -> Option<(i32, i32)> {
+    let mut open = Vec::new();
+    for y in from.1 - TELEPORT_RANGE..=from.1 + TELEPORT_RANGE {
+        for x in from.0 - TELEPORT_RANGE..=from.0 + TELEPORT_RANGE {
+            if (x, y) != from && !map.is_blocked(x, y) && !positions.join().any(|pos| (pos.x, pos.y) == (x, y)) {
+                open.push((x, y));
+            }
+        }
+    }
+    if open.is_empty() {
+        return None;
+    }
+    Some(open[rng.range(0, open.len() as i32 - 1) as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::map::TileType;
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.insert(AreaEffectQueue::default());
+        world.insert(ParticleEmitter::default());
+        world.insert(DivineDeeds::default());
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(4));
+        let mut map = Map::new(20, 20, 1);
+        for y in 1..19 {
+            for x in 1..19 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        world.insert(map);
+        world
+    }
+
+    fn cast(world: &mut World, caster: Entity, ability: AbilityType) {
+        world.write_storage::<WantsToUseAbility>()
+            .insert(caster, WantsToUseAbility {
+                ability,
+                target: None,
+                mana_cost: ability.get_mana_cost(),
+                stamina_cost: ability.get_stamina_cost(),
+            })
+            .expect("Unable to insert ability intent");
+        AbilityResolutionSystem {}.run_now(world);
+        world.maintain();
+    }
+
+    #[test]
+    fn test_abilities_cost_their_price_and_wait_out_their_cooldown() {
+        let mut world = world();
+        let mut known = Abilities::new();
+        known.add_ability(AbilityType::Heal);
+        let cleric = world.create_entity()
+            .with(Player)
+            .with(Position { x: 5, y: 5 })
+            .with(CombatStats { max_hp: 40, hp: 10, defense: 0, power: 1 })
+            .with(PlayerResources::new(30, 30))
+            .with(known)
+            .build();
+
+        cast(&mut world, cleric, AbilityType::Heal);
+        let hp = world.read_storage::<CombatStats>().get(cleric).unwrap().hp;
+        assert!(hp > 10);
+        assert_eq!(world.read_storage::<PlayerResources>().get(cleric).map(|r| r.mana), Some(30 - AbilityType::Heal.get_mana_cost()));
+        assert_eq!(world.read_storage::<Abilities>().get(cleric).map(|a| a.get_cooldown(AbilityType::Heal)), Some(AbilityType::Heal.cooldown()));
+
+        // Still cooling down, so the second cast is refused and costs nothing
+        cast(&mut world, cleric, AbilityType::Heal);
+        assert_eq!(world.read_storage::<CombatStats>().get(cleric).unwrap().hp, hp);
+        assert_eq!(world.read_storage::<PlayerResources>().get(cleric).map(|r| r.mana), Some(30 - AbilityType::Heal.get_mana_cost()));
+    }
+
+    #[test]
+    fn test_abilities_need_knowing_and_paying_for() {
+        let mut known = Abilities::new();
+        known.add_ability(AbilityType::Fireball);
+        let mut drained = PlayerResources::new(30, 30);
+        drained.mana = 2;
+
+        assert_eq!(ability_problem(AbilityType::Heal, Some(&known), None, 0, 0).map(|m| m.key), Some("hotbar.not_known".to_string()));
+        assert_eq!(ability_problem(AbilityType::Fireball, Some(&known), Some(&drained), 15, 3).map(|m| m.key), Some("hotbar.too_tired".to_string()));
+        assert_eq!(ability_problem(AbilityType::Fireball, Some(&known), None, 15, 3), None);
+    }
+
+    #[test]
+    fn test_a_favoured_cleric_blesses_for_longer_and_the_gods_see_it() {
+        let mut world = world();
+        let mut favoured = Piety::new(crate::components::Deity::Aurelion);
+        favoured.piety = 60;
+        let mut known = Abilities::new();
+        known.add_ability(AbilityType::BlessWeapon);
+        let cleric = world.create_entity()
+            .with(Position { x: 5, y: 5 })
+            .with(CombatStats { max_hp: 40, hp: 40, defense: 0, power: 1 })
+            .with(known)
+            .with(favoured.clone())
+            .build();
+
+        cast(&mut world, cleric, AbilityType::BlessWeapon);
+        let effects = world.read_storage::<StatusEffects>();
+        let blessing = effects.get(cleric).and_then(|effects| effects.get_effect(StatusEffectType::Blessed)).unwrap();
+        assert_eq!(blessing.duration, 10 + 2 * favoured.bonus());
+        assert_eq!(
+            world.write_resource::<DivineDeeds>().take(),
+            vec![(cleric, Deed::Used { ability: AbilityType::BlessWeapon, spell: AbilityType::BlessWeapon.get_mana_cost() > 0 })]
+        );
+    }
+}
@@ -1,8 +1,10 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Read, Write};
 use crate::components::{
     Position, AbilityType, WantsToUseAbility, Player, Monster, Name, CombatStats
 };
-use crate::resources::GameLog;
+use crate::localization::Message;
+use crate::resources::{GameLog, GameStateResource};
+use crate::systems::CHARGE_RANGE;
 
 pub struct AbilityTargetingSystem {}
 
@@ -72,6 +74,7 @@ impl AbilityTargetingSystem {
             // Offensive abilities that need targets
             AbilityType::PowerAttack |
             AbilityType::ShieldBash |
+            AbilityType::Charge |
             AbilityType::Backstab |
             AbilityType::Fireball |
             AbilityType::IceSpike |
@@ -113,7 +116,7 @@ impl AbilityTargetingSystem {
         let caster_pos = positions.get(caster)?;
         let is_caster_player = players.contains(caster);
         
-        let max_range = self.get_ability_range(ability_type);
+        let max_range = ability_type.get_range();
         let mut best_target = None;
         let mut best_score = f32::MIN;
         
@@ -136,6 +139,7 @@ impl AbilityTargetingSystem {
                 // Offensive abilities
                 AbilityType::PowerAttack |
                 AbilityType::ShieldBash |
+                AbilityType::Charge |
                 AbilityType::Backstab |
                 AbilityType::Fireball |
                 AbilityType::IceSpike |
@@ -186,26 +190,6 @@ impl AbilityTargetingSystem {
         best_target
     }
     
-    fn get_ability_range(&self, ability_type: AbilityType) -> i32 {
-        match ability_type {
-            // Melee abilities
-            AbilityType::PowerAttack |
-            AbilityType::ShieldBash |
-            AbilityType::Backstab => 1,
-            
-            // Ranged abilities
-            AbilityType::Fireball |
-            AbilityType::IceSpike |
-            AbilityType::MagicMissile |
-            AbilityType::PreciseShot => 8,
-            
-            // Healing
-            AbilityType::Heal => 5,
-            
-            _ => 1,
-        }
-    }
-    
     fn calculate_target_score(
         &self,
         ability_type: AbilityType,
@@ -220,6 +204,7 @@ impl AbilityTargetingSystem {
             // Offensive abilities prefer closer, weaker targets
             AbilityType::PowerAttack |
             AbilityType::ShieldBash |
+            AbilityType::Charge |
             AbilityType::Backstab |
             AbilityType::Fireball |
             AbilityType::IceSpike |
@@ -258,43 +243,44 @@ impl AbilityTargetingSystem {
     }
 }
 
-// System for managing ability cooldowns and resource costs
-pub struct AbilityCooldownSystem {}
+/// Counts every cooldown down by one each turn, saying when one of the player's abilities is ready
+pub struct AbilityCooldownSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl AbilityCooldownSystem {
+    pub fn new() -> Self {
+        AbilityCooldownSystem { last_turn: None }
+    }
+}
 
 impl<'a> System<'a> for AbilityCooldownSystem {
     type SystemData = (
         Entities<'a>,
         WriteStorage<'a, crate::components::Abilities>,
-        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        Read<'a, GameStateResource>,
         Write<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut abilities, names, mut gamelog) = data;
+        let (entities, mut abilities, players, game_state, mut gamelog) = data;
 
-        // Update cooldowns for all entities
-        for (entity, mut ability_comp) in (&entities, &mut abilities).join() {
-            let mut cooldowns_updated = Vec::new();
-            
-            // Check which abilities came off cooldown this turn
-            for ability_type in AbilityType::get_all_abilities() {
-                if ability_comp.has_ability(ability_type) {
-                    let old_cooldown = ability_comp.get_cooldown(ability_type);
-                    if old_cooldown == 1 { // About to come off cooldown
-                        cooldowns_updated.push(ability_type);
-                    }
-                }
-            }
-            
-            // Update cooldowns
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        for (entity, ability_comp) in (&entities, &mut abilities).join() {
+            // Whatever is on its last turn of cooldown comes off it now
+            let ready: Vec<AbilityType> = AbilityType::get_all_abilities().into_iter()
+                .filter(|ability| ability_comp.has_ability(*ability) && ability_comp.get_cooldown(*ability) == 1)
+                .collect();
             ability_comp.update_cooldowns();
-            
-            // Log abilities that came off cooldown
-            if !cooldowns_updated.is_empty() {
-                let entity_name = names.get(entity).map_or("Unknown", |n| &n.name);
-                for ability_type in cooldowns_updated {
-                    gamelog.add_entry(format!("{}'s {} is ready to use again!", 
-                        entity_name, ability_type.name()));
+
+            if players.contains(entity) {
+                for ability_type in ready {
+                    gamelog.add_message(Message::new("log.ability_ready").arg("ability", ability_type.name()));
                 }
             }
         }
@@ -310,6 +296,7 @@ impl AbilityType {
             AbilityType::Cleave,
             AbilityType::ShieldBash,
             AbilityType::SecondWind,
+            AbilityType::Charge,
             
             // Rogue abilities
             AbilityType::Backstab,
@@ -337,6 +324,30 @@ impl AbilityType {
         ]
     }
     
+    /// How far away a target may be, in tiles
+    pub fn get_range(&self) -> i32 {
+        match self {
+            // Melee abilities
+            AbilityType::PowerAttack |
+            AbilityType::ShieldBash |
+            AbilityType::Backstab => 1,
+            
+            // Charges cover ground before they land
+            AbilityType::Charge => CHARGE_RANGE,
+            
+            // Ranged abilities
+            AbilityType::Fireball |
+            AbilityType::IceSpike |
+            AbilityType::MagicMissile |
+            AbilityType::PreciseShot => 8,
+            
+            // Healing
+            AbilityType::Heal => 5,
+            
+            _ => 1,
+        }
+    }
+    
     pub fn get_mana_cost(&self) -> i32 {
         match self {
             // Fighter abilities (low mana cost)
@@ -344,6 +355,7 @@ impl AbilityType {
             AbilityType::Cleave => 8,
             AbilityType::ShieldBash => 3,
             AbilityType::SecondWind => 10,
+            AbilityType::Charge => 4,
             
             // Rogue abilities (medium mana cost)
             AbilityType::Backstab => 6,
@@ -378,6 +390,7 @@ impl AbilityType {
             AbilityType::Cleave => 12,
             AbilityType::ShieldBash => 6,
             AbilityType::SecondWind => 5,
+            AbilityType::Charge => 14,
             
             // Rogue abilities (medium stamina cost)
             AbilityType::Backstab => 10,
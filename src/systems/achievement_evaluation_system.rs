@@ -0,0 +1,105 @@
+use specs::{System, Write, WriteExpect};
+use crate::achievements::{AchievementSystem, AchievementNotificationSystem};
+use crate::localization::Message;
+use crate::progression::{UnlockableContentSystem, UNLOCKS_PATH};
+use crate::resources::{GameLog, GameEvents};
+
+/// Feeds the turn's game events to the achievements, announces any that unlock and hands their
+/// rewards to the unlockable content, saving it whenever something new opens up
+pub struct AchievementEvaluationSystem {
+    /// Where unlocked content is kept between characters, if anywhere
+    pub unlocks_path: Option<String>,
+}
+
+impl AchievementEvaluationSystem {
+    pub fn new() -> Self {
+        AchievementEvaluationSystem { unlocks_path: Some(UNLOCKS_PATH.to_string()) }
+    }
+}
+
+impl<'a> System<'a> for AchievementEvaluationSystem {
+    type SystemData = (
+        Write<'a, GameEvents>,
+        WriteExpect<'a, AchievementSystem>,
+        WriteExpect<'a, AchievementNotificationSystem>,
+        WriteExpect<'a, UnlockableContentSystem>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut events, mut achievements, mut notifications, mut content, mut gamelog) = data;
+
+        for event in events.take() {
+            achievements.process_game_event(&event);
+        }
+
+        let mut opened_up = false;
+        for unlocked in achievements.get_pending_notifications() {
+            gamelog.add_message(
+                Message::new("log.achievement_unlocked")
+                    .arg("name", &unlocked.achievement_name)
+                    .arg("points", unlocked.points)
+            );
+
+            let rewards = achievements.claim_rewards(&unlocked.achievement_id);
+            for id in content.apply_achievement_rewards(&unlocked.achievement_id, &rewards) {
+                if let Some(item) = content.get_content(&id) {
+                    gamelog.add_message(Message::new("log.content_unlocked").arg("name", &item.name));
+                }
+                opened_up = true;
+            }
+
+            notifications.add_notification(unlocked);
+        }
+
+        if let (true, Some(path)) = (opened_up, &self.unlocks_path) {
+            if let Err(e) = content.export_data().save(path) {
+                gamelog.add_message(Message::new("log.unlocks_not_saved").arg("error", e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, RunNow};
+    use crate::achievements::{GameEvent, NotificationConfig};
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert(GameLog::new(10));
+        world.insert(GameEvents::default());
+        world.insert(AchievementSystem::new());
+        world.insert(AchievementNotificationSystem::new(NotificationConfig::default()));
+        world.insert(UnlockableContentSystem::new());
+        world
+    }
+
+    #[test]
+    fn test_published_events_unlock_achievements_and_queue_a_notification() {
+        let world = world();
+
+        world.write_resource::<GameEvents>().publish(GameEvent::EnemyKilled);
+        AchievementEvaluationSystem { unlocks_path: None }.run_now(&world);
+
+        assert!(world.read_resource::<AchievementSystem>().is_unlocked("first_kill"));
+        assert!(world.read_resource::<GameEvents>().events.is_empty());
+        assert_eq!(world.read_resource::<GameLog>().entries.len(), 1);
+
+        let mut notifications = world.write_resource::<AchievementNotificationSystem>();
+        notifications.update();
+        assert_eq!(notifications.get_active_notifications().len(), 1);
+    }
+
+    #[test]
+    fn test_achievement_rewards_unlock_content() {
+        let world = world();
+
+        world.write_resource::<GameEvents>().publish(GameEvent::BossDefeated);
+        AchievementEvaluationSystem { unlocks_path: None }.run_now(&world);
+
+        assert!(world.read_resource::<UnlockableContentSystem>().is_content_unlocked("glyph_gold"));
+        assert_eq!(world.read_resource::<GameLog>().entries.len(), 2);
+    }
+}
@@ -0,0 +1,435 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, WriteExpect};
+use serde::{Serialize, Deserialize};
+use crate::components::{
+    AbilityType, BreathWeapon, CombatStats, Companion, DamageInfo, DamageResistances, DamageType, Monster,
+    Name, Player, Position, StatusEffect, StatusEffects, StatusEffectType, SufferDamage, Viewshed
+};
+use crate::map::{Map, Decal};
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+use crate::systems::{apply_status_effect, damage_color, ParticleEmitter, NoiseEvents, BLAST_NOISE};
+
+/// Shape of the tiles an area effect covers
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum AreaTemplate {
+    /// A disc centred on the target tile
+    Circle { radius: i32 },
+    /// A 90 degree wedge spreading from the origin towards the target
+    Cone { length: i32 },
+    /// A straight line from the origin through the target, stopped by walls
+    Line { length: i32 },
+    /// A ring around the origin itself, not including it
+    Burst { radius: i32 },
+}
+
+impl AreaTemplate {
+    /// Whether the template is aimed at a target, rather than centred on its origin
+    pub fn needs_target(&self) -> bool {
+        !matches!(self, AreaTemplate::Burst { .. })
+    }
+
+    /// Tiles the template covers on this map; walls block the blast
+    pub fn tiles(&self, map: &Map, origin: (i32, i32), target: (i32, i32)) -> Vec<(i32, i32)> {
+        match *self {
+            AreaTemplate::Circle { radius } => disc(map, target, radius),
+            AreaTemplate::Burst { radius } => disc(map, origin, radius).into_iter()
+                .filter(|tile| *tile != origin)
+                .collect(),
+            AreaTemplate::Cone { length } => {
+                let (ax, ay) = (target.0 - origin.0, target.1 - origin.1);
+                if ax == 0 && ay == 0 {
+                    return Vec::new();
+                }
+                let aim_len = ((ax * ax + ay * ay) as f32).sqrt();
+                disc(map, origin, length).into_iter()
+                    .filter(|tile| *tile != origin)
+                    .filter(|(x, y)| {
+                        let (bx, by) = (x - origin.0, y - origin.1);
+                        let len = ((bx * bx + by * by) as f32).sqrt();
+                        (ax * bx + ay * by) as f32 >= aim_len * len * std::f32::consts::FRAC_1_SQRT_2
+                    })
+                    .collect()
+            },
+            AreaTemplate::Line { length } => {
+                let (dx, dy) = (target.0 - origin.0, target.1 - origin.1);
+                if dx == 0 && dy == 0 {
+                    return Vec::new();
+                }
+                // Extend the aim out to the full length of the line
+                let scale = length as f32 / i32::max(dx.abs(), dy.abs()) as f32;
+                let end = (
+                    origin.0 + (dx as f32 * scale).round() as i32,
+                    origin.1 + (dy as f32 * scale).round() as i32,
+                );
+                line(origin, end).into_iter()
+                    .skip(1)
+                    .take_while(|(x, y)| !map.is_opaque(*x, *y) && map.in_bounds(*x, *y))
+                    .collect()
+            },
+        }
+    }
+}
+
+/// Who an area effect is allowed to hurt
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum FriendlyFire {
+    /// Only the source's enemies
+    HostileOnly,
+    /// Everyone in the area except the source
+    SparesSource,
+    /// Everyone in the area, source included
+    Everyone,
+}
+
+/// The area and friendly-fire rule an ability resolves with, if it has one
+pub fn ability_area(ability: AbilityType) -> Option<(AreaTemplate, FriendlyFire)> {
+    match ability {
+        AbilityType::Fireball => Some((AreaTemplate::Circle { radius: 2 }, FriendlyFire::SparesSource)),
+        AbilityType::Cleave => Some((AreaTemplate::Burst { radius: 1 }, FriendlyFire::HostileOnly)),
+        _ => None,
+    }
+}
+
+/// Damage an area ability deals to each creature it catches: a flat amount plus dice
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaDamage {
+    pub flat: i32,
+    pub dice: i32,
+    pub sides: i32,
+    pub damage_type: DamageType,
+}
+
+impl AreaDamage {
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> i32 {
+        self.flat + rng.roll_dice(self.dice, self.sides)
+    }
+
+    pub fn min(&self) -> i32 {
+        self.flat + self.dice
+    }
+
+    pub fn max(&self) -> i32 {
+        self.flat + self.dice * self.sides
+    }
+}
+
+/// The damage an area ability rolls, if it has an area
+pub fn ability_damage(ability: AbilityType) -> Option<AreaDamage> {
+    match ability {
+        AbilityType::Fireball => Some(AreaDamage { flat: 12, dice: 2, sides: 6, damage_type: DamageType::Fire }),
+        AbilityType::Cleave => Some(AreaDamage { flat: 8, dice: 1, sides: 6, damage_type: DamageType::Physical }),
+        _ => None,
+    }
+}
+
+/// One blast, breath or sweep waiting to be resolved
+#[derive(Debug, Clone)]
+pub struct AreaEffect {
+    /// Whoever caused it; traps and other hazards have no source
+    pub source: Option<Entity>,
+    pub origin: (i32, i32),
+    pub target: (i32, i32),
+    pub template: AreaTemplate,
+    pub friendly_fire: FriendlyFire,
+    pub damage: i32,
+    pub damage_type: DamageType,
+    /// Status effect (type, duration, magnitude) applied to everything caught in the area
+    pub status: Option<(StatusEffectType, i32, i32)>,
+    /// What to call it in the combat log
+    pub name: String,
+}
+
+/// Area effects queued this frame, resolved by `AreaEffectSystem`
+#[derive(Debug, Clone, Default)]
+pub struct AreaEffectQueue {
+    pub effects: Vec<AreaEffect>,
+}
+
+impl AreaEffectQueue {
+    pub fn push(&mut self, effect: AreaEffect) {
+        self.effects.push(effect);
+    }
+}
+
+/// Resolves queued area effects against every creature in the covered tiles
+pub struct AreaEffectSystem;
+
+impl<'a> System<'a> for AreaEffectSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, AreaEffectQueue>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, DamageResistances>,
+        WriteStorage<'a, DamageInfo>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, StatusEffects>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, Map>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, ParticleEmitter>,
+        Write<'a, NoiseEvents>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut queue,
+            positions,
+            combat_stats,
+            players,
+            companions,
+            monsters,
+            resistances,
+            mut damage_info,
+            mut suffer_damage,
+            mut status_effects,
+            names,
+            mut map,
+            mut gamelog,
+            mut rng,
+            mut particles,
+            mut noises,
+        ) = data;
+
+        let is_monster_side = |entity: Entity| monsters.contains(entity) && !companions.contains(entity);
+
+        for effect in queue.effects.drain(..) {
+            particles.explosion(effect.target, damage_color(effect.damage_type));
+            noises.emit(effect.target, BLAST_NOISE);
+            let tiles = effect.template.tiles(&map, effect.origin, effect.target);
+            if effect.damage_type == DamageType::Fire {
+                for &(x, y) in &tiles {
+                    map.add_decal(x, y, Decal::Scorch);
+                }
+            }
+
+            let victims: Vec<Entity> = (&entities, &positions, &combat_stats).join()
+                .filter(|(_, pos, stats)| stats.hp > 0 && tiles.contains(&(pos.x, pos.y)))
+                .map(|(entity, _, _)| entity)
+                .filter(|entity| match (effect.friendly_fire, effect.source) {
+                    (_, None) | (FriendlyFire::Everyone, _) => true,
+                    (FriendlyFire::SparesSource, Some(source)) => *entity != source,
+                    (FriendlyFire::HostileOnly, Some(source)) => is_monster_side(*entity) != is_monster_side(source),
+                })
+                .collect();
+
+            if victims.is_empty() {
+                gamelog.add_entry(format!("The {} hits nothing.", effect.name));
+                continue;
+            }
+
+            for victim in &victims {
+                let damage = match resistances.get(*victim) {
+                    Some(resist) => resist.calculate_damage(effect.damage, effect.damage_type),
+                    None => effect.damage,
+                };
+
+                match effect.source {
+                    // Several areas can land on one creature in a frame, so damage stacks
+                    Some(source) => match damage_info.get_mut(*victim) {
+                        Some(existing) => existing.base_damage += damage,
+                        None => {
+                            damage_info.insert(*victim, DamageInfo {
+                                base_damage: damage,
+                                damage_type: effect.damage_type,
+                                source,
+                                is_critical: false,
+                                penetration: 0,
+                            }).expect("Failed to insert area damage");
+                        },
+                    },
+                    None => SufferDamage::new_damage(&mut suffer_damage, *victim, damage),
+                }
+
+                if let Some((effect_type, duration, magnitude)) = effect.status {
+                    apply_status_effect(*victim, StatusEffect::new(effect_type, duration, magnitude),
+                        &mut status_effects, resistances.get(*victim), &mut rng);
+                }
+
+                if players.contains(*victim) {
+                    gamelog.add_entry(format!("You are caught in the {} for {} {} damage!",
+                        effect.name, damage, effect.damage_type.name()));
+                }
+            }
+
+            let caught: Vec<&str> = victims.iter()
+                .filter(|victim| !players.contains(**victim))
+                .filter_map(|victim| names.get(*victim).map(|n| n.name.as_str()))
+                .collect();
+            if !caught.is_empty() {
+                gamelog.add_entry(format!("The {} engulfs {}!", effect.name, caught.join(", ")));
+            }
+        }
+    }
+}
+
+/// Lets dragons and other breathers loose a cone at the player when they have a clear view
+pub struct BreathWeaponSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl BreathWeaponSystem {
+    pub fn new() -> Self {
+        BreathWeaponSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for BreathWeaponSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, BreathWeapon>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        Read<'a, GameStateResource>,
+        Write<'a, AreaEffectQueue>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut breaths, positions, viewsheds, combat_stats, players, names, game_state, mut queue, mut gamelog, mut rng) = data;
+
+        // Breath recharges once per turn
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let player_pos = match (&players, &positions).join().next() {
+            Some((_, pos)) => (pos.x, pos.y),
+            None => return,
+        };
+
+        for (entity, breath, pos, viewshed, stats) in (&entities, &mut breaths, &positions, &viewsheds, &combat_stats).join() {
+            if breath.ready_in > 0 {
+                breath.ready_in -= 1;
+                continue;
+            }
+
+            let distance_sq = (player_pos.0 - pos.x).pow(2) + (player_pos.1 - pos.y).pow(2);
+            if stats.hp <= 0 || distance_sq > breath.range * breath.range || !viewshed.visible_tiles.contains(&player_pos) {
+                continue;
+            }
+
+            let name = names.get(entity).map_or("Something", |n| n.name.as_str());
+            gamelog.add_entry(format!("The {} breathes {}!", name, breath.damage_type.name().to_lowercase()));
+            queue.push(AreaEffect {
+                source: Some(entity),
+                origin: (pos.x, pos.y),
+                target: player_pos,
+                template: AreaTemplate::Cone { length: breath.range },
+                friendly_fire: FriendlyFire::SparesSource,
+                damage: rng.roll_dice(breath.damage_dice.0, breath.damage_dice.1),
+                damage_type: breath.damage_type,
+                status: (breath.damage_type == DamageType::Fire).then(|| (StatusEffectType::Burning, 3, 2)),
+                name: format!("{}'s breath", name),
+            });
+            breath.ready_in = breath.cooldown;
+        }
+    }
+}
+
+/// Tiles within `radius` of `center` that the blast can reach from the centre
+fn disc(map: &Map, center: (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+    let mut tiles = Vec::new();
+    for y in center.1 - radius..=center.1 + radius {
+        for x in center.0 - radius..=center.0 + radius {
+            let (dx, dy) = (x - center.0, y - center.1);
+            if dx * dx + dy * dy > radius * radius + radius || !map.in_bounds(x, y) || map.is_opaque(x, y) {
+                continue;
+            }
+            // The blast doesn't wrap around walls
+            let sheltered = line(center, (x, y)).iter().any(|(lx, ly)| map.is_opaque(*lx, *ly));
+            if !sheltered {
+                tiles.push((x, y));
+            }
+        }
+    }
+    tiles
+}
+
+/// Bresenham line from `from` to `to`, both ends included
+fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut points = Vec::new();
+    let (mut x, mut y) = from;
+    let dx = (to.0 - from.0).abs();
+    let dy = -(to.1 - from.1).abs();
+    let sx = if from.0 < to.0 { 1 } else { -1 };
+    let sy = if from.1 < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    loop {
+        points.push((x, y));
+        if (x, y) == to {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    fn open_room() -> Map {
+        let mut map = Map::new(12, 12, 1);
+        for y in 1..11 {
+            for x in 1..11 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_templates_cover_the_expected_tiles() {
+        let map = open_room();
+
+        let burst = AreaTemplate::Burst { radius: 1 }.tiles(&map, (5, 5), (5, 5));
+        assert_eq!(burst.len(), 8);
+        assert!(!burst.contains(&(5, 5)));
+
+        let circle = AreaTemplate::Circle { radius: 2 }.tiles(&map, (1, 1), (5, 5));
+        assert!(circle.contains(&(5, 5)) && circle.contains(&(7, 5)));
+        assert!(!circle.contains(&(8, 5)));
+
+        let line = AreaTemplate::Line { length: 4 }.tiles(&map, (2, 2), (3, 2));
+        assert_eq!(line, vec![(3, 2), (4, 2), (5, 2), (6, 2)]);
+
+        let cone = AreaTemplate::Cone { length: 3 }.tiles(&map, (5, 5), (8, 5));
+        assert!(cone.contains(&(8, 5)) && cone.contains(&(7, 6)));
+        assert!(!cone.contains(&(4, 5)));
+    }
+
+    #[test]
+    fn test_walls_shelter_tiles_behind_them() {
+        let mut map = open_room();
+        for y in 1..11 {
+            map.set_tile(6, y, TileType::Wall);
+        }
+
+        let circle = AreaTemplate::Circle { radius: 3 }.tiles(&map, (5, 5), (5, 5));
+        assert!(circle.contains(&(4, 5)));
+        assert!(!circle.contains(&(7, 5)));
+
+        let line = AreaTemplate::Line { length: 6 }.tiles(&map, (2, 5), (3, 5));
+        assert_eq!(line.last(), Some(&(5, 5)));
+    }
+}
@@ -0,0 +1,156 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect};
+use crate::components::{CombatStats, Monster, Name, Player, Position, Viewshed, WantsToExplore, WantsToMove};
+use crate::map::{DijkstraMap, DijkstraMaps, DijkstraSource, Map};
+use crate::resources::{GameLog, GameStateResource};
+
+/// The next step toward whichever is closer, an item lying about or the edge of the unexplored
+pub fn explore_step(map: &Map, items: Option<&DijkstraMap>, unexplored: Option<&DijkstraMap>, from: (i32, i32)) -> Option<(i32, i32)> {
+    let distance_to = |dijkstra: Option<&DijkstraMap>| dijkstra.and_then(|dijkstra| dijkstra.value(from.0, from.1));
+    let nearest = match (distance_to(items), distance_to(unexplored)) {
+        (Some(item), Some(edge)) if item <= edge => items,
+        (Some(_), None) => items,
+        (_, Some(_)) => unexplored,
+        (None, None) => None,
+    };
+    nearest.and_then(|dijkstra| dijkstra.downhill(map, from))
+}
+
+/// What stops a player walking on their own: a monster coming into view, or fresh wounds
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalkInterruption {
+    Spotted(String),
+    Hurt,
+}
+
+/// Whether a player walking on their own should stop before taking their next step
+pub fn walk_interruption(
+    viewshed: &Viewshed,
+    monsters: &ReadStorage<Monster>,
+    positions: &ReadStorage<Position>,
+    names: &ReadStorage<Name>,
+    last_hp: Option<i32>,
+    hp: Option<i32>,
+) -> Option<WalkInterruption> {
+    let spotted = (monsters, positions, names).join()
+        .find(|(_, monster_pos, _)| viewshed.visible_tiles.contains(&(monster_pos.x, monster_pos.y)))
+        .map(|(_, _, name)| name.name.clone());
+    if let Some(name) = spotted {
+        return Some(WalkInterruption::Spotted(name));
+    }
+    if matches!((last_hp, hp), (Some(last), Some(now)) if now < last) {
+        return Some(WalkInterruption::Hurt);
+    }
+    None
+}
+
+/// Walks the exploring player one step per update, moving the turn on, until there is nothing
+/// left to find or something needs their attention
+pub struct AutoExploreSystem;
+
+impl<'a> System<'a> for AutoExploreSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToExplore>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, CombatStats>,
+        ReadExpect<'a, Map>,
+        Read<'a, DijkstraMaps>,
+        Write<'a, GameStateResource>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_explore,
+            mut wants_move,
+            mut viewsheds,
+            players,
+            positions,
+            monsters,
+            names,
+            combat_stats,
+            map,
+            maps,
+            mut game_state,
+            mut gamelog,
+        ) = data;
+
+        let mut finished: Vec<Entity> = Vec::new();
+
+        for (entity, explore, pos, viewshed, _) in (&entities, &mut wants_explore, &positions, &mut viewsheds, &players).join() {
+            let here = (pos.x, pos.y);
+            let hp = combat_stats.get(entity).map(|stats| stats.hp);
+
+            match walk_interruption(viewshed, &monsters, &positions, &names, explore.last_hp, hp) {
+                Some(WalkInterruption::Spotted(name)) => {
+                    gamelog.add_entry(format!("You spot a {} and stop exploring.", name));
+                    finished.push(entity);
+                    continue;
+                },
+                Some(WalkInterruption::Hurt) => {
+                    gamelog.add_entry("You are hurt and stop exploring.".to_string());
+                    finished.push(entity);
+                    continue;
+                },
+                None => {},
+            }
+            let items = maps.get(DijkstraSource::Items);
+            if explore.steps > 0 && items.and_then(|items| items.value(here.0, here.1)) == Some(0) {
+                gamelog.add_entry("You find something lying here.".to_string());
+                finished.push(entity);
+                continue;
+            }
+
+            match explore_step(&map, items, maps.get(DijkstraSource::Unexplored), here) {
+                Some(step) => {
+                    wants_move.insert(entity, WantsToMove { destination: step })
+                        .expect("Unable to insert move intent");
+                    viewshed.dirty = true;
+                    game_state.turn_count += 1;
+                    explore.steps += 1;
+                    explore.last_hp = hp;
+                },
+                None => {
+                    let message = if explore.steps == 0 {
+                        "There is nowhere left to explore that you can reach."
+                    } else {
+                        "You have explored everything you can reach."
+                    };
+                    gamelog.add_entry(message.to_string());
+                    finished.push(entity);
+                },
+            }
+        }
+
+        for entity in finished {
+            wants_explore.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    #[test]
+    fn test_exploring_heads_for_the_nearer_of_items_and_the_unknown() {
+        let mut map = Map::new(12, 5, 1);
+        for x in 1..11 {
+            map.set_tile(x, 2, TileType::Floor);
+        }
+        let items = DijkstraMap::new(&map, &[(2, 2)]);
+        let unexplored = DijkstraMap::new(&map, &[(10, 2)]);
+
+        assert_eq!(explore_step(&map, Some(&items), Some(&unexplored), (4, 2)), Some((3, 2)));
+        assert_eq!(explore_step(&map, Some(&items), Some(&unexplored), (8, 2)), Some((9, 2)));
+        assert_eq!(explore_step(&map, None, Some(&unexplored), (4, 2)), Some((5, 2)));
+        assert_eq!(explore_step(&map, None, None, (4, 2)), None);
+    }
+}
@@ -0,0 +1,92 @@
+use specs::{System, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
+use std::collections::HashSet;
+use crate::components::{Monster, Name, Position, Renderable, CombatStats, DamageResistances, Elite};
+use crate::map::Map;
+use crate::progression::Bestiary;
+
+/// Fills in the bestiary: each monster counts once when it first comes into view and once
+/// when it dies
+pub struct BestiarySystem {
+    seen: HashSet<Entity>,
+    killed: HashSet<Entity>,
+}
+
+impl BestiarySystem {
+    pub fn new() -> Self {
+        BestiarySystem { seen: HashSet::new(), killed: HashSet::new() }
+    }
+}
+
+impl<'a> System<'a> for BestiarySystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, DamageResistances>,
+        ReadStorage<'a, Elite>,
+        ReadExpect<'a, Map>,
+        Write<'a, Bestiary>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, monsters, names, positions, renderables, combat_stats, resistances, elites, map, mut bestiary) = data;
+
+        self.seen.retain(|entity| entities.is_alive(*entity));
+        self.killed.retain(|entity| entities.is_alive(*entity));
+
+        for (entity, _monster, name, pos) in (&entities, &monsters, &names, &positions).join() {
+            let glyph = renderables.get(entity).map_or('?', |render| render.glyph);
+            let stats = combat_stats.get(entity);
+            if map.is_visible(pos.x, pos.y) && self.seen.insert(entity) {
+                bestiary.record_seen(&name.name, glyph, stats, resistances.get(entity), elites.contains(entity));
+            }
+            if stats.map_or(false, |stats| stats.hp <= 0) && self.killed.insert(entity) {
+                bestiary.record_kill(&name.name, glyph);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+
+    #[test]
+    fn test_each_monster_is_seen_and_killed_once() {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.insert(Bestiary::default());
+        let mut map = Map::new(20, 20, 1);
+        let idx = map.xy_idx(5, 5);
+        map.visible_tiles[idx] = true;
+        world.insert(map);
+
+        let goblin = world.create_entity()
+            .with(Monster {})
+            .with(Name { name: "Goblin".to_string() })
+            .with(Position { x: 5, y: 5 })
+            .with(CombatStats { max_hp: 6, hp: 6, defense: 1, power: 4 })
+            .build();
+        world.create_entity()
+            .with(Monster {})
+            .with(Name { name: "Goblin".to_string() })
+            .with(Position { x: 10, y: 10 })
+            .build();
+
+        let mut system = BestiarySystem::new();
+        system.run_now(&world);
+        system.run_now(&world);
+        world.write_storage::<CombatStats>().get_mut(goblin).unwrap().hp = 0;
+        system.run_now(&world);
+        system.run_now(&world);
+
+        let bestiary = world.read_resource::<Bestiary>();
+        let entry = bestiary.entry("Goblin").unwrap();
+        assert_eq!((entry.seen, entry.killed), (1, 1));
+        assert_eq!(entry.stats, Some((6, 1, 4)));
+    }
+}
@@ -0,0 +1,269 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Join, Write, Read, ReadExpect};
+use crate::components::{
+    Campfire, CombatStats, Exposure, Player, PlayerResources, Position, StatusEffect, StatusEffectType, StatusEffects,
+    SufferDamage
+};
+use crate::items::{
+    item_equipment_stats, AdvancedInventory, Equipment, ItemBonuses, ItemProperties, ItemType, ToolType
+};
+use crate::localization::Message;
+use crate::map::{Climate, Map, TileEffect, TileType};
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+
+/// Turns spent unprotected before the climate starts to tell
+pub const EXPOSURE_GRACE: i32 = 10;
+/// Further turns of exposure for each step worse it gets
+pub const EXPOSURE_STEP: i32 = 25;
+/// The worst the climate gets
+const MAX_SEVERITY: i32 = 3;
+/// Percent resistance to the climate's element that keeps it off entirely
+pub const CLIMATE_PROOF_RESISTANCE: i32 = 50;
+/// Exposure shaken off each turn spent protected
+const EXPOSURE_RECOVERY: i32 = 3;
+/// With stamina gone, health goes every this many turns
+const CLIMATE_DAMAGE_INTERVAL: i32 = 3;
+/// Turns the chill or the heat lingers once out of it
+const CLIMATE_EFFECT_TURNS: i32 = 2;
+
+/// How badly `turns` of exposure tell, or `None` while still within the grace
+pub fn exposure_severity(turns: i32) -> Option<i32> {
+    if turns <= EXPOSURE_GRACE {
+        None
+    } else {
+        Some((1 + (turns - EXPOSURE_GRACE) / EXPOSURE_STEP).min(MAX_SEVERITY))
+    }
+}
+
+/// The status the climate inflicts, and the one that wards it off
+pub fn climate_effects(climate: Climate) -> Option<(StatusEffectType, StatusEffectType)> {
+    match climate {
+        Climate::Temperate => None,
+        Climate::Cold => Some((StatusEffectType::Chilled, StatusEffectType::Warmed)),
+        Climate::Hot => Some((StatusEffectType::Overheated, StatusEffectType::Hydrated)),
+    }
+}
+
+/// Once a turn, wears down the player on a level too cold or too hot for them. Unprotected
+/// turns add up, and after a short grace the chill or the heat drains stamina, then health once
+/// stamina is gone. Against the cold, a torch in the pack, a nearby fire, a warming draught or
+/// cold-proof gear keeps it off; against the heat, water or fire-proof gear does. Gear with some
+/// resistance, but not enough, slows the exposure down
+pub struct ClimateSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl ClimateSystem {
+    pub fn new() -> Self {
+        ClimateSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for ClimateSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Equipment>,
+        ReadStorage<'a, ItemBonuses>,
+        ReadStorage<'a, ItemProperties>,
+        ReadStorage<'a, AdvancedInventory>,
+        ReadStorage<'a, Campfire>,
+        WriteStorage<'a, Exposure>,
+        WriteStorage<'a, PlayerResources>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, SufferDamage>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            players,
+            positions,
+            combat_stats,
+            equipment,
+            bonuses,
+            properties,
+            inventories,
+            campfires,
+            mut exposures,
+            mut player_resources,
+            mut status_effects,
+            mut suffer_damage,
+            map,
+            game_state,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let climate = map.theme.climate();
+        let fires: Vec<(i32, i32, i32)> = (&positions, &campfires).join()
+            .map(|(pos, fire)| (pos.x, pos.y, fire.radius))
+            .collect();
+
+        for (entity, _, pos, stats) in (&entities, &players, &positions, &combat_stats).join() {
+            if stats.hp <= 0 {
+                continue;
+            }
+            let previous = exposures.get(entity).map_or(0, |exposure| exposure.turns);
+            let (inflicted, ward) = match climate_effects(climate) {
+                Some(effects) => effects,
+                None => {
+                    if previous > 0 {
+                        exposures.insert(entity, Exposure::default()).expect("Unable to reset exposure");
+                    }
+                    continue;
+                },
+            };
+
+            let warded = status_effects.get(entity).map_or(false, |effects| effects.has_effect(ward));
+            let gear = equipment.get(entity).map_or(0, |worn| {
+                worn.slots.values()
+                    .flatten()
+                    .map(|item| item_equipment_stats(bonuses.get(*item), properties.get(*item)))
+                    .map(|stats| if climate == Climate::Hot { stats.fire_resistance } else { stats.cold_resistance })
+                    .sum()
+            });
+            let kept_warm = climate == Climate::Cold && (
+                carries_torch(inventories.get(entity), equipment.get(entity), &properties)
+                    || beside_fire(&map, pos, &fires)
+            );
+
+            // Some resistance only slows the exposure; enough of it, or the right provisions, stops it
+            let turns = if warded || kept_warm || gear >= CLIMATE_PROOF_RESISTANCE {
+                (previous - EXPOSURE_RECOVERY).max(0)
+            } else if gear > 0 && rng.roll_dice(1, 100) <= gear {
+                previous
+            } else {
+                previous + 1
+            };
+            exposures.insert(entity, Exposure { turns }).expect("Unable to record exposure");
+
+            let severity = match exposure_severity(turns) {
+                Some(severity) if turns > previous => severity,
+                _ => continue,
+            };
+            if let Some(effects) = status_effects.get_mut(entity) {
+                if !effects.has_effect(inflicted) {
+                    let key = if climate == Climate::Hot { "log.heat_sets_in" } else { "log.cold_sets_in" };
+                    gamelog.add_message(Message::new(key));
+                }
+                effects.add_effect(StatusEffect::new(inflicted, CLIMATE_EFFECT_TURNS, severity));
+            }
+
+            let spent = match player_resources.get_mut(entity) {
+                Some(resources) => {
+                    resources.stamina = (resources.stamina - (severity + 1)).max(0);
+                    resources.stamina == 0
+                },
+                None => true,
+            };
+            if spent && turns % CLIMATE_DAMAGE_INTERVAL == 0 {
+                SufferDamage::new_damage(&mut suffer_damage, entity, severity);
+                let key = if climate == Climate::Hot { "log.heatstroke" } else { "log.freezing" };
+                gamelog.add_message(Message::new(key).arg("damage", severity));
+            }
+        }
+    }
+}
+
+/// Whether a torch is in the pack or in hand
+fn carries_torch(inventory: Option<&AdvancedInventory>, worn: Option<&Equipment>, properties: &ReadStorage<ItemProperties>) -> bool {
+    let is_torch = |item: &specs::Entity| properties.get(*item)
+        .map_or(false, |props| props.item_type == ItemType::Tool(ToolType::Torch));
+    inventory.map_or(false, |inventory| inventory.items.iter().any(|slot| is_torch(&slot.entity)))
+        || worn.map_or(false, |worn| worn.slots.values().flatten().any(is_torch))
+}
+
+/// Whether a campfire's warmth reaches the tile, or flames or lava burn right beside it
+fn beside_fire(map: &Map, pos: &Position, fires: &[(i32, i32, i32)]) -> bool {
+    let by_campfire = fires.iter().any(|(x, y, radius)| (x - pos.x).abs().max((y - pos.y).abs()) <= *radius);
+    by_campfire || (-1..=1).any(|dy| (-1..=1).any(|dx| {
+        let (x, y) = (pos.x + dx, pos.y + dy);
+        map.get_tile(x, y) == Some(TileType::Lava) || map.has_tile_effect(x, y, TileEffect::Burning)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::map::MapTheme;
+
+    fn world(theme: MapTheme) -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<Equipment>();
+        world.register::<ItemBonuses>();
+        world.register::<ItemProperties>();
+        world.register::<AdvancedInventory>();
+        world.insert(Map::new_with_theme(10, 10, 1, theme, 0));
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(5));
+        world
+    }
+
+    fn player(world: &mut World) -> specs::Entity {
+        world.create_entity()
+            .with(Player)
+            .with(Position { x: 5, y: 5 })
+            .with(CombatStats { max_hp: 30, hp: 30, defense: 0, power: 1 })
+            .with(PlayerResources::new(10, 20))
+            .with(StatusEffects::new())
+            .build()
+    }
+
+    fn run_turns(world: &mut World, turns: u32) {
+        let mut climate = ClimateSystem::new();
+        for turn in 0..turns {
+            world.write_resource::<GameStateResource>().turn_count = turn;
+            climate.run_now(world);
+        }
+    }
+
+    #[test]
+    fn test_the_cold_drains_stamina_then_health() {
+        let mut world = world(MapTheme::Ice);
+        let player = player(&mut world);
+
+        run_turns(&mut world, EXPOSURE_GRACE as u32 + 12);
+
+        assert_eq!(world.read_storage::<PlayerResources>().get(player).map(|r| r.stamina), Some(0));
+        assert!(world.read_storage::<StatusEffects>().get(player).unwrap().has_effect(StatusEffectType::Chilled));
+        assert!(world.read_storage::<SufferDamage>().get(player).map_or(0, |damage| damage.amount) > 0);
+    }
+
+    #[test]
+    fn test_water_keeps_the_heat_off_and_mild_levels_do_nothing() {
+        let mut world = world(MapTheme::Volcanic);
+        let player = player(&mut world);
+        world.write_storage::<StatusEffects>().get_mut(player).unwrap()
+            .add_effect(StatusEffect::new(StatusEffectType::Hydrated, 100, 1));
+
+        run_turns(&mut world, EXPOSURE_GRACE as u32 + 12);
+        assert_eq!(world.read_storage::<Exposure>().get(player).map(|e| e.turns), Some(0));
+        assert!(!world.read_storage::<StatusEffects>().get(player).unwrap().has_effect(StatusEffectType::Overheated));
+
+        *world.write_resource::<Map>() = Map::new(10, 10, 1);
+        run_turns(&mut world, EXPOSURE_GRACE as u32 + 12);
+        assert_eq!(world.read_storage::<PlayerResources>().get(player).map(|r| r.stamina), Some(20));
+    }
+
+    #[test]
+    fn test_exposure_worsens_in_steps() {
+        assert_eq!(exposure_severity(EXPOSURE_GRACE), None);
+        assert_eq!(exposure_severity(EXPOSURE_GRACE + 1), Some(1));
+        assert_eq!(exposure_severity(EXPOSURE_GRACE + EXPOSURE_STEP), Some(2));
+        assert_eq!(exposure_severity(EXPOSURE_GRACE + EXPOSURE_STEP * 10), Some(MAX_SEVERITY));
+    }
+}
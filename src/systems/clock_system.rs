@@ -0,0 +1,69 @@
+use specs::{System, WriteStorage, ReadStorage, Join, Write, Read, ReadExpect};
+use crate::components::{Player, StatusEffect, StatusEffects, StatusEffectType, Viewshed};
+use crate::map::Map;
+use crate::resources::{GameClock, GameLog, GameStateResource, TimeOfDay};
+
+/// Advances the game clock with the turn counter and applies what the hour brings
+pub struct ClockSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl ClockSystem {
+    pub fn new() -> Self {
+        ClockSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for ClockSystem {
+    type SystemData = (
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, Viewshed>,
+        ReadExpect<'a, Map>,
+        Write<'a, GameClock>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (players, mut status_effects, mut viewsheds, map, mut clock, game_state, mut gamelog) = data;
+
+        // Several turns can pass at once, such as while resting
+        let turns = match self.last_turn {
+            Some(last) if game_state.turn_count > last => game_state.turn_count - last,
+            _ => 0,
+        };
+        self.last_turn = Some(game_state.turn_count);
+        if turns == 0 {
+            return;
+        }
+
+        let before = clock.time_of_day();
+        clock.advance_turns(turns as u64);
+        let now = clock.time_of_day();
+
+        // The light changes on the surface; below ground nobody notices
+        if now != before && map.theme.is_outdoors() {
+            for (viewshed, _) in (&mut viewsheds, &players).join() {
+                viewshed.dirty = true;
+            }
+            let message = match now {
+                TimeOfDay::Dawn => "The sky pales with the coming dawn.",
+                TimeOfDay::Day => "The sun is up.",
+                TimeOfDay::Dusk => "The light fades as dusk falls.",
+                TimeOfDay::Night => "Night has fallen.",
+            };
+            gamelog.add_entry(message.to_string());
+        }
+
+        // Too long without rest wears the body down until the next proper sleep
+        if clock.is_weary() {
+            for (effects, _) in (&mut status_effects, &players).join() {
+                if !effects.has_effect(StatusEffectType::StaminaRegenPenalty) {
+                    gamelog.add_entry(format!("You have been awake for {} hours and feel weary. Rest soon.", clock.hours_awake()));
+                }
+                effects.add_effect(StatusEffect::new(StatusEffectType::StaminaRegenPenalty, 2, 2));
+            }
+        }
+    }
+}
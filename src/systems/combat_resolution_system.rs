@@ -1,9 +1,28 @@
 use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
 use crate::components::{
     WantsToAttack, CombatStats, Attacker, Defender, DamageInfo, DamageResistances, 
-    DamageType, DefenseResult, Name, Player, Monster, Initiative, Attributes, Skills, SkillType
+    DamageType, DefenseResult, Name, Player, Monster, Initiative, Attributes, Skills, SkillType,
+    StatusEffects, GameSettings, Position, Renderable
 };
-use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::items::{EquipmentWear, WearReason};
+use crate::resources::{GameLog, RandomNumberGenerator, COMBAT_STREAM};
+use crate::rendering::{Animation, AnimationQueue};
+use crate::systems::{ParticleEmitter, NoiseEvents, MELEE_NOISE};
+use crate::ui::{TutorialEvents, TutorialTrigger};
+
+/// A natural 20 always hits and threatens a critical
+pub const CRITICAL_THREAT_ROLL: i32 = 20;
+/// A natural 1 always misses
+pub const FUMBLE_ROLL: i32 = 1;
+
+/// Whether an attack roll beats the target's armor class
+pub fn attack_hits(natural_roll: i32, total_attack: i32, total_defense: i32) -> bool {
+    match natural_roll {
+        FUMBLE_ROLL => false,
+        CRITICAL_THREAT_ROLL => true,
+        _ => total_attack >= total_defense,
+    }
+}
 
 pub struct CombatResolutionSystem {}
 
@@ -18,11 +37,20 @@ impl<'a> System<'a> for CombatResolutionSystem {
         ReadStorage<'a, DamageResistances>,
         ReadStorage<'a, Attributes>,
         ReadStorage<'a, Skills>,
+        ReadStorage<'a, StatusEffects>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, Player>,
         ReadStorage<'a, Monster>,
+        ReadStorage<'a, GameSettings>,
+        WriteStorage<'a, EquipmentWear>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Renderable>,
         Write<'a, GameLog>,
         Write<'a, RandomNumberGenerator>,
+        Write<'a, AnimationQueue>,
+        Write<'a, ParticleEmitter>,
+        Write<'a, NoiseEvents>,
+        Write<'a, TutorialEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -36,11 +64,20 @@ impl<'a> System<'a> for CombatResolutionSystem {
             damage_resistances,
             attributes,
             skills,
+            status_effects,
             names, 
             players,
             monsters,
+            settings,
+            mut equipment_wear,
+            positions,
+            renderables,
             mut gamelog, 
-            mut rng
+            mut rng,
+            mut animations,
+            mut particles,
+            mut noises,
+            mut tutorials
         ) = data;
 
         // Process attack intents
@@ -51,9 +88,30 @@ impl<'a> System<'a> for CombatResolutionSystem {
         
         // Clear attack intents
         wants_attack.clear();
+
+        let verbose = (&players, &settings).join().any(|(_, settings)| settings.verbose_combat);
+        let gore = !(&players, &settings).join().any(|(_, settings)| settings.disable_gore);
         
         // Process attacks with detailed resolution
         for (attacker_entity, target_entity) in attack_intents {
+            // Show the swing, hit or miss, before anything else happens
+            if let (Some(from), Some(to), Some(render)) =
+                (positions.get(attacker_entity), positions.get(target_entity), renderables.get(attacker_entity)) {
+                animations.push(Animation::MeleeBump {
+                    from: (from.x, from.y),
+                    to: (to.x, to.y),
+                    glyph: render.glyph,
+                    color: render.fg,
+                });
+            }
+            // Fighting is loud; anything close enough comes to look
+            if let Some(to) = positions.get(target_entity) {
+                noises.emit((to.x, to.y), MELEE_NOISE);
+            }
+            if players.contains(attacker_entity) || players.contains(target_entity) {
+                tutorials.publish(TutorialTrigger::FirstCombat);
+            }
+            
             let resolution = self.resolve_attack(
                 attacker_entity,
                 target_entity,
@@ -62,12 +120,19 @@ impl<'a> System<'a> for CombatResolutionSystem {
                 &defenders,
                 &attributes,
                 &skills,
+                &status_effects,
                 &names,
-                &mut rng,
-                &mut gamelog
+                &mut equipment_wear,
+                rng.stream(COMBAT_STREAM),
+                &mut gamelog,
+                verbose
             );
             
             if let Some(damage) = resolution {
+                // Landing a hit wears the weapon and the armor that took it
+                EquipmentWear::add(&mut equipment_wear, attacker_entity, WearReason::Attack);
+                EquipmentWear::add(&mut equipment_wear, target_entity, WearReason::Struck);
+                
                 // Apply damage resistances
                 let final_damage = if let Some(resistances) = damage_resistances.get(target_entity) {
                     resistances.calculate_damage(damage.base_damage, damage.damage_type)
@@ -86,12 +151,23 @@ impl<'a> System<'a> for CombatResolutionSystem {
                 
                 damage_info.insert(target_entity, final_damage_info)
                     .expect("Failed to insert damage info");
+                
+                if let (true, Some(from), Some(to)) = (gore, positions.get(attacker_entity), positions.get(target_entity)) {
+                    particles.blood_spray((to.x, to.y), (from.x, from.y));
+                }
             }
         }
     }
 }
 
 impl CombatResolutionSystem {
+    fn effective_stats(stats: &CombatStats, effects: Option<&StatusEffects>) -> CombatStats {
+        match effects {
+            Some(effects) => effects.stat_modifiers().apply_to(stats),
+            None => stats.clone(),
+        }
+    }
+    
     fn resolve_attack(
         &self,
         attacker: Entity,
@@ -101,18 +177,27 @@ impl CombatResolutionSystem {
         defenders: &ReadStorage<Defender>,
         attributes: &ReadStorage<Attributes>,
         skills: &ReadStorage<Skills>,
+        status_effects: &ReadStorage<StatusEffects>,
         names: &ReadStorage<Name>,
+        equipment_wear: &mut WriteStorage<EquipmentWear>,
         rng: &mut RandomNumberGenerator,
         gamelog: &mut GameLog,
+        verbose: bool,
     ) -> Option<DamageInfo> {
-        // Get required components
-        let attacker_stats = combat_stats.get(attacker)?;
-        let target_stats = combat_stats.get(target)?;
+        // Get required components, adjusted by active status effects
+        let attacker_stats = &Self::effective_stats(combat_stats.get(attacker)?, status_effects.get(attacker));
+        let target_stats = &Self::effective_stats(combat_stats.get(target)?, status_effects.get(target));
         
         // Get names for logging
         let attacker_name = names.get(attacker).map_or("Unknown", |n| &n.name);
         let target_name = names.get(target).map_or("Unknown", |n| &n.name);
         
+        // Stunned, paralyzed or sleeping attackers lose their attack
+        if status_effects.get(attacker).map_or(false, |effects| effects.is_incapacitated()) {
+            gamelog.add_entry(format!("{} is unable to attack!", attacker_name));
+            return None;
+        }
+        
         // Phase 1: Attack Roll Calculation
         let attack_result = self.calculate_attack_roll(
             attacker, attacker_stats, attackers, attributes, skills, rng
@@ -123,10 +208,21 @@ impl CombatResolutionSystem {
             target, target_stats, defenders, attributes, skills, rng
         );
         
+        if verbose {
+            gamelog.add_entry(format!("  [Attack d20 {} +{} attr +{} skill +{} power +{} bonus = {} vs AC {} +{} dex +{} skill +{} def = {}]",
+                attack_result.base_roll, attack_result.attribute_bonus, attack_result.skill_bonus,
+                attack_result.stats_bonus, attack_result.attacker_bonus, attack_result.total_attack,
+                defense_result.base_ac, defense_result.attribute_bonus, defense_result.skill_bonus,
+                defense_result.stats_bonus, defense_result.total_defense));
+        }
+        
         // Phase 3: Hit Determination
-        if attack_result.total_attack < defense_result.total_defense {
-            gamelog.add_entry(format!("{} attacks {} but misses! (Attack: {} vs Defense: {})", 
-                attacker_name, target_name, attack_result.total_attack, defense_result.total_defense));
+        if !attack_hits(attack_result.base_roll, attack_result.total_attack, defense_result.total_defense) {
+            if attack_result.base_roll == FUMBLE_ROLL {
+                gamelog.add_entry(format!("{} attacks {} but fumbles!", attacker_name, target_name));
+            } else {
+                gamelog.add_entry(format!("{} attacks {} but misses!", attacker_name, target_name));
+            }
             return None;
         }
         
@@ -138,6 +234,7 @@ impl CombatResolutionSystem {
             },
             DefenseResult::Blocked => {
                 gamelog.add_entry(format!("{} attacks {} but the attack is blocked!", attacker_name, target_name));
+                EquipmentWear::add(equipment_wear, target, WearReason::Block);
                 return None;
             },
             DefenseResult::Parried => {
@@ -150,14 +247,36 @@ impl CombatResolutionSystem {
             }
         }
         
-        // Phase 5: Damage Calculation
+        // Phase 5: Critical Threat and Confirmation
+        // A natural 20 or a lucky strike threatens a critical; a second roll against AC confirms it
+        let threatened = attack_result.base_roll == CRITICAL_THREAT_ROLL
+            || attackers.get(attacker).map_or(false, |a| a.is_critical_hit(rng));
+        let is_critical = threatened && {
+            let confirm_roll = rng.roll_dice(1, 20);
+            let confirm_total = attack_result.total_attack - attack_result.base_roll + confirm_roll;
+            if verbose {
+                gamelog.add_entry(format!("  [Critical threat! Confirm d20 {} = {} vs {}]",
+                    confirm_roll, confirm_total, defense_result.total_defense));
+            }
+            attack_hits(confirm_roll, confirm_total, defense_result.total_defense)
+        };
+        
+        // Phase 6: Damage Calculation
         let damage_result = self.calculate_damage(
             attacker, attacker_stats, attackers, attributes, skills, 
-            target, target_stats, defenders, rng
+            target, target_stats, defenders, is_critical
         );
         
-        // Phase 6: Damage Type and Critical Hit Processing
-        let final_damage = self.process_damage_modifiers(damage_result, rng);
+        if verbose {
+            gamelog.add_entry(format!("  [Damage {} +{} attr +{} skill{} - {} DR ({} pen) = {}]",
+                damage_result.base_damage, damage_result.attribute_bonus, damage_result.skill_bonus,
+                if damage_result.is_critical { " x crit" } else { "" },
+                damage_result.damage_reduction, damage_result.penetration, damage_result.final_damage));
+        }
+        
+        // Phase 7: Damage Type and Critical Hit Processing
+        let mut final_damage = self.process_damage_modifiers(damage_result, rng);
+        final_damage.source = attacker;
         
         // Log the successful attack
         if final_damage.is_critical {
@@ -281,7 +400,7 @@ impl CombatResolutionSystem {
         target: Entity,
         target_stats: &CombatStats,
         defenders: &ReadStorage<Defender>,
-        rng: &mut RandomNumberGenerator,
+        is_critical: bool,
     ) -> DamageResult {
         // Base damage from combat stats
         let base_damage = attacker_stats.power;
@@ -300,13 +419,6 @@ impl CombatResolutionSystem {
             0
         };
         
-        // Check for critical hit
-        let is_critical = if let Some(attacker) = attackers.get(attacker) {
-            attacker.is_critical_hit(rng)
-        } else {
-            false
-        };
-        
         // Calculate total damage before critical
         let total_damage = base_damage + attribute_bonus + skill_bonus;
         
@@ -322,9 +434,10 @@ impl CombatResolutionSystem {
             total_damage
         };
         
-        // Apply damage reduction from defender
+        // Armor penetration strips away part of the defender's damage reduction
+        let penetration = attackers.get(attacker).map_or(0, |a| a.armor_penetration);
         let damage_reduction = if let Some(defender) = defenders.get(target) {
-            defender.damage_reduction
+            defender.effective_damage_reduction(penetration)
         } else {
             0
         };
@@ -337,6 +450,7 @@ impl CombatResolutionSystem {
             skill_bonus,
             is_critical,
             damage_reduction,
+            penetration,
             final_damage: reduced_damage,
         }
     }
@@ -353,7 +467,7 @@ impl CombatResolutionSystem {
             damage_type: DamageType::Physical,
             source: Entity::from_raw(0), // This would be set properly in the calling function
             is_critical: damage_result.is_critical,
-            penetration: 0, // Could be calculated based on weapon properties
+            penetration: damage_result.penetration,
         }
     }
 }
@@ -386,5 +500,29 @@ struct DamageResult {
     skill_bonus: i32,
     is_critical: bool,
     damage_reduction: i32,
+    penetration: i32,
     final_damage: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_rolls_override_armor_class() {
+        assert!(attack_hits(CRITICAL_THREAT_ROLL, 5, 30));
+        assert!(!attack_hits(FUMBLE_ROLL, 40, 10));
+        assert!(attack_hits(12, 15, 15));
+        assert!(!attack_hits(12, 14, 15));
+    }
+
+    #[test]
+    fn test_penetration_reduces_damage_reduction() {
+        let mut defender = Defender::new();
+        defender.damage_reduction = 5;
+
+        assert_eq!(defender.effective_damage_reduction(0), 5);
+        assert_eq!(defender.effective_damage_reduction(3), 2);
+        assert_eq!(defender.effective_damage_reduction(8), 0);
+    }
 }
\ No newline at end of file
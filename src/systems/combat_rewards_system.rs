@@ -1,10 +1,13 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Builder};
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, Builder, LazyUpdate};
 use crate::components::{
-    CombatStats, Experience, Name, Player, Monster, Position, Item, Renderable,
-    ProvidesHealing, MeleePowerBonus, DefenseBonus, Equippable, EquipmentSlot,
-    LootTable, LootDrop, UniqueEnemy, CombatReward
+    CombatStats, Experience, Name, Player, Monster, Position, Renderable, EquipmentSlot,
+    LootTable, LootDrop, UniqueEnemy, CombatReward, Corpse, Treasure, TreasureType, Undead, Elite, BossEnemy,
+    LastAttacker, Companion, Rival
 };
-use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::resources::{GameLog, RandomNumberGenerator, GameEvents};
+use crate::achievements::GameEvent;
+use crate::ui::{TutorialEvents, TutorialTrigger};
+use crate::systems::{kill_credit, DivineDeeds, Deed, Reputation, Conduct};
 use crossterm::style::Color;
 
 pub struct CombatRewardsSystem {}
@@ -20,9 +23,20 @@ impl<'a> System<'a> for CombatRewardsSystem {
         ReadStorage<'a, Position>,
         ReadStorage<'a, LootTable>,
         ReadStorage<'a, UniqueEnemy>,
+        ReadStorage<'a, Elite>,
+        ReadStorage<'a, Undead>,
+        ReadStorage<'a, BossEnemy>,
+        ReadStorage<'a, LastAttacker>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Rival>,
         WriteStorage<'a, CombatReward>,
+        Read<'a, LazyUpdate>,
         Write<'a, GameLog>,
         Write<'a, RandomNumberGenerator>,
+        Write<'a, TutorialEvents>,
+        Write<'a, GameEvents>,
+        Write<'a, DivineDeeds>,
+        Write<'a, Reputation>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -36,15 +50,26 @@ impl<'a> System<'a> for CombatRewardsSystem {
             positions,
             loot_tables,
             unique_enemies,
+            elites,
+            undead,
+            bosses,
+            last_attackers,
+            companions,
+            rivals,
             mut combat_rewards,
+            lazy,
             mut gamelog, 
-            mut rng
+            mut rng,
+            mut tutorials,
+            mut events,
+            mut deeds,
+            mut reputation,
         ) = data;
 
         // Find dead monsters and process rewards
         let mut dead_monsters = Vec::new();
         
-        for (entity, stats, _monster, name) in (&entities, &combat_stats, &monsters, &names).join() {
+        for (entity, stats, _monster, name, _) in (&entities, &combat_stats, &monsters, &names, !&combat_rewards).join() {
             if stats.hp <= 0 {
                 let monster_pos = positions.get(entity).cloned();
                 let loot_table = loot_tables.get(entity).cloned();
@@ -54,8 +79,25 @@ impl<'a> System<'a> for CombatRewardsSystem {
             }
         }
         
+        if !dead_monsters.is_empty() {
+            tutorials.publish(TutorialTrigger::Custom("enemy_defeated".to_string()));
+        }
+        
         // Process rewards for each dead monster
         for (dead_entity, monster_name, monster_stats, monster_pos, loot_table, is_unique) in dead_monsters {
+            events.publish(GameEvent::EnemyKilled);
+            if bosses.contains(dead_entity) {
+                events.publish(GameEvent::BossDefeated);
+            }
+            if let Some(killer) = last_attackers.get(dead_entity) {
+                let deed = Deed::Slew { undead: undead.contains(dead_entity), boss: bosses.contains(dead_entity) };
+                let credit = kill_credit(killer.entity, &companions);
+                deeds.publish(credit, deed);
+                if players.contains(credit) {
+                    reputation.record(Conduct::Slew { boss: bosses.contains(dead_entity) });
+                }
+            }
+
             // Calculate and distribute experience
             self.distribute_experience(
                 dead_entity,
@@ -70,19 +112,12 @@ impl<'a> System<'a> for CombatRewardsSystem {
                 &mut rng
             );
             
-            // Generate and drop loot
+            // Leave a corpse holding whatever the monster carried
             if let Some(pos) = monster_pos {
-                self.generate_loot(
-                    dead_entity,
-                    &monster_name,
-                    &monster_stats,
-                    pos,
-                    loot_table,
-                    is_unique,
-                    &entities,
-                    &mut gamelog,
-                    &mut rng
-                );
+                let affixes = elites.get(dead_entity).map_or(0, |elite| elite.affixes.len());
+                let drops = self.generate_loot(&monster_name, &monster_stats, loot_table, is_unique, affixes, &mut rng);
+                let remains = Corpse::new(Some(dead_entity), &monster_name, monster_stats.max_hp, undead.contains(dead_entity));
+                self.leave_corpse(remains, pos, drops, &entities, &lazy);
             }
             
             // Create combat reward summary
@@ -97,6 +132,26 @@ impl<'a> System<'a> for CombatRewardsSystem {
             combat_rewards.insert(dead_entity, reward)
                 .expect("Failed to insert combat reward");
         }
+
+        // Word gets around when the player cuts down another adventurer
+        let slain_rivals: Vec<(Entity, String)> = (&entities, &combat_stats, &rivals, &names, !&combat_rewards).join()
+            .filter(|(entity, stats, _, _, _)| {
+                stats.hp <= 0 && last_attackers.get(*entity)
+                    .map_or(false, |killer| players.contains(kill_credit(killer.entity, &companions)))
+            })
+            .map(|(entity, _, _, name, _)| (entity, name.name.clone()))
+            .collect();
+        for (rival, name) in slain_rivals {
+            reputation.record(Conduct::SlewRival);
+            let reward = CombatReward {
+                source_entity: rival,
+                source_name: name,
+                experience_gained: 0,
+                loot_generated: false,
+                special_drops: false,
+            };
+            combat_rewards.insert(rival, reward).expect("Failed to insert combat reward");
+        }
     }
 }
 
@@ -167,16 +222,13 @@ impl CombatRewardsSystem {
     
     fn generate_loot(
         &self,
-        dead_entity: Entity,
         monster_name: &str,
         monster_stats: &CombatStats,
-        position: Position,
         loot_table: Option<LootTable>,
         is_unique: bool,
-        entities: &Entities,
-        gamelog: &mut GameLog,
+        elite_affixes: usize,
         rng: &mut RandomNumberGenerator,
-    ) {
+    ) -> Vec<LootDrop> {
         let mut items_dropped = Vec::new();
         
         // Use custom loot table if available, otherwise generate based on monster stats
@@ -191,14 +243,48 @@ impl CombatRewardsSystem {
             items_dropped.extend(self.generate_unique_loot(monster_name, monster_stats, rng));
         }
         
-        // Create item entities and place them at the monster's position
-        for loot_drop in items_dropped {
-            self.create_loot_item(loot_drop, position, entities, gamelog);
+        // Elites carry a little extra for every affix they rolled
+        for _ in 0..elite_affixes {
+            items_dropped.extend(self.generate_default_loot(monster_stats, false, rng));
+            items_dropped.push(LootDrop::Currency {
+                amount: 10 + rng.roll_dice(2, 10),
+            });
         }
         
-        if !items_dropped.is_empty() {
-            gamelog.add_entry(format!("{} drops {} item(s)!", monster_name, items_dropped.len()));
+        items_dropped
+    }
+    
+    /// Drop a corpse that can be searched for the monster's loot
+    fn leave_corpse(
+        &self,
+        remains: Corpse,
+        position: Position,
+        drops: Vec<LootDrop>,
+        entities: &Entities,
+        lazy: &LazyUpdate,
+    ) {
+        let mut loot_table = LootTable::new();
+        for drop in drops {
+            loot_table.add_entry(drop, 100);
         }
+        
+        lazy.create_entity(entities)
+            .with(Name { name: format!("{} corpse", remains.name) })
+            .with(position)
+            .with(Renderable {
+                glyph: TreasureType::Corpse.get_glyph(),
+                fg: TreasureType::Corpse.get_color(),
+                bg: Color::Black,
+                render_order: 0,
+            })
+            .with(Treasure {
+                treasure_type: TreasureType::Corpse,
+                loot_table,
+                is_opened: false,
+                requires_key: false,
+            })
+            .with(remains)
+            .build();
     }
     
     fn calculate_base_experience(&self, monster_stats: &CombatStats, is_unique: bool) -> i32 {
@@ -384,76 +470,4 @@ impl CombatRewardsSystem {
             defense_bonus,
         }
     }
-    
-    fn create_loot_item(
-        &self,
-        loot_drop: LootDrop,
-        position: Position,
-        entities: &Entities,
-        gamelog: &mut GameLog,
-    ) {
-        match loot_drop {
-            LootDrop::Equipment { name, slot, power_bonus, defense_bonus } => {
-                let mut item_builder = entities.create()
-                    .with(Item {})
-                    .with(Name { name: name.clone() })
-                    .with(Position { x: position.x, y: position.y })
-                    .with(Renderable {
-                        glyph: self.get_equipment_glyph(&slot),
-                        fg: Color::Cyan,
-                        bg: Color::Black,
-                        render_order: 2,
-                    })
-                    .with(Equippable { slot });
-                
-                if power_bonus > 0 {
-                    item_builder = item_builder.with(MeleePowerBonus { power: power_bonus });
-                }
-                
-                if defense_bonus > 0 {
-                    item_builder = item_builder.with(DefenseBonus { defense: defense_bonus });
-                }
-                
-                item_builder.build();
-                gamelog.add_entry(format!("A {} appears!", name));
-            },
-            
-            LootDrop::Consumable { name, healing } => {
-                entities.create()
-                    .with(Item {})
-                    .with(Name { name: name.clone() })
-                    .with(Position { x: position.x, y: position.y })
-                    .with(Renderable {
-                        glyph: '!',
-                        fg: Color::Magenta,
-                        bg: Color::Black,
-                        render_order: 2,
-                    })
-                    .with(ProvidesHealing { heal_amount: healing })
-                    .build();
-                
-                gamelog.add_entry(format!("A {} appears!", name));
-            },
-            
-            LootDrop::Currency { amount } => {
-                // For now, just log the currency drop
-                // In a full implementation, this would create a currency item or add to player inventory
-                gamelog.add_entry(format!("{} gold coins scatter on the ground!", amount));
-            },
-        }
-    }
-    
-    fn get_equipment_glyph(&self, slot: &EquipmentSlot) -> char {
-        match slot {
-            EquipmentSlot::Melee => '/',
-            EquipmentSlot::Shield => '(',
-            EquipmentSlot::Armor => '[',
-            EquipmentSlot::Helmet => '^',
-            EquipmentSlot::Boots => 'b',
-            EquipmentSlot::Gloves => 'g',
-            EquipmentSlot::Ring => '=',
-            EquipmentSlot::Amulet => '"',
-            EquipmentSlot::Ranged => '}',
-        }
-    }
-}
\ No newline at end of file
+}
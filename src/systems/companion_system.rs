@@ -0,0 +1,279 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect};
+use crate::components::{
+    Companion, WantsToSummon, WantsToDismissCompanion, WantsToMove, WantsToAttack,
+    Position, Renderable, Name, CombatStats, BlocksTile, Monster, Experience
+};
+use crate::map::Map;
+use crate::resources::{GameLog, GameStateResource};
+use crate::utils::chebyshev;
+
+/// Most companions a single owner can have active at once
+pub const MAX_ACTIVE_COMPANIONS: usize = 2;
+/// Fraction of the experience from a companion's kill that the companion keeps
+pub const COMPANION_XP_SHARE: f32 = 0.25;
+/// How far from its owner a companion will go looking for enemies
+const ASSIST_RANGE: i32 = 6;
+
+/// The entity that should be credited for a kill made by `attacker`
+pub fn kill_credit(attacker: Entity, companions: &ReadStorage<Companion>) -> Entity {
+    companions.get(attacker).map_or(attacker, |companion| companion.owner)
+}
+
+fn step_toward(from: &Position, to: &Position) -> (i32, i32) {
+    (from.x + (to.x - from.x).signum(), from.y + (to.y - from.y).signum())
+}
+
+/// Spawns companions next to their summoner, enforcing the active summon cap
+pub struct SummonSystem {}
+
+impl<'a> System<'a> for SummonSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToSummon>,
+        WriteStorage<'a, Companion>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, Name>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, BlocksTile>,
+        ReadStorage<'a, Experience>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_summon,
+            mut companions,
+            mut positions,
+            mut renderables,
+            mut names,
+            mut combat_stats,
+            mut blockers,
+            experience,
+            map,
+            game_state,
+            mut gamelog,
+        ) = data;
+
+        let mut requests = Vec::new();
+        for (entity, summon) in (&entities, &wants_summon).join() {
+            requests.push((entity, summon.kind));
+        }
+        wants_summon.clear();
+
+        for (owner, kind) in requests {
+            let owner_pos = match positions.get(owner) {
+                Some(pos) => pos.clone(),
+                None => continue,
+            };
+
+            let spawn = map.get_neighbors(owner_pos.x, owner_pos.y).into_iter()
+                .find(|(x, y)| {
+                    !map.is_blocked(*x, *y) && !(&positions).join().any(|pos| pos.x == *x && pos.y == *y)
+                });
+            let (x, y) = match spawn {
+                Some(spot) => spot,
+                None => {
+                    gamelog.add_entry("There is no room for a companion to appear.".to_string());
+                    continue;
+                }
+            };
+
+            // Dismiss the oldest companions to make room under the cap
+            let mut active: Vec<(Entity, u32)> = (&entities, &companions).join()
+                .filter(|(_, companion)| companion.owner == owner)
+                .map(|(entity, companion)| (entity, companion.summoned_turn))
+                .collect();
+            active.sort_by_key(|(_, turn)| *turn);
+            while active.len() >= MAX_ACTIVE_COMPANIONS {
+                let (oldest, _) = active.remove(0);
+                if let Some(name) = names.get(oldest) {
+                    gamelog.add_entry(format!("Your {} departs to make room.", name.name));
+                }
+                entities.delete(oldest).expect("Unable to delete companion");
+            }
+
+            let owner_level = experience.get(owner).map_or(1, |exp| exp.level);
+            let companion = entities.create();
+            positions.insert(companion, Position { x, y }).expect("Unable to insert position");
+            renderables.insert(companion, Renderable {
+                glyph: kind.glyph(),
+                fg: kind.color(),
+                bg: crossterm::style::Color::Black,
+                render_order: 1,
+            }).expect("Unable to insert renderable");
+            names.insert(companion, Name { name: kind.name().to_string() }).expect("Unable to insert name");
+            combat_stats.insert(companion, kind.stats(owner_level)).expect("Unable to insert combat stats");
+            blockers.insert(companion, BlocksTile).expect("Unable to insert blocker");
+            companions.insert(companion, Companion {
+                owner,
+                kind,
+                turns_remaining: kind.default_duration(),
+                summoned_turn: game_state.turn_count,
+                follow_distance: 2,
+            }).expect("Unable to insert companion");
+
+            gamelog.add_entry(format!("A {} answers your call!", kind.name()));
+        }
+    }
+}
+
+/// Handles companion duration, dismissal, death and owner loss
+pub struct CompanionLifecycleSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl CompanionLifecycleSystem {
+    pub fn new() -> Self {
+        CompanionLifecycleSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for CompanionLifecycleSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Companion>,
+        WriteStorage<'a, WantsToDismissCompanion>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Name>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut companions, mut wants_dismiss, combat_stats, names, game_state, mut gamelog) = data;
+
+        let mut departing = Vec::new();
+
+        for dismissal in (&wants_dismiss).join() {
+            if companions.contains(dismissal.companion) {
+                departing.push((dismissal.companion, "is dismissed"));
+            }
+        }
+        wants_dismiss.clear();
+
+        let new_turn = self.last_turn != Some(game_state.turn_count);
+        self.last_turn = Some(game_state.turn_count);
+
+        for (entity, companion) in (&entities, &mut companions).join() {
+            let owner_alive = entities.is_alive(companion.owner)
+                && combat_stats.get(companion.owner).map_or(true, |stats| stats.hp > 0);
+
+            if combat_stats.get(entity).map_or(false, |stats| stats.hp <= 0) {
+                departing.push((entity, "has fallen"));
+            } else if !owner_alive {
+                departing.push((entity, "fades away without its master"));
+            } else if new_turn {
+                if let Some(turns) = companion.turns_remaining.as_mut() {
+                    *turns -= 1;
+                    if *turns <= 0 {
+                        departing.push((entity, "crumbles as the summoning ends"));
+                    }
+                }
+            }
+        }
+
+        for (entity, reason) in departing {
+            if !entities.is_alive(entity) {
+                continue;
+            }
+            let name = names.get(entity).map_or("companion", |n| n.name.as_str());
+            gamelog.add_entry(format!("Your {} {}.", name, reason));
+            entities.delete(entity).expect("Unable to delete companion");
+        }
+    }
+}
+
+/// Companions follow their owner and attack nearby enemies
+pub struct CompanionAISystem {
+    pub last_turn: Option<u32>,
+}
+
+impl CompanionAISystem {
+    pub fn new() -> Self {
+        CompanionAISystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for CompanionAISystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, CombatStats>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, WantsToAttack>,
+        Read<'a, GameStateResource>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, companions, positions, monsters, combat_stats, mut wants_move, mut wants_attack, game_state) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        for (entity, companion, pos) in (&entities, &companions, &positions).join() {
+            let owner_pos = match positions.get(companion.owner) {
+                Some(owner_pos) => owner_pos,
+                None => continue,
+            };
+
+            // Assist: the nearest living enemy close to the owner
+            let target = (&entities, &monsters, &positions).join()
+                .filter(|(monster, _, _)| combat_stats.get(*monster).map_or(false, |stats| stats.hp > 0))
+                .filter(|(_, _, monster_pos)| chebyshev((owner_pos.x, owner_pos.y), (monster_pos.x, monster_pos.y)) <= ASSIST_RANGE)
+                .min_by_key(|(_, _, monster_pos)| chebyshev((pos.x, pos.y), (monster_pos.x, monster_pos.y)));
+
+            if let Some((monster, _, monster_pos)) = target {
+                if chebyshev((pos.x, pos.y), (monster_pos.x, monster_pos.y)) <= 1 {
+                    wants_attack.insert(entity, WantsToAttack { target: monster })
+                        .expect("Unable to insert attack intent");
+                } else {
+                    wants_move.insert(entity, WantsToMove { destination: step_toward(pos, monster_pos) })
+                        .expect("Unable to insert move intent");
+                }
+                continue;
+            }
+
+            // Follow: stay within a few steps of the owner
+            if chebyshev((pos.x, pos.y), (owner_pos.x, owner_pos.y)) > companion.follow_distance {
+                wants_move.insert(entity, WantsToMove { destination: step_toward(pos, owner_pos) })
+                    .expect("Unable to insert move intent");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::CompanionKind;
+
+    #[test]
+    fn test_step_toward_moves_one_tile() {
+        let from = Position { x: 5, y: 5 };
+        assert_eq!(step_toward(&from, &Position { x: 9, y: 2 }), (6, 4));
+        assert_eq!(step_toward(&from, &Position { x: 5, y: 5 }), (5, 5));
+    }
+
+    #[test]
+    fn test_companion_stats_scale_with_owner_level() {
+        let novice = CompanionKind::Wolf.stats(1);
+        let veteran = CompanionKind::Wolf.stats(7);
+        assert!(veteran.max_hp > novice.max_hp);
+        assert!(veteran.power > novice.power);
+        assert_eq!(veteran.hp, veteran.max_hp);
+    }
+
+    #[test]
+    fn test_only_undead_expire() {
+        assert!(CompanionKind::Wolf.default_duration().is_none());
+        assert!(CompanionKind::Skeleton.default_duration().is_some());
+    }
+}
@@ -0,0 +1,217 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, WriteExpect, Builder, LazyUpdate};
+use crate::components::{
+    Corpse, WantsToButcher, WantsToRaiseCorpse, Companion, Position, Renderable, Name,
+    BlocksTile, Undead, Experience, Player, GameSettings, Item
+};
+use crate::items::{ConsumableFactory, FoodType};
+use crate::map::{Map, Decal};
+use crate::resources::{GameLog, GameStateResource};
+use crate::systems::{MAX_ACTIVE_COMPANIONS, DivineDeeds, Deed};
+
+/// Whether two positions are on the same or neighbouring tiles
+fn within_reach(a: &Position, b: &Position) -> bool {
+    (a.x - b.x).abs() <= 1 && (a.y - b.y).abs() <= 1
+}
+
+/// Butchers, raises and decays corpses
+pub struct CorpseSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl CorpseSystem {
+    pub fn new() -> Self {
+        CorpseSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for CorpseSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Corpse>,
+        WriteStorage<'a, WantsToButcher>,
+        WriteStorage<'a, WantsToRaiseCorpse>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Experience>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, GameSettings>,
+        WriteExpect<'a, Map>,
+        Read<'a, LazyUpdate>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, DivineDeeds>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut corpses,
+            mut wants_butcher,
+            mut wants_raise,
+            companions,
+            positions,
+            experience,
+            players,
+            settings,
+            mut map,
+            lazy,
+            game_state,
+            mut gamelog,
+            mut deeds,
+        ) = data;
+        let gore = !(&players, &settings).join().any(|(_, settings)| settings.disable_gore);
+
+        // Butchering carves fresh corpses into raw meat
+        let butcher_requests: Vec<(Entity, Entity)> = (&entities, &wants_butcher).join()
+            .map(|(entity, request)| (entity, request.corpse))
+            .collect();
+        wants_butcher.clear();
+
+        for (butcher, target) in butcher_requests {
+            let corpse_pos = match (positions.get(butcher), positions.get(target)) {
+                (Some(a), Some(b)) if within_reach(a, b) => b.clone(),
+                _ => continue,
+            };
+            let corpse = match corpses.get_mut(target) {
+                Some(corpse) => corpse,
+                None => continue,
+            };
+
+            let portions = corpse.meat_yield();
+            if portions == 0 {
+                if players.contains(butcher) {
+                    gamelog.add_entry(format!("There is nothing edible left on the {} corpse.", corpse.name));
+                }
+                continue;
+            }
+            corpse.butchered = true;
+            deeds.publish(butcher, Deed::Butchered);
+
+            let (name, properties, consumable, mut stack, renderable) = ConsumableFactory::food_components(FoodType::RawMeat);
+            stack.quantity = portions.min(stack.max_stack);
+            lazy.create_entity(&entities)
+                .with(Item)
+                .with(name)
+                .with(properties)
+                .with(consumable)
+                .with(stack)
+                .with(corpse_pos.clone())
+                .with(renderable)
+                .build();
+
+            if gore {
+                map.add_decal(corpse_pos.x, corpse_pos.y, Decal::Blood);
+            }
+            if players.contains(butcher) {
+                gamelog.add_entry(format!("You carve {} portions of meat from the {} corpse.", portions, corpse.name));
+            }
+        }
+
+        // Necromancy raises corpses as undead allies of whoever called them up
+        let raise_requests: Vec<(Entity, Entity)> = (&entities, &wants_raise).join()
+            .map(|(entity, request)| (entity, request.corpse))
+            .collect();
+        wants_raise.clear();
+
+        for (raiser, target) in raise_requests {
+            let corpse = match corpses.get(target) {
+                Some(corpse) if corpse.can_be_raised() => corpse.clone(),
+                Some(corpse) => {
+                    if players.contains(raiser) {
+                        gamelog.add_entry(format!("The {} corpse is too ruined to rise.", corpse.name));
+                    }
+                    continue;
+                },
+                None => continue,
+            };
+            let corpse_pos = match positions.get(target) {
+                Some(pos) => pos.clone(),
+                None => continue,
+            };
+
+            let active = (&companions).join().filter(|companion| companion.owner == raiser).count();
+            if active >= MAX_ACTIVE_COMPANIONS {
+                if players.contains(raiser) {
+                    gamelog.add_entry("You cannot bind any more servants.".to_string());
+                }
+                continue;
+            }
+
+            let kind = corpse.raised_kind();
+            let level = experience.get(raiser).map_or(1, |exp| exp.level);
+            lazy.create_entity(&entities)
+                .with(corpse_pos)
+                .with(Renderable {
+                    glyph: kind.glyph(),
+                    fg: kind.color(),
+                    bg: crossterm::style::Color::Black,
+                    render_order: 1,
+                })
+                .with(Name { name: format!("{} {}", corpse.name, kind.name()) })
+                .with(kind.stats(level))
+                .with(BlocksTile)
+                .with(Undead)
+                .with(Companion {
+                    owner: raiser,
+                    kind,
+                    turns_remaining: kind.default_duration(),
+                    summoned_turn: game_state.turn_count,
+                    follow_distance: 2,
+                })
+                .build();
+
+            entities.delete(target).expect("Unable to delete raised corpse");
+            deeds.publish(raiser, Deed::RaisedDead);
+            gamelog.add_entry(format!("The {} corpse lurches to its feet as a {}!", corpse.name, kind.name().to_lowercase()));
+        }
+
+        // Corpses rot once per turn, leaving their bones behind
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let mut rotted = Vec::new();
+        for (entity, corpse) in (&entities, &mut corpses).join() {
+            corpse.decay_timer -= 1;
+            if corpse.decay_timer <= 0 {
+                rotted.push(entity);
+            }
+        }
+        for entity in rotted {
+            if let (true, Some(pos)) = (gore, positions.get(entity)) {
+                map.add_decal(pos.x, pos.y, Decal::Bones);
+            }
+            entities.delete(entity).expect("Unable to delete decayed corpse");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{CompanionKind, CORPSE_DECAY_TURNS};
+
+    #[test]
+    fn test_meat_yield_scales_with_size() {
+        assert_eq!(Corpse::new(None, "rat", 4, false).meat_yield(), 1);
+        assert_eq!(Corpse::new(None, "ogre", 45, false).meat_yield(), 4);
+        assert_eq!(Corpse::new(None, "dragon", 200, false).meat_yield(), 5);
+        assert_eq!(Corpse::new(None, "skeleton", 30, true).meat_yield(), 0);
+
+        let mut carved = Corpse::new(None, "orc", 20, false);
+        carved.butchered = true;
+        assert_eq!(carved.meat_yield(), 0);
+        assert!(!carved.can_be_raised());
+    }
+
+    #[test]
+    fn test_rotting_corpses_rise_as_skeletons() {
+        let mut corpse = Corpse::new(None, "orc", 20, false);
+        assert_eq!(corpse.raised_kind(), CompanionKind::Zombie);
+
+        corpse.decay_timer = CORPSE_DECAY_TURNS / 4;
+        assert_eq!(corpse.raised_kind(), CompanionKind::Skeleton);
+        assert!(corpse.can_be_raised());
+    }
+}
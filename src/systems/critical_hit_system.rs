@@ -89,14 +89,14 @@ impl CriticalHitSystem {
                 let effect_roll = rng.roll_dice(1, 100);
                 if effect_roll <= 30 { // 30% chance
                     effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Poisoned, // Using as bleeding
+                        effect_type: StatusEffectType::Bleeding,
                         duration: 3,
                         magnitude: 2,
                     });
                     gamelog.add_entry(format!("{} is bleeding from the critical hit!", target_name));
                 } else if effect_roll <= 50 { // 20% chance for stun
                     effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Slow, // Using as stun
+                        effect_type: StatusEffectType::Stunned,
                         duration: 1,
                         magnitude: 3,
                     });
@@ -106,7 +106,7 @@ impl CriticalHitSystem {
             crate::components::DamageType::Fire => {
                 // Fire crits cause burning
                 effects.add_effect(StatusEffect {
-                    effect_type: StatusEffectType::Poisoned, // Using as burning
+                    effect_type: StatusEffectType::Burning,
                     duration: 4,
                     magnitude: 3,
                 });
@@ -124,7 +124,7 @@ impl CriticalHitSystem {
             crate::components::DamageType::Lightning => {
                 // Lightning crits cause paralysis
                 effects.add_effect(StatusEffect {
-                    effect_type: StatusEffectType::Slow, // Using as paralysis
+                    effect_type: StatusEffectType::Paralyzed,
                     duration: 2,
                     magnitude: 4,
                 });
@@ -166,14 +166,14 @@ impl CriticalHitSystem {
                 let effect_roll = rng.roll_dice(1, 2);
                 if effect_roll == 1 {
                     effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Cursed, // Using as confusion
+                        effect_type: StatusEffectType::Confused,
                         duration: 3,
                         magnitude: 1,
                     });
                     gamelog.add_entry(format!("{} is confused by the psychic critical hit!", target_name));
                 } else {
                     effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Slow, // Using as fear
+                        effect_type: StatusEffectType::Feared,
                         duration: 2,
                         magnitude: 2,
                     });
@@ -4,6 +4,7 @@ use crate::components::{
     StatusEffects, StatusEffect, StatusEffectType
 };
 use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::systems::apply_status_effect;
 
 pub struct DamageTypeSystem {}
 
@@ -48,6 +49,7 @@ impl<'a> System<'a> for DamageTypeSystem {
                 target_entity,
                 &damage,
                 &mut status_effects,
+                resistances.get(target_entity),
                 &names,
                 &mut gamelog,
                 &mut rng
@@ -97,100 +99,55 @@ impl DamageTypeSystem {
         target: Entity,
         damage: &DamageInfo,
         status_effects: &mut WriteStorage<StatusEffects>,
+        resistances: Option<&DamageResistances>,
         names: &ReadStorage<Name>,
         gamelog: &mut GameLog,
         rng: &mut RandomNumberGenerator,
     ) {
         let target_name = names.get(target).map_or("Unknown", |n| &n.name);
         
-        // Get or create status effects
-        let effects = if let Some(effects) = status_effects.get_mut(target) {
-            effects
-        } else {
-            status_effects.insert(target, StatusEffects::new())
-                .expect("Failed to insert status effects");
-            status_effects.get_mut(target).unwrap()
-        };
-        
         // Apply damage type specific effects with probability
-        match damage.damage_type {
-            DamageType::Fire => {
-                if rng.roll_dice(1, 100) <= 25 { // 25% chance
-                    effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Poisoned, // Burning
-                        duration: 3,
-                        magnitude: 2,
-                    });
-                    gamelog.add_entry(format!("{} catches fire!", target_name));
-                }
-            },
-            DamageType::Ice => {
-                if rng.roll_dice(1, 100) <= 30 { // 30% chance
-                    effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Slow,
-                        duration: 2,
-                        magnitude: 1,
-                    });
-                    gamelog.add_entry(format!("{} is slowed by the cold!", target_name));
-                }
-            },
-            DamageType::Lightning => {
-                if rng.roll_dice(1, 100) <= 20 { // 20% chance
-                    effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Slow, // Paralysis
-                        duration: 1,
-                        magnitude: 3,
-                    });
-                    gamelog.add_entry(format!("{} is paralyzed by electricity!", target_name));
-                }
-            },
-            DamageType::Poison => {
-                // Poison always applies poison effect
-                effects.add_effect(StatusEffect {
-                    effect_type: StatusEffectType::Poisoned,
-                    duration: 4,
-                    magnitude: 1,
-                });
-                gamelog.add_entry(format!("{} is poisoned!", target_name));
-            },
+        let (chance, effect, message) = match damage.damage_type {
+            DamageType::Fire => (25, StatusEffect::new(StatusEffectType::Burning, 3, 2), "catches fire"),
+            DamageType::Ice => (30, StatusEffect::new(StatusEffectType::Slow, 2, 1), "is slowed by the cold"),
+            DamageType::Lightning => (20, StatusEffect::new(StatusEffectType::Paralyzed, 1, 3), "is paralyzed by electricity"),
+            // Poison always applies poison effect
+            DamageType::Poison => (100, StatusEffect::new(StatusEffectType::Poisoned, 4, 1), "is poisoned"),
+            DamageType::Dark => (35, StatusEffect::new(StatusEffectType::Cursed, 5, 1), "is cursed by dark energy"),
+            DamageType::Psychic => (15, StatusEffect::new(StatusEffectType::Confused, 3, 2), "is confused by psychic energy"),
             DamageType::Holy => {
                 // Holy damage can purify negative effects
-                let mut purified_effects = Vec::new();
-                for effect in &effects.effects {
-                    if !effect.effect_type.is_beneficial() {
-                        purified_effects.push(effect.effect_type);
+                if let Some(effects) = status_effects.get_mut(target) {
+                    let mut purified_effects = Vec::new();
+                    for effect in &effects.effects {
+                        if !effect.effect_type.is_beneficial() {
+                            purified_effects.push(effect.effect_type);
+                        }
+                    }
+                    
+                    for effect_type in purified_effects {
+                        effects.remove_effect(effect_type);
+                        gamelog.add_entry(format!("Holy energy purifies {} of {}!", 
+                            target_name, effect_type.name()));
                     }
                 }
-                
-                for effect_type in purified_effects {
-                    effects.remove_effect(effect_type);
-                    gamelog.add_entry(format!("Holy energy purifies {} of {}!", 
-                        target_name, effect_type.name()));
-                }
-            },
-            DamageType::Dark => {
-                if rng.roll_dice(1, 100) <= 35 { // 35% chance
-                    effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Cursed,
-                        duration: 5,
-                        magnitude: 1,
-                    });
-                    gamelog.add_entry(format!("{} is cursed by dark energy!", target_name));
-                }
-            },
-            DamageType::Psychic => {
-                if rng.roll_dice(1, 100) <= 15 { // 15% chance
-                    effects.add_effect(StatusEffect {
-                        effect_type: StatusEffectType::Cursed, // Confusion
-                        duration: 3,
-                        magnitude: 2,
-                    });
-                    gamelog.add_entry(format!("{} is confused by psychic energy!", target_name));
-                }
+                return;
             },
             DamageType::Physical => {
                 // Physical damage has no special effects by default
+                return;
             },
+        };
+        
+        if chance < 100 && rng.roll_dice(1, 100) > chance {
+            return;
+        }
+        
+        let effect_type = effect.effect_type;
+        if apply_status_effect(target, effect, status_effects, resistances, rng) {
+            gamelog.add_entry(format!("{} {}!", target_name, message));
+        } else {
+            gamelog.add_entry(format!("{} resists being {}!", target_name, effect_type.name().to_lowercase()));
         }
     }
     
@@ -284,6 +241,10 @@ impl<'a> System<'a> for ResistanceManagementSystem {
                             base_resistances.add_resistance(DamageType::Holy, 0.2);
                             base_resistances.add_resistance(DamageType::Dark, -0.1);
                         },
+                        StatusEffectType::Wet => {
+                            base_resistances.add_resistance(DamageType::Fire, 0.2);
+                            base_resistances.add_resistance(DamageType::Lightning, -0.25);
+                        },
                         _ => {}
                     }
                 }
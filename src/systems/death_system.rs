@@ -1,10 +1,11 @@
 use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect, WriteExpect, Component};
 use crate::components::{
     CombatStats, Player, Name, Position, Renderable, Item, Inventory,
-    Dead, DeathCause, Corpse, DeathAnimation, DeathAnimationType
+    Dead, DeathCause, Corpse, DeathAnimation, DeathAnimationType, Undead, GameSettings, CORPSE_DECAY_TURNS
 };
 use crate::resources::{GameLog, RandomNumberGenerator};
-use crate::map::Map;
+use crate::map::{Map, Decal};
+use crate::rendering::{Animation, AnimationQueue};
 
 // System to handle entity death
 pub struct DeathSystem {}
@@ -22,9 +23,12 @@ impl<'a> System<'a> for DeathSystem {
         ReadStorage<'a, Renderable>,
         ReadStorage<'a, Item>,
         WriteStorage<'a, Inventory>,
+        ReadStorage<'a, Undead>,
+        ReadStorage<'a, GameSettings>,
         Write<'a, GameLog>,
         Write<'a, RandomNumberGenerator>,
         WriteExpected<'a, Map>,
+        Write<'a, AnimationQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -40,10 +44,14 @@ impl<'a> System<'a> for DeathSystem {
             renderables,
             items,
             mut inventories,
+            undead,
+            settings,
             mut gamelog,
             mut rng,
             mut map,
+            mut animations,
         ) = data;
+        let gore = !(&players, &settings).join().any(|(_, settings)| settings.disable_gore);
 
         // Find entities that should die
         let mut entities_to_kill = Vec::new();
@@ -55,6 +63,10 @@ impl<'a> System<'a> for DeathSystem {
 
         // Process each death
         for entity in entities_to_kill {
+            // The living leave a pool of blood where they fall
+            if let (true, false, Some(pos)) = (gore, undead.contains(entity), positions.get(entity)) {
+                map.add_decal(pos.x, pos.y, Decal::Blood);
+            }
             self.process_death(
                 entity,
                 &entities,
@@ -70,6 +82,7 @@ impl<'a> System<'a> for DeathSystem {
                 &mut gamelog,
                 &mut rng,
                 &mut map,
+                &mut animations,
             );
         }
 
@@ -86,7 +99,13 @@ impl<'a> System<'a> for DeathSystem {
                 
                 // Create corpse if entity had a position
                 if let Some(pos) = positions.get(entity) {
-                    corpses_to_create.push((pos.clone(), entity));
+                    let remains = Corpse::new(
+                        Some(entity),
+                        names.get(entity).map_or("something", |n| n.name.as_str()),
+                        combat_stats.get(entity).map_or(0, |stats| stats.max_hp),
+                        undead.contains(entity),
+                    );
+                    corpses_to_create.push((pos.clone(), remains));
                 }
             }
         }
@@ -97,19 +116,19 @@ impl<'a> System<'a> for DeathSystem {
         }
 
         // Create corpses
-        for (pos, original_entity) in corpses_to_create {
+        for (pos, remains) in corpses_to_create {
             self.create_corpse(
                 &entities,
                 &mut corpses,
                 &mut renderables,
                 &mut positions,
                 pos,
-                Some(original_entity),
+                remains,
             );
         }
 
         // Handle corpse decay
-        self.handle_corpse_decay(&entities, &mut corpses, &mut gamelog);
+        self.handle_corpse_decay(&entities, &mut corpses, &positions, &mut map, gore, &mut gamelog);
     }
 }
 
@@ -130,6 +149,7 @@ impl DeathSystem {
         gamelog: &mut GameLog,
         rng: &mut RandomNumberGenerator,
         map: &mut Map,
+        animations: &mut AnimationQueue,
     ) {
         // Mark entity as dead
         dead.insert(entity, Dead {
@@ -169,6 +189,14 @@ impl DeathSystem {
         // Start death animation
         if let Some(renderable) = renderables.get(entity) {
             let animation_type = self.choose_death_animation(rng);
+            if let Some(pos) = positions.get(entity) {
+                animations.push(Animation::Death {
+                    pos: (pos.x, pos.y),
+                    glyph: renderable.glyph,
+                    color: renderable.fg,
+                    style: animation_type,
+                });
+            }
             death_animations.insert(entity, DeathAnimation {
                 animation_type,
                 duration: 1.0, // 1 second animation
@@ -253,15 +281,11 @@ impl DeathSystem {
         renderables: &mut WriteStorage<Renderable>,
         positions: &mut WriteStorage<Position>,
         position: Position,
-        original_entity: Option<Entity>,
+        remains: Corpse,
     ) {
         let corpse_entity = entities.create();
         
-        corpses.insert(corpse_entity, Corpse {
-            original_entity,
-            decay_timer: 100, // Corpse lasts 100 turns
-            loot_generated: false,
-        }).expect("Unable to insert corpse");
+        corpses.insert(corpse_entity, remains).expect("Unable to insert corpse");
 
         renderables.insert(corpse_entity, Renderable {
             glyph: '%',
@@ -326,6 +350,9 @@ impl DeathSystem {
         &self,
         entities: &Entities,
         corpses: &mut WriteStorage<Corpse>,
+        positions: &ReadStorage<Position>,
+        map: &mut Map,
+        gore: bool,
         gamelog: &mut GameLog,
     ) {
         let mut to_remove = Vec::new();
@@ -338,8 +365,11 @@ impl DeathSystem {
             }
         }
 
-        // Remove decayed corpses
+        // Remove decayed corpses, leaving their bones behind
         for entity in to_remove {
+            if let (true, Some(pos)) = (gore, positions.get(entity)) {
+                map.add_decal(pos.x, pos.y, Decal::Bones);
+            }
             entities.delete(entity).expect("Unable to delete decayed corpse");
         }
     }
@@ -418,13 +448,9 @@ mod tests {
     
     #[test]
     fn test_corpse_decay_timer() {
-        let corpse = Corpse {
-            original_entity: None,
-            decay_timer: 100,
-            loot_generated: false,
-        };
+        let corpse = Corpse::new(None, "goblin", 12, false);
         
-        assert_eq!(corpse.decay_timer, 100);
+        assert_eq!(corpse.decay_timer, CORPSE_DECAY_TURNS);
         assert!(!corpse.loot_generated);
     }
     
@@ -79,7 +79,7 @@ mod tests {
             &mut renderables,
             &mut positions,
             Position { x: 3, y: 3 },
-            None,
+            Corpse::new(None, "orc", 20, false),
         );
         
         // Check that a corpse was created
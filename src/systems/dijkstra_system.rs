@@ -0,0 +1,72 @@
+use specs::{System, ReadStorage, Join, Write, Read, ReadExpect};
+use crate::components::{Item, Player, Position};
+use crate::map::{DijkstraMaps, DijkstraSource, Map};
+use crate::resources::GameStateResource;
+
+/// Walkable tiles the player has seen that border tiles they have not
+pub fn unexplored_edge(map: &Map) -> Vec<(i32, i32)> {
+    let mut edge = Vec::new();
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let idx = map.xy_idx(x, y);
+            if !map.revealed_tiles[idx] || map.tiles[idx].blocks_movement() {
+                continue;
+            }
+            let borders_unknown = (-1..=1).any(|dy| (-1..=1).any(|dx| {
+                map.in_bounds(x + dx, y + dy) && !map.revealed_tiles[map.xy_idx(x + dx, y + dy)]
+            }));
+            if borders_unknown {
+                edge.push((x, y));
+            }
+        }
+    }
+    edge
+}
+
+/// Brings the shared Dijkstra maps up to date once per turn, rebuilding only those whose level
+/// layout or sources have changed
+pub struct DijkstraMapSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl DijkstraMapSystem {
+    pub fn new() -> Self {
+        DijkstraMapSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for DijkstraMapSystem {
+    type SystemData = (
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, DijkstraMaps>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (players, items, positions, map, game_state, mut maps) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        maps.set_layout(&map);
+
+        let players_at: Vec<(i32, i32)> = (&positions, &players).join().map(|(pos, _)| (pos.x, pos.y)).collect();
+        // Safety is only worth redoing when the player has moved
+        if maps.update(DijkstraSource::Player, &map, players_at) || maps.get(DijkstraSource::Safety).is_none() {
+            maps.update_safety(&map);
+        }
+        maps.update(DijkstraSource::Stairs, &map, vec![map.entrance, map.exit]);
+
+        let items_at: Vec<(i32, i32)> = (&positions, &items).join()
+            .map(|(pos, _)| (pos.x, pos.y))
+            .filter(|&(x, y)| map.in_bounds(x, y) && map.revealed_tiles[map.xy_idx(x, y)])
+            .collect();
+        maps.update(DijkstraSource::Items, &map, items_at);
+        maps.update(DijkstraSource::Unexplored, &map, unexplored_edge(&map));
+    }
+}
@@ -0,0 +1,245 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, WriteExpect};
+use crate::components::{
+    Attributes, AttributeType, DoorAction, Name, Player, Position, Viewshed, WantsToOperateDoor
+};
+use crate::items::{AdvancedInventory, ItemProperties, ItemType, ToolType};
+use crate::localization::Message;
+use crate::map::{DoorLock, Map, DOOR_BASH_DIFFICULTY};
+use crate::resources::{GameLog, RandomNumberGenerator};
+use super::monster_ai_system::{NoiseEvents, BASH_NOISE};
+
+/// Opens, closes, bashes and jams the doors next to whoever asked. A locked door opens to anyone
+/// carrying a key, which is used up in the lock. Only the player is told how it went, along with
+/// anything seen opening a door in view
+pub struct DoorSystem;
+
+impl<'a> System<'a> for DoorSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToOperateDoor>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, ItemProperties>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, Map>,
+        Write<'a, NoiseEvents>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_door,
+            positions,
+            players,
+            attributes,
+            names,
+            properties,
+            mut inventories,
+            mut viewsheds,
+            mut map,
+            mut noises,
+            mut rng,
+            mut gamelog,
+        ) = data;
+
+        let mut changed = false;
+        for (entity, intent, pos) in (&entities, &wants_door, &positions).join() {
+            let (x, y) = intent.target;
+            let adjacent = (x - pos.x).abs() <= 1 && (y - pos.y).abs() <= 1 && (x, y) != (pos.x, pos.y);
+            if !adjacent || !(map.is_closed_door(x, y) || map.is_open_door(x, y)) {
+                continue;
+            }
+            let is_player = players.contains(entity);
+
+            let message = match intent.action {
+                DoorAction::Open if !map.is_closed_door(x, y) => None,
+                DoorAction::Open => match map.door_lock(x, y) {
+                    None => {
+                        map.set_door(x, y, true);
+                        changed = true;
+                        if is_player {
+                            Some(Message::new("log.door_opened"))
+                        } else if map.is_visible(x, y) {
+                            names.get(entity).map(|name| Message::new("log.door_opened_by").arg("name", &name.name))
+                        } else {
+                            None
+                        }
+                    },
+                    // A wedge comes out by hand, but only a hand that knows to look for it
+                    Some(DoorLock::Jammed) if is_player => {
+                        map.set_door_lock(x, y, None);
+                        Some(Message::new("log.wedge_pulled"))
+                    },
+                    Some(DoorLock::Locked) => match carried_key(inventories.get(entity), &properties) {
+                        Some(key) => {
+                            if let Some(inventory) = inventories.get_mut(entity) {
+                                let slot = inventory.find_item(key);
+                                let weight = properties.get(key).map_or(0.0, |props| props.weight);
+                                if let Some((key, _)) = slot.and_then(|slot| inventory.remove_item(slot, 1, weight)) {
+                                    if inventory.find_item(key).is_none() {
+                                        entities.delete(key).expect("Unable to delete used key");
+                                    }
+                                }
+                            }
+                            map.set_door(x, y, true);
+                            changed = true;
+                            if is_player { Some(Message::new("log.door_unlocked")) } else { None }
+                        },
+                        None => Some(Message::new("log.door_locked")),
+                    },
+                    Some(_) => Some(Message::new("log.door_stuck")),
+                },
+                DoorAction::Close if !map.is_open_door(x, y) => None,
+                DoorAction::Close => {
+                    if (&positions).join().any(|other| (other.x, other.y) == (x, y)) {
+                        Some(Message::new("log.doorway_blocked"))
+                    } else {
+                        map.set_door(x, y, false);
+                        changed = true;
+                        Some(Message::new("log.door_closed"))
+                    }
+                },
+                DoorAction::Bash if !map.is_closed_door(x, y) => None,
+                DoorAction::Bash => {
+                    let difficulty = map.door_lock(x, y).map_or(DOOR_BASH_DIFFICULTY, |lock| lock.bash_difficulty());
+                    let strength = attributes.get(entity).map_or(0, |stats| stats.get_modifier(AttributeType::Strength));
+                    noises.emit((x, y), BASH_NOISE);
+                    if rng.roll_dice(1, 20) + strength >= difficulty {
+                        map.set_door(x, y, true);
+                        changed = true;
+                        Some(Message::new("log.door_bashed_open"))
+                    } else {
+                        Some(Message::new("log.door_holds"))
+                    }
+                },
+                DoorAction::Jam if !map.is_closed_door(x, y) => Some(Message::new("log.door_must_be_closed")),
+                DoorAction::Jam => match map.door_lock(x, y) {
+                    None => {
+                        map.set_door_lock(x, y, Some(DoorLock::Jammed));
+                        Some(Message::new("log.door_jammed"))
+                    },
+                    Some(_) => Some(Message::new("log.door_already_held")),
+                },
+            };
+
+            // Monsters' failures are their own business; only a door seen swinging open is news
+            let shown = is_player || (intent.action == DoorAction::Open && map.is_open_door(x, y));
+            if let (true, Some(message)) = (shown, message) {
+                gamelog.add_message(message);
+            }
+        }
+        wants_door.clear();
+
+        // A door swinging open or shut changes sight lines for everyone
+        if changed {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+    }
+}
+
+/// A key somewhere in the pack
+fn carried_key(inventory: Option<&AdvancedInventory>, properties: &ReadStorage<ItemProperties>) -> Option<Entity> {
+    inventory?.items.iter()
+        .map(|slot| slot.entity)
+        .find(|item| properties.get(*item).map_or(false, |props| props.item_type == ItemType::Tool(ToolType::Key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::map::TileType;
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<ItemProperties>();
+        world.insert(NoiseEvents::default());
+        world.insert(RandomNumberGenerator::new(3));
+        world.insert(GameLog::new(10));
+        let mut map = Map::new(10, 10, 1);
+        for x in 1..9 {
+            map.set_tile(x, 5, TileType::Floor);
+        }
+        map.set_tile(5, 5, TileType::Door(false));
+        world.insert(map);
+        world
+    }
+
+    fn operate(world: &mut World, entity: specs::Entity, action: DoorAction) {
+        world.write_storage::<WantsToOperateDoor>()
+            .insert(entity, WantsToOperateDoor { target: (5, 5), action })
+            .expect("Unable to insert door intent");
+        DoorSystem.run_now(world);
+        world.maintain();
+    }
+
+    #[test]
+    fn test_doors_open_and_close_but_not_on_someone_standing_in_them() {
+        let mut world = world();
+        let player = world.create_entity().with(Player).with(Position { x: 4, y: 5 }).build();
+
+        operate(&mut world, player, DoorAction::Open);
+        assert!(world.fetch::<Map>().is_open_door(5, 5));
+
+        let rat = world.create_entity().with(Position { x: 5, y: 5 }).build();
+        operate(&mut world, player, DoorAction::Close);
+        assert!(world.fetch::<Map>().is_open_door(5, 5));
+
+        world.delete_entity(rat).unwrap();
+        operate(&mut world, player, DoorAction::Close);
+        assert!(world.fetch::<Map>().is_closed_door(5, 5));
+    }
+
+    #[test]
+    fn test_a_jammed_door_keeps_monsters_out_until_bashed_open() {
+        let mut world = world();
+        let player = world.create_entity()
+            .with(Player)
+            .with(Position { x: 4, y: 5 })
+            .with(Attributes::from_scores([30, 10, 10, 10, 10, 10]))
+            .build();
+        let goblin = world.create_entity().with(Position { x: 6, y: 5 }).build();
+
+        operate(&mut world, player, DoorAction::Jam);
+        operate(&mut world, goblin, DoorAction::Open);
+        assert_eq!(world.fetch::<Map>().door_lock(5, 5), Some(DoorLock::Jammed));
+
+        // Strength 30 adds 10 to the roll, more than enough for a wedged door
+        operate(&mut world, player, DoorAction::Bash);
+        let map = world.fetch::<Map>();
+        assert!(map.is_open_door(5, 5));
+        assert_eq!(map.door_lock(5, 5), None);
+        assert!(world.read_resource::<NoiseEvents>().noises.iter().any(|noise| noise.loudness == BASH_NOISE));
+    }
+
+    #[test]
+    fn test_a_key_opens_a_locked_door_and_is_used_up() {
+        let mut world = world();
+        world.write_resource::<Map>().set_door_lock(5, 5, Some(DoorLock::Locked));
+        let player = world.create_entity().with(Player).with(Position { x: 4, y: 5 }).with(AdvancedInventory::new(26, 100.0)).build();
+
+        operate(&mut world, player, DoorAction::Open);
+        assert!(world.fetch::<Map>().is_closed_door(5, 5));
+
+        let key = world.create_entity()
+            .with(ItemProperties::new("Iron Key".to_string(), ItemType::Tool(ToolType::Key)))
+            .build();
+        world.write_storage::<AdvancedInventory>().get_mut(player).unwrap()
+            .items.push(crate::items::InventorySlot::new(key, 1));
+        operate(&mut world, player, DoorAction::Open);
+
+        assert!(world.fetch::<Map>().is_open_door(5, 5));
+        assert_eq!(world.fetch::<Map>().door_lock(5, 5), None);
+        assert!(!world.is_alive(key));
+        assert!(world.read_storage::<AdvancedInventory>().get(player).unwrap().items.is_empty());
+    }
+}
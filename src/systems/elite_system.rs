@@ -0,0 +1,113 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect, Builder, LazyUpdate};
+use crate::components::{
+    Elite, EliteAffix, CombatStats, Position, Renderable, Name, Monster, Viewshed, BlocksTile
+};
+use crate::map::Map;
+use crate::resources::GameLog;
+
+/// Splits dying Splitting elites into two lesser copies of themselves
+pub struct EliteSplitSystem {}
+
+impl<'a> System<'a> for EliteSplitSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Elite>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, BlocksTile>,
+        ReadExpect<'a, Map>,
+        Read<'a, LazyUpdate>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut elites, combat_stats, positions, renderables, names, monsters, blockers, map, lazy, mut gamelog) = data;
+
+        let splitting: Vec<Entity> = (&entities, &elites, &combat_stats, &monsters).join()
+            .filter(|(_, elite, stats, _)| stats.hp <= 0 && elite.has(EliteAffix::Splitting))
+            .map(|(entity, _, _, _)| entity)
+            .collect();
+
+        let mut occupied: Vec<(i32, i32)> = (&positions, &blockers, &combat_stats).join()
+            .filter(|(_, _, stats)| stats.hp > 0)
+            .map(|(pos, _, _)| (pos.x, pos.y))
+            .collect();
+
+        for entity in splitting {
+            // Each elite only splits once, however long its body lingers
+            if let Some(elite) = elites.get_mut(entity) {
+                elite.affixes.retain(|affix| *affix != EliteAffix::Splitting);
+            }
+            let (pos, stats, renderable) = match (positions.get(entity), combat_stats.get(entity), renderables.get(entity)) {
+                (Some(pos), Some(stats), Some(renderable)) => (pos.clone(), stats.clone(), renderable.clone()),
+                _ => continue,
+            };
+            let name = names.get(entity).map_or("Something".to_string(), |n| n.name.clone());
+
+            let spots: Vec<(i32, i32)> = map.get_neighbors(pos.x, pos.y).into_iter()
+                .filter(|(x, y)| !map.is_blocked(*x, *y) && !occupied.contains(&(*x, *y)))
+                .take(2)
+                .collect();
+            if spots.is_empty() {
+                continue;
+            }
+
+            let half_hp = i32::max(1, stats.max_hp / 2);
+            for (x, y) in spots {
+                occupied.push((x, y));
+                lazy.create_entity(&entities)
+                    .with(Position { x, y })
+                    .with(Renderable { render_order: 1, ..renderable.clone() })
+                    .with(Viewshed {
+                        visible_tiles: Vec::new(),
+                        range: 6,
+                        dirty: true,
+                    })
+                    .with(Name { name: format!("Lesser {}", name) })
+                    .with(BlocksTile {})
+                    .with(CombatStats {
+                        max_hp: half_hp,
+                        hp: half_hp,
+                        defense: stats.defense,
+                        power: i32::max(1, stats.power - 1),
+                    })
+                    .with(Monster {})
+                    .build();
+            }
+            gamelog.add_entry(format!("The {} splits in two!", name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::RandomNumberGenerator;
+
+    #[test]
+    fn test_elite_chance_scales_with_depth() {
+        assert!(EliteAffix::elite_chance(1) < EliteAffix::elite_chance(6));
+        assert_eq!(EliteAffix::elite_chance(50), 40);
+    }
+
+    #[test]
+    fn test_rolled_affixes_are_distinct() {
+        let mut rng = RandomNumberGenerator::new();
+        for _ in 0..200 {
+            let affixes = EliteAffix::roll(20, &mut rng);
+            assert!(affixes.len() <= 2);
+            if affixes.len() == 2 {
+                assert_ne!(affixes[0], affixes[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decorated_elite_name() {
+        let elite = Elite { affixes: vec![EliteAffix::Fast, EliteAffix::Vampiric] };
+        assert_eq!(elite.decorate("Orc"), "Orc (Fast, Vampiric)");
+    }
+}
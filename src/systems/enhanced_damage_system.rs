@@ -1,6 +1,13 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
-use crate::components::{DamageInfo, CombatStats, DamageResistances, Player, Name, StatusEffects, StatusEffect, StatusEffectType};
-use crate::resources::GameLog;
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, WriteExpect};
+use crate::components::{
+    DamageInfo, DamageType, CombatStats, DamageResistances, Player, Name, StatusEffects, StatusEffect, StatusEffectType,
+    LastAttacker, Position, Undead, GameSettings, Elite, EliteAffix
+};
+use crate::map::{Map, Decal};
+use crate::resources::{GameLog, RandomNumberGenerator};
+
+/// Hits at least this hard spray blood onto a neighbouring tile as well
+const HEAVY_HIT_DAMAGE: i32 = 10;
 
 pub struct EnhancedDamageSystem {}
 
@@ -11,13 +18,24 @@ impl<'a> System<'a> for EnhancedDamageSystem {
         WriteStorage<'a, DamageInfo>,
         ReadStorage<'a, DamageResistances>,
         WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, LastAttacker>,
         ReadStorage<'a, Player>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Undead>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, Elite>,
+        WriteExpect<'a, Map>,
         Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut combat_stats, mut damage_info, resistances, mut status_effects, player, names, mut gamelog) = data;
+        let (
+            entities, mut combat_stats, mut damage_info, resistances, mut status_effects, mut last_attackers,
+            player, names, positions, undead, settings, elites, mut map, mut gamelog, mut rng,
+        ) = data;
+        let gore = !(&player, &settings).join().any(|(_, settings)| settings.disable_gore);
 
         // Process all damage
         let mut damage_to_apply = Vec::new();
@@ -30,6 +48,7 @@ impl<'a> System<'a> for EnhancedDamageSystem {
         damage_info.clear();
         
         // Apply damage
+        let mut lifesteal = Vec::new();
         for (entity, damage) in damage_to_apply {
             if let Some(stats) = combat_stats.get_mut(entity) {
                 let mut final_damage = damage.base_damage;
@@ -42,6 +61,15 @@ impl<'a> System<'a> for EnhancedDamageSystem {
                 // Apply damage
                 stats.hp -= final_damage;
                 
+                // Vampiric elites drink a share of what they deal
+                if final_damage > 0 && elites.get(damage.source).map_or(false, |elite| elite.has(EliteAffix::Vampiric)) {
+                    lifesteal.push((damage.source, i32::max(1, final_damage / 2)));
+                }
+                
+                // Remember who landed the hit for kill credit
+                last_attackers.insert(entity, LastAttacker { entity: damage.source })
+                    .expect("Unable to insert last attacker");
+                
                 // Log damage for player
                 if player.contains(entity) {
                     let damage_desc = if damage.is_critical {
@@ -59,13 +87,30 @@ impl<'a> System<'a> for EnhancedDamageSystem {
                     gamelog.add_entry(damage_desc);
                 }
                 
+                // Mark the floor where the hit landed
+                if let (Some(pos), true) = (positions.get(entity), final_damage > 0) {
+                    match damage.damage_type {
+                        DamageType::Fire => {
+                            map.add_decal(pos.x, pos.y, Decal::Scorch);
+                        },
+                        _ if gore && !undead.contains(entity) => {
+                            map.add_decal(pos.x, pos.y, Decal::Blood);
+                            if final_damage >= HEAVY_HIT_DAMAGE || damage.is_critical {
+                                let (dx, dy) = (rng.range(-1, 1), rng.range(-1, 1));
+                                map.add_decal(pos.x + dx, pos.y + dy, Decal::Blood);
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+                
                 // Apply special damage type effects
                 if let Some(effects) = status_effects.get_mut(entity) {
                     match damage.damage_type {
                         crate::components::DamageType::Fire => {
                             // Chance to apply burning
                             effects.add_effect(StatusEffect {
-                                effect_type: StatusEffectType::Poisoned, // Using poison as burning for now
+                                effect_type: StatusEffectType::Burning,
                                 duration: 3,
                                 magnitude: 2,
                             });
@@ -79,15 +124,15 @@ impl<'a> System<'a> for EnhancedDamageSystem {
                             });
                         },
                         crate::components::DamageType::Lightning => {
-                            // Chance to stun (using slow as stun)
+                            // Chance to stun
                             effects.add_effect(StatusEffect {
-                                effect_type: StatusEffectType::Slow,
+                                effect_type: StatusEffectType::Stunned,
                                 duration: 1,
                                 magnitude: 2,
                             });
                         },
                         crate::components::DamageType::Poison => {
-                            // Apply poison
+                            // Poison stacks with repeated exposure
                             effects.add_effect(StatusEffect {
                                 effect_type: StatusEffectType::Poisoned,
                                 duration: 5,
@@ -122,5 +167,16 @@ impl<'a> System<'a> for EnhancedDamageSystem {
                 }
             }
         }
+        
+        for (drinker, amount) in lifesteal {
+            if let Some(stats) = combat_stats.get_mut(drinker) {
+                if stats.hp > 0 {
+                    stats.hp = i32::min(stats.max_hp, stats.hp + amount);
+                    if let Some(name) = names.get(drinker) {
+                        gamelog.add_entry(format!("{} drinks the spilled blood and recovers {} HP.", name.name, amount));
+                    }
+                }
+            }
+        }
     }
 }
\ No newline at end of file
@@ -4,6 +4,7 @@ use crate::components::{
     Inventory, CombatStats, EquipmentSlot
 };
 use crate::resources::GameLog;
+use crate::ui::{TutorialEvents, TutorialTrigger};
 
 pub struct EquipmentSystem {}
 
@@ -18,6 +19,7 @@ impl<'a> System<'a> for EquipmentSystem {
         ReadStorage<'a, DefenseBonus>,
         WriteStorage<'a, Inventory>,
         Write<'a, GameLog>,
+        Write<'a, TutorialEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -30,7 +32,8 @@ impl<'a> System<'a> for EquipmentSystem {
             melee_power_bonuses,
             defense_bonuses,
             mut inventories,
-            mut gamelog
+            mut gamelog,
+            mut tutorials
         ) = data;
 
         // Process equip/unequip requests
@@ -78,6 +81,7 @@ impl<'a> System<'a> for EquipmentSystem {
                     equipped.insert(use_item.item, Equipped { owner: entity, slot: can_equip.slot })
                         .expect("Failed to equip item");
                     gamelog.add_entry(format!("You equip the {}.", item_name));
+                    tutorials.publish(TutorialTrigger::EquipItem);
                 }
                 
                 to_equip.push(entity);
@@ -1,6 +1,7 @@
 use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
-use crate::components::{Experience, CombatStats, Player, Monster, Name};
+use crate::components::{Experience, CombatStats, Player, Monster, Name, Companion, LastAttacker};
 use crate::resources::GameLog;
+use crate::systems::{kill_credit, COMPANION_XP_SHARE};
 
 pub struct ExperienceGainSystem {}
 
@@ -12,28 +13,46 @@ impl<'a> System<'a> for ExperienceGainSystem {
         ReadStorage<'a, Player>,
         ReadStorage<'a, Monster>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, LastAttacker>,
         Write<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut experience, combat_stats, players, monsters, names, mut gamelog) = data;
+        let (entities, mut experience, combat_stats, players, monsters, names, companions, last_attackers, mut gamelog) = data;
 
         // Find dead monsters and award experience to players
         let mut dead_monsters = Vec::new();
         
         for (entity, stats, _monster, name) in (&entities, &combat_stats, &monsters, &names).join() {
             if stats.hp <= 0 {
-                dead_monsters.push((entity, name.name.clone(), stats.max_hp));
+                let killer = last_attackers.get(entity).map(|attacker| attacker.entity);
+                dead_monsters.push((entity, name.name.clone(), stats.max_hp, killer));
             }
         }
         
-        // Award experience to all players for each dead monster
-        for (dead_entity, monster_name, monster_max_hp) in dead_monsters {
+        // Award experience for each dead monster
+        for (dead_entity, monster_name, monster_max_hp, killer) in dead_monsters {
             // Calculate experience based on monster's max HP and level
-            let base_exp = monster_max_hp * 2; // 2 XP per HP point
+            let mut base_exp = monster_max_hp * 2; // 2 XP per HP point
             
-            // Award experience to all players
+            // Kills made by a companion are credited to its owner, minus the companion's share
+            let credited = killer.map(|killer| kill_credit(killer, &companions));
+            if let Some(companion_entity) = killer.filter(|killer| companions.contains(*killer)) {
+                let share = (base_exp as f32 * COMPANION_XP_SHARE) as i32;
+                if let Some(companion_exp) = experience.get_mut(companion_entity) {
+                    companion_exp.gain_exp(share);
+                    base_exp -= share;
+                }
+            }
+            
+            // Award experience to the credited player, or to all players if no player landed the kill
+            let credited_player = credited.filter(|entity| players.contains(*entity));
             for (player_entity, mut exp, _player) in (&entities, &mut experience, &players).join() {
+                if credited_player.map_or(false, |credited| credited != player_entity) {
+                    continue;
+                }
+                
                 // Scale experience based on level difference (simple version)
                 let scaled_exp = if exp.level > 1 {
                     std::cmp::max(1, base_exp - (exp.level - 1) * 2)
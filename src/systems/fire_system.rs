@@ -0,0 +1,166 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Join, Write, WriteExpect, Read};
+use crate::components::{
+    CombatStats, DamageResistances, Player, Position, StatusEffect, StatusEffectType, StatusEffects, SufferDamage, Viewshed
+};
+use crate::localization::Message;
+use crate::map::{Map, TileEffect, TransientTileState, SMOKE_TURNS};
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+use super::status_effect_system::apply_status_effect;
+
+/// Damage dealt each turn to anything standing in flames
+pub const BURNING_TILE_DAMAGE: i32 = 2;
+/// Percent chance per turn for fire to spread to each flammable neighbor
+const FIRE_SPREAD_CHANCE: i32 = 30;
+/// Percent chance per turn for a fire to push smoke onto a neighboring tile
+const SMOKE_DRIFT_CHANCE: i32 = 40;
+/// How long the flames cling on after stepping out of the fire
+const CATCH_FIRE_TURNS: i32 = 2;
+
+/// Once a turn, lets the level's fires burn: whatever stands in the flames is hurt and may catch
+/// fire itself, fire creeps into neighboring grass, wood and webs, smoke drifts off and blocks
+/// sight, and spent fires leave scorched floor behind. Every other passing tile state runs down
+/// on the same clock, so ice thaws here too
+pub struct FireSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl FireSystem {
+    pub fn new() -> Self {
+        FireSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for FireSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, DamageResistances>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            positions,
+            players,
+            combat_stats,
+            resistances,
+            mut status_effects,
+            mut suffer_damage,
+            mut viewsheds,
+            mut map,
+            game_state,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let burning: Vec<(i32, i32)> = map.tile_states.iter()
+            .filter(|(_, state)| state.effect == TileEffect::Burning)
+            .map(|(idx, _)| map.idx_xy(*idx))
+            .collect();
+        let smoky = map.tile_states.values().any(|state| state.effect == TileEffect::Smoke);
+
+        // Flames hurt whatever stands in them, and may set it alight
+        for (entity, pos, stats) in (&entities, &positions, &combat_stats).join() {
+            if stats.hp <= 0 || !burning.contains(&(pos.x, pos.y)) {
+                continue;
+            }
+            SufferDamage::new_damage(&mut suffer_damage, entity, BURNING_TILE_DAMAGE);
+            let flames = StatusEffect::new(StatusEffectType::Burning, CATCH_FIRE_TURNS, 1);
+            apply_status_effect(entity, flames, &mut status_effects, resistances.get(entity), &mut rng);
+            if players.contains(entity) {
+                gamelog.add_message(Message::new("log.standing_in_fire").arg("damage", BURNING_TILE_DAMAGE));
+            }
+        }
+
+        for (x, y) in &burning {
+            // Fire creeps into neighboring grass, wood and webs
+            for (nx, ny) in map.get_orthogonal_neighbors(*x, *y) {
+                if map.can_ignite(nx, ny) && rng.roll_dice(1, 100) <= FIRE_SPREAD_CHANCE {
+                    map.ignite(nx, ny);
+                }
+            }
+
+            // Smoke billows off into the open air around it
+            if rng.roll_dice(1, 100) <= SMOKE_DRIFT_CHANCE {
+                let open: Vec<(i32, i32)> = map.get_orthogonal_neighbors(*x, *y).into_iter()
+                    .filter(|(nx, ny)| !map.is_opaque(*nx, *ny) && map.get_tile_state(*nx, *ny).is_none())
+                    .collect();
+                if !open.is_empty() {
+                    let (sx, sy) = open[rng.range(0, open.len() as i32 - 1) as usize];
+                    map.set_tile_state(sx, sy, TransientTileState::new(TileEffect::Smoke, SMOKE_TURNS));
+                }
+            }
+        }
+
+        // Spent fires leave scorched ground and smoke behind; thawed ice reverts on its own
+        for (x, y, effect) in map.tick_tile_states() {
+            if effect == TileEffect::Burning {
+                map.burn_out(x, y);
+                if map.visible_tiles[map.xy_idx(x, y)] {
+                    gamelog.add_message(Message::new("log.fire_burns_out"));
+                }
+            }
+        }
+
+        // Smoke coming or going changes what everyone can see
+        if smoky || !burning.is_empty() {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::map::{Decal, TileType};
+
+    #[test]
+    fn test_fire_burns_those_in_it_and_leaves_scorched_smoke() {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        let mut map = Map::new(10, 10, 1);
+        map.set_tile(4, 4, TileType::Grass);
+        map.ignite(4, 4);
+        world.insert(map);
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(3));
+        let victim = world.create_entity()
+            .with(Position { x: 4, y: 4 })
+            .with(CombatStats { max_hp: 20, hp: 20, defense: 0, power: 1 })
+            .with(StatusEffects::new())
+            .build();
+
+        let mut fire = FireSystem::new();
+        let burn_turns = crate::map::burn_turns(TileType::Grass);
+        for turn in 0..burn_turns as u32 {
+            world.write_resource::<GameStateResource>().turn_count = turn;
+            fire.run_now(&world);
+        }
+
+        let damage = world.read_storage::<SufferDamage>().get(victim).map(|damage| damage.amount);
+        assert_eq!(damage, Some(burn_turns * BURNING_TILE_DAMAGE));
+        assert!(world.read_storage::<StatusEffects>().get(victim).unwrap().has_effect(StatusEffectType::Burning));
+        let map = world.fetch::<Map>();
+        assert_eq!(map.get_tile(4, 4), Some(TileType::Floor));
+        assert_eq!(map.get_decal(4, 4), Some(Decal::Scorch));
+        assert!(map.has_tile_effect(4, 4, TileEffect::Smoke));
+    }
+}
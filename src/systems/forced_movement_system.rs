@@ -0,0 +1,259 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::components::{
+    ForcedMovement, ForcedMovementKind, Position, Viewshed, CombatStats, SufferDamage, DamageInfo,
+    DamageType, DamageResistances, StatusEffect, StatusEffects, StatusEffectType, Name, Player, MovementMode
+};
+use crate::map::{Map, TileType};
+use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::systems::apply_status_effect;
+
+/// Furthest a charge can carry the attacker, in tiles
+pub const CHARGE_RANGE: i32 = 4;
+/// Extra charge damage for every tile run before the impact
+const CHARGE_DAMAGE_PER_TILE: i32 = 2;
+/// Damage dealt when a creature is slammed into lava
+const LAVA_DAMAGE: i32 = 15;
+
+/// What stopped a forced movement short
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collision {
+    /// A wall or other blocking tile
+    Wall,
+    /// A creature standing at this tile
+    Creature(i32, i32),
+}
+
+/// Where a forced movement ends and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForcedPath {
+    pub end: (i32, i32),
+    pub steps: i32,
+    pub collision: Option<Collision>,
+}
+
+/// Walk a forced movement tile by tile, stopping at walls, creatures and hazards. What counts as
+/// either depends on how the creature gets about: a bat is flung straight over a chasm
+pub fn trace_forced_path(
+    map: &Map,
+    occupied: &HashSet<(i32, i32)>,
+    start: (i32, i32),
+    dx: i32,
+    dy: i32,
+    distance: i32,
+    mode: MovementMode,
+) -> ForcedPath {
+    let mut end = start;
+    let mut steps = 0;
+    let mut collision = None;
+
+    while steps < distance && (dx != 0 || dy != 0) {
+        let next = (end.0 + dx, end.1 + dy);
+        if !map.can_enter(next.0, next.1, mode) {
+            collision = Some(Collision::Wall);
+            break;
+        }
+        if occupied.contains(&next) {
+            collision = Some(Collision::Creature(next.0, next.1));
+            break;
+        }
+
+        end = next;
+        steps += 1;
+
+        // Nobody keeps moving once they land in lava or fall into the void
+        if map.is_dangerous_for(end.0, end.1, mode) {
+            break;
+        }
+    }
+
+    ForcedPath { end, steps, collision }
+}
+
+/// Resolves knockbacks and charges against the map, applying collision and hazard damage
+pub struct ForcedMovementSystem;
+
+impl<'a> System<'a> for ForcedMovementSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, ForcedMovement>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, DamageInfo>,
+        ReadStorage<'a, DamageResistances>,
+        WriteStorage<'a, StatusEffects>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, MovementMode>,
+        ReadExpect<'a, Map>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut forced_movement,
+            mut positions,
+            mut viewsheds,
+            combat_stats,
+            mut suffer_damage,
+            mut damage_info,
+            resistances,
+            mut status_effects,
+            names,
+            players,
+            modes,
+            map,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        // Charges knock their victims back, so movements can chain within a frame
+        let mut pending: VecDeque<(Entity, ForcedMovement)> = (&entities, &forced_movement).join()
+            .map(|(entity, movement)| (entity, movement.clone()))
+            .collect();
+        forced_movement.clear();
+
+        let mut occupants: HashMap<(i32, i32), Entity> = (&entities, &positions, &combat_stats).join()
+            .filter(|(_, _, stats)| stats.hp > 0)
+            .map(|(entity, pos, _)| ((pos.x, pos.y), entity))
+            .collect();
+
+        while let Some((mover, movement)) = pending.pop_front() {
+            let start = match positions.get(mover) {
+                Some(pos) => (pos.x, pos.y),
+                None => continue,
+            };
+            let occupied: HashSet<(i32, i32)> = occupants.keys()
+                .filter(|tile| **tile != start)
+                .copied()
+                .collect();
+            let mode = MovementMode::current(modes.get(mover), status_effects.get(mover));
+            let path = trace_forced_path(&map, &occupied, start, movement.dx, movement.dy, movement.distance, mode);
+
+            if path.steps > 0 {
+                occupants.remove(&start);
+                occupants.insert(path.end, mover);
+                if let Some(pos) = positions.get_mut(mover) {
+                    pos.x = path.end.0;
+                    pos.y = path.end.1;
+                }
+                if let Some(viewshed) = viewsheds.get_mut(mover) {
+                    viewshed.dirty = true;
+                }
+            }
+
+            let mover_name = names.get(mover).map_or("Someone".to_string(), |n| n.name.clone());
+            match (movement.kind, path.collision) {
+                (ForcedMovementKind::Knockback, Some(Collision::Wall)) => {
+                    let damage = rng.roll_dice(1, 6);
+                    SufferDamage::new_damage(&mut suffer_damage, mover, damage);
+                    gamelog.add_entry(format!("{} slams into the wall for {} damage!", mover_name, damage));
+                },
+                (ForcedMovementKind::Knockback, Some(Collision::Creature(x, y))) => {
+                    let damage = rng.roll_dice(1, 4);
+                    SufferDamage::new_damage(&mut suffer_damage, mover, damage);
+                    if let Some(&other) = occupants.get(&(x, y)) {
+                        SufferDamage::new_damage(&mut suffer_damage, other, damage);
+                        let other_name = names.get(other).map_or("someone", |n| n.name.as_str());
+                        gamelog.add_entry(format!("{} crashes into {}! Both take {} damage.", mover_name, other_name, damage));
+                    }
+                },
+                (ForcedMovementKind::Charge, Some(Collision::Creature(x, y))) => {
+                    if let Some(&victim) = occupants.get(&(x, y)) {
+                        let damage = 6 + rng.roll_dice(1, 6) + path.steps * CHARGE_DAMAGE_PER_TILE;
+                        damage_info.insert(victim, DamageInfo {
+                            base_damage: damage,
+                            damage_type: DamageType::Physical,
+                            source: movement.source,
+                            is_critical: false,
+                            penetration: path.steps,
+                        }).expect("Failed to insert charge damage");
+
+                        let victim_name = names.get(victim).map_or("someone", |n| n.name.as_str());
+                        gamelog.add_entry(format!("{} charges into {}!", mover_name, victim_name));
+                        if path.steps > 0 {
+                            pending.push_back((victim, ForcedMovement::knockback(mover, path.end, (x, y), 1)));
+                        }
+                    }
+                },
+                (ForcedMovementKind::Charge, Some(Collision::Wall)) if path.steps == 0 => {
+                    if players.contains(mover) {
+                        gamelog.add_entry("There's no room to charge.".to_string());
+                    }
+                },
+                (ForcedMovementKind::Charge, _) => {
+                    gamelog.add_entry(format!("{} charges, but finds no one to hit.", mover_name));
+                },
+                (ForcedMovementKind::Knockback, None) => {},
+            }
+
+            // Wherever they stopped, hazards there take their toll on anything not held above them
+            if path.steps == 0 || !map.is_dangerous_for(path.end.0, path.end.1, mode) {
+                continue;
+            }
+            match map.get_tile(path.end.0, path.end.1) {
+                Some(TileType::Lava) => {
+                    SufferDamage::new_damage(&mut suffer_damage, mover, LAVA_DAMAGE);
+                    let burning = StatusEffect::new(StatusEffectType::Burning, 3, 3);
+                    apply_status_effect(mover, burning, &mut status_effects, resistances.get(mover), &mut rng);
+                    gamelog.add_entry(format!("{} is hurled into the lava!", mover_name));
+                },
+                Some(TileType::Void) => {
+                    let remaining = combat_stats.get(mover).map_or(0, |stats| stats.hp);
+                    SufferDamage::new_damage(&mut suffer_damage, mover, remaining);
+                    occupants.remove(&path.end);
+                    gamelog.add_entry(format!("{} plunges into the void!", mover_name));
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corridor() -> Map {
+        let mut map = Map::new(10, 3, 1);
+        for x in 1..9 {
+            map.set_tile(x, 1, TileType::Floor);
+        }
+        map
+    }
+
+    #[test]
+    fn test_knockback_stops_at_walls_and_creatures() {
+        let map = corridor();
+        let empty = HashSet::new();
+
+        let path = trace_forced_path(&map, &empty, (7, 1), 1, 0, 3, MovementMode::Walking);
+        assert_eq!(path.end, (8, 1));
+        assert_eq!(path.steps, 1);
+        assert_eq!(path.collision, Some(Collision::Wall));
+
+        let occupied: HashSet<(i32, i32)> = [(5, 1)].into_iter().collect();
+        let path = trace_forced_path(&map, &occupied, (2, 1), 1, 0, 4, MovementMode::Walking);
+        assert_eq!(path.end, (4, 1));
+        assert_eq!(path.collision, Some(Collision::Creature(5, 1)));
+    }
+
+    #[test]
+    fn test_knockback_ends_in_lava() {
+        let mut map = corridor();
+        map.set_tile(4, 1, TileType::Lava);
+
+        let path = trace_forced_path(&map, &HashSet::new(), (2, 1), 1, 0, 4, MovementMode::Walking);
+        assert_eq!(path.end, (4, 1));
+        assert_eq!(path.steps, 2);
+        assert_eq!(path.collision, None);
+
+        // Something with wings is carried straight over
+        let path = trace_forced_path(&map, &HashSet::new(), (2, 1), 1, 0, 4, MovementMode::Flying);
+        assert_eq!(path.end, (6, 1));
+    }
+}
@@ -0,0 +1,280 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Read, Write, WriteExpect};
+use crate::components::{
+    CombatStats, Furniture, FurnitureKind, Piety, Player, PlayerResources, Position, Skills, SkillType,
+    StatusEffect, StatusEffectType, StatusEffects, Viewshed, WantsToUseFurniture, MAX_PIETY
+};
+use crate::items::{AdvancedInventory, Equipment, ItemProperties};
+use crate::localization::Message;
+use crate::map::Map;
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+use super::status_effect_system::apply_status_effect;
+
+/// Gold an offering must be worth for each point of piety it earns
+pub const SHRINE_VALUE_PER_PIETY: i32 = 10;
+/// Longest blessing a shrine grants the godless
+const SHRINE_MAX_BLESSING: i32 = 300;
+/// Least skill experience a bookshelf teaches
+pub const BOOKSHELF_SKILL_XP: i32 = 20;
+/// What can be learned from books
+const STUDIED_SKILLS: [SkillType; 7] = [
+    SkillType::Arcane, SkillType::Divine, SkillType::Elemental, SkillType::Alchemy,
+    SkillType::Enchanting, SkillType::Perception, SkillType::Survival,
+];
+
+/// Carried items that could go on a shrine: anything not being worn
+pub fn offerings(inventory: Option<&AdvancedInventory>, equipment: Option<&Equipment>) -> Vec<Entity> {
+    inventory.map_or(Vec::new(), |inventory| inventory.items.iter()
+        .map(|slot| slot.entity)
+        .filter(|item| equipment.map_or(true, |gear| gear.find_item_slot(*item).is_none()))
+        .collect())
+}
+
+/// Drinks from fountains, takes offerings at shrines, pulls levers and reads bookshelves for
+/// whoever stands beside them, then starts the furniture's cooldown or uses it up
+pub struct FurnitureSystem;
+
+impl<'a> System<'a> for FurnitureSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToUseFurniture>,
+        WriteStorage<'a, Furniture>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, PlayerResources>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, Skills>,
+        WriteStorage<'a, Piety>,
+        WriteStorage<'a, AdvancedInventory>,
+        ReadStorage<'a, Equipment>,
+        ReadStorage<'a, ItemProperties>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_furniture,
+            mut furniture,
+            positions,
+            players,
+            mut combat_stats,
+            mut resources,
+            mut status_effects,
+            mut skills,
+            mut pieties,
+            mut inventories,
+            equipment,
+            properties,
+            mut viewsheds,
+            mut map,
+            game_state,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        let requests: Vec<(Entity, WantsToUseFurniture)> = (&entities, &wants_furniture).join()
+            .map(|(entity, request)| (entity, request.clone()))
+            .collect();
+        wants_furniture.clear();
+        let turn = game_state.turn_count;
+
+        for (user, request) in requests {
+            let within_reach = match (positions.get(user), positions.get(request.furniture)) {
+                (Some(a), Some(b)) => (a.x - b.x).abs() <= 1 && (a.y - b.y).abs() <= 1,
+                _ => false,
+            };
+            let piece = match furniture.get_mut(request.furniture) {
+                Some(piece) if within_reach => piece,
+                _ => continue,
+            };
+            let is_player = players.contains(user);
+            if !piece.is_ready(turn) {
+                if is_player {
+                    gamelog.add_message(Message::new("log.furniture_not_ready").arg("name", piece.kind.name()));
+                }
+                continue;
+            }
+
+            let message = match piece.kind {
+                FurnitureKind::Fountain => {
+                    drink(user, &mut combat_stats, &mut resources, &mut status_effects, &mut rng)
+                },
+                FurnitureKind::Shrine => {
+                    let item = match request.sacrifice {
+                        Some(item) if offerings(inventories.get(user), equipment.get(user)).contains(&item) => item,
+                        _ => continue,
+                    };
+                    let value = properties.get(item).map_or(0, |props| props.value);
+                    let name = properties.get(item).map_or("offering".to_string(), |props| props.name.clone());
+                    if let Some(inventory) = inventories.get_mut(user) {
+                        if let Some(slot) = inventory.find_item(item) {
+                            let quantity = inventory.items[slot].quantity;
+                            let weight = properties.get(item).map_or(0.0, |props| props.weight);
+                            inventory.remove_item(slot, quantity, weight);
+                        }
+                    }
+                    entities.delete(item).expect("Unable to delete sacrificed item");
+
+                    match pieties.get_mut(user) {
+                        Some(piety) => {
+                            let gained = (value / SHRINE_VALUE_PER_PIETY).max(1);
+                            piety.piety = (piety.piety + gained).min(MAX_PIETY);
+                            Message::new("log.shrine_piety").arg("item", name).arg("god", piety.deity.name()).arg("piety", gained)
+                        },
+                        None => {
+                            let turns = (50 + value).min(SHRINE_MAX_BLESSING);
+                            let blessing = StatusEffect::new(StatusEffectType::Blessed, turns, 1);
+                            apply_status_effect(user, blessing, &mut status_effects, None, &mut rng);
+                            Message::new("log.shrine_blessing").arg("item", name).arg("turns", turns)
+                        },
+                    }
+                },
+                FurnitureKind::Lever => match piece.opens {
+                    Some((x, y)) => {
+                        map.set_door(x, y, true);
+                        if let Some(viewshed) = viewsheds.get_mut(user) {
+                            viewshed.dirty = true;
+                        }
+                        Message::new("log.lever_opens")
+                    },
+                    None => Message::new("log.lever_nothing"),
+                },
+                FurnitureKind::Bookshelf => {
+                    let skill = STUDIED_SKILLS[rng.range(0, STUDIED_SKILLS.len() as i32 - 1) as usize];
+                    let xp = BOOKSHELF_SKILL_XP + rng.roll_dice(1, 20);
+                    let improved = skills.get_mut(user).map_or(false, |skills| skills.gain_experience(skill, xp));
+                    if let (true, true, Some(skills)) = (improved, is_player, skills.get(user)) {
+                        gamelog.add_message(Message::new("log.skill_improved")
+                            .arg("skill", skill.name())
+                            .arg("level", skills.get_skill_level(skill)));
+                    }
+                    Message::new("log.bookshelf_study").arg("skill", skill.name()).arg("xp", xp)
+                },
+            };
+
+            piece.mark_used(turn);
+            if is_player {
+                gamelog.add_message(message);
+            }
+        }
+    }
+}
+
+/// A drink from a fountain: usually refreshing, now and then foul
+fn drink(
+    drinker: Entity,
+    combat_stats: &mut WriteStorage<CombatStats>,
+    resources: &mut WriteStorage<PlayerResources>,
+    status_effects: &mut WriteStorage<StatusEffects>,
+    rng: &mut RandomNumberGenerator,
+) -> Message {
+    let mut afflict = |effect: StatusEffect, rng: &mut RandomNumberGenerator| {
+        apply_status_effect(drinker, effect, status_effects, None, rng);
+    };
+    match rng.roll_dice(1, 6) {
+        1 | 2 => {
+            let healed = match combat_stats.get_mut(drinker) {
+                Some(stats) => {
+                    let healed = rng.roll_dice(2, 6).min(stats.max_hp - stats.hp).max(0);
+                    stats.hp += healed;
+                    healed
+                },
+                None => 0,
+            };
+            Message::new("log.fountain_heal").arg("hp", healed)
+        },
+        3 => {
+            if let Some(reserves) = resources.get_mut(drinker) {
+                reserves.mana = reserves.max_mana;
+                reserves.stamina = reserves.max_stamina;
+            }
+            Message::new("log.fountain_refresh")
+        },
+        4 => {
+            afflict(StatusEffect::new(StatusEffectType::Regeneration, 10, 1), rng);
+            Message::new("log.fountain_regeneration")
+        },
+        5 => {
+            afflict(StatusEffect::new(StatusEffectType::StrengthBoost, 50, 1), rng);
+            Message::new("log.fountain_strength")
+        },
+        _ => {
+            afflict(StatusEffect::new(StatusEffectType::Poisoned, 5, 2), rng);
+            Message::new("log.fountain_poison")
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::components::Deity;
+    use crate::items::{InventorySlot, ItemType, MaterialType};
+    use crate::map::TileType;
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<Equipment>();
+        world.register::<ItemProperties>();
+        world.insert(Map::new(10, 10, 1));
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(6));
+        world
+    }
+
+    fn use_furniture(world: &mut World, user: Entity, piece: Entity, sacrifice: Option<Entity>) {
+        world.write_storage::<WantsToUseFurniture>()
+            .insert(user, WantsToUseFurniture { furniture: piece, sacrifice })
+            .expect("Unable to insert furniture intent");
+        FurnitureSystem.run_now(world);
+        world.maintain();
+    }
+
+    #[test]
+    fn test_levers_work_once_and_fountains_refill() {
+        let mut world = world();
+        let player = world.create_entity().with(Player).with(Position { x: 2, y: 2 }).build();
+        let lever = world.create_entity().with(Position { x: 3, y: 2 }).with(Furniture::lever((5, 5))).build();
+        let fountain = world.create_entity().with(Position { x: 1, y: 1 }).with(Furniture::new(FurnitureKind::Fountain)).build();
+
+        use_furniture(&mut world, player, lever, None);
+        assert_eq!(world.fetch::<Map>().get_tile(5, 5), Some(TileType::Door(true)));
+        assert!(world.read_storage::<Furniture>().get(lever).unwrap().spent);
+
+        use_furniture(&mut world, player, fountain, None);
+        let ready = world.read_storage::<Furniture>().get(fountain).unwrap().turns_until_ready(0);
+        assert_eq!(ready, Some(crate::components::FOUNTAIN_REFILL_TURNS));
+    }
+
+    #[test]
+    fn test_a_shrine_turns_offerings_into_piety() {
+        let mut world = world();
+        let ring = world.create_entity()
+            .with(ItemProperties::new("Silver Ring".to_string(), ItemType::Material(MaterialType::Metal)).with_value(45))
+            .build();
+        let mut inventory = AdvancedInventory::new(26, 100.0);
+        inventory.items.push(InventorySlot::new(ring, 1));
+        let player = world.create_entity()
+            .with(Player)
+            .with(Position { x: 2, y: 2 })
+            .with(inventory)
+            .with(Piety::new(Deity::Aurelion))
+            .build();
+        let shrine = world.create_entity().with(Position { x: 2, y: 3 }).with(Furniture::new(FurnitureKind::Shrine)).build();
+
+        use_furniture(&mut world, player, shrine, Some(ring));
+        assert_eq!(world.read_storage::<Piety>().get(player).unwrap().piety, 45 / SHRINE_VALUE_PER_PIETY);
+        assert!(!world.is_alive(ring));
+        assert!(world.read_storage::<AdvancedInventory>().get(player).unwrap().items.is_empty());
+        assert!(!world.read_storage::<Furniture>().get(shrine).unwrap().is_ready(1));
+    }
+}
@@ -0,0 +1,262 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, WriteExpect, Read};
+use crate::components::{
+    CombatStats, DamageResistances, GasFlask, GasKind, MovementMode, Player, Position, ReleasesGas, StatusEffect,
+    StatusEffects, WantsToThrow
+};
+use crate::items::{AdvancedInventory, ItemProperties, ItemStack};
+use crate::localization::Message;
+use crate::map::{Map, TileType};
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+use super::status_effect_system::apply_status_effect;
+
+/// Gas in a flask of it, enough to fill a small room
+pub const FLASK_GAS_VOLUME: i32 = 20;
+/// Gas a sprung trap lets loose
+pub const TRAP_GAS_VOLUME: i32 = 24;
+/// How long a breath of gas lingers once out of the cloud
+const GAS_EXPOSURE_TURNS: i32 = 3;
+/// Density for each point of magnitude of the gas's effect
+const DENSITY_PER_MAGNITUDE: i32 = 4;
+
+/// Gas let loose by things that can't reach the map themselves, such as trapped containers
+#[derive(Debug, Clone, Default)]
+pub struct GasReleases {
+    pub releases: Vec<((i32, i32), GasKind, i32)>,
+}
+
+impl GasReleases {
+    pub fn release(&mut self, pos: (i32, i32), kind: GasKind, volume: i32) {
+        self.releases.push((pos, kind, volume));
+    }
+
+    pub fn take(&mut self) -> Vec<((i32, i32), GasKind, i32)> {
+        std::mem::take(&mut self.releases)
+    }
+}
+
+/// Lets loose gas from traps, thrown flasks and the corpses of the creatures that carry it, and once
+/// a turn lets every cloud drift and thin out, afflicting whatever breathes it in. A floor trap,
+/// hidden or spotted, sprays poison gas over whoever walks onto it and is spent
+pub struct GasSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl GasSystem {
+    pub fn new() -> Self {
+        GasSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for GasSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToThrow>,
+        ReadStorage<'a, GasFlask>,
+        ReadStorage<'a, ItemProperties>,
+        WriteStorage<'a, ItemStack>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, ReleasesGas>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, DamageResistances>,
+        ReadStorage<'a, MovementMode>,
+        WriteStorage<'a, StatusEffects>,
+        WriteExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GasReleases>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_throw,
+            flasks,
+            properties,
+            mut stacks,
+            mut inventories,
+            mut releases_gas,
+            positions,
+            combat_stats,
+            players,
+            resistances,
+            modes,
+            mut status_effects,
+            mut map,
+            game_state,
+            mut releases,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        for (pos, kind, volume) in releases.take() {
+            map.release_gas(pos.0, pos.1, kind, volume);
+        }
+
+        // Thrown flasks burst where they land; anything else thrown just breaks
+        let throws: Vec<(Entity, WantsToThrow)> = (&entities, &wants_throw).join()
+            .map(|(entity, request)| (entity, request.clone()))
+            .collect();
+        wants_throw.clear();
+        for (thrower, request) in throws {
+            let inventory = match inventories.get_mut(thrower) {
+                Some(inventory) => inventory,
+                None => continue,
+            };
+            let slot = match inventory.find_item(request.item) {
+                Some(slot) => slot,
+                None => continue,
+            };
+            let weight = properties.get(request.item).map_or(0.0, |props| props.weight);
+            let name = properties.get(request.item).map_or("flask".to_string(), |props| props.name.clone());
+            let flask = flasks.get(request.item).cloned();
+            match stacks.get_mut(request.item) {
+                Some(stack) if stack.quantity > 1 => {
+                    stack.quantity -= 1;
+                    inventory.remove_item(slot, 1, weight);
+                },
+                _ => {
+                    let quantity = inventory.items[slot].quantity;
+                    inventory.remove_item(slot, quantity, weight);
+                    entities.delete(request.item).expect("Unable to delete thrown item");
+                },
+            }
+
+            let (x, y) = request.target;
+            let message = match flask {
+                Some(flask) if map.release_gas(x, y, flask.kind, flask.volume) => {
+                    Message::new("log.flask_bursts").arg("item", name).arg("gas", flask.kind.name())
+                },
+                _ => Message::new("log.thrown_shatters").arg("item", name),
+            };
+            if players.contains(thrower) {
+                gamelog.add_message(message);
+            }
+        }
+
+        // Some creatures give off their gas as they die
+        let dying: Vec<(Entity, (i32, i32), ReleasesGas)> = (&entities, &releases_gas, &combat_stats, &positions).join()
+            .filter(|(_, _, stats, _)| stats.hp <= 0)
+            .map(|(entity, gas, _, pos)| (entity, (pos.x, pos.y), gas.clone()))
+            .collect();
+        for (entity, (x, y), gas) in dying {
+            releases_gas.remove(entity);
+            if map.release_gas(x, y, gas.kind, gas.volume) && map.is_visible(x, y) {
+                gamelog.add_message(Message::new("log.corpse_releases_gas").arg("gas", gas.kind.name()));
+            }
+        }
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        // Traps go off under anything that walks onto them
+        let sprung: Vec<(Entity, i32, i32)> = (&entities, &positions, &combat_stats).join()
+            .filter(|(entity, pos, stats)| {
+                stats.hp > 0
+                    && matches!(map.get_tile(pos.x, pos.y), Some(TileType::Trap(_)))
+                    && !MovementMode::current(modes.get(*entity), status_effects.get(*entity)).is_airborne()
+            })
+            .map(|(entity, pos, _)| (entity, pos.x, pos.y))
+            .collect();
+        for (victim, x, y) in sprung {
+            map.set_tile(x, y, TileType::Floor);
+            map.release_gas(x, y, GasKind::PoisonGas, TRAP_GAS_VOLUME);
+            if players.contains(victim) {
+                gamelog.add_message(Message::new("log.gas_trap"));
+            }
+        }
+
+        // Whatever breathes the gas in suffers for it, more so where it is thick
+        for (entity, pos, stats) in (&entities, &positions, &combat_stats).join() {
+            let cloud = match map.gas_at(pos.x, pos.y) {
+                Some(cloud) if stats.hp > 0 => cloud,
+                _ => continue,
+            };
+            let magnitude = (cloud.density / DENSITY_PER_MAGNITUDE).max(1);
+            let effect = StatusEffect::new(cloud.kind.status(), GAS_EXPOSURE_TURNS, magnitude);
+            apply_status_effect(entity, effect, &mut status_effects, resistances.get(entity), &mut rng);
+            if players.contains(entity) {
+                gamelog.add_message(Message::new("log.breathe_gas").arg("gas", cloud.kind.name()));
+            }
+        }
+
+        map.spread_gas();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::components::StatusEffectType;
+    use crate::items::{InventorySlot, ItemType, ConsumableType};
+    use crate::map::TileType;
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<ItemProperties>();
+        world.register::<ItemStack>();
+        let mut map = Map::new(10, 10, 1);
+        for x in 1..9 {
+            for y in 1..9 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        world.insert(map);
+        world.insert(GameStateResource::default());
+        world.insert(GasReleases::default());
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(2));
+        world
+    }
+
+    #[test]
+    fn test_a_thrown_flask_gasses_whoever_stands_there() {
+        let mut world = world();
+        let flask = world.create_entity()
+            .with(ItemProperties::new("Flask of Spores".to_string(), ItemType::Consumable(ConsumableType::Potion)))
+            .with(GasFlask { kind: GasKind::Spores, volume: FLASK_GAS_VOLUME })
+            .build();
+        let mut inventory = AdvancedInventory::new(26, 100.0);
+        inventory.items.push(InventorySlot::new(flask, 1));
+        let player = world.create_entity().with(Player).with(Position { x: 1, y: 1 }).with(inventory).build();
+        let goblin = world.create_entity()
+            .with(Position { x: 5, y: 5 })
+            .with(CombatStats { max_hp: 10, hp: 10, defense: 0, power: 2 })
+            .with(StatusEffects::new())
+            .build();
+
+        world.write_storage::<WantsToThrow>()
+            .insert(player, WantsToThrow { item: flask, target: (5, 5) })
+            .unwrap();
+        GasSystem::new().run_now(&world);
+        world.maintain();
+
+        assert!(!world.is_alive(flask));
+        assert!(world.read_storage::<AdvancedInventory>().get(player).unwrap().items.is_empty());
+        assert!(world.read_storage::<StatusEffects>().get(goblin).unwrap().has_effect(StatusEffectType::Confused));
+        assert!(world.fetch::<Map>().gas_at(6, 5).is_some());
+    }
+
+    #[test]
+    fn test_the_dead_give_off_their_gas_once() {
+        let mut world = world();
+        let zombie = world.create_entity()
+            .with(Position { x: 3, y: 3 })
+            .with(CombatStats { max_hp: 10, hp: 0, defense: 0, power: 2 })
+            .with(ReleasesGas { kind: GasKind::Miasma, volume: 12 })
+            .build();
+
+        let mut gas = GasSystem::new();
+        gas.run_now(&world);
+        assert!(!world.read_storage::<ReleasesGas>().contains(zombie));
+        let released = world.fetch::<Map>().gas.values().map(|cloud| cloud.density).sum::<i32>();
+        assert!(released > 0 && released < 12);
+    }
+}
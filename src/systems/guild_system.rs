@@ -0,0 +1,156 @@
+use specs::{System, ReadStorage, Join, Read, Write};
+use crate::components::{GameMode, GameSettings, Player};
+use crate::guild::{narration_request, template_narrative, ContractReport, GuildRoster};
+use crate::language_model::{ModelPurpose, ModelQueue, Priority};
+use crate::localization::Message;
+use crate::presence::{self, Notification};
+use crate::progression::{Bank, WorldEvents};
+use crate::resources::{GameClock, GameLog, RandomNumberGenerator, GUILD_STREAM};
+
+/// Keeps the guild's agents going while the character is elsewhere: as each day turns over the
+/// infirmary mends its patients, agents whose contracts are up come back with gold for the bank
+/// and experience, or hurt, or not at all, and a new batch of candidates turns up at the guild
+/// hall. Raids and caravans up above make some contracts pay better. Each contract's story is
+/// filed in the guild's ledger; with a model set up it is asked to tell it and
+/// `ModelResultSystem` brings the story to the log, otherwise the templates' version goes there
+/// straight away
+pub struct GuildSystem {
+    pub last_day: Option<u64>,
+}
+
+impl GuildSystem {
+    pub fn new() -> Self {
+        GuildSystem { last_day: None }
+    }
+}
+
+impl<'a> System<'a> for GuildSystem {
+    type SystemData = (
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, GameSettings>,
+        Read<'a, GameClock>,
+        Read<'a, ModelQueue>,
+        Read<'a, WorldEvents>,
+        Write<'a, GuildRoster>,
+        Write<'a, Bank>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (players, settings, clock, queue, world, mut roster, mut bank, mut rng, mut gamelog) = data;
+
+        let today = clock.day();
+        let days = match self.last_day {
+            Some(last) if today > last => today - last,
+            _ => 0,
+        };
+        self.last_day = Some(today);
+        if days == 0 {
+            return;
+        }
+        let mode = (&players, &settings).join()
+            .next()
+            .map_or(GameMode::Normal, |(_, settings)| settings.game_mode.clone());
+        let rng = rng.stream(GUILD_STREAM);
+        for mut report in roster.pass_days(days as u32, &mode, rng) {
+            report.gold = report.gold * world.reward_percent(report.contract) / 100;
+            bank.balance += report.gold;
+            announce(&report, &mut gamelog);
+            report.day = today;
+            report.narrative = template_narrative(&report, rng);
+            let request = narration_request(&report);
+            let narrative = report.narrative.clone();
+            let id = roster.ledger.record(report);
+            let asked = request.and_then(|request| queue.submit(request, Priority::Low, None, ModelPurpose::Narration(id)));
+            if asked.is_err() {
+                gamelog.add_entry(narrative);
+            }
+        }
+        roster.refresh_recruits(rng);
+    }
+}
+
+/// Tell the player how a contract went, here and wherever they get notifications
+fn announce(report: &ContractReport, gamelog: &mut GameLog) {
+    let contract = report.contract.name();
+    if report.died {
+        gamelog.add_message(Message::new("log.agent_died").arg("agent", &report.agent).arg("contract", contract));
+        presence::post(Notification::mission_failed(contract));
+        return;
+    }
+    if report.success {
+        gamelog.add_message(Message::new("log.contract_done")
+            .arg("agent", &report.agent)
+            .arg("contract", contract)
+            .arg("gold", report.gold)
+            .arg("experience", report.experience));
+        presence::post(Notification::mission_completed(contract));
+    } else {
+        gamelog.add_message(Message::new("log.contract_failed")
+            .arg("agent", &report.agent)
+            .arg("contract", contract)
+            .arg("experience", report.experience));
+        presence::post(Notification::mission_failed(contract));
+    }
+    if report.levels > 0 {
+        gamelog.add_message(Message::new("log.agent_level").arg("agent", &report.agent));
+    }
+    if report.injury_days > 0 {
+        gamelog.add_message(Message::new("log.agent_injured").arg("agent", &report.agent).arg("days", report.injury_days));
+    }
+    for milestone in &report.milestones {
+        gamelog.add_message(Message::new("log.guild_milestone").arg("milestone", milestone.name()).arg("renown", milestone.renown()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::guild::{Agent, Assignment, Contract};
+    use crate::components::ClassType;
+
+    #[test]
+    fn test_a_returning_agent_pays_into_the_bank_and_new_recruits_turn_up() {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.insert(GameClock::default());
+        world.insert(Bank::default());
+        world.insert(RandomNumberGenerator::new(3));
+        world.insert(GameLog::new(10));
+        world.insert(ModelQueue::default());
+        world.insert(WorldEvents::default());
+        let mut roster = GuildRoster::default();
+        roster.agents.push(Agent {
+            name: "Edda".to_string(),
+            class: ClassType::Fighter,
+            traits: Vec::new(),
+            level: 10,
+            experience: 0,
+            max_hp: 60,
+            power: 20,
+            defense: 30,
+            recovery_days: 0,
+            assignment: Some(Assignment { contract: Contract::Patrol, days_left: 1 }),
+        });
+        let mut expected = RandomNumberGenerator::new(3);
+        let reports = roster.clone().pass_days(1, &GameMode::Normal, expected.stream(GUILD_STREAM));
+        world.insert(roster);
+        world.create_entity().with(Player).build();
+
+        let mut guild = GuildSystem::new();
+        guild.run_now(&world);
+        assert_eq!(world.read_resource::<Bank>().balance, 0);
+
+        world.write_resource::<GameClock>().minutes += 24 * 60;
+        guild.run_now(&world);
+        assert_eq!(world.read_resource::<Bank>().balance, reports[0].gold);
+        let roster = world.read_resource::<GuildRoster>();
+        assert!(roster.agents[0].is_ready());
+        assert_eq!(roster.recruits.len(), crate::guild::RECRUIT_POOL);
+        assert_eq!(roster.ledger.reports.len(), 1);
+        assert_eq!(roster.ledger.reports[0].day, 2);
+        assert!(!roster.ledger.reports[0].narrative.is_empty());
+    }
+}
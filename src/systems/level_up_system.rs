@@ -1,6 +1,8 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, ReadExpect, Write};
-use crate::components::{Experience, Attributes, Skills, Abilities, AbilityType, CharacterClass, CombatStats, Name};
-use crate::resources::GameLog;
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use crate::components::{Experience, Attributes, AttributeType, Skills, Abilities, CharacterClass, CombatStats, Name, Player, PendingLevelUp};
+use crate::resources::{GameLog, GameEvents};
+use crate::achievements::GameEvent;
+use crate::ui::{TutorialEvents, TutorialTrigger};
 
 // Event to signal that an entity has leveled up
 pub struct LevelUpEvent {
@@ -8,47 +10,23 @@ pub struct LevelUpEvent {
     pub new_level: i32,
 }
 
+/// Hit points a new level brings: the class's share plus the constitution modifier, at least one
+pub fn hp_for_level(class: Option<&CharacterClass>, attributes: Option<&Attributes>) -> i32 {
+    let base = class.map_or(5, |class| class.class_type.hp_per_level());
+    let constitution = attributes.map_or(0, |attrs| attrs.get_modifier(AttributeType::Constitution));
+    (base + constitution).max(1)
+}
+
 pub struct ExperienceSystem {}
 
 impl<'a> System<'a> for ExperienceSystem {
-    type SystemData = (
-        Entities<'a>,
-        WriteStorage<'a, Experience>,
-        ReadStorage<'a, Name>,
-        WriteStorage<'a, CombatStats>,
-        ReadStorage<'a, CharacterClass>,
-        Write<'a, GameLog>,
-    );
+    type SystemData = WriteStorage<'a, Experience>;
 
-    fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut experience, names, mut combat_stats, character_classes, mut gamelog) = data;
-
-        // Check for level ups
-        for (entity, exp, name) in (&entities, &mut experience, &names).join() {
-            if exp.current >= exp.level_up_target {
-                // Level up!
+    fn run(&mut self, mut experience: Self::SystemData) {
+        // Experience added without `gain_exp` can be worth more than one level
+        for exp in (&mut experience).join() {
+            while exp.current >= exp.level_up_target {
                 exp.level_up();
-                
-                // Increase HP based on class
-                if let Some(class) = character_classes.get(entity) {
-                    if let Some(stats) = combat_stats.get_mut(entity) {
-                        let hp_gain = class.class_type.hp_per_level();
-                        stats.max_hp += hp_gain;
-                        stats.hp += hp_gain; // Also heal on level up
-                        
-                        gamelog.add_entry(format!("{} leveled up to level {}! HP increased by {}.", 
-                            name.name, exp.level, hp_gain));
-                    }
-                } else {
-                    // Default HP gain if no class is found
-                    if let Some(stats) = combat_stats.get_mut(entity) {
-                        stats.max_hp += 5;
-                        stats.hp += 5;
-                        
-                        gamelog.add_entry(format!("{} leveled up to level {}! HP increased by 5.", 
-                            name.name, exp.level));
-                    }
-                }
             }
         }
     }
@@ -59,43 +37,110 @@ pub struct LevelUpSystem {}
 impl<'a> System<'a> for LevelUpSystem {
     type SystemData = (
         Entities<'a>,
-        ReadStorage<'a, Experience>,
+        WriteStorage<'a, Experience>,
         WriteStorage<'a, Attributes>,
         WriteStorage<'a, Skills>,
         WriteStorage<'a, Abilities>,
         ReadStorage<'a, CharacterClass>,
+        WriteStorage<'a, CombatStats>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, PendingLevelUp>,
+        Write<'a, GameLog>,
+        Write<'a, TutorialEvents>,
+        Write<'a, GameEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, experience, mut attributes, mut skills, mut abilities, character_classes) = data;
+        let (entities, mut experience, mut attributes, mut skills, mut abilities, character_classes,
+            mut combat_stats, names, players, mut pending, mut gamelog, mut tutorials, mut events) = data;
 
-        // Process entities with unspent attribute points
-        for (entity, exp, attrs) in (&entities, &experience, &mut attributes).join() {
-            if exp.unspent_points > 0 {
-                // Add unspent points to attributes
-                attrs.unspent_points += exp.unspent_points;
+        for (entity, exp) in (&entities, &mut experience).join() {
+            if exp.levels_unclaimed <= 0 {
+                continue;
             }
-        }
-        
-        // Process skill points (every 2 levels)
-        for (entity, exp, mut skill_comp) in (&entities, &experience, &mut skills).join() {
-            // Grant skill points every 2 levels
-            if exp.level % 2 == 0 {
-                skill_comp.unspent_skill_points += 1;
+            let gained = exp.levels_unclaimed;
+            exp.levels_unclaimed = 0;
+            let class = character_classes.get(entity);
+            let name = names.get(entity).map_or("Someone", |n| n.name.as_str());
+
+            for level in (exp.level - gained + 1)..=exp.level {
+                let hp_gain = hp_for_level(class, attributes.get(entity));
+                if let Some(stats) = combat_stats.get_mut(entity) {
+                    stats.max_hp += hp_gain;
+                    stats.hp += hp_gain; // Also heal on level up
+                }
+                gamelog.add_entry(format!("{} leveled up to level {}! HP increased by {}.", name, level, hp_gain));
+
+                // A skill point every second level
+                if level % 2 == 0 {
+                    if let Some(skill_comp) = skills.get_mut(entity) {
+                        skill_comp.add_skill_points(1);
+                    }
+                }
             }
-        }
-        
-        // Check for ability unlocks based on level
-        for (entity, exp, mut ability_comp, class) in (&entities, &experience, &mut abilities, &character_classes).join() {
-            // Get abilities for this class
-            let class_abilities = AbilityType::get_class_abilities(class.class_type);
-            
-            // Check each ability to see if it should be unlocked at this level
-            for ability in class_abilities {
-                if ability.required_level() <= exp.level && !ability_comp.has_ability(ability) {
+
+            // Attribute points wait on the attributes they will be spent on
+            if let Some(attrs) = attributes.get_mut(entity) {
+                attrs.unspent_points += exp.unspent_points;
+                exp.unspent_points = 0;
+            }
+
+            if players.contains(entity) {
+                // The player spends the points and picks an ability on the level-up screen
+                let levels = pending.get(entity).map_or(0, |pending| pending.levels) + gained;
+                pending.insert(entity, PendingLevelUp { levels })
+                    .expect("Unable to insert pending level-up");
+                tutorials.publish(TutorialTrigger::FirstLevelUp);
+                events.publish(GameEvent::LevelChanged(exp.level));
+            } else if let (Some(class), Some(ability_comp)) = (class, abilities.get_mut(entity)) {
+                // Everyone else learns whatever their level allows
+                for ability in ability_comp.learnable(class.class_type, exp.level) {
                     ability_comp.add_ability(ability);
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::components::ClassType;
+
+    #[test]
+    fn test_levels_from_gain_exp_grant_hit_points_and_points_then_wait_for_the_player() {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.insert(GameLog::new(10));
+        world.insert(TutorialEvents::default());
+        world.insert(GameEvents::default());
+
+        let mut exp = Experience::new();
+        assert!(exp.gain_exp(100));
+        let player = world.create_entity()
+            .with(Player {})
+            .with(Name { name: "Ada".to_string() })
+            .with(exp)
+            .with(Attributes::from_scores([10, 10, 14, 10, 10, 10]))
+            .with(Skills::new())
+            .with(Abilities::starting(ClassType::Fighter))
+            .with(CharacterClass { class_type: ClassType::Fighter })
+            .with(CombatStats { max_hp: 14, hp: 10, defense: 2, power: 5 })
+            .build();
+
+        ExperienceSystem {}.run_now(&world);
+        LevelUpSystem {}.run_now(&world);
+        LevelUpSystem {}.run_now(&world);
+
+        let stats = world.read_storage::<CombatStats>().get(player).cloned().unwrap();
+        assert_eq!((stats.max_hp, stats.hp), (26, 22));
+        assert_eq!(world.read_storage::<Attributes>().get(player).unwrap().unspent_points, 3);
+        assert_eq!(world.read_storage::<Experience>().get(player).unwrap().unspent_points, 0);
+        assert_eq!(world.read_storage::<Skills>().get(player).unwrap().unspent_skill_points, 1);
+        assert_eq!(world.read_storage::<PendingLevelUp>().get(player).unwrap().levels, 1);
+        // Level two brings no new Fighter ability, and the player picks their own anyway
+        assert_eq!(world.read_storage::<Abilities>().get(player).unwrap().abilities.len(), 1);
+    }
+}
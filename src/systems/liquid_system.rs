@@ -0,0 +1,247 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect};
+use crate::components::{
+    Attributes, AttributeType, CombatStats, DamageResistances, DamageType, MovementMode, Player, PlayerResources, Position,
+    StatusEffect, StatusEffectType, StatusEffects, SufferDamage, Swimming
+};
+use crate::items::{Equipment, ItemProperties};
+use crate::localization::Message;
+use crate::map::{Map, TileType};
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+use super::status_effect_system::apply_status_effect;
+
+/// Swim check needed to stay afloat with nothing weighing you down
+pub const SWIM_DIFFICULTY: i32 = 8;
+/// Pounds of worn and wielded gear that each add one to the swim check
+pub const SWIM_WEIGHT_STEP: f32 = 10.0;
+/// Stamina spent on each turn of swimming; with none left you sink
+pub const SWIM_STAMINA_COST: i32 = 2;
+/// Damage for each turn in a row spent under water, so it grows the longer you struggle
+pub const DROWNING_DAMAGE: i32 = 2;
+/// Fire damage each turn spent wading through lava
+pub const LAVA_DAMAGE: i32 = 8;
+/// How long the flames cling on after leaving the lava
+const LAVA_BURN_TURNS: i32 = 3;
+const LAVA_BURN_DAMAGE: i32 = 2;
+
+/// The swim check to beat while carrying `load` pounds of equipment
+pub fn swim_difficulty(load: f32) -> i32 {
+    SWIM_DIFFICULTY + (load / SWIM_WEIGHT_STEP) as i32
+}
+
+/// Pounds of everything worn or wielded
+pub fn equipment_load(equipment: Option<&Equipment>, properties: &ReadStorage<ItemProperties>) -> f32 {
+    equipment.map_or(0.0, |equipment| {
+        equipment.slots.values()
+            .flatten()
+            .filter_map(|item| properties.get(*item))
+            .map(|props| props.weight)
+            .sum()
+    })
+}
+
+/// Once a turn, makes whatever is in deep water swim for it and burns whatever is in lava.
+/// Fliers and levitating creatures pass over both, and water walking keeps feet dry but not from the lava
+pub struct LiquidSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl LiquidSystem {
+    pub fn new() -> Self {
+        LiquidSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for LiquidSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Equipment>,
+        ReadStorage<'a, ItemProperties>,
+        ReadStorage<'a, DamageResistances>,
+        ReadStorage<'a, MovementMode>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, PlayerResources>,
+        WriteStorage<'a, Swimming>,
+        WriteStorage<'a, SufferDamage>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            positions,
+            combat_stats,
+            players,
+            attributes,
+            equipment,
+            properties,
+            resistances,
+            modes,
+            mut status_effects,
+            mut player_resources,
+            mut swimming,
+            mut suffer_damage,
+            map,
+            game_state,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let mut ashore: Vec<Entity> = Vec::new();
+        for (entity, pos, stats) in (&entities, &positions, &combat_stats).join() {
+            if stats.hp <= 0 {
+                continue;
+            }
+            let is_player = players.contains(entity);
+            let airborne = MovementMode::current(modes.get(entity), status_effects.get(entity)).is_airborne();
+            let water_walking = status_effects.get(entity).map_or(false, |effects| effects.has_effect(StatusEffectType::WaterWalking));
+
+            match map.get_tile(pos.x, pos.y) {
+                Some(TileType::Water) if !airborne && !water_walking => {
+                    if let Some(effects) = status_effects.get_mut(entity) {
+                        if effects.has_effect(StatusEffectType::Burning) {
+                            effects.remove_effect(StatusEffectType::Burning);
+                            if is_player {
+                                gamelog.add_message(Message::new("log.flames_doused"));
+                            }
+                        }
+                    }
+
+                    // Out of breath or out of luck, it goes under
+                    let tired = player_resources.get_mut(entity).map_or(false, |resources| !resources.consume_stamina(SWIM_STAMINA_COST));
+                    let strength = attributes.get(entity).map_or(0, |stats| stats.get_modifier(AttributeType::Strength));
+                    let difficulty = swim_difficulty(equipment_load(equipment.get(entity), &properties));
+                    let afloat = !tired && rng.roll_dice(1, 20) + strength >= difficulty;
+
+                    if !swimming.contains(entity) {
+                        swimming.insert(entity, Swimming::default()).expect("Unable to insert swimming");
+                        if is_player {
+                            gamelog.add_message(Message::new("log.start_swimming"));
+                        }
+                    }
+                    let swimmer = match swimming.get_mut(entity) {
+                        Some(swimmer) => swimmer,
+                        None => continue,
+                    };
+                    if afloat {
+                        if swimmer.turns_under > 0 && is_player {
+                            gamelog.add_message(Message::new("log.surface"));
+                        }
+                        swimmer.turns_under = 0;
+                        continue;
+                    }
+                    swimmer.turns_under += 1;
+                    let damage = DROWNING_DAMAGE * swimmer.turns_under;
+                    SufferDamage::new_damage(&mut suffer_damage, entity, damage);
+                    if is_player {
+                        let key = if tired { "log.too_tired_to_swim" } else { "log.going_under" };
+                        gamelog.add_message(Message::new(key).arg("damage", damage));
+                    }
+                },
+                Some(TileType::Lava) if !airborne => {
+                    ashore.push(entity);
+                    let resist = resistances.get(entity);
+                    let damage = resist.map_or(LAVA_DAMAGE, |resist| resist.calculate_damage(LAVA_DAMAGE, DamageType::Fire));
+                    if damage > 0 {
+                        SufferDamage::new_damage(&mut suffer_damage, entity, damage);
+                    }
+                    let burning = StatusEffect::new(StatusEffectType::Burning, LAVA_BURN_TURNS, LAVA_BURN_DAMAGE);
+                    apply_status_effect(entity, burning, &mut status_effects, resist, &mut rng);
+                    if is_player {
+                        gamelog.add_message(Message::new("log.lava_burns").arg("damage", damage));
+                    }
+                },
+                _ => ashore.push(entity),
+            }
+        }
+
+        for entity in ashore {
+            swimming.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<Equipment>();
+        world.register::<ItemProperties>();
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(5));
+        let mut map = Map::new(10, 10, 1);
+        map.set_tile(2, 2, TileType::Water);
+        map.set_tile(4, 4, TileType::Lava);
+        world.insert(map);
+        world
+    }
+
+    #[test]
+    fn test_heavy_gear_makes_swimming_harder() {
+        assert_eq!(swim_difficulty(0.0), SWIM_DIFFICULTY);
+        assert_eq!(swim_difficulty(35.0), SWIM_DIFFICULTY + 3);
+    }
+
+    #[test]
+    fn test_an_exhausted_swimmer_sinks_deeper_each_turn() {
+        let mut world = world();
+        let mut resources = PlayerResources::new(10, 10);
+        resources.stamina = 0;
+        let player = world.create_entity()
+            .with(Player)
+            .with(Position { x: 2, y: 2 })
+            .with(CombatStats { max_hp: 30, hp: 30, defense: 0, power: 1 })
+            .with(resources)
+            .build();
+
+        let mut system = LiquidSystem::new();
+        system.run_now(&world);
+        world.write_resource::<GameStateResource>().turn_count += 1;
+        system.run_now(&world);
+
+        assert_eq!(world.read_storage::<Swimming>().get(player).map(|swimmer| swimmer.turns_under), Some(2));
+        assert_eq!(world.read_storage::<SufferDamage>().get(player).map(|damage| damage.amount), Some(DROWNING_DAMAGE * 3));
+    }
+
+    #[test]
+    fn test_levitation_floats_over_lava_and_water_walking_does_not() {
+        let mut world = world();
+        let mut floating = StatusEffects::new();
+        floating.add_effect(StatusEffect::new(StatusEffectType::Levitating, 5, 1));
+        let mut dry_feet = StatusEffects::new();
+        dry_feet.add_effect(StatusEffect::new(StatusEffectType::WaterWalking, 5, 1));
+        let levitating = world.create_entity()
+            .with(Position { x: 4, y: 4 })
+            .with(CombatStats { max_hp: 30, hp: 30, defense: 0, power: 1 })
+            .with(floating)
+            .build();
+        let water_walking = world.create_entity()
+            .with(Position { x: 4, y: 4 })
+            .with(CombatStats { max_hp: 30, hp: 30, defense: 0, power: 1 })
+            .with(dry_feet)
+            .build();
+
+        LiquidSystem::new().run_now(&world);
+
+        let damage = world.read_storage::<SufferDamage>();
+        assert!(damage.get(levitating).is_none());
+        assert_eq!(damage.get(water_walking).map(|damage| damage.amount), Some(LAVA_DAMAGE));
+        assert!(world.read_storage::<StatusEffects>().get(water_walking).unwrap().has_effect(StatusEffectType::Burning));
+    }
+}
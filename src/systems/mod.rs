@@ -10,6 +10,7 @@ mod progression_persistence;
 mod equipment_system;
 mod resource_system;
 mod death_system;
+mod player_death_system;
 mod enhanced_combat_system;
 mod enhanced_damage_system;
 mod combat_resolution_system;
@@ -18,10 +19,45 @@ mod damage_type_system;
 mod combat_feedback_system;
 mod sound_effect_system;
 mod visual_effects_system;
-mod special_abilities_system;
+mod ability_resolution_system;
 mod ability_targeting_system;
 mod combat_rewards_system;
 mod treasure_system;
+mod terrain_reaction_system;
+mod status_effect_system;
+mod companion_system;
+mod victory_system;
+mod achievement_evaluation_system;
+mod bestiary_system;
+mod ranged_combat_system;
+mod forced_movement_system;
+mod area_of_effect;
+mod corpse_system;
+mod elite_system;
+mod weather_system;
+mod clock_system;
+mod rest_system;
+mod wandering_monster_system;
+mod monster_ai_system;
+mod morale_system;
+mod squad_system;
+mod dijkstra_system;
+mod auto_explore_system;
+mod travel_system;
+mod door_system;
+mod liquid_system;
+mod religion_system;
+mod furniture_system;
+mod fire_system;
+mod gas_system;
+mod climate_system;
+mod rescue_system;
+mod rival_system;
+mod reputation_system;
+mod town_system;
+mod guild_system;
+mod world_event_system;
+mod model_result_system;
 
 pub use visibility_system::VisibilitySystem;
 pub use movement_system::MovementSystem;
@@ -33,17 +69,68 @@ pub use progression_system::{AttributePointSystem, SkillPointSystem, AbilitySyst
 pub use experience_gain_system::ExperienceGainSystem;
 pub use progression_persistence::ProgressionPersistence;
 pub use equipment_system::{EquipmentSystem, EquipmentBonusSystem};
-pub use resource_system::{ResourceRegenerationSystem, StatusEffectSystem, AbilityUsageSystem};
+pub use resource_system::{
+    ResourceRegenerationSystem, regen_amount, is_heavy_weapon, MANA_REGEN_INTERVAL, STAMINA_REGEN_INTERVAL,
+    SPRINT_STAMINA_COST, HEAVY_WEAPON_WEIGHT, HEAVY_ATTACK_STAMINA_COST, EXHAUSTION_PENALTY
+};
 pub use death_system::{DeathSystem, DeadEntityCleanupSystem};
+pub use player_death_system::{PlayerDeathSystem, DeathPenaltySystem, RevivalSystem, GameOverSystem, carried_revival_items, can_be_revived};
 pub use enhanced_combat_system::{EnhancedCombatSystem, InitiativeSystem, TurnOrderSystem};
 pub use enhanced_damage_system::EnhancedDamageSystem;
-pub use combat_resolution_system::CombatResolutionSystem;
+pub use combat_resolution_system::{CombatResolutionSystem, attack_hits, CRITICAL_THREAT_ROLL, FUMBLE_ROLL};
 pub use critical_hit_system::{CriticalHitSystem, CriticalChanceSystem};
 pub use damage_type_system::{DamageTypeSystem, ResistanceManagementSystem};
 pub use combat_feedback_system::CombatFeedbackSystem;
 pub use sound_effect_system::{SoundEffectSystem, ScreenShakeSystem, ScreenShakeState};
-pub use visual_effects_system::{VisualEffectsSystem, ParticleEffectSystem};
-pub use special_abilities_system::SpecialAbilitiesSystem;
+pub use visual_effects_system::{
+    VisualEffectsSystem, ParticleEffectSystem, ParticleEmitter, step_particle, damage_color,
+    PARTICLE_DEPTH_GROUND, PARTICLE_DEPTH_AIR
+};
+pub use ability_resolution_system::{AbilityResolutionSystem, ability_problem, TELEPORT_RANGE, TURN_UNDEAD_RANGE};
 pub use ability_targeting_system::{AbilityTargetingSystem, AbilityCooldownSystem};
 pub use combat_rewards_system::CombatRewardsSystem;
-pub use treasure_system::{TreasureSystem, TreasureGenerationSystem, WantsToInteract};
\ No newline at end of file
+pub use treasure_system::{TreasureSystem, TreasureGenerationSystem, WantsToInteract};
+pub use terrain_reaction_system::TerrainReactionSystem;
+pub use status_effect_system::{StatusEffectSystem, apply_status_effect, status_resist_chance};
+pub use companion_system::{
+    SummonSystem, CompanionLifecycleSystem, CompanionAISystem, kill_credit,
+    MAX_ACTIVE_COMPANIONS, COMPANION_XP_SHARE
+};
+pub use victory_system::{VictorySystem, has_escaped};
+pub use achievement_evaluation_system::AchievementEvaluationSystem;
+pub use bestiary_system::BestiarySystem;
+pub use ranged_combat_system::{RangedCombatSystem, launcher_range};
+pub use forced_movement_system::{ForcedMovementSystem, ForcedPath, Collision, trace_forced_path, CHARGE_RANGE};
+pub use area_of_effect::{AreaTemplate, FriendlyFire, AreaEffect, AreaEffectQueue, AreaEffectSystem, BreathWeaponSystem, AreaDamage, ability_area, ability_damage};
+pub use corpse_system::CorpseSystem;
+pub use elite_system::EliteSplitSystem;
+pub use weather_system::WeatherSystem;
+pub use clock_system::ClockSystem;
+pub use rest_system::{RestSystem, RestSpot, TURNS_PER_MEAL, TURNS_TO_SLEEP};
+pub use wandering_monster_system::{WanderingMonsterSystem, WanderingSpawns};
+pub use monster_ai_system::{
+    MonsterAISystem, Noise, NoiseEvents, loudest_heard, search_spots, FOOTSTEP_NOISE, MELEE_NOISE, BLAST_NOISE, BASH_NOISE
+};
+pub use dijkstra_system::{DijkstraMapSystem, unexplored_edge};
+pub use auto_explore_system::{AutoExploreSystem, explore_step, walk_interruption, WalkInterruption};
+pub use travel_system::TravelSystem;
+pub use door_system::DoorSystem;
+pub use liquid_system::{
+    LiquidSystem, swim_difficulty, equipment_load, SWIM_DIFFICULTY, SWIM_STAMINA_COST, DROWNING_DAMAGE, LAVA_DAMAGE
+};
+pub use squad_system::{SquadSystem, Squads, SquadOrder, focus_target, flank_posts, should_retreat};
+pub use morale_system::{MoraleSystem, escape_route, witness_death, CALL_FOR_HELP_NOISE, SHAKEN_PER_DEATH};
+pub use religion_system::{
+    ReligionSystem, DivineDeeds, Deed, Boon, judge, piety_bonus, altar_near, devote, pray, WRATH_DAMAGE, SMITE_RANGE
+};
+pub use furniture_system::{FurnitureSystem, offerings, SHRINE_VALUE_PER_PIETY, BOOKSHELF_SKILL_XP};
+pub use fire_system::{FireSystem, BURNING_TILE_DAMAGE};
+pub use gas_system::{GasSystem, GasReleases, FLASK_GAS_VOLUME, TRAP_GAS_VOLUME};
+pub use climate_system::{ClimateSystem, climate_effects, exposure_severity, EXPOSURE_GRACE, EXPOSURE_STEP, CLIMATE_PROOF_RESISTANCE};
+pub use rescue_system::{RescueSystem, EscortSystem, ADVENTURER_REWARD_PER_DEPTH, MERCHANT_REWARD_PER_DEPTH};
+pub use rival_system::{RivalSystem, RIVAL_ROOMS_BEFORE_DESCENT, RIVAL_ALLIANCE_FEE_PER_DEPTH, alliance_fee, rival_price};
+pub use reputation_system::{ReputationSystem, Reputation, Faction, Standing, Conduct, sway, MAX_REPUTATION};
+pub use guild_system::GuildSystem;
+pub use world_event_system::{WorldEventSystem, report_world_news};
+pub use model_result_system::ModelResultSystem;
+pub use town_system::{TownsfolkSystem, CrimeSystem, BankSystem, TownWatch, Crime, Training, ware_price, unpaid_wares, SENTENCE_TURNS_PER_GOLD, JAIL_BOARD_PERCENT, RESPEC_LIMIT};
@@ -0,0 +1,108 @@
+use specs::{System, Entities, Read, Write};
+use crate::guild::{finish_narrative, GuildRoster};
+use crate::language_model::{ModelPurpose, ModelQueue, ModelResult};
+use crate::resources::GameLog;
+
+/// Collects whatever the language model has answered since the last update and puts it where it
+/// was asked for, without ever waiting on it. Requests made for entities that have since gone are
+/// dropped first, so the model doesn't spend any time on them
+pub struct ModelResultSystem;
+
+impl<'a> System<'a> for ModelResultSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, ModelQueue>,
+        Write<'a, GuildRoster>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, queue, mut roster, mut gamelog) = data;
+
+        if !queue.has_model() {
+            return;
+        }
+        queue.cancel_dead(|entity| entities.is_alive(entity));
+        for result in queue.poll() {
+            if result.owner.map_or(false, |owner| !entities.is_alive(owner)) {
+                continue;
+            }
+            deliver(result, &mut roster, &mut gamelog);
+        }
+    }
+}
+
+/// Put an answer in its place. A report whose story the model couldn't tell keeps the templates'
+fn deliver(result: ModelResult, roster: &mut GuildRoster, gamelog: &mut GameLog) {
+    match result.purpose {
+        ModelPurpose::Narration(id) => {
+            let report = match roster.ledger.find_mut(id) {
+                Some(report) => report,
+                None => return,
+            };
+            match result.outcome {
+                Ok(response) => {
+                    if let Some(told) = finish_narrative(&response.text) {
+                        report.narrative = told;
+                    }
+                },
+                Err(e) => log::warn!("Falling back to the template narrative: {}", e),
+            }
+            gamelog.add_entry(report.narrative.clone());
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use specs::{World, WorldExt, RunNow};
+    use crate::components::ClassType;
+    use crate::config::LlmConfig;
+    use crate::guild::{Contract, ContractReport};
+    use crate::language_model::{LlmRequest, MockProvider, ModelClient, Priority, ResponseCache, TokenBudget};
+
+    fn report() -> ContractReport {
+        ContractReport {
+            id: 0,
+            day: 2,
+            agent: "Edda".to_string(),
+            class: ClassType::Fighter,
+            traits: Vec::new(),
+            contract: Contract::Patrol,
+            success: true,
+            gold: 40,
+            experience: 30,
+            levels: 0,
+            injury_days: 0,
+            died: false,
+            reagents: 0,
+            renown: 0,
+            milestones: Vec::new(),
+            narrative: "Edda walked the walls.".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_the_models_story_takes_the_templates_place_once_it_is_told() {
+        let mut world = World::new();
+        world.insert(GameLog::new(10));
+        let mut roster = GuildRoster::default();
+        let id = roster.ledger.record(report());
+        world.insert(roster);
+        let client = ModelClient::new(Box::new(MockProvider::with_replies(&["  Edda held the gate\n all night.  "])), 0, TokenBudget::default());
+        let queue = ModelQueue::start(client, LlmConfig::default(), ResponseCache::new(0));
+        queue.submit(LlmRequest::new("Tell it"), Priority::Low, None, ModelPurpose::Narration(id)).unwrap();
+        world.insert(queue);
+
+        let started = Instant::now();
+        while world.read_resource::<GameLog>().entries.is_empty() && started.elapsed() < Duration::from_secs(2) {
+            ModelResultSystem.run_now(&world);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let roster = world.read_resource::<GuildRoster>();
+        assert_eq!(roster.ledger.reports[0].narrative, "Edda held the gate all night.");
+        assert_eq!(world.read_resource::<GameLog>().entries.len(), 1);
+    }
+}
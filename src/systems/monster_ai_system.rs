@@ -0,0 +1,450 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect};
+use crate::components::{
+    AIMode, CombatStats, MonsterAI, Player, Position, Rival, Viewshed, WantsToAttack, WantsToMove,
+    WantsToOperateDoor, DoorAction, OpensDoors, MovementMode, StatusEffects
+};
+use crate::map::{DijkstraMap, DijkstraMaps, DijkstraSource, Map};
+use crate::resources::{GameStateResource, RandomNumberGenerator};
+use crate::ai::behavior_tree::Blackboard;
+use crate::ai::monster_behavior::{MonsterAction, MonsterBehaviors, MonsterCondition};
+use crate::utils::chebyshev;
+use super::squad_system::{SquadOrder, Squads};
+
+/// Tiles away footsteps carry
+pub const FOOTSTEP_NOISE: i32 = 3;
+/// Tiles away the clash of a melee carries
+pub const MELEE_NOISE: i32 = 8;
+/// Tiles away an explosion carries
+pub const BLAST_NOISE: i32 = 12;
+/// Tiles away the crash of a shoulder against a door carries
+pub const BASH_NOISE: i32 = 10;
+/// Turns a monster spends searching before heading home
+const SEARCH_TURNS: i32 = 8;
+/// How far from the noise a room can be and still get searched
+const SEARCH_ROOM_RANGE: i32 = 12;
+/// Rooms checked on each search
+const SEARCH_ROOMS: usize = 2;
+/// How far from home a patrolling monster strays
+const PATROL_RADIUS: i32 = 3;
+
+/// Something loud enough to be heard, and how many tiles away it carries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Noise {
+    pub pos: (i32, i32),
+    pub loudness: i32,
+}
+
+/// Noises made since monsters last got to listen
+#[derive(Debug, Clone, Default)]
+pub struct NoiseEvents {
+    pub noises: Vec<Noise>,
+}
+
+impl NoiseEvents {
+    pub fn emit(&mut self, pos: (i32, i32), loudness: i32) {
+        self.noises.push(Noise { pos, loudness });
+    }
+
+    pub fn take(&mut self) -> Vec<Noise> {
+        std::mem::take(&mut self.noises)
+    }
+}
+
+/// The closest noise a monster at `pos` can make out, if any
+pub fn loudest_heard(noises: &[Noise], pos: (i32, i32), alertness: i32) -> Option<(i32, i32)> {
+    noises.iter()
+        .filter(|noise| chebyshev(noise.pos, pos) <= noise.loudness + alertness)
+        .min_by_key(|noise| chebyshev(noise.pos, pos))
+        .map(|noise| noise.pos)
+}
+
+/// Centres of the rooms around a spot, nearest first, for a searching monster to check
+pub fn search_spots(map: &Map, around: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut rooms: Vec<(i32, i32)> = map.rooms.iter()
+        .map(|room| room.center())
+        .filter(|&center| center != around && chebyshev(center, around) <= SEARCH_ROOM_RANGE)
+        .collect();
+    rooms.sort_by_key(|&center| chebyshev(center, around));
+    rooms.truncate(SEARCH_ROOMS);
+    rooms
+}
+
+/// Moves monsters once per turn by ticking each one's behavior tree: out of the box they hunt what
+/// they see, investigate what they hear, search the rooms nearby when they arrive and find nothing,
+/// and then wander back to their posts. Any adventurer will do as quarry, rival parties included;
+/// each monster goes after the nearest one it can see, and otherwise keeps the player in mind
+pub struct MonsterAISystem {
+    pub last_turn: Option<u32>,
+}
+
+impl MonsterAISystem {
+    pub fn new() -> Self {
+        MonsterAISystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for MonsterAISystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, MonsterAI>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Rival>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, CombatStats>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, WantsToAttack>,
+        WriteStorage<'a, WantsToOperateDoor>,
+        ReadStorage<'a, OpensDoors>,
+        ReadStorage<'a, MovementMode>,
+        ReadStorage<'a, StatusEffects>,
+        ReadExpect<'a, Map>,
+        Read<'a, MonsterBehaviors>,
+        Read<'a, DijkstraMaps>,
+        Write<'a, NoiseEvents>,
+        Read<'a, Squads>,
+        Read<'a, GameStateResource>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut ais,
+            positions,
+            players,
+            rivals,
+            viewsheds,
+            combat_stats,
+            mut wants_move,
+            mut wants_attack,
+            mut wants_door,
+            door_openers,
+            modes,
+            status_effects,
+            map,
+            behaviors,
+            dijkstra_maps,
+            mut noise_events,
+            squads,
+            game_state,
+            mut rng,
+        ) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let noises = noise_events.take();
+        let player = (&entities, &positions, &players).join()
+            .next()
+            .map(|(entity, pos, _)| (entity, (pos.x, pos.y)));
+        let adventurers: Vec<(Entity, (i32, i32))> = (&entities, &positions, &rivals, &combat_stats).join()
+            .filter(|(_, _, _, stats)| stats.hp > 0)
+            .map(|(entity, pos, _, _)| (entity, (pos.x, pos.y)))
+            .chain(player)
+            .collect();
+
+        for (entity, ai, pos, viewshed) in (&entities, &mut ais, &positions, &viewsheds).join() {
+            if combat_stats.get(entity).map_or(false, |stats| stats.hp <= 0) {
+                continue;
+            }
+            let tree = match behaviors.tree(&ai.behavior) {
+                Some(tree) => tree,
+                None => continue,
+            };
+            let here = (pos.x, pos.y);
+            let mode = MovementMode::current(modes.get(entity), status_effects.get(entity));
+            let target = adventurers.iter()
+                .filter(|(_, there)| chebyshev(here, *there) <= viewshed.range && map.has_line_of_sight_as(here, *there, mode))
+                .min_by_key(|(_, there)| chebyshev(here, *there))
+                .copied()
+                .or(player);
+            let mut monster = MonsterBlackboard {
+                entity,
+                here,
+                sight: viewshed.range,
+                mode,
+                ai: &mut *ai,
+                map: &map,
+                target,
+                approach: dijkstra_maps.get(DijkstraSource::Player).filter(|_| target == player),
+                heard: None,
+                noises: &noises,
+                order: squads.orders.get(&entity),
+                wants_move: &mut wants_move,
+                wants_attack: &mut wants_attack,
+                wants_door: if door_openers.contains(entity) { Some(&mut wants_door) } else { None },
+                rng: &mut rng,
+            };
+            let (_, active) = tree.tick(&mut monster);
+            ai.active_node = active;
+        }
+    }
+}
+
+/// One monster's view of the world while its behavior tree runs
+struct MonsterBlackboard<'s, 'a> {
+    entity: Entity,
+    here: (i32, i32),
+    sight: i32,
+    /// Flying things see over boulders and path over chasms, ghosts through walls
+    mode: MovementMode,
+    ai: &'s mut MonsterAI,
+    map: &'s Map,
+    target: Option<(Entity, (i32, i32))>,
+    /// Distances to the player, shared by every monster closing in
+    approach: Option<&'s DijkstraMap>,
+    /// The noise it picked out, once asked
+    heard: Option<(i32, i32)>,
+    noises: &'s [Noise],
+    order: Option<&'s SquadOrder>,
+    wants_move: &'s mut WriteStorage<'a, WantsToMove>,
+    wants_attack: &'s mut WriteStorage<'a, WantsToAttack>,
+    /// Only for monsters that can open doors
+    wants_door: Option<&'s mut WriteStorage<'a, WantsToOperateDoor>>,
+    rng: &'s mut RandomNumberGenerator,
+}
+
+impl<'s, 'a> MonsterBlackboard<'s, 'a> {
+    fn visible_target(&self) -> Option<(Entity, (i32, i32))> {
+        self.target.filter(|&(_, pos)| chebyshev(self.here, pos) <= self.sight && self.map.has_line_of_sight_as(self.here, pos, self.mode))
+    }
+
+    /// Attack the foe if close enough, or close in on it, by way of `post` if given
+    fn engage(&mut self, foe: Entity, foe_pos: (i32, i32), post: Option<(i32, i32)>) {
+        self.ai.mode = AIMode::Hunting;
+        self.ai.goal = Some(foe_pos);
+        if chebyshev(self.here, foe_pos) <= 1 {
+            self.wants_attack.insert(self.entity, WantsToAttack { target: foe })
+                .expect("Unable to insert attack intent");
+        } else if !post.map_or(false, |post| self.step_toward(post)) {
+            // Downhill on the player map steps around other monsters instead of queueing behind them
+            let downhill = self.approach
+                .filter(|_| self.target.map(|(player, _)| player) == Some(foe))
+                .and_then(|approach| approach.downhill(self.map, self.here));
+            match downhill {
+                Some(step) => {
+                    self.wants_move.insert(self.entity, WantsToMove { destination: step })
+                        .expect("Unable to insert move intent");
+                },
+                None => {
+                    self.step_toward(foe_pos);
+                },
+            }
+        }
+    }
+
+    /// Take the first step toward `goal`, opening the door in the way first if it knows how
+    fn step_toward(&mut self, goal: (i32, i32)) -> bool {
+        let opens_doors = self.wants_door.is_some();
+        match self.map.find_path_for(self.here, goal, self.mode, opens_doors).and_then(|path| path.first().copied()) {
+            Some(step) if self.map.is_closed_door(step.0, step.1) => {
+                let doors = match self.wants_door.as_mut() {
+                    Some(doors) => doors,
+                    None => return false,
+                };
+                doors.insert(self.entity, WantsToOperateDoor { target: step, action: DoorAction::Open })
+                    .expect("Unable to insert door intent");
+                true
+            },
+            Some(step) => {
+                self.wants_move.insert(self.entity, WantsToMove { destination: step })
+                    .expect("Unable to insert move intent");
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Head for the goal, and start searching once there or if there is no way to reach it
+    fn investigate(&mut self) {
+        let goal = match self.ai.goal {
+            Some(goal) => goal,
+            None => {
+                self.ai.mode = AIMode::Returning;
+                return;
+            }
+        };
+        if self.here == goal || !self.step_toward(goal) {
+            self.ai.mode = AIMode::Searching;
+            self.ai.search_turns = SEARCH_TURNS;
+            self.ai.search_spots = search_spots(self.map, goal);
+        }
+    }
+}
+
+impl<'s, 'a> Blackboard<MonsterCondition, MonsterAction> for MonsterBlackboard<'s, 'a> {
+    fn check(&mut self, condition: &MonsterCondition) -> bool {
+        match condition {
+            MonsterCondition::InMode(mode) => self.ai.mode == *mode,
+            MonsterCondition::HasPackQuarry => self.order.map_or(false, |order| order.target.is_some()),
+            MonsterCondition::HasPackPost => self.order.map_or(false, |order| order.post.is_some()),
+            MonsterCondition::SeesPlayer => self.visible_target().is_some(),
+            MonsterCondition::HeardNoise => {
+                self.heard = loudest_heard(self.noises, self.here, self.ai.alertness);
+                self.heard.is_some()
+            },
+            MonsterCondition::Chance(odds) => self.rng.roll_dice(1, (*odds).max(1)) == 1,
+        }
+    }
+
+    fn act(&mut self, action: &MonsterAction) -> bool {
+        let (entity, here) = (self.entity, self.here);
+        match action {
+            MonsterAction::Wait => true,
+            MonsterAction::HuntPackQuarry => match self.order.and_then(|order| order.target.map(|target| (target, order.post))) {
+                Some(((foe, foe_pos), post)) => {
+                    self.engage(foe, foe_pos, post);
+                    true
+                },
+                None => false,
+            },
+            MonsterAction::KeepFormation => {
+                let post = match self.order.and_then(|order| order.post) {
+                    Some(post) if matches!(self.ai.mode, AIMode::Patrol | AIMode::Returning) => post,
+                    _ => return false,
+                };
+                if chebyshev(here, post) > 1 {
+                    self.step_toward(post);
+                }
+                true
+            },
+            MonsterAction::Hunt => match self.visible_target() {
+                Some((foe, foe_pos)) => {
+                    self.engage(foe, foe_pos, None);
+                    true
+                },
+                None => false,
+            },
+            MonsterAction::Listen => match self.heard {
+                Some(heard) => {
+                    self.ai.mode = AIMode::Investigating;
+                    self.ai.goal = Some(heard);
+                    self.investigate();
+                    true
+                },
+                None => false,
+            },
+            MonsterAction::LoseTrail | MonsterAction::Investigate => {
+                self.ai.mode = AIMode::Investigating;
+                self.investigate();
+                true
+            },
+            MonsterAction::Search => {
+                let ai = &mut *self.ai;
+                ai.search_turns -= 1;
+                if ai.search_turns <= 0 {
+                    ai.mode = AIMode::Returning;
+                    ai.search_spots.clear();
+                    ai.goal = None;
+                    return true;
+                }
+                while ai.search_spots.first() == Some(&here) {
+                    ai.search_spots.remove(0);
+                }
+                match ai.search_spots.first().copied() {
+                    // A spot there is no way to reach is passed over for the next one
+                    Some(spot) => if !self.step_toward(spot) {
+                        self.ai.search_spots.remove(0);
+                    },
+                    // Nothing left to check but the corners close by
+                    None => {
+                        let anchor = ai.goal.unwrap_or(here);
+                        wander(self.map, self.wants_move, self.rng, entity, here, anchor, 2, self.mode);
+                    },
+                }
+                true
+            },
+            MonsterAction::GiveUp => {
+                self.ai.mode = AIMode::Returning;
+                self.ai.search_spots.clear();
+                self.ai.goal = None;
+                true
+            },
+            MonsterAction::ReturnHome => {
+                let home = self.ai.home;
+                if here == home || !self.step_toward(home) {
+                    self.ai.mode = AIMode::Patrol;
+                }
+                true
+            },
+            MonsterAction::Patrol => {
+                let home = self.ai.home;
+                wander(self.map, self.wants_move, self.rng, entity, here, home, PATROL_RADIUS, self.mode);
+                true
+            },
+            MonsterAction::Flutter => {
+                wander(self.map, self.wants_move, self.rng, entity, here, here, 1, self.mode);
+                true
+            },
+        }
+    }
+}
+
+/// Take the first step along a path to `goal`; false when there is no path
+pub fn step_toward(
+    map: &Map,
+    wants_move: &mut WriteStorage<WantsToMove>,
+    entity: specs::Entity,
+    here: (i32, i32),
+    goal: (i32, i32),
+) -> bool {
+    match map.find_path(here, goal).and_then(|path| path.first().copied()) {
+        Some(step) => {
+            wants_move.insert(entity, WantsToMove { destination: step })
+                .expect("Unable to insert move intent");
+            true
+        },
+        None => false,
+    }
+}
+
+/// Shuffle to a random open tile next to `here` without straying more than `radius` from `anchor`,
+/// never onto ground that would hurt
+#[allow(clippy::too_many_arguments)]
+fn wander(
+    map: &Map,
+    wants_move: &mut WriteStorage<WantsToMove>,
+    rng: &mut RandomNumberGenerator,
+    entity: specs::Entity,
+    here: (i32, i32),
+    anchor: (i32, i32),
+    radius: i32,
+    mode: MovementMode,
+) {
+    let step = (here.0 + rng.range(-1, 1), here.1 + rng.range(-1, 1));
+    let safe = map.can_enter(step.0, step.1, mode) && !map.is_dangerous_for(step.0, step.1, mode);
+    if step != here && safe && chebyshev(step, anchor) <= radius {
+        wants_move.insert(entity, WantsToMove { destination: step })
+            .expect("Unable to insert move intent");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Rect;
+
+    #[test]
+    fn test_only_noises_within_earshot_are_heard() {
+        let noises = vec![
+            Noise { pos: (10, 10), loudness: FOOTSTEP_NOISE },
+            Noise { pos: (20, 10), loudness: MELEE_NOISE },
+        ];
+        assert_eq!(loudest_heard(&noises, (14, 10), 0), Some((20, 10)));
+        assert_eq!(loudest_heard(&noises, (12, 10), 0), Some((10, 10)));
+        assert_eq!(loudest_heard(&noises, (10, 30), 0), None);
+        // Sharp ears pick up the footsteps from further off
+        assert_eq!(loudest_heard(&noises[..1], (15, 10), 2), Some((10, 10)));
+    }
+
+    #[test]
+    fn test_searches_check_the_nearest_rooms_first() {
+        let mut map = Map::new(80, 50, 1);
+        map.rooms = vec![Rect::new(30, 5, 6, 6), Rect::new(4, 4, 4, 4), Rect::new(12, 4, 4, 4), Rect::new(60, 40, 4, 4)];
+        assert_eq!(search_spots(&map, (6, 10)), vec![(6, 6), (14, 6)]);
+        assert!(search_spots(&map, (70, 10)).is_empty());
+    }
+}
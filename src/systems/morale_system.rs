@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect, LazyUpdate};
+use crate::components::{
+    AIMode, CombatStats, LootDrop, LootTable, Monster, MonsterAI, Morale, Name, Player, Position, Renderable,
+    Treasure, TreasureType, Viewshed, WantsToAttack, WantsToMove
+};
+use crate::map::{DijkstraMaps, DijkstraSource, Map};
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+use crate::utils::chebyshev;
+use super::monster_ai_system::{step_toward, NoiseEvents};
+
+/// Tiles away a fleeing monster's cry for help carries
+pub const CALL_FOR_HELP_NOISE: i32 = 10;
+/// Percent added to a monster's flee threshold for each ally it sees fall
+pub const SHAKEN_PER_DEATH: i32 = 15;
+/// The most that watching allies die can add to a flee threshold
+const MAX_SHAKEN: i32 = 45;
+/// One in this many caught monsters that can surrender will do so
+const SURRENDER_ODDS: i32 = 2;
+/// Closer than this to the player, a fleeing monster gets clear before making for the stairs
+const PANIC_RANGE: i32 = 3;
+
+/// Shake a monster's nerve after it watches an ally die
+pub fn witness_death(morale: &mut Morale) {
+    if morale.flee_below.is_some() {
+        morale.shaken = (morale.shaken + SHAKEN_PER_DEATH).min(MAX_SHAKEN);
+    }
+}
+
+/// The way off the level furthest from the player, which is where a fleeing monster heads
+pub fn escape_route(map: &Map, player: Option<(i32, i32)>) -> (i32, i32) {
+    match player {
+        Some(player) if chebyshev(map.entrance, player) > chebyshev(map.exit, player) => map.entrance,
+        _ => map.exit,
+    }
+}
+
+/// Breaks monsters whose nerve fails, runs them for the exits, and lets the cornered ones surrender
+pub struct MoraleSystem {
+    pub last_turn: Option<u32>,
+    /// The fallen whose deaths have already shaken whoever saw them
+    pub mourned: HashSet<Entity>,
+}
+
+impl MoraleSystem {
+    pub fn new() -> Self {
+        MoraleSystem { last_turn: None, mourned: HashSet::new() }
+    }
+}
+
+impl<'a> System<'a> for MoraleSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Morale>,
+        WriteStorage<'a, MonsterAI>,
+        WriteStorage<'a, Monster>,
+        WriteStorage<'a, Renderable>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, LootTable>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, WantsToAttack>,
+        ReadExpect<'a, Map>,
+        Read<'a, DijkstraMaps>,
+        Read<'a, LazyUpdate>,
+        Write<'a, NoiseEvents>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut morales,
+            mut ais,
+            mut monsters,
+            mut renderables,
+            positions,
+            players,
+            viewsheds,
+            combat_stats,
+            names,
+            loot_tables,
+            mut wants_move,
+            mut wants_attack,
+            map,
+            dijkstra_maps,
+            lazy,
+            mut noise_events,
+            game_state,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        // Deaths are seen as they happen, whether or not the turn has moved on
+        self.mourned.retain(|fallen| entities.is_alive(*fallen));
+        let fallen: Vec<(Entity, (i32, i32))> = (&entities, &monsters, &combat_stats, &positions).join()
+            .filter(|(entity, _, stats, _)| stats.hp <= 0 && !self.mourned.contains(entity))
+            .map(|(entity, _, _, pos)| (entity, (pos.x, pos.y)))
+            .collect();
+        for (dead, at) in fallen {
+            self.mourned.insert(dead);
+            for (entity, morale, pos, viewshed, stats) in (&entities, &mut morales, &positions, &viewsheds, &combat_stats).join() {
+                let here = (pos.x, pos.y);
+                if entity != dead && stats.hp > 0 && chebyshev(here, at) <= viewshed.range && map.has_line_of_sight(here, at) {
+                    witness_death(morale);
+                }
+            }
+        }
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let player = (&entities, &positions, &players).join()
+            .next()
+            .map(|(entity, pos, _)| (entity, (pos.x, pos.y)));
+        let escape = escape_route(&map, player.map(|(_, pos)| pos));
+
+        let mut escaped: Vec<Entity> = Vec::new();
+        let mut surrendered: Vec<(Entity, (i32, i32))> = Vec::new();
+
+        for (entity, morale, ai, pos, stats, _) in (&entities, &mut morales, &mut ais, &positions, &combat_stats, &monsters).join() {
+            if stats.hp <= 0 || morale.surrendered {
+                continue;
+            }
+            let here = (pos.x, pos.y);
+            let name = names.get(entity).map_or("monster".to_string(), |name| name.name.clone());
+            let seen = map.is_visible(here.0, here.1);
+
+            if ai.mode != AIMode::Fleeing {
+                if !morale.breaks(stats) {
+                    continue;
+                }
+                ai.mode = AIMode::Fleeing;
+                ai.goal = Some(escape);
+                // The cry brings anything within earshot running
+                noise_events.emit(here, CALL_FOR_HELP_NOISE);
+                if seen {
+                    gamelog.add_entry(format!("The {} panics and flees, shrieking for help!", name));
+                }
+            }
+
+            if here == escape {
+                if seen {
+                    gamelog.add_entry(format!("The {} escapes the level.", name));
+                }
+                escaped.push(entity);
+                continue;
+            }
+
+            // Caught by the player: give up if it can, or fight with its back to the wall
+            if let Some((target, player_pos)) = player {
+                if chebyshev(here, player_pos) <= 1 {
+                    if morale.can_surrender && rng.roll_dice(1, SURRENDER_ODDS) == 1 {
+                        morale.surrendered = true;
+                        gamelog.add_entry(format!("The {} throws down its pack and begs for mercy.", name));
+                        surrendered.push((entity, here));
+                    } else {
+                        wants_attack.insert(entity, WantsToAttack { target })
+                            .expect("Unable to insert attack intent");
+                    }
+                    continue;
+                }
+            }
+
+            // Too close for comfort: get away first, then head for the stairs
+            let panicked = player.map_or(false, |(_, player_pos)| chebyshev(here, player_pos) <= PANIC_RANGE);
+            let away = dijkstra_maps.get(DijkstraSource::Safety)
+                .filter(|_| panicked)
+                .and_then(|safety| safety.downhill(&map, here));
+            match away {
+                Some(step) => {
+                    wants_move.insert(entity, WantsToMove { destination: step })
+                        .expect("Unable to insert move intent");
+                },
+                None => {
+                    step_toward(&map, &mut wants_move, entity, here, escape);
+                },
+            }
+        }
+
+        for entity in escaped {
+            entities.delete(entity).expect("Unable to remove escaped monster");
+        }
+
+        for (entity, at) in surrendered {
+            monsters.remove(entity);
+            ais.remove(entity);
+            if let Some(renderable) = renderables.get_mut(entity) {
+                renderable.fg = crossterm::style::Color::DarkGrey;
+            }
+            let loot_table = loot_tables.get(entity).cloned().unwrap_or_else(|| {
+                let mut table = LootTable::new();
+                table.add_entry(LootDrop::Currency { amount: 5 + rng.roll_dice(1, 10) }, 100);
+                table
+            });
+            let treasure_type = TreasureType::Pack;
+            lazy.create_entity(&entities)
+                .with(Position { x: at.0, y: at.1 })
+                .with(Renderable {
+                    glyph: treasure_type.glyph(),
+                    fg: treasure_type.color(),
+                    bg: crossterm::style::Color::Black,
+                    render_order: 1,
+                })
+                .with(Name { name: "Abandoned Pack".to_string() })
+                .with(Treasure {
+                    treasure_type,
+                    loot_table,
+                    is_opened: false,
+                    requires_key: false,
+                })
+                .build();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watching_allies_die_breaks_nerve_sooner() {
+        let stats = CombatStats { max_hp: 20, hp: 8, defense: 0, power: 4 };
+        let mut goblin = Morale::new(Some(25), true);
+        assert!(!goblin.breaks(&stats));
+
+        witness_death(&mut goblin);
+        assert!(goblin.breaks(&stats));
+
+        for _ in 0..10 {
+            witness_death(&mut goblin);
+        }
+        assert_eq!(goblin.shaken, MAX_SHAKEN);
+        assert!(!goblin.breaks(&CombatStats { hp: 20, ..stats }));
+
+        // The fearless cannot be shaken at all
+        let mut skeleton = Morale::new(None, false);
+        witness_death(&mut skeleton);
+        assert!(!skeleton.breaks(&CombatStats { hp: 1, ..stats }));
+    }
+
+    #[test]
+    fn test_fleeing_monsters_run_for_the_far_exit() {
+        let mut map = Map::new(40, 20, 1);
+        map.entrance = (2, 10);
+        map.exit = (37, 10);
+        assert_eq!(escape_route(&map, Some((30, 10))), (2, 10));
+        assert_eq!(escape_route(&map, Some((5, 10))), (37, 10));
+        assert_eq!(escape_route(&map, None), (37, 10));
+    }
+}
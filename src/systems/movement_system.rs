@@ -1,6 +1,12 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Join, ReadExpect};
-use crate::components::{Position, WantsToMove, BlocksTile};
-use crate::map::Map;
+use specs::{System, WriteStorage, ReadStorage, Entities, Join, ReadExpect, Read, Write};
+use std::collections::HashSet;
+use crate::components::{Position, WantsToMove, BlocksTile, Player, MovementMode, StatusEffects, PlayerResources, Sprinting};
+use crate::localization::Message;
+use crate::map::{Map, TileType, Weather};
+use crate::systems::{NoiseEvents, FOOTSTEP_NOISE, SPRINT_STAMINA_COST};
+use crate::resources::{GameLog, RandomNumberGenerator, GameEvents};
+use crate::achievements::GameEvent;
+use crate::ui::{TutorialEvents, TutorialTrigger};
 
 pub struct MovementSystem;
 
@@ -8,45 +14,87 @@ impl<'a> System<'a> for MovementSystem {
     type SystemData = (
         Entities<'a>,
         WriteStorage<'a, Position>,
-        ReadStorage<'a, WantsToMove>,
+        WriteStorage<'a, WantsToMove>,
         ReadStorage<'a, BlocksTile>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, MovementMode>,
+        ReadStorage<'a, StatusEffects>,
+        WriteStorage<'a, PlayerResources>,
+        WriteStorage<'a, Sprinting>,
         ReadExpect<'a, Map>,
+        Read<'a, Weather>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, NoiseEvents>,
+        Write<'a, TutorialEvents>,
+        Write<'a, GameEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut positions, wants_move, blockers, map) = data;
+        let (entities, mut positions, mut wants_move, blockers, players, modes, status_effects, mut resources, mut sprinting, map, weather, mut gamelog, mut rng, mut noises, mut tutorials, mut events) = data;
+
+        // Tiles already taken by something that blocks movement
+        let mut occupied: HashSet<(i32, i32)> = (&positions, &blockers).join()
+            .map(|(pos, _)| (pos.x, pos.y))
+            .collect();
+        let is_open = |occupied: &HashSet<(i32, i32)>, x: i32, y: i32, mode: MovementMode| {
+            map.can_enter(x, y, mode) && !occupied.contains(&(x, y))
+        };
 
         // Process movement intents
         for (entity, pos, movement) in (&entities, &mut positions, &wants_move).join() {
             let (destination_x, destination_y) = movement.destination;
-            
-            // Check if the destination is valid
-            if map.in_bounds(destination_x, destination_y) {
-                let destination_idx = map.xy_idx(destination_x, destination_y);
-                
-                // Check if the destination is blocked by the map
-                if !map.is_blocked(destination_x, destination_y) {
-                    // Check if the destination is blocked by an entity
-                    let mut blocked = false;
-                    for (blocker_entity, blocker_pos, _) in (&entities, &positions, &blockers).join() {
-                        if blocker_pos.x == destination_x && blocker_pos.y == destination_y {
-                            blocked = true;
-                            break;
-                        }
+            let mode = MovementMode::current(modes.get(entity), status_effects.get(entity));
+            if !is_open(&occupied, destination_x, destination_y, mode) {
+                continue;
+            }
+
+            let (from_x, from_y) = (pos.x, pos.y);
+            let (mut x, mut y) = (destination_x, destination_y);
+            let step = ((x - from_x).signum(), (y - from_y).signum());
+
+            // Ice can carry a walker one tile further than they meant to go, more often under snow;
+            // anything off the ground never touches it
+            let on_ice = !mode.is_airborne() && map.get_tile(x, y) == Some(TileType::Ice);
+            if on_ice && rng.roll_dice(1, 100) <= weather.kind.slip_chance() {
+                let (slide_x, slide_y) = (x + step.0, y + step.1);
+                if is_open(&occupied, slide_x, slide_y, mode) {
+                    x = slide_x;
+                    y = slide_y;
+                    if players.contains(entity) {
+                        gamelog.add_entry("You slip and slide across the ice!".to_string());
                     }
-                    
-                    // If not blocked, move the entity
-                    if !blocked {
-                        pos.x = destination_x;
-                        pos.y = destination_y;
+                }
+            }
+
+            // A sprinter covers a second tile for a little stamina, and stops once it runs short
+            if sprinting.contains(entity) && is_open(&occupied, x + step.0, y + step.1, mode) {
+                let paid = resources.get_mut(entity).map_or(false, |resources| resources.consume_stamina(SPRINT_STAMINA_COST));
+                if paid {
+                    x += step.0;
+                    y += step.1;
+                } else {
+                    sprinting.remove(entity);
+                    if players.contains(entity) {
+                        gamelog.add_message(Message::new("log.too_winded_to_sprint"));
                     }
                 }
             }
+
+            if blockers.contains(entity) {
+                occupied.remove(&(from_x, from_y));
+                occupied.insert((x, y));
+            }
+            pos.x = x;
+            pos.y = y;
+            if players.contains(entity) {
+                noises.emit((x, y), FOOTSTEP_NOISE);
+                tutorials.publish(TutorialTrigger::FirstMovement);
+                events.publish(GameEvent::PlayerMoved);
+            }
         }
-        
+
         // Clean up the WantsToMove components
-        entities.join().for_each(|entity| {
-            let _ = wants_move.remove(entity);
-        });
+        wants_move.clear();
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,14 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, ReadExpect};
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, ReadExpect, Write};
 use crate::components::{
     Position, Player, PlayerInput, WantsToMove, WantsToAttack, WantsToPickupItem,
-    WantsToUseItem, WantsToDropItem, Viewshed
+    WantsToUseItem, WantsToDropItem, Viewshed, WantsToOperateDoor, DoorAction, GameSettings,
+    MovementMode, StatusEffects, PlayerResources
 };
+use crate::items::{Equipment, EquipmentSlot, ItemProperties};
+use crate::localization::Message;
 use crate::map::Map;
+use crate::resources::GameLog;
+use super::resource_system::{is_heavy_weapon, HEAVY_ATTACK_STAMINA_COST};
 
 pub struct PlayerController;
 
@@ -19,7 +24,15 @@ impl<'a> System<'a> for PlayerController {
         ReadStorage<'a, Position>,
         WriteStorage<'a, PlayerInput>,
         WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, WantsToOperateDoor>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, MovementMode>,
+        ReadStorage<'a, StatusEffects>,
+        WriteStorage<'a, PlayerResources>,
+        ReadStorage<'a, Equipment>,
+        ReadStorage<'a, ItemProperties>,
         ReadExpect<'a, Map>,
+        Write<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -34,7 +47,15 @@ impl<'a> System<'a> for PlayerController {
             positions, 
             mut player_input, 
             mut viewsheds,
-            map
+            mut wants_door,
+            settings,
+            modes,
+            status_effects,
+            mut resources,
+            equipment,
+            properties,
+            map,
+            mut gamelog
         ) = data;
 
         // Process player input
@@ -60,7 +81,26 @@ impl<'a> System<'a> for PlayerController {
                     if let Some(target) = attack_target {
                         // Create attack intent
                         wants_attack.insert(entity, WantsToAttack { target }).expect("Failed to insert attack intent");
-                    } else if !map.is_blocked(destination_x, destination_y) {
+
+                        // A heavy weapon costs stamina to swing; short of it, the swing empties what is left
+                        let heavy = equipment.get(entity)
+                            .and_then(|equipment| equipment.get_equipped(&EquipmentSlot::MainHand))
+                            .and_then(|weapon| properties.get(weapon))
+                            .map_or(false, is_heavy_weapon);
+                        if let (true, Some(resources)) = (heavy, resources.get_mut(entity)) {
+                            if !resources.consume_stamina(HEAVY_ATTACK_STAMINA_COST) {
+                                resources.stamina = 0;
+                            }
+                        }
+                    } else if map.is_closed_door(destination_x, destination_y) {
+                        // Walking into a door opens it, unless the player would rather it didn't
+                        if settings.get(entity).map_or(true, |settings| settings.bump_to_open) {
+                            wants_door.insert(entity, WantsToOperateDoor { target: (destination_x, destination_y), action: DoorAction::Open })
+                                .expect("Failed to insert door intent");
+                        } else {
+                            gamelog.add_message(Message::new("log.door_in_the_way"));
+                        }
+                    } else if map.can_enter(destination_x, destination_y, MovementMode::current(modes.get(entity), status_effects.get(entity))) {
                         // Create movement intent
                         wants_move.insert(entity, WantsToMove { destination: (destination_x, destination_y) }).expect("Failed to insert move intent");
                         
@@ -95,7 +135,7 @@ impl<'a> System<'a> for PlayerController {
             // Handle drop item intent
             if let Some(item_idx) = input.drop_intent {
                 // The actual item entity will be resolved in the inventory system
-                wants_drop.insert(entity, WantsToDropItem { item: Entity::from_bits(item_idx as u64) }).expect("Failed to insert drop item intent");
+                wants_drop.insert(entity, WantsToDropItem { item: Entity::from_bits(item_idx as u64), quantity: 1 }).expect("Failed to insert drop item intent");
             }
             
             // Clear input after processing
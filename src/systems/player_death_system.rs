@@ -0,0 +1,355 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read};
+use crate::components::{
+    Player, Name, CombatStats, Experience, LastAttacker, GameSettings, GameMode,
+    DeathState, DeathPenalty, RevivalItem, WantsToRevive, StatusEffects, StatusEffect, StatusEffectType,
+};
+use crate::items::{AdvancedInventory, ItemProperties, ItemStack};
+use crate::localization::Message;
+use crate::resources::{GameLog, GameStateResource};
+
+/// Revival items in `inventory`, with their stats, in pack order
+pub fn carried_revival_items(inventory: Option<&AdvancedInventory>, revival_items: &ReadStorage<RevivalItem>) -> Vec<(Entity, RevivalItem)> {
+    inventory.map_or(Vec::new(), |inventory| {
+        inventory.items.iter()
+            .filter_map(|slot| revival_items.get(slot.entity).map(|item| (slot.entity, item.clone())))
+            .collect()
+    })
+}
+
+/// Whether a dead character still has a way back: a revival left or an item that allows one
+pub fn can_be_revived(state: &DeathState, mode: &GameMode, inventory: Option<&AdvancedInventory>, revival_items: &ReadStorage<RevivalItem>) -> bool {
+    state.can_revive() || (mode.allows_revival_items() && !carried_revival_items(inventory, revival_items).is_empty())
+}
+
+/// Notices the player falling: records how and when, and reaches for any revival item that
+/// works by itself
+pub struct PlayerDeathSystem {}
+
+impl<'a> System<'a> for PlayerDeathSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, LastAttacker>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, DeathState>,
+        WriteStorage<'a, DeathPenalty>,
+        WriteStorage<'a, WantsToRevive>,
+        ReadStorage<'a, AdvancedInventory>,
+        ReadStorage<'a, RevivalItem>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            players,
+            combat_stats,
+            settings,
+            last_attackers,
+            names,
+            mut death_states,
+            mut penalties,
+            mut wants_to_revive,
+            inventories,
+            revival_items,
+            game_state,
+            mut gamelog,
+        ) = data;
+
+        for (entity, _player, stats) in (&entities, &players, &combat_stats).join() {
+            if stats.hp > 0 {
+                continue;
+            }
+            let mode = settings.get(entity).map_or(GameMode::Normal, |settings| settings.game_mode.clone());
+            if !death_states.contains(entity) {
+                death_states.insert(entity, DeathState::for_mode(&mode)).expect("Unable to insert death state");
+            }
+            if !penalties.contains(entity) {
+                penalties.insert(entity, mode.death_penalty()).expect("Unable to insert death penalty");
+            }
+            let state = match death_states.get_mut(entity) {
+                Some(state) => state,
+                None => continue,
+            };
+            if state.is_dead {
+                continue;
+            }
+
+            let cause = last_attackers.get(entity)
+                .and_then(|attacker| names.get(attacker.entity))
+                .map_or("misadventure".to_string(), |name| name.name.clone());
+            state.kill(cause, game_state.turn_count as i32);
+            gamelog.add_message(Message::new("log.you_died"));
+
+            // Something that works by itself brings the player straight back
+            if mode.allows_revival_items() {
+                let automatic = carried_revival_items(inventories.get(entity), &revival_items).into_iter()
+                    .find(|(_, item)| item.auto_use);
+                if let Some((item, _)) = automatic {
+                    wants_to_revive.insert(entity, WantsToRevive { item: Some(item) })
+                        .expect("Unable to insert revival intent");
+                }
+            }
+        }
+    }
+}
+
+/// Takes the death penalty from a player coming back on one of the run's revivals: some of their
+/// experience, and a weakness that wears off with time
+pub struct DeathPenaltySystem {}
+
+impl<'a> System<'a> for DeathPenaltySystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, WantsToRevive>,
+        WriteStorage<'a, DeathState>,
+        ReadStorage<'a, DeathPenalty>,
+        WriteStorage<'a, Experience>,
+        WriteStorage<'a, StatusEffects>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, wants_to_revive, mut death_states, penalties, mut experiences, mut status_effects, mut gamelog) = data;
+
+        for (entity, intent, state, penalty) in (&entities, &wants_to_revive, &mut death_states, &penalties).join() {
+            // Items bring the player back without the penalty
+            if !state.is_dead || state.death_penalty_applied || intent.item.is_some() || !state.can_revive() {
+                continue;
+            }
+            state.death_penalty_applied = true;
+
+            if let Some(experience) = experiences.get_mut(entity) {
+                let lost = (experience.current as f32 * penalty.experience_loss_percentage / 100.0).round() as i32;
+                if lost > 0 {
+                    experience.current -= lost;
+                    gamelog.add_message(Message::new("log.death_xp_lost").arg("xp", lost));
+                }
+            }
+
+            if penalty.penalty_duration <= 0 {
+                continue;
+            }
+            let mut weaknesses = Vec::new();
+            if penalty.temporary_stat_reduction > 0 {
+                weaknesses.push(StatusEffect::new(StatusEffectType::Weakened, penalty.penalty_duration, penalty.temporary_stat_reduction));
+            }
+            if penalty.attribute_penalty > 0 {
+                weaknesses.push(StatusEffect::new(StatusEffectType::Frail, penalty.penalty_duration, penalty.attribute_penalty));
+            }
+            if weaknesses.is_empty() {
+                continue;
+            }
+            if !status_effects.contains(entity) {
+                status_effects.insert(entity, StatusEffects::new()).expect("Unable to insert status effects");
+            }
+            if let Some(effects) = status_effects.get_mut(entity) {
+                for weakness in weaknesses {
+                    effects.add_effect(weakness);
+                }
+            }
+            gamelog.add_message(Message::new("log.death_weakened").arg("turns", penalty.penalty_duration));
+        }
+    }
+}
+
+/// Brings a dead player back as they chose on the revival screen, or as an item decided for them
+pub struct RevivalSystem {}
+
+impl<'a> System<'a> for RevivalSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToRevive>,
+        WriteStorage<'a, DeathState>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, AdvancedInventory>,
+        ReadStorage<'a, RevivalItem>,
+        ReadStorage<'a, ItemProperties>,
+        WriteStorage<'a, ItemStack>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, Name>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_to_revive,
+            mut death_states,
+            mut combat_stats,
+            mut inventories,
+            revival_items,
+            item_properties,
+            mut stacks,
+            settings,
+            names,
+            mut gamelog,
+        ) = data;
+
+        let intents: Vec<(Entity, Option<Entity>)> = (&entities, &wants_to_revive).join()
+            .map(|(entity, intent)| (entity, intent.item))
+            .collect();
+        wants_to_revive.clear();
+
+        for (entity, item) in intents {
+            let state = match death_states.get_mut(entity) {
+                Some(state) if state.is_dead => state,
+                _ => continue,
+            };
+            let max_hp = combat_stats.get(entity).map_or(1, |stats| stats.max_hp);
+            let restored = match item {
+                Some(item) => {
+                    let allowed = settings.get(entity).map_or(true, |settings| settings.game_mode.allows_revival_items());
+                    let inventory = match inventories.get_mut(entity) {
+                        Some(inventory) => inventory,
+                        None => continue,
+                    };
+                    let (slot, revival) = match (inventory.find_item(item), revival_items.get(item)) {
+                        (Some(slot), Some(revival)) if allowed => (slot, revival),
+                        _ => continue,
+                    };
+                    if revival.consumed_on_use {
+                        let weight = item_properties.get(item).map_or(0.0, |props| props.weight);
+                        inventory.remove_item(slot, 1, weight);
+                        if let Some(stack) = stacks.get_mut(item) {
+                            stack.remove(1);
+                        }
+                        // The last of a stack goes with it
+                        if inventory.find_item(item).is_none() {
+                            entities.delete(item).expect("Unable to delete used revival item");
+                        }
+                    }
+                    state.is_dead = false;
+                    let name = names.get(item).map_or("Something".to_string(), |name| name.name.clone());
+                    gamelog.add_message(Message::new("log.revived_by_item").arg("item", name));
+                    revival.revival_power
+                },
+                None => {
+                    if !state.revive() {
+                        continue;
+                    }
+                    let message = if state.max_revival_attempts == i32::MAX {
+                        Message::new("log.revived")
+                    } else {
+                        Message::new("log.revived_counted").arg("left", state.max_revival_attempts - state.revival_attempts)
+                    };
+                    gamelog.add_message(message);
+                    max_hp / 4
+                },
+            };
+            if let Some(stats) = combat_stats.get_mut(entity) {
+                stats.hp = restored.clamp(1, stats.max_hp.max(1));
+            }
+        }
+    }
+}
+
+/// Ends the run once a dead player has no way back
+pub struct GameOverSystem {}
+
+impl<'a> System<'a> for GameOverSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, DeathState>,
+        ReadStorage<'a, WantsToRevive>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, AdvancedInventory>,
+        ReadStorage<'a, RevivalItem>,
+        Write<'a, GameStateResource>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, players, death_states, wants_to_revive, settings, inventories, revival_items, mut game_state) = data;
+
+        for (entity, _player, state) in (&entities, &players, &death_states).join() {
+            if !state.is_dead || wants_to_revive.contains(entity) {
+                continue;
+            }
+            let mode = settings.get(entity).map_or(GameMode::Normal, |settings| settings.game_mode.clone());
+            if !can_be_revived(state, &mode, inventories.get(entity), &revival_items) {
+                game_state.game_over = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::components::register_components;
+    use crate::items::InventorySlot;
+
+    fn setup(mode: GameMode) -> (World, Entity) {
+        let mut world = World::new();
+        register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<ItemProperties>();
+        world.register::<ItemStack>();
+        world.insert(GameLog::new(10));
+        world.insert(GameStateResource::default());
+        let mut experience = Experience::new();
+        experience.current = 100;
+        let player = world.create_entity()
+            .with(Player {})
+            .with(Name { name: "Ada".to_string() })
+            .with(CombatStats { max_hp: 40, hp: 0, defense: 2, power: 5 })
+            .with(experience)
+            .with(GameSettings::new(mode))
+            .with(AdvancedInventory::new(26, 100.0))
+            .build();
+        (world, player)
+    }
+
+    fn run_death(world: &World) {
+        PlayerDeathSystem {}.run_now(world);
+        DeathPenaltySystem {}.run_now(world);
+        RevivalSystem {}.run_now(world);
+        GameOverSystem {}.run_now(world);
+    }
+
+    #[test]
+    fn test_reviving_spends_a_revival_and_takes_the_penalty() {
+        let (mut world, player) = setup(GameMode::Normal);
+        run_death(&world);
+        assert!(world.read_storage::<DeathState>().get(player).unwrap().is_dead);
+        assert!(!world.read_resource::<GameStateResource>().game_over);
+
+        world.write_storage::<WantsToRevive>().insert(player, WantsToRevive { item: None }).unwrap();
+        run_death(&world);
+        world.maintain();
+
+        let state = world.read_storage::<DeathState>().get(player).unwrap().clone();
+        assert!(!state.is_dead);
+        assert_eq!(state.revival_attempts, 1);
+        assert_eq!(world.read_storage::<CombatStats>().get(player).unwrap().hp, 10);
+        assert_eq!(world.read_storage::<Experience>().get(player).unwrap().current, 90);
+        assert!(world.read_storage::<StatusEffects>().get(player).unwrap().has_effect(StatusEffectType::Weakened));
+    }
+
+    #[test]
+    fn test_items_that_work_by_themselves_revive_without_a_penalty_but_not_in_permadeath() {
+        for (mode, revived) in [(GameMode::Hardcore, true), (GameMode::Permadeath, false)] {
+            let (mut world, player) = setup(mode);
+            let feather = world.create_entity()
+                .with(RevivalItem { revival_power: 25, auto_use: true, consumed_on_use: true })
+                .build();
+            world.write_storage::<AdvancedInventory>().get_mut(player).unwrap().items.push(InventorySlot::new(feather, 1));
+
+            run_death(&world);
+            world.maintain();
+
+            assert_eq!(!world.read_storage::<DeathState>().get(player).unwrap().is_dead, revived);
+            assert_eq!(world.read_resource::<GameStateResource>().game_over, !revived);
+            if revived {
+                assert_eq!(world.read_storage::<CombatStats>().get(player).unwrap().hp, 25);
+                assert_eq!(world.read_storage::<Experience>().get(player).unwrap().current, 100);
+                assert!(!world.is_alive(feather));
+            }
+        }
+    }
+}
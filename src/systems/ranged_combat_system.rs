@@ -0,0 +1,243 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use crate::components::{
+    CombatStats, Attacker, Defender, DamageInfo, DamageResistances, Name, Player, Position, Viewshed,
+    Attributes, AttributeType, Skills, SkillType, StatusEffect, StatusEffects, Undead
+};
+use crate::items::{
+    Ammunition, WantsToShoot, Equipment, EquipmentSlot, ItemProperties, ItemType, ItemStack,
+    AdvancedInventory, EquipmentWear, WearReason, AmmoKind
+};
+use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::rendering::{Animation, AnimationQueue};
+use crate::systems::{apply_status_effect, attack_hits};
+
+/// Furthest a launcher can shoot, in tiles
+pub fn launcher_range(kind: AmmoKind) -> i32 {
+    match kind {
+        AmmoKind::Arrow => 8,
+        AmmoKind::Bolt => 10,
+    }
+}
+
+/// Resolves shots from bows and crossbows, spending ammunition and applying its material's effects
+pub struct RangedCombatSystem;
+
+impl<'a> System<'a> for RangedCombatSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToShoot>,
+        WriteStorage<'a, Equipment>,
+        ReadStorage<'a, Ammunition>,
+        WriteStorage<'a, ItemStack>,
+        ReadStorage<'a, ItemProperties>,
+        WriteStorage<'a, AdvancedInventory>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Attacker>,
+        ReadStorage<'a, Defender>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        ReadStorage<'a, DamageResistances>,
+        ReadStorage<'a, Undead>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        WriteStorage<'a, DamageInfo>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, EquipmentWear>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, AnimationQueue>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_shoot,
+            mut equipment,
+            ammunition,
+            mut stacks,
+            item_properties,
+            mut inventories,
+            combat_stats,
+            attackers,
+            defenders,
+            attributes,
+            skills,
+            resistances,
+            undead,
+            positions,
+            viewsheds,
+            mut damage_info,
+            mut status_effects,
+            mut equipment_wear,
+            names,
+            players,
+            mut gamelog,
+            mut rng,
+            mut animations,
+        ) = data;
+
+        let shots: Vec<(Entity, WantsToShoot)> = (&entities, &wants_shoot).join()
+            .map(|(shooter, shot)| (shooter, shot.clone()))
+            .collect();
+        wants_shoot.clear();
+
+        for (shooter, shot) in shots {
+            let is_player = players.get(shooter).is_some();
+            let gear = match equipment.get_mut(shooter) {
+                Some(gear) => gear,
+                None => continue,
+            };
+
+            // A bow or crossbow must be in hand
+            let launcher = gear.get_equipped(&EquipmentSlot::MainHand)
+                .and_then(|weapon| item_properties.get(weapon))
+                .and_then(|props| match &props.item_type {
+                    ItemType::Weapon(weapon_type) => AmmoKind::for_weapon(weapon_type),
+                    _ => None,
+                });
+            let launcher = match launcher {
+                Some(kind) => kind,
+                None => {
+                    if is_player {
+                        gamelog.add_entry("You have nothing to shoot with.".to_string());
+                    }
+                    continue;
+                }
+            };
+
+            let ammo_entity = match shot.ammo.or_else(|| gear.get_equipped(&EquipmentSlot::Quiver)) {
+                Some(ammo) => ammo,
+                None => {
+                    if is_player {
+                        gamelog.add_entry("Your quiver is empty.".to_string());
+                    }
+                    continue;
+                }
+            };
+            let ammo = match ammunition.get(ammo_entity) {
+                Some(ammo) if ammo.kind == launcher => ammo.clone(),
+                _ => {
+                    if is_player {
+                        gamelog.add_entry(format!("That won't fit your {}.", if launcher == AmmoKind::Arrow { "bow" } else { "crossbow" }));
+                    }
+                    continue;
+                }
+            };
+
+            let in_range = match (positions.get(shooter), positions.get(shot.target)) {
+                (Some(from), Some(to)) => {
+                    let distance = (((from.x - to.x).pow(2) + (from.y - to.y).pow(2)) as f32).sqrt();
+                    let visible = viewsheds.get(shooter)
+                        .map_or(true, |viewshed| viewshed.visible_tiles.contains(&(to.x, to.y)));
+                    visible && distance <= launcher_range(launcher) as f32
+                },
+                _ => false,
+            };
+            if !in_range {
+                if is_player {
+                    gamelog.add_entry("You can't get a clear shot at that.".to_string());
+                }
+                continue;
+            }
+
+            if let (Some(from), Some(to)) = (positions.get(shooter), positions.get(shot.target)) {
+                animations.push(Animation::Projectile {
+                    from: (from.x, from.y),
+                    to: (to.x, to.y),
+                    color: ammo.material.color(),
+                });
+            }
+
+            // Every shot spends one piece, hit or miss
+            let emptied = match stacks.get_mut(ammo_entity) {
+                Some(stack) => {
+                    stack.quantity -= 1;
+                    stack.quantity <= 0
+                },
+                None => true,
+            };
+            if let Some(inventory) = inventories.get_mut(shooter) {
+                if let Some(slot) = inventory.find_item(ammo_entity) {
+                    let weight = item_properties.get(ammo_entity).map_or(0.0, |props| props.weight);
+                    inventory.remove_item(slot, 1, weight);
+                }
+            }
+            if emptied {
+                if gear.get_equipped(&EquipmentSlot::Quiver) == Some(ammo_entity) {
+                    gear.unequip_item(&EquipmentSlot::Quiver);
+                }
+                entities.delete(ammo_entity).expect("Unable to delete spent ammunition");
+                if is_player {
+                    gamelog.add_entry(format!("That was your last {}.", ammo.name()));
+                }
+            }
+
+            let shooter_name = names.get(shooter).map_or("Someone".to_string(), |n| n.name.clone());
+            let target_name = names.get(shot.target).map_or("something".to_string(), |n| n.name.clone());
+
+            let shooter_stats = match combat_stats.get(shooter) {
+                Some(stats) => stats,
+                None => continue,
+            };
+            let dex_modifier = attributes.get(shooter)
+                .map_or(0, |attrs| attrs.get_modifier(AttributeType::Dexterity));
+            let skill_level = skills.get(shooter)
+                .map_or(0, |skills| skills.get_skill_level(SkillType::RangedWeapons));
+
+            // Attack roll against the target's armor class, as in melee but driven by Dexterity
+            let natural_roll = rng.roll_dice(1, 20);
+            let attack = natural_roll
+                + dex_modifier
+                + skill_level
+                + shooter_stats.power / 2
+                + attackers.get(shooter).map_or(0, |a| a.attack_bonus);
+            let defense = defenders.get(shot.target).map_or(10, |d| d.armor_class)
+                + attributes.get(shot.target).map_or(0, |attrs| attrs.get_modifier(AttributeType::Dexterity))
+                + combat_stats.get(shot.target).map_or(0, |stats| stats.defense);
+
+            if !attack_hits(natural_roll, attack, defense) {
+                gamelog.add_entry(format!("{}'s {} misses {}.", shooter_name, ammo.name(), target_name));
+                continue;
+            }
+
+            let base = shooter_stats.power + dex_modifier + skill_level / 2;
+            let mut damage = ammo.hit_damage(base, undead.contains(shot.target));
+            let is_critical = attackers.get(shooter).map_or(false, |a| a.is_critical_hit(&mut rng));
+            if is_critical {
+                damage = (damage as f32 * attackers.get(shooter).map_or(2.0, |a| a.critical_multiplier)) as i32;
+            }
+            let penetration = attackers.get(shooter).map_or(0, |a| a.armor_penetration);
+            damage = i32::max(1, damage - defenders.get(shot.target).map_or(0, |d| d.effective_damage_reduction(penetration)));
+
+            let damage_type = ammo.material.damage_type();
+            let final_damage = match resistances.get(shot.target) {
+                Some(resist) => resist.calculate_damage(damage, damage_type),
+                None => damage,
+            };
+
+            gamelog.add_entry(format!("{}'s {} {} {} for {} {} damage!",
+                shooter_name, ammo.name(), if is_critical { "pierces" } else { "hits" },
+                target_name, final_damage, damage_type.name()));
+
+            if let Some((effect_type, duration, magnitude)) = ammo.material.on_hit_status() {
+                let effect = StatusEffect::new(effect_type, duration, magnitude);
+                if apply_status_effect(shot.target, effect, &mut status_effects, resistances.get(shot.target), &mut rng) {
+                    gamelog.add_entry(format!("{} is {}!", target_name, effect_type.name().to_lowercase()));
+                }
+            }
+
+            EquipmentWear::add(&mut equipment_wear, shooter, WearReason::Attack);
+            EquipmentWear::add(&mut equipment_wear, shot.target, WearReason::Struck);
+
+            damage_info.insert(shot.target, DamageInfo {
+                base_damage: final_damage,
+                damage_type,
+                source: shooter,
+                is_critical,
+                penetration,
+            }).expect("Failed to insert damage info");
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, World, WorldExt};
+use crate::components::{
+    AbilityType, Altar, CombatStats, Deity, Monster, Piety, Player, Position, StatusEffect, StatusEffectType,
+    StatusEffects, SufferDamage, Viewshed, MAX_PIETY
+};
+use crate::items::{AdvancedInventory, ConsumableFactory, ItemFactory, ItemProperties, PotionPotency};
+use crate::localization::Message;
+use crate::resources::{GameLog, RandomNumberGenerator};
+use super::status_effect_system::apply_status_effect;
+
+/// Damage a god deals to whoever falls out of its favour
+pub const WRATH_DAMAGE: i32 = 8;
+/// How long the god's curse lingers
+const WRATH_TURNS: i32 = 10;
+/// Furthest away a smite can find its mark
+pub const SMITE_RANGE: i32 = 8;
+
+/// Something done that a god might care about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deed {
+    Slew { undead: bool, boss: bool },
+    Butchered,
+    RaisedDead,
+    /// Used an ability; `spell` when it was paid for in mana
+    Used { ability: AbilityType, spell: bool },
+    /// Gave gold to the temple in the god's name
+    Donated { gold: i32 },
+}
+
+/// Deeds done since the gods last looked
+#[derive(Debug, Clone, Default)]
+pub struct DivineDeeds {
+    pub deeds: Vec<(Entity, Deed)>,
+}
+
+impl DivineDeeds {
+    pub fn publish(&mut self, doer: Entity, deed: Deed) {
+        self.deeds.push((doer, deed));
+    }
+
+    pub fn take(&mut self) -> Vec<(Entity, Deed)> {
+        std::mem::take(&mut self.deeds)
+    }
+}
+
+/// Piety `deity` grants for `deed`; below zero is a transgression
+pub fn judge(deity: Deity, deed: Deed) -> i32 {
+    match (deity, deed) {
+        (Deity::Aurelion, Deed::Slew { undead: true, .. }) => 5,
+        (Deity::Aurelion, Deed::RaisedDead) => -25,
+        (Deity::Khorga, Deed::Slew { boss: true, .. }) => 10,
+        (Deity::Khorga, Deed::Slew { .. }) => 2,
+        (Deity::Khorga, Deed::Used { spell: true, .. }) => -3,
+        (Deity::Sylvara, Deed::Butchered) => 3,
+        (Deity::Sylvara, Deed::Slew { undead: true, .. }) => 2,
+        (Deity::Sylvara, Deed::Used { ability: AbilityType::AnimalCompanion, .. }) => 2,
+        (Deity::Sylvara, Deed::Used { ability: AbilityType::Fireball, .. }) => -10,
+        (Deity::Sylvara, Deed::RaisedDead) => -10,
+        // Khorga would rather be paid in blood
+        (Deity::Khorga, Deed::Donated { gold }) => gold / 20,
+        (_, Deed::Donated { gold }) => gold / 10,
+        _ => 0,
+    }
+}
+
+/// How much stronger a worshipper's Cleric abilities are
+pub fn piety_bonus(piety: Option<&Piety>) -> i32 {
+    piety.map_or(0, |piety| piety.bonus())
+}
+
+/// What a worshipper can ask of their god, at a price in piety
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boon {
+    Heal,
+    Smite,
+    Gift,
+}
+
+impl Boon {
+    pub fn all() -> [Boon; 3] {
+        [Boon::Heal, Boon::Smite, Boon::Gift]
+    }
+
+    pub fn cost(&self) -> i32 {
+        match self {
+            Boon::Heal => 20,
+            Boon::Smite => 30,
+            Boon::Gift => 50,
+        }
+    }
+
+    /// The locale key for the boon's name
+    pub fn key(&self) -> &'static str {
+        match self {
+            Boon::Heal => "prayer.heal",
+            Boon::Smite => "prayer.smite",
+            Boon::Gift => "prayer.gift",
+        }
+    }
+}
+
+/// The god's curse on whoever has angered it
+fn wrath_effect(deity: Deity) -> StatusEffect {
+    match deity {
+        Deity::Aurelion => StatusEffect::new(StatusEffectType::Blinded, WRATH_TURNS, 1),
+        Deity::Khorga => StatusEffect::new(StatusEffectType::Cursed, WRATH_TURNS, 2),
+        Deity::Sylvara => StatusEffect::new(StatusEffectType::Poisoned, WRATH_TURNS, 2),
+    }
+}
+
+fn strike_down(
+    deity: Deity,
+    victim: Entity,
+    suffer_damage: &mut WriteStorage<SufferDamage>,
+    status_effects: &mut WriteStorage<StatusEffects>,
+    rng: &mut RandomNumberGenerator,
+) {
+    SufferDamage::new_damage(suffer_damage, victim, WRATH_DAMAGE);
+    // No one resists a god
+    apply_status_effect(victim, wrath_effect(deity), status_effects, None, rng);
+}
+
+/// Lets each god weigh what its worshippers have done: piety for what pleases it, less for
+/// what does not, and its wrath on anyone who falls below nothing
+pub struct ReligionSystem {}
+
+impl<'a> System<'a> for ReligionSystem {
+    type SystemData = (
+        WriteStorage<'a, Piety>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, SufferDamage>,
+        Write<'a, DivineDeeds>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut pieties, players, mut status_effects, mut suffer_damage, mut deeds, mut gamelog, mut rng) = data;
+
+        for (doer, deed) in deeds.take() {
+            let piety = match pieties.get_mut(doer) {
+                Some(piety) => piety,
+                None => continue,
+            };
+            let change = judge(piety.deity, deed);
+            if change == 0 {
+                continue;
+            }
+            let standing = piety.standing();
+            piety.piety = (piety.piety + change).min(MAX_PIETY);
+            let is_player = players.contains(doer);
+
+            if change > 0 {
+                if is_player && piety.standing() != standing {
+                    gamelog.add_message(Message::new("log.piety_standing")
+                        .arg("god", piety.deity.name())
+                        .arg("standing", piety.standing()));
+                }
+                continue;
+            }
+            if is_player {
+                gamelog.add_message(Message::new("log.transgression").arg("god", piety.deity.name()));
+            }
+            if piety.piety < 0 {
+                piety.piety = 0;
+                strike_down(piety.deity, doer, &mut suffer_damage, &mut status_effects, &mut rng);
+                if is_player {
+                    gamelog.add_message(Message::new("log.divine_wrath").arg("god", piety.deity.name()).arg("damage", WRATH_DAMAGE));
+                }
+            }
+        }
+    }
+}
+
+/// The altar at or beside `pos`, if there is one
+pub fn altar_near(world: &World, pos: (i32, i32)) -> Option<(Entity, Deity)> {
+    let entities = world.entities();
+    let altars = world.read_storage::<Altar>();
+    let positions = world.read_storage::<Position>();
+    (&entities, &altars, &positions).join()
+        .find(|(_, _, at)| (at.x - pos.0).abs() <= 1 && (at.y - pos.1).abs() <= 1)
+        .map(|(entity, altar, _)| (entity, altar.deity))
+}
+
+/// Take `deity` as the worshipper's god, starting from no piety. Whoever they turned away from
+/// strikes them down
+pub fn devote(world: &mut World, worshipper: Entity, deity: Deity) -> Message {
+    let forsaken = world.read_storage::<Piety>().get(worshipper).map(|piety| piety.deity).filter(|old| *old != deity);
+    world.write_storage::<Piety>().insert(worshipper, Piety::new(deity)).expect("Unable to insert piety");
+
+    match forsaken {
+        Some(old) => {
+            let mut rng = world.write_resource::<RandomNumberGenerator>();
+            strike_down(old, worshipper, &mut world.write_storage::<SufferDamage>(), &mut world.write_storage::<StatusEffects>(), &mut rng);
+            Message::new("log.forsake_god").arg("old", old.name()).arg("god", deity.name()).arg("damage", WRATH_DAMAGE)
+        },
+        None => Message::new("log.devoted").arg("god", deity.name()).arg("title", deity.title()),
+    }
+}
+
+/// Ask the worshipper's god for `boon`, paying its price in piety
+pub fn pray(world: &mut World, worshipper: Entity, boon: Boon) -> Result<Message, Message> {
+    let piety = match world.read_storage::<Piety>().get(worshipper) {
+        Some(piety) => piety.clone(),
+        None => return Err(Message::new("log.no_god")),
+    };
+    let god = piety.deity;
+    if piety.piety < boon.cost() {
+        return Err(Message::new("log.not_enough_piety").arg("god", god.name()).arg("cost", boon.cost()));
+    }
+
+    let answer = match boon {
+        Boon::Heal => {
+            let mut combat_stats = world.write_storage::<CombatStats>();
+            let stats = match combat_stats.get_mut(worshipper) {
+                Some(stats) => stats,
+                None => return Err(Message::new("log.prayer_unanswered").arg("god", god.name())),
+            };
+            let healed = (stats.max_hp / 2).min(stats.max_hp - stats.hp).max(0);
+            stats.hp += healed;
+            Message::new("log.boon_heal").arg("god", god.name()).arg("hp", healed)
+        },
+        Boon::Smite => {
+            let target = match smite_target(world, worshipper) {
+                Some(target) => target,
+                None => return Err(Message::new("log.nothing_to_smite").arg("god", god.name())),
+            };
+            let damage = {
+                let mut rng = world.write_resource::<RandomNumberGenerator>();
+                rng.roll_dice(3, 8) + piety.bonus() * 2
+            };
+            SufferDamage::new_damage(&mut world.write_storage::<SufferDamage>(), target, damage);
+            Message::new("log.boon_smite").arg("god", god.name()).arg("damage", damage)
+        },
+        Boon::Gift => {
+            let gift = give_gift(world, worshipper, god);
+            let name = world.read_storage::<ItemProperties>().get(gift).map_or("gift".to_string(), |props| props.name.clone());
+            Message::new("log.boon_gift").arg("god", god.name()).arg("item", name)
+        },
+    };
+
+    if let Some(piety) = world.write_storage::<Piety>().get_mut(worshipper) {
+        piety.piety -= boon.cost();
+    }
+    Ok(answer)
+}
+
+/// The nearest monster the worshipper can see, within smiting range
+fn smite_target(world: &World, worshipper: Entity) -> Option<Entity> {
+    let entities = world.entities();
+    let monsters = world.read_storage::<Monster>();
+    let positions = world.read_storage::<Position>();
+    let stats = world.read_storage::<CombatStats>();
+    let viewsheds = world.read_storage::<Viewshed>();
+    let origin = positions.get(worshipper)?;
+    let viewshed = viewsheds.get(worshipper)?;
+    (&entities, &monsters, &positions, &stats).join()
+        .filter(|(_, _, _, stats)| stats.hp > 0)
+        .filter(|(_, _, pos, _)| viewshed.visible_tiles.contains(&(pos.x, pos.y)))
+        .map(|(entity, _, pos, _)| (entity, (pos.x - origin.x).abs().max((pos.y - origin.y).abs())))
+        .filter(|(_, distance)| *distance <= SMITE_RANGE)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(entity, _)| entity)
+}
+
+/// Something from the god's own hand, put straight in the worshipper's pack
+fn give_gift(world: &mut World, worshipper: Entity, deity: Deity) -> Entity {
+    let position = Position { x: 0, y: 0 };
+    let mut rng = world.read_resource::<RandomNumberGenerator>().fork("divine_gift");
+    let gift = match deity {
+        Deity::Aurelion => ConsumableFactory::new().create_health_potion(world, position, PotionPotency::Greater),
+        Deity::Khorga => ItemFactory::new().create_random_weapon(world, position, &mut rng),
+        Deity::Sylvara => ConsumableFactory::new().create_regeneration_potion(world, position, 20.0, 2),
+    };
+    world.write_storage::<Position>().remove(gift);
+    let weight = world.read_storage::<ItemProperties>().get(gift).map_or(0.0, |props| props.weight);
+    if let Some(inventory) = world.write_storage::<AdvancedInventory>().get_mut(worshipper) {
+        inventory.add_item(gift, 1, weight);
+    }
+    gift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{Builder, RunNow};
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<ItemProperties>();
+        world.register::<AdvancedInventory>();
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(3));
+        world.insert(DivineDeeds::default());
+        world
+    }
+
+    #[test]
+    fn test_gods_reward_what_they_love_and_punish_what_they_hate() {
+        let mut world = world();
+        let mut devout = Piety::new(Deity::Aurelion);
+        devout.piety = 10;
+        let player = world.create_entity().with(Player).with(devout).build();
+
+        world.write_resource::<DivineDeeds>().publish(player, Deed::Slew { undead: true, boss: false });
+        world.write_resource::<DivineDeeds>().publish(player, Deed::Butchered);
+        ReligionSystem {}.run_now(&world);
+        assert_eq!(world.read_storage::<Piety>().get(player).unwrap().piety, 15);
+        assert!(world.read_storage::<SufferDamage>().get(player).is_none());
+
+        // Necromancy costs more favour than there is, and the god strikes
+        world.write_resource::<DivineDeeds>().publish(player, Deed::RaisedDead);
+        ReligionSystem {}.run_now(&world);
+        assert_eq!(world.read_storage::<Piety>().get(player).unwrap().piety, 0);
+        assert_eq!(world.read_storage::<SufferDamage>().get(player).map(|damage| damage.amount), Some(WRATH_DAMAGE));
+        assert!(world.read_storage::<StatusEffects>().get(player).unwrap().has_effect(StatusEffectType::Blinded));
+    }
+
+    #[test]
+    fn test_boons_cost_piety_and_forsaking_a_god_angers_it() {
+        let mut world = world();
+        let player = world.create_entity()
+            .with(Player)
+            .with(CombatStats { max_hp: 40, hp: 10, defense: 0, power: 1 })
+            .build();
+
+        assert!(pray(&mut world, player, Boon::Heal).is_err());
+        devote(&mut world, player, Deity::Khorga);
+        assert!(pray(&mut world, player, Boon::Heal).is_err());
+
+        world.write_storage::<Piety>().get_mut(player).unwrap().piety = Boon::Heal.cost() + 5;
+        assert!(pray(&mut world, player, Boon::Heal).is_ok());
+        assert_eq!(world.read_storage::<CombatStats>().get(player).unwrap().hp, 30);
+        assert_eq!(world.read_storage::<Piety>().get(player).unwrap().piety, 5);
+
+        devote(&mut world, player, Deity::Sylvara);
+        assert_eq!(world.read_storage::<Piety>().get(player), Some(&Piety::new(Deity::Sylvara)));
+        assert!(world.read_storage::<StatusEffects>().get(player).unwrap().has_effect(StatusEffectType::Cursed));
+    }
+}
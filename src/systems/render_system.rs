@@ -1,8 +1,17 @@
-use specs::{System, ReadStorage, ReadExpect, Join};
-use crate::components::{Position, Renderable, Player};
-use crate::map::Map;
-use crate::resources::GameLog;
-use crate::rendering::RenderContext;
+use specs::{System, ReadStorage, ReadExpect, Read, Write, Join};
+use std::time::Instant;
+use crate::components::{
+    Position, Renderable, Player, Name, CombatStats, PlayerResources, Experience, StatusEffects, Monster,
+    GameSettings, ParticleEffect
+};
+use crate::map::{Map, Weather};
+use crate::resources::{GameLog, GameStateResource, GameClock};
+use crate::rendering::{RenderContext, SidebarInfo, SidebarEnemy, SidebarStatus, AnimationQueue, MotionSettings};
+use crate::ui::{StatusIconRegistry, is_expiring};
+use crate::game_state::HealthEstimate;
+use crate::systems::ScreenShakeState;
+use crate::localization;
+use crate::config::GameConfig;
 
 pub struct RenderSystem {
     pub context: RenderContext,
@@ -21,7 +30,7 @@ impl RenderSystem {
         
         // Update camera if it exists
         if let Some(camera) = &mut self.context.camera {
-            camera.resize(width as i32, height as i32);
+            camera.resize(crate::rendering::map_viewport_width(width) as i32, height as i32);
         }
     }
 }
@@ -31,12 +40,51 @@ impl<'a> System<'a> for RenderSystem {
         ReadStorage<'a, Position>,
         ReadStorage<'a, Renderable>,
         ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, PlayerResources>,
+        ReadStorage<'a, Experience>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, GameSettings>,
+        ReadStorage<'a, ParticleEffect>,
         ReadExpect<'a, Map>,
         ReadExpect<'a, GameLog>,
+        Read<'a, GameStateResource>,
+        Read<'a, ScreenShakeState>,
+        Write<'a, AnimationQueue>,
+        Write<'a, Weather>,
+        Read<'a, GameClock>,
+        Read<'a, GameConfig>,
+        Read<'a, MotionSettings>,
+        Read<'a, StatusIconRegistry>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (positions, renderables, players, map, game_log) = data;
+        let (
+            positions,
+            renderables,
+            players,
+            names,
+            combat_stats,
+            player_resources,
+            experience,
+            status_effects,
+            monsters,
+            settings,
+            particles,
+            map,
+            game_log,
+            game_state,
+            screen_shake,
+            mut animations,
+            mut weather,
+            clock,
+            config,
+            motion,
+            status_icons,
+        ) = data;
+        self.context.effect_manager.motion = *motion;
 
         // Clear the screen
         self.context.clear();
@@ -48,8 +96,18 @@ impl<'a> System<'a> for RenderSystem {
             break;
         }
 
+        // Ease the camera towards the player, jolted by any screen shake
+        self.context.update_camera(&map, player_pos, (screen_shake.offset_x, screen_shake.offset_y));
+
         // Render the map
         self.context.render_map(&map, player_pos);
+        
+        // Slow terminals can leave out the weather overlays
+        let weather_effects = !(&players, &settings).join().any(|(_, settings)| settings.disable_weather_effects);
+        weather.tick_frame();
+        if weather_effects {
+            self.context.render_lightning(&weather, &map, player_pos);
+        }
 
         // Collect entities with position and renderable components
         let mut rendering_data = Vec::new();
@@ -60,20 +118,85 @@ impl<'a> System<'a> for RenderSystem {
         // Sort by render order
         rendering_data.sort_by(|a, b| a.1.render_order.cmp(&b.1.render_order));
 
+        // Ground-level particles go under creatures, the rest over everything
+        let mut particle_data: Vec<ParticleEffect> = particles.join().cloned().collect();
+        particle_data.sort_by_key(|particle| particle.depth);
+        let split = particle_data.partition_point(|particle| particle.depth < 0);
+        self.context.render_particles(&particle_data[..split], &map, player_pos);
+
         // Render entities
         self.context.render_entities(&rendering_data, &map, player_pos);
         
+        // Play back this turn's attacks and deaths one at a time
+        if let Some((_, settings)) = (&players, &settings).join().next() {
+            animations.configure(settings.animation_speed, settings.skip_animations);
+        }
+        animations.update(Instant::now());
+        self.context.render_animation(&animations.cells(), &map, player_pos);
+        self.context.render_particles(&particle_data[split..], &map, player_pos);
+        if weather_effects {
+            self.context.render_weather(&weather, &map, player_pos);
+        }
+        
         // Update and render effects
         self.context.update_effects();
         self.context.render_effects(&map, player_pos);
 
-        // Get player stats (placeholder for now)
-        let player_stats = "HP: 30/30 | Mana: 10/10";
+        // Gather the player's vitals and what they can see for the sidebar
+        let mut sidebar = SidebarInfo {
+            location: format!("{}, depth {}", game_state.location.branch.name(), game_state.location.depth),
+            turn: game_state.turn_count,
+            clock: clock.label(),
+            night: clock.is_night(),
+            text_labels: config.accessibility.screen_reader,
+            ..Default::default()
+        };
+        if let Some((_, name, stats)) = (&players, names.maybe(), &combat_stats).join().next() {
+            sidebar.name = name.map_or("You".to_string(), |n| n.name.clone());
+            sidebar.hp = stats.hp;
+            sidebar.max_hp = stats.max_hp;
+        }
+        if let Some((_, exp)) = (&players, &experience).join().next() {
+            sidebar.level = exp.level;
+            sidebar.xp_progress = exp.progress_percentage();
+        }
+        if let Some((_, resources)) = (&players, &player_resources).join().next() {
+            sidebar.mana = Some((resources.mana, resources.max_mana));
+            sidebar.stamina = Some((resources.stamina, resources.max_stamina));
+        }
+        if let Some((_, status)) = (&players, &status_effects).join().next() {
+            sidebar.statuses = status.effects.iter()
+                .map(|effect| SidebarStatus {
+                    name: effect.effect_type.name().to_string(),
+                    icon: status_icons.icon(effect.effect_type),
+                    turns: effect.duration,
+                    expiring: is_expiring(effect),
+                })
+                .collect();
+        }
+
+        let mut enemies: Vec<(i32, SidebarEnemy)> = (&monsters, &positions, &renderables, &combat_stats, names.maybe())
+            .join()
+            .filter(|(_, pos, _, stats, _)| stats.hp > 0 && map.is_visible(pos.x, pos.y))
+            .map(|(_, pos, render, stats, name)| {
+                let distance = (pos.x - player_pos.0).abs().max((pos.y - player_pos.1).abs());
+                (distance, SidebarEnemy {
+                    name: name.map_or("Something".to_string(), |n| localization::name(&n.name)),
+                    glyph: render.glyph,
+                    color: render.fg,
+                    health: HealthEstimate::from_stats(stats),
+                })
+            })
+            .collect();
+        enemies.sort_by_key(|(distance, _)| *distance);
+        sidebar.enemies = enemies.into_iter().map(|(_, enemy)| enemy).collect();
+
+        self.context.render_sidebar(&sidebar);
 
         // Get log messages
         let messages: Vec<String> = game_log.entries.iter().cloned().collect();
 
         // Render UI
-        self.context.render_ui(player_stats, &messages);
+        self.context.render_ui(&messages);
     }
 }
\ No newline at end of file
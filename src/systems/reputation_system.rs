@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use specs::{System, Write};
+use crate::components::CaptiveKind;
+use crate::localization::Message;
+use crate::resources::GameLog;
+
+/// Furthest standing with anyone can climb or sink
+pub const MAX_REPUTATION: i32 = 100;
+
+/// Those who keep track of what the character gets up to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Faction {
+    /// The people of Ravenhollow, its shopkeepers and its watch
+    Townsfolk,
+    /// The adventurers' guild that hands out missions
+    Guild,
+    /// Everyone else who goes down into the dungeon
+    Adventurers,
+}
+
+impl Faction {
+    pub fn all() -> [Faction; 3] {
+        [Faction::Townsfolk, Faction::Guild, Faction::Adventurers]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Faction::Townsfolk => "Townsfolk of Ravenhollow",
+            Faction::Guild => "Adventurers' Guild",
+            Faction::Adventurers => "Other adventurers",
+        }
+    }
+
+    /// What standing with them decides
+    pub fn description(&self) -> &'static str {
+        match self {
+            Faction::Townsfolk => "Sets prices in town, and how long the watch will wait before drawing steel.",
+            Faction::Guild => "Decides how hard a mission the guild will trust you with, and what its trainers charge.",
+            Faction::Adventurers => "Decides whether rival parties will trade with you or fight at your side, and at what price.",
+        }
+    }
+}
+
+/// How a faction sees the character, from their score with it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Standing {
+    Hated,
+    Disliked,
+    Neutral,
+    Liked,
+    Honored,
+}
+
+impl Standing {
+    pub fn of(score: i32) -> Standing {
+        match score {
+            i32::MIN..=-50 => Standing::Hated,
+            -49..=-15 => Standing::Disliked,
+            -14..=14 => Standing::Neutral,
+            15..=49 => Standing::Liked,
+            _ => Standing::Honored,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Standing::Hated => "Hated",
+            Standing::Disliked => "Disliked",
+            Standing::Neutral => "Neutral",
+            Standing::Liked => "Liked",
+            Standing::Honored => "Honored",
+        }
+    }
+
+    pub fn color(&self) -> crossterm::style::Color {
+        match self {
+            Standing::Hated => crossterm::style::Color::Red,
+            Standing::Disliked => crossterm::style::Color::DarkYellow,
+            Standing::Neutral => crossterm::style::Color::Grey,
+            Standing::Liked => crossterm::style::Color::Green,
+            Standing::Honored => crossterm::style::Color::Cyan,
+        }
+    }
+
+    /// Multiplier for what those who feel this way charge
+    pub fn price_multiplier(&self) -> f32 {
+        match self {
+            Standing::Hated => 1.5,
+            Standing::Disliked => 1.2,
+            Standing::Neutral => 1.0,
+            Standing::Liked => 0.9,
+            Standing::Honored => 0.8,
+        }
+    }
+
+    /// Whether the town watch goes for the character the moment they see them
+    pub fn guards_attack_on_sight(&self) -> bool {
+        *self == Standing::Hated
+    }
+
+    /// Turns the town watch gives a wanted character to come and answer for it before it stops
+    /// asking
+    pub fn watch_patience(&self) -> i32 {
+        match self {
+            Standing::Hated => 0,
+            Standing::Disliked => 10,
+            Standing::Neutral => 25,
+            Standing::Liked => 50,
+            Standing::Honored => 100,
+        }
+    }
+}
+
+/// Something done that word gets around about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Conduct {
+    Slew { boss: bool },
+    /// Went deeper than ever before
+    Delved,
+    Rescued(CaptiveKind),
+    /// Turned on a rival party that wasn't looking for a fight
+    TurnedOnRival,
+    SlewRival,
+    /// Walked out of a shop without paying
+    Theft,
+    /// Raised a hand against someone in town
+    Assault,
+    /// Killed someone in town
+    Murder,
+}
+
+/// Standing `faction` gains for `conduct`; below zero is a mark against the character
+pub fn sway(faction: Faction, conduct: Conduct) -> i32 {
+    match (faction, conduct) {
+        (Faction::Townsfolk, Conduct::Slew { boss: true }) => 10,
+        (Faction::Townsfolk, Conduct::Slew { boss: false }) => 1,
+        (Faction::Guild, Conduct::Slew { boss: true }) => 5,
+        (Faction::Guild, Conduct::Delved) => 3,
+        (Faction::Townsfolk, Conduct::Rescued(CaptiveKind::Merchant)) => 10,
+        (Faction::Guild, Conduct::Rescued(CaptiveKind::Merchant)) => 3,
+        (Faction::Townsfolk, Conduct::Rescued(CaptiveKind::Adventurer)) => 2,
+        (Faction::Adventurers, Conduct::Rescued(CaptiveKind::Adventurer)) => 10,
+        (Faction::Adventurers, Conduct::TurnedOnRival) => -15,
+        (Faction::Adventurers, Conduct::SlewRival) => -25,
+        (Faction::Townsfolk, Conduct::SlewRival) => -10,
+        (Faction::Guild, Conduct::SlewRival) => -10,
+        (Faction::Townsfolk, Conduct::Theft) => -10,
+        (Faction::Townsfolk, Conduct::Assault) => -15,
+        (Faction::Townsfolk, Conduct::Murder) => -40,
+        (Faction::Guild, Conduct::Murder) => -15,
+        _ => 0,
+    }
+}
+
+/// The character's standing with every faction, and what they have done since it was last
+/// weighed. Saved with the game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Reputation {
+    pub scores: HashMap<Faction, i32>,
+    #[serde(skip)]
+    pub conduct: Vec<Conduct>,
+}
+
+impl Reputation {
+    pub fn record(&mut self, conduct: Conduct) {
+        self.conduct.push(conduct);
+    }
+
+    pub fn take(&mut self) -> Vec<Conduct> {
+        std::mem::take(&mut self.conduct)
+    }
+
+    pub fn score(&self, faction: Faction) -> i32 {
+        self.scores.get(&faction).copied().unwrap_or(0)
+    }
+
+    pub fn standing(&self, faction: Faction) -> Standing {
+        Standing::of(self.score(faction))
+    }
+
+    /// Move standing with a faction by `change`, within the limits either way
+    pub fn adjust(&mut self, faction: Faction, change: i32) {
+        let score = (self.score(faction) + change).clamp(-MAX_REPUTATION, MAX_REPUTATION);
+        self.scores.insert(faction, score);
+    }
+}
+
+/// Lets each faction weigh what the character has done, and says so whenever one of them comes
+/// to see the character differently
+pub struct ReputationSystem {}
+
+impl<'a> System<'a> for ReputationSystem {
+    type SystemData = (
+        Write<'a, Reputation>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut reputation, mut gamelog) = data;
+
+        for conduct in reputation.take() {
+            for faction in Faction::all() {
+                let change = sway(faction, conduct);
+                if change == 0 {
+                    continue;
+                }
+                let standing = reputation.standing(faction);
+                reputation.adjust(faction, change);
+                let now = reputation.standing(faction);
+                if now != standing {
+                    gamelog.add_message(Message::new("log.reputation_changed")
+                        .arg("faction", faction.name())
+                        .arg("standing", now.name()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, RunNow};
+
+    #[test]
+    fn test_deeds_move_standing_and_news_of_a_new_standing_is_logged() {
+        let mut world = World::new();
+        world.insert(GameLog::new(10));
+        world.insert(Reputation::default());
+
+        world.write_resource::<Reputation>().record(Conduct::Rescued(CaptiveKind::Adventurer));
+        world.write_resource::<Reputation>().record(Conduct::Slew { boss: false });
+        ReputationSystem {}.run_now(&world);
+        {
+            let reputation = world.read_resource::<Reputation>();
+            assert_eq!(reputation.score(Faction::Adventurers), 10);
+            assert_eq!(reputation.score(Faction::Townsfolk), 3);
+            assert_eq!(reputation.standing(Faction::Adventurers), Standing::Neutral);
+        }
+        assert!(world.read_resource::<GameLog>().entries.is_empty());
+
+        world.write_resource::<Reputation>().record(Conduct::SlewRival);
+        world.write_resource::<Reputation>().record(Conduct::SlewRival);
+        ReputationSystem {}.run_now(&world);
+        let reputation = world.read_resource::<Reputation>();
+        assert_eq!(reputation.score(Faction::Adventurers), -40);
+        assert_eq!(reputation.standing(Faction::Adventurers), Standing::Disliked);
+        assert_eq!(reputation.standing(Faction::Townsfolk), Standing::Disliked);
+        assert_eq!(world.read_resource::<GameLog>().entries.len(), 3);
+    }
+
+    #[test]
+    fn test_standing_sets_prices_and_stays_within_bounds() {
+        let mut reputation = Reputation::default();
+        assert_eq!(reputation.standing(Faction::Townsfolk).price_multiplier(), 1.0);
+        reputation.adjust(Faction::Townsfolk, 500);
+        assert_eq!(reputation.score(Faction::Townsfolk), MAX_REPUTATION);
+        assert!(reputation.standing(Faction::Townsfolk).price_multiplier() < 1.0);
+        reputation.adjust(Faction::Townsfolk, -500);
+        assert!(reputation.standing(Faction::Townsfolk).guards_attack_on_sight());
+    }
+}
@@ -0,0 +1,349 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, WriteExpect, ReadExpect, Read};
+use crate::achievements::GameEvent;
+use crate::components::{Captive, CaptiveKind, CombatStats, Escort, Monster, Name, Player, Position, WantsToMove, WantsToRescue};
+use crate::items::AdvancedInventory;
+use crate::localization::Message;
+use crate::map::{Map, TileEffect};
+use crate::resources::{GameEvents, GameLog, GameStateResource, RunStatistics};
+use crate::utils::chebyshev;
+use super::reputation_system::{Reputation, Conduct};
+
+/// Gold a lost adventurer hands over for each level of depth they were found on
+pub const ADVENTURER_REWARD_PER_DEPTH: i32 = 15;
+/// Gold a merchant pays for each level of depth once seen to the stairs
+pub const MERCHANT_REWARD_PER_DEPTH: i32 = 40;
+/// How close to the stairs an escort has to get before they can see themselves out
+const DELIVERY_RANGE: i32 = 2;
+/// How far an escort lets their rescuer get ahead before hurrying after them
+const ESCORT_FOLLOW_DISTANCE: i32 = 2;
+
+/// Frees the captives the player walks into. A lost adventurer pays up there and then, points
+/// out the way down and makes their own way out; a captured merchant falls in behind the
+/// player to be led to the stairs
+pub struct RescueSystem;
+
+impl<'a> System<'a> for RescueSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToRescue>,
+        WriteStorage<'a, Captive>,
+        WriteStorage<'a, Escort>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteExpect<'a, Map>,
+        Write<'a, GameLog>,
+        Write<'a, RunStatistics>,
+        Write<'a, GameEvents>,
+        Write<'a, Reputation>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_rescue,
+            mut captives,
+            mut escorts,
+            positions,
+            names,
+            players,
+            mut inventories,
+            mut map,
+            mut gamelog,
+            mut stats,
+            mut events,
+            mut reputation,
+        ) = data;
+
+        let requests: Vec<(Entity, Entity)> = (&entities, &wants_rescue).join()
+            .map(|(rescuer, request)| (rescuer, request.captive))
+            .collect();
+        wants_rescue.clear();
+
+        for (rescuer, captive) in requests {
+            let (here, there) = match (positions.get(rescuer), positions.get(captive)) {
+                (Some(here), Some(there)) => ((here.x, here.y), (there.x, there.y)),
+                _ => continue,
+            };
+            if chebyshev(here, there) > 1 {
+                continue;
+            }
+            let kind = match captives.remove(captive) {
+                Some(held) => held.kind,
+                None => continue,
+            };
+            // Nothing holds the tile they were kept on any more
+            let idx = map.xy_idx(there.0, there.1);
+            map.blocked[idx] = false;
+
+            let name = names.get(captive).map_or("the captive".to_string(), |name| name.name.clone());
+            if kind.needs_escort() {
+                escorts.insert(captive, Escort { leader: rescuer, kind }).expect("Unable to insert escort");
+                if players.contains(rescuer) {
+                    gamelog.add_message(Message::new("log.captive_freed").arg("name", name));
+                }
+                continue;
+            }
+
+            let reward = ADVENTURER_REWARD_PER_DEPTH * map.depth.max(1);
+            if let Some(inventory) = inventories.get_mut(rescuer) {
+                inventory.gold += reward;
+            }
+            let exit = map.exit;
+            map.reveal_tile(exit.0, exit.1);
+            if players.contains(rescuer) {
+                gamelog.add_message(Message::new("log.adventurer_rescued").arg("name", name.clone()).arg("gold", reward));
+                stats.record_rescue(&name, kind);
+                events.publish(GameEvent::CaptiveRescued);
+                events.publish(GameEvent::GoldCollected(reward as u32));
+                reputation.record(Conduct::Rescued(kind));
+            }
+            entities.delete(captive).expect("Unable to delete rescued adventurer");
+        }
+    }
+}
+
+/// Once a turn, leads each freed merchant after their rescuer, picking a way that keeps them out
+/// of harm: clear of lava and chasms, gas and flames, and any monster's reach. One caught beside
+/// danger backs away from it first. Brought within reach of the stairs, they pay their rescuer
+/// and see themselves out
+pub struct EscortSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl EscortSystem {
+    pub fn new() -> Self {
+        EscortSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for EscortSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Escort>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, AdvancedInventory>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, RunStatistics>,
+        Write<'a, GameEvents>,
+        Write<'a, Reputation>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            escorts,
+            positions,
+            combat_stats,
+            monsters,
+            names,
+            players,
+            mut wants_move,
+            mut inventories,
+            map,
+            game_state,
+            mut gamelog,
+            mut stats,
+            mut events,
+            mut reputation,
+        ) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let threats: Vec<(i32, i32)> = (&monsters, &positions, &combat_stats).join()
+            .filter(|(_, _, stats)| stats.hp > 0)
+            .map(|(_, pos, _)| (pos.x, pos.y))
+            .collect();
+
+        for (entity, escort, pos) in (&entities, &escorts, &positions).join() {
+            if combat_stats.get(entity).map_or(false, |stats| stats.hp <= 0) {
+                continue;
+            }
+            let here = (pos.x, pos.y);
+
+            if chebyshev(here, map.exit) <= DELIVERY_RANGE || chebyshev(here, map.entrance) <= DELIVERY_RANGE {
+                deliver(entity, escort, &names, &players, &mut inventories, &map, &mut gamelog, &mut stats, &mut events, &mut reputation);
+                entities.delete(entity).expect("Unable to delete delivered escort");
+                continue;
+            }
+
+            let leader = match positions.get(escort.leader) {
+                Some(leader) => (leader.x, leader.y),
+                None => continue,
+            };
+
+            if !is_safe(&map, &threats, here.0, here.1) {
+                if let Some(step) = retreat(&map, &threats, here) {
+                    wants_move.insert(entity, WantsToMove { destination: step }).expect("Unable to insert move intent");
+                }
+                continue;
+            }
+            if chebyshev(here, leader) <= ESCORT_FOLLOW_DISTANCE {
+                continue;
+            }
+            let step = map.find_path_avoiding(here, leader, |x, y| !is_safe(&map, &threats, x, y))
+                .and_then(|path| path.first().copied());
+            if let Some(step) = step.filter(|step| *step != leader) {
+                wants_move.insert(entity, WantsToMove { destination: step }).expect("Unable to insert move intent");
+            }
+        }
+    }
+}
+
+/// Whether an escort could stand on the tile without coming to harm
+fn is_safe(map: &Map, threats: &[(i32, i32)], x: i32, y: i32) -> bool {
+    !map.is_dangerous(x, y)
+        && map.gas_at(x, y).is_none()
+        && !map.has_tile_effect(x, y, TileEffect::Burning)
+        && !threats.iter().any(|threat| chebyshev(*threat, (x, y)) <= 1)
+}
+
+/// The safe open tile beside `here` furthest from the nearest monster, if any
+fn retreat(map: &Map, threats: &[(i32, i32)], here: (i32, i32)) -> Option<(i32, i32)> {
+    let nearest = |tile: (i32, i32)| threats.iter().map(|threat| chebyshev(*threat, tile)).min().unwrap_or(i32::MAX);
+    map.get_neighbors(here.0, here.1)
+        .into_iter()
+        .filter(|(x, y)| !map.is_blocked(*x, *y) && is_safe(map, threats, *x, *y))
+        .max_by_key(|tile| nearest(*tile))
+}
+
+/// Pay the rescuer for seeing an escort safely to the stairs
+#[allow(clippy::too_many_arguments)]
+fn deliver(
+    entity: Entity,
+    escort: &Escort,
+    names: &ReadStorage<Name>,
+    players: &ReadStorage<Player>,
+    inventories: &mut WriteStorage<AdvancedInventory>,
+    map: &Map,
+    gamelog: &mut GameLog,
+    stats: &mut RunStatistics,
+    events: &mut GameEvents,
+    reputation: &mut Reputation,
+) {
+    if !players.contains(escort.leader) {
+        return;
+    }
+    let name = names.get(entity).map_or("The escort".to_string(), |name| name.name.clone());
+    let reward = match escort.kind {
+        CaptiveKind::Merchant => MERCHANT_REWARD_PER_DEPTH,
+        CaptiveKind::Adventurer => ADVENTURER_REWARD_PER_DEPTH,
+    } * map.depth.max(1);
+    if let Some(inventory) = inventories.get_mut(escort.leader) {
+        inventory.gold += reward;
+    }
+    gamelog.add_message(Message::new("log.escort_delivered").arg("name", name.clone()).arg("gold", reward));
+    stats.record_rescue(&name, escort.kind);
+    events.publish(GameEvent::CaptiveRescued);
+    events.publish(GameEvent::GoldCollected(reward as u32));
+    reputation.record(Conduct::Rescued(escort.kind));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::components::{BlocksTile, GasKind};
+    use crate::map::TileType;
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        let mut map = Map::new(12, 12, 3);
+        for x in 1..11 {
+            for y in 1..11 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        map.entrance = (1, 1);
+        map.exit = (10, 10);
+        world.insert(map);
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(RunStatistics::default());
+        world.insert(GameEvents::default());
+        world.insert(Reputation::default());
+        world
+    }
+
+    fn player(world: &mut World, x: i32, y: i32) -> Entity {
+        world.create_entity()
+            .with(Player)
+            .with(Position { x, y })
+            .with(AdvancedInventory::new(26, 100.0))
+            .build()
+    }
+
+    fn captive(world: &mut World, kind: CaptiveKind, x: i32, y: i32) -> Entity {
+        world.create_entity()
+            .with(Position { x, y })
+            .with(Name { name: "Edda".to_string() })
+            .with(CombatStats { max_hp: 12, hp: 12, defense: 0, power: 0 })
+            .with(BlocksTile {})
+            .with(Captive { kind })
+            .build()
+    }
+
+    fn gold(world: &World, player: Entity) -> i32 {
+        world.read_storage::<AdvancedInventory>().get(player).map_or(0, |inventory| inventory.gold)
+    }
+
+    #[test]
+    fn test_a_lost_adventurer_pays_up_and_leaves() {
+        let mut world = world();
+        let player = player(&mut world, 5, 5);
+        let adventurer = captive(&mut world, CaptiveKind::Adventurer, 6, 5);
+
+        world.write_storage::<WantsToRescue>().insert(player, WantsToRescue { captive: adventurer }).unwrap();
+        RescueSystem.run_now(&world);
+        world.maintain();
+
+        assert!(!world.is_alive(adventurer));
+        assert_eq!(gold(&world, player), ADVENTURER_REWARD_PER_DEPTH * 3);
+        assert!(world.fetch::<Map>().is_revealed(10, 10));
+        assert_eq!(world.fetch::<RunStatistics>().rescued_adventurers, vec!["Edda".to_string()]);
+        assert_eq!(world.fetch::<Reputation>().conduct, vec![Conduct::Rescued(CaptiveKind::Adventurer)]);
+    }
+
+    #[test]
+    fn test_a_merchant_is_led_around_gas_and_paid_for_at_the_stairs() {
+        let mut world = world();
+        let player = player(&mut world, 5, 5);
+        let merchant = captive(&mut world, CaptiveKind::Merchant, 6, 5);
+
+        world.write_storage::<WantsToRescue>().insert(player, WantsToRescue { captive: merchant }).unwrap();
+        RescueSystem.run_now(&world);
+        assert!(world.read_storage::<Escort>().contains(merchant));
+        assert_eq!(gold(&world, player), 0);
+
+        // The rescuer runs ahead past a cloud; the merchant keeps out of it
+        world.write_storage::<Position>().insert(player, Position { x: 5, y: 8 }).unwrap();
+        world.write_storage::<Position>().insert(merchant, Position { x: 5, y: 4 }).unwrap();
+        world.write_resource::<Map>().release_gas(5, 5, GasKind::PoisonGas, 4);
+        let mut escorts = EscortSystem::new();
+        escorts.run_now(&world);
+        let step = world.read_storage::<WantsToMove>().get(merchant).map(|wants| wants.destination);
+        assert!(step.is_some());
+        assert!(world.fetch::<Map>().gas_at(step.unwrap().0, step.unwrap().1).is_none());
+
+        world.write_storage::<Position>().insert(merchant, Position { x: 9, y: 9 }).unwrap();
+        world.write_resource::<GameStateResource>().turn_count += 1;
+        escorts.run_now(&world);
+        world.maintain();
+        assert!(!world.is_alive(merchant));
+        assert_eq!(gold(&world, player), MERCHANT_REWARD_PER_DEPTH * 3);
+        assert_eq!(world.fetch::<RunStatistics>().captives_rescued, 1);
+    }
+}
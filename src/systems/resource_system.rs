@@ -1,170 +1,157 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
-use crate::components::{PlayerResources, StatusEffects, StatusEffectType, CombatStats, Player};
-use crate::resources::GameLog;
+use specs::{System, WriteStorage, ReadStorage, Entities, Join, Write, Read};
+use crate::components::{PlayerResources, StatusEffect, StatusEffects, StatusEffectType, Player, Sprinting};
+use crate::items::{is_two_handed, ItemProperties};
+use crate::localization::Message;
+use crate::resources::{GameLog, GameStateResource, RandomNumberGenerator};
+use super::status_effect_system::apply_status_effect;
 
-pub struct ResourceRegenerationSystem {}
+/// Turns between each trickle of mana
+pub const MANA_REGEN_INTERVAL: i32 = 3;
+/// Turns between each trickle of stamina
+pub const STAMINA_REGEN_INTERVAL: i32 = 2;
+/// Stamina spent on each sprinting move
+pub const SPRINT_STAMINA_COST: i32 = 3;
+/// Pounds a weapon must weigh for each swing of it to cost stamina
+pub const HEAVY_WEAPON_WEIGHT: f32 = 10.0;
+/// Stamina spent on each swing of a heavy weapon
+pub const HEAVY_ATTACK_STAMINA_COST: i32 = 2;
+/// Power and defense lost while out of stamina
+pub const EXHAUSTION_PENALTY: i32 = 2;
+/// Exhaustion outlasts an empty stamina pool by this long, so a breather is needed to shake it
+const EXHAUSTION_TURNS: i32 = 2;
+
+/// Whether swinging the weapon takes it out of you: anything two-handed or weighty enough
+pub fn is_heavy_weapon(props: &ItemProperties) -> bool {
+    is_two_handed(props) || props.weight >= HEAVY_WEAPON_WEIGHT
+}
+
+/// What `rate` comes to once the regeneration modifiers from status effects are applied
+pub fn regen_amount(rate: i32, modifier: f32) -> i32 {
+    ((rate as f32) * (1.0 + modifier)).round().max(0.0) as i32
+}
+
+/// Once a turn, trickles mana and stamina back at their regeneration rates, faster or slower for
+/// effects such as ManaRegenBoost, and leaves anyone with no stamina left exhausted
+pub struct ResourceRegenerationSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl ResourceRegenerationSystem {
+    pub fn new() -> Self {
+        ResourceRegenerationSystem { last_turn: None }
+    }
+}
 
 impl<'a> System<'a> for ResourceRegenerationSystem {
     type SystemData = (
         Entities<'a>,
         WriteStorage<'a, PlayerResources>,
-        ReadStorage<'a, StatusEffects>,
+        WriteStorage<'a, StatusEffects>,
         ReadStorage<'a, Player>,
+        WriteStorage<'a, Sprinting>,
+        Read<'a, GameStateResource>,
         Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut resources, status_effects, players, mut gamelog) = data;
+        let (entities, mut resources, mut status_effects, players, mut sprinting, game_state, mut gamelog, mut rng) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        for (entity, resource) in (&entities, &mut resources).join() {
+            let is_player = players.contains(entity);
+            let modifiers = status_effects.get(entity)
+                .map(|effects| effects.stat_modifiers())
+                .unwrap_or_default();
 
-        for (entity, mut resource, _player) in (&entities, &mut resources, &players).join() {
-            // Update regeneration timers
             resource.mana_regen_timer += 1;
+            if resource.mana_regen_timer >= MANA_REGEN_INTERVAL {
+                resource.mana_regen_timer = 0;
+                resource.restore_mana(regen_amount(resource.mana_regen_rate, modifiers.mana_regen));
+            }
+
+            // Out of breath, stamina only starts coming back once the legs stop
             resource.stamina_regen_timer += 1;
-            
-            // Calculate regeneration rates (affected by status effects)
-            let mut mana_regen_modifier = 1.0;
-            let mut stamina_regen_modifier = 1.0;
-            
-            if let Some(effects) = status_effects.get(entity) {
-                for effect in &effects.effects {
-                    match effect.effect_type {
-                        StatusEffectType::ManaRegenBoost => {
-                            mana_regen_modifier += effect.magnitude as f32 * 0.1;
-                        },
-                        StatusEffectType::ManaRegenPenalty => {
-                            mana_regen_modifier -= effect.magnitude as f32 * 0.1;
-                        },
-                        StatusEffectType::StaminaRegenBoost => {
-                            stamina_regen_modifier += effect.magnitude as f32 * 0.1;
-                        },
-                        StatusEffectType::StaminaRegenPenalty => {
-                            stamina_regen_modifier -= effect.magnitude as f32 * 0.1;
-                        },
-                        _ => {}
-                    }
-                }
+            if resource.stamina_regen_timer >= STAMINA_REGEN_INTERVAL && !sprinting.contains(entity) {
+                resource.stamina_regen_timer = 0;
+                resource.restore_stamina(regen_amount(resource.stamina_regen_rate, modifiers.stamina_regen));
             }
-            
-            // Regenerate mana every 3 turns
-            if resource.mana_regen_timer >= 3 {
-                let mana_gain = ((resource.mana_regen_rate as f32) * mana_regen_modifier) as i32;
-                if mana_gain > 0 && resource.mana < resource.max_mana {
-                    resource.restore_mana(mana_gain);
-                    resource.mana_regen_timer = 0;
-                }
+
+            if resource.stamina > 0 || resource.max_stamina <= 0 {
+                continue;
+            }
+            if sprinting.remove(entity).is_some() && is_player {
+                gamelog.add_message(Message::new("log.too_winded_to_sprint"));
             }
-            
-            // Regenerate stamina every 2 turns
-            if resource.stamina_regen_timer >= 2 {
-                let stamina_gain = ((resource.stamina_regen_rate as f32) * stamina_regen_modifier) as i32;
-                if stamina_gain > 0 && resource.stamina < resource.max_stamina {
-                    resource.restore_stamina(stamina_gain);
-                    resource.stamina_regen_timer = 0;
-                }
+            let already = status_effects.get(entity).map_or(false, |effects| effects.has_effect(StatusEffectType::Exhausted));
+            let exhausted = StatusEffect::new(StatusEffectType::Exhausted, EXHAUSTION_TURNS, EXHAUSTION_PENALTY);
+            apply_status_effect(entity, exhausted, &mut status_effects, None, &mut rng);
+            if !already && is_player {
+                gamelog.add_message(Message::new("log.exhausted"));
             }
         }
     }
 }
 
-pub struct StatusEffectSystem {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
 
-impl<'a> System<'a> for StatusEffectSystem {
-    type SystemData = (
-        Entities<'a>,
-        WriteStorage<'a, StatusEffects>,
-        WriteStorage<'a, CombatStats>,
-        WriteStorage<'a, PlayerResources>,
-        Write<'a, GameLog>,
-    );
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(RandomNumberGenerator::new(1));
+        world
+    }
 
-    fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut status_effects, mut combat_stats, mut resources, mut gamelog) = data;
-
-        for (entity, mut effects) in (&entities, &mut status_effects).join() {
-            // Apply status effect damage/healing
-            for effect in &effects.effects {
-                match effect.effect_type {
-                    StatusEffectType::Poisoned => {
-                        if let Some(stats) = combat_stats.get_mut(entity) {
-                            let damage = effect.magnitude;
-                            stats.hp -= damage;
-                            gamelog.add_entry(format!("Poison deals {} damage!", damage));
-                        }
-                    },
-                    StatusEffectType::Blessed => {
-                        if let Some(stats) = combat_stats.get_mut(entity) {
-                            let healing = effect.magnitude;
-                            stats.hp = i32::min(stats.hp + healing, stats.max_hp);
-                            gamelog.add_entry(format!("Blessing heals {} HP!", healing));
-                        }
-                    },
-                    _ => {}
-                }
-            }
-            
-            // Update effect durations
-            effects.update_effects();
+    fn run_turns(world: &mut World, system: &mut ResourceRegenerationSystem, turns: u32) {
+        for _ in 0..turns {
+            world.write_resource::<GameStateResource>().turn_count += 1;
+            system.run_now(world);
+            // A second frame in the same turn changes nothing
+            system.run_now(world);
         }
     }
-}
 
-pub struct AbilityUsageSystem {}
+    #[test]
+    fn test_regeneration_ticks_once_a_turn_and_follows_status_effects() {
+        let mut world = world();
+        let mut resources = PlayerResources::new(20, 20);
+        resources.mana = 0;
+        resources.stamina = 10;
+        let plain = world.create_entity().with(Player).with(resources.clone()).build();
+        let mut boosted = StatusEffects::new();
+        boosted.add_effect(StatusEffect::new(StatusEffectType::ManaRegenBoost, 20, 10));
+        let inspired = world.create_entity().with(resources).with(boosted).build();
 
-impl<'a> System<'a> for AbilityUsageSystem {
-    type SystemData = (
-        Entities<'a>,
-        WriteStorage<'a, crate::components::WantsToUseAbility>,
-        WriteStorage<'a, PlayerResources>,
-        WriteStorage<'a, crate::components::Abilities>,
-        Write<'a, GameLog>,
-    );
+        let mut system = ResourceRegenerationSystem::new();
+        run_turns(&mut world, &mut system, 6);
 
-    fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_use_ability, mut resources, mut abilities, mut gamelog) = data;
+        let resources = world.read_storage::<PlayerResources>();
+        assert_eq!(resources.get(plain).map(|r| (r.mana, r.stamina)), Some((2, 16)));
+        assert_eq!(resources.get(inspired).map(|r| r.mana), Some(4));
+    }
 
-        let mut ability_uses = Vec::new();
-        
-        // Collect ability usage requests
-        for (entity, ability_use) in (&entities, &wants_use_ability).join() {
-            ability_uses.push((entity, ability_use.ability, ability_use.mana_cost, ability_use.stamina_cost));
-        }
-        
-        // Clear ability usage requests
-        wants_use_ability.clear();
-        
-        // Process ability usage
-        for (entity, ability_type, mana_cost, stamina_cost) in ability_uses {
-            let mut can_use = true;
-            let mut insufficient_resources = Vec::new();
-            
-            // Check resource requirements
-            if let Some(resource) = resources.get_mut(entity) {
-                if resource.mana < mana_cost {
-                    can_use = false;
-                    insufficient_resources.push("mana");
-                }
-                if resource.stamina < stamina_cost {
-                    can_use = false;
-                    insufficient_resources.push("stamina");
-                }
-                
-                if can_use {
-                    // Consume resources
-                    resource.consume_mana(mana_cost);
-                    resource.consume_stamina(stamina_cost);
-                    
-                    // Set ability on cooldown
-                    if let Some(ability_comp) = abilities.get_mut(entity) {
-                        ability_comp.set_cooldown(ability_type, ability_type.cooldown());
-                    }
-                    
-                    gamelog.add_entry(format!("You used {}! (Cost: {} mana, {} stamina)", 
-                        ability_type.name(), mana_cost, stamina_cost));
-                } else {
-                    gamelog.add_entry(format!("Not enough {} to use {}!", 
-                        insufficient_resources.join(" and "), ability_type.name()));
-                }
-            }
-        }
+    #[test]
+    fn test_running_dry_ends_the_sprint_and_exhausts() {
+        let mut world = world();
+        let mut resources = PlayerResources::new(10, 10);
+        resources.stamina = 0;
+        let player = world.create_entity().with(Player).with(resources).with(Sprinting).build();
+
+        let mut system = ResourceRegenerationSystem::new();
+        run_turns(&mut world, &mut system, 1);
+
+        assert!(!world.read_storage::<Sprinting>().contains(player));
+        let effects = world.read_storage::<StatusEffects>();
+        let modifiers = effects.get(player).unwrap().stat_modifiers();
+        assert_eq!((modifiers.power, modifiers.defense), (-EXHAUSTION_PENALTY, -EXHAUSTION_PENALTY));
     }
-}
\ No newline at end of file
+}
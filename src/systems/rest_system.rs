@@ -0,0 +1,270 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
+use crate::components::{
+    Player, Position, Monster, Campfire, CombatStats, PlayerResources, Inventory, Name, Viewshed,
+    StatusEffects, StatusEffectType, WantsToRest
+};
+use crate::items::{Consumable, ConsumableType, ItemStack};
+use crate::map::Map;
+use crate::resources::{GameClock, GameLog, GameStateResource, RandomNumberGenerator};
+
+/// Turns rested between meals
+pub const TURNS_PER_MEAL: u32 = 50;
+/// Turns of unbroken rest that count as a proper sleep and shake off weariness
+pub const TURNS_TO_SLEEP: u32 = 60;
+/// One in this many rest turns something wanders close enough to wake the player in the open
+const WANDERING_MONSTER_ODDS: i32 = 100;
+
+/// Where the player is resting, which decides how well the rest goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestSpot {
+    Open,
+    /// Within the warmth of a campfire
+    Campfire,
+    /// Inside a warded safe room
+    SafeRoom,
+}
+
+impl RestSpot {
+    pub fn at(map: &Map, pos: &Position, campfires: &[(Position, i32)]) -> Self {
+        if map.in_safe_room(pos.x, pos.y) {
+            RestSpot::SafeRoom
+        } else if campfires.iter().any(|(fire, radius)| (fire.x - pos.x).abs().max((fire.y - pos.y).abs()) <= *radius) {
+            RestSpot::Campfire
+        } else {
+            RestSpot::Open
+        }
+    }
+
+    /// Rest turns between each bit of health, mana and stamina coming back
+    pub fn restore_interval(&self) -> u32 {
+        match self {
+            RestSpot::Open => 4,
+            RestSpot::Campfire | RestSpot::SafeRoom => 1,
+        }
+    }
+
+    /// One-in-N chance per turn of a wandering monster disturbing the rest; none reach a safe room
+    pub fn wandering_monster_odds(&self) -> Option<i32> {
+        match self {
+            RestSpot::Open => Some(WANDERING_MONSTER_ODDS),
+            // Most things keep their distance from a fire
+            RestSpot::Campfire => Some(WANDERING_MONSTER_ODDS * 2),
+            RestSpot::SafeRoom => None,
+        }
+    }
+}
+
+/// Rests the player one turn per update, advancing the turn and the clock, until done or disturbed
+pub struct RestSystem;
+
+impl<'a> System<'a> for RestSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToRest>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Campfire>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Consumable>,
+        WriteStorage<'a, ItemStack>,
+        WriteStorage<'a, Inventory>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, PlayerResources>,
+        WriteStorage<'a, StatusEffects>,
+        ReadExpect<'a, Map>,
+        Write<'a, GameStateResource>,
+        Write<'a, GameClock>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_rest,
+            players,
+            positions,
+            monsters,
+            campfires,
+            viewsheds,
+            names,
+            consumables,
+            mut stacks,
+            mut inventories,
+            mut combat_stats,
+            mut resources,
+            mut status_effects,
+            map,
+            mut game_state,
+            mut clock,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        let fires: Vec<(Position, i32)> = (&positions, &campfires).join()
+            .map(|(pos, fire)| (pos.clone(), fire.radius))
+            .collect();
+        let mut finished: Vec<Entity> = Vec::new();
+
+        for (entity, rest, pos, _) in (&entities, &mut wants_rest, &positions, &players).join() {
+            let hp = combat_stats.get(entity).map(|stats| stats.hp);
+
+            // Anything hostile in view, or a fresh wound, ends the rest at once
+            let enemy_in_view = viewsheds.get(entity).map_or(false, |viewshed| {
+                (&monsters, &positions).join()
+                    .any(|(_, monster_pos)| viewshed.visible_tiles.contains(&(monster_pos.x, monster_pos.y)))
+            });
+            let interruption = if enemy_in_view {
+                Some(if rest.turns_rested == 0 {
+                    "You cannot rest with enemies in view."
+                } else {
+                    "An enemy comes into view and you stop resting."
+                })
+            } else if matches!((rest.last_hp, hp), (Some(last), Some(now)) if now < last) {
+                Some("You are hurt and stop resting.")
+            } else {
+                None
+            };
+            if let Some(message) = interruption {
+                gamelog.add_entry(message.to_string());
+                finished.push(entity);
+                continue;
+            }
+
+            let healed = is_fully_healed(combat_stats.get(entity), resources.get(entity));
+            if rest.until_healed && healed {
+                if rest.turns_rested == 0 {
+                    gamelog.add_entry("You are already fully rested.".to_string());
+                } else {
+                    gamelog.add_entry(format!("You rest for {} turns and feel fully restored.", rest.turns_rested));
+                }
+                finished.push(entity);
+                continue;
+            }
+
+            // Every so often a rest needs a meal to keep going
+            if rest.turns_rested > 0 && rest.turns_rested % TURNS_PER_MEAL == 0 {
+                match eat_food(entity, &entities, &mut inventories, &consumables, &mut stacks, &names) {
+                    Some(food) => gamelog.add_entry(format!("You stop to eat some {}.", food)),
+                    None => {
+                        gamelog.add_entry("You have nothing left to eat and are too hungry to rest.".to_string());
+                        finished.push(entity);
+                        continue;
+                    }
+                }
+            }
+
+            let spot = RestSpot::at(&map, pos, &fires);
+            if let Some(odds) = spot.wandering_monster_odds() {
+                if rng.roll_dice(1, odds) == 1 {
+                    gamelog.add_entry("You hear something moving nearby and start awake.".to_string());
+                    finished.push(entity);
+                    continue;
+                }
+            }
+
+            // One turn of rest passes, and the clock moves on with it
+            game_state.turn_count += 1;
+            rest.turns_rested += 1;
+            if rest.turns_rested % spot.restore_interval() == 0 {
+                if let Some(stats) = combat_stats.get_mut(entity) {
+                    stats.hp = (stats.hp + 1).min(stats.max_hp);
+                }
+                if let Some(resource) = resources.get_mut(entity) {
+                    resource.restore_mana(1);
+                    resource.restore_stamina(2);
+                }
+            }
+            rest.last_hp = combat_stats.get(entity).map(|stats| stats.hp);
+
+            if !rest.until_healed {
+                rest.turns_left = rest.turns_left.saturating_sub(1);
+                if rest.turns_left == 0 {
+                    gamelog.add_entry(format!("You rest for {} turns.", rest.turns_rested));
+                    finished.push(entity);
+                }
+            }
+        }
+
+        // A long enough rest counts as sleep, whatever ended it
+        for entity in finished {
+            let slept = wants_rest.get(entity).map_or(false, |rest| rest.turns_rested >= TURNS_TO_SLEEP);
+            if slept {
+                clock.rested();
+                if let Some(effects) = status_effects.get_mut(entity) {
+                    effects.remove_effect(StatusEffectType::StaminaRegenPenalty);
+                }
+                gamelog.add_entry("You feel refreshed.".to_string());
+            }
+            wants_rest.remove(entity);
+        }
+    }
+}
+
+fn is_fully_healed(stats: Option<&CombatStats>, resources: Option<&PlayerResources>) -> bool {
+    stats.map_or(true, |stats| stats.hp >= stats.max_hp)
+        && resources.map_or(true, |r| r.mana >= r.max_mana && r.stamina >= r.max_stamina)
+}
+
+/// Eat one portion of the first food in the pack, returning what was eaten
+fn eat_food(
+    eater: Entity,
+    entities: &Entities,
+    inventories: &mut WriteStorage<Inventory>,
+    consumables: &ReadStorage<Consumable>,
+    stacks: &mut WriteStorage<ItemStack>,
+    names: &ReadStorage<Name>,
+) -> Option<String> {
+    let inventory = inventories.get_mut(eater)?;
+    let index = inventory.items.iter().position(|item| {
+        consumables.get(*item).map_or(false, |consumable| consumable.consumable_type == ConsumableType::Food)
+    })?;
+    let food = inventory.items[index];
+    let name = names.get(food).map_or("food".to_string(), |name| name.name.clone());
+
+    let left = match stacks.get_mut(food) {
+        Some(stack) => {
+            stack.quantity -= 1;
+            stack.quantity
+        }
+        None => 0,
+    };
+    if left <= 0 {
+        inventory.items.remove(index);
+        entities.delete(food).expect("Unable to delete eaten food");
+    }
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_campfires_and_safe_rooms_rest_better() {
+        let mut map = Map::new(20, 20, 1);
+        map.safe_rooms.push(crate::map::Rect::new(10, 10, 5, 5));
+        let fires = vec![(Position { x: 2, y: 2 }, 2)];
+
+        assert_eq!(RestSpot::at(&map, &Position { x: 12, y: 12 }, &fires), RestSpot::SafeRoom);
+        assert_eq!(RestSpot::at(&map, &Position { x: 4, y: 3 }, &fires), RestSpot::Campfire);
+        assert_eq!(RestSpot::at(&map, &Position { x: 5, y: 2 }, &fires), RestSpot::Open);
+
+        assert!(RestSpot::Campfire.restore_interval() < RestSpot::Open.restore_interval());
+        assert!(RestSpot::Campfire.wandering_monster_odds() > RestSpot::Open.wandering_monster_odds());
+        assert_eq!(RestSpot::SafeRoom.wandering_monster_odds(), None);
+    }
+
+    #[test]
+    fn test_fully_healed_needs_every_pool_topped_up() {
+        let stats = CombatStats { max_hp: 30, hp: 30, defense: 2, power: 5 };
+        let mut resources = PlayerResources::new(10, 20);
+        assert!(is_fully_healed(Some(&stats), Some(&resources)));
+
+        resources.stamina -= 1;
+        assert!(!is_fully_healed(Some(&stats), Some(&resources)));
+        assert!(!is_fully_healed(Some(&CombatStats { hp: 29, ..stats }), None));
+    }
+}
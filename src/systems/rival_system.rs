@@ -0,0 +1,427 @@
+use std::collections::BTreeMap;
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect};
+use crate::components::{
+    CombatStats, Companion, DoorAction, Monster, Name, PackTactics, Parley, Player, Position, Rival, RivalStance,
+    WantsToAttack, WantsToMove, WantsToOperateDoor, WantsToParley, WantsToPickupItem
+};
+use crate::items::{AdvancedInventory, ItemProperties};
+use crate::localization::Message;
+use crate::map::Map;
+use crate::resources::{GameLog, GameStateResource};
+use crate::utils::chebyshev;
+use super::squad_system::{flank_posts, focus_target, should_retreat};
+use super::reputation_system::{Conduct, Faction, Reputation, Standing};
+
+/// How far a rival adventurer sees
+const RIVAL_SIGHT: i32 = 8;
+/// How far off loot catches a rival party's eye
+const RIVAL_LOOT_RANGE: i32 = 6;
+/// Rooms a rival party goes through before making for the stairs down
+pub const RIVAL_ROOMS_BEFORE_DESCENT: u32 = 4;
+/// How far a rival lets their leader get ahead before catching up
+const RIVAL_FOLLOW_DISTANCE: i32 = 2;
+/// Gold for each level of depth a rival party asks to fight at the player's side
+pub const RIVAL_ALLIANCE_FEE_PER_DEPTH: i32 = 20;
+
+/// What a party asks to fight at the player's side on a level this deep, given how adventurers
+/// see the player
+pub fn alliance_fee(depth: i32, standing: Standing) -> i32 {
+    ((RIVAL_ALLIANCE_FEE_PER_DEPTH * depth.max(1)) as f32 * standing.price_multiplier()).ceil() as i32
+}
+
+/// What a rival asks for an item worth `value`
+pub fn rival_price(value: i32, standing: Standing) -> i32 {
+    (value.max(1) as f32 * standing.price_multiplier()).ceil() as i32
+}
+
+/// Runs rival adventuring parties the way a player might: once a turn each party fights what it
+/// sees, picks up what it passes, works its way through the level's rooms and then takes the
+/// stairs down, ahead of the player if it gets there first. Parties fight together through the
+/// same flanking and retreat rules as monster packs. Monsters are always their foes; the player
+/// is one only once the party has been turned on, and an allied party follows the player about
+/// instead of exploring. Also settles what the player puts to a party: buying from its pack,
+/// paying it to join up or turning on it
+pub struct RivalSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl RivalSystem {
+    pub fn new() -> Self {
+        RivalSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for RivalSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Rival>,
+        WriteStorage<'a, WantsToParley>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, ItemProperties>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, WantsToAttack>,
+        WriteStorage<'a, WantsToPickupItem>,
+        WriteStorage<'a, WantsToOperateDoor>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, Reputation>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut rivals,
+            mut wants_parley,
+            positions,
+            combat_stats,
+            monsters,
+            players,
+            companions,
+            names,
+            properties,
+            mut inventories,
+            mut wants_move,
+            mut wants_attack,
+            mut wants_pickup,
+            mut wants_door,
+            map,
+            game_state,
+            mut gamelog,
+            mut reputation,
+        ) = data;
+
+        let requests: Vec<(Entity, WantsToParley)> = (&entities, &wants_parley).join()
+            .map(|(entity, request)| (entity, request.clone()))
+            .collect();
+        wants_parley.clear();
+        let standing = reputation.standing(Faction::Adventurers);
+        for (player, request) in requests {
+            let party = match rivals.get(request.rival) {
+                Some(rival) => rival.party,
+                None => continue,
+            };
+            let name = names.get(request.rival).map_or("The rival".to_string(), |name| name.name.clone());
+            match request.offer {
+                Parley::Buy(item) => {
+                    let message = sell(player, request.rival, item, standing, &properties, &mut inventories)
+                        .unwrap_or_else(|| Message::new("log.rival_wont_sell"));
+                    gamelog.add_message(message.arg("name", name));
+                },
+                Parley::Ally => {
+                    let fee = alliance_fee(map.depth, standing);
+                    let paid = match inventories.get_mut(player) {
+                        Some(inventory) if inventory.gold >= fee => {
+                            inventory.gold -= fee;
+                            true
+                        },
+                        _ => false,
+                    };
+                    if paid {
+                        set_stance(&mut rivals, party, RivalStance::Allied);
+                        gamelog.add_message(Message::new("log.rival_allied").arg("name", name).arg("gold", fee));
+                    } else {
+                        gamelog.add_message(Message::new("log.rival_alliance_too_dear").arg("name", name).arg("gold", fee));
+                    }
+                },
+                Parley::Attack => {
+                    set_stance(&mut rivals, party, RivalStance::Hostile);
+                    reputation.record(Conduct::TurnedOnRival);
+                    wants_attack.insert(player, WantsToAttack { target: request.rival })
+                        .expect("Unable to insert attack intent");
+                    gamelog.add_message(Message::new("log.rival_turned_on").arg("name", name));
+                },
+            }
+        }
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        let alive = |entity: Entity| combat_stats.get(entity).map_or(false, |stats| stats.hp > 0);
+        let mut parties: BTreeMap<u32, Vec<Entity>> = BTreeMap::new();
+        for (entity, rival, _) in (&entities, &rivals, &positions).join() {
+            if alive(entity) {
+                parties.entry(rival.party).or_default().push(entity);
+            }
+        }
+        let player = (&entities, &positions, &players).join()
+            .next()
+            .map(|(entity, pos, _)| (entity, (pos.x, pos.y)));
+        let monster_foes: Vec<(Entity, (i32, i32), i32)> = (&entities, &positions, &monsters, &combat_stats).join()
+            .filter(|(_, _, _, stats)| stats.hp > 0)
+            .map(|(entity, pos, _, stats)| (entity, (pos.x, pos.y), stats.hp))
+            .collect();
+        let player_side: Vec<(Entity, (i32, i32), i32)> = (&entities, &positions, &combat_stats).join()
+            .filter(|(entity, _, stats)| stats.hp > 0 && (players.contains(*entity) || companions.contains(*entity)))
+            .map(|(entity, pos, stats)| (entity, (pos.x, pos.y), stats.hp))
+            .collect();
+        let loot: Vec<(Entity, (i32, i32))> = (&entities, &positions, &properties).join()
+            .filter(|(_, pos, _)| !map.in_vault(pos.x, pos.y))
+            .map(|(entity, pos, _)| (entity, (pos.x, pos.y)))
+            .collect();
+
+        for (party, mut members) in parties {
+            members.sort();
+            let leader = members[0];
+            let (stance, explored) = match rivals.get(leader) {
+                Some(rival) => (rival.stance, rival.rooms_explored),
+                None => continue,
+            };
+            let pos = |entity: Entity| positions.get(entity).map_or((0, 0), |pos| (pos.x, pos.y));
+            let leader_pos = pos(leader);
+            let member_pos: Vec<(i32, i32)> = members.iter().map(|&member| pos(member)).collect();
+
+            // Monsters are always fair game; the player and their companions only once turned on
+            let foes: Vec<&(Entity, (i32, i32), i32)> = monster_foes.iter()
+                .chain(player_side.iter().filter(|_| stance == RivalStance::Hostile))
+                .filter(|(_, foe_pos, _)| member_pos.iter().any(|&here| {
+                    chebyshev(here, *foe_pos) <= RIVAL_SIGHT && map.has_line_of_sight(here, *foe_pos)
+                }))
+                .collect();
+            let (total_hp, total_max) = members.iter()
+                .filter_map(|&member| combat_stats.get(member))
+                .fold((0, 0), |(hp, max), stats| (hp + stats.hp, max + stats.max_hp));
+            let retreating = should_retreat(PackTactics::Warband, total_hp, total_max, false);
+
+            let candidates: Vec<((i32, i32), i32)> = foes.iter().map(|(_, foe_pos, hp)| (*foe_pos, *hp)).collect();
+            let quarry = focus_target(PackTactics::Warband, &candidates, leader_pos).map(|idx| foes[idx]);
+            if let (false, Some(&(foe, foe_pos, _))) = (retreating, quarry) {
+                let posts = flank_posts(&map, foe_pos, &member_pos);
+                for ((&member, &here), post) in members.iter().zip(&member_pos).zip(posts) {
+                    if chebyshev(here, foe_pos) <= 1 {
+                        wants_attack.insert(member, WantsToAttack { target: foe })
+                            .expect("Unable to insert attack intent");
+                    } else {
+                        step_toward(&map, &mut wants_move, &mut wants_door, member, here, post.unwrap_or(foe_pos));
+                    }
+                }
+                continue;
+            }
+
+            // Done here, or too hurt to go on: down the stairs and away
+            let leaving = retreating || (stance != RivalStance::Allied && explored >= RIVAL_ROOMS_BEFORE_DESCENT);
+            if leaving && leader_pos == map.exit {
+                if member_pos.iter().any(|&(x, y)| map.is_visible(x, y)) {
+                    let name = names.get(leader).map_or("A rival".to_string(), |name| name.name.clone());
+                    gamelog.add_message(Message::new("log.rival_party_descends").arg("name", name));
+                }
+                for &member in &members {
+                    if let Some(inventory) = inventories.get(member) {
+                        for slot in &inventory.items {
+                            entities.delete(slot.entity).expect("Unable to delete carried item");
+                        }
+                    }
+                    entities.delete(member).expect("Unable to delete departing rival");
+                }
+                continue;
+            }
+
+            // Whatever lies in plain sight nearby is worth a detour, while there's room to carry it
+            let pack = inventories.get(leader);
+            let spotted = loot.iter()
+                .filter(|(item, _)| pack.map_or(false, |pack| {
+                    properties.get(*item).map_or(false, |props| pack.can_add_item(props.weight))
+                }))
+                .filter(|(_, spot)| chebyshev(leader_pos, *spot) <= RIVAL_LOOT_RANGE && map.has_line_of_sight(leader_pos, *spot))
+                .min_by_key(|(_, spot)| chebyshev(leader_pos, *spot));
+            let goal = match spotted {
+                Some(&(item, spot)) if spot == leader_pos => {
+                    wants_pickup.insert(leader, WantsToPickupItem { item }).expect("Unable to insert pickup intent");
+                    None
+                },
+                Some(&(_, spot)) if !retreating => Some(spot),
+                _ if leaving => Some(map.exit),
+                _ if stance == RivalStance::Allied => {
+                    player.map(|(_, there)| there).filter(|&there| chebyshev(leader_pos, there) > RIVAL_FOLLOW_DISTANCE)
+                },
+                _ if map.rooms.is_empty() => Some(map.exit),
+                _ => {
+                    let room = map.rooms[(party as usize + explored as usize) % map.rooms.len()].center();
+                    if chebyshev(leader_pos, room) <= 1 {
+                        for &member in &members {
+                            if let Some(rival) = rivals.get_mut(member) {
+                                rival.rooms_explored += 1;
+                            }
+                        }
+                        None
+                    } else {
+                        Some(room)
+                    }
+                },
+            };
+            if let Some(goal) = goal {
+                step_toward(&map, &mut wants_move, &mut wants_door, leader, leader_pos, goal);
+            }
+            for (&member, &here) in members.iter().zip(&member_pos).skip(1) {
+                if chebyshev(here, leader_pos) > RIVAL_FOLLOW_DISTANCE {
+                    step_toward(&map, &mut wants_move, &mut wants_door, member, here, leader_pos);
+                }
+            }
+        }
+    }
+}
+
+/// Change how every member of a party stands with the player
+fn set_stance(rivals: &mut WriteStorage<Rival>, party: u32, stance: RivalStance) {
+    for rival in rivals.join().filter(|rival| rival.party == party) {
+        rival.stance = stance;
+    }
+}
+
+/// Move an item from a rival's pack into the player's for its price in gold, returning what to
+/// tell the player; `None` if the rival no longer has it
+fn sell(
+    buyer: Entity,
+    seller: Entity,
+    item: Entity,
+    standing: Standing,
+    properties: &ReadStorage<ItemProperties>,
+    inventories: &mut WriteStorage<AdvancedInventory>,
+) -> Option<Message> {
+    let props = properties.get(item)?;
+    let price = rival_price(props.value, standing);
+    let (gold, room) = inventories.get(buyer).map(|inventory| (inventory.gold, inventory.can_add_item(props.weight)))?;
+    if gold < price {
+        return Some(Message::new("log.rival_too_dear").arg("item", props.name.clone()).arg("gold", price));
+    }
+    if !room {
+        return Some(Message::new("log.rival_no_room").arg("item", props.name.clone()));
+    }
+    let quantity = {
+        let pack = inventories.get_mut(seller)?;
+        let slot = pack.find_item(item)?;
+        let quantity = pack.items[slot].quantity;
+        pack.remove_item(slot, quantity, props.weight);
+        pack.gold += price;
+        quantity
+    };
+    let inventory = inventories.get_mut(buyer)?;
+    inventory.add_item(item, quantity, props.weight);
+    inventory.gold -= price;
+    Some(Message::new("log.rival_sells").arg("item", props.name.clone()).arg("gold", price))
+}
+
+/// Take the first step toward `goal`, opening the closed door in the way first
+pub fn step_toward(
+    map: &Map,
+    wants_move: &mut WriteStorage<WantsToMove>,
+    wants_door: &mut WriteStorage<WantsToOperateDoor>,
+    entity: Entity,
+    here: (i32, i32),
+    goal: (i32, i32),
+) {
+    match map.find_path_through_doors(here, goal).and_then(|path| path.first().copied()) {
+        Some(step) if map.is_closed_door(step.0, step.1) => {
+            wants_door.insert(entity, WantsToOperateDoor { target: step, action: DoorAction::Open })
+                .expect("Unable to insert door intent");
+        },
+        Some(step) => {
+            wants_move.insert(entity, WantsToMove { destination: step })
+                .expect("Unable to insert move intent");
+        },
+        None => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::items::{ItemType, ConsumableType};
+    use crate::map::{Rect, TileType};
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<ItemProperties>();
+        let mut map = Map::new(20, 12, 3);
+        for x in 1..19 {
+            for y in 1..11 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        map.rooms = vec![Rect::new(1, 1, 5, 5), Rect::new(12, 4, 5, 5)];
+        map.exit = (18, 10);
+        world.insert(map);
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(Reputation::default());
+        world
+    }
+
+    fn rival(world: &mut World, x: i32, y: i32) -> Entity {
+        world.create_entity()
+            .with(Position { x, y })
+            .with(Name { name: "Brann the Fighter".to_string() })
+            .with(CombatStats { max_hp: 20, hp: 20, defense: 1, power: 5 })
+            .with(Rival { party: 7, stance: RivalStance::Neutral, rooms_explored: 0 })
+            .with(AdvancedInventory::new(10, 60.0))
+            .build()
+    }
+
+    fn player(world: &mut World, gold: i32) -> Entity {
+        let mut inventory = AdvancedInventory::new(26, 150.0);
+        inventory.gold = gold;
+        world.create_entity().with(Player).with(Position { x: 2, y: 2 }).with(inventory).build()
+    }
+
+    #[test]
+    fn test_a_party_goes_for_a_monster_before_exploring() {
+        let mut world = world();
+        let fighter = rival(&mut world, 5, 5);
+        let goblin = world.create_entity()
+            .with(Monster)
+            .with(Position { x: 6, y: 5 })
+            .with(CombatStats { max_hp: 8, hp: 8, defense: 0, power: 2 })
+            .build();
+
+        RivalSystem::new().run_now(&world);
+        assert_eq!(world.read_storage::<WantsToAttack>().get(fighter).map(|attack| attack.target), Some(goblin));
+    }
+
+    #[test]
+    fn test_a_party_that_has_seen_enough_takes_the_stairs() {
+        let mut world = world();
+        let fighter = rival(&mut world, 18, 10);
+        world.write_storage::<Rival>().get_mut(fighter).unwrap().rooms_explored = RIVAL_ROOMS_BEFORE_DESCENT;
+
+        RivalSystem::new().run_now(&world);
+        world.maintain();
+        assert!(!world.is_alive(fighter));
+    }
+
+    #[test]
+    fn test_buying_from_a_rival_and_paying_for_an_alliance() {
+        let mut world = world();
+        let fighter = rival(&mut world, 3, 2);
+        let player = player(&mut world, 100);
+        let mut potion = ItemProperties::new("Healing Potion".to_string(), ItemType::Consumable(ConsumableType::Potion));
+        potion.value = 30;
+        let item = world.create_entity().with(potion).build();
+        world.write_storage::<AdvancedInventory>().get_mut(fighter).unwrap().add_item(item, 1, 0.5);
+
+        let mut rivals = RivalSystem::new();
+        world.write_storage::<WantsToParley>().insert(player, WantsToParley { rival: fighter, offer: Parley::Buy(item) }).unwrap();
+        rivals.run_now(&world);
+        {
+            let inventories = world.read_storage::<AdvancedInventory>();
+            assert_eq!(inventories.get(player).unwrap().gold, 70);
+            assert!(inventories.get(player).unwrap().find_item(item).is_some());
+            assert!(inventories.get(fighter).unwrap().items.is_empty());
+        }
+
+        world.write_storage::<WantsToParley>().insert(player, WantsToParley { rival: fighter, offer: Parley::Ally }).unwrap();
+        rivals.run_now(&world);
+        assert_eq!(world.read_storage::<Rival>().get(fighter).unwrap().stance, RivalStance::Allied);
+        assert_eq!(world.read_storage::<AdvancedInventory>().get(player).unwrap().gold, 70 - RIVAL_ALLIANCE_FEE_PER_DEPTH * 3);
+    }
+}
@@ -1,9 +1,10 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Read, Write};
 use crate::components::{
     CombatFeedback, CombatFeedbackType, SoundEffectType, DamageInfo, 
     CombatStats, Name, Player, DefenseResult
 };
 use crate::resources::GameLog;
+use crate::rendering::MotionSettings;
 
 pub struct SoundEffectSystem {}
 
@@ -97,16 +98,19 @@ impl<'a> System<'a> for ScreenShakeSystem {
         Entities<'a>,
         WriteStorage<'a, CombatFeedback>,
         Write<'a, ScreenShakeState>,
+        Read<'a, MotionSettings>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut combat_feedback, mut screen_shake) = data;
+        let (entities, mut combat_feedback, mut screen_shake, motion) = data;
 
         // Process screen shake feedback
         for (entity, feedback) in (&entities, &combat_feedback).join() {
             if let CombatFeedbackType::ScreenShake { intensity } = &feedback.feedback_type {
-                // Update screen shake state
-                screen_shake.add_shake(intensity.clone(), feedback.duration);
+                // Update screen shake state, unless the screen is to be held still
+                if motion.shake_intensity(intensity.get_offset()) > 0.0 {
+                    screen_shake.add_shake(intensity.clone(), feedback.duration);
+                }
             }
         }
         
@@ -168,4 +172,10 @@ impl ScreenShakeState {
     pub fn is_shaking(&self) -> bool {
         self.current_intensity > 0.1
     }
+}
+
+impl Default for ScreenShakeState {
+    fn default() -> Self {
+        ScreenShakeState::new()
+    }
 }
\ No newline at end of file
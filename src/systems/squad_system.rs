@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read, ReadExpect};
+use crate::components::{
+    AIMode, CombatStats, Companion, Monster, MonsterAI, Name, PackMember, PackTactics, Player, Position, Rival, Viewshed
+};
+use crate::map::Map;
+use crate::resources::{GameLog, GameStateResource};
+use crate::utils::chebyshev;
+
+/// Share of its combined health below which a whole pack pulls back
+const RETREAT_HEALTH_PERCENT: i32 = 35;
+/// Where warband followers stand around their leader while on the move, keeping their spacing
+const FORMATION: [(i32, i32); 8] = [(-2, 0), (2, 0), (0, -2), (0, 2), (-2, -2), (2, 2), (-2, 2), (2, -2)];
+/// The tiles around a target that flankers can close in from
+const SURROUNDING: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// What the pack wants from one of its members this turn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SquadOrder {
+    /// The foe the whole pack is going after
+    pub target: Option<(Entity, (i32, i32))>,
+    /// Where this member should stand: a flanking tile in a fight, or its place in formation
+    pub post: Option<(i32, i32)>,
+}
+
+/// Orders for every pack member, worked out before the monsters take their own turns
+#[derive(Debug, Clone, Default)]
+pub struct Squads {
+    pub orders: HashMap<Entity, SquadOrder>,
+}
+
+/// Which of the foes a pack goes after: warbands gang up on the weakest, swarms on whatever the leader is nearest
+pub fn focus_target(tactics: PackTactics, foes: &[((i32, i32), i32)], leader: (i32, i32)) -> Option<usize> {
+    let indexed = foes.iter().enumerate();
+    match tactics {
+        PackTactics::Warband => indexed.min_by_key(|(_, (_, hp))| *hp).map(|(idx, _)| idx),
+        PackTactics::Swarm => indexed.min_by_key(|(_, (pos, _))| chebyshev(*pos, leader)).map(|(idx, _)| idx),
+    }
+}
+
+/// A different tile beside the target for each member, so the pack closes in from every side
+pub fn flank_posts(map: &Map, target: (i32, i32), members: &[(i32, i32)]) -> Vec<Option<(i32, i32)>> {
+    let mut free: Vec<(i32, i32)> = SURROUNDING.iter()
+        .map(|(dx, dy)| (target.0 + dx, target.1 + dy))
+        .filter(|&(x, y)| !map.is_blocked(x, y) || members.contains(&(x, y)))
+        .collect();
+    let mut order: Vec<usize> = (0..members.len()).collect();
+    order.sort_by_key(|&idx| chebyshev(members[idx], target));
+
+    let mut posts = vec![None; members.len()];
+    for idx in order {
+        let nearest = free.iter().enumerate()
+            .min_by_key(|(_, tile)| chebyshev(**tile, members[idx]))
+            .map(|(slot, _)| slot);
+        if let Some(slot) = nearest {
+            posts[idx] = Some(free.swap_remove(slot));
+        }
+    }
+    posts
+}
+
+/// Whether a pack this battered, or a warband that has lost its leader, falls back as one
+pub fn should_retreat(tactics: PackTactics, hp: i32, max_hp: i32, leader_fell: bool) -> bool {
+    (leader_fell && tactics == PackTactics::Warband)
+        || (max_hp > 0 && hp * 100 / max_hp < RETREAT_HEALTH_PERCENT)
+}
+
+/// Picks each pack's leader, quarry and formation once per turn, ahead of the individual monster AI
+pub struct SquadSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl SquadSystem {
+    pub fn new() -> Self {
+        SquadSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for SquadSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, PackMember>,
+        WriteStorage<'a, MonsterAI>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Rival>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameStateResource>,
+        Write<'a, Squads>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut pack_members,
+            mut ais,
+            monsters,
+            positions,
+            viewsheds,
+            combat_stats,
+            players,
+            companions,
+            rivals,
+            names,
+            map,
+            game_state,
+            mut squads,
+            mut gamelog,
+        ) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+        squads.orders.clear();
+
+        let hp = |entity: Entity| combat_stats.get(entity).map_or(0, |stats| stats.hp);
+        let pos = |entity: Entity| positions.get(entity).map(|pos| (pos.x, pos.y));
+
+        // Packs are whoever is still standing under each leader
+        let mut packs: HashMap<Entity, Vec<Entity>> = HashMap::new();
+        for (entity, member, _) in (&entities, &pack_members, &monsters).join() {
+            if hp(entity) > 0 {
+                packs.entry(member.leader).or_default().push(entity);
+            }
+        }
+        let foes: Vec<(Entity, (i32, i32), i32)> = (&entities, &positions, &combat_stats).join()
+            .filter(|(entity, _, stats)| {
+                stats.hp > 0 && (players.contains(*entity) || companions.contains(*entity) || rivals.contains(*entity))
+            })
+            .map(|(entity, pos, stats)| (entity, (pos.x, pos.y), stats.hp))
+            .collect();
+
+        for (leader, mut members) in packs {
+            members.sort();
+            let tactics = match pack_members.get(members[0]) {
+                Some(member) => member.tactics,
+                None => continue,
+            };
+            // The last one left is on its own
+            if members.len() < 2 {
+                pack_members.remove(members[0]);
+                continue;
+            }
+
+            let leader_fell = !members.contains(&leader);
+            let leader = if leader_fell {
+                let strongest = *members.iter().max_by_key(|&&member| hp(member)).expect("Pack has members");
+                for &member in &members {
+                    if let Some(pack) = pack_members.get_mut(member) {
+                        pack.leader = strongest;
+                    }
+                }
+                strongest
+            } else {
+                leader
+            };
+            let leader_pos = match pos(leader) {
+                Some(leader_pos) => leader_pos,
+                None => continue,
+            };
+            let name = names.get(leader).map_or("monster".to_string(), |name| name.name.to_lowercase());
+            let in_view = members.iter().any(|&member| pos(member).map_or(false, |(x, y)| map.is_visible(x, y)));
+
+            let (total_hp, total_max) = members.iter()
+                .filter_map(|&member| combat_stats.get(member))
+                .fold((0, 0), |(hp, max), stats| (hp + stats.hp, max + stats.max_hp));
+            if should_retreat(tactics, total_hp, total_max, leader_fell) {
+                let mut fell_back = false;
+                for &member in &members {
+                    if let Some(ai) = ais.get_mut(member) {
+                        if ai.mode != AIMode::Fleeing {
+                            ai.mode = AIMode::Fleeing;
+                            fell_back = true;
+                        }
+                    }
+                }
+                if fell_back && in_view {
+                    let message = if leader_fell && tactics == PackTactics::Warband {
+                        format!("Its leader fallen, the {} warband breaks and falls back!", name)
+                    } else {
+                        format!("The {} pack falls back as one!", name)
+                    };
+                    gamelog.add_entry(message);
+                }
+                continue;
+            }
+
+            // What any of them can see, all of them know about
+            let seen: Vec<&(Entity, (i32, i32), i32)> = foes.iter()
+                .filter(|(_, foe_pos, _)| members.iter().any(|&member| {
+                    match (pos(member), viewsheds.get(member)) {
+                        (Some(here), Some(viewshed)) => {
+                            chebyshev(here, *foe_pos) <= viewshed.range && map.has_line_of_sight(here, *foe_pos)
+                        },
+                        _ => false,
+                    }
+                }))
+                .collect();
+            let candidates: Vec<((i32, i32), i32)> = seen.iter().map(|(_, foe_pos, foe_hp)| (*foe_pos, *foe_hp)).collect();
+
+            match focus_target(tactics, &candidates, leader_pos).map(|idx| seen[idx]) {
+                Some(&(foe, foe_pos, _)) => {
+                    let member_pos: Vec<(i32, i32)> = members.iter().map(|&member| pos(member).unwrap_or(leader_pos)).collect();
+                    let posts = match tactics {
+                        PackTactics::Swarm => flank_posts(&map, foe_pos, &member_pos),
+                        PackTactics::Warband => vec![None; members.len()],
+                    };
+                    for (&member, post) in members.iter().zip(posts) {
+                        squads.orders.insert(member, SquadOrder { target: Some((foe, foe_pos)), post });
+                    }
+                },
+                None => {
+                    // On the move, followers keep to the leader: a warband spread out, a swarm bunched up
+                    for (slot, &member) in members.iter().filter(|&&member| member != leader).enumerate() {
+                        let post = match tactics {
+                            PackTactics::Warband => {
+                                let (dx, dy) = FORMATION[slot % FORMATION.len()];
+                                let spot = (leader_pos.0 + dx, leader_pos.1 + dy);
+                                if map.is_blocked(spot.0, spot.1) { leader_pos } else { spot }
+                            },
+                            PackTactics::Swarm => leader_pos,
+                        };
+                        squads.orders.insert(member, SquadOrder { target: None, post: Some(post) });
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    #[test]
+    fn test_warbands_focus_the_weakest_and_swarms_the_nearest() {
+        let foes = vec![((10, 10), 30), ((20, 10), 6)];
+        assert_eq!(focus_target(PackTactics::Warband, &foes, (9, 10)), Some(1));
+        assert_eq!(focus_target(PackTactics::Swarm, &foes, (9, 10)), Some(0));
+        assert_eq!(focus_target(PackTactics::Swarm, &[], (9, 10)), None);
+    }
+
+    #[test]
+    fn test_flankers_take_different_sides() {
+        let mut map = Map::new(20, 20, 1);
+        for y in 1..19 {
+            for x in 1..19 {
+                map.set_tile(x, y, TileType::Floor);
+            }
+        }
+        let members = vec![(5, 10), (5, 11), (15, 10)];
+        let posts = flank_posts(&map, (10, 10), &members);
+
+        assert!(posts.iter().all(|post| post.map_or(false, |tile| chebyshev(tile, (10, 10)) == 1)));
+        assert_ne!(posts[0], posts[1]);
+        assert!(posts[2].map_or(false, |(x, _)| x == 11));
+    }
+
+    #[test]
+    fn test_packs_retreat_together() {
+        assert!(!should_retreat(PackTactics::Swarm, 20, 40, false));
+        assert!(should_retreat(PackTactics::Swarm, 10, 40, false));
+        assert!(should_retreat(PackTactics::Warband, 40, 40, true));
+        assert!(!should_retreat(PackTactics::Swarm, 40, 40, true));
+    }
+}
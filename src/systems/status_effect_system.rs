@@ -0,0 +1,185 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Read};
+use crate::components::{
+    StatusEffects, StatusEffect, StatusEffectType, CombatStats, DamageResistances, Player, Name
+};
+use crate::resources::{GameLog, RandomNumberGenerator, GameStateResource};
+
+/// Chance (0-100) that an entity shrugs off an effect, taken from its matching damage resistance
+pub fn status_resist_chance(effect_type: StatusEffectType, resistances: Option<&DamageResistances>) -> i32 {
+    match (effect_type.resisted_by(), resistances) {
+        (Some(damage_type), Some(resist)) => (resist.get_resistance(damage_type) * 100.0) as i32,
+        _ => 0,
+    }
+}
+
+/// Apply an effect unless the target resists it; returns whether the effect took hold
+pub fn apply_status_effect(
+    target: Entity,
+    effect: StatusEffect,
+    status_effects: &mut WriteStorage<StatusEffects>,
+    resistances: Option<&DamageResistances>,
+    rng: &mut RandomNumberGenerator,
+) -> bool {
+    let resist_chance = status_resist_chance(effect.effect_type, resistances);
+    if resist_chance >= 100 || (resist_chance > 0 && rng.roll_dice(1, 100) <= resist_chance) {
+        return false;
+    }
+
+    if let Some(effects) = status_effects.get_mut(target) {
+        effects.add_effect(effect);
+    } else {
+        let mut effects = StatusEffects::new();
+        effects.add_effect(effect);
+        status_effects.insert(target, effects)
+            .expect("Failed to insert status effects");
+    }
+    true
+}
+
+/// Ticks every active status effect once per game turn
+pub struct StatusEffectSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl StatusEffectSystem {
+    pub fn new() -> Self {
+        StatusEffectSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for StatusEffectSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, CombatStats>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut status_effects, mut combat_stats, players, names, game_state, mut gamelog) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        for (entity, effects) in (&entities, &mut status_effects).join() {
+            let is_player = players.contains(entity);
+            let name = names.get(entity).map_or("Something", |n| n.name.as_str());
+
+            // Apply damage and healing over time
+            if let Some(stats) = combat_stats.get_mut(entity) {
+                for effect in &effects.effects {
+                    self.apply_tick(effect, stats, is_player, name, &mut gamelog);
+                }
+            }
+
+            // Update effect durations
+            for expired in effects.update_effects() {
+                if is_player {
+                    gamelog.add_entry(expired.expiry_message().to_string());
+                } else {
+                    gamelog.add_entry(format!("{} is no longer {}.", name, expired.name().to_lowercase()));
+                }
+            }
+        }
+    }
+}
+
+impl StatusEffectSystem {
+    fn apply_tick(&self, effect: &StatusEffect, stats: &mut CombatStats, is_player: bool, name: &str, gamelog: &mut GameLog) {
+        let (damage, verb) = match effect.effect_type {
+            StatusEffectType::Poisoned => (effect.magnitude, "Poison deals"),
+            StatusEffectType::Burning => (effect.magnitude, "Flames deal"),
+            StatusEffectType::Bleeding => (effect.magnitude, "Bleeding deals"),
+            StatusEffectType::Diseased => (1, "Disease deals"),
+            StatusEffectType::Regeneration | StatusEffectType::Blessed => {
+                let healing = i32::min(effect.magnitude, stats.max_hp - stats.hp);
+                if healing > 0 {
+                    stats.hp += healing;
+                    if is_player {
+                        gamelog.add_entry(format!("{} heals you for {} HP!", effect.effect_type.name(), healing));
+                    }
+                }
+                return;
+            },
+            _ => return,
+        };
+
+        stats.hp = i32::max(0, stats.hp - damage);
+        if is_player {
+            gamelog.add_entry(format!("{} {} damage to you!", verb, damage));
+        } else {
+            gamelog.add_entry(format!("{} {} damage to {}!", verb, damage, name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{DamageType, StackingRule};
+
+    #[test]
+    fn test_poison_intensifies_up_to_cap() {
+        let mut effects = StatusEffects::new();
+        for _ in 0..10 {
+            effects.add_effect(StatusEffect::new(StatusEffectType::Poisoned, 3, 1));
+        }
+
+        let poison = effects.get_effect(StatusEffectType::Poisoned).unwrap();
+        match StatusEffectType::Poisoned.stacking_rule() {
+            StackingRule::Intensify { max_magnitude } => assert_eq!(poison.magnitude, max_magnitude),
+            StackingRule::Refresh => panic!("poison should intensify"),
+        }
+        assert_eq!(effects.effects.len(), 1);
+    }
+
+    #[test]
+    fn test_refresh_keeps_longest_duration() {
+        let mut effects = StatusEffects::new();
+        effects.add_effect(StatusEffect::new(StatusEffectType::Haste, 5, 1));
+        effects.add_effect(StatusEffect::new(StatusEffectType::Haste, 2, 2));
+
+        let haste = effects.get_effect(StatusEffectType::Haste).unwrap();
+        assert_eq!(haste.duration, 5);
+        assert_eq!(haste.magnitude, 2);
+    }
+
+    #[test]
+    fn test_expired_effects_are_reported() {
+        let mut effects = StatusEffects::new();
+        effects.add_effect(StatusEffect::new(StatusEffectType::Slow, 1, 1));
+        effects.add_effect(StatusEffect::new(StatusEffectType::Blessed, 3, 1));
+
+        let expired = effects.update_effects();
+        assert_eq!(expired, vec![StatusEffectType::Slow]);
+        assert!(effects.has_effect(StatusEffectType::Blessed));
+    }
+
+    #[test]
+    fn test_stat_modifiers_aggregate() {
+        let mut effects = StatusEffects::new();
+        effects.add_effect(StatusEffect::new(StatusEffectType::DefenseBoost, 5, 3));
+        effects.add_effect(StatusEffect::new(StatusEffectType::Cursed, 5, 1));
+        effects.add_effect(StatusEffect::new(StatusEffectType::StrengthBoost, 5, 2));
+
+        let modifiers = effects.stat_modifiers();
+        assert_eq!(modifiers.defense, 2);
+        assert_eq!(modifiers.power, -1);
+        assert_eq!(modifiers.attribute(crate::components::AttributeType::Strength), 2);
+    }
+
+    #[test]
+    fn test_resistance_blocks_matching_effects() {
+        let mut resistances = DamageResistances::new();
+        resistances.add_resistance(DamageType::Fire, 1.0);
+
+        assert_eq!(status_resist_chance(StatusEffectType::Burning, Some(&resistances)), 100);
+        assert_eq!(status_resist_chance(StatusEffectType::Poisoned, Some(&resistances)), 0);
+        assert_eq!(status_resist_chance(StatusEffectType::Burning, None), 0);
+    }
+}
@@ -1,182 +1,188 @@
 use specs::prelude::*;
 use crate::systems::{
     VisibilitySystem, MovementSystem, RenderSystem, PlayerController,
-    ExperienceSystem, LevelUpSystem, AbilitySystem, ExperienceGainSystem,
+    ExperienceSystem, LevelUpSystem, ExperienceGainSystem,
     EquipmentSystem, EquipmentBonusSystem, ResourceRegenerationSystem,
-    StatusEffectSystem, AbilityUsageSystem, PlayerDeathSystem,
+    StatusEffectSystem, PlayerDeathSystem,
     DeathPenaltySystem, RevivalSystem, GameOverSystem, EnhancedCombatSystem,
     EnhancedDamageSystem, InitiativeSystem, TurnOrderSystem, CombatResolutionSystem,
     CriticalHitSystem, CriticalChanceSystem, DamageTypeSystem, ResistanceManagementSystem,
     CombatFeedbackSystem, SoundEffectSystem, ScreenShakeSystem, VisualEffectsSystem,
-    ParticleEffectSystem, ScreenShakeState, SpecialAbilitiesSystem, AbilityTargetingSystem,
-    AbilityCooldownSystem, CombatRewardsSystem, TreasureSystem
+    ParticleEffectSystem, AbilityResolutionSystem, AbilityTargetingSystem,
+    AbilityCooldownSystem, CombatRewardsSystem, TreasureSystem, TerrainReactionSystem, FireSystem, GasSystem, ClimateSystem,
+    SummonSystem, CompanionLifecycleSystem, CompanionAISystem, VictorySystem, RangedCombatSystem,
+    ForcedMovementSystem, AreaEffectSystem, BreathWeaponSystem, CorpseSystem,
+    EliteSplitSystem, WeatherSystem, ClockSystem, RestSystem, WanderingMonsterSystem,
+    MonsterAISystem, MoraleSystem, SquadSystem, DijkstraMapSystem, AutoExploreSystem, TravelSystem, DoorSystem, FurnitureSystem,
+    RescueSystem, EscortSystem, RivalSystem, TownsfolkSystem, CrimeSystem, BankSystem, GuildSystem, WorldEventSystem,
+    ModelResultSystem, LiquidSystem, ReligionSystem, ReputationSystem,
+    AchievementEvaluationSystem, BestiarySystem
+};
+use crate::items::{
+    ItemPickupSystem, ItemDropSystem, EquipmentSystem as ItemEquipSystem, ConsumableUsageSystem,
+    DurabilitySystem, RepairSystem, EnchantingSystem, ArtifactSystem, CurseSystem, AlchemySystem
 };
-use crate::inventory::{InventorySystem, EquipmentSystem, ItemUseSystem};
 use crate::combat::{CombatSystem, DamageSystem, DeathSystem};
 
+/// Runs the game systems once per update through a specs dispatcher, so systems that touch
+/// different data run side by side on the rayon thread pool.
+///
+/// Each update goes through these stages in order, with a barrier between each one:
+///
+/// 1. Input: the player controller turns key presses into intents.
+/// 2. Perception and time: field of view, then resting, the shared Dijkstra maps and auto-explore,
+///    which may move the turn on for everything after them.
+/// 3. AI: companions, freed captives, rival parties and the townsfolk think alongside the monsters;
+///    packs pick their quarry before morale breaks the frightened, and both come before each
+///    monster's own behavior tree.
+/// 4. Movement, once any doors in the way have been opened, closed, bashed or jammed, any
+///    furniture beside the player has been used and any captive beside them freed.
+/// 5. Combat and the world turn: attacks resolve in a fixed chain, then damage and healing over
+///    time tick, and death comes last so a kill by either resolves this update. Terrain,
+///    fire, liquids, gas, weather, the climate, the clock, the bank, the world above, the guild's
+///    agents and wandering monsters tick alongside. Whatever the language model has answered since
+///    the last update is collected once the guild is done.
+/// 6. Aftermath: companions, inventory and equipment, regeneration, rewards, treasure, corpses,
+///    victory, the bestiary and experience, each chain independent of the others,
+///    while the town watch takes note of any crime once pickups are done.
+/// 7. Abilities and area effects: cooldowns tick once a turn, then every ability request is
+///    targeted and resolved in one place before its knockbacks and blasts play out. The gods
+///    then weigh the deeds done this update, abilities included, while the factions weigh the
+///    character's conduct.
+/// 8. Player death and revival.
+/// 9. Feedback: combat text, sound, screen shake, visual effects and particles, all in parallel,
+///    along with the achievements weighing up the events published earlier in the update.
+///
+/// Within a stage, only the dependencies named below order the systems; anything else may run at
+/// the same time as long as it does not write what another reads. Rendering stays on the main
+/// thread and runs separately once the update is done.
 pub struct SystemRunner {
-    pub visibility_system: VisibilitySystem,
-    pub movement_system: MovementSystem,
+    pub dispatcher: Dispatcher<'static, 'static>,
     pub render_system: RenderSystem,
-    pub player_controller: PlayerController,
-    pub experience_system: ExperienceSystem,
-    pub level_up_system: LevelUpSystem,
-    pub ability_system: AbilitySystem,
-    pub experience_gain_system: ExperienceGainSystem,
-    pub equipment_system: EquipmentSystem,
-    pub equipment_bonus_system: EquipmentBonusSystem,
-    pub resource_regeneration_system: ResourceRegenerationSystem,
-    pub status_effect_system: StatusEffectSystem,
-    pub ability_usage_system: AbilityUsageSystem,
-    pub player_death_system: PlayerDeathSystem,
-    pub death_penalty_system: DeathPenaltySystem,
-    pub revival_system: RevivalSystem,
-    pub game_over_system: GameOverSystem,
-    pub enhanced_combat_system: EnhancedCombatSystem,
-    pub enhanced_damage_system: EnhancedDamageSystem,
-    pub initiative_system: InitiativeSystem,
-    pub turn_order_system: TurnOrderSystem,
-    pub combat_resolution_system: CombatResolutionSystem,
-    pub critical_hit_system: CriticalHitSystem,
-    pub critical_chance_system: CriticalChanceSystem,
-    pub damage_type_system: DamageTypeSystem,
-    pub resistance_management_system: ResistanceManagementSystem,
-    pub combat_feedback_system: CombatFeedbackSystem,
-    pub sound_effect_system: SoundEffectSystem,
-    pub screen_shake_system: ScreenShakeSystem,
-    pub visual_effects_system: VisualEffectsSystem,
-    pub particle_effect_system: ParticleEffectSystem,
-    pub special_abilities_system: SpecialAbilitiesSystem,
-    pub ability_targeting_system: AbilityTargetingSystem,
-    pub ability_cooldown_system: AbilityCooldownSystem,
-    pub combat_rewards_system: CombatRewardsSystem,
-    pub treasure_system: TreasureSystem,
-    pub inventory_system: InventorySystem,
-    pub equipment_system: EquipmentSystem,
-    pub item_use_system: ItemUseSystem,
-    pub combat_system: CombatSystem,
-    pub damage_system: DamageSystem,
-    pub death_system: DeathSystem,
 }
 
 impl SystemRunner {
     pub fn new() -> Self {
+        let dispatcher = DispatcherBuilder::new()
+            // Input
+            .with(PlayerController {}, "player_controller", &[])
+            .with_barrier()
+            // Perception and time
+            .with(VisibilitySystem {}, "visibility", &[])
+            .with(RestSystem, "rest", &["visibility"])
+            .with(DijkstraMapSystem::new(), "dijkstra_maps", &["rest"])
+            .with(AutoExploreSystem, "auto_explore", &["dijkstra_maps"])
+            .with(TravelSystem, "travel", &["rest"])
+            .with_barrier()
+            // AI
+            .with(CompanionAISystem::new(), "companion_ai", &[])
+            .with(EscortSystem::new(), "escorts", &[])
+            .with(RivalSystem::new(), "rivals", &[])
+            .with(TownsfolkSystem::new(), "townsfolk", &[])
+            .with(SquadSystem::new(), "squads", &[])
+            .with(MoraleSystem::new(), "morale", &["squads"])
+            .with(MonsterAISystem::new(), "monster_ai", &["squads", "morale"])
+            .with_barrier()
+            // Movement
+            .with(DoorSystem, "doors", &[])
+            .with(FurnitureSystem, "furniture", &["doors"])
+            .with(RescueSystem, "rescues", &["doors"])
+            .with(MovementSystem {}, "movement", &["doors"])
+            .with_barrier()
+            // Combat and the world turn
+            .with(InitiativeSystem {}, "initiative", &[])
+            .with(TurnOrderSystem {}, "turn_order", &["initiative"])
+            .with(CriticalChanceSystem {}, "critical_chance", &["turn_order"])
+            .with(ResistanceManagementSystem {}, "resistance_management", &["turn_order"])
+            .with(CombatResolutionSystem {}, "combat_resolution", &["critical_chance", "resistance_management"])
+            .with(RangedCombatSystem, "ranged_combat", &["combat_resolution"])
+            .with(CriticalHitSystem {}, "critical_hit", &["ranged_combat"])
+            .with(DamageTypeSystem {}, "damage_type", &["critical_hit"])
+            .with(ArtifactSystem::new(), "artifacts", &["damage_type"])
+            .with(EnhancedCombatSystem {}, "enhanced_combat", &["artifacts"])
+            .with(EnhancedDamageSystem {}, "enhanced_damage", &["enhanced_combat"])
+            .with(CombatSystem {}, "combat", &["enhanced_damage"])
+            .with(DamageSystem {}, "damage", &["combat"])
+            .with(StatusEffectSystem::new(), "status_effects", &["damage"])
+            .with(DeathSystem {}, "death", &["damage", "status_effects"])
+            .with(TerrainReactionSystem, "terrain_reaction", &[])
+            .with(FireSystem::new(), "fire", &["terrain_reaction"])
+            .with(LiquidSystem::new(), "liquids", &["fire"])
+            .with(GasSystem::new(), "gas", &["death", "liquids"])
+            .with(WeatherSystem::new(), "weather", &[])
+            .with(ClimateSystem::new(), "climate", &["liquids"])
+            .with(ClockSystem::new(), "clock", &[])
+            .with(WanderingMonsterSystem::new(), "wandering_monsters", &["clock"])
+            .with(BankSystem::new(), "bank", &["clock"])
+            .with(WorldEventSystem::new(), "world_events", &["clock"])
+            .with(GuildSystem::new(), "guild", &["clock", "bank", "world_events"])
+            .with(ModelResultSystem, "model_results", &["guild"])
+            .with_barrier()
+            // Aftermath
+            .with(SummonSystem {}, "summon", &[])
+            .with(CompanionLifecycleSystem::new(), "companion_lifecycle", &["summon"])
+            .with(ItemPickupSystem, "inventory", &[])
+            .with(ItemDropSystem, "item_drop", &["inventory"])
+            .with(EquipmentSystem {}, "equipment", &["item_drop"])
+            .with(ItemEquipSystem, "item_equip", &["equipment"])
+            .with(ConsumableUsageSystem, "item_use", &["item_equip"])
+            .with(CurseSystem, "curses", &["item_use"])
+            .with(DurabilitySystem, "durability", &["curses"])
+            .with(RepairSystem, "repair", &["durability"])
+            .with(EnchantingSystem, "enchanting", &["repair"])
+            .with(AlchemySystem, "alchemy", &["enchanting"])
+            .with(EquipmentBonusSystem {}, "equipment_bonus", &["alchemy"])
+            .with(ResourceRegenerationSystem::new(), "resource_regeneration", &["equipment_bonus"])
+            .with(CombatRewardsSystem {}, "combat_rewards", &[])
+            .with(EliteSplitSystem {}, "elite_split", &["combat_rewards"])
+            .with(TreasureSystem {}, "treasure", &["combat_rewards"])
+            .with(CorpseSystem::new(), "corpses", &["elite_split"])
+            .with(VictorySystem::new(), "victory", &[])
+            .with(BestiarySystem::new(), "bestiary", &[])
+            .with(ExperienceGainSystem {}, "experience_gain", &["combat_rewards"])
+            .with(ExperienceSystem {}, "experience", &["experience_gain"])
+            .with(LevelUpSystem {}, "level_up", &["experience"])
+            .with(CrimeSystem {}, "crimes", &["inventory", "combat_rewards"])
+            .with_barrier()
+            // Abilities and area effects
+            .with(AbilityCooldownSystem::new(), "ability_cooldown", &[])
+            .with(AbilityTargetingSystem {}, "ability_targeting", &["ability_cooldown"])
+            .with(AbilityResolutionSystem {}, "ability_resolution", &["ability_targeting"])
+            .with(ForcedMovementSystem, "forced_movement", &["ability_resolution"])
+            .with(BreathWeaponSystem::new(), "breath_weapons", &["forced_movement"])
+            .with(AreaEffectSystem, "area_effects", &["breath_weapons"])
+            .with(ReligionSystem {}, "religion", &["ability_resolution"])
+            .with(ReputationSystem {}, "reputation", &[])
+            .with_barrier()
+            // Player death and revival
+            .with(PlayerDeathSystem {}, "player_death", &[])
+            .with(DeathPenaltySystem {}, "death_penalty", &["player_death"])
+            .with(RevivalSystem {}, "revival", &["death_penalty"])
+            .with(GameOverSystem {}, "game_over", &["revival"])
+            .with_barrier()
+            // Feedback
+            .with(CombatFeedbackSystem {}, "combat_feedback", &[])
+            .with(SoundEffectSystem {}, "sound_effects", &[])
+            .with(ScreenShakeSystem {}, "screen_shake", &[])
+            .with(VisualEffectsSystem {}, "visual_effects", &[])
+            .with(ParticleEffectSystem {}, "particles", &[])
+            .with(AchievementEvaluationSystem::new(), "achievements", &[])
+            .build();
+        
         SystemRunner {
-            visibility_system: VisibilitySystem {},
-            movement_system: MovementSystem {},
+            dispatcher,
             render_system: RenderSystem::new(),
-            player_controller: PlayerController {},
-            experience_system: ExperienceSystem {},
-            level_up_system: LevelUpSystem {},
-            ability_system: AbilitySystem {},
-            experience_gain_system: ExperienceGainSystem {},
-            equipment_system: EquipmentSystem {},
-            equipment_bonus_system: EquipmentBonusSystem {},
-            resource_regeneration_system: ResourceRegenerationSystem {},
-            status_effect_system: StatusEffectSystem {},
-            ability_usage_system: AbilityUsageSystem {},
-            player_death_system: PlayerDeathSystem {},
-            death_penalty_system: DeathPenaltySystem {},
-            revival_system: RevivalSystem {},
-            game_over_system: GameOverSystem {},
-            enhanced_combat_system: EnhancedCombatSystem {},
-            enhanced_damage_system: EnhancedDamageSystem {},
-            initiative_system: InitiativeSystem {},
-            turn_order_system: TurnOrderSystem {},
-            combat_resolution_system: CombatResolutionSystem {},
-            critical_hit_system: CriticalHitSystem {},
-            critical_chance_system: CriticalChanceSystem {},
-            damage_type_system: DamageTypeSystem {},
-            resistance_management_system: ResistanceManagementSystem {},
-            combat_feedback_system: CombatFeedbackSystem {},
-            sound_effect_system: SoundEffectSystem {},
-            screen_shake_system: ScreenShakeSystem {},
-            visual_effects_system: VisualEffectsSystem {},
-            particle_effect_system: ParticleEffectSystem {},
-            special_abilities_system: SpecialAbilitiesSystem {},
-            ability_targeting_system: AbilityTargetingSystem {},
-            ability_cooldown_system: AbilityCooldownSystem {},
-            combat_rewards_system: CombatRewardsSystem {},
-            treasure_system: TreasureSystem {},
-            inventory_system: InventorySystem {},
-            equipment_system: EquipmentSystem {},
-            item_use_system: ItemUseSystem {},
-            combat_system: CombatSystem {},
-            damage_system: DamageSystem {},
-            death_system: DeathSystem {},
         }
     }
     
+    /// Register every component and insert the default of every resource the systems use,
+    /// before the first update
+    pub fn setup(&mut self, world: &mut World) {
+        self.dispatcher.setup(world);
+    }
+    
     pub fn run_systems(&mut self, world: &mut World) {
-        // Run the player controller system
-        self.player_controller.run_now(world);
-        
-        // Run the visibility system
-        self.visibility_system.run_now(world);
-        
-        // Run the movement system
-        self.movement_system.run_now(world);
-        
-        // Run the combat systems
-        self.initiative_system.run_now(world);
-        self.turn_order_system.run_now(world);
-        self.critical_chance_system.run_now(world);
-        self.resistance_management_system.run_now(world);
-        self.combat_resolution_system.run_now(world);
-        self.critical_hit_system.run_now(world);
-        self.damage_type_system.run_now(world);
-        self.enhanced_combat_system.run_now(world);
-        self.enhanced_damage_system.run_now(world);
-        self.combat_system.run_now(world);
-        self.damage_system.run_now(world);
-        self.death_system.run_now(world);
-        
-        // Run the inventory systems
-        self.inventory_system.run_now(world);
-        self.equipment_system.run_now(world);
-        self.item_use_system.run_now(world);
-        
-        // Run the equipment bonus system
-        self.equipment_bonus_system.run_now(world);
-        
-        // Run the resource systems
-        self.resource_regeneration_system.run_now(world);
-        self.status_effect_system.run_now(world);
-        self.ability_usage_system.run_now(world);
-        
-        // Run the combat rewards system
-        self.combat_rewards_system.run_now(world);
-        
-        // Run the treasure system
-        self.treasure_system.run_now(world);
-        
-        // Run the experience gain system to award XP for kills
-        self.experience_gain_system.run_now(world);
-        
-        // Run the experience system to check for level ups
-        self.experience_system.run_now(world);
-        
-        // Run the level up system to apply level up bonuses
-        self.level_up_system.run_now(world);
-        
-        // Run the ability systems
-        self.ability_cooldown_system.run_now(world);
-        self.ability_targeting_system.run_now(world);
-        self.special_abilities_system.run_now(world);
-        self.ability_system.run_now(world);
-        
-        // Run the death and revival systems
-        self.player_death_system.run_now(world);
-        self.death_penalty_system.run_now(world);
-        self.revival_system.run_now(world);
-        self.game_over_system.run_now(world);
-        
-        // Run the combat feedback systems
-        self.combat_feedback_system.run_now(world);
-        self.sound_effect_system.run_now(world);
-        self.screen_shake_system.run_now(world);
-        self.visual_effects_system.run_now(world);
-        self.particle_effect_system.run_now(world);
+        self.dispatcher.dispatch(world);
         
         // Apply changes to the world
         world.maintain();
@@ -186,4 +192,4 @@ impl SystemRunner {
         // Run the render system
         self.render_system.run_now(world);
     }
-}
\ No newline at end of file
+}
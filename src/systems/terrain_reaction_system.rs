@@ -0,0 +1,152 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, WriteExpect};
+use std::collections::{HashSet, VecDeque};
+use crate::components::{DamageInfo, DamageType, Position, Player, SufferDamage};
+use crate::map::{Map, TileEffect};
+use crate::resources::GameLog;
+
+/// How long frozen water stays walkable
+const FREEZE_DURATION: i32 = 10;
+/// How far lightning travels through connected water
+const LIGHTNING_CHAIN_RANGE: usize = 12;
+
+/// System that lets elemental damage reshape the terrain around its target. Once alight, fires
+/// are left to the `FireSystem`
+pub struct TerrainReactionSystem;
+
+impl<'a> System<'a> for TerrainReactionSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, DamageInfo>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, SufferDamage>,
+        WriteExpect<'a, Map>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            damage_info,
+            positions,
+            players,
+            mut suffer_damage,
+            mut map,
+            mut gamelog,
+        ) = data;
+
+        // React to this frame's elemental hits
+        let mut hits = Vec::new();
+        for (entity, damage, pos) in (&entities, &damage_info, &positions).join() {
+            hits.push((entity, damage.damage_type, damage.base_damage, pos.x, pos.y));
+        }
+
+        for (target, damage_type, amount, x, y) in hits {
+            match damage_type {
+                DamageType::Fire => self.apply_fire(&mut map, x, y, &mut gamelog),
+                DamageType::Ice => self.apply_ice(&mut map, x, y, &mut gamelog),
+                DamageType::Lightning => self.apply_lightning(
+                    target, amount, x, y, &map, &entities, &positions, &players, &mut suffer_damage, &mut gamelog,
+                ),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl TerrainReactionSystem {
+    fn apply_fire(&self, map: &mut Map, x: i32, y: i32, gamelog: &mut GameLog) {
+        if map.has_tile_effect(x, y, TileEffect::Frozen) {
+            map.clear_tile_state(x, y);
+            gamelog.add_entry("The ice melts back into water.".to_string());
+            return;
+        }
+
+        let webbed = map.has_tile_effect(x, y, TileEffect::Webbed);
+        if map.ignite(x, y) {
+            let message = if webbed { "The webs go up in flames!" } else { "The ground catches fire!" };
+            gamelog.add_entry(message.to_string());
+        }
+    }
+
+    fn apply_ice(&self, map: &mut Map, x: i32, y: i32, gamelog: &mut GameLog) {
+        if map.has_tile_effect(x, y, TileEffect::Burning) {
+            map.clear_tile_state(x, y);
+            gamelog.add_entry("The flames are snuffed out.".to_string());
+        }
+
+        let mut froze = map.freeze_tile(x, y, FREEZE_DURATION);
+        for (nx, ny) in map.get_orthogonal_neighbors(x, y) {
+            froze |= map.freeze_tile(nx, ny, FREEZE_DURATION);
+        }
+
+        if froze {
+            gamelog.add_entry("The water freezes solid!".to_string());
+        }
+    }
+
+    fn apply_lightning(
+        &self,
+        target: Entity,
+        amount: i32,
+        x: i32,
+        y: i32,
+        map: &Map,
+        entities: &Entities,
+        positions: &ReadStorage<Position>,
+        players: &ReadStorage<Player>,
+        suffer_damage: &mut WriteStorage<SufferDamage>,
+        gamelog: &mut GameLog,
+    ) {
+        let conductive = conductive_region(map, x, y, LIGHTNING_CHAIN_RANGE);
+        if conductive.is_empty() {
+            return;
+        }
+
+        let chain_damage = (amount / 2).max(1);
+        let mut shocked = 0;
+        for (entity, pos) in (entities, positions).join() {
+            if entity == target || !conductive.contains(&(pos.x, pos.y)) {
+                continue;
+            }
+            SufferDamage::new_damage(suffer_damage, entity, chain_damage);
+            if players.get(entity).is_some() {
+                gamelog.add_entry(format!("Lightning arcs through the water and shocks you for {}!", chain_damage));
+            }
+            shocked += 1;
+        }
+
+        if shocked > 0 {
+            gamelog.add_entry("Lightning chains through the water!".to_string());
+        }
+    }
+}
+
+/// All conductive tiles connected to (x, y), limited to `max_tiles`
+pub fn conductive_region(map: &Map, x: i32, y: i32, max_tiles: usize) -> HashSet<(i32, i32)> {
+    let mut region = HashSet::new();
+    if !map.get_tile(x, y).map_or(false, |tile| tile.conducts_electricity()) {
+        return region;
+    }
+
+    let mut queue = VecDeque::new();
+    queue.push_back((x, y));
+    region.insert((x, y));
+
+    while let Some((cx, cy)) = queue.pop_front() {
+        if region.len() >= max_tiles {
+            break;
+        }
+        for (nx, ny) in map.get_orthogonal_neighbors(cx, cy) {
+            if region.contains(&(nx, ny)) {
+                continue;
+            }
+            if map.get_tile(nx, ny).map_or(false, |tile| tile.conducts_electricity()) {
+                region.insert((nx, ny));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    region
+}
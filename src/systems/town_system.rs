@@ -0,0 +1,806 @@
+use serde::{Serialize, Deserialize};
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Read, Write, ReadExpect};
+use specs::storage::GenericReadStorage;
+use crate::components::{
+    Abilities, AbilityType, CharacterClass, CombatReward, CombatStats, Companion, Experience, ForSale, GameMode,
+    GameSettings, LastAttacker, Name, Piety, Player, Position, TownDeal, TownRole, Townsperson, Viewshed, WantsToAttack,
+    WantsToDeal, WantsToMove, WantsToOperateDoor,
+};
+use crate::items::{AdvancedInventory, ItemProperties};
+use crate::localization::Message;
+use crate::map::{Map, TownLayout, BuildingKind};
+use crate::progression::{Bank, WorldEvents};
+use crate::resources::{GameClock, GameLog, GameStateResource, RandomNumberGenerator};
+use crate::utils::chebyshev;
+use super::companion_system::kill_credit;
+use super::religion_system::{Deed, DivineDeeds};
+use super::reputation_system::{Conduct, Faction, Reputation, Standing};
+use super::rival_system::step_toward;
+
+/// How far a guard sees
+const GUARD_SIGHT: i32 = 10;
+/// Percent chance a villager ambles a step on any turn
+const VILLAGER_WANDER_CHANCE: i32 = 30;
+/// How far villagers stray from where they usually stand
+const VILLAGER_ROAM: i32 = 5;
+/// Turns in the cells for every gold of the fine
+pub const SENTENCE_TURNS_PER_GOLD: u32 = 6;
+/// Fewest turns anyone spends in the cells
+const MIN_SENTENCE: u32 = 120;
+/// Percent of the purse the watch keeps for a prisoner's board
+pub const JAIL_BOARD_PERCENT: i32 = 25;
+/// Gold a trainer asks for every level the character has, to move their skill points
+const RETRAIN_COST_PER_LEVEL: i32 = 25;
+/// Gold a trainer asks for every level the character has, to move their attribute points
+const RESPEC_COST_PER_LEVEL: i32 = 40;
+/// Gold a trainer asks for every level of the character and of the ability they teach
+const TUITION_PER_LEVEL: i32 = 30;
+/// Furthest a respec lets any attribute drop from where it stood
+pub const RESPEC_LIMIT: i32 = 2;
+
+/// Something done in Ravenhollow that the watch comes asking about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crime {
+    /// Walked out of the general store with wares worth `value` unpaid
+    Theft { value: i32 },
+    Assault,
+    Murder,
+}
+
+impl Crime {
+    /// What the watch fines for it from someone the town thinks nothing of either way
+    pub fn base_fine(&self) -> i32 {
+        match self {
+            Crime::Theft { value } => value.max(1) * 2,
+            Crime::Assault => 50,
+            Crime::Murder => 250,
+        }
+    }
+
+    /// Turns of the watch's patience used up at once; violence wears it thinner than theft
+    pub fn alarm(&self) -> i32 {
+        match self {
+            Crime::Theft { .. } => 0,
+            Crime::Assault => 10,
+            Crime::Murder => 25,
+        }
+    }
+
+    pub fn conduct(&self) -> Conduct {
+        match self {
+            Crime::Theft { .. } => Conduct::Theft,
+            Crime::Assault => Conduct::Assault,
+            Crime::Murder => Conduct::Murder,
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            Crime::Theft { .. } => "log.crime_theft",
+            Crime::Assault => "log.crime_assault",
+            Crime::Murder => "log.crime_murder",
+        }
+    }
+}
+
+/// What the shopkeeper asks for a ware worth `value`, given how the town sees the character and
+/// `percent` of the usual price for whatever is going on in the world
+pub fn ware_price(value: i32, standing: Standing, percent: i32) -> i32 {
+    (value.max(1) as f32 * standing.price_multiplier() * percent as f32 / 100.0).ceil() as i32
+}
+
+/// Unpaid wares in a pack, with how many of each; stolen goods only if `stolen`
+pub fn unpaid_wares(pack: &AdvancedInventory, for_sale: &impl GenericReadStorage<Component = ForSale>, stolen: bool) -> Vec<(Entity, i32)> {
+    pack.items.iter()
+        .filter(|slot| for_sale.get(slot.entity).map_or(false, |ware| ware.stolen == stolen))
+        .map(|slot| (slot.entity, slot.quantity))
+        .collect()
+}
+
+/// What the trainers at the guild hall offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Training {
+    /// Take skill points back out of skills and put them into others
+    Skills,
+    /// Move attribute points about, dropping none by more than `RESPEC_LIMIT`
+    Attributes,
+    /// Learn an ability from another class
+    Ability(AbilityType),
+}
+
+impl Training {
+    /// What a trainer charges a character of `level`, given how the guild sees them. The higher
+    /// the level, the more there is to undo, and the dearer it gets
+    pub fn cost(&self, level: i32, standing: Standing) -> i32 {
+        let level = level.max(1);
+        let base = match self {
+            Training::Skills => RETRAIN_COST_PER_LEVEL * level,
+            Training::Attributes => RESPEC_COST_PER_LEVEL * level,
+            Training::Ability(ability) => TUITION_PER_LEVEL * (level + ability.required_level()),
+        };
+        (base as f32 * standing.price_multiplier()).ceil() as i32
+    }
+}
+
+/// Where the character stands with the law in Ravenhollow. Saved with the game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TownWatch {
+    /// Gold owed for crimes not yet answered for; anything owed means the character is wanted
+    pub fine: i32,
+    /// Turns the watch has been kept waiting since telling the character what they owe
+    pub alarm: i32,
+    /// The watch has stopped asking and is out to take the character down
+    pub hostile: bool,
+    /// A guard has caught up with the character and told them what they owe
+    pub warned: bool,
+    /// The guard who just did, for the game to ask how the character answers
+    #[serde(skip)]
+    pub confronting: Option<Entity>,
+    #[serde(skip)]
+    pub crimes: Vec<Crime>,
+}
+
+impl TownWatch {
+    pub fn report(&mut self, crime: Crime) {
+        self.crimes.push(crime);
+    }
+
+    pub fn take(&mut self) -> Vec<Crime> {
+        std::mem::take(&mut self.crimes)
+    }
+
+    pub fn is_wanted(&self) -> bool {
+        self.fine > 0
+    }
+
+    /// Whether guards fight the character rather than talk, for what they have done or for how
+    /// the town sees them
+    pub fn guards_hostile(&self, standing: Standing) -> bool {
+        self.hostile || standing.guards_attack_on_sight()
+    }
+
+    /// The turns in the cells that would answer for what is owed
+    pub fn sentence(&self) -> u32 {
+        (self.fine.max(0) as u32 * SENTENCE_TURNS_PER_GOLD).max(MIN_SENTENCE)
+    }
+
+    /// Wipe the slate once the character has answered for their crimes
+    pub fn settle(&mut self) {
+        self.fine = 0;
+        self.alarm = 0;
+        self.hostile = false;
+        self.warned = false;
+    }
+
+    /// Away from town tempers cool, though what is owed is still owed
+    pub fn cool_off(&mut self) {
+        self.alarm = 0;
+        self.hostile = false;
+        self.warned = false;
+    }
+}
+
+/// Runs the people of Ravenhollow once a turn. Shopkeepers, bankers, priests and gamblers keep
+/// to their places and villagers amble about the commons, while guards keep to their posts until the character is wanted.
+/// Then any guard who sees them comes to tell them what they owe, and after that the watch
+/// waits only as long as the town's opinion of them allows before drawing steel; a watch that
+/// has drawn it hunts them through the streets. Also settles what the character does when
+/// dealing with someone in town: paying for wares, paying a fine, going quietly to the cells,
+/// banking, buying property, giving to the temple, throwing dice or turning on them. The watch
+/// cools off once the character leaves town
+pub struct TownsfolkSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl TownsfolkSystem {
+    pub fn new() -> Self {
+        TownsfolkSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for TownsfolkSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Townsperson>,
+        WriteStorage<'a, WantsToDeal>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, ItemProperties>,
+        ReadStorage<'a, Piety>,
+        ReadStorage<'a, Experience>,
+        ReadStorage<'a, CharacterClass>,
+        WriteStorage<'a, Abilities>,
+        WriteStorage<'a, ForSale>,
+        WriteStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, WantsToAttack>,
+        WriteStorage<'a, WantsToOperateDoor>,
+        ReadExpect<'a, Map>,
+        Write<'a, GameStateResource>,
+        Write<'a, TownWatch>,
+        Read<'a, Reputation>,
+        Read<'a, WorldEvents>,
+        Write<'a, Bank>,
+        Write<'a, DivineDeeds>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut townsfolk,
+            mut wants_deal,
+            mut positions,
+            mut viewsheds,
+            combat_stats,
+            players,
+            properties,
+            pieties,
+            experience,
+            classes,
+            mut abilities,
+            mut for_sale,
+            mut inventories,
+            mut wants_move,
+            mut wants_attack,
+            mut wants_door,
+            map,
+            mut game_state,
+            mut watch,
+            reputation,
+            world_events,
+            mut bank,
+            mut deeds,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        let standing = reputation.standing(Faction::Townsfolk);
+        let price_percent = world_events.price_percent();
+        let requests: Vec<(Entity, WantsToDeal)> = (&entities, &wants_deal).join()
+            .map(|(entity, request)| (entity, request.clone()))
+            .collect();
+        wants_deal.clear();
+        for (player, request) in requests {
+            match request.deal {
+                TownDeal::PayForWares => {
+                    let wares = inventories.get(player).map_or(Vec::new(), |pack| unpaid_wares(pack, &for_sale, false));
+                    let price: i32 = wares.iter()
+                        .filter_map(|(item, quantity)| properties.get(*item).map(|props| ware_price(props.value, standing, price_percent) * quantity))
+                        .sum();
+                    let paid = match inventories.get_mut(player) {
+                        Some(pack) if pack.gold >= price => {
+                            pack.gold -= price;
+                            true
+                        },
+                        _ => false,
+                    };
+                    if paid {
+                        for (item, _) in wares {
+                            for_sale.remove(item);
+                        }
+                        gamelog.add_message(Message::new("log.wares_paid").arg("gold", price));
+                    } else {
+                        gamelog.add_message(Message::new("log.wares_too_dear").arg("gold", price));
+                    }
+                },
+                TownDeal::PayFine => {
+                    let paid = match inventories.get_mut(player) {
+                        Some(pack) if pack.gold >= watch.fine => {
+                            pack.gold -= watch.fine;
+                            true
+                        },
+                        _ => false,
+                    };
+                    if !paid {
+                        gamelog.add_message(Message::new("log.fine_too_dear").arg("gold", watch.fine));
+                        continue;
+                    }
+                    // The fine pays for whatever was taken, too
+                    let stolen = inventories.get(player).map_or(Vec::new(), |pack| unpaid_wares(pack, &for_sale, true));
+                    for (item, _) in stolen {
+                        for_sale.remove(item);
+                    }
+                    gamelog.add_message(Message::new("log.fine_paid").arg("gold", watch.fine));
+                    watch.settle();
+                },
+                TownDeal::ServeSentence => {
+                    let sentence = watch.sentence();
+                    let mut board = 0;
+                    if let Some(pack) = inventories.get_mut(player) {
+                        board = pack.gold * JAIL_BOARD_PERCENT / 100;
+                        pack.gold -= board;
+                        // Stolen goods go back where they came from
+                        for (item, quantity) in unpaid_wares(pack, &for_sale, true) {
+                            let weight = properties.get(item).map_or(0.0, |props| props.weight);
+                            if let Some(slot) = pack.find_item(item) {
+                                pack.remove_item(slot, quantity, weight);
+                            }
+                            entities.delete(item).expect("Unable to delete confiscated goods");
+                        }
+                    }
+                    let release = TownLayout::ravenhollow().building(BuildingKind::Guardhouse).map(|house| house.doorstep());
+                    if let (Some((x, y)), Some(pos)) = (release, positions.get_mut(player)) {
+                        pos.x = x;
+                        pos.y = y;
+                    }
+                    if let Some(viewshed) = viewsheds.get_mut(player) {
+                        viewshed.dirty = true;
+                    }
+                    game_state.turn_count += sentence;
+                    watch.settle();
+                    gamelog.add_message(Message::new("log.sentence_served")
+                        .arg("hours", (sentence / 60).max(1))
+                        .arg("gold", board));
+                },
+                TownDeal::Deposit(amount) => {
+                    let amount = inventories.get(player).map_or(0, |pack| amount.min(pack.gold));
+                    if let Some(pack) = inventories.get_mut(player) {
+                        pack.gold -= amount;
+                        bank.balance += amount;
+                        gamelog.add_message(Message::new("log.deposited").arg("gold", amount).arg("balance", bank.balance));
+                    }
+                },
+                TownDeal::Withdraw(amount) => {
+                    let amount = amount.min(bank.balance);
+                    if let Some(pack) = inventories.get_mut(player) {
+                        pack.gold += amount;
+                        bank.balance -= amount;
+                        gamelog.add_message(Message::new("log.withdrew").arg("gold", amount).arg("balance", bank.balance));
+                    }
+                },
+                TownDeal::BuyProperty(property) => {
+                    if bank.buy(property) {
+                        gamelog.add_message(Message::new("log.property_bought")
+                            .arg("property", property.name())
+                            .arg("gold", property.cost()));
+                    } else if !bank.owns(property) {
+                        gamelog.add_message(Message::new("log.property_too_dear")
+                            .arg("property", property.name())
+                            .arg("gold", property.cost()));
+                    }
+                },
+                TownDeal::Donate(gold) => {
+                    let god = match pieties.get(player) {
+                        Some(piety) => piety.deity,
+                        None => continue,
+                    };
+                    match inventories.get_mut(player) {
+                        Some(pack) if pack.gold >= gold => pack.gold -= gold,
+                        _ => {
+                            gamelog.add_message(Message::new("log.not_enough_gold").arg("gold", gold));
+                            continue;
+                        },
+                    }
+                    deeds.publish(player, Deed::Donated { gold });
+                    gamelog.add_message(Message::new("log.donated").arg("gold", gold).arg("god", god.name()));
+                },
+                TownDeal::Gamble(bet) => {
+                    let pack = match inventories.get_mut(player) {
+                        Some(pack) if pack.gold >= bet => pack,
+                        _ => {
+                            gamelog.add_message(Message::new("log.not_enough_gold").arg("gold", bet));
+                            continue;
+                        },
+                    };
+                    // Ties go to the house
+                    let (thrown, house) = (rng.roll_dice(2, 6), rng.roll_dice(2, 6));
+                    let message = if thrown > house {
+                        pack.gold += bet;
+                        "log.dice_won"
+                    } else {
+                        pack.gold -= bet;
+                        "log.dice_lost"
+                    };
+                    gamelog.add_message(Message::new(message).arg("you", thrown).arg("house", house).arg("gold", bet));
+                },
+                TownDeal::LearnAbility(ability) => {
+                    let level = experience.get(player).map_or(1, |exp| exp.level);
+                    let teachable = match (classes.get(player), abilities.get(player)) {
+                        (Some(class), Some(known)) => known.cross_class_learnable(class.class_type, level).contains(&ability),
+                        _ => false,
+                    };
+                    if !teachable {
+                        continue;
+                    }
+                    let cost = Training::Ability(ability).cost(level, reputation.standing(Faction::Guild));
+                    match inventories.get_mut(player) {
+                        Some(pack) if pack.gold >= cost => pack.gold -= cost,
+                        _ => {
+                            gamelog.add_message(Message::new("log.not_enough_gold").arg("gold", cost));
+                            continue;
+                        },
+                    }
+                    if let Some(known) = abilities.get_mut(player) {
+                        known.add_ability(ability);
+                    }
+                    gamelog.add_message(Message::new("log.ability_taught").arg("ability", ability.name()).arg("gold", cost));
+                },
+                TownDeal::Attack => {
+                    if let Some(person) = townsfolk.get_mut(request.townsperson) {
+                        person.wronged = true;
+                    }
+                    watch.report(Crime::Assault);
+                    wants_attack.insert(player, WantsToAttack { target: request.townsperson })
+                        .expect("Unable to insert attack intent");
+                },
+            }
+        }
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        let turns = match self.last_turn {
+            Some(last) if game_state.turn_count > last => (game_state.turn_count - last) as i32,
+            _ => 0,
+        };
+        self.last_turn = Some(game_state.turn_count);
+        if !map.location.is_town() {
+            watch.cool_off();
+            return;
+        }
+
+        // Once told what they owe, the character has as long as the town's opinion of them buys
+        if watch.warned {
+            watch.alarm += turns;
+        }
+        if watch.is_wanted() && !watch.hostile && watch.alarm >= standing.watch_patience() {
+            watch.hostile = true;
+            gamelog.add_message(Message::new("log.watch_draws_steel"));
+        }
+        let hostile = watch.guards_hostile(standing);
+
+        let player = (&entities, &positions, &players).join()
+            .next()
+            .map(|(entity, pos, _)| (entity, (pos.x, pos.y)));
+        let people: Vec<(Entity, TownRole, (i32, i32), (i32, i32))> = (&entities, &townsfolk, &positions, &combat_stats).join()
+            .filter(|(_, _, _, stats)| stats.hp > 0)
+            .map(|(entity, person, pos, _)| (entity, person.role, person.post, (pos.x, pos.y)))
+            .collect();
+
+        let mut confronting = None;
+        for (person, role, post, here) in people {
+            let goal = match role {
+                TownRole::Guard => {
+                    let quarry = player.filter(|&(_, there)| {
+                        hostile || (watch.is_wanted() && chebyshev(here, there) <= GUARD_SIGHT && map.has_line_of_sight(here, there))
+                    });
+                    match quarry {
+                        Some((target, there)) if chebyshev(here, there) <= 1 => {
+                            if hostile {
+                                wants_attack.insert(person, WantsToAttack { target })
+                                    .expect("Unable to insert attack intent");
+                            } else if !watch.warned && confronting.is_none() {
+                                confronting = Some(person);
+                            }
+                            None
+                        },
+                        Some((_, there)) => Some(there),
+                        None => Some(post),
+                    }
+                },
+                TownRole::Shopkeeper | TownRole::Banker | TownRole::Priest | TownRole::Gambler | TownRole::Trainer | TownRole::Blacksmith => Some(post),
+                TownRole::Villager => {
+                    if rng.range(1, 100) <= VILLAGER_WANDER_CHANCE {
+                        let step = (here.0 + rng.range(-1, 1), here.1 + rng.range(-1, 1));
+                        if step != here && chebyshev(step, post) <= VILLAGER_ROAM && !map.is_blocked(step.0, step.1) {
+                            wants_move.insert(person, WantsToMove { destination: step })
+                                .expect("Unable to insert move intent");
+                        }
+                    }
+                    None
+                },
+            };
+            if let Some(goal) = goal.filter(|&goal| goal != here) {
+                step_toward(&map, &mut wants_move, &mut wants_door, person, here, goal);
+            }
+        }
+
+        if let Some(guard) = confronting {
+            watch.warned = true;
+            watch.confronting = Some(guard);
+            gamelog.add_message(Message::new("log.guard_confronts")
+                .arg("gold", watch.fine)
+                .arg("turns", standing.watch_patience().saturating_sub(watch.alarm).max(0)));
+        }
+    }
+}
+
+/// Lets a day's interest build up on the bank account, or a day's fees eat into it, as each day
+/// turns over, at the rate the character's game mode and property set
+pub struct BankSystem {
+    pub last_day: Option<u64>,
+}
+
+impl BankSystem {
+    pub fn new() -> Self {
+        BankSystem { last_day: None }
+    }
+}
+
+impl<'a> System<'a> for BankSystem {
+    type SystemData = (
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, GameSettings>,
+        Read<'a, GameClock>,
+        Write<'a, Bank>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (players, settings, clock, mut bank, mut gamelog) = data;
+
+        let today = clock.day();
+        let days = match self.last_day {
+            Some(last) if today > last => today - last,
+            _ => 0,
+        };
+        self.last_day = Some(today);
+        if days == 0 || bank.balance <= 0 {
+            return;
+        }
+        let mode = (&players, &settings).join()
+            .next()
+            .map_or(GameMode::Normal, |(_, settings)| settings.game_mode.clone());
+        let change = bank.accrue(bank.daily_rate(&mode), days);
+        if change > 0 {
+            gamelog.add_message(Message::new("log.bank_interest").arg("gold", change).arg("balance", bank.balance));
+        } else if change < 0 {
+            gamelog.add_message(Message::new("log.bank_fees").arg("gold", -change).arg("balance", bank.balance));
+        }
+    }
+}
+
+/// Notices crimes in Ravenhollow as they happen, and has the watch weigh every one reported:
+/// each adds to the fine, costs standing with the town and wears the watch's patience thin.
+/// Walking out of the general store with wares unpaid for is theft, and hurting or killing
+/// anyone in town is assault or murder, whoever struck the blow for the character
+pub struct CrimeSystem {}
+
+impl<'a> System<'a> for CrimeSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Townsperson>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, LastAttacker>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, ItemProperties>,
+        ReadStorage<'a, AdvancedInventory>,
+        WriteStorage<'a, ForSale>,
+        WriteStorage<'a, CombatReward>,
+        ReadExpect<'a, Map>,
+        Write<'a, TownWatch>,
+        Write<'a, Reputation>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut townsfolk,
+            combat_stats,
+            last_attackers,
+            companions,
+            players,
+            positions,
+            names,
+            properties,
+            inventories,
+            mut for_sale,
+            mut combat_rewards,
+            map,
+            mut watch,
+            mut reputation,
+            mut gamelog,
+        ) = data;
+
+        if map.location.is_town() {
+            let victims: Vec<(Entity, bool)> = (&entities, &townsfolk, &combat_stats, &last_attackers).join()
+                .filter(|(_, _, _, attacker)| players.contains(kill_credit(attacker.entity, &companions)))
+                .filter(|(entity, person, stats, _)| if stats.hp <= 0 { !combat_rewards.contains(*entity) } else { !person.wronged })
+                .map(|(entity, _, stats, _)| (entity, stats.hp <= 0))
+                .collect();
+            for (victim, slain) in victims {
+                if let Some(person) = townsfolk.get_mut(victim) {
+                    person.wronged = true;
+                }
+                if !slain {
+                    watch.report(Crime::Assault);
+                    continue;
+                }
+                watch.report(Crime::Murder);
+                let reward = CombatReward {
+                    source_entity: victim,
+                    source_name: names.get(victim).map_or(String::new(), |name| name.name.clone()),
+                    experience_gained: 0,
+                    loot_generated: false,
+                    special_drops: false,
+                };
+                combat_rewards.insert(victim, reward).expect("Failed to insert combat reward");
+            }
+
+            let shop = TownLayout::ravenhollow().building(BuildingKind::Shop).map(|shop| shop.room);
+            for (_, pos, pack) in (&players, &positions, &inventories).join() {
+                if shop.map_or(true, |room| room.contains(pos.x, pos.y)) {
+                    continue;
+                }
+                let taken = unpaid_wares(pack, &for_sale, false);
+                if taken.is_empty() {
+                    continue;
+                }
+                let value = taken.iter()
+                    .filter_map(|(item, quantity)| properties.get(*item).map(|props| props.value * quantity))
+                    .sum();
+                for (item, _) in taken {
+                    if let Some(ware) = for_sale.get_mut(item) {
+                        ware.stolen = true;
+                    }
+                }
+                watch.report(Crime::Theft { value });
+            }
+        }
+
+        // What the watch asks is set by how the town saw the character when they did it
+        let standing = reputation.standing(Faction::Townsfolk);
+        for crime in watch.take() {
+            let fine = (crime.base_fine() as f32 * standing.price_multiplier()).ceil() as i32;
+            watch.fine += fine;
+            watch.alarm += crime.alarm();
+            reputation.record(crime.conduct());
+            gamelog.add_message(Message::new(crime.message()).arg("gold", fine));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::items::{ItemType, ConsumableType};
+
+    fn world() -> World {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<ItemProperties>();
+        world.insert(TownLayout::ravenhollow().generate(80, 50, 3));
+        world.insert(GameStateResource::default());
+        world.insert(GameLog::new(10));
+        world.insert(Reputation::default());
+        world.insert(TownWatch::default());
+        world.insert(Bank::default());
+        world.insert(WorldEvents::default());
+        world.insert(DivineDeeds::default());
+        world.insert(RandomNumberGenerator::new(3));
+        world
+    }
+
+    fn ware(world: &mut World, value: i32) -> Entity {
+        let mut potion = ItemProperties::new("Healing Potion".to_string(), ItemType::Consumable(ConsumableType::Potion));
+        potion.value = value;
+        world.create_entity().with(potion).with(ForSale::default()).build()
+    }
+
+    #[test]
+    fn test_walking_out_unpaid_is_theft_and_paying_the_fine_settles_it() {
+        let mut world = world();
+        let item = ware(&mut world, 30);
+        let mut pack = AdvancedInventory::new(26, 150.0);
+        pack.gold = 100;
+        pack.add_item(item, 1, 0.5);
+        let shop = TownLayout::ravenhollow().building(BuildingKind::Shop).unwrap().room.center();
+        let player = world.create_entity().with(Player).with(Position { x: shop.0, y: shop.1 }).with(pack).build();
+
+        // Carrying it about the shop is no crime
+        CrimeSystem {}.run_now(&world);
+        assert!(!world.read_resource::<TownWatch>().is_wanted());
+
+        world.write_storage::<Position>().get_mut(player).unwrap().y = 30;
+        CrimeSystem {}.run_now(&world);
+        assert_eq!(world.read_resource::<TownWatch>().fine, 60);
+        assert!(world.read_storage::<ForSale>().get(item).unwrap().stolen);
+        assert_eq!(world.read_resource::<Reputation>().conduct, vec![Conduct::Theft]);
+
+        world.write_storage::<WantsToDeal>().insert(player, WantsToDeal { townsperson: player, deal: TownDeal::PayFine }).unwrap();
+        TownsfolkSystem::new().run_now(&world);
+        assert!(!world.read_resource::<TownWatch>().is_wanted());
+        assert_eq!(world.read_storage::<AdvancedInventory>().get(player).unwrap().gold, 40);
+        assert!(!world.read_storage::<ForSale>().contains(item));
+    }
+
+    #[test]
+    fn test_the_watch_waits_longer_on_those_the_town_thinks_well_of() {
+        let mut world = world();
+        let post = (45, 25);
+        let guard = world.create_entity()
+            .with(Position { x: post.0, y: post.1 })
+            .with(CombatStats { max_hp: 30, hp: 30, defense: 3, power: 6 })
+            .with(Townsperson { role: TownRole::Guard, post, wronged: false })
+            .build();
+        let player = world.create_entity().with(Player).with(Position { x: 46, y: 25 }).with(AdvancedInventory::new(26, 150.0)).build();
+        world.write_resource::<TownWatch>().fine = 50;
+
+        let mut townsfolk = TownsfolkSystem::new();
+        townsfolk.run_now(&world);
+        assert_eq!(world.write_resource::<TownWatch>().confronting.take(), Some(guard));
+
+        let patience = Standing::Neutral.watch_patience();
+        world.write_resource::<GameStateResource>().turn_count += patience as u32 - 1;
+        townsfolk.run_now(&world);
+        assert!(!world.read_resource::<TownWatch>().hostile);
+        assert!(!world.read_storage::<WantsToAttack>().contains(guard));
+
+        world.write_resource::<GameStateResource>().turn_count += 1;
+        townsfolk.run_now(&world);
+        assert!(world.read_resource::<TownWatch>().hostile);
+        assert_eq!(world.read_storage::<WantsToAttack>().get(guard).map(|attack| attack.target), Some(player));
+    }
+
+    #[test]
+    fn test_gold_goes_into_the_bank_and_to_the_temple_in_the_gods_name() {
+        let mut world = world();
+        let mut pack = AdvancedInventory::new(26, 150.0);
+        pack.gold = 120;
+        let player = world.create_entity()
+            .with(Player)
+            .with(Position { x: 40, y: 26 })
+            .with(pack)
+            .with(Piety::new(crate::components::Deity::Aurelion))
+            .build();
+        let deal = |world: &World, deal: TownDeal| {
+            world.write_storage::<WantsToDeal>().insert(player, WantsToDeal { townsperson: player, deal }).unwrap();
+            TownsfolkSystem::new().run_now(world);
+        };
+
+        deal(&world, TownDeal::Deposit(500));
+        assert_eq!(world.read_resource::<Bank>().balance, 120);
+        deal(&world, TownDeal::Withdraw(60));
+        assert_eq!(world.read_resource::<Bank>().balance, 60);
+        deal(&world, TownDeal::Donate(50));
+        assert_eq!(world.read_storage::<AdvancedInventory>().get(player).unwrap().gold, 10);
+        assert_eq!(world.write_resource::<DivineDeeds>().take(), vec![(player, Deed::Donated { gold: 50 })]);
+
+        // Nothing is given that isn't there to give
+        deal(&world, TownDeal::Donate(50));
+        assert!(world.read_resource::<DivineDeeds>().deeds.is_empty());
+    }
+    #[test]
+    fn test_trainers_teach_other_classes_abilities_as_far_as_the_level_allows() {
+        let mut world = world();
+        let mut pack = AdvancedInventory::new(26, 150.0);
+        pack.gold = 1000;
+        let mut exp = Experience::new();
+        exp.level = 3;
+        let player = world.create_entity()
+            .with(Player)
+            .with(pack)
+            .with(exp)
+            .with(CharacterClass { class_type: crate::components::ClassType::Fighter })
+            .with(Abilities::starting(crate::components::ClassType::Fighter))
+            .build();
+        let learn = |world: &World, ability: AbilityType| {
+            world.write_storage::<WantsToDeal>().insert(player, WantsToDeal { townsperson: player, deal: TownDeal::LearnAbility(ability) }).unwrap();
+            TownsfolkSystem::new().run_now(world);
+        };
+
+        // Beyond the character's level, then their own class's, then one too many
+        learn(&world, AbilityType::Teleport);
+        learn(&world, AbilityType::Cleave);
+        learn(&world, AbilityType::Fireball);
+        learn(&world, AbilityType::Heal);
+        let abilities = world.read_storage::<Abilities>();
+        let known = abilities.get(player).unwrap();
+        assert!(known.has_ability(AbilityType::Fireball));
+        assert!(!known.has_ability(AbilityType::Teleport) && !known.has_ability(AbilityType::Cleave) && !known.has_ability(AbilityType::Heal));
+        let cost = Training::Ability(AbilityType::Fireball).cost(3, Standing::Neutral);
+        assert_eq!(cost, 180);
+        assert_eq!(world.read_storage::<AdvancedInventory>().get(player).unwrap().gold, 1000 - cost);
+        assert!(Training::Attributes.cost(6, Standing::Neutral) > Training::Attributes.cost(2, Standing::Neutral));
+    }
+}
@@ -0,0 +1,150 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
+use crate::components::{CombatStats, Monster, Name, Player, Position, Viewshed, WantsToTravel, WantsToMove};
+use crate::map::Map;
+use crate::resources::{GameLog, GameStateResource};
+use crate::localization::{self, Message};
+use super::auto_explore_system::{walk_interruption, WalkInterruption};
+
+/// Walks the travelling player one step per update along the known map toward the tile they
+/// picked, moving the turn on, until they arrive or are interrupted as exploring would be
+pub struct TravelSystem;
+
+impl<'a> System<'a> for TravelSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToTravel>,
+        WriteStorage<'a, WantsToMove>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, CombatStats>,
+        ReadExpect<'a, Map>,
+        Write<'a, GameStateResource>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_travel,
+            mut wants_move,
+            mut viewsheds,
+            players,
+            positions,
+            monsters,
+            names,
+            combat_stats,
+            map,
+            mut game_state,
+            mut gamelog,
+        ) = data;
+
+        let mut finished: Vec<Entity> = Vec::new();
+
+        for (entity, travel, pos, viewshed, _) in (&entities, &mut wants_travel, &positions, &mut viewsheds, &players).join() {
+            let here = (pos.x, pos.y);
+            let hp = combat_stats.get(entity).map(|stats| stats.hp);
+
+            if here == travel.destination {
+                if travel.steps > 0 {
+                    gamelog.add_message(Message::new("log.travel_arrived"));
+                }
+                finished.push(entity);
+                continue;
+            }
+            match walk_interruption(viewshed, &monsters, &positions, &names, travel.last_hp, hp) {
+                Some(WalkInterruption::Spotted(name)) => {
+                    gamelog.add_message(Message::new("log.travel_spotted").arg("name", localization::name(&name)));
+                    finished.push(entity);
+                    continue;
+                },
+                Some(WalkInterruption::Hurt) => {
+                    gamelog.add_message(Message::new("log.travel_hurt"));
+                    finished.push(entity);
+                    continue;
+                },
+                None => {},
+            }
+
+            // The way is worked out afresh each step, in case a door shut or something moved in
+            match map.find_known_path(here, travel.destination).and_then(|path| path.first().copied()) {
+                Some(step) if !map.is_blocked(step.0, step.1) => {
+                    wants_move.insert(entity, WantsToMove { destination: step })
+                        .expect("Unable to insert move intent");
+                    viewshed.dirty = true;
+                    game_state.turn_count += 1;
+                    travel.steps += 1;
+                    travel.last_hp = hp;
+                },
+                Some(_) => {
+                    gamelog.add_message(Message::new("log.travel_blocked"));
+                    finished.push(entity);
+                },
+                None => {
+                    gamelog.add_message(Message::new("log.travel_no_way"));
+                    finished.push(entity);
+                },
+            }
+        }
+
+        for entity in finished {
+            wants_travel.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::map::TileType;
+
+    fn corridor() -> (World, Entity) {
+        let mut world = World::new();
+        crate::components::register_components(&mut world);
+        world.insert(GameLog::new(10));
+        world.insert(GameStateResource::default());
+        let mut map = Map::new(12, 5, 1);
+        for x in 1..11 {
+            map.set_tile(x, 2, TileType::Floor);
+            let idx = map.xy_idx(x, 2);
+            map.revealed_tiles[idx] = true;
+        }
+        world.insert(map);
+        let player = world.create_entity()
+            .with(Player {})
+            .with(Position { x: 1, y: 2 })
+            .with(Viewshed { visible_tiles: Vec::new(), range: 8, dirty: false })
+            .with(CombatStats { max_hp: 20, hp: 20, defense: 1, power: 4 })
+            .with(WantsToTravel::to((4, 2)))
+            .build();
+        (world, player)
+    }
+
+    #[test]
+    fn test_travel_steps_toward_the_destination_and_stops_there() {
+        let (mut world, player) = corridor();
+
+        TravelSystem.run_now(&world);
+        assert_eq!(world.read_storage::<WantsToMove>().get(player).map(|intent| intent.destination), Some((2, 2)));
+        assert_eq!(world.read_resource::<GameStateResource>().turn_count, 1);
+
+        world.write_storage::<WantsToMove>().clear();
+        world.write_storage::<Position>().insert(player, Position { x: 4, y: 2 }).unwrap();
+        TravelSystem.run_now(&world);
+        world.maintain();
+        assert!(!world.read_storage::<WantsToTravel>().contains(player));
+    }
+
+    #[test]
+    fn test_travel_stops_on_getting_hurt() {
+        let (world, player) = corridor();
+
+        TravelSystem.run_now(&world);
+        world.write_storage::<CombatStats>().get_mut(player).unwrap().hp = 15;
+        TravelSystem.run_now(&world);
+        assert!(!world.read_storage::<WantsToTravel>().contains(player));
+    }
+}
@@ -1,9 +1,10 @@
 use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write, Builder};
 use crate::components::{
-    Treasure, Position, Name, Player, WantsToInteract, Item, Renderable,
+    Treasure, TreasureType, Corpse, Position, Name, Player, WantsToInteract, Item, Renderable,
     ProvidesHealing, MeleePowerBonus, DefenseBonus, Equippable, LootDrop
 };
-use crate::resources::{GameLog, RandomNumberGenerator};
+use crate::resources::{GameLog, GameEvents, RandomNumberGenerator, LOOT_STREAM};
+use crate::achievements::GameEvent;
 use crossterm::style::Color;
 
 pub struct TreasureSystem {}
@@ -13,15 +14,17 @@ impl<'a> System<'a> for TreasureSystem {
         Entities<'a>,
         WriteStorage<'a, WantsToInteract>,
         WriteStorage<'a, Treasure>,
+        WriteStorage<'a, Corpse>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, Player>,
         Write<'a, GameLog>,
         Write<'a, RandomNumberGenerator>,
+        Write<'a, GameEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_interact, mut treasures, positions, names, players, mut gamelog, mut rng) = data;
+        let (entities, mut wants_interact, mut treasures, mut corpses, positions, names, players, mut gamelog, mut rng, mut events) = data;
 
         // Process treasure interaction requests
         let mut interactions = Vec::new();
@@ -45,8 +48,12 @@ impl<'a> System<'a> for TreasureSystem {
                         &players,
                         &entities,
                         &mut gamelog,
-                        &mut rng
+                        &mut events,
+                        rng.stream(LOOT_STREAM)
                     );
+                    if let Some(corpse) = corpses.get_mut(target) {
+                        corpse.loot_generated = true;
+                    }
                 } else {
                     let interactor_name = names.get(interactor).map_or("Someone", |n| &n.name);
                     let treasure_name = names.get(target).map_or("treasure", |n| &n.name);
@@ -68,6 +75,7 @@ impl TreasureSystem {
         players: &ReadStorage<Player>,
         entities: &Entities,
         gamelog: &mut GameLog,
+        events: &mut GameEvents,
         rng: &mut RandomNumberGenerator,
     ) {
         let interactor_name = names.get(interactor).map_or("Someone", |n| &n.name);
@@ -86,21 +94,30 @@ impl TreasureSystem {
         
         // Open the treasure
         treasure.is_opened = true;
-        gamelog.add_entry(format!("{} opens the {}!", interactor_name, treasure_name));
+        if matches!(treasure.treasure_type, TreasureType::Corpse) {
+            gamelog.add_entry(format!("{} searches the {}.", interactor_name, treasure_name));
+        } else {
+            gamelog.add_entry(format!("{} opens the {}!", interactor_name, treasure_name));
+        }
         
         // Generate loot from the treasure's loot table
         let treasure_pos = positions.get(treasure_entity).cloned();
         if let Some(pos) = treasure_pos {
             let mut items_generated = 0;
+            let mut gold_found = 0;
             
             for entry in &treasure.loot_table.entries {
                 let roll = rng.roll_dice(1, 100);
                 if roll <= entry.chance {
-                    self.create_treasure_loot(&entry.loot_drop, pos, entities, gamelog);
+                    gold_found += self.create_treasure_loot(&entry.loot_drop, pos, entities, gamelog);
                     items_generated += 1;
                 }
             }
             
+            if gold_found > 0 && players.contains(interactor) {
+                events.publish(GameEvent::GoldCollected(gold_found));
+            }
+            
             if items_generated == 0 {
                 gamelog.add_entry(format!("The {} is empty.", treasure_name));
             } else {
@@ -109,13 +126,14 @@ impl TreasureSystem {
         }
     }
     
+    /// Spawns one loot drop and returns the gold it was worth, if any
     fn create_treasure_loot(
         &self,
         loot_drop: &LootDrop,
         position: Position,
         entities: &Entities,
         gamelog: &mut GameLog,
-    ) {
+    ) -> u32 {
         match loot_drop {
             LootDrop::Equipment { name, slot, power_bonus, defense_bonus } => {
                 let mut item_builder = entities.create()
@@ -140,6 +158,7 @@ impl TreasureSystem {
                 
                 item_builder.build();
                 gamelog.add_entry(format!("Found: {}!", name));
+                0
             },
             
             LootDrop::Consumable { name, healing } => {
@@ -157,10 +176,12 @@ impl TreasureSystem {
                     .build();
                 
                 gamelog.add_entry(format!("Found: {}!", name));
+                0
             },
             
             LootDrop::Currency { amount } => {
                 gamelog.add_entry(format!("Found: {} gold coins!", amount));
+                (*amount).max(0) as u32
             },
         }
     }
@@ -206,6 +227,7 @@ impl TreasureGenerationSystem {
                     crate::components::TreasureType::Urn => "Ancient Urn".to_string(),
                     crate::components::TreasureType::Corpse => "Corpse".to_string(),
                     crate::components::TreasureType::SecretCache => "Secret Cache".to_string(),
+                    crate::components::TreasureType::Pack => "Abandoned Pack".to_string(),
                 }
             })
             .with(position)
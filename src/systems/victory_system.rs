@@ -0,0 +1,169 @@
+use specs::{System, ReadStorage, Entities, Entity, Join, Write, ReadExpect};
+use std::collections::{HashMap, HashSet};
+use crate::components::{VictoryArtifact, BossEnemy, BossType, CombatStats, Monster, Position, Player, Name, Experience};
+use crate::map::{Map, Location};
+use crate::progression::{PlayerHistorySystem, HistoryEvent, HistoryEventType, EventImportance};
+use crate::resources::{GameLog, GameEvents, GameStateResource, RunPhase, RunStatistics};
+use crate::achievements::GameEvent;
+use super::reputation_system::{Reputation, Conduct};
+
+/// Tracks run statistics and the player's history, and moves the run through descent, ascent and victory
+pub struct VictorySystem {
+    counted: HashSet<Entity>,
+    /// The player's level when last seen, to notice level-ups however the experience came
+    player_level: Option<i32>,
+    /// Rooms the player has stood in, by level and room index
+    visited_rooms: HashSet<(Location, usize)>,
+}
+
+impl VictorySystem {
+    pub fn new() -> Self {
+        VictorySystem { counted: HashSet::new(), player_level: None, visited_rooms: HashSet::new() }
+    }
+}
+
+/// Where on the map an event happened, as the history records it
+fn place(map: &Map) -> String {
+    format!("{} {}", map.location.branch.name(), map.location.depth)
+}
+
+/// A run-defining moment for the history, such as claiming the artifact
+fn special_event(id: &str, title: &str, description: &str, location: String) -> HistoryEvent {
+    HistoryEvent::new(id.to_string(), HistoryEventType::Special, EventImportance::Legendary, title.to_string(), description.to_string())
+        .with_location(location)
+}
+
+/// Whether a player standing at `pos` on `map` has carried the artifact out of the dungeon
+pub fn has_escaped(map: &Map, pos: &Position) -> bool {
+    map.location == Location::main(1) && (pos.x, pos.y) == map.entrance
+}
+
+impl<'a> System<'a> for VictorySystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, VictoryArtifact>,
+        ReadStorage<'a, BossEnemy>,
+        ReadStorage<'a, CombatStats>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Experience>,
+        ReadExpect<'a, Map>,
+        Write<'a, GameStateResource>,
+        Write<'a, RunStatistics>,
+        Write<'a, GameLog>,
+        Write<'a, PlayerHistorySystem>,
+        Write<'a, GameEvents>,
+        Write<'a, Reputation>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            artifacts,
+            bosses,
+            combat_stats,
+            monsters,
+            positions,
+            players,
+            names,
+            experience,
+            map,
+            mut game_state,
+            mut stats,
+            mut gamelog,
+            mut history,
+            mut events,
+            mut reputation,
+        ) = data;
+
+        let deepest = stats.deepest_depth;
+        // Fresh statistics mean a fresh run, whose levels are all new ground
+        if deepest == 0 {
+            self.visited_rooms.clear();
+        }
+        stats.record_depth(game_state.depth);
+        if stats.deepest_depth > deepest {
+            events.publish(GameEvent::DepthReached(stats.deepest_depth));
+        }
+        if deepest > 0 && stats.deepest_depth > deepest {
+            reputation.record(Conduct::Delved);
+            history.add_event(HistoryEvent::new(
+                format!("depth_{}", stats.deepest_depth),
+                HistoryEventType::Exploration,
+                EventImportance::Normal,
+                format!("Reached Depth {}", stats.deepest_depth),
+                format!("Went deeper than ever before, to depth {}", stats.deepest_depth),
+            ).with_location(place(&map)));
+        }
+
+        // A level lower than last seen is a new character, not a level lost
+        let level = (&players, &experience).join().map(|(_, exp)| exp.level).next();
+        if let (Some(level), Some(previous)) = (level, self.player_level) {
+            for new_level in (previous + 1)..=level {
+                history.log_level_up(new_level as u32, &place(&map), HashMap::new(), HashMap::new());
+            }
+        }
+        self.player_level = level;
+
+        for (_, pos) in (&players, &positions).join() {
+            let room = map.rooms.iter().position(|room| {
+                pos.x >= room.x1 && pos.x <= room.x2 && pos.y >= room.y1 && pos.y <= room.y2
+            });
+            if let Some(room) = room {
+                if self.visited_rooms.insert((map.location, room)) {
+                    events.publish(GameEvent::RoomVisited);
+                }
+            }
+        }
+
+        // Count each kill once, however many frames the corpse lingers
+        self.counted.retain(|entity| entities.is_alive(*entity));
+        for (entity, monster_stats, _monster) in (&entities, &combat_stats, &monsters).join() {
+            if monster_stats.hp > 0 || !self.counted.insert(entity) {
+                continue;
+            }
+            stats.kills += 1;
+            if let Some(name) = names.get(entity) {
+                history.log_combat_victory(&name.name, &place(&map), HashMap::new(), HashMap::new());
+            }
+
+            if let Some(boss) = bosses.get(entity) {
+                stats.bosses_defeated += 1;
+                if boss.boss_type == BossType::FinalBoss {
+                    let name = names.get(entity).map_or("The guardian", |n| n.name.as_str());
+                    gamelog.add_entry(format!("{} is slain! The way to the artifact lies open.", name));
+                }
+            }
+        }
+
+        match game_state.phase {
+            RunPhase::Descent => {
+                // An artifact without a position has been picked up
+                let taken = (&entities, &artifacts).join().any(|(entity, _)| !positions.contains(entity));
+                if taken {
+                    game_state.phase = RunPhase::Ascent;
+                    stats.artifact_taken_turn = Some(game_state.turn_count);
+                    gamelog.add_entry("You claim the Amulet of the Depths!".to_string());
+                    gamelog.add_entry("The dungeon shudders. Something stirs on every level above.".to_string());
+                    history.add_event(special_event("artifact_claimed", "Claimed the Amulet",
+                        "Took the Amulet of the Depths from its guardian's lair", place(&map)));
+                    events.publish(GameEvent::ArtifactClaimed);
+                }
+            }
+            RunPhase::Ascent => {
+                let escaped = (&players, &positions).join().any(|(_, pos)| has_escaped(&map, pos));
+                if escaped {
+                    game_state.phase = RunPhase::Victory;
+                    stats.victory_turn = Some(game_state.turn_count);
+                    gamelog.add_entry("You emerge into daylight with the amulet. Victory!".to_string());
+                    history.add_event(special_event("victory", "Victory",
+                        "Carried the Amulet of the Depths back into daylight", place(&map)));
+                    events.publish(GameEvent::Victory { turns: game_state.turn_count });
+                }
+            }
+            RunPhase::Victory => {}
+        }
+    }
+}
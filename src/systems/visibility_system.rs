@@ -1,6 +1,8 @@
-use specs::{System, ReadStorage, WriteStorage, ReadExpect, WriteExpect, Join};
+use specs::{System, ReadStorage, WriteStorage, WriteExpect, Read, Write, Join};
 use crate::components::{Position, Viewshed, Player};
-use crate::map::Map;
+use crate::map::{Map, TileType, Weather};
+use crate::resources::GameClock;
+use crate::ui::{TutorialEvents, TutorialTrigger};
 
 pub struct VisibilitySystem {}
 
@@ -10,10 +12,13 @@ impl<'a> System<'a> for VisibilitySystem {
         ReadStorage<'a, Position>,
         ReadStorage<'a, Player>,
         WriteExpect<'a, Map>,
+        Read<'a, Weather>,
+        Read<'a, GameClock>,
+        Write<'a, TutorialEvents>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut viewshed, pos, player, mut map) = data;
+        let (mut viewshed, pos, player, mut map, weather, clock, mut tutorials) = data;
 
         // Reset all visible tiles
         for tile in map.visible_tiles.iter_mut() {
@@ -28,8 +33,11 @@ impl<'a> System<'a> for VisibilitySystem {
                 
                 // Simple field of view algorithm (will be replaced with a more sophisticated one)
                 // For now, just make a square around the player visible
-                for y in -viewshed.range..=viewshed.range {
-                    for x in -viewshed.range..=viewshed.range {
+                // Fog, falling weather and, under the open sky, the dark close in on what can be seen
+                let night_penalty = if map.theme.is_outdoors() { clock.time_of_day().sight_penalty() } else { 0 };
+                let range = (viewshed.range - weather.kind.sight_penalty() - night_penalty).max(2);
+                for y in -range..=range {
+                    for x in -range..=range {
                         let target_x = pos.x + x;
                         let target_y = pos.y + y;
                         
@@ -41,6 +49,13 @@ impl<'a> System<'a> for VisibilitySystem {
                         }
                     }
                 }
+
+                // The way down is the level's exit, or any stair tile on levels with more than one
+                let sees_stairs = viewshed.visible_tiles.iter()
+                    .any(|&(x, y)| (x, y) == map.exit || map.get_tile(x, y) == Some(TileType::DownStairs));
+                if sees_stairs {
+                    tutorials.publish(TutorialTrigger::FindStairs);
+                }
             }
         }
     }
@@ -1,8 +1,10 @@
-use specs::{System, WriteStorage, ReadStorage, Entities, Entity, Join, Write};
+use specs::{System, WriteStorage, ReadStorage, Entities, Join, Read, Write};
 use crate::components::{
-    CombatFeedback, CombatFeedbackType, Position, Renderable, AnimationType
+    CombatFeedback, CombatFeedbackType, Position, Renderable, AnimationType, DamageType,
+    ParticleEffect, ParticleVelocity, FloatingPosition
 };
 use crate::rendering::terminal::with_terminal;
+use crate::rendering::MotionSettings;
 use crossterm::style::Color;
 
 pub struct VisualEffectsSystem {}
@@ -14,10 +16,11 @@ impl<'a> System<'a> for VisualEffectsSystem {
         ReadStorage<'a, Position>,
         WriteStorage<'a, Renderable>,
         Write<'a, crate::systems::sound_effect_system::ScreenShakeState>,
+        Read<'a, MotionSettings>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, combat_feedback, positions, mut renderables, mut screen_shake) = data;
+        let (entities, combat_feedback, positions, mut renderables, mut screen_shake, motion) = data;
 
         // Render floating combat text and effects
         let _ = with_terminal(|terminal| {
@@ -67,7 +70,7 @@ impl<'a> System<'a> for VisualEffectsSystem {
         for (entity, feedback) in (&entities, &combat_feedback).join() {
             if matches!(feedback.animation_type, AnimationType::Flash) {
                 if let Some(renderable) = renderables.get_mut(entity) {
-                    self.apply_flash_effect(renderable, feedback);
+                    self.apply_flash_effect(renderable, feedback, &motion);
                 }
             }
         }
@@ -166,143 +169,171 @@ impl VisualEffectsSystem {
         Ok(())
     }
     
-    fn apply_flash_effect(&self, renderable: &mut Renderable, feedback: &CombatFeedback) {
+    fn apply_flash_effect(&self, renderable: &mut Renderable, feedback: &CombatFeedback, motion: &MotionSettings) {
         // Calculate flash intensity based on remaining duration
         let flash_intensity = feedback.duration / feedback.max_duration;
-        
-        if flash_intensity > 0.5 {
-            // Full flash effect
-            renderable.bg = feedback.color;
-        } else if flash_intensity > 0.0 {
-            // Fading flash effect
-            renderable.bg = Color::DarkGrey;
-        } else {
-            // Restore original background
-            renderable.bg = Color::Black;
+        renderable.bg = motion.hit_background(feedback.color, flash_intensity);
+    }
+}
+
+/// Seconds of particle motion simulated per frame; the game loop runs at about 30 frames a second
+pub const PARTICLE_STEP: f32 = 0.033;
+/// Particles drawn under creatures, such as blood hitting the floor
+pub const PARTICLE_DEPTH_GROUND: i32 = -1;
+/// Particles drawn over everything else on the map
+pub const PARTICLE_DEPTH_AIR: i32 = 1;
+
+/// Particles queued by other systems this frame, spawned by `ParticleEffectSystem`
+#[derive(Debug, Clone, Default)]
+pub struct ParticleEmitter {
+    pub particles: Vec<ParticleEffect>,
+}
+
+impl ParticleEmitter {
+    fn emit(&mut self, pos: (i32, i32), angle: f32, speed: f32, character: char, color: Color, lifetime: f32, gravity: f32, depth: i32) {
+        self.particles.push(ParticleEffect {
+            position: FloatingPosition { x: pos.0 as f32, y: pos.1 as f32, offset_x: 0.0, offset_y: 0.0 },
+            origin: pos,
+            velocity: ParticleVelocity { x: angle.cos() * speed, y: angle.sin() * speed },
+            color,
+            character,
+            lifetime,
+            max_lifetime: lifetime,
+            gravity,
+            depth,
+        });
+    }
+
+    /// A ring of sparks flying out from a blast
+    pub fn explosion(&mut self, pos: (i32, i32), color: Color) {
+        for i in 0..12 {
+            let angle = i as f32 * std::f32::consts::TAU / 12.0;
+            self.emit(pos, angle, 6.0, '*', color, 0.5, 0.0, PARTICLE_DEPTH_AIR);
         }
     }
+
+    /// Droplets thrown away from whoever struck the blow
+    pub fn blood_spray(&mut self, pos: (i32, i32), from: (i32, i32)) {
+        let (dx, dy) = (pos.0 - from.0, pos.1 - from.1);
+        let heading = if dx == 0 && dy == 0 { -std::f32::consts::FRAC_PI_2 } else { (dy as f32).atan2(dx as f32) };
+        for (i, character) in ['.', ',', '\'', '.', ','].iter().enumerate() {
+            let angle = heading + (i as f32 - 2.0) * 0.25;
+            self.emit(pos, angle, 4.0 + i as f32 * 0.5, *character, Color::DarkRed, 0.4, 8.0, PARTICLE_DEPTH_GROUND);
+        }
+    }
+
+    /// Glitter drifting upwards from a spell
+    pub fn sparkles(&mut self, pos: (i32, i32), color: Color) {
+        for (i, character) in ['*', '+', '.', '*', '+', '.'].iter().enumerate() {
+            let angle = -std::f32::consts::FRAC_PI_2 + (i as f32 - 2.5) * 0.3;
+            self.emit(pos, angle, 1.5, *character, color, 0.8, 0.0, PARTICLE_DEPTH_AIR);
+        }
+    }
+
+    /// A shimmer rising around someone arriving on, or leaving, a level
+    pub fn stair_transition(&mut self, pos: (i32, i32)) {
+        for i in 0..8 {
+            let angle = i as f32 * std::f32::consts::TAU / 8.0;
+            let start = ((pos.0 as f32 + angle.cos()).round() as i32, (pos.1 as f32 + angle.sin()).round() as i32);
+            self.emit(start, -std::f32::consts::FRAC_PI_2, 3.0, '\'', Color::Cyan, 0.6, 0.0, PARTICLE_DEPTH_AIR);
+        }
+    }
+}
+
+/// Move a particle one step; returns false once it has burnt out
+pub fn step_particle(particle: &mut ParticleEffect, dt: f32) -> bool {
+    particle.position.x += particle.velocity.x * dt;
+    particle.position.y += particle.velocity.y * dt;
+    particle.velocity.y += particle.gravity * dt;
+    particle.velocity.x *= 0.98; // Air resistance
+    particle.lifetime -= dt;
+    particle.lifetime > 0.0
 }
 
-// System for creating particle effects
+/// Spawns queued particles as entities, moves them and clears them away when they burn out
 pub struct ParticleEffectSystem {}
 
 impl<'a> System<'a> for ParticleEffectSystem {
     type SystemData = (
         Entities<'a>,
         WriteStorage<'a, ParticleEffect>,
+        Write<'a, ParticleEmitter>,
         ReadStorage<'a, CombatFeedback>,
         ReadStorage<'a, Position>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut particles, combat_feedback, positions) = data;
+        let (entities, mut particles, mut emitter, combat_feedback, positions) = data;
 
-        // Create particle effects based on combat feedback
-        for (entity, feedback) in (&entities, &combat_feedback).join() {
-            if let Some(pos) = positions.get(entity) {
-                match &feedback.feedback_type {
-                    CombatFeedbackType::DamageText { damage_type, is_critical, .. } => {
-                        if *is_critical {
-                            // Create particle burst for critical hits
-                            self.create_particle_burst(
-                                entity,
-                                pos,
-                                *damage_type,
-                                &mut particles
-                            );
-                        }
-                    },
-                    _ => {}
+        // Critical hits burst in the color of their damage, once when the feedback appears
+        for (feedback, pos) in (&combat_feedback, &positions).join() {
+            if let CombatFeedbackType::DamageText { damage_type, is_critical: true, .. } = &feedback.feedback_type {
+                if feedback.duration >= feedback.max_duration {
+                    emitter.explosion((pos.x, pos.y), damage_color(*damage_type));
                 }
             }
         }
-        
-        // Update existing particles
-        self.update_particles(&mut particles);
-    }
-}
 
-impl ParticleEffectSystem {
-    fn create_particle_burst(
-        &self,
-        entity: Entity,
-        position: &Position,
-        damage_type: crate::components::DamageType,
-        particles: &mut WriteStorage<ParticleEffect>,
-    ) {
-        let particle_color = match damage_type {
-            crate::components::DamageType::Fire => Color::Red,
-            crate::components::DamageType::Ice => Color::Cyan,
-            crate::components::DamageType::Lightning => Color::Yellow,
-            crate::components::DamageType::Poison => Color::Green,
-            crate::components::DamageType::Holy => Color::White,
-            crate::components::DamageType::Dark => Color::Magenta,
-            _ => Color::White,
-        };
-        
-        let particle = ParticleEffect {
-            position: crate::components::FloatingPosition {
-                x: position.x as f32,
-                y: position.y as f32,
-                offset_x: 0.0,
-                offset_y: 0.0,
-            },
-            velocity: ParticleVelocity {
-                x: 0.0,
-                y: -1.0, // Move upward
-            },
-            color: particle_color,
-            character: '*',
-            lifetime: 1.0,
-            max_lifetime: 1.0,
-        };
-        
-        particles.insert(entity, particle)
-            .expect("Failed to insert particle effect");
-    }
-    
-    fn update_particles(&self, particles: &mut WriteStorage<ParticleEffect>) {
-        let mut expired_particles = Vec::new();
-        
-        for (entity, mut particle) in (&entities, particles).join() {
-            // Update particle position
-            particle.position.offset_x += particle.velocity.x * 0.016;
-            particle.position.offset_y += particle.velocity.y * 0.016;
-            
-            // Update lifetime
-            particle.lifetime -= 0.016;
-            
-            // Apply gravity/physics
-            particle.velocity.y += 0.5; // Gravity
-            particle.velocity.x *= 0.98; // Air resistance
-            
-            // Mark expired particles
-            if particle.lifetime <= 0.0 {
-                expired_particles.push(entity);
+        for particle in emitter.particles.drain(..) {
+            let entity = entities.create();
+            particles.insert(entity, particle).expect("Failed to insert particle effect");
+        }
+
+        let mut expired = Vec::new();
+        for (entity, particle) in (&entities, &mut particles).join() {
+            if !step_particle(particle, PARTICLE_STEP) {
+                expired.push(entity);
             }
         }
-        
-        // Remove expired particles
-        for entity in expired_particles {
-            particles.remove(entity);
+        for entity in expired {
+            entities.delete(entity).expect("Unable to delete expired particle");
         }
     }
 }
 
-// Particle effect component
-#[derive(Component, Debug, Clone)]
-#[storage(VecStorage)]
-pub struct ParticleEffect {
-    pub position: crate::components::FloatingPosition,
-    pub velocity: ParticleVelocity,
-    pub color: Color,
-    pub character: char,
-    pub lifetime: f32,
-    pub max_lifetime: f32,
+/// The color sparks and bursts take for a kind of damage
+pub fn damage_color(damage_type: DamageType) -> Color {
+    match damage_type {
+        DamageType::Fire => Color::Red,
+        DamageType::Ice => Color::Cyan,
+        DamageType::Lightning => Color::Yellow,
+        DamageType::Poison => Color::Green,
+        DamageType::Holy => Color::White,
+        DamageType::Dark => Color::Magenta,
+        _ => Color::White,
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct ParticleVelocity {
-    pub x: f32,
-    pub y: f32,
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emitters_queue_particles_at_their_depth() {
+        let mut emitter = ParticleEmitter::default();
+        emitter.explosion((5, 5), Color::Red);
+        assert_eq!(emitter.particles.len(), 12);
+        assert!(emitter.particles.iter().all(|p| p.depth == PARTICLE_DEPTH_AIR));
+
+        emitter.particles.clear();
+        emitter.blood_spray((5, 5), (4, 5));
+        assert!(emitter.particles.iter().all(|p| p.depth == PARTICLE_DEPTH_GROUND));
+        // Sprayed away from the attacker
+        assert!(emitter.particles.iter().all(|p| p.velocity.x > 0.0));
+    }
+
+    #[test]
+    fn test_particles_move_fall_and_burn_out() {
+        let mut emitter = ParticleEmitter::default();
+        emitter.blood_spray((0, 0), (0, 1));
+        let mut particle = emitter.particles[2].clone();
+        assert!(particle.velocity.y < 0.0);
+
+        assert!(step_particle(&mut particle, 0.1));
+        assert!(particle.position.y < 0.0);
+        let rising = particle.velocity.y;
+        step_particle(&mut particle, 0.1);
+        assert!(particle.velocity.y > rising);
+        assert!(!step_particle(&mut particle, 0.3));
+    }
+}
@@ -0,0 +1,81 @@
+use specs::{System, ReadStorage, Join, Write, Read, ReadExpect};
+use crate::components::{Player, Position};
+use crate::map::{Map, EntitySpawn, LevelTension, SpawnDirector};
+use crate::resources::{GameClock, GameLog, GameStateResource, RandomNumberGenerator};
+
+/// Wandering monsters rolled this turn, waiting for the game state to build them
+#[derive(Debug, Clone, Default)]
+pub struct WanderingSpawns {
+    pub spawns: Vec<EntitySpawn>,
+}
+
+impl WanderingSpawns {
+    pub fn take(&mut self) -> Vec<EntitySpawn> {
+        std::mem::take(&mut self.spawns)
+    }
+}
+
+/// Counts the turns spent on each level and sends in wandering monsters as the player lingers
+pub struct WanderingMonsterSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl WanderingMonsterSystem {
+    pub fn new() -> Self {
+        WanderingMonsterSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for WanderingMonsterSystem {
+    type SystemData = (
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        Read<'a, GameClock>,
+        Read<'a, GameStateResource>,
+        Write<'a, LevelTension>,
+        Write<'a, WanderingSpawns>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (players, positions, map, clock, game_state, mut tension, mut wanderers, mut gamelog, mut rng) = data;
+
+        let turns = match self.last_turn {
+            Some(last) if game_state.turn_count > last => game_state.turn_count - last,
+            _ => 0,
+        };
+        self.last_turn = Some(game_state.turn_count);
+        // Nothing wanders in from the dark past the town watch
+        if game_state.location.is_town() {
+            return;
+        }
+        if tension.enter(game_state.location) || turns == 0 {
+            return;
+        }
+
+        let before = tension.danger();
+        tension.tick(turns);
+        let now = tension.danger();
+        if now > before {
+            if let Some(warning) = now.warning() {
+                gamelog.add_entry(warning.to_string());
+            }
+        }
+
+        let player_positions: Vec<(i32, i32)> = (&positions, &players).join()
+            .map(|(pos, _)| (pos.x, pos.y))
+            .collect();
+        let daylight = !clock.is_night();
+        for _ in 0..turns {
+            if !tension.roll_wanderer(&mut rng) {
+                continue;
+            }
+            if let Some(spawn) = SpawnDirector::wandering_spawn(&map, daylight, &mut rng, &player_positions) {
+                tension.wanderers += 1;
+                wanderers.spawns.push(spawn);
+            }
+        }
+    }
+}
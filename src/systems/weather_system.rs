@@ -0,0 +1,96 @@
+use specs::{System, WriteStorage, ReadStorage, Entities, Join, Write, Read, ReadExpect};
+use crate::components::{Player, Position, StatusEffect, StatusEffects, StatusEffectType, Viewshed};
+use crate::map::{Map, Weather, WeatherKind};
+use crate::resources::{GameLog, RandomNumberGenerator, GameStateResource};
+
+/// Turns a soaking lasts after leaving the rain
+const WET_DURATION: i32 = 3;
+/// One in this many turns a storm throws lightning
+const LIGHTNING_ODDS: i32 = 12;
+/// One in this many turns the weather turns on the same level
+const WEATHER_CHANGE_ODDS: i32 = 150;
+
+/// Rolls each level's weather and applies its effects once per turn
+pub struct WeatherSystem {
+    pub last_turn: Option<u32>,
+}
+
+impl WeatherSystem {
+    pub fn new() -> Self {
+        WeatherSystem { last_turn: None }
+    }
+}
+
+impl<'a> System<'a> for WeatherSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, Viewshed>,
+        ReadExpect<'a, Map>,
+        Write<'a, Weather>,
+        Read<'a, GameStateResource>,
+        Write<'a, GameLog>,
+        Write<'a, RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            positions,
+            players,
+            mut status_effects,
+            mut viewsheds,
+            map,
+            mut weather,
+            game_state,
+            mut gamelog,
+            mut rng,
+        ) = data;
+
+        if self.last_turn == Some(game_state.turn_count) {
+            return;
+        }
+        self.last_turn = Some(game_state.turn_count);
+
+        // A new level brings its own weather, and now and then the sky turns
+        let previous = weather.kind;
+        if weather.theme != Some(map.theme) || rng.roll_dice(1, WEATHER_CHANGE_ODDS) == 1 {
+            weather.change(map.theme, &mut rng);
+            if weather.kind != previous {
+                // Fog coming or going changes how far everyone can see
+                for viewshed in (&mut viewsheds).join() {
+                    viewshed.dirty = true;
+                }
+                match weather.kind.arrival_message() {
+                    Some(message) => gamelog.add_entry(message.to_string()),
+                    None if previous != WeatherKind::Clear => gamelog.add_entry("The weather clears.".to_string()),
+                    None => {}
+                }
+            }
+        }
+
+        if weather.kind == WeatherKind::Storm && rng.roll_dice(1, LIGHTNING_ODDS) == 1 {
+            weather.strike_lightning();
+            gamelog.add_entry("Lightning splits the sky!".to_string());
+        }
+
+        if !weather.kind.soaks() {
+            return;
+        }
+        for (entity, effects, _) in (&entities, &mut status_effects, &positions).join() {
+            // Rain puts out the flames before it soaks through
+            if effects.has_effect(StatusEffectType::Burning) {
+                effects.remove_effect(StatusEffectType::Burning);
+                if players.contains(entity) {
+                    gamelog.add_entry("The rain puts out the flames.".to_string());
+                }
+            }
+            if players.contains(entity) && !effects.has_effect(StatusEffectType::Wet) {
+                gamelog.add_entry("You are soaked to the skin.".to_string());
+            }
+            effects.add_effect(StatusEffect::new(StatusEffectType::Wet, WET_DURATION, 1));
+        }
+    }
+}
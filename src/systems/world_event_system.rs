@@ -0,0 +1,86 @@
+use specs::{System, Read, ReadExpect, Write};
+use crate::localization::Message;
+use crate::map::Map;
+use crate::progression::{WorldEvents, WorldNews};
+use crate::resources::{GameClock, GameLog, RandomNumberGenerator, WORLD_STREAM};
+
+/// Moves the world above along as each day turns over: raids, caravans and floods run their
+/// course, and while the character is below Ravenhollow, new ones begin every few days. The
+/// player hears of them in the log, the way word reaches the dungeon
+pub struct WorldEventSystem {
+    pub last_day: Option<u64>,
+}
+
+impl WorldEventSystem {
+    pub fn new() -> Self {
+        WorldEventSystem { last_day: None }
+    }
+}
+
+impl<'a> System<'a> for WorldEventSystem {
+    type SystemData = (
+        Read<'a, GameClock>,
+        ReadExpect<'a, Map>,
+        Write<'a, WorldEvents>,
+        Write<'a, RandomNumberGenerator>,
+        Write<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (clock, map, mut world, mut rng, mut gamelog) = data;
+
+        let today = clock.day();
+        let days = match self.last_day {
+            Some(last) if today > last => today - last,
+            _ => 0,
+        };
+        self.last_day = Some(today);
+        if days == 0 {
+            return;
+        }
+        let underground = !map.location.is_town();
+        for news in world.pass_days(days as u32, today, underground, rng.stream(WORLD_STREAM)) {
+            report_world_news(news, &mut gamelog);
+        }
+    }
+}
+
+/// Tell the player what has begun or ended up above
+pub fn report_world_news(news: WorldNews, gamelog: &mut GameLog) {
+    match news {
+        WorldNews::Began(kind) => gamelog.add_message(Message::new(kind.news_key())),
+        WorldNews::Ended(kind) => gamelog.add_message(Message::new("log.event_over").arg("event", kind.name())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, RunNow};
+
+    #[test]
+    fn test_news_only_reaches_the_dungeon() {
+        let mut world = World::new();
+        world.insert(GameClock::default());
+        world.insert(Map::new(10, 10, 1));
+        world.insert(WorldEvents::default());
+        world.insert(RandomNumberGenerator::new(5));
+        world.insert(GameLog::new(10));
+
+        let mut events = WorldEventSystem::new();
+        events.run_now(&world);
+        assert!(world.read_resource::<WorldEvents>().active.is_empty());
+
+        world.write_resource::<GameClock>().minutes += 24 * 60;
+        events.run_now(&world);
+        assert_eq!(world.read_resource::<WorldEvents>().active.len(), 1);
+        assert_eq!(world.read_resource::<WorldEvents>().timeline[0].day, 2);
+
+        world.write_resource::<Map>().location = crate::map::Location::town();
+        world.write_resource::<GameClock>().minutes += 24 * 60 * 10;
+        events.run_now(&world);
+        let events = world.read_resource::<WorldEvents>();
+        assert!(events.active.is_empty());
+        assert_eq!(events.timeline.len(), 1);
+    }
+}
@@ -0,0 +1,427 @@
+use crossterm::{event::KeyCode, style::Color};
+use crate::guild::{Agent, Contract, ContractReport, GuildRoster, GuildStores, MAX_AGENTS};
+use crate::localization::{tr, Message};
+use crate::progression::WorldEvents;
+use crate::ui::{UIPanel, UIRenderCommand};
+use crate::ui::bestiary_screen::wrap;
+
+/// What the player asks of the guild from the agents screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentAction {
+    /// Sign on the candidate at this place among the recruits
+    Hire(usize),
+    /// Send the agent at this place on the roster out on a contract
+    Send(usize, Contract),
+    Close,
+}
+
+/// The guild's agents and the candidates waiting to join them, one list with the agents first.
+/// Picking a ready agent offers the contracts to send them on; picking a candidate asks whether
+/// to pay what they want to sign on. Tab turns to the guild's ledger, where every contract's
+/// report can be read back
+#[derive(Debug, Clone)]
+pub struct AgentScreen {
+    pub selected: usize,
+    /// The agent the contracts are being offered to, and the contract highlighted
+    pub picking: Option<(usize, usize)>,
+    /// The candidate the player has picked, waiting on them to agree to the price
+    pub confirming: Option<usize>,
+    /// The report highlighted in the ledger, newest first, while the ledger is open
+    pub reports: Option<usize>,
+}
+
+impl AgentScreen {
+    pub fn new() -> Self {
+        AgentScreen { selected: 0, picking: None, confirming: None, reports: None }
+    }
+
+    pub fn open(&mut self) {
+        *self = AgentScreen::new();
+    }
+
+    /// The agent or candidate highlighted, and whether they are a candidate
+    fn highlighted<'a>(&self, roster: &'a GuildRoster) -> Option<(&'a Agent, bool)> {
+        match roster.agents.get(self.selected) {
+            Some(agent) => Some((agent, false)),
+            None => roster.recruits.get(self.selected - roster.agents.len()).map(|recruit| (recruit, true)),
+        }
+    }
+
+    /// The report highlighted in the ledger
+    fn highlighted_report<'a>(&self, roster: &'a GuildRoster) -> Option<&'a ContractReport> {
+        roster.ledger.newest_first().get(self.reports?).copied()
+    }
+
+    /// Move the selection, or return what the player asked for
+    pub fn handle_key(&mut self, key: KeyCode, roster: &GuildRoster) -> Option<AgentAction> {
+        if let Some(report) = self.reports {
+            let count = roster.ledger.reports.len();
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => self.reports = Some(report.saturating_sub(1)),
+                KeyCode::Down | KeyCode::Char('j') => self.reports = Some((report + 1).min(count.saturating_sub(1))),
+                KeyCode::Tab | KeyCode::Esc => self.reports = None,
+                KeyCode::Char('q') => return Some(AgentAction::Close),
+                _ => {},
+            }
+            return None;
+        }
+        if let Some(recruit) = self.confirming.take() {
+            return match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Some(AgentAction::Hire(recruit)),
+                _ => None,
+            };
+        }
+        if let Some((agent, contract)) = self.picking {
+            let contracts = Contract::all();
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => self.picking = Some((agent, contract.saturating_sub(1))),
+                KeyCode::Down | KeyCode::Char('j') => self.picking = Some((agent, (contract + 1).min(contracts.len() - 1))),
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    self.picking = None;
+                    return Some(AgentAction::Send(agent, contracts[contract]));
+                },
+                KeyCode::Esc => self.picking = None,
+                _ => {},
+            }
+            return None;
+        }
+        let count = roster.agents.len() + roster.recruits.len();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(count.saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char(' ') => match self.highlighted(roster) {
+                Some((agent, false)) if agent.is_ready() => self.picking = Some((self.selected, 0)),
+                Some((_, true)) => self.confirming = Some(self.selected - roster.agents.len()),
+                _ => {},
+            },
+            KeyCode::Tab => self.reports = Some(0),
+            KeyCode::Esc | KeyCode::Char('q') => return Some(AgentAction::Close),
+            _ => {},
+        }
+        None
+    }
+
+    /// Where an agent is and when they will be fit to go out again
+    fn status(agent: &Agent) -> (String, Color) {
+        match agent.assignment {
+            Some(assignment) => (
+                Message::new("agents.away").arg("contract", assignment.contract.name()).arg("days", assignment.days_left).text(),
+                Color::Cyan,
+            ),
+            None if agent.recovery_days > 0 => (
+                Message::new("agents.recovering").arg("days", agent.recovery_days).text(),
+                Color::Red,
+            ),
+            None => (tr("agents.ready"), Color::Green),
+        }
+    }
+
+    /// Everything about an agent or candidate, one line at a time
+    pub fn describe(agent: &Agent, recruit: bool) -> Vec<(String, Color)> {
+        let traits: Vec<&str> = agent.traits.iter().map(|agent_trait| agent_trait.name()).collect();
+        let mut lines = vec![
+            (Message::new("agents.heading").arg("name", &agent.name).arg("level", agent.level).arg("class", agent.class.name()).text(), Color::Yellow),
+            (Message::new("agents.stats").arg("hp", agent.max_hp).arg("power", agent.power).arg("defense", agent.defense).text(), Color::White),
+            (Message::new("agents.experience").arg("experience", agent.experience).arg("next", agent.experience_to_level()).text(), Color::White),
+        ];
+        if traits.is_empty() {
+            lines.push((tr("agents.no_traits"), Color::DarkGrey));
+        } else {
+            lines.push((Message::new("agents.traits").arg("list", traits.join(", ")).text(), Color::Magenta));
+        }
+        if recruit {
+            lines.push((Message::new("agents.hire_cost").arg("gold", agent.hire_cost()).text(), Color::Yellow));
+        } else {
+            lines.push(AgentScreen::status(agent));
+        }
+        lines
+    }
+
+    /// A contract as offered to `agent`, at what it pays with the world as it is, or what keeps
+    /// it from being offered
+    fn contract_label(agent: &Agent, contract: Contract, stores: &GuildStores, world: &WorldEvents) -> String {
+        if let Some(event) = world.closing(contract) {
+            return Message::new("agents.contract_closed")
+                .arg("contract", contract.name())
+                .arg("event", event.name())
+                .text();
+        }
+        if stores.renown < contract.renown_required() {
+            return Message::new("agents.contract_locked")
+                .arg("contract", contract.name())
+                .arg("renown", contract.renown_required())
+                .text();
+        }
+        Message::new("agents.contract")
+            .arg("contract", contract.name())
+            .arg("days", contract.days())
+            .arg("gold", contract.reward() * world.reward_percent(contract) / 100)
+            .arg("supplies", stores.supplies_for(contract))
+            .arg("chance", agent.success_chance(contract))
+            .text()
+    }
+
+    /// How a contract went, one line at a time, before the story of it
+    pub fn describe_report(report: &ContractReport) -> Vec<(String, Color)> {
+        let mut lines = vec![(
+            Message::new("agents.report_heading")
+                .arg("day", report.day)
+                .arg("agent", &report.agent)
+                .arg("contract", report.contract.name())
+                .text(),
+            Color::Yellow,
+        )];
+        if report.died {
+            lines.push((tr("agents.report_died"), Color::Red));
+            return lines;
+        }
+        if report.success {
+            lines.push((Message::new("agents.report_success")
+                .arg("gold", report.gold)
+                .arg("reagents", report.reagents)
+                .arg("experience", report.experience)
+                .text(), Color::Green));
+        } else {
+            lines.push((Message::new("agents.report_failure").arg("experience", report.experience).text(), Color::DarkYellow));
+        }
+        if report.injury_days > 0 {
+            lines.push((Message::new("agents.report_injured").arg("days", report.injury_days).text(), Color::Red));
+        }
+        for milestone in &report.milestones {
+            lines.push((Message::new("agents.report_milestone").arg("milestone", milestone.name()).text(), Color::Magenta));
+        }
+        lines
+    }
+
+    /// The color a report is listed in, by how it went
+    fn report_color(report: &ContractReport) -> Color {
+        if report.died {
+            Color::Red
+        } else if report.success {
+            Color::Green
+        } else {
+            Color::DarkYellow
+        }
+    }
+
+    /// What the player is asked before signing on a candidate
+    fn confirm_prompt(&self, roster: &GuildRoster) -> Option<String> {
+        let recruit = roster.recruits.get(self.confirming?)?;
+        Some(Message::new("agents.confirm_hire").arg("name", &recruit.name).arg("gold", recruit.hire_cost()).text())
+    }
+
+    /// The highlighted line, for the screen reader
+    pub fn selected_line(&self, roster: &GuildRoster, world: &WorldEvents) -> Option<String> {
+        if let Some(prompt) = self.confirm_prompt(roster) {
+            return Some(prompt);
+        }
+        if self.reports.is_some() {
+            return Some(match self.highlighted_report(roster) {
+                Some(report) => {
+                    let mut lines: Vec<String> = AgentScreen::describe_report(report).into_iter().map(|(line, _)| line).collect();
+                    lines.push(report.narrative.clone());
+                    lines.join(". ")
+                },
+                None => tr("agents.no_reports"),
+            });
+        }
+        if let Some((index, contract)) = self.picking {
+            return roster.agents.get(index).map(|agent| AgentScreen::contract_label(agent, Contract::all()[contract], &roster.stores, world));
+        }
+        self.highlighted(roster).map(|(agent, recruit)| {
+            AgentScreen::describe(agent, recruit).into_iter().map(|(line, _)| line).collect::<Vec<_>>().join(". ")
+        })
+    }
+
+    pub fn render(&self, roster: &GuildRoster, world: &WorldEvents, purse: i32, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let width = 76.min(screen_width - 2);
+        let height = 24.min(screen_height - 2);
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(tr("agents.title"), x, y, width, height)
+            .with_colors(Color::White, Color::Black, Color::Yellow)
+            .render();
+        let mut text = |column: i32, row: i32, text: String, max: i32, fg: Color| {
+            let text: String = text.chars().take(max.max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + column, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        let summary = Message::new("agents.summary")
+            .arg("agents", roster.agents.len())
+            .arg("max", MAX_AGENTS)
+            .arg("gold", purse)
+            .arg("supplies", roster.stores.supplies)
+            .text();
+        text(2, 1, summary, width - 4, Color::Cyan);
+
+        let list_width = 24;
+        let detail_x = 2 + list_width + 2;
+        let detail_width = (width - detail_x - 2).max(1);
+        if self.reports.is_some() {
+            // The ledger down the left, newest first, and the highlighted report told on the right
+            let reports = roster.ledger.newest_first();
+            let rows = (height - 6).max(1) as usize;
+            let selected = self.reports.unwrap_or(0);
+            let first = selected.saturating_sub(rows - 1);
+            text(2, 3, Message::new("agents.ledger").arg("count", reports.len()).text(), list_width, Color::Cyan);
+            if reports.is_empty() {
+                text(2, 4, tr("agents.no_reports"), width - 4, Color::DarkGrey);
+            }
+            for (i, report) in reports.iter().enumerate().skip(first).take(rows - 1) {
+                let marker = if i == selected { '>' } else { ' ' };
+                let fg = if i == selected { Color::Yellow } else { AgentScreen::report_color(report) };
+                let entry = Message::new("agents.report_entry").arg("day", report.day).arg("agent", &report.agent).text();
+                text(2, 4 + (i - first) as i32, format!("{} {}", marker, entry), list_width, fg);
+            }
+            if let Some(report) = self.highlighted_report(roster) {
+                let mut row = 3;
+                for (line, fg) in AgentScreen::describe_report(report) {
+                    text(detail_x, row, line, detail_width, fg);
+                    row += 1;
+                }
+                row += 1;
+                for line in wrap(&report.narrative, detail_width as usize) {
+                    if row >= height - 2 {
+                        break;
+                    }
+                    text(detail_x, row, line, detail_width, Color::White);
+                    row += 1;
+                }
+            }
+            text(2, height - 2, tr("agents.reports_controls"), width - 4, Color::DarkGrey);
+            return commands;
+        }
+
+        // The agents down the left, then the candidates under a heading of their own
+        let mut row = 3;
+        if roster.agents.is_empty() {
+            text(2, row, tr("agents.none"), list_width, Color::DarkGrey);
+            row += 1;
+        }
+        for (i, agent) in roster.agents.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = if i == self.selected { Color::Yellow } else if agent.is_ready() { Color::White } else { Color::DarkGrey };
+            text(2, row, format!("{} {} ({})", marker, agent.name, agent.level), list_width, fg);
+            row += 1;
+        }
+        row += 1;
+        text(2, row, tr("agents.recruits"), list_width, Color::Cyan);
+        row += 1;
+        for (i, recruit) in roster.recruits.iter().enumerate() {
+            let index = roster.agents.len() + i;
+            let marker = if index == self.selected { '>' } else { ' ' };
+            let fg = if index == self.selected { Color::Yellow } else { Color::White };
+            text(2, row, format!("{} {} ({})", marker, recruit.name, recruit.level), list_width, fg);
+            row += 1;
+        }
+        if !roster.fallen.is_empty() {
+            row += 1;
+            let names: Vec<&str> = roster.fallen.iter().map(|agent| agent.name.as_str()).collect();
+            text(2, row, Message::new("agents.fallen").arg("names", names.join(", ")).text(), width - 4, Color::DarkGrey);
+        }
+
+        // The highlighted agent on the right, with the contracts on offer when picking one
+        if let Some((agent, recruit)) = self.highlighted(roster) {
+            let mut row = 3;
+            for (line, fg) in AgentScreen::describe(agent, recruit) {
+                text(detail_x, row, line, detail_width, fg);
+                row += 1;
+            }
+            if let Some((_, picked)) = self.picking {
+                row += 1;
+                for (i, contract) in Contract::all().into_iter().enumerate() {
+                    let marker = if i == picked { '>' } else { ' ' };
+                    let fg = if i == picked {
+                        Color::Yellow
+                    } else if world.closing(contract).is_none() && roster.stores.can_take(contract).is_ok() {
+                        Color::White
+                    } else {
+                        Color::DarkGrey
+                    };
+                    text(detail_x, row, format!("{} {}", marker, AgentScreen::contract_label(agent, contract, &roster.stores, world)), detail_width, fg);
+                    row += 1;
+                }
+            }
+        }
+
+        match self.confirm_prompt(roster) {
+            Some(prompt) => text(2, height - 2, prompt, width - 4, Color::Yellow),
+            None => text(2, height - 2, tr("agents.controls"), width - 4, Color::DarkGrey),
+        }
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::RandomNumberGenerator;
+
+    #[test]
+    fn test_picking_an_agent_offers_contracts_and_a_recruit_asks_first() {
+        let mut rng = RandomNumberGenerator::new(5);
+        let mut roster = GuildRoster::default();
+        roster.agents.push(Agent::generate(&mut rng));
+        roster.refresh_recruits(&mut rng);
+        let mut screen = AgentScreen::new();
+
+        assert_eq!(screen.handle_key(KeyCode::Enter, &roster), None);
+        assert_eq!(screen.handle_key(KeyCode::Down, &roster), None);
+        assert_eq!(screen.handle_key(KeyCode::Enter, &roster), Some(AgentAction::Send(0, Contract::Bounty)));
+
+        screen.handle_key(KeyCode::Down, &roster);
+        assert_eq!(screen.handle_key(KeyCode::Enter, &roster), None);
+        assert_eq!(screen.handle_key(KeyCode::Char('n'), &roster), None);
+        screen.handle_key(KeyCode::Enter, &roster);
+        assert_eq!(screen.handle_key(KeyCode::Char('y'), &roster), Some(AgentAction::Hire(0)));
+    }
+
+    #[test]
+    fn test_a_flood_keeps_the_deep_contracts_off_the_books() {
+        let mut rng = RandomNumberGenerator::new(5);
+        let mut roster = GuildRoster::default();
+        roster.agents.push(Agent::generate(&mut rng));
+        let mut world = WorldEvents::default();
+        world.active.push(crate::progression::WorldEvent { kind: crate::progression::WorldEventKind::DungeonFlood, days_left: 2 });
+        let mut screen = AgentScreen::new();
+
+        screen.handle_key(KeyCode::Enter, &roster);
+        screen.handle_key(KeyCode::Down, &roster);
+        screen.handle_key(KeyCode::Down, &roster);
+        let line = screen.selected_line(&roster, &world).unwrap();
+        assert!(line.contains("Deep delve") && line.contains("Dungeon flood"), "{}", line);
+    }
+
+    #[test]
+    fn test_tab_reads_the_ledger_newest_first() {
+        let mut roster = GuildRoster::default();
+        for day in 1..=3 {
+            roster.ledger.record(ContractReport {
+                id: 0,
+                day,
+                agent: "Edda".to_string(),
+                class: crate::components::ClassType::Fighter,
+                traits: Vec::new(),
+                contract: Contract::Patrol,
+                success: true,
+                gold: 40,
+                experience: 30,
+                levels: 0,
+                injury_days: 0,
+                died: false,
+                reagents: 0,
+                renown: 1,
+                milestones: Vec::new(),
+                narrative: format!("Day {} went by.", day),
+            });
+        }
+        let mut screen = AgentScreen::new();
+        screen.handle_key(KeyCode::Tab, &roster);
+        assert!(screen.selected_line(&roster, &WorldEvents::default()).unwrap().contains("Day 3 went by."));
+        screen.handle_key(KeyCode::Down, &roster);
+        assert!(screen.selected_line(&roster, &WorldEvents::default()).unwrap().contains("Day 2 went by."));
+        screen.handle_key(KeyCode::Esc, &roster);
+        assert_eq!(screen.reports, None);
+        assert_eq!(screen.handle_key(KeyCode::Esc, &roster), Some(AgentAction::Close));
+    }
+}
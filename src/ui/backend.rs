@@ -0,0 +1,269 @@
+use crossterm::style::Color;
+use std::error::Error;
+use crate::rendering::{Terminal, with_terminal};
+use crate::ui::ui_components::UIRenderCommand;
+
+/// A rectangle of screen cells a widget is drawn into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiRect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl UiRect {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        UiRect { x, y, width, height }
+    }
+
+    /// The area inside a one-cell border
+    pub fn inner(&self) -> UiRect {
+        UiRect {
+            x: self.x + 1,
+            y: self.y + 1,
+            width: self.width.saturating_sub(2),
+            height: self.height.saturating_sub(2),
+        }
+    }
+}
+
+/// Widgets a UI screen can ask a backend to draw
+#[derive(Debug, Clone)]
+pub enum UiWidget {
+    /// Plain draw commands from the hand-rolled components
+    Commands(Vec<UIRenderCommand>),
+    /// A bordered, scrollable list with one highlighted row
+    List {
+        area: UiRect,
+        title: String,
+        items: Vec<String>,
+        selected: Option<usize>,
+        offset: usize,
+    },
+    /// A bordered table; `widths` are column widths in cells
+    Table {
+        area: UiRect,
+        title: String,
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+        widths: Vec<u16>,
+        selected: Option<usize>,
+        offset: usize,
+    },
+    /// A one-line bar filled to `ratio` (0.0 to 1.0)
+    Gauge {
+        area: UiRect,
+        label: String,
+        ratio: f64,
+        color: Color,
+    },
+    /// A vertical scrollbar for a view of `visible` rows out of `total`
+    Scrollbar {
+        area: UiRect,
+        position: usize,
+        total: usize,
+        visible: usize,
+    },
+    /// A bordered block of text lines
+    Paragraph {
+        area: UiRect,
+        title: String,
+        lines: Vec<String>,
+        color: Color,
+    },
+}
+
+/// Something that can put UI widgets on screen
+pub trait UiBackend {
+    /// Queue or draw a widget; nothing is guaranteed visible until `present`
+    fn draw(&mut self, widget: UiWidget) -> Result<(), Box<dyn Error>>;
+    /// Show everything drawn since the last present
+    fn present(&mut self) -> Result<(), Box<dyn Error>>;
+    /// Screen size in cells
+    fn size(&self) -> (u16, u16);
+}
+
+/// Start row and length of a scrollbar thumb in a track `height` cells tall
+pub fn scrollbar_thumb(position: usize, total: usize, visible: usize, height: u16) -> (u16, u16) {
+    let height = height as usize;
+    if total <= visible || height == 0 {
+        return (0, height as u16);
+    }
+    let length = (visible * height / total).max(1);
+    let max_position = total - visible;
+    let start = position.min(max_position) * (height - length) / max_position;
+    (start as u16, length as u16)
+}
+
+/// Cells of a `width`-wide gauge that should be filled
+pub fn gauge_fill(ratio: f64, width: u16) -> u16 {
+    (ratio.clamp(0.0, 1.0) * width as f64).round() as u16
+}
+
+/// Cut or pad text to exactly `width` cells
+fn fit(text: &str, width: u16) -> String {
+    let clipped: String = text.chars().take(width as usize).collect();
+    format!("{:<width$}", clipped, width = width as usize)
+}
+
+/// The default backend, drawing into the double-buffered terminal
+pub struct TerminalUiBackend;
+
+impl TerminalUiBackend {
+    pub fn new() -> Self {
+        TerminalUiBackend
+    }
+
+    fn draw_border(terminal: &mut Terminal, area: UiRect, title: &str) -> crossterm::Result<()> {
+        if area.width < 2 || area.height < 2 {
+            return Ok(());
+        }
+        terminal.fill_rect(area.x, area.y, area.width, area.height, ' ', Color::White, Color::Black)?;
+        terminal.draw_box(area.x, area.y, area.width, area.height, Color::White, Color::Black)?;
+        if !title.is_empty() {
+            let title = format!(" {} ", title);
+            terminal.draw_text(area.x + 2, area.y, &fit(&title, area.width.saturating_sub(4).min(title.chars().count() as u16)), Color::Yellow, Color::Black)?;
+        }
+        Ok(())
+    }
+
+    fn draw_command(terminal: &mut Terminal, command: &UIRenderCommand) -> crossterm::Result<()> {
+        match command {
+            UIRenderCommand::DrawText { x, y, text, fg, bg } => {
+                if *x >= 0 && *y >= 0 {
+                    terminal.draw_text(*x as u16, *y as u16, text, *fg, *bg)?;
+                }
+            }
+            UIRenderCommand::DrawBox { x, y, width, height, border_color, fill_color } => {
+                if *x >= 0 && *y >= 0 && *width >= 2 && *height >= 2 {
+                    let (x, y, width, height) = (*x as u16, *y as u16, *width as u16, *height as u16);
+                    terminal.fill_rect(x, y, width, height, ' ', *border_color, *fill_color)?;
+                    terminal.draw_box(x, y, width, height, *border_color, *fill_color)?;
+                }
+            }
+            UIRenderCommand::DrawLine { x1, y1, x2, y2, color, character } => {
+                if *y1 == *y2 && *y1 >= 0 {
+                    for x in (*x1).min(*x2).max(0)..=(*x1).max(*x2) {
+                        terminal.draw_char_at(x as u16, *y1 as u16, *character, *color, Color::Black)?;
+                    }
+                } else if *x1 == *x2 && *x1 >= 0 {
+                    for y in (*y1).min(*y2).max(0)..=(*y1).max(*y2) {
+                        terminal.draw_char_at(*x1 as u16, y as u16, *character, *color, Color::Black)?;
+                    }
+                }
+            }
+            // The double buffer owns the cursor
+            UIRenderCommand::SetCursor { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+impl UiBackend for TerminalUiBackend {
+    fn draw(&mut self, widget: UiWidget) -> Result<(), Box<dyn Error>> {
+        with_terminal(|terminal| {
+            match &widget {
+                UiWidget::Commands(commands) => {
+                    for command in commands {
+                        Self::draw_command(terminal, command)?;
+                    }
+                }
+                UiWidget::List { area, title, items, selected, offset } => {
+                    Self::draw_border(terminal, *area, title)?;
+                    let inner = area.inner();
+                    for (row, (index, item)) in items.iter().enumerate().skip(*offset).take(inner.height as usize).enumerate() {
+                        let (fg, bg) = if Some(index) == *selected { (Color::Black, Color::White) } else { (Color::White, Color::Black) };
+                        terminal.draw_text(inner.x, inner.y + row as u16, &fit(item, inner.width), fg, bg)?;
+                    }
+                }
+                UiWidget::Table { area, title, header, rows, widths, selected, offset } => {
+                    Self::draw_border(terminal, *area, title)?;
+                    let inner = area.inner();
+                    let line = |cells: &[String]| -> String {
+                        cells.iter().zip(widths.iter())
+                            .map(|(cell, width)| fit(cell, *width))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    };
+                    terminal.draw_text(inner.x, inner.y, &fit(&line(header), inner.width), Color::Cyan, Color::Black)?;
+                    let body_height = inner.height.saturating_sub(1) as usize;
+                    for (row, (index, cells)) in rows.iter().enumerate().skip(*offset).take(body_height).enumerate() {
+                        let (fg, bg) = if Some(index) == *selected { (Color::Black, Color::White) } else { (Color::White, Color::Black) };
+                        terminal.draw_text(inner.x, inner.y + 1 + row as u16, &fit(&line(cells), inner.width), fg, bg)?;
+                    }
+                }
+                UiWidget::Gauge { area, label, ratio, color } => {
+                    let label = format!("{} ", label);
+                    let label_width = (label.chars().count() as u16).min(area.width);
+                    terminal.draw_text(area.x, area.y, &fit(&label, label_width), Color::White, Color::Black)?;
+                    let bar_width = area.width - label_width;
+                    let filled = gauge_fill(*ratio, bar_width);
+                    for i in 0..bar_width {
+                        let (ch, fg) = if i < filled { ('█', *color) } else { ('░', Color::DarkGrey) };
+                        terminal.draw_char_at(area.x + label_width + i, area.y, ch, fg, Color::Black)?;
+                    }
+                }
+                UiWidget::Scrollbar { area, position, total, visible } => {
+                    let (start, length) = scrollbar_thumb(*position, *total, *visible, area.height);
+                    for i in 0..area.height {
+                        let (ch, fg) = if i >= start && i < start + length { ('█', Color::Grey) } else { ('│', Color::DarkGrey) };
+                        terminal.draw_char_at(area.x, area.y + i, ch, fg, Color::Black)?;
+                    }
+                }
+                UiWidget::Paragraph { area, title, lines, color } => {
+                    Self::draw_border(terminal, *area, title)?;
+                    let inner = area.inner();
+                    for (row, line) in lines.iter().take(inner.height as usize).enumerate() {
+                        terminal.draw_text(inner.x, inner.y + row as u16, &fit(line, inner.width), *color, Color::Black)?;
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), Box<dyn Error>> {
+        with_terminal(|terminal| terminal.flush())?;
+        Ok(())
+    }
+
+    fn size(&self) -> (u16, u16) {
+        with_terminal(|terminal| Ok(terminal.size())).unwrap_or((80, 24))
+    }
+}
+
+/// The backend picked at compile time: ratatui with the `ratatui_ui` feature, the terminal otherwise
+pub fn default_ui_backend() -> Result<Box<dyn UiBackend>, Box<dyn Error>> {
+    #[cfg(feature = "ratatui_ui")]
+    {
+        Ok(Box::new(crate::ui::ratatui_backend::RatatuiBackend::new()?))
+    }
+    #[cfg(not(feature = "ratatui_ui"))]
+    {
+        Ok(Box::new(TerminalUiBackend::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrollbar_thumb_tracks_position() {
+        // Everything fits, so the thumb fills the track
+        assert_eq!(scrollbar_thumb(0, 5, 10, 10), (0, 10));
+        assert_eq!(scrollbar_thumb(0, 40, 10, 10), (0, 2));
+        assert_eq!(scrollbar_thumb(30, 40, 10, 10), (8, 2));
+        // Positions past the end pin the thumb to the bottom
+        assert_eq!(scrollbar_thumb(99, 40, 10, 10), (8, 2));
+    }
+
+    #[test]
+    fn test_gauge_fill_is_clamped() {
+        assert_eq!(gauge_fill(0.5, 10), 5);
+        assert_eq!(gauge_fill(1.7, 10), 10);
+        assert_eq!(gauge_fill(-0.2, 10), 0);
+    }
+}
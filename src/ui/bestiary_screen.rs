@@ -0,0 +1,172 @@
+use crossterm::{event::KeyCode, style::Color};
+use crate::components::MovementMode;
+use crate::localization::{tr, Message};
+use crate::progression::{Bestiary, BestiaryEntry};
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// Browser over every kind of monster met, reached from the main menu and the look command
+#[derive(Debug, Clone)]
+pub struct BestiaryScreen {
+    pub selected: usize,
+}
+
+impl BestiaryScreen {
+    pub fn new() -> Self {
+        BestiaryScreen { selected: 0 }
+    }
+
+    /// Start at the named kind, or at the top when it has no entry
+    pub fn open(&mut self, bestiary: &Bestiary, name: Option<&str>) {
+        self.selected = name
+            .and_then(|name| bestiary.sorted().iter().position(|entry| entry.name == name))
+            .unwrap_or(0);
+    }
+
+    /// Move the selection, returning true once the screen should close
+    pub fn handle_key(&mut self, key: KeyCode, bestiary: &Bestiary) -> bool {
+        let count = bestiary.entries.len();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(count.saturating_sub(1)),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return true,
+            _ => {},
+        }
+        false
+    }
+
+    /// Everything known about a kind, one line at a time
+    pub fn describe(entry: &BestiaryEntry) -> Vec<(String, Color)> {
+        let mut lines = vec![
+            (format!("{} {}", entry.glyph, entry.name), Color::Yellow),
+            (Message::new("bestiary.tally").arg("seen", entry.seen).arg("killed", entry.killed).text(), Color::White),
+        ];
+
+        match (entry.reveals_stats(), entry.stats) {
+            (true, Some((hp, defense, power))) => lines.push((
+                Message::new("bestiary.stats").arg("hp", hp).arg("defense", defense).arg("power", power).text(),
+                Color::Cyan,
+            )),
+            _ => lines.push((tr("bestiary.stats_unknown"), Color::DarkGrey)),
+        }
+
+        if entry.reveals_resistances() {
+            let resistances = if entry.resistances.is_empty() {
+                tr("bestiary.no_resistances")
+            } else {
+                let list: Vec<String> = entry.resistances.iter().map(|(kind, percent)| format!("{} {}%", kind, percent)).collect();
+                Message::new("bestiary.resistances").arg("list", list.join(", ")).text()
+            };
+            lines.push((resistances, Color::Cyan));
+            if let Some(enemy) = entry.enemy_type() {
+                let mut nature = Vec::new();
+                if enemy.is_undead() {
+                    nature.push(tr("bestiary.undead"));
+                }
+                if enemy.flee_below().is_none() {
+                    nature.push(tr("bestiary.never_flees"));
+                }
+                if enemy.pack().is_some() {
+                    nature.push(tr("bestiary.packs"));
+                }
+                if enemy.is_nocturnal() {
+                    nature.push(tr("bestiary.nocturnal"));
+                }
+                match enemy.movement_mode() {
+                    MovementMode::Flying => nature.push(tr("bestiary.flies")),
+                    MovementMode::Phasing => nature.push(tr("bestiary.phases")),
+                    _ => {},
+                }
+                if !nature.is_empty() {
+                    lines.push((nature.join(", "), Color::Magenta));
+                }
+            }
+        } else {
+            lines.push((tr("bestiary.resistances_unknown"), Color::DarkGrey));
+        }
+
+        if entry.reveals_lore() {
+            let lore = entry.enemy_type().map_or_else(|| tr("bestiary.no_lore"), |enemy| enemy.lore().to_string());
+            lines.push((lore, Color::Grey));
+        }
+        if let Some(kills) = entry.kills_to_next_reveal() {
+            lines.push((Message::new("bestiary.next_reveal").arg("kills", kills).text(), Color::DarkGrey));
+        }
+        lines
+    }
+
+    /// The highlighted kind, for the screen reader
+    pub fn selected_line(&self, bestiary: &Bestiary) -> Option<String> {
+        bestiary.sorted().get(self.selected).map(|entry| {
+            BestiaryScreen::describe(entry).into_iter().map(|(line, _)| line).collect::<Vec<_>>().join(". ")
+        })
+    }
+
+    pub fn render(&self, bestiary: &Bestiary, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let entries = bestiary.sorted();
+        let width = 76.min(screen_width - 2);
+        let height = 24.min(screen_height - 2);
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(tr("bestiary.title"), x, y, width, height)
+            .with_colors(Color::White, Color::Black, Color::Yellow)
+            .render();
+        let mut text = |column: i32, row: i32, text: String, max: i32, fg: Color| {
+            let text: String = text.chars().take(max.max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + column, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        let summary = Message::new("bestiary.summary").arg("kinds", entries.len()).arg("kills", bestiary.total_kills()).text();
+        text(2, 1, summary, width - 4, Color::Cyan);
+
+        if entries.is_empty() {
+            text(2, 3, tr("bestiary.empty"), width - 4, Color::DarkGrey);
+        }
+
+        // The list down the left, scrolled to keep the selection in view
+        let list_width = 22;
+        let visible = (height - 5).max(1) as usize;
+        let first = self.selected.saturating_sub(visible - 1);
+        for (row, (i, entry)) in entries.iter().enumerate().skip(first).take(visible).enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = if i == self.selected { Color::Yellow } else { Color::White };
+            text(2, 3 + row as i32, format!("{} {} {}", marker, entry.glyph, entry.name), list_width, fg);
+        }
+
+        // What is known of the selected kind on the right
+        if let Some(entry) = entries.get(self.selected) {
+            let detail_x = 2 + list_width + 2;
+            let detail_width = (width - detail_x - 2).max(1);
+            let mut row = 3;
+            for (line, fg) in BestiaryScreen::describe(entry) {
+                for wrapped in wrap(&line, detail_width as usize) {
+                    if row >= height - 2 {
+                        break;
+                    }
+                    text(detail_x, row, wrapped, detail_width, fg);
+                    row += 1;
+                }
+            }
+        }
+
+        text(2, height - 2, tr("bestiary.controls"), width - 4, Color::DarkGrey);
+        commands
+    }
+}
+
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -1,157 +1,485 @@
-use specs::{World, WorldExt, Entity, Join};
-use crossterm::style::Color;
-use crate::rendering::terminal::with_terminal;
+use specs::{World, WorldExt, Entity};
+use crossterm::{event::KeyCode, style::Color};
 use crate::components::*;
+use crate::items::Equipment;
+use crate::localization::{tr, Message};
+use crate::progression::{PlayerHistorySystem, EventImportance};
+use crate::systems::{Faction, Reputation, TownWatch};
+use crate::ui::{UIPanel, UIRenderCommand, assign_hotbar_slot};
 
-pub fn render_character_sheet(world: &World, player_entity: Entity) {
-    let _ = with_terminal(|terminal| {
-        // Clear the screen
-        terminal.clear()?;
-        
-        // Get terminal size
-        let (width, height) = terminal.size();
-        
-        // Calculate center position
-        let center_x = width / 2;
-        
-        // Draw title
-        terminal.draw_text_centered(2, "CHARACTER SHEET", Color::Yellow, Color::Black)?;
-        
-        // Get player components
-        let names = world.read_storage::<Name>();
-        let classes = world.read_storage::<CharacterClass>();
-        let backgrounds = world.read_storage::<Background>();
-        let attributes = world.read_storage::<Attributes>();
-        let experiences = world.read_storage::<Experience>();
-        let combat_stats = world.read_storage::<CombatStats>();
-        let skills = world.read_storage::<Skills>();
-        let abilities = world.read_storage::<Abilities>();
-        
-        // Draw character info
-        if let Some(name) = names.get(player_entity) {
-            terminal.draw_text(2, 4, &format!("Name: {}", name.name), Color::White, Color::Black)?;
+/// The character sheet's pages, in the order Tab cycles through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterSheetTab {
+    Stats,
+    Skills,
+    Abilities,
+    History,
+    Reputation,
+}
+
+impl CharacterSheetTab {
+    pub fn all() -> [CharacterSheetTab; 5] {
+        [
+            CharacterSheetTab::Stats,
+            CharacterSheetTab::Skills,
+            CharacterSheetTab::Abilities,
+            CharacterSheetTab::History,
+            CharacterSheetTab::Reputation,
+        ]
+    }
+
+    pub fn title(&self) -> String {
+        tr(match self {
+            CharacterSheetTab::Stats => "sheet.stats",
+            CharacterSheetTab::Skills => "sheet.skills",
+            CharacterSheetTab::Abilities => "sheet.abilities",
+            CharacterSheetTab::History => "sheet.history",
+            CharacterSheetTab::Reputation => "sheet.reputation",
+        })
+    }
+
+    fn index(&self) -> usize {
+        CharacterSheetTab::all().iter().position(|tab| tab == self).unwrap_or(0)
+    }
+
+    /// The tab `steps` to the right, wrapping around at either end
+    fn offset(&self, steps: i32) -> CharacterSheetTab {
+        let tabs = CharacterSheetTab::all();
+        let index = (self.index() as i32 + steps).rem_euclid(tabs.len() as i32);
+        tabs[index as usize]
+    }
+}
+
+/// The tabbed character sheet, read from the player's components each time it is drawn
+#[derive(Debug, Clone)]
+pub struct CharacterSheet {
+    pub tab: CharacterSheetTab,
+    player_entity: Option<Entity>,
+    /// First line of the tab shown, for tabs too long for the screen
+    pub scroll_offset: usize,
+    pub lines_per_page: usize,
+    /// The learned ability picked out on the abilities page, for putting on the hotbar
+    pub ability_cursor: usize,
+}
+
+impl CharacterSheet {
+    pub fn new() -> Self {
+        CharacterSheet {
+            tab: CharacterSheetTab::Stats,
+            player_entity: None,
+            scroll_offset: 0,
+            lines_per_page: 14,
+            ability_cursor: 0,
         }
-        
-        if let Some(class) = classes.get(player_entity) {
-            terminal.draw_text(2, 5, &format!("Class: {}", class.class_type.name()), Color::White, Color::Black)?;
+    }
+
+    /// Show as many lines as a screen `screen_height` rows tall has room for
+    pub fn fit_to(&mut self, screen_height: i32) {
+        self.lines_per_page = (screen_height - 10).max(1) as usize;
+    }
+
+    /// Open on the tab last looked at
+    pub fn open(&mut self, player_entity: Entity) {
+        self.player_entity = Some(player_entity);
+        self.scroll_offset = 0;
+        self.ability_cursor = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.player_entity = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.player_entity.is_some()
+    }
+
+    fn select_tab(&mut self, tab: CharacterSheetTab) {
+        self.tab = tab;
+        self.scroll_offset = 0;
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) {
+        if self.tab == CharacterSheetTab::Abilities && self.handle_ability_key(key, world) {
+            return;
         }
-        
-        if let Some(background) = backgrounds.get(player_entity) {
-            terminal.draw_text(2, 6, &format!("Background: {}", background.background_type.name()), Color::White, Color::Black)?;
+        let line_count = self.tab_lines(world).len();
+        let last_page = line_count.saturating_sub(self.lines_per_page);
+        match key {
+            KeyCode::Tab | KeyCode::Right => self.select_tab(self.tab.offset(1)),
+            KeyCode::BackTab | KeyCode::Left => self.select_tab(self.tab.offset(-1)),
+            KeyCode::Char(digit @ '1'..='5') => {
+                let index = digit as usize - '1' as usize;
+                self.select_tab(CharacterSheetTab::all()[index]);
+            },
+            KeyCode::Up => self.scroll_offset = self.scroll_offset.saturating_sub(1),
+            KeyCode::Down => self.scroll_offset = (self.scroll_offset + 1).min(last_page),
+            KeyCode::PageUp => self.scroll_offset = self.scroll_offset.saturating_sub(self.lines_per_page),
+            KeyCode::PageDown => self.scroll_offset = (self.scroll_offset + self.lines_per_page).min(last_page),
+            KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => self.close(),
+            _ => {},
         }
-        
-        if let Some(exp) = experiences.get(player_entity) {
-            terminal.draw_text(2, 7, &format!("Level: {}", exp.level), Color::White, Color::Black)?;
-            terminal.draw_text(2, 8, &format!("Experience: {}/{} ({}%)", 
-                exp.current, exp.level_up_target, exp.progress_percentage() as i32), Color::White, Color::Black)?;
-            terminal.draw_text(2, 9, &format!("Unspent Points: {}", exp.unspent_points), Color::Green, Color::Black)?;
-            
-            // Draw experience bar
-            let bar_width = 20;
-            let filled = ((exp.current as f32 / exp.level_up_target as f32) * bar_width as f32) as usize;
-            let bar = format!("[{}{}]", 
-                "=".repeat(filled), 
-                " ".repeat(bar_width - filled));
-            terminal.draw_text(2, 10, &bar, Color::Yellow, Color::Black)?;
+    }
+
+    /// On the abilities page Up and Down pick a learned ability and 1-9 put it on the hotbar.
+    /// Returns false for keys the page leaves to the rest of the sheet.
+    fn handle_ability_key(&mut self, key: KeyCode, world: &World) -> bool {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return false,
+        };
+        let learned = learned_abilities(world, player);
+        match key {
+            KeyCode::Up => self.ability_cursor = self.ability_cursor.saturating_sub(1),
+            KeyCode::Down => self.ability_cursor = (self.ability_cursor + 1).min(learned.len().saturating_sub(1)),
+            KeyCode::Char(digit @ '1'..='9') => {
+                if let Some(ability) = learned.get(self.ability_cursor) {
+                    let slot = digit as usize - '1' as usize;
+                    assign_hotbar_slot(world, player, slot, HotbarEntry::Ability(*ability));
+                }
+            },
+            KeyCode::Delete | KeyCode::Backspace => {
+                if let Some(ability) = learned.get(self.ability_cursor) {
+                    if let Some(hotbar) = world.write_storage::<Hotbar>().get_mut(player) {
+                        if let Some(slot) = hotbar.slot_of(&HotbarEntry::Ability(*ability)) {
+                            hotbar.clear(slot);
+                        }
+                    }
+                }
+            },
+            _ => return false,
         }
-        
-        // Draw attributes
-        if let Some(attr) = attributes.get(player_entity) {
-            terminal.draw_text(2, 12, "Attributes:", Color::Yellow, Color::Black)?;
-            terminal.draw_text(4, 13, &format!("STR: {} ({})", attr.strength, 
-                format_modifier(attr.get_modifier(AttributeType::Strength))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 14, &format!("DEX: {} ({})", attr.dexterity, 
-                format_modifier(attr.get_modifier(AttributeType::Dexterity))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 15, &format!("CON: {} ({})", attr.constitution, 
-                format_modifier(attr.get_modifier(AttributeType::Constitution))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 16, &format!("INT: {} ({})", attr.intelligence, 
-                format_modifier(attr.get_modifier(AttributeType::Intelligence))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 17, &format!("WIS: {} ({})", attr.wisdom, 
-                format_modifier(attr.get_modifier(AttributeType::Wisdom))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 18, &format!("CHA: {} ({})", attr.charisma, 
-                format_modifier(attr.get_modifier(AttributeType::Charisma))), Color::White, Color::Black)?;
+
+        // Keep the picked ability and its description on the page
+        let line = 1 + self.ability_cursor * 2;
+        if line < self.scroll_offset {
+            self.scroll_offset = line;
+        } else if line + 2 > self.scroll_offset + self.lines_per_page {
+            self.scroll_offset = (line + 2).saturating_sub(self.lines_per_page);
         }
-        
-        // Draw combat stats
-        if let Some(stats) = combat_stats.get(player_entity) {
-            terminal.draw_text(center_x + 5, 12, "Combat Stats:", Color::Yellow, Color::Black)?;
-            terminal.draw_text(center_x + 7, 13, &format!("HP: {}/{}", stats.hp, stats.max_hp), Color::White, Color::Black)?;
-            terminal.draw_text(center_x + 7, 14, &format!("Attack: {}", stats.power), Color::White, Color::Black)?;
-            terminal.draw_text(center_x + 7, 15, &format!("Defense: {}", stats.defense), Color::White, Color::Black)?;
+        true
+    }
+
+    /// Every line of the current tab, before scrolling
+    pub fn tab_lines(&self, world: &World) -> Vec<(String, Color)> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        match self.tab {
+            CharacterSheetTab::Stats => stats_lines(world, player),
+            CharacterSheetTab::Skills => skills_lines(world, player),
+            CharacterSheetTab::Abilities => abilities_lines(world, player, self.ability_cursor),
+            CharacterSheetTab::History => history_lines(world),
+            CharacterSheetTab::Reputation => reputation_lines(world),
         }
-        
-        // Draw skills
-        if let Some(skill) = skills.get(player_entity) {
-            terminal.draw_text(2, 20, "Skills:", Color::Yellow, Color::Black)?;
-            terminal.draw_text(4, 21, &format!("Unspent Skill Points: {}", skill.unspent_skill_points), Color::Green, Color::Black)?;
-            
-            let mut row = 22;
-            let mut col = 4;
-            
-            for skill_type in SkillType::all() {
-                let level = skill.get_skill_level(skill_type);
-                let stars = "*".repeat(level as usize);
-                terminal.draw_text(col, row, &format!("{}: {}", skill_type.name(), stars), Color::White, Color::Black)?;
-                
-                row += 1;
-                if row > 30 {
-                    row = 22;
-                    col += 25;
-                }
-            }
+    }
+
+    /// The tab name and its first line, for the screen-reader transcript
+    pub fn announcement(&self, world: &World) -> String {
+        let first = self.tab_lines(world).into_iter().next().map_or(String::new(), |(line, _)| line);
+        Message::new("access.character_sheet").arg("tab", self.tab.title()).arg("line", first.trim()).text()
+    }
+
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let mut commands = Vec::new();
+        let panel_width = screen_width - 4;
+        let panel_height = screen_height - 4;
+        let panel = UIPanel::new(tr("sheet.title"), 2, 2, panel_width, panel_height)
+            .with_colors(Color::White, Color::Black, Color::Yellow);
+        commands.extend(panel.render());
+
+        // Tab bar, with the open tab picked out
+        let mut x = 4;
+        for (i, tab) in CharacterSheetTab::all().iter().enumerate() {
+            let label = format!(" {} {} ", i + 1, tab.title());
+            let (fg, bg) = if *tab == self.tab { (Color::Black, Color::Yellow) } else { (Color::Grey, Color::Black) };
+            let width = label.chars().count() as i32;
+            commands.push(UIRenderCommand::DrawText { x, y: 3, text: label, fg, bg });
+            x += width + 1;
         }
-        
-        // Draw abilities
-        if let Some(ability) = abilities.get(player_entity) {
-            terminal.draw_text(center_x + 5, 20, "Abilities:", Color::Yellow, Color::Black)?;
-            
-            let mut row = 22;
-            let mut col = center_x + 7;
-            
-            if let Some(class) = classes.get(player_entity) {
-                let class_abilities = AbilityType::get_class_abilities(class.class_type);
-                
-                for &ability_type in &class_abilities {
-                    let has_ability = ability.has_ability(ability_type);
-                    let on_cooldown = ability.is_on_cooldown(ability_type);
-                    let cooldown = ability.get_cooldown(ability_type);
-                    let req_level = ability_type.required_level();
-                    
-                    let color = if has_ability {
-                        if on_cooldown {
-                            Color::DarkGrey
-                        } else {
-                            Color::Green
-                        }
-                    } else {
-                        Color::DarkGrey
-                    };
-                    
-                    let status = if has_ability {
-                        if on_cooldown {
-                            format!("(Cooldown: {})", cooldown)
-                        } else {
-                            "".to_string()
-                        }
-                    } else {
-                        format!("(Unlocks at level {})", req_level)
-                    };
-                    
-                    terminal.draw_text(col, row, &format!("{} {}", ability_type.name(), status), color, Color::Black)?;
-                    
-                    row += 1;
-                }
-            }
+
+        let text_width = (panel_width - 4).max(0) as usize;
+        for (i, (line, color)) in self.tab_lines(world).into_iter().skip(self.scroll_offset).take(self.lines_per_page).enumerate() {
+            commands.push(UIRenderCommand::DrawText {
+                x: 4,
+                y: 5 + i as i32,
+                text: line.chars().take(text_width).collect(),
+                fg: color,
+                bg: Color::Black,
+            });
+        }
+
+        commands.push(UIRenderCommand::DrawText {
+            x: 4,
+            y: 2 + panel_height - 2,
+            text: tr(if self.tab == CharacterSheetTab::Abilities { "sheet.ability_controls" } else { "sheet.controls" })
+                .chars().take(text_width).collect(),
+            fg: Color::DarkGrey,
+            bg: Color::Black,
+        });
+        commands
+    }
+}
+
+fn attribute_label(attribute: AttributeType) -> &'static str {
+    match attribute {
+        AttributeType::Strength => "STR",
+        AttributeType::Dexterity => "DEX",
+        AttributeType::Constitution => "CON",
+        AttributeType::Intelligence => "INT",
+        AttributeType::Wisdom => "WIS",
+        AttributeType::Charisma => "CHA",
+    }
+}
+
+/// What equipped gear adds to an attribute
+fn gear_attribute_bonus(equipment: Option<&Equipment>, attribute: AttributeType) -> i32 {
+    let stats = match equipment {
+        Some(equipment) => &equipment.stat_cache,
+        None => return 0,
+    };
+    match attribute {
+        AttributeType::Strength => stats.strength_bonus,
+        AttributeType::Dexterity => stats.dexterity_bonus,
+        AttributeType::Constitution => stats.constitution_bonus,
+        AttributeType::Intelligence => stats.intelligence_bonus,
+        AttributeType::Wisdom => stats.wisdom_bonus,
+        AttributeType::Charisma => stats.charisma_bonus,
+    }
+}
+
+/// "(base 4, effects +1, gear +1)" after a total, or nothing when only the base counts
+fn breakdown(base: i32, effects: i32, gear: i32) -> String {
+    let mut parts = Vec::new();
+    if effects != 0 {
+        parts.push(format!("effects {}", format_modifier(effects)));
+    }
+    if gear != 0 {
+        parts.push(format!("gear {}", format_modifier(gear)));
+    }
+    if parts.is_empty() {
+        return String::new();
+    }
+    format!("(base {}, {})", base, parts.join(", "))
+}
+
+/// Who the character is, their attributes, and the stats that follow from them
+fn stats_lines(world: &World, player: Entity) -> Vec<(String, Color)> {
+    let mut lines = Vec::new();
+    let names = world.read_storage::<Name>();
+    let classes = world.read_storage::<CharacterClass>();
+    let backgrounds = world.read_storage::<Background>();
+    let equipment = world.read_storage::<Equipment>();
+    let equipment = equipment.get(player);
+    let effects = world.read_storage::<StatusEffects>();
+    let modifiers = effects.get(player).map(|effects| effects.stat_modifiers()).unwrap_or_default();
+
+    let mut identity = Vec::new();
+    if let Some(name) = names.get(player) {
+        identity.push(name.name.clone());
+    }
+    if let Some(class) = classes.get(player) {
+        identity.push(class.class_type.name().to_string());
+    }
+    if let Some(background) = backgrounds.get(player) {
+        identity.push(background.background_type.name().to_string());
+    }
+    lines.push((identity.join(", "), Color::Yellow));
+    if let Some(background) = backgrounds.get(player) {
+        let perk = background.background_type.perk();
+        lines.push((Message::new("sheet.perk").arg("perk", perk.name()).arg("description", perk.description()).text(), Color::Cyan));
+    }
+    if let Some(contact) = world.read_storage::<Contact>().get(player) {
+        lines.push((Message::new("sheet.contact").arg("name", contact.name.as_str()).arg("role", contact.role.as_str()).text(), Color::Grey));
+    }
+    if let Some(piety) = world.read_storage::<Piety>().get(player) {
+        let devotion = Message::new("sheet.devotion")
+            .arg("god", piety.deity.name())
+            .arg("title", piety.deity.title())
+            .arg("piety", piety.piety)
+            .arg("standing", piety.standing());
+        lines.push((devotion.text(), piety.deity.color()));
+    }
+
+    if let Some(exp) = world.read_storage::<Experience>().get(player) {
+        let filled = ((exp.progress_percentage() / 10.0) as usize).min(10);
+        lines.push((format!("Level {}  Experience {}/{} [{}{}]",
+            exp.level, exp.current, exp.level_up_target, "=".repeat(filled), " ".repeat(10 - filled)), Color::White));
+    }
+
+    if let Some(attributes) = world.read_storage::<Attributes>().get(player) {
+        lines.push((String::new(), Color::White));
+        lines.push((tr("sheet.attributes"), Color::Yellow));
+        for attribute in AttributeType::all() {
+            let base = attributes.get_attribute(attribute);
+            let effects = modifiers.attribute(attribute);
+            let gear = gear_attribute_bonus(equipment, attribute);
+            let total = base + effects + gear;
+            lines.push((format!("  {} {:>2} ({})  {}", attribute_label(attribute), total,
+                format_modifier((total - 10) / 2), breakdown(base, effects, gear)).trim_end().to_string(), Color::White));
+        }
+        if attributes.unspent_points > 0 {
+            lines.push((Message::new("sheet.unspent_attributes").arg("points", attributes.unspent_points).text(), Color::Green));
+        }
+    }
+
+    lines.push((String::new(), Color::White));
+    lines.push((tr("sheet.derived"), Color::Yellow));
+    if let Some(stats) = world.read_storage::<CombatStats>().get(player) {
+        let gear = equipment.map(|equipment| &equipment.stat_cache);
+        let gear_power = gear.map_or(0, |gear| gear.attack_bonus + gear.damage_bonus);
+        let gear_defense = gear.map_or(0, |gear| gear.defense_bonus);
+        lines.push((format!("  HP {}/{}", stats.hp, stats.max_hp), Color::White));
+        lines.push((format!("  Power {} {}", stats.power + modifiers.power + gear_power,
+            breakdown(stats.power, modifiers.power, gear_power)).trim_end().to_string(), Color::White));
+        lines.push((format!("  Defense {} {}", stats.defense + modifiers.defense + gear_defense,
+            breakdown(stats.defense, modifiers.defense, gear_defense)).trim_end().to_string(), Color::White));
+        if let Some(gear) = gear.filter(|gear| gear.critical_chance_bonus != 0) {
+            lines.push((format!("  Critical chance {}%", format_modifier(gear.critical_chance_bonus)), Color::White));
         }
-        
-        // Draw instructions
-        terminal.draw_text_centered(height - 2, "Press Esc to return to game", Color::Grey, Color::Black)?;
-        
-        terminal.flush()
-    });
+    }
+    if let Some(resources) = world.read_storage::<PlayerResources>().get(player) {
+        lines.push((format!("  Mana {}/{}  Stamina {}/{}",
+            resources.mana, resources.max_mana, resources.stamina, resources.max_stamina), Color::White));
+    }
+    if modifiers.speed != 0 {
+        lines.push((format!("  Speed {}", format_modifier(modifiers.speed)), Color::White));
+    }
+    lines
+}
+
+/// Each skill's level as a bar out of the most it can be trained
+fn skills_lines(world: &World, player: Entity) -> Vec<(String, Color)> {
+    let skills = world.read_storage::<Skills>();
+    let skills = match skills.get(player) {
+        Some(skills) => skills,
+        None => return vec![(tr("sheet.no_skills"), Color::DarkGrey)],
+    };
+    let mut lines = Vec::new();
+    if skills.unspent_skill_points > 0 {
+        lines.push((Message::new("sheet.unspent_skills").arg("points", skills.unspent_skill_points).text(), Color::Green));
+    }
+    for skill in SkillType::all() {
+        let level = skills.get_skill_level(skill).clamp(0, MAX_SKILL_LEVEL);
+        let bar = format!("[{}{}]", "#".repeat(level as usize), "-".repeat((MAX_SKILL_LEVEL - level) as usize));
+        let color = if level > 0 { Color::White } else { Color::DarkGrey };
+        lines.push((format!("{:<16} {} {}/{}", skill.name(), bar, level, MAX_SKILL_LEVEL), color));
+    }
+    lines
+}
+
+/// Costs and cooldown as shown beside each ability
+fn ability_costs(ability: AbilityType) -> String {
+    format!("MP {} SP {} CD {}", ability.get_mana_cost(), ability.get_stamina_cost(), ability.cooldown())
+}
+
+fn class_abilities(world: &World, player: Entity) -> Vec<AbilityType> {
+    world.read_storage::<CharacterClass>().get(player)
+        .map_or(Vec::new(), |class| AbilityType::get_class_abilities(class.class_type))
+}
+
+/// Learned abilities in the order the page lists them: the class's own first, then the rest by name
+fn learned_abilities(world: &World, player: Entity) -> Vec<AbilityType> {
+    let abilities = world.read_storage::<Abilities>();
+    let class_abilities = class_abilities(world, player);
+    let mut learned: Vec<AbilityType> = Vec::new();
+    if let Some(abilities) = abilities.get(player) {
+        learned.extend(class_abilities.iter().copied().filter(|ability| abilities.has_ability(*ability)));
+        let mut others: Vec<AbilityType> = abilities.abilities.iter().copied()
+            .filter(|ability| !class_abilities.contains(ability))
+            .collect();
+        others.sort_by_key(|ability| ability.name());
+        learned.extend(others);
+    }
+    learned
+}
+
+/// Abilities already learned, when they are ready and which hotbar slot they sit in, then those
+/// the class learns later
+fn abilities_lines(world: &World, player: Entity, cursor: usize) -> Vec<(String, Color)> {
+    let abilities = world.read_storage::<Abilities>();
+    let hotbars = world.read_storage::<Hotbar>();
+    let learned = learned_abilities(world, player);
+
+    let mut lines = vec![(tr("sheet.learned"), Color::Yellow)];
+    if learned.is_empty() {
+        lines.push((format!("  {}", tr("sheet.none_learned")), Color::DarkGrey));
+    }
+    for (i, ability) in learned.iter().enumerate() {
+        let marker = if i == cursor { '>' } else { ' ' };
+        let slot = hotbars.get(player).and_then(|hotbar| hotbar.slot_of(&HotbarEntry::Ability(*ability)))
+            .map_or("   ".to_string(), |slot| format!("[{}]", slot + 1));
+        let cooldown = abilities.get(player).map_or(0, |abilities| abilities.get_cooldown(*ability));
+        let (status, color) = if cooldown > 0 {
+            (Message::new("sheet.cooling_down").arg("turns", cooldown).text(), Color::DarkGrey)
+        } else {
+            (tr("sheet.ready"), Color::Green)
+        };
+        lines.push((format!("{} {} {:<18} {:<12} {}", marker, slot, ability.name(), status, ability_costs(*ability)), color));
+        lines.push((format!("      {}", ability.description()), Color::DarkGrey));
+    }
+
+    let mut learnable: Vec<AbilityType> = class_abilities(world, player).into_iter().filter(|ability| !learned.contains(ability)).collect();
+    learnable.sort_by_key(|ability| ability.required_level());
+    if !learnable.is_empty() {
+        lines.push((String::new(), Color::White));
+        lines.push((tr("sheet.learnable"), Color::Yellow));
+    }
+    for ability in learnable {
+        let level = Message::new("sheet.at_level").arg("level", ability.required_level()).text();
+        lines.push((format!("      {:<18} {:<12} {}", ability.name(), level, ability_costs(ability)), Color::Grey));
+        lines.push((format!("      {}", ability.description()), Color::DarkGrey));
+    }
+    lines
 }
 
-fn format_modifier(modifier: i32) -> String {
+/// The run's history, newest first
+fn history_lines(world: &World) -> Vec<(String, Color)> {
+    let history = world.read_resource::<PlayerHistorySystem>();
+    let events = history.get_recent_events(usize::MAX);
+    if events.is_empty() {
+        return vec![(tr("sheet.no_history"), Color::DarkGrey)];
+    }
+    let mut lines = Vec::new();
+    for event in events {
+        let color = match event.importance {
+            EventImportance::Legendary | EventImportance::Critical => Color::Magenta,
+            EventImportance::Major | EventImportance::Important => Color::Yellow,
+            _ => Color::White,
+        };
+        lines.push((event.title.clone(), color));
+        let detail = match &event.location {
+            Some(location) => format!("  {} ({})", event.description, location),
+            None => format!("  {}", event.description),
+        };
+        lines.push((detail, Color::DarkGrey));
+    }
+    lines
+}
+
+/// How each faction sees the character, and what that decides, after what the town watch wants
+/// of them if anything
+fn reputation_lines(world: &World) -> Vec<(String, Color)> {
+    let reputation = world.read_resource::<Reputation>();
+    let watch = world.read_resource::<TownWatch>();
+    let mut lines = Vec::new();
+    if watch.is_wanted() {
+        lines.push((Message::new("sheet.wanted").arg("gold", watch.fine).text(), Color::Red));
+    }
+    for faction in Faction::all() {
+        let standing = reputation.standing(faction);
+        let line = Message::new("sheet.standing")
+            .arg("faction", faction.name())
+            .arg("standing", standing.name())
+            .arg("score", format_modifier(reputation.score(faction)));
+        lines.push((line.text(), standing.color()));
+        lines.push((format!("  {}", faction.description()), Color::DarkGrey));
+    }
+    lines
+}
+
+/// A modifier with its sign, "+2" or "-1"
+pub fn format_modifier(modifier: i32) -> String {
     if modifier >= 0 {
         format!("+{}", modifier)
     } else {
@@ -159,83 +487,108 @@ fn format_modifier(modifier: i32) -> String {
     }
 }
 
-pub fn render_level_up_screen(world: &World, player_entity: Entity) {
-    let _ = with_terminal(|terminal| {
-        // Clear the screen
-        terminal.clear()?;
-        
-        // Get terminal size
-        let (width, height) = terminal.size();
-        
-        // Calculate center position
-        let center_x = width / 2;
-        
-        // Draw title
-        terminal.draw_text_centered(2, "LEVEL UP!", Color::Yellow, Color::Black)?;
-        
-        // Get player components
-        let names = world.read_storage::<Name>();
-        let classes = world.read_storage::<CharacterClass>();
-        let experiences = world.read_storage::<Experience>();
-        let attributes = world.read_storage::<Attributes>();
-        
-        // Draw character info
-        if let Some(name) = names.get(player_entity) {
-            terminal.draw_text(2, 4, &format!("Name: {}", name.name), Color::White, Color::Black)?;
-        }
-        
-        if let Some(class) = classes.get(player_entity) {
-            terminal.draw_text(2, 5, &format!("Class: {}", class.class_type.name()), Color::White, Color::Black)?;
-        }
-        
-        if let Some(exp) = experiences.get(player_entity) {
-            terminal.draw_text(2, 6, &format!("New Level: {}", exp.level), Color::White, Color::Black)?;
-            terminal.draw_text(2, 7, &format!("Unspent Points: {}", exp.unspent_points), Color::Green, Color::Black)?;
-        }
-        
-        // Draw attributes
-        if let Some(attr) = attributes.get(player_entity) {
-            terminal.draw_text(2, 9, "Attributes:", Color::Yellow, Color::Black)?;
-            terminal.draw_text(4, 10, &format!("STR: {} ({})", attr.strength, 
-                format_modifier(attr.get_modifier(AttributeType::Strength))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 11, &format!("DEX: {} ({})", attr.dexterity, 
-                format_modifier(attr.get_modifier(AttributeType::Dexterity))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 12, &format!("CON: {} ({})", attr.constitution, 
-                format_modifier(attr.get_modifier(AttributeType::Constitution))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 13, &format!("INT: {} ({})", attr.intelligence, 
-                format_modifier(attr.get_modifier(AttributeType::Intelligence))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 14, &format!("WIS: {} ({})", attr.wisdom, 
-                format_modifier(attr.get_modifier(AttributeType::Wisdom))), Color::White, Color::Black)?;
-            terminal.draw_text(4, 15, &format!("CHA: {} ({})", attr.charisma, 
-                format_modifier(attr.get_modifier(AttributeType::Charisma))), Color::White, Color::Black)?;
-        }
-        
-        // Draw instructions
-        terminal.draw_text_centered(18, "Allocate your attribute points:", Color::Yellow, Color::Black)?;
-        terminal.draw_text_centered(19, "1-6: Increase attribute (STR, DEX, CON, INT, WIS, CHA)", Color::White, Color::Black)?;
-        terminal.draw_text_centered(20, "Enter: Continue when done", Color::White, Color::Black)?;
-        
-        // Draw new abilities
-        if let (Some(exp), Some(class)) = (experiences.get(player_entity), classes.get(player_entity)) {
-            let class_abilities = AbilityType::get_class_abilities(class.class_type);
-            
-            let mut new_abilities = Vec::new();
-            for &ability_type in &class_abilities {
-                if ability_type.required_level() == exp.level {
-                    new_abilities.push(ability_type);
-                }
-            }
-            
-            if !new_abilities.is_empty() {
-                terminal.draw_text_centered(22, "New Abilities Unlocked:", Color::Yellow, Color::Black)?;
-                
-                for (i, &ability) in new_abilities.iter().enumerate() {
-                    terminal.draw_text_centered(24 + i as u16, &format!("{}: {}", ability.name(), ability.description()), 
-                        Color::Green, Color::Black)?;
-                }
-            }
-        }
-        
-        terminal.flush()
-    });
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use std::collections::HashMap;
+
+    fn setup() -> (World, Entity) {
+        let mut world = World::new();
+        world.register::<Name>();
+        world.register::<CharacterClass>();
+        world.register::<Background>();
+        world.register::<Experience>();
+        world.register::<Attributes>();
+        world.register::<CombatStats>();
+        world.register::<Skills>();
+        world.register::<Abilities>();
+        world.register::<Hotbar>();
+        world.register::<StatusEffects>();
+        world.register::<PlayerResources>();
+        world.register::<Equipment>();
+        world.insert(PlayerHistorySystem::default());
+        world.insert(Reputation::default());
+        world.insert(TownWatch::default());
+
+        let mut skills = Skills::new();
+        skills.skills.insert(SkillType::MeleeWeapons, 3);
+        let mut abilities = Abilities::new();
+        abilities.add_ability(AbilityType::PowerAttack);
+        abilities.set_cooldown(AbilityType::PowerAttack, 2);
+        let player = world.create_entity()
+            .with(Name { name: "Ada".to_string() })
+            .with(CharacterClass { class_type: ClassType::Fighter })
+            .with(Experience::new())
+            .with(Attributes::from_scores([14, 12, 13, 8, 10, 9]))
+            .with(CombatStats { max_hp: 20, hp: 15, defense: 2, power: 5 })
+            .with(skills)
+            .with(abilities)
+            .build();
+        (world, player)
+    }
+
+    #[test]
+    fn test_tabs_show_the_players_own_stats_skills_abilities_history_and_reputation() {
+        let (world, player) = setup();
+        let mut sheet = CharacterSheet::new();
+        sheet.open(player);
+        let lines = |sheet: &CharacterSheet| -> Vec<String> {
+            sheet.tab_lines(&world).into_iter().map(|(line, _)| line).collect()
+        };
+
+        let stats = lines(&sheet);
+        assert_eq!(stats[0], "Ada, Fighter");
+        assert!(stats.contains(&"  STR 14 (+2)".to_string()));
+        assert!(stats.contains(&"  Power 5".to_string()));
+
+        sheet.handle_key(KeyCode::Char('2'), &world);
+        assert!(lines(&sheet).iter().any(|line| line.starts_with("Melee Weapons") && line.ends_with("[###--] 3/5")));
+
+        sheet.handle_key(KeyCode::Tab, &world);
+        let abilities = lines(&sheet);
+        assert!(abilities[1].contains("Power Attack") && abilities[1].contains("2 turns left") && abilities[1].ends_with("MP 5 SP 8 CD 3"));
+        assert!(abilities.iter().any(|line| line.contains("Cleave") && line.contains("Level 3")));
+
+        sheet.handle_key(KeyCode::Tab, &world);
+        assert_eq!(sheet.tab, CharacterSheetTab::History);
+        assert_eq!(lines(&sheet), vec![tr("sheet.no_history")]);
+        world.write_resource::<PlayerHistorySystem>().log_level_up(2, "Dungeon 1", HashMap::new(), HashMap::new());
+        assert_eq!(lines(&sheet)[0], "Reached Level 2");
+
+        sheet.handle_key(KeyCode::Tab, &world);
+        assert_eq!(sheet.tab, CharacterSheetTab::Reputation);
+        world.write_resource::<Reputation>().adjust(Faction::Guild, 20);
+        assert!(lines(&sheet).contains(&"Adventurers' Guild: Liked (+20)".to_string()));
+        world.write_resource::<TownWatch>().fine = 60;
+        assert_eq!(lines(&sheet)[0], "Wanted in Ravenhollow: the watch wants 60 gold");
+
+        // Tab wraps back round to the first page, and Esc closes the sheet
+        sheet.handle_key(KeyCode::Tab, &world);
+        assert_eq!(sheet.tab, CharacterSheetTab::Stats);
+        sheet.handle_key(KeyCode::Esc, &world);
+        assert!(!sheet.is_open());
+    }
+
+    #[test]
+    fn test_number_keys_on_the_abilities_page_fill_the_hotbar() {
+        let (mut world, player) = setup();
+        world.insert(crate::resources::GameLog::new(10));
+        world.write_storage::<Abilities>().get_mut(player).unwrap().add_ability(AbilityType::Cleave);
+        let mut sheet = CharacterSheet::new();
+        sheet.open(player);
+        sheet.handle_key(KeyCode::Char('3'), &world);
+        assert_eq!(sheet.tab, CharacterSheetTab::Abilities);
+
+        // Digits here pick slots rather than pages
+        sheet.handle_key(KeyCode::Down, &world);
+        sheet.handle_key(KeyCode::Char('4'), &world);
+        assert_eq!(sheet.tab, CharacterSheetTab::Abilities);
+        let hotbar = world.read_storage::<Hotbar>().get(player).cloned().unwrap();
+        assert_eq!(hotbar.get(3), Some(&HotbarEntry::Ability(AbilityType::Cleave)));
+        assert!(sheet.tab_lines(&world)[3].starts_with("> [4] Cleave"));
+
+        sheet.handle_key(KeyCode::Delete, &world);
+        assert_eq!(world.read_storage::<Hotbar>().get(player).unwrap().get(3), None);
+    }
+}
@@ -1,198 +1,219 @@
-use specs::{World, WorldExt, Entity, Join};
-use crossterm::style::Color;
-use crate::components::{DeathState, GameSettings, Name, RevivalItem, Inventory, GameMode};
-use crate::rendering::terminal::with_terminal;
-
-pub fn show_death_screen(world: &World, player_entity: Entity) -> Option<DeathAction> {
-    let death_states = world.read_storage::<DeathState>();
-    let game_settings = world.read_storage::<GameSettings>();
-    let names = world.read_storage::<Name>();
-    let revival_items = world.read_storage::<RevivalItem>();
-    let inventories = world.read_storage::<Inventory>();
-    
-    let death_state = death_states.get(player_entity)?;
-    let player_name = names.get(player_entity)?.name.clone();
-    let settings = game_settings.get(player_entity);
-    let inventory = inventories.get(player_entity);
-    
-    if !death_state.is_dead {
-        return None;
+use specs::{World, WorldExt, Entity};
+use crossterm::{event::KeyCode, style::Color};
+use crate::components::{DeathState, DeathPenalty, GameSettings, GameMode, Name, RevivalItem};
+use crate::items::AdvancedInventory;
+use crate::localization::{tr, Message};
+use crate::systems::carried_revival_items;
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// A way out of the revival screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathAction {
+    /// Spend one of the run's revivals and take the death penalty
+    ReviveWithPenalty,
+    UseRevivalItem(Entity),
+    GiveUp,
+}
+
+/// The prompt shown when the player falls with some way back still open
+#[derive(Debug, Clone)]
+pub struct RevivalScreen {
+    player_entity: Option<Entity>,
+    pub selected: usize,
+    /// Giving up was picked and waits for a Y
+    pub confirming_give_up: bool,
+}
+
+impl RevivalScreen {
+    pub fn new() -> Self {
+        RevivalScreen {
+            player_entity: None,
+            selected: 0,
+            confirming_give_up: false,
+        }
     }
-    
-    // Find available revival items
-    let mut revival_options = Vec::new();
-    if let Some(inv) = inventory {
-        for &item_entity in &inv.items {
-            if let Some(revival_item) = revival_items.get(item_entity) {
-                if !revival_item.auto_use {
-                    let item_name = names.get(item_entity).map_or("Unknown Item".to_string(), |n| n.name.clone());
-                    revival_options.push((item_entity, item_name, revival_item.revival_power));
-                }
+
+    pub fn open(&mut self, player_entity: Entity) {
+        self.player_entity = Some(player_entity);
+        self.selected = 0;
+        self.confirming_give_up = false;
+    }
+
+    pub fn close(&mut self) {
+        self.player_entity = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.player_entity.is_some()
+    }
+
+    /// What the player can do, in the order listed: a revival if any are left, each revival item
+    /// the game mode allows, and giving up
+    pub fn choices(&self, world: &World) -> Vec<DeathAction> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        let mut choices = Vec::new();
+        if world.read_storage::<DeathState>().get(player).map_or(false, |state| state.can_revive()) {
+            choices.push(DeathAction::ReviveWithPenalty);
+        }
+        if game_mode(world, player).allows_revival_items() {
+            let inventories = world.read_storage::<AdvancedInventory>();
+            for (item, _) in carried_revival_items(inventories.get(player), &world.read_storage::<RevivalItem>()) {
+                choices.push(DeathAction::UseRevivalItem(item));
             }
         }
+        choices.push(DeathAction::GiveUp);
+        choices
     }
-    
-    let mut selected_option = 0;
-    let mut result = None;
-    
-    let _ = with_terminal(|terminal| {
-        // Clear the screen
-        terminal.clear()?;
-        
-        // Get terminal size
-        let (width, height) = terminal.size();
-        
-        // Calculate center position
-        let center_x = width / 2;
-        let center_y = height / 2;
-        
-        // Draw death screen border
-        for x in center_x - 30..center_x + 30 {
-            terminal.draw_text(x, center_y - 15, "═", Color::Red, Color::Black)?;
-            terminal.draw_text(x, center_y + 15, "═", Color::Red, Color::Black)?;
-        }
-        for y in center_y - 15..center_y + 15 {
-            terminal.draw_text(center_x - 30, y, "║", Color::Red, Color::Black)?;
-            terminal.draw_text(center_x + 30, y, "║", Color::Red, Color::Black)?;
+
+    /// Move the selection, or return the choice made
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) -> Option<DeathAction> {
+        if self.confirming_give_up {
+            self.confirming_give_up = false;
+            return match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Some(DeathAction::GiveUp),
+                _ => None,
+            };
         }
-        
-        // Draw corners
-        terminal.draw_text(center_x - 30, center_y - 15, "╔", Color::Red, Color::Black)?;
-        terminal.draw_text(center_x + 30, center_y - 15, "╗", Color::Red, Color::Black)?;
-        terminal.draw_text(center_x - 30, center_y + 15, "╚", Color::Red, Color::Black)?;
-        terminal.draw_text(center_x + 30, center_y + 15, "╝", Color::Red, Color::Black)?;
-        
-        // Draw death message
-        terminal.draw_text_centered(center_y - 12, "YOU HAVE DIED", Color::Red, Color::Black)?;
-        terminal.draw_text_centered(center_y - 10, &format!("{} has fallen!", player_name), Color::White, Color::Black)?;
-        terminal.draw_text_centered(center_y - 8, &format!("Cause: {}", death_state.death_cause), Color::Grey, Color::Black)?;
-        
-        // Draw game mode info
-        if let Some(settings) = settings {
-            terminal.draw_text_centered(center_y - 6, &format!("Game Mode: {}", settings.game_mode.name()), Color::Yellow, Color::Black)?;
-            
-            match settings.game_mode {
-                GameMode::Permadeath => {
-                    terminal.draw_text_centered(center_y - 4, "PERMADEATH - GAME OVER", Color::Red, Color::Black)?;
-                    terminal.draw_text_centered(center_y + 10, "Press any key to return to main menu", Color::Grey, Color::Black)?;
-                    return Ok(());
-                },
-                _ => {
-                    terminal.draw_text_centered(center_y - 4, 
-                        &format!("Revival attempts: {}/{}", death_state.revival_attempts, death_state.max_revival_attempts), 
-                        Color::Cyan, Color::Black)?;
+        let choices = self.choices(world);
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(choices.len().saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                match choices.get(self.selected) {
+                    Some(DeathAction::GiveUp) => self.confirming_give_up = true,
+                    Some(choice) => return Some(*choice),
+                    None => {},
                 }
-            }
+            },
+            _ => {},
         }
-        
-        // Draw revival options
-        if death_state.can_revive() {
-            terminal.draw_text_centered(center_y - 2, "Revival Options:", Color::Green, Color::Black)?;
-            
-            let mut option_index = 0;
-            
-            // Option to revive with penalty
-            let color = if selected_option == option_index { Color::Yellow } else { Color::White };
-            if selected_option == option_index {
-                terminal.draw_text(center_x - 25, center_y + option_index as u16, "→", Color::Yellow, Color::Black)?;
-            }
-            terminal.draw_text(center_x - 23, center_y + option_index as u16, "Revive with penalty (25% HP)", color, Color::Black)?;
-            option_index += 1;
-            
-            // Revival item options
-            for (i, (_, item_name, revival_power)) in revival_options.iter().enumerate() {
-                let color = if selected_option == option_index { Color::Yellow } else { Color::Green };
-                if selected_option == option_index {
-                    terminal.draw_text(center_x - 25, center_y + option_index as u16, "→", Color::Yellow, Color::Black)?;
-                }
-                terminal.draw_text(center_x - 23, center_y + option_index as u16, 
-                    &format!("Use {} (Restore {} HP)", item_name, revival_power), color, Color::Black)?;
-                option_index += 1;
-            }
-            
-            // Option to give up
-            let color = if selected_option == option_index { Color::Yellow } else { Color::Red };
-            if selected_option == option_index {
-                terminal.draw_text(center_x - 25, center_y + option_index as u16, "→", Color::Yellow, Color::Black)?;
-            }
-            terminal.draw_text(center_x - 23, center_y + option_index as u16, "Give up (Game Over)", color, Color::Black)?;
-            
-        } else {
-            terminal.draw_text_centered(center_y + 2, "No revival attempts remaining", Color::Red, Color::Black)?;
-            terminal.draw_text_centered(center_y + 4, "GAME OVER", Color::Red, Color::Black)?;
+        None
+    }
+
+    fn choice_label(world: &World, choice: DeathAction) -> String {
+        match choice {
+            DeathAction::ReviveWithPenalty => tr("revival.revive"),
+            DeathAction::UseRevivalItem(item) => {
+                let name = world.read_storage::<Name>().get(item).map_or("?".to_string(), |name| name.name.clone());
+                let power = world.read_storage::<RevivalItem>().get(item).map_or(0, |item| item.revival_power);
+                Message::new("revival.use_item").arg("item", name).arg("hp", power).text()
+            },
+            DeathAction::GiveUp => tr("revival.give_up"),
         }
-        
-        // Draw instructions
-        if death_state.can_revive() {
-            terminal.draw_text_centered(center_y + 12, "Up/Down to select, Enter to confirm", Color::Grey, Color::Black)?;
-        } else {
-            terminal.draw_text_centered(center_y + 12, "Press any key to continue", Color::Grey, Color::Black)?;
+    }
+
+    /// The highlighted choice, for the screen reader
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        if self.confirming_give_up {
+            return Some(tr("revival.confirm_give_up"));
         }
-        
-        // Flush the output
-        terminal.flush()
-    });
-    
-    result
-}
+        self.choices(world).get(self.selected).map(|choice| RevivalScreen::choice_label(world, *choice))
+    }
 
-pub fn show_revival_confirmation(world: &World, player_entity: Entity, action: &DeathAction) -> bool {
-    let names = world.read_storage::<Name>();
-    let player_name = names.get(player_entity).map_or("Player".to_string(), |n| n.name.clone());
-    
-    let _ = with_terminal(|terminal| {
-        // Clear the screen
-        terminal.clear()?;
-        
-        // Get terminal size
-        let (width, height) = terminal.size();
-        
-        // Calculate center position
-        let center_x = width / 2;
-        let center_y = height / 2;
-        
-        // Draw confirmation message
-        terminal.draw_text_centered(center_y - 5, "REVIVAL CONFIRMATION", Color::Yellow, Color::Black)?;
-        
-        match action {
-            DeathAction::ReviveWithPenalty => {
-                terminal.draw_text_centered(center_y - 2, &format!("{} will be revived with:", player_name), Color::White, Color::Black)?;
-                terminal.draw_text_centered(center_y, "• 25% HP restored", Color::Green, Color::Black)?;
-                terminal.draw_text_centered(center_y + 1, "• Experience penalty applied", Color::Red, Color::Black)?;
-                terminal.draw_text_centered(center_y + 2, "• Temporary stat reduction", Color::Red, Color::Black)?;
-            },
-            DeathAction::UseRevivalItem(item_entity) => {
-                let revival_items = world.read_storage::<crate::components::RevivalItem>();
-                if let Some(revival_item) = revival_items.get(*item_entity) {
-                    let item_name = names.get(*item_entity).map_or("Revival Item".to_string(), |n| n.name.clone());
-                    terminal.draw_text_centered(center_y - 2, &format!("Use {} to revive {}:", item_name, player_name), Color::White, Color::Black)?;
-                    terminal.draw_text_centered(center_y, &format!("• {} HP will be restored", revival_item.revival_power), Color::Green, Color::Black)?;
-                    if revival_item.consumed_on_use {
-                        terminal.draw_text_centered(center_y + 1, "• Item will be consumed", Color::Yellow, Color::Black)?;
-                    }
-                }
-            },
-            DeathAction::GiveUp => {
-                terminal.draw_text_centered(center_y - 2, "Are you sure you want to give up?", Color::Red, Color::Black)?;
-                terminal.draw_text_centered(center_y, "This will end your game!", Color::Red, Color::Black)?;
-            },
-            DeathAction::GameOver => {
-                return Ok(());
-            }
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        let choices = self.choices(world);
+        let width = 60.min(screen_width - 4);
+        let height = 14 + choices.len() as i32;
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(tr("revival.title"), x, y, width, height)
+            .with_colors(Color::Red, Color::Black, Color::Red)
+            .render();
+        let line = |commands: &mut Vec<UIRenderCommand>, row: i32, text: String, fg: Color| {
+            let text: String = text.chars().take((width - 4).max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        let name = world.read_storage::<Name>().get(player).map_or("?".to_string(), |name| name.name.clone());
+        let death_states = world.read_storage::<DeathState>();
+        let state = death_states.get(player);
+        let cause = state.map_or("?".to_string(), |state| state.death_cause.clone());
+        let revivals_left = state
+            .filter(|state| state.max_revival_attempts != i32::MAX)
+            .map(|state| state.max_revival_attempts - state.revival_attempts);
+        line(&mut commands, 2, Message::new("revival.slain").arg("name", name).arg("cause", cause).text(), Color::White);
+        let mode = game_mode(world, player);
+        line(&mut commands, 3, Message::new("revival.mode").arg("mode", mode.name()).text(), Color::Yellow);
+        let revivals = match revivals_left {
+            Some(left) => Message::new("revival.revivals_left").arg("left", left).text(),
+            None => tr("revival.revivals_unlimited"),
+        };
+        line(&mut commands, 4, revivals, Color::Cyan);
+        if let Some(penalty) = world.read_storage::<DeathPenalty>().get(player) {
+            let text = Message::new("revival.penalty")
+                .arg("xp", penalty.experience_loss_percentage)
+                .arg("turns", penalty.penalty_duration)
+                .text();
+            line(&mut commands, 6, text, Color::Grey);
+        }
+
+        for (i, choice) in choices.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = match (i == self.selected, choice) {
+                (true, _) => Color::Yellow,
+                (false, DeathAction::GiveUp) => Color::Red,
+                (false, DeathAction::UseRevivalItem(_)) => Color::Green,
+                (false, DeathAction::ReviveWithPenalty) => Color::White,
+            };
+            line(&mut commands, 8 + i as i32, format!("{} {}", marker, RevivalScreen::choice_label(world, *choice)), fg);
         }
-        
-        terminal.draw_text_centered(center_y + 5, "Press Y to confirm, N to cancel", Color::Grey, Color::Black)?;
-        
-        // Flush the output
-        terminal.flush()
-    });
-    
-    false // This would be determined by user input in a real implementation
+
+        let footer = if self.confirming_give_up { tr("revival.confirm_give_up") } else { tr("revival.controls") };
+        line(&mut commands, height - 2, footer, if self.confirming_give_up { Color::Red } else { Color::DarkGrey });
+        commands
+    }
 }
 
-pub enum DeathAction {
-    ReviveWithPenalty,
-    UseRevivalItem(Entity),
-    GiveUp,
-    GameOver,
-}
\ No newline at end of file
+fn game_mode(world: &World, player: Entity) -> GameMode {
+    world.read_storage::<GameSettings>().get(player).map_or(GameMode::Normal, |settings| settings.game_mode.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use crate::components::register_components;
+    use crate::items::InventorySlot;
+
+    #[test]
+    fn test_choices_follow_the_revivals_left_and_giving_up_asks_first() {
+        let mut world = World::new();
+        register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        let feather = world.create_entity()
+            .with(Name { name: "Phoenix Feather".to_string() })
+            .with(RevivalItem { revival_power: 30, auto_use: false, consumed_on_use: true })
+            .build();
+        let mut inventory = AdvancedInventory::new(26, 100.0);
+        inventory.items.push(InventorySlot::new(feather, 1));
+        let mut state = DeathState::for_mode(&GameMode::Hardcore);
+        state.kill("a goblin".to_string(), 10);
+        let player = world.create_entity()
+            .with(GameSettings::new(GameMode::Hardcore))
+            .with(state)
+            .with(inventory)
+            .build();
+
+        let mut screen = RevivalScreen::new();
+        screen.open(player);
+        assert_eq!(screen.choices(&world), vec![DeathAction::ReviveWithPenalty, DeathAction::UseRevivalItem(feather), DeathAction::GiveUp]);
+        assert_eq!(screen.handle_key(KeyCode::Enter, &world), Some(DeathAction::ReviveWithPenalty));
+
+        // With the one Hardcore revival spent only the feather and giving up are left
+        world.write_storage::<DeathState>().get_mut(player).unwrap().revival_attempts = 1;
+        assert_eq!(screen.choices(&world), vec![DeathAction::UseRevivalItem(feather), DeathAction::GiveUp]);
+        screen.handle_key(KeyCode::Down, &world);
+        assert_eq!(screen.handle_key(KeyCode::Enter, &world), None);
+        assert!(screen.confirming_give_up);
+        assert_eq!(screen.handle_key(KeyCode::Char('n'), &world), None);
+        screen.handle_key(KeyCode::Enter, &world);
+        assert_eq!(screen.handle_key(KeyCode::Char('y'), &world), Some(DeathAction::GiveUp));
+    }
+}
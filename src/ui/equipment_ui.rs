@@ -2,6 +2,7 @@ use specs::{World, WorldExt, Entity, Join};
 use crossterm::style::Color;
 use crate::components::{Name, Equipped, Inventory, EquipmentSlot, Equippable, MeleePowerBonus, DefenseBonus};
 use crate::rendering::terminal::with_terminal;
+use crate::localization;
 
 pub fn show_equipment_screen(world: &World, player_entity: Entity) -> Option<EquipmentAction> {
     // Get player's equipped items
@@ -21,7 +22,7 @@ pub fn show_equipment_screen(world: &World, player_entity: Entity) -> Option<Equ
     for &item_entity in &player_inventory.items {
         if let Some(equipped) = equipped_items.get(item_entity) {
             if equipped.owner == player_entity {
-                let name = names.get(item_entity).map_or("Unknown Item".to_string(), |name| name.name.clone());
+                let name = names.get(item_entity).map_or("Unknown Item".to_string(), |name| localization::name(&name.name));
                 
                 // Get item bonuses
                 let power_bonus = melee_bonuses.get(item_entity).map(|b| b.power).unwrap_or(0);
@@ -139,7 +140,7 @@ pub fn show_equippable_items(world: &World, player_entity: Entity, slot: Equipme
     for &item_entity in &player_inventory.items {
         if let Some(equippable) = equippables.get(item_entity) {
             if equippable.slot == slot {
-                let name = names.get(item_entity).map_or("Unknown Item".to_string(), |name| name.name.clone());
+                let name = names.get(item_entity).map_or("Unknown Item".to_string(), |name| localization::name(&name.name));
                 let is_equipped = equipped_items.get(item_entity).is_some();
                 equippable_items.push((item_entity, name, is_equipped));
             }
@@ -0,0 +1,197 @@
+use specs::{World, WorldExt, Entity};
+use crossterm::{event::KeyCode, style::Color};
+use crate::components::{Furniture, FurnitureKind};
+use crate::items::{AdvancedInventory, Equipment, ItemProperties};
+use crate::localization::{tr, Message};
+use crate::resources::GameStateResource;
+use crate::systems::offerings;
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// What to do with a piece of furniture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FurnitureChoice {
+    /// Drink, pull or read
+    Use,
+    /// Leave an item on a shrine
+    Sacrifice(Entity),
+    Leave,
+}
+
+/// The menu shown when the player bumps into a fountain, shrine, lever or bookshelf
+#[derive(Debug, Clone)]
+pub struct FurnitureMenu {
+    player_entity: Option<Entity>,
+    furniture: Option<Entity>,
+    pub selected: usize,
+}
+
+impl FurnitureMenu {
+    pub fn new() -> Self {
+        FurnitureMenu {
+            player_entity: None,
+            furniture: None,
+            selected: 0,
+        }
+    }
+
+    pub fn open(&mut self, player_entity: Entity, furniture: Entity) {
+        self.player_entity = Some(player_entity);
+        self.furniture = Some(furniture);
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.player_entity = None;
+        self.furniture = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.player_entity.is_some()
+    }
+
+    /// The furniture the menu is about
+    pub fn furniture(&self) -> Option<Entity> {
+        self.furniture
+    }
+
+    /// Whatever the furniture allows right now, always ending with walking away
+    pub fn choices(&self, world: &World) -> Vec<FurnitureChoice> {
+        let (player, furniture) = match (self.player_entity, self.furniture) {
+            (Some(player), Some(furniture)) => (player, furniture),
+            _ => return Vec::new(),
+        };
+        let turn = world.read_resource::<GameStateResource>().turn_count;
+        let mut choices = Vec::new();
+        match world.read_storage::<Furniture>().get(furniture) {
+            Some(piece) if piece.is_ready(turn) && piece.kind == FurnitureKind::Shrine => {
+                let inventories = world.read_storage::<AdvancedInventory>();
+                let equipment = world.read_storage::<Equipment>();
+                choices.extend(offerings(inventories.get(player), equipment.get(player))
+                    .into_iter()
+                    .map(FurnitureChoice::Sacrifice));
+            },
+            Some(piece) if piece.is_ready(turn) => choices.push(FurnitureChoice::Use),
+            _ => {},
+        }
+        choices.push(FurnitureChoice::Leave);
+        choices
+    }
+
+    /// Move the selection, or return the choice made. Escape walks away
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) -> Option<FurnitureChoice> {
+        let choices = self.choices(world);
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(choices.len().saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char(' ') => match choices.get(self.selected).copied() {
+                Some(FurnitureChoice::Leave) => self.close(),
+                choice => return choice,
+            },
+            KeyCode::Esc => self.close(),
+            _ => {},
+        }
+        None
+    }
+
+    fn choice_label(&self, choice: FurnitureChoice, world: &World) -> String {
+        match choice {
+            FurnitureChoice::Use => {
+                let kind = self.furniture.and_then(|furniture| world.read_storage::<Furniture>().get(furniture).map(|piece| piece.kind));
+                match kind {
+                    Some(FurnitureKind::Fountain) => tr("furniture.drink"),
+                    Some(FurnitureKind::Lever) => tr("furniture.pull"),
+                    _ => tr("furniture.read"),
+                }
+            },
+            FurnitureChoice::Sacrifice(item) => {
+                let properties = world.read_storage::<ItemProperties>();
+                let (name, value) = properties.get(item).map_or(("?".to_string(), 0), |props| (props.name.clone(), props.value));
+                Message::new("furniture.sacrifice").arg("item", name).arg("value", value).text()
+            },
+            FurnitureChoice::Leave => tr("furniture.leave"),
+        }
+    }
+
+    /// Why the furniture can't be used yet, if it can't
+    fn status(&self, world: &World) -> Option<String> {
+        let furniture = self.furniture?;
+        let turn = world.read_resource::<GameStateResource>().turn_count;
+        let pieces = world.read_storage::<Furniture>();
+        let piece = pieces.get(furniture)?;
+        if piece.is_ready(turn) {
+            if piece.kind == FurnitureKind::Shrine && self.choices(world).len() == 1 {
+                return Some(tr("furniture.no_offerings"));
+            }
+            return None;
+        }
+        Some(match piece.turns_until_ready(turn) {
+            Some(turns) => Message::new("furniture.not_ready").arg("turns", turns).text(),
+            None => tr("furniture.spent"),
+        })
+    }
+
+    /// The highlighted choice, for the screen reader
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        self.choices(world).get(self.selected).map(|choice| self.choice_label(*choice, world))
+    }
+
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let kind = match self.furniture.and_then(|furniture| world.read_storage::<Furniture>().get(furniture).map(|piece| piece.kind)) {
+            Some(kind) => kind,
+            None => return Vec::new(),
+        };
+        let choices = self.choices(world);
+        let width = 50.min(screen_width - 4);
+        let height = 8 + choices.len() as i32;
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(kind.name().to_string(), x, y, width, height)
+            .with_colors(kind.color(), Color::Black, kind.color())
+            .render();
+        let line = |commands: &mut Vec<UIRenderCommand>, row: i32, text: String, fg: Color| {
+            let text: String = text.chars().take((width - 4).max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        if let Some(status) = self.status(world) {
+            line(&mut commands, 2, status, Color::DarkGrey);
+        }
+        for (i, choice) in choices.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = if i == self.selected { Color::Yellow } else { Color::White };
+            line(&mut commands, 4 + i as i32, format!("{} {}", marker, self.choice_label(*choice, world)), fg);
+        }
+
+        line(&mut commands, height - 2, tr("furniture.controls"), Color::DarkGrey);
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use crate::components::register_components;
+
+    #[test]
+    fn test_spent_furniture_only_offers_to_leave() {
+        let mut world = World::new();
+        register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<Equipment>();
+        world.register::<ItemProperties>();
+        world.insert(GameStateResource::default());
+        let player = world.create_entity().build();
+        let lever = world.create_entity().with(Furniture::lever((4, 4))).build();
+        let mut menu = FurnitureMenu::new();
+
+        menu.open(player, lever);
+        assert_eq!(menu.choices(&world), vec![FurnitureChoice::Use, FurnitureChoice::Leave]);
+        assert_eq!(menu.handle_key(KeyCode::Enter, &world), Some(FurnitureChoice::Use));
+
+        world.write_storage::<Furniture>().get_mut(lever).unwrap().mark_used(0);
+        assert_eq!(menu.choices(&world), vec![FurnitureChoice::Leave]);
+        assert_eq!(menu.handle_key(KeyCode::Enter, &world), None);
+        assert!(!menu.is_open());
+    }
+}
@@ -0,0 +1,301 @@
+use crossterm::{event::KeyCode, style::Color};
+use crate::guild::{CraftingService, GuildRoster, HallFacility, SUPPLY_CRATE_COST, SUPPLY_CRATE_SIZE};
+use crate::localization::{tr, Message};
+use crate::progression::WorldEvents;
+use crate::ui::{UIPanel, UIRenderCommand};
+use crate::ui::bestiary_screen::wrap;
+
+/// What the player asks of the guild from its hall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HallAction {
+    BuySupplies,
+    Build(HallFacility),
+    Craft(CraftingService),
+    /// Go through to the agents
+    Agents,
+    Close,
+}
+
+/// One line of the hall's menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HallEntry {
+    Supplies,
+    Facility(HallFacility),
+    Service(CraftingService),
+}
+
+/// Every line of the menu, in the order shown
+fn entries() -> Vec<HallEntry> {
+    let mut entries = vec![HallEntry::Supplies];
+    entries.extend(HallFacility::all().into_iter().map(HallEntry::Facility));
+    entries.extend(CraftingService::all().into_iter().map(HallEntry::Service));
+    entries
+}
+
+/// The guild at a glance: its renown, supplies and reagents, how its agents are doing and the
+/// next milestone to make, over a menu for buying supplies, building onto the hall and having
+/// the alchemist brew something, with what is going on in the world and what has gone on before
+/// under the highlighted entry. Building asks first, since the rooms don't come cheap
+#[derive(Debug, Clone)]
+pub struct GuildOverview {
+    pub selected: usize,
+    /// The room the player has picked, waiting on them to agree to the price
+    pub confirming: Option<HallFacility>,
+}
+
+impl GuildOverview {
+    pub fn new() -> Self {
+        GuildOverview { selected: 0, confirming: None }
+    }
+
+    pub fn open(&mut self) {
+        *self = GuildOverview::new();
+    }
+
+    /// Move the selection, or return what the player asked for
+    pub fn handle_key(&mut self, key: KeyCode) -> Option<HallAction> {
+        if let Some(facility) = self.confirming.take() {
+            return match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Some(HallAction::Build(facility)),
+                _ => None,
+            };
+        }
+        let entries = entries();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(entries.len() - 1),
+            KeyCode::Enter | KeyCode::Char(' ') => match entries[self.selected] {
+                HallEntry::Supplies => return Some(HallAction::BuySupplies),
+                HallEntry::Facility(facility) => self.confirming = Some(facility),
+                HallEntry::Service(service) => return Some(HallAction::Craft(service)),
+            },
+            KeyCode::Char('a') | KeyCode::Char('A') => return Some(HallAction::Agents),
+            KeyCode::Esc | KeyCode::Char('q') => return Some(HallAction::Close),
+            _ => {},
+        }
+        None
+    }
+
+    /// The guild's standing, a line at a time, for the top of the screen
+    fn standing(roster: &GuildRoster, purse: i32) -> Vec<(String, Color)> {
+        let stores = &roster.stores;
+        let away = roster.agents.iter().filter(|agent| agent.assignment.is_some()).count();
+        let mending = roster.agents.iter().filter(|agent| agent.assignment.is_none() && agent.recovery_days > 0).count();
+        let ready = roster.agents.len() - away - mending;
+        let milestone = match stores.next_milestone() {
+            Some(milestone) => Message::new("guild.next_milestone").arg("milestone", milestone.name()).arg("renown", milestone.renown()).text(),
+            None => tr("guild.all_milestones"),
+        };
+        vec![
+            (Message::new("guild.summary")
+                .arg("renown", stores.renown)
+                .arg("supplies", stores.supplies)
+                .arg("reagents", stores.reagents)
+                .arg("gold", purse)
+                .text(), Color::Cyan),
+            (Message::new("guild.agents")
+                .arg("ready", ready)
+                .arg("away", away)
+                .arg("mending", mending)
+                .arg("done", stores.contracts_done)
+                .text(), Color::White),
+            (milestone, Color::Magenta),
+        ]
+    }
+
+    /// An entry as listed down the left, in a color for whether it can be had
+    fn label(entry: HallEntry, roster: &GuildRoster) -> (String, Color) {
+        let stores = &roster.stores;
+        match entry {
+            HallEntry::Supplies => (tr("guild.buy_supplies"), Color::White),
+            HallEntry::Facility(facility) if stores.has(facility) => (facility.name().to_string(), Color::Green),
+            HallEntry::Facility(facility) if stores.renown < facility.renown_required() => (facility.name().to_string(), Color::DarkGrey),
+            HallEntry::Facility(facility) => (facility.name().to_string(), Color::White),
+            HallEntry::Service(service) if stores.can_craft(service).is_ok() => (service.name().to_string(), Color::White),
+            HallEntry::Service(service) => (service.name().to_string(), Color::DarkGrey),
+        }
+    }
+
+    /// Everything about an entry, one line at a time
+    fn describe(entry: HallEntry, roster: &GuildRoster) -> Vec<(String, Color)> {
+        let stores = &roster.stores;
+        match entry {
+            HallEntry::Supplies => vec![
+                (tr("guild.buy_supplies"), Color::Yellow),
+                (Message::new("guild.supplies_offer").arg("count", SUPPLY_CRATE_SIZE).arg("gold", SUPPLY_CRATE_COST).text(), Color::White),
+                (tr("guild.supplies_use"), Color::DarkGrey),
+            ],
+            HallEntry::Facility(facility) => {
+                let status = if stores.has(facility) {
+                    (tr("guild.built"), Color::Green)
+                } else if stores.renown < facility.renown_required() {
+                    (Message::new("guild.needs_renown").arg("renown", facility.renown_required()).text(), Color::DarkGrey)
+                } else {
+                    (tr("guild.can_build"), Color::White)
+                };
+                vec![
+                    (facility.name().to_string(), Color::Yellow),
+                    (tr(facility.description_key()), Color::White),
+                    (Message::new("guild.facility_cost").arg("gold", facility.cost()).arg("renown", facility.renown_required()).text(), Color::White),
+                    status,
+                ]
+            },
+            HallEntry::Service(service) => {
+                let mut lines = vec![
+                    (service.name().to_string(), Color::Yellow),
+                    (Message::new("guild.service_cost").arg("reagents", service.reagents()).text(), Color::White),
+                ];
+                if let Some(facility) = service.facility() {
+                    let fg = if stores.has(facility) { Color::Green } else { Color::DarkGrey };
+                    lines.push((Message::new("guild.service_needs").arg("facility", facility.name()).text(), fg));
+                }
+                lines
+            },
+        }
+    }
+
+    /// What is going on in the world and the latest of what has gone on, a line at a time
+    fn abroad(world: &WorldEvents, rows: usize) -> Vec<(String, Color)> {
+        let mut lines = vec![(tr("guild.abroad"), Color::Cyan)];
+        if world.active.is_empty() {
+            lines.push((tr("guild.all_quiet"), Color::DarkGrey));
+        }
+        for event in &world.active {
+            lines.push((Message::new("guild.event_active").arg("event", event.kind.name()).arg("days", event.days_left).text(), Color::Yellow));
+        }
+        let room = rows.saturating_sub(lines.len());
+        for entry in world.timeline.iter().rev().take(room) {
+            lines.push((Message::new("guild.event_past")
+                .arg("run", entry.run)
+                .arg("day", entry.day)
+                .arg("event", entry.kind.name())
+                .text(), Color::DarkGrey));
+        }
+        lines
+    }
+
+    /// What the player is asked before building
+    fn confirm_prompt(&self) -> Option<String> {
+        let facility = self.confirming?;
+        Some(Message::new("guild.confirm_build").arg("facility", facility.name()).arg("gold", facility.cost()).text())
+    }
+
+    /// The highlighted line, for the screen reader
+    pub fn selected_line(&self, roster: &GuildRoster) -> Option<String> {
+        if let Some(prompt) = self.confirm_prompt() {
+            return Some(prompt);
+        }
+        let entry = *entries().get(self.selected)?;
+        Some(GuildOverview::describe(entry, roster).into_iter().map(|(line, _)| line).collect::<Vec<_>>().join(". "))
+    }
+
+    pub fn render(&self, roster: &GuildRoster, world: &WorldEvents, purse: i32, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let width = 76.min(screen_width - 2);
+        let height = 24.min(screen_height - 2);
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(tr("guild.title"), x, y, width, height)
+            .with_colors(Color::White, Color::Black, Color::Yellow)
+            .render();
+        let mut text = |column: i32, row: i32, text: String, max: i32, fg: Color| {
+            let text: String = text.chars().take(max.max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + column, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        let mut row = 1;
+        for (line, fg) in GuildOverview::standing(roster, purse) {
+            text(2, row, line, width - 4, fg);
+            row += 1;
+        }
+
+        // The menu down the left, under a heading for each kind of thing on offer
+        let list_width = 26;
+        row += 1;
+        let mut heading = None;
+        for (i, entry) in entries().into_iter().enumerate() {
+            let kind = match entry {
+                HallEntry::Supplies => "guild.stores",
+                HallEntry::Facility(_) => "guild.facilities",
+                HallEntry::Service(_) => "guild.services",
+            };
+            if heading != Some(kind) {
+                if heading.is_some() {
+                    row += 1;
+                }
+                text(2, row, tr(kind), list_width, Color::Cyan);
+                row += 1;
+                heading = Some(kind);
+            }
+            let (label, fg) = GuildOverview::label(entry, roster);
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = if i == self.selected { Color::Yellow } else { fg };
+            text(2, row, format!("{} {}", marker, label), list_width, fg);
+            row += 1;
+        }
+
+        // The highlighted entry on the right
+        let detail_x = 2 + list_width + 2;
+        let detail_width = (width - detail_x - 2).max(1);
+        let mut row = 5;
+        if let Some(entry) = entries().get(self.selected) {
+            for (line, fg) in GuildOverview::describe(*entry, roster) {
+                for line in wrap(&line, detail_width as usize) {
+                    text(detail_x, row, line, detail_width, fg);
+                    row += 1;
+                }
+            }
+        }
+
+        // The world abroad under it, as far as the room left allows
+        row += 1;
+        let rows = (height - 3 - row).max(0) as usize;
+        for (line, fg) in GuildOverview::abroad(world, rows).into_iter().take(rows) {
+            text(detail_x, row, line, detail_width, fg);
+            row += 1;
+        }
+
+        match self.confirm_prompt() {
+            Some(prompt) => text(2, height - 2, prompt, width - 4, Color::Yellow),
+            None => text(2, height - 2, tr("guild.controls"), width - 4, Color::DarkGrey),
+        }
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supplies_sell_at_once_and_rooms_ask_first() {
+        let mut hall = GuildOverview::new();
+        assert_eq!(hall.handle_key(KeyCode::Enter), Some(HallAction::BuySupplies));
+
+        hall.handle_key(KeyCode::Down);
+        assert_eq!(hall.handle_key(KeyCode::Enter), None);
+        assert!(hall.selected_line(&GuildRoster::default()).unwrap().contains("Alchemy laboratory"));
+        assert_eq!(hall.handle_key(KeyCode::Char('y')), Some(HallAction::Build(HallFacility::Laboratory)));
+
+        for _ in 0..10 {
+            hall.handle_key(KeyCode::Down);
+        }
+        assert_eq!(hall.handle_key(KeyCode::Enter), Some(HallAction::Craft(CraftingService::Regeneration)));
+        assert_eq!(hall.handle_key(KeyCode::Char('a')), Some(HallAction::Agents));
+    }
+
+    #[test]
+    fn test_abroad_lists_what_is_going_on_then_the_latest_news() {
+        use crate::progression::{TimelineEntry, WorldEvent, WorldEventKind};
+        let mut world = WorldEvents::default();
+        assert_eq!(GuildOverview::abroad(&world, 5)[1].0, tr("guild.all_quiet"));
+
+        world.active.push(WorldEvent { kind: WorldEventKind::GoblinRaid, days_left: 2 });
+        for day in 1..=6 {
+            world.timeline.push(TimelineEntry { kind: WorldEventKind::MerchantCaravan, run: 1, day });
+        }
+        let lines = GuildOverview::abroad(&world, 5);
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].0.contains("Goblin raid"));
+        assert!(lines[2].0.contains('6'));
+    }
+}
@@ -1,9 +1,11 @@
 use crossterm::{event::KeyCode, style::Color};
 use specs::{World, Entity};
 use std::collections::HashMap;
+use crate::localization::tr;
 use crate::ui::{
     ui_components::{UIComponent, UIRenderCommand, UIPanel, UIText, TextAlignment},
     menu_system::{MenuRenderer, MenuInput},
+    backend::{UiBackend, UiWidget, UiRect},
 };
 
 /// Help system state
@@ -50,6 +52,24 @@ pub enum TutorialTrigger {
     Custom(String),
 }
 
+/// Tutorial triggers published since the help system last looked, each at most once
+#[derive(Debug, Clone, Default)]
+pub struct TutorialEvents {
+    pub triggers: Vec<TutorialTrigger>,
+}
+
+impl TutorialEvents {
+    pub fn publish(&mut self, trigger: TutorialTrigger) {
+        if !self.triggers.contains(&trigger) {
+            self.triggers.push(trigger);
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<TutorialTrigger> {
+        std::mem::take(&mut self.triggers)
+    }
+}
+
 /// Context-sensitive help topics
 #[derive(Debug, Clone, PartialEq)]
 pub enum HelpContext {
@@ -77,6 +97,16 @@ pub struct HelpSection {
     pub examples: Vec<String>,
 }
 
+/// Topics on the main help screen and what each covers, as message keys
+const HELP_TOPICS: [(&str, &str); 6] = [
+    ("help.controls", "help.controls_summary"),
+    ("help.mechanics", "help.mechanics_summary"),
+    ("help.combat", "help.combat_summary"),
+    ("help.items", "help.items_summary"),
+    ("help.magic", "help.magic_summary"),
+    ("help.tutorial", "help.tutorial_summary"),
+];
+
 /// Main help and tutorial system
 pub struct HelpSystem {
     pub state: HelpSystemState,
@@ -152,6 +182,16 @@ impl HelpSystem {
         None
     }
 
+    /// The popup for the step `trigger` starts, marked done so it is only ever shown once
+    pub fn tutorial_popup(&mut self, trigger: TutorialTrigger) -> Option<TutorialMessage> {
+        self.trigger_tutorial(trigger)?;
+        let step_id = self.current_tutorial.clone()?;
+        let step = self.tutorial_steps.get(&step_id)?;
+        let popup = TutorialMessage::new(step.title.clone(), step.content.clone());
+        self.complete_tutorial_step(&step_id);
+        Some(popup)
+    }
+
     pub fn complete_tutorial_step(&mut self, step_id: &str) {
         if let Some(step) = self.tutorial_steps.get_mut(step_id) {
             step.completed = true;
@@ -654,7 +694,7 @@ impl HelpSystem {
                     ],
                     examples: vec![
                         "Tutorial messages appear at the bottom of screen".to_string(),
-                        "Press any key to dismiss tutorial messages".to_string(),
+                        "Press Enter to dismiss tutorial messages".to_string(),
                         "F1 opens context-sensitive help".to_string(),
                     ],
                 },
@@ -780,6 +820,69 @@ impl HelpSystem {
         }
     }
 
+    /// Draw through a widget backend: topics as a table, topic pages as scrollable text
+    pub fn draw(&self, backend: &mut dyn UiBackend) -> Result<(), Box<dyn std::error::Error>> {
+        let (width, height) = backend.size();
+        let panel = UiRect::new(2, 2, width.saturating_sub(4), height.saturating_sub(4));
+        let topic_key = match self.state {
+            HelpSystemState::MainHelp => {
+                backend.draw(UiWidget::Table {
+                    area: panel,
+                    title: tr("help.title"),
+                    header: vec![tr("help.topic"), tr("help.description")],
+                    rows: HELP_TOPICS.iter().map(|(topic, description)| vec![tr(topic), tr(description)]).collect(),
+                    widths: vec![20, panel.width.saturating_sub(23)],
+                    selected: Some(self.selected_topic),
+                    offset: 0,
+                })?;
+                return Ok(());
+            }
+            HelpSystemState::Controls => "controls",
+            HelpSystemState::GameMechanics => "game_mechanics",
+            HelpSystemState::Combat => "combat",
+            HelpSystemState::Items => "items",
+            HelpSystemState::Magic => "magic",
+            _ => return backend.draw(UiWidget::Commands(self.render(width as i32, height as i32))),
+        };
+        let content = match self.help_content.get(topic_key) {
+            Some(content) => content,
+            None => return Ok(()),
+        };
+
+        let wrap_width = panel.width.saturating_sub(6) as usize;
+        let mut lines = Vec::new();
+        for section in &content.sections {
+            lines.push(section.title.clone());
+            for content_line in &section.content {
+                lines.extend(self.wrap_text(content_line, wrap_width).into_iter().map(|line| format!("  {}", line)));
+            }
+            if !section.examples.is_empty() {
+                lines.push("  Examples:".to_string());
+                lines.extend(section.examples.iter().map(|example| format!("    • {}", example)));
+            }
+            lines.push(String::new());
+        }
+        if !content.related_topics.is_empty() {
+            lines.push("Related Topics:".to_string());
+            lines.extend(content.related_topics.iter().map(|related| format!("  • {}", related.replace("_", " "))));
+        }
+
+        let total = lines.len();
+        let visible = panel.inner().height as usize;
+        backend.draw(UiWidget::Paragraph {
+            area: panel,
+            title: content.title.clone(),
+            lines: lines.into_iter().skip(self.scroll_offset).collect(),
+            color: Color::White,
+        })?;
+        backend.draw(UiWidget::Scrollbar {
+            area: UiRect::new(panel.x + panel.width.saturating_sub(1), panel.y + 1, 1, visible as u16),
+            position: self.scroll_offset,
+            total,
+            visible,
+        })
+    }
+
     fn render_main_help(&self, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
         let mut commands = Vec::new();
 
@@ -787,7 +890,7 @@ impl HelpSystem {
         let panel_width = screen_width - 4;
         let panel_height = screen_height - 4;
         let panel = UIPanel::new(
-            "Help System".to_string(),
+            tr("help.title"),
             2,
             2,
             panel_width,
@@ -797,20 +900,13 @@ impl HelpSystem {
         commands.extend(panel.render());
 
         // Help topics
-        let topics = vec![
-            ("Controls", "Learn the basic game controls"),
-            ("Game Mechanics", "Understand how the game works"),
-            ("Combat", "Master the combat system"),
-            ("Items & Equipment", "Manage your inventory and gear"),
-            ("Magic System", "Learn about spells and magic"),
-            ("Tutorial", "Replay tutorial messages"),
-        ];
+        let topics = HELP_TOPICS;
 
         let start_y = 5;
         commands.push(UIRenderCommand::DrawText {
             x: 4,
             y: start_y,
-            text: "Select a help topic:".to_string(),
+            text: tr("help.select_topic"),
             fg: Color::Cyan,
             bg: Color::Black,
         });
@@ -828,7 +924,7 @@ impl HelpSystem {
             commands.push(UIRenderCommand::DrawText {
                 x: 4,
                 y,
-                text: format!("{:<20} - {}", topic, description),
+                text: format!("{:<20} - {}", tr(topic), tr(description)),
                 fg,
                 bg,
             });
@@ -839,7 +935,7 @@ impl HelpSystem {
         commands.push(UIRenderCommand::DrawText {
             x: 4,
             y: nav_y,
-            text: "Navigation: ↑↓ Select, Enter: Open Topic, Tab: Next Section, ESC: Close".to_string(),
+            text: tr("help.navigation"),
             fg: Color::DarkGrey,
             bg: Color::Black,
         });
@@ -1288,7 +1384,7 @@ impl TutorialMessage {
             commands.push(UIRenderCommand::DrawText {
                 x: panel_x + 2,
                 y: panel_y + panel_height - 2,
-                text: "Press any key to continue...".to_string(),
+                text: tr("tutorial.dismiss"),
                 fg: Color::Yellow,
                 bg: Color::DarkBlue,
             });
@@ -1413,6 +1509,25 @@ mod tests {
         assert!(message2.is_none());
     }
 
+    #[test]
+    fn test_published_triggers_become_popups_once() {
+        let mut events = TutorialEvents::default();
+        events.publish(TutorialTrigger::FirstMovement);
+        events.publish(TutorialTrigger::FirstMovement);
+        events.publish(TutorialTrigger::FirstItemPickup);
+        let triggers = events.take();
+        assert_eq!(triggers, vec![TutorialTrigger::FirstMovement, TutorialTrigger::FirstItemPickup]);
+        assert!(events.take().is_empty());
+
+        let mut help_system = HelpSystem::new();
+        let popup = help_system.tutorial_popup(TutorialTrigger::FirstMovement).unwrap();
+        assert_eq!(popup.title, "Great! You're moving!");
+        assert!(help_system.tutorial_popup(TutorialTrigger::FirstMovement).is_none());
+
+        help_system.tutorial_enabled = false;
+        assert!(help_system.tutorial_popup(TutorialTrigger::FirstItemPickup).is_none());
+    }
+
     #[test]
     fn test_tutorial_completion() {
         let mut help_system = HelpSystem::new();
@@ -0,0 +1,221 @@
+use crossterm::style::Color;
+use specs::{World, WorldExt, Entity};
+use crate::components::*;
+use crate::items::{AdvancedInventory, ItemProperties, ItemType, WantsToUseConsumable};
+use crate::localization::{self, Message};
+use crate::systems::ability_area;
+use crate::ui::UIRenderCommand;
+
+/// Rows the hotbar takes along the bottom of the map: names above, costs and cooldowns below
+pub const HOTBAR_ROWS: u16 = 2;
+
+/// What firing a hotbar slot leaves for the game to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotbarAction {
+    /// The ability or item is under way
+    Used,
+    /// The ability lands where the player aims it
+    Aim(AbilityType),
+}
+
+/// Put an ability or consumable on a slot, giving the player a hotbar if they had none
+pub fn assign_hotbar_slot(world: &World, player: Entity, slot: usize, entry: HotbarEntry) {
+    if slot >= HOTBAR_SLOTS {
+        return;
+    }
+    let label = entry_label(&entry);
+    let mut hotbars = world.write_storage::<Hotbar>();
+    if !hotbars.contains(player) {
+        hotbars.insert(player, Hotbar::new()).expect("Unable to insert hotbar");
+    }
+    if let Some(hotbar) = hotbars.get_mut(player) {
+        hotbar.assign(slot, entry);
+    }
+    world.write_resource::<crate::resources::GameLog>()
+        .add_message(Message::new("log.hotbar_assigned").arg("name", label).arg("slot", slot + 1));
+}
+
+/// Fire the slot under number key `slot + 1`, or say why it can't be
+pub fn activate_hotbar_slot(world: &World, player: Entity, slot: usize) -> Result<HotbarAction, String> {
+    let entry = world.read_storage::<Hotbar>().get(player).and_then(|hotbar| hotbar.get(slot).cloned())
+        .ok_or_else(|| Message::new("hotbar.empty").arg("slot", slot + 1).text())?;
+    match entry {
+        HotbarEntry::Ability(ability) => {
+            if let Some(problem) = ability_problem(world, player, ability) {
+                return Err(problem);
+            }
+            if matches!(ability_area(ability), Some((template, _)) if template.needs_target()) {
+                return Ok(HotbarAction::Aim(ability));
+            }
+            // Anything else finds its own target, or needs none
+            world.write_storage::<WantsToUseAbility>()
+                .insert(player, WantsToUseAbility {
+                    ability,
+                    target: None,
+                    mana_cost: ability.get_mana_cost(),
+                    stamina_cost: ability.get_stamina_cost(),
+                })
+                .expect("Unable to insert ability intent");
+            Ok(HotbarAction::Used)
+        },
+        HotbarEntry::Item(name) => {
+            let item = carried(world, player, &name).into_iter().next()
+                .ok_or_else(|| Message::new("hotbar.none_left").arg("item", localization::name(&name)).text())?;
+            world.write_storage::<WantsToUseConsumable>()
+                .insert(player, WantsToUseConsumable { item, target: None })
+                .expect("Unable to insert use intent");
+            Ok(HotbarAction::Used)
+        },
+    }
+}
+
+/// Why the player can't use `ability` right now, if they can't
+fn ability_problem(world: &World, player: Entity, ability: AbilityType) -> Option<String> {
+    let abilities = world.read_storage::<Abilities>();
+    let resources = world.read_storage::<PlayerResources>();
+    crate::systems::ability_problem(ability, abilities.get(player), resources.get(player), ability.get_mana_cost(), ability.get_stamina_cost())
+        .map(|problem| problem.text())
+}
+
+fn affordable(world: &World, player: Entity, ability: AbilityType) -> bool {
+    world.read_storage::<PlayerResources>().get(player).map_or(true, |resources| {
+        resources.mana >= ability.get_mana_cost() && resources.stamina >= ability.get_stamina_cost()
+    })
+}
+
+/// Consumables in the player's pack going by `name`, with how many each stack holds
+fn carried_stacks(world: &World, player: Entity, name: &str) -> Vec<(Entity, i32)> {
+    let inventories = world.read_storage::<AdvancedInventory>();
+    let properties = world.read_storage::<ItemProperties>();
+    inventories.get(player).map_or(Vec::new(), |inventory| {
+        inventory.items.iter()
+            .filter(|slot| properties.get(slot.entity).map_or(false, |props| {
+                props.name == name && matches!(props.item_type, ItemType::Consumable(_))
+            }))
+            .map(|slot| (slot.entity, slot.quantity))
+            .collect()
+    })
+}
+
+fn carried(world: &World, player: Entity, name: &str) -> Vec<Entity> {
+    carried_stacks(world, player, name).into_iter().map(|(item, _)| item).collect()
+}
+
+fn entry_label(entry: &HotbarEntry) -> String {
+    match entry {
+        HotbarEntry::Ability(ability) => ability.name().to_string(),
+        HotbarEntry::Item(name) => localization::name(name),
+    }
+}
+
+/// A slot's name line and its cost, cooldown or count line, with the color they are drawn in
+fn slot_lines(world: &World, player: Entity, entry: Option<&HotbarEntry>) -> (String, String, Color) {
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return (String::new(), "-".to_string(), Color::DarkGrey),
+    };
+    match entry {
+        HotbarEntry::Ability(ability) => {
+            let cooldown = world.read_storage::<Abilities>().get(player).map_or(0, |abilities| abilities.get_cooldown(*ability));
+            let costs = match (ability.get_mana_cost(), ability.get_stamina_cost()) {
+                (0, 0) => String::new(),
+                (mana, 0) => format!("{}MP", mana),
+                (0, stamina) => format!("{}SP", stamina),
+                (mana, stamina) => format!("{}MP {}SP", mana, stamina),
+            };
+            if cooldown > 0 {
+                (entry_label(entry), format!("CD {}", cooldown), Color::DarkGrey)
+            } else if !affordable(world, player, *ability) {
+                (entry_label(entry), costs, Color::Red)
+            } else {
+                (entry_label(entry), costs, Color::White)
+            }
+        },
+        HotbarEntry::Item(name) => {
+            let count: i32 = carried_stacks(world, player, name).iter().map(|(_, quantity)| *quantity).sum();
+            let color = if count > 0 { Color::White } else { Color::DarkGrey };
+            (entry_label(entry), format!("x{}", count), color)
+        },
+    }
+}
+
+/// The hotbar's nine slots across the bottom `HOTBAR_ROWS` of a map `width` columns wide
+pub fn render_hotbar(world: &World, player: Entity, width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+    let hotbars = world.read_storage::<Hotbar>();
+    let hotbar = hotbars.get(player);
+    let cell_width = (width / HOTBAR_SLOTS as i32).max(4);
+    let top = screen_height - HOTBAR_ROWS as i32;
+    let fit = |text: String| -> String {
+        let text: String = text.chars().take((cell_width - 1) as usize).collect();
+        format!("{:<width$}", text, width = cell_width as usize)
+    };
+
+    let mut commands = Vec::new();
+    for slot in 0..HOTBAR_SLOTS {
+        let x = slot as i32 * cell_width;
+        if x + cell_width > width {
+            break;
+        }
+        let (name, detail, color) = slot_lines(world, player, hotbar.and_then(|hotbar| hotbar.get(slot)));
+        commands.push(UIRenderCommand::DrawText {
+            x,
+            y: top,
+            text: fit(format!("{} {}", slot + 1, name)),
+            fg: color,
+            bg: Color::Black,
+        });
+        commands.push(UIRenderCommand::DrawText {
+            x,
+            y: top + 1,
+            text: fit(format!("  {}", detail)),
+            fg: if color == Color::White { Color::Cyan } else { color },
+            bg: Color::Black,
+        });
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use crate::items::{AdvancedInventory, InventorySlot, ConsumableType};
+    use crate::resources::GameLog;
+
+    #[test]
+    fn test_slots_fire_abilities_and_the_first_stack_of_a_consumable() {
+        let mut world = World::new();
+        register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.register::<ItemProperties>();
+        world.register::<WantsToUseConsumable>();
+        world.insert(GameLog::new(10));
+
+        let potion = world.create_entity()
+            .with(ItemProperties::new("Healing Potion".to_string(), ItemType::Consumable(ConsumableType::Potion)))
+            .build();
+        let mut inventory = AdvancedInventory::new(26, 100.0);
+        inventory.items.push(InventorySlot::new(potion, 3));
+        let mut abilities = Abilities::starting(ClassType::Fighter);
+        abilities.add_ability(AbilityType::Cleave);
+        let player = world.create_entity()
+            .with(Hotbar::for_class(ClassType::Fighter, &abilities))
+            .with(abilities)
+            .with(inventory)
+            .build();
+
+        assert_eq!(activate_hotbar_slot(&world, player, 0), Ok(HotbarAction::Used));
+        assert_eq!(world.read_storage::<WantsToUseAbility>().get(player).map(|intent| intent.ability), Some(AbilityType::PowerAttack));
+        assert!(activate_hotbar_slot(&world, player, 4).is_err());
+
+        // A cooling-down ability is refused before it costs a turn
+        world.write_storage::<Abilities>().get_mut(player).unwrap().set_cooldown(AbilityType::Cleave, 2);
+        assert_eq!(activate_hotbar_slot(&world, player, 1).map_err(|_| ()), Err(()));
+
+        assign_hotbar_slot(&world, player, 4, HotbarEntry::Item("Healing Potion".to_string()));
+        assign_hotbar_slot(&world, player, 8, HotbarEntry::Item("Healing Potion".to_string()));
+        assert_eq!(world.read_storage::<Hotbar>().get(player).unwrap().get(4), None);
+        assert_eq!(activate_hotbar_slot(&world, player, 8), Ok(HotbarAction::Used));
+        assert_eq!(world.read_storage::<WantsToUseConsumable>().get(player).map(|intent| intent.item), Some(potion));
+    }
+}
@@ -1,11 +1,12 @@
 use crossterm::style::Color;
 use specs::{World, Entity, Join, ReadStorage, WorldExt};
-use crate::components::{Player, Position, CombatStats, Name, Viewshed};
-use crate::items::{Equipment, StatusEffects};
+use crate::components::{Player, Position, CombatStats, Name, Viewshed, StatusEffects, PlayerResources, Experience};
+use crate::items::Equipment;
 use crate::map::Map;
 use crate::resources::GameLog;
 use crate::ui::{
-    ui_components::{UIComponent, UIRenderCommand, UIPanel, UIText, TextAlignment},
+    ui_components::{UIComponent, UIRenderCommand, UIPanel, UIText, UIBar, TextAlignment},
+    status_icons::StatusIconRegistry,
 };
 
 /// In-game HUD component that displays player status, minimap, and messages
@@ -16,6 +17,8 @@ pub struct GameHUD {
     pub message_log_size: usize,
     pub hud_height: i32,
     pub minimap_size: i32,
+    /// Phase of the blink shared by every bar that is running low
+    pub flash_on: bool,
 }
 
 impl GameHUD {
@@ -27,6 +30,7 @@ impl GameHUD {
             message_log_size: 5,
             hud_height: 8,
             minimap_size: 20,
+            flash_on: true,
         }
     }
 
@@ -95,22 +99,37 @@ impl GameHUD {
                 combat_stats.get(player_entity),
                 positions.get(player_entity),
             ) {
-                let mut status_lines = Vec::new();
+                let experience = world.read_storage::<Experience>();
+                let exp = experience.get(player_entity);
+                let level = exp.map_or(1, |exp| exp.level);
 
-                // Player name and level (placeholder level)
-                status_lines.push(format!("{} (Lvl 1)", name.name));
+                commands.push(UIRenderCommand::DrawText {
+                    x,
+                    y,
+                    text: format!("{:<width$}", format!("{} (Lvl {})", name.name, level), width = width as usize),
+                    fg: Color::White,
+                    bg: Color::Black,
+                });
 
-                // Health bar
-                let health_percentage = (stats.hp as f32 / stats.max_hp as f32 * 100.0) as i32;
-                let health_bar = self.create_bar(stats.hp, stats.max_hp, 20, '█', '░');
-                let health_color = if health_percentage > 75 {
-                    Color::Green
-                } else if health_percentage > 25 {
-                    Color::Yellow
-                } else {
-                    Color::Red
-                };
-                status_lines.push(format!("HP: {} {}/{}", health_bar, stats.hp, stats.max_hp));
+                // Segmented bars for each pool, colored by how full they are
+                let segments = (width - 12).clamp(5, 20);
+                let mut bars = vec![UIBar::new("HP".to_string(), stats.hp, stats.max_hp, x, 0, segments)];
+                if let Some(resources) = world.read_storage::<PlayerResources>().get(player_entity) {
+                    bars.push(UIBar::new("MP".to_string(), resources.mana, resources.max_mana, x, 0, segments)
+                        .with_fill_color(Color::Blue));
+                    bars.push(UIBar::new("SP".to_string(), resources.stamina, resources.max_stamina, x, 0, segments));
+                }
+                if let Some(exp) = exp {
+                    bars.push(UIBar::experience(exp.progress_percentage(), x, 0, segments));
+                }
+                let mut line_y = y + 1;
+                for mut bar in bars {
+                    bar.y = line_y;
+                    commands.extend(bar.with_flash(self.flash_on).render(0, 0, 0, 0));
+                    line_y += 1;
+                }
+
+                let mut status_lines = Vec::new();
 
                 // Combat stats
                 status_lines.push(format!("ATK: {}  DEF: {}", stats.power, stats.defense));
@@ -126,18 +145,17 @@ impl GameHUD {
                 }
 
                 // Render status lines
-                for (i, line) in status_lines.iter().enumerate() {
-                    let line_y = y + i as i32;
+                for line in status_lines.iter() {
                     if line_y < y + 6 { // Limit to available space
-                        let color = if i == 1 { health_color } else { Color::White };
                         commands.push(UIRenderCommand::DrawText {
                             x,
                             y: line_y,
                             text: format!("{:<width$}", line, width = width as usize),
-                            fg: color,
+                            fg: Color::White,
                             bg: Color::Black,
                         });
                     }
+                    line_y += 1;
                 }
             }
         } else {
@@ -290,37 +308,39 @@ impl GameHUD {
             let status_effects = world.read_storage::<StatusEffects>();
 
             if let Some(effects) = status_effects.get(player_entity) {
-                let mut effect_strings = Vec::new();
+                if effects.effects.is_empty() {
+                    return commands;
+                }
 
+                commands.push(UIRenderCommand::DrawText {
+                    x,
+                    y,
+                    text: "Effects:".to_string(),
+                    fg: Color::Magenta,
+                    bg: Color::Black,
+                });
+
+                // Each effect is drawn as its icon followed by the turns remaining
+                let registry = world.fetch::<StatusIconRegistry>();
+                let mut current_x = x + 9;
                 for effect in &effects.effects {
-                    let effect_str = format!("{}({})", effect.effect_type.name(), effect.duration);
-                    effect_strings.push((effect_str, effect.effect_type.color()));
-                }
+                    let icon = registry.icon_for(effect, self.flash_on);
+                    let effect_text = format!("{}{}", icon.glyph, effect.duration);
+                    let text_width = effect_text.chars().count() as i32;
 
-                if !effect_strings.is_empty() {
-                    // Render status effects in a single line
-                    let mut current_x = x;
-                    let mut effect_text = String::new();
-
-                    for (i, (effect_str, color)) in effect_strings.iter().enumerate() {
-                        if i > 0 {
-                            effect_text.push_str(" | ");
-                        }
-                        effect_text.push_str(effect_str);
-
-                        // Check if we have space
-                        if effect_text.len() > width as usize {
-                            break;
-                        }
+                    // Check if we have space
+                    if current_x + text_width > x + width {
+                        break;
                     }
 
                     commands.push(UIRenderCommand::DrawText {
                         x: current_x,
                         y,
-                        text: format!("Effects: {}", effect_text),
-                        fg: Color::Magenta,
+                        text: effect_text,
+                        fg: icon.color,
                         bg: Color::Black,
                     });
+                    current_x += text_width + 1;
                 }
             }
         }
@@ -406,6 +426,7 @@ impl HUDManager {
         if frame - self.last_update_frame >= self.update_frequency {
             // Update HUD state if needed
             self.last_update_frame = frame;
+            self.hud.flash_on = (frame / 8) % 2 == 0;
         }
     }
 
@@ -422,40 +443,6 @@ impl HUDManager {
     }
 }
 
-// Extension trait for status effect types to provide colors
-trait StatusEffectDisplay {
-    fn name(&self) -> &'static str;
-    fn color(&self) -> Color;
-}
-
-impl StatusEffectDisplay for crate::items::StatusEffectType {
-    fn name(&self) -> &'static str {
-        match self {
-            crate::items::StatusEffectType::Poison => "Poison",
-            crate::items::StatusEffectType::Regeneration => "Regen",
-            crate::items::StatusEffectType::Strength => "Str+",
-            crate::items::StatusEffectType::Weakness => "Weak",
-            crate::items::StatusEffectType::Speed => "Fast",
-            crate::items::StatusEffectType::Slow => "Slow",
-            crate::items::StatusEffectType::Protection => "Prot",
-            crate::items::StatusEffectType::Vulnerability => "Vuln",
-        }
-    }
-
-    fn color(&self) -> Color {
-        match self {
-            crate::items::StatusEffectType::Poison => Color::Green,
-            crate::items::StatusEffectType::Regeneration => Color::DarkGreen,
-            crate::items::StatusEffectType::Strength => Color::Red,
-            crate::items::StatusEffectType::Weakness => Color::DarkRed,
-            crate::items::StatusEffectType::Speed => Color::Yellow,
-            crate::items::StatusEffectType::Slow => Color::DarkYellow,
-            crate::items::StatusEffectType::Protection => Color::Blue,
-            crate::items::StatusEffectType::Vulnerability => Color::Magenta,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -471,7 +458,10 @@ mod tests {
         world.register::<Equipment>();
         world.register::<StatusEffects>();
         world.register::<Viewshed>();
+        world.register::<PlayerResources>();
+        world.register::<Experience>();
         world.insert(GameLog::new());
+        world.insert(StatusIconRegistry::default());
         world.insert(Map::new(80, 50, 1));
 
         let player = world.create_entity()
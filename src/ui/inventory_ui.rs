@@ -1,14 +1,18 @@
 use crossterm::{event::KeyCode, style::Color};
 use specs::{World, Entity, Join, ReadStorage, WorldExt};
 use std::collections::HashMap;
-use crate::components::{Player, Name, Position};
+use crate::components::{Player, Name, Position, Hotbar, HotbarEntry};
+use crate::localization::{self, tr, Message};
 use crate::items::{
-    ItemProperties, ItemType, ItemRarity, WeaponType, ArmorType, ConsumableType,
-    AdvancedInventory, InventorySlot, Equipment, Equippable, ItemBonuses
+    ItemProperties, ItemType, ItemRarity, ItemTag, WeaponType, ArmorType, ConsumableType,
+    AdvancedInventory, InventorySlot, Equipment, EquipmentSlot, ItemBonuses,
+    EquipmentComparison, StatDelta, compare_equipment
 };
 use crate::ui::{
     ui_components::{UIComponent, UIRenderCommand, UIPanel, UIText, TextAlignment},
     menu_system::{MenuRenderer, MenuInput},
+    backend::{UiBackend, UiWidget, UiRect},
+    hotbar::assign_hotbar_slot,
 };
 
 /// Inventory UI state
@@ -20,6 +24,8 @@ pub enum InventoryUIState {
     FilterMenu,
     SortMenu,
     ActionMenu,
+    /// How many of a stack to drop or split off
+    Quantity,
     Closed,
 }
 
@@ -30,6 +36,8 @@ pub enum InventoryAction {
     Equip,
     Unequip,
     Drop,
+    /// Hurl a potion or flask at a target
+    Throw,
     Examine,
     Compare,
     Split,
@@ -47,6 +55,7 @@ impl InventoryAction {
             InventoryAction::Equip => "Equip".to_string(),
             InventoryAction::Unequip => "Unequip".to_string(),
             InventoryAction::Drop => "Drop".to_string(),
+            InventoryAction::Throw => "Throw".to_string(),
             InventoryAction::Examine => "Examine".to_string(),
             InventoryAction::Compare => "Compare".to_string(),
             InventoryAction::Split => "Split Stack".to_string(),
@@ -63,10 +72,11 @@ impl InventoryAction {
             InventoryAction::Use => matches!(item_type, ItemType::Consumable(_)),
             InventoryAction::Equip => !is_equipped && matches!(item_type, ItemType::Weapon(_) | ItemType::Armor(_)),
             InventoryAction::Unequip => is_equipped,
-            InventoryAction::Drop => true,
+            InventoryAction::Drop => !is_equipped,
+            InventoryAction::Throw => matches!(item_type, ItemType::Consumable(ConsumableType::Potion)),
             InventoryAction::Examine => true,
             InventoryAction::Compare => matches!(item_type, ItemType::Weapon(_) | ItemType::Armor(_)),
-            InventoryAction::Split => false, // TODO: Implement stack splitting
+            InventoryAction::Split => !is_equipped,
             InventoryAction::Combine => false, // TODO: Implement item combining
             InventoryAction::Repair => matches!(item_type, ItemType::Weapon(_) | ItemType::Armor(_)),
             InventoryAction::Enchant => matches!(item_type, ItemType::Weapon(_) | ItemType::Armor(_)),
@@ -169,7 +179,7 @@ impl InventoryFilter {
             InventoryFilter::Consumables => matches!(item_props.item_type, ItemType::Consumable(_)),
             InventoryFilter::Tools => matches!(item_props.item_type, ItemType::Tool(_)),
             InventoryFilter::Materials => matches!(item_props.item_type, ItemType::Material(_)),
-            InventoryFilter::Quest => item_props.tags.contains(&crate::items::ItemTag::Quest),
+            InventoryFilter::Quest => item_props.item_type == ItemType::Quest || item_props.tags.contains(&ItemTag::QuestItem),
             InventoryFilter::Equipped => is_equipped,
             InventoryFilter::Unequipped => !is_equipped,
             InventoryFilter::Rarity(rarity) => item_props.rarity == *rarity,
@@ -190,6 +200,12 @@ pub struct InventoryUI {
     pub sort_ascending: bool,
     pub filtered_items: Vec<(Entity, InventorySlot)>,
     pub comparison_item: Option<Entity>,
+    /// How many of the selected stack a drop or split takes
+    pub quantity: i32,
+    /// What the quantity prompt is asking for, a drop or a split
+    pub quantity_action: InventoryAction,
+    /// Whether a number has been typed since the quantity prompt opened
+    quantity_typed: bool,
     pub scroll_offset: usize,
     pub items_per_page: usize,
     pub show_item_icons: bool,
@@ -210,6 +226,9 @@ impl InventoryUI {
             sort_ascending: true,
             filtered_items: Vec::new(),
             comparison_item: None,
+            quantity: 1,
+            quantity_action: InventoryAction::Drop,
+            quantity_typed: false,
             scroll_offset: 0,
             items_per_page: 20,
             show_item_icons: true,
@@ -239,19 +258,13 @@ impl InventoryUI {
         if let Some(player_entity) = self.player_entity {
             let inventories = world.read_storage::<AdvancedInventory>();
             let item_properties = world.read_storage::<ItemProperties>();
-            let equippables = world.read_storage::<Equippable>();
 
             if let Some(inventory) = inventories.get(player_entity) {
-                for slot in &inventory.slots {
-                    if let Some(item_entity) = slot.item {
-                        if let Some(props) = item_properties.get(item_entity) {
-                            let is_equipped = equippables.get(item_entity)
-                                .map(|e| e.equipped)
-                                .unwrap_or(false);
-
-                            if self.current_filter.matches_item(props, is_equipped) {
-                                self.filtered_items.push((item_entity, slot.clone()));
-                            }
+                for slot in &inventory.items {
+                    if let Some(props) = item_properties.get(slot.entity) {
+                        let is_equipped = self.equipped_slot(world, slot.entity).is_some();
+                        if self.current_filter.matches_item(props, is_equipped) {
+                            self.filtered_items.push((slot.entity, slot.clone()));
                         }
                     }
                 }
@@ -260,11 +273,37 @@ impl InventoryUI {
                 self.sort_items(world);
             }
         }
+
+        self.selected_item_index = self.selected_item_index.min(self.filtered_items.len().saturating_sub(1));
+        self.ensure_item_visible();
+    }
+
+    /// The player's slot holding `item`, if it is worn or wielded
+    pub fn equipped_slot(&self, world: &World, item: Entity) -> Option<EquipmentSlot> {
+        let player_entity = self.player_entity?;
+        world.read_storage::<Equipment>().get(player_entity)?.find_item_slot(item)
+    }
+
+    /// The letter an item in the list is picked with, `a` to `z`
+    pub fn item_letter(index: usize) -> Option<char> {
+        if index < 26 {
+            Some((b'a' + index as u8) as char)
+        } else {
+            None
+        }
     }
 
     fn sort_items(&mut self, world: &World) {
         let item_properties = world.read_storage::<ItemProperties>();
 
+        // Items are kept in the order they were picked up
+        if self.current_sort == InventorySortMode::Recent {
+            if !self.sort_ascending {
+                self.filtered_items.reverse();
+            }
+            return;
+        }
+
         self.filtered_items.sort_by(|a, b| {
             let props_a = item_properties.get(a.0);
             let props_b = item_properties.get(b.0);
@@ -278,9 +317,11 @@ impl InventoryUI {
                         type_order_a.cmp(&type_order_b)
                     }
                     InventorySortMode::Rarity => props_a.rarity.cmp(&props_b.rarity),
-                    InventorySortMode::Value => props_a.value.cmp(&props_b.value),
-                    InventorySortMode::Weight => props_a.weight.partial_cmp(&props_b.weight).unwrap_or(std::cmp::Ordering::Equal),
-                    InventorySortMode::Recent => a.1.added_time.cmp(&b.1.added_time),
+                    InventorySortMode::Value => (props_a.value * a.1.quantity).cmp(&(props_b.value * b.1.quantity)),
+                    InventorySortMode::Weight => (props_a.weight * a.1.quantity as f32)
+                        .partial_cmp(&(props_b.weight * b.1.quantity as f32))
+                        .unwrap_or(std::cmp::Ordering::Equal),
+                    InventorySortMode::Recent => std::cmp::Ordering::Equal,
                 };
 
                 if self.sort_ascending {
@@ -302,7 +343,7 @@ impl InventoryUI {
             ItemType::Tool(_) => 3,
             ItemType::Material(_) => 4,
             ItemType::Quest => 5,
-            ItemType::Misc => 6,
+            ItemType::Miscellaneous => 6,
         }
     }
 
@@ -311,23 +352,24 @@ impl InventoryUI {
             InventoryUIState::ItemList => self.handle_item_list_key(key, world),
             InventoryUIState::ItemDetails => self.handle_item_details_key(key),
             InventoryUIState::ItemComparison => self.handle_comparison_key(key),
-            InventoryUIState::FilterMenu => self.handle_filter_menu_key(key),
-            InventoryUIState::SortMenu => self.handle_sort_menu_key(key),
+            InventoryUIState::FilterMenu => self.handle_filter_menu_key(key, world),
+            InventoryUIState::SortMenu => self.handle_sort_menu_key(key, world),
             InventoryUIState::ActionMenu => self.handle_action_menu_key(key, world),
+            InventoryUIState::Quantity => self.handle_quantity_key(key),
             InventoryUIState::Closed => None,
         }
     }
 
     fn handle_item_list_key(&mut self, key: KeyCode, world: &World) -> Option<InventoryAction> {
         match key {
-            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => {
+            KeyCode::Up => {
                 if self.selected_item_index > 0 {
                     self.selected_item_index -= 1;
                     self.ensure_item_visible();
                 }
                 None
             }
-            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s') => {
+            KeyCode::Down => {
                 if self.selected_item_index < self.filtered_items.len().saturating_sub(1) {
                     self.selected_item_index += 1;
                     self.ensure_item_visible();
@@ -341,35 +383,62 @@ impl InventoryUI {
                 }
                 None
             }
-            KeyCode::Char('e') => {
+            KeyCode::Char(digit @ '1'..='9') => {
+                // Put the highlighted consumable on that hotbar slot
+                let (item_entity, _) = self.get_selected_item()?;
+                let player = self.player_entity?;
+                let name = world.read_storage::<ItemProperties>().get(item_entity)
+                    .filter(|props| matches!(props.item_type, ItemType::Consumable(_)))
+                    .map(|props| props.name.clone())?;
+                assign_hotbar_slot(world, player, digit as usize - '1' as usize, HotbarEntry::Item(name));
+                None
+            }
+            KeyCode::Char(letter @ 'a'..='z') => {
+                // Pick an item by its letter
+                let index = (letter as u8 - b'a') as usize;
+                if index < self.filtered_items.len() {
+                    self.selected_item_index = index;
+                    self.ensure_item_visible();
+                    self.state = InventoryUIState::ActionMenu;
+                    self.selected_action_index = 0;
+                }
+                None
+            }
+            KeyCode::Char('E') => {
                 // Quick equip/use
-                if let Some((item_entity, _)) = self.get_selected_item() {
-                    let item_properties = world.read_storage::<ItemProperties>();
-                    if let Some(props) = item_properties.get(item_entity) {
-                        match props.item_type {
-                            ItemType::Weapon(_) | ItemType::Armor(_) => Some(InventoryAction::Equip),
-                            ItemType::Consumable(_) => Some(InventoryAction::Use),
-                            _ => None,
-                        }
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+                let (item_entity, _) = self.get_selected_item()?;
+                let item_properties = world.read_storage::<ItemProperties>();
+                let props = item_properties.get(item_entity)?;
+                match props.item_type {
+                    ItemType::Weapon(_) | ItemType::Armor(_) if self.equipped_slot(world, item_entity).is_some() => Some(InventoryAction::Unequip),
+                    ItemType::Weapon(_) | ItemType::Armor(_) => Some(InventoryAction::Equip),
+                    ItemType::Consumable(_) => Some(InventoryAction::Use),
+                    _ => None,
                 }
             }
-            KeyCode::Char('d') => {
+            KeyCode::Char('D') => {
                 // Quick drop
-                Some(InventoryAction::Drop)
+                self.begin_drop(world)
+            }
+            KeyCode::Char('T') => {
+                // Quick throw
+                let (item_entity, _) = self.get_selected_item()?;
+                let item_properties = world.read_storage::<ItemProperties>();
+                let props = item_properties.get(item_entity)?;
+                if InventoryAction::Throw.is_available_for_item(&props.item_type, false) {
+                    Some(InventoryAction::Throw)
+                } else {
+                    None
+                }
             }
-            KeyCode::Char('x') => {
+            KeyCode::Char('X') => {
                 // Examine item
                 if !self.filtered_items.is_empty() {
                     self.state = InventoryUIState::ItemDetails;
                 }
                 None
             }
-            KeyCode::Char('c') => {
+            KeyCode::Char('C') => {
                 // Compare item
                 if let Some((item_entity, _)) = self.get_selected_item() {
                     self.comparison_item = Some(item_entity);
@@ -377,23 +446,23 @@ impl InventoryUI {
                 }
                 None
             }
-            KeyCode::Char('f') => {
+            KeyCode::Char('F') => {
                 // Filter menu
                 self.state = InventoryUIState::FilterMenu;
                 None
             }
-            KeyCode::Char('o') => {
+            KeyCode::Char('O') => {
                 // Sort menu
                 self.state = InventoryUIState::SortMenu;
                 None
             }
-            KeyCode::Char('r') => {
+            KeyCode::Char('R') => {
                 // Reverse sort order
                 self.sort_ascending = !self.sort_ascending;
                 self.update_filtered_items(world);
                 None
             }
-            KeyCode::Esc | KeyCode::Char('q') => {
+            KeyCode::Esc => {
                 self.close();
                 None
             }
@@ -401,6 +470,77 @@ impl InventoryUI {
         }
     }
 
+    /// Drop the selected item, asking how many first when it is a stack
+    fn begin_drop(&mut self, world: &World) -> Option<InventoryAction> {
+        let (item_entity, slot) = self.get_selected_item()?;
+        if self.equipped_slot(world, item_entity).is_some() {
+            return None;
+        }
+        self.quantity = slot.quantity;
+        self.quantity_action = InventoryAction::Drop;
+        if slot.quantity > 1 {
+            self.quantity_typed = false;
+            self.state = InventoryUIState::Quantity;
+            return None;
+        }
+        self.state = InventoryUIState::ItemList;
+        Some(InventoryAction::Drop)
+    }
+
+    /// Ask how many to split off the selected stack, half of it to begin with
+    fn begin_split(&mut self) -> Option<InventoryAction> {
+        let (_, slot) = self.get_selected_item()?;
+        if slot.quantity < 2 {
+            return None;
+        }
+        self.quantity = slot.quantity / 2;
+        self.quantity_action = InventoryAction::Split;
+        self.quantity_typed = false;
+        self.state = InventoryUIState::Quantity;
+        None
+    }
+
+    fn handle_quantity_key(&mut self, key: KeyCode) -> Option<InventoryAction> {
+        // A split leaves at least one behind
+        let stack = self.get_selected_item().map_or(1, |(_, slot)| slot.quantity);
+        let most = if self.quantity_action == InventoryAction::Split { stack - 1 } else { stack };
+        match key {
+            KeyCode::Char(digit @ '0'..='9') => {
+                let digit = digit as i32 - '0' as i32;
+                let typed = if self.quantity_typed { self.quantity * 10 + digit } else { digit };
+                self.quantity = typed.min(most);
+                self.quantity_typed = true;
+                None
+            }
+            KeyCode::Backspace => {
+                self.quantity /= 10;
+                self.quantity_typed = true;
+                None
+            }
+            KeyCode::Up | KeyCode::Char('+') => {
+                self.quantity = (self.quantity + 1).min(most);
+                None
+            }
+            KeyCode::Down | KeyCode::Char('-') => {
+                self.quantity = (self.quantity - 1).max(1);
+                None
+            }
+            KeyCode::Enter => {
+                if self.quantity > 0 {
+                    self.state = InventoryUIState::ItemList;
+                    Some(self.quantity_action.clone())
+                } else {
+                    None
+                }
+            }
+            KeyCode::Esc => {
+                self.state = InventoryUIState::ItemList;
+                None
+            }
+            _ => None,
+        }
+    }
+
     fn handle_item_details_key(&mut self, key: KeyCode) -> Option<InventoryAction> {
         match key {
             KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
@@ -422,7 +562,7 @@ impl InventoryUI {
         }
     }
 
-    fn handle_filter_menu_key(&mut self, key: KeyCode) -> Option<InventoryAction> {
+    fn handle_filter_menu_key(&mut self, key: KeyCode, world: &World) -> Option<InventoryAction> {
         let filters = InventoryFilter::all_filters();
         
         match key {
@@ -441,6 +581,8 @@ impl InventoryUI {
             KeyCode::Enter | KeyCode::Char(' ') => {
                 self.current_filter = filters[self.selected_filter_index].clone();
                 self.state = InventoryUIState::ItemList;
+                self.selected_item_index = 0;
+                self.update_filtered_items(world);
                 None
             }
             KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
@@ -451,7 +593,7 @@ impl InventoryUI {
         }
     }
 
-    fn handle_sort_menu_key(&mut self, key: KeyCode) -> Option<InventoryAction> {
+    fn handle_sort_menu_key(&mut self, key: KeyCode, world: &World) -> Option<InventoryAction> {
         let sort_modes = InventorySortMode::all_modes();
         
         match key {
@@ -470,6 +612,7 @@ impl InventoryUI {
             KeyCode::Enter | KeyCode::Char(' ') => {
                 self.current_sort = sort_modes[self.selected_sort_index];
                 self.state = InventoryUIState::ItemList;
+                self.update_filtered_items(world);
                 None
             }
             KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
@@ -497,11 +640,22 @@ impl InventoryUI {
                 None
             }
             KeyCode::Enter | KeyCode::Char(' ') => {
-                if let Some(action) = available_actions.get(self.selected_action_index) {
-                    self.state = InventoryUIState::ItemList;
-                    Some(action.clone())
-                } else {
-                    None
+                let action = available_actions.get(self.selected_action_index)?.clone();
+                self.state = InventoryUIState::ItemList;
+                match action {
+                    InventoryAction::Drop => self.begin_drop(world),
+                    InventoryAction::Split => self.begin_split(),
+                    InventoryAction::Examine => {
+                        self.state = InventoryUIState::ItemDetails;
+                        None
+                    }
+                    InventoryAction::Compare => {
+                        self.comparison_item = self.get_selected_item().map(|(item_entity, _)| item_entity);
+                        self.state = InventoryUIState::ItemComparison;
+                        None
+                    }
+                    InventoryAction::Cancel => None,
+                    action => Some(action),
                 }
             }
             KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
@@ -515,26 +669,29 @@ impl InventoryUI {
     fn get_available_actions(&self, world: &World) -> Vec<InventoryAction> {
         let mut actions = Vec::new();
 
-        if let Some((item_entity, _)) = self.get_selected_item() {
+        if let Some((item_entity, slot)) = self.get_selected_item() {
             let item_properties = world.read_storage::<ItemProperties>();
-            let equippables = world.read_storage::<Equippable>();
 
             if let Some(props) = item_properties.get(item_entity) {
-                let is_equipped = equippables.get(item_entity)
-                    .map(|e| e.equipped)
-                    .unwrap_or(false);
+                let is_equipped = self.equipped_slot(world, item_entity).is_some();
 
                 for action in &[
                     InventoryAction::Use,
                     InventoryAction::Equip,
                     InventoryAction::Unequip,
                     InventoryAction::Drop,
+                    InventoryAction::Throw,
+                    InventoryAction::Split,
                     InventoryAction::Examine,
                     InventoryAction::Compare,
                     InventoryAction::Repair,
                     InventoryAction::Enchant,
                     InventoryAction::Sell,
                 ] {
+                    // Only a stack of more than one can be split
+                    if *action == InventoryAction::Split && slot.quantity < 2 {
+                        continue;
+                    }
                     if action.is_available_for_item(&props.item_type, is_equipped) {
                         actions.push(action.clone());
                     }
@@ -546,10 +703,16 @@ impl InventoryUI {
         actions
     }
 
-    fn get_selected_item(&self) -> Option<(Entity, InventorySlot)> {
+    pub fn get_selected_item(&self) -> Option<(Entity, InventorySlot)> {
         self.filtered_items.get(self.selected_item_index).cloned()
     }
 
+    /// The selected item's line as the list shows it, for the screen-reader transcript
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        let (item_entity, slot) = self.get_selected_item()?;
+        Some(self.format_list_line(world, self.selected_item_index, item_entity, &slot))
+    }
+
     fn ensure_item_visible(&mut self) {
         if self.selected_item_index < self.scroll_offset {
             self.scroll_offset = self.selected_item_index;
@@ -559,17 +722,93 @@ impl InventoryUI {
     }
 
     pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
-        match self.state {
+        // Menus and prompts open over the item list
+        let mut commands = match self.state {
+            InventoryUIState::FilterMenu | InventoryUIState::SortMenu
+                | InventoryUIState::ActionMenu | InventoryUIState::Quantity => self.render_item_list(world, screen_width, screen_height),
+            _ => Vec::new(),
+        };
+        commands.extend(match self.state {
             InventoryUIState::ItemList => self.render_item_list(world, screen_width, screen_height),
             InventoryUIState::ItemDetails => self.render_item_details(world, screen_width, screen_height),
             InventoryUIState::ItemComparison => self.render_item_comparison(world, screen_width, screen_height),
             InventoryUIState::FilterMenu => self.render_filter_menu(screen_width, screen_height),
             InventoryUIState::SortMenu => self.render_sort_menu(screen_width, screen_height),
             InventoryUIState::ActionMenu => self.render_action_menu(world, screen_width, screen_height),
+            InventoryUIState::Quantity => self.render_quantity(world, screen_width, screen_height),
             InventoryUIState::Closed => Vec::new(),
+        });
+        commands
+    }
+
+    /// Items carried out of the capacity, and weight out of the limit
+    fn capacity_text(&self, world: &World) -> String {
+        let inventories = world.read_storage::<AdvancedInventory>();
+        match self.player_entity.and_then(|player| inventories.get(player)) {
+            Some(inventory) => format!("Items: {}/{}  Weight: {:.1}/{:.1}",
+                inventory.items.len(), inventory.capacity, inventory.current_weight, inventory.weight_limit),
+            None => format!("Items: {}", self.filtered_items.len()),
         }
     }
 
+    /// Draw through a widget backend; the item list becomes a real list widget, other views stay as commands
+    pub fn draw(&self, world: &World, backend: &mut dyn UiBackend) -> Result<(), Box<dyn std::error::Error>> {
+        let (width, height) = backend.size();
+        if self.state != InventoryUIState::ItemList {
+            return backend.draw(UiWidget::Commands(self.render(world, width as i32, height as i32)));
+        }
+
+        let panel = UiRect::new(2, 2, width.saturating_sub(4), height.saturating_sub(4));
+        let header = format!("Filter: {} | Sort: {} {}",
+            self.current_filter.to_string(),
+            self.current_sort.to_string(),
+            if self.sort_ascending { "↑" } else { "↓" },
+        );
+        backend.draw(UiWidget::Paragraph {
+            area: UiRect::new(panel.x, panel.y, panel.width, 3),
+            title: "Inventory".to_string(),
+            lines: vec![header],
+            color: Color::Cyan,
+        })?;
+        let capacity = self.player_entity
+            .and_then(|player| world.read_storage::<AdvancedInventory>().get(player).map(|inventory| inventory.capacity))
+            .unwrap_or(26)
+            .max(1);
+        backend.draw(UiWidget::Gauge {
+            area: UiRect::new(panel.x + 1, panel.y + 3, panel.width.saturating_sub(2), 1),
+            label: format!("Items {}/{}", self.filtered_items.len(), capacity),
+            ratio: self.filtered_items.len() as f64 / capacity as f64,
+            color: Color::Yellow,
+        })?;
+
+        let list_area = UiRect::new(panel.x, panel.y + 4, panel.width.saturating_sub(1), panel.height.saturating_sub(6));
+        let items: Vec<String> = self.filtered_items.iter().enumerate()
+            .map(|(i, (item_entity, slot))| self.format_list_line(world, i, *item_entity, slot))
+            .collect();
+        let total = items.len();
+        backend.draw(UiWidget::List {
+            area: list_area,
+            title: String::new(),
+            items,
+            selected: Some(self.selected_item_index),
+            offset: self.scroll_offset,
+        })?;
+        backend.draw(UiWidget::Scrollbar {
+            area: UiRect::new(list_area.x + list_area.width, list_area.y + 1, 1, list_area.height.saturating_sub(2)),
+            position: self.scroll_offset,
+            total,
+            visible: list_area.inner().height as usize,
+        })?;
+
+        backend.draw(UiWidget::Commands(vec![UIRenderCommand::DrawText {
+            x: panel.x as i32 + 2,
+            y: (list_area.y + list_area.height) as i32,
+            text: tr("inventory.controls"),
+            fg: Color::DarkGrey,
+            bg: Color::Black,
+        }]))
+    }
+
     fn render_item_list(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
         let mut commands = Vec::new();
 
@@ -588,13 +827,11 @@ impl InventoryUI {
 
         // Header with filter and sort info
         let header_y = 3;
-        let header_text = format!("Filter: {} | Sort: {} {} | Items: {}/{}",
+        let header_text = format!("Filter: {} | Sort: {} {} | {}",
             self.current_filter.to_string(),
             self.current_sort.to_string(),
             if self.sort_ascending { "↑" } else { "↓" },
-            self.filtered_items.len(),
-            // TODO: Get max inventory size
-            100
+            self.capacity_text(world),
         );
 
         commands.push(UIRenderCommand::DrawText {
@@ -605,6 +842,16 @@ impl InventoryUI {
             bg: Color::Black,
         });
 
+        // Hovering something wearable shows what it would change beside the list
+        const COMPARE_WIDTH: i32 = 34;
+        let comparison = self.get_selected_item()
+            .and_then(|(item_entity, _)| self.comparison_for(world, item_entity))
+            .filter(|_| panel_width >= COMPARE_WIDTH + 30);
+        let list_width = match comparison {
+            Some(_) => panel_width - COMPARE_WIDTH - 6,
+            None => panel_width - 4,
+        };
+
         // Item list
         let list_start_y = header_y + 2;
         let list_height = panel_height - 6;
@@ -616,29 +863,48 @@ impl InventoryUI {
             let y = list_start_y + i as i32;
             let is_selected = (i + self.scroll_offset) == self.selected_item_index;
 
-            let item_text = self.format_item_text(world, *item_entity, slot);
+            let item_text = self.format_list_line(world, i + self.scroll_offset, *item_entity, slot);
             let (fg, bg) = if is_selected {
                 (Color::Black, Color::White)
             } else {
                 (Color::White, Color::Black)
             };
 
+            let item_text: String = item_text.chars().take(list_width as usize).collect();
             commands.push(UIRenderCommand::DrawText {
                 x: 4,
                 y,
-                text: format!("{:<width$}", item_text, width = (panel_width - 4) as usize),
+                text: format!("{:<width$}", item_text, width = list_width as usize),
                 fg,
                 bg,
             });
         }
 
+        if let Some(comparison) = &comparison {
+            let compare_x = 2 + panel_width - COMPARE_WIDTH - 1;
+            let title = Message::new("inventory.equipped_in").arg("slot", comparison.slot.name()).text();
+            let compare_panel = UIPanel::new(title, compare_x, list_start_y - 1, COMPARE_WIDTH, list_height + 1)
+                .with_colors(Color::DarkGrey, Color::Black, Color::Yellow);
+            commands.extend(compare_panel.render());
+            commands.extend(self.render_deltas(world, comparison, compare_x + 2, list_start_y));
+        }
+
+        if self.filtered_items.is_empty() {
+            commands.push(UIRenderCommand::DrawText {
+                x: 4,
+                y: list_start_y,
+                text: tr("inventory.empty"),
+                fg: Color::DarkGrey,
+                bg: Color::Black,
+            });
+        }
+
         // Controls help
-        let controls_y = panel_height - 2;
-        let controls_text = "E:Equip/Use D:Drop X:Examine C:Compare F:Filter O:Sort R:Reverse ESC:Close";
+        let controls_y = panel_height;
         commands.push(UIRenderCommand::DrawText {
             x: 4,
             y: controls_y,
-            text: controls_text.to_string(),
+            text: tr("inventory.controls"),
             fg: Color::DarkGrey,
             bg: Color::Black,
         });
@@ -646,9 +912,14 @@ impl InventoryUI {
         commands
     }
 
+    /// An item's line in the list, after the letter it is picked with
+    fn format_list_line(&self, world: &World, index: usize, item_entity: Entity, slot: &InventorySlot) -> String {
+        let letter = Self::item_letter(index).map_or("   ".to_string(), |letter| format!("{}) ", letter));
+        format!("{}{}", letter, self.format_item_text(world, item_entity, slot))
+    }
+
     fn format_item_text(&self, world: &World, item_entity: Entity, slot: &InventorySlot) -> String {
         let item_properties = world.read_storage::<ItemProperties>();
-        let equippables = world.read_storage::<Equippable>();
 
         if let Some(props) = item_properties.get(item_entity) {
             let mut text = String::new();
@@ -671,7 +942,7 @@ impl InventoryUI {
             }
 
             // Item name with rarity color coding
-            text.push_str(&props.name);
+            text.push_str(&localization::name(&props.name));
 
             // Quantity if stacked
             if slot.quantity > 1 {
@@ -679,10 +950,18 @@ impl InventoryUI {
             }
 
             // Equipped indicator
-            if equippables.get(item_entity).map(|e| e.equipped).unwrap_or(false) {
+            if self.equipped_slot(world, item_entity).is_some() {
                 text.push_str(" [E]");
             }
 
+            // Hotbar slot, for consumables put on one
+            let hotbar_slot = self.player_entity
+                .and_then(|player| world.read_storage::<Hotbar>().get(player).cloned())
+                .and_then(|hotbar| hotbar.slot_of(&HotbarEntry::Item(props.name.clone())));
+            if let Some(slot) = hotbar_slot {
+                text.push_str(&format!(" [{}]", slot + 1));
+            }
+
             // Condition indicator
             if let Some(durability) = &props.durability {
                 let condition_percent = (durability.current as f32 / durability.max as f32 * 100.0) as i32;
@@ -700,7 +979,7 @@ impl InventoryUI {
     fn render_item_details(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
         let mut commands = Vec::new();
 
-        if let Some((item_entity, _)) = self.get_selected_item() {
+        if let Some((item_entity, slot)) = self.get_selected_item() {
             let panel_width = screen_width - 4;
             let panel_height = screen_height - 4;
             let panel = UIPanel::new(
@@ -724,7 +1003,7 @@ impl InventoryUI {
                 commands.push(UIRenderCommand::DrawText {
                     x: 4,
                     y,
-                    text: format!("{} ({})", props.name, self.format_item_type(&props.item_type)),
+                    text: format!("{} ({})", localization::name(&props.name), self.format_item_type(&props.item_type)),
                     fg: self.get_rarity_color(&props.rarity),
                     bg: Color::Black,
                 });
@@ -740,11 +1019,17 @@ impl InventoryUI {
                 });
                 y += 2;
 
-                // Stats
+                // Stats, for the whole stack when there is more than one
+                let stats = if slot.quantity > 1 {
+                    format!("Quantity: {}  Value: {} gold ({} each)  Weight: {:.1} lbs ({:.1} each)",
+                        slot.quantity, props.value * slot.quantity, props.value, props.weight * slot.quantity as f32, props.weight)
+                } else {
+                    format!("Value: {} gold  Weight: {:.1} lbs", props.value, props.weight)
+                };
                 commands.push(UIRenderCommand::DrawText {
                     x: 4,
                     y,
-                    text: format!("Value: {} gold  Weight: {:.1} lbs", props.value, props.weight),
+                    text: stats,
                     fg: Color::Cyan,
                     bg: Color::Black,
                 });
@@ -830,11 +1115,193 @@ impl InventoryUI {
         commands
     }
 
+    /// What wearing `item_entity` would change, unless it is already worn
+    pub fn comparison_for(&self, world: &World, item_entity: Entity) -> Option<EquipmentComparison> {
+        let player_entity = self.player_entity?;
+        let equipment = world.read_storage::<Equipment>();
+        let comparison = compare_equipment(
+            item_entity,
+            equipment.get(player_entity)?,
+            &world.read_storage::<ItemProperties>(),
+            &world.read_storage::<ItemBonuses>(),
+        )?;
+        if comparison.equipped == Some(item_entity) {
+            return None;
+        }
+        Some(comparison)
+    }
+
+    /// "Power   4 →  7   +3", the change green when it helps and red when it hurts
+    fn delta_line(delta: &StatDelta) -> (String, Color) {
+        let precision = delta.precision;
+        let text = format!("{:<12}{:>5.*} →{:>5.*}{:>+7.*}",
+            delta.label, precision, delta.equipped, precision, delta.candidate, precision, delta.delta());
+        let color = match delta.is_improvement() {
+            Some(true) => Color::Green,
+            Some(false) => Color::Red,
+            None => Color::DarkGrey,
+        };
+        (text, color)
+    }
+
+    /// What is worn now, then a line per stat that would change
+    fn render_deltas(&self, world: &World, comparison: &EquipmentComparison, x: i32, y: i32) -> Vec<UIRenderCommand> {
+        let mut commands = Vec::new();
+        let heading = match comparison.equipped.and_then(|equipped| world.read_storage::<ItemProperties>().get(equipped).map(|props| props.name.clone())) {
+            Some(name) => Message::new("inventory.versus").arg("item", localization::name(&name)).text(),
+            None => tr("inventory.nothing_equipped"),
+        };
+        commands.push(UIRenderCommand::DrawText {
+            x,
+            y,
+            text: heading,
+            fg: Color::White,
+            bg: Color::Black,
+        });
+        for (i, delta) in comparison.deltas.iter().enumerate() {
+            let (text, fg) = Self::delta_line(delta);
+            commands.push(UIRenderCommand::DrawText {
+                x,
+                y: y + 2 + i as i32,
+                text,
+                fg,
+                bg: Color::Black,
+            });
+        }
+        commands
+    }
+
+    /// The lines describing an item in the comparison panes, each with its color
+    fn item_summary_lines(&self, world: &World, item_entity: Entity) -> Vec<(String, Color)> {
+        let item_properties = world.read_storage::<ItemProperties>();
+        let item_bonuses = world.read_storage::<ItemBonuses>();
+        let mut lines = Vec::new();
+
+        if let Some(props) = item_properties.get(item_entity) {
+            lines.push((localization::name(&props.name), self.get_rarity_color(&props.rarity)));
+            lines.push((self.format_item_type(&props.item_type), Color::Grey));
+            lines.push((format!("Value: {} gold  Weight: {:.1} lbs", props.value, props.weight), Color::Cyan));
+            if let Some(durability) = &props.durability {
+                lines.push((format!("Condition: {}/{}", durability.current, durability.max), Color::Yellow));
+            }
+        }
+        if let Some(bonuses) = item_bonuses.get(item_entity) {
+            let combat = &bonuses.combat_bonuses;
+            for (label, value) in [("Attack", combat.attack_bonus), ("Damage", combat.damage_bonus), ("Defense", combat.defense_bonus)] {
+                if value != 0 {
+                    lines.push((format!("{}: {:+}", label, value), Color::White));
+                }
+            }
+        }
+        lines
+    }
+
     fn render_item_comparison(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
         let mut commands = Vec::new();
 
-        // TODO: Implement item comparison view
-        // This would show two items side by side with their stats compared
+        let item_entity = match self.comparison_item {
+            Some(item_entity) => item_entity,
+            None => return commands,
+        };
+        let slot = world.read_storage::<ItemProperties>().get(item_entity)
+            .and_then(|props| EquipmentSlot::for_item_type(&props.item_type));
+        let equipped = match (&slot, self.player_entity) {
+            (Some(slot), Some(player)) => world.read_storage::<Equipment>().get(player).and_then(|equipment| equipment.get_equipped(slot)),
+            _ => None,
+        };
+
+        // The selected item on the left, what it would replace on the right
+        let pane_width = (screen_width - 6) / 2;
+        let pane_height = screen_height - 6;
+        let right_heading = match &slot {
+            Some(slot) => Message::new("inventory.equipped_in").arg("slot", slot.name()).text(),
+            None => tr("inventory.equipped"),
+        };
+        let panes = [
+            (2, tr("inventory.selected"), Some(item_entity)),
+            (4 + pane_width, right_heading, equipped),
+        ];
+        for (x, title, item) in panes {
+            let panel = UIPanel::new(title, x, 2, pane_width, pane_height)
+                .with_colors(Color::White, Color::Black, Color::Yellow);
+            commands.extend(panel.render());
+
+            let lines = match item {
+                Some(item) => self.item_summary_lines(world, item),
+                None => vec![(tr("inventory.nothing_equipped"), Color::DarkGrey)],
+            };
+            let summary_height = lines.len() as i32;
+            for (i, (text, fg)) in lines.into_iter().enumerate() {
+                let text: String = text.chars().take((pane_width - 4).max(0) as usize).collect();
+                commands.push(UIRenderCommand::DrawText {
+                    x: x + 2,
+                    y: 4 + i as i32,
+                    text,
+                    fg,
+                    bg: Color::Black,
+                });
+            }
+
+            // The changes go under the selected item
+            if item == Some(item_entity) {
+                if let Some(comparison) = self.comparison_for(world, item_entity) {
+                    commands.extend(self.render_deltas(world, &comparison, x + 2, 5 + summary_height));
+                }
+            }
+        }
+
+        commands.push(UIRenderCommand::DrawText {
+            x: 4,
+            y: screen_height - 3,
+            text: "ESC:Back".to_string(),
+            fg: Color::DarkGrey,
+            bg: Color::Black,
+        });
+
+        commands
+    }
+
+    fn render_quantity(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let mut commands = Vec::new();
+
+        let (item_entity, slot) = match self.get_selected_item() {
+            Some(selected) => selected,
+            None => return commands,
+        };
+        let name = world.read_storage::<ItemProperties>().get(item_entity)
+            .map_or_else(|| tr("access.something"), |props| localization::name(&props.name));
+        let (title, how_many, controls) = match self.quantity_action {
+            InventoryAction::Split => ("inventory.split_title", "inventory.split_how_many", "inventory.split_controls"),
+            _ => ("inventory.drop_title", "inventory.drop_how_many", "inventory.drop_controls"),
+        };
+        let prompt = Message::new(how_many)
+            .arg("count", self.quantity)
+            .arg("stack", slot.quantity)
+            .arg("item", name)
+            .text();
+        let hint = tr(controls);
+
+        let panel_width = (prompt.chars().count().max(hint.chars().count()) as i32 + 4).min(screen_width);
+        let panel_height = 5;
+        let panel_x = (screen_width - panel_width) / 2;
+        let panel_y = (screen_height - panel_height) / 2;
+        let panel = UIPanel::new(tr(title), panel_x, panel_y, panel_width, panel_height)
+            .with_colors(Color::White, Color::Black, Color::Yellow);
+        commands.extend(panel.render());
+        commands.push(UIRenderCommand::DrawText {
+            x: panel_x + 2,
+            y: panel_y + 1,
+            text: prompt,
+            fg: Color::White,
+            bg: Color::Black,
+        });
+        commands.push(UIRenderCommand::DrawText {
+            x: panel_x + 2,
+            y: panel_y + 3,
+            text: hint,
+            fg: Color::DarkGrey,
+            bg: Color::Black,
+        });
 
         commands
     }
@@ -992,7 +1459,7 @@ impl InventoryUI {
             ItemType::Tool(tool_type) => format!("Tool ({})", self.format_tool_type(tool_type)),
             ItemType::Material(material_type) => format!("Material ({})", self.format_material_type(material_type)),
             ItemType::Quest => "Quest Item".to_string(),
-            ItemType::Misc => "Miscellaneous".to_string(),
+            ItemType::Miscellaneous => "Miscellaneous".to_string(),
         }
     }
 
@@ -1030,8 +1497,7 @@ impl InventoryUI {
             ConsumableType::Potion => "Potion",
             ConsumableType::Food => "Food",
             ConsumableType::Scroll => "Scroll",
-            ConsumableType::Wand => "Wand",
-            ConsumableType::Misc => "Miscellaneous",
+            ConsumableType::Ammunition => "Ammunition",
         }
     }
 
@@ -1041,7 +1507,8 @@ impl InventoryUI {
             crate::items::ToolType::Rope => "Rope",
             crate::items::ToolType::Torch => "Torch",
             crate::items::ToolType::Key => "Key",
-            crate::items::ToolType::Misc => "Miscellaneous",
+            crate::items::ToolType::Container => "Container",
+            crate::items::ToolType::RepairKit => "Repair Kit",
         }
     }
 
@@ -1053,17 +1520,20 @@ impl InventoryUI {
             crate::items::MaterialType::Leather => "Leather",
             crate::items::MaterialType::Gem => "Gem",
             crate::items::MaterialType::Herb => "Herb",
-            crate::items::MaterialType::Misc => "Miscellaneous",
+            crate::items::MaterialType::Bone => "Bone",
+            crate::items::MaterialType::Stone => "Stone",
         }
     }
 
     fn get_rarity_color(&self, rarity: &ItemRarity) -> Color {
         match rarity {
+            ItemRarity::Trash => Color::DarkGrey,
             ItemRarity::Common => Color::White,
             ItemRarity::Uncommon => Color::Green,
             ItemRarity::Rare => Color::Blue,
             ItemRarity::Epic => Color::Magenta,
             ItemRarity::Legendary => Color::Yellow,
+            ItemRarity::Artifact => Color::DarkYellow,
         }
     }
 }
@@ -1086,7 +1556,7 @@ impl UIComponent for InventoryUI {
 
         // We need World access for proper handling, so this is a simplified version
         match key {
-            KeyCode::Esc | KeyCode::Char('q') => {
+            KeyCode::Esc => {
                 self.close();
                 true
             }
@@ -1117,13 +1587,16 @@ mod tests {
         world.register::<Name>();
         world.register::<AdvancedInventory>();
         world.register::<ItemProperties>();
-        world.register::<Equippable>();
+        world.register::<Equipment>();
         world.register::<ItemBonuses>();
+        world.register::<Hotbar>();
+        world.insert(crate::resources::GameLog::new(10));
 
         let player = world.create_entity()
             .with(Player)
             .with(Name { name: "Hero".to_string() })
-            .with(AdvancedInventory::new(30))
+            .with(AdvancedInventory::new(30, 100.0))
+            .with(Equipment::new())
             .build();
 
         (world, player)
@@ -1171,9 +1644,71 @@ mod tests {
         assert!(!InventoryAction::Use.is_available_for_item(&weapon_type, false));
         
         assert!(InventoryAction::Drop.is_available_for_item(&weapon_type, false));
+        assert!(!InventoryAction::Drop.is_available_for_item(&weapon_type, true));
         assert!(InventoryAction::Examine.is_available_for_item(&weapon_type, false));
     }
 
+    #[test]
+    fn test_letters_pick_items_and_stacks_ask_how_many_to_drop() {
+        let (mut world, player) = setup_test_world();
+        let sword = world.create_entity()
+            .with(ItemProperties::new("Sword".to_string(), ItemType::Weapon(WeaponType::Sword)))
+            .build();
+        let potions = world.create_entity()
+            .with(ItemProperties::new("Health Potion".to_string(), ItemType::Consumable(ConsumableType::Potion)))
+            .build();
+        {
+            let mut inventories = world.write_storage::<AdvancedInventory>();
+            let inventory = inventories.get_mut(player).unwrap();
+            inventory.add_item(potions, 5, 0.5);
+            inventory.add_item(sword, 1, 3.0);
+        }
+        world.write_storage::<Equipment>().get_mut(player).unwrap().equip_item(EquipmentSlot::MainHand, sword);
+
+        let mut ui = InventoryUI::new();
+        ui.open(player);
+        ui.update_filtered_items(&world);
+        assert_eq!(ui.filtered_items.iter().map(|(item, _)| *item).collect::<Vec<_>>(), vec![sword, potions]);
+
+        // The sword is wielded, so it can be taken off but not dropped
+        assert_eq!(ui.handle_key(KeyCode::Char('a'), &world), None);
+        assert_eq!(ui.state, InventoryUIState::ActionMenu);
+        let actions = ui.get_available_actions(&world);
+        assert!(actions.contains(&InventoryAction::Unequip));
+        assert!(!actions.contains(&InventoryAction::Drop));
+        ui.handle_key(KeyCode::Esc, &world);
+
+        ui.handle_key(KeyCode::Char('b'), &world);
+        assert_eq!(ui.selected_item_index, 1);
+        ui.handle_key(KeyCode::Esc, &world);
+        ui.handle_key(KeyCode::Char('2'), &world);
+        assert_eq!(world.read_storage::<Hotbar>().get(player).and_then(|hotbar| hotbar.get(1).cloned()),
+            Some(HotbarEntry::Item("Health Potion".to_string())));
+        assert!(ui.format_list_line(&world, 1, potions, &ui.filtered_items[1].1).ends_with("[2]"));
+        assert_eq!(ui.handle_key(KeyCode::Char('D'), &world), None);
+        assert_eq!(ui.state, InventoryUIState::Quantity);
+        assert_eq!(ui.quantity, 5);
+        ui.handle_key(KeyCode::Char('9'), &world);
+        assert_eq!(ui.quantity, 5);
+        ui.handle_key(KeyCode::Char('-'), &world);
+        ui.handle_key(KeyCode::Char('-'), &world);
+        assert_eq!(ui.handle_key(KeyCode::Enter, &world), Some(InventoryAction::Drop));
+        assert_eq!(ui.quantity, 3);
+        assert_eq!(ui.state, InventoryUIState::ItemList);
+
+        // Splitting starts at half the stack and always leaves one behind
+        assert!(ui.get_available_actions(&world).contains(&InventoryAction::Split));
+        assert_eq!(ui.begin_split(), None);
+        assert_eq!((ui.state.clone(), ui.quantity), (InventoryUIState::Quantity, 2));
+        ui.handle_key(KeyCode::Char('9'), &world);
+        assert_eq!(ui.quantity, 4);
+        assert_eq!(ui.handle_key(KeyCode::Enter, &world), Some(InventoryAction::Split));
+
+        // Only things that can be worn, and aren't already, are compared
+        assert!(ui.comparison_for(&world, sword).is_none());
+        assert!(ui.comparison_for(&world, potions).is_none());
+    }
+
     #[test]
     fn test_inventory_filter_matching() {
         let weapon_props = ItemProperties::new("Sword".to_string(), ItemType::Weapon(WeaponType::Sword));
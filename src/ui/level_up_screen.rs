@@ -0,0 +1,559 @@
+use crossterm::{event::KeyCode, style::Color};
+use specs::{World, WorldExt, Entity};
+use crate::components::*;
+use crate::items::AdvancedInventory;
+use crate::localization::{tr, Message};
+use crate::resources::GameLog;
+use crate::systems::{Training, RESPEC_LIMIT};
+use crate::ui::{UIPanel, UIRenderCommand, format_modifier};
+
+/// The parts of a level-up, in the order Tab moves through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelUpSection {
+    Attributes,
+    Skills,
+    Ability,
+}
+
+impl LevelUpSection {
+    pub fn all() -> [LevelUpSection; 3] {
+        [LevelUpSection::Attributes, LevelUpSection::Skills, LevelUpSection::Ability]
+    }
+
+    fn next(&self) -> LevelUpSection {
+        match self {
+            LevelUpSection::Attributes => LevelUpSection::Skills,
+            LevelUpSection::Skills => LevelUpSection::Ability,
+            LevelUpSection::Ability => LevelUpSection::Attributes,
+        }
+    }
+
+    fn previous(&self) -> LevelUpSection {
+        self.next().next()
+    }
+}
+
+/// Carry a change of attributes through to the stats derived from them, by the same rules
+/// character creation uses: strength to power, dexterity to defense, and constitution to the
+/// hit points of every level
+pub fn apply_attribute_changes(stats: &mut CombatStats, before: &Attributes, after: &Attributes, level: i32) {
+    let change = |attribute| after.get_modifier(attribute) - before.get_modifier(attribute);
+    stats.power += change(AttributeType::Strength);
+    stats.defense += change(AttributeType::Dexterity);
+    let hp_change = change(AttributeType::Constitution) * level.max(1);
+    stats.max_hp = (stats.max_hp + hp_change).max(1);
+    stats.hp = (stats.hp + hp_change).clamp(1, stats.max_hp);
+}
+
+/// The level-up modal: spend attribute and skill points and learn one new class ability.
+/// Choices are made on copies and only written to the player when confirmed.
+///
+/// A trainer opens it too, to move points already spent: skill points out of any skill but
+/// what the character's background taught, or attribute points out of any attribute by up to
+/// `RESPEC_LIMIT`. The trainer is paid only once the new build is confirmed.
+#[derive(Debug, Clone)]
+pub struct LevelUpScreen {
+    player_entity: Option<Entity>,
+    pub section: LevelUpSection,
+    pub cursor: usize,
+    level: i32,
+    /// The character as the screen found them
+    start_attributes: Attributes,
+    start_skills: Skills,
+    /// How far points can be taken back: where the character started, unless training
+    attribute_floor: Attributes,
+    skill_floor: Skills,
+    /// What a trainer was asked for, and what they charge for it
+    pub training: Option<Training>,
+    pub cost: i32,
+    /// Waiting on the player to agree to pay the trainer
+    pub confirming: bool,
+    pub attributes: Attributes,
+    pub skills: Skills,
+    /// Class abilities the character's level allows that they have not learned
+    pub learnable: Vec<AbilityType>,
+    pub chosen_ability: Option<AbilityType>,
+    /// Why the last attempt to confirm was turned down
+    pub problem: Option<String>,
+}
+
+impl LevelUpScreen {
+    pub fn new() -> Self {
+        LevelUpScreen {
+            player_entity: None,
+            section: LevelUpSection::Attributes,
+            cursor: 0,
+            level: 1,
+            start_attributes: Attributes::from_scores([10; 6]),
+            start_skills: Skills::new(),
+            attribute_floor: Attributes::from_scores([10; 6]),
+            skill_floor: Skills::new(),
+            training: None,
+            cost: 0,
+            confirming: false,
+            attributes: Attributes::from_scores([10; 6]),
+            skills: Skills::new(),
+            learnable: Vec::new(),
+            chosen_ability: None,
+            problem: None,
+        }
+    }
+
+    /// Take a fresh copy of the player to make choices on
+    pub fn open(&mut self, world: &World, player_entity: Entity) {
+        self.player_entity = Some(player_entity);
+        self.level = world.read_storage::<Experience>().get(player_entity).map_or(1, |exp| exp.level);
+        self.start_attributes = world.read_storage::<Attributes>().get(player_entity).cloned()
+            .unwrap_or_else(|| Attributes::from_scores([10; 6]));
+        self.start_skills = world.read_storage::<Skills>().get(player_entity).cloned().unwrap_or_else(Skills::new);
+        self.learnable = match (world.read_storage::<CharacterClass>().get(player_entity), world.read_storage::<Abilities>().get(player_entity)) {
+            (Some(class), Some(abilities)) => abilities.learnable(class.class_type, self.level),
+            _ => Vec::new(),
+        };
+        self.attribute_floor = self.start_attributes.clone();
+        self.skill_floor = self.start_skills.clone();
+        self.training = None;
+        self.cost = 0;
+        self.start_over();
+    }
+
+    /// Take a fresh copy of the player for a trainer to rework, for `cost` gold
+    pub fn open_training(&mut self, world: &World, player_entity: Entity, training: Training, cost: i32) {
+        self.open(world, player_entity);
+        self.learnable.clear();
+        self.training = Some(training);
+        self.cost = cost;
+        for attribute in AttributeType::all() {
+            let start = self.start_attributes.get_attribute(attribute);
+            self.attribute_floor.set_attribute(attribute, (start - RESPEC_LIMIT).max(start.min(8)));
+        }
+        let taught = world.read_storage::<Background>().get(player_entity)
+            .map_or_else(Skills::new, |background| Skills::for_background(background.background_type));
+        for skill in SkillType::all() {
+            let start = self.start_skills.get_skill_level(skill);
+            self.skill_floor.skills.insert(skill, taught.get_skill_level(skill).min(start));
+        }
+        self.start_over();
+    }
+
+    pub fn close(&mut self) {
+        self.player_entity = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.player_entity.is_some()
+    }
+
+    /// Undo every choice made since the screen opened
+    pub fn start_over(&mut self) {
+        self.attributes = self.start_attributes.clone();
+        self.skills = self.start_skills.clone();
+        self.chosen_ability = None;
+        self.problem = None;
+        self.confirming = false;
+        self.section = match self.training {
+            Some(Training::Skills) => LevelUpSection::Skills,
+            Some(_) => LevelUpSection::Attributes,
+            None => LevelUpSection::all().into_iter()
+                .find(|section| self.has_choices(*section))
+                .unwrap_or(LevelUpSection::Attributes),
+        };
+        self.cursor = 0;
+    }
+
+    /// Whether anything has been moved since the screen opened
+    fn changed(&self) -> bool {
+        AttributeType::all().iter().any(|attribute| {
+            self.attributes.get_attribute(*attribute) != self.start_attributes.get_attribute(*attribute)
+        }) || SkillType::all().into_iter().any(|skill| {
+            self.skills.get_skill_level(skill) != self.start_skills.get_skill_level(skill)
+        })
+    }
+
+    fn has_choices(&self, section: LevelUpSection) -> bool {
+        match section {
+            LevelUpSection::Attributes => self.attributes.unspent_points > 0,
+            LevelUpSection::Skills => self.skills.unspent_skill_points > 0,
+            LevelUpSection::Ability => !self.learnable.is_empty(),
+        }
+    }
+
+    fn row_count(&self) -> usize {
+        match self.section {
+            LevelUpSection::Attributes => AttributeType::all().len(),
+            LevelUpSection::Skills => SkillType::all().len(),
+            LevelUpSection::Ability => self.learnable.len(),
+        }
+    }
+
+    fn raise(&mut self) {
+        match self.section {
+            LevelUpSection::Attributes => {
+                self.attributes.increase_attribute(AttributeType::all()[self.cursor]);
+            },
+            LevelUpSection::Skills => {
+                self.skills.increase_skill(SkillType::all()[self.cursor]);
+            },
+            LevelUpSection::Ability => self.chosen_ability = self.learnable.get(self.cursor).copied(),
+        }
+    }
+
+    /// Take back a point spent here, never going below the floor: where the character started,
+    /// or as far as the trainer allows
+    fn lower(&mut self) {
+        match self.section {
+            LevelUpSection::Attributes => {
+                let attribute = AttributeType::all()[self.cursor];
+                let value = self.attributes.get_attribute(attribute);
+                if value > self.attribute_floor.get_attribute(attribute) {
+                    self.attributes.set_attribute(attribute, value - 1);
+                    self.attributes.unspent_points += 1;
+                }
+            },
+            LevelUpSection::Skills => {
+                let skill = SkillType::all()[self.cursor];
+                let level = self.skills.get_skill_level(skill);
+                if level > self.skill_floor.get_skill_level(skill) {
+                    self.skills.skills.insert(skill, level - 1);
+                    self.skills.unspent_skill_points += 1;
+                }
+            },
+            LevelUpSection::Ability => {
+                if self.chosen_ability == self.learnable.get(self.cursor).copied() {
+                    self.chosen_ability = None;
+                }
+            },
+        }
+    }
+
+    /// What still has to be chosen before the level-up can be confirmed
+    pub fn unfinished(&self) -> Option<String> {
+        let attribute_room = AttributeType::all().iter().any(|attribute| self.attributes.get_attribute(*attribute) < 18);
+        if self.attributes.unspent_points > 0 && attribute_room {
+            return Some(Message::new("levelup.spend_attributes").arg("points", self.attributes.unspent_points).text());
+        }
+        let skill_room = SkillType::all().iter().any(|skill| self.skills.get_skill_level(*skill) < MAX_SKILL_LEVEL);
+        if self.skills.unspent_skill_points > 0 && skill_room {
+            return Some(Message::new("levelup.spend_skills").arg("points", self.skills.unspent_skill_points).text());
+        }
+        if !self.learnable.is_empty() && self.chosen_ability.is_none() {
+            return Some(tr("levelup.choose_ability"));
+        }
+        None
+    }
+
+    /// Returns true once the choices have been confirmed and written to the player, or the
+    /// player has walked away from the trainer
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) -> bool {
+        if self.confirming {
+            self.confirming = false;
+            return match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.confirm(world),
+                _ => false,
+            };
+        }
+        if self.training.is_some() {
+            match key {
+                KeyCode::Tab | KeyCode::BackTab => return false,
+                KeyCode::Esc => {
+                    self.close();
+                    return true;
+                },
+                KeyCode::Enter => {
+                    self.problem = self.unfinished();
+                    if self.problem.is_some() {
+                        return false;
+                    }
+                    if !self.changed() {
+                        self.close();
+                        return true;
+                    }
+                    self.confirming = true;
+                    return false;
+                },
+                _ => {},
+            }
+        }
+        match key {
+            KeyCode::Tab => {
+                self.section = self.section.next();
+                self.cursor = 0;
+            },
+            KeyCode::BackTab => {
+                self.section = self.section.previous();
+                self.cursor = 0;
+            },
+            KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down => self.cursor = (self.cursor + 1).min(self.row_count().saturating_sub(1)),
+            KeyCode::Right | KeyCode::Char('+') | KeyCode::Char(' ') if self.cursor < self.row_count() => self.raise(),
+            KeyCode::Left | KeyCode::Char('-') if self.cursor < self.row_count() => self.lower(),
+            KeyCode::Esc => self.start_over(),
+            KeyCode::Enter => return self.confirm(world),
+            _ => {},
+        }
+        false
+    }
+
+    /// Write the choices to the player, recalculating what follows from the attributes
+    pub fn confirm(&mut self, world: &World) -> bool {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return false,
+        };
+        self.problem = self.unfinished();
+        if self.problem.is_some() {
+            return false;
+        }
+        if self.training.is_some() {
+            match world.write_storage::<AdvancedInventory>().get_mut(player) {
+                Some(pack) if pack.gold >= self.cost => pack.gold -= self.cost,
+                _ => {
+                    self.problem = Some(Message::new("levelup.too_dear").arg("gold", self.cost).text());
+                    return false;
+                },
+            }
+        }
+
+        if let Some(stats) = world.write_storage::<CombatStats>().get_mut(player) {
+            apply_attribute_changes(stats, &self.start_attributes, &self.attributes, self.level);
+        }
+        if let Some(attributes) = world.write_storage::<Attributes>().get_mut(player) {
+            *attributes = self.attributes.clone();
+        }
+        if let Some(skills) = world.write_storage::<Skills>().get_mut(player) {
+            *skills = self.skills.clone();
+        }
+        let mut log = world.write_resource::<GameLog>();
+        if let Some(ability) = self.chosen_ability {
+            if let Some(abilities) = world.write_storage::<Abilities>().get_mut(player) {
+                abilities.add_ability(ability);
+            }
+            log.add_message(Message::new("log.ability_learned").arg("ability", ability.name()));
+        }
+        if self.training.is_some() {
+            log.add_message(Message::new("log.retrained").arg("gold", self.cost));
+        } else {
+            world.write_storage::<PendingLevelUp>().remove(player);
+            log.add_message(Message::new("log.level_up_done").arg("level", self.level));
+        }
+        self.close();
+        true
+    }
+
+    /// Every row of the current section, with the one under the cursor picked out
+    fn section_lines(&self) -> Vec<String> {
+        match self.section {
+            LevelUpSection::Attributes => AttributeType::all().iter().map(|attribute| {
+                let value = self.attributes.get_attribute(*attribute);
+                let added = value - self.start_attributes.get_attribute(*attribute);
+                let mut line = format!("{:<13} {:>2} ({})", attribute_name(*attribute), value, format_modifier(self.attributes.get_modifier(*attribute)));
+                if added != 0 {
+                    line.push_str(&format!("  {:+}", added));
+                }
+                line
+            }).collect(),
+            LevelUpSection::Skills => SkillType::all().into_iter().map(|skill| {
+                let level = self.skills.get_skill_level(skill);
+                let added = level - self.start_skills.get_skill_level(skill);
+                let bar = format!("[{}{}]", "#".repeat(level.clamp(0, MAX_SKILL_LEVEL) as usize), "-".repeat((MAX_SKILL_LEVEL - level).max(0) as usize));
+                let mut line = format!("{:<16} {} {}/{}", skill.name(), bar, level, MAX_SKILL_LEVEL);
+                if added != 0 {
+                    line.push_str(&format!("  {:+}", added));
+                }
+                line
+            }).collect(),
+            LevelUpSection::Ability => self.learnable.iter().map(|ability| {
+                let mark = if self.chosen_ability == Some(*ability) { "(*)" } else { "( )" };
+                format!("{} {:<18} MP {} SP {} CD {}", mark, ability.name(), ability.get_mana_cost(), ability.get_stamina_cost(), ability.cooldown())
+            }).collect(),
+        }
+    }
+
+    /// The highlighted row, for the screen-reader transcript
+    pub fn selected_line(&self) -> Option<String> {
+        if self.confirming {
+            return Some(self.confirm_prompt());
+        }
+        self.section_lines().into_iter().nth(self.cursor)
+            .map(|line| Message::new("access.level_up_line").arg("line", line.trim()).text())
+    }
+
+    fn confirm_prompt(&self) -> String {
+        Message::new("levelup.confirm_training").arg("gold", self.cost).text()
+    }
+
+    pub fn render(&self, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let mut commands = Vec::new();
+        let width = (screen_width - 4).min(72);
+        let height = (screen_height - 4).min(26);
+        let x = (screen_width - width) / 2;
+        let y = (screen_height - height) / 2;
+        let title = match self.training {
+            Some(Training::Skills) => Message::new("levelup.retrain_title").arg("gold", self.cost).text(),
+            Some(_) => Message::new("levelup.respec_title").arg("gold", self.cost).text(),
+            None => Message::new("levelup.title").arg("level", self.level).text(),
+        };
+        commands.extend(UIPanel::new(title, x, y, width, height)
+            .with_colors(Color::Yellow, Color::Black, Color::Yellow)
+            .render());
+        let text_width = (width - 4).max(0) as usize;
+        let mut text = |row: i32, line: String, fg: Color, bg: Color| {
+            commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text: line.chars().take(text_width).collect(), fg, bg });
+        };
+
+        // Sections, each with what is left to choose in it; a trainer only reworks one
+        let tabs: Vec<String> = LevelUpSection::all().iter()
+            .filter(|section| self.training.is_none() || **section == self.section)
+            .map(|section| {
+                let label = match section {
+                    LevelUpSection::Attributes => Message::new("levelup.attributes").arg("points", self.attributes.unspent_points).text(),
+                    LevelUpSection::Skills => Message::new("levelup.skills").arg("points", self.skills.unspent_skill_points).text(),
+                    LevelUpSection::Ability => tr("levelup.ability"),
+                };
+                if *section == self.section { format!("[{}]", label) } else { format!(" {} ", label) }
+            })
+            .collect();
+        text(1, tabs.join(" "), Color::Cyan, Color::Black);
+
+        let lines = self.section_lines();
+        if lines.is_empty() {
+            text(3, tr("levelup.no_abilities"), Color::DarkGrey, Color::Black);
+        }
+        let visible = (height - 8).max(1) as usize;
+        let first = self.cursor.saturating_sub(visible - 1);
+        for (i, line) in lines.iter().enumerate().skip(first).take(visible) {
+            let (fg, bg) = if i == self.cursor { (Color::Black, Color::White) } else { (Color::White, Color::Black) };
+            text(3 + (i - first) as i32, format!("{:<width$}", line, width = text_width), fg, bg);
+        }
+
+        // What the highlighted ability does
+        if self.section == LevelUpSection::Ability {
+            if let Some(ability) = self.learnable.get(self.cursor) {
+                text(height - 5, ability.description().to_string(), Color::Grey, Color::Black);
+            }
+        }
+        if let Some(problem) = &self.problem {
+            text(height - 4, problem.clone(), Color::Red, Color::Black);
+        }
+        let (footer, fg) = match (self.confirming, self.training) {
+            (true, _) => (self.confirm_prompt(), Color::Yellow),
+            (false, Some(_)) => (tr("levelup.training_controls"), Color::DarkGrey),
+            (false, None) => (tr("levelup.controls"), Color::DarkGrey),
+        };
+        text(height - 2, footer, fg, Color::Black);
+        commands
+    }
+}
+
+fn attribute_name(attribute: AttributeType) -> &'static str {
+    match attribute {
+        AttributeType::Strength => "Strength",
+        AttributeType::Dexterity => "Dexterity",
+        AttributeType::Constitution => "Constitution",
+        AttributeType::Intelligence => "Intelligence",
+        AttributeType::Wisdom => "Wisdom",
+        AttributeType::Charisma => "Charisma",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+
+    #[test]
+    fn test_choices_are_checked_then_written_with_derived_stats_recalculated() {
+        let mut world = World::new();
+        register_components(&mut world);
+        world.insert(GameLog::new(10));
+
+        let mut attributes = Attributes::from_scores([14, 12, 10, 10, 10, 10]);
+        attributes.unspent_points = 3;
+        let mut skills = Skills::new();
+        skills.add_skill_points(1);
+        let mut exp = Experience::new();
+        exp.level = 3;
+        let player = world.create_entity()
+            .with(exp)
+            .with(attributes)
+            .with(skills)
+            .with(Abilities::starting(ClassType::Fighter))
+            .with(CharacterClass { class_type: ClassType::Fighter })
+            .with(CombatStats { max_hp: 30, hp: 30, defense: 3, power: 7 })
+            .with(PendingLevelUp { levels: 2 })
+            .build();
+
+        let mut screen = LevelUpScreen::new();
+        screen.open(&world, player);
+        assert_eq!(screen.learnable, vec![AbilityType::Cleave]);
+        assert!(!screen.handle_key(KeyCode::Enter, &world));
+        assert!(screen.problem.is_some());
+
+        // Three points into strength, a fourth has nothing to spend, and dexterity cannot drop below its start
+        for _ in 0..4 {
+            screen.handle_key(KeyCode::Right, &world);
+        }
+        screen.handle_key(KeyCode::Down, &world);
+        screen.handle_key(KeyCode::Left, &world);
+        assert_eq!((screen.attributes.strength, screen.attributes.dexterity, screen.attributes.unspent_points), (17, 12, 0));
+
+        screen.handle_key(KeyCode::Tab, &world);
+        screen.handle_key(KeyCode::Char('+'), &world);
+        assert!(!screen.handle_key(KeyCode::Enter, &world));
+        assert_eq!(screen.problem, Some(tr("levelup.choose_ability")));
+        screen.handle_key(KeyCode::Tab, &world);
+        screen.handle_key(KeyCode::Char(' '), &world);
+        assert!(screen.handle_key(KeyCode::Enter, &world));
+
+        assert_eq!(world.read_storage::<Attributes>().get(player).unwrap().strength, 17);
+        assert_eq!(world.read_storage::<Skills>().get(player).unwrap().get_skill_level(SkillType::MeleeWeapons), 1);
+        assert!(world.read_storage::<Abilities>().get(player).unwrap().has_ability(AbilityType::Cleave));
+        assert_eq!(world.read_storage::<CombatStats>().get(player).unwrap().power, 8);
+        assert!(world.read_storage::<PendingLevelUp>().get(player).is_none());
+        assert!(!screen.is_open());
+    }
+    #[test]
+    fn test_a_trainer_moves_attribute_points_within_limits_once_paid() {
+        let mut world = World::new();
+        register_components(&mut world);
+        world.register::<AdvancedInventory>();
+        world.insert(GameLog::new(10));
+
+        let mut pack = AdvancedInventory::new(26, 150.0);
+        pack.gold = 100;
+        let player = world.create_entity()
+            .with(Experience::new())
+            .with(Attributes::from_scores([14, 12, 10, 10, 10, 10]))
+            .with(Skills::new())
+            .with(CombatStats { max_hp: 30, hp: 30, defense: 3, power: 7 })
+            .with(pack)
+            .build();
+
+        let mut screen = LevelUpScreen::new();
+        screen.open_training(&world, player, Training::Attributes, 80);
+        for _ in 0..=RESPEC_LIMIT {
+            screen.handle_key(KeyCode::Left, &world);
+        }
+        assert_eq!((screen.attributes.strength, screen.attributes.unspent_points), (14 - RESPEC_LIMIT, RESPEC_LIMIT));
+        assert!(!screen.handle_key(KeyCode::Enter, &world));
+        assert!(screen.problem.is_some());
+
+        screen.handle_key(KeyCode::Down, &world);
+        for _ in 0..RESPEC_LIMIT {
+            screen.handle_key(KeyCode::Right, &world);
+        }
+        // Nothing is paid or written until the player agrees to the price
+        assert!(!screen.handle_key(KeyCode::Enter, &world));
+        assert!(screen.confirming);
+        assert!(!screen.handle_key(KeyCode::Char('n'), &world));
+        assert_eq!(world.read_storage::<Attributes>().get(player).unwrap().strength, 14);
+        screen.handle_key(KeyCode::Enter, &world);
+        assert!(screen.handle_key(KeyCode::Char('y'), &world));
+
+        let attributes = world.read_storage::<Attributes>();
+        assert_eq!((attributes.get(player).unwrap().strength, attributes.get(player).unwrap().dexterity), (12, 14));
+        let stats = world.read_storage::<CombatStats>();
+        assert_eq!((stats.get(player).unwrap().power, stats.get(player).unwrap().defense), (6, 4));
+        assert_eq!(world.read_storage::<AdvancedInventory>().get(player).unwrap().gold, 20);
+    }
+}
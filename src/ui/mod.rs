@@ -5,17 +5,49 @@ pub mod menu_integration;
 pub mod hud;
 pub mod inventory_ui;
 pub mod character_screen;
+pub mod character_sheet;
+pub mod level_up_screen;
+pub mod hotbar;
+pub mod options_screen;
+pub mod death_ui;
+pub mod prayer_screen;
+pub mod furniture_menu;
+pub mod rival_menu;
+pub mod town_menu;
+pub mod bestiary_screen;
+pub mod agent_screen;
+pub mod guild_overview;
+pub mod status_icons;
 pub mod help_system;
 pub mod feedback_system;
 pub mod save_load_ui;
+pub mod backend;
+#[cfg(feature = "ratatui_ui")]
+pub mod ratatui_backend;
 
 pub use main_menu::{MainMenu, MainMenuState, MenuOption, MainMenuRunner};
 pub use menu_system::{MenuSystem, MenuRenderer, MenuInput};
-pub use ui_components::{UIComponent, UIPanel, UIButton, UIText, UIList, UIRenderCommand, TextAlignment};
+pub use ui_components::{UIComponent, UIPanel, UIButton, UIText, UIList, UIBar, UIRenderCommand, TextAlignment, CRITICAL_BAR_PERCENT};
 pub use menu_integration::{MenuIntegration, MenuAction, PauseMenuAction, PauseMenu};
 pub use hud::{GameHUD, HUDManager};
 pub use inventory_ui::{InventoryUI, InventoryUIState, InventoryAction, InventorySortMode, InventoryFilter};
 pub use character_screen::{CharacterScreen, CharacterScreenState, CharacterAttributes, CharacterSkills, CharacterAbilities, CharacterProgression};
-pub use help_system::{HelpSystem, HelpSystemState, TutorialStep, TutorialTrigger, HelpContext, TutorialMessage};
+pub use character_sheet::{CharacterSheet, CharacterSheetTab, format_modifier};
+pub use level_up_screen::{LevelUpScreen, LevelUpSection, apply_attribute_changes};
+pub use hotbar::{HotbarAction, HOTBAR_ROWS, assign_hotbar_slot, activate_hotbar_slot, render_hotbar};
+pub use options_screen::{OptionsScreen, OptionsTab, OptionsInput};
+pub use death_ui::{RevivalScreen, DeathAction};
+pub use prayer_screen::{PrayerScreen, PrayerAction};
+pub use furniture_menu::{FurnitureMenu, FurnitureChoice};
+pub use rival_menu::{RivalMenu, RivalChoice};
+pub use town_menu::{TownMenu, TownChoice};
+pub use bestiary_screen::BestiaryScreen;
+pub use agent_screen::{AgentScreen, AgentAction};
+pub use guild_overview::{GuildOverview, HallAction};
+pub use status_icons::{StatusIcon, StatusIconRegistry, EXPIRING_TURNS, is_expiring, status_detail};
+pub use help_system::{HelpSystem, HelpSystemState, TutorialStep, TutorialTrigger, TutorialEvents, HelpContext, TutorialMessage};
 pub use feedback_system::{UIFeedbackSystem, FeedbackType, Notification, NotificationPosition, NotificationPriority, VisualEffect, SoundCue, FloatingText, ScreenShake};
-pub use save_load_ui::{SaveLoadUI, SaveLoadUIState, SaveLoadAction};
\ No newline at end of file
+pub use save_load_ui::{SaveLoadUI, SaveLoadUIState, SaveLoadResult};
+pub use backend::{UiBackend, UiWidget, UiRect, TerminalUiBackend, default_ui_backend};
+#[cfg(feature = "ratatui_ui")]
+pub use ratatui_backend::RatatuiBackend;
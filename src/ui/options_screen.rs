@@ -0,0 +1,261 @@
+use crossterm::{event::KeyCode, style::Color};
+use crate::config::{ConfigOption, GameConfig};
+use crate::localization::{tr, Message};
+use crate::ui::{UIComponent, UIPanel, UIBar, UIRenderCommand};
+
+/// Segments in a slider's bar
+const SLIDER_SEGMENTS: i32 = 10;
+
+/// The Options screen's pages, in the order Tab cycles through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsTab {
+    Display,
+    Audio,
+    Gameplay,
+    Accessibility,
+}
+
+impl OptionsTab {
+    pub fn all() -> [OptionsTab; 4] {
+        [OptionsTab::Display, OptionsTab::Audio, OptionsTab::Gameplay, OptionsTab::Accessibility]
+    }
+
+    pub fn title(&self) -> String {
+        tr(match self {
+            OptionsTab::Display => "options.tab_display",
+            OptionsTab::Audio => "options.tab_audio",
+            OptionsTab::Gameplay => "options.tab_gameplay",
+            OptionsTab::Accessibility => "options.tab_accessibility",
+        })
+    }
+
+    /// The options listed on this page, top to bottom
+    pub fn options(&self) -> &'static [ConfigOption] {
+        match self {
+            OptionsTab::Display => &[
+                ConfigOption::Language,
+                ConfigOption::Colors,
+                ConfigOption::Glyphs,
+                ConfigOption::AnimationSpeed,
+                ConfigOption::SkipAnimations,
+                ConfigOption::WeatherEffects,
+                ConfigOption::ScreenshotHtml,
+            ],
+            OptionsTab::Audio => &[
+                ConfigOption::MasterVolume,
+                ConfigOption::MusicVolume,
+                ConfigOption::EffectsVolume,
+            ],
+            OptionsTab::Gameplay => &[
+                ConfigOption::AutosaveInterval,
+                ConfigOption::VerboseCombat,
+                ConfigOption::Gore,
+                ConfigOption::BumpToOpen,
+                ConfigOption::Tutorial,
+                ConfigOption::TutorialHints,
+            ],
+            OptionsTab::Accessibility => &[
+                ConfigOption::ScreenReader,
+                ConfigOption::ReducedMotion,
+                ConfigOption::NoFlash,
+            ],
+        }
+    }
+
+    fn index(&self) -> usize {
+        OptionsTab::all().iter().position(|tab| tab == self).unwrap_or(0)
+    }
+
+    /// The tab `steps` to the right, wrapping around at either end
+    fn offset(&self, steps: i32) -> OptionsTab {
+        let tabs = OptionsTab::all();
+        let index = (self.index() as i32 + steps).rem_euclid(tabs.len() as i32);
+        tabs[index as usize]
+    }
+}
+
+/// What a key press on the Options screen leaves for the game to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsInput {
+    Nothing,
+    /// The options changed and should be applied and saved
+    Changed,
+    Close,
+}
+
+/// The tabbed Options screen over the game's `config.toml`
+#[derive(Debug, Clone)]
+pub struct OptionsScreen {
+    pub tab: OptionsTab,
+    /// The line picked on the open tab
+    pub selected: usize,
+}
+
+impl OptionsScreen {
+    pub fn new() -> Self {
+        OptionsScreen { tab: OptionsTab::Display, selected: 0 }
+    }
+
+    /// The option on the highlighted line
+    pub fn selected_option(&self) -> ConfigOption {
+        let options = self.tab.options();
+        options[self.selected.min(options.len() - 1)]
+    }
+
+    fn select_tab(&mut self, tab: OptionsTab) {
+        self.tab = tab;
+        self.selected = 0;
+    }
+
+    /// Move around the screen, or change `config` straight away
+    pub fn handle_key(&mut self, key: KeyCode, config: &mut GameConfig) -> OptionsInput {
+        let step = match key {
+            KeyCode::Tab => {
+                self.select_tab(self.tab.offset(1));
+                return OptionsInput::Nothing;
+            },
+            KeyCode::BackTab => {
+                self.select_tab(self.tab.offset(-1));
+                return OptionsInput::Nothing;
+            },
+            KeyCode::Char(digit @ '1'..='4') => {
+                let index = digit as usize - '1' as usize;
+                self.select_tab(OptionsTab::all()[index]);
+                return OptionsInput::Nothing;
+            },
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                return OptionsInput::Nothing;
+            },
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.tab.options().len() - 1);
+                return OptionsInput::Nothing;
+            },
+            KeyCode::Char('r') => {
+                // Back to the defaults, this page only
+                for option in self.tab.options() {
+                    option.reset(config);
+                }
+                return OptionsInput::Changed;
+            },
+            KeyCode::Char('R') => {
+                // Back to the defaults, every page
+                *config = GameConfig::default();
+                return OptionsInput::Changed;
+            },
+            KeyCode::Esc | KeyCode::Char('q') => return OptionsInput::Close,
+            KeyCode::Left | KeyCode::Char('h') => -1,
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter | KeyCode::Char(' ') => 1,
+            _ => return OptionsInput::Nothing,
+        };
+        self.selected_option().adjust(config, step);
+        OptionsInput::Changed
+    }
+
+    /// The option's value as a bar, a check box or a choice to step through
+    fn value_commands(option: ConfigOption, config: &GameConfig, x: i32, y: i32) -> Vec<UIRenderCommand> {
+        let value = option.value(config);
+        if let Some(fraction) = option.slider(config) {
+            let mut bar = UIBar::new(String::new(), (fraction * 100.0).round() as i32, 100, x - 1, y, SLIDER_SEGMENTS)
+                .with_fill_color(Color::Cyan);
+            bar.threshold_colors = false;
+            bar.flash_when_low = false;
+            bar.show_values = false;
+            let mut commands = bar.render(0, 0, 0, 0);
+            commands.push(UIRenderCommand::DrawText {
+                x: x + SLIDER_SEGMENTS + 1,
+                y,
+                text: value,
+                fg: Color::White,
+                bg: Color::Black,
+            });
+            return commands;
+        }
+        let text = match option.switch(config) {
+            Some(on) => format!("[{}] {}", if on { 'x' } else { ' ' }, value),
+            None => format!("< {} >", value),
+        };
+        vec![UIRenderCommand::DrawText { x, y, text, fg: Color::White, bg: Color::Black }]
+    }
+
+    pub fn render(&self, config: &GameConfig, path: &str, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let mut commands = Vec::new();
+        let panel_width = screen_width - 4;
+        let panel_height = screen_height - 4;
+        let panel = UIPanel::new(tr("options.title"), 2, 2, panel_width, panel_height)
+            .with_colors(Color::White, Color::Black, Color::Yellow);
+        commands.extend(panel.render());
+
+        // Tab bar, with the open tab picked out
+        let mut x = 4;
+        for (i, tab) in OptionsTab::all().iter().enumerate() {
+            let label = format!(" {} {} ", i + 1, tab.title());
+            let (fg, bg) = if *tab == self.tab { (Color::Black, Color::Yellow) } else { (Color::Grey, Color::Black) };
+            let width = label.chars().count() as i32;
+            commands.push(UIRenderCommand::DrawText { x, y: 3, text: label, fg, bg });
+            x += width + 1;
+        }
+
+        for (i, option) in self.tab.options().iter().enumerate() {
+            let y = 5 + i as i32;
+            let (fg, bg) = if i == self.selected { (Color::Black, Color::White) } else { (Color::White, Color::Black) };
+            commands.push(UIRenderCommand::DrawText { x: 4, y, text: format!(" {:<24}", option.label()), fg, bg });
+            commands.extend(OptionsScreen::value_commands(*option, config, 31, y));
+        }
+
+        commands.push(UIRenderCommand::DrawText {
+            x: 4,
+            y: screen_height - 5,
+            text: Message::new("options.saved_to").arg("path", path).text(),
+            fg: Color::DarkGrey,
+            bg: Color::Black,
+        });
+        commands.push(UIRenderCommand::DrawText {
+            x: 4,
+            y: screen_height - 4,
+            text: tr("options.controls"),
+            fg: Color::DarkGrey,
+            bg: Color::Black,
+        });
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_option_is_on_exactly_one_tab() {
+        for option in ConfigOption::ALL.iter() {
+            let tabs = OptionsTab::all().iter().filter(|tab| tab.options().contains(option)).count();
+            assert_eq!(tabs, 1, "{:?}", option);
+        }
+    }
+
+    #[test]
+    fn test_changes_apply_at_once_and_reset_returns_a_page_to_its_defaults() {
+        let mut screen = OptionsScreen::new();
+        let mut config = GameConfig::default();
+        let defaults = GameConfig::default();
+
+        assert_eq!(screen.handle_key(KeyCode::Char('2'), &mut config), OptionsInput::Nothing);
+        assert_eq!(screen.selected_option(), ConfigOption::MasterVolume);
+        assert_eq!(screen.handle_key(KeyCode::Left, &mut config), OptionsInput::Changed);
+        assert!(config.audio.master_volume < defaults.audio.master_volume);
+
+        screen.handle_key(KeyCode::Tab, &mut config);
+        screen.handle_key(KeyCode::Down, &mut config);
+        screen.handle_key(KeyCode::Enter, &mut config);
+        assert_ne!(config.gameplay.verbose_combat, defaults.gameplay.verbose_combat);
+
+        // Resetting the gameplay page leaves the audio page as it was
+        assert_eq!(screen.handle_key(KeyCode::Char('r'), &mut config), OptionsInput::Changed);
+        assert_eq!(config.gameplay, defaults.gameplay);
+        assert!(config.audio.master_volume < defaults.audio.master_volume);
+
+        screen.handle_key(KeyCode::Char('R'), &mut config);
+        assert_eq!(config, defaults);
+        assert_eq!(screen.handle_key(KeyCode::Esc, &mut config), OptionsInput::Close);
+    }
+}
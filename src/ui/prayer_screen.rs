@@ -0,0 +1,160 @@
+use specs::{World, WorldExt, Entity};
+use crossterm::{event::KeyCode, style::Color};
+use crate::components::{Deity, Piety};
+use crate::localization::{tr, Message};
+use crate::systems::Boon;
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// Something asked of the gods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrayerAction {
+    /// Take the altar's god as your own
+    Devote(Deity),
+    Ask(Boon),
+}
+
+/// Prayers to the player's god, and devotion to whichever god's altar they kneel at
+#[derive(Debug, Clone)]
+pub struct PrayerScreen {
+    player_entity: Option<Entity>,
+    /// The god of the altar the player is beside, if any
+    altar: Option<Deity>,
+    pub selected: usize,
+}
+
+impl PrayerScreen {
+    pub fn new() -> Self {
+        PrayerScreen {
+            player_entity: None,
+            altar: None,
+            selected: 0,
+        }
+    }
+
+    pub fn open(&mut self, player_entity: Entity, altar: Option<Deity>) {
+        self.player_entity = Some(player_entity);
+        self.altar = altar;
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.player_entity = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.player_entity.is_some()
+    }
+
+    /// Devotion to a god not yet the player's, then each boon their own god can grant
+    pub fn choices(&self, world: &World) -> Vec<PrayerAction> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        let god = world.read_storage::<Piety>().get(player).map(|piety| piety.deity);
+        let mut choices = Vec::new();
+        if let Some(altar) = self.altar.filter(|altar| Some(*altar) != god) {
+            choices.push(PrayerAction::Devote(altar));
+        }
+        if god.is_some() {
+            choices.extend(Boon::all().iter().map(|boon| PrayerAction::Ask(*boon)));
+        }
+        choices
+    }
+
+    /// Move the selection, or return the prayer made. Escape closes the screen
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) -> Option<PrayerAction> {
+        let choices = self.choices(world);
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(choices.len().saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char(' ') => return choices.get(self.selected).copied(),
+            KeyCode::Esc | KeyCode::Char('p') => self.close(),
+            _ => {},
+        }
+        None
+    }
+
+    fn choice_label(choice: PrayerAction) -> String {
+        match choice {
+            PrayerAction::Devote(deity) => Message::new("prayer.devote").arg("god", deity.name()).arg("title", deity.title()).text(),
+            PrayerAction::Ask(boon) => Message::new("prayer.boon").arg("boon", tr(boon.key())).arg("cost", boon.cost()).text(),
+        }
+    }
+
+    /// The highlighted choice, for the screen reader
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        self.choices(world).get(self.selected).map(|choice| PrayerScreen::choice_label(*choice))
+    }
+
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        let choices = self.choices(world);
+        let width = 60.min(screen_width - 4);
+        let height = 9 + choices.len() as i32;
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let piety = world.read_storage::<Piety>().get(player).cloned();
+        let border = piety.as_ref().map_or(Color::Grey, |piety| piety.deity.color());
+        let mut commands = UIPanel::new(tr("prayer.title"), x, y, width, height)
+            .with_colors(border, Color::Black, border)
+            .render();
+        let line = |commands: &mut Vec<UIRenderCommand>, row: i32, text: String, fg: Color| {
+            let text: String = text.chars().take((width - 4).max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        let standing = match &piety {
+            Some(piety) => Message::new("prayer.piety")
+                .arg("god", piety.deity.name())
+                .arg("piety", piety.piety)
+                .arg("standing", piety.standing())
+                .text(),
+            None => tr("prayer.no_god"),
+        };
+        line(&mut commands, 2, standing, Color::White);
+
+        let devotion = piety.as_ref().map_or(0, |piety| piety.piety);
+        for (i, choice) in choices.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = match (i == self.selected, choice) {
+                (true, _) => Color::Yellow,
+                (false, PrayerAction::Devote(deity)) => deity.color(),
+                (false, PrayerAction::Ask(boon)) if boon.cost() > devotion => Color::DarkGrey,
+                (false, PrayerAction::Ask(_)) => Color::White,
+            };
+            line(&mut commands, 4 + i as i32, format!("{} {}", marker, PrayerScreen::choice_label(*choice)), fg);
+        }
+
+        line(&mut commands, height - 2, tr("prayer.controls"), Color::DarkGrey);
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::Builder;
+    use crate::components::register_components;
+
+    #[test]
+    fn test_an_altar_offers_devotion_only_to_a_new_god() {
+        let mut world = World::new();
+        register_components(&mut world);
+        let player = world.create_entity().build();
+        let mut screen = PrayerScreen::new();
+
+        screen.open(player, Some(Deity::Sylvara));
+        assert_eq!(screen.choices(&world), vec![PrayerAction::Devote(Deity::Sylvara)]);
+
+        world.write_storage::<Piety>().insert(player, Piety::new(Deity::Sylvara)).unwrap();
+        assert_eq!(screen.choices(&world).len(), Boon::all().len());
+        assert_eq!(screen.handle_key(KeyCode::Enter, &world), Some(PrayerAction::Ask(Boon::Heal)));
+
+        screen.handle_key(KeyCode::Esc, &world);
+        assert!(!screen.is_open());
+    }
+}
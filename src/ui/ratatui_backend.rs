@@ -0,0 +1,221 @@
+use crossterm::style::Color;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color as TuiColor, Modifier, Style};
+use ratatui::text::{Span, Text};
+use ratatui::widgets::{
+    Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Scrollbar,
+    ScrollbarOrientation, ScrollbarState, StatefulWidget, Table, TableState, Widget,
+};
+use std::error::Error;
+use crate::rendering::{Terminal, with_terminal};
+use crate::ui::backend::{TerminalUiBackend, UiBackend, UiRect, UiWidget};
+
+/// Widgets drawn with ratatui
+///
+/// Each widget is rendered into a scratch buffer and copied into the game's own double-buffered
+/// terminal, so ratatui never writes to the screen and the two can't disagree about what is shown.
+pub struct RatatuiBackend {
+    fallback: TerminalUiBackend,
+}
+
+impl RatatuiBackend {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(RatatuiBackend { fallback: TerminalUiBackend::new() })
+    }
+
+    fn block(title: &str) -> Block<'_> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(TuiColor::White))
+            .title(Span::styled(title, Style::default().fg(TuiColor::Yellow)))
+    }
+
+    fn highlight() -> Style {
+        Style::default().fg(TuiColor::Black).bg(TuiColor::White).add_modifier(Modifier::BOLD)
+    }
+
+    fn render(widget: &UiWidget, area: Rect, buffer: &mut Buffer) {
+        Clear.render(area, buffer);
+        match widget {
+            UiWidget::List { title, items, selected, .. } => {
+                let items: Vec<ListItem> = items.iter().map(|item| ListItem::new(item.as_str())).collect();
+                let mut state = ListState::default();
+                state.select(*selected);
+                StatefulWidget::render(
+                    List::new(items).block(Self::block(title)).highlight_style(Self::highlight()),
+                    area,
+                    buffer,
+                    &mut state,
+                );
+            }
+            UiWidget::Table { title, header, rows, widths, selected, .. } => {
+                let constraints: Vec<Constraint> = widths.iter().map(|width| Constraint::Length(*width)).collect();
+                let rows: Vec<Row> = rows.iter().map(|cells| Row::new(cells.clone())).collect();
+                let mut state = TableState::default();
+                state.select(*selected);
+                StatefulWidget::render(
+                    Table::new(rows)
+                        .header(Row::new(header.clone()).style(Style::default().fg(TuiColor::Cyan)))
+                        .block(Self::block(title))
+                        .widths(&constraints)
+                        .highlight_style(Self::highlight()),
+                    area,
+                    buffer,
+                    &mut state,
+                );
+            }
+            UiWidget::Gauge { label, ratio, color, .. } => {
+                Gauge::default()
+                    .gauge_style(Style::default().fg(to_ratatui(*color)).bg(TuiColor::Black))
+                    .ratio(ratio.clamp(0.0, 1.0))
+                    .label(label.as_str())
+                    .render(area, buffer);
+            }
+            UiWidget::Scrollbar { position, total, visible, .. } => {
+                let mut state = ScrollbarState::default()
+                    .content_length(*total as u16)
+                    .viewport_content_length(*visible as u16)
+                    .position(*position as u16);
+                Scrollbar::default()
+                    .orientation(ScrollbarOrientation::VerticalRight)
+                    .render(area, buffer, &mut state);
+            }
+            UiWidget::Paragraph { title, lines, color, .. } => {
+                Paragraph::new(Text::from(lines.join("\n")))
+                    .style(Style::default().fg(to_ratatui(*color)))
+                    .block(Self::block(title))
+                    .render(area, buffer);
+            }
+            UiWidget::Commands(_) => {}
+        }
+    }
+
+    /// Copy a rendered area into the game terminal's back buffer
+    fn blit(terminal: &mut Terminal, buffer: &Buffer, area: Rect) -> crossterm::Result<()> {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buffer.get(x, y);
+                // ratatui leaves the cell after a wide glyph empty; the terminal fills it in itself
+                let ch = match cell.symbol.chars().next() {
+                    Some(ch) => ch,
+                    None => continue,
+                };
+                terminal.draw_char_at(x, y, ch, from_ratatui(cell.fg, Color::White), from_ratatui(cell.bg, Color::Black))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl UiBackend for RatatuiBackend {
+    fn draw(&mut self, widget: UiWidget) -> Result<(), Box<dyn Error>> {
+        let area = match &widget {
+            UiWidget::Commands(_) => return self.fallback.draw(widget),
+            UiWidget::List { area, .. }
+            | UiWidget::Table { area, .. }
+            | UiWidget::Gauge { area, .. }
+            | UiWidget::Scrollbar { area, .. }
+            | UiWidget::Paragraph { area, .. } => *area,
+        };
+
+        let (width, height) = self.size();
+        let screen = Rect::new(0, 0, width, height);
+        let area = Rect::new(area.x, area.y, area.width, area.height).intersection(screen);
+        if area.area() == 0 {
+            return Ok(());
+        }
+
+        let mut buffer = Buffer::empty(screen);
+        Self::render(&widget, area, &mut buffer);
+        with_terminal(|terminal| Self::blit(terminal, &buffer, area))?;
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), Box<dyn Error>> {
+        self.fallback.present()
+    }
+
+    fn size(&self) -> (u16, u16) {
+        self.fallback.size()
+    }
+}
+
+fn to_ratatui(color: Color) -> TuiColor {
+    // crossterm names the bright colors plainly and prefixes the dim ones; ratatui does the opposite
+    match color {
+        Color::Reset => TuiColor::Reset,
+        Color::Black => TuiColor::Black,
+        Color::DarkGrey => TuiColor::DarkGray,
+        Color::Red => TuiColor::LightRed,
+        Color::DarkRed => TuiColor::Red,
+        Color::Green => TuiColor::LightGreen,
+        Color::DarkGreen => TuiColor::Green,
+        Color::Yellow => TuiColor::LightYellow,
+        Color::DarkYellow => TuiColor::Yellow,
+        Color::Blue => TuiColor::LightBlue,
+        Color::DarkBlue => TuiColor::Blue,
+        Color::Magenta => TuiColor::LightMagenta,
+        Color::DarkMagenta => TuiColor::Magenta,
+        Color::Cyan => TuiColor::LightCyan,
+        Color::DarkCyan => TuiColor::Cyan,
+        Color::White => TuiColor::White,
+        Color::Grey => TuiColor::Gray,
+        Color::Rgb { r, g, b } => TuiColor::Rgb(r, g, b),
+        Color::AnsiValue(value) => TuiColor::Indexed(value),
+    }
+}
+
+fn from_ratatui(color: TuiColor, reset: Color) -> Color {
+    match color {
+        TuiColor::Reset => reset,
+        TuiColor::Black => Color::Black,
+        TuiColor::DarkGray => Color::DarkGrey,
+        TuiColor::LightRed => Color::Red,
+        TuiColor::Red => Color::DarkRed,
+        TuiColor::LightGreen => Color::Green,
+        TuiColor::Green => Color::DarkGreen,
+        TuiColor::LightYellow => Color::Yellow,
+        TuiColor::Yellow => Color::DarkYellow,
+        TuiColor::LightBlue => Color::Blue,
+        TuiColor::Blue => Color::DarkBlue,
+        TuiColor::LightMagenta => Color::Magenta,
+        TuiColor::Magenta => Color::DarkMagenta,
+        TuiColor::LightCyan => Color::Cyan,
+        TuiColor::Cyan => Color::DarkCyan,
+        TuiColor::White => Color::White,
+        TuiColor::Gray => Color::Grey,
+        TuiColor::Rgb(r, g, b) => Color::Rgb { r, g, b },
+        TuiColor::Indexed(value) => Color::AnsiValue(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_survive_the_round_trip() {
+        for color in [Color::Red, Color::DarkRed, Color::Grey, Color::DarkGrey, Color::Rgb { r: 1, g: 2, b: 3 }] {
+            assert_eq!(from_ratatui(to_ratatui(color), Color::Black), color);
+        }
+        assert_eq!(from_ratatui(TuiColor::Reset, Color::Black), Color::Black);
+    }
+
+    #[test]
+    fn test_list_highlights_selection() {
+        let area = Rect::new(0, 0, 12, 4);
+        let mut buffer = Buffer::empty(area);
+        let widget = UiWidget::List {
+            area: UiRect::new(0, 0, 12, 4),
+            title: "Pack".to_string(),
+            items: vec!["Sword".to_string(), "Potion".to_string()],
+            selected: Some(1),
+            offset: 0,
+        };
+        RatatuiBackend::render(&widget, area, &mut buffer);
+        assert_eq!(buffer.get(1, 2).symbol, "P");
+        assert_eq!(buffer.get(1, 2).bg, TuiColor::White);
+        assert_eq!(buffer.get(1, 1).bg, TuiColor::Reset);
+    }
+}
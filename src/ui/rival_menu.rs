@@ -0,0 +1,178 @@
+use specs::{World, WorldExt, Entity};
+use crossterm::{event::KeyCode, style::Color};
+use crate::components::{Name, Parley, Rival, RivalStance};
+use crate::items::{AdvancedInventory, ItemProperties};
+use crate::localization::{tr, Message};
+use crate::map::Map;
+use crate::systems::{Faction, Reputation, Standing, alliance_fee, rival_price};
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// What to say to a rival adventurer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RivalChoice {
+    /// Buy an item out of their pack
+    Buy(Entity),
+    /// Pay the party to fight alongside the player
+    Ally,
+    Attack,
+    Leave,
+}
+
+impl RivalChoice {
+    /// The offer put to the rival, if the choice makes one
+    pub fn offer(self) -> Option<Parley> {
+        match self {
+            RivalChoice::Buy(item) => Some(Parley::Buy(item)),
+            RivalChoice::Ally => Some(Parley::Ally),
+            RivalChoice::Attack => Some(Parley::Attack),
+            RivalChoice::Leave => None,
+        }
+    }
+}
+
+/// The menu shown when the player bumps into a rival adventurer who isn't yet hostile
+#[derive(Debug, Clone)]
+pub struct RivalMenu {
+    player_entity: Option<Entity>,
+    rival: Option<Entity>,
+    pub selected: usize,
+}
+
+impl RivalMenu {
+    pub fn new() -> Self {
+        RivalMenu {
+            player_entity: None,
+            rival: None,
+            selected: 0,
+        }
+    }
+
+    pub fn open(&mut self, player_entity: Entity, rival: Entity) {
+        self.player_entity = Some(player_entity);
+        self.rival = Some(rival);
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.player_entity = None;
+        self.rival = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.player_entity.is_some()
+    }
+
+    /// The rival the menu is about
+    pub fn rival(&self) -> Option<Entity> {
+        self.rival
+    }
+
+    /// How adventurers in general see the player
+    fn standing(world: &World) -> Standing {
+        world.read_resource::<Reputation>().standing(Faction::Adventurers)
+    }
+
+    /// Everything in the rival's pack, then an alliance if there isn't one yet, then a fight or
+    /// walking away. Adventurers who hate the player won't trade, and only those who think
+    /// well enough of them will join up
+    pub fn choices(&self, world: &World) -> Vec<RivalChoice> {
+        let rival = match self.rival {
+            Some(rival) => rival,
+            None => return Vec::new(),
+        };
+        let standing = RivalMenu::standing(world);
+        let mut choices: Vec<RivalChoice> = match world.read_storage::<AdvancedInventory>().get(rival) {
+            Some(pack) if standing > Standing::Hated => pack.items.iter().map(|slot| RivalChoice::Buy(slot.entity)).collect(),
+            _ => Vec::new(),
+        };
+        let allied = world.read_storage::<Rival>().get(rival).map_or(false, |rival| rival.stance == RivalStance::Allied);
+        if !allied && standing >= Standing::Neutral {
+            choices.push(RivalChoice::Ally);
+        }
+        choices.push(RivalChoice::Attack);
+        choices.push(RivalChoice::Leave);
+        choices
+    }
+
+    /// Move the selection, or return the choice made. Escape walks away
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) -> Option<RivalChoice> {
+        let choices = self.choices(world);
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(choices.len().saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char(' ') => match choices.get(self.selected).copied() {
+                Some(RivalChoice::Leave) => self.close(),
+                choice => return choice,
+            },
+            KeyCode::Esc => self.close(),
+            _ => {},
+        }
+        None
+    }
+
+    fn choice_label(&self, choice: RivalChoice, world: &World) -> String {
+        match choice {
+            RivalChoice::Buy(item) => {
+                let properties = world.read_storage::<ItemProperties>();
+                let (name, value) = properties.get(item).map_or(("?".to_string(), 0), |props| (props.name.clone(), props.value));
+                Message::new("rival.buy").arg("item", name).arg("gold", rival_price(value, RivalMenu::standing(world))).text()
+            },
+            RivalChoice::Ally => {
+                let fee = alliance_fee(world.read_resource::<Map>().depth, RivalMenu::standing(world));
+                Message::new("rival.ally").arg("gold", fee).text()
+            },
+            RivalChoice::Attack => tr("rival.attack"),
+            RivalChoice::Leave => tr("rival.leave"),
+        }
+    }
+
+    /// The player's purse, that the party already travels with them, or that it wants nothing
+    /// to do with them
+    fn status(&self, world: &World) -> Option<String> {
+        let rival = self.rival?;
+        if world.read_storage::<Rival>().get(rival)?.stance == RivalStance::Allied {
+            return Some(tr("rival.allied"));
+        }
+        if RivalMenu::standing(world) == Standing::Hated {
+            return Some(tr("rival.shunned"));
+        }
+        let gold = world.read_storage::<AdvancedInventory>().get(self.player_entity?)?.gold;
+        Some(Message::new("rival.purse").arg("gold", gold).text())
+    }
+
+    /// The highlighted choice, for the screen reader
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        self.choices(world).get(self.selected).map(|choice| self.choice_label(*choice, world))
+    }
+
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let title = match self.rival.and_then(|rival| world.read_storage::<Name>().get(rival).map(|name| name.name.clone())) {
+            Some(title) => title,
+            None => return Vec::new(),
+        };
+        let choices = self.choices(world);
+        let width = 50.min(screen_width - 4);
+        let height = 8 + choices.len() as i32;
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(title, x, y, width, height)
+            .with_colors(Color::Cyan, Color::Black, Color::Cyan)
+            .render();
+        let line = |commands: &mut Vec<UIRenderCommand>, row: i32, text: String, fg: Color| {
+            let text: String = text.chars().take((width - 4).max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        if let Some(status) = self.status(world) {
+            line(&mut commands, 2, status, Color::DarkGrey);
+        }
+        for (i, choice) in choices.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = if i == self.selected { Color::Yellow } else { Color::White };
+            line(&mut commands, 4 + i as i32, format!("{} {}", marker, self.choice_label(*choice, world)), fg);
+        }
+
+        line(&mut commands, height - 2, tr("rival.controls"), Color::DarkGrey);
+        commands
+    }
+}
@@ -1,10 +1,12 @@
 use crossterm::{event::KeyCode, style::Color};
 use specs::{World, Entity};
-use std::path::PathBuf;
-use crate::persistence::{SaveSystem, SaveSlot, SaveMetadata, SaveFile, SaveError};
+use std::path::{Path, PathBuf};
+use crate::persistence::{SaveSystem, SaveSlot, SaveMetadata, SaveFile, SaveError, VersionCompatibility};
+use crate::persistence::hero_file::{list_hero_files, HeroError, HeroFile, HeroRecord};
 use crate::ui::{
     ui_components::{UIComponent, UIRenderCommand, UIPanel, UIText, TextAlignment},
     menu_system::{MenuRenderer, MenuInput},
+    backend::{UiBackend, UiWidget, UiRect},
 };
 
 /// Save/Load UI state
@@ -19,6 +21,7 @@ pub enum SaveLoadUIState {
     ConfirmOverwrite,
     SaveInProgress,
     LoadInProgress,
+    HeroList,
     Error,
     Closed,
 }
@@ -30,6 +33,20 @@ pub enum SaveLoadOperation {
     Load,
     Delete,
     Overwrite,
+    ImportHero,
+}
+
+/// What the game should do after a key on the save/load screens
+#[derive(Debug, Clone)]
+pub enum SaveLoadResult {
+    Cancelled,
+    SaveRequested(u32),
+    LoadCompleted(SaveFile),
+    SlotDeleted(u32),
+    /// Write the current character to a hero file
+    HeroExportRequested,
+    /// Start a new run as this hero, already checked over
+    HeroImportRequested(HeroRecord),
 }
 
 /// Save/Load UI component
@@ -46,6 +63,9 @@ pub struct SaveLoadUI {
     pub show_details: bool,
     pub slots_per_page: usize,
     pub last_operation_result: Option<Result<(), SaveError>>,
+    /// Hero files found for import, each read or with why it could not be
+    pub hero_files: Vec<(PathBuf, Result<HeroFile, HeroError>)>,
+    pub selected_hero: usize,
 }
 
 impl SaveLoadUI {
@@ -63,6 +83,8 @@ impl SaveLoadUI {
             show_details: false,
             slots_per_page: 8,
             last_operation_result: None,
+            hero_files: Vec::new(),
+            selected_hero: 0,
         }
     }
 
@@ -85,6 +107,19 @@ impl SaveLoadUI {
         self.scroll_offset = 0;
     }
 
+    /// List the heroes in `directory` to start a new run with
+    pub fn open_hero_import(&mut self, directory: &Path) {
+        self.operation = SaveLoadOperation::ImportHero;
+        self.state = SaveLoadUIState::HeroList;
+        self.hero_files = list_hero_files(directory).into_iter()
+            .map(|path| {
+                let hero = HeroFile::read(&path);
+                (path, hero)
+            })
+            .collect();
+        self.selected_hero = 0;
+    }
+
     pub fn close(&mut self) {
         self.state = SaveLoadUIState::Closed;
         self.error_message.clear();
@@ -122,6 +157,9 @@ impl SaveLoadUI {
             SaveLoadUIState::ConfirmDelete | SaveLoadUIState::ConfirmOverwrite => {
                 self.handle_confirmation_key(key)
             }
+            SaveLoadUIState::HeroList => {
+                self.handle_hero_list_key(key)
+            }
             SaveLoadUIState::Error => {
                 self.handle_error_key(key)
             }
@@ -166,6 +204,13 @@ impl SaveLoadUI {
                 }
                 None
             }
+            KeyCode::Char('x') if self.operation == SaveLoadOperation::Save => {
+                Some(SaveLoadResult::HeroExportRequested)
+            }
+            KeyCode::Char('h') if self.operation == SaveLoadOperation::Load => {
+                self.open_hero_import(Path::new(crate::persistence::HEROES_DIR));
+                None
+            }
             KeyCode::Esc | KeyCode::Char('q') => {
                 self.close();
                 Some(SaveLoadResult::Cancelled)
@@ -174,6 +219,42 @@ impl SaveLoadUI {
         }
     }
 
+    fn handle_hero_list_key(&mut self, key: KeyCode) -> Option<SaveLoadResult> {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => {
+                self.selected_hero = self.selected_hero.saturating_sub(1);
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s') => {
+                if self.selected_hero < self.hero_files.len().saturating_sub(1) {
+                    self.selected_hero += 1;
+                }
+                None
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                match self.hero_files.get(self.selected_hero) {
+                    Some((_, Ok(file))) => {
+                        let hero = file.hero.clone().validated();
+                        self.close();
+                        Some(SaveLoadResult::HeroImportRequested(hero))
+                    }
+                    Some((path, Err(e))) => {
+                        self.error_message = format!("Cannot import {}: {}", path.display(), e);
+                        self.state = SaveLoadUIState::Error;
+                        None
+                    }
+                    None => None,
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
+                self.operation = SaveLoadOperation::Load;
+                self.state = SaveLoadUIState::LoadMenu;
+                None
+            }
+            _ => None,
+        }
+    }
+
     fn handle_details_key(&mut self, key: KeyCode) -> Option<SaveLoadResult> {
         match key {
             KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
@@ -213,6 +294,7 @@ impl SaveLoadUI {
                 self.state = match self.operation {
                     SaveLoadOperation::Save => SaveLoadUIState::SaveMenu,
                     SaveLoadOperation::Load => SaveLoadUIState::LoadMenu,
+                    SaveLoadOperation::ImportHero => SaveLoadUIState::HeroList,
                     _ => SaveLoadUIState::SaveMenu,
                 };
                 None
@@ -240,10 +322,16 @@ impl SaveLoadUI {
                     }
                 }
                 SaveLoadOperation::Load => {
-                    if slot.is_occupied && !slot.is_corrupted {
+                    let compatibility = slot.compatibility.clone();
+                    if let Some(report) = compatibility.as_ref().filter(|report| !report.can_load()) {
+                        self.error_message = report.lines().join(" ");
+                        self.state = SaveLoadUIState::Error;
+                    } else if slot.is_occupied && !slot.is_corrupted {
+                        let upgrade = compatibility.as_ref().map_or(false, |report| report.status == VersionCompatibility::NeedsMigration);
                         self.confirmation_message = format!(
-                            "Load save from slot {}?",
-                            self.selected_slot + 1
+                            "Load save from slot {}?{}",
+                            self.selected_slot + 1,
+                            if upgrade { " It will be upgraded to the current save format." } else { "" }
                         );
                         self.state = SaveLoadUIState::ConfirmLoad;
                     } else if slot.is_corrupted {
@@ -299,6 +387,8 @@ impl SaveLoadUI {
                         }
                     }
                 }
+                // Heroes are imported straight from the list
+                SaveLoadOperation::ImportHero => None,
             }
         } else {
             self.error_message = "Save system not available.".to_string();
@@ -307,6 +397,141 @@ impl SaveLoadUI {
         }
     }
 
+    /// Draw through a widget backend: the slots as a table, with any prompt in a box over it
+    pub fn draw(&self, backend: &mut dyn UiBackend) -> Result<(), Box<dyn std::error::Error>> {
+        if self.state == SaveLoadUIState::Closed {
+            return Ok(());
+        }
+        let (width, height) = backend.size();
+        let panel = UiRect::new(2, 2, width.saturating_sub(4), height.saturating_sub(4));
+        if self.operation == SaveLoadOperation::ImportHero {
+            return self.draw_hero_list(backend, panel);
+        }
+        let title = match self.operation {
+            SaveLoadOperation::Load => "Load Game",
+            _ => "Save Game",
+        };
+        let rows: Vec<Vec<String>> = self.save_slots.iter().map(|slot| {
+            let mut row = vec![format!("{}", slot.slot_id + 1)];
+            if slot.is_corrupted {
+                row.push("<corrupted>".to_string());
+            } else if !slot.is_occupied {
+                row.push("<empty>".to_string());
+            } else {
+                let metadata = &slot.metadata;
+                row.push(metadata.player_name.clone());
+                row.push(metadata.character_level.to_string());
+                row.push(metadata.current_depth.to_string());
+                row.push(format!("{}h {:02}m", metadata.playtime_seconds / 3600, metadata.playtime_seconds / 60 % 60));
+                row.push(slot.compatibility.as_ref().map_or("", |report| report.summary()).to_string());
+            }
+            row
+        }).collect();
+        let total = rows.len();
+        let visible = panel.inner().height.saturating_sub(1) as usize;
+
+        backend.draw(UiWidget::Table {
+            area: panel,
+            title: title.to_string(),
+            header: ["Slot", "Character", "Level", "Depth", "Played", "Format"].iter().map(|h| h.to_string()).collect(),
+            rows,
+            widths: vec![4, 20, 5, 5, 8, 11],
+            selected: Some(self.selected_slot),
+            offset: self.scroll_offset,
+        })?;
+        backend.draw(UiWidget::Scrollbar {
+            area: UiRect::new(panel.x + panel.width.saturating_sub(1), panel.y + 2, 1, visible as u16),
+            position: self.scroll_offset,
+            total,
+            visible,
+        })?;
+
+        let prompt = match self.state {
+            SaveLoadUIState::ConfirmSave | SaveLoadUIState::ConfirmLoad
+            | SaveLoadUIState::ConfirmDelete | SaveLoadUIState::ConfirmOverwrite => {
+                Some(("Confirm", vec![self.confirmation_message.clone(), "Y: Yes  N: No".to_string()], Color::Yellow))
+            }
+            SaveLoadUIState::Error => Some(("Error", vec![self.error_message.clone(), "Enter: Continue".to_string()], Color::Red)),
+            SaveLoadUIState::SaveInProgress => Some(("Saving", vec!["Saving game...".to_string()], Color::White)),
+            SaveLoadUIState::LoadInProgress => Some(("Loading", vec!["Loading game...".to_string()], Color::White)),
+            SaveLoadUIState::SlotDetails => self.get_selected_slot().map(|slot| {
+                let metadata = &slot.metadata;
+                let mut lines = vec![
+                    format!("Save: {}", metadata.save_name),
+                    format!("{} (level {})", metadata.player_name, metadata.character_level),
+                    format!("Depth {}  Difficulty {}", metadata.current_depth, metadata.difficulty),
+                    format!("Achievements: {}", metadata.achievements_count),
+                ];
+                match &slot.compatibility {
+                    Some(report) => lines.extend(report.lines()),
+                    None => lines.push(format!("Version {}", metadata.game_version)),
+                }
+                ("Details", lines, Color::White)
+            }),
+            _ => None,
+        };
+        if let Some((prompt_title, lines, color)) = prompt {
+            let prompt_width = (lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 4).min(panel.width);
+            let prompt_height = lines.len() as u16 + 2;
+            backend.draw(UiWidget::Paragraph {
+                area: UiRect::new(
+                    panel.x + (panel.width - prompt_width) / 2,
+                    panel.y + panel.height.saturating_sub(prompt_height) / 2,
+                    prompt_width,
+                    prompt_height,
+                ),
+                title: prompt_title.to_string(),
+                lines,
+                color,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The heroes that can start a new run, with any error over them
+    fn draw_hero_list(&self, backend: &mut dyn UiBackend, panel: UiRect) -> Result<(), Box<dyn std::error::Error>> {
+        let rows: Vec<Vec<String>> = self.hero_files.iter().map(|(path, hero)| match hero {
+            Ok(file) => vec![
+                file.hero.name.clone(),
+                file.hero.level().to_string(),
+                file.hero.class_name().to_string(),
+                file.hero.items.len().to_string(),
+            ],
+            Err(e) => vec![
+                path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned()),
+                String::new(),
+                e.to_string(),
+            ],
+        }).collect();
+        let empty = rows.is_empty();
+        backend.draw(UiWidget::Table {
+            area: panel,
+            title: "Import Hero".to_string(),
+            header: ["Hero", "Level", "Class", "Items"].iter().map(|h| h.to_string()).collect(),
+            rows,
+            widths: vec![20, 5, 12, 5],
+            selected: Some(self.selected_hero),
+            offset: self.selected_hero.saturating_sub(panel.inner().height.saturating_sub(2) as usize),
+        })?;
+
+        let message = match self.state {
+            SaveLoadUIState::Error => Some(("Error", self.error_message.clone(), "Enter: Continue", Color::Red)),
+            _ if empty => Some(("Import Hero", format!("No heroes in {}/ yet", crate::persistence::HEROES_DIR), "Esc: Back", Color::White)),
+            _ => None,
+        };
+        if let Some((title, line, hint, color)) = message {
+            let lines = vec![line, hint.to_string()];
+            let prompt_width = (lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 4).min(panel.width);
+            backend.draw(UiWidget::Paragraph {
+                area: UiRect::new(panel.x + (panel.width - prompt_width) / 2, panel.y + panel.height.saturating_sub(4) / 2, prompt_width, 4),
+                title: title.to_string(),
+                lines,
+                color,
+            })?;
+        }
+        Ok(())
+    }
+
     fn get_selected_slot(&self) -> Option<&SaveSlot> {
         self.save_slots.get(self.selected_slot)
     }
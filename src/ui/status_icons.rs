@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use crossterm::style::Color;
+use crate::components::{StatusEffect, StatusEffectType};
+
+/// Turns left at which an effect's icon starts to blink
+pub const EXPIRING_TURNS: i32 = 3;
+
+/// How one kind of effect shows in the icon strip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusIcon {
+    pub glyph: char,
+    pub color: Color,
+}
+
+impl StatusIcon {
+    pub fn new(glyph: char, color: Color) -> Self {
+        StatusIcon { glyph, color }
+    }
+}
+
+/// The glyph and color for each kind of status effect. Kinds without an entry of their own show
+/// as an arrow, blue and up for a boon or yellow and down for a bane
+#[derive(Debug, Clone)]
+pub struct StatusIconRegistry {
+    icons: HashMap<StatusEffectType, StatusIcon>,
+}
+
+impl Default for StatusIconRegistry {
+    fn default() -> Self {
+        use StatusEffectType::*;
+        let mut registry = StatusIconRegistry { icons: HashMap::new() };
+        registry.register(&[Poisoned, Diseased], '☠', Color::Green);
+        registry.register(&[Burning], '♨', Color::Red);
+        registry.register(&[Bleeding], '♥', Color::DarkRed);
+        registry.register(&[Regeneration], '+', Color::DarkGreen);
+        registry.register(&[Blessed], '☼', Color::Blue);
+        registry.register(&[Cursed], '‡', Color::Magenta);
+        registry.register(&[Haste], '»', Color::Blue);
+        registry.register(&[Slow], '«', Color::Cyan);
+        registry.register(&[Stunned, Paralyzed], '*', Color::Yellow);
+        registry.register(&[Asleep], 'z', Color::Yellow);
+        registry.register(&[Confused], '?', Color::Magenta);
+        registry.register(&[Feared], '!', Color::Magenta);
+        registry.register(&[Blinded], 'ø', Color::DarkYellow);
+        registry.register(&[Silenced], '∅', Color::DarkYellow);
+        registry.register(&[Invisible], '○', Color::Blue);
+        registry.register(&[Levitating], '^', Color::Blue);
+        registry.register(&[WaterWalking], '~', Color::Blue);
+        registry.register(&[Detecting], '◊', Color::Blue);
+        registry.register(&[WellFed], '♣', Color::Blue);
+        registry.register(&[Wet], '≈', Color::Blue);
+        registry.register(&[Chilled], '❄', Color::Cyan);
+        registry.register(&[Overheated], '☀', Color::Red);
+        registry.register(&[Warmed], '♦', Color::DarkRed);
+        registry.register(&[Hydrated], '¤', Color::Blue);
+        registry.register(&[Exhausted], '%', Color::DarkYellow);
+        registry
+    }
+}
+
+impl StatusIconRegistry {
+    /// Show every kind in `effects` with this glyph and color, replacing what they had
+    pub fn register(&mut self, effects: &[StatusEffectType], glyph: char, color: Color) {
+        for effect in effects {
+            self.icons.insert(*effect, StatusIcon::new(glyph, color));
+        }
+    }
+
+    pub fn icon(&self, effect: StatusEffectType) -> StatusIcon {
+        self.icons.get(&effect).copied().unwrap_or_else(|| {
+            if effect.is_beneficial() {
+                StatusIcon::new('↑', Color::Blue)
+            } else {
+                StatusIcon::new('↓', Color::DarkYellow)
+            }
+        })
+    }
+
+    /// The icon as drawn this frame: an effect about to wear off dims on the unlit half of the blink
+    pub fn icon_for(&self, effect: &StatusEffect, flash_on: bool) -> StatusIcon {
+        let icon = self.icon(effect.effect_type);
+        if is_expiring(effect) && !flash_on {
+            StatusIcon::new(icon.glyph, Color::DarkGrey)
+        } else {
+            icon
+        }
+    }
+}
+
+/// Whether the effect is close enough to wearing off to warn about
+pub fn is_expiring(effect: &StatusEffect) -> bool {
+    effect.duration <= EXPIRING_TURNS
+}
+
+/// One line on an effect for the look panel: its name, the turns it has left and what it does
+pub fn status_detail(effect: &StatusEffect) -> String {
+    let turns = if effect.duration == 1 { "1 turn".to_string() } else { format!("{} turns", effect.duration) };
+    format!("{} ({}): {}", effect.effect_type.name(), turns, effect.effect_type.description())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlisted_effects_fall_back_to_an_arrow() {
+        let mut registry = StatusIconRegistry::default();
+        assert_eq!(registry.icon(StatusEffectType::Burning), StatusIcon::new('♨', Color::Red));
+        assert_eq!(registry.icon(StatusEffectType::StrengthBoost).glyph, '↑');
+        assert_eq!(registry.icon(StatusEffectType::DefensePenalty).glyph, '↓');
+
+        registry.register(&[StatusEffectType::StrengthBoost], 'S', Color::Red);
+        assert_eq!(registry.icon(StatusEffectType::StrengthBoost), StatusIcon::new('S', Color::Red));
+    }
+
+    #[test]
+    fn test_only_effects_about_to_expire_blink() {
+        let registry = StatusIconRegistry::default();
+        let lasting = StatusEffect::new(StatusEffectType::Burning, 10, 1);
+        let fading = StatusEffect::new(StatusEffectType::Burning, EXPIRING_TURNS, 1);
+
+        assert_eq!(registry.icon_for(&lasting, false).color, Color::Red);
+        assert_eq!(registry.icon_for(&fading, true).color, Color::Red);
+        assert_eq!(registry.icon_for(&fading, false).color, Color::DarkGrey);
+        assert!(status_detail(&fading).starts_with("Burning (3 turns): "));
+    }
+}
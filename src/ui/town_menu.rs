@@ -0,0 +1,437 @@
+use specs::{World, WorldExt, Entity};
+use crossterm::{event::KeyCode, style::Color};
+use crate::components::{
+    Abilities, AbilityType, Background, CharacterClass, Difficulty, Experience, ForSale, GameSettings, Name, Piety,
+    TownDeal, TownRole, Townsperson,
+};
+use crate::items::{AdvancedInventory, BlacksmithService, ClericService, Equipment, ItemProperties, MagicalItem};
+use crate::localization::{self, tr, Message};
+use crate::progression::{Bank, Property, WorldEvents};
+use crate::resources::GameClock;
+use crate::systems::{Faction, Reputation, Standing, Training, TownWatch, unpaid_wares, ware_price};
+use crate::ui::{UIPanel, UIRenderCommand};
+
+/// The smaller sum offered at the bank, the smaller gift at the temple, and the smaller stake at
+/// the dice; the larger ones are everything, a bigger gift and a bigger stake
+const BANK_STEP: i32 = 50;
+const DONATIONS: [i32; 2] = [25, 100];
+const STAKES: [i32; 2] = [10, 50];
+
+/// What to say to someone in Ravenhollow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TownChoice {
+    PayForWares,
+    PayFine,
+    ServeSentence,
+    Deposit(i32),
+    Withdraw(i32),
+    BuyProperty(Property),
+    Donate(i32),
+    Bet(i32),
+    Train(Training),
+    /// Have the blacksmith mend a worn item from the pack
+    Repair(Entity),
+    /// Have the temple's cleric lift a known curse from something carried or worn
+    LiftCurse(Entity),
+    Attack,
+    Leave,
+}
+
+impl TownChoice {
+    /// The deal struck, if the choice makes one
+    pub fn deal(self) -> Option<TownDeal> {
+        match self {
+            TownChoice::PayForWares => Some(TownDeal::PayForWares),
+            TownChoice::PayFine => Some(TownDeal::PayFine),
+            TownChoice::ServeSentence => Some(TownDeal::ServeSentence),
+            TownChoice::Deposit(gold) => Some(TownDeal::Deposit(gold)),
+            TownChoice::Withdraw(gold) => Some(TownDeal::Withdraw(gold)),
+            TownChoice::BuyProperty(property) => Some(TownDeal::BuyProperty(property)),
+            TownChoice::Donate(gold) => Some(TownDeal::Donate(gold)),
+            TownChoice::Bet(gold) => Some(TownDeal::Gamble(gold)),
+            TownChoice::Train(Training::Ability(ability)) => Some(TownDeal::LearnAbility(ability)),
+            // Reworking skills or attributes happens on the level-up screen
+            TownChoice::Train(_) => None,
+            // The blacksmith's work goes through the repair system, which charges for it
+            TownChoice::Repair(_) => None,
+            // So does lifting a curse, through the curse system
+            TownChoice::LiftCurse(_) => None,
+            TownChoice::Attack => Some(TownDeal::Attack),
+            TownChoice::Leave => None,
+        }
+    }
+}
+
+/// The menu shown when the player bumps into someone in town, or when a guard catches up with
+/// them to say what they owe
+#[derive(Debug, Clone)]
+pub struct TownMenu {
+    player_entity: Option<Entity>,
+    townsperson: Option<Entity>,
+    pub selected: usize,
+    /// An ability the player has picked to be taught, waiting on them to agree to the price
+    pub confirming: Option<TownChoice>,
+}
+
+impl TownMenu {
+    pub fn new() -> Self {
+        TownMenu {
+            player_entity: None,
+            townsperson: None,
+            selected: 0,
+            confirming: None,
+        }
+    }
+
+    pub fn open(&mut self, player_entity: Entity, townsperson: Entity) {
+        self.player_entity = Some(player_entity);
+        self.townsperson = Some(townsperson);
+        self.selected = 0;
+        self.confirming = None;
+    }
+
+    pub fn close(&mut self) {
+        self.player_entity = None;
+        self.townsperson = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.player_entity.is_some()
+    }
+
+    /// Who the menu is about
+    pub fn townsperson(&self) -> Option<Entity> {
+        self.townsperson
+    }
+
+    fn role(&self, world: &World) -> Option<TownRole> {
+        world.read_storage::<Townsperson>().get(self.townsperson?).map(|person| person.role)
+    }
+
+    fn standing(world: &World) -> Standing {
+        world.read_resource::<Reputation>().standing(Faction::Townsfolk)
+    }
+
+    fn purse(&self, world: &World) -> i32 {
+        self.player_entity
+            .and_then(|player| world.read_storage::<AdvancedInventory>().get(player).map(|pack| pack.gold))
+            .unwrap_or(0)
+    }
+
+    fn bank_open(world: &World) -> bool {
+        world.read_resource::<GameClock>().shops_open()
+    }
+
+    fn level(&self, world: &World) -> i32 {
+        self.player_entity
+            .and_then(|player| world.read_storage::<Experience>().get(player).map(|exp| exp.level))
+            .unwrap_or(1)
+    }
+
+    /// What the trainer asks the player for `training`, given how the guild sees them
+    pub fn training_cost(&self, world: &World, training: Training) -> i32 {
+        let standing = world.read_resource::<Reputation>().standing(Faction::Guild);
+        training.cost(self.level(world), standing)
+    }
+
+    /// Abilities from other classes the trainer can teach the player
+    fn teachable(&self, world: &World) -> Vec<AbilityType> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        match (world.read_storage::<CharacterClass>().get(player), world.read_storage::<Abilities>().get(player)) {
+            (Some(class), Some(abilities)) => abilities.cross_class_learnable(class.class_type, self.level(world)),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The blacksmith's prices for the player, given their difficulty, background and how the
+    /// town sees them
+    fn blacksmith(&self, world: &World) -> BlacksmithService {
+        let player = self.player_entity;
+        let difficulty = player
+            .and_then(|player| world.read_storage::<GameSettings>().get(player).map(|settings| settings.difficulty))
+            .unwrap_or(Difficulty::Normal);
+        let backgrounds = world.read_storage::<Background>();
+        BlacksmithService::for_customer(difficulty, player.and_then(|player| backgrounds.get(player)), TownMenu::standing(world))
+    }
+
+    /// What the blacksmith asks to mend `item`
+    fn repair_cost(&self, world: &World, item: Entity) -> i32 {
+        world.read_storage::<ItemProperties>().get(item).map_or(0, |props| self.blacksmith(world).repair_cost(props))
+    }
+
+    /// Worn items in the player's pack that the player can afford to have mended
+    fn repairable(&self, world: &World) -> Vec<Entity> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        let purse = self.purse(world);
+        let properties = world.read_storage::<ItemProperties>();
+        world.read_storage::<AdvancedInventory>().get(player)
+            .map_or(Vec::new(), |pack| pack.items.iter().map(|slot| slot.entity).collect())
+            .into_iter()
+            .filter(|item| properties.get(*item).map_or(false, |props| props.needs_repair()))
+            .filter(|item| self.repair_cost(world, *item) <= purse)
+            .collect()
+    }
+
+    /// The cleric's prices for the player, given their difficulty, background and how the town
+    /// sees them
+    fn cleric(&self, world: &World) -> ClericService {
+        let player = self.player_entity;
+        let difficulty = player
+            .and_then(|player| world.read_storage::<GameSettings>().get(player).map(|settings| settings.difficulty))
+            .unwrap_or(Difficulty::Normal);
+        let backgrounds = world.read_storage::<Background>();
+        ClericService::for_customer(difficulty, player.and_then(|player| backgrounds.get(player)), TownMenu::standing(world))
+    }
+
+    /// What the cleric asks to lift the curse on `item`
+    fn curse_cost(&self, world: &World, item: Entity) -> i32 {
+        world.read_storage::<MagicalItem>().get(item)
+            .and_then(|magical| magical.curse.as_ref())
+            .map_or(0, |curse| self.cleric(world).remove_curse_cost(curse))
+    }
+
+    /// Items carried or worn under a curse the player knows of and can afford to have lifted
+    fn liftable(&self, world: &World) -> Vec<Entity> {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        let mut items: Vec<Entity> = world.read_storage::<Equipment>().get(player)
+            .map_or(Vec::new(), |gear| gear.get_all_equipped());
+        if let Some(pack) = world.read_storage::<AdvancedInventory>().get(player) {
+            items.extend(pack.items.iter().map(|slot| slot.entity).filter(|item| !items.contains(item)).collect::<Vec<_>>());
+        }
+        let purse = self.purse(world);
+        let magical_items = world.read_storage::<MagicalItem>();
+        items.into_iter()
+            .filter(|item| magical_items.get(*item)
+                .and_then(|magical| magical.curse.as_ref())
+                .map_or(false, |curse| curse.revealed))
+            .filter(|item| self.curse_cost(world, *item) <= purse)
+            .collect()
+    }
+
+    /// A small sum and everything, or just everything when that is no more than the small sum
+    fn sums(total: i32) -> Vec<i32> {
+        match total {
+            total if total <= 0 => Vec::new(),
+            total if total <= BANK_STEP => vec![total],
+            total => vec![BANK_STEP, total],
+        }
+    }
+
+    /// What the shopkeeper asks for everything unpaid for in the player's pack
+    fn wares_due(&self, world: &World) -> i32 {
+        let player = match self.player_entity {
+            Some(player) => player,
+            None => return 0,
+        };
+        let inventories = world.read_storage::<AdvancedInventory>();
+        let properties = world.read_storage::<ItemProperties>();
+        let standing = TownMenu::standing(world);
+        let percent = world.read_resource::<WorldEvents>().price_percent();
+        inventories.get(player)
+            .map_or(Vec::new(), |pack| unpaid_wares(pack, &world.read_storage::<ForSale>(), false))
+            .iter()
+            .filter_map(|(item, quantity)| properties.get(*item).map(|props| ware_price(props.value, standing, percent) * quantity))
+            .sum()
+    }
+
+    /// Paying for wares taken from the shelves, when talking to the shopkeeper with some in the
+    /// pack; squaring things with the law, when talking to a guard while wanted; banking and
+    /// buying property while the bank is open; giving to the temple for a character with a god,
+    /// and having the temple's cleric lift known curses;
+    /// a stake the player can cover at the dice; reworking skills and attributes and learning
+    /// what the character's level allows of other classes' abilities from the trainer; or
+    /// mending whatever worn gear the player can pay the blacksmith for. Then a fight or walking
+    /// away
+    pub fn choices(&self, world: &World) -> Vec<TownChoice> {
+        let role = match self.role(world) {
+            Some(role) => role,
+            None => return Vec::new(),
+        };
+        let mut choices = Vec::new();
+        match role {
+            TownRole::Shopkeeper if self.wares_due(world) > 0 => choices.push(TownChoice::PayForWares),
+            TownRole::Guard if world.read_resource::<TownWatch>().is_wanted() => {
+                choices.push(TownChoice::PayFine);
+                choices.push(TownChoice::ServeSentence);
+            },
+            TownRole::Banker if TownMenu::bank_open(world) => {
+                let bank = world.read_resource::<Bank>();
+                choices.extend(TownMenu::sums(self.purse(world)).into_iter().map(TownChoice::Deposit));
+                choices.extend(TownMenu::sums(bank.balance).into_iter().map(TownChoice::Withdraw));
+                choices.extend(Property::all().into_iter().filter(|property| !bank.owns(*property)).map(TownChoice::BuyProperty));
+            },
+            TownRole::Priest => {
+                if self.player_entity.map_or(false, |player| world.read_storage::<Piety>().contains(player)) {
+                    let purse = self.purse(world);
+                    choices.extend(DONATIONS.into_iter().filter(|gold| *gold <= purse).map(TownChoice::Donate));
+                }
+                choices.extend(self.liftable(world).into_iter().map(TownChoice::LiftCurse));
+            },
+            TownRole::Gambler => {
+                let purse = self.purse(world);
+                choices.extend(STAKES.into_iter().filter(|gold| *gold <= purse).map(TownChoice::Bet));
+            },
+            TownRole::Trainer => {
+                choices.push(TownChoice::Train(Training::Skills));
+                choices.push(TownChoice::Train(Training::Attributes));
+                choices.extend(self.teachable(world).into_iter().map(|ability| TownChoice::Train(Training::Ability(ability))));
+            },
+            TownRole::Blacksmith => choices.extend(self.repairable(world).into_iter().map(TownChoice::Repair)),
+            _ => {},
+        }
+        choices.push(TownChoice::Attack);
+        choices.push(TownChoice::Leave);
+        choices
+    }
+
+    /// Move the selection, or return the choice made. Escape walks away. Being taught an ability
+    /// asks first whether the player will pay for it
+    pub fn handle_key(&mut self, key: KeyCode, world: &World) -> Option<TownChoice> {
+        if let Some(choice) = self.confirming.take() {
+            return match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Some(choice),
+                _ => None,
+            };
+        }
+        let choices = self.choices(world);
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(choices.len().saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Char(' ') => match choices.get(self.selected).copied() {
+                Some(TownChoice::Leave) => self.close(),
+                Some(choice @ TownChoice::Train(Training::Ability(_))) => self.confirming = Some(choice),
+                choice => return choice,
+            },
+            KeyCode::Esc => self.close(),
+            _ => {},
+        }
+        None
+    }
+
+    fn choice_label(&self, choice: TownChoice, world: &World) -> String {
+        match choice {
+            TownChoice::PayForWares => Message::new("town.pay_wares").arg("gold", self.wares_due(world)).text(),
+            TownChoice::PayFine => Message::new("town.pay_fine").arg("gold", world.read_resource::<TownWatch>().fine).text(),
+            TownChoice::ServeSentence => {
+                let hours = (world.read_resource::<TownWatch>().sentence() / 60).max(1);
+                Message::new("town.serve_sentence").arg("hours", hours).text()
+            },
+            TownChoice::Deposit(gold) => Message::new("town.deposit").arg("gold", gold).text(),
+            TownChoice::Withdraw(gold) => Message::new("town.withdraw").arg("gold", gold).text(),
+            TownChoice::BuyProperty(property) => Message::new("town.buy_property")
+                .arg("property", property.name())
+                .arg("gold", property.cost())
+                .text(),
+            TownChoice::Donate(gold) => Message::new("town.donate").arg("gold", gold).text(),
+            TownChoice::Bet(gold) => Message::new("town.bet").arg("gold", gold).text(),
+            TownChoice::Train(training) => {
+                let gold = self.training_cost(world, training);
+                match training {
+                    Training::Skills => Message::new("town.retrain").arg("gold", gold).text(),
+                    Training::Attributes => Message::new("town.respec").arg("gold", gold).text(),
+                    Training::Ability(ability) => Message::new("town.learn").arg("ability", ability.name()).arg("gold", gold).text(),
+                }
+            },
+            TownChoice::Repair(item) => {
+                let name = world.read_storage::<ItemProperties>().get(item).map_or(String::new(), |props| localization::name(&props.name));
+                Message::new("town.repair").arg("item", name).arg("gold", self.repair_cost(world, item)).text()
+            },
+            TownChoice::LiftCurse(item) => {
+                let name = world.read_storage::<ItemProperties>().get(item).map_or(String::new(), |props| localization::name(&props.name));
+                Message::new("town.lift_curse").arg("item", name).arg("gold", self.curse_cost(world, item)).text()
+            },
+            TownChoice::Attack => tr("town.attack"),
+            TownChoice::Leave => tr("town.leave"),
+        }
+    }
+
+    /// What they have to say to the player: what is owed, what is in the bank, or a word of
+    /// greeting
+    fn status(&self, world: &World) -> Option<String> {
+        let watch = world.read_resource::<TownWatch>();
+        let line = match self.role(world)? {
+            TownRole::Guard if watch.is_wanted() => Message::new("town.fine_owed").arg("gold", watch.fine).text(),
+            TownRole::Guard => tr("town.guard_greeting"),
+            TownRole::Shopkeeper => {
+                let gold = world.read_storage::<AdvancedInventory>().get(self.player_entity?)?.gold;
+                Message::new("town.shop_greeting").arg("gold", gold).text()
+            },
+            TownRole::Villager => tr("town.villager_greeting"),
+            TownRole::Banker if !TownMenu::bank_open(world) => tr("town.bank_closed"),
+            TownRole::Banker => Message::new("town.balance")
+                .arg("balance", world.read_resource::<Bank>().balance)
+                .arg("gold", self.purse(world))
+                .text(),
+            TownRole::Priest => match world.read_storage::<Piety>().get(self.player_entity?) {
+                Some(piety) => Message::new("town.priest_greeting").arg("god", piety.deity.name()).text(),
+                None => tr("town.unsworn"),
+            },
+            TownRole::Gambler => Message::new("town.gambler_greeting").arg("gold", self.purse(world)).text(),
+            TownRole::Trainer => Message::new("town.trainer_greeting").arg("gold", self.purse(world)).text(),
+            TownRole::Blacksmith => Message::new("town.blacksmith_greeting").arg("gold", self.purse(world)).text(),
+        };
+        Some(line)
+    }
+
+    /// What the player is asked before being taught an ability
+    fn confirm_prompt(&self, world: &World) -> Option<String> {
+        match self.confirming? {
+            TownChoice::Train(training @ Training::Ability(ability)) => Some(Message::new("town.confirm_learn")
+                .arg("ability", ability.name())
+                .arg("gold", self.training_cost(world, training))
+                .text()),
+            _ => None,
+        }
+    }
+
+    /// The highlighted choice, for the screen reader
+    pub fn selected_line(&self, world: &World) -> Option<String> {
+        if let Some(prompt) = self.confirm_prompt(world) {
+            return Some(prompt);
+        }
+        self.choices(world).get(self.selected).map(|choice| self.choice_label(*choice, world))
+    }
+
+    pub fn render(&self, world: &World, screen_width: i32, screen_height: i32) -> Vec<UIRenderCommand> {
+        let title = match self.townsperson.and_then(|person| world.read_storage::<Name>().get(person).map(|name| name.name.clone())) {
+            Some(title) => title,
+            None => return Vec::new(),
+        };
+        let choices = self.choices(world);
+        let width = 50.min(screen_width - 4);
+        let height = 8 + choices.len() as i32;
+        let x = (screen_width - width) / 2;
+        let y = ((screen_height - height) / 2).max(0);
+        let mut commands = UIPanel::new(title, x, y, width, height)
+            .with_colors(Color::Yellow, Color::Black, Color::Yellow)
+            .render();
+        let line = |commands: &mut Vec<UIRenderCommand>, row: i32, text: String, fg: Color| {
+            let text: String = text.chars().take((width - 4).max(0) as usize).collect();
+            commands.push(UIRenderCommand::DrawText { x: x + 2, y: y + row, text, fg, bg: Color::Black });
+        };
+
+        if let Some(status) = self.status(world) {
+            line(&mut commands, 2, status, Color::DarkGrey);
+        }
+        for (i, choice) in choices.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let fg = if i == self.selected { Color::Yellow } else { Color::White };
+            line(&mut commands, 4 + i as i32, format!("{} {}", marker, self.choice_label(*choice, world)), fg);
+        }
+
+        match self.confirm_prompt(world) {
+            Some(prompt) => line(&mut commands, height - 2, prompt, Color::Yellow),
+            None => line(&mut commands, height - 2, tr("town.controls"), Color::DarkGrey),
+        }
+        commands
+    }
+}
@@ -556,6 +556,168 @@ impl UIComponent for UIList {
     }
 }
 
+/// Values below this percentage make a bar flash
+pub const CRITICAL_BAR_PERCENT: f32 = 25.0;
+
+/// A segmented meter for health, mana, stamina or experience
+#[derive(Debug, Clone)]
+pub struct UIBar {
+    pub label: String,
+    pub current: i32,
+    pub max: i32,
+    pub x: i32,
+    pub y: i32,
+    /// Number of segments in the bar itself, excluding the label and values
+    pub segments: i32,
+    /// Color while the bar is comfortably full
+    pub fill_color: Color,
+    /// Shift to yellow then red as the bar empties
+    pub threshold_colors: bool,
+    /// Blink while below the critical percentage
+    pub flash_when_low: bool,
+    /// Which half of the blink cycle this frame falls in
+    pub flash_on: bool,
+    pub show_values: bool,
+}
+
+impl UIBar {
+    pub fn new(label: String, current: i32, max: i32, x: i32, y: i32, segments: i32) -> Self {
+        UIBar {
+            label,
+            current,
+            max,
+            x,
+            y,
+            segments,
+            fill_color: Color::Green,
+            threshold_colors: true,
+            flash_when_low: true,
+            flash_on: true,
+            show_values: true,
+        }
+    }
+
+    /// Progress towards the next level, from `Experience::progress_percentage`
+    pub fn experience(progress_percentage: f32, x: i32, y: i32, segments: i32) -> Self {
+        let mut bar = UIBar::new("XP".to_string(), progress_percentage.round() as i32, 100, x, y, segments)
+            .with_fill_color(Color::Cyan);
+        bar.threshold_colors = false;
+        bar.flash_when_low = false;
+        bar.show_values = false;
+        bar
+    }
+
+    pub fn with_fill_color(mut self, color: Color) -> Self {
+        self.fill_color = color;
+        self
+    }
+
+    pub fn with_flash(mut self, flash_on: bool) -> Self {
+        self.flash_on = flash_on;
+        self
+    }
+
+    pub fn percentage(&self) -> f32 {
+        if self.max <= 0 {
+            return 0.0;
+        }
+        (self.current.clamp(0, self.max) as f32 / self.max as f32) * 100.0
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.percentage() < CRITICAL_BAR_PERCENT
+    }
+
+    /// Filled segments, never rounding a non-empty bar down to nothing
+    pub fn filled_segments(&self) -> i32 {
+        let filled = (self.percentage() / 100.0 * self.segments as f32).round() as i32;
+        if self.current > 0 && filled == 0 {
+            1
+        } else {
+            filled.min(self.segments)
+        }
+    }
+
+    /// The fill color for the current level
+    pub fn color(&self) -> Color {
+        if !self.threshold_colors {
+            return self.fill_color;
+        }
+        let percentage = self.percentage();
+        if percentage > 60.0 {
+            self.fill_color
+        } else if percentage >= CRITICAL_BAR_PERCENT {
+            Color::Yellow
+        } else {
+            Color::Red
+        }
+    }
+}
+
+impl UIComponent for UIBar {
+    fn render(&self, _x: i32, _y: i32, _width: i32, _height: i32) -> Vec<UIRenderCommand> {
+        let mut commands = Vec::new();
+        let label_width = self.label.chars().count() as i32 + 1;
+        commands.push(UIRenderCommand::DrawText {
+            x: self.x,
+            y: self.y,
+            text: format!("{} ", self.label),
+            fg: Color::White,
+            bg: Color::Black,
+        });
+
+        // Low bars blink by dimming their filled segments every other phase
+        let fill_color = if self.flash_when_low && self.is_critical() && !self.flash_on {
+            Color::DarkGrey
+        } else {
+            self.color()
+        };
+        let filled = self.filled_segments();
+        if filled > 0 {
+            commands.push(UIRenderCommand::DrawText {
+                x: self.x + label_width,
+                y: self.y,
+                text: "█".repeat(filled as usize),
+                fg: fill_color,
+                bg: Color::Black,
+            });
+        }
+        if filled < self.segments {
+            commands.push(UIRenderCommand::DrawText {
+                x: self.x + label_width + filled,
+                y: self.y,
+                text: "░".repeat((self.segments - filled) as usize),
+                fg: Color::DarkGrey,
+                bg: Color::Black,
+            });
+        }
+
+        if self.show_values {
+            commands.push(UIRenderCommand::DrawText {
+                x: self.x + label_width + self.segments + 1,
+                y: self.y,
+                text: format!("{}/{}", self.current, self.max),
+                fg: Color::White,
+                bg: Color::Black,
+            });
+        }
+
+        commands
+    }
+
+    fn handle_input(&mut self, _input: char) -> bool {
+        false // Bars only display values
+    }
+
+    fn is_focused(&self) -> bool {
+        false
+    }
+
+    fn set_focus(&mut self, _focused: bool) {
+        // Bars cannot be focused
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -673,4 +835,33 @@ mod tests {
         let aligned = text.align_text("Test", 10);
         assert!(aligned.starts_with("   ")); // Should have padding for centering
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ui_bar_color_thresholds() {
+        let healthy = UIBar::new("HP".to_string(), 30, 30, 0, 0, 10);
+        assert_eq!(healthy.color(), Color::Green);
+        assert!(!healthy.is_critical());
+
+        let hurt = UIBar::new("HP".to_string(), 12, 30, 0, 0, 10);
+        assert_eq!(hurt.color(), Color::Yellow);
+
+        let dying = UIBar::new("HP".to_string(), 3, 30, 0, 0, 10);
+        assert_eq!(dying.color(), Color::Red);
+        assert!(dying.is_critical());
+        assert_eq!(dying.filled_segments(), 1);
+
+        let xp = UIBar::experience(10.0, 0, 0, 10);
+        assert_eq!(xp.color(), Color::Cyan);
+    }
+
+    #[test]
+    fn test_ui_bar_flashes_when_low() {
+        let dim = UIBar::new("HP".to_string(), 2, 30, 0, 0, 10).with_flash(false);
+        let commands = dim.render(0, 0, 0, 0);
+        let fill = commands.iter().find_map(|cmd| match cmd {
+            UIRenderCommand::DrawText { text, fg, .. } if text.starts_with('█') => Some(*fg),
+            _ => None,
+        });
+        assert_eq!(fill, Some(Color::DarkGrey));
+    }
+}
@@ -1,6 +1,10 @@
 use rand::Rng;
 use thiserror::Error;
 
+mod name_generator;
+
+pub use name_generator::{NameGenerator, NameStyle};
+
 #[derive(Error, Debug)]
 pub enum GameError {
     #[error("IO error: {0}")]
@@ -32,6 +36,20 @@ pub fn distance(x1: i32, y1: i32, x2: i32, y2: i32) -> f32 {
     (dx * dx + dy * dy).sqrt()
 }
 
+/// Where a 64-bit FNV-1a hash starts from
+pub const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// 64-bit FNV-1a of `bytes`, carrying on from `hash`. Unlike `DefaultHasher` it is the same on
+/// every build, so it can key things kept on disk or shared between players
+pub fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// Steps between two tiles for something that can move diagonally
+pub fn chebyshev(a: (i32, i32), b: (i32, i32)) -> i32 {
+    i32::max((a.0 - b.0).abs(), (a.1 - b.1).abs())
+}
+
 // Direction to delta conversion
 pub fn direction_to_delta(dx: i32, dy: i32) -> (i32, i32) {
     let mut ndx = 0;
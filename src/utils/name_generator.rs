@@ -0,0 +1,126 @@
+use crate::resources::RandomNumberGenerator;
+
+/// Sound of a generated name; each style has its own syllable set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    Human,
+    Orcish,
+    Elder,
+}
+
+struct Syllables {
+    onsets: &'static [&'static str],
+    nuclei: &'static [&'static str],
+    codas: &'static [&'static str],
+}
+
+const HUMAN: Syllables = Syllables {
+    onsets: &["Al", "Br", "C", "D", "El", "F", "G", "H", "J", "K", "L", "M", "R", "S", "T", "W"],
+    nuclei: &["a", "e", "i", "o", "ae", "ia", "ou"],
+    codas: &["n", "r", "l", "th", "ric", "wen", "da", "mir", "s", ""],
+};
+
+const ORCISH: Syllables = Syllables {
+    onsets: &["Gr", "Kr", "Z", "Sn", "Dr", "Ug", "Th", "Vr", "B", "Gh"],
+    nuclei: &["u", "a", "o", "ug", "az"],
+    codas: &["lk", "sh", "g", "rk", "th", "z", "gg", "mm"],
+};
+
+const ELDER: Syllables = Syllables {
+    onsets: &["Vor", "Xe", "Ith", "Quel", "Mor", "Aza", "Nyx", "Sel", "Oth"],
+    nuclei: &["a", "e", "i", "y", "ae", "ua"],
+    codas: &["thal", "ris", "mon", "rax", "vel", "zar", "dun"],
+};
+
+const EPITHETS: [&str; 16] = [
+    "Flayer", "Cruel", "Unbroken", "Gnawer", "Hollow", "Butcher", "Defiler", "Red",
+    "Patient", "Devourer", "Tyrant", "Bonebreaker", "Silent", "Grim", "Twice-Born", "Wretched",
+];
+
+const ARTIFACT_NOUNS: [&str; 12] = [
+    "Reckoning", "Lament", "Oath", "Wrath", "Vigil", "Bane",
+    "Promise", "Ruin", "Mercy", "Heart", "Eye", "Crown",
+];
+
+const ARTIFACT_TITLES: [&str; 8] = [
+    "the Sundering", "the Last Light", "the Unmaker", "the Undying",
+    "the Deep Star", "the Pale Flame", "the Worldroot", "the Silent Song",
+];
+
+/// Syllable-based generator for character, monster and artifact names.
+/// Every roll comes from the caller's RNG, so a seeded run always produces the same names.
+pub struct NameGenerator;
+
+impl NameGenerator {
+    /// A name of two or three syllables in the given style
+    pub fn personal_name(style: NameStyle, rng: &mut RandomNumberGenerator) -> String {
+        let syllables = match style {
+            NameStyle::Human => &HUMAN,
+            NameStyle::Orcish => &ORCISH,
+            NameStyle::Elder => &ELDER,
+        };
+
+        let mut name = pick(syllables.onsets, rng).to_string();
+        let middle = rng.range(1, 2);
+        for i in 0..middle {
+            name.push_str(pick(syllables.nuclei, rng));
+            if i + 1 < middle {
+                name.push_str(&pick(syllables.onsets, rng).to_lowercase());
+            }
+        }
+        name.push_str(pick(syllables.codas, rng));
+        name
+    }
+
+    /// "the Flayer", "the Cruel", ...
+    pub fn epithet(rng: &mut RandomNumberGenerator) -> String {
+        format!("the {}", pick(&EPITHETS, rng))
+    }
+
+    /// A named champion such as "Grulk the Flayer"
+    pub fn unique_enemy_name(style: NameStyle, rng: &mut RandomNumberGenerator) -> String {
+        let name = Self::personal_name(style, rng);
+        format!("{} {}", name, Self::epithet(rng))
+    }
+
+    /// A name fit for an artifact: "Vorthal's Reckoning" or "Xeris, the Sundering"
+    pub fn artifact_name(rng: &mut RandomNumberGenerator) -> String {
+        let name = Self::personal_name(NameStyle::Elder, rng);
+        if rng.roll_dice(1, 2) == 1 {
+            format!("{}'s {}", name, pick(&ARTIFACT_NOUNS, rng))
+        } else {
+            format!("{}, {}", name, pick(&ARTIFACT_TITLES, rng))
+        }
+    }
+}
+
+fn pick<'a>(options: &[&'a str], rng: &mut RandomNumberGenerator) -> &'a str {
+    options[rng.range(0, options.len() as i32 - 1) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_gives_same_names() {
+        let mut first = RandomNumberGenerator::new(42);
+        let mut second = RandomNumberGenerator::new(42);
+
+        for _ in 0..10 {
+            assert_eq!(
+                NameGenerator::personal_name(NameStyle::Human, &mut first),
+                NameGenerator::personal_name(NameStyle::Human, &mut second)
+            );
+        }
+        assert_eq!(NameGenerator::artifact_name(&mut first), NameGenerator::artifact_name(&mut second));
+    }
+
+    #[test]
+    fn test_unique_enemies_carry_an_epithet() {
+        let mut rng = RandomNumberGenerator::new(8);
+        let name = NameGenerator::unique_enemy_name(NameStyle::Orcish, &mut rng);
+        assert!(name.contains(" the "));
+        assert!(name.chars().next().unwrap().is_uppercase());
+    }
+}